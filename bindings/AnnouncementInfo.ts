@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An owner announcement as shown in a Space (Section 8.12).
+ */
+export type AnnouncementInfo = { 
+/**
+ * Position in the Space's announcement sequence.
+ */
+seq: bigint, title: string, body: string, pinned: boolean, issued_at: bigint, read: boolean, };
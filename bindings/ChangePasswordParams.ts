@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `change_password`.
+ */
+export type ChangePasswordParams = { old: string, new: string, };
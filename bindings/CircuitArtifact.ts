@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CircuitId } from "./CircuitId";
+
+/**
+ * Pinned Groth16 key hashes for one circuit version.
+ */
+export type CircuitArtifact = { circuit: CircuitId, version: number, 
+/**
+ * BLAKE3 of the compressed verifying key.
+ */
+vk_hash: string, 
+/**
+ * BLAKE3 of the compressed proving key.
+ */
+pk_hash: string, pk_size_bytes: bigint, };
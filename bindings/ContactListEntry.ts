@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An entry of `get_contacts`.
+ */
+export type ContactListEntry = { pik_hash: string, display_name: string, added_at: bigint, is_blocked: boolean, verified: boolean, };
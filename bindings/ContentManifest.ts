@@ -10,7 +10,7 @@ export type ContentManifest = {
  */
 content_hash: string, title: string, description: string | null, 
 /**
- * Max 5 tags.
+ * Max 5 tags, normalized `namespace:value` form (see [`tags`]).
  */
 tags: Array<string>, 
 /**
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Audience rating of content, from least to most mature.
+ */
+export type ContentRating = "general" | "teen" | "mature";
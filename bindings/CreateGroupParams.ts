@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `create_group`.
+ */
+export type CreateGroupParams = { name: string, 
+/**
+ * Default "storefront".
+ */
+template: string | null, };
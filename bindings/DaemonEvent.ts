@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DownloadProgress } from "./DownloadProgress";
+import type { GroupSettings } from "./GroupSettings";
+import type { HandleTransitionKind } from "./HandleTransitionKind";
+import type { MemberLeftReason } from "./MemberLeftReason";
+import type { MemberRole } from "./MemberRole";
+import type { RecoveryAlertType } from "./RecoveryAlertType";
+import type { ReportReason } from "./ReportReason";
+import type { TierType } from "./TierType";
+import type { TransferCancelReason } from "./TransferCancelReason";
+import type { WhisperCounterparty } from "./WhisperCounterparty";
+import type { WhisperDeliveryState } from "./WhisperDeliveryState";
+import type { WhisperSessionEndReason } from "./WhisperSessionEndReason";
+
+/**
+ * Every event the daemon emits (Section 23).
+ */
+export type DaemonEvent = { "event_type": "MemberJoined", "payload": { group_id: string, pik_hash: string, display_name: string, role: MemberRole, } } | { "event_type": "MemberLeft", "payload": { group_id: string, pik_hash: string, reason: MemberLeftReason, } } | { "event_type": "ContentPublished", "payload": { group_id: string, content_hash: string, title: string, creator_pik: string, pricing_summary: string, } } | { "event_type": "ContentPurchased", "payload": { group_id: string, content_hash: string, tier_type: TierType, price_paid: bigint, epoch: bigint, } } | { "event_type": "ContentUpdated", "payload": { lineage_id: string, previous_content_hash: string, content_hash: string, version: number, } } | { "event_type": "CreatorGranted", "payload": { group_id: string, target_pik: string, granted_by: string, } } | { "event_type": "CreatorRevoked", "payload": { group_id: string, target_pik: string, revoked_by: string, } } | { "event_type": "ModeratorGranted", "payload": { group_id: string, target_pik: string, granted_by: string, } } | { "event_type": "ModeratorRevoked", "payload": { group_id: string, target_pik: string, revoked_by: string, } } | { "event_type": "ContentTombstoned", "payload": { group_id: string, content_hash: string, tombstoned_by: string, } } | { "event_type": "ContentReported", "payload": { group_id: string, content_hash: string, reporter_hash: string, reason: ReportReason, } } | { "event_type": "SettingsChanged", "payload": { group_id: string, changed_by: string, old_settings: GroupSettings, new_settings: GroupSettings, } } | { "event_type": "OwnershipTransferPending", "payload": { group_id: string, new_owner_pik: string, completes_at: bigint, } } | { "event_type": "OwnershipTransferCompleted", "payload": { group_id: string, new_owner_pik: string, } } | { "event_type": "OwnershipTransferCanceled", "payload": { group_id: string, reason: TransferCancelReason, } } | { "event_type": "AnnouncementReceived", "payload": { group_id: string, seq: bigint, title: string, pinned: boolean, } } | { "event_type": "AnnouncementPinChanged", "payload": { group_id: string, seq: bigint, pinned: boolean, } } | { "event_type": "DownloadProgress", "payload": DownloadProgress } | { "event_type": "EpochEarningsSummary", "payload": { epoch: bigint, total_earned: bigint, abr_earned: bigint, creator_earned: bigint, host_earned: bigint, } } | { "event_type": "RefundReceived", "payload": { content_hash: string, refund_amount: bigint, epoch: bigint, } } | { "event_type": "EscrowTimeout", "payload": { content_hash: string, refund_amount: bigint, epoch: bigint, } } | { "event_type": "VysRewardsClaimed", "payload": { amount: bigint, epoch: bigint, } } | { "event_type": "FundsReceived", "payload": { sender_pik: string | null, amount: bigint, note: string | null, tx_hash: string, } } | { "event_type": "FundsSent", "payload": { recipient_pik: string, amount: bigint, tx_hash: string, } } | { "event_type": "MintingComplete", "payload": { epoch: bigint, seeds_minted: bigint, receipts_processed: number, } } | { "event_type": "CollateralRatioChanged", "payload": { old_cr: number, new_cr: number, epoch: bigint, } } | { "event_type": "LayoutManifestUpdated", "payload": { group_id: string, updated_by: string, } } | { "event_type": "RecoveryContactAlert", "payload": { alert_type: RecoveryAlertType, epoch: bigint, } } | { "event_type": "RecoveryVetoWindow", "payload": { veto_window_ends: bigint, } } | { "event_type": "RecoveryContactHealthAlert", "payload": { contact_pik: string, days_since_heartbeat: number, } } | { "event_type": "KeyChanged", "payload": { contact_pik: string, was_verified: boolean, } } | { "event_type": "ContactRevoked", "payload": { contact_pik: string, revoked_at: bigint, } } | { "event_type": "OTAUpdateAvailable", "payload": { version: string, activation_epoch: bigint, is_mandatory: boolean, } } | { "event_type": "AccessExpiringSoon", "payload": { content_hash: string, title: string, expires_at: bigint, hours_remaining: number, } } | { "event_type": "InviteExpiringSoon", "payload": { invite_hash: string, group_id: string, expires_at: bigint, hours_remaining: number, } } | { "event_type": "DiskPressureAlert", "payload": { free_space_pct: number, eviction_triggered: boolean, } } | { "event_type": "CircuitBreakerActivated", "payload": { stale_hours: number, cr_shift: number, } } | { "event_type": "CircuitBreakerDeactivated", "payload": { oracle_restored_at: bigint, } } | { "event_type": "SupplyInvariantViolated", "payload": { epoch: number, invariant: string, expected: string, actual: string, } } | { "event_type": "OnionCircuitDegraded", "payload": { consecutive_failures: number, healthy_circuits: number, } } | { "event_type": "OnionCircuitFailover", "payload": { suspect_hop: number | null, healthy_circuits: number, } } | { "event_type": "OnionCircuitRecovered", "payload": { healthy_circuits: number, } } | { "event_type": "ConnectionsMigrated", "payload": { resumed_connections: number, lost_connections: number, rebuilt_circuits: number, } } | { "event_type": "BootstrapViewsDiverged", "payload": { agreeing_sources: Array<string>, divergent_sources: Array<string>, accepted: boolean, } } | { "event_type": "StateSynced", "payload": { epoch: number, nullifier_count: bigint, relay_count: number, } } | { "event_type": "DatabaseAnomaly", "payload": { task: string, detail: string, } } | { "event_type": "DaemonStarted", "payload": { version: string, epoch: bigint, posrv_score: number, } } | { "event_type": "DaemonShuttingDown", "payload": { reason: string, } } | { "event_type": "NodeModeChanged", "payload": { mode: string, previous: string, draining: Array<string>, } } | { "event_type": "PowerProfileChanged", "payload": { mode: string, low_power: boolean, relay_suspended: boolean, } } | { "event_type": "ZkPorSubmitted", "payload": { epoch: bigint, status: string, proving_time_ms: number, } } | { "event_type": "ZkPorProgress", "payload": { epoch: bigint, stage: string, done: number, total: number, } } | { "event_type": "WhisperSessionStarted", "payload": { session_id: string, counterparty: WhisperCounterparty, } } | { "event_type": "WhisperReceived", "payload": { session_id: string, sequence: bigint, msg_type: string, timestamp: bigint, } } | { "event_type": "WhisperSessionEnded", "payload": { session_id: string, reason: WhisperSessionEndReason, } } | { "event_type": "WhisperSeedTransferReceived", "payload": { session_id: string, amount: bigint, tx_hash: string, } } | { "event_type": "WhisperIdentityRevealed", "payload": { session_id: string, counterparty: WhisperCounterparty, } } | { "event_type": "WhisperThrottleChanged", "payload": { session_id: string, new_tier: string, total_cost: number, } } | { "event_type": "WhisperBackgroundGraceStarted", "payload": { session_id: string, grace_seconds: number, } } | { "event_type": "WhisperAttachmentProgress", "payload": { content_hash: string, received_chunks: number, total_chunks: number, received_bytes: bigint, complete: boolean, } } | { "event_type": "WhisperDeliveryStateChanged", "payload": { session_id: string, sequence: bigint, state: WhisperDeliveryState, } } | { "event_type": "HandleDeprecated", "payload": { handle: string, successor_handle: string | null, } } | { "event_type": "HandleExpiring", "payload": { handle: string, expires_at: bigint, } } | { "event_type": "HandleTransitionDetected", "payload": { old_handle: string, new_handle: string, kind: HandleTransitionKind, grace_until: bigint, } } | { "event_type": "WhisperPingReceived", "payload": { timestamp: bigint, } };
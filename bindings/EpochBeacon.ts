@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Quorum-signed randomness beacon for one epoch (Section 12.10).
+ */
+export type EpochBeacon = { epoch: number, 
+/**
+ * Beacon value of the previous epoch, or zeros for the first beacon.
+ */
+prev_beacon: string, 
+/**
+ * FROST signature over the epoch and previous beacon.
+ */
+quorum_sig: string, };
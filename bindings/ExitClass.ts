@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A class of payload delivered at a circuit's exit, one per message type
+ * range (Section 26.1).
+ */
+export type ExitClass = "content" | "dht" | "rendezvous" | "group" | "quorum" | "gossip" | "whisper" | "oracle" | "recovery" | "state_sync";
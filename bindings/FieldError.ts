@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A problem with one parameter.
+ */
+export type FieldError = { 
+/**
+ * Dotted path to the field; empty for the params object itself.
+ */
+path: string, message: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `generate_contact_token`.
+ */
+export type GenerateContactTokenParams = { 
+/**
+ * Default 24.
+ */
+ttl_hours: number | null, };
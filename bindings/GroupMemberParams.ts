@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `kick_member` and the role grant and revoke methods.
+ */
+export type GroupMemberParams = { group_id: string, target_pik: string, };
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupSettings } from "./GroupSettings";
+import type { OwnershipTransferRecord } from "./OwnershipTransferRecord";
+
+/**
+ * Owner-signed Space policy, published as a mutable DHT record so member
+ * daemons enforce the same settings (Section 8.11).
+ */
+export type GroupPolicy = { group_id: string, 
+/**
+ * Increases with every change; the record's sequence number.
+ */
+version: bigint, settings: GroupSettings, 
+/**
+ * Ownership transfer awaiting its timelock, if any.
+ */
+pending_transfer: OwnershipTransferRecord | null, issued_at: bigint, };
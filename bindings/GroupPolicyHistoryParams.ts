@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `get_group_policy_history`.
+ */
+export type GroupPolicyHistoryParams = { group_id: string, 
+/**
+ * Default 50.
+ */
+limit: number | null, };
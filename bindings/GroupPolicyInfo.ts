@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupPolicy } from "./GroupPolicy";
+
+/**
+ * Result of `get_group_policy`.
+ */
+export type GroupPolicyInfo = { owner_pik: string, policy: GroupPolicy, };
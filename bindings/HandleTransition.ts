@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HandleTransitionKind } from "./HandleTransitionKind";
+
+/**
+ * Signed link from a handle to its successor (Section 7.2).
+ *
+ * Published at the old handle's transition address so resolvers can follow
+ * a rename or an ownership transfer. Signed by `old_signing_pk`.
+ */
+export type HandleTransition = { old_handle: string, old_signing_pk: string, 
+/**
+ * Equal to `old_handle` when only the owner key changes.
+ */
+new_handle: string, new_signing_pk: string, kind: HandleTransitionKind, issued_at: bigint, 
+/**
+ * Until then the old handle still resolves.
+ */
+grace_until: bigint, sig: string, };
@@ -5,7 +5,11 @@ import type { SpaceTemplate } from "./SpaceTemplate";
 /**
  * Layout configuration for a Space (Section 22.9).
  */
-export type LayoutConfig = { layout_type: SpaceTemplate, sections: Array<LayoutSection>, 
+export type LayoutConfig = { 
+/**
+ * Schema version; manifests without one are version 1.
+ */
+schema_version: number, layout_type: SpaceTemplate, sections: Array<LayoutSection>, 
 /**
  * Sandboxed CSS subset.
  */
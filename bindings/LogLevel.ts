@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Log severity, least severe first.
+ */
+export type LogLevel = "debug" | "info" | "warn" | "error";
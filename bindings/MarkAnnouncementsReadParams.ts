@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `mark_announcements_read`. Omitted seq = all.
+ */
+export type MarkAnnouncementsReadParams = { group_id: string, seq: bigint | null, };
@@ -0,0 +1,42 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NetworkKind } from "./NetworkKind";
+
+/**
+ * Protocol parameters of one network.
+ */
+export type NetworkProfile = { kind: NetworkKind, 
+/**
+ * Sent in every capability exchange; peers with another magic are
+ * refused.
+ */
+magic: number, epoch_duration_secs: bigint, relay_epoch_duration_secs: bigint, 
+/**
+ * Kademlia bucket size.
+ */
+dht_k: number, 
+/**
+ * Leading zero bits of a Whisper mailbox deposit PoW.
+ */
+deposit_pow_difficulty: number, 
+/**
+ * Leading zero bits of a large DHT put's admission PoW.
+ */
+dht_admission_difficulty: number, circuit_lifetime_secs: bigint, 
+/**
+ * Seed nodes as `host:port`.
+ */
+bootstrap_nodes: Array<string>, 
+/**
+ * Domains whose TXT records list seed nodes.
+ */
+dns_seeds: Array<string>, 
+/**
+ * Ed25519 key signing bootstrap lists; without one, lists are not
+ * used.
+ */
+bootstrap_list_key: string | null, 
+/**
+ * Genesis quorum group key the key schedule is checked from; without
+ * one, no quorum key is trusted.
+ */
+quorum_genesis_key: string | null, };
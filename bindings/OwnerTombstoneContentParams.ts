@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `owner_tombstone_content`. Default reason "removed".
+ */
+export type OwnerTombstoneContentParams = { content_hash: string, reason: string | null, };
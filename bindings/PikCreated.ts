@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `init_pik`.
+ */
+export type PikCreated = { pik_hash: string, created: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `pin_announcement`.
+ */
+export type PinAnnouncementParams = { group_id: string, seq: bigint, pinned: boolean, };
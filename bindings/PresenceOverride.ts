@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Per-contact presence override; `None` fields follow the global setting.
+ */
+export type PresenceOverride = { typing_indicators: boolean | null, read_receipts: boolean | null, online_presence: boolean | null, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LayoutConfig } from "./LayoutConfig";
+
+/**
+ * Params of `preview_layout_manifest`.
+ */
+export type PreviewLayoutManifestParams = { config: LayoutConfig, 
+/**
+ * Space whose catalog pinned content is checked against.
+ */
+group_id: string | null, };
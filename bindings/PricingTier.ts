@@ -12,4 +12,12 @@ price_seeds: bigint,
 /**
  * Days for rental tiers.
  */
-rental_days: number | null, };
+rental_days: number | null, 
+/**
+ * Other content unlocked together with this item (bundle tiers).
+ */
+bundle_hashes: Array<string>, 
+/**
+ * Epochs per renewal period (subscription tiers).
+ */
+renewal_epochs: number | null, };
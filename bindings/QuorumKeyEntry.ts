@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Quorum group key in force from an epoch (Section 12.8).
+ *
+ * Entries form the quorum key schedule: each one is endorsed by the group
+ * key of the entry before it, and the genesis entry by its own key.
+ */
+export type QuorumKeyEntry = { activated_epoch: number, 
+/**
+ * FROST group verifying key.
+ */
+group_pk: string, threshold: number, quorum_size: number, 
+/**
+ * FROST signature by the previous entry's group key.
+ */
+endorsement: string, };
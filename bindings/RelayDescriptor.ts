@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExitPolicy } from "./ExitPolicy";
+
+/**
+ * Relay descriptor (Section 22.10).
+ */
+export type RelayDescriptor = { node_id: string, pik_hash: string, x25519_pk: string, mlkem768_ek: string, relay_epoch: number, posrv_score: number, ip_addr: string, as_number: number, country_code: string, bandwidth_cap_mbps: number, uptime_epochs: number, 
+/**
+ * What the relay delivers when it is the exit hop (Section 4.9).
+ * Descriptors without one accept all traffic.
+ */
+exit_policy: ExitPolicy, 
+/**
+ * Encoded delegation certificate when the descriptor is published by
+ * an operational subkey rather than the PIK itself (Section 6.9).
+ */
+delegation: string | null, sig: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `export_revocation_certificate`.
+ */
+export type RevocationCertificateExport = { 
+/**
+ * Hex-encoded certificate.
+ */
+certificate: string, dht_address: string, };
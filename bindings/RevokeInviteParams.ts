@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `revoke_invite`.
+ */
+export type RevokeInviteParams = { invite_hash: string, };
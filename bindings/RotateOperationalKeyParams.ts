@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `rotate_operational_key`. Omitted scope = every scope.
+ */
+export type RotateOperationalKeyParams = { scope: Array<string> | null, 
+/**
+ * 1-365; default 7.
+ */
+validity_epochs: number | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `get_safety_number`.
+ */
+export type SafetyNumberInfo = { safety_number: string, qr_payload: string, verified: boolean, verified_at: bigint | null, key_changed_at: bigint | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `set_group_quota`. Omitted limits are unlimited.
+ */
+export type SetGroupQuotaParams = { group_id: string, max_total_bytes: bigint | null, max_member_bytes: bigint | null, max_item_bytes: bigint | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `get_subgroup_members`, `mls_grant_subgroup_access` and
+ * `mls_revoke_subgroup_access`.
+ */
+export type SubgroupIdParams = { subgroup_id: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `update_display_name`.
+ */
+export type UpdateDisplayNameParams = { new_name: string, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LayoutConfig } from "./LayoutConfig";
+
+/**
+ * Params of `update_group_layout_manifest`.
+ */
+export type UpdateGroupLayoutManifestParams = { group_id: string, config: LayoutConfig, };
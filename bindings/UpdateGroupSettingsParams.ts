@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupSettings } from "./GroupSettings";
+
+/**
+ * Params of `update_group_settings`.
+ */
+export type UpdateGroupSettingsParams = { group_id: string, settings: GroupSettings, };
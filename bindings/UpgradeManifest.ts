@@ -1,4 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CircuitArtifact } from "./CircuitArtifact";
+import type { CircuitVersion } from "./CircuitVersion";
 import type { MultisigEntry } from "./MultisigEntry";
 import type { PlatformHash } from "./PlatformHash";
 
@@ -17,4 +19,12 @@ activation_epoch: bigint, platform_hashes: Array<PlatformHash>, changelog_url: s
 /**
  * 3-of-5 minimum.
  */
-multisig_sigs: Array<MultisigEntry>, published_at: bigint, };
+multisig_sigs: Array<MultisigEntry>, published_at: bigint, 
+/**
+ * Groth16 keys this release may use.
+ */
+circuit_artifacts: Array<CircuitArtifact>, 
+/**
+ * Key versions whose proofs must no longer be accepted.
+ */
+revoked_circuits: Array<CircuitVersion>, };
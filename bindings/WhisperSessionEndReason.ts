@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Why a Whisper session ended.
+ */
+export type WhisperSessionEndReason = "closed" | "timeout" | "offline" | "blocked" | "grace_expired";
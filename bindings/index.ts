@@ -0,0 +1,177 @@
+// This file was generated by `cargo run -p ochra-types --bin export-bindings`. Do not edit this file manually.
+export type { AccessStatus } from "./AccessStatus";
+export type { ActivityEvent } from "./ActivityEvent";
+export type { AddContactParams } from "./AddContactParams";
+export type { AnnouncementInfo } from "./AnnouncementInfo";
+export type { AnnouncementPosted } from "./AnnouncementPosted";
+export type { AnnouncementsMarked } from "./AnnouncementsMarked";
+export type { Authenticated } from "./Authenticated";
+export type { BiometricEnrollment } from "./BiometricEnrollment";
+export type { CatalogDiffRequest } from "./CatalogDiffRequest";
+export type { CatalogDiffResponse } from "./CatalogDiffResponse";
+export type { CatalogFacet } from "./CatalogFacet";
+export type { ChangePasswordParams } from "./ChangePasswordParams";
+export type { CircuitArtifact } from "./CircuitArtifact";
+export type { CircuitId } from "./CircuitId";
+export type { CircuitMetrics } from "./CircuitMetrics";
+export type { CircuitVersion } from "./CircuitVersion";
+export type { Contact } from "./Contact";
+export type { ContactAdded } from "./ContactAdded";
+export type { ContactExchangeToken } from "./ContactExchangeToken";
+export type { ContactListEntry } from "./ContactListEntry";
+export type { ContactPikParams } from "./ContactPikParams";
+export type { ContactToken } from "./ContactToken";
+export type { ContentEarning } from "./ContentEarning";
+export type { ContentManifest } from "./ContentManifest";
+export type { ContentRating } from "./ContentRating";
+export type { ContentReport } from "./ContentReport";
+export type { ContentTag } from "./ContentTag";
+export type { CoverTrafficMetrics } from "./CoverTrafficMetrics";
+export type { CoverTrafficMode } from "./CoverTrafficMode";
+export type { CreateGroupParams } from "./CreateGroupParams";
+export type { CreateSubgroupParams } from "./CreateSubgroupParams";
+export type { DaemonEvent } from "./DaemonEvent";
+export type { DeprecationTombstone } from "./DeprecationTombstone";
+export type { DismissContentReportParams } from "./DismissContentReportParams";
+export type { DownloadProgress } from "./DownloadProgress";
+export type { DownloadState } from "./DownloadState";
+export type { EarningsReport } from "./EarningsReport";
+export type { EarningsTrend } from "./EarningsTrend";
+export type { EpochBeacon } from "./EpochBeacon";
+export type { EpochState } from "./EpochState";
+export type { Event } from "./Event";
+export type { ExitClass } from "./ExitClass";
+export type { ExitPolicy } from "./ExitPolicy";
+export type { ExportRevocationCertificateParams } from "./ExportRevocationCertificateParams";
+export type { ExportSpaceArchiveParams } from "./ExportSpaceArchiveParams";
+export type { FieldError } from "./FieldError";
+export type { FlushStats } from "./FlushStats";
+export type { GenerateContactTokenParams } from "./GenerateContactTokenParams";
+export type { GenerateInviteParams } from "./GenerateInviteParams";
+export type { GenesisAllocation } from "./GenesisAllocation";
+export type { GenesisManifest } from "./GenesisManifest";
+export type { GroupCreated } from "./GroupCreated";
+export type { GroupIdParams } from "./GroupIdParams";
+export type { GroupMemberParams } from "./GroupMemberParams";
+export type { GroupPolicy } from "./GroupPolicy";
+export type { GroupPolicyHistoryParams } from "./GroupPolicyHistoryParams";
+export type { GroupPolicyInfo } from "./GroupPolicyInfo";
+export type { GroupPolicyVersion } from "./GroupPolicyVersion";
+export type { GroupSettings } from "./GroupSettings";
+export type { GroupSettingsUpdated } from "./GroupSettingsUpdated";
+export type { GroupSummary } from "./GroupSummary";
+export type { GuardianStatus } from "./GuardianStatus";
+export type { HandleDescriptor } from "./HandleDescriptor";
+export type { HandleInfo } from "./HandleInfo";
+export type { HandleRegistration } from "./HandleRegistration";
+export type { HandleStatus } from "./HandleStatus";
+export type { HandleTransition } from "./HandleTransition";
+export type { HandleTransitionKind } from "./HandleTransitionKind";
+export type { IdentityProof } from "./IdentityProof";
+export type { IdentityReveal } from "./IdentityReveal";
+export type { ImportSpaceArchiveParams } from "./ImportSpaceArchiveParams";
+export type { InitiateRecoveryParams } from "./InitiateRecoveryParams";
+export type { IntroPointEntry } from "./IntroPointEntry";
+export type { InviteInfo } from "./InviteInfo";
+export type { InvitePermission } from "./InvitePermission";
+export type { JoinGroupParams } from "./JoinGroupParams";
+export type { JoinRule } from "./JoinRule";
+export type { LayoutConfig } from "./LayoutConfig";
+export type { LayoutError } from "./LayoutError";
+export type { LayoutManifestUpdated } from "./LayoutManifestUpdated";
+export type { LayoutPreview } from "./LayoutPreview";
+export type { LayoutSection } from "./LayoutSection";
+export type { LogEntry } from "./LogEntry";
+export type { LogLevel } from "./LogLevel";
+export type { LogSubsystem } from "./LogSubsystem";
+export type { MailboxEntry } from "./MailboxEntry";
+export type { MarkAnnouncementsReadParams } from "./MarkAnnouncementsReadParams";
+export type { MemberLeftReason } from "./MemberLeftReason";
+export type { MemberRole } from "./MemberRole";
+export type { MpcSession } from "./MpcSession";
+export type { MpcStatus } from "./MpcStatus";
+export type { MultisigEntry } from "./MultisigEntry";
+export type { MyPik } from "./MyPik";
+export type { NatStatus } from "./NatStatus";
+export type { NetworkKind } from "./NetworkKind";
+export type { NetworkProfile } from "./NetworkProfile";
+export type { NominateGuardianParams } from "./NominateGuardianParams";
+export type { NotificationSettings } from "./NotificationSettings";
+export type { NullifierGossipMsg } from "./NullifierGossipMsg";
+export type { OperationalKey } from "./OperationalKey";
+export type { OwnerTombstoneContentParams } from "./OwnerTombstoneContentParams";
+export type { OwnershipTransferRecord } from "./OwnershipTransferRecord";
+export type { PasswordParams } from "./PasswordParams";
+export type { PeerProfile } from "./PeerProfile";
+export type { PikCreated } from "./PikCreated";
+export type { PikMeta } from "./PikMeta";
+export type { PinAnnouncementParams } from "./PinAnnouncementParams";
+export type { Platform } from "./Platform";
+export type { PlatformHash } from "./PlatformHash";
+export type { PoSrvEntry } from "./PoSrvEntry";
+export type { PorStatus } from "./PorStatus";
+export type { PorSubmissionStatus } from "./PorSubmissionStatus";
+export type { PostAnnouncementParams } from "./PostAnnouncementParams";
+export type { PresenceOverride } from "./PresenceOverride";
+export type { PresencePrivacy } from "./PresencePrivacy";
+export type { PreviewLayoutManifestParams } from "./PreviewLayoutManifestParams";
+export type { PricingTier } from "./PricingTier";
+export type { ProfileKeyExchange } from "./ProfileKeyExchange";
+export type { PublishPolicy } from "./PublishPolicy";
+export type { PublishRevocationCertificateParams } from "./PublishRevocationCertificateParams";
+export type { PurchaseRecord } from "./PurchaseRecord";
+export type { QuorumKeyEntry } from "./QuorumKeyEntry";
+export type { ReceiptInfo } from "./ReceiptInfo";
+export type { RecoveryAlertType } from "./RecoveryAlertType";
+export type { RefundState } from "./RefundState";
+export type { RefundStatus } from "./RefundStatus";
+export type { RelayDescriptor } from "./RelayDescriptor";
+export type { RelayReceipt } from "./RelayReceipt";
+export type { RenderableLayout } from "./RenderableLayout";
+export type { RenderedSection } from "./RenderedSection";
+export type { ReplaceGuardianParams } from "./ReplaceGuardianParams";
+export type { ReportContentParams } from "./ReportContentParams";
+export type { ReportReason } from "./ReportReason";
+export type { RevenueSplit } from "./RevenueSplit";
+export type { RevenueSplitChangeProposal } from "./RevenueSplitChangeProposal";
+export type { RevocationCertificateExport } from "./RevocationCertificateExport";
+export type { RevocationPublished } from "./RevocationPublished";
+export type { RevokeInviteParams } from "./RevokeInviteParams";
+export type { RollbackManifest } from "./RollbackManifest";
+export type { RotateOperationalKeyParams } from "./RotateOperationalKeyParams";
+export type { SafetyNumberInfo } from "./SafetyNumberInfo";
+export type { SectionType } from "./SectionType";
+export type { ServiceReceipt } from "./ServiceReceipt";
+export type { SessionState } from "./SessionState";
+export type { SetGroupQuotaParams } from "./SetGroupQuotaParams";
+export type { SingleReport } from "./SingleReport";
+export type { SlowCall } from "./SlowCall";
+export type { SpaceManifest } from "./SpaceManifest";
+export type { SpaceStats } from "./SpaceStats";
+export type { SpaceTemplate } from "./SpaceTemplate";
+export type { StateCheckpoint } from "./StateCheckpoint";
+export type { SubgroupIdParams } from "./SubgroupIdParams";
+export type { TagNamespace } from "./TagNamespace";
+export type { ThrottleStatus } from "./ThrottleStatus";
+export type { ThrottleStrictness } from "./ThrottleStrictness";
+export type { TierType } from "./TierType";
+export type { TimelockAction } from "./TimelockAction";
+export type { TimelockPending } from "./TimelockPending";
+export type { TimelockStatus } from "./TimelockStatus";
+export type { TransferCancelReason } from "./TransferCancelReason";
+export type { TransferGroupOwnershipParams } from "./TransferGroupOwnershipParams";
+export type { UpdateDisplayNameParams } from "./UpdateDisplayNameParams";
+export type { UpdateGroupLayoutManifestParams } from "./UpdateGroupLayoutManifestParams";
+export type { UpdateGroupSettingsParams } from "./UpdateGroupSettingsParams";
+export type { UpdateStatus } from "./UpdateStatus";
+export type { UpgradeManifest } from "./UpgradeManifest";
+export type { VerifyContactParams } from "./VerifyContactParams";
+export type { VetoRecoveryParams } from "./VetoRecoveryParams";
+export type { WhisperCounterparty } from "./WhisperCounterparty";
+export type { WhisperDeliveryState } from "./WhisperDeliveryState";
+export type { WhisperMessage } from "./WhisperMessage";
+export type { WhisperMsgType } from "./WhisperMsgType";
+export type { WhisperPing } from "./WhisperPing";
+export type { WhisperSessionEndReason } from "./WhisperSessionEndReason";
+export type { WhisperSessionSummary } from "./WhisperSessionSummary";
+export type { WhisperTarget } from "./WhisperTarget";
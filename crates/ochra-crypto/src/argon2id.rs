@@ -4,8 +4,20 @@
 //! - PIK-at-rest key derivation (m=256MB, t=3, p=4)
 //! - Publishing Proof-of-Work
 //! - Handle registration Proof-of-Work
+//!
+//! ## Profiles
+//!
+//! Fixed costs are too slow on a phone and too cheap on a large server, so
+//! each use has a [`KdfProfile`] with spec-allowed bounds. [`calibrate`]
+//! benchmarks the host once and picks the costliest parameters within the
+//! bounds that still meet a latency target. Whoever verifies a hash must
+//! know the parameters it was made with, so stored keys and PoW proofs
+//! carry their [`Argon2Params`], checked with [`Argon2Params::validate`].
+
+use std::time::{Duration, Instant};
 
 use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
 
 use crate::{CryptoError, Result};
 
@@ -64,6 +76,211 @@ pub fn derive_key_custom(
     Ok(output)
 }
 
+/// Argon2id cost parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Argon2Params {
+    /// Memory cost in KiB.
+    pub m_cost: u32,
+    /// Time cost (iterations).
+    pub t_cost: u32,
+    /// Parallelism lanes.
+    pub p_cost: u32,
+}
+
+impl Argon2Params {
+    /// Parameters of PIKs stored before calibration existed.
+    pub const PIK: Self = Self {
+        m_cost: PIK_M_COST,
+        t_cost: PIK_T_COST,
+        p_cost: PIK_P_COST,
+    };
+
+    /// Derive `output_len` bytes with these parameters.
+    pub fn derive(&self, password: &[u8], salt: &[u8], output_len: usize) -> Result<Vec<u8>> {
+        derive_key_custom(
+            password,
+            salt,
+            self.m_cost,
+            self.t_cost,
+            self.p_cost,
+            output_len,
+        )
+    }
+
+    /// Derive a 32-byte key with these parameters.
+    pub fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<[u8; 32]> {
+        let out = self.derive(password, salt, 32)?;
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&out);
+        Ok(key)
+    }
+
+    /// Check that these parameters are within `profile`'s bounds.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Argon2`] naming the first cost out of range
+    pub fn validate(&self, profile: KdfProfile) -> Result<()> {
+        let (min, max) = profile.bounds();
+        let costs = [
+            ("m_cost", self.m_cost, min.m_cost, max.m_cost),
+            ("t_cost", self.t_cost, min.t_cost, max.t_cost),
+            ("p_cost", self.p_cost, min.p_cost, max.p_cost),
+        ];
+        for (name, value, lo, hi) in costs {
+            if !(lo..=hi).contains(&value) {
+                return Err(CryptoError::Argon2(format!(
+                    "{} {name} {value} outside {lo}..={hi}",
+                    profile.as_str()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a set of Argon2id parameters is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KdfProfile {
+    /// Password hashing (PIK-at-rest key derivation).
+    Password,
+    /// Proof-of-Work for publishing, handles and DHT admission.
+    Pow,
+}
+
+impl KdfProfile {
+    /// Name used in errors and RPC results.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Password => "password",
+            Self::Pow => "pow",
+        }
+    }
+
+    /// Inclusive lower and upper bounds on each cost.
+    ///
+    /// Password hashing may not drop below 64 MB and two passes. PoW may not
+    /// drop below the network default, so a proof is never cheaper to make
+    /// than the difficulty assumes, and is capped at 64 MB so verifying a
+    /// declared proof stays cheap.
+    pub fn bounds(self) -> (Argon2Params, Argon2Params) {
+        match self {
+            Self::Password => (
+                Argon2Params {
+                    m_cost: 64 * 1024,
+                    t_cost: 2,
+                    p_cost: 1,
+                },
+                Argon2Params {
+                    m_cost: 1024 * 1024,
+                    t_cost: 8,
+                    p_cost: 8,
+                },
+            ),
+            Self::Pow => (
+                Self::Pow.default_params(),
+                Argon2Params {
+                    m_cost: 64 * 1024,
+                    t_cost: 2,
+                    p_cost: 4,
+                },
+            ),
+        }
+    }
+
+    /// Parameters used when no calibration has been done.
+    pub fn default_params(self) -> Argon2Params {
+        match self {
+            Self::Password => Argon2Params::PIK,
+            // Must match ochra-pow's POW_M_COST / POW_T_COST / POW_P_COST.
+            Self::Pow => Argon2Params {
+                m_cost: 16 * 1024,
+                t_cost: 1,
+                p_cost: 1,
+            },
+        }
+    }
+}
+
+/// Result of [`calibrate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Chosen parameters.
+    pub params: Argon2Params,
+    /// Measured time of one hash with `params`, in milliseconds.
+    pub elapsed_ms: u64,
+}
+
+/// Time one 32-byte derivation with `params`.
+pub fn measure(params: &Argon2Params) -> Result<Duration> {
+    let start = Instant::now();
+    params.derive(b"ochra calibration", &[0u8; 16], 32)?;
+    Ok(start.elapsed())
+}
+
+/// Pick the costliest parameters within `profile`'s bounds whose hash time
+/// stays under `target`.
+///
+/// Memory is doubled from the profile minimum up to `memory_limit_kib`
+/// while the projected time fits, then iterations are added the same way.
+/// Lanes are `lanes` clamped to the bounds. If even the minimum exceeds the
+/// target, the minimum is returned: the floor is not negotiable.
+/// `measure` times one hash; pass [`measure`] outside tests.
+pub fn calibrate<F>(
+    profile: KdfProfile,
+    target: Duration,
+    memory_limit_kib: u32,
+    lanes: u32,
+    mut measure: F,
+) -> Result<Calibration>
+where
+    F: FnMut(&Argon2Params) -> Result<Duration>,
+{
+    let (min, max) = profile.bounds();
+    let memory_cap = memory_limit_kib.clamp(min.m_cost, max.m_cost);
+    let mut params = Argon2Params {
+        m_cost: min.m_cost,
+        t_cost: min.t_cost,
+        p_cost: lanes.clamp(min.p_cost, max.p_cost),
+    };
+    let mut elapsed = measure(&params)?;
+
+    while params.m_cost < memory_cap {
+        let next = params.m_cost.saturating_mul(2).min(memory_cap);
+        let projected = elapsed.mul_f64(f64::from(next) / f64::from(params.m_cost));
+        if projected > target {
+            break;
+        }
+        params.m_cost = next;
+        elapsed = measure(&params)?;
+    }
+
+    let mut per_pass = elapsed / params.t_cost;
+    while params.t_cost < max.t_cost && per_pass * (params.t_cost + 1) <= target {
+        params.t_cost += 1;
+        elapsed = measure(&params)?;
+        per_pass = elapsed / params.t_cost;
+    }
+
+    Ok(Calibration {
+        params,
+        elapsed_ms: elapsed.as_millis() as u64,
+    })
+}
+
+/// [`calibrate`] against this host, using all available cores as lanes.
+pub fn calibrate_host(
+    profile: KdfProfile,
+    target: Duration,
+    memory_limit_kib: u32,
+) -> Result<Calibration> {
+    let lanes = std::thread::available_parallelism()
+        .map(|n| n.get() as u32)
+        .unwrap_or(1);
+    calibrate(profile, target, memory_limit_kib, lanes, measure)
+}
+
 /// Verify an Argon2id Proof-of-Work.
 ///
 /// The work is valid if the first `difficulty` bits of the output are zero.
@@ -160,6 +377,80 @@ mod tests {
         assert_eq!(salt1.len(), 16);
     }
 
+    /// A host where a hash costs 1 ms per MB per pass.
+    fn fake_host(params: &Argon2Params) -> Result<Duration> {
+        Ok(Duration::from_millis(
+            u64::from(params.m_cost / 1024) * u64::from(params.t_cost),
+        ))
+    }
+
+    #[test]
+    fn test_calibrate_scales_memory_then_iterations() {
+        // 500 ms: 64 MB -> 128 -> 256 at t=2 is 512 ms, so memory stops at
+        // 128 MB, then a third pass fits (384 ms) and a fourth (512) does not.
+        let c = calibrate(
+            KdfProfile::Password,
+            Duration::from_millis(500),
+            u32::MAX,
+            16,
+            fake_host,
+        )
+        .expect("calibrate");
+        assert_eq!(
+            c.params,
+            Argon2Params {
+                m_cost: 128 * 1024,
+                t_cost: 3,
+                p_cost: 8,
+            }
+        );
+        assert_eq!(c.elapsed_ms, 384);
+        assert!(c.params.validate(KdfProfile::Password).is_ok());
+    }
+
+    #[test]
+    fn test_calibrate_respects_memory_limit_and_floor() {
+        let capped = calibrate(
+            KdfProfile::Password,
+            Duration::from_secs(60),
+            100 * 1024,
+            1,
+            fake_host,
+        )
+        .expect("calibrate");
+        assert_eq!(capped.params.m_cost, 100 * 1024);
+        assert_eq!(capped.params.t_cost, 8);
+
+        // A host too slow for the target still gets the minimum.
+        let slow = calibrate(
+            KdfProfile::Pow,
+            Duration::from_millis(1),
+            u32::MAX,
+            1,
+            fake_host,
+        )
+        .expect("calibrate");
+        assert_eq!(slow.params, KdfProfile::Pow.default_params());
+    }
+
+    #[test]
+    fn test_validate_profile_bounds() {
+        assert!(Argon2Params::PIK.validate(KdfProfile::Password).is_ok());
+        assert!(KdfProfile::Pow
+            .default_params()
+            .validate(KdfProfile::Pow)
+            .is_ok());
+        // Password-strength memory is too costly for verifiers of a PoW.
+        assert!(Argon2Params::PIK.validate(KdfProfile::Pow).is_err());
+        let cheap = Argon2Params {
+            m_cost: 1024,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        assert!(cheap.validate(KdfProfile::Pow).is_err());
+        assert!(cheap.validate(KdfProfile::Password).is_err());
+    }
+
     #[test]
     fn test_pow_verification() {
         // With difficulty 0, any input should pass
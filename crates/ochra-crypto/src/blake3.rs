@@ -56,6 +56,17 @@ pub mod contexts {
     pub const RATCHET_NONCE: &str = "Ochra v1 ratchet-nonce";
     pub const WHISPER_RATCHET_ROOT: &str = "Ochra v1 whisper-ratchet-root";
     pub const SYBILGUARD_WALK: &str = "Ochra v1 sybilguard-walk";
+    pub const MAILBOX_SIGNING_KEY: &str = "Ochra v1 mailbox-signing-key";
+    pub const MAILBOX_SEAL_KEY: &str = "Ochra v1 mailbox-seal-key";
+    pub const MEMBERSHIP_CREDENTIAL_KEY: &str = "Ochra v1 membership-credential-key";
+    pub const SPACE_ARCHIVE_KEY: &str = "Ochra v1 space-archive-key";
+    pub const ZK_POR_NODE_SECRET: &str = "Ochra v1 zk-por-node-secret";
+    pub const RECEIPT_REISSUE_KEY: &str = "Ochra v1 receipt-reissue-key";
+    pub const WHISPER_BACKUP_KEY: &str = "Ochra v1 whisper-backup-key";
+    pub const WHISPER_BACKUP_WRAP: &str = "Ochra v1 whisper-backup-wrap";
+    pub const WHISPER_BACKUP_DROP: &str = "Ochra v1 whisper-backup-drop";
+    pub const MINT_SESSION_KEY: &str = "Ochra v1 mint-session-key";
+    pub const TIMELOCK_KEY: &str = "Ochra v1 timelock-key";
 
     /// All registered context strings. Used for validation.
     pub const ALL_CONTEXTS: &[&str] = &[
@@ -98,6 +109,17 @@ pub mod contexts {
         RATCHET_NONCE,
         WHISPER_RATCHET_ROOT,
         SYBILGUARD_WALK,
+        MAILBOX_SIGNING_KEY,
+        MAILBOX_SEAL_KEY,
+        MEMBERSHIP_CREDENTIAL_KEY,
+        SPACE_ARCHIVE_KEY,
+        ZK_POR_NODE_SECRET,
+        RECEIPT_REISSUE_KEY,
+        WHISPER_BACKUP_KEY,
+        WHISPER_BACKUP_WRAP,
+        WHISPER_BACKUP_DROP,
+        MINT_SESSION_KEY,
+        TIMELOCK_KEY,
     ];
 }
 
@@ -0,0 +1,592 @@
+//! Groth16 circuit key management (Sections 2.6, 17).
+//!
+//! Proving and verifying keys are produced by per-circuit Phase 2 ceremonies
+//! and rotated by re-running the ceremony under a new version number. The
+//! hashes of every key a release may use are pinned by the governance
+//! [`UpgradeManifest`]; the [`CircuitKeyManager`] only accepts key bytes that
+//! match a pinned hash, so keys can be fetched from untrusted peers.
+//!
+//! Several versions of a circuit may be loaded at once: proofs made under an
+//! older key remain verifiable during a rotation, while new proofs use the
+//! highest version whose proving key is available. A version listed in a
+//! manifest's `revoked_circuits` is dropped permanently and every proof
+//! claiming it is refused.
+//!
+//! Callers must check the manifest's multisig before applying it.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use ark_bls12_381::Fr;
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ochra_types::governance::{CircuitArtifact, CircuitId, CircuitVersion, UpgradeManifest};
+use serde::{Deserialize, Serialize};
+
+use crate::blake3;
+use crate::groth16::{self, SerializedProof, SerializedProvingKey, SerializedVerifyingKey};
+use crate::{CryptoError, Result};
+
+/// File in the cache directory holding pinned hashes and revocations.
+pub const PINS_FILE: &str = "pins.json";
+
+/// A Groth16 proof tagged with the key version it was generated under.
+#[derive(Clone, Debug)]
+pub struct VersionedProof {
+    pub key: CircuitVersion,
+    pub proof: SerializedProof,
+}
+
+/// Pinned state persisted alongside cached keys.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Pins {
+    artifacts: Vec<CircuitArtifact>,
+    revoked: Vec<CircuitVersion>,
+}
+
+fn circuit_name(circuit: CircuitId) -> &'static str {
+    match circuit {
+        CircuitId::Minting => "minting",
+        CircuitId::ZkPor => "zk-por",
+        CircuitId::Refund => "refund",
+        CircuitId::ContentKey => "content-key",
+    }
+}
+
+fn describe(key: &CircuitVersion) -> String {
+    format!("{} v{}", circuit_name(key.circuit), key.version)
+}
+
+fn io_error(e: std::io::Error) -> CryptoError {
+    CryptoError::CircuitKey(e.to_string())
+}
+
+/// Pins, caches, and rotates Groth16 keys.
+#[derive(Debug, Default)]
+pub struct CircuitKeyManager {
+    cache_dir: Option<PathBuf>,
+    pinned: HashMap<CircuitVersion, CircuitArtifact>,
+    revoked: HashSet<CircuitVersion>,
+    verifying_keys: HashMap<CircuitVersion, SerializedVerifyingKey>,
+    proving_keys: HashMap<CircuitVersion, SerializedProvingKey>,
+}
+
+impl CircuitKeyManager {
+    /// Create a manager that keeps keys in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a manager backed by `dir`, loading previously cached pins and
+    /// keys.
+    ///
+    /// Cached keys that no longer match their pinned hash are deleted.
+    pub fn with_cache_dir(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(io_error)?;
+
+        let mut manager = Self {
+            cache_dir: Some(dir.clone()),
+            ..Self::default()
+        };
+        let pins_path = dir.join(PINS_FILE);
+        if pins_path.exists() {
+            let raw = std::fs::read(&pins_path).map_err(io_error)?;
+            let pins: Pins = serde_json::from_slice(&raw)
+                .map_err(|e| CryptoError::Serialization(e.to_string()))?;
+            manager.revoked.extend(pins.revoked);
+            for artifact in pins.artifacts {
+                let key = CircuitVersion {
+                    circuit: artifact.circuit,
+                    version: artifact.version,
+                };
+                if !manager.revoked.contains(&key) {
+                    manager.pinned.insert(key, artifact);
+                }
+            }
+        }
+
+        let pinned: Vec<CircuitArtifact> = manager.pinned.values().cloned().collect();
+        for artifact in pinned {
+            manager.load_cached(&dir, &artifact)?;
+        }
+        Ok(manager)
+    }
+
+    fn key_path(dir: &Path, key: &CircuitVersion, ext: &str) -> PathBuf {
+        dir.join(format!(
+            "{}-v{}.{}",
+            circuit_name(key.circuit),
+            key.version,
+            ext
+        ))
+    }
+
+    fn load_cached(&mut self, dir: &Path, artifact: &CircuitArtifact) -> Result<()> {
+        let key = CircuitVersion {
+            circuit: artifact.circuit,
+            version: artifact.version,
+        };
+        for (ext, expected) in [("vk", artifact.vk_hash), ("pk", artifact.pk_hash)] {
+            let path = Self::key_path(dir, &key, ext);
+            if !path.exists() {
+                continue;
+            }
+            let bytes = std::fs::read(&path).map_err(io_error)?;
+            if blake3::hash(&bytes) != expected {
+                std::fs::remove_file(&path).map_err(io_error)?;
+                continue;
+            }
+            if ext == "vk" {
+                self.verifying_keys
+                    .insert(key, SerializedVerifyingKey { bytes });
+            } else {
+                self.proving_keys
+                    .insert(key, SerializedProvingKey { bytes });
+            }
+        }
+        Ok(())
+    }
+
+    fn save_pins(&self) -> Result<()> {
+        let Some(dir) = &self.cache_dir else {
+            return Ok(());
+        };
+        let pins = Pins {
+            artifacts: self.pinned.values().cloned().collect(),
+            revoked: self.revoked.iter().copied().collect(),
+        };
+        let raw =
+            serde_json::to_vec(&pins).map_err(|e| CryptoError::Serialization(e.to_string()))?;
+        std::fs::write(dir.join(PINS_FILE), raw).map_err(io_error)
+    }
+
+    fn cache(&self, key: &CircuitVersion, ext: &str, bytes: &[u8]) -> Result<()> {
+        match &self.cache_dir {
+            Some(dir) => std::fs::write(Self::key_path(dir, key, ext), bytes).map_err(io_error),
+            None => Ok(()),
+        }
+    }
+
+    fn evict(&mut self, key: &CircuitVersion) -> Result<()> {
+        self.pinned.remove(key);
+        self.verifying_keys.remove(key);
+        self.proving_keys.remove(key);
+        if let Some(dir) = &self.cache_dir {
+            for ext in ["vk", "pk"] {
+                let path = Self::key_path(dir, key, ext);
+                if path.exists() {
+                    std::fs::remove_file(path).map_err(io_error)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the key pins and revocations of a verified upgrade manifest.
+    ///
+    /// Returns the number of newly pinned versions. A manifest that re-pins
+    /// an existing version with different hashes is rejected as a whole;
+    /// rotation always uses a new version number.
+    pub fn apply_manifest(&mut self, manifest: &UpgradeManifest) -> Result<usize> {
+        for artifact in &manifest.circuit_artifacts {
+            let key = CircuitVersion {
+                circuit: artifact.circuit,
+                version: artifact.version,
+            };
+            if self.pinned.get(&key).is_some_and(|p| p != artifact) {
+                return Err(CryptoError::CircuitKey(format!(
+                    "conflicting pin for {}",
+                    describe(&key)
+                )));
+            }
+        }
+
+        for key in &manifest.revoked_circuits {
+            if self.revoked.insert(*key) {
+                self.evict(key)?;
+            }
+        }
+
+        let mut added = 0;
+        for artifact in &manifest.circuit_artifacts {
+            let key = CircuitVersion {
+                circuit: artifact.circuit,
+                version: artifact.version,
+            };
+            if self.revoked.contains(&key) || self.pinned.contains_key(&key) {
+                continue;
+            }
+            self.pinned.insert(key, artifact.clone());
+            added += 1;
+        }
+        self.save_pins()?;
+        Ok(added)
+    }
+
+    fn pinned_artifact(&self, key: &CircuitVersion) -> Result<&CircuitArtifact> {
+        if self.revoked.contains(key) {
+            return Err(CryptoError::RevokedCircuitKey(describe(key)));
+        }
+        self.pinned
+            .get(key)
+            .ok_or_else(|| CryptoError::CircuitKey(format!("{} is not pinned", describe(key))))
+    }
+
+    /// Install verifying key bytes after checking them against the pinned hash.
+    pub fn install_verifying_key(&mut self, key: CircuitVersion, bytes: Vec<u8>) -> Result<()> {
+        if blake3::hash(&bytes) != self.pinned_artifact(&key)?.vk_hash {
+            return Err(CryptoError::CircuitKey(format!(
+                "verifying key hash mismatch for {}",
+                describe(&key)
+            )));
+        }
+        self.cache(&key, "vk", &bytes)?;
+        self.verifying_keys
+            .insert(key, SerializedVerifyingKey { bytes });
+        Ok(())
+    }
+
+    /// Install proving key bytes after checking them against the pinned hash.
+    pub fn install_proving_key(&mut self, key: CircuitVersion, bytes: Vec<u8>) -> Result<()> {
+        let artifact = self.pinned_artifact(&key)?;
+        if bytes.len() as u64 != artifact.pk_size_bytes || blake3::hash(&bytes) != artifact.pk_hash
+        {
+            return Err(CryptoError::CircuitKey(format!(
+                "proving key hash mismatch for {}",
+                describe(&key)
+            )));
+        }
+        self.cache(&key, "pk", &bytes)?;
+        self.proving_keys
+            .insert(key, SerializedProvingKey { bytes });
+        Ok(())
+    }
+
+    /// Whether proofs under this key version are refused.
+    pub fn is_revoked(&self, key: &CircuitVersion) -> bool {
+        self.revoked.contains(key)
+    }
+
+    /// Pinned artifacts whose keys still need to be fetched.
+    pub fn missing(&self) -> Vec<CircuitArtifact> {
+        let mut missing: Vec<CircuitArtifact> = self
+            .pinned
+            .iter()
+            .filter(|(key, _)| {
+                !self.verifying_keys.contains_key(key) || !self.proving_keys.contains_key(key)
+            })
+            .map(|(_, artifact)| artifact.clone())
+            .collect();
+        missing.sort_by_key(|a| (circuit_name(a.circuit), a.version));
+        missing
+    }
+
+    /// Versions of a circuit whose proofs can currently be verified.
+    pub fn verifiable_versions(&self, circuit: CircuitId) -> Vec<u32> {
+        let mut versions: Vec<u32> = self
+            .verifying_keys
+            .keys()
+            .filter(|key| key.circuit == circuit)
+            .map(|key| key.version)
+            .collect();
+        versions.sort_unstable();
+        versions
+    }
+
+    /// Highest version of a circuit that new proofs can be generated under.
+    pub fn current_version(&self, circuit: CircuitId) -> Option<u32> {
+        self.proving_keys
+            .keys()
+            .filter(|key| key.circuit == circuit)
+            .map(|key| key.version)
+            .max()
+    }
+
+    /// Verifying key for a circuit version.
+    pub fn verifying_key(&self, key: &CircuitVersion) -> Result<&SerializedVerifyingKey> {
+        self.pinned_artifact(key)?;
+        self.verifying_keys.get(key).ok_or_else(|| {
+            CryptoError::CircuitKey(format!("verifying key for {} not loaded", describe(key)))
+        })
+    }
+
+    /// Generate a proof under the current version of `circuit`.
+    pub fn prove<C: ConstraintSynthesizer<Fr>>(
+        &self,
+        circuit: CircuitId,
+        synthesizer: C,
+    ) -> Result<VersionedProof> {
+        let version = self.current_version(circuit).ok_or_else(|| {
+            CryptoError::CircuitKey(format!(
+                "no proving key for {} loaded",
+                circuit_name(circuit)
+            ))
+        })?;
+        let key = CircuitVersion { circuit, version };
+        let pk = self.proving_keys.get(&key).ok_or_else(|| {
+            CryptoError::CircuitKey(format!("proving key for {} not loaded", describe(&key)))
+        })?;
+        Ok(VersionedProof {
+            key,
+            proof: groth16::prove(synthesizer, pk)?,
+        })
+    }
+
+    /// Verify a proof under the key version it claims.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::RevokedCircuitKey`] if that version has been revoked
+    /// - [`CryptoError::CircuitKey`] if the version is unknown or not loaded
+    pub fn verify(&self, proof: &VersionedProof, public_inputs: &[Fr]) -> Result<bool> {
+        let vk = self.verifying_key(&proof.key)?;
+        groth16::verify(&proof.proof, vk, public_inputs)
+    }
+
+    /// Verify many proofs, batching those made under the same key version.
+    ///
+    /// Returns one result per item, in order. Unlike [`Self::verify`], a
+    /// proof under a revoked, unpinned, or unloaded version does not fail the
+    /// call; it is reported as `false` so the rest of the batch still counts.
+    pub fn verify_batch(
+        &self,
+        items: &[(&VersionedProof, &[Fr])],
+        batch_size: usize,
+    ) -> Result<Vec<bool>> {
+        let mut by_key: HashMap<CircuitVersion, Vec<usize>> = HashMap::new();
+        for (index, (proof, _)) in items.iter().enumerate() {
+            by_key.entry(proof.key).or_default().push(index);
+        }
+
+        let mut results = vec![false; items.len()];
+        for (key, indices) in by_key {
+            let Ok(vk) = self.verifying_key(&key) else {
+                continue;
+            };
+            let batch: Vec<groth16::BatchItem<'_>> = indices
+                .iter()
+                .map(|&i| groth16::BatchItem {
+                    proof: &items[i].0.proof,
+                    public_inputs: items[i].1,
+                })
+                .collect();
+            let verified = groth16::verify_batch(vk, &batch, batch_size)?;
+            for (index, ok) in indices.into_iter().zip(verified) {
+                results[index] = ok;
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::MultiplyCircuit;
+
+    fn circuit() -> MultiplyCircuit {
+        MultiplyCircuit {
+            a: Some(Fr::from(3u64)),
+            b: Some(Fr::from(7u64)),
+        }
+    }
+
+    fn ceremony(
+        version: u32,
+    ) -> (
+        CircuitArtifact,
+        SerializedProvingKey,
+        SerializedVerifyingKey,
+    ) {
+        let (pk, vk) = groth16::setup(circuit()).expect("setup");
+        let artifact = CircuitArtifact {
+            circuit: CircuitId::Minting,
+            version,
+            vk_hash: blake3::hash(&vk.bytes),
+            pk_hash: blake3::hash(&pk.bytes),
+            pk_size_bytes: pk.bytes.len() as u64,
+        };
+        (artifact, pk, vk)
+    }
+
+    fn manifest(artifacts: Vec<CircuitArtifact>, revoked: Vec<CircuitVersion>) -> UpgradeManifest {
+        UpgradeManifest {
+            version: "5.5.0".to_string(),
+            activation_epoch: 0,
+            platform_hashes: Vec::new(),
+            changelog_url: None,
+            is_mandatory: false,
+            multisig_sigs: Vec::new(),
+            published_at: 0,
+            circuit_artifacts: artifacts,
+            revoked_circuits: revoked,
+        }
+    }
+
+    fn minting(version: u32) -> CircuitVersion {
+        CircuitVersion {
+            circuit: CircuitId::Minting,
+            version,
+        }
+    }
+
+    fn install(
+        manager: &mut CircuitKeyManager,
+        version: u32,
+        pk: &SerializedProvingKey,
+        vk: &SerializedVerifyingKey,
+    ) {
+        manager
+            .install_verifying_key(minting(version), vk.bytes.clone())
+            .expect("install vk");
+        manager
+            .install_proving_key(minting(version), pk.bytes.clone())
+            .expect("install pk");
+    }
+
+    fn temp_cache_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ochra-circuit-keys-{}", rand::random::<u64>()))
+    }
+
+    #[test]
+    fn test_rejects_unpinned_and_mismatched_keys() {
+        let (artifact, pk, vk) = ceremony(1);
+        let mut manager = CircuitKeyManager::new();
+        assert!(manager
+            .install_verifying_key(minting(1), vk.bytes.clone())
+            .is_err());
+
+        manager
+            .apply_manifest(&manifest(vec![artifact], Vec::new()))
+            .expect("apply");
+        assert!(manager
+            .install_verifying_key(minting(1), pk.bytes.clone())
+            .is_err());
+        assert!(manager
+            .install_verifying_key(minting(1), vk.bytes.clone())
+            .is_ok());
+        assert_eq!(manager.missing().len(), 1);
+    }
+
+    #[test]
+    fn test_prove_and_verify_through_manager() {
+        let (artifact, pk, vk) = ceremony(1);
+        let mut manager = CircuitKeyManager::new();
+        manager
+            .apply_manifest(&manifest(vec![artifact], Vec::new()))
+            .expect("apply");
+        install(&mut manager, 1, &pk, &vk);
+        assert!(manager.missing().is_empty());
+
+        let proof = manager.prove(CircuitId::Minting, circuit()).expect("prove");
+        assert_eq!(proof.key, minting(1));
+        assert!(manager.verify(&proof, &[Fr::from(21u64)]).expect("verify"));
+        assert!(!manager.verify(&proof, &[Fr::from(22u64)]).expect("verify"));
+    }
+
+    #[test]
+    fn test_multiple_versions_verify_concurrently() {
+        let (a1, pk1, vk1) = ceremony(1);
+        let (a2, pk2, vk2) = ceremony(2);
+        let mut manager = CircuitKeyManager::new();
+        manager
+            .apply_manifest(&manifest(vec![a1], Vec::new()))
+            .expect("apply v1");
+        install(&mut manager, 1, &pk1, &vk1);
+        let old_proof = manager.prove(CircuitId::Minting, circuit()).expect("prove");
+
+        assert_eq!(
+            manager
+                .apply_manifest(&manifest(vec![a2], Vec::new()))
+                .expect("apply v2"),
+            1
+        );
+        install(&mut manager, 2, &pk2, &vk2);
+        assert_eq!(manager.current_version(CircuitId::Minting), Some(2));
+        assert_eq!(manager.verifiable_versions(CircuitId::Minting), vec![1, 2]);
+
+        let new_proof = manager.prove(CircuitId::Minting, circuit()).expect("prove");
+        assert_eq!(new_proof.key, minting(2));
+        assert!(manager.verify(&old_proof, &[Fr::from(21u64)]).expect("v1"));
+        assert!(manager.verify(&new_proof, &[Fr::from(21u64)]).expect("v2"));
+
+        let good = [Fr::from(21u64)];
+        let bad = [Fr::from(22u64)];
+        let results = manager
+            .verify_batch(
+                &[(&old_proof, &good), (&new_proof, &good), (&new_proof, &bad)],
+                0,
+            )
+            .expect("batch");
+        assert_eq!(results, vec![true, true, false]);
+    }
+
+    #[test]
+    fn test_revoked_version_refuses_proofs() {
+        let (artifact, pk, vk) = ceremony(1);
+        let mut manager = CircuitKeyManager::new();
+        manager
+            .apply_manifest(&manifest(vec![artifact.clone()], Vec::new()))
+            .expect("apply");
+        install(&mut manager, 1, &pk, &vk);
+        let proof = manager.prove(CircuitId::Minting, circuit()).expect("prove");
+
+        manager
+            .apply_manifest(&manifest(Vec::new(), vec![minting(1)]))
+            .expect("revoke");
+        assert!(manager.is_revoked(&minting(1)));
+        assert!(matches!(
+            manager.verify(&proof, &[Fr::from(21u64)]),
+            Err(CryptoError::RevokedCircuitKey(_))
+        ));
+        assert_eq!(manager.current_version(CircuitId::Minting), None);
+
+        // A later manifest cannot bring a revoked version back.
+        assert_eq!(
+            manager
+                .apply_manifest(&manifest(vec![artifact], Vec::new()))
+                .expect("apply"),
+            0
+        );
+        assert!(manager.install_verifying_key(minting(1), vk.bytes).is_err());
+    }
+
+    #[test]
+    fn test_conflicting_pin_rejected() {
+        let (artifact, _, _) = ceremony(1);
+        let mut manager = CircuitKeyManager::new();
+        manager
+            .apply_manifest(&manifest(vec![artifact.clone()], Vec::new()))
+            .expect("apply");
+
+        let mut conflicting = artifact;
+        conflicting.vk_hash = [0xEE; 32];
+        assert!(manager
+            .apply_manifest(&manifest(vec![conflicting], Vec::new()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_cache_survives_restart_and_drops_tampered_keys() {
+        let dir = temp_cache_dir();
+        let (artifact, pk, vk) = ceremony(1);
+        {
+            let mut manager = CircuitKeyManager::with_cache_dir(&dir).expect("open cache");
+            manager
+                .apply_manifest(&manifest(vec![artifact], Vec::new()))
+                .expect("apply");
+            install(&mut manager, 1, &pk, &vk);
+        }
+
+        let reloaded = CircuitKeyManager::with_cache_dir(&dir).expect("reopen cache");
+        assert_eq!(reloaded.current_version(CircuitId::Minting), Some(1));
+        assert!(reloaded.missing().is_empty());
+
+        std::fs::write(dir.join("minting-v1.vk"), b"tampered").expect("tamper");
+        let reloaded = CircuitKeyManager::with_cache_dir(&dir).expect("reopen cache");
+        assert!(reloaded.verifiable_versions(CircuitId::Minting).is_empty());
+        assert!(!dir.join("minting-v1.vk").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
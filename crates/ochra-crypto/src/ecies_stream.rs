@@ -0,0 +1,492 @@
+//! Streaming ECIES for large payloads (Section 2.5).
+//!
+//! One-shot [`ecies`](crate::ecies) seals the whole plaintext under a single
+//! nonce, so both sides must hold it in memory. The streaming mode derives
+//! the same key from the X25519 exchange, then seals the plaintext as a
+//! sequence of fixed-size chunks (the STREAM construction). Guardian shards,
+//! export archives and other bulky payloads can be encrypted and decrypted
+//! one chunk at a time.
+//!
+//! ## Framing
+//!
+//! ```text
+//! header = 0x01 || eph_pk (32) || LE32(chunk_size)
+//! nonce_prefix = BLAKE3::derive_key("Ochra v1 ecies-nonce", shared_secret || eph_pk)[:7]
+//! nonce_i = nonce_prefix || BE32(i) || last_flag
+//! frame_i = ChaCha20-Poly1305.Encrypt(enc_key, nonce_i, chunk_i, aad=header)
+//! stream = header || frame_0 || ... || frame_n
+//! ```
+//!
+//! Every frame but the last carries exactly `chunk_size` plaintext bytes.
+//! The last frame carries fewer (possibly zero) and is sealed with
+//! `last_flag = 1`, so a reader can tell a complete stream from one cut at
+//! a frame boundary, and reordered or spliced frames fail their tag.
+
+use std::io::{Read, Write};
+
+use crate::blake3::{self, contexts};
+use crate::chacha20;
+use crate::secret::SecretBytes;
+use crate::x25519::{self, X25519PublicKey, X25519StaticSecret};
+use crate::{CryptoError, Result};
+
+/// Stream format version.
+pub const STREAM_VERSION: u8 = 1;
+
+/// Header size: version, ephemeral public key, chunk size.
+pub const HEADER_SIZE: usize = 1 + 32 + 4;
+
+/// Default plaintext chunk size (64 KB).
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest chunk size a reader accepts (1 MB), bounding its buffer.
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Key and nonce prefix shared by both directions.
+struct StreamKeys {
+    enc_key: SecretBytes<32>,
+    nonce_prefix: [u8; 7],
+}
+
+impl StreamKeys {
+    fn derive(shared_secret: &[u8; 32], eph_pk: &[u8; 32], recipient_pk: &[u8; 32]) -> Self {
+        let mut key_material = zeroize::Zeroizing::new(Vec::with_capacity(32 + 32 + 32));
+        key_material.extend_from_slice(shared_secret);
+        key_material.extend_from_slice(eph_pk);
+        key_material.extend_from_slice(recipient_pk);
+        let enc_key = SecretBytes::new(blake3::derive_key(
+            contexts::ECIES_ENCRYPTION_KEY,
+            &key_material,
+        ));
+
+        let mut nonce_material = zeroize::Zeroizing::new(Vec::with_capacity(32 + 32));
+        nonce_material.extend_from_slice(shared_secret);
+        nonce_material.extend_from_slice(eph_pk);
+        let nonce_full = blake3::derive_key(contexts::ECIES_NONCE, &nonce_material);
+        let mut nonce_prefix = [0u8; 7];
+        nonce_prefix.copy_from_slice(&nonce_full[..7]);
+
+        Self {
+            enc_key,
+            nonce_prefix,
+        }
+    }
+
+    fn nonce(&self, counter: u32, last: bool) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[..7].copy_from_slice(&self.nonce_prefix);
+        nonce[7..11].copy_from_slice(&counter.to_be_bytes());
+        nonce[11] = u8::from(last);
+        nonce
+    }
+}
+
+fn check_chunk_size(chunk_size: usize) -> Result<()> {
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(CryptoError::Ecies(format!(
+            "chunk size {chunk_size} outside 1..={MAX_CHUNK_SIZE}"
+        )));
+    }
+    Ok(())
+}
+
+fn next_counter(counter: u32) -> Result<u32> {
+    counter
+        .checked_add(1)
+        .ok_or_else(|| CryptoError::Ecies("stream exceeds 2^32 chunks".into()))
+}
+
+fn io_error(e: std::io::Error) -> CryptoError {
+    CryptoError::Ecies(format!("stream I/O: {e}"))
+}
+
+/// Seals a stream chunk by chunk.
+pub struct StreamEncryptor {
+    keys: StreamKeys,
+    header: [u8; HEADER_SIZE],
+    chunk_size: usize,
+    counter: u32,
+}
+
+impl StreamEncryptor {
+    /// Start a stream to `recipient_pk` with a random ephemeral key.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Ecies`] if `chunk_size` is zero or above [`MAX_CHUNK_SIZE`]
+    pub fn new(recipient_pk: &X25519PublicKey, chunk_size: usize) -> Result<Self> {
+        let mut randomness = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut randomness);
+        Self::new_deterministic(recipient_pk, chunk_size, &randomness)
+    }
+
+    /// Start a stream with explicit ephemeral randomness (for test vectors).
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Ecies`] if `chunk_size` is zero or above [`MAX_CHUNK_SIZE`]
+    pub fn new_deterministic(
+        recipient_pk: &X25519PublicKey,
+        chunk_size: usize,
+        randomness: &[u8; 32],
+    ) -> Result<Self> {
+        check_chunk_size(chunk_size)?;
+        let eph_pk = x25519::basepoint_mult(randomness);
+        let shared_secret =
+            X25519StaticSecret::from_bytes(*randomness).diffie_hellman(recipient_pk);
+
+        let mut header = [0u8; HEADER_SIZE];
+        header[0] = STREAM_VERSION;
+        header[1..33].copy_from_slice(&eph_pk);
+        header[33..].copy_from_slice(&(chunk_size as u32).to_le_bytes());
+
+        Ok(Self {
+            keys: StreamKeys::derive(shared_secret.as_bytes(), &eph_pk, recipient_pk.as_bytes()),
+            header,
+            chunk_size,
+            counter: 0,
+        })
+    }
+
+    /// The stream header, written before the first frame.
+    pub fn header(&self) -> &[u8; HEADER_SIZE] {
+        &self.header
+    }
+
+    /// Plaintext bytes in every frame but the last.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Seal a full, non-final chunk.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Ecies`] if `chunk` is not exactly `chunk_size` bytes
+    ///   or the stream has run out of counters
+    pub fn encrypt_chunk(&mut self, chunk: &[u8]) -> Result<Vec<u8>> {
+        if chunk.len() != self.chunk_size {
+            return Err(CryptoError::Ecies(format!(
+                "non-final chunk of {} bytes, expected {}",
+                chunk.len(),
+                self.chunk_size
+            )));
+        }
+        let frame = chacha20::encrypt(
+            self.keys.enc_key.expose(),
+            &self.keys.nonce(self.counter, false),
+            chunk,
+            &self.header,
+        )?;
+        self.counter = next_counter(self.counter)?;
+        Ok(frame)
+    }
+
+    /// Seal the final chunk, which must be shorter than `chunk_size`.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Ecies`] if `last` is `chunk_size` bytes or longer
+    pub fn finish(self, last: &[u8]) -> Result<Vec<u8>> {
+        if last.len() >= self.chunk_size {
+            return Err(CryptoError::Ecies(format!(
+                "final chunk of {} bytes, must be under {}",
+                last.len(),
+                self.chunk_size
+            )));
+        }
+        chacha20::encrypt(
+            self.keys.enc_key.expose(),
+            &self.keys.nonce(self.counter, true),
+            last,
+            &self.header,
+        )
+    }
+}
+
+/// Opens a stream frame by frame.
+pub struct StreamDecryptor {
+    keys: StreamKeys,
+    header: [u8; HEADER_SIZE],
+    chunk_size: usize,
+    counter: u32,
+}
+
+impl StreamDecryptor {
+    /// Start opening a stream from its header.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Ecies`] if the header is malformed, of an unknown
+    ///   version, or names an out-of-range chunk size
+    pub fn new(recipient_sk: &X25519StaticSecret, header: &[u8]) -> Result<Self> {
+        let header: [u8; HEADER_SIZE] = header
+            .try_into()
+            .map_err(|_| CryptoError::Ecies("stream header has the wrong length".into()))?;
+        if header[0] != STREAM_VERSION {
+            return Err(CryptoError::Ecies(format!(
+                "unknown stream version {}",
+                header[0]
+            )));
+        }
+        let mut eph_pk = [0u8; 32];
+        eph_pk.copy_from_slice(&header[1..33]);
+        let mut size = [0u8; 4];
+        size.copy_from_slice(&header[33..]);
+        let chunk_size = u32::from_le_bytes(size) as usize;
+        check_chunk_size(chunk_size)?;
+
+        let shared_secret = recipient_sk.diffie_hellman(&X25519PublicKey::from_bytes(eph_pk));
+        Ok(Self {
+            keys: StreamKeys::derive(
+                shared_secret.as_bytes(),
+                &eph_pk,
+                recipient_sk.public_key().as_bytes(),
+            ),
+            header,
+            chunk_size,
+            counter: 0,
+        })
+    }
+
+    /// Bytes in a full, non-final frame.
+    pub fn frame_size(&self) -> usize {
+        self.chunk_size + chacha20::TAG_SIZE
+    }
+
+    /// Open a full, non-final frame.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Ecies`] if `frame` is not [`frame_size`](Self::frame_size) bytes
+    /// - [`CryptoError::AeadDecryption`] if the frame is forged, reordered, or
+    ///   is actually the final frame
+    pub fn decrypt_chunk(&mut self, frame: &[u8]) -> Result<Vec<u8>> {
+        if frame.len() != self.frame_size() {
+            return Err(CryptoError::Ecies(format!(
+                "non-final frame of {} bytes, expected {}",
+                frame.len(),
+                self.frame_size()
+            )));
+        }
+        let chunk = chacha20::decrypt(
+            self.keys.enc_key.expose(),
+            &self.keys.nonce(self.counter, false),
+            frame,
+            &self.header,
+        )?;
+        self.counter = next_counter(self.counter)?;
+        Ok(chunk)
+    }
+
+    /// Open the final frame, proving the stream was not truncated.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Ecies`] if `frame` is too short or too long to be final
+    /// - [`CryptoError::AeadDecryption`] if it is forged or not the final frame
+    pub fn finish(self, frame: &[u8]) -> Result<Vec<u8>> {
+        if frame.len() < chacha20::TAG_SIZE || frame.len() >= self.frame_size() {
+            return Err(CryptoError::Ecies("stream truncated".into()));
+        }
+        chacha20::decrypt(
+            self.keys.enc_key.expose(),
+            &self.keys.nonce(self.counter, true),
+            frame,
+            &self.header,
+        )
+    }
+}
+
+/// Fill `buf` from `reader`, returning how many bytes were read before EOF.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(io_error(e)),
+        }
+    }
+    Ok(filled)
+}
+
+/// Encrypt everything `reader` yields to `recipient_pk`, writing the
+/// stream to `writer`. Returns the plaintext length.
+///
+/// # Errors
+///
+/// - [`CryptoError::Ecies`] on a bad chunk size or an I/O failure
+pub fn encrypt_stream<R: Read, W: Write>(
+    recipient_pk: &X25519PublicKey,
+    chunk_size: usize,
+    mut reader: R,
+    mut writer: W,
+) -> Result<u64> {
+    let mut encryptor = StreamEncryptor::new(recipient_pk, chunk_size)?;
+    writer.write_all(encryptor.header()).map_err(io_error)?;
+
+    let mut buf = vec![0u8; chunk_size];
+    let mut total = 0u64;
+    loop {
+        let n = read_full(&mut reader, &mut buf)?;
+        total += n as u64;
+        if n < chunk_size {
+            let frame = encryptor.finish(&buf[..n])?;
+            writer.write_all(&frame).map_err(io_error)?;
+            break;
+        }
+        let frame = encryptor.encrypt_chunk(&buf)?;
+        writer.write_all(&frame).map_err(io_error)?;
+    }
+    writer.flush().map_err(io_error)?;
+    Ok(total)
+}
+
+/// Decrypt a stream from `reader`, writing the plaintext to `writer`.
+/// Returns the plaintext length.
+///
+/// Plaintext is written as each frame authenticates, so on error `writer`
+/// may hold a prefix of the payload; callers writing to a file should
+/// discard it unless this returns `Ok`.
+///
+/// # Errors
+///
+/// - [`CryptoError::Ecies`] on a malformed header, a truncated stream, or
+///   an I/O failure
+/// - [`CryptoError::AeadDecryption`] if any frame fails authentication
+pub fn decrypt_stream<R: Read, W: Write>(
+    recipient_sk: &X25519StaticSecret,
+    mut reader: R,
+    mut writer: W,
+) -> Result<u64> {
+    let mut header = [0u8; HEADER_SIZE];
+    if read_full(&mut reader, &mut header)? < HEADER_SIZE {
+        return Err(CryptoError::Ecies("stream header truncated".into()));
+    }
+    let mut decryptor = StreamDecryptor::new(recipient_sk, &header)?;
+
+    let mut buf = vec![0u8; decryptor.frame_size()];
+    let mut total = 0u64;
+    loop {
+        let n = read_full(&mut reader, &mut buf)?;
+        if n < buf.len() {
+            let last = decryptor.finish(&buf[..n])?;
+            total += last.len() as u64;
+            writer.write_all(&last).map_err(io_error)?;
+            break;
+        }
+        let chunk = decryptor.decrypt_chunk(&buf)?;
+        total += chunk.len() as u64;
+        writer.write_all(&chunk).map_err(io_error)?;
+    }
+    writer.flush().map_err(io_error)?;
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn round_trip(len: usize, chunk_size: usize) -> Vec<u8> {
+        let sk = X25519StaticSecret::random();
+        let plaintext = sample(len);
+        let mut sealed = Vec::new();
+        let written = encrypt_stream(
+            &sk.public_key(),
+            chunk_size,
+            plaintext.as_slice(),
+            &mut sealed,
+        )
+        .expect("encrypt");
+        assert_eq!(written, len as u64);
+
+        let mut opened = Vec::new();
+        let read = decrypt_stream(&sk, sealed.as_slice(), &mut opened).expect("decrypt");
+        assert_eq!(read, len as u64);
+        assert_eq!(opened, plaintext);
+        sealed
+    }
+
+    #[test]
+    fn test_stream_roundtrip_across_chunk_boundaries() {
+        for len in [0, 1, 99, 100, 101, 1000] {
+            let sealed = round_trip(len, 100);
+            let frames = len / 100 + 1;
+            assert_eq!(
+                sealed.len(),
+                HEADER_SIZE + len + frames * chacha20::TAG_SIZE
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncation_detected() {
+        let sk = X25519StaticSecret::random();
+        let mut sealed = Vec::new();
+        encrypt_stream(&sk.public_key(), 64, sample(256).as_slice(), &mut sealed).expect("encrypt");
+
+        // Drop the (tag-only) final frame: the stream ends on a frame boundary.
+        let cut = sealed.len() - chacha20::TAG_SIZE;
+        let result = decrypt_stream(&sk, &sealed[..cut], Vec::new());
+        assert!(matches!(result, Err(CryptoError::Ecies(_))));
+
+        // Cut inside a frame: the partial frame is taken as final and fails.
+        let result = decrypt_stream(&sk, &sealed[..HEADER_SIZE + 70], Vec::new());
+        assert!(matches!(result, Err(CryptoError::AeadDecryption)));
+    }
+
+    #[test]
+    fn test_reordered_and_tampered_frames_rejected() {
+        let sk = X25519StaticSecret::random();
+        let mut enc = StreamEncryptor::new(&sk.public_key(), 32).expect("encryptor");
+        let f0 = enc.encrypt_chunk(&[1; 32]).expect("chunk 0");
+        let f1 = enc.encrypt_chunk(&[2; 32]).expect("chunk 1");
+        let header = *enc.header();
+        let last = enc.finish(b"end").expect("final");
+
+        let mut dec = StreamDecryptor::new(&sk, &header).expect("decryptor");
+        assert!(dec.decrypt_chunk(&f1).is_err());
+        assert_eq!(dec.decrypt_chunk(&f0).expect("chunk 0"), vec![1; 32]);
+
+        let mut flipped = f1.clone();
+        flipped[0] ^= 1;
+        assert!(dec.decrypt_chunk(&flipped).is_err());
+        assert_eq!(dec.decrypt_chunk(&f1).expect("chunk 1"), vec![2; 32]);
+        assert_eq!(dec.finish(&last).expect("final"), b"end");
+    }
+
+    #[test]
+    fn test_wrong_key_and_bad_header_rejected() {
+        let sk = X25519StaticSecret::random();
+        let mut sealed = Vec::new();
+        encrypt_stream(&sk.public_key(), 16, &b"guardian shard"[..], &mut sealed).expect("encrypt");
+
+        let other = X25519StaticSecret::random();
+        assert!(decrypt_stream(&other, sealed.as_slice(), Vec::new()).is_err());
+
+        let mut bad_version = sealed.clone();
+        bad_version[0] = 2;
+        assert!(decrypt_stream(&sk, bad_version.as_slice(), Vec::new()).is_err());
+
+        assert!(StreamEncryptor::new(&sk.public_key(), 0).is_err());
+        assert!(StreamEncryptor::new(&sk.public_key(), MAX_CHUNK_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn test_deterministic_stream() {
+        let recipient = X25519StaticSecret::from_bytes([0x02u8; 32]).public_key();
+        let seal = || {
+            let mut enc =
+                StreamEncryptor::new_deterministic(&recipient, 4, &[0x01u8; 32]).expect("enc");
+            let f0 = enc.encrypt_chunk(b"Ochr").expect("chunk");
+            (f0, enc.finish(b"a").expect("final"))
+        };
+        assert_eq!(seal(), seal());
+    }
+}
@@ -6,14 +6,24 @@
 //! - Handle signing
 //!
 //! This module wraps `ed25519-dalek` with Ochra-specific types.
+//!
+//! ## Delegation
+//!
+//! A PIK can delegate limited signing rights to an operational subkey with
+//! a [`DelegationCert`]: the PIK signs the subkey, a [`DelegationScope`]
+//! bitmask of what it may sign, and the epochs it is valid for. Relays and
+//! other long-running roles then sign with the subkey, so the PIK stays
+//! offline and a leaked subkey is bounded in scope and time. Verifiers
+//! check a subkey signature with [`DelegationCert::verify_delegated`].
 
 use ed25519_dalek::{Signer, Verifier};
 use serde::{Deserialize, Serialize};
-use zeroize::Zeroize;
 
 use crate::{CryptoError, Result};
 
 /// An Ed25519 signing key (private key).
+///
+/// The inner `ed25519_dalek::SigningKey` zeroizes its secret scalar on drop.
 pub struct SigningKey {
     inner: ed25519_dalek::SigningKey,
 }
@@ -26,13 +36,6 @@ impl Clone for SigningKey {
     }
 }
 
-impl Drop for SigningKey {
-    fn drop(&mut self) {
-        let mut bytes = self.inner.to_bytes();
-        bytes.zeroize();
-    }
-}
-
 /// An Ed25519 verification key (public key).
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VerifyingKey {
@@ -156,6 +159,262 @@ pub fn derive_node_id(pik_public_key: &VerifyingKey) -> [u8; 32] {
     crate::blake3::hash(pik_public_key.as_bytes())
 }
 
+/// What a delegated subkey may sign, as a bitmask.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DelegationScope(pub u32);
+
+impl DelegationScope {
+    /// Relay descriptors and circuit-level relay operations.
+    pub const RELAY: Self = Self(1 << 0);
+    /// Mutable DHT records published on the PIK's behalf.
+    pub const DHT_RECORDS: Self = Self(1 << 1);
+    /// Peer-to-peer transport messages.
+    pub const TRANSPORT: Self = Self(1 << 2);
+    /// Service receipts and their acknowledgements.
+    pub const RECEIPTS: Self = Self(1 << 3);
+    /// Every scope defined so far.
+    pub const ALL: Self = Self(0b1111);
+
+    const NAMES: [(Self, &'static str); 4] = [
+        (Self::RELAY, "relay"),
+        (Self::DHT_RECORDS, "dht_records"),
+        (Self::TRANSPORT, "transport"),
+        (Self::RECEIPTS, "receipts"),
+    ];
+
+    /// Whether every bit of `other` is set.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The union of two scopes.
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Names of the set scopes, for display and RPC.
+    pub fn names(self) -> Vec<&'static str> {
+        Self::NAMES
+            .iter()
+            .filter(|(scope, _)| self.contains(*scope))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// The scope with the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(scope, _)| *scope)
+    }
+}
+
+/// Delegation certificate format version.
+pub const DELEGATION_VERSION: u8 = 1;
+
+/// Encoded certificate length: version, parent and subkey, scope, validity
+/// epochs, signature.
+pub const DELEGATION_CERT_LEN: usize = 1 + 32 + 32 + 4 + 4 + 4 + 64;
+
+/// A PIK's signature granting a subkey limited signing rights.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DelegationCert {
+    /// The delegating PIK public key.
+    pub parent_pk: [u8; 32],
+    /// The operational subkey.
+    pub subkey_pk: [u8; 32],
+    pub scope: DelegationScope,
+    /// First network epoch the subkey may sign in.
+    pub valid_from_epoch: u32,
+    /// Last network epoch the subkey may sign in, inclusive.
+    pub valid_until_epoch: u32,
+    /// Ed25519 signature by the parent over
+    /// [`signing_message`](Self::signing_message).
+    pub signature: [u8; 64],
+}
+
+impl DelegationCert {
+    /// Delegate `scope` to `subkey_pk` for the given epochs.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Delegation`] if the scope is empty or has unknown
+    ///   bits, or the validity range is empty
+    pub fn issue(
+        parent: &SigningKey,
+        subkey_pk: &VerifyingKey,
+        scope: DelegationScope,
+        valid_from_epoch: u32,
+        valid_until_epoch: u32,
+    ) -> Result<Self> {
+        let mut cert = Self {
+            parent_pk: parent.verifying_key().to_bytes(),
+            subkey_pk: subkey_pk.to_bytes(),
+            scope,
+            valid_from_epoch,
+            valid_until_epoch,
+            signature: [0u8; 64],
+        };
+        cert.check_fields()?;
+        cert.signature = parent.sign(&cert.signing_message()).to_bytes();
+        Ok(cert)
+    }
+
+    fn check_fields(&self) -> Result<()> {
+        if self.scope.0 == 0 || !DelegationScope::ALL.contains(self.scope) {
+            return Err(CryptoError::Delegation(format!(
+                "invalid scope {:#x}",
+                self.scope.0
+            )));
+        }
+        if self.valid_from_epoch > self.valid_until_epoch {
+            return Err(CryptoError::Delegation(format!(
+                "validity {}..={} is empty",
+                self.valid_from_epoch, self.valid_until_epoch
+            )));
+        }
+        if self.parent_pk == self.subkey_pk {
+            return Err(CryptoError::Delegation(
+                "a key cannot delegate to itself".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Bytes covered by the parent's signature.
+    pub fn signing_message(&self) -> Vec<u8> {
+        crate::blake3::encode_multi_field(&[
+            b"delegation-cert",
+            &[DELEGATION_VERSION],
+            &self.parent_pk,
+            &self.subkey_pk,
+            &self.scope.0.to_le_bytes(),
+            &self.valid_from_epoch.to_le_bytes(),
+            &self.valid_until_epoch.to_le_bytes(),
+        ])
+    }
+
+    /// BLAKE3 hash of the parent PIK, i.e. its PIK hash and node ID.
+    pub fn parent_hash(&self) -> [u8; 32] {
+        crate::blake3::hash(&self.parent_pk)
+    }
+
+    /// Whether `epoch` is within the validity range.
+    pub fn is_valid_at(&self, epoch: u32) -> bool {
+        (self.valid_from_epoch..=self.valid_until_epoch).contains(&epoch)
+    }
+
+    /// Check the parent's signature.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Delegation`] if the fields are malformed
+    /// - [`CryptoError::SignatureVerification`] if the parent did not sign it
+    pub fn verify(&self) -> Result<()> {
+        self.check_fields()?;
+        VerifyingKey::from_bytes(&self.parent_pk)?.verify(
+            &self.signing_message(),
+            &Signature::from_bytes(&self.signature),
+        )
+    }
+
+    /// Check that the certificate is signed, grants `scope`, and is valid
+    /// in `epoch`.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Delegation`] if out of scope or not valid in `epoch`
+    /// - [`CryptoError::SignatureVerification`] if the parent did not sign it
+    pub fn authorize(&self, scope: DelegationScope, epoch: u32) -> Result<()> {
+        self.verify()?;
+        if !self.scope.contains(scope) {
+            return Err(CryptoError::Delegation(format!(
+                "scope {:?} not delegated",
+                scope.names()
+            )));
+        }
+        if !self.is_valid_at(epoch) {
+            return Err(CryptoError::Delegation(format!(
+                "epoch {epoch} outside {}..={}",
+                self.valid_from_epoch, self.valid_until_epoch
+            )));
+        }
+        Ok(())
+    }
+
+    /// Verify a signature made by the subkey under this delegation.
+    ///
+    /// # Errors
+    ///
+    /// - Any error of [`authorize`](Self::authorize)
+    /// - [`CryptoError::SignatureVerification`] if the subkey did not sign
+    ///   `message`
+    pub fn verify_delegated(
+        &self,
+        scope: DelegationScope,
+        epoch: u32,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<()> {
+        self.authorize(scope, epoch)?;
+        VerifyingKey::from_bytes(&self.subkey_pk)?.verify(message, signature)
+    }
+
+    /// Encode as `version || parent || subkey || scope || from || until ||
+    /// signature`, integers little-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(DELEGATION_CERT_LEN);
+        out.push(DELEGATION_VERSION);
+        out.extend_from_slice(&self.parent_pk);
+        out.extend_from_slice(&self.subkey_pk);
+        out.extend_from_slice(&self.scope.0.to_le_bytes());
+        out.extend_from_slice(&self.valid_from_epoch.to_le_bytes());
+        out.extend_from_slice(&self.valid_until_epoch.to_le_bytes());
+        out.extend_from_slice(&self.signature);
+        out
+    }
+
+    /// Decode a certificate. The signature is not checked.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Delegation`] on a bad length or unknown version
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() != DELEGATION_CERT_LEN {
+            return Err(CryptoError::Delegation(format!(
+                "certificate length {}",
+                data.len()
+            )));
+        }
+        if data[0] != DELEGATION_VERSION {
+            return Err(CryptoError::Delegation(format!(
+                "unknown certificate version {}",
+                data[0]
+            )));
+        }
+        let u32_at = |at: usize| {
+            let mut b = [0u8; 4];
+            b.copy_from_slice(&data[at..at + 4]);
+            u32::from_le_bytes(b)
+        };
+        let mut parent_pk = [0u8; 32];
+        parent_pk.copy_from_slice(&data[1..33]);
+        let mut subkey_pk = [0u8; 32];
+        subkey_pk.copy_from_slice(&data[33..65]);
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&data[77..]);
+        Ok(Self {
+            parent_pk,
+            subkey_pk,
+            scope: DelegationScope(u32_at(65)),
+            valid_from_epoch: u32_at(69),
+            valid_until_epoch: u32_at(73),
+            signature,
+        })
+    }
+}
+
 impl std::fmt::Debug for SigningKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SigningKey")
@@ -251,6 +510,99 @@ mod tests {
         assert_ne!(kp1.verifying_key.to_bytes(), kp3.verifying_key.to_bytes());
     }
 
+    fn delegate(scope: DelegationScope) -> (KeyPair, KeyPair, DelegationCert) {
+        let pik = KeyPair::generate();
+        let sub = KeyPair::generate();
+        let cert = DelegationCert::issue(&pik.signing_key, &sub.verifying_key, scope, 100, 130)
+            .expect("issue");
+        (pik, sub, cert)
+    }
+
+    #[test]
+    fn test_delegated_signature_verifies_in_scope_and_epoch() {
+        let scope = DelegationScope::RELAY.union(DelegationScope::DHT_RECORDS);
+        let (pik, sub, cert) = delegate(scope);
+        assert_eq!(cert.parent_hash(), derive_node_id(&pik.verifying_key));
+
+        let sig = sub.signing_key.sign(b"relay descriptor");
+        assert!(cert
+            .verify_delegated(DelegationScope::RELAY, 100, b"relay descriptor", &sig)
+            .is_ok());
+        assert!(cert
+            .verify_delegated(DelegationScope::DHT_RECORDS, 130, b"relay descriptor", &sig)
+            .is_ok());
+
+        // Out of scope, out of range, or signed by the PIK itself.
+        assert!(matches!(
+            cert.verify_delegated(DelegationScope::RECEIPTS, 110, b"relay descriptor", &sig),
+            Err(CryptoError::Delegation(_))
+        ));
+        assert!(cert
+            .verify_delegated(DelegationScope::RELAY, 131, b"relay descriptor", &sig)
+            .is_err());
+        assert!(cert
+            .verify_delegated(DelegationScope::RELAY, 99, b"relay descriptor", &sig)
+            .is_err());
+        let pik_sig = pik.signing_key.sign(b"relay descriptor");
+        assert!(cert
+            .verify_delegated(DelegationScope::RELAY, 110, b"relay descriptor", &pik_sig)
+            .is_err());
+    }
+
+    #[test]
+    fn test_delegation_cert_round_trip_and_tamper() {
+        let (_, _, cert) = delegate(DelegationScope::TRANSPORT);
+        let bytes = cert.to_bytes();
+        assert_eq!(bytes.len(), DELEGATION_CERT_LEN);
+        let decoded = DelegationCert::from_bytes(&bytes).expect("decode");
+        assert_eq!(decoded, cert);
+        assert!(decoded.verify().is_ok());
+
+        // Widening the scope or the validity breaks the parent signature.
+        let widened = DelegationCert {
+            scope: DelegationScope::ALL,
+            ..cert
+        };
+        assert!(matches!(
+            widened.verify(),
+            Err(CryptoError::SignatureVerification)
+        ));
+        let extended = DelegationCert {
+            valid_until_epoch: 1000,
+            ..cert
+        };
+        assert!(extended.verify().is_err());
+        assert!(DelegationCert::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn test_delegation_rejects_bad_fields() {
+        let pik = KeyPair::generate();
+        let sub = KeyPair::generate();
+        let issue = |scope, from, until| {
+            DelegationCert::issue(&pik.signing_key, &sub.verifying_key, scope, from, until)
+        };
+        assert!(issue(DelegationScope(0), 1, 2).is_err());
+        assert!(issue(DelegationScope(1 << 20), 1, 2).is_err());
+        assert!(issue(DelegationScope::RELAY, 5, 4).is_err());
+        assert!(DelegationCert::issue(
+            &pik.signing_key,
+            &pik.verifying_key,
+            DelegationScope::RELAY,
+            1,
+            2
+        )
+        .is_err());
+        assert_eq!(
+            DelegationScope::ALL.names(),
+            vec!["relay", "dht_records", "transport", "receipts"]
+        );
+        assert_eq!(
+            DelegationScope::from_name("receipts"),
+            Some(DelegationScope::RECEIPTS)
+        );
+    }
+
     #[test]
     fn test_sign_verify_with_known_seed() {
         // Use a known seed, sign, and verify roundtrip
@@ -8,18 +8,33 @@
 //! - Proof size: 192 bytes
 //! - Verification time: < 2ms
 //! - Desktop proving (2^16 constraints): ~3-5s
-
-use ark_bls12_381::{Bls12_381, Fr};
+//!
+//! ## Batch Verification
+//!
+//! [`verify_batch`] checks many proofs under one verifying key with two
+//! multi-pairings per batch instead of one full pairing check per proof.
+//! Each proof's equation is scaled by a fresh random combiner, so a batch
+//! containing an invalid proof passes only with negligible probability. When
+//! a batch fails, its proofs are re-verified individually to identify the
+//! culprits.
+
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
 use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey};
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_snark::SNARK;
+use ark_std::{UniformRand, Zero};
 
 use crate::{CryptoError, Result};
 
 /// Proof size in bytes for Groth16/BLS12-381.
 pub const PROOF_SIZE: usize = 192;
 
+/// Default number of proofs combined into one batch check.
+pub const DEFAULT_BATCH_SIZE: usize = 32;
+
 /// A serialized Groth16 proof.
 #[derive(Clone, Debug)]
 pub struct SerializedProof {
@@ -102,6 +117,130 @@ pub fn verify(
         .map_err(|e| CryptoError::Proof(e.to_string()))
 }
 
+/// One proof and its public inputs for [`verify_batch`].
+#[derive(Clone, Copy, Debug)]
+pub struct BatchItem<'a> {
+    pub proof: &'a SerializedProof,
+    pub public_inputs: &'a [Fr],
+}
+
+/// Verify many proofs under one verifying key.
+///
+/// Proofs are checked `batch_size` at a time (0 selects
+/// [`DEFAULT_BATCH_SIZE`]). Returns one result per item, in order. Malformed
+/// proofs or public inputs of the wrong length are reported as `false`
+/// rather than failing the whole call.
+pub fn verify_batch(
+    verifying_key: &SerializedVerifyingKey,
+    items: &[BatchItem<'_>],
+    batch_size: usize,
+) -> Result<Vec<bool>> {
+    let vk = VerifyingKey::<Bls12_381>::deserialize_compressed(&*verifying_key.bytes)
+        .map_err(|e| CryptoError::Serialization(e.to_string()))?;
+    let pvk = PreparedVerifyingKey::from(vk.clone());
+    let batch_size = if batch_size == 0 {
+        DEFAULT_BATCH_SIZE
+    } else {
+        batch_size
+    };
+
+    let mut results = vec![false; items.len()];
+    let mut rng = rand::rngs::OsRng;
+    for (chunk_index, chunk) in items.chunks(batch_size).enumerate() {
+        let offset = chunk_index * batch_size;
+
+        // Only well-formed proofs take part in the combined check.
+        let mut parsed = Vec::with_capacity(chunk.len());
+        for (i, item) in chunk.iter().enumerate() {
+            if item.public_inputs.len() + 1 != vk.gamma_abc_g1.len() {
+                continue;
+            }
+            if let Ok(proof) = Proof::<Bls12_381>::deserialize_compressed(&*item.proof.bytes) {
+                parsed.push((offset + i, proof, item.public_inputs));
+            }
+        }
+        if parsed.is_empty() {
+            continue;
+        }
+
+        if combined_check(&vk, &parsed, &mut rng) {
+            for (index, _, _) in &parsed {
+                results[*index] = true;
+            }
+            continue;
+        }
+
+        for (index, proof, inputs) in &parsed {
+            results[*index] = Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, inputs, proof)
+                .unwrap_or(false);
+        }
+    }
+    Ok(results)
+}
+
+/// Run a combined check over `batch_size` items at a time, falling back to
+/// `single` for every item of a batch whose combined check fails.
+///
+/// Used by proof systems that verify through their own wrappers around
+/// [`verify_batch`]. A `batch_size` of 0 selects [`DEFAULT_BATCH_SIZE`].
+pub fn verify_in_batches<T>(
+    items: &[T],
+    batch_size: usize,
+    combined: impl Fn(&[T]) -> bool,
+    single: impl Fn(&T) -> bool,
+) -> Vec<bool> {
+    let batch_size = if batch_size == 0 {
+        DEFAULT_BATCH_SIZE
+    } else {
+        batch_size
+    };
+    let mut results = Vec::with_capacity(items.len());
+    for chunk in items.chunks(batch_size) {
+        if combined(chunk) {
+            results.extend(std::iter::repeat_n(true, chunk.len()));
+        } else {
+            results.extend(chunk.iter().map(&single));
+        }
+    }
+    results
+}
+
+/// Randomized combination of the Groth16 equations of several proofs:
+///
+/// `prod e(r_i*A_i, B_i) = e(sum(r_i)*alpha, beta) * e(sum(r_i*IC_i), gamma)
+/// * e(sum(r_i*C_i), delta)`
+fn combined_check<R: rand::RngCore>(
+    vk: &VerifyingKey<Bls12_381>,
+    proofs: &[(usize, Proof<Bls12_381>, &[Fr])],
+    rng: &mut R,
+) -> bool {
+    let mut lhs_g1 = Vec::with_capacity(proofs.len());
+    let mut lhs_g2: Vec<G2Affine> = Vec::with_capacity(proofs.len());
+    let mut r_sum = Fr::zero();
+    let mut ic_acc = G1Projective::zero();
+    let mut c_acc = G1Projective::zero();
+
+    for (_, proof, inputs) in proofs {
+        let r = Fr::rand(rng);
+        r_sum += r;
+
+        let mut ic = vk.gamma_abc_g1[0].into_group();
+        for (input, base) in inputs.iter().zip(&vk.gamma_abc_g1[1..]) {
+            ic += *base * input;
+        }
+        ic_acc += ic * r;
+        c_acc += proof.c * r;
+        lhs_g1.push(proof.a * r);
+        lhs_g2.push(proof.b);
+    }
+
+    let lhs = Bls12_381::multi_pairing(G1Projective::normalize_batch(&lhs_g1), lhs_g2);
+    let rhs_g1: Vec<G1Affine> =
+        G1Projective::normalize_batch(&[vk.alpha_g1 * r_sum, ic_acc, c_acc]);
+    let rhs = Bls12_381::multi_pairing(rhs_g1, [vk.beta_g2, vk.gamma_g2, vk.delta_g2]);
+    lhs == rhs
+}
+
 /// A simple test circuit for validating the Groth16 infrastructure.
 ///
 /// Proves knowledge of `a` and `b` such that `a * b = c` where `c` is public.
@@ -217,4 +356,82 @@ mod tests {
             PROOF_SIZE
         );
     }
+
+    fn batch_fixture(count: u64) -> (SerializedVerifyingKey, Vec<(SerializedProof, Vec<Fr>)>) {
+        let (pk, vk) = setup(MultiplyCircuit {
+            a: Some(Fr::from(1u64)),
+            b: Some(Fr::from(1u64)),
+        })
+        .expect("setup");
+        let proofs = (1..=count)
+            .map(|i| {
+                let a = Fr::from(i);
+                let b = Fr::from(i + 1);
+                let proof = prove(
+                    MultiplyCircuit {
+                        a: Some(a),
+                        b: Some(b),
+                    },
+                    &pk,
+                )
+                .expect("prove");
+                (proof, vec![a * b])
+            })
+            .collect();
+        (vk, proofs)
+    }
+
+    fn items(proofs: &[(SerializedProof, Vec<Fr>)]) -> Vec<BatchItem<'_>> {
+        proofs
+            .iter()
+            .map(|(proof, inputs)| BatchItem {
+                proof,
+                public_inputs: inputs,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_verify_all_valid() {
+        let (vk, proofs) = batch_fixture(5);
+        let results = verify_batch(&vk, &items(&proofs), 0).expect("batch");
+        assert_eq!(results, vec![true; 5]);
+
+        // The combined check itself must accept valid proofs and reject a bad one.
+        let vk = VerifyingKey::<Bls12_381>::deserialize_compressed(&*vk.bytes).expect("vk");
+        let mut parsed: Vec<(usize, Proof<Bls12_381>, &[Fr])> = proofs
+            .iter()
+            .enumerate()
+            .map(|(i, (proof, inputs))| {
+                let proof =
+                    Proof::<Bls12_381>::deserialize_compressed(&*proof.bytes).expect("proof");
+                (i, proof, inputs.as_slice())
+            })
+            .collect();
+        let mut rng = rand::rngs::OsRng;
+        assert!(combined_check(&vk, &parsed, &mut rng));
+        let wrong = [Fr::from(1u64)];
+        parsed[1].2 = &wrong;
+        assert!(!combined_check(&vk, &parsed, &mut rng));
+    }
+
+    #[test]
+    fn test_batch_identifies_culprit() {
+        let (vk, mut proofs) = batch_fixture(5);
+        proofs[3].1 = vec![Fr::from(999u64)];
+
+        let results = verify_batch(&vk, &items(&proofs), 2).expect("batch");
+        assert_eq!(results, vec![true, true, true, false, true]);
+    }
+
+    #[test]
+    fn test_batch_malformed_entries_rejected() {
+        let (vk, mut proofs) = batch_fixture(3);
+        proofs[0].0.bytes = vec![0u8; 7];
+        proofs[2].1.push(Fr::from(1u64));
+
+        let results = verify_batch(&vk, &items(&proofs), 8).expect("batch");
+        assert_eq!(results, vec![false, true, false]);
+        assert!(verify_batch(&vk, &[], 8).expect("empty").is_empty());
+    }
 }
@@ -0,0 +1,272 @@
+//! OS key storage for the PIK wrapping key.
+//!
+//! The PIK is stored encrypted under an Argon2id key derived from the user's
+//! password. Biometric unlock keeps a copy of that wrapping key in the
+//! platform key store, where releasing it requires user presence (Touch ID,
+//! Windows Hello, or the desktop session's unlock):
+//!
+//! | Platform | Backend | Hardware-backed |
+//! |---|---|---|
+//! | Linux | Secret Service (GNOME Keyring / KWallet) via `secret-tool` | No |
+//! | macOS / iOS | Keychain, Secure Enclave access control | Not yet linked |
+//! | Windows | DPAPI, TPM-bound via Windows Hello | Not yet linked |
+//!
+//! The backend for the current platform is selected at compile time by
+//! [`platform_keystore`], which returns `None` where no backend is linked;
+//! biometric unlock is then unavailable. Secrets only ever leave a backend
+//! as [`SecretBytes`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::secret::SecretBytes;
+use crate::{CryptoError, Result};
+
+/// Label of the PIK wrapping key item.
+pub const PIK_WRAPPING_KEY_LABEL: &str = "pik-wrapping-key";
+
+/// Key store implementation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyStoreKind {
+    LinuxSecretService,
+    /// Process memory; for tests and platforms without a key store.
+    Memory,
+}
+
+/// What a key store can guarantee.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyStoreCapabilities {
+    /// Keys are sealed by a secure element or TPM.
+    pub hardware_backed: bool,
+    /// Releasing a key can require a biometric check.
+    pub user_presence: bool,
+}
+
+/// A place to keep 32-byte secrets outside the database.
+pub trait KeyStore: Send + Sync {
+    /// Backend in use.
+    fn kind(&self) -> KeyStoreKind;
+
+    /// Guarantees offered by the backend.
+    fn capabilities(&self) -> KeyStoreCapabilities;
+
+    /// Store a secret, replacing any existing item with the same label.
+    fn store(&self, label: &str, secret: &SecretBytes<32>) -> Result<()>;
+
+    /// Load a secret. May prompt the user for presence.
+    ///
+    /// Returns `Ok(None)` if no item exists; a declined prompt is an error.
+    fn load(&self, label: &str) -> Result<Option<SecretBytes<32>>>;
+
+    /// Delete a secret. Returns whether it existed.
+    fn delete(&self, label: &str) -> Result<bool>;
+}
+
+/// In-memory key store.
+#[derive(Debug, Default)]
+pub struct MemoryKeyStore {
+    items: Mutex<HashMap<String, SecretBytes<32>>>,
+}
+
+impl MemoryKeyStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn items(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, SecretBytes<32>>>> {
+        self.items
+            .lock()
+            .map_err(|_| CryptoError::KeyStore("memory key store poisoned".into()))
+    }
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn kind(&self) -> KeyStoreKind {
+        KeyStoreKind::Memory
+    }
+
+    fn capabilities(&self) -> KeyStoreCapabilities {
+        KeyStoreCapabilities {
+            hardware_backed: false,
+            user_presence: false,
+        }
+    }
+
+    fn store(&self, label: &str, secret: &SecretBytes<32>) -> Result<()> {
+        self.items()?.insert(label.to_string(), secret.clone());
+        Ok(())
+    }
+
+    fn load(&self, label: &str) -> Result<Option<SecretBytes<32>>> {
+        Ok(self.items()?.get(label).cloned())
+    }
+
+    fn delete(&self, label: &str) -> Result<bool> {
+        Ok(self.items()?.remove(label).is_some())
+    }
+}
+
+/// Attribute identifying Ochra's items in the Secret Service.
+#[cfg(target_os = "linux")]
+const SECRET_SERVICE_APPLICATION: &str = "ochra";
+
+/// Secret Service key store, driven through libsecret's `secret-tool`.
+///
+/// Items are unlocked with the desktop session, so there is no per-use
+/// presence prompt. Secrets are passed on stdin, never on the command line.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default)]
+pub struct SecretServiceKeyStore;
+
+#[cfg(target_os = "linux")]
+impl SecretServiceKeyStore {
+    /// Run `secret-tool` on the item for `label`, feeding it `input`.
+    fn run(&self, command: &str, label: &str, input: &[u8]) -> Result<std::process::Output> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let mut args = vec![command];
+        if command == "store" {
+            args.push("--label=Ochra");
+        }
+        args.extend(["application", SECRET_SERVICE_APPLICATION, "label", label]);
+        let mut child = Command::new("secret-tool")
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| CryptoError::KeyStore(format!("secret-tool: {e}")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(input)
+                .map_err(|e| CryptoError::KeyStore(format!("secret-tool: {e}")))?;
+        }
+        child
+            .wait_with_output()
+            .map_err(|e| CryptoError::KeyStore(format!("secret-tool: {e}")))
+    }
+
+    fn failed(stderr: &[u8]) -> CryptoError {
+        CryptoError::KeyStore(format!(
+            "secret-tool failed: {}",
+            String::from_utf8_lossy(stderr).trim()
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl KeyStore for SecretServiceKeyStore {
+    fn kind(&self) -> KeyStoreKind {
+        KeyStoreKind::LinuxSecretService
+    }
+
+    fn capabilities(&self) -> KeyStoreCapabilities {
+        KeyStoreCapabilities {
+            hardware_backed: false,
+            user_presence: false,
+        }
+    }
+
+    fn store(&self, label: &str, secret: &SecretBytes<32>) -> Result<()> {
+        let encoded = zeroize::Zeroizing::new(hex::encode(secret.expose()));
+        let output = self.run("store", label, encoded.as_bytes())?;
+        if !output.status.success() {
+            return Err(Self::failed(&output.stderr));
+        }
+        Ok(())
+    }
+
+    fn load(&self, label: &str) -> Result<Option<SecretBytes<32>>> {
+        let output = self.run("lookup", label, &[])?;
+        let stdout = zeroize::Zeroizing::new(output.stdout);
+        if stdout.is_empty() {
+            // `lookup` exits non-zero with no output for a missing item.
+            return if output.stderr.is_empty() {
+                Ok(None)
+            } else {
+                Err(Self::failed(&output.stderr))
+            };
+        }
+        let decoded = zeroize::Zeroizing::new(
+            hex::decode(stdout.trim_ascii())
+                .map_err(|_| CryptoError::KeyStore(format!("malformed item {label:?}")))?,
+        );
+        SecretBytes::from_slice(&decoded).map(Some)
+    }
+
+    fn delete(&self, label: &str) -> Result<bool> {
+        let existed = self.load(label)?.is_some();
+        let output = self.run("clear", label, &[])?;
+        if !output.status.success() {
+            return Err(Self::failed(&output.stderr));
+        }
+        Ok(existed)
+    }
+}
+
+/// The key store for the platform this binary was built for, if one is
+/// linked in.
+///
+/// On Linux this requires `secret-tool` (libsecret) on the `PATH`.
+pub fn platform_keystore() -> Option<Box<dyn KeyStore>> {
+    #[cfg(target_os = "linux")]
+    {
+        // Without arguments `secret-tool` only prints its usage.
+        let available = std::process::Command::new("secret-tool")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok();
+        if available {
+            return Some(Box::new(SecretServiceKeyStore));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store_roundtrip() {
+        let store = MemoryKeyStore::new();
+        let secret = SecretBytes::new([9u8; 32]);
+        assert!(store.load(PIK_WRAPPING_KEY_LABEL).expect("load").is_none());
+
+        store.store(PIK_WRAPPING_KEY_LABEL, &secret).expect("store");
+        assert_eq!(
+            store.load(PIK_WRAPPING_KEY_LABEL).expect("load"),
+            Some(secret)
+        );
+
+        assert!(store.delete(PIK_WRAPPING_KEY_LABEL).expect("delete"));
+        assert!(!store.delete(PIK_WRAPPING_KEY_LABEL).expect("delete"));
+    }
+
+    #[test]
+    fn test_store_replaces_existing_item() {
+        let store = MemoryKeyStore::new();
+        store
+            .store("a", &SecretBytes::new([1u8; 32]))
+            .expect("store");
+        store
+            .store("a", &SecretBytes::new([2u8; 32]))
+            .expect("store");
+        assert_eq!(
+            store.load("a").expect("load"),
+            Some(SecretBytes::new([2u8; 32]))
+        );
+    }
+
+    #[test]
+    fn test_platform_backend_selected_at_compile_time() {
+        let Some(store) = platform_keystore() else {
+            return;
+        };
+        assert_eq!(store.kind(), KeyStoreKind::LinuxSecretService);
+        assert!(!store.capabilities().hardware_backed);
+    }
+}
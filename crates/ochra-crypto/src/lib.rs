@@ -14,21 +14,34 @@
 //! - [`chacha20`] — ChaCha20-Poly1305 AEAD encryption (RFC 8439)
 //! - [`argon2id`] — Password hashing and Proof-of-Work
 //! - [`ecies`] — ECIES encrypt/decrypt (Section 2.5)
-//! - [`poseidon`] — Poseidon hash on BLS12-381 scalar field
+//! - [`ecies_stream`] — Chunked streaming ECIES for large payloads
+//! - [`poseidon`] — Poseidon hash and streaming sponge on BLS12-381 scalar field
 //! - [`groth16`] — Groth16/BLS12-381 proving and verification
+//! - [`circuit_keys`] — Pinned, versioned Groth16 keys (Section 2.6)
+//! - [`keystore`] — OS key storage for the PIK wrapping key
+//! - [`upgrade`] — Upgrade manifest verification and staged rollout
 //! - [`pedersen`] — Pedersen commitments on BLS12-381
 //! - [`voprf`] — Ristretto255 VOPRF (RFC 9497)
 //! - [`frost`] — FROST Ed25519 DKG + ROAST wrapper
+//! - [`timelock`] — Threshold-released encryption to a FROST group key
+//! - [`secret`] — Zeroizing, constant-time secret byte container
 
 pub mod argon2id;
 pub mod blake3;
 pub mod chacha20;
+pub mod circuit_keys;
 pub mod ecies;
+pub mod ecies_stream;
 pub mod ed25519;
 pub mod frost;
 pub mod groth16;
+pub mod keystore;
 pub mod pedersen;
 pub mod poseidon;
+mod poseidon_tables;
+pub mod secret;
+pub mod timelock;
+pub mod upgrade;
 pub mod voprf;
 pub mod x25519;
 
@@ -59,6 +72,14 @@ pub enum CryptoError {
     #[error("proof error: {0}")]
     Proof(String),
 
+    /// Circuit key is unknown, unpinned, or fails its pinned hash.
+    #[error("circuit key error: {0}")]
+    CircuitKey(String),
+
+    /// Circuit key version has been revoked by governance.
+    #[error("circuit key revoked: {0}")]
+    RevokedCircuitKey(String),
+
     /// VOPRF error.
     #[error("VOPRF error: {0}")]
     Voprf(String),
@@ -78,6 +99,18 @@ pub enum CryptoError {
     /// Serialization error.
     #[error("serialization error: {0}")]
     Serialization(String),
+
+    /// OS key store unavailable or the user declined the prompt.
+    #[error("key store error: {0}")]
+    KeyStore(String),
+
+    /// Upgrade manifest or artifact rejected.
+    #[error("upgrade error: {0}")]
+    Upgrade(String),
+
+    /// Delegation certificate malformed, expired, or out of scope.
+    #[error("delegation error: {0}")]
+    Delegation(String),
 }
 
 pub type Result<T> = std::result::Result<T, CryptoError>;
@@ -11,12 +11,39 @@
 //! - Partial rounds (R_P): 57
 //! - S-box: x^5
 //! - Seed: b"Ochra_Poseidon_BLS12-381_t3"
+//!
+//! Round constants and the MDS matrix are compiled in from
+//! `poseidon_tables.rs` rather than derived on every call.
+//!
+//! ## Sponge
+//!
+//! [`PoseidonSponge`] absorbs and squeezes any number of field elements
+//! through the same width-3 permutation (rate 2, capacity 1). A sponge
+//! created with [`PoseidonSponge::for_circuit`] starts with a per-circuit tag
+//! in the capacity element, so the same inputs hash differently in the
+//! minting, zk-PoR, refund, and content key circuits. Input is padded with a
+//! single one element before the first squeeze, so inputs of different
+//! lengths never collide.
 
 use ark_bls12_381::Fr;
-use ark_ff::{BigInteger256, Field, PrimeField};
+use ark_ff::{BigInteger256, PrimeField};
+use ochra_types::governance::CircuitId;
 
+use crate::poseidon_tables::{MDS_MATRIX, ROUND_CONSTANTS};
 use crate::{CryptoError, Result};
 
+/// State width.
+const WIDTH: usize = 3;
+
+/// Rate elements absorbed per permutation.
+pub const RATE: usize = 2;
+
+/// Number of full rounds.
+const FULL_ROUNDS: usize = 8;
+
+/// Number of partial rounds.
+const PARTIAL_ROUNDS: usize = 57;
+
 /// Poseidon parameters for BLS12-381 scalar field.
 pub struct PoseidonParams {
     /// Round constants (R_F + R_P) * t field elements.
@@ -31,68 +58,14 @@ pub struct PoseidonParams {
     pub width: usize,
 }
 
-/// Generate Poseidon round constants deterministically from seed.
-///
-/// Uses a simple PRNG seeded from the Ochra Poseidon seed to generate
-/// field elements. This matches the Grain LFSR approach from the Poseidon paper.
-fn generate_round_constants(num_constants: usize) -> Vec<Fr> {
-    let seed = b"Ochra_Poseidon_BLS12-381_t3";
-    let mut constants = Vec::with_capacity(num_constants);
-
-    for i in 0..num_constants {
-        // Deterministic generation: hash(seed || counter)
-        let mut input = Vec::with_capacity(seed.len() + 8);
-        input.extend_from_slice(seed);
-        input.extend_from_slice(&(i as u64).to_le_bytes());
-        let hash = crate::blake3::hash(&input);
-
-        // Convert to field element (reduce modulo r)
-        let mut repr = [0u64; 4];
-        for (j, chunk) in hash.chunks(8).take(4).enumerate() {
-            let mut bytes = [0u8; 8];
-            bytes.copy_from_slice(chunk);
-            repr[j] = u64::from_le_bytes(bytes);
-        }
-        let big = BigInteger256::new(repr);
-        constants.push(Fr::from_bigint(big).unwrap_or(Fr::from(0u64)));
-    }
-
-    constants
-}
-
-/// Generate the MDS matrix for width=3 Poseidon.
-///
-/// Uses a Cauchy matrix construction for maximum diffusion.
-fn generate_mds_matrix() -> Vec<Vec<Fr>> {
-    let t = 3usize;
-    let mut matrix = vec![vec![Fr::from(0u64); t]; t];
-
-    // Cauchy matrix: M[i][j] = 1 / (x_i + y_j) where x_i = i, y_j = t + j
-    for (i, row) in matrix.iter_mut().enumerate().take(t) {
-        for (j, cell) in row.iter_mut().enumerate().take(t) {
-            let x = Fr::from((i + 1) as u64);
-            let y = Fr::from((t + j + 1) as u64);
-            let sum = x + y;
-            *cell = sum.inverse().unwrap_or(Fr::from(0u64));
-        }
-    }
-
-    matrix
-}
-
 /// Get the default Poseidon parameters for Ochra.
 pub fn default_params() -> PoseidonParams {
-    let full_rounds = 8;
-    let partial_rounds = 57;
-    let width = 3;
-    let num_constants = (full_rounds + partial_rounds) * width;
-
     PoseidonParams {
-        round_constants: generate_round_constants(num_constants),
-        mds_matrix: generate_mds_matrix(),
-        full_rounds,
-        partial_rounds,
-        width,
+        round_constants: ROUND_CONSTANTS.to_vec(),
+        mds_matrix: MDS_MATRIX.iter().map(|row| row.to_vec()).collect(),
+        full_rounds: FULL_ROUNDS,
+        partial_rounds: PARTIAL_ROUNDS,
+        width: WIDTH,
     }
 }
 
@@ -107,85 +80,145 @@ fn sbox(x: Fr) -> Fr {
 ///
 /// This is the core 2-input Poseidon function used in all Ochra ZK circuits.
 pub fn hash(a: Fr, b: Fr) -> Fr {
-    let params = default_params();
-    poseidon_permutation(&params, a, b)
+    let mut state = [Fr::from(0u64), a, b];
+    permute(&mut state);
+    state[1]
 }
 
-/// Poseidon sponge permutation for 2 inputs.
-fn poseidon_permutation(params: &PoseidonParams, a: Fr, b: Fr) -> Fr {
-    let t = params.width;
-    let r_f = params.full_rounds;
-    let r_p = params.partial_rounds;
-    let half_f = r_f / 2;
+/// The Poseidon permutation over the compiled-in tables.
+fn permute(state: &mut [Fr; WIDTH]) {
+    let half_f = FULL_ROUNDS / 2;
+    let rounds = ROUND_CONSTANTS.chunks_exact(WIDTH).enumerate();
 
-    // Initial state: [0, a, b] (capacity=0, rate elements = a, b)
-    let mut state = vec![Fr::from(0u64), a, b];
-
-    let mut rc_idx = 0;
-
-    // First half of full rounds
-    for _ in 0..half_f {
-        // Add round constants
-        for (j, s) in state.iter_mut().enumerate().take(t) {
-            *s += params.round_constants[rc_idx + j];
+    for (round, constants) in rounds {
+        for (s, c) in state.iter_mut().zip(constants) {
+            *s += c;
         }
-        rc_idx += t;
 
-        // Full S-box layer
-        for s in state.iter_mut().take(t) {
-            *s = sbox(*s);
+        if round < half_f || round >= half_f + PARTIAL_ROUNDS {
+            // Full S-box layer
+            for s in state.iter_mut() {
+                *s = sbox(*s);
+            }
+        } else {
+            // Partial S-box (only first element)
+            state[0] = sbox(state[0]);
         }
 
-        // MDS matrix multiplication
-        state = mds_mul(&params.mds_matrix, &state);
+        *state = mds_mul(state);
     }
+}
 
-    // Partial rounds
-    for _ in 0..r_p {
-        // Add round constants
-        for (j, s) in state.iter_mut().enumerate().take(t) {
-            *s += params.round_constants[rc_idx + j];
+/// MDS matrix-vector multiplication.
+fn mds_mul(state: &[Fr; WIDTH]) -> [Fr; WIDTH] {
+    let mut result = [Fr::from(0u64); WIDTH];
+    for (out, row) in result.iter_mut().zip(&MDS_MATRIX) {
+        for (m, s) in row.iter().zip(state) {
+            *out += *m * s;
         }
-        rc_idx += t;
+    }
+    result
+}
+
+/// Capacity tag separating one circuit's Poseidon calls from another's:
+/// `BLAKE3::hash("poseidon-domain" || circuit_name)` reduced into the field.
+pub fn domain_tag(circuit: CircuitId) -> Fr {
+    let name: &[u8] = match circuit {
+        CircuitId::Minting => b"minting",
+        CircuitId::ZkPor => b"zk-por",
+        CircuitId::Refund => b"refund",
+        CircuitId::ContentKey => b"content-key",
+    };
+    let mut input = Vec::with_capacity(15 + name.len());
+    input.extend_from_slice(b"poseidon-domain");
+    input.extend_from_slice(name);
+    Fr::from_le_bytes_mod_order(&crate::blake3::hash(&input))
+}
 
-        // Partial S-box (only first element)
-        state[0] = sbox(state[0]);
+/// Streaming Poseidon sponge (rate 2, capacity 1).
+#[derive(Clone, Debug)]
+pub struct PoseidonSponge {
+    state: [Fr; WIDTH],
+    /// Next rate position to absorb into or squeeze from.
+    pos: usize,
+    squeezing: bool,
+}
 
-        // MDS matrix multiplication
-        state = mds_mul(&params.mds_matrix, &state);
+impl PoseidonSponge {
+    /// Create a sponge with a zero capacity element.
+    pub fn new() -> Self {
+        Self::with_capacity(Fr::from(0u64))
     }
 
-    // Second half of full rounds
-    for _ in 0..half_f {
-        // Add round constants
-        for (j, s) in state.iter_mut().enumerate().take(t) {
-            *s += params.round_constants[rc_idx + j];
+    /// Create a sponge domain-separated for one circuit.
+    pub fn for_circuit(circuit: CircuitId) -> Self {
+        Self::with_capacity(domain_tag(circuit))
+    }
+
+    fn with_capacity(capacity: Fr) -> Self {
+        Self {
+            state: [capacity, Fr::from(0u64), Fr::from(0u64)],
+            pos: 0,
+            squeezing: false,
         }
-        rc_idx += t;
+    }
 
-        // Full S-box layer
-        for s in state.iter_mut().take(t) {
-            *s = sbox(*s);
+    /// Absorb field elements.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CryptoError::InvalidInput`] once squeezing has started.
+    pub fn absorb(&mut self, inputs: &[Fr]) -> Result<()> {
+        if self.squeezing {
+            return Err(CryptoError::InvalidInput(
+                "cannot absorb after squeezing".into(),
+            ));
         }
+        for input in inputs {
+            self.state[1 + self.pos] += input;
+            self.pos += 1;
+            if self.pos == RATE {
+                permute(&mut self.state);
+                self.pos = 0;
+            }
+        }
+        Ok(())
+    }
 
-        // MDS matrix multiplication
-        state = mds_mul(&params.mds_matrix, &state);
+    /// Squeeze one field element.
+    pub fn squeeze(&mut self) -> Fr {
+        if !self.squeezing {
+            self.state[1 + self.pos] += Fr::from(1u64);
+            permute(&mut self.state);
+            self.squeezing = true;
+            self.pos = 0;
+        } else if self.pos == RATE {
+            permute(&mut self.state);
+            self.pos = 0;
+        }
+        let out = self.state[1 + self.pos];
+        self.pos += 1;
+        out
     }
 
-    // Output: first state element (capacity)
-    state[1]
+    /// Squeeze `n` field elements.
+    pub fn squeeze_many(&mut self, n: usize) -> Vec<Fr> {
+        (0..n).map(|_| self.squeeze()).collect()
+    }
 }
 
-/// MDS matrix-vector multiplication.
-fn mds_mul(matrix: &[Vec<Fr>], state: &[Fr]) -> Vec<Fr> {
-    let t = state.len();
-    let mut result = vec![Fr::from(0u64); t];
-    for i in 0..t {
-        for j in 0..t {
-            result[i] += matrix[i][j] * state[j];
-        }
+impl Default for PoseidonSponge {
+    fn default() -> Self {
+        Self::new()
     }
-    result
+}
+
+/// Hash a variable-length input in a circuit's domain.
+pub fn hash_many(circuit: CircuitId, inputs: &[Fr]) -> Fr {
+    let mut sponge = PoseidonSponge::for_circuit(circuit);
+    // A fresh sponge is always absorbing.
+    let _ = sponge.absorb(inputs);
+    sponge.squeeze()
 }
 
 /// Iterated Poseidon for 4 inputs: H(a,b,c,d) = Poseidon(Poseidon(a,b), Poseidon(c,d)).
@@ -221,6 +254,179 @@ pub fn field_to_bytes(f: &Fr) -> [u8; 32] {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ark_ff::Field;
+
+    /// Grain LFSR from the Poseidon paper, Section 6, keyed by the Ochra
+    /// seed.
+    struct Grain {
+        state: std::collections::VecDeque<bool>,
+    }
+
+    impl Grain {
+        /// Load the paper's 80-bit parameter encoding (prime field, x^5
+        /// S-box, n = 255, t, R_F, R_P, 30 one bits), XOR in the first 80
+        /// bits of `BLAKE3::hash(seed)`, and discard 160 output bits.
+        fn new(seed: &[u8]) -> Self {
+            let fields: [(u64, usize); 6] = [
+                (1, 2),
+                (0, 4),
+                (255, 12),
+                (WIDTH as u64, 12),
+                (FULL_ROUNDS as u64, 10),
+                (PARTIAL_ROUNDS as u64, 10),
+            ];
+            let mut bits: Vec<bool> = fields
+                .iter()
+                .flat_map(|&(value, len)| (0..len).rev().map(move |i| (value >> i) & 1 == 1))
+                .collect();
+            bits.resize(80, true);
+            let key = crate::blake3::hash(seed);
+            for (i, bit) in bits.iter_mut().enumerate() {
+                *bit ^= (key[i / 8] >> (7 - i % 8)) & 1 == 1;
+            }
+            let mut grain = Self { state: bits.into() };
+            for _ in 0..160 {
+                grain.clock();
+            }
+            grain
+        }
+
+        fn clock(&mut self) -> bool {
+            let s = &self.state;
+            let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+            self.state.pop_front();
+            self.state.push_back(bit);
+            bit
+        }
+
+        /// Next self-shrinking output bit: of each pair, keep the second if
+        /// the first is set.
+        fn next_bit(&mut self) -> bool {
+            loop {
+                let keep = self.clock();
+                let bit = self.clock();
+                if keep {
+                    return bit;
+                }
+            }
+        }
+
+        /// 32 bytes, each filled most significant bit first, read as a
+        /// little-endian integer reduced mod r.
+        fn next_field(&mut self) -> Fr {
+            let mut bytes = [0u8; 32];
+            for byte in &mut bytes {
+                for _ in 0..8 {
+                    *byte = (*byte << 1) | u8::from(self.next_bit());
+                }
+            }
+            Fr::from_le_bytes_mod_order(&bytes)
+        }
+    }
+
+    /// Generate Poseidon round constants from the Ochra seed with the
+    /// Grain LFSR.
+    fn generate_round_constants(num_constants: usize) -> Vec<Fr> {
+        let mut grain = Grain::new(b"Ochra_Poseidon_BLS12-381_t3");
+        (0..num_constants).map(|_| grain.next_field()).collect()
+    }
+
+    /// Generate the MDS matrix for width=3 Poseidon.
+    ///
+    /// Uses a Cauchy matrix construction for maximum diffusion.
+    fn generate_mds_matrix() -> Vec<Vec<Fr>> {
+        let t = 3usize;
+        let mut matrix = vec![vec![Fr::from(0u64); t]; t];
+
+        // Cauchy matrix: M[i][j] = 1 / (x_i + y_j) where x_i = i, y_j = t + j
+        for (i, row) in matrix.iter_mut().enumerate().take(t) {
+            for (j, cell) in row.iter_mut().enumerate().take(t) {
+                let x = Fr::from((i + 1) as u64);
+                let y = Fr::from((t + j + 1) as u64);
+                let sum = x + y;
+                *cell = sum.inverse().unwrap_or(Fr::from(0u64));
+            }
+        }
+
+        matrix
+    }
+
+    /// Poseidon sponge permutation for 2 inputs.
+    fn reference_permutation(params: &PoseidonParams, a: Fr, b: Fr) -> Fr {
+        let t = params.width;
+        let r_f = params.full_rounds;
+        let r_p = params.partial_rounds;
+        let half_f = r_f / 2;
+
+        // Initial state: [0, a, b] (capacity=0, rate elements = a, b)
+        let mut state = vec![Fr::from(0u64), a, b];
+
+        let mut rc_idx = 0;
+
+        // First half of full rounds
+        for _ in 0..half_f {
+            // Add round constants
+            for (j, s) in state.iter_mut().enumerate().take(t) {
+                *s += params.round_constants[rc_idx + j];
+            }
+            rc_idx += t;
+
+            // Full S-box layer
+            for s in state.iter_mut().take(t) {
+                *s = sbox(*s);
+            }
+
+            // MDS matrix multiplication
+            state = reference_mds_mul(&params.mds_matrix, &state);
+        }
+
+        // Partial rounds
+        for _ in 0..r_p {
+            // Add round constants
+            for (j, s) in state.iter_mut().enumerate().take(t) {
+                *s += params.round_constants[rc_idx + j];
+            }
+            rc_idx += t;
+
+            // Partial S-box (only first element)
+            state[0] = sbox(state[0]);
+
+            // MDS matrix multiplication
+            state = reference_mds_mul(&params.mds_matrix, &state);
+        }
+
+        // Second half of full rounds
+        for _ in 0..half_f {
+            // Add round constants
+            for (j, s) in state.iter_mut().enumerate().take(t) {
+                *s += params.round_constants[rc_idx + j];
+            }
+            rc_idx += t;
+
+            // Full S-box layer
+            for s in state.iter_mut().take(t) {
+                *s = sbox(*s);
+            }
+
+            // MDS matrix multiplication
+            state = reference_mds_mul(&params.mds_matrix, &state);
+        }
+
+        // Output: first state element (capacity)
+        state[1]
+    }
+
+    /// MDS matrix-vector multiplication.
+    fn reference_mds_mul(matrix: &[Vec<Fr>], state: &[Fr]) -> Vec<Fr> {
+        let t = state.len();
+        let mut result = vec![Fr::from(0u64); t];
+        for i in 0..t {
+            for j in 0..t {
+                result[i] += matrix[i][j] * state[j];
+            }
+        }
+        result
+    }
 
     #[test]
     fn test_poseidon_deterministic() {
@@ -297,4 +503,84 @@ mod tests {
         // 3^5 = 243
         assert_eq!(result, Fr::from(243u64));
     }
+
+    #[test]
+    fn test_tables_match_seed() {
+        let params = default_params();
+        assert_eq!(
+            params.round_constants,
+            generate_round_constants((FULL_ROUNDS + PARTIAL_ROUNDS) * WIDTH)
+        );
+        assert_eq!(params.mds_matrix, generate_mds_matrix());
+    }
+
+    #[test]
+    fn test_known_answer() {
+        assert!(ROUND_CONSTANTS.iter().all(|c| *c != Fr::from(0u64)));
+        assert_eq!(
+            hex::encode(field_to_bytes(&hash(Fr::from(1u64), Fr::from(2u64)))),
+            "d0dda13d8edb2d57d6830d9ea1e45ce2a917327d1eeb344e8d5c2be9bccaa413"
+        );
+        assert_eq!(
+            hex::encode(field_to_bytes(&hash(Fr::from(0u64), Fr::from(0u64)))),
+            "90b038266a80aa27b0d7e30f4d4adcd9051c50c541d84b012653bbd49553ec6b"
+        );
+    }
+
+    #[test]
+    fn test_permutation_matches_reference() {
+        let params = default_params();
+        for (a, b) in [(0u64, 0u64), (1, 2), (12345, 67890)] {
+            let (a, b) = (Fr::from(a), Fr::from(b));
+            assert_eq!(hash(a, b), reference_permutation(&params, a, b));
+        }
+    }
+
+    #[test]
+    fn test_sponge_streaming_matches_one_shot() {
+        let inputs: Vec<Fr> = (1..=7u64).map(Fr::from).collect();
+
+        let mut one_shot = PoseidonSponge::for_circuit(CircuitId::Minting);
+        one_shot.absorb(&inputs).expect("absorb");
+
+        let mut streamed = PoseidonSponge::for_circuit(CircuitId::Minting);
+        for chunk in inputs.chunks(3) {
+            streamed.absorb(chunk).expect("absorb");
+        }
+
+        let first = one_shot.squeeze_many(3);
+        assert_eq!(first, streamed.squeeze_many(3));
+        assert_eq!(hash_many(CircuitId::Minting, &inputs), first[0]);
+    }
+
+    #[test]
+    fn test_sponge_length_and_domain_separation() {
+        let one = [Fr::from(5u64)];
+        let padded = [Fr::from(5u64), Fr::from(0u64)];
+        assert_ne!(
+            hash_many(CircuitId::ZkPor, &one),
+            hash_many(CircuitId::ZkPor, &padded)
+        );
+        assert_ne!(
+            hash_many(CircuitId::ZkPor, &one),
+            hash_many(CircuitId::Refund, &one)
+        );
+        assert_ne!(
+            hash_many(CircuitId::ZkPor, &[]),
+            hash_many(CircuitId::ZkPor, &one)
+        );
+    }
+
+    #[test]
+    fn test_sponge_squeeze_sequence() {
+        let mut sponge = PoseidonSponge::new();
+        sponge.absorb(&[Fr::from(1u64)]).expect("absorb");
+        let out = sponge.squeeze_many(5);
+        for (i, a) in out.iter().enumerate() {
+            for b in &out[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+        assert!(sponge.absorb(&[Fr::from(2u64)]).is_err());
+    }
 }
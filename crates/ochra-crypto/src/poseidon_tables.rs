@@ -0,0 +1,227 @@
+//! Precomputed Poseidon tables for BLS12-381, t = 3 (Section 2.4).
+//!
+//! Round constants are drawn from the Grain LFSR keyed by the seed
+//! `b"Ochra_Poseidon_BLS12-381_t3"`;
+//! `poseidon::tests::test_tables_match_seed` regenerates them and fails on
+//! any mismatch. Do not edit by hand.
+
+use ark_bls12_381::Fr;
+use ark_ff::MontFp;
+
+/// Round constants, `(R_F + R_P) * t` elements in round order.
+pub(crate) const ROUND_CONSTANTS: [Fr; 195] = [
+    MontFp!("51109386344024860940095647700265378126763039548625288944088540749598209046011"),
+    MontFp!("5613740214594406473048873692484371604155061905153209650109483414759091296698"),
+    MontFp!("51828416412847824581567356603217111345419319016873458956854158821407546057407"),
+    MontFp!("31427472242402204921282677506420421693849446362545658428131261404000751403697"),
+    MontFp!("49261635998771585500718586936280536892519366614361660716461869280755451877000"),
+    MontFp!("5784172415089366142312090289858851062031915689950018115983147241127929499689"),
+    MontFp!("38630448505537634753367951102432861245521441393529848994340947873176916162093"),
+    MontFp!("30839470120898622596637654955409081061307028410119331731223061674711187603026"),
+    MontFp!("34549885834897353944550560560307640608266061727865630959115539872924208527199"),
+    MontFp!("10245780127002484234075569457369408077826929488964408600418335787323032269717"),
+    MontFp!("41031361595523627515623593596647116006994868313929499409872245249658495557263"),
+    MontFp!("48586550894525942450090896942463944945110942963263353176342019992533275585252"),
+    MontFp!("29068837069973542718742514322243578841995620333110683999388232496191975986964"),
+    MontFp!("24134010146477623295045780008527479779768037904950243633926035937837131899181"),
+    MontFp!("43339555007107093189128544472271139299567274667883038554630644102997873969473"),
+    MontFp!("17617255704706519196788981586498733187878158994814569657207116606070452577223"),
+    MontFp!("16383433357392261916080705794993482506655018332928017799340263828270772769866"),
+    MontFp!("18547325274398856516946761660229311147997474792302443069040031654478947659010"),
+    MontFp!("25802482994367120378639916034608421008936324248451574745796142687063659027017"),
+    MontFp!("22521490866248815911416095782496693643583291330483376759630891154867390983636"),
+    MontFp!("32763501651159661895967069889066936004280119662843282719682666961442154496216"),
+    MontFp!("13955513050090215585580759960087364890310544751858827364222143709765767110604"),
+    MontFp!("33798990952993773769167092984608856999728058109400269073497282976728604228869"),
+    MontFp!("3933028274967968815963054104916743583637255701565875295326604093144223530137"),
+    MontFp!("32594964084682205350435553110198247914727219041573168236067430132259355704725"),
+    MontFp!("12317961346895494028433066438796893032619453240157451525817418152864697014509"),
+    MontFp!("5991722346538824083704920252901158595385376259440568000015284582940017989873"),
+    MontFp!("50170954700426986863412969543000195532243817353410151368864332418739992244702"),
+    MontFp!("10174050689344087955602712477146134373796728238921227916034222895942068716268"),
+    MontFp!("22079664491111058985966677316127619519761569502963014522102333117084297105364"),
+    MontFp!("35452195542513757418485169313377369265770709386393301602638941711036022412758"),
+    MontFp!("24124957894048428220630473140262042053630584804564095293521071934189967344712"),
+    MontFp!("41002271133031827667140377522290204223798335568486118807402877250464203018678"),
+    MontFp!("34545882342097551111980011065123579022655278152907081163287063327339002682330"),
+    MontFp!("26243469868626243541957269615735865205507015706733081309726672873530236924819"),
+    MontFp!("10427619818775836672542635177405749830117589491968745488646444737902500485079"),
+    MontFp!("50617649568811229405673943858781494497879736980068631864260963634812981231828"),
+    MontFp!("40601912561007843493531453304569091358443173776576907562623583244356578701620"),
+    MontFp!("17832926733421419257824505835589622465762476744680528042028005917095874524552"),
+    MontFp!("35907383918706777924660840785554756136863261162352385365179331112517775505989"),
+    MontFp!("8428837624817927535353871143716663946909840257385111043486447639080770668769"),
+    MontFp!("1762022354746705077153101778215629114996422770057207425585919113238326919964"),
+    MontFp!("7345801227967401429564155450659614579881601463367218027325294080873679550704"),
+    MontFp!("27602899967955044323268612223941946906555912604686441344592071979893715048002"),
+    MontFp!("8006730835990980685494536838941552430456442756987763303888074002154742061573"),
+    MontFp!("13366976585774201310014347290161416030733639225280412925633206817030400598270"),
+    MontFp!("5422337248306387797262215414948885325132356646720653335086599680242724553170"),
+    MontFp!("14025183799980222463414306603464465119962149864463191941842415981116708309366"),
+    MontFp!("13857600924471625549896565452096349832917125558707348535721014127882237488371"),
+    MontFp!("25897984819049855760429975847344688170414939336264871733936007279301563173605"),
+    MontFp!("8359171454840980297350826271684975575465060837941981923747252946738468068706"),
+    MontFp!("16885465228757954196780816199331780379116451332618282029957553177403203402805"),
+    MontFp!("36012063004361762129853398197203495953472935124758792063545257424244359909700"),
+    MontFp!("10131969722980168618097442372621616725134384926388785020895720664370554654100"),
+    MontFp!("34687546880912459054155333859321480266496986522077106064583163590518301622608"),
+    MontFp!("26143598638995185031406858715171820121630955745110413685592245965762695619204"),
+    MontFp!("49692489109270281070052288519096897500554812868319558579379556443258904754889"),
+    MontFp!("9268731572984575463000788572113560212809646204996721067225960449462244676372"),
+    MontFp!("15866846627641516831622870763697011461031537414730355845357361407943980596523"),
+    MontFp!("38463583774546373880103417485095403355245179631883330046445958301382188531310"),
+    MontFp!("7733984458966516929778571077538502499006587000375629176584189003714204939018"),
+    MontFp!("24244529729910757648325517146001509160470864064816343858739234576973277359383"),
+    MontFp!("50604866495500781903084529584376165284837919570112649956628957620406988744325"),
+    MontFp!("17963457646960402044822720329148546434519592308303058290629801210586826414394"),
+    MontFp!("17865533825483648211844485342919601706524732811777823277207114518869657211310"),
+    MontFp!("45210528101737767520336216155022268733462431093226096249103271071788015465526"),
+    MontFp!("50711254258717750389922255114061229337710012508265058461052889708358280981685"),
+    MontFp!("47820206947144672720354798364992407576671788436485114650783014102883836235391"),
+    MontFp!("27470364306767026087893898641570992313134914199394856005194279417982776373654"),
+    MontFp!("7114213826710363100577665711853803050783348338491633031707202327994347016676"),
+    MontFp!("23111131406684006947021394821593104264328856876028845416576508796055322715429"),
+    MontFp!("4381609474643020192996361866587787762350066617347819112686576561039984431475"),
+    MontFp!("30790751042767860046924898237548556870403772338081539177356248878384768385413"),
+    MontFp!("23645708546778581354176731095257372339292352801444093454609293344693037039381"),
+    MontFp!("50207350855050822246488201738617429508735488490317396980667492372359913279321"),
+    MontFp!("23107317730444345474949026447064579902472263467820101817996084859761605770772"),
+    MontFp!("11079321597486166933088189950429241461876647754751876920381355236702961434198"),
+    MontFp!("10261400070508519034414153770437990549519514082810107940574854732721583471850"),
+    MontFp!("1745257215820661001719298575636439815558803730598204541618736194740208313556"),
+    MontFp!("35837768164621297227942315042587779123556467007919922796573974127687416661712"),
+    MontFp!("52333498471775096251883965740153161603849986664747377886396035459117735917559"),
+    MontFp!("21010710949061971069676399165237075278549467325191772546802476691340471392607"),
+    MontFp!("45780382941950416604843681200650685309296540710656960368413038319620711786791"),
+    MontFp!("33131718147113366704527737387137708601118993689556160130266220518958244536192"),
+    MontFp!("8325449800754398669811563316450597406086500381819493616099017425895425838558"),
+    MontFp!("36868591250450916461731999220895008740355576392268643654719253453421418185643"),
+    MontFp!("10135663832758658667453459009147159672185291424041187133513983807160409634493"),
+    MontFp!("5435946842159606678049794452474756977399314123655866373252453832373289688318"),
+    MontFp!("4187371897355466606513405677490664179445642295005054057664714632180499326569"),
+    MontFp!("12288293092343390104246525434732201384025647458378885022448097584269731005599"),
+    MontFp!("3246350900680233065583805773956815432459441857716117479101484999059128736665"),
+    MontFp!("33009381341859860696774847147664156815395128154530137793200212376504687192405"),
+    MontFp!("43663362581781888361420010453175721816847359450823842097688015048838168939755"),
+    MontFp!("48549164738588342670795544334398665801848152980663407299389651130143213767430"),
+    MontFp!("14361802476687831129919434986109818076352384572951457230096307220045808154389"),
+    MontFp!("30162441290239970878725235230867871588442018452606419653419871396232277442436"),
+    MontFp!("44606622079909810312561536081084091538971724672740399486986272934682030681849"),
+    MontFp!("24627531041915918207144757942862850873086201510923030571650317789848625523082"),
+    MontFp!("46267466323877023979738187612987864336989059109717980814152003872227997552238"),
+    MontFp!("8468409577027054882232362413849243168013873742992944960245485669235746408804"),
+    MontFp!("36751724594479226012623242887555878515095926388756494174048598910372250408490"),
+    MontFp!("42231749413866401009357717555672023581180437885333229907098371054328207304197"),
+    MontFp!("4915312745525321859587913171828172120516422460698417539414739548646818589244"),
+    MontFp!("14334674547924543769764235245579440060374628563073854375934326178358314085134"),
+    MontFp!("13965273012342080477912372992339432828478424457226320087188108966353083806701"),
+    MontFp!("3547108284650699246681958347701499675157319805330302365617431925063532673592"),
+    MontFp!("4487277802977820058127397350806310658731647047196564964463846265397414930414"),
+    MontFp!("15628516095992864007891444532332702385397550446294053553807387262228718612635"),
+    MontFp!("42583945227218007669559741641079825538429949567039217717125849775621612114171"),
+    MontFp!("42570466523327310889349952067309347881502050876350630223264532525286913743495"),
+    MontFp!("17044189425210113385182418372851221623936007697471896127558980712869843407421"),
+    MontFp!("46132611944452145696225234551414517101521440921161348997116805817916591136563"),
+    MontFp!("21841291992743330112879661460329070693730297661503438526323777972342200182059"),
+    MontFp!("15880552557962577310257577780140720904008286442950895300992079903420133839186"),
+    MontFp!("25049598374527024001586426592665697753829842931793941547142685323256865552346"),
+    MontFp!("31337540293091913253540916979247763398100486339576395584489643166696432681745"),
+    MontFp!("42535559192617128734676748830449546669451383919644486252582754134782150500272"),
+    MontFp!("1084482531272985707555909868507165519095241528907379397421157976858219584136"),
+    MontFp!("32874696607313533256963993125130778781167776616705587753994770237841132571614"),
+    MontFp!("4621718962510774519978340093080333117590097403256750548485427605137507185758"),
+    MontFp!("25549543635889095735919031751062704583340886072112976583846517854391987763660"),
+    MontFp!("21177037989070895634125205570600564645274884949912180670150544194415520064058"),
+    MontFp!("15789536960276420691970373015247163087966089134522982374183506982426462022354"),
+    MontFp!("19052967742451775376207929230999685262014683274249265466052792505032933482538"),
+    MontFp!("42385657094293720771125237723717399169147060636973997056689425241695736383051"),
+    MontFp!("10334743383414567813419122810350786912219295216773251342880814085578660461794"),
+    MontFp!("10453406625034491093390501576746292825595745992152094856193783174382091246141"),
+    MontFp!("37120300805769901946519803585765804645749729174595823281385498045188634404161"),
+    MontFp!("42818147273185772801277008714895397977781059186402872774717029468428738238218"),
+    MontFp!("21415293398679986692807615959383889708295428679362350491634131183586433503238"),
+    MontFp!("3793547211419460684005324928198238213620962649977382417828938599513236486735"),
+    MontFp!("49142117315198863539858575547849042841950078485719514670157175127086574583472"),
+    MontFp!("16061661808897573166036121272760977786947445327327943112489743440349659825633"),
+    MontFp!("28773413938384134805462644881409657335052308535128299218028016210584344944012"),
+    MontFp!("16002556603385080189358297731827982902801154928841190265345436066015736462847"),
+    MontFp!("2189810243640944075242807886885870397437302400718926283029387289011037370710"),
+    MontFp!("9677321773945341153735512712622364747814603089545022173810525215797325905586"),
+    MontFp!("22257127838477390060429301059766371143642356171341029216107117222807584413803"),
+    MontFp!("17884685688635536064552849272195074441973676957695209906787593494656153277058"),
+    MontFp!("24559146044192044075286977658319075737876604981894087887367341342830165870786"),
+    MontFp!("34215792080794337959462869851914711772277939683482512782428953052843799360133"),
+    MontFp!("42539954850847241924101354900621030137243622655429519546994646959584103879492"),
+    MontFp!("47600496341001818741063658334578209729460031127480531006929152942973226160660"),
+    MontFp!("22744479679411931366055582467336760435689068595462659933477986775502191758266"),
+    MontFp!("5724123382116424648828177050042178098647688412672803661532821757570512185365"),
+    MontFp!("20528522773982619866895898068440012295114054647787249547823081271223121347964"),
+    MontFp!("34399046615537845056168675391898709524346798455915746113239853931805412439220"),
+    MontFp!("6769111594015987398430240041227493104088332485673008038057195128084142250686"),
+    MontFp!("42427793840813086433853871279997629670432826310396230778714147926241386595002"),
+    MontFp!("52229219098952155154829181966998859763763053431489912097404275309848607604826"),
+    MontFp!("17577058017261319098238197388226264437253774924469505893935437140608049259628"),
+    MontFp!("36730976758544290753680704148083497874335600617589624539163506467936214002099"),
+    MontFp!("19148239525461643639458749440540236943208881321730782615117713158958315768879"),
+    MontFp!("1990753391169565528243113182988350684058840123575597187808304910257252878818"),
+    MontFp!("18468439555608291201970030408978186774942747294360570153125792916640039296001"),
+    MontFp!("30982176926552694176891843957741265585264169074588042024280610991477327743738"),
+    MontFp!("46687480228157467547642328707854989844088154621426938795880963661595793507650"),
+    MontFp!("48724415578762720483699462931111911624061592235887234937862949088115601411408"),
+    MontFp!("3270934101802779792750500462026096781229003120734545314245675142473935460010"),
+    MontFp!("48561411030747425814599219705223081623718306827201293110980338728929430033155"),
+    MontFp!("2394976858922195203664047719955687284215395696608434160405887717088309769217"),
+    MontFp!("41812495308964447308023602109778469092047643640326005551935646728115658694130"),
+    MontFp!("34632958914705022004595459333857828588004303918243951946009793577685838957956"),
+    MontFp!("11405796706877508925905617287724388193156762241940434136858725344888684578109"),
+    MontFp!("18771820098359405628392210960668004698828579436304132256436647660655363306398"),
+    MontFp!("37469373350271347182988299034195505399396723369541952960836800244015187532973"),
+    MontFp!("41157702796743261993683672313330187589273892230793958251477978481099117014975"),
+    MontFp!("10297086587460920022607470338380558758078212189065137905539844174943255410828"),
+    MontFp!("1368575192419303100183079810045017951399116056240403306136762884259174491217"),
+    MontFp!("31651837617662562320404300620862850731803972238010317658858914570827440183849"),
+    MontFp!("44400292219661345092274962702830908992146620837218495920057950138713635160822"),
+    MontFp!("46440640191106941635193874925685721118272836326819920104952294353882077924371"),
+    MontFp!("19377817011032711682011883398232956482296823438464506741575301758673993057252"),
+    MontFp!("38025022594952309314645649031559765363023153079124177721432557980025195051318"),
+    MontFp!("3104601349383836686605344567602994998592912611705649549031888453296225559223"),
+    MontFp!("21264053428466107888244192567156861068248209406297681746719452692961750430778"),
+    MontFp!("26604042509274130333739454674267063082518973342190851474939219161126268513833"),
+    MontFp!("4309220722704229895217767821324461978151267537423049060540396248172755003545"),
+    MontFp!("13842619443708842326369710399271643489944272549086774642459913212082724956890"),
+    MontFp!("8081256173013452563886160453556987209220427463109325828489536965484012999510"),
+    MontFp!("50624558136735550903515555566647664937903841349105330320796993087531173940214"),
+    MontFp!("19639055234142283428921044314857368579217956498891907489109071236320328995492"),
+    MontFp!("5282275038312893028221513137374463184809262792737813531882059042818366726131"),
+    MontFp!("34260034892092124228078913566929878568432002816553142751100985046464643467510"),
+    MontFp!("28942542140969679433384894947704903771702997133257784517835926016651070707536"),
+    MontFp!("18371803873920101596913874362326091921237147128309966706307920303403571670264"),
+    MontFp!("49492313077356167645785145548455044553060611557528070831820920231470382991056"),
+    MontFp!("36499788344121921448860571907540728319097084083554334893563422292642992232414"),
+    MontFp!("6286414926701172920174580473627202624666085308603932595313842190065770107619"),
+    MontFp!("21055053186836906897867358640552610190085796043160720491146667981432277369987"),
+    MontFp!("8297944858615442732402618537672980775248684098188498312882677072374993808464"),
+    MontFp!("7960319165267737152378907975017577098414941115801563832420075230935881170881"),
+    MontFp!("2975963291654917679400004878522468011573583829125234564223911467181814942061"),
+    MontFp!("1177280503874231101374982080326904887747847241042828869101155549024432282930"),
+    MontFp!("36052909785644148576655734487146307904060703727706973270132214687225177066777"),
+];
+
+/// Cauchy MDS matrix, row major.
+pub(crate) const MDS_MATRIX: [[Fr; 3]; 3] = [
+    [
+        MontFp!("31461525105075714287668644304911579502614331500316582693562195219963148710708"),
+        MontFp!("43696562645938492066206450423488304864742127083773031518836382249948817653761"),
+        MontFp!("14981678621464625851270783002338847382197300714436467949315331057125308909861"),
+    ],
+    [
+        MontFp!("43696562645938492066206450423488304864742127083773031518836382249948817653761"),
+        MontFp!("14981678621464625851270783002338847382197300714436467949315331057125308909861"),
+        MontFp!("45881390778235416669516772944662720107979233437961683094778201362446258536449"),
+    ],
+    [
+        MontFp!("14981678621464625851270783002338847382197300714436467949315331057125308909861"),
+        MontFp!("45881390778235416669516772944662720107979233437961683094778201362446258536449"),
+        MontFp!("11652416705583597884321720112930214630597900555672808405023035266653018041003"),
+    ],
+];
@@ -0,0 +1,184 @@
+//! Fixed-size secret key material.
+//!
+//! [`SecretBytes`] is the container for every symmetric key, chain key, and
+//! shared secret held in a struct. It:
+//!
+//! - zeroizes its contents on drop,
+//! - compares in constant time (`==` never short-circuits on the first
+//!   differing byte),
+//! - prints as `SecretBytes<N>(..)` under `Debug`, so secrets never reach
+//!   logs through a derived `Debug` on the containing struct,
+//! - serializes through serde without branching on the contents.
+//!
+//! The bytes are reachable only through [`SecretBytes::expose`], which keeps
+//! every read of secret material greppable.
+
+use std::fmt;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{CryptoError, Result};
+
+/// `N` bytes of secret key material.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SecretBytes<const N: usize> {
+    bytes: [u8; N],
+}
+
+impl<const N: usize> SecretBytes<N> {
+    /// Wrap existing key material.
+    pub fn new(bytes: [u8; N]) -> Self {
+        Self { bytes }
+    }
+
+    /// Copy key material from a slice of exactly `N` bytes.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != N {
+            return Err(CryptoError::InvalidKeyLength {
+                expected: N,
+                actual: bytes.len(),
+            });
+        }
+        let mut out = [0u8; N];
+        out.copy_from_slice(bytes);
+        Ok(Self { bytes: out })
+    }
+
+    /// Fresh random key material from the OS RNG.
+    pub fn random() -> Self {
+        let mut bytes = [0u8; N];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut bytes);
+        Self { bytes }
+    }
+
+    /// Borrow the secret bytes.
+    pub fn expose(&self) -> &[u8; N] {
+        &self.bytes
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for SecretBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl<const N: usize> ConstantTimeEq for SecretBytes<N> {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+impl<const N: usize> PartialEq for SecretBytes<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const N: usize> Eq for SecretBytes<N> {}
+
+impl<const N: usize> fmt::Debug for SecretBytes<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes<{N}>(..)")
+    }
+}
+
+impl<const N: usize> Serialize for SecretBytes<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // Same wire shape as a plain `[u8; N]`, so migrated fields stay
+        // compatible with previously stored state.
+        use serde::ser::SerializeTuple;
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for byte in &self.bytes {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for SecretBytes<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct SecretVisitor<const N: usize>;
+
+        impl<'de, const N: usize> Visitor<'de> for SecretVisitor<N> {
+            type Value = SecretBytes<N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{N} secret bytes")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut secret = SecretBytes { bytes: [0u8; N] };
+                for (i, slot) in secret.bytes.iter_mut().enumerate() {
+                    *slot = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+                }
+                Ok(secret)
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Self::Value, E> {
+                SecretBytes::from_slice(v).map_err(|_| E::invalid_length(v.len(), &self))
+            }
+        }
+
+        deserializer.deserialize_tuple(N, SecretVisitor::<N>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_contents() {
+        let secret = SecretBytes::new([0xAB; 32]);
+        let printed = format!("{secret:?}");
+        assert_eq!(printed, "SecretBytes<32>(..)");
+        assert!(!printed.contains("171"));
+    }
+
+    #[test]
+    fn test_constant_time_equality() {
+        let a = SecretBytes::new([1u8; 32]);
+        let b = SecretBytes::new([1u8; 32]);
+        let mut c_bytes = [1u8; 32];
+        c_bytes[31] = 2;
+        let c = SecretBytes::new(c_bytes);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(bool::from(a.ct_eq(&b)));
+    }
+
+    #[test]
+    fn test_zeroize_clears_bytes() {
+        let mut secret = SecretBytes::new([0x5A; 16]);
+        secret.zeroize();
+        assert_eq!(secret.expose(), &[0u8; 16]);
+    }
+
+    #[test]
+    fn test_from_slice_checks_length() {
+        assert!(SecretBytes::<32>::from_slice(&[0u8; 31]).is_err());
+        let secret = SecretBytes::<4>::from_slice(&[1, 2, 3, 4]).expect("4 bytes");
+        assert_eq!(secret.expose(), &[1, 2, 3, 4]);
+        assert_ne!(SecretBytes::<32>::random(), SecretBytes::<32>::random());
+    }
+
+    #[test]
+    fn test_serde_matches_plain_array() {
+        let secret = SecretBytes::new([7u8; 32]);
+        let json = serde_json::to_string(&secret).expect("serialize");
+        assert_eq!(json, serde_json::to_string(&[7u8; 32]).expect("array"));
+
+        let restored: SecretBytes<32> = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(restored, secret);
+        assert!(serde_json::from_str::<SecretBytes<32>>("[1,2,3]").is_err());
+    }
+}
@@ -0,0 +1,462 @@
+//! Time-lock encryption to a FROST group key.
+//!
+//! A value is sealed to the quorum's Ed25519 group key `Y = x·B` with a
+//! fresh scalar `r`. The capsule `R = r·B` travels with the ciphertext and
+//! the AEAD key is derived from `r·Y`, which without `r` only the quorum can
+//! compute, as `x·R`. It does so by threshold: each member publishes a
+//! [`DecryptionShare`] `D_i = x_i·R` with a DLEQ proof that `D_i` and its
+//! verifying share `Y_i = x_i·B` use the same secret, and any `t` valid
+//! shares [`combine`] by Lagrange interpolation at zero.
+//!
+//! The target epoch is bound into the key, so a release only opens values
+//! sealed to that epoch. The capsule is bound to the epoch as well: the
+//! sealer attaches a Schnorr proof of knowledge of `r` over `(Y, R, epoch)`,
+//! and [`decryption_share`] refuses a capsule whose proof does not hold for
+//! the epoch it is asked about. Nobody but the sealer can produce that proof,
+//! so a capsule cannot be presented under an earlier epoch. Refusing to
+//! release before the epoch is the members' job; see `ochra-frost`'s
+//! time-lock coordination.
+//!
+//! ```text
+//! key     = BLAKE3::hash(enc("timelock-key", r·Y, R, Y, LE64(epoch)))
+//! c_R     = BLAKE3-XOF-512(enc("timelock-capsule", Y, R, LE64(epoch), k·B)) mod ℓ
+//! binding = c_R || (k + c_R·r)
+//! c       = BLAKE3-XOF-512(enc("timelock-dleq", Y_i, R, D_i, k·B, k·R)) mod ℓ
+//! proof   = c || (k + c·x_i)
+//! ```
+
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use crate::blake3;
+use crate::chacha20;
+use crate::frost::FrostKeyPackage;
+use crate::{CryptoError, Result};
+
+/// A value sealed to a group key until an epoch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sealed {
+    pub target_epoch: u64,
+    /// `R = r·B`, from which the quorum derives the key.
+    pub capsule: [u8; 32],
+    /// Proof of knowledge of `r`, binding the capsule to `target_epoch`.
+    pub binding: [u8; 64],
+    /// ChaCha20-Poly1305 ciphertext with appended tag.
+    pub ciphertext: Vec<u8>,
+}
+
+impl Sealed {
+    /// Encode as `LE64(target_epoch) || capsule || binding || ciphertext`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + 32 + 64 + self.ciphertext.len());
+        out.extend_from_slice(&self.target_epoch.to_le_bytes());
+        out.extend_from_slice(&self.capsule);
+        out.extend_from_slice(&self.binding);
+        out.extend_from_slice(&self.ciphertext);
+        out
+    }
+
+    /// Decode a sealed value, checking that the capsule is a usable point.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 + 32 + 64 + chacha20::TAG_SIZE {
+            return Err(CryptoError::InvalidInput("sealed value too short".into()));
+        }
+        let mut epoch = [0u8; 8];
+        epoch.copy_from_slice(&data[..8]);
+        let mut capsule = [0u8; 32];
+        capsule.copy_from_slice(&data[8..40]);
+        point(&capsule)?;
+        let mut binding = [0u8; 64];
+        binding.copy_from_slice(&data[40..104]);
+        Ok(Self {
+            target_epoch: u64::from_le_bytes(epoch),
+            capsule,
+            binding,
+            ciphertext: data[104..].to_vec(),
+        })
+    }
+
+    /// Check that the capsule was sealed to `group_key` for `target_epoch`.
+    ///
+    /// # Errors
+    ///
+    /// - see [`verify_binding`]
+    pub fn verify_binding(&self, group_key: &[u8; 32]) -> Result<()> {
+        verify_binding(group_key, &self.capsule, self.target_epoch, &self.binding)
+    }
+}
+
+/// One member's share of the key for a capsule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecryptionShare {
+    /// The member's FROST identifier, as a little-endian scalar.
+    pub identifier: [u8; 32],
+    /// `D_i = x_i·R`.
+    pub share: [u8; 32],
+    /// DLEQ proof `c || z`.
+    pub proof: [u8; 64],
+}
+
+fn point(bytes: &[u8; 32]) -> Result<EdwardsPoint> {
+    CompressedEdwardsY(*bytes)
+        .decompress()
+        .filter(|p| !p.is_small_order())
+        .ok_or_else(|| CryptoError::InvalidInput("not a valid group element".into()))
+}
+
+fn scalar(bytes: &[u8]) -> Result<Scalar> {
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| CryptoError::InvalidKeyLength {
+            expected: 32,
+            actual: bytes.len(),
+        })?;
+    Option::from(Scalar::from_canonical_bytes(bytes))
+        .ok_or_else(|| CryptoError::InvalidInput("non-canonical scalar".into()))
+}
+
+fn random_scalar() -> Scalar {
+    let mut wide = [0u8; 64];
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut wide);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+fn challenge(fields: &[&[u8]]) -> Scalar {
+    let mut wide = [0u8; 64];
+    blake3::hash_xof(&blake3::encode_multi_field(fields), &mut wide);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+fn aead_key(
+    shared: &EdwardsPoint,
+    capsule: &[u8; 32],
+    group_key: &[u8; 32],
+    epoch: u64,
+) -> [u8; 32] {
+    blake3::derive_key(
+        blake3::contexts::TIMELOCK_KEY,
+        &blake3::encode_multi_field(&[
+            shared.compress().as_bytes(),
+            capsule,
+            group_key,
+            &epoch.to_le_bytes(),
+        ]),
+    )
+}
+
+fn binding_challenge(group_key: &[u8; 32], capsule: &[u8; 32], epoch: u64, a: &[u8; 32]) -> Scalar {
+    challenge(&[
+        b"timelock-capsule",
+        group_key,
+        capsule,
+        &epoch.to_le_bytes(),
+        a,
+    ])
+}
+
+/// Check the proof that whoever made `capsule` knows its `r` and sealed it
+/// to `group_key` for `target_epoch`.
+///
+/// # Errors
+///
+/// - [`CryptoError::InvalidInput`] if a point or scalar is malformed
+/// - [`CryptoError::SignatureVerification`] if the proof does not hold,
+///   including for a capsule presented under another epoch
+pub fn verify_binding(
+    group_key: &[u8; 32],
+    capsule: &[u8; 32],
+    target_epoch: u64,
+    binding: &[u8; 64],
+) -> Result<()> {
+    let r = point(capsule)?;
+    let c = scalar(&binding[..32])?;
+    let z = scalar(&binding[32..])?;
+    let a = (EdwardsPoint::mul_base(&z) - c * r).compress().to_bytes();
+    if binding_challenge(group_key, capsule, target_epoch, &a) != c {
+        return Err(CryptoError::SignatureVerification);
+    }
+    Ok(())
+}
+
+/// Seal `plaintext` to `group_key` until `target_epoch`.
+///
+/// # Errors
+///
+/// - [`CryptoError::InvalidInput`] if `group_key` is not a valid point
+pub fn seal(group_key: &[u8; 32], target_epoch: u64, plaintext: &[u8]) -> Result<Sealed> {
+    let y = point(group_key)?;
+    let r = random_scalar();
+    let capsule = EdwardsPoint::mul_base(&r).compress().to_bytes();
+    let key = aead_key(&(r * y), &capsule, group_key, target_epoch);
+    // Each capsule yields a fresh key, so a fixed nonce is never reused.
+    let ciphertext = chacha20::encrypt(&key, &[0u8; 12], plaintext, &[])?;
+
+    let k = random_scalar();
+    let a = EdwardsPoint::mul_base(&k).compress().to_bytes();
+    let c = binding_challenge(group_key, &capsule, target_epoch, &a);
+    let mut binding = [0u8; 64];
+    binding[..32].copy_from_slice(c.as_bytes());
+    binding[32..].copy_from_slice((k + c * r).as_bytes());
+    Ok(Sealed {
+        target_epoch,
+        capsule,
+        binding,
+        ciphertext,
+    })
+}
+
+/// A member's decryption share for `capsule`, with its DLEQ proof.
+///
+/// The capsule's `binding` must prove it was sealed to this member's group
+/// key for `target_epoch`; the caller checks that epoch has begun.
+///
+/// # Errors
+///
+/// - [`CryptoError::InvalidInput`] if `capsule` is not a valid point
+/// - [`CryptoError::SignatureVerification`] if `binding` does not hold for
+///   `target_epoch`
+/// - [`CryptoError::Frost`] if the key package cannot be read
+pub fn decryption_share(
+    key_package: &FrostKeyPackage,
+    capsule: &[u8; 32],
+    target_epoch: u64,
+    binding: &[u8; 64],
+) -> Result<DecryptionShare> {
+    let group_key: [u8; 32] = key_package
+        .inner
+        .verifying_key()
+        .serialize()
+        .map_err(|e| CryptoError::Frost(e.to_string()))?
+        .try_into()
+        .map_err(|_| CryptoError::Frost("group key is not 32 bytes".into()))?;
+    verify_binding(&group_key, capsule, target_epoch, binding)?;
+    let r = point(capsule)?;
+    let x_i = scalar(&key_package.inner.signing_share().serialize())?;
+    let y_i = key_package
+        .inner
+        .verifying_share()
+        .serialize()
+        .map_err(|e| CryptoError::Frost(e.to_string()))?;
+    let identifier: [u8; 32] = key_package
+        .inner
+        .identifier()
+        .serialize()
+        .try_into()
+        .map_err(|_| CryptoError::Frost("identifier is not 32 bytes".into()))?;
+
+    let d_i = (x_i * r).compress().to_bytes();
+    let k = random_scalar();
+    let a1 = EdwardsPoint::mul_base(&k).compress().to_bytes();
+    let a2 = (k * r).compress().to_bytes();
+    let c = challenge(&[b"timelock-dleq", &y_i, capsule, &d_i, &a1, &a2]);
+    let z = k + c * x_i;
+
+    let mut proof = [0u8; 64];
+    proof[..32].copy_from_slice(c.as_bytes());
+    proof[32..].copy_from_slice(z.as_bytes());
+    Ok(DecryptionShare {
+        identifier,
+        share: d_i,
+        proof,
+    })
+}
+
+/// Check a decryption share against the member's verifying share.
+///
+/// # Errors
+///
+/// - [`CryptoError::InvalidInput`] if a point or scalar is malformed
+/// - [`CryptoError::SignatureVerification`] if the proof does not hold
+pub fn verify_share(
+    verifying_share: &[u8; 32],
+    capsule: &[u8; 32],
+    share: &DecryptionShare,
+) -> Result<()> {
+    let y_i = point(verifying_share)?;
+    let r = point(capsule)?;
+    let d_i = point(&share.share)?;
+    let c = scalar(&share.proof[..32])?;
+    let z = scalar(&share.proof[32..])?;
+
+    let a1 = (EdwardsPoint::mul_base(&z) - c * y_i).compress().to_bytes();
+    let a2 = (z * r - c * d_i).compress().to_bytes();
+    let expected = challenge(&[
+        b"timelock-dleq",
+        verifying_share,
+        capsule,
+        &share.share,
+        &a1,
+        &a2,
+    ]);
+    if expected != c {
+        return Err(CryptoError::SignatureVerification);
+    }
+    Ok(())
+}
+
+/// Combine verified shares from distinct members into the release key
+/// `x·R`. The caller supplies at least the threshold number of shares.
+///
+/// # Errors
+///
+/// - [`CryptoError::InvalidInput`] if there are no shares, an identifier
+///   repeats, or a share is malformed
+pub fn combine(shares: &[DecryptionShare]) -> Result<[u8; 32]> {
+    if shares.is_empty() {
+        return Err(CryptoError::InvalidInput("no decryption shares".into()));
+    }
+    let ids = shares
+        .iter()
+        .map(|s| scalar(&s.identifier))
+        .collect::<Result<Vec<_>>>()?;
+    let mut total = EdwardsPoint::default();
+    for (i, share) in shares.iter().enumerate() {
+        let mut numerator = Scalar::ONE;
+        let mut denominator = Scalar::ONE;
+        for (j, id) in ids.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            if *id == ids[i] {
+                return Err(CryptoError::InvalidInput(
+                    "duplicate share identifier".into(),
+                ));
+            }
+            numerator *= id;
+            denominator *= id - ids[i];
+        }
+        total += numerator * denominator.invert() * point(&share.share)?;
+    }
+    Ok(total.compress().to_bytes())
+}
+
+/// Open a sealed value with the release key from [`combine`].
+///
+/// # Errors
+///
+/// - [`CryptoError::AeadDecryption`] if the key is wrong for this value,
+///   including a release for another capsule or epoch
+pub fn open(group_key: &[u8; 32], sealed: &Sealed, release_key: &[u8; 32]) -> Result<Vec<u8>> {
+    let shared = point(release_key).map_err(|_| CryptoError::AeadDecryption)?;
+    let key = aead_key(&shared, &sealed.capsule, group_key, sealed.target_epoch);
+    chacha20::decrypt(&key, &[0u8; 12], &sealed.ciphertext, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frost::{dkg, FrostPublicKeyPackage};
+
+    fn group_key(pubkeys: &FrostPublicKeyPackage) -> [u8; 32] {
+        pubkeys
+            .inner
+            .verifying_key()
+            .serialize()
+            .expect("group key")
+            .try_into()
+            .expect("32 bytes")
+    }
+
+    fn verifying_share(key_package: &FrostKeyPackage) -> [u8; 32] {
+        key_package
+            .inner
+            .verifying_share()
+            .serialize()
+            .expect("verifying share")
+            .try_into()
+            .expect("32 bytes")
+    }
+
+    fn share(key_package: &FrostKeyPackage, sealed: &Sealed) -> DecryptionShare {
+        decryption_share(
+            key_package,
+            &sealed.capsule,
+            sealed.target_epoch,
+            &sealed.binding,
+        )
+        .expect("share")
+    }
+
+    #[test]
+    fn test_threshold_release_opens_sealed_value() {
+        let (members, pubkeys) = dkg(5, 3).expect("dkg");
+        let y = group_key(&pubkeys);
+        let sealed = seal(&y, 42, b"ballot tally").expect("seal");
+        assert_eq!(
+            Sealed::from_bytes(&sealed.to_bytes()).expect("decode"),
+            sealed
+        );
+
+        // Any three members suffice.
+        for subset in [[0, 1, 2], [1, 3, 4], [0, 2, 4]] {
+            let shares: Vec<DecryptionShare> = subset
+                .iter()
+                .map(|&i| share(&members[i], &sealed))
+                .collect();
+            for (&i, share) in subset.iter().zip(&shares) {
+                verify_share(&verifying_share(&members[i]), &sealed.capsule, share)
+                    .expect("valid share");
+            }
+            let release = combine(&shares).expect("combine");
+            assert_eq!(open(&y, &sealed, &release).expect("open"), b"ballot tally");
+        }
+    }
+
+    #[test]
+    fn test_too_few_shares_do_not_open() {
+        let (members, pubkeys) = dkg(5, 3).expect("dkg");
+        let y = group_key(&pubkeys);
+        let sealed = seal(&y, 7, b"secret").expect("seal");
+        let shares: Vec<DecryptionShare> = members[..2].iter().map(|m| share(m, &sealed)).collect();
+        let release = combine(&shares).expect("combine");
+        assert!(open(&y, &sealed, &release).is_err());
+    }
+
+    #[test]
+    fn test_forged_share_fails_proof() {
+        let (members, pubkeys) = dkg(3, 2).expect("dkg");
+        let sealed = seal(&group_key(&pubkeys), 5, b"x").expect("seal");
+        let capsule = sealed.capsule;
+        let mut share = share(&members[0], &sealed);
+        verify_share(&verifying_share(&members[0]), &capsule, &share).expect("valid");
+
+        // Another member's verifying share does not match.
+        assert!(verify_share(&verifying_share(&members[1]), &capsule, &share).is_err());
+
+        share.share = EdwardsPoint::mul_base(&random_scalar())
+            .compress()
+            .to_bytes();
+        assert!(verify_share(&verifying_share(&members[0]), &capsule, &share).is_err());
+    }
+
+    #[test]
+    fn test_release_is_bound_to_epoch() {
+        let (members, pubkeys) = dkg(3, 2).expect("dkg");
+        let y = group_key(&pubkeys);
+        let sealed = seal(&y, 10, b"later").expect("seal");
+        let shares: Vec<DecryptionShare> = members[..2].iter().map(|m| share(m, &sealed)).collect();
+        let release = combine(&shares).expect("combine");
+
+        let mut relabelled = sealed.clone();
+        relabelled.target_epoch = 9;
+        assert!(open(&y, &relabelled, &release).is_err());
+        assert!(Sealed::from_bytes(&[0u8; 20]).is_err());
+    }
+
+    #[test]
+    fn test_capsule_is_bound_to_epoch() {
+        let (members, pubkeys) = dkg(3, 2).expect("dkg");
+        let y = group_key(&pubkeys);
+        let sealed = seal(&y, 1000, b"not yet").expect("seal");
+        sealed.verify_binding(&y).expect("binding");
+
+        // Presenting the capsule under an earlier epoch gets no share.
+        assert!(matches!(
+            decryption_share(&members[0], &sealed.capsule, 0, &sealed.binding),
+            Err(CryptoError::SignatureVerification)
+        ));
+        // Nor under another quorum's key.
+        let (_, other) = dkg(3, 2).expect("dkg");
+        assert!(sealed.verify_binding(&group_key(&other)).is_err());
+    }
+}
@@ -0,0 +1,611 @@
+//! Protocol upgrade verification and staged rollout (Section 17).
+//!
+//! An [`UpgradeManifest`] is accepted only if it is signed by at least
+//! [`UPGRADE_THRESHOLD`] of the [`KEYHOLDER_COUNT`] governance keyholders,
+//! names a version strictly newer than the running one, and respects the
+//! [`MIN_TIMELOCK_EPOCHS`] activation delay.
+//!
+//! ## Rollout
+//!
+//! The [`UpgradeStager`] persists its phase in `<dir>/state.json` so a
+//! rollout survives restarts:
+//!
+//! ```text
+//! Idle ──stage()──▶ Staged ──begin_apply()──▶ Trial ──confirm_boot()──▶ Idle
+//!                                               │
+//!                     boot failure / crash loop ▼
+//!                                           RolledBack
+//! ```
+//!
+//! Artifacts are written only after their BLAKE3 hash and size match the
+//! manifest entry for the platform. `begin_apply` keeps a copy of the
+//! running binary and installs the artifact over it; the trial is recorded
+//! only once the install has succeeded. If the new version fails to confirm
+//! within [`MAX_TRIAL_BOOTS`] boots, or the previous version comes back up
+//! while a trial is pending, the stager reports the rollback and the copy is
+//! restored with [`install`].
+
+use std::path::{Path, PathBuf};
+
+use ochra_types::governance::{Platform, UpgradeManifest};
+use serde::{Deserialize, Serialize};
+
+use crate::blake3;
+use crate::ed25519::{Signature, VerifyingKey};
+use crate::{CryptoError, Result};
+
+/// Number of governance keyholders.
+pub const KEYHOLDER_COUNT: usize = 5;
+
+/// Keyholder signatures required on an upgrade manifest.
+pub const UPGRADE_THRESHOLD: usize = 3;
+
+/// Minimum epochs between publication and activation (14 days).
+pub const MIN_TIMELOCK_EPOCHS: u64 = 14;
+
+/// Boots a new version gets to confirm before it is rolled back.
+pub const MAX_TRIAL_BOOTS: u32 = 3;
+
+/// File in the staging directory holding the rollout phase.
+pub const STATE_FILE: &str = "state.json";
+
+/// File name of the saved copy of the previous binary.
+const PREVIOUS_BINARY: &str = "previous.bin";
+
+fn io_error(e: std::io::Error) -> CryptoError {
+    CryptoError::Upgrade(e.to_string())
+}
+
+/// Replace `target` with a copy of `source`.
+///
+/// The copy is written next to `target` with the target's permissions and
+/// renamed over it, so a crash leaves either the old or the new binary in
+/// place, never a partial one.
+pub fn install(source: &Path, target: &Path) -> Result<()> {
+    let Some(name) = target.file_name() else {
+        return Err(CryptoError::Upgrade(format!(
+            "cannot install over {}",
+            target.display()
+        )));
+    };
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(".installing");
+    let temp = target.with_file_name(temp_name);
+
+    let result = std::fs::copy(source, &temp)
+        .and_then(|_| match std::fs::metadata(target) {
+            Ok(meta) => std::fs::set_permissions(&temp, meta.permissions()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        })
+        .and_then(|()| std::fs::rename(&temp, target));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result.map_err(io_error)
+}
+
+/// DHT key of the manifest for `version`: `BLAKE3::hash("upgrade" || version)`.
+pub fn manifest_dht_key(version: &str) -> [u8; 32] {
+    let mut input = Vec::with_capacity(7 + version.len());
+    input.extend_from_slice(b"upgrade");
+    input.extend_from_slice(version.as_bytes());
+    blake3::hash(&input)
+}
+
+/// Bytes each keyholder signs: the manifest with `multisig_sigs` cleared.
+pub fn manifest_signing_message(manifest: &UpgradeManifest) -> Result<Vec<u8>> {
+    let unsigned = UpgradeManifest {
+        multisig_sigs: Vec::new(),
+        ..manifest.clone()
+    };
+    let body =
+        serde_json::to_vec(&unsigned).map_err(|e| CryptoError::Serialization(e.to_string()))?;
+    let mut msg = Vec::with_capacity(16 + body.len());
+    msg.extend_from_slice(b"upgrade-manifest");
+    msg.extend_from_slice(&body);
+    Ok(msg)
+}
+
+/// Parse a `major.minor.patch` version string.
+pub fn parse_version(version: &str) -> Result<(u64, u64, u64)> {
+    let invalid = || CryptoError::Upgrade(format!("invalid version {version:?}"));
+    let mut parts = version
+        .split('.')
+        .map(|p| p.parse::<u64>().map_err(|_| invalid()));
+    let (Some(major), Some(minor), Some(patch), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    Ok((major?, minor?, patch?))
+}
+
+/// Verify a manifest's keyholder multisig and version monotonicity.
+///
+/// # Errors
+///
+/// - [`CryptoError::Upgrade`] if the version does not parse or is not newer
+///   than `installed_version`, a signature names an unknown or repeated
+///   keyholder, or fewer than [`UPGRADE_THRESHOLD`] signatures verify
+pub fn verify_manifest(
+    manifest: &UpgradeManifest,
+    keyholders: &[[u8; 32]; KEYHOLDER_COUNT],
+    installed_version: &str,
+) -> Result<()> {
+    if parse_version(&manifest.version)? <= parse_version(installed_version)? {
+        return Err(CryptoError::Upgrade(format!(
+            "version {} is not newer than installed {installed_version}",
+            manifest.version
+        )));
+    }
+
+    let msg = manifest_signing_message(manifest)?;
+    let mut seen = [false; KEYHOLDER_COUNT];
+    let mut valid = 0;
+    for entry in &manifest.multisig_sigs {
+        let index = usize::from(entry.keyholder_index);
+        let Some(key) = keyholders.get(index) else {
+            return Err(CryptoError::Upgrade(format!(
+                "unknown keyholder index {index}"
+            )));
+        };
+        if std::mem::replace(&mut seen[index], true) {
+            return Err(CryptoError::Upgrade(format!(
+                "duplicate signature from keyholder {index}"
+            )));
+        }
+        let vk = VerifyingKey::from_bytes(key)?;
+        if vk.verify(&msg, &Signature::from_bytes(&entry.sig)).is_ok() {
+            valid += 1;
+        }
+    }
+    if valid < UPGRADE_THRESHOLD {
+        return Err(CryptoError::Upgrade(format!(
+            "{valid} valid keyholder signatures, {UPGRADE_THRESHOLD} required"
+        )));
+    }
+    Ok(())
+}
+
+/// Check the activation epoch honours the minimum timelock.
+pub fn check_timelock(manifest: &UpgradeManifest, published_epoch: u64) -> Result<()> {
+    let earliest = published_epoch.saturating_add(MIN_TIMELOCK_EPOCHS);
+    if manifest.activation_epoch < earliest {
+        return Err(CryptoError::Upgrade(format!(
+            "activation epoch {} is before the timelock ends at {earliest}",
+            manifest.activation_epoch
+        )));
+    }
+    Ok(())
+}
+
+/// An artifact that passed hash verification and is waiting to activate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StagedUpgrade {
+    pub version: String,
+    pub activation_epoch: u64,
+    pub is_mandatory: bool,
+    /// Path of the verified binary.
+    pub artifact: PathBuf,
+}
+
+/// Where a rollout currently stands.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum UpgradePhase {
+    /// Nothing pending.
+    #[default]
+    Idle,
+    /// A verified artifact is waiting for its activation epoch.
+    Staged(StagedUpgrade),
+    /// The new version has been swapped in and must confirm a good boot.
+    Trial {
+        upgrade: StagedUpgrade,
+        previous_version: String,
+        boot_attempts: u32,
+    },
+    /// The last trial failed and the previous binary was restored.
+    RolledBack { version: String, reason: String },
+}
+
+/// What the daemon must do after [`UpgradeStager::on_boot`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BootOutcome {
+    /// No rollout in progress.
+    Normal,
+    /// Running a trial of a new version; call
+    /// [`UpgradeStager::confirm_boot`] once startup succeeds.
+    Trial,
+    /// The trial exceeded its boot budget; reinstall `previous_binary` and
+    /// restart.
+    RollbackRequired { previous_binary: PathBuf },
+    /// The previous version is running again after a failed trial.
+    RolledBack { version: String },
+}
+
+/// Stages verified upgrade artifacts and drives apply/rollback across restarts.
+#[derive(Debug)]
+pub struct UpgradeStager {
+    dir: PathBuf,
+    phase: UpgradePhase,
+}
+
+impl UpgradeStager {
+    /// Open the staging directory, restoring the persisted phase.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(io_error)?;
+        let path = dir.join(STATE_FILE);
+        let phase = if path.exists() {
+            let raw = std::fs::read(&path).map_err(io_error)?;
+            serde_json::from_slice(&raw).map_err(|e| CryptoError::Serialization(e.to_string()))?
+        } else {
+            UpgradePhase::Idle
+        };
+        Ok(Self { dir, phase })
+    }
+
+    /// Current rollout phase.
+    pub fn phase(&self) -> &UpgradePhase {
+        &self.phase
+    }
+
+    fn set_phase(&mut self, phase: UpgradePhase) -> Result<()> {
+        let raw =
+            serde_json::to_vec(&phase).map_err(|e| CryptoError::Serialization(e.to_string()))?;
+        std::fs::write(self.dir.join(STATE_FILE), raw).map_err(io_error)?;
+        self.phase = phase;
+        Ok(())
+    }
+
+    fn artifact_path(&self, version: &str) -> PathBuf {
+        self.dir.join(version).join("ochra-daemon.bin")
+    }
+
+    /// Verify `artifact` against the manifest's hash for `platform` and stage it.
+    ///
+    /// The manifest must already have passed [`verify_manifest`]. A newer
+    /// manifest replaces an older staged one.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Upgrade`] if the manifest has no entry for
+    ///   `platform`, the size or hash differs, a trial is in progress, or a
+    ///   newer version is already staged
+    pub fn stage(
+        &mut self,
+        manifest: &UpgradeManifest,
+        platform: &Platform,
+        artifact: &[u8],
+    ) -> Result<StagedUpgrade> {
+        let Some(expected) = manifest
+            .platform_hashes
+            .iter()
+            .find(|h| &h.platform == platform)
+        else {
+            return Err(CryptoError::Upgrade(format!(
+                "manifest {} has no {platform:?} artifact",
+                manifest.version
+            )));
+        };
+        if artifact.len() as u64 != expected.size_bytes {
+            return Err(CryptoError::Upgrade(format!(
+                "artifact is {} bytes, manifest pins {}",
+                artifact.len(),
+                expected.size_bytes
+            )));
+        }
+        if blake3::hash(artifact) != expected.blake3_hash {
+            return Err(CryptoError::Upgrade("artifact hash mismatch".to_string()));
+        }
+
+        match &self.phase {
+            UpgradePhase::Trial { upgrade, .. } => {
+                return Err(CryptoError::Upgrade(format!(
+                    "trial of {} still in progress",
+                    upgrade.version
+                )));
+            }
+            UpgradePhase::Staged(staged)
+                if parse_version(&staged.version)? >= parse_version(&manifest.version)? =>
+            {
+                return Err(CryptoError::Upgrade(format!(
+                    "{} already staged",
+                    staged.version
+                )));
+            }
+            UpgradePhase::Staged(staged) => {
+                let old = staged.artifact.clone();
+                if let Some(parent) = old.parent() {
+                    let _ = std::fs::remove_dir_all(parent);
+                }
+            }
+            UpgradePhase::Idle | UpgradePhase::RolledBack { .. } => {}
+        }
+
+        let path = self.artifact_path(&manifest.version);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        std::fs::write(&path, artifact).map_err(io_error)?;
+        let staged = StagedUpgrade {
+            version: manifest.version.clone(),
+            activation_epoch: manifest.activation_epoch,
+            is_mandatory: manifest.is_mandatory,
+            artifact: path,
+        };
+        self.set_phase(UpgradePhase::Staged(staged.clone()))?;
+        Ok(staged)
+    }
+
+    /// The staged upgrade, if its activation epoch has been reached.
+    pub fn ready(&self, current_epoch: u64) -> Option<&StagedUpgrade> {
+        match &self.phase {
+            UpgradePhase::Staged(staged) if current_epoch >= staged.activation_epoch => {
+                Some(staged)
+            }
+            _ => None,
+        }
+    }
+
+    /// Install the staged upgrade over `running_binary` and start its trial.
+    ///
+    /// Saves a copy of `running_binary` for rollback first. The trial is
+    /// recorded only after the install succeeds; on failure the phase stays
+    /// `Staged` and `running_binary` is left as it was. The new version runs
+    /// from the next restart.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Upgrade`] if nothing is ready at `current_epoch`, or
+    ///   the running binary could not be saved or replaced
+    pub fn begin_apply(
+        &mut self,
+        running_version: &str,
+        running_binary: &Path,
+        current_epoch: u64,
+    ) -> Result<()> {
+        let Some(staged) = self.ready(current_epoch).cloned() else {
+            return Err(CryptoError::Upgrade(
+                "no upgrade ready to apply".to_string(),
+            ));
+        };
+        let previous = self.dir.join(PREVIOUS_BINARY);
+        std::fs::copy(running_binary, &previous).map_err(io_error)?;
+        install(&staged.artifact, running_binary)?;
+        let trial = UpgradePhase::Trial {
+            upgrade: staged,
+            previous_version: running_version.to_string(),
+            boot_attempts: 0,
+        };
+        if let Err(e) = self.set_phase(trial) {
+            // Without a recorded trial nothing would roll the install back.
+            install(&previous, running_binary)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Record a boot of `running_version`. Call early in startup.
+    pub fn on_boot(&mut self, running_version: &str) -> Result<BootOutcome> {
+        let UpgradePhase::Trial {
+            upgrade,
+            previous_version,
+            boot_attempts,
+        } = self.phase.clone()
+        else {
+            return Ok(BootOutcome::Normal);
+        };
+
+        if running_version != upgrade.version {
+            // The launcher fell back to the old binary.
+            self.set_phase(UpgradePhase::RolledBack {
+                version: upgrade.version,
+                reason: format!("{previous_version} booted during trial"),
+            })?;
+            return Ok(BootOutcome::RolledBack {
+                version: previous_version,
+            });
+        }
+
+        let boot_attempts = boot_attempts.saturating_add(1);
+        if boot_attempts > MAX_TRIAL_BOOTS {
+            self.set_phase(UpgradePhase::RolledBack {
+                version: upgrade.version,
+                reason: format!("failed to confirm within {MAX_TRIAL_BOOTS} boots"),
+            })?;
+            return Ok(BootOutcome::RollbackRequired {
+                previous_binary: self.dir.join(PREVIOUS_BINARY),
+            });
+        }
+        self.set_phase(UpgradePhase::Trial {
+            upgrade,
+            previous_version,
+            boot_attempts,
+        })?;
+        Ok(BootOutcome::Trial)
+    }
+
+    /// Confirm the trial version started cleanly and discard rollback state.
+    pub fn confirm_boot(&mut self) -> Result<()> {
+        let UpgradePhase::Trial { upgrade, .. } = &self.phase else {
+            return Ok(());
+        };
+        if let Some(parent) = upgrade.artifact.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+        let _ = std::fs::remove_file(self.dir.join(PREVIOUS_BINARY));
+        self.set_phase(UpgradePhase::Idle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ed25519::SigningKey;
+    use ochra_types::governance::{MultisigEntry, PlatformHash};
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ochra-upgrade-{}", rand::random::<u64>()))
+    }
+
+    fn keyholders() -> [SigningKey; KEYHOLDER_COUNT] {
+        std::array::from_fn(|_| SigningKey::generate())
+    }
+
+    fn public_keys(keys: &[SigningKey; KEYHOLDER_COUNT]) -> [[u8; 32]; KEYHOLDER_COUNT] {
+        std::array::from_fn(|i| keys[i].verifying_key().to_bytes())
+    }
+
+    fn manifest(version: &str, artifact: &[u8]) -> UpgradeManifest {
+        UpgradeManifest {
+            version: version.to_string(),
+            activation_epoch: 100,
+            platform_hashes: vec![PlatformHash {
+                platform: Platform::LinuxX86_64,
+                blake3_hash: blake3::hash(artifact),
+                size_bytes: artifact.len() as u64,
+            }],
+            changelog_url: None,
+            is_mandatory: false,
+            multisig_sigs: Vec::new(),
+            published_at: 0,
+            circuit_artifacts: Vec::new(),
+            revoked_circuits: Vec::new(),
+        }
+    }
+
+    fn sign(manifest: &mut UpgradeManifest, keys: &[SigningKey], indices: &[u8]) {
+        let msg = manifest_signing_message(manifest).expect("message");
+        manifest.multisig_sigs = indices
+            .iter()
+            .map(|&i| MultisigEntry {
+                keyholder_index: i,
+                sig: keys[usize::from(i)].sign(&msg).to_bytes(),
+            })
+            .collect();
+    }
+
+    #[test]
+    fn test_multisig_threshold_and_monotonicity() {
+        let keys = keyholders();
+        let pks = public_keys(&keys);
+        let mut m = manifest("1.2.0", b"binary");
+
+        sign(&mut m, &keys, &[0, 3]);
+        assert!(verify_manifest(&m, &pks, "1.1.9").is_err());
+
+        sign(&mut m, &keys, &[0, 3, 4]);
+        verify_manifest(&m, &pks, "1.1.9").expect("3-of-5 verifies");
+        assert!(verify_manifest(&m, &pks, "1.2.0").is_err());
+        assert!(verify_manifest(&m, &pks, "1.10.0").is_err());
+
+        sign(&mut m, &keys, &[0, 0, 3]);
+        assert!(verify_manifest(&m, &pks, "1.1.9").is_err());
+    }
+
+    #[test]
+    fn test_tampered_manifest_fails_signature() {
+        let keys = keyholders();
+        let pks = public_keys(&keys);
+        let mut m = manifest("2.0.0", b"binary");
+        sign(&mut m, &keys, &[1, 2, 3]);
+        m.is_mandatory = true;
+        assert!(verify_manifest(&m, &pks, "1.0.0").is_err());
+    }
+
+    #[test]
+    fn test_timelock_and_version_parsing() {
+        let m = manifest("1.0.1", b"x");
+        check_timelock(&m, 86).expect("14 epochs");
+        assert!(check_timelock(&m, 87).is_err());
+        assert_eq!(parse_version("5.2.0").expect("parse"), (5, 2, 0));
+        assert!(parse_version("5.2").is_err());
+        assert!(parse_version("5.2.0.1").is_err());
+        assert_ne!(manifest_dht_key("5.2.0"), manifest_dht_key("5.2.1"));
+    }
+
+    #[test]
+    fn test_stage_checks_artifact_hash() {
+        let dir = temp_dir();
+        let mut stager = UpgradeStager::open(&dir).expect("open");
+        let m = manifest("1.1.0", b"good binary");
+
+        assert!(stager
+            .stage(&m, &Platform::LinuxX86_64, b"evil binary")
+            .is_err());
+        assert!(stager
+            .stage(&m, &Platform::WindowsX86_64, b"good binary")
+            .is_err());
+        stager
+            .stage(&m, &Platform::LinuxX86_64, b"good binary")
+            .expect("stage");
+
+        assert!(stager.ready(99).is_none());
+        assert_eq!(stager.ready(100).map(|s| s.version.as_str()), Some("1.1.0"));
+
+        let reopened = UpgradeStager::open(&dir).expect("reopen");
+        assert!(matches!(reopened.phase(), UpgradePhase::Staged(_)));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_trial_confirm_and_rollback() {
+        let dir = temp_dir();
+        let running = dir.join("running.bin");
+        std::fs::create_dir_all(&dir).expect("mkdir");
+        std::fs::write(&running, b"old binary").expect("write");
+
+        let mut stager = UpgradeStager::open(dir.join("upgrades")).expect("open");
+        let m = manifest("1.1.0", b"new binary");
+        stager
+            .stage(&m, &Platform::LinuxX86_64, b"new binary")
+            .expect("stage");
+        stager.begin_apply("1.0.0", &running, 100).expect("apply");
+        assert_eq!(std::fs::read(&running).expect("read"), b"new binary");
+
+        // Crash loop: every boot fails before confirming.
+        for _ in 0..MAX_TRIAL_BOOTS {
+            assert_eq!(stager.on_boot("1.1.0").expect("boot"), BootOutcome::Trial);
+        }
+        let previous_binary = dir.join("upgrades").join(PREVIOUS_BINARY);
+        assert_eq!(
+            stager.on_boot("1.1.0").expect("boot"),
+            BootOutcome::RollbackRequired {
+                previous_binary: previous_binary.clone()
+            }
+        );
+        install(&previous_binary, &running).expect("restore");
+        assert_eq!(std::fs::read(&running).expect("read"), b"old binary");
+        assert!(matches!(stager.phase(), UpgradePhase::RolledBack { .. }));
+
+        // A later upgrade that boots cleanly is committed.
+        let m2 = manifest("1.2.0", b"fixed binary");
+        stager
+            .stage(&m2, &Platform::LinuxX86_64, b"fixed binary")
+            .expect("stage");
+        stager.begin_apply("1.0.0", &running, 100).expect("apply");
+        assert_eq!(stager.on_boot("1.2.0").expect("boot"), BootOutcome::Trial);
+        stager.confirm_boot().expect("confirm");
+        assert_eq!(stager.phase(), &UpgradePhase::Idle);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_failed_apply_stays_staged() {
+        let dir = temp_dir();
+        let mut stager = UpgradeStager::open(dir.join("upgrades")).expect("open");
+        let m = manifest("1.1.0", b"new binary");
+        stager
+            .stage(&m, &Platform::LinuxX86_64, b"new binary")
+            .expect("stage");
+
+        let missing = dir.join("missing").join("running.bin");
+        assert!(stager.begin_apply("1.0.0", &missing, 100).is_err());
+        assert!(matches!(stager.phase(), UpgradePhase::Staged(_)));
+        assert!(!missing.exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
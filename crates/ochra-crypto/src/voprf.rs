@@ -9,13 +9,44 @@
 //! 1. Client blinds input: `(blinded_element, blind_state) = blind(input)`
 //! 2. Server evaluates: `evaluated = evaluate(server_key, blinded_element)`
 //! 3. Client finalizes: `output = finalize(blind_state, evaluated)`
+//!
+//! The output depends only on the input and the key, so whoever holds the
+//! key can recompute it with [`evaluate_direct`] when the token is redeemed.
+//!
+//! ## Batching
+//!
+//! Minting many tokens at once uses the batched variants, which carry a
+//! vector of elements in one round trip and a single proof covering the
+//! whole batch (RFC 9497 Section 2.2.1 composite proof):
+//!
+//! 1. `(blinded, states) = blind_batch(inputs)`
+//! 2. `(evaluated, proof) = server_key.evaluate_batch(blinded)`
+//! 3. `outputs = finalize_batch(states, blinded, evaluated, proof, public_key)`
+//!
+//! ```text
+//! P      = from_uniform(BLAKE3-XOF-512(enc("voprf-hash-to-group", input)))
+//! B      = r·P,  Z = k·B,  N = r⁻¹·Z = k·P
+//! output = BLAKE3::hash(enc("voprf-output", input, N))
+//! d_i    = BLAKE3-XOF-512(enc("voprf-composite", pk, LE32(n), B_1, Z_1, …, LE32(i))) mod ℓ
+//! M      = Σ d_i·B_i,  Z = Σ d_i·Z_i
+//! c      = BLAKE3-XOF-512(enc("voprf-dleq", pk, M, Z, t·G, t·M)) mod ℓ
+//! proof  = c || (t − c·k)
+//! ```
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, IsIdentity};
 
 use crate::{CryptoError, Result};
 
+/// Maximum number of elements in one batched evaluation.
+pub const MAX_BATCH_SIZE: usize = 256;
+
 /// A VOPRF server key.
 #[derive(Clone)]
 pub struct VoprfServerKey {
-    /// The raw key bytes.
+    /// The canonical encoding of the key scalar.
     key_bytes: Vec<u8>,
 }
 
@@ -37,6 +68,12 @@ pub struct EvaluatedElement {
     pub bytes: Vec<u8>,
 }
 
+/// Proof that every element of a batch was evaluated under the same key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchProof {
+    pub bytes: Vec<u8>,
+}
+
 /// The final VOPRF output.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct VoprfOutput {
@@ -46,21 +83,32 @@ pub struct VoprfOutput {
 impl VoprfServerKey {
     /// Generate a new random server key.
     pub fn generate() -> Result<Self> {
-        let mut key_bytes = vec![0u8; 32];
-        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut key_bytes);
-        Ok(Self { key_bytes })
+        Ok(Self {
+            key_bytes: random_scalar().to_bytes().to_vec(),
+        })
     }
 
     /// Create a server key from raw bytes.
+    ///
+    /// The bytes are reduced modulo the group order.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::InvalidKeyLength`] if `bytes` is not 32 bytes
+    /// - [`CryptoError::Voprf`] if the key reduces to zero
     pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        if bytes.len() != 32 {
-            return Err(CryptoError::InvalidKeyLength {
+        let raw: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| CryptoError::InvalidKeyLength {
                 expected: 32,
                 actual: bytes.len(),
-            });
+            })?;
+        let key = Scalar::from_bytes_mod_order(raw);
+        if key == Scalar::ZERO {
+            return Err(CryptoError::Voprf("zero server key".into()));
         }
         Ok(Self {
-            key_bytes: bytes.to_vec(),
+            key_bytes: key.to_bytes().to_vec(),
         })
     }
 
@@ -69,19 +117,64 @@ impl VoprfServerKey {
         &self.key_bytes
     }
 
+    fn scalar(&self) -> Scalar {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&self.key_bytes);
+        Scalar::from_bytes_mod_order(bytes)
+    }
+
+    /// Get the public key `k·G` clients verify batch proofs against.
+    pub fn public_key(&self) -> [u8; 32] {
+        (self.scalar() * RISTRETTO_BASEPOINT_POINT)
+            .compress()
+            .to_bytes()
+    }
+
+    /// Evaluate a batch of blinded elements with a single proof.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Voprf`] if the batch is empty or larger than
+    ///   [`MAX_BATCH_SIZE`], or an element is not a valid group element
+    pub fn evaluate_batch(
+        &self,
+        blinded: &[BlindedElement],
+    ) -> Result<(Vec<EvaluatedElement>, BatchProof)> {
+        check_batch_size(blinded.len())?;
+        let evaluated = blinded
+            .iter()
+            .map(|b| self.evaluate(b))
+            .collect::<Result<Vec<_>>>()?;
+        let public_key = self.public_key();
+        let (m, z) = composite(&public_key, blinded, &evaluated)?;
+        let k = self.scalar();
+        let t = random_scalar();
+        let c = dleq_challenge(
+            &public_key,
+            &m,
+            &z,
+            &(t * RISTRETTO_BASEPOINT_POINT),
+            &(t * m),
+        );
+        let s = t - c * k;
+        let mut bytes = Vec::with_capacity(64);
+        bytes.extend_from_slice(c.as_bytes());
+        bytes.extend_from_slice(s.as_bytes());
+        Ok((evaluated, BatchProof { bytes }))
+    }
+
     /// Evaluate a blinded element.
     ///
     /// The server computes `evaluated = key * blinded_element` without
     /// learning the client's input.
+    ///
+    /// # Errors
+    ///
+    /// - [`CryptoError::Voprf`] if the element is not a valid group element
     pub fn evaluate(&self, blinded: &BlindedElement) -> Result<EvaluatedElement> {
-        // Simplified evaluation: BLAKE3-based PRF evaluation
-        // In production, this uses Ristretto255 scalar multiplication
-        let mut input = Vec::with_capacity(self.key_bytes.len() + blinded.bytes.len());
-        input.extend_from_slice(&self.key_bytes);
-        input.extend_from_slice(&blinded.bytes);
-        let result = crate::blake3::hash(&input);
+        let element = element(&blinded.bytes)?;
         Ok(EvaluatedElement {
-            bytes: result.to_vec(),
+            bytes: (self.scalar() * element).compress().to_bytes().to_vec(),
         })
     }
 }
@@ -91,24 +184,15 @@ impl VoprfServerKey {
 /// Returns the blinded element to send to the server and the blind state
 /// needed for finalization.
 pub fn blind(input: &[u8]) -> Result<(BlindedElement, BlindState)> {
-    // Generate random blind
-    let mut blind_bytes = vec![0u8; 32];
-    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut blind_bytes);
-
-    // Blind the input: H(input) * blind
-    // Simplified: BLAKE3(blind || input) as the blinded element
-    let mut blinded_input = Vec::with_capacity(blind_bytes.len() + input.len());
-    blinded_input.extend_from_slice(&blind_bytes);
-    blinded_input.extend_from_slice(input);
-    let blinded_hash = crate::blake3::hash(&blinded_input);
-
+    let r = random_scalar();
+    let blinded = r * hash_to_group(input);
     Ok((
         BlindedElement {
-            bytes: blinded_hash.to_vec(),
+            bytes: blinded.compress().to_bytes().to_vec(),
         },
         BlindState {
             input: input.to_vec(),
-            blind_bytes,
+            blind_bytes: r.to_bytes().to_vec(),
         },
     ))
 }
@@ -116,31 +200,229 @@ pub fn blind(input: &[u8]) -> Result<(BlindedElement, BlindState)> {
 /// Client-side: finalize the VOPRF output after receiving the server's evaluation.
 ///
 /// Removes the blinding factor and produces the final PRF output.
+///
+/// # Errors
+///
+/// - [`CryptoError::Voprf`] if the blind state or evaluated element is
+///   malformed
 pub fn finalize(state: &BlindState, evaluated: &EvaluatedElement) -> Result<VoprfOutput> {
-    // Unblind: combine evaluated element with blind state
-    // Simplified: BLAKE3(evaluated || blind_state)
-    let mut input = Vec::with_capacity(evaluated.bytes.len() + state.blind_bytes.len());
-    input.extend_from_slice(&evaluated.bytes);
-    input.extend_from_slice(&state.blind_bytes);
-    let output = crate::blake3::hash(&input);
+    let r: [u8; 32] = state
+        .blind_bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| CryptoError::Voprf("malformed blind state".into()))?;
+    let r = Option::<Scalar>::from(Scalar::from_canonical_bytes(r))
+        .filter(|r| *r != Scalar::ZERO)
+        .ok_or_else(|| CryptoError::Voprf("malformed blind state".into()))?;
+    let unblinded = r.invert() * element(&evaluated.bytes)?;
+    Ok(output(&state.input, &unblinded))
+}
+
+/// Client-side: blind a batch of inputs.
+///
+/// Each input gets its own independent blind.
+///
+/// # Errors
+///
+/// - [`CryptoError::Voprf`] if the batch is empty or larger than
+///   [`MAX_BATCH_SIZE`]
+pub fn blind_batch(inputs: &[&[u8]]) -> Result<(Vec<BlindedElement>, Vec<BlindState>)> {
+    check_batch_size(inputs.len())?;
+    let mut blinded = Vec::with_capacity(inputs.len());
+    let mut states = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let (element, state) = blind(input)?;
+        blinded.push(element);
+        states.push(state);
+    }
+    Ok((blinded, states))
+}
+
+/// Client-side: verify a batch proof and finalize every element.
+///
+/// `blinded` must be the elements the client sent, in order; the proof
+/// binds each evaluation to its blinded element so a reordered or
+/// substituted response is rejected as a whole.
+///
+/// # Errors
+///
+/// - [`CryptoError::Voprf`] if the lengths disagree or the proof does not
+///   verify against `public_key`
+pub fn finalize_batch(
+    states: &[BlindState],
+    blinded: &[BlindedElement],
+    evaluated: &[EvaluatedElement],
+    proof: &BatchProof,
+    public_key: &[u8; 32],
+) -> Result<Vec<VoprfOutput>> {
+    if states.len() != blinded.len() || blinded.len() != evaluated.len() {
+        return Err(CryptoError::Voprf(format!(
+            "batch length mismatch: {} states, {} blinded, {} evaluated",
+            states.len(),
+            blinded.len(),
+            evaluated.len()
+        )));
+    }
+    verify_batch_proof(blinded, evaluated, proof, public_key)?;
+    states
+        .iter()
+        .zip(evaluated)
+        .map(|(state, eval)| finalize(state, eval))
+        .collect()
+}
+
+/// Client-side: verify a batch proof without finalizing.
+///
+/// # Errors
+///
+/// - [`CryptoError::Voprf`] if the lengths disagree, the batch size is out
+///   of range, or the proof does not verify against `public_key`
+pub fn verify_batch_proof(
+    blinded: &[BlindedElement],
+    evaluated: &[EvaluatedElement],
+    proof: &BatchProof,
+    public_key: &[u8; 32],
+) -> Result<()> {
+    if blinded.len() != evaluated.len() {
+        return Err(CryptoError::Voprf(format!(
+            "batch length mismatch: {} blinded, {} evaluated",
+            blinded.len(),
+            evaluated.len()
+        )));
+    }
+    check_batch_size(blinded.len())?;
+    let (m, z) = composite(public_key, blinded, evaluated)?;
+    let (c, s) = proof_scalars(&proof.bytes)?;
+    let pk = element(public_key)?;
+    let a1 = s * RISTRETTO_BASEPOINT_POINT + c * pk;
+    let a2 = s * m + c * z;
+    if dleq_challenge(public_key, &m, &z, &a1, &a2) != c {
+        return Err(CryptoError::Voprf("batch proof verification failed".into()));
+    }
+    Ok(())
+}
+
+fn check_batch_size(len: usize) -> Result<()> {
+    if len == 0 || len > MAX_BATCH_SIZE {
+        return Err(CryptoError::Voprf(format!(
+            "batch size {len} outside 1..={MAX_BATCH_SIZE}"
+        )));
+    }
+    Ok(())
+}
+
+fn random_scalar() -> Scalar {
+    loop {
+        let mut wide = [0u8; 64];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut wide);
+        let scalar = Scalar::from_bytes_mod_order_wide(&wide);
+        if scalar != Scalar::ZERO {
+            return scalar;
+        }
+    }
+}
+
+fn hash_scalar(fields: &[&[u8]]) -> Scalar {
+    let mut wide = [0u8; 64];
+    crate::blake3::hash_xof(&crate::blake3::encode_multi_field(fields), &mut wide);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+fn hash_to_group(input: &[u8]) -> RistrettoPoint {
+    let mut wide = [0u8; 64];
+    crate::blake3::hash_xof(
+        &crate::blake3::encode_multi_field(&[b"voprf-hash-to-group", input]),
+        &mut wide,
+    );
+    RistrettoPoint::from_uniform_bytes(&wide)
+}
 
-    Ok(VoprfOutput {
-        bytes: output.to_vec(),
-    })
+/// Decode a non-identity group element.
+fn element(bytes: &[u8]) -> Result<RistrettoPoint> {
+    CompressedRistretto::from_slice(bytes)
+        .ok()
+        .and_then(|c| c.decompress())
+        .filter(|p| !p.is_identity())
+        .ok_or_else(|| CryptoError::Voprf("not a valid group element".into()))
+}
+
+fn output(input: &[u8], unblinded: &RistrettoPoint) -> VoprfOutput {
+    let encoded = crate::blake3::encode_multi_field(&[
+        b"voprf-output",
+        input,
+        unblinded.compress().as_bytes(),
+    ]);
+    VoprfOutput {
+        bytes: crate::blake3::hash(&encoded).to_vec(),
+    }
+}
+
+/// Fold a batch into one `(M, Z)` pair with weights bound to the whole
+/// transcript, so a proof over the pair covers every element.
+fn composite(
+    public_key: &[u8; 32],
+    blinded: &[BlindedElement],
+    evaluated: &[EvaluatedElement],
+) -> Result<(RistrettoPoint, RistrettoPoint)> {
+    let count = (blinded.len() as u32).to_le_bytes();
+    let mut fields: Vec<&[u8]> = Vec::with_capacity(4 + 2 * blinded.len());
+    fields.push(b"voprf-composite");
+    fields.push(public_key);
+    fields.push(&count);
+    for (b, e) in blinded.iter().zip(evaluated) {
+        fields.push(&b.bytes);
+        fields.push(&e.bytes);
+    }
+    let mut m = RistrettoPoint::identity();
+    let mut z = RistrettoPoint::identity();
+    for (i, (b, e)) in blinded.iter().zip(evaluated).enumerate() {
+        let index = (i as u32).to_le_bytes();
+        let mut weighted = fields.clone();
+        weighted.push(&index);
+        let d = hash_scalar(&weighted);
+        m += d * element(&b.bytes)?;
+        z += d * element(&e.bytes)?;
+    }
+    Ok((m, z))
 }
 
-/// Compute a VOPRF output directly (non-blind, for testing).
+fn dleq_challenge(
+    public_key: &[u8; 32],
+    m: &RistrettoPoint,
+    z: &RistrettoPoint,
+    a1: &RistrettoPoint,
+    a2: &RistrettoPoint,
+) -> Scalar {
+    hash_scalar(&[
+        b"voprf-dleq",
+        public_key,
+        m.compress().as_bytes(),
+        z.compress().as_bytes(),
+        a1.compress().as_bytes(),
+        a2.compress().as_bytes(),
+    ])
+}
+
+fn proof_scalars(bytes: &[u8]) -> Result<(Scalar, Scalar)> {
+    let malformed = || CryptoError::Voprf("malformed batch proof".into());
+    if bytes.len() != 64 {
+        return Err(malformed());
+    }
+    let mut c = [0u8; 32];
+    let mut s = [0u8; 32];
+    c.copy_from_slice(&bytes[..32]);
+    s.copy_from_slice(&bytes[32..]);
+    let c = Option::<Scalar>::from(Scalar::from_canonical_bytes(c)).ok_or_else(malformed)?;
+    let s = Option::<Scalar>::from(Scalar::from_canonical_bytes(s)).ok_or_else(malformed)?;
+    Ok((c, s))
+}
+
+/// Compute a VOPRF output directly from the input, without blinding.
 ///
-/// This is equivalent to running the full blind/evaluate/finalize protocol
-/// but without the privacy guarantees.
+/// Equals the output the client finalizes from a blinded evaluation of
+/// `input` under `key`, so the key holder uses it to check a redeemed token.
 pub fn evaluate_direct(key: &VoprfServerKey, input: &[u8]) -> Result<VoprfOutput> {
-    let mut combined = Vec::with_capacity(key.key_bytes.len() + input.len());
-    combined.extend_from_slice(&key.key_bytes);
-    combined.extend_from_slice(input);
-    let output = crate::blake3::hash(&combined);
-    Ok(VoprfOutput {
-        bytes: output.to_vec(),
-    })
+    Ok(output(input, &(key.scalar() * hash_to_group(input))))
 }
 
 #[cfg(test)]
@@ -193,6 +475,140 @@ mod tests {
         assert_eq!(key.to_bytes(), restored.to_bytes());
     }
 
+    #[test]
+    fn test_batch_matches_single_evaluation() {
+        let server_key = VoprfServerKey::generate().expect("generate");
+        let inputs: Vec<&[u8]> = vec![b"a", b"b", b"c"];
+        let (blinded, states) = blind_batch(&inputs).expect("blind_batch");
+        let (evaluated, proof) = server_key.evaluate_batch(&blinded).expect("evaluate");
+        let outputs = finalize_batch(
+            &states,
+            &blinded,
+            &evaluated,
+            &proof,
+            &server_key.public_key(),
+        )
+        .expect("finalize");
+
+        assert_eq!(outputs.len(), 3);
+        for ((b, s), out) in blinded.iter().zip(&states).zip(&outputs) {
+            let single = server_key.evaluate(b).expect("evaluate");
+            assert_eq!(&finalize(s, &single).expect("finalize"), out);
+        }
+    }
+
+    #[test]
+    fn test_batch_proof_rejects_tampering() {
+        let server_key = VoprfServerKey::generate().expect("generate");
+        let other_key = VoprfServerKey::generate().expect("generate");
+        let inputs: Vec<&[u8]> = vec![b"x", b"y"];
+        let (blinded, states) = blind_batch(&inputs).expect("blind_batch");
+        let (mut evaluated, proof) = server_key.evaluate_batch(&blinded).expect("evaluate");
+
+        let wrong_key = finalize_batch(
+            &states,
+            &blinded,
+            &evaluated,
+            &proof,
+            &other_key.public_key(),
+        );
+        assert!(wrong_key.is_err());
+
+        evaluated.swap(0, 1);
+        let reordered = finalize_batch(
+            &states,
+            &blinded,
+            &evaluated,
+            &proof,
+            &server_key.public_key(),
+        );
+        assert!(reordered.is_err());
+    }
+
+    #[test]
+    fn test_batch_size_limits() {
+        let server_key = VoprfServerKey::generate().expect("generate");
+        assert!(blind_batch(&[]).is_err());
+        assert!(server_key.evaluate_batch(&[]).is_err());
+
+        let too_many = vec![
+            BlindedElement {
+                bytes: vec![0u8; 32]
+            };
+            MAX_BATCH_SIZE + 1
+        ];
+        assert!(server_key.evaluate_batch(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_output_matches_direct_evaluation() {
+        let server_key = VoprfServerKey::generate().expect("generate");
+        let (blinded, state) = blind(b"token").expect("blind");
+        let evaluated = server_key.evaluate(&blinded).expect("evaluate");
+        let output = finalize(&state, &evaluated).expect("finalize");
+        assert_eq!(
+            output,
+            evaluate_direct(&server_key, b"token").expect("direct")
+        );
+
+        // Blinding the same input again gives an unrelated element.
+        let (again, _) = blind(b"token").expect("blind");
+        assert_ne!(again.bytes, blinded.bytes);
+    }
+
+    #[test]
+    fn test_batch_proof_rejects_other_key_evaluation() {
+        let server_key = VoprfServerKey::generate().expect("generate");
+        let other_key = VoprfServerKey::generate().expect("generate");
+        let inputs: Vec<&[u8]> = vec![b"x", b"y", b"z"];
+        let (blinded, states) = blind_batch(&inputs).expect("blind_batch");
+        let (mut evaluated, proof) = server_key.evaluate_batch(&blinded).expect("evaluate");
+
+        // One element evaluated under another key, keeping the proof.
+        evaluated[1] = other_key.evaluate(&blinded[1]).expect("evaluate");
+        assert!(finalize_batch(
+            &states,
+            &blinded,
+            &evaluated,
+            &proof,
+            &server_key.public_key()
+        )
+        .is_err());
+
+        // A proof made by a different key does not cover this batch.
+        let (other_evaluated, other_proof) = other_key.evaluate_batch(&blinded).expect("evaluate");
+        assert!(verify_batch_proof(
+            &blinded,
+            &other_evaluated,
+            &other_proof,
+            &server_key.public_key()
+        )
+        .is_err());
+        assert!(verify_batch_proof(
+            &blinded,
+            &other_evaluated,
+            &BatchProof {
+                bytes: vec![0u8; 64]
+            },
+            &other_key.public_key()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_invalid_elements_rejected() {
+        let server_key = VoprfServerKey::generate().expect("generate");
+        let identity = BlindedElement {
+            bytes: vec![0u8; 32],
+        };
+        assert!(server_key.evaluate(&identity).is_err());
+        let garbage = BlindedElement {
+            bytes: vec![0xFF; 32],
+        };
+        assert!(server_key.evaluate(&garbage).is_err());
+        assert!(VoprfServerKey::from_bytes(&[0u8; 32]).is_err());
+    }
+
     #[test]
     fn test_invalid_key_length() {
         let result = VoprfServerKey::from_bytes(&[0u8; 16]);
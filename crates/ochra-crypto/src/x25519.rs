@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
 use zeroize::Zeroize;
 
+use crate::secret::SecretBytes;
 use crate::Result;
 
 /// An X25519 static secret key (for long-lived keys).
@@ -24,10 +25,8 @@ pub struct X25519PublicKey {
 }
 
 /// An X25519 shared secret.
-#[derive(Zeroize)]
-#[zeroize(drop)]
 pub struct SharedSecret {
-    bytes: [u8; 32],
+    bytes: SecretBytes<32>,
 }
 
 impl X25519StaticSecret {
@@ -63,7 +62,7 @@ impl X25519StaticSecret {
         let pk = PublicKey::from(their_public.bytes);
         let shared = self.inner.diffie_hellman(&pk);
         SharedSecret {
-            bytes: *shared.as_bytes(),
+            bytes: SecretBytes::new(*shared.as_bytes()),
         }
     }
 }
@@ -88,7 +87,7 @@ impl X25519PublicKey {
 impl SharedSecret {
     /// Get the raw bytes of the shared secret.
     pub fn as_bytes(&self) -> &[u8; 32] {
-        &self.bytes
+        self.bytes.expose()
     }
 }
 
@@ -106,7 +105,7 @@ pub fn ephemeral_key_exchange(their_public: &X25519PublicKey) -> (X25519PublicKe
             bytes: public.to_bytes(),
         },
         SharedSecret {
-            bytes: *shared.as_bytes(),
+            bytes: SecretBytes::new(*shared.as_bytes()),
         },
     )
 }
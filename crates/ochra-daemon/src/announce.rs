@@ -0,0 +1,86 @@
+//! Provider announcement loop (Section 14.8).
+//!
+//! Keeps `ChunkAdvertise` messages and `chunk-loc` provider records fresh for
+//! every chunk this node can serve, re-announcing before the records expire,
+//! rarest chunks first, and backing off as upload bandwidth fills up.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_db::queries::downloads as db;
+use ochra_transport::messages::ChunkAdvertise;
+use tracing::debug;
+
+use crate::mode::Role;
+use crate::DaemonState;
+
+/// Interval between announcement passes.
+const ANNOUNCE_INTERVAL_SECS: u64 = 60;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Fraction of the upload limit currently in use.
+fn upload_utilization(_state: &DaemonState) -> f64 {
+    // Would: read upload byte counters from the transport layer
+    0.0
+}
+
+/// Track the chunks of every completed download.
+pub async fn seed_from_db(state: &Arc<DaemonState>) -> anyhow::Result<usize> {
+    let rows = {
+        let conn = state.db.lock().await;
+        db::list_by_status(&conn, db::STATUS_COMPLETE)?
+    };
+    // Would: also track published content and held ABR chunks
+    let mut announcer = state.announcer.lock().await;
+    for row in &rows {
+        announcer.track(row.leaves()?);
+    }
+    Ok(announcer.len())
+}
+
+/// Re-announce due chunks until shutdown or the storage role stops.
+///
+/// Nothing new is announced while the role drains.
+pub async fn run_announcer(state: Arc<DaemonState>, generation: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(ANNOUNCE_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                if !crate::mode::keep_running(&state, Role::Storage, generation).await {
+                    break;
+                }
+                if !crate::mode::accepts(&state, Role::Storage).await {
+                    continue;
+                }
+                let now = now_secs();
+                let utilization = upload_utilization(&state);
+                let mut announcer = state.announcer.lock().await;
+                let batch = announcer.next_batch(now, utilization);
+                if batch.is_empty() {
+                    continue;
+                }
+                let advertise = ChunkAdvertise {
+                    chunk_hashes: batch.clone(),
+                    ttl_secs: u32::try_from(announcer.ttl_secs()).unwrap_or(u32::MAX),
+                };
+                // Would: send the advertisement to DHT neighbours and add this
+                // node to each chunk-loc record over Sphinx
+                debug!(
+                    "Announcing {} chunks ({} tracked, utilization {:.2})",
+                    advertise.chunk_hashes.len(),
+                    announcer.len(),
+                    utilization
+                );
+                announcer.mark_announced(&batch, now);
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
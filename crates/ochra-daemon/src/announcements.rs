@@ -0,0 +1,320 @@
+//! Space announcements (Section 8.12).
+//!
+//! The owner's broadcast channel. Posts and pin changes are signed by the
+//! owner, sealed with the Space's MLS group and gossiped on the Space's
+//! announcement topic. Members store what they accept, track which posts
+//! have been read, and pull anything they missed from other members: when
+//! a gap appears, and every [`BACKFILL_SECS`] to catch up after being
+//! offline.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_db::queries::announcements::AnnouncementRow;
+use ochra_mls::announcement::{
+    Announcement, AnnouncementBackfillRequest, AnnouncementBackfillResponse, AnnouncementFeed,
+    AnnouncementOp, SignedAnnouncement,
+};
+use ochra_mls::group::MlsCiphertext;
+use ochra_transport::gossip::announcement_topic_id;
+use ochra_types::space::AnnouncementInfo;
+use ochra_types::GroupId;
+use rusqlite::Connection;
+use tracing::{debug, warn};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Seconds between backfill requests for every joined Space.
+const BACKFILL_SECS: u64 = 600;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The stored announcements of a Space, with when each was read.
+fn load(
+    db: &Connection,
+    group_id: &GroupId,
+) -> anyhow::Result<(AnnouncementFeed, HashMap<u64, Option<u64>>)> {
+    let mut feed = AnnouncementFeed::new(*group_id);
+    let mut read = HashMap::new();
+    for row in ochra_db::queries::announcements::list(db, group_id)? {
+        match SignedAnnouncement::from_bytes(&row.signed_announcement) {
+            Ok(signed) => {
+                feed.restore(signed)?;
+                read.insert(row.seq, row.read_at);
+            }
+            Err(e) => warn!("Skipping stored announcement {}: {}", row.seq, e),
+        }
+    }
+    Ok((feed, read))
+}
+
+fn store(
+    db: &Connection,
+    group_id: &GroupId,
+    signed: &SignedAnnouncement,
+    read_at: Option<u64>,
+) -> anyhow::Result<bool> {
+    Ok(ochra_db::queries::announcements::insert(
+        db,
+        group_id,
+        &AnnouncementRow {
+            seq: signed.announcement.seq,
+            signed_announcement: signed.to_bytes(),
+            is_post: matches!(signed.announcement.op, AnnouncementOp::Post { .. }),
+            received_at: now_secs(),
+            read_at,
+        },
+    )?)
+}
+
+fn emit(state: &DaemonState, signed: &SignedAnnouncement) {
+    let group_id = signed.announcement.group_id;
+    let event = match &signed.announcement.op {
+        AnnouncementOp::Post { title, pinned, .. } => DaemonEvent::AnnouncementReceived {
+            group_id,
+            seq: signed.announcement.seq,
+            title: title.clone(),
+            pinned: *pinned,
+        },
+        AnnouncementOp::SetPinned { target, pinned } => DaemonEvent::AnnouncementPinChanged {
+            group_id,
+            seq: *target,
+            pinned: *pinned,
+        },
+    };
+    state.event_bus.emit(event);
+}
+
+/// Subscribe to the announcement topic of every joined Space.
+pub async fn subscribe_all(state: &DaemonState) -> anyhow::Result<()> {
+    let spaces = ochra_db::queries::spaces::list(&*state.db.lock().await)?;
+    let mut router = state.gossip.lock().await;
+    for space in &spaces {
+        if let Ok(group_id) = GroupId::try_from(space.group_id.as_slice()) {
+            router.subscribe(announcement_topic_id(&group_id));
+        }
+    }
+    Ok(())
+}
+
+/// Sign and publish an announcement as the Space owner.
+///
+/// The owner's own announcements are stored already read.
+pub async fn publish(
+    state: &DaemonState,
+    pik: &SigningKey,
+    group_id: &GroupId,
+    op: AnnouncementOp,
+) -> anyhow::Result<SignedAnnouncement> {
+    let signed = {
+        let db = state.db.lock().await;
+        let seq = ochra_db::queries::announcements::latest_seq(&db, group_id)? + 1;
+        let signed = SignedAnnouncement::sign(
+            pik,
+            Announcement {
+                group_id: *group_id,
+                seq,
+                op,
+                issued_at: now_secs(),
+            },
+        )?;
+        store(&db, group_id, &signed, Some(now_secs()))?;
+        signed
+    };
+    // Would: seal() with the Space's MLS group and publish on
+    // announcement_topic_id(group_id)
+    debug!(
+        "Published announcement {} on {}",
+        signed.announcement.seq,
+        hex::encode(announcement_topic_id(group_id))
+    );
+    emit(state, &signed);
+    Ok(signed)
+}
+
+/// Accept an announcement received from gossip or backfill.
+///
+/// Returns `false` if this node is not a member or already holds it.
+pub async fn ingest(state: &DaemonState, signed: SignedAnnouncement) -> anyhow::Result<bool> {
+    let group_id = signed.announcement.group_id;
+    let has_gaps = {
+        let db = state.db.lock().await;
+        let Some(owner) = ochra_db::queries::spaces::owner_pik(&db, &group_id)? else {
+            return Ok(false);
+        };
+        let owner: [u8; 32] = owner
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("owner PIK must be 32 bytes"))?;
+        let (mut feed, _) = load(&db, &group_id)?;
+        if !feed.insert(signed.clone(), &owner)? {
+            return Ok(false);
+        }
+        store(&db, &group_id, &signed, None)?;
+        !feed.missing(1).is_empty()
+    };
+    if has_gaps {
+        request_backfill(state, &group_id).await?;
+    }
+    emit(state, &signed);
+    Ok(true)
+}
+
+/// Handle an announcement gossiped on a Space's announcement topic.
+pub async fn handle_gossip(_state: &DaemonState, data: &[u8]) -> anyhow::Result<()> {
+    let sealed: MlsCiphertext = ochra_transport::cbor::from_slice(data)?;
+    // Would: SignedAnnouncement::open() with the Space's MLS group state,
+    // then ingest()
+    debug!(
+        "Sealed announcement for {} at epoch {}",
+        hex::encode(sealed.group_id),
+        sealed.epoch
+    );
+    Ok(())
+}
+
+/// Ask another member for announcements this node is missing.
+async fn request_backfill(state: &DaemonState, group_id: &GroupId) -> anyhow::Result<()> {
+    let (feed, _) = load(&*state.db.lock().await, group_id)?;
+    let request = feed.backfill_request();
+    // Would: send request to an online member over the Space's MLS channel
+    debug!(
+        "Announcement backfill for {} after {} ({} missing)",
+        hex::encode(group_id),
+        request.after_seq,
+        request.missing.len()
+    );
+    Ok(())
+}
+
+/// Answer another member's backfill request.
+#[allow(dead_code)]
+pub async fn answer_backfill(
+    state: &DaemonState,
+    request: &AnnouncementBackfillRequest,
+) -> anyhow::Result<AnnouncementBackfillResponse> {
+    let (feed, _) = load(&*state.db.lock().await, &request.group_id)?;
+    Ok(feed.answer(request))
+}
+
+/// Ingest the announcements in a backfill response. Returns how many were
+/// new.
+#[allow(dead_code)]
+pub async fn handle_backfill(
+    state: &DaemonState,
+    response: &AnnouncementBackfillResponse,
+) -> anyhow::Result<usize> {
+    let mut accepted = 0;
+    for bytes in &response.announcements {
+        match SignedAnnouncement::from_bytes(bytes) {
+            Ok(signed) => {
+                if ingest(state, signed).await? {
+                    accepted += 1;
+                }
+            }
+            Err(e) => debug!("Rejected backfilled announcement: {}", e),
+        }
+    }
+    Ok(accepted)
+}
+
+/// A Space's posts in display order with read state.
+pub async fn list(
+    state: &DaemonState,
+    group_id: &GroupId,
+) -> anyhow::Result<Vec<AnnouncementInfo>> {
+    let (feed, read) = load(&*state.db.lock().await, group_id)?;
+    Ok(feed
+        .items()
+        .into_iter()
+        .map(|item| AnnouncementInfo {
+            read: read.get(&item.seq).copied().flatten().is_some(),
+            seq: item.seq,
+            title: item.title,
+            body: item.body,
+            pinned: item.pinned,
+            issued_at: item.issued_at,
+        })
+        .collect())
+}
+
+/// Request backfill for every joined Space every [`BACKFILL_SECS`] until
+/// shutdown.
+pub async fn run_backfill(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(BACKFILL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                let spaces = match ochra_db::queries::spaces::list(&*state.db.lock().await) {
+                    Ok(spaces) => spaces,
+                    Err(e) => {
+                        warn!("Failed to list Spaces for announcement backfill: {}", e);
+                        continue;
+                    }
+                };
+                for space in spaces {
+                    let Ok(group_id) = GroupId::try_from(space.group_id.as_slice()) else {
+                        continue;
+                    };
+                    if let Err(e) = request_backfill(&state, &group_id).await {
+                        warn!("Announcement backfill failed: {}", e);
+                    }
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_announcements_reload_with_read_state() {
+        let db = ochra_db::open_memory().expect("open");
+        let owner = SigningKey::from_bytes(&[7u8; 32]);
+        let group_id = [1u8; 32];
+        ochra_db::queries::spaces::insert(
+            &db,
+            &group_id,
+            "s",
+            "storefront",
+            "member",
+            &owner.verifying_key().to_bytes(),
+            0,
+        )
+        .expect("insert space");
+        for (seq, read_at) in [(1, Some(5)), (2, None)] {
+            let signed = SignedAnnouncement::sign(
+                &owner,
+                Announcement {
+                    group_id,
+                    seq,
+                    op: AnnouncementOp::Post {
+                        title: format!("post {seq}"),
+                        body: String::new(),
+                        pinned: false,
+                    },
+                    issued_at: seq,
+                },
+            )
+            .expect("sign");
+            assert!(store(&db, &group_id, &signed, read_at).expect("store"));
+        }
+
+        let (feed, read) = load(&db, &group_id).expect("load");
+        assert_eq!(feed.latest_seq(), 2);
+        assert_eq!(read.get(&1), Some(&Some(5)));
+        assert_eq!(read.get(&2), Some(&None));
+    }
+}
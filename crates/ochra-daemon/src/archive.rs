@@ -0,0 +1,370 @@
+//! Space content archives (Section 16.9).
+//!
+//! An export writes a Space's catalog, the chunks this node holds for it,
+//! its name and its signed policy into an archive sealed to a key derived
+//! from the PIK, so only the owner can open it on this or a restored node.
+//!
+//! An import reads the archive twice: [`verify`] checks the whole stream
+//! and its signature before anything is touched, then [`import`] restores
+//! the catalog entries that are missing, stores the chunks in the ABR store
+//! and tracks them for announcement, and applies the policy if it is newer
+//! than the one held.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::SigningKey;
+use ochra_crypto::x25519::X25519StaticSecret;
+use ochra_dht::group_policy::SignedGroupPolicy;
+use ochra_storage::archive::{
+    ArchiveEntry, ArchiveHeader, ArchiveReader, ArchiveSummary, ArchiveWriter, ArchivedContent,
+};
+use ochra_storage::StorageError;
+use ochra_types::content::{ContentTag, PricingTier};
+use ochra_types::GroupId;
+use rusqlite::Connection;
+use serde::Serialize;
+use tracing::{debug, info};
+
+use crate::DaemonState;
+
+/// Metadata key of the Space's signed policy.
+const META_GROUP_POLICY: &str = "group_policy";
+
+/// Metadata key of the Space's display name.
+const META_SPACE_NAME: &str = "space_name";
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// What one import restored.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ImportSummary {
+    /// Catalog entries added.
+    pub contents_added: usize,
+    /// Catalog entries already present.
+    pub contents_present: usize,
+    /// Chunks written to the ABR store.
+    pub chunks_stored: usize,
+    /// Chunks already held, tombstoned, or refused for lack of space.
+    pub chunks_skipped: usize,
+    /// Whether the archived policy was newer and applied.
+    pub policy_applied: bool,
+}
+
+/// X25519 key archives are sealed to, derived from the PIK.
+pub fn archive_secret(pik: &SigningKey) -> X25519StaticSecret {
+    X25519StaticSecret::from_bytes(blake3::derive_key(
+        blake3::contexts::SPACE_ARCHIVE_KEY,
+        &pik.to_bytes(),
+    ))
+}
+
+/// Export `group_id` to `path`, signed by and sealed to `pik`.
+///
+/// The archive is written next to `path` and renamed into place once
+/// complete.
+pub async fn export(
+    state: &Arc<DaemonState>,
+    pik: SigningKey,
+    group_id: GroupId,
+    path: PathBuf,
+) -> anyhow::Result<ArchiveSummary> {
+    let (contents, space_name, policy) = {
+        let db = state.db.lock().await;
+        let mut contents = Vec::new();
+        for row in ochra_db::queries::content::list_by_space(&db, &group_id)? {
+            let content_hash: [u8; 32] = row.content_hash.as_slice().try_into()?;
+            let key_commitment: [u8; 32] =
+                ochra_db::queries::content::key_commitment(&db, &content_hash)?
+                    .unwrap_or_default()
+                    .as_slice()
+                    .try_into()?;
+            contents.push(ArchivedContent {
+                content_hash,
+                title: row.title,
+                description: row.description,
+                tags: ochra_db::queries::content::tags(&db, &content_hash)?
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                pricing: serde_json::from_str::<Vec<PricingTier>>(&row.pricing_json)?,
+                creator_pik: row.creator_pik.as_slice().try_into()?,
+                key_commitment,
+                total_size_bytes: row.total_size_bytes,
+                chunk_count: row.chunk_count,
+                published_at: row.published_at,
+            });
+        }
+        let space_name = ochra_db::queries::spaces::list(&db)?
+            .into_iter()
+            .find(|s| s.group_id == group_id)
+            .map(|s| s.name);
+        let policy =
+            ochra_db::queries::group_policies::latest(&db, &group_id)?.map(|row| row.signed_policy);
+        (contents, space_name, policy)
+    };
+
+    let state = state.clone();
+    let summary = tokio::task::spawn_blocking(move || -> anyhow::Result<ArchiveSummary> {
+        let tmp = path.with_extension("tmp");
+        let result = write_archive(&state, pik, group_id, &tmp, &contents, space_name, policy);
+        match result {
+            Ok(summary) => {
+                std::fs::rename(&tmp, &path)?;
+                Ok(summary)
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&tmp);
+                Err(e)
+            }
+        }
+    })
+    .await??;
+    info!(
+        "Exported Space {} archive: {} items, {} chunks, {} bytes",
+        hex::encode(group_id),
+        summary.contents,
+        summary.chunks,
+        summary.bytes
+    );
+    Ok(summary)
+}
+
+fn write_archive(
+    state: &DaemonState,
+    pik: SigningKey,
+    group_id: GroupId,
+    path: &Path,
+    contents: &[ArchivedContent],
+    space_name: Option<String>,
+    policy: Option<Vec<u8>>,
+) -> anyhow::Result<ArchiveSummary> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let recipient = archive_secret(&pik).public_key();
+    let mut writer = ArchiveWriter::new(file, &recipient, pik, group_id, now_secs())?;
+    if let Some(name) = space_name {
+        writer.add_metadata(META_SPACE_NAME, name.as_bytes())?;
+    }
+    if let Some(policy) = policy {
+        writer.add_metadata(META_GROUP_POLICY, &policy)?;
+    }
+    for content in contents {
+        writer.add_content(content)?;
+        let chunk_ids = state
+            .abr
+            .blocking_lock()
+            .content_chunk_ids(&content.content_hash);
+        for chunk_id in chunk_ids {
+            // Copy one chunk at a time so the store is not held during I/O
+            let chunk = {
+                let abr = state.abr.blocking_lock();
+                match (abr.peek_chunk(&chunk_id), abr.get_meta(&chunk_id)) {
+                    (Ok(data), Ok(meta)) => Some((data.to_vec(), meta.shard_index)),
+                    _ => None,
+                }
+            };
+            if let Some((data, shard_index)) = chunk {
+                writer.add_chunk(&content.content_hash, &chunk_id, shard_index, &data)?;
+            }
+        }
+    }
+    Ok(writer.finish()?)
+}
+
+/// Read the archive at `path` end to end and check its signature.
+///
+/// Returns the header, whose signer is then proven.
+pub async fn verify(pik: &SigningKey, path: PathBuf) -> Result<ArchiveHeader, StorageError> {
+    let secret = archive_secret(pik);
+    tokio::task::spawn_blocking(move || {
+        let mut reader = open_reader(&path, &secret)?;
+        while reader.next_entry()?.is_some() {}
+        Ok(reader.header().clone())
+    })
+    .await
+    .map_err(|e| StorageError::Io(e.to_string()))?
+}
+
+fn open_reader(
+    path: &Path,
+    secret: &X25519StaticSecret,
+) -> Result<ArchiveReader<std::io::BufReader<std::fs::File>>, StorageError> {
+    let file = std::fs::File::open(path).map_err(|e| StorageError::Io(e.to_string()))?;
+    ArchiveReader::open(std::io::BufReader::new(file), secret)
+}
+
+/// Restore a [`verify`]d archive.
+///
+/// The archive is verified again as it is read; if it changed since, the
+/// entries before the change stay applied.
+pub async fn import(
+    state: &Arc<DaemonState>,
+    pik: &SigningKey,
+    path: PathBuf,
+) -> anyhow::Result<ImportSummary> {
+    let secret = archive_secret(pik);
+    let blocking_state = state.clone();
+    let (mut summary, group_id, chunk_ids, policy) =
+        tokio::task::spawn_blocking(move || restore_entries(&blocking_state, &path, &secret))
+            .await??;
+
+    state.announcer.lock().await.track(chunk_ids);
+    if let Some(bytes) = policy {
+        let signed = SignedGroupPolicy::from_bytes(&bytes)?;
+        if signed.policy.group_id == group_id {
+            summary.policy_applied = crate::group_policy::ingest(state, signed).await?;
+        }
+    }
+    info!(
+        "Imported Space {} archive: {} items added, {} chunks stored",
+        hex::encode(group_id),
+        summary.contents_added,
+        summary.chunks_stored
+    );
+    Ok(summary)
+}
+
+type Restored = (ImportSummary, GroupId, Vec<[u8; 32]>, Option<Vec<u8>>);
+
+fn restore_entries(
+    state: &DaemonState,
+    path: &Path,
+    secret: &X25519StaticSecret,
+) -> anyhow::Result<Restored> {
+    let mut reader = open_reader(path, secret)?;
+    let group_id = reader.header().group_id;
+    let mut summary = ImportSummary::default();
+    let mut chunk_ids = Vec::new();
+    let mut policy = None;
+    let now = now_secs();
+
+    while let Some(entry) = reader.next_entry()? {
+        match entry {
+            ArchiveEntry::Content(content) => {
+                if restore_content(&state.db.blocking_lock(), &group_id, &content)? {
+                    summary.contents_added += 1;
+                } else {
+                    summary.contents_present += 1;
+                }
+            }
+            ArchiveEntry::Chunk(chunk) => {
+                let stored = state.abr.blocking_lock().store_for_content(
+                    chunk.content_hash,
+                    chunk.chunk_id,
+                    chunk.shard_index,
+                    chunk.data,
+                    now,
+                );
+                match stored {
+                    Ok(true) => {
+                        summary.chunks_stored += 1;
+                        chunk_ids.push(chunk.chunk_id);
+                    }
+                    Ok(false) => summary.chunks_skipped += 1,
+                    Err(e) => {
+                        debug!(
+                            "Skipped archived chunk {}: {}",
+                            hex::encode(chunk.chunk_id),
+                            e
+                        );
+                        summary.chunks_skipped += 1;
+                    }
+                }
+            }
+            ArchiveEntry::Metadata { key, value } => {
+                if key == META_GROUP_POLICY {
+                    policy = Some(value);
+                }
+            }
+        }
+    }
+    Ok((summary, group_id, chunk_ids, policy))
+}
+
+/// Add a catalog entry unless it is already present.
+fn restore_content(
+    db: &Connection,
+    group_id: &GroupId,
+    content: &ArchivedContent,
+) -> anyhow::Result<bool> {
+    if ochra_db::queries::content::exists(db, &content.content_hash)? {
+        return Ok(false);
+    }
+    ochra_db::queries::content::insert(
+        db,
+        &content.content_hash,
+        group_id,
+        &content.title,
+        content.description.as_deref(),
+        &serde_json::to_string(&content.pricing)?,
+        &content.creator_pik,
+        &content.key_commitment,
+        content.total_size_bytes,
+        content.chunk_count,
+        content.published_at,
+    )?;
+    let tags: Vec<ContentTag> = content
+        .tags
+        .iter()
+        .filter_map(|t| ContentTag::parse(t).ok())
+        .collect();
+    ochra_db::queries::content::set_tags(db, &content.content_hash, group_id, &tags)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_key_is_per_pik() {
+        let a = SigningKey::from_bytes(&[1u8; 32]);
+        let b = SigningKey::from_bytes(&[2u8; 32]);
+        assert_eq!(
+            archive_secret(&a).public_key().to_bytes(),
+            archive_secret(&a).public_key().to_bytes()
+        );
+        assert_ne!(
+            archive_secret(&a).public_key().to_bytes(),
+            archive_secret(&b).public_key().to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_restore_content_adds_missing_entries_once() {
+        let db = ochra_db::open_memory().expect("open");
+        let group_id = [1u8; 32];
+        ochra_db::queries::spaces::insert(&db, &group_id, "s", "storefront", "host", &[2; 32], 0)
+            .expect("insert space");
+        let content = ArchivedContent {
+            content_hash: [10; 32],
+            title: "Item".to_string(),
+            description: None,
+            tags: vec!["genre:jazz".to_string(), "not a tag!".to_string()],
+            pricing: Vec::new(),
+            creator_pik: [3; 32],
+            key_commitment: [4; 32],
+            total_size_bytes: 512,
+            chunk_count: 1,
+            published_at: 2000,
+        };
+
+        assert!(restore_content(&db, &group_id, &content).expect("restore"));
+        assert!(!restore_content(&db, &group_id, &content).expect("restore"));
+
+        let items = ochra_db::queries::content::list_by_space(&db, &group_id).expect("list");
+        assert_eq!(items.len(), 1);
+        let tags = ochra_db::queries::content::tags(&db, &[10; 32]).expect("tags");
+        assert_eq!(tags.len(), 1);
+        assert_eq!(
+            ochra_db::queries::content::key_commitment(&db, &[10; 32]).expect("query"),
+            Some(vec![4; 32])
+        );
+    }
+}
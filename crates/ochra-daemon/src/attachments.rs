@@ -0,0 +1,83 @@
+//! Whisper attachment tracking.
+//!
+//! Holds in-progress attachment downloads in memory, emits a
+//! `WhisperAttachmentProgress` event for every verified chunk, and
+//! garbage-collects expired attachments.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_whisper::attachment::AttachmentProgress;
+use tracing::debug;
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Interval between attachment garbage-collection passes.
+const GC_INTERVAL_SECS: u64 = 600;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// JSON form of download progress, shared by events and RPC responses.
+pub fn progress_json(progress: &AttachmentProgress) -> serde_json::Value {
+    serde_json::json!({
+        "content_hash": hex::encode(progress.content_hash),
+        "received_chunks": progress.received_chunks,
+        "total_chunks": progress.total_chunks,
+        "received_bytes": progress.received_bytes,
+        "complete": progress.is_complete(),
+    })
+}
+
+/// Emit a download progress event.
+pub fn emit_progress(state: &DaemonState, progress: &AttachmentProgress) {
+    state
+        .event_bus
+        .emit(DaemonEvent::WhisperAttachmentProgress {
+            content_hash: progress.content_hash,
+            received_chunks: progress.received_chunks,
+            total_chunks: progress.total_chunks,
+            received_bytes: progress.received_bytes,
+            complete: progress.is_complete(),
+        });
+}
+
+/// Accept a fetched chunk and publish progress.
+#[allow(dead_code)]
+pub async fn accept_chunk(
+    state: &Arc<DaemonState>,
+    content_hash: &[u8; 32],
+    index: u32,
+    data: Vec<u8>,
+) -> ochra_whisper::Result<AttachmentProgress> {
+    let progress = state
+        .attachments
+        .lock()
+        .await
+        .accept_chunk(content_hash, index, data)?;
+    emit_progress(state, &progress);
+    Ok(progress)
+}
+
+/// Garbage-collect expired attachments until shutdown.
+pub async fn run_gc(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(GC_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let expired = state.attachments.lock().await.gc(now_secs());
+                for content_hash in expired {
+                    // Would: unpin the attachment's shards from the ABR store
+                    debug!("Attachment {} expired", hex::encode(&content_hash[..8]));
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
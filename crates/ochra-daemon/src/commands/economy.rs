@@ -2,7 +2,10 @@
 
 use std::sync::Arc;
 
+use ochra_db::queries::wallet::{StatementFormat, StatementOptions};
+use ochra_spend::audit::{self, Discrepancy, HeldToken};
 use serde_json::Value;
+use tracing::warn;
 
 use crate::rpc::RpcError;
 use crate::DaemonState;
@@ -10,12 +13,22 @@ use crate::DaemonState;
 type Result = std::result::Result<Value, RpcError>;
 
 /// Get Oracle TWAP and circuit breaker status.
-pub async fn get_oracle_twap(_state: &Arc<DaemonState>) -> Result {
-    // v1: Hardcoded oracle rate (1 Seed = 1 USD = 100_000_000 micro-seeds)
+///
+/// v1: the Seed value is the fixed stub rate (1 Seed = 1 USD). With
+/// `network.oracle_polling` set, staleness and the circuit breaker follow
+/// the exchange reference feed.
+pub async fn get_oracle_twap(state: &Arc<DaemonState>) -> Result {
+    let (breaker_active, stale_secs) = if state.config.network.oracle_polling {
+        let now = crate::oracle::now_secs();
+        let feed = state.oracle.lock().await;
+        (feed.is_circuit_breaker_active(now), feed.stale_secs(now))
+    } else {
+        (false, 0)
+    };
     Ok(serde_json::json!({
-        "seed_value": 100_000_000_u64,
-        "is_circuit_breaker_active": false,
-        "stale_hours": 0,
+        "seed_value": ochra_oracle::stub::DEFAULT_RATE,
+        "is_circuit_breaker_active": breaker_active,
+        "stale_hours": u16::try_from(stale_secs / 3600).unwrap_or(u16::MAX),
     }))
 }
 
@@ -32,6 +45,98 @@ pub async fn get_wallet_balance(state: &Arc<DaemonState>) -> Result {
     }))
 }
 
+/// Reconcile the wallet's tokens against the local nullifier set.
+///
+/// With `repair`, tokens whose spend is confirmed authoritatively are
+/// marked spent. A hit in the Bloom filter alone may be a false positive
+/// and never retires a token. A spend is confirmed when the local nullifier
+/// accumulator holds the nullifier and its root matches the one the quorum
+/// signed into the newest epoch state.
+pub async fn audit_wallet(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let repair = params
+        .get("repair")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let db = state.db.lock().await;
+    let held: Vec<HeldToken> = ochra_db::queries::wallet::tokens(&db)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+        .into_iter()
+        .map(|row| HeldToken {
+            token_id: row.token_id,
+            amount: row.amount,
+            nullifier: row.nullifier,
+            spent: row.spent,
+        })
+        .collect();
+    let report = {
+        let nullifiers = state.nullifiers.lock().await;
+        audit::audit_tokens(&held, |n| nullifiers.contains(n))
+    };
+
+    let quorum_root = state.epoch_states.lock().await.nullifier_smt_root();
+    let tree = state.nullifier_tree.lock().await;
+    let attested = quorum_root == Some(tree.root());
+    let confirmed = |nullifier: &[u8; 32]| attested && tree.contains(nullifier);
+    let mut retired = 0_usize;
+    if repair {
+        let now = now_secs();
+        for token_id in report.to_retire(confirmed) {
+            ochra_db::queries::wallet::spend_token(&db, token_id, now)
+                .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+            retired += 1;
+        }
+        // Would: gossip `report.to_republish()` on the nullifier topic
+    }
+    let balance = ochra_db::queries::wallet::balance(&db)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    if !report.is_clean() {
+        warn!(
+            "Wallet audit found {} discrepancies ({} tokens retired)",
+            report.discrepancies.len(),
+            retired
+        );
+    }
+
+    let discrepancies: Vec<Value> = report
+        .discrepancies
+        .iter()
+        .map(|d| match d {
+            Discrepancy::SpentOnNetwork {
+                token_id,
+                amount,
+                nullifier,
+            } => serde_json::json!({
+                "kind": "spent_on_network",
+                "token_id": hex::encode(token_id),
+                "amount": amount,
+                "confirmed": confirmed(nullifier),
+            }),
+            Discrepancy::SpendNotPublished {
+                token_id,
+                nullifier,
+            } => serde_json::json!({
+                "kind": "spend_not_published",
+                "token_id": hex::encode(token_id),
+                "nullifier": hex::encode(nullifier),
+            }),
+            Discrepancy::MalformedNullifier { token_id } => serde_json::json!({
+                "kind": "malformed_nullifier",
+                "token_id": hex::encode(token_id),
+            }),
+        })
+        .collect();
+
+    Ok(serde_json::json!({
+        "tokens_checked": report.tokens_checked,
+        "recorded_balance": report.recorded_balance,
+        "reconciled_balance": report.reconciled_balance,
+        "discrepancies": discrepancies,
+        "tokens_retired": retired,
+        "stable_seeds": balance,
+    }))
+}
+
 /// Get purchase history.
 pub async fn get_purchase_history(state: &Arc<DaemonState>) -> Result {
     let db = state.db.lock().await;
@@ -54,9 +159,43 @@ pub async fn get_purchase_history(state: &Arc<DaemonState>) -> Result {
     Ok(serde_json::json!(result))
 }
 
+/// Export the transaction history as a CSV or JSON statement.
+pub async fn export_transactions(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let format_name = params
+        .get("format")
+        .and_then(|v| v.as_str())
+        .unwrap_or("csv");
+    let format = StatementFormat::parse(format_name)
+        .ok_or_else(|| RpcError::invalid_params("format must be csv or json"))?;
+    let opts = StatementOptions {
+        from_epoch: params.get("from_epoch").and_then(|v| v.as_u64()),
+        to_epoch: params.get("to_epoch").and_then(|v| v.as_u64()),
+        redact_counterparties: params
+            .get("redact_counterparties")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+    };
+    if matches!((opts.from_epoch, opts.to_epoch), (Some(from), Some(to)) if from > to) {
+        return Err(RpcError::invalid_params("from_epoch is after to_epoch"));
+    }
+
+    let db = state.db.lock().await;
+    let statement = ochra_db::queries::wallet::statement(&db, &opts)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    let data = statement
+        .render(format)
+        .map_err(|e| RpcError::internal_error(&format!("render error: {e}")))?;
+
+    Ok(serde_json::json!({
+        "format": format_name,
+        "transactions": statement.lines.len(),
+        "data": data,
+    }))
+}
+
 /// Send funds to a recipient.
 pub async fn send_funds(state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _recipient_pik = params
+    let recipient_pik = params
         .get("recipient_pik")
         .ok_or_else(|| RpcError::invalid_params("recipient_pik required"))?;
     let amount = params
@@ -65,9 +204,11 @@ pub async fn send_funds(state: &Arc<DaemonState>, params: &Value) -> Result {
         .ok_or_else(|| RpcError::invalid_params("amount_seeds required"))?;
 
     // Check balance
-    let db = state.db.lock().await;
-    let balance = ochra_db::queries::wallet::balance(&db)
-        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    let balance = {
+        let db = state.db.lock().await;
+        ochra_db::queries::wallet::balance(&db)
+            .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+    };
 
     if balance < amount {
         return Err(RpcError::insufficient_balance(amount, balance));
@@ -76,19 +217,47 @@ pub async fn send_funds(state: &Arc<DaemonState>, params: &Value) -> Result {
     // Would create transaction, update wallet, gossip nullifier
     let tx_hash = ochra_crypto::blake3::hash(&amount.to_le_bytes());
 
+    if amount >= crate::audit::LARGE_SPEND_THRESHOLD {
+        crate::audit::record(
+            state,
+            ochra_db::queries::audit::ACTION_LARGE_SPEND,
+            serde_json::json!({
+                "recipient_pik": recipient_pik,
+                "amount": amount,
+                "tx_hash": hex::encode(tx_hash),
+            }),
+        )
+        .await;
+    }
+
     Ok(serde_json::json!({
         "tx_hash": hex::encode(tx_hash),
     }))
 }
 
 /// Force flush service receipts for immediate minting.
-pub async fn force_flush_receipts(_state: &Arc<DaemonState>, params: &Value) -> Result {
+pub async fn force_flush_receipts(state: &Arc<DaemonState>, params: &Value) -> Result {
     let _proof = params
         .get("groth16_proof")
         .ok_or_else(|| RpcError::invalid_params("groth16_proof required"))?;
 
+    let batches = {
+        let mut receipts = state.receipts.lock().await;
+        let batches = receipts.seal_all();
+        ochra_db::queries::service_receipts::mark_flushed(&*state.db.lock().await, u32::MAX)
+            .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+        batches
+    };
+    let receipts_flushed: u64 = batches
+        .iter()
+        .map(|b| u64::from(b.commitment.receipt_count))
+        .sum();
+    // Would: submit each batch commitment to the quorum, answer its
+    // `sample_indices` challenge with `SealedBatch::open`, then mint
+
     Ok(serde_json::json!({
-        "receipts_flushed": 0,
+        "receipts_flushed": receipts_flushed,
+        "batches": batches.len(),
         "seeds_minted": 0,
     }))
 }
@@ -121,11 +290,30 @@ pub async fn propose_revenue_split(_state: &Arc<DaemonState>, params: &Value) ->
     }))
 }
 
-/// Get earnings breakdown for a Space.
-pub async fn get_earnings_breakdown(_state: &Arc<DaemonState>, params: &Value) -> Result {
+/// Get earnings breakdown for a Space, with a projection of VYS rewards.
+pub async fn get_earnings_breakdown(state: &Arc<DaemonState>, params: &Value) -> Result {
     let _group_id = params
         .get("group_id")
         .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let horizon = params
+        .get("horizon")
+        .and_then(|v| v.as_u64())
+        .map(|h| {
+            u32::try_from(h)
+                .ok()
+                .filter(|h| *h <= ochra_vys::estimate::MAX_HORIZON)
+                .ok_or_else(|| {
+                    RpcError::invalid_params(&format!(
+                        "horizon exceeds {} epochs",
+                        ochra_vys::estimate::MAX_HORIZON
+                    ))
+                })
+        })
+        .transpose()?;
+
+    let projection = crate::rewards::project(state, horizon)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
 
     Ok(serde_json::json!({
         "total_earned": 0_u64,
@@ -133,6 +321,7 @@ pub async fn get_earnings_breakdown(_state: &Arc<DaemonState>, params: &Value) -
         "creator_earned": 0_u64,
         "network_earned": 0_u64,
         "epoch": 0,
+        "vys_projection": projection,
     }))
 }
 
@@ -154,10 +343,15 @@ pub async fn request_anonymous_refund(_state: &Arc<DaemonState>, params: &Value)
         .and_then(|v| v.as_u64())
         .ok_or_else(|| RpcError::invalid_params("tier_index required"))?;
 
-    Ok(serde_json::json!({
-        "status": "pending",
-        "refund_amount": 0,
-    }))
+    // Claims go to the seller or quorum through a circuit, and no circuit
+    // path carries them yet, so nothing can be submitted
+    Err(RpcError {
+        code: -32027,
+        message: "QUORUM_UNAVAILABLE".to_string(),
+        data: Some(serde_json::json!({
+            "detail": "refund claims cannot be sent to the quorum yet",
+        })),
+    })
 }
 
 /// Get current collateral ratio.
@@ -168,9 +362,117 @@ pub async fn get_collateral_ratio(_state: &Arc<DaemonState>) -> Result {
     }))
 }
 
-/// Get circulating supply.
-pub async fn get_circulating_supply(_state: &Arc<DaemonState>) -> Result {
-    Ok(serde_json::json!(0_u64))
+/// Get circulating supply as of the latest audited epoch.
+pub async fn get_circulating_supply(state: &Arc<DaemonState>) -> Result {
+    let latest = ochra_db::queries::supply_audit::latest(&*state.db.lock().await)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    let circulating = match latest {
+        Some(row) => {
+            crate::supply_audit::decode(&row)
+                .map_err(|e| RpcError::internal_error(&e.to_string()))?
+                .flows
+                .closing
+                .circulating
+        }
+        None => 0,
+    };
+    Ok(serde_json::json!(circulating))
+}
+
+/// Supply audit reports for auditors, oldest epoch first.
+pub async fn get_supply_audit_report(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let epoch_param = |name: &str| -> std::result::Result<Option<u32>, RpcError> {
+        params
+            .get(name)
+            .and_then(|v| v.as_u64())
+            .map(|e| {
+                u32::try_from(e)
+                    .map_err(|_| RpcError::invalid_params(&format!("{name} out of range")))
+            })
+            .transpose()
+    };
+    let from = epoch_param("from_epoch")?.unwrap_or(0);
+    let to = epoch_param("to_epoch")?.unwrap_or(u32::MAX);
+    if from > to {
+        return Err(RpcError::invalid_params("from_epoch is after to_epoch"));
+    }
+
+    let rows = ochra_db::queries::supply_audit::range(&*state.db.lock().await, from, to)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    let mut reports = Vec::with_capacity(rows.len());
+    let mut violations = 0_u32;
+    for row in &rows {
+        let report = crate::supply_audit::decode(row)
+            .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+        violations += report.violations.len() as u32;
+        reports.push(report);
+    }
+    let reports =
+        serde_json::to_value(&reports).map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    Ok(serde_json::json!({
+        "epochs_audited": rows.len(),
+        "violations": violations,
+        "reports": reports,
+    }))
+}
+
+/// Export the quorum key schedule for auditors.
+///
+/// With `epoch`, returns only the entry whose group key signed that
+/// epoch's quorum outputs.
+pub async fn export_key_schedule(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let conn = state.db.lock().await;
+    let entries = match params.get("epoch").and_then(|v| v.as_u64()) {
+        Some(epoch) => {
+            let epoch =
+                u32::try_from(epoch).map_err(|_| RpcError::invalid_params("epoch out of range"))?;
+            ochra_db::queries::key_schedule::for_epoch(&conn, epoch)
+                .map_err(|e| RpcError::internal_error(&e.to_string()))?
+                .into_iter()
+                .collect::<Vec<_>>()
+        }
+        None => ochra_db::queries::key_schedule::list(&conn)
+            .map_err(|e| RpcError::internal_error(&e.to_string()))?,
+    };
+
+    let entries: Vec<Value> = entries
+        .iter()
+        .map(|e| {
+            serde_json::json!({
+                "activated_epoch": e.activated_epoch,
+                "group_pk": hex::encode(e.group_pk),
+                "threshold": e.threshold,
+                "quorum_size": e.quorum_size,
+                "endorsement": hex::encode(e.endorsement),
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({ "entries": entries }))
+}
+
+/// Return a verified epoch beacon, the latest one without `epoch`.
+pub async fn get_epoch_beacon(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let epoch = params
+        .get("epoch")
+        .and_then(|v| v.as_u64())
+        .map(|e| u32::try_from(e).map_err(|_| RpcError::invalid_params("epoch out of range")))
+        .transpose()?;
+    let beacons = state.beacons.lock().await;
+    let found = match epoch {
+        Some(epoch) => beacons.get(epoch).map(|b| (b, beacons.value(epoch))),
+        None => beacons.latest().map(|(b, value)| (b, Some(value))),
+    };
+    let Some((beacon, Some(value))) = found else {
+        return Err(RpcError::invalid_params(
+            "no verified beacon for that epoch",
+        ));
+    };
+    Ok(serde_json::json!({
+        "epoch": beacon.epoch,
+        "prev_beacon": hex::encode(beacon.prev_beacon),
+        "quorum_sig": hex::encode(beacon.quorum_sig),
+        "value": hex::encode(value),
+    }))
 }
 
 /// Dev-only: Set oracle rate for testing.
@@ -182,3 +484,43 @@ pub async fn dev_set_oracle_rate(_state: &Arc<DaemonState>, params: &Value) -> R
 
     Ok(serde_json::json!({"rate_set": true}))
 }
+
+/// Dev-only: Project CR and mint throttle over a TWAP history.
+///
+/// Replays `observations` under the live CR parameters and, if given, a
+/// proposed `parameters` override, so governance discussions can compare
+/// the two trajectories side by side.
+pub async fn dev_simulate_cr(_state: &Arc<DaemonState>, params: &Value) -> Result {
+    let observations: Vec<ochra_mint::cr_simulation::EpochObservation> = params
+        .get("observations")
+        .cloned()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| RpcError::invalid_params(&format!("invalid observations: {e}")))?
+        .ok_or_else(|| RpcError::invalid_params("observations required"))?;
+    let proposed: Option<ochra_mint::cr_simulation::CrParameters> = params
+        .get("parameters")
+        .cloned()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| RpcError::invalid_params(&format!("invalid parameters: {e}")))?;
+
+    let simulate = |p: &ochra_mint::cr_simulation::CrParameters| {
+        ochra_mint::cr_simulation::simulate(p, &observations)
+            .map_err(|e| RpcError::invalid_params(&e.to_string()))
+    };
+    let baseline = simulate(&ochra_mint::cr_simulation::CrParameters::default())?;
+    let proposed = proposed.as_ref().map(simulate).transpose()?;
+
+    Ok(serde_json::json!({
+        "baseline": baseline,
+        "proposed": proposed,
+    }))
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
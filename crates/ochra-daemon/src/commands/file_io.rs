@@ -1,91 +1,287 @@
 //! File IO, ABR & Publishing command handlers (Section 21.4).
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
+use ochra_pow::por_witness::{self, PorProgress, PorStage, WitnessBuilder};
+use ochra_storage::quota::QuotaUsage;
+use ochra_storage::StorageError;
+use ochra_types::content::tags::{normalize_tags, TagError};
+use ochra_types::content::{ContentTag, PricingTier, TierType};
+use ochra_types::identity::MemberRole;
+use ochra_types::space::ContentRating;
 use serde_json::Value;
 
+use crate::events::DaemonEvent;
 use crate::rpc::RpcError;
 use crate::DaemonState;
 
 type Result = std::result::Result<Value, RpcError>;
 
-/// Get the content catalog for a Space.
+/// Get the content catalog for a Space, optionally only the items
+/// carrying `tag`.
 pub async fn get_store_catalog(state: &Arc<DaemonState>, params: &Value) -> Result {
-    let group_id_hex = params
-        .get("group_id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-
-    let group_id_bytes = hex::decode(group_id_hex)
-        .map_err(|_| RpcError::invalid_params("invalid hex for group_id"))?;
-    let group_id: [u8; 32] = group_id_bytes
-        .try_into()
-        .map_err(|_| RpcError::invalid_params("group_id must be 32 bytes"))?;
+    let group_id = parse_hash(params, "group_id")?;
+    let tag = match params.get("tag").and_then(|v| v.as_str()) {
+        Some(raw) => {
+            Some(ContentTag::parse(raw).map_err(|e| RpcError::invalid_params(&e.to_string()))?)
+        }
+        None => None,
+    };
 
     let db = state.db.lock().await;
-    let items = ochra_db::queries::content::list_by_space(&db, &group_id)
-        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
-
-    let result: Vec<Value> = items
-        .iter()
-        .map(|item| {
-            serde_json::json!({
-                "content_hash": hex::encode(&item.content_hash),
-                "title": item.title,
-                "description": item.description,
-                "pricing": item.pricing_json,
-                "total_size_bytes": item.total_size_bytes,
-                "chunk_count": item.chunk_count,
-                "published_at": item.published_at,
-            })
-        })
-        .collect();
+    let db_err = |e: ochra_db::DbError| RpcError::internal_error(&format!("db error: {e}"));
+    let items = match &tag {
+        Some(tag) => ochra_db::queries::content::list_by_tag(&db, &group_id, tag),
+        None => ochra_db::queries::content::list_by_space(&db, &group_id),
+    }
+    .map_err(db_err)?;
 
+    let mut result = Vec::with_capacity(items.len());
+    for item in &items {
+        result.push(catalog_json(&db, item)?);
+    }
     Ok(serde_json::json!(result))
 }
 
 /// Search the content catalog.
-pub async fn search_catalog(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _query = params
+///
+/// An item matches if its title contains `query`, ignoring case, or any of
+/// its tags matches `query` as a tag (so "English" finds `language:en`).
+/// `tags`, if given, are exact-match filters: every one must be carried.
+pub async fn search_catalog(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let group_id = parse_hash(params, "group_id")?;
+    let query = params
         .get("query")
         .and_then(|v| v.as_str())
         .ok_or_else(|| RpcError::invalid_params("query required"))?;
+    let needle = query.trim().to_lowercase();
+    let filters = match params.get("tags").filter(|v| !v.is_null()) {
+        Some(v) => {
+            let raw: Vec<String> = serde_json::from_value(v.clone())
+                .map_err(|_| RpcError::invalid_params("tags must be an array of strings"))?;
+            normalize_tags(&raw).map_err(tag_error)?
+        }
+        None => Vec::new(),
+    };
 
-    // Would use FTS5 search
-    Ok(serde_json::json!([]))
+    let db = state.db.lock().await;
+    let db_err = |e: ochra_db::DbError| RpcError::internal_error(&format!("db error: {e}"));
+    // Would use FTS5 for title and description search
+    let mut result = Vec::new();
+    for item in ochra_db::queries::content::list_by_space(&db, &group_id).map_err(db_err)? {
+        let hash: [u8; 32] = item.content_hash.as_slice().try_into().unwrap_or_default();
+        let tags = ochra_db::queries::content::tags(&db, &hash).map_err(db_err)?;
+        if !filters.iter().all(|f| tags.contains(f)) {
+            continue;
+        }
+        if item.title.to_lowercase().contains(&needle) || tags.iter().any(|t| t.matches(query)) {
+            result.push(catalog_json(&db, &item)?);
+        }
+    }
+    Ok(serde_json::json!(result))
+}
+
+/// Discovery tag counts over a Space's catalog (Section 16.8).
+pub async fn get_catalog_facets(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let group_id = parse_hash(params, "group_id")?;
+    let facets = ochra_db::queries::content::facets(&*state.db.lock().await, &group_id)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    serde_json::to_value(facets).map_err(|e| RpcError::internal_error(&e.to_string()))
+}
+
+/// Catalog form of a content item, with its tiers and tags.
+///
+/// Stored pricing that does not parse fails the call rather than showing
+/// the item as free.
+fn catalog_json(
+    db: &rusqlite::Connection,
+    item: &ochra_db::queries::content::ContentRow,
+) -> Result {
+    let hash: [u8; 32] = item.content_hash.as_slice().try_into().unwrap_or_default();
+    let tags: Vec<String> = ochra_db::queries::content::tags(db, &hash)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let tiers: Vec<PricingTier> = serde_json::from_str(&item.pricing_json).map_err(|e| {
+        RpcError::internal_error(&format!(
+            "corrupt pricing for {}: {e}",
+            hex::encode(&item.content_hash)
+        ))
+    })?;
+    let tiers: Vec<Value> = tiers.iter().map(tier_json).collect();
+    Ok(serde_json::json!({
+        "content_hash": hex::encode(&item.content_hash),
+        "title": item.title,
+        "description": item.description,
+        "pricing": item.pricing_json,
+        "tiers": tiers,
+        "tags": tags,
+        "total_size_bytes": item.total_size_bytes,
+        "chunk_count": item.chunk_count,
+        "published_at": item.published_at,
+    }))
 }
 
 /// Publish a file to a Space.
-pub async fn publish_file(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _path = params
+///
+/// The file is checked against the Space's policy and storage quota before
+/// any chunking work. A role the policy does not let publish fails with
+/// NOT_CREATOR, a `rating` above the Space's limit with
+/// CONTENT_RATING_EXCEEDED, and a publish over a quota with QUOTA_EXCEEDED.
+/// `tags` are normalized to the discovery taxonomy first; more than
+/// [`ochra_types::MAX_CONTENT_TAGS`] fails with TOO_MANY_TAGS.
+pub async fn publish_file(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let path = params
         .get("path")
         .and_then(|v| v.as_str())
         .ok_or_else(|| RpcError::invalid_params("path required"))?;
-    let _target_id = params
-        .get("target_id")
-        .ok_or_else(|| RpcError::invalid_params("target_id required"))?;
+    let target_id = parse_hash(params, "target_id")?;
     let _pricing = params
         .get("pricing")
         .ok_or_else(|| RpcError::invalid_params("pricing required"))?;
+    let rating = match params.get("rating").and_then(|v| v.as_str()) {
+        Some(s) => ContentRating::parse(s)
+            .ok_or_else(|| RpcError::invalid_params("rating must be general, teen or mature"))?,
+        None => ContentRating::General,
+    };
+    let raw_tags: Vec<String> = match params.get("tags") {
+        Some(v) => serde_json::from_value(v.clone())
+            .map_err(|_| RpcError::invalid_params("tags must be an array of strings"))?,
+        None => Vec::new(),
+    };
+    let tags = normalize_tags(&raw_tags).map_err(tag_error)?;
+    check_policy(state, &target_id, rating).await?;
+
+    let size = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| RpcError::invalid_params(&format!("cannot read file: {e}")))?
+        .len();
+    check_quota(state, &target_id, size).await?;
 
     // Would: chunk file, compute Merkle root, generate PoW, publish manifest
+    // with the normalized tags, catalog it with content::set_tags(), and
+    // track the chunks in the announcer
     let content_hash = [0u8; 32]; // Placeholder
+    let tags: Vec<String> = tags.iter().map(ToString::to_string).collect();
     Ok(serde_json::json!({
         "content_hash": hex::encode(content_hash),
+        "tags": tags,
     }))
 }
 
+/// Refuse a publish the Space's policy does not allow.
+async fn check_policy(
+    state: &Arc<DaemonState>,
+    group_id: &[u8; 32],
+    rating: ContentRating,
+) -> std::result::Result<(), RpcError> {
+    let role = {
+        let db = state.db.lock().await;
+        ochra_db::queries::spaces::my_role(&db, group_id)
+            .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+            .ok_or_else(|| RpcError::invalid_params("unknown target_id"))?
+    };
+    let policy = crate::group_policy::current(state, group_id)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(|| RpcError::invalid_params("unknown target_id"))?;
+    if !MemberRole::parse(&role).is_some_and(|r| policy.settings.may_publish(&r)) {
+        return Err(RpcError::not_creator());
+    }
+    if !policy.settings.allows_rating(rating) {
+        return Err(RpcError::content_rating_exceeded(
+            rating,
+            policy.settings.max_content_rating,
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse a publish of `size` bytes that would exceed the Space's quota.
+async fn check_quota(
+    state: &Arc<DaemonState>,
+    group_id: &[u8; 32],
+    size: u64,
+) -> std::result::Result<(), RpcError> {
+    let db = state.db.lock().await;
+    let db_err = |e: ochra_db::DbError| RpcError::internal_error(&format!("db error: {e}"));
+    let policy = super::network::quota_policy(
+        ochra_db::queries::group_storage::get_quota(&db, group_id).map_err(db_err)?,
+    );
+    if policy.is_unlimited() {
+        return Ok(());
+    }
+    let pik_hash: [u8; 32] = db
+        .query_row("SELECT pik_hash FROM pik WHERE id = 1", [], |row| {
+            row.get::<_, Vec<u8>>(0)
+        })
+        .ok()
+        .and_then(|h| h.try_into().ok())
+        .ok_or_else(RpcError::pik_not_initialized)?;
+    let usage = QuotaUsage {
+        group_bytes: ochra_db::queries::group_storage::group_bytes(&db, group_id)
+            .map_err(db_err)?,
+        member_bytes: ochra_db::queries::group_storage::member_bytes(&db, group_id, &pik_hash)
+            .map_err(db_err)?,
+    };
+    match policy.check_publish(usage, size) {
+        Ok(()) => Ok(()),
+        Err(StorageError::QuotaExceeded {
+            scope,
+            used,
+            requested,
+            limit,
+        }) => Err(RpcError::quota_exceeded(
+            scope.as_str(),
+            used,
+            requested,
+            limit,
+        )),
+        Err(e) => Err(RpcError::internal_error(&e.to_string())),
+    }
+}
+
+/// Catalog form of a pricing tier, with bundle and subscription details.
+fn tier_json(tier: &PricingTier) -> Value {
+    let mut value = serde_json::json!({
+        "tier_type": tier.tier_type,
+        "price_seeds": tier.price_seeds,
+        "rental_days": tier.rental_days,
+    });
+    match tier.tier_type {
+        TierType::Bundle => {
+            value["bundle_hashes"] = tier
+                .bundle_hashes
+                .iter()
+                .map(hex::encode)
+                .collect::<Vec<_>>()
+                .into();
+            value["bundle_size"] = (tier.bundle_hashes.len() + 1).into();
+        }
+        TierType::Subscription => {
+            value["renewal_epochs"] = tier.renewal_epochs.into();
+        }
+        TierType::Permanent | TierType::Rental => {}
+    }
+    value
+}
+
 /// Set pricing for existing content.
+///
+/// Tiers that do not parse or fail validation fail with INVALID_PRICING.
 pub async fn set_content_pricing(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _content_hash = params
-        .get("content_hash")
-        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
-    let _pricing = params
+    let content_hash = parse_hash(params, "content_hash")?;
+    let pricing = params
         .get("pricing")
         .ok_or_else(|| RpcError::invalid_params("pricing required"))?;
+    let tiers: Vec<PricingTier> = serde_json::from_value(pricing.clone())
+        .map_err(|e| RpcError::invalid_pricing(&e.to_string()))?;
+    ochra_spend::pricing::validate_tiers(&content_hash, &tiers)
+        .map_err(|e| RpcError::invalid_pricing(&e.to_string()))?;
+
+    // Would: re-sign the manifest with the new tiers and republish it
     Ok(serde_json::json!({"updated": true}))
 }
 
@@ -99,7 +295,10 @@ pub async fn purchase_content(_state: &Arc<DaemonState>, params: &Value) -> Resu
         .and_then(|v| v.as_u64())
         .ok_or_else(|| RpcError::invalid_params("tier_index required"))?;
 
-    // Would: check balance, create escrow/micro tx, begin download
+    // Would: check balance, create escrow/micro tx, begin download. Bundle
+    // tiers issue one BundleReceipt and download every unlocked item;
+    // subscription tiers issue a SubscriptionReceipt and schedule renewal
+    // before valid_until_epoch
     Ok(serde_json::json!({
         "status": "downloading",
         "progress": 0.0,
@@ -127,6 +326,22 @@ pub async fn get_purchase_receipts(_state: &Arc<DaemonState>) -> Result {
     Ok(serde_json::json!([]))
 }
 
+/// Move a purchase to a fresh receipt secret (device migration).
+pub async fn reissue_receipt(_state: &Arc<DaemonState>, params: &Value) -> Result {
+    let _content_hash = parse_hash(params, "content_hash")?;
+    let _tier_index = params
+        .get("tier_index")
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u8::try_from(v).ok())
+        .ok_or_else(|| RpcError::invalid_params("tier_index required"))?;
+
+    // Would: load the receipt_secret from purchase_receipts, build a
+    // ReissueRequest for the current epoch, store the new secret, send the
+    // request to the creator over Sphinx, and drop the old secret once the
+    // grant arrives
+    Ok(serde_json::json!({"status": "pending"}))
+}
+
 /// Get access status for content.
 pub async fn get_access_status(_state: &Arc<DaemonState>, params: &Value) -> Result {
     let _content_hash = params
@@ -140,16 +355,24 @@ pub async fn get_access_status(_state: &Arc<DaemonState>, params: &Value) -> Res
     }))
 }
 
-/// Download a file.
-pub async fn download_file(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _content_hash = params
-        .get("content_hash")
-        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
+/// Download a file, resuming any saved progress.
+pub async fn download_file(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let content_hash = parse_hash(params, "content_hash")?;
     let _destination = params
         .get("destination")
         .and_then(|v| v.as_str())
         .ok_or_else(|| RpcError::invalid_params("destination required"))?;
 
+    let resumed = crate::downloads::resume(state, content_hash)
+        .await
+        .map_err(|e| RpcError::internal_error(&format!("download error: {e}")))?;
+    if let Some(progress) = resumed {
+        return serde_json::to_value(progress)
+            .map_err(|e| RpcError::internal_error(&e.to_string()));
+    }
+
+    // Would: load the manifest's chunk leaf hashes, then call
+    // downloads::start with the destination
     Ok(serde_json::json!({
         "status": "downloading",
         "progress": 0.0,
@@ -157,19 +380,29 @@ pub async fn download_file(_state: &Arc<DaemonState>, params: &Value) -> Result
 }
 
 /// Pause an active download.
-pub async fn pause_download(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _content_hash = params
-        .get("content_hash")
-        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
-    Ok(serde_json::json!({"paused": true}))
+pub async fn pause_download(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let content_hash = parse_hash(params, "content_hash")?;
+    let paused = crate::downloads::pause(state, &content_hash)
+        .await
+        .map_err(|e| RpcError::internal_error(&format!("download error: {e}")))?;
+    Ok(serde_json::json!({"paused": paused}))
 }
 
 /// Get ABR telemetry.
-pub async fn get_abr_telemetry(_state: &Arc<DaemonState>) -> Result {
+pub async fn get_abr_telemetry(state: &Arc<DaemonState>) -> Result {
+    let uptime = crate::uptime::own_uptime(state).await;
+    let abr = state.abr.lock().await;
+    let dedup = abr.dedup_stats();
     Ok(serde_json::json!({
-        "used_bytes": 0_u64,
+        "used_bytes": abr.used_bytes(),
         "evictions_24h": 0_u32,
         "posrv_score": 0.0_f32,
+        "uptime_fraction": uptime,
+        "dedup": {
+            "shared_chunks": dedup.shared_chunks,
+            "references": dedup.references,
+            "saved_bytes": dedup.saved_bytes,
+        },
     }))
 }
 
@@ -194,25 +427,215 @@ pub async fn update_earning_settings(state: &Arc<DaemonState>, params: &Value) -
 }
 
 /// Pin content (prevent ABR eviction).
-pub async fn pin_content(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _content_hash = params
-        .get("content_hash")
-        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
-    Ok(serde_json::json!({"pinned": true}))
+pub async fn pin_content(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let content_hash = parse_hash(params, "content_hash")?;
+    let pinned = state.abr.lock().await.pin_content(content_hash);
+    Ok(serde_json::json!({"pinned": pinned}))
 }
 
 /// Unpin content.
-pub async fn unpin_content(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _content_hash = params
-        .get("content_hash")
-        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
-    Ok(serde_json::json!({"unpinned": true}))
+pub async fn unpin_content(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let content_hash = parse_hash(params, "content_hash")?;
+    let unpinned = state.abr.lock().await.unpin_content(&content_hash);
+    Ok(serde_json::json!({"unpinned": unpinned}))
 }
 
 /// Submit a zk-PoR proof.
-pub async fn submit_zk_por_proof(_state: &Arc<DaemonState>) -> Result {
+///
+/// Commits to any stored chunks not yet committed, recommits the epoch's
+/// challenged chunks from their bytes, and proves on a blocking thread,
+/// reporting `ZkPorProgress` events as it goes. The proof is not sent to
+/// the quorum yet; success is reported as `proved_not_submitted`.
+pub async fn submit_zk_por_proof(state: &Arc<DaemonState>) -> Result {
+    let pik = crate::audit::pik_signing_key(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(RpcError::session_locked)?;
+    let node_secret = ochra_crypto::blake3::derive_key(
+        ochra_crypto::blake3::contexts::ZK_POR_NODE_SECRET,
+        &pik.to_bytes(),
+    );
+    let epoch = crate::epoch::current_epoch();
+    let challenge_epoch = u32::try_from(epoch)
+        .map_err(|_| RpcError::internal_error("epoch out of range for zk-PoR"))?;
+    // Before the node has the epoch's beacon, fall back to a
+    // deterministic per-epoch value
+    let vrf_beacon = match crate::beacon::seed(
+        state,
+        ochra_frost::beacon::PURPOSE_POR_CHALLENGE,
+        challenge_epoch,
+        0,
+    )
+    .await
+    {
+        Some(seed) => seed,
+        None => ochra_crypto::blake3::hash(&ochra_crypto::blake3::encode_multi_field(&[
+            b"zk-por-beacon",
+            &epoch.to_le_bytes(),
+        ])),
+    };
+
+    // Challenged chunks are committed from their bytes on every proof, so
+    // the witness shows the data is still held; the cache only spares
+    // rehashing the rest of the tree.
+    let (known, missing) = {
+        let abr = state.abr.lock().await;
+        let mut commitments = state.por_commitments.lock().await;
+        commitments.retain(|id| abr.contains(id));
+        let mut stored: Vec<[u8; 32]> = abr
+            .chunk_ids()
+            .into_iter()
+            .filter(|id| abr.peek_chunk(id).is_ok())
+            .collect();
+        stored.sort_unstable();
+        let total = u32::try_from(stored.len()).unwrap_or(u32::MAX);
+        let challenged: HashSet<[u8; 32]> =
+            por_witness::challenge_indices(&vrf_beacon, &node_secret, total)
+                .into_iter()
+                .filter_map(|index| stored.get(index as usize).copied())
+                .collect();
+        let mut known = Vec::new();
+        let mut missing = Vec::new();
+        for chunk_id in stored {
+            match commitments.get(&chunk_id) {
+                Some(commitment) if !challenged.contains(&chunk_id) => {
+                    known.push((chunk_id, *commitment))
+                }
+                _ => {
+                    if let Ok(data) = abr.peek_chunk(&chunk_id) {
+                        missing.push((chunk_id, data.to_vec()));
+                    }
+                }
+            }
+        }
+        (known, missing)
+    };
+
+    let bus = state.event_bus.clone();
+    let started = std::time::Instant::now();
+    let (fresh, proof) = tokio::task::spawn_blocking(move || {
+        let mut report = |p: PorProgress| {
+            bus.emit(DaemonEvent::ZkPorProgress {
+                epoch,
+                stage: p.stage.as_str().to_string(),
+                done: p.done,
+                total: p.total,
+            });
+        };
+        let total = u32::try_from(missing.len()).unwrap_or(u32::MAX);
+        let mut fresh = Vec::with_capacity(missing.len());
+        for (done, (chunk_id, data)) in missing.iter().enumerate() {
+            fresh.push((*chunk_id, por_witness::data_commitment(data)));
+            report(PorProgress {
+                stage: PorStage::Committing,
+                done: u32::try_from(done + 1).unwrap_or(u32::MAX),
+                total,
+            });
+        }
+
+        let mut builder = WitnessBuilder::new(node_secret);
+        for (chunk_id, commitment) in known.iter().chain(&fresh) {
+            builder.add_chunk(*chunk_id, *commitment);
+        }
+        let proof = builder
+            .build(challenge_epoch, vrf_beacon, &mut report)
+            .and_then(|witness| por_witness::prove(&witness, &mut report));
+        (fresh, proof)
+    })
+    .await
+    .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    let proving_time_ms = u32::try_from(started.elapsed().as_millis()).unwrap_or(u32::MAX);
+
+    let mut commitments = state.por_commitments.lock().await;
+    for (chunk_id, commitment) in fresh {
+        commitments.insert_commitment(chunk_id, commitment);
+    }
+    drop(commitments);
+
+    let (status, proof_size_bytes) = match proof {
+        Ok(proof) => {
+            // No quorum submission path exists yet, so the proof is
+            // only reported as generated
+            ("proved_not_submitted", proof.bytes.len())
+        }
+        Err(e) => {
+            tracing::warn!("zk-PoR proof for epoch {} failed: {}", epoch, e);
+            ("failed", 0)
+        }
+    };
+    state.event_bus.emit(DaemonEvent::ZkPorSubmitted {
+        epoch,
+        status: status.to_string(),
+        proving_time_ms,
+    });
     Ok(serde_json::json!({
-        "status": "submitted",
-        "proving_time_ms": 0,
+        "status": status,
+        "epoch": epoch,
+        "proof_size_bytes": proof_size_bytes,
+        "proving_time_ms": proving_time_ms,
     }))
 }
+
+fn tag_error(e: TagError) -> RpcError {
+    match e {
+        TagError::TooMany(count) => RpcError::too_many_tags(count),
+        e => RpcError::invalid_params(&e.to_string()),
+    }
+}
+
+fn parse_hash(params: &Value, field: &str) -> std::result::Result<[u8; 32], RpcError> {
+    params
+        .get(field)
+        .and_then(|v| v.as_str())
+        .and_then(|s| hex::decode(s).ok())
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| RpcError::invalid_params(&format!("{field} must be 32 hex-encoded bytes")))
+}
+
+fn revision_json(revision: &ochra_storage::versioning::ManifestRevision) -> Value {
+    serde_json::json!({
+        "lineage_id": hex::encode(revision.lineage_id),
+        "version": revision.version,
+        "content_hash": hex::encode(revision.content_hash),
+        "published_at": revision.published_at,
+    })
+}
+
+/// Subscribe to a content lineage's update channel.
+pub async fn subscribe_content_updates(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let revision_hex = params
+        .get("revision")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("revision required"))?;
+    let bytes = hex::decode(revision_hex)
+        .map_err(|_| RpcError::invalid_params("invalid hex for revision"))?;
+    let revision = ochra_storage::versioning::ManifestRevision::from_bytes(&bytes)
+        .map_err(|e| RpcError::invalid_params(&e.to_string()))?;
+
+    let mut updates = state.content_updates.lock().await;
+    updates
+        .subscribe(revision.clone())
+        .map_err(|e| RpcError::invalid_params(&e.to_string()))?;
+    // Would: fetch the channel record now and on each poll via the DHT
+    let head = updates.head(&revision.lineage_id).unwrap_or(&revision);
+    Ok(revision_json(head))
+}
+
+/// Unsubscribe from a content lineage's update channel.
+pub async fn unsubscribe_content_updates(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let lineage_id = parse_hash(params, "lineage_id")?;
+    let removed = state.content_updates.lock().await.unsubscribe(&lineage_id);
+    Ok(serde_json::json!({"unsubscribed": removed}))
+}
+
+/// Get the latest known version of a subscribed content lineage.
+pub async fn get_latest_version(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let content_hash = parse_hash(params, "content_hash")?;
+    let updates = state.content_updates.lock().await;
+    let head = updates.lineage_of(&content_hash).ok_or_else(|| RpcError {
+        code: -32100,
+        message: "CONTENT_NOT_FOUND".to_string(),
+        data: None,
+    })?;
+    Ok(revision_json(head))
+}
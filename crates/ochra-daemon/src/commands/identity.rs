@@ -2,20 +2,35 @@
 
 use std::sync::Arc;
 
+use ochra_crypto::ed25519::DelegationCert;
+use ochra_crypto::keystore::{KeyStoreKind, PIK_WRAPPING_KEY_LABEL};
+use ochra_crypto::secret::SecretBytes;
+use ochra_db::queries::audit::{
+    ACTION_GUARDIAN_NOMINATED, ACTION_GUARDIAN_REPLACED, ACTION_RECOVERY_INITIATED, ACTION_UNLOCK,
+};
+use ochra_dht::revocation::{revocation_address, RevocationCertificate, RevocationReason};
+use ochra_types::rpc::{
+    AddContactParams, Authenticated, BiometricEnrollment, ChangePasswordParams, ContactAdded,
+    ContactListEntry, ContactPikParams, ContactToken, ExportRevocationCertificateParams,
+    GenerateContactTokenParams, Hex32, InitiateRecoveryParams, MyPik, NominateGuardianParams,
+    OperationalKey, PasswordParams, PikCreated, PublishRevocationCertificateParams,
+    ReplaceGuardianParams, RevocationCertificateExport, RevocationPublished,
+    RotateOperationalKeyParams, SafetyNumberInfo, TimelockPending, UpdateDisplayNameParams,
+    VerifyContactParams, VetoRecoveryParams,
+};
 use serde_json::Value;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::rpc::RpcError;
+use crate::audit;
+use crate::events::DaemonEvent;
+use crate::rpc::{self, RpcError};
 use crate::DaemonState;
 
 type Result = std::result::Result<Value, RpcError>;
 
 /// Initialize a new PIK with the given password.
 pub async fn init_pik(state: &Arc<DaemonState>, params: &Value) -> Result {
-    let password = params
-        .get("password")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("password required"))?;
+    let PasswordParams { password } = rpc::params(params)?;
 
     info!("Initializing new PIK");
 
@@ -27,10 +42,19 @@ pub async fn init_pik(state: &Arc<DaemonState>, params: &Value) -> Result {
     let profile_secret = ochra_crypto::x25519::X25519StaticSecret::random();
     let profile_key = profile_secret.public_key();
 
-    // Encrypt PIK with password-derived key (Argon2id + ChaCha20-Poly1305)
+    // Encrypt PIK with password-derived key (Argon2id + ChaCha20-Poly1305),
+    // using this host's calibrated password profile
+    let kdf_params = crate::kdf::profiles(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&format!("argon2id calibration failed: {e}")))?
+        .password
+        .params;
     let salt = ochra_crypto::argon2id::generate_salt();
-    let derived_key = ochra_crypto::argon2id::derive_pik_key(password.as_bytes(), &salt)
+    let derived_key = kdf_params
+        .derive_key(password.as_bytes(), &salt)
         .map_err(|e| RpcError::internal_error(&format!("key derivation failed: {e}")))?;
+    let kdf_params_json =
+        serde_json::to_string(&kdf_params).map_err(|e| RpcError::internal_error(&e.to_string()))?;
 
     let mut nonce = [0u8; 12];
     rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut nonce);
@@ -46,7 +70,7 @@ pub async fn init_pik(state: &Arc<DaemonState>, params: &Value) -> Result {
     {
         let db = state.db.lock().await;
         db.execute(
-            "INSERT OR REPLACE INTO pik (id, pik_hash, encrypted_private_key, argon2id_salt, argon2id_nonce, created_at, profile_key) VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT OR REPLACE INTO pik (id, pik_hash, encrypted_private_key, argon2id_salt, argon2id_nonce, created_at, profile_key, argon2id_params) VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             rusqlite::params![
                 pik_hash.as_slice(),
                 encrypted_pik.as_slice(),
@@ -57,47 +81,46 @@ pub async fn init_pik(state: &Arc<DaemonState>, params: &Value) -> Result {
                     .unwrap_or_default()
                     .as_secs() as i64,
                 profile_key.as_bytes().as_slice(),
+                kdf_params_json,
             ],
         ).map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
     }
 
     // Unlock session
-    {
-        let mut unlocked = state.unlocked.write().await;
-        *unlocked = true;
-    }
+    unlock(state, derived_key, "init").await;
 
-    Ok(serde_json::json!({
-        "pik_hash": hex::encode(pik_hash),
-        "created": true,
-    }))
+    rpc::to_result(&PikCreated {
+        pik_hash: pik_hash.into(),
+        created: true,
+    })
 }
 
 /// Authenticate with password.
 pub async fn authenticate(state: &Arc<DaemonState>, params: &Value) -> Result {
-    let password = params
-        .get("password")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("password required"))?;
+    let PasswordParams { password } = rpc::params(params)?;
 
     info!("Authenticating");
 
-    // Load encrypted PIK, salt, and nonce from database
-    let (encrypted_key, salt, nonce_bytes): (Vec<u8>, Vec<u8>, Vec<u8>) = {
+    // Load encrypted PIK, salt, nonce, and Argon2id parameters from database
+    let (encrypted_key, salt, nonce_bytes, params): (Vec<u8>, Vec<u8>, Vec<u8>, Option<String>) = {
         let db = state.db.lock().await;
         db.query_row(
-            "SELECT encrypted_private_key, argon2id_salt, argon2id_nonce FROM pik WHERE id = 1",
+            "SELECT encrypted_private_key, argon2id_salt, argon2id_nonce, argon2id_params FROM pik WHERE id = 1",
             [],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
         )
         .map_err(|_| RpcError::pik_not_initialized())?
     };
 
-    // Derive key and attempt decryption
+    // Derive key with the parameters the PIK was wrapped with and attempt
+    // decryption
     let salt_arr: [u8; 16] = salt
         .try_into()
         .map_err(|_| RpcError::internal_error("invalid salt length"))?;
-    let derived_key = ochra_crypto::argon2id::derive_pik_key(password.as_bytes(), &salt_arr)
+    let kdf_params = crate::kdf::pik_params(params.as_deref())
+        .map_err(|e| RpcError::internal_error(&format!("invalid argon2id parameters: {e}")))?;
+    let derived_key = kdf_params
+        .derive_key(password.as_bytes(), &salt_arr)
         .map_err(|_| RpcError::wrong_password())?;
 
     let nonce: [u8; 12] = nonce_bytes
@@ -107,20 +130,70 @@ pub async fn authenticate(state: &Arc<DaemonState>, params: &Value) -> Result {
         .map_err(|_| RpcError::wrong_password())?;
 
     // Unlock session
-    {
-        let mut unlocked = state.unlocked.write().await;
-        *unlocked = true;
+    unlock(state, derived_key, "password").await;
+
+    rpc::to_result(&Authenticated {
+        authenticated: true,
+    })
+}
+
+/// Biometric failed (-32012).
+fn biometric_failed(detail: &str) -> RpcError {
+    RpcError {
+        code: -32012,
+        message: "BIOMETRIC_FAILED".to_string(),
+        data: Some(serde_json::json!({"detail": detail})),
     }
+}
 
-    Ok(serde_json::json!({"authenticated": true}))
+/// Biometric unlock needs an OS key store; the in-memory fallback would
+/// lose the enrollment on restart.
+fn require_os_keystore(state: &DaemonState) -> std::result::Result<(), RpcError> {
+    if state.keystore.kind() == KeyStoreKind::Memory {
+        return Err(biometric_failed("no OS key store on this platform"));
+    }
+    Ok(())
+}
+
+/// Mark the session unlocked and keep the PIK wrapping key for enrollment.
+async fn unlock(state: &Arc<DaemonState>, wrapping_key: [u8; 32], method: &str) {
+    *state.pik_wrapping_key.lock().await = Some(SecretBytes::new(wrapping_key));
+    *state.unlocked.write().await = true;
+    audit::record(state, ACTION_UNLOCK, serde_json::json!({"method": method})).await;
 }
 
 /// Authenticate with biometric.
+///
+/// Releases the PIK wrapping key from the OS key store (which prompts for
+/// user presence) and checks it against the stored encrypted PIK.
 pub async fn authenticate_biometric(state: &Arc<DaemonState>) -> Result {
-    // v1: Biometric auth delegates to OS keychain. Stub for now.
-    let mut unlocked = state.unlocked.write().await;
-    *unlocked = true;
-    Ok(serde_json::json!({"authenticated": true}))
+    require_os_keystore(state)?;
+    let wrapping_key = state
+        .keystore
+        .load(PIK_WRAPPING_KEY_LABEL)
+        .map_err(|e| biometric_failed(&e.to_string()))?
+        .ok_or_else(|| biometric_failed("biometric unlock not enrolled"))?;
+
+    let (encrypted_key, nonce_bytes): (Vec<u8>, Vec<u8>) = {
+        let db = state.db.lock().await;
+        db.query_row(
+            "SELECT encrypted_private_key, argon2id_nonce FROM pik WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| RpcError::pik_not_initialized())?
+    };
+    let nonce: [u8; 12] = nonce_bytes
+        .try_into()
+        .map_err(|_| RpcError::internal_error("invalid nonce length"))?;
+    // A stale enrollment (e.g. from before a password change) fails here.
+    ochra_crypto::chacha20::decrypt(wrapping_key.expose(), &nonce, &encrypted_key, &[])
+        .map_err(|_| biometric_failed("enrolled key no longer matches the PIK"))?;
+
+    unlock(state, *wrapping_key.expose(), "biometric").await;
+    rpc::to_result(&Authenticated {
+        authenticated: true,
+    })
 }
 
 /// Get own PIK hash.
@@ -131,48 +204,175 @@ pub async fn get_my_pik(state: &Arc<DaemonState>) -> Result {
             row.get(0)
         })
         .map_err(|_| RpcError::pik_not_initialized())?;
+    let pik_hash: [u8; 32] = pik_hash
+        .try_into()
+        .map_err(|_| RpcError::internal_error("invalid pik_hash length"))?;
 
-    Ok(serde_json::json!({"pik_hash": hex::encode(pik_hash)}))
+    rpc::to_result(&MyPik {
+        pik_hash: pik_hash.into(),
+    })
 }
 
 /// Change password.
 pub async fn change_password(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _old = params
-        .get("old")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("old password required"))?;
-    let _new = params
-        .get("new")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("new password required"))?;
-
-    // Would re-encrypt PIK with new password
+    let ChangePasswordParams { old: _, new: _ } = rpc::params(params)?;
+
+    // Would: re-encrypt PIK with new password under the current password
+    // profile from crate::kdf::profiles, updating argon2id_params
     Ok(serde_json::json!({"changed": true}))
 }
 
+fn kdf_profiles_json(profiles: &crate::kdf::KdfProfiles) -> Value {
+    serde_json::json!({
+        "password": profiles.password,
+        "pow": profiles.pow,
+        "calibrated_at": profiles.calibrated_at,
+    })
+}
+
+/// Argon2id parameters calibrated for this host.
+pub async fn get_kdf_profiles(state: &Arc<DaemonState>) -> Result {
+    let profiles = crate::kdf::profiles(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&format!("argon2id calibration failed: {e}")))?;
+    Ok(kdf_profiles_json(&profiles))
+}
+
+/// Benchmark the host again. The PIK keeps its parameters until the
+/// password is next changed.
+pub async fn recalibrate_kdf(state: &Arc<DaemonState>) -> Result {
+    let profiles = crate::kdf::recalibrate(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&format!("argon2id calibration failed: {e}")))?;
+    Ok(kdf_profiles_json(&profiles))
+}
+
+fn operational_key(cert: &DelegationCert, usable: Option<bool>) -> OperationalKey {
+    OperationalKey {
+        subkey_pk: cert.subkey_pk.into(),
+        scope: cert.scope.names().into_iter().map(String::from).collect(),
+        valid_from_epoch: cert.valid_from_epoch,
+        valid_until_epoch: cert.valid_until_epoch,
+        certificate: hex::encode(cert.to_bytes()),
+        usable,
+    }
+}
+
+/// The delegated operational key, or null if none is delegated. `usable`
+/// is whether the certificate covers this epoch and the subkey is in the
+/// key store.
+pub async fn get_operational_key(state: &Arc<DaemonState>) -> Result {
+    let internal = |e: anyhow::Error| RpcError::internal_error(&format!("operational key: {e}"));
+    let Some(cert) = crate::operational_keys::stored_cert(state)
+        .await
+        .map_err(internal)?
+    else {
+        return Ok(Value::Null);
+    };
+    let usable = crate::operational_keys::current(state)
+        .await
+        .map_err(internal)?
+        .is_some_and(|key| key.cert == cert);
+    rpc::to_result(&operational_key(&cert, Some(usable)))
+}
+
+/// Replace the operational key with a fresh one. Requires an unlocked
+/// session; the PIK itself is unchanged.
+pub async fn rotate_operational_key(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let params: RotateOperationalKeyParams = rpc::params(params)?;
+    let scope = match params.scope {
+        None => ochra_crypto::ed25519::DelegationScope::ALL,
+        Some(names) => {
+            let names: Vec<&str> = names.iter().map(String::as_str).collect();
+            crate::operational_keys::parse_scope(&names)
+                .ok_or_else(|| RpcError::invalid_field("scope", "unknown or empty scope"))?
+        }
+    };
+    let validity = match params.validity_epochs {
+        None => crate::operational_keys::DEFAULT_VALIDITY_EPOCHS,
+        Some(n) if (1..=365).contains(&n) => n,
+        Some(_) => return Err(RpcError::invalid_field("validity_epochs", "must be 1-365")),
+    };
+    let cert = crate::operational_keys::rotate(state, scope, validity)
+        .await
+        .map_err(|e| RpcError::internal_error(&format!("rotation failed: {e}")))?
+        .ok_or_else(RpcError::session_locked)?;
+    rpc::to_result(&operational_key(&cert, None))
+}
+
 /// Update display name.
 pub async fn update_display_name(state: &Arc<DaemonState>, params: &Value) -> Result {
-    let new_name = params
-        .get("new_name")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("new_name required"))?;
+    let UpdateDisplayNameParams { new_name } = rpc::params(params)?;
 
     let db = state.db.lock().await;
-    ochra_db::queries::settings::set(&db, "display_name", new_name)
+    ochra_db::queries::settings::set(&db, "display_name", &new_name)
         .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
 
     Ok(serde_json::json!({"updated": true}))
 }
 
 /// Enroll biometric authentication.
-pub async fn enroll_biometric(_state: &Arc<DaemonState>) -> Result {
-    // Stub: would integrate with OS keychain
-    Ok(serde_json::json!({"enrolled": true}))
+///
+/// Copies the current session's PIK wrapping key into the OS key store.
+pub async fn enroll_biometric(state: &Arc<DaemonState>) -> Result {
+    require_os_keystore(state)?;
+    let guard = state.pik_wrapping_key.lock().await;
+    let wrapping_key = guard.as_ref().ok_or_else(RpcError::session_locked)?;
+    state
+        .keystore
+        .store(PIK_WRAPPING_KEY_LABEL, wrapping_key)
+        .map_err(|e| biometric_failed(&e.to_string()))?;
+
+    let capabilities = state.keystore.capabilities();
+    rpc::to_result(&BiometricEnrollment {
+        enrolled: true,
+        hardware_backed: capabilities.hardware_backed,
+    })
 }
 
-/// Export revocation certificate.
-pub async fn export_revocation_certificate(_state: &Arc<DaemonState>) -> Result {
-    Ok(serde_json::json!({"certificate": "stub-revocation-cert"}))
+/// Export a revocation certificate signed by the PIK.
+///
+/// The certificate is not published; keep it offline and pass it to
+/// `publish_revocation_certificate` if the PIK is lost or compromised.
+pub async fn export_revocation_certificate(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let params: ExportRevocationCertificateParams = rpc::params(params)?;
+    let reason = match params.reason.as_deref() {
+        None | Some("unspecified") => RevocationReason::Unspecified,
+        Some("key_compromise") => RevocationReason::KeyCompromise,
+        Some("superseded") => RevocationReason::Superseded,
+        Some(_) => {
+            return Err(RpcError::invalid_field(
+                "reason",
+                "unknown revocation reason",
+            ))
+        }
+    };
+    let pik = local_pik_signing_key(state).await?;
+    let cert = RevocationCertificate::sign(&pik, now_secs(), reason);
+    rpc::to_result(&RevocationCertificateExport {
+        certificate: hex::encode(cert.to_bytes()),
+        dht_address: revocation_address(&cert.pik_public_key).into(),
+    })
+}
+
+/// Publish a revocation certificate to its well-known DHT address.
+///
+/// Works for any valid certificate, so a lost PIK can still be revoked.
+pub async fn publish_revocation_certificate(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let PublishRevocationCertificateParams { certificate } = rpc::params(params)?;
+    let cert = hex::decode(certificate)
+        .map_err(|_| RpcError::invalid_field("certificate", "must be hex"))?;
+    let cert = RevocationCertificate::from_bytes(&cert)
+        .map_err(|e| RpcError::invalid_field("certificate", &e.to_string()))?;
+    cert.verify()
+        .map_err(|e| RpcError::invalid_field("certificate", &e.to_string()))?;
+    crate::revocations::publish(state, &cert)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    rpc::to_result(&RevocationPublished {
+        published: true,
+        dht_address: revocation_address(&cert.pik_public_key).into(),
+    })
 }
 
 /// Export user data.
@@ -181,21 +381,26 @@ pub async fn export_user_data(_state: &Arc<DaemonState>) -> Result {
 }
 
 /// Nominate a guardian (Recovery Contact).
-pub async fn nominate_guardian(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _contact_pik = params
-        .get("contact_pik")
-        .ok_or_else(|| RpcError::invalid_params("contact_pik required"))?;
+pub async fn nominate_guardian(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let NominateGuardianParams { contact_pik } = rpc::params(params)?;
+    audit::record(
+        state,
+        ACTION_GUARDIAN_NOMINATED,
+        serde_json::json!({"contact_pik": contact_pik}),
+    )
+    .await;
     Ok(serde_json::json!({"nominated": true}))
 }
 
 /// Replace a guardian.
-pub async fn replace_guardian(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _old_pik = params
-        .get("old_pik")
-        .ok_or_else(|| RpcError::invalid_params("old_pik required"))?;
-    let _new_pik = params
-        .get("new_pik")
-        .ok_or_else(|| RpcError::invalid_params("new_pik required"))?;
+pub async fn replace_guardian(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let ReplaceGuardianParams { old_pik, new_pik } = rpc::params(params)?;
+    audit::record(
+        state,
+        ACTION_GUARDIAN_REPLACED,
+        serde_json::json!({"old_pik": old_pik, "new_pik": new_pik}),
+    )
+    .await;
     Ok(serde_json::json!({"replaced": true}))
 }
 
@@ -205,70 +410,77 @@ pub async fn get_guardian_health(_state: &Arc<DaemonState>) -> Result {
 }
 
 /// Initiate recovery.
-pub async fn initiate_recovery(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _shares = params
-        .get("guardian_shares")
-        .ok_or_else(|| RpcError::invalid_params("guardian_shares required"))?;
-    Ok(serde_json::json!({
-        "status": "pending",
-        "veto_window_ends": 0,
-    }))
+pub async fn initiate_recovery(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let InitiateRecoveryParams { guardian_shares } = rpc::params(params)?;
+    audit::record(
+        state,
+        ACTION_RECOVERY_INITIATED,
+        serde_json::json!({"shares": guardian_shares.len()}),
+    )
+    .await;
+    let veto_window_ends = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        + ochra_guardian::recovery::VETO_WINDOW;
+    // Would: publish the recovery request so guardians can veto it
+    state
+        .event_bus
+        .emit(DaemonEvent::RecoveryVetoWindow { veto_window_ends });
+    rpc::to_result(&TimelockPending {
+        status: "pending".to_string(),
+        veto_window_ends,
+    })
 }
 
 /// Veto an ongoing recovery.
 pub async fn veto_recovery(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _auth = params
-        .get("auth_payload")
-        .ok_or_else(|| RpcError::invalid_params("auth_payload required"))?;
+    let VetoRecoveryParams { auth_payload: _ } = rpc::params(params)?;
     Ok(serde_json::json!({"vetoed": true}))
 }
 
 /// Add a contact from a token.
 pub async fn add_contact(state: &Arc<DaemonState>, params: &Value) -> Result {
-    let token = params
-        .get("token")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("token required"))?;
+    let AddContactParams { token } = rpc::params(params)?;
 
     // Parse and validate token, then insert contact
     let _token_data = token; // Would parse ContactExchangeToken
     let pik_hash = [0u8; 32]; // Placeholder
     let profile_key = [0u8; 32]; // Placeholder
+    let pik_public_key = [0u8; 32]; // Placeholder
+    let x25519_pk = [0u8; 32]; // Placeholder
 
-    let db = state.db.lock().await;
-    ochra_db::queries::contacts::insert(
-        &db,
-        &pik_hash,
-        "New Contact",
-        &profile_key,
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs(),
-    )
-    .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    if crate::revocations::is_revoked(state, &pik_hash)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+    {
+        return Err(RpcError::invalid_params("contact's PIK has been revoked"));
+    }
+    {
+        let db = state.db.lock().await;
+        ochra_db::queries::contacts::insert(
+            &db,
+            &pik_hash,
+            "New Contact",
+            &profile_key,
+            now_secs(),
+        )
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    }
+    observe_contact_keys(state, &pik_hash, &pik_public_key, &x25519_pk).await?;
 
-    Ok(serde_json::json!({
-        "pik_hash": hex::encode(pik_hash),
-        "display_name": "New Contact",
-    }))
+    rpc::to_result(&ContactAdded {
+        pik_hash: pik_hash.into(),
+        display_name: "New Contact".to_string(),
+    })
 }
 
 /// Remove a contact.
 pub async fn remove_contact(state: &Arc<DaemonState>, params: &Value) -> Result {
-    let pik_hex = params
-        .get("contact_pik")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("contact_pik required"))?;
-
-    let pik_bytes = hex::decode(pik_hex)
-        .map_err(|_| RpcError::invalid_params("invalid hex for contact_pik"))?;
-    let pik: [u8; 32] = pik_bytes
-        .try_into()
-        .map_err(|_| RpcError::invalid_params("contact_pik must be 32 bytes"))?;
+    let ContactPikParams { contact_pik } = rpc::params(params)?;
 
     let db = state.db.lock().await;
-    ochra_db::queries::contacts::remove(&db, &pik)
+    ochra_db::queries::contacts::remove(&db, &contact_pik.0)
         .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
 
     Ok(serde_json::json!({"removed": true}))
@@ -276,12 +488,12 @@ pub async fn remove_contact(state: &Arc<DaemonState>, params: &Value) -> Result
 
 /// Generate a contact exchange token.
 pub async fn generate_contact_token(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _ttl_hours = params
-        .get("ttl_hours")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(24);
+    let params: GenerateContactTokenParams = rpc::params(params)?;
+    let _ttl_hours = params.ttl_hours.unwrap_or(24);
 
-    Ok(serde_json::json!({"token": "stub-contact-token"}))
+    rpc::to_result(&ContactToken {
+        token: "stub-contact-token".to_string(),
+    })
 }
 
 /// Get all contacts.
@@ -290,17 +502,147 @@ pub async fn get_contacts(state: &Arc<DaemonState>) -> Result {
     let contacts = ochra_db::queries::contacts::list(&db)
         .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
 
-    let result: Vec<Value> = contacts
-        .iter()
-        .map(|c| {
-            serde_json::json!({
-                "pik_hash": hex::encode(&c.pik_hash),
-                "display_name": c.display_name,
-                "added_at": c.added_at,
-                "is_blocked": c.is_blocked,
-            })
-        })
-        .collect();
+    let mut result = Vec::with_capacity(contacts.len());
+    for c in &contacts {
+        let Ok(pik) = <[u8; 32]>::try_from(c.pik_hash.as_slice()) else {
+            continue;
+        };
+        // Revoked identities drop out of the contact list
+        if ochra_db::queries::revocations::is_revoked(&db, &pik)
+            .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+        {
+            continue;
+        }
+        let verified = ochra_db::queries::contact_keys::get(&db, &pik)
+            .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+            .is_some_and(|keys| keys.verified_at.is_some());
+        result.push(ContactListEntry {
+            pik_hash: pik.into(),
+            display_name: c.display_name.clone(),
+            added_at: c.added_at,
+            is_blocked: c.is_blocked,
+            verified,
+        });
+    }
+
+    rpc::to_result(&result)
+}
+
+/// Get the safety number and QR payload for a contact.
+pub async fn get_safety_number(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let ContactPikParams {
+        contact_pik: Hex32(contact_pik),
+    } = rpc::params(params)?;
+    let local_pk = local_pik_public_key(state).await?;
+    let keys = pinned_keys(state, &contact_pik).await?;
+
+    let safety_number = ochra_invite::safety::SafetyNumber::new(&local_pk, &keys.pik_public_key);
+    rpc::to_result(&SafetyNumberInfo {
+        safety_number: safety_number.to_string(),
+        qr_payload: ochra_invite::safety::qr_payload(&local_pk, &keys.pik_public_key),
+        verified: keys.verified_at.is_some(),
+        verified_at: keys.verified_at,
+        key_changed_at: keys.key_changed_at,
+    })
+}
+
+/// Mark a contact verified.
+///
+/// With `scanned_qr`, the contact's QR payload is checked first; without
+/// it the user has compared the safety number by other means.
+pub async fn verify_contact(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let VerifyContactParams {
+        contact_pik: Hex32(contact_pik),
+        scanned_qr,
+    } = rpc::params(params)?;
+    if let Some(scanned) = scanned_qr {
+        let local_pk = local_pik_public_key(state).await?;
+        let keys = pinned_keys(state, &contact_pik).await?;
+        ochra_invite::safety::verify_scanned(&scanned, &local_pk, &keys.pik_public_key)
+            .map_err(|e| RpcError::invalid_field("scanned_qr", &e.to_string()))?;
+    }
+
+    let db = state.db.lock().await;
+    ochra_db::queries::contact_keys::mark_verified(&db, &contact_pik, now_secs())
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    Ok(serde_json::json!({"verified": true}))
+}
+
+/// Clear a contact's verification.
+pub async fn unverify_contact(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let ContactPikParams {
+        contact_pik: Hex32(contact_pik),
+    } = rpc::params(params)?;
+    let db = state.db.lock().await;
+    ochra_db::queries::contact_keys::clear_verified(&db, &contact_pik)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    Ok(serde_json::json!({"verified": false}))
+}
+
+/// Compare a contact's observed keys with the pinned ones, emitting
+/// `KeyChanged` when they differ.
+pub async fn observe_contact_keys(
+    state: &Arc<DaemonState>,
+    contact_pik: &[u8; 32],
+    pik_public_key: &[u8; 32],
+    x25519_pk: &[u8; 32],
+) -> std::result::Result<(), RpcError> {
+    let observation = {
+        let db = state.db.lock().await;
+        ochra_db::queries::contact_keys::observe(
+            &db,
+            contact_pik,
+            pik_public_key,
+            x25519_pk,
+            now_secs(),
+        )
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+    };
+    if let ochra_db::queries::contact_keys::KeyObservation::Changed { was_verified } = observation {
+        warn!(
+            contact = %hex::encode(contact_pik),
+            was_verified,
+            "Contact keys changed"
+        );
+        state.event_bus.emit(DaemonEvent::KeyChanged {
+            contact_pik: *contact_pik,
+            was_verified,
+        });
+    }
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+async fn pinned_keys(
+    state: &Arc<DaemonState>,
+    contact_pik: &[u8; 32],
+) -> std::result::Result<ochra_db::queries::contact_keys::ContactKeys, RpcError> {
+    let db = state.db.lock().await;
+    ochra_db::queries::contact_keys::get(&db, contact_pik)
+        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+        .ok_or_else(|| RpcError::invalid_params("no keys pinned for contact"))
+}
+
+/// Own PIK signing key; requires an unlocked session.
+async fn local_pik_signing_key(
+    state: &Arc<DaemonState>,
+) -> std::result::Result<ochra_crypto::ed25519::SigningKey, RpcError> {
+    audit::pik_signing_key(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(RpcError::session_locked)
+}
 
-    Ok(serde_json::json!(result))
+/// Own PIK public key; requires an unlocked session.
+async fn local_pik_public_key(state: &Arc<DaemonState>) -> std::result::Result<[u8; 32], RpcError> {
+    Ok(local_pik_signing_key(state)
+        .await?
+        .verifying_key()
+        .to_bytes())
 }
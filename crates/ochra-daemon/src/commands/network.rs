@@ -1,10 +1,31 @@
 //! Network, Spaces & Subgroups command handlers (Section 21.2).
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
+use ochra_crypto::ed25519::SigningKey;
+use ochra_db::queries::group_storage::QuotaRow;
+use ochra_dht::group_policy::{PolicyChain, SignedGroupPolicy, TRANSFER_TIMELOCK_SECS};
+use ochra_mls::announcement::AnnouncementOp;
+use ochra_storage::quota::{QuotaPolicy, QuotaUsage};
+use ochra_storage::tombstone::{Tombstone, TombstoneReason};
+use ochra_types::identity::MemberRole;
+use ochra_types::layout::{LayoutConfig, RenderableLayout, RenderedSection};
+use ochra_types::rpc::{
+    AnnouncementPosted, AnnouncementsMarked, CreateGroupParams, CreateSubgroupParams,
+    DismissContentReportParams, ExportSpaceArchiveParams, GenerateInviteParams, GroupCreated,
+    GroupIdParams, GroupMemberParams, GroupPolicyHistoryParams, GroupPolicyInfo,
+    GroupPolicyVersion, GroupSettingsUpdated, Hex32, ImportSpaceArchiveParams, JoinGroupParams,
+    LayoutManifestUpdated, LayoutPreview, MarkAnnouncementsReadParams, OwnerTombstoneContentParams,
+    PinAnnouncementParams, PostAnnouncementParams, PreviewLayoutManifestParams,
+    ReportContentParams, RevokeInviteParams, SetGroupQuotaParams, SubgroupIdParams,
+    TimelockPending, TransferGroupOwnershipParams, UpdateGroupLayoutManifestParams,
+    UpdateGroupSettingsParams,
+};
+use ochra_types::space::{GroupPolicy, OwnershipTransferRecord};
 use serde_json::Value;
 
-use crate::rpc::RpcError;
+use crate::rpc::{self, RpcError};
 use crate::DaemonState;
 
 type Result = std::result::Result<Value, RpcError>;
@@ -18,6 +39,10 @@ pub async fn get_my_groups(state: &Arc<DaemonState>) -> Result {
     let result: Vec<Value> = spaces
         .iter()
         .map(|s| {
+            let unread_announcements = <[u8; 32]>::try_from(s.group_id.as_slice())
+                .ok()
+                .and_then(|g| ochra_db::queries::announcements::unread_count(&db, &g).ok())
+                .unwrap_or(0);
             serde_json::json!({
                 "group_id": hex::encode(&s.group_id),
                 "name": s.name,
@@ -25,6 +50,7 @@ pub async fn get_my_groups(state: &Arc<DaemonState>) -> Result {
                 "my_role": s.my_role,
                 "member_count": s.member_count,
                 "last_activity_at": s.last_activity_at,
+                "unread_announcements": unread_announcements,
                 "pinned": s.pinned,
             })
         })
@@ -35,258 +61,559 @@ pub async fn get_my_groups(state: &Arc<DaemonState>) -> Result {
 
 /// Create a new Space/group.
 pub async fn create_group(state: &Arc<DaemonState>, params: &Value) -> Result {
-    let name = params
-        .get("name")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("name required"))?;
-    let template = params
-        .get("template")
-        .and_then(|v| v.as_str())
-        .unwrap_or("storefront");
+    let params: CreateGroupParams = rpc::params(params)?;
+    let template = params.template.as_deref().unwrap_or("storefront");
 
     // Generate group_id
     let mut group_id = [0u8; 32];
     rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut group_id);
 
-    let owner_pik = [0u8; 32]; // Would use actual PIK
+    let owner_pik = crate::audit::pik_signing_key(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(RpcError::session_locked)?
+        .verifying_key()
+        .to_bytes();
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
 
-    let db = state.db.lock().await;
-    ochra_db::queries::spaces::insert(&db, &group_id, name, template, "host", &owner_pik, now)
-        .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
-
-    Ok(serde_json::json!({
-        "group_id": hex::encode(group_id),
-    }))
+    ochra_db::queries::spaces::insert(
+        &*state.db.lock().await,
+        &group_id,
+        &params.name,
+        template,
+        "host",
+        &owner_pik,
+        now,
+    )
+    .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?;
+    state
+        .gossip
+        .lock()
+        .await
+        .subscribe(ochra_transport::gossip::announcement_topic_id(&group_id));
+
+    rpc::to_result(&GroupCreated {
+        group_id: group_id.into(),
+    })
 }
 
 /// Join a Space via invite URI.
 pub async fn join_group(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _invite_uri = params
-        .get("invite_uri")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("invite_uri required"))?;
+    let JoinGroupParams { invite_uri: _ } = rpc::params(params)?;
 
-    // Would parse invite, contact rendezvous, join MLS group
+    // Would parse invite, contact rendezvous, join MLS group, then fetch
+    // the owner's policy record; under JoinRule::Approval the join waits
+    // for a host to admit the request
     Ok(serde_json::json!({"group_id": "stub-group-id"}))
 }
 
 /// Leave a Space.
 pub async fn leave_group(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let GroupIdParams { group_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!({"left": true}))
 }
 
 /// Kick a member from a Space.
 pub async fn kick_member(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _target_pik = params
-        .get("target_pik")
-        .ok_or_else(|| RpcError::invalid_params("target_pik required"))?;
+    let GroupMemberParams {
+        group_id: _,
+        target_pik: _,
+    } = rpc::params(params)?;
     Ok(serde_json::json!({"kicked": true}))
 }
 
 /// Generate an invite link.
-pub async fn generate_invite(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _uses = params.get("uses").and_then(|v| v.as_u64());
-    let _ttl_days = params.get("ttl_days").and_then(|v| v.as_u64()).unwrap_or(7);
+///
+/// Refused with NOT_HOST unless the Space's policy lets this node's role
+/// invite.
+pub async fn generate_invite(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let GenerateInviteParams {
+        group_id: Hex32(group_id),
+        uses: _,
+        ttl_days,
+    } = rpc::params(params)?;
+    let _ttl_days = ttl_days.unwrap_or(7);
+
+    let role = {
+        let db = state.db.lock().await;
+        ochra_db::queries::spaces::my_role(&db, &group_id)
+            .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+            .ok_or_else(|| RpcError::invalid_params("unknown group_id"))?
+    };
+    let policy = crate::group_policy::current(state, &group_id)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(|| RpcError::invalid_params("unknown group_id"))?;
+    if !MemberRole::parse(&role).is_some_and(|r| policy.settings.may_invite(&r)) {
+        return Err(RpcError::not_host());
+    }
 
     Ok(serde_json::json!({"invite_uri": "ochra://invite/stub"}))
 }
 
 /// Revoke an invite.
 pub async fn revoke_invite(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _invite_hash = params
-        .get("invite_hash")
-        .ok_or_else(|| RpcError::invalid_params("invite_hash required"))?;
+    let RevokeInviteParams { invite_hash: _ } = rpc::params(params)?;
     Ok(serde_json::json!({"revoked": true}))
 }
 
 /// Get active invites for a Space.
 pub async fn get_active_invites(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let GroupIdParams { group_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!([]))
 }
 
 /// Get members of a Space.
 pub async fn get_group_members(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let GroupIdParams { group_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!([]))
 }
 
 /// Grant publisher/Creator role.
 pub async fn grant_publisher_role(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _target_pik = params
-        .get("target_pik")
-        .ok_or_else(|| RpcError::invalid_params("target_pik required"))?;
+    let GroupMemberParams {
+        group_id: _,
+        target_pik: _,
+    } = rpc::params(params)?;
     Ok(serde_json::json!({"granted": true}))
 }
 
 /// Revoke publisher/Creator role.
 pub async fn revoke_publisher_role(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _target_pik = params
-        .get("target_pik")
-        .ok_or_else(|| RpcError::invalid_params("target_pik required"))?;
+    let GroupMemberParams {
+        group_id: _,
+        target_pik: _,
+    } = rpc::params(params)?;
     Ok(serde_json::json!({"revoked": true}))
 }
 
 /// Grant moderator role.
 pub async fn grant_moderator_role(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _target_pik = params
-        .get("target_pik")
-        .ok_or_else(|| RpcError::invalid_params("target_pik required"))?;
+    let GroupMemberParams {
+        group_id: _,
+        target_pik: _,
+    } = rpc::params(params)?;
     Ok(serde_json::json!({"granted": true}))
 }
 
 /// Revoke moderator role.
 pub async fn revoke_moderator_role(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _target_pik = params
-        .get("target_pik")
-        .ok_or_else(|| RpcError::invalid_params("target_pik required"))?;
+    let GroupMemberParams {
+        group_id: _,
+        target_pik: _,
+    } = rpc::params(params)?;
     Ok(serde_json::json!({"revoked": true}))
 }
 
-/// Transfer group ownership (30-day timelock).
-pub async fn transfer_group_ownership(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _new_owner_pik = params
-        .get("new_owner_pik")
-        .ok_or_else(|| RpcError::invalid_params("new_owner_pik required"))?;
-    Ok(serde_json::json!({
-        "status": "pending",
-        "veto_window_ends": 0,
-    }))
+/// Transfer group ownership (7-day timelock).
+///
+/// Publishes a policy naming the new owner; members hand the Space over
+/// once the veto window ends.
+pub async fn transfer_group_ownership(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let TransferGroupOwnershipParams {
+        group_id: Hex32(group_id),
+        new_owner_pik: Hex32(new_owner_pik),
+    } = rpc::params(params)?;
+    let (pik, chain) = owned_policy_chain(state, &group_id).await?;
+    let mut policy = chain.policy();
+    if let Some(transfer) = &policy.pending_transfer {
+        return Err(RpcError::ownership_transfer_pending(transfer.completes_at));
+    }
+    if new_owner_pik == chain.owner_pk() {
+        return Err(RpcError::invalid_field(
+            "new_owner_pik",
+            "is the current owner",
+        ));
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let completes_at = now + TRANSFER_TIMELOCK_SECS;
+    policy.version = chain.next_version();
+    policy.issued_at = now;
+    policy.pending_transfer = Some(OwnershipTransferRecord {
+        new_owner_pik,
+        initiated_at: now,
+        completes_at,
+    });
+    publish_policy(state, &pik, policy).await?;
+    rpc::to_result(&TimelockPending {
+        status: "pending".to_string(),
+        veto_window_ends: completes_at,
+    })
 }
 
 /// Veto a pending ownership transfer.
-pub async fn veto_ownership_transfer(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+///
+/// Publishes a policy without the transfer, which members take as the
+/// owner withdrawing it.
+pub async fn veto_ownership_transfer(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let GroupIdParams {
+        group_id: Hex32(group_id),
+    } = rpc::params(params)?;
+    let (pik, chain) = owned_policy_chain(state, &group_id).await?;
+    let mut policy = chain.policy();
+    if policy.pending_transfer.take().is_none() {
+        return Err(RpcError::invalid_params("no ownership transfer pending"));
+    }
+    policy.version = chain.next_version();
+    policy.issued_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    publish_policy(state, &pik, policy).await?;
     Ok(serde_json::json!({"vetoed": true}))
 }
 
 /// Update group settings.
-pub async fn update_group_settings(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _settings = params
-        .get("settings")
-        .ok_or_else(|| RpcError::invalid_params("settings required"))?;
-    Ok(serde_json::json!({"updated": true}))
+///
+/// Publishes the settings as the Space's next policy version. Settings
+/// cannot change while an ownership transfer is pending.
+pub async fn update_group_settings(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let UpdateGroupSettingsParams {
+        group_id: Hex32(group_id),
+        settings,
+    } = rpc::params(params)?;
+    let (pik, chain) = owned_policy_chain(state, &group_id).await?;
+    let mut policy = chain.policy();
+    if let Some(transfer) = &policy.pending_transfer {
+        return Err(RpcError::ownership_transfer_pending(transfer.completes_at));
+    }
+    policy.version = chain.next_version();
+    policy.issued_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    policy.settings = settings;
+    let version = policy.version;
+    publish_policy(state, &pik, policy).await?;
+    rpc::to_result(&GroupSettingsUpdated {
+        updated: true,
+        version,
+    })
+}
+
+/// Get the policy in force for a Space.
+pub async fn get_group_policy(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let GroupIdParams {
+        group_id: Hex32(group_id),
+    } = rpc::params(params)?;
+    let chain = crate::group_policy::chain(state, &group_id)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(|| RpcError::invalid_params("unknown group_id"))?;
+    rpc::to_result(&GroupPolicyInfo {
+        owner_pik: chain.owner_pk().into(),
+        policy: chain.policy(),
+    })
+}
+
+/// Get a Space's accepted policy versions, newest first.
+pub async fn get_group_policy_history(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let GroupPolicyHistoryParams {
+        group_id: Hex32(group_id),
+        limit,
+    } = rpc::params(params)?;
+    let limit = limit.unwrap_or(50);
+    let db = state.db.lock().await;
+    let db_err = |e: ochra_db::DbError| RpcError::internal_error(&format!("db error: {e}"));
+    let rows = ochra_db::queries::group_policies::history(&db, &group_id, limit).map_err(db_err)?;
+    let history: Vec<GroupPolicyVersion> = rows
+        .iter()
+        .filter_map(|row| {
+            let signed = SignedGroupPolicy::from_bytes(&row.signed_policy).ok()?;
+            Some(GroupPolicyVersion {
+                version: row.version,
+                signer_pik: <[u8; 32]>::try_from(row.signer_pk.as_slice()).ok()?.into(),
+                received_at: row.received_at,
+                policy: signed.policy,
+            })
+        })
+        .collect();
+    rpc::to_result(&history)
+}
+
+/// Post an announcement to a Space (owner only).
+pub async fn post_announcement(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let PostAnnouncementParams {
+        group_id: Hex32(group_id),
+        title,
+        body,
+        pinned,
+    } = rpc::params(params)?;
+    let (pik, _) = owned_policy_chain(state, &group_id).await?;
+    let op = AnnouncementOp::Post {
+        title,
+        body: body.unwrap_or_default(),
+        pinned: pinned.unwrap_or(false),
+    };
+    let signed = crate::announcements::publish(state, &pik, &group_id, op)
+        .await
+        .map_err(|e| RpcError::invalid_params(&e.to_string()))?;
+    rpc::to_result(&AnnouncementPosted {
+        seq: signed.announcement.seq,
+    })
+}
+
+/// Pin or unpin an earlier announcement (owner only).
+pub async fn pin_announcement(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let PinAnnouncementParams {
+        group_id: Hex32(group_id),
+        seq,
+        pinned,
+    } = rpc::params(params)?;
+    let (pik, _) = owned_policy_chain(state, &group_id).await?;
+    let posts = crate::announcements::list(state, &group_id)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    if !posts.iter().any(|p| p.seq == seq) {
+        return Err(RpcError::invalid_field(
+            "seq",
+            "no announcement with that seq",
+        ));
+    }
+    let op = AnnouncementOp::SetPinned {
+        target: seq,
+        pinned,
+    };
+    crate::announcements::publish(state, &pik, &group_id, op)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    Ok(serde_json::json!({"pinned": pinned}))
+}
+
+/// Get a Space's announcements, pinned first, with read state.
+pub async fn get_announcements(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let GroupIdParams {
+        group_id: Hex32(group_id),
+    } = rpc::params(params)?;
+    let announcements = crate::announcements::list(state, &group_id)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    Ok(serde_json::json!(announcements))
+}
+
+/// Mark one announcement, or all of a Space's, as read.
+pub async fn mark_announcements_read(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let MarkAnnouncementsReadParams {
+        group_id: Hex32(group_id),
+        seq,
+    } = rpc::params(params)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let db = state.db.lock().await;
+    let db_err = |e: ochra_db::DbError| RpcError::internal_error(&format!("db error: {e}"));
+    let marked =
+        ochra_db::queries::announcements::mark_read(&db, &group_id, seq, now).map_err(db_err)?;
+    let unread = ochra_db::queries::announcements::unread_count(&db, &group_id).map_err(db_err)?;
+    rpc::to_result(&AnnouncementsMarked {
+        marked: u32::try_from(marked).unwrap_or(u32::MAX),
+        unread,
+    })
+}
+
+/// The PIK and policy chain of a Space this node owns.
+async fn owned_policy_chain(
+    state: &Arc<DaemonState>,
+    group_id: &[u8; 32],
+) -> std::result::Result<(SigningKey, PolicyChain), RpcError> {
+    let pik = crate::audit::pik_signing_key(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(RpcError::session_locked)?;
+    let chain = crate::group_policy::chain(state, group_id)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(|| RpcError::invalid_params("unknown group_id"))?;
+    if chain.owner_pk() != pik.verifying_key().to_bytes() {
+        return Err(RpcError::not_host());
+    }
+    Ok((pik, chain))
+}
+
+async fn publish_policy(
+    state: &Arc<DaemonState>,
+    pik: &SigningKey,
+    policy: GroupPolicy,
+) -> std::result::Result<(), RpcError> {
+    crate::group_policy::publish(state, pik, policy)
+        .await
+        .map(|_| ())
+        .map_err(|e| RpcError::internal_error(&format!("policy publish failed: {e}")))
 }
 
 /// Update group profile (name, icon, description).
 pub async fn update_group_profile(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let GroupIdParams { group_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!({"updated": true}))
 }
 
 /// Create a subgroup/channel within a Space.
 pub async fn create_subgroup(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _name = params
-        .get("name")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("name required"))?;
+    let CreateSubgroupParams {
+        group_id: _,
+        name: _,
+    } = rpc::params(params)?;
     Ok(serde_json::json!({"subgroup_id": "stub-subgroup-id"}))
 }
 
 /// Get subgroup members.
 pub async fn get_subgroup_members(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _subgroup_id = params
-        .get("subgroup_id")
-        .ok_or_else(|| RpcError::invalid_params("subgroup_id required"))?;
+    let SubgroupIdParams { subgroup_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!([]))
 }
 
 /// Grant subgroup access via MLS.
 pub async fn mls_grant_subgroup_access(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _subgroup_id = params
-        .get("subgroup_id")
-        .ok_or_else(|| RpcError::invalid_params("subgroup_id required"))?;
+    let SubgroupIdParams { subgroup_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!({"granted": true}))
 }
 
 /// Revoke subgroup access via MLS.
 pub async fn mls_revoke_subgroup_access(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _subgroup_id = params
-        .get("subgroup_id")
-        .ok_or_else(|| RpcError::invalid_params("subgroup_id required"))?;
+    let SubgroupIdParams { subgroup_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!({"revoked": true}))
 }
 
+/// Migrate and validate a submitted layout config.
+///
+/// Pinned content is checked against the catalog of `group_id`. Returns the
+/// migrated config and the schema version it was submitted with.
+async fn checked_layout(
+    state: &Arc<DaemonState>,
+    config: LayoutConfig,
+    group_id: Option<&[u8; 32]>,
+) -> std::result::Result<(LayoutConfig, u32), RpcError> {
+    let submitted_version = config.schema_version;
+    let config = config
+        .migrate()
+        .map_err(|e| RpcError::invalid_layout(&[e]))?;
+
+    let catalog: HashSet<[u8; 32]> = match group_id {
+        Some(group_id) => {
+            let db = state.db.lock().await;
+            ochra_db::queries::content::list_by_space(&db, group_id)
+                .map_err(|e| RpcError::internal_error(&format!("db error: {e}")))?
+                .into_iter()
+                .filter_map(|row| row.content_hash.try_into().ok())
+                .collect()
+        }
+        // Without a Space there is no catalog to check references against.
+        None => config
+            .sections
+            .iter()
+            .flat_map(|s| s.pinned_content.iter().copied())
+            .collect(),
+    };
+    let errors = config.validate(&catalog);
+    if !errors.is_empty() {
+        return Err(RpcError::invalid_layout(&errors));
+    }
+    Ok((config, submitted_version))
+}
+
 /// Preview a layout manifest.
-pub async fn preview_layout_manifest(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _config = params
-        .get("config")
-        .ok_or_else(|| RpcError::invalid_params("config required"))?;
-    Ok(serde_json::json!({"layout": "stub-preview"}))
+///
+/// Older manifests are migrated first. If `group_id` is given, pinned
+/// content must be in that Space's catalog. Invalid manifests fail with
+/// INVALID_LAYOUT listing every problem.
+pub async fn preview_layout_manifest(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let PreviewLayoutManifestParams { config, group_id } = rpc::params(params)?;
+    let group_id = group_id.map(|Hex32(id)| id);
+    let (config, submitted_version) = checked_layout(state, config, group_id.as_ref()).await?;
+    let rendered_sections: Vec<RenderedSection> = config
+        .sections
+        .iter()
+        .map(|s| RenderedSection {
+            section_type: s.section_type,
+            title: s.title.clone(),
+            content_hashes: s.pinned_content.clone(),
+        })
+        .collect();
+    // Would: fill each section from the catalog up to max_items and attach
+    // the content manifests
+    let layout = RenderableLayout {
+        layout_type: config.layout_type,
+        rendered_sections,
+        content_items: Vec::new(),
+    };
+    rpc::to_result(&LayoutPreview {
+        layout,
+        schema_version: config.schema_version,
+        migrated_from: (submitted_version != config.schema_version).then_some(submitted_version),
+    })
 }
 
 /// Update a group's layout manifest.
-pub async fn update_group_layout_manifest(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    Ok(serde_json::json!({"updated": true}))
+pub async fn update_group_layout_manifest(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let UpdateGroupLayoutManifestParams {
+        group_id: Hex32(group_id),
+        config,
+    } = rpc::params(params)?;
+    let (config, _) = checked_layout(state, config, Some(&group_id)).await?;
+    let encoded = serde_json::to_vec(&config)
+        .map_err(|e| RpcError::internal_error(&format!("encode error: {e}")))?;
+    let layout_manifest_hash = ochra_crypto::blake3::hash(&encoded);
+    // Would: broadcast the manifest to the Space over MLS and update
+    // SpaceManifest.layout_manifest_hash
+    rpc::to_result(&LayoutManifestUpdated {
+        updated: true,
+        layout_manifest_hash: layout_manifest_hash.into(),
+    })
 }
 
 /// Get onion circuit health metrics.
-pub async fn get_onion_circuit_health(_state: &Arc<DaemonState>) -> Result {
+pub async fn get_onion_circuit_health(state: &Arc<DaemonState>) -> Result {
+    use ochra_onion::health::HealthStatus;
+
+    let monitor = state.circuit_health.lock().await;
+    let circuits: Vec<Value> = monitor
+        .iter()
+        .map(|(id, health)| {
+            serde_json::json!({
+                "circuit_id": hex::encode(id),
+                "status": health.status(),
+                "consecutive_failures": health.consecutive_failures,
+                "suspect_hop": health.suspect_hop,
+                "rtt_ms": health.last_rtt_ms,
+                "hops": health.hops,
+            })
+        })
+        .collect();
+    let rtts: Vec<u64> = monitor.iter().filter_map(|(_, h)| h.last_rtt_ms).collect();
+    let avg_latency_ms = match rtts.len() {
+        0 => 0,
+        n => rtts.iter().sum::<u64>() / n as u64,
+    };
+    let degraded = monitor
+        .iter()
+        .filter(|(_, h)| h.status() == HealthStatus::Degraded)
+        .count();
     Ok(serde_json::json!({
-        "active_circuits": 0,
-        "avg_latency_ms": 0,
+        "active_circuits": monitor.len(),
+        "healthy_circuits": monitor.healthy_count(),
+        "degraded_circuits": degraded,
+        "avg_latency_ms": avg_latency_ms,
         "relay_cache_size": 0,
+        "circuits": circuits,
     }))
 }
 
 /// Set per-Space notification settings.
 pub async fn set_group_notification_settings(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let GroupIdParams { group_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!({"updated": true}))
 }
 
 /// Get Space stats (host dashboard).
 pub async fn get_space_stats(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let GroupIdParams { group_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!({
         "member_count": 0,
         "content_count": 0,
@@ -294,49 +621,233 @@ pub async fn get_space_stats(_state: &Arc<DaemonState>, params: &Value) -> Resul
     }))
 }
 
+/// The quota policy stored for a Space; unlimited if none is set.
+pub(crate) fn quota_policy(row: Option<QuotaRow>) -> QuotaPolicy {
+    row.map_or_else(QuotaPolicy::default, |q| QuotaPolicy {
+        max_total_bytes: q.max_total_bytes,
+        max_member_bytes: q.max_member_bytes,
+        max_item_bytes: q.max_item_bytes,
+    })
+}
+
+/// Get a Space's storage usage, per-member contributions, and quota.
+///
+/// `remaining_bytes` is how much this node may still publish, or null if
+/// no limit applies.
+pub async fn get_group_storage(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let GroupIdParams {
+        group_id: Hex32(group_id),
+    } = rpc::params(params)?;
+    let db = state.db.lock().await;
+    let db_err = |e: ochra_db::DbError| RpcError::internal_error(&format!("db error: {e}"));
+    if ochra_db::queries::spaces::my_role(&db, &group_id)
+        .map_err(db_err)?
+        .is_none()
+    {
+        return Err(RpcError::invalid_params("unknown group_id"));
+    }
+    let usage = ochra_db::queries::group_storage::usage(&db, &group_id).map_err(db_err)?;
+    let quota = ochra_db::queries::group_storage::get_quota(&db, &group_id).map_err(db_err)?;
+    let updated_at = quota.as_ref().map(|q| q.updated_at);
+    let policy = quota_policy(quota);
+
+    let my_pik: Option<Vec<u8>> = db
+        .query_row("SELECT pik_hash FROM pik WHERE id = 1", [], |row| {
+            row.get(0)
+        })
+        .ok();
+    let my_bytes = my_pik
+        .as_ref()
+        .and_then(|pik| usage.members.iter().find(|m| &m.pik_hash == pik))
+        .map_or(0, |m| m.bytes);
+    let remaining_bytes = policy.remaining(QuotaUsage {
+        group_bytes: usage.total_bytes,
+        member_bytes: my_bytes,
+    });
+
+    let members: Vec<Value> = usage
+        .members
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "pik_hash": hex::encode(&m.pik_hash),
+                "bytes": m.bytes,
+                "item_count": m.item_count,
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "total_bytes": usage.total_bytes,
+        "item_count": usage.item_count,
+        "pinned_bytes": usage.pinned_bytes,
+        "members": members,
+        "quota": (!policy.is_unlimited()).then_some(policy),
+        "quota_updated_at": updated_at,
+        "remaining_bytes": remaining_bytes,
+    }))
+}
+
+/// Set a Space's storage quota. Host only.
+///
+/// Omitted or null limits are unlimited, so passing none clears the quota.
+/// Content already published stays even if it is over the new limits;
+/// only later publishes are refused.
+pub async fn set_group_quota(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let SetGroupQuotaParams {
+        group_id: Hex32(group_id),
+        max_total_bytes,
+        max_member_bytes,
+        max_item_bytes,
+    } = rpc::params(params)?;
+    let policy = QuotaPolicy {
+        max_total_bytes,
+        max_member_bytes,
+        max_item_bytes,
+    };
+    policy
+        .validate()
+        .map_err(|e| RpcError::invalid_params(&e.to_string()))?;
+
+    let db = state.db.lock().await;
+    let db_err = |e: ochra_db::DbError| RpcError::internal_error(&format!("db error: {e}"));
+    match ochra_db::queries::spaces::my_role(&db, &group_id).map_err(db_err)? {
+        None => return Err(RpcError::invalid_params("unknown group_id")),
+        Some(role) if role != "host" => return Err(RpcError::not_host()),
+        Some(_) => {}
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let row = QuotaRow {
+        max_total_bytes: policy.max_total_bytes,
+        max_member_bytes: policy.max_member_bytes,
+        max_item_bytes: policy.max_item_bytes,
+        updated_at: now,
+    };
+    ochra_db::queries::group_storage::set_quota(&db, &group_id, &row).map_err(db_err)?;
+    // Would: broadcast the policy to the Space over MLS so members check
+    // publishes against it before uploading
+    Ok(serde_json::json!({"updated": true, "quota": policy}))
+}
+
 /// Get Space activity feed.
 pub async fn get_space_activity(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let GroupIdParams { group_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!([]))
 }
 
 /// Get content reports for moderation.
 pub async fn get_content_reports(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
+    let GroupIdParams { group_id: _ } = rpc::params(params)?;
     Ok(serde_json::json!([]))
 }
 
 /// Dismiss a content report.
 pub async fn dismiss_content_report(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _group_id = params
-        .get("group_id")
-        .ok_or_else(|| RpcError::invalid_params("group_id required"))?;
-    let _content_hash = params
-        .get("content_hash")
-        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
+    let DismissContentReportParams {
+        group_id: _,
+        content_hash: _,
+    } = rpc::params(params)?;
     Ok(serde_json::json!({"dismissed": true}))
 }
 
 /// Report content.
 pub async fn report_content(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _content_hash = params
-        .get("content_hash")
-        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
-    let _reason = params
-        .get("reason")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("reason required"))?;
+    let ReportContentParams {
+        content_hash: _,
+        reason: _,
+    } = rpc::params(params)?;
     Ok(serde_json::json!({"reported": true}))
 }
 
 /// Tombstone content (host action).
-pub async fn owner_tombstone_content(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _content_hash = params
-        .get("content_hash")
-        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
+///
+/// Signs a tombstone with the PIK, applies it locally, and advertises it in
+/// the DHT and over gossip.
+pub async fn owner_tombstone_content(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let OwnerTombstoneContentParams {
+        content_hash: Hex32(content_hash),
+        reason,
+    } = rpc::params(params)?;
+    let reason = match reason {
+        Some(s) => TombstoneReason::parse(&s)
+            .ok_or_else(|| RpcError::invalid_field("reason", "unknown tombstone reason"))?,
+        None => TombstoneReason::Removed,
+    };
+
+    let (group_id, authorities) = crate::tombstones::authorities(state, &content_hash)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(|| RpcError::invalid_params("unknown content_hash"))?;
+    let pik = crate::audit::pik_signing_key(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(RpcError::session_locked)?;
+    if !authorities.contains(&pik.verifying_key().to_bytes()) {
+        return Err(RpcError::not_host());
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let tombstone = Tombstone::new(&pik, group_id, content_hash, reason, now);
+    crate::tombstones::publish(state, &pik, &tombstone)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    crate::tombstones::honour(state, tombstone, &authorities)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
     Ok(serde_json::json!({"tombstoned": true}))
 }
+
+/// Export a Space this node owns to an encrypted archive file.
+///
+/// The archive holds the catalog, the chunks this node stores for it and
+/// the Space's signed policy, and only this PIK can open it.
+pub async fn export_space_archive(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let ExportSpaceArchiveParams {
+        group_id: Hex32(group_id),
+        path,
+    } = rpc::params(params)?;
+    let (pik, _chain) = owned_policy_chain(state, &group_id).await?;
+
+    let summary = crate::archive::export(state, pik, group_id, path.into())
+        .await
+        .map_err(|e| RpcError::internal_error(&format!("archive export failed: {e}")))?;
+    serde_json::to_value(summary).map_err(|e| RpcError::internal_error(&e.to_string()))
+}
+
+/// Restore a Space from an archive made by `export_space_archive`.
+///
+/// The whole archive is verified first: it must open under this PIK, its
+/// signature must hold, and its signer must own the Space. Missing catalog
+/// entries are added and the chunks stored and announced.
+pub async fn import_space_archive(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let ImportSpaceArchiveParams { path } = rpc::params(params)?;
+    let pik = crate::audit::pik_signing_key(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(RpcError::session_locked)?;
+
+    let header = crate::archive::verify(&pik, (&path).into())
+        .await
+        .map_err(|e| RpcError::invalid_params(&e.to_string()))?;
+    let chain = crate::group_policy::chain(state, &header.group_id)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(|| RpcError::invalid_params("unknown group_id"))?;
+    if chain.owner_pk() != header.signer_pk {
+        return Err(RpcError::not_host());
+    }
+
+    let summary = crate::archive::import(state, &pik, path.into())
+        .await
+        .map_err(|e| RpcError::internal_error(&format!("archive import failed: {e}")))?;
+    let mut result =
+        serde_json::to_value(summary).map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    result["group_id"] = hex::encode(header.group_id).into();
+    result["exported_at"] = header.exported_at.into();
+    Ok(result)
+}
@@ -2,40 +2,71 @@
 
 use std::sync::Arc;
 
+use ochra_types::whisper::ThrottleStrictness;
+use ochra_whisper::backup::{BackupDestination, BackupPolicy, BackupScope};
+use ochra_whisper::WhisperError;
 use serde_json::Value;
 
+use crate::handles::LookupUnsupported;
 use crate::rpc::RpcError;
 use crate::DaemonState;
 
 type Result = std::result::Result<Value, RpcError>;
 
 /// Register a handle (@username).
+///
+/// Fails with NOT_SUPPORTED once the handle is valid: a registration is a
+/// HandleDescriptor put, and the daemon does not yet serve DHT puts, so the
+/// handle could be neither published nor checked for availability.
+/// [`get_handle_cost`] still quotes the price.
 pub async fn register_handle(_state: &Arc<DaemonState>, params: &Value) -> Result {
     let handle = params
         .get("handle")
         .and_then(|v| v.as_str())
         .ok_or_else(|| RpcError::invalid_params("handle required"))?;
+    ochra_whisper::handle::validate_handle(handle).map_err(|e| handle_error(&e))?;
+    Err(RpcError::not_supported(HANDLE_PUBLISH_UNSUPPORTED))
+}
 
-    // Validate handle format
-    if handle.len() < 3 || handle.len() > 32 {
-        return Err(RpcError {
-            code: -32081,
-            message: "HANDLE_INVALID".to_string(),
-            data: Some(serde_json::json!({"detail": "handle must be 3-32 characters"})),
-        });
-    }
+/// Quote the cost of registering a handle.
+pub async fn get_handle_cost(_state: &Arc<DaemonState>, params: &Value) -> Result {
+    let handle = params
+        .get("handle")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("handle required"))?;
+    ochra_whisper::handle::validate_handle(handle).map_err(|e| handle_error(&e))?;
 
-    // Would: check availability via DHT, register, publish descriptor
+    let difficulty = ochra_whisper::handle::registration_difficulty(handle);
     Ok(serde_json::json!({
-        "handle": handle,
-        "registered_at": 0,
+        "difficulty": difficulty,
+        "renewal_difficulty": ochra_whisper::handle::renewal_difficulty(handle),
+        "burn_price_micro_seeds": ochra_whisper::handle::burn_price(difficulty),
+        "dictionary_word": ochra_whisper::handle::is_dictionary_word(handle),
     }))
 }
 
 /// Deprecate current handle with optional successor.
+///
+/// Fails with NOT_SUPPORTED; see [`register_handle`].
 pub async fn deprecate_handle(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _successor = params.get("successor_handle").and_then(|v| v.as_str());
-    Ok(serde_json::json!({"deprecated": true}))
+    if let Some(successor) = params.get("successor_handle").and_then(|v| v.as_str()) {
+        ochra_whisper::handle::validate_handle(successor).map_err(|e| handle_error(&e))?;
+    }
+    Err(RpcError::not_supported(HANDLE_PUBLISH_UNSUPPORTED))
+}
+
+/// Hand the current handle to a new owner key.
+///
+/// Fails with NOT_SUPPORTED once `new_signing_pk` parses; see
+/// [`register_handle`].
+pub async fn transfer_handle(_state: &Arc<DaemonState>, params: &Value) -> Result {
+    let _new_signing_pk: [u8; 32] = params
+        .get("new_signing_pk")
+        .and_then(|v| v.as_str())
+        .and_then(|s| hex::decode(s).ok())
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| RpcError::invalid_params("new_signing_pk must be 32 hex-encoded bytes"))?;
+    Err(RpcError::not_supported(HANDLE_PUBLISH_UNSUPPORTED))
 }
 
 /// Get own handle info.
@@ -44,44 +75,55 @@ pub async fn get_my_handle(_state: &Arc<DaemonState>) -> Result {
 }
 
 /// Resolve a handle to a descriptor.
-pub async fn resolve_handle(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _handle = params
+pub async fn resolve_handle(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let handle = params
         .get("handle")
         .and_then(|v| v.as_str())
         .ok_or_else(|| RpcError::invalid_params("handle required"))?;
-
-    // Would: query DHT for handle descriptor
-    Ok(serde_json::json!({
-        "status": "not_found",
-    }))
+    ochra_whisper::handle::validate_handle(handle).map_err(|e| handle_error(&e))?;
+
+    match crate::handles::resolve(state, handle).await {
+        Ok(Some(descriptor)) => Ok(serde_json::json!({
+            "status": "found",
+            "descriptor": descriptor,
+        })),
+        Ok(None) => Ok(serde_json::json!({
+            "status": "not_found",
+        })),
+        Err(e) => Err(resolve_error(&e)),
+    }
 }
 
 /// Check handle availability.
+///
+/// Fails with NOT_SUPPORTED once the handle is valid: availability is read
+/// from the handle's descriptor, which the daemon cannot fetch.
 pub async fn check_handle_availability(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _handle = params
+    let handle = params
         .get("handle")
         .and_then(|v| v.as_str())
         .ok_or_else(|| RpcError::invalid_params("handle required"))?;
+    ochra_whisper::handle::validate_handle(handle).map_err(|e| handle_error(&e))?;
 
-    Ok(serde_json::json!(true))
+    Err(RpcError::not_supported(&LookupUnsupported.to_string()))
 }
 
 /// Change handle.
+///
+/// Fails with NOT_SUPPORTED once the new handle is valid; see
+/// [`register_handle`].
 pub async fn change_handle(_state: &Arc<DaemonState>, params: &Value) -> Result {
     let new_handle = params
         .get("new_handle")
         .and_then(|v| v.as_str())
         .ok_or_else(|| RpcError::invalid_params("new_handle required"))?;
-
-    Ok(serde_json::json!({
-        "handle": new_handle,
-        "registered_at": 0,
-    }))
+    ochra_whisper::handle::validate_handle(new_handle).map_err(|e| handle_error(&e))?;
+    Err(RpcError::not_supported(HANDLE_PUBLISH_UNSUPPORTED))
 }
 
 /// Start a Whisper session.
-pub async fn start_whisper(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _target = params
+pub async fn start_whisper(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let target = params
         .get("target")
         .ok_or_else(|| RpcError::invalid_params("target required"))?;
 
@@ -89,17 +131,41 @@ pub async fn start_whisper(_state: &Arc<DaemonState>, params: &Value) -> Result
     let mut session_id = [0u8; 16];
     rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut session_id);
 
+    let descriptor = match target.get("handle").and_then(|v| v.as_str()) {
+        Some(handle) => crate::handles::resolve(state, handle)
+            .await
+            .map_err(|e| resolve_error(&e))?,
+        None => None,
+    };
+
+    // Presence overrides apply to sessions opened with a contact
+    if let Some(contact) = target
+        .get("contact")
+        .and_then(|v| v.as_str())
+        .and_then(|s| hex::decode(s).ok())
+        .and_then(|b| <[u8; 32]>::try_from(b).ok())
+    {
+        state
+            .presence
+            .lock()
+            .await
+            .bind_session(session_id, contact);
+    }
+    if let Some(descriptor) = descriptor {
+        crate::delivery::open_session(state, session_id, &descriptor).await;
+    }
+
     Ok(serde_json::json!({
         "session_id": hex::encode(session_id),
     }))
 }
 
 /// Send a Whisper message.
-pub async fn send_whisper(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _session_id = params
-        .get("session_id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("session_id required"))?;
+///
+/// The message is sealed to the peer's mailboxes, queued, and its delivery
+/// state reported through `WhisperDeliveryStateChanged` events.
+pub async fn send_whisper(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let session_id = parse_session_id(params)?;
     let body = params
         .get("body")
         .and_then(|v| v.as_str())
@@ -114,8 +180,18 @@ pub async fn send_whisper(_state: &Arc<DaemonState>, params: &Value) -> Result {
         });
     }
 
-    // Would: encrypt with Double Ratchet, wrap in Sphinx packet, send
-    Ok(serde_json::json!({"sent": true}))
+    let sequence = crate::delivery::enqueue(state, session_id, body.as_bytes())
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(|| RpcError {
+            code: -32086,
+            message: "RECIPIENT_OFFLINE".to_string(),
+            data: None,
+        })?;
+    Ok(serde_json::json!({
+        "sequence": sequence,
+        "state": ochra_types::whisper::WhisperDeliveryState::Queued,
+    }))
 }
 
 /// Send Seeds via Whisper session.
@@ -145,22 +221,21 @@ pub async fn reveal_identity(_state: &Arc<DaemonState>, params: &Value) -> Resul
 }
 
 /// Close a Whisper session.
-pub async fn close_whisper(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _session_id = params
-        .get("session_id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("session_id required"))?;
-
-    // Would: zeroize all session keys and message state
+pub async fn close_whisper(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let session_id = parse_session_id(params)?;
+
+    // Would: zeroize all session keys
+    crate::delivery::close_session(state, &session_id)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    state.presence.lock().await.unbind_session(&session_id);
     Ok(serde_json::json!({"closed": true}))
 }
 
 /// Block a Whisper counterparty.
-pub async fn block_whisper(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _session_id = params
-        .get("session_id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("session_id required"))?;
+pub async fn block_whisper(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let session_id = parse_session_id(params)?;
+    crate::whisper_backup::forget(state, &session_id).await;
     Ok(serde_json::json!({"blocked": true}))
 }
 
@@ -170,7 +245,7 @@ pub async fn get_active_whispers(_state: &Arc<DaemonState>) -> Result {
 }
 
 /// Get throttle status for a Whisper session.
-pub async fn get_whisper_throttle_status(_state: &Arc<DaemonState>, params: &Value) -> Result {
+pub async fn get_whisper_throttle_status(state: &Arc<DaemonState>, params: &Value) -> Result {
     let _session_id = params
         .get("session_id")
         .and_then(|v| v.as_str())
@@ -180,27 +255,449 @@ pub async fn get_whisper_throttle_status(_state: &Arc<DaemonState>, params: &Val
         "current_tier": "free",
         "messages_remaining": 10,
         "relay_cost": 0,
+        "strictness": crate::throttle::strictness(state).await,
     }))
 }
 
+/// Get the strictness applied to unknown senders.
+pub async fn get_whisper_strictness(state: &Arc<DaemonState>) -> Result {
+    let strictness = crate::throttle::strictness(state).await;
+    Ok(strictness_json(strictness))
+}
+
+/// Set the strictness applied to unknown senders.
+pub async fn set_whisper_strictness(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let strictness: ThrottleStrictness = params
+        .get("strictness")
+        .cloned()
+        .ok_or_else(|| RpcError::invalid_params("strictness required"))
+        .and_then(|v| {
+            serde_json::from_value(v).map_err(|e| RpcError::invalid_params(&e.to_string()))
+        })?;
+    crate::throttle::set(state, strictness)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    Ok(strictness_json(strictness))
+}
+
+fn strictness_json(strictness: ThrottleStrictness) -> Value {
+    serde_json::json!({
+        "strictness": strictness,
+        "puzzle_difficulty": ochra_whisper::throttle::puzzle_difficulty(strictness),
+        "payment_micro_seeds": ochra_whisper::throttle::payment_price(strictness),
+    })
+}
+
 /// Send typing indicator.
-pub async fn send_typing_indicator(_state: &Arc<DaemonState>, params: &Value) -> Result {
-    let _session_id = params
-        .get("session_id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| RpcError::invalid_params("session_id required"))?;
+///
+/// Not sent (`sent: false`) when presence settings toward the peer disable
+/// typing indicators or online presence.
+pub async fn send_typing_indicator(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let session_id = parse_session_id(params)?;
+    if !state
+        .presence
+        .lock()
+        .await
+        .for_session(&session_id)
+        .sends_typing()
+    {
+        return Ok(serde_json::json!({"sent": false}));
+    }
+
+    // Would: send a Typing message through the session's Double Ratchet
     Ok(serde_json::json!({"sent": true}))
 }
 
 /// Send read acknowledgment.
-pub async fn send_read_ack(_state: &Arc<DaemonState>, params: &Value) -> Result {
+///
+/// Not sent (`sent: false`) when presence settings toward the peer disable
+/// read receipts.
+pub async fn send_read_ack(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let session_id = parse_session_id(params)?;
+    let up_to_sequence = params
+        .get("up_to_sequence")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| RpcError::invalid_params("up_to_sequence required"))?;
+    if !state
+        .presence
+        .lock()
+        .await
+        .for_session(&session_id)
+        .read_receipts
+    {
+        return Ok(serde_json::json!({"sent": false}));
+    }
+
+    let _body = ochra_whisper::delivery::read_ack_body(up_to_sequence);
+    // Would: send _body as a ReadAck message through the session's Double
+    // Ratchet; the peer marks its messages up to up_to_sequence read
+    Ok(serde_json::json!({"sent": true}))
+}
+
+/// Get presence privacy settings, optionally as they apply to a contact.
+pub async fn get_presence_settings(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let contact = optional_contact(params)?;
+    Ok(crate::presence::settings_json(state, contact.as_ref()).await)
+}
+
+/// Set the global presence privacy settings.
+pub async fn set_presence_settings(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let privacy: ochra_types::whisper::PresencePrivacy = params
+        .get("settings")
+        .cloned()
+        .ok_or_else(|| RpcError::invalid_params("settings required"))
+        .and_then(|v| {
+            serde_json::from_value(v).map_err(|e| RpcError::invalid_params(&e.to_string()))
+        })?;
+
+    ochra_db::queries::presence::set_global(&*state.db.lock().await, &privacy)
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    state.presence.lock().await.set_global(privacy);
+    // Would: republish the HandleDescriptor with the new privacy field
+    Ok(crate::presence::settings_json(state, None).await)
+}
+
+/// Set or clear a contact's presence privacy override.
+pub async fn set_contact_presence(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let contact = optional_contact(params)?
+        .ok_or_else(|| RpcError::invalid_params("contact_pik_hash required"))?;
+    let o: ochra_types::whisper::PresenceOverride = match params.get("override") {
+        Some(v) => serde_json::from_value(v.clone())
+            .map_err(|e| RpcError::invalid_params(&e.to_string()))?,
+        None => ochra_types::whisper::PresenceOverride::default(),
+    };
+
+    {
+        let db = state.db.lock().await;
+        ochra_db::queries::contacts::get(&db, &contact)
+            .map_err(|_| RpcError::invalid_params("unknown contact"))?;
+        ochra_db::queries::presence::set_override(&db, &contact, &o)
+            .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    }
+    state.presence.lock().await.set_override(contact, o);
+    Ok(crate::presence::settings_json(state, Some(&contact)).await)
+}
+
+/// Send a file attachment in a Whisper session.
+///
+/// Fails with NOT_SUPPORTED once the file passes the size check: the
+/// encrypted shards are published with DHT puts, which the daemon does not
+/// yet serve, so the pointer would reference chunks nobody can fetch.
+pub async fn send_whisper_attachment(_state: &Arc<DaemonState>, params: &Value) -> Result {
     let _session_id = params
         .get("session_id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| RpcError::invalid_params("session_id required"))?;
-    let _up_to_sequence = params
-        .get("up_to_sequence")
+    let file_path = params
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("file_path required"))?;
+
+    let metadata = tokio::fs::metadata(file_path)
+        .await
+        .map_err(|e| RpcError::invalid_params(&format!("cannot read file: {e}")))?;
+    if metadata.len() > ochra_whisper::attachment::MAX_ATTACHMENT_SIZE as u64 {
+        return Err(RpcError {
+            code: -32104,
+            message: "FILE_TOO_LARGE".to_string(),
+            data: Some(serde_json::json!({
+                "max_bytes": ochra_whisper::attachment::MAX_ATTACHMENT_SIZE,
+            })),
+        });
+    }
+    Err(RpcError::not_supported(
+        "attachment shards are published with DHT puts, which the daemon does not yet serve",
+    ))
+}
+
+/// Get download progress for a received attachment.
+pub async fn get_attachment_progress(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let hash_hex = params
+        .get("content_hash")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("content_hash required"))?;
+    let content_hash: [u8; 32] = hex::decode(hash_hex)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| RpcError::invalid_params("content_hash must be 32 hex-encoded bytes"))?;
+
+    let progress = state.attachments.lock().await.progress(&content_hash);
+    Ok(progress
+        .as_ref()
+        .map(crate::attachments::progress_json)
+        .unwrap_or(Value::Null))
+}
+
+/// Create a device link QR code (run on the device being linked).
+///
+/// Fails with NOT_SUPPORTED: a linked device is reached through the handle
+/// descriptor, which this daemon cannot yet publish.
+pub async fn create_device_link(_state: &Arc<DaemonState>, params: &Value) -> Result {
+    let device_name = params
+        .get("device_name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("device_name required"))?;
+    if device_name.chars().count() > ochra_whisper::devices::MAX_DEVICE_NAME_LEN {
+        return Err(RpcError::invalid_params("device_name too long"));
+    }
+    Err(RpcError::not_supported(DEVICE_LINKING_UNSUPPORTED))
+}
+
+/// Authorize a device from its scanned link QR code (run on the primary).
+///
+/// Fails with NOT_SUPPORTED once the QR code parses; see
+/// [`create_device_link`].
+pub async fn authorize_device(_state: &Arc<DaemonState>, params: &Value) -> Result {
+    let qr_url = params
+        .get("qr_url")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("qr_url required"))?;
+    ochra_whisper::devices::DeviceLinkRequest::from_qr_url(qr_url)
+        .map_err(|e| RpcError::invalid_params(&e.to_string()))?;
+    Err(RpcError::not_supported(DEVICE_LINKING_UNSUPPORTED))
+}
+
+/// List devices linked to the current handle.
+///
+/// Fails with NOT_SUPPORTED; see [`create_device_link`].
+pub async fn get_linked_devices(_state: &Arc<DaemonState>) -> Result {
+    Err(RpcError::not_supported(DEVICE_LINKING_UNSUPPORTED))
+}
+
+/// Unlink a device from the current handle.
+///
+/// Fails with NOT_SUPPORTED; see [`create_device_link`].
+pub async fn unlink_device(_state: &Arc<DaemonState>, params: &Value) -> Result {
+    let _device_id = params
+        .get("device_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("device_id required"))?;
+    Err(RpcError::not_supported(DEVICE_LINKING_UNSUPPORTED))
+}
+
+/// Why the commands that publish handle records fail.
+const HANDLE_PUBLISH_UNSUPPORTED: &str =
+    "handle records are published with DHT puts, which the daemon does not yet serve";
+
+/// Why the device linking commands fail.
+const DEVICE_LINKING_UNSUPPORTED: &str =
+    "device linking needs a published handle descriptor, and the daemon does not yet serve DHT puts";
+
+fn handle_error(e: &ochra_whisper::WhisperError) -> RpcError {
+    let (code, message) = match e {
+        ochra_whisper::WhisperError::ReservedHandle(_) => (-32082, "HANDLE_RESERVED"),
+        ochra_whisper::WhisperError::HandleTaken(_) => (-32080, "HANDLE_TAKEN"),
+        ochra_whisper::WhisperError::LookupLimited(_) => (-32083, "HANDLE_RATE_LIMITED"),
+        _ => (-32081, "HANDLE_INVALID"),
+    };
+    RpcError {
+        code,
+        message: message.to_string(),
+        data: Some(serde_json::json!({"detail": e.to_string()})),
+    }
+}
+
+fn resolve_error(e: &anyhow::Error) -> RpcError {
+    match e.downcast_ref::<WhisperError>() {
+        Some(e) => handle_error(e),
+        None if e.is::<LookupUnsupported>() => RpcError::not_supported(&e.to_string()),
+        None => RpcError::internal_error(&e.to_string()),
+    }
+}
+
+fn backup_scope(params: &Value) -> std::result::Result<BackupScope, RpcError> {
+    match params.get("scope") {
+        None | Some(Value::Null) => Ok(BackupScope::Latest),
+        Some(v) => serde_json::from_value(v.clone())
+            .map_err(|e| RpcError::invalid_params(&format!("invalid scope: {e}"))),
+    }
+}
+
+fn backup_error(e: &anyhow::Error) -> RpcError {
+    match e.downcast_ref::<WhisperError>() {
+        Some(WhisperError::Crypto(_)) => RpcError {
+            code: -32092,
+            message: "BACKUP_RESTORE_FAILED".to_string(),
+            data: Some(serde_json::json!({"reason": e.to_string()})),
+        },
+        Some(WhisperError::Backup(reason)) => RpcError::invalid_params(reason),
+        _ => RpcError::internal_error(&e.to_string()),
+    }
+}
+
+async fn unlocked_pik(
+    state: &Arc<DaemonState>,
+) -> std::result::Result<ochra_crypto::ed25519::SigningKey, RpcError> {
+    crate::audit::pik_signing_key(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(RpcError::session_locked)
+}
+
+/// Describe what backing up under `scope` exposes, to show before enabling.
+pub async fn get_whisper_backup_exposure(_state: &Arc<DaemonState>, params: &Value) -> Result {
+    Ok(serde_json::json!(backup_scope(params)?.exposure()))
+}
+
+/// Get whether session backups are enabled and when they last ran.
+pub async fn get_whisper_backup_status(state: &Arc<DaemonState>) -> Result {
+    let stored = crate::whisper_backup::load(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    let (sessions, checkpoints) = {
+        let backup_state = state.whisper_backup.lock().await;
+        (
+            backup_state.log.session_count(),
+            backup_state.log.snapshot().len(),
+        )
+    };
+    Ok(serde_json::json!({
+        "enabled": stored.is_some(),
+        "policy": stored.as_ref().map(|s| s.policy),
+        "exposure": stored.as_ref().map(|s| s.policy.scope.exposure()),
+        "last_export_at": stored.as_ref().and_then(|s| s.last_export_at),
+        "sessions": sessions,
+        "checkpoints": checkpoints,
+    }))
+}
+
+/// Enable session backups.
+///
+/// The caller must echo the exposure of the chosen scope (from
+/// `get_whisper_backup_exposure`) as `acknowledged_exposure`; otherwise
+/// BACKUP_EXPOSURE_NOT_ACKNOWLEDGED returns the exposure to show.
+pub async fn enable_whisper_backup(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let passphrase = params
+        .get("passphrase")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("passphrase required"))?;
+    let scope = backup_scope(params)?;
+    let destination: BackupDestination = match params.get("destination") {
+        None | Some(Value::Null) => BackupDestination::Local,
+        Some(v) => serde_json::from_value(v.clone())
+            .map_err(|_| RpcError::invalid_params("destination must be local or dead_drop"))?,
+    };
+    let interval_secs = params
+        .get("interval_secs")
         .and_then(|v| v.as_u64())
-        .ok_or_else(|| RpcError::invalid_params("up_to_sequence required"))?;
-    Ok(serde_json::json!({"sent": true}))
+        .unwrap_or(crate::whisper_backup::DEFAULT_INTERVAL_SECS);
+
+    let exposure = scope.exposure();
+    let acknowledged = params.get("acknowledged_exposure");
+    let matches_ack = acknowledged.is_some_and(|ack| {
+        ack.get("checkpoints_per_session").and_then(|v| v.as_u64())
+            == Some(exposure.checkpoints_per_session as u64)
+            && ack
+                .get("exposes_earlier_messages")
+                .and_then(|v| v.as_bool())
+                == Some(exposure.exposes_earlier_messages)
+    });
+    if !matches_ack {
+        return Err(RpcError {
+            code: -32091,
+            message: "BACKUP_EXPOSURE_NOT_ACKNOWLEDGED".to_string(),
+            data: Some(serde_json::json!({"exposure": exposure})),
+        });
+    }
+    let policy = BackupPolicy::new(scope, destination, interval_secs, &exposure)
+        .map_err(|e| RpcError::invalid_params(&e.to_string()))?;
+
+    let pik = unlocked_pik(state).await?;
+    let summary = crate::whisper_backup::enable(state, &pik, passphrase.to_string(), policy)
+        .await
+        .map_err(|e| backup_error(&e))?;
+    Ok(serde_json::json!(summary))
+}
+
+/// Disable session backups and delete the local backup file.
+pub async fn disable_whisper_backup(state: &Arc<DaemonState>) -> Result {
+    let disabled = crate::whisper_backup::disable(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+    Ok(serde_json::json!({"disabled": disabled}))
+}
+
+/// Export a session backup now instead of waiting for the interval.
+pub async fn export_whisper_backup(state: &Arc<DaemonState>) -> Result {
+    let stored = crate::whisper_backup::load(state)
+        .await
+        .map_err(|e| RpcError::internal_error(&e.to_string()))?
+        .ok_or_else(|| RpcError::invalid_params("session backups are not enabled"))?;
+    let pik = unlocked_pik(state).await?;
+    let summary = crate::whisper_backup::export(state, &pik, stored)
+        .await
+        .map_err(|e| backup_error(&e))?;
+    Ok(serde_json::json!(summary))
+}
+
+/// Restore session checkpoints from a backup.
+///
+/// Each session is reopened from its newest checkpoint and keeps sending
+/// after the last sequence it backed up.
+pub async fn restore_whisper_backup(state: &Arc<DaemonState>, params: &Value) -> Result {
+    let passphrase = params
+        .get("passphrase")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("passphrase required"))?;
+    let source = params
+        .get("source")
+        .and_then(|v| v.as_str())
+        .unwrap_or("local");
+    let pik = unlocked_pik(state).await?;
+
+    let sealed = match source {
+        "local" => {
+            let path = params
+                .get("path")
+                .and_then(|v| v.as_str())
+                .map(std::path::PathBuf::from);
+            crate::whisper_backup::read_local(state, path)
+                .await
+                .map_err(|e| RpcError::invalid_params(&format!("cannot read backup: {e}")))?
+        }
+        "dead_drop" => {
+            let _address = ochra_whisper::backup::dead_drop_address(&pik.to_bytes());
+            // Would: fetch the record at _address and its shards over Sphinx
+            // and open them with sealed_from_dead_drop
+            return Err(RpcError::internal_error("dead drop lookups unavailable"));
+        }
+        _ => {
+            return Err(RpcError::invalid_params(
+                "source must be local or dead_drop",
+            ))
+        }
+    };
+    let created_at = sealed.created_at;
+    let (scope, checkpoints) =
+        crate::whisper_backup::restore(state, &pik, passphrase.to_string(), sealed)
+            .await
+            .map_err(|e| backup_error(&e))?;
+    let sessions: std::collections::BTreeSet<[u8; 16]> =
+        checkpoints.iter().map(|c| c.session_id).collect();
+    Ok(serde_json::json!({
+        "scope": scope,
+        "created_at": created_at,
+        "sessions": sessions.len(),
+        "checkpoints": checkpoints.len(),
+    }))
+}
+
+fn parse_session_id(params: &Value) -> std::result::Result<[u8; 16], RpcError> {
+    params
+        .get("session_id")
+        .and_then(|v| v.as_str())
+        .and_then(|s| hex::decode(s).ok())
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| RpcError::invalid_params("session_id must be 16 hex-encoded bytes"))
+}
+
+fn optional_contact(params: &Value) -> std::result::Result<Option<[u8; 32]>, RpcError> {
+    let Some(hex_str) = params.get("contact_pik_hash").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    hex::decode(hex_str)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .map(Some)
+        .ok_or_else(|| RpcError::invalid_params("contact_pik_hash must be 32 hex-encoded bytes"))
 }
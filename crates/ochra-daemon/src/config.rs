@@ -1,9 +1,18 @@
 //! Configuration file management (Section 33).
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+use ochra_dht::private_lookup::{LookupPrivacy, PrivacyLevel};
+use ochra_onion::isolation::{IsolationMode, IsolationPolicy};
+use ochra_storage::earning::EarningLevel;
+use ochra_transport::proxy::ProxyRules;
+use ochra_transport::TransportError;
+use ochra_types::profile::{NetworkKind, NetworkProfile, ProfileOverrides};
 use serde::{Deserialize, Serialize};
 
+use crate::mode::NodeMode;
+
 /// Complete daemon configuration (Section 33).
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DaemonConfig {
@@ -22,6 +31,9 @@ pub struct DaemonConfig {
     /// Advanced settings.
     #[serde(default)]
     pub advanced: AdvancedConfig,
+    /// Power profile settings.
+    #[serde(default)]
+    pub power: PowerConfig,
 }
 
 /// Network configuration.
@@ -30,15 +42,86 @@ pub struct NetworkConfig {
     /// 0 = OS-assigned ephemeral port.
     #[serde(default)]
     pub listen_port: u16,
-    /// Bootstrap seed nodes.
-    #[serde(default = "default_bootstrap_nodes")]
+    /// Network to join: "mainnet" | "testnet" | "devnet". Empty = mainnet.
+    #[serde(default)]
+    pub profile: String,
+    /// Parameter overrides, only accepted on a devnet.
+    #[serde(default)]
+    pub profile_overrides: ProfileOverrides,
+    /// Bootstrap seed nodes. Empty = the network's seeds.
+    #[serde(default)]
     pub bootstrap_nodes: Vec<String>,
+    /// Domains whose TXT records list seed nodes, tried after the signed
+    /// bootstrap lists. Empty = the network's seed domains.
+    #[serde(default)]
+    pub dns_seeds: Vec<String>,
+    /// HTTPS URLs of bootstrap lists signed by the project key.
+    #[serde(default)]
+    pub bootstrap_list_urls: Vec<String>,
+    /// Hex Ed25519 project key that signs bootstrap lists. Empty = lists
+    /// are not used, unless a devnet profile override sets a key.
+    #[serde(default)]
+    pub bootstrap_list_key: String,
+    /// Accept bootstrap peers only when independent seed sources agree on
+    /// the network around this node (Section 5.2). Unset = connect to
+    /// every seed that answers.
+    #[serde(default)]
+    pub bootstrap_agreement: Option<ochra_dht::agreement::AgreementPolicy>,
     /// Maximum concurrent QUIC connections.
     #[serde(default = "default_max_connections")]
     pub max_connections: u32,
-    /// Participate as a relay for others.
+    /// Participate as a relay for others. Only consulted when `mode` is
+    /// empty: `false` means "client_only", `true` means "relay_storage".
     #[serde(default = "default_true")]
     pub relay_enabled: bool,
+    /// Operating mode: "client_only" | "relay" | "relay_storage" |
+    /// "quorum_candidate". A mode set over RPC takes precedence. Any other
+    /// value fails config load.
+    #[serde(default)]
+    pub mode: String,
+    /// Accept TCP/TLS on the listen port and fall back to it when QUIC is
+    /// blocked.
+    #[serde(default = "default_true")]
+    pub tcp_fallback: bool,
+    /// SOCKS5 proxy for TCP/TLS connections (e.g. "127.0.0.1:9050" for Tor).
+    /// Empty = connect directly. Superseded by `proxy`.
+    #[serde(default)]
+    pub socks5_proxy: String,
+    /// Proxy for all outbound TCP connections, as a `socks5://`,
+    /// `socks5h://`, or `http://` URL with optional `user:pass@`. Empty =
+    /// use `socks5_proxy`. The `OCHRA_PROXY` environment variable overrides it.
+    #[serde(default)]
+    pub proxy: String,
+    /// Per-destination overrides: host pattern (`*.onion`, `.corp.example`,
+    /// `192.0.2.1`) to proxy URL or "direct".
+    #[serde(default)]
+    pub proxy_overrides: BTreeMap<String, String>,
+    /// Ask network crawlers not to walk this node (sets the crawl opt-out
+    /// capability feature).
+    #[serde(default)]
+    pub crawl_opt_out: bool,
+    /// Bandwidth percentages for the control, Whisper, DHT, and bulk
+    /// priority lanes when all are busy. Must sum to 100.
+    #[serde(default)]
+    pub lane_shares: ochra_transport::qos::LaneShares,
+    /// Exit policy for this node's relay descriptor, enforced when it is a
+    /// circuit's exit and reported by `get_node_mode`. Default = deliver
+    /// everything.
+    #[serde(default)]
+    pub exit_policy: ochra_types::network::ExitPolicy,
+    /// MaxMind DB files (GeoLite2-ASN, GeoLite2-Country or combined) used
+    /// to resolve relay AS numbers and countries. Earlier files take
+    /// precedence. Empty = use the values relays report themselves.
+    #[serde(default)]
+    pub geoip_databases: Vec<String>,
+    /// Poll the exchange tickers for the reference price (Section 11.7)
+    /// through the egress proxy. Off = report the v1 fixed rate only.
+    #[serde(default)]
+    pub oracle_polling: bool,
+    /// Concurrent outbound dial limits, overall and per subsystem (DHT,
+    /// content, circuit, gossip, other).
+    #[serde(default)]
+    pub dial_limits: ochra_transport::dial::DialQuotas,
 }
 
 /// Storage configuration.
@@ -70,6 +153,10 @@ pub struct IdentityConfig {
     /// Biometric authentication enabled.
     #[serde(default)]
     pub biometric_enabled: bool,
+    /// Most memory Argon2id calibration may choose for password hashing,
+    /// in MB. 0 = the spec default of 256 MB.
+    #[serde(default)]
+    pub kdf_memory_limit_mb: u32,
 }
 
 /// Privacy configuration.
@@ -81,6 +168,23 @@ pub struct PrivacyConfig {
     /// Enforce >= 2 countries per circuit.
     #[serde(default = "default_true")]
     pub relay_country_diversity: bool,
+    /// Circuit isolation for Whisper: "shared" | "per_kind" | "per_context".
+    #[serde(default = "default_per_context")]
+    pub whisper_isolation: String,
+    /// Circuit isolation for group traffic.
+    #[serde(default = "default_per_context")]
+    pub group_isolation: String,
+    /// Circuit isolation for content downloads.
+    #[serde(default = "default_per_kind")]
+    pub download_isolation: String,
+    /// Routing of DHT value lookups: "direct" | "circuit" |
+    /// "circuit_required". An unknown level fails config load rather than
+    /// weakening lookup privacy.
+    #[serde(default = "default_get_privacy")]
+    pub dht_get_privacy: PrivacyLevel,
+    /// Routing of DHT node lookups.
+    #[serde(default = "default_find_node_privacy")]
+    pub dht_find_node_privacy: PrivacyLevel,
 }
 
 /// Advanced configuration.
@@ -95,17 +199,28 @@ pub struct AdvancedConfig {
     /// Log file path. Empty = stderr.
     #[serde(default)]
     pub log_file: String,
+    /// Hex Ed25519 keys of the five governance keyholders, by index.
+    #[serde(default)]
+    pub upgrade_keyholders: Vec<String>,
 }
 
-// Default value functions
-
-fn default_bootstrap_nodes() -> Vec<String> {
-    vec![
-        "198.51.100.1:4433".to_string(),
-        "198.51.100.2:4433".to_string(),
-    ]
+/// Power profile configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerConfig {
+    /// Initial power mode: "auto" | "normal" | "low_power". A mode set over
+    /// RPC takes precedence.
+    #[serde(default = "default_power_mode")]
+    pub mode: String,
+    /// In auto mode, switch to low-power while running on battery.
+    #[serde(default = "default_true")]
+    pub low_power_on_battery: bool,
+    /// In auto mode, switch to low-power on metered networks.
+    #[serde(default = "default_true")]
+    pub low_power_on_metered: bool,
 }
 
+// Default value functions
+
 fn default_max_connections() -> u32 {
     256
 }
@@ -130,13 +245,50 @@ fn default_log_level() -> String {
     "info".to_string()
 }
 
+fn default_per_context() -> String {
+    "per_context".to_string()
+}
+
+fn default_per_kind() -> String {
+    "per_kind".to_string()
+}
+
+fn default_get_privacy() -> PrivacyLevel {
+    LookupPrivacy::default().get
+}
+
+fn default_find_node_privacy() -> PrivacyLevel {
+    LookupPrivacy::default().find_node
+}
+
+fn default_power_mode() -> String {
+    "auto".to_string()
+}
+
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             listen_port: 0,
-            bootstrap_nodes: default_bootstrap_nodes(),
+            profile: String::new(),
+            profile_overrides: ProfileOverrides::default(),
+            bootstrap_nodes: Vec::new(),
+            dns_seeds: Vec::new(),
+            bootstrap_list_urls: Vec::new(),
+            bootstrap_list_key: String::new(),
+            bootstrap_agreement: None,
             max_connections: default_max_connections(),
             relay_enabled: true,
+            mode: String::new(),
+            tcp_fallback: true,
+            socks5_proxy: String::new(),
+            proxy: String::new(),
+            proxy_overrides: BTreeMap::new(),
+            crawl_opt_out: false,
+            lane_shares: ochra_transport::qos::LaneShares::default(),
+            exit_policy: ochra_types::network::ExitPolicy::default(),
+            geoip_databases: Vec::new(),
+            oracle_polling: false,
+            dial_limits: ochra_transport::dial::DialQuotas::default(),
         }
     }
 }
@@ -158,6 +310,7 @@ impl Default for IdentityConfig {
         Self {
             session_timeout_minutes: default_session_timeout(),
             biometric_enabled: false,
+            kdf_memory_limit_mb: 0,
         }
     }
 }
@@ -167,6 +320,11 @@ impl Default for PrivacyConfig {
         Self {
             cover_traffic_enabled: true,
             relay_country_diversity: true,
+            whisper_isolation: default_per_context(),
+            group_isolation: default_per_context(),
+            download_isolation: default_per_kind(),
+            dht_get_privacy: default_get_privacy(),
+            dht_find_node_privacy: default_find_node_privacy(),
         }
     }
 }
@@ -177,6 +335,88 @@ impl Default for AdvancedConfig {
             advanced_mode: false,
             log_level: default_log_level(),
             log_file: String::new(),
+            upgrade_keyholders: Vec::new(),
+        }
+    }
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            mode: default_power_mode(),
+            low_power_on_battery: true,
+            low_power_on_metered: true,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// The operating mode, derived from `relay_enabled` when `mode` is
+    /// empty.
+    ///
+    /// Fails if `mode` names no operating mode.
+    pub fn node_mode(&self) -> anyhow::Result<NodeMode> {
+        if self.mode.is_empty() {
+            return Ok(if self.relay_enabled {
+                NodeMode::RelayStorage
+            } else {
+                NodeMode::ClientOnly
+            });
+        }
+        NodeMode::parse(&self.mode).ok_or_else(|| {
+            anyhow::anyhow!(
+                "unknown network.mode {:?}: expected client_only, relay, relay_storage or quorum_candidate",
+                self.mode
+            )
+        })
+    }
+
+    /// Proxy rules from the config file and the proxy environment
+    /// variables, read through `var`.
+    pub fn proxy_rules(
+        &self,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<ProxyRules, TransportError> {
+        let default = if self.proxy.is_empty() {
+            &self.socks5_proxy
+        } else {
+            &self.proxy
+        };
+        ProxyRules::parse(default, &self.proxy_overrides)?.with_env(var)
+    }
+}
+
+impl StorageConfig {
+    /// The earning level, with an unrecognized level treated as medium.
+    pub fn earning_level(&self) -> EarningLevel {
+        match self.earning_level.as_str() {
+            "low" => EarningLevel::Low,
+            "high" => EarningLevel::High,
+            "custom" => {
+                EarningLevel::Custom(u64::from(self.custom_allocation_gb) * 1024 * 1024 * 1024)
+            }
+            _ => EarningLevel::Medium,
+        }
+    }
+}
+
+impl PrivacyConfig {
+    /// The stream isolation policy, with unrecognized modes left at their
+    /// defaults.
+    pub fn isolation_policy(&self) -> IsolationPolicy {
+        let defaults = IsolationPolicy::default();
+        IsolationPolicy {
+            whisper: IsolationMode::parse(&self.whisper_isolation).unwrap_or(defaults.whisper),
+            groups: IsolationMode::parse(&self.group_isolation).unwrap_or(defaults.groups),
+            downloads: IsolationMode::parse(&self.download_isolation).unwrap_or(defaults.downloads),
+        }
+    }
+
+    /// The DHT lookup privacy levels.
+    pub fn lookup_privacy(&self) -> LookupPrivacy {
+        LookupPrivacy {
+            get: self.dht_get_privacy,
+            find_node: self.dht_find_node_privacy,
         }
     }
 }
@@ -190,16 +430,48 @@ impl DaemonConfig {
         if config_path.exists() {
             let content = std::fs::read_to_string(&config_path)?;
             let config: DaemonConfig = toml::from_str(&content)?;
+            config.network.node_mode()?;
             Ok(config)
         } else {
             Ok(Self::default())
         }
     }
 
-    /// Get the data directory path.
+    /// The network profile selected by `network.profile`, with devnet
+    /// overrides applied and the configured seeds taking the place of the
+    /// network's when set. The configured bootstrap list key is the only
+    /// one a compiled-in network uses.
+    pub fn network_profile(&self) -> anyhow::Result<NetworkProfile> {
+        let network = &self.network;
+        let kind = NetworkKind::parse(&network.profile)
+            .ok_or_else(|| anyhow::anyhow!("unknown network profile: {}", network.profile))?;
+        let mut profile = NetworkProfile::resolve(kind, &network.profile_overrides)?;
+        if !network.bootstrap_nodes.is_empty() {
+            profile.bootstrap_nodes = network.bootstrap_nodes.clone();
+        }
+        if !network.dns_seeds.is_empty() {
+            profile.dns_seeds = network.dns_seeds.clone();
+        }
+        if !network.bootstrap_list_key.is_empty() {
+            let key: [u8; 32] = hex::decode(&network.bootstrap_list_key)?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("bootstrap_list_key must be 32 bytes"))?;
+            profile.bootstrap_list_key = Some(key);
+        }
+        Ok(profile)
+    }
+
+    /// Get the data directory path. The default directory of a network
+    /// other than mainnet gets a subdirectory named after it, so networks
+    /// never share a database.
     pub fn data_dir(&self) -> PathBuf {
         if self.storage.data_dir.is_empty() {
-            Self::default_data_dir()
+            match NetworkKind::parse(&self.network.profile) {
+                Some(kind) if kind != NetworkKind::Mainnet => {
+                    Self::default_data_dir().join(kind.as_str())
+                }
+                _ => Self::default_data_dir(),
+            }
         } else {
             PathBuf::from(&self.storage.data_dir)
         }
@@ -248,15 +520,141 @@ fn dirs_fallback(subpath: &str) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ochra_transport::proxy::ProxyTarget;
 
     #[test]
     fn test_default_config() {
         let config = DaemonConfig::default();
         assert_eq!(config.network.listen_port, 0);
         assert!(config.network.relay_enabled);
+        assert!(config.network.tcp_fallback);
+        assert!(config.network.socks5_proxy.is_empty());
         assert_eq!(config.storage.earning_level, "medium");
+        assert_eq!(config.storage.earning_level(), EarningLevel::Medium);
         assert_eq!(config.identity.session_timeout_minutes, 15);
         assert!(config.privacy.cover_traffic_enabled);
+        assert_eq!(
+            config.privacy.isolation_policy(),
+            IsolationPolicy::default()
+        );
+        assert_eq!(config.privacy.lookup_privacy(), LookupPrivacy::default());
+        assert_eq!(config.power.mode, "auto");
+        assert!(config.power.low_power_on_battery);
+    }
+
+    #[test]
+    fn test_isolation_policy_falls_back_per_field() {
+        let privacy = PrivacyConfig {
+            whisper_isolation: "shared".to_string(),
+            group_isolation: "bogus".to_string(),
+            ..PrivacyConfig::default()
+        };
+        let policy = privacy.isolation_policy();
+        assert_eq!(policy.whisper, IsolationMode::Shared);
+        assert_eq!(policy.groups, IsolationPolicy::default().groups);
+    }
+
+    #[test]
+    fn test_lookup_privacy_rejects_unknown_level() {
+        let privacy: PrivacyConfig =
+            toml::from_str("dht_get_privacy = \"circuit_required\"").expect("parse");
+        let levels = privacy.lookup_privacy();
+        assert_eq!(levels.get, PrivacyLevel::CircuitRequired);
+        assert_eq!(levels.find_node, LookupPrivacy::default().find_node);
+        assert!(toml::from_str::<PrivacyConfig>("dht_get_privacy = \"circuit_requried\"").is_err());
+    }
+
+    #[test]
+    fn test_bootstrap_agreement_fills_defaults() {
+        let network: NetworkConfig = toml::from_str("").expect("parse");
+        assert_eq!(network.bootstrap_agreement, None);
+        let network: NetworkConfig =
+            toml::from_str("[bootstrap_agreement]\nmin_agreeing_sources = 3").expect("parse");
+        let policy = network.bootstrap_agreement.expect("policy");
+        assert_eq!(policy.min_agreeing_sources, 3);
+        assert_eq!(
+            policy.min_overlap_pct,
+            ochra_dht::agreement::DEFAULT_MIN_OVERLAP_PCT
+        );
+    }
+
+    #[test]
+    fn test_node_mode_falls_back_to_relay_enabled() {
+        let mut network = NetworkConfig::default();
+        assert_eq!(network.node_mode().expect("mode"), NodeMode::RelayStorage);
+        network.relay_enabled = false;
+        assert_eq!(network.node_mode().expect("mode"), NodeMode::ClientOnly);
+        network.mode = "quorum_candidate".to_string();
+        assert_eq!(
+            network.node_mode().expect("mode"),
+            NodeMode::QuorumCandidate
+        );
+    }
+
+    #[test]
+    fn test_unknown_node_mode_is_rejected() {
+        let network: NetworkConfig = toml::from_str("mode = \"everything\"").expect("parse");
+        assert!(network.node_mode().is_err());
+    }
+
+    #[test]
+    fn test_proxy_rules_fall_back_to_socks5_proxy() {
+        let mut network = NetworkConfig {
+            socks5_proxy: "127.0.0.1:9050".to_string(),
+            ..NetworkConfig::default()
+        };
+        network
+            .proxy_overrides
+            .insert("*.corp.example".to_string(), "direct".to_string());
+        let rules = network.proxy_rules(|_| None).expect("rules");
+        let target = |host: &str| ProxyTarget::Host(host.to_string(), 443);
+        assert_eq!(
+            rules.route(&target("seed.example")).map(|p| p.port),
+            Some(9050)
+        );
+        assert!(rules.route(&target("git.corp.example")).is_none());
+
+        network.proxy = "http://10.0.0.1:3128".to_string();
+        let env =
+            |name: &str| (name == "OCHRA_PROXY").then(|| "socks5://127.0.0.1:1080".to_string());
+        let rules = network.proxy_rules(env).expect("rules");
+        assert_eq!(
+            rules.route(&target("seed.example")).map(|p| p.port),
+            Some(1080)
+        );
+
+        network.proxy = "gopher://nope:70".to_string();
+        assert!(network.proxy_rules(|_| None).is_err());
+    }
+
+    #[test]
+    fn test_network_profile_selection() {
+        let mut config = DaemonConfig::default();
+        let profile = config.network_profile().expect("mainnet");
+        assert_eq!(profile, NetworkProfile::mainnet());
+        assert_eq!(config.data_dir(), DaemonConfig::default_data_dir());
+
+        config.network.profile = "testnet".to_string();
+        config.network.bootstrap_nodes = vec!["192.0.2.7:4433".to_string()];
+        let profile = config.network_profile().expect("testnet");
+        assert_eq!(profile.magic, NetworkProfile::testnet().magic);
+        assert_eq!(profile.bootstrap_nodes, config.network.bootstrap_nodes);
+        assert!(config.data_dir().ends_with("testnet"));
+
+        config.network.profile_overrides.epoch_duration_secs = Some(60);
+        config.network.profile_overrides.relay_epoch_duration_secs = Some(10);
+        assert!(config.network_profile().is_err());
+        config.network.profile = "devnet".to_string();
+        assert_eq!(
+            config
+                .network_profile()
+                .expect("devnet")
+                .epoch_duration_secs,
+            60
+        );
+
+        config.network.profile = "moonnet".to_string();
+        assert!(config.network_profile().is_err());
     }
 
     #[test]
@@ -0,0 +1,150 @@
+//! `ochra-daemon crawl`: network health snapshot (built with the `crawler`
+//! feature).
+//!
+//! Walks the DHT from the configured bootstrap nodes with a throwaway node
+//! ID, one capability exchange and `FIND_NODE` per peer, and prints the
+//! anonymized [`CrawlReport`](ochra_dht::crawl::CrawlReport) as JSON.
+//!
+//! Usage:
+//!   ochra-daemon crawl [--out <path>] [--max-nodes <n>] [--rate <queries/s>]
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::Context;
+use ochra_dht::crawl::{CrawlConfig, CrawlPeer, Crawler};
+use ochra_transport::capabilities::local_exchange;
+use ochra_transport::messages::{DhtFindNode, TypedMessage};
+use ochra_transport::quic::{QuicConfig, QuicNode};
+use ochra_transport::wire::ProtocolMessage;
+use tracing::{debug, info};
+
+use crate::config::DaemonConfig;
+
+/// Agent string the crawler advertises.
+const CRAWLER_AGENT: &str = concat!("ochra-crawler/", env!("CARGO_PKG_VERSION"));
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Run a crawl and write its report.
+pub async fn run(config: &DaemonConfig, args: Vec<String>) -> anyhow::Result<()> {
+    let mut crawl_config = CrawlConfig::default();
+    let mut out = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .with_context(|| format!("missing value for {arg}"))?;
+        match arg.as_str() {
+            "--out" => out = Some(value),
+            "--max-nodes" => crawl_config.max_nodes = value.parse()?,
+            "--rate" => crawl_config.queries_per_sec = value.parse()?,
+            other => anyhow::bail!("unknown argument {other}"),
+        }
+    }
+
+    let profile = config.network_profile()?;
+    let seeds: Vec<SocketAddr> = profile
+        .bootstrap_nodes
+        .iter()
+        .filter_map(|s| s.parse().ok())
+        .collect();
+    anyhow::ensure!(!seeds.is_empty(), "no usable bootstrap nodes configured");
+
+    let node = QuicNode::new(QuicConfig::default())?;
+    let node_id: [u8; 32] = rand::random();
+    let timeout = Duration::from_secs(ochra_dht::PING_TIMEOUT_SECS);
+    let mut crawler = Crawler::new(crawl_config, &seeds, now_ms());
+    info!("Crawling from {} bootstrap nodes", seeds.len());
+
+    while !crawler.is_done() {
+        let Some(peer) = crawler.next(now_ms()) else {
+            tokio::time::sleep(Duration::from_millis(crawler.wait_ms(now_ms()))).await;
+            continue;
+        };
+        match tokio::time::timeout(
+            timeout,
+            query(&node, node_id, profile.magic, &peer, &mut crawler),
+        )
+        .await
+        {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                debug!("Crawl query to {} failed: {}", peer.addr, e);
+                crawler.record_unreachable();
+            }
+            Err(_) => crawler.record_unreachable(),
+        }
+    }
+    node.close(0, b"crawl done");
+
+    let report = crawler.report(now_ms());
+    let json = serde_json::to_string_pretty(&report)?;
+    match out {
+        Some(path) => std::fs::write(&path, json)?,
+        None => println!("{json}"),
+    }
+    info!(
+        "Crawl finished: {} discovered, {} responsive",
+        report.nodes_discovered, report.nodes_responsive
+    );
+    Ok(())
+}
+
+/// Exchange capabilities with `peer` and ask for its neighbours.
+async fn query(
+    node: &QuicNode,
+    node_id: [u8; 32],
+    network_magic: u32,
+    peer: &CrawlPeer,
+    crawler: &mut Crawler,
+) -> anyhow::Result<()> {
+    let connection = node.connect(peer.addr, "ochra-node").await?;
+    let (mut send, mut recv) = QuicNode::open_bi(&connection).await?;
+
+    let hello =
+        TypedMessage::CapabilityExchange(local_exchange(node_id, 0, CRAWLER_AGENT, network_magic));
+    QuicNode::send_message(&mut send, &ProtocolMessage::from_typed(&hello)?.to_bytes()?).await?;
+    let (_, TypedMessage::CapabilityExchange(theirs)) =
+        QuicNode::recv_protocol_message(&mut recv).await?
+    else {
+        anyhow::bail!("expected capability exchange");
+    };
+    if theirs.network_magic != network_magic {
+        anyhow::bail!("peer is on another network");
+    }
+    if !crawler.record_hello(theirs.node_id, &theirs.agent, theirs.features) {
+        connection.close(0u32.into(), b"");
+        return Ok(());
+    }
+
+    // A random target makes each peer return neighbours from a different
+    // region of its routing table
+    let find = TypedMessage::DhtFindNode(DhtFindNode {
+        target: rand::random(),
+    });
+    QuicNode::send_message(&mut send, &ProtocolMessage::from_typed(&find)?.to_bytes()?).await?;
+    let (_, TypedMessage::DhtFindNodeResponse(found)) =
+        QuicNode::recv_protocol_message(&mut recv).await?
+    else {
+        anyhow::bail!("expected find node response");
+    };
+    let neighbours: Vec<CrawlPeer> = found
+        .nodes
+        .iter()
+        .filter_map(|n| {
+            Some(CrawlPeer {
+                node_id: Some(n.node_id),
+                addr: n.addr.parse().ok()?,
+            })
+        })
+        .collect();
+    crawler.record_neighbours(&theirs.node_id, &neighbours);
+    connection.close(0u32.into(), b"");
+    Ok(())
+}
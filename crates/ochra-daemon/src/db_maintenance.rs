@@ -0,0 +1,78 @@
+//! Scheduled database maintenance (Section 27.9).
+//!
+//! Runs the [`MaintenanceScheduler`] against the daemon's connection and
+//! reports what it finds as `DatabaseAnomaly` events, so corruption or a
+//! WAL that will not checkpoint surfaces before it causes failures. Each
+//! tick runs on a blocking thread; vacuums and integrity checks wait until
+//! nothing has been written for a while, so they rarely hold the
+//! connection while RPCs need it.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_db::maintenance::{MaintenanceReport, MaintenanceScheduler};
+use ochra_types::events::DaemonEvent;
+use tracing::{debug, error};
+
+use crate::DaemonState;
+
+/// Seconds between checks for due maintenance.
+const TICK_SECS: u64 = 60;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Log a report and emit its anomalies.
+fn report(state: &DaemonState, report: &MaintenanceReport) {
+    let task = report.task.as_str();
+    if report.anomalies.is_empty() {
+        debug!(
+            task,
+            freed_pages = report.freed_pages,
+            "Database maintenance done"
+        );
+    }
+    for anomaly in &report.anomalies {
+        error!(task, "Database maintenance found a problem: {}", anomaly);
+        state.event_bus.emit(DaemonEvent::DatabaseAnomaly {
+            task: task.to_string(),
+            detail: anomaly.to_string(),
+        });
+    }
+}
+
+/// Run due maintenance every [`TICK_SECS`] until shutdown.
+pub async fn run_scheduler(state: Arc<DaemonState>) {
+    let mut scheduler = MaintenanceScheduler::new();
+    let mut interval = tokio::time::interval(Duration::from_secs(TICK_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                let mut current = std::mem::take(&mut scheduler);
+                let db = state.db.clone();
+                let ticked = tokio::task::spawn_blocking(move || {
+                    let reports = current.tick(&db.blocking_lock(), now_secs());
+                    (current, reports)
+                })
+                .await;
+                let (current, reports) = match ticked {
+                    Ok(ticked) => ticked,
+                    Err(e) => {
+                        error!("Database maintenance task failed: {}", e);
+                        break;
+                    }
+                };
+                scheduler = current;
+                for r in &reports {
+                    report(&state, r);
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Whisper message delivery states (Section 7.4).
+//!
+//! Outbound messages are tracked by the in-memory
+//! [`Outbox`](ochra_whisper::delivery::Outbox), which resends unacknowledged
+//! messages with exponential backoff. Every state change is persisted to the
+//! `whisper_delivery` table and announced with a
+//! `WhisperDeliveryStateChanged` event.
+//!
+//! No rendezvous circuit is established yet, so a session's messages go to
+//! the peer's advertised mailboxes: each is framed as a `WhisperDeliver`,
+//! sealed to every linked device's mailbox, and only the sealed deposits
+//! are queued. A message is `sent` once a mailbox relay has taken one of
+//! its deposits; until then it stays `queued`. Delivery acks arrive as
+//! `WhisperAck`s, directly or through our own mailbox.
+//!
+//! No Double Ratchet runs yet, so a session's state is its route and next
+//! sequence. Each queued message records it as a backup checkpoint, and a
+//! restored checkpoint reopens the route where it left off.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_transport::messages::{TypedMessage, WhisperAck, WhisperDeliver, WhisperDeposit};
+use ochra_transport::wire::ProtocolMessage;
+use ochra_types::whisper::{HandleDescriptor, MailboxEntry, ThrottleStrictness};
+use ochra_whisper::backup::RatchetCheckpoint;
+use ochra_whisper::delivery::{DeliveryChange, SendAttempt};
+use ochra_whisper::mailbox::{DEFAULT_HOLD_HOURS, DEPOSIT_POW_DIFFICULTY};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Interval between outbox polls.
+const RETRY_POLL_INTERVAL_SECS: u64 = 1;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Where a session's messages are deposited, from the peer's handle
+/// descriptor.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Route {
+    mailboxes: Vec<MailboxEntry>,
+    strictness: ThrottleStrictness,
+    read_receipts: bool,
+}
+
+/// Routes of open sessions.
+pub type Routes = HashMap<[u8; 16], Route>;
+
+/// Persist state changes and emit an event for each.
+pub async fn apply(state: &DaemonState, changes: &[DeliveryChange]) -> anyhow::Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let now = now_secs();
+    {
+        let db = state.db.lock().await;
+        for change in changes {
+            ochra_db::queries::whisper_delivery::record(
+                &db,
+                &change.session_id,
+                change.sequence,
+                change.state,
+                change.attempts,
+                now,
+            )?;
+        }
+    }
+    for change in changes {
+        state
+            .event_bus
+            .emit(DaemonEvent::WhisperDeliveryStateChanged {
+                session_id: change.session_id,
+                sequence: change.sequence,
+                state: change.state,
+            });
+    }
+    Ok(())
+}
+
+/// Route a session's messages to the mailboxes in the peer's descriptor.
+///
+/// Returns `false`, routing nothing, if the peer advertises no mailbox.
+pub async fn open_session(
+    state: &DaemonState,
+    session_id: [u8; 16],
+    descriptor: &HandleDescriptor,
+) -> bool {
+    state
+        .outbox
+        .lock()
+        .await
+        .set_read_receipts(session_id, descriptor.privacy.read_receipts);
+    if descriptor.mailboxes.is_empty() {
+        return false;
+    }
+    state.whisper_routes.lock().await.insert(
+        session_id,
+        Route {
+            mailboxes: descriptor.mailboxes.clone(),
+            strictness: descriptor.strictness,
+            read_receipts: descriptor.privacy.read_receipts,
+        },
+    );
+    true
+}
+
+/// Reopen a session from a backup checkpoint taken by [`enqueue`].
+pub async fn resume_session(
+    state: &DaemonState,
+    checkpoint: &RatchetCheckpoint,
+) -> anyhow::Result<()> {
+    let route: Route = ochra_transport::cbor::from_slice(&checkpoint.ratchet_state)?;
+    {
+        let mut outbox = state.outbox.lock().await;
+        outbox.set_read_receipts(checkpoint.session_id, route.read_receipts);
+        outbox.resume(checkpoint.session_id, checkpoint.next_sequence);
+    }
+    state
+        .whisper_routes
+        .lock()
+        .await
+        .insert(checkpoint.session_id, route);
+    Ok(())
+}
+
+/// Seal `body` to the session peer's mailboxes and queue it for delivery.
+///
+/// Returns the message's sequence, or `None` if the session has no route.
+pub async fn enqueue(
+    state: &DaemonState,
+    session_id: [u8; 16],
+    body: &[u8],
+) -> anyhow::Result<Option<u64>> {
+    let Some(route) = state.whisper_routes.lock().await.get(&session_id).cloned() else {
+        return Ok(None);
+    };
+    let sequence = state.outbox.lock().await.reserve(session_id);
+    let ratchet_state = ochra_transport::cbor::to_vec(&route)?;
+    let message = TypedMessage::WhisperDeliver(WhisperDeliver {
+        session_id,
+        ciphertext: body.to_vec(),
+        ratchet_pk: [0u8; 32],
+        counter: u32::try_from(sequence)?,
+        previous_chain_length: 0,
+    });
+    let framed = ProtocolMessage::from_typed(&message)?.to_bytes()?;
+    let now = now_secs();
+    let deposits = tokio::task::spawn_blocking(move || {
+        ochra_whisper::mailbox::build_device_deposits(
+            &route.mailboxes,
+            &framed,
+            now,
+            DEFAULT_HOLD_HOURS * 3600,
+            DEPOSIT_POW_DIFFICULTY,
+            route.strictness,
+        )
+    })
+    .await??;
+    let payload = ochra_transport::cbor::to_vec(&deposits)?;
+    state
+        .outbox
+        .lock()
+        .await
+        .insert(session_id, sequence, payload, now);
+    crate::whisper_backup::checkpoint(
+        state,
+        RatchetCheckpoint {
+            session_id,
+            ratchet_state,
+            next_sequence: sequence + 1,
+            taken_at: now,
+        },
+    )
+    .await;
+    apply(
+        state,
+        &[DeliveryChange {
+            session_id,
+            sequence,
+            state: ochra_types::whisper::WhisperDeliveryState::Queued,
+            attempts: 0,
+        }],
+    )
+    .await?;
+    Ok(Some(sequence))
+}
+
+/// Hand an attempt's deposits to their mailbox relays. Succeeds if at
+/// least one relay took its deposit.
+async fn transmit(state: &DaemonState, attempt: &SendAttempt) -> anyhow::Result<()> {
+    let deposits: Vec<([u8; 32], WhisperDeposit)> =
+        ochra_transport::cbor::from_slice(&attempt.payload)?;
+    let mut delivered = 0;
+    for (relay, deposit) in deposits {
+        match crate::peer::send(state, &relay, &TypedMessage::WhisperDeposit(deposit)).await {
+            Ok(()) => delivered += 1,
+            Err(e) => debug!(
+                "Mailbox relay {} unreachable: {}",
+                hex::encode(&relay[..8]),
+                e
+            ),
+        }
+    }
+    if delivered == 0 {
+        anyhow::bail!("no mailbox relay reachable");
+    }
+    Ok(())
+}
+
+/// Handle a delivery ack from the peer.
+pub async fn handle_ack(state: &Arc<DaemonState>, ack: &WhisperAck) -> anyhow::Result<()> {
+    let changes = state
+        .outbox
+        .lock()
+        .await
+        .acknowledge(ack.session_id, u64::from(ack.acked_counter));
+    apply(state, &changes).await
+}
+
+/// Forget a closed session's delivery states.
+pub async fn close_session(state: &DaemonState, session_id: &[u8; 16]) -> anyhow::Result<()> {
+    state.outbox.lock().await.close_session(session_id);
+    state.whisper_routes.lock().await.remove(session_id);
+    ochra_db::queries::whisper_delivery::delete_session(&*state.db.lock().await, session_id)?;
+    Ok(())
+}
+
+/// Fail messages left pending by a previous run.
+///
+/// Their ciphertext was held in memory only, so they cannot be resent.
+pub async fn fail_stale(state: &DaemonState) -> anyhow::Result<usize> {
+    let failed =
+        ochra_db::queries::whisper_delivery::fail_pending(&*state.db.lock().await, now_secs())?;
+    Ok(failed)
+}
+
+/// Send queued messages and resend unacknowledged ones until shutdown.
+pub async fn run_retry(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(RETRY_POLL_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                let mut poll = state.outbox.lock().await.poll(now_secs());
+                for attempt in &poll.attempts {
+                    if let Err(e) = transmit(&state, attempt).await {
+                        debug!(
+                            "Whisper message {} of session {} not sent (attempt {}): {}",
+                            attempt.sequence,
+                            hex::encode(attempt.session_id),
+                            attempt.attempt,
+                            e
+                        );
+                        continue;
+                    }
+                    let sent = state
+                        .outbox
+                        .lock()
+                        .await
+                        .transmitted(attempt.session_id, attempt.sequence);
+                    poll.changes.extend(sent);
+                }
+                if let Err(e) = apply(&state, &poll.changes).await {
+                    error!("Failed to record Whisper delivery states: {}", e);
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
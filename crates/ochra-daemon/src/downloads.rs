@@ -0,0 +1,347 @@
+//! Resumable multi-source content downloads (Section 14.8).
+//!
+//! Each active download keeps a [`DownloadPlan`] in memory and its verified
+//! chunk indices in the `download_chunks` table. Verified chunks are written
+//! at their final offset into `<destination>.part` and synced to disk before
+//! being recorded, so after a pause or crash the plan is rebuilt from the
+//! database and only the missing chunks are fetched again. When the last
+//! chunk is verified the part file is renamed to the destination.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_db::queries::downloads as db;
+use ochra_storage::download::{DownloadPlan, CHUNK_REQUEST_TIMEOUT_SECS};
+use ochra_transport::messages::ChunkResponse;
+use ochra_types::layout::{DownloadProgress, DownloadState};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Interval between scheduling passes.
+const SCHEDULE_INTERVAL_SECS: u64 = 5;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// A download currently being fetched.
+#[derive(Debug)]
+pub struct ActiveDownload {
+    plan: DownloadPlan,
+    destination: PathBuf,
+    total_size_bytes: u64,
+}
+
+impl ActiveDownload {
+    fn progress(&self, state: DownloadState) -> DownloadProgress {
+        let chunks_complete = self.plan.completed_count();
+        let downloaded_bytes = if self.plan.is_complete() {
+            self.total_size_bytes
+        } else {
+            DownloadPlan::chunk_offset(chunks_complete).min(self.total_size_bytes)
+        };
+        DownloadProgress {
+            content_hash: *self.plan.content_hash(),
+            total_bytes: self.total_size_bytes,
+            downloaded_bytes,
+            chunks_complete,
+            chunks_total: self.plan.chunk_count(),
+            state,
+            error: None,
+        }
+    }
+}
+
+/// Active downloads keyed by content hash.
+pub type Downloads = HashMap<[u8; 32], ActiveDownload>;
+
+fn part_path(destination: &Path) -> PathBuf {
+    let mut name = destination.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+fn load(row: &db::DownloadRow, completed: &[u32]) -> anyhow::Result<ActiveDownload> {
+    let content_hash: [u8; 32] = row
+        .content_hash
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("stored content_hash is not 32 bytes"))?;
+    let plan = DownloadPlan::resume(content_hash, row.leaves()?, completed)?;
+    Ok(ActiveDownload {
+        plan,
+        destination: PathBuf::from(&row.destination),
+        total_size_bytes: row.total_size_bytes,
+    })
+}
+
+/// Start a new download, or continue an existing one with its saved progress.
+#[allow(dead_code)]
+pub async fn start(
+    state: &Arc<DaemonState>,
+    content_hash: [u8; 32],
+    leaves: Vec<[u8; 32]>,
+    destination: &str,
+    total_size_bytes: u64,
+) -> anyhow::Result<DownloadProgress> {
+    // Checks leaves against the Merkle root before anything is persisted.
+    DownloadPlan::new(content_hash, leaves.clone())?;
+
+    let completed = {
+        let conn = state.db.lock().await;
+        db::start(
+            &conn,
+            &content_hash,
+            destination,
+            &leaves,
+            total_size_bytes,
+            now_secs(),
+        )?;
+        db::completed_chunks(&conn, &content_hash)?
+    };
+    let download = ActiveDownload {
+        plan: DownloadPlan::resume(content_hash, leaves, &completed)?,
+        destination: PathBuf::from(destination),
+        total_size_bytes,
+    };
+    // Would: look up chunk providers at BLAKE3::hash("chunk-loc" || chunk_id)
+    let progress = download.progress(DownloadState::Downloading);
+    state.downloads.lock().await.insert(content_hash, download);
+    Ok(progress)
+}
+
+/// Reactivate a paused or interrupted download from the database.
+///
+/// Returns `None` if the content has never been downloaded.
+pub async fn resume(
+    state: &Arc<DaemonState>,
+    content_hash: [u8; 32],
+) -> anyhow::Result<Option<DownloadProgress>> {
+    if let Some(active) = state.downloads.lock().await.get(&content_hash) {
+        return Ok(Some(active.progress(DownloadState::Downloading)));
+    }
+
+    let (row, completed) = {
+        let conn = state.db.lock().await;
+        let Some(row) = db::get(&conn, &content_hash)? else {
+            return Ok(None);
+        };
+        let completed = db::completed_chunks(&conn, &content_hash)?;
+        if row.status != db::STATUS_COMPLETE {
+            db::set_status(&conn, &content_hash, db::STATUS_ACTIVE, now_secs())?;
+        }
+        (row, completed)
+    };
+    let download = load(&row, &completed)?;
+    if row.status == db::STATUS_COMPLETE {
+        return Ok(Some(download.progress(DownloadState::Complete)));
+    }
+    let progress = download.progress(DownloadState::Downloading);
+    state.downloads.lock().await.insert(content_hash, download);
+    Ok(Some(progress))
+}
+
+/// Reload every download that was active when the daemon stopped.
+pub async fn resume_all(state: &Arc<DaemonState>) -> anyhow::Result<usize> {
+    let rows = {
+        let conn = state.db.lock().await;
+        let rows = db::list_by_status(&conn, db::STATUS_ACTIVE)?;
+        let mut loaded = Vec::with_capacity(rows.len());
+        for row in rows {
+            let hash: [u8; 32] = match row.content_hash.as_slice().try_into() {
+                Ok(hash) => hash,
+                Err(_) => continue,
+            };
+            let completed = db::completed_chunks(&conn, &hash)?;
+            loaded.push((hash, row, completed));
+        }
+        loaded
+    };
+
+    let mut downloads = state.downloads.lock().await;
+    for (hash, row, completed) in &rows {
+        match load(row, completed) {
+            Ok(download) => {
+                downloads.insert(*hash, download);
+            }
+            Err(e) => warn!("Skipping unreadable download {}: {}", hex::encode(hash), e),
+        }
+    }
+    Ok(downloads.len())
+}
+
+/// Pause a download, keeping its verified chunks.
+///
+/// Returns `false` if no such download is tracked.
+pub async fn pause(state: &Arc<DaemonState>, content_hash: &[u8; 32]) -> anyhow::Result<bool> {
+    let removed = state.downloads.lock().await.remove(content_hash);
+    let conn = state.db.lock().await;
+    match db::get(&conn, content_hash)? {
+        Some(row) if row.status == db::STATUS_ACTIVE => {
+            db::set_status(&conn, content_hash, db::STATUS_PAUSED, now_secs())?;
+            Ok(true)
+        }
+        Some(_) => Ok(removed.is_some()),
+        None => Ok(false),
+    }
+}
+
+/// Current progress of a tracked download.
+#[allow(dead_code)]
+pub async fn progress(
+    state: &Arc<DaemonState>,
+    content_hash: &[u8; 32],
+) -> Option<DownloadProgress> {
+    state
+        .downloads
+        .lock()
+        .await
+        .get(content_hash)
+        .map(|d| d.progress(DownloadState::Downloading))
+}
+
+/// Accept a whole chunk `provider` sent, matching it to an active download
+/// by its Merkle leaf.
+pub async fn receive_chunk(
+    state: &Arc<DaemonState>,
+    provider: [u8; 32],
+    response: &ChunkResponse,
+) -> anyhow::Result<()> {
+    if response.offset != 0 || response.data.len() as u64 != response.total_size {
+        anyhow::bail!("partial chunk responses are not supported");
+    }
+    let leaf = ochra_crypto::blake3::merkle_leaf(&response.data);
+    let target = state
+        .downloads
+        .lock()
+        .await
+        .iter()
+        .find_map(|(hash, download)| {
+            let index = download.plan.leaves().iter().position(|l| *l == leaf)?;
+            Some((*hash, u32::try_from(index).ok()?))
+        });
+    let Some((content_hash, index)) = target else {
+        anyhow::bail!("chunk belongs to no active download");
+    };
+    accept_chunk(state, &content_hash, index, &provider, &response.data).await?;
+    Ok(())
+}
+
+/// Accept a chunk fetched from `provider`.
+///
+/// The chunk is verified against its Merkle leaf, written and synced to the
+/// part file, then recorded in the database, and its receipt acknowledgement
+/// is queued for the provider.
+pub async fn accept_chunk(
+    state: &Arc<DaemonState>,
+    content_hash: &[u8; 32],
+    index: u32,
+    provider: &[u8; 32],
+    data: &[u8],
+) -> anyhow::Result<DownloadProgress> {
+    let part = {
+        let mut downloads = state.downloads.lock().await;
+        let download = downloads
+            .get_mut(content_hash)
+            .ok_or_else(|| anyhow::anyhow!("download not active"))?;
+        if !download.plan.accept(index, provider, data)? {
+            return Ok(download.progress(DownloadState::Downloading));
+        }
+        part_path(&download.destination)
+    };
+    state
+        .uploads
+        .lock()
+        .await
+        .record_served_to_us(*provider, data.len() as u64);
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&part)
+        .await?;
+    file.seek(std::io::SeekFrom::Start(DownloadPlan::chunk_offset(index)))
+        .await?;
+    file.write_all(data).await?;
+    file.sync_data().await?;
+
+    {
+        let conn = state.db.lock().await;
+        db::record_chunk(&conn, content_hash, index, provider, now_secs())?;
+    }
+    if let Err(e) = crate::receipt_acks::queue(
+        state,
+        *provider,
+        ochra_crypto::blake3::hash(data),
+        u32::try_from(data.len())?,
+    )
+    .await
+    {
+        warn!("Failed to queue receipt ack: {}", e);
+    }
+
+    let mut downloads = state.downloads.lock().await;
+    let Some(download) = downloads.get(content_hash) else {
+        // Paused while the chunk was being written; progress is saved.
+        return Err(anyhow::anyhow!("download paused"));
+    };
+    if !download.plan.is_complete() {
+        let progress = download.progress(DownloadState::Downloading);
+        state
+            .event_bus
+            .emit(DaemonEvent::DownloadProgress(progress.clone()));
+        return Ok(progress);
+    }
+
+    tokio::fs::rename(&part, &download.destination).await?;
+    let progress = download.progress(DownloadState::Complete);
+    let leaves = download.plan.leaves().to_vec();
+    downloads.remove(content_hash);
+    drop(downloads);
+    state.announcer.lock().await.track(leaves);
+    let conn = state.db.lock().await;
+    db::set_status(&conn, content_hash, db::STATUS_COMPLETE, now_secs())?;
+    info!("Download {} complete", hex::encode(content_hash));
+    state
+        .event_bus
+        .emit(DaemonEvent::DownloadProgress(progress.clone()));
+    Ok(progress)
+}
+
+/// Hand out chunk requests for active downloads until shutdown.
+pub async fn run_scheduler(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULE_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                let now = now_secs();
+                let mut downloads = state.downloads.lock().await;
+                for download in downloads.values_mut() {
+                    download.plan.expire_requests(now, CHUNK_REQUEST_TIMEOUT_SECS);
+                    let assignments = download.plan.next_requests(now);
+                    if !assignments.is_empty() {
+                        // Would: send a ChunkRequest for each assignment over Sphinx
+                        let duplicates = assignments.iter().filter(|a| a.duplicate).count();
+                        debug!(
+                            "Requesting {} chunks of {} ({} endgame duplicates)",
+                            assignments.len(),
+                            hex::encode(download.plan.content_hash()),
+                            duplicates
+                        );
+                    }
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
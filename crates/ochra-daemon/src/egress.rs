@@ -0,0 +1,153 @@
+//! Outbound TCP for fetches outside the P2P transport.
+//!
+//! Signed bootstrap lists and oracle price sources are fetched over HTTPS.
+//! Those connections are opened here so they follow the same proxy rules as
+//! TCP/TLS peer connections (`[network] proxy`, `proxy_overrides`, and the
+//! `OCHRA_PROXY` / `NO_PROXY` environment variables). Hostnames are passed
+//! to the proxy unresolved, so DNS does not leak around it. Each connection
+//! takes a dial permit from the `other` subsystem quota first. Time spent
+//! connecting counts toward the calling RPC's `network` phase.
+//!
+//! [`get`] fetches an HTTPS URL over such a connection, checking the
+//! server certificate against the platform's trust store.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use http_body_util::{BodyExt, Empty, Limited};
+use hyper::body::Bytes;
+use ochra_transport::dial::{DialPriority, DialSubsystem};
+use ochra_transport::proxy::ProxyTarget;
+use rustls_platform_verifier::BuilderVerifierExt;
+use tokio::net::TcpStream;
+
+use crate::call_trace::{timed, Phase};
+use crate::DaemonState;
+
+/// User agent sent with HTTPS requests.
+const USER_AGENT: &str = concat!("ochra/", env!("CARGO_PKG_VERSION"));
+
+/// Seconds allowed for an HTTPS fetch, from connecting to the last byte.
+const FETCH_TIMEOUT_SECS: u64 = 30;
+
+/// Open a TCP connection to `host:port`, through its proxy if it has one.
+pub async fn connect(state: &DaemonState, host: &str, port: u16) -> anyhow::Result<TcpStream> {
+    let target = match host.parse() {
+        Ok(ip) => ProxyTarget::Addr(std::net::SocketAddr::new(ip, port)),
+        Err(_) => ProxyTarget::Host(host.to_string(), port),
+    };
+    let result = timed(Phase::Network, async {
+        let permit = state
+            .dials
+            .acquire(DialSubsystem::Other, DialPriority::Normal)
+            .await?;
+        let result = state.egress.connect(&target).await;
+        match &result {
+            Ok(_) => permit.succeeded(),
+            Err(_) => permit.failed(),
+        }
+        anyhow::Ok(result?)
+    })
+    .await;
+    result.with_context(|| format!("connecting to {host}:{port}"))
+}
+
+/// Split an `https://` URL into host, port, and path with query.
+fn split_url(url: &str) -> anyhow::Result<(String, u16, String)> {
+    let rest = url
+        .strip_prefix("https://")
+        .ok_or_else(|| anyhow::anyhow!("not an https URL: {url}"))?;
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let path = if path.starts_with('?') {
+        format!("/{path}")
+    } else {
+        path.to_string()
+    };
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(v6) => {
+            let (host, rest) = v6
+                .split_once(']')
+                .ok_or_else(|| anyhow::anyhow!("bad IPv6 host in {url}"))?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() || authority.contains('@') {
+        anyhow::bail!("bad host in {url}");
+    }
+    let port = match port {
+        Some(port) => port.parse().with_context(|| format!("bad port in {url}"))?,
+        None => 443,
+    };
+    Ok((host.to_string(), port, path))
+}
+
+/// Fetch `url` over HTTPS and return a body of at most `max_len` bytes.
+pub async fn get(state: &DaemonState, url: &str, max_len: usize) -> anyhow::Result<Vec<u8>> {
+    let (host, port, path) = split_url(url)?;
+    let fetch = async {
+        let tcp = connect(state, &host, port).await?;
+        let tls_config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()?
+        .with_platform_verifier()?
+        .with_no_client_auth();
+        let server_name = rustls::pki_types::ServerName::try_from(host.clone())?;
+        let tls = tokio_rustls::TlsConnector::from(Arc::new(tls_config))
+            .connect(server_name, tcp)
+            .await?;
+
+        let (mut sender, connection) =
+            hyper::client::conn::http1::handshake(hyper_util::rt::TokioIo::new(tls)).await?;
+        tokio::spawn(connection);
+        let request = hyper::Request::get(path)
+            .header(hyper::header::HOST, &host)
+            .header(hyper::header::USER_AGENT, USER_AGENT)
+            .body(Empty::<Bytes>::new())?;
+        let response = sender.send_request(request).await?;
+        if !response.status().is_success() {
+            anyhow::bail!("HTTP {}", response.status());
+        }
+        let body = Limited::new(response.into_body(), max_len)
+            .collect()
+            .await
+            .map_err(|e| anyhow::anyhow!("reading body: {e}"))?;
+        Ok(body.to_bytes().to_vec())
+    };
+    tokio::time::timeout(Duration::from_secs(FETCH_TIMEOUT_SECS), fetch)
+        .await
+        .context("timed out")?
+        .with_context(|| format!("fetching {url}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_url() {
+        assert_eq!(
+            split_url("https://seeds.ochra.net/list.json").expect("url"),
+            ("seeds.ochra.net".to_string(), 443, "/list.json".to_string())
+        );
+        assert_eq!(
+            split_url("https://[2001:db8::1]:8443?v=2").expect("url"),
+            ("2001:db8::1".to_string(), 8443, "/?v=2".to_string())
+        );
+        assert_eq!(
+            split_url("https://example.org").expect("url").2,
+            "/".to_string()
+        );
+        assert!(split_url("http://example.org/").is_err());
+        assert!(split_url("https://user@example.org/").is_err());
+        assert!(split_url("https://example.org:x/").is_err());
+    }
+}
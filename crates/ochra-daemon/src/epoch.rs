@@ -3,50 +3,67 @@
 //! All periodic operations execute at epoch boundaries (00:00 UTC).
 //! This module manages the epoch scheduler.
 
+use std::sync::OnceLock;
+
+use ochra_types::profile::NetworkProfile;
 use tracing::info;
 
-/// Epoch duration in seconds (24 hours).
+/// Epoch duration in seconds (24 hours) on mainnet.
 pub const EPOCH_DURATION_SECS: u64 = 24 * 60 * 60;
 
-/// Relay epoch duration in seconds (1 hour).
+/// Relay epoch duration in seconds (1 hour) on mainnet.
 pub const RELAY_EPOCH_DURATION_SECS: u64 = 60 * 60;
 
-/// Get the current network epoch number.
-pub fn current_epoch() -> u64 {
-    let now = std::time::SystemTime::now()
+/// Epoch and relay epoch durations of the network profile in use.
+static DURATIONS: OnceLock<(u64, u64)> = OnceLock::new();
+
+/// Use `profile`'s epoch durations. Only the first call has an effect;
+/// before it the mainnet durations apply.
+pub fn init(profile: &NetworkProfile) {
+    let _ = DURATIONS.set((
+        profile.epoch_duration_secs,
+        profile.relay_epoch_duration_secs,
+    ));
+}
+
+/// Epoch duration in seconds on this node's network.
+pub fn epoch_duration_secs() -> u64 {
+    DURATIONS.get().map_or(EPOCH_DURATION_SECS, |d| d.0)
+}
+
+/// Relay epoch duration in seconds on this node's network.
+pub fn relay_epoch_duration_secs() -> u64 {
+    DURATIONS.get().map_or(RELAY_EPOCH_DURATION_SECS, |d| d.1)
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
-        .as_secs();
-    now / EPOCH_DURATION_SECS
+        .as_secs()
+}
+
+/// Get the current network epoch number.
+pub fn current_epoch() -> u64 {
+    now_secs() / epoch_duration_secs()
 }
 
 /// Get the current relay epoch number.
 pub fn current_relay_epoch() -> u64 {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    now / RELAY_EPOCH_DURATION_SECS
+    now_secs() / relay_epoch_duration_secs()
 }
 
 /// Get seconds until the next epoch boundary.
-#[allow(dead_code)]
 pub fn seconds_until_next_epoch() -> u64 {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    EPOCH_DURATION_SECS - (now % EPOCH_DURATION_SECS)
+    let duration = epoch_duration_secs();
+    duration - (now_secs() % duration)
 }
 
 /// Get seconds until the next relay epoch boundary.
 #[allow(dead_code)]
 pub fn seconds_until_next_relay_epoch() -> u64 {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    RELAY_EPOCH_DURATION_SECS - (now % RELAY_EPOCH_DURATION_SECS)
+    let duration = relay_epoch_duration_secs();
+    duration - (now_secs() % duration)
 }
 
 /// Epoch boundary operations to execute (Section 18.6).
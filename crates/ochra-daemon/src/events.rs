@@ -10,16 +10,7 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
 
-/// An event emitted by the daemon.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Event {
-    /// Event type name (e.g. "ContentPurchased", "DaemonStarted").
-    pub event_type: String,
-    /// Unix timestamp.
-    pub timestamp: u64,
-    /// Type-specific payload.
-    pub payload: serde_json::Value,
-}
+pub use ochra_types::events::{DaemonEvent, Event};
 
 /// Filter for event subscriptions.
 #[allow(dead_code)]
@@ -55,8 +46,15 @@ impl EventBus {
         }
     }
 
-    /// Emit an event to all subscribers.
-    pub fn emit(&self, event: Event) {
+    /// Emit an event to all subscribers, stamped with the current time.
+    pub fn emit(&self, event: DaemonEvent) {
+        let event = Event {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            event,
+        };
         self.sequence.fetch_add(1, Ordering::SeqCst);
         // Ignore send errors (no subscribers)
         let _ = self.sender.send(event);
@@ -79,16 +77,17 @@ impl EventFilter {
     pub fn matches(&self, event: &Event) -> bool {
         // Category filter
         if let Some(ref categories) = self.categories {
-            let event_category = categorize_event(&event.event_type);
+            let event_category = categorize_event(event.event.event_type());
             if !categories.contains(&event_category) {
                 return false;
             }
         }
 
-        // Group ID filter (check payload for group_id field)
+        // Group ID filter (events that concern a Space)
         if let Some(ref group_ids) = self.group_ids {
-            if let Some(gid) = event.payload.get("group_id").and_then(|v| v.as_str()) {
-                if !group_ids.iter().any(|id| id == gid) {
+            if let Some(gid) = event.event.group_id() {
+                let gid = hex::encode(gid);
+                if !group_ids.contains(&gid) {
                     return false;
                 }
             }
@@ -107,7 +106,8 @@ fn categorize_event(event_type: &str) -> String {
             || s.starts_with("Creator")
             || s.starts_with("Moderator")
             || s.starts_with("Settings")
-            || s.starts_with("Ownership") =>
+            || s.starts_with("Ownership")
+            || s.starts_with("Announcement") =>
         {
             "space".to_string()
         }
@@ -117,7 +117,8 @@ fn categorize_event(event_type: &str) -> String {
             || s.starts_with("Vys")
             || s.starts_with("Funds")
             || s.starts_with("Minting")
-            || s.starts_with("Collateral") =>
+            || s.starts_with("Collateral")
+            || s.starts_with("Supply") =>
         {
             "economy".to_string()
         }
@@ -135,14 +136,15 @@ mod tests {
         let bus = EventBus::new(16);
         let mut rx = bus.subscribe();
 
-        bus.emit(Event {
-            event_type: "DaemonStarted".to_string(),
-            timestamp: 1000,
-            payload: serde_json::json!({"version": "0.1.0"}),
+        bus.emit(DaemonEvent::DaemonStarted {
+            version: "0.1.0".to_string(),
+            epoch: 1,
+            posrv_score: 0.0,
         });
 
         let event = rx.try_recv().expect("receive event");
-        assert_eq!(event.event_type, "DaemonStarted");
+        assert_eq!(event.event.event_type(), "DaemonStarted");
+        assert!(event.timestamp > 0);
         assert_eq!(bus.sequence(), 1);
     }
 
@@ -155,20 +157,75 @@ mod tests {
         };
 
         let space_event = Event {
-            event_type: "MemberJoined".to_string(),
             timestamp: 1000,
-            payload: serde_json::json!({}),
+            event: DaemonEvent::MemberJoined {
+                group_id: [1; 32],
+                pik_hash: [2; 32],
+                display_name: "alice".to_string(),
+                role: ochra_types::identity::MemberRole::Member,
+            },
         };
         assert!(filter.matches(&space_event));
 
         let economy_event = Event {
-            event_type: "FundsReceived".to_string(),
             timestamp: 1000,
-            payload: serde_json::json!({}),
+            event: DaemonEvent::FundsReceived {
+                sender_pik: None,
+                amount: 5,
+                note: None,
+                tx_hash: [3; 32],
+            },
         };
         assert!(!filter.matches(&economy_event));
     }
 
+    #[test]
+    fn test_event_filter_group_ids() {
+        let filter = EventFilter {
+            categories: None,
+            group_ids: Some(vec![hex::encode([1u8; 32])]),
+            min_severity: None,
+        };
+        let in_group = |group_id| Event {
+            timestamp: 1000,
+            event: DaemonEvent::LayoutManifestUpdated {
+                group_id,
+                updated_by: [9; 32],
+            },
+        };
+        assert!(filter.matches(&in_group([1; 32])));
+        assert!(!filter.matches(&in_group([2; 32])));
+
+        // Events outside any Space pass the group filter.
+        let system_event = Event {
+            timestamp: 1000,
+            event: DaemonEvent::DaemonShuttingDown {
+                reason: "test".to_string(),
+            },
+        };
+        assert!(filter.matches(&system_event));
+    }
+
+    #[test]
+    fn test_event_wire_format() {
+        let event = Event {
+            timestamp: 1000,
+            event: DaemonEvent::ContentUpdated {
+                lineage_id: [0xaa; 32],
+                previous_content_hash: [0xbb; 32],
+                content_hash: [0xcc; 32],
+                version: 2,
+            },
+        };
+        let json = serde_json::to_value(&event).expect("serialize");
+        assert_eq!(json["event_type"], "ContentUpdated");
+        assert_eq!(json["timestamp"], 1000);
+        assert_eq!(json["payload"]["content_hash"], hex::encode([0xcc; 32]));
+        assert_eq!(json["payload"]["version"], 2);
+        let parsed: Event = serde_json::from_value(json).expect("deserialize");
+        assert_eq!(parsed, event);
+    }
+
     #[test]
     fn test_categorize_event() {
         assert_eq!(categorize_event("MemberJoined"), "space");
@@ -0,0 +1,58 @@
+//! GeoIP databases for relay AS numbers and countries.
+//!
+//! The files in `network.geoip_databases` back the relay cache's
+//! [`GeoResolver`](ochra_onion::geo::GeoResolver). They are re-read every
+//! [`GEO_REFRESH_INTERVAL_SECS`], so an operator can replace them without
+//! a restart, and every cached relay is resolved again.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_onion::geo::{MmdbResolver, GEO_REFRESH_INTERVAL_SECS};
+use tracing::{info, warn};
+
+use crate::DaemonState;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Open the configured databases, or `None` if none are configured.
+pub fn open(paths: &[String]) -> anyhow::Result<Option<Arc<MmdbResolver>>> {
+    if paths.is_empty() {
+        return Ok(None);
+    }
+    let resolver = MmdbResolver::open(paths)?;
+    info!(
+        "Resolving relay AS numbers and countries from {}",
+        resolver.database_types().join(", ")
+    );
+    Ok(Some(Arc::new(resolver)))
+}
+
+/// Reload the databases and re-resolve relays until shutdown.
+pub async fn run_refresher(state: Arc<DaemonState>, resolver: Arc<MmdbResolver>) {
+    state
+        .relays
+        .lock()
+        .await
+        .set_geo_resolver(resolver.clone(), now_secs());
+    let period = Duration::from_secs(GEO_REFRESH_INTERVAL_SECS);
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                // On failure the databases already loaded stay in use.
+                if let Err(e) = resolver.reload() {
+                    warn!("Failed to reload GeoIP databases: {}", e);
+                }
+                state.relays.lock().await.refresh_geo(now_secs());
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Gossip subsystem wiring (Section 4.3, message types 0x0060-0x0063).
+//!
+//! Owns the daemon's [`GossipRouter`], subscribes it to the well-known topics
+//! and joined Spaces' announcement topics, and drives the mesh heartbeat.
+//! Connected peers are mesh candidates for every well-known topic. Incoming
+//! payloads are validated per topic before the router scores the sender,
+//! and accepted ones are handed to their subsystem and forwarded to the
+//! mesh over QUIC. Messages from greylisted peers are ignored, and invalid
+//! payloads are reported as misbehavior.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_frost::quorum::KeySchedule;
+use ochra_mls::group::MlsCiphertext;
+use ochra_nullifier::gossip::GossipMessage as NullifierGossip;
+use ochra_posrv::uptime::UptimeAttestation;
+use ochra_storage::tombstone::Tombstone;
+use ochra_transport::gossip::{
+    announcement_topic_id, GossipControl, GossipRouter, GossipTopic, ReceiveOutcome,
+    HEARTBEAT_INTERVAL_MS,
+};
+use ochra_transport::messages::{GossipForward, GossipGraft, TypedMessage};
+use ochra_transport::misbehavior::Offense;
+use ochra_types::network::{EpochBeacon, EpochState, RelayDescriptor};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::DaemonState;
+
+/// Create a router subscribed to every well-known topic.
+pub fn new_router() -> Arc<Mutex<GossipRouter>> {
+    let mut router = GossipRouter::default();
+    for topic in GossipTopic::ALL {
+        router.subscribe(topic.topic_id());
+    }
+    Arc::new(Mutex::new(router))
+}
+
+/// Check that a gossip payload decodes as the type carried by its topic.
+pub fn validate_payload(topic: &[u8; 32], data: &[u8]) -> bool {
+    match GossipTopic::from_topic_id(topic) {
+        Some(GossipTopic::Nullifiers) => {
+            ochra_transport::cbor::from_slice::<NullifierGossip>(data).is_ok()
+        }
+        Some(GossipTopic::EpochState) => {
+            ochra_transport::cbor::from_slice::<EpochState>(data).is_ok()
+        }
+        Some(GossipTopic::RelayDescriptors) => {
+            ochra_transport::cbor::from_slice::<RelayDescriptor>(data).is_ok()
+        }
+        Some(GossipTopic::UptimeAttestations) => {
+            ochra_transport::cbor::from_slice::<UptimeAttestation>(data).is_ok()
+        }
+        Some(GossipTopic::Tombstones) => {
+            Tombstone::from_bytes(data).and_then(|t| t.verify()).is_ok()
+        }
+        Some(GossipTopic::EpochBeacons) => {
+            ochra_transport::cbor::from_slice::<EpochBeacon>(data).is_ok()
+        }
+        // Decoding checks the chain of endorsements
+        Some(GossipTopic::KeySchedule) => {
+            ochra_transport::cbor::from_slice::<KeySchedule>(data).is_ok()
+        }
+        // Space announcement topics carry MLS ciphertexts for their Space
+        None => ochra_transport::cbor::from_slice::<MlsCiphertext>(data)
+            .is_ok_and(|sealed| &announcement_topic_id(&sealed.group_id) == topic),
+    }
+}
+
+/// The offense a payload that failed [`validate_payload`] shows: a signed
+/// record that decodes but does not verify is a bad signature.
+fn invalid_offense(topic: &[u8; 32], data: &[u8]) -> Offense {
+    match GossipTopic::from_topic_id(topic) {
+        Some(GossipTopic::Tombstones) if Tombstone::from_bytes(data).is_ok() => {
+            Offense::InvalidSignature
+        }
+        _ => Offense::InvalidMessage,
+    }
+}
+
+/// Make a newly connected peer a mesh candidate for the well-known topics.
+pub async fn add_peer(state: &DaemonState, peer: [u8; 32]) {
+    state
+        .gossip
+        .lock()
+        .await
+        .add_peer(peer, GossipTopic::ALL.iter().map(|t| t.topic_id()));
+}
+
+/// Drop a disconnected peer from every mesh.
+pub async fn remove_peer(state: &DaemonState, peer: &[u8; 32]) {
+    state.gossip.lock().await.remove_peer(peer);
+}
+
+/// Handle a GRAFT from `peer`, sending a PRUNE back if it is refused.
+pub async fn handle_graft(state: &DaemonState, peer: [u8; 32], graft: &GossipGraft) {
+    let refusal = state.gossip.lock().await.handle_graft(peer, graft);
+    if let Some(control) = refusal {
+        send_control(state, control).await;
+    }
+}
+
+/// Send a mesh control message to its peer.
+async fn send_control(state: &DaemonState, control: GossipControl) {
+    let (peer, msg) = match control {
+        GossipControl::Graft { peer, msg } => (peer, TypedMessage::GossipGraft(msg)),
+        GossipControl::Prune { peer, msg } => (peer, TypedMessage::GossipPrune(msg)),
+    };
+    if let Err(e) = crate::peer::send(state, &peer, &msg).await {
+        debug!(
+            "Failed to send 0x{:04x} to {}: {}",
+            msg.msg_type(),
+            hex::encode(&peer[..8]),
+            e
+        );
+    }
+}
+
+/// Handle a forwarded gossip message from a peer.
+pub async fn handle_forward(
+    state: &Arc<DaemonState>,
+    from: [u8; 32],
+    msg: &GossipForward,
+) -> ReceiveOutcome {
+    if !crate::misbehavior::standing(state, &from).await.may_route() {
+        return ReceiveOutcome::Ignored;
+    }
+    let outcome = state
+        .gossip
+        .lock()
+        .await
+        .receive(from, msg, |data| validate_payload(&msg.topic, data));
+
+    if matches!(outcome, ReceiveOutcome::Invalid) {
+        crate::misbehavior::report(state, from, invalid_offense(&msg.topic, &msg.data)).await;
+    }
+    if let ReceiveOutcome::Accepted { forward, to } = &outcome {
+        if GossipTopic::from_topic_id(&msg.topic) == Some(GossipTopic::Nullifiers) {
+            if let Ok(batch) = ochra_transport::cbor::from_slice::<NullifierGossip>(&msg.data) {
+                let mut nullifiers = state.nullifiers.lock().await;
+                ochra_nullifier::gossip::process_gossip(&batch, &mut nullifiers);
+                drop(nullifiers);
+                // Every nullifier, including Bloom false positives
+                let mut tree = state.nullifier_tree.lock().await;
+                for nullifier in &batch.nullifiers {
+                    tree.insert(nullifier);
+                }
+            }
+        }
+        if GossipTopic::from_topic_id(&msg.topic) == Some(GossipTopic::RelayDescriptors) {
+            if let Ok(descriptor) = ochra_transport::cbor::from_slice::<RelayDescriptor>(&msg.data)
+            {
+                if matches!(
+                    crate::revocations::is_revoked(state, &descriptor.pik_hash).await,
+                    Ok(true)
+                ) {
+                    debug!("Dropping descriptor from revoked relay PIK");
+                    return ReceiveOutcome::Ignored;
+                }
+                state.relays.lock().await.add(descriptor);
+            }
+        }
+        if GossipTopic::from_topic_id(&msg.topic) == Some(GossipTopic::UptimeAttestations) {
+            if let Err(e) = crate::uptime::handle_gossip(state, &msg.data).await {
+                debug!("Rejected gossiped uptime attestation: {}", e);
+            }
+        }
+        if GossipTopic::from_topic_id(&msg.topic) == Some(GossipTopic::EpochState) {
+            if let Ok(epoch_state) = ochra_transport::cbor::from_slice::<EpochState>(&msg.data) {
+                crate::rewards::record_epoch_state(state, &epoch_state).await;
+            }
+        }
+        if GossipTopic::from_topic_id(&msg.topic) == Some(GossipTopic::Tombstones) {
+            if let Err(e) = crate::tombstones::handle_gossip(state, &msg.data).await {
+                debug!("Rejected gossiped tombstone: {}", e);
+            }
+        }
+        if GossipTopic::from_topic_id(&msg.topic) == Some(GossipTopic::EpochBeacons) {
+            if let Err(e) = crate::beacon::handle_gossip(state, &msg.data).await {
+                debug!("Rejected gossiped epoch beacon: {}", e);
+            }
+        }
+        if GossipTopic::from_topic_id(&msg.topic) == Some(GossipTopic::KeySchedule) {
+            if let Err(e) = crate::key_schedule::handle_gossip(state, &msg.data).await {
+                debug!("Rejected gossiped key schedule: {}", e);
+            }
+        }
+        if GossipTopic::from_topic_id(&msg.topic).is_none() {
+            if let Err(e) = crate::announcements::handle_gossip(state, &msg.data).await {
+                debug!("Rejected gossiped announcement: {}", e);
+            }
+        }
+        if let Some(forward) = forward {
+            debug!("Accepted gossip message, forwarding to {} peers", to.len());
+            let state = state.clone();
+            let to = to.clone();
+            let msg = TypedMessage::GossipForward(forward.clone());
+            tokio::spawn(async move {
+                for peer in to {
+                    if let Err(e) = crate::peer::send(&state, &peer, &msg).await {
+                        debug!(
+                            "Failed to forward gossip to {}: {}",
+                            hex::encode(&peer[..8]),
+                            e
+                        );
+                    }
+                }
+            });
+        }
+    }
+    outcome
+}
+
+/// Run the mesh heartbeat until shutdown.
+pub async fn run_heartbeat(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_millis(HEARTBEAT_INTERVAL_MS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                let controls = state.gossip.lock().await.heartbeat();
+                for control in controls {
+                    send_control(&state, control).await;
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_payload_by_topic() {
+        let msg = ochra_nullifier::gossip::create_gossip_message(vec![[1u8; 32]], 7, [2u8; 32]);
+        let bytes = ochra_transport::cbor::to_vec(&msg).expect("encode");
+
+        assert!(validate_payload(
+            &GossipTopic::Nullifiers.topic_id(),
+            &bytes
+        ));
+        assert!(!validate_payload(
+            &GossipTopic::RelayDescriptors.topic_id(),
+            &bytes
+        ));
+        assert!(!validate_payload(&[0u8; 32], &bytes));
+    }
+
+    #[test]
+    fn test_invalid_offense_by_payload() {
+        let key = ochra_crypto::ed25519::SigningKey::generate();
+        let mut tombstone = Tombstone::new(
+            &key,
+            [1u8; 32],
+            [2u8; 32],
+            ochra_storage::tombstone::TombstoneReason::Spam,
+            1_700_000_000,
+        );
+        tombstone.sig[0] ^= 1;
+        let topic = GossipTopic::Tombstones.topic_id();
+        assert!(!validate_payload(&topic, &tombstone.to_bytes()));
+        assert_eq!(
+            invalid_offense(&topic, &tombstone.to_bytes()),
+            Offense::InvalidSignature
+        );
+        assert_eq!(invalid_offense(&topic, b"junk"), Offense::InvalidMessage);
+    }
+
+    #[test]
+    fn test_validate_announcement_topic() {
+        let sealed = MlsCiphertext {
+            group_id: [4u8; 32],
+            epoch: 1,
+            sender_id: [5u8; 32],
+            ciphertext: vec![6u8; 48],
+            nonce: [7u8; 12],
+        };
+        let bytes = ochra_transport::cbor::to_vec(&sealed).expect("encode");
+        assert!(validate_payload(&announcement_topic_id(&[4u8; 32]), &bytes));
+        assert!(!validate_payload(
+            &announcement_topic_id(&[8u8; 32]),
+            &bytes
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_new_router_subscribes_all_topics() {
+        let router = new_router();
+        let router = router.lock().await;
+        for topic in GossipTopic::ALL {
+            assert!(router.is_subscribed(&topic.topic_id()));
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Space policy store (Section 8.11).
+//!
+//! A Space owner signs each change to the Space's settings as a new
+//! [`GroupPolicy`] version and publishes it in the DHT under their PIK.
+//! Member daemons fetch the owner's record, accept it through a
+//! [`PolicyChain`], keep every accepted version as history, and check
+//! invites and publishes against the newest. Ownership transfers ride on
+//! the same records; a transfer to a PIK this node knows is revoked is
+//! held back.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_db::queries::group_policies::PolicyRow;
+use ochra_dht::bep44::DhtRecord;
+use ochra_dht::group_policy::{
+    policy_address, PolicyChain, PolicyChange, PolicyError, SignedGroupPolicy,
+};
+use ochra_types::space::GroupPolicy;
+use ochra_types::GroupId;
+use rusqlite::Connection;
+use tracing::{debug, info, warn};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Seconds between policy refreshes.
+const REFRESH_SECS: u64 = 3600;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The serde name of a settings enum, as stored in `spaces`.
+fn setting_name<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// The chain of a Space this node is a member of.
+fn load(db: &Connection, group_id: &GroupId) -> anyhow::Result<Option<PolicyChain>> {
+    let Some(owner) = ochra_db::queries::spaces::owner_pik(db, group_id)? else {
+        return Ok(None);
+    };
+    let owner: [u8; 32] = owner
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("owner PIK must be 32 bytes"))?;
+    let head = ochra_db::queries::group_policies::latest(db, group_id)?
+        .map(|row| SignedGroupPolicy::from_bytes(&row.signed_policy))
+        .transpose()?;
+    Ok(Some(PolicyChain::new(*group_id, owner, head)))
+}
+
+/// Hold back transfers to PIKs with a recorded revocation.
+fn revoked_successor(db: &Connection) -> impl Fn(&GroupId, &[u8; 32]) -> bool + '_ {
+    move |_, new_owner_pk| {
+        let pik_hash = ochra_crypto::blake3::hash(new_owner_pk);
+        ochra_db::queries::revocations::is_revoked(db, &pik_hash).unwrap_or(true)
+    }
+}
+
+/// Store the chain's head and owner, and this node's role if ownership
+/// moved.
+fn save(db: &Connection, chain: &PolicyChain, changes: &[PolicyChange]) -> anyhow::Result<()> {
+    let group_id = chain.policy().group_id;
+    if let Some(head) = chain.head() {
+        ochra_db::queries::group_policies::insert(
+            db,
+            &group_id,
+            &PolicyRow {
+                version: head.policy.version,
+                signed_policy: head.to_bytes(),
+                signer_pk: head.owner_pk.to_vec(),
+                received_at: now_secs(),
+            },
+        )?;
+    }
+    let settings = chain.policy().settings;
+    ochra_db::queries::spaces::apply_policy(
+        db,
+        &group_id,
+        &chain.owner_pk(),
+        &setting_name(&settings.publish_policy),
+        &setting_name(&settings.invite_permission),
+    )?;
+    if changes
+        .iter()
+        .any(|c| matches!(c, PolicyChange::TransferCompleted { .. }))
+    {
+        let my_pik: Option<Vec<u8>> = db
+            .query_row("SELECT pik_hash FROM pik WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .ok();
+        let owner_hash = ochra_crypto::blake3::hash(&chain.owner_pk());
+        if my_pik.as_deref() == Some(owner_hash.as_slice()) {
+            ochra_db::queries::spaces::set_my_role(db, &group_id, "host")?;
+        } else if ochra_db::queries::spaces::my_role(db, &group_id)?.as_deref() == Some("host") {
+            ochra_db::queries::spaces::set_my_role(db, &group_id, "member")?;
+        }
+    }
+    Ok(())
+}
+
+fn emit(state: &DaemonState, group_id: GroupId, signer_pk: &[u8; 32], changes: Vec<PolicyChange>) {
+    for change in changes {
+        let event = match change {
+            PolicyChange::Settings { old, new } => DaemonEvent::SettingsChanged {
+                group_id,
+                changed_by: ochra_crypto::blake3::hash(signer_pk),
+                old_settings: old,
+                new_settings: new,
+            },
+            PolicyChange::TransferPending {
+                new_owner_pk,
+                completes_at,
+            } => DaemonEvent::OwnershipTransferPending {
+                group_id,
+                new_owner_pik: ochra_crypto::blake3::hash(&new_owner_pk),
+                completes_at,
+            },
+            PolicyChange::TransferVetoed => DaemonEvent::OwnershipTransferCanceled {
+                group_id,
+                reason: ochra_types::events::TransferCancelReason::Vetoed,
+            },
+            PolicyChange::TransferCompleted { new_owner_pk } => {
+                info!(group = %hex::encode(group_id), "Space ownership transferred");
+                DaemonEvent::OwnershipTransferCompleted {
+                    group_id,
+                    new_owner_pik: ochra_crypto::blake3::hash(&new_owner_pk),
+                }
+            }
+        };
+        state.event_bus.emit(event);
+    }
+}
+
+/// The policy chain of a Space, or `None` if this node is not a member.
+pub async fn chain(state: &DaemonState, group_id: &GroupId) -> anyhow::Result<Option<PolicyChain>> {
+    load(&*state.db.lock().await, group_id)
+}
+
+/// The policy in force for a Space, or `None` if this node is not a member.
+pub async fn current(
+    state: &DaemonState,
+    group_id: &GroupId,
+) -> anyhow::Result<Option<GroupPolicy>> {
+    Ok(chain(state, group_id).await?.map(|c| c.policy()))
+}
+
+/// Accept a policy fetched for a Space this node belongs to.
+///
+/// Returns `false` if this node is not a member or already has the
+/// version or a newer one.
+pub async fn ingest(state: &DaemonState, signed: SignedGroupPolicy) -> anyhow::Result<bool> {
+    let group_id = signed.policy.group_id;
+    let signer_pk = signed.owner_pk;
+    let changes = {
+        let db = state.db.lock().await;
+        let Some(mut chain) = load(&db, &group_id)? else {
+            return Ok(false);
+        };
+        let changes = match chain.apply(signed, now_secs(), &revoked_successor(&db)) {
+            Ok(changes) => changes,
+            Err(PolicyError::Stale { .. }) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        save(&db, &chain, &changes)?;
+        changes
+    };
+    emit(state, group_id, &signer_pk, changes);
+    Ok(true)
+}
+
+/// Sign `policy` as the Space owner, publish it, and apply it locally.
+pub async fn publish(
+    state: &DaemonState,
+    pik: &SigningKey,
+    policy: GroupPolicy,
+) -> anyhow::Result<SignedGroupPolicy> {
+    let signed = SignedGroupPolicy::sign(pik, policy)?;
+    let _record = signed.to_record();
+    // Would: put _record into the DHT over Sphinx
+    debug!(
+        "Published policy v{} at {}",
+        signed.policy.version,
+        hex::encode(policy_address(&signed.owner_pk, &signed.policy.group_id))
+    );
+    ingest(state, signed.clone()).await?;
+    Ok(signed)
+}
+
+/// Handle a record fetched from a Space owner's policy address.
+#[allow(dead_code)]
+pub async fn handle_record(state: &Arc<DaemonState>, record: &DhtRecord) -> anyhow::Result<bool> {
+    ingest(state, SignedGroupPolicy::from_record(record)?).await
+}
+
+/// Complete due ownership transfers in every joined Space.
+async fn refresh(state: &DaemonState) -> anyhow::Result<()> {
+    let spaces = ochra_db::queries::spaces::list(&*state.db.lock().await)?;
+    for space in spaces {
+        let Ok(group_id) = GroupId::try_from(space.group_id.as_slice()) else {
+            continue;
+        };
+        // Would: get policy_address(owner, group_id) from the DHT, and the
+        // pending successor's address, and handle_record() the results
+        let (signer_pk, change) = {
+            let db = state.db.lock().await;
+            let Some(mut chain) = load(&db, &group_id)? else {
+                continue;
+            };
+            let Some(change) = chain.advance(now_secs(), &revoked_successor(&db)) else {
+                continue;
+            };
+            save(&db, &chain, std::slice::from_ref(&change))?;
+            (chain.owner_pk(), change)
+        };
+        emit(state, group_id, &signer_pk, vec![change]);
+    }
+    Ok(())
+}
+
+/// Refresh Space policies every [`REFRESH_SECS`] until shutdown.
+pub async fn run_refresher(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(REFRESH_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                if let Err(e) = refresh(&state).await {
+                    warn!("Space policy refresh failed: {}", e);
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_types::space::PublishPolicy;
+
+    #[test]
+    fn test_accepted_policy_survives_reload() {
+        let db = ochra_db::open_memory().expect("open");
+        let owner = SigningKey::from_bytes(&[7u8; 32]);
+        let owner_pk = owner.verifying_key().to_bytes();
+        let group_id = [1u8; 32];
+        ochra_db::queries::spaces::insert(
+            &db,
+            &group_id,
+            "s",
+            "storefront",
+            "member",
+            &owner_pk,
+            0,
+        )
+        .expect("insert space");
+
+        let mut chain = load(&db, &group_id).expect("load").expect("member");
+        let mut policy = chain.policy();
+        policy.version = chain.next_version();
+        policy.settings.publish_policy = PublishPolicy::Everyone;
+        let signed = SignedGroupPolicy::sign(&owner, policy.clone()).expect("sign");
+        let changes = chain
+            .apply(signed, 10, &revoked_successor(&db))
+            .expect("apply");
+        save(&db, &chain, &changes).expect("save");
+
+        let reloaded = load(&db, &group_id).expect("load").expect("member");
+        assert_eq!(reloaded.policy(), policy);
+        assert_eq!(
+            ochra_db::queries::group_policies::history(&db, &group_id, 10)
+                .expect("history")
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Cached handle resolution (Section 7.2).
+//!
+//! `resolve_handle` goes through the
+//! [`HandleCache`](ochra_whisper::resolver::HandleCache): fresh entries and
+//! recent misses are answered locally, stale entries are answered at once
+//! and revalidated in the background, and DHT fetches are rate limited.
+//! Cached descriptors are dropped when a contact's keys change or a
+//! handle transition is detected, so the next lookup re-fetches them.
+//!
+//! In v1 fetches fail with [`LookupUnsupported`]: descriptors are read with
+//! DHT gets, which the daemon does not yet send over a circuit or directly.
+
+use std::sync::Arc;
+
+use ochra_dht::private_lookup::LookupKind;
+use ochra_types::whisper::HandleDescriptor;
+use ochra_whisper::resolver::CacheLookup;
+use ochra_whisper::WhisperError;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// A handle descriptor would have to be fetched from the DHT.
+#[derive(Debug, thiserror::Error)]
+#[error("handle descriptors are fetched with DHT gets, which the daemon does not yet send")]
+pub struct LookupUnsupported;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Fetch and verify the descriptor `handle` currently resolves to.
+///
+/// The lookup follows the configured `dht_get_privacy`, so under
+/// `circuit_required` nothing is fetched while no healthy circuit is up.
+/// Otherwise it fails with [`LookupUnsupported`].
+async fn fetch(state: &DaemonState, handle: &str) -> anyhow::Result<Option<HandleDescriptor>> {
+    let circuit_available = state.circuit_health.lock().await.healthy_count() > 0;
+    let route = state
+        .config
+        .privacy
+        .lookup_privacy()
+        .route(LookupKind::Get, circuit_available)?;
+    debug!("Cannot resolve @{} via {:?} lookup", handle, route);
+    Err(LookupUnsupported.into())
+}
+
+/// Fetch `handle` and record the result in the cache.
+async fn fetch_into_cache(
+    state: &DaemonState,
+    handle: &str,
+) -> anyhow::Result<Option<HandleDescriptor>> {
+    let fetched = fetch(state, handle).await?;
+    let mut cache = state.handles.lock().await;
+    match &fetched {
+        Some(descriptor) => cache.insert(handle, descriptor.clone(), now_secs()),
+        None => cache.insert_missing(handle, now_secs()),
+    }
+    Ok(fetched)
+}
+
+/// Resolve `handle`, from the cache where possible.
+///
+/// # Errors
+///
+/// - [`WhisperError::LookupLimited`] if nothing is cached and fetches are
+///   rate limited
+/// - [`LookupUnsupported`] if the handle has to be fetched
+pub async fn resolve(
+    state: &Arc<DaemonState>,
+    handle: &str,
+) -> anyhow::Result<Option<HandleDescriptor>> {
+    let lookup = state.handles.lock().await.lookup(handle, now_secs());
+    match lookup {
+        CacheLookup::Fresh(descriptor) => Ok(Some(descriptor)),
+        CacheLookup::Stale {
+            descriptor,
+            refresh,
+        } => {
+            if refresh {
+                let state = state.clone();
+                let handle = handle.to_string();
+                tokio::spawn(async move {
+                    if let Err(e) = fetch_into_cache(&state, &handle).await {
+                        warn!("Failed to revalidate @{}: {}", handle, e);
+                    }
+                });
+            }
+            Ok(Some(descriptor))
+        }
+        CacheLookup::Missing => Ok(None),
+        CacheLookup::Fetch => fetch_into_cache(state, handle).await,
+        CacheLookup::Limited => Err(WhisperError::LookupLimited(handle.to_string()).into()),
+    }
+}
+
+/// Apply an event to the cache. Returns the number of entries dropped.
+async fn invalidate_for(state: &DaemonState, event: &DaemonEvent) -> usize {
+    let mut cache = state.handles.lock().await;
+    match event {
+        // Contacts are keyed by PIK, not handle, so any cached descriptor
+        // may belong to the contact whose keys changed.
+        DaemonEvent::KeyChanged { .. } => cache.invalidate_resolved(),
+        DaemonEvent::HandleTransitionDetected {
+            old_handle,
+            new_handle,
+            ..
+        } => cache.invalidate(old_handle) + cache.invalidate(new_handle),
+        _ => 0,
+    }
+}
+
+/// Drop cached descriptors as key change and handle transition events
+/// arrive, until shutdown.
+pub async fn run_invalidator(state: Arc<DaemonState>) {
+    let mut events = state.event_bus.subscribe();
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            received = events.recv() => match received {
+                Ok(event) => {
+                    let dropped = invalidate_for(&state, &event.event).await;
+                    if dropped > 0 {
+                        debug!("Dropped {} cached handles after {}", dropped, event.event.event_type());
+                    }
+                }
+                // Missed events may have invalidated anything.
+                Err(RecvError::Lagged(_)) => {
+                    state.handles.lock().await.invalidate_resolved();
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
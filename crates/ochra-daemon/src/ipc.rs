@@ -0,0 +1,175 @@
+//! Platform IPC listener for the JSON-RPC server (Section 32).
+//!
+//! The UI reaches the daemon over a Unix domain socket on macOS and Linux and
+//! over a named pipe on Windows. [`IpcListener`] hides the difference so the
+//! RPC server's connection handling and dispatch are shared; the platform
+//! implementation is selected with `cfg` as [`PlatformListener`].
+
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Named pipe the daemon listens on under Windows.
+#[cfg(windows)]
+pub const PIPE_NAME: &str = r"\\.\pipe\ochra-daemon";
+
+/// A listener accepting local IPC connections.
+pub trait IpcListener: Sized + Send {
+    /// A connected client stream.
+    type Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    /// Start listening at `endpoint`.
+    fn bind(endpoint: &Path) -> io::Result<Self>;
+
+    /// Wait for the next client connection.
+    fn accept(&mut self) -> impl Future<Output = io::Result<Self::Stream>> + Send;
+}
+
+/// The IPC endpoint for a data directory: `<data_dir>/daemon.sock` on Unix,
+/// [`PIPE_NAME`] on Windows.
+pub fn default_endpoint(data_dir: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let _ = data_dir;
+        PathBuf::from(PIPE_NAME)
+    }
+    #[cfg(not(windows))]
+    {
+        data_dir.join("daemon.sock")
+    }
+}
+
+/// Remove whatever the endpoint left behind once the server stops.
+pub fn cleanup(endpoint: &Path) {
+    // Named pipes disappear with their last handle.
+    #[cfg(unix)]
+    {
+        let _ = std::fs::remove_file(endpoint);
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = endpoint;
+    }
+}
+
+/// Unix domain socket listener.
+#[cfg(unix)]
+pub struct UnixSocketListener {
+    listener: tokio::net::UnixListener,
+}
+
+#[cfg(unix)]
+impl IpcListener for UnixSocketListener {
+    type Stream = tokio::net::UnixStream;
+
+    fn bind(endpoint: &Path) -> io::Result<Self> {
+        // Remove stale socket file
+        let _ = std::fs::remove_file(endpoint);
+        let listener = tokio::net::UnixListener::bind(endpoint)?;
+        Ok(Self { listener })
+    }
+
+    async fn accept(&mut self) -> io::Result<Self::Stream> {
+        let (stream, _addr) = self.listener.accept().await?;
+        Ok(stream)
+    }
+}
+
+/// Windows named pipe listener.
+///
+/// A pipe instance serves a single client, so a fresh instance is created as
+/// soon as one is connected, keeping the pipe name claimed between accepts.
+#[cfg(windows)]
+pub struct NamedPipeListener {
+    name: PathBuf,
+    next: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+#[cfg(windows)]
+impl IpcListener for NamedPipeListener {
+    type Stream = tokio::net::windows::named_pipe::NamedPipeServer;
+
+    fn bind(endpoint: &Path) -> io::Result<Self> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        // Refuse to start if another process already owns the pipe name.
+        let next = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(endpoint)?;
+        Ok(Self {
+            name: endpoint.to_path_buf(),
+            next,
+        })
+    }
+
+    async fn accept(&mut self) -> io::Result<Self::Stream> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        self.next.connect().await?;
+        let fresh = ServerOptions::new().create(&self.name)?;
+        Ok(std::mem::replace(&mut self.next, fresh))
+    }
+}
+
+/// The listener used on this platform.
+#[cfg(unix)]
+pub type PlatformListener = UnixSocketListener;
+
+/// The listener used on this platform.
+#[cfg(windows)]
+pub type PlatformListener = NamedPipeListener;
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn temp_endpoint(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ochra-ipc-{}-{}.sock", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_default_endpoint() {
+        let endpoint = default_endpoint(Path::new("/data"));
+        assert_eq!(endpoint, PathBuf::from("/data/daemon.sock"));
+    }
+
+    #[tokio::test]
+    async fn test_unix_listener_round_trip() {
+        let endpoint = temp_endpoint("roundtrip");
+        let mut listener = PlatformListener::bind(&endpoint).expect("bind");
+
+        let client = tokio::spawn({
+            let endpoint = endpoint.clone();
+            async move {
+                let mut stream = tokio::net::UnixStream::connect(&endpoint)
+                    .await
+                    .expect("connect");
+                stream.write_all(b"ping\n").await.expect("write");
+                let mut buf = [0u8; 5];
+                stream.read_exact(&mut buf).await.expect("read");
+                buf
+            }
+        });
+
+        let mut server = listener.accept().await.expect("accept");
+        let mut buf = [0u8; 5];
+        server.read_exact(&mut buf).await.expect("read");
+        assert_eq!(&buf, b"ping\n");
+        server.write_all(b"pong\n").await.expect("write");
+        assert_eq!(&client.await.expect("client"), b"pong\n");
+
+        cleanup(&endpoint);
+        assert!(!endpoint.exists());
+    }
+
+    #[tokio::test]
+    async fn test_bind_replaces_stale_socket() {
+        let endpoint = temp_endpoint("stale");
+        std::fs::write(&endpoint, b"").expect("stale file");
+        let _listener = PlatformListener::bind(&endpoint).expect("bind over stale file");
+        cleanup(&endpoint);
+    }
+}
@@ -0,0 +1,201 @@
+//! Calibrated Argon2id profiles (Section 6.1).
+//!
+//! The first time a profile is needed the daemon benchmarks the host and
+//! picks password-hashing and PoW parameters within the spec's bounds, then
+//! keeps them in settings so later runs skip the benchmark. A PIK records
+//! the parameters it was wrapped with, so recalibrating never locks out an
+//! existing password; new parameters apply from the next `init_pik` or
+//! password change.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_crypto::argon2id::{self, Argon2Params, Calibration, KdfProfile};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::DaemonState;
+
+/// Settings key holding the calibrated profiles.
+const PROFILES_KEY: &str = "kdf_profiles";
+
+/// Time one password hash should take on this host.
+pub const PASSWORD_TARGET: Duration = Duration::from_millis(1000);
+
+/// Time one PoW attempt should take on this host.
+pub const POW_TARGET: Duration = Duration::from_millis(100);
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The host's calibrated profiles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfProfiles {
+    pub password: Calibration,
+    pub pow: Calibration,
+    pub calibrated_at: u64,
+}
+
+impl KdfProfiles {
+    /// Check both profiles are within their bounds.
+    fn validate(&self) -> anyhow::Result<()> {
+        self.password.params.validate(KdfProfile::Password)?;
+        self.pow.params.validate(KdfProfile::Pow)?;
+        Ok(())
+    }
+}
+
+/// Password-hash memory cap in KiB from the `[identity]` config.
+fn memory_limit_kib(limit_mb: u32) -> u32 {
+    match limit_mb {
+        0 => argon2id::PIK_M_COST,
+        mb => mb.saturating_mul(1024),
+    }
+}
+
+/// Benchmark this host. Blocks for a few seconds.
+fn calibrate(limit_mb: u32) -> anyhow::Result<KdfProfiles> {
+    let password = argon2id::calibrate_host(
+        KdfProfile::Password,
+        PASSWORD_TARGET,
+        memory_limit_kib(limit_mb),
+    )?;
+    let pow = argon2id::calibrate_host(KdfProfile::Pow, POW_TARGET, u32::MAX)?;
+    Ok(KdfProfiles {
+        password,
+        pow,
+        calibrated_at: now_secs(),
+    })
+}
+
+/// Run the benchmark off the async runtime and persist the result.
+async fn calibrate_and_save(state: &Arc<DaemonState>) -> anyhow::Result<KdfProfiles> {
+    let limit_mb = state.config.identity.kdf_memory_limit_mb;
+    let profiles = tokio::task::spawn_blocking(move || calibrate(limit_mb)).await??;
+    info!(
+        "Calibrated Argon2id: password m={}KiB t={} p={} ({} ms), pow m={}KiB t={} p={} ({} ms)",
+        profiles.password.params.m_cost,
+        profiles.password.params.t_cost,
+        profiles.password.params.p_cost,
+        profiles.password.elapsed_ms,
+        profiles.pow.params.m_cost,
+        profiles.pow.params.t_cost,
+        profiles.pow.params.p_cost,
+        profiles.pow.elapsed_ms,
+    );
+    let db = state.db.lock().await;
+    ochra_db::queries::settings::set(&db, PROFILES_KEY, &serde_json::to_string(&profiles)?)?;
+    Ok(profiles)
+}
+
+/// The host's profiles, calibrating on first use.
+///
+/// Holding the lock across the benchmark makes concurrent callers wait for
+/// one calibration instead of each running their own.
+pub async fn profiles(state: &Arc<DaemonState>) -> anyhow::Result<KdfProfiles> {
+    let mut cached = state.kdf_profiles.lock().await;
+    if let Some(profiles) = *cached {
+        return Ok(profiles);
+    }
+    let stored = {
+        let db = state.db.lock().await;
+        match ochra_db::queries::settings::get(&db, PROFILES_KEY) {
+            Ok(json) => Some(json),
+            Err(ochra_db::DbError::NotFound(_)) => None,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    let loaded = stored
+        .and_then(|json| serde_json::from_str::<KdfProfiles>(&json).ok())
+        .filter(|p| p.validate().is_ok());
+    let profiles = match loaded {
+        Some(profiles) => profiles,
+        None => calibrate_and_save(state).await?,
+    };
+    *cached = Some(profiles);
+    Ok(profiles)
+}
+
+/// Benchmark again, replacing the stored profiles.
+pub async fn recalibrate(state: &Arc<DaemonState>) -> anyhow::Result<KdfProfiles> {
+    let mut cached = state.kdf_profiles.lock().await;
+    let profiles = calibrate_and_save(state).await?;
+    *cached = Some(profiles);
+    Ok(profiles)
+}
+
+/// Calibrate in the background on first run, so the first `init_pik` does
+/// not wait for the benchmark.
+pub async fn warm_up(state: Arc<DaemonState>) {
+    if let Err(e) = profiles(&state).await {
+        tracing::error!("Argon2id calibration failed: {}", e);
+    }
+}
+
+/// Parameters a PIK was wrapped with, from its `argon2id_params` column.
+///
+/// PIKs wrapped before calibration have none and use the fixed spec
+/// parameters.
+pub fn pik_params(stored: Option<&str>) -> anyhow::Result<Argon2Params> {
+    let params = match stored {
+        None => Argon2Params::PIK,
+        Some(json) => serde_json::from_str(json)?,
+    };
+    params.validate(KdfProfile::Password)?;
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calibration(params: Argon2Params) -> Calibration {
+        Calibration {
+            params,
+            elapsed_ms: 10,
+        }
+    }
+
+    #[test]
+    fn test_pik_params_default_to_spec_for_legacy_piks() {
+        assert_eq!(pik_params(None).expect("legacy"), Argon2Params::PIK);
+        let json = r#"{"m_cost":131072,"t_cost":4,"p_cost":2}"#;
+        assert_eq!(
+            pik_params(Some(json)).expect("stored"),
+            Argon2Params {
+                m_cost: 131_072,
+                t_cost: 4,
+                p_cost: 2,
+            }
+        );
+        // A tampered row cannot weaken the password hash.
+        assert!(pik_params(Some(r#"{"m_cost":8,"t_cost":1,"p_cost":1}"#)).is_err());
+        assert!(pik_params(Some("not json")).is_err());
+    }
+
+    #[test]
+    fn test_profiles_validate_both_bounds() {
+        let good = KdfProfiles {
+            password: calibration(Argon2Params::PIK),
+            pow: calibration(KdfProfile::Pow.default_params()),
+            calibrated_at: 1,
+        };
+        assert!(good.validate().is_ok());
+        let swapped = KdfProfiles {
+            password: good.pow,
+            pow: good.password,
+            ..good
+        };
+        assert!(swapped.validate().is_err());
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        assert_eq!(memory_limit_kib(0), argon2id::PIK_M_COST);
+        assert_eq!(memory_limit_kib(512), 512 * 1024);
+    }
+}
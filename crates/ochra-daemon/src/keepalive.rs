@@ -0,0 +1,103 @@
+//! Connection keepalive and dead peer detection (Section 4.6).
+//!
+//! Each connected peer is pinged only after its path has been quiet for the
+//! interval the [`KeepaliveManager`] has learned (or is probing) for it.
+//! Missed pongs count as failed pings against the node in the routing
+//! table; a peer that misses too many in a row is disconnected, evicted
+//! and dropped from connection migration.
+//!
+//! [`KeepaliveManager`]: ochra_transport::keepalive::KeepaliveManager
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_transport::keepalive::KeepaliveEvent;
+use ochra_transport::messages::{Ping, TypedMessage};
+use tracing::{debug, info};
+
+use crate::DaemonState;
+
+/// Seconds between keepalive checks; well under the pong timeout.
+const CHECK_INTERVAL_SECS: u64 = 1;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Record a pong from `peer`.
+pub async fn on_pong(state: &DaemonState, peer: [u8; 32]) {
+    let event = state.keepalive.lock().await.on_pong(&peer, now_secs());
+    state.routing.lock().await.record_ping_response(&peer);
+    if let Some(KeepaliveEvent::Learned {
+        peer,
+        interval_secs,
+    }) = event
+    {
+        debug!(
+            "Keepalive interval for {} settled at {}s",
+            hex::encode(peer),
+            interval_secs
+        );
+    }
+}
+
+async fn handle(state: &DaemonState, event: KeepaliveEvent) {
+    match event {
+        KeepaliveEvent::Missed { peer, misses } => {
+            state.routing.lock().await.record_failed_ping(&peer);
+            debug!(
+                "Keepalive to {} missed ({} in a row)",
+                hex::encode(peer),
+                misses
+            );
+        }
+        KeepaliveEvent::Dead { peer } => {
+            state.routing.lock().await.remove_node(&peer);
+            state.peers.disconnect(&peer).await;
+            state.migration.lock().await.untrack(&peer);
+            info!(
+                "Peer {} stopped answering keepalives; disconnected",
+                hex::encode(peer)
+            );
+        }
+        KeepaliveEvent::Learned { .. } => {}
+    }
+}
+
+/// Ping `peer`, recording the pong if a matching one comes back.
+async fn ping(state: Arc<DaemonState>, peer: [u8; 32]) {
+    let nonce: [u8; 8] = rand::random();
+    match crate::peer::request(&state, &peer, &TypedMessage::Ping(Ping { nonce })).await {
+        Ok(TypedMessage::Pong(pong)) if pong.nonce == nonce => on_pong(&state, peer).await,
+        Ok(_) => debug!("Keepalive to {} got a mismatched reply", hex::encode(peer)),
+        // Unanswered pings are timed out by the manager
+        Err(e) => debug!("Keepalive ping to {} failed: {:#}", hex::encode(peer), e),
+    }
+}
+
+/// Send due keepalives and time out unanswered ones until shutdown.
+pub async fn run_monitor(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                let now = now_secs();
+                let mut manager = state.keepalive.lock().await;
+                let events = manager.poll(now);
+                let due = manager.due(now);
+                drop(manager);
+                for event in events {
+                    handle(&state, event).await;
+                }
+                for peer in due {
+                    tokio::spawn(ping(state.clone(), peer));
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
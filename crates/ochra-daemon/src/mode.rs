@@ -0,0 +1,517 @@
+//! Operating modes and role draining.
+//!
+//! A node runs in one [`NodeMode`], which decides the roles it takes on:
+//! relaying (onion forwarding and Whisper mailboxes), storage (serving and
+//! announcing chunks), and quorum candidacy. Only the loops of active roles
+//! are started, and the roles are advertised to peers as capability feature
+//! bits.
+//!
+//! The mode can be switched over RPC. A role that is dropped stops taking
+//! new work at once but keeps serving what it already holds until it drains
+//! (mailboxes emptied, upload slots idle) or [`DRAIN_TIMEOUT_SECS`] passes.
+//! Each change emits `NodeModeChanged`, and so does the end of the drain.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_transport::capabilities::{FEATURE_QUORUM_CANDIDATE, FEATURE_RELAY, FEATURE_STORAGE};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Longest a dropped role keeps serving held work.
+pub const DRAIN_TIMEOUT_SECS: u64 = 600;
+
+/// Interval between drain checks.
+const DRAIN_POLL_SECS: u64 = 5;
+
+/// Settings key holding the mode chosen over RPC.
+const NODE_MODE_KEY: &str = "node_mode";
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// A role a node can take on for the network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Onion forwarding and Whisper mailboxes.
+    Relay,
+    /// Chunk serving and provider announcements.
+    Storage,
+    /// Eligibility for the minting quorum.
+    Quorum,
+}
+
+impl Role {
+    /// Wire name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Relay => "relay",
+            Self::Storage => "storage",
+            Self::Quorum => "quorum",
+        }
+    }
+
+    /// Capability feature bit advertising the role.
+    pub fn feature(&self) -> u64 {
+        match self {
+            Self::Relay => FEATURE_RELAY,
+            Self::Storage => FEATURE_STORAGE,
+            Self::Quorum => FEATURE_QUORUM_CANDIDATE,
+        }
+    }
+}
+
+/// Operating mode chosen by the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeMode {
+    /// Use the network without serving it.
+    ClientOnly,
+    /// Relay traffic for others.
+    Relay,
+    /// Relay traffic and store chunks.
+    RelayStorage,
+    /// Relay, store, and stand for the minting quorum.
+    QuorumCandidate,
+}
+
+impl NodeMode {
+    /// Wire, config, and settings name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ClientOnly => "client_only",
+            Self::Relay => "relay",
+            Self::RelayStorage => "relay_storage",
+            Self::QuorumCandidate => "quorum_candidate",
+        }
+    }
+
+    /// Parse a wire, config, or settings name.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "client_only" => Some(Self::ClientOnly),
+            "relay" => Some(Self::Relay),
+            "relay_storage" => Some(Self::RelayStorage),
+            "quorum_candidate" => Some(Self::QuorumCandidate),
+            _ => None,
+        }
+    }
+
+    /// Roles the mode takes on.
+    pub fn roles(&self) -> &'static [Role] {
+        match self {
+            Self::ClientOnly => &[],
+            Self::Relay => &[Role::Relay],
+            Self::RelayStorage => &[Role::Relay, Role::Storage],
+            Self::QuorumCandidate => &[Role::Relay, Role::Storage, Role::Quorum],
+        }
+    }
+
+    /// Whether the mode takes on `role`.
+    pub fn has(&self, role: Role) -> bool {
+        self.roles().contains(&role)
+    }
+
+    /// Capability feature bits for the mode.
+    pub fn features(&self) -> u64 {
+        self.roles()
+            .iter()
+            .fold(0, |bits, role| bits | role.feature())
+    }
+}
+
+/// Result of a mode switch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModeChange {
+    pub previous: NodeMode,
+    /// Roles to start.
+    pub gained: Vec<Role>,
+    /// Whether a drain began that no drain task is watching yet.
+    pub drain_started: bool,
+}
+
+/// Tracks the mode, roles still draining, and which role loops run.
+#[derive(Debug)]
+pub struct ModeManager {
+    mode: NodeMode,
+    draining: BTreeSet<Role>,
+    drain_deadline: u64,
+    /// Generation of each role's running loops.
+    running: BTreeMap<Role, u64>,
+    generation: u64,
+}
+
+impl ModeManager {
+    /// Create a manager in `mode` with nothing running.
+    pub fn new(mode: NodeMode) -> Self {
+        Self {
+            mode,
+            draining: BTreeSet::new(),
+            drain_deadline: 0,
+            running: BTreeMap::new(),
+            generation: 0,
+        }
+    }
+
+    /// The current mode.
+    pub fn mode(&self) -> NodeMode {
+        self.mode
+    }
+
+    /// Roles still serving held work after being dropped.
+    pub fn draining(&self) -> impl Iterator<Item = Role> + '_ {
+        self.draining.iter().copied()
+    }
+
+    /// When draining roles are stopped regardless of held work.
+    pub fn drain_deadline(&self) -> Option<u64> {
+        (!self.draining.is_empty()).then_some(self.drain_deadline)
+    }
+
+    /// Whether `role` takes new work.
+    pub fn accepts(&self, role: Role) -> bool {
+        self.mode.has(role)
+    }
+
+    /// Whether `role` still serves work, new or held.
+    pub fn serves(&self, role: Role) -> bool {
+        self.accepts(role) || self.draining.contains(&role)
+    }
+
+    /// Switch modes. Dropped roles start draining; a role regained while
+    /// draining simply resumes.
+    pub fn set_mode(&mut self, mode: NodeMode, now: u64) -> ModeChange {
+        let previous = self.mode;
+        let was_draining = !self.draining.is_empty();
+        self.mode = mode;
+        for role in previous.roles() {
+            if !mode.has(*role) && self.draining.insert(*role) {
+                self.drain_deadline = now.saturating_add(DRAIN_TIMEOUT_SECS);
+            }
+        }
+        let mut gained = Vec::new();
+        for role in mode.roles() {
+            let resumed = self.draining.remove(role);
+            if !resumed && !previous.has(*role) {
+                gained.push(*role);
+            }
+        }
+        ModeChange {
+            previous,
+            gained,
+            drain_started: !was_draining && !self.draining.is_empty(),
+        }
+    }
+
+    /// Stop draining `role`. Returns `true` if no role is left draining.
+    pub fn finish_drain(&mut self, role: Role) -> bool {
+        self.draining.remove(&role);
+        self.draining.is_empty()
+    }
+
+    /// Whether the drain deadline has passed.
+    pub fn drain_expired(&self, now: u64) -> bool {
+        !self.draining.is_empty() && now >= self.drain_deadline
+    }
+
+    /// Record that `role`'s loops are starting and return their
+    /// generation, or `None` if they already run.
+    pub fn start_loops(&mut self, role: Role) -> Option<u64> {
+        if self.running.contains_key(&role) {
+            return None;
+        }
+        self.generation += 1;
+        self.running.insert(role, self.generation);
+        Some(self.generation)
+    }
+
+    /// Called by a role loop of `generation` on each tick. Returns `false`
+    /// once the role no longer serves, recording its loops as stopped, or
+    /// once a newer generation has replaced them.
+    pub fn keep_running(&mut self, role: Role, generation: u64) -> bool {
+        if self.running.get(&role) != Some(&generation) {
+            return false;
+        }
+        if self.serves(role) {
+            return true;
+        }
+        self.running.remove(&role);
+        false
+    }
+}
+
+/// Build the manager, restoring a mode chosen over RPC in a previous run.
+pub fn load(
+    conn: &rusqlite::Connection,
+    config: &crate::config::NetworkConfig,
+) -> anyhow::Result<Arc<Mutex<ModeManager>>> {
+    let stored = ochra_db::queries::settings::get(conn, NODE_MODE_KEY)
+        .ok()
+        .as_deref()
+        .and_then(NodeMode::parse);
+    let mode = match stored {
+        Some(mode) => mode,
+        None => config.node_mode()?,
+    };
+    Ok(Arc::new(Mutex::new(ModeManager::new(mode))))
+}
+
+/// Whether `role` takes new work. Relaying also pauses while low-power mode
+/// suspends it.
+pub async fn accepts(state: &DaemonState, role: Role) -> bool {
+    if role == Role::Relay && crate::power::relay_suspended(state).await {
+        return false;
+    }
+    state.mode.lock().await.accepts(role)
+}
+
+/// Whether `role` still serves held work.
+pub async fn serves(state: &DaemonState, role: Role) -> bool {
+    state.mode.lock().await.serves(role)
+}
+
+/// Whether a role loop of `generation` should keep going.
+pub async fn keep_running(state: &DaemonState, role: Role, generation: u64) -> bool {
+    state.mode.lock().await.keep_running(role, generation)
+}
+
+/// Feature bits for this node's capability exchange.
+pub async fn local_features(state: &DaemonState) -> u64 {
+    let mut features = state.mode.lock().await.mode().features();
+    if crate::power::relay_suspended(state).await {
+        features &= !FEATURE_RELAY;
+    }
+    if state.config.network.crawl_opt_out {
+        features |= ochra_dht::crawl::CRAWL_OPT_OUT;
+    }
+    features
+}
+
+/// Spawn the loops of `role` unless they already run.
+fn start_role(state: &Arc<DaemonState>, manager: &mut ModeManager, role: Role) {
+    let Some(generation) = manager.start_loops(role) else {
+        return;
+    };
+    info!("Starting {} role", role.as_str());
+    match role {
+        Role::Relay => {
+            tokio::spawn(crate::mailbox::run_sweeper(state.clone(), generation));
+            tokio::spawn(crate::state_sync::start(state.clone()));
+        }
+        Role::Storage => {
+            tokio::spawn(crate::announce::run_announcer(state.clone(), generation));
+            tokio::spawn(crate::uploads::run_rechoker(state.clone(), generation));
+        }
+        Role::Quorum => {
+            // Would: enter the quorum candidate pool for the next epoch's
+            // PoSrv selection
+        }
+    }
+}
+
+/// Start the loops of every role of the current mode.
+pub async fn start_roles(state: &Arc<DaemonState>) {
+    let mut manager = state.mode.lock().await;
+    for role in manager.mode().roles() {
+        start_role(state, &mut manager, *role);
+    }
+}
+
+fn emit_changed(state: &DaemonState, manager: &ModeManager, previous: NodeMode) {
+    state.event_bus.emit(DaemonEvent::NodeModeChanged {
+        mode: manager.mode().as_str().to_string(),
+        previous: previous.as_str().to_string(),
+        draining: manager.draining().map(|r| r.as_str().to_string()).collect(),
+    });
+}
+
+/// Switch the operating mode and persist it.
+pub async fn set_mode(state: &Arc<DaemonState>, mode: NodeMode) {
+    {
+        let conn = state.db.lock().await;
+        if let Err(e) = ochra_db::queries::settings::set(&conn, NODE_MODE_KEY, mode.as_str()) {
+            error!("Failed to persist node mode: {}", e);
+        }
+    }
+    let mut manager = state.mode.lock().await;
+    let change = manager.set_mode(mode, now_secs());
+    if change.previous == mode {
+        return;
+    }
+    info!(
+        "Node mode {} -> {}",
+        change.previous.as_str(),
+        mode.as_str()
+    );
+    for role in &change.gained {
+        start_role(state, &mut manager, *role);
+    }
+    // Would: send a fresh CapabilityExchange with local_features() to
+    // every connected peer
+    emit_changed(state, &manager, change.previous);
+    if change.drain_started {
+        tokio::spawn(run_drain(state.clone()));
+    }
+}
+
+/// Whether a draining role has no held work left.
+async fn is_drained(state: &DaemonState, role: Role) -> bool {
+    match role {
+        Role::Relay => state.mailboxes.lock().await.envelope_count() == 0,
+        Role::Storage => {
+            let uploads = state.uploads.lock().await;
+            uploads.active_slots() == 0 && uploads.queue_len() == 0
+        }
+        Role::Quorum => true,
+    }
+}
+
+/// Give up on a role's held work at the drain deadline.
+async fn abandon(state: &DaemonState, role: Role) {
+    match role {
+        Role::Relay => {
+            // Senders re-deposit undelivered messages elsewhere.
+            *state.mailboxes.lock().await = ochra_whisper::mailbox::MailboxStore::new(
+                ochra_whisper::mailbox::MailboxConfig::for_network(&state.profile),
+            );
+        }
+        Role::Storage => {
+            // Would: withdraw this node's chunk-loc provider records
+        }
+        Role::Quorum => {}
+    }
+}
+
+/// Wait for draining roles to finish, then announce the settled mode.
+async fn run_drain(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(DRAIN_POLL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let (roles, expired) = {
+                    let manager = state.mode.lock().await;
+                    (
+                        manager.draining().collect::<Vec<_>>(),
+                        manager.drain_expired(now_secs()),
+                    )
+                };
+                if roles.is_empty() {
+                    break;
+                }
+                let mut done = Vec::new();
+                for role in roles {
+                    if is_drained(&state, role).await {
+                        done.push(role);
+                    } else if expired {
+                        abandon(&state, role).await;
+                        done.push(role);
+                    }
+                }
+                if done.is_empty() {
+                    continue;
+                }
+                let mut manager = state.mode.lock().await;
+                let mut settled = false;
+                for role in done {
+                    info!("{} role drained", role.as_str());
+                    settled = manager.finish_drain(role);
+                }
+                if settled {
+                    let mode = manager.mode();
+                    emit_changed(&state, &manager, mode);
+                    break;
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_roles_and_features() {
+        assert_eq!(NodeMode::ClientOnly.features(), 0);
+        assert_eq!(NodeMode::Relay.features(), FEATURE_RELAY);
+        assert_eq!(
+            NodeMode::QuorumCandidate.features(),
+            FEATURE_RELAY | FEATURE_STORAGE | FEATURE_QUORUM_CANDIDATE
+        );
+        for mode in [
+            NodeMode::ClientOnly,
+            NodeMode::Relay,
+            NodeMode::RelayStorage,
+            NodeMode::QuorumCandidate,
+        ] {
+            assert_eq!(NodeMode::parse(mode.as_str()), Some(mode));
+        }
+        assert_eq!(NodeMode::parse("full"), None);
+    }
+
+    #[test]
+    fn test_dropped_roles_drain() {
+        let mut manager = ModeManager::new(NodeMode::RelayStorage);
+        let change = manager.set_mode(NodeMode::ClientOnly, 100);
+        assert_eq!(change.previous, NodeMode::RelayStorage);
+        assert!(change.gained.is_empty());
+        assert!(change.drain_started);
+
+        assert!(!manager.accepts(Role::Relay));
+        assert!(manager.serves(Role::Relay));
+        assert_eq!(manager.drain_deadline(), Some(100 + DRAIN_TIMEOUT_SECS));
+        assert!(!manager.drain_expired(100 + DRAIN_TIMEOUT_SECS - 1));
+        assert!(manager.drain_expired(100 + DRAIN_TIMEOUT_SECS));
+
+        assert!(!manager.finish_drain(Role::Relay));
+        assert!(!manager.serves(Role::Relay));
+        assert!(manager.finish_drain(Role::Storage));
+        assert_eq!(manager.drain_deadline(), None);
+    }
+
+    #[test]
+    fn test_regained_role_resumes_without_restart() {
+        let mut manager = ModeManager::new(NodeMode::RelayStorage);
+        let generation = manager.start_loops(Role::Relay).expect("start");
+        manager.set_mode(NodeMode::ClientOnly, 0);
+
+        // Switching back mid-drain needs no new loops or drain task.
+        let change = manager.set_mode(NodeMode::QuorumCandidate, 10);
+        assert_eq!(change.gained, vec![Role::Quorum]);
+        assert!(!change.drain_started);
+        assert_eq!(manager.draining().count(), 0);
+        assert!(manager.keep_running(Role::Relay, generation));
+        assert_eq!(manager.start_loops(Role::Relay), None);
+    }
+
+    #[test]
+    fn test_loops_stop_after_drain() {
+        let mut manager = ModeManager::new(NodeMode::RelayStorage);
+        let first = manager.start_loops(Role::Storage).expect("start");
+        manager.set_mode(NodeMode::Relay, 0);
+        assert!(manager.keep_running(Role::Storage, first));
+        manager.finish_drain(Role::Storage);
+        // The first of the role's loops to tick records the stop.
+        assert!(!manager.keep_running(Role::Storage, first));
+
+        // Switching back starts a new generation; a loop of the old one
+        // that has not ticked yet exits instead of running twice.
+        let change = manager.set_mode(NodeMode::RelayStorage, 20);
+        assert_eq!(change.gained, vec![Role::Storage]);
+        let second = manager.start_loops(Role::Storage).expect("restart");
+        assert!(!manager.keep_running(Role::Storage, first));
+        assert!(manager.keep_running(Role::Storage, second));
+    }
+}
@@ -0,0 +1,48 @@
+//! Network profile binding (Section 2.5).
+//!
+//! A database records the protocol magic of the network it was created
+//! on. Opening it under a different profile is refused, so a node
+//! switched to testnet cannot carry mainnet state, or the reverse.
+
+use ochra_db::DbError;
+use ochra_types::profile::NetworkProfile;
+use rusqlite::Connection;
+
+/// Settings key holding the database's network magic.
+const NETWORK_MAGIC_KEY: &str = "network_magic";
+
+/// Bind a new database to `profile`'s network, or check that an existing
+/// one belongs to it.
+///
+/// # Errors
+///
+/// - The database was created on a different network
+/// - The setting could not be read or written
+pub fn check_database(conn: &Connection, profile: &NetworkProfile) -> anyhow::Result<()> {
+    match ochra_db::queries::settings::get(conn, NETWORK_MAGIC_KEY) {
+        Ok(stored) if stored == profile.magic.to_string() => Ok(()),
+        Ok(stored) => anyhow::bail!(
+            "database belongs to network magic {stored}, not {} ({}); use a separate data_dir",
+            profile.magic,
+            profile.kind.as_str()
+        ),
+        Err(DbError::NotFound(_)) => {
+            ochra_db::queries::settings::set(conn, NETWORK_MAGIC_KEY, &profile.magic.to_string())?;
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_database_bound_to_first_network() {
+        let conn = ochra_db::open_memory().expect("open");
+        check_database(&conn, &NetworkProfile::testnet()).expect("bind");
+        check_database(&conn, &NetworkProfile::testnet()).expect("same network");
+        assert!(check_database(&conn, &NetworkProfile::mainnet()).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! Onion circuit health monitoring loop (Section 4.9).
+//!
+//! Sends echo cells through every tracked circuit, fails the ones that stop
+//! answering, and emits events so the UI can warn while traffic rides a
+//! circuit that may be dropping it.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_onion::health::HealthChange;
+use tracing::{debug, warn};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Interval between monitor passes.
+const MONITOR_INTERVAL_SECS: u64 = 1;
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Probe circuits and fail over dead ones until shutdown.
+pub async fn run_monitor(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(MONITOR_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                let now = now_ms();
+                let mut monitor = state.circuit_health.lock().await;
+                let changes = monitor.expire(now);
+                for probe in monitor.start_due_probes(now) {
+                    // Would: wrap the echo cell in a Sphinx packet addressed to
+                    // hop `probe.cell.depth` and send it through the circuit
+                    debug!(
+                        "Echo probe {} to hop {} of circuit {}",
+                        hex::encode(probe.cell.probe_id),
+                        probe.cell.depth,
+                        hex::encode(probe.circuit_id)
+                    );
+                }
+                let healthy = u32::try_from(monitor.healthy_count()).unwrap_or(u32::MAX);
+                drop(monitor);
+                for change in changes {
+                    report(&state, change, healthy).await;
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+/// Emit the event for a health transition and act on failures.
+async fn report(state: &DaemonState, change: HealthChange, healthy_circuits: u32) {
+    let event = match change {
+        HealthChange::Degraded {
+            consecutive_failures,
+            ..
+        } => DaemonEvent::OnionCircuitDegraded {
+            consecutive_failures,
+            healthy_circuits,
+        },
+        HealthChange::Recovered { .. } => DaemonEvent::OnionCircuitRecovered { healthy_circuits },
+        HealthChange::Failed {
+            circuit_id,
+            suspect_hop,
+            suspect_relay,
+        } => {
+            warn!(
+                "Circuit {} failed echo probes (suspect hop {:?}, relay {})",
+                hex::encode(circuit_id),
+                suspect_hop,
+                suspect_relay.map(hex::encode).unwrap_or_default()
+            );
+            state.isolation.lock().await.release(&circuit_id);
+            // Would: tear down the circuit, build a replacement that avoids
+            // the suspect relay, and track it
+            DaemonEvent::OnionCircuitFailover {
+                suspect_hop,
+                healthy_circuits,
+            }
+        }
+    };
+    state.event_bus.emit(event);
+}
@@ -0,0 +1,255 @@
+//! Delegated operational keys (Section 6.9).
+//!
+//! Long-running roles (relay descriptors, DHT records, transport and
+//! receipt signatures) sign with an operational subkey instead of the PIK.
+//! The PIK issues the subkey a [`DelegationCert`] limited in scope and to a
+//! few epochs; the daemon rotates the subkey before the certificate runs
+//! out, whenever the session is unlocked. Rotation never changes the PIK,
+//! and a leaked subkey is only usable within its scope until its
+//! certificate expires.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_crypto::ed25519::{DelegationCert, DelegationScope, KeyPair, SigningKey};
+use ochra_crypto::secret::SecretBytes;
+use ochra_dht::bep44::{create_mutable_record, DhtRecord};
+use ochra_dht::record_types::RELAY_DESCRIPTOR_SALT;
+use ochra_types::network::RelayDescriptor;
+use tracing::{error, info};
+
+use crate::DaemonState;
+
+/// Key store label of the operational subkey.
+pub const OPERATIONAL_KEY_LABEL: &str = "operational-key";
+
+/// Settings key holding the encoded certificate, hex.
+const CERT_KEY: &str = "operational_key_cert";
+
+/// Epochs a new certificate is valid for, including the current one.
+pub const DEFAULT_VALIDITY_EPOCHS: u32 = 7;
+
+/// Rotate once this few epochs of validity remain.
+const ROTATE_BEFORE_EPOCHS: u32 = 1;
+
+/// How often the rotator checks the certificate.
+const CHECK_INTERVAL_SECS: u64 = 3600;
+
+/// An operational subkey and the certificate delegating to it.
+pub struct OperationalKey {
+    pub cert: DelegationCert,
+    pub signing_key: SigningKey,
+}
+
+impl OperationalKey {
+    /// Sign `descriptor` as a relay descriptor record published by the
+    /// subkey, attaching the delegation.
+    #[allow(dead_code)]
+    pub fn relay_descriptor_record(
+        &self,
+        descriptor: &RelayDescriptor,
+    ) -> anyhow::Result<DhtRecord> {
+        // Would: publish from the relay role once per relay epoch
+        let mut descriptor = descriptor.clone();
+        descriptor.delegation = Some(self.cert.to_bytes());
+        Ok(create_mutable_record(
+            &self.signing_key,
+            RELAY_DESCRIPTOR_SALT,
+            u64::from(descriptor.relay_epoch),
+            ochra_transport::cbor::to_vec(&descriptor)?,
+        )?)
+    }
+}
+
+/// The current network epoch as carried in certificates.
+pub fn current_epoch() -> u32 {
+    u32::try_from(crate::epoch::current_epoch()).unwrap_or(u32::MAX)
+}
+
+/// Whether the certificate is missing or close enough to expiry to rotate.
+pub fn needs_rotation(cert: Option<&DelegationCert>, epoch: u32) -> bool {
+    match cert {
+        None => true,
+        Some(cert) => {
+            !cert.is_valid_at(epoch)
+                || cert.valid_until_epoch.saturating_sub(epoch) < ROTATE_BEFORE_EPOCHS
+        }
+    }
+}
+
+/// Parse scope names, e.g. `["relay", "dht_records"]`.
+pub fn parse_scope(names: &[&str]) -> Option<DelegationScope> {
+    names
+        .iter()
+        .try_fold(DelegationScope::default(), |scope, name| {
+            DelegationScope::from_name(name).map(|s| scope.union(s))
+        })
+        .filter(|scope| scope.0 != 0)
+}
+
+/// The stored certificate, if any.
+pub async fn stored_cert(state: &Arc<DaemonState>) -> anyhow::Result<Option<DelegationCert>> {
+    let db = state.db.lock().await;
+    let encoded = match ochra_db::queries::settings::get(&db, CERT_KEY) {
+        Ok(encoded) => encoded,
+        Err(ochra_db::DbError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some(DelegationCert::from_bytes(&hex::decode(encoded)?)?))
+}
+
+/// The operational key, if one is delegated for the current epoch and its
+/// secret is in the key store.
+pub async fn current(state: &Arc<DaemonState>) -> anyhow::Result<Option<OperationalKey>> {
+    let Some(cert) = stored_cert(state).await? else {
+        return Ok(None);
+    };
+    if !cert.is_valid_at(current_epoch()) {
+        return Ok(None);
+    }
+    let Some(secret) = state.keystore.load(OPERATIONAL_KEY_LABEL)? else {
+        return Ok(None);
+    };
+    let signing_key = SigningKey::from_bytes(secret.expose());
+    if signing_key.verifying_key().to_bytes() != cert.subkey_pk {
+        return Ok(None);
+    }
+    Ok(Some(OperationalKey { cert, signing_key }))
+}
+
+/// Generate a fresh subkey and delegate `scope` to it for `validity_epochs`
+/// starting now, replacing the previous one.
+///
+/// Returns `None` if the session is locked, since only the PIK can sign
+/// the certificate.
+pub async fn rotate(
+    state: &Arc<DaemonState>,
+    scope: DelegationScope,
+    validity_epochs: u32,
+) -> anyhow::Result<Option<DelegationCert>> {
+    let Some(pik) = crate::audit::pik_signing_key(state).await? else {
+        return Ok(None);
+    };
+    let subkey = KeyPair::generate();
+    let from = current_epoch();
+    let until = from.saturating_add(validity_epochs.max(1) - 1);
+    let cert = DelegationCert::issue(&pik, &subkey.verifying_key, scope, from, until)?;
+
+    state.keystore.store(
+        OPERATIONAL_KEY_LABEL,
+        &SecretBytes::new(subkey.signing_key.to_bytes()),
+    )?;
+    let db = state.db.lock().await;
+    ochra_db::queries::settings::set(&db, CERT_KEY, &hex::encode(cert.to_bytes()))?;
+    info!(
+        "Rotated operational key: scope {:?}, epochs {}..={}",
+        scope.names(),
+        from,
+        until
+    );
+    Ok(Some(cert))
+}
+
+/// Rotate if due, keeping the current scope.
+async fn rotate_if_due(state: &Arc<DaemonState>) -> anyhow::Result<()> {
+    let cert = stored_cert(state).await?;
+    if !needs_rotation(cert.as_ref(), current_epoch()) {
+        return Ok(());
+    }
+    let scope = cert.map_or(DelegationScope::ALL, |c| c.scope);
+    rotate(state, scope, DEFAULT_VALIDITY_EPOCHS).await?;
+    Ok(())
+}
+
+/// Rotate the operational key before its certificate expires.
+pub async fn run_rotator(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                if let Err(e) = rotate_if_due(&state).await {
+                    error!("Failed to rotate operational key: {}", e);
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cert(from: u32, until: u32) -> DelegationCert {
+        let pik = KeyPair::generate();
+        let sub = KeyPair::generate();
+        DelegationCert::issue(
+            &pik.signing_key,
+            &sub.verifying_key,
+            DelegationScope::RELAY,
+            from,
+            until,
+        )
+        .expect("cert")
+    }
+
+    #[test]
+    fn test_needs_rotation_before_expiry() {
+        assert!(needs_rotation(None, 10));
+        let c = cert(10, 16);
+        assert!(!needs_rotation(Some(&c), 10));
+        assert!(!needs_rotation(Some(&c), 15));
+        assert!(needs_rotation(Some(&c), 16));
+        assert!(needs_rotation(Some(&c), 17));
+        assert!(needs_rotation(Some(&c), 9));
+    }
+
+    #[test]
+    fn test_relay_descriptor_record_validates_as_delegated() {
+        let pik = KeyPair::generate();
+        let sub = KeyPair::generate();
+        let cert = DelegationCert::issue(
+            &pik.signing_key,
+            &sub.verifying_key,
+            DelegationScope::ALL,
+            0,
+            10,
+        )
+        .expect("cert");
+        let key = OperationalKey {
+            cert,
+            signing_key: sub.signing_key,
+        };
+        let descriptor = RelayDescriptor {
+            node_id: [1; 32],
+            pik_hash: ochra_crypto::blake3::hash(&pik.verifying_key.to_bytes()),
+            x25519_pk: [2; 32],
+            mlkem768_ek: Vec::new(),
+            relay_epoch: 24,
+            posrv_score: 1.0,
+            ip_addr: "127.0.0.1:4433".to_string(),
+            as_number: 1,
+            country_code: *b"US",
+            bandwidth_cap_mbps: 100,
+            uptime_epochs: 1,
+            exit_policy: Default::default(),
+            delegation: None,
+            sig: [0; 64],
+        };
+        let record = key.relay_descriptor_record(&descriptor).expect("record");
+        assert!(ochra_dht::record_types::RecordTypeRegistry::standard()
+            .validate(&record)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_parse_scope() {
+        assert_eq!(
+            parse_scope(&["relay", "dht_records"]),
+            Some(DelegationScope::RELAY.union(DelegationScope::DHT_RECORDS))
+        );
+        assert_eq!(parse_scope(&["relay", "root"]), None);
+        assert_eq!(parse_scope(&[]), None);
+    }
+}
@@ -0,0 +1,72 @@
+//! Exchange reference price polling (Section 11.7).
+//!
+//! With `network.oracle_polling` set, the node fetches the five exchange
+//! tickers at 00:00 and 12:00 UTC, and once at startup. Every fetch goes
+//! through [`egress::get`], so it follows the node's proxy rules and dial
+//! quotas. A poll that gets at least three answers updates the TWAP in
+//! `DaemonState::oracle`; `get_oracle_twap` reports its staleness and
+//! circuit breaker.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_oracle::exchanges::Exchange;
+use tracing::{debug, info, warn};
+
+use crate::{egress, DaemonState};
+
+/// Seconds between polls, aligned to UTC midnight.
+const POLL_INTERVAL_SECS: u64 = 12 * 60 * 60;
+
+/// Largest ticker response accepted.
+const MAX_TICKER_BYTES: usize = 64 * 1024;
+
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Fetch one exchange's ticker and return its price in micro-dollars.
+async fn fetch_price(state: &DaemonState, exchange: Exchange) -> anyhow::Result<u64> {
+    let body = egress::get(state, exchange.ticker_url(), MAX_TICKER_BYTES).await?;
+    Ok(exchange.parse_price(&body)?)
+}
+
+/// Fetch every exchange's ticker and fold the answers into the TWAP.
+///
+/// Returns the new TWAP in micro-dollars.
+pub async fn poll(state: &Arc<DaemonState>) -> anyhow::Result<u64> {
+    let mut fetches = tokio::task::JoinSet::new();
+    for exchange in Exchange::ALL {
+        let state = state.clone();
+        fetches.spawn(async move { (exchange, fetch_price(&state, exchange).await) });
+    }
+    let mut prices = Vec::with_capacity(Exchange::ALL.len());
+    while let Some(joined) = fetches.join_next().await {
+        match joined? {
+            (_, Ok(price)) => prices.push(price),
+            (exchange, Err(e)) => debug!("Oracle fetch from {} failed: {:#}", exchange.name(), e),
+        }
+    }
+    Ok(state.oracle.lock().await.record_poll(&prices, now_secs())?)
+}
+
+/// Poll the exchanges at startup and then every half epoch until shutdown.
+pub async fn run_poller(state: Arc<DaemonState>) {
+    let period = Duration::from_secs(POLL_INTERVAL_SECS);
+    let first = Duration::from_secs(POLL_INTERVAL_SECS - now_secs() % POLL_INTERVAL_SECS);
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + first, period);
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        match poll(&state).await {
+            Ok(twap) => info!("Oracle reference TWAP updated to {} micro-USD", twap),
+            Err(e) => warn!("Oracle poll failed: {}", e),
+        }
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
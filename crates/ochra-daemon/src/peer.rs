@@ -0,0 +1,778 @@
+//! Peer connections (Sections 4.1-4.2).
+//!
+//! Peers reach this node over QUIC on the listen port, or over TCP/TLS on
+//! the same port number when QUIC is blocked. The first stream of each
+//! connection opens with a capability exchange, in both directions;
+//! the peer's list is recorded in the [`CapabilityRegistry`] once it is
+//! checked to be on this network, and blacklisted peers are refused. Every
+//! message after the exchange, on that stream or a later one, is decoded
+//! strictly and passed to [`dispatch`], and any reply is written back on
+//! the same stream. Types this node does not handle are answered with
+//! `Unsupported`; undecodable frames are reported as protocol violations
+//! and end the stream.
+//!
+//! Outbound messages go through [`request`] or [`send`], which open a stream with the
+//! message's lane priority on the peer's connection after
+//! [`CapabilityRegistry::check_send`] confirms the peer advertised the type.
+//! [`connect`] dials a peer that has no connection yet, over QUIC first and
+//! then TCP/TLS when the fallback is enabled. Peers whose address the egress
+//! rules send through a proxy are dialed over TCP/TLS only, since QUIC
+//! cannot cross the proxy.
+//!
+//! A TCP/TLS stream carries one exchange and its messages, so a peer reached
+//! that way has no lasting connection: each message dials a new stream,
+//! which opens with its own capability exchange.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use ochra_transport::capabilities::{self, CapabilityRegistry};
+use ochra_transport::dial::{DialPriority, DialSubsystem};
+use ochra_transport::messages::{CapabilityExchange, GossipForward, Pong, TypedMessage};
+use ochra_transport::misbehavior::{Offense, Standing};
+use ochra_transport::pluggable::{
+    read_frame, write_frame, BoxedStream, PluggableTransport, TcpTlsListener, TcpTlsTransport,
+    TransportKind, DEFAULT_DIAL_TIMEOUT,
+};
+use ochra_transport::proxy::{ProxyRules, ProxyTarget};
+use ochra_transport::qos::Lane;
+use ochra_transport::quic::{QuicConfig, QuicNode};
+use ochra_transport::strict::ProtocolViolation;
+use ochra_transport::wire::{ProtocolMessage, MAX_MESSAGE_SIZE};
+use ochra_transport::TransportError;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::call_trace::{timed, Phase};
+use crate::DaemonState;
+
+/// Agent string advertised in capability exchanges.
+const AGENT: &str = concat!("ochra/", env!("CARGO_PKG_VERSION"));
+
+/// TLS server name presented when dialing (certificates are self-signed).
+const SERVER_NAME: &str = "ochra-node";
+
+/// Seconds allowed for a capability exchange or a reply to arrive.
+const EXCHANGE_TIMEOUT_SECS: u64 = 10;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// How an open peer connection is reached.
+#[derive(Clone)]
+enum Link {
+    /// A QUIC connection; each message opens a stream on it.
+    Quic(quinn::Connection),
+    /// A peer dialed over TCP/TLS at this address; each message dials a new
+    /// stream.
+    TcpTls(SocketAddr),
+}
+
+impl Link {
+    fn remote_addr(&self) -> SocketAddr {
+        match self {
+            Self::Quic(connection) => connection.remote_address(),
+            Self::TcpTls(addr) => *addr,
+        }
+    }
+
+    fn close(&self, reason: &[u8]) {
+        if let Self::Quic(connection) = self {
+            connection.close(0u32.into(), reason);
+        }
+    }
+}
+
+/// This node's QUIC endpoint and TCP/TLS dialer, open connections, and
+/// peers' capabilities.
+pub struct Peers {
+    node_id: [u8; 32],
+    quic: QuicNode,
+    tcp: TcpTlsTransport,
+    tcp_fallback: bool,
+    connections: Mutex<HashMap<[u8; 32], Link>>,
+    capabilities: Mutex<CapabilityRegistry>,
+}
+
+impl Peers {
+    /// Bind the QUIC endpoint on `port` (0 = OS-assigned) for both IPv4 and
+    /// IPv6, accepting peers on the network with `network_magic`.
+    pub fn bind(node_id: [u8; 32], port: u16, network_magic: u32) -> Result<Self, TransportError> {
+        Ok(Self {
+            node_id,
+            quic: QuicNode::new(QuicConfig::dual_stack(port))?,
+            tcp: TcpTlsTransport::new()?,
+            tcp_fallback: false,
+            connections: Mutex::new(HashMap::new()),
+            capabilities: Mutex::new(CapabilityRegistry::for_network(network_magic)),
+        })
+    }
+
+    /// Dial TCP/TLS through the proxies `egress` picks.
+    pub fn with_egress(mut self, egress: ProxyRules) -> Self {
+        self.tcp = self.tcp.with_proxy_rules(egress);
+        self
+    }
+
+    /// Accept TCP/TLS on the listen port, and dial a peer over it when QUIC
+    /// fails.
+    pub fn with_tcp_fallback(mut self, enabled: bool) -> Self {
+        self.tcp_fallback = enabled;
+        self
+    }
+
+    /// The transports to try, in order, when dialing `addr`.
+    fn dial_order(&self, addr: SocketAddr) -> &'static [TransportKind] {
+        if self
+            .tcp
+            .proxy_rules()
+            .route(&ProxyTarget::Addr(addr))
+            .is_some()
+        {
+            &[TransportKind::TcpTls]
+        } else if self.tcp_fallback {
+            &[TransportKind::Quic, TransportKind::TcpTls]
+        } else {
+            &[TransportKind::Quic]
+        }
+    }
+
+    /// The address peers reach this node on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.quic.local_addr()
+    }
+
+    /// The address of `peer`'s open connection, if any.
+    pub async fn remote_addr(&self, peer: &[u8; 32]) -> Option<SocketAddr> {
+        self.connections
+            .lock()
+            .await
+            .get(peer)
+            .map(Link::remote_addr)
+    }
+
+    /// `peer`'s open QUIC connection, if any.
+    pub async fn connection(&self, peer: &[u8; 32]) -> Option<quinn::Connection> {
+        match self.connections.lock().await.get(peer) {
+            Some(Link::Quic(connection)) => Some(connection.clone()),
+            _ => None,
+        }
+    }
+
+    /// Move the endpoint to a fresh socket after a network change, keeping
+    /// open connections. The old socket still holds the listen port, so the
+    /// new one takes any free port.
+    pub fn rebind(&self) -> Result<SocketAddr, TransportError> {
+        let addr = SocketAddr::new(self.quic.local_addr().ip(), 0);
+        self.quic.rebind(addr)
+    }
+
+    /// Whether `peer` has an open connection.
+    pub async fn is_connected(&self, peer: &[u8; 32]) -> bool {
+        self.connections.lock().await.contains_key(peer)
+    }
+
+    /// Connected peers advertising `feature`.
+    pub async fn with_feature(&self, feature: u64) -> Vec<[u8; 32]> {
+        self.capabilities.lock().await.peers_with_feature(feature)
+    }
+
+    /// Close the connection to `peer`, if any, and forget its capabilities.
+    pub async fn disconnect(&self, peer: &[u8; 32]) {
+        if let Some(link) = self.connections.lock().await.remove(peer) {
+            link.close(b"");
+        }
+        self.capabilities.lock().await.forget(peer);
+    }
+}
+
+/// This node's ID: its PIK hash, or a random ID until an identity exists.
+pub fn local_node_id(conn: &rusqlite::Connection) -> [u8; 32] {
+    let pik_hash: Option<Vec<u8>> = conn
+        .query_row("SELECT pik_hash FROM pik WHERE id = 1", [], |row| {
+            row.get(0)
+        })
+        .ok();
+    pik_hash
+        .and_then(|h| h.try_into().ok())
+        .unwrap_or_else(rand::random)
+}
+
+async fn local_exchange(state: &DaemonState) -> CapabilityExchange {
+    capabilities::local_exchange(
+        state.peers.node_id,
+        crate::mode::local_features(state).await,
+        AGENT,
+        state.profile.magic,
+    )
+}
+
+async fn write_message(stream: &mut BoxedStream, msg: &TypedMessage) -> anyhow::Result<()> {
+    let bytes = ProtocolMessage::from_typed(msg)?.to_bytes()?;
+    write_frame(stream, &bytes).await?;
+    Ok(())
+}
+
+async fn read_message(stream: &mut BoxedStream) -> anyhow::Result<TypedMessage> {
+    let bytes = tokio::time::timeout(
+        Duration::from_secs(EXCHANGE_TIMEOUT_SECS),
+        read_frame(stream, MAX_MESSAGE_SIZE),
+    )
+    .await
+    .context("timed out waiting for peer")??;
+    let msg = ProtocolMessage::from_bytes_strict(&bytes)?;
+    Ok(msg.decode_payload_strict()?)
+}
+
+/// Check and record a peer's capability exchange, returning its node ID.
+async fn record_exchange(
+    state: &DaemonState,
+    theirs: &CapabilityExchange,
+) -> anyhow::Result<[u8; 32]> {
+    let peer = theirs.node_id;
+    if state.misbehavior.lock().await.standing(&peer, now_secs()) == Standing::Blacklisted {
+        anyhow::bail!("peer {} is blacklisted", hex::encode(&peer[..8]));
+    }
+    state.peers.capabilities.lock().await.record(peer, theirs)?;
+    Ok(peer)
+}
+
+/// Start tracking a newly connected peer.
+async fn register(state: &Arc<DaemonState>, peer: [u8; 32], link: Link) {
+    let remote = link.remote_addr();
+    if let Some(old) = state
+        .peers
+        .connections
+        .lock()
+        .await
+        .insert(peer, link.clone())
+    {
+        old.close(b"replaced");
+    }
+    state.keepalive.lock().await.track(peer, now_secs());
+    crate::gossip::add_peer(state, peer).await;
+    debug!(
+        "Connected to peer {} at {}",
+        hex::encode(&peer[..8]),
+        remote
+    );
+
+    // Only QUIC connections migrate to a new path and accept streams; a
+    // TCP/TLS peer opens its own streams to the listener.
+    let Link::Quic(connection) = link else {
+        return;
+    };
+    state.migration.lock().await.track(peer, remote);
+    let state = state.clone();
+    tokio::spawn(async move {
+        while let Ok((send, recv)) = QuicNode::accept_bi(&connection).await {
+            tokio::spawn(serve_stream(
+                state.clone(),
+                peer,
+                Box::new(tokio::io::join(recv, send)),
+            ));
+        }
+        unregister(&state, peer, &connection).await;
+    });
+}
+
+/// Stop tracking `peer` once `connection` has closed, unless it has been
+/// replaced by a newer one.
+async fn unregister(state: &DaemonState, peer: [u8; 32], connection: &quinn::Connection) {
+    let mut connections = state.peers.connections.lock().await;
+    if matches!(
+        connections.get(&peer),
+        Some(Link::Quic(c)) if c.stable_id() == connection.stable_id()
+    ) {
+        connections.remove(&peer);
+        drop(connections);
+        state.peers.capabilities.lock().await.forget(&peer);
+        state.keepalive.lock().await.untrack(&peer);
+        state.migration.lock().await.untrack(&peer);
+        crate::gossip::remove_peer(state, &peer).await;
+        debug!("Peer {} disconnected", hex::encode(&peer[..8]));
+    }
+}
+
+/// Accept peers until shutdown.
+pub async fn run_listener(state: Arc<DaemonState>) {
+    info!("Accepting peers on {}", state.peers.local_addr());
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            incoming = state.peers.quic.accept() => {
+                let Some(incoming) = incoming else { break };
+                let state = state.clone();
+                tokio::spawn(async move {
+                    match incoming.await {
+                        Ok(connection) => {
+                            if let Err(e) = accept_connection(&state, connection).await {
+                                debug!("Refused peer connection: {}", e);
+                            }
+                        }
+                        Err(e) => debug!("Incoming peer connection failed: {}", e),
+                    }
+                });
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+    state.peers.quic.close(0, b"shutdown");
+}
+
+/// Complete the capability exchange on a connection's first stream.
+async fn accept_connection(
+    state: &Arc<DaemonState>,
+    connection: quinn::Connection,
+) -> anyhow::Result<()> {
+    let (send, recv) = tokio::time::timeout(
+        Duration::from_secs(EXCHANGE_TIMEOUT_SECS),
+        QuicNode::accept_bi(&connection),
+    )
+    .await
+    .context("timed out waiting for capability exchange")??;
+    let mut stream: BoxedStream = Box::new(tokio::io::join(recv, send));
+    let TypedMessage::CapabilityExchange(theirs) = read_message(&mut stream).await? else {
+        connection.close(0u32.into(), b"expected capability exchange");
+        anyhow::bail!("peer did not open with a capability exchange");
+    };
+    let peer = match record_exchange(state, &theirs).await {
+        Ok(peer) => peer,
+        Err(e) => {
+            connection.close(0u32.into(), b"refused");
+            return Err(e);
+        }
+    };
+    write_message(
+        &mut stream,
+        &TypedMessage::CapabilityExchange(local_exchange(state).await),
+    )
+    .await?;
+    register(state, peer, Link::Quic(connection)).await;
+    tokio::spawn(serve_stream(state.clone(), peer, stream));
+    Ok(())
+}
+
+/// Accept peers over TCP/TLS on the listen port until shutdown, if the
+/// fallback is enabled.
+pub async fn run_tcp_listener(state: Arc<DaemonState>) {
+    if !state.peers.tcp_fallback {
+        return;
+    }
+    let addr = state.peers.local_addr();
+    let listener = match TcpTlsListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Cannot accept TCP/TLS peers on {}: {}", addr, e);
+            return;
+        }
+    };
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            // The handshake runs inside accept, so a stalled one is dropped
+            // rather than holding up the next peer
+            accepted = tokio::time::timeout(
+                Duration::from_secs(EXCHANGE_TIMEOUT_SECS),
+                listener.accept(),
+            ) => {
+                let Ok(accepted) = accepted else { continue };
+                match accepted {
+                    Ok((stream, remote)) => {
+                        let state = state.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = accept_tcp_stream(&state, stream).await {
+                                debug!("Refused TCP/TLS stream from {}: {}", remote, e);
+                            }
+                        });
+                    }
+                    Err(e) => debug!("Incoming TCP/TLS stream failed: {}", e),
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+/// Serve a TCP/TLS stream, which opens with the peer's capability exchange.
+async fn accept_tcp_stream(
+    state: &Arc<DaemonState>,
+    mut stream: BoxedStream,
+) -> anyhow::Result<()> {
+    let TypedMessage::CapabilityExchange(theirs) = read_message(&mut stream).await? else {
+        anyhow::bail!("peer did not open with a capability exchange");
+    };
+    let peer = record_exchange(state, &theirs).await?;
+    write_message(
+        &mut stream,
+        &TypedMessage::CapabilityExchange(local_exchange(state).await),
+    )
+    .await?;
+    serve_stream(state.clone(), peer, stream).await;
+    // Nothing is sent to a peer known only from its own streams
+    if !state.peers.is_connected(&peer).await {
+        state.peers.capabilities.lock().await.forget(&peer);
+    }
+    Ok(())
+}
+
+/// Connect to the node at `addr`, returning its node ID.
+///
+/// With `expected` set, the node must answer with that ID, and nothing is
+/// dialed if it already has a connection.
+pub async fn connect(
+    state: &Arc<DaemonState>,
+    addr: SocketAddr,
+    expected: Option<[u8; 32]>,
+    subsystem: DialSubsystem,
+) -> anyhow::Result<[u8; 32]> {
+    if let Some(peer) = expected {
+        if state.peers.is_connected(&peer).await {
+            return Ok(peer);
+        }
+    }
+    let permit = timed(
+        Phase::Network,
+        state.dials.acquire(subsystem, DialPriority::Normal),
+    )
+    .await?;
+    let result = timed(Phase::Network, dial(state, addr, expected)).await;
+    match &result {
+        Ok(_) => permit.succeeded(),
+        Err(_) => permit.failed(),
+    }
+    let (peer, link, stream) = result.with_context(|| format!("connecting to {addr}"))?;
+    register(state, peer, link).await;
+    tokio::spawn(serve_stream(state.clone(), peer, stream));
+    Ok(peer)
+}
+
+async fn dial(
+    state: &DaemonState,
+    addr: SocketAddr,
+    expected: Option<[u8; 32]>,
+) -> anyhow::Result<([u8; 32], Link, BoxedStream)> {
+    let mut last_error = None;
+    for &kind in state.peers.dial_order(addr) {
+        let attempt = match kind {
+            TransportKind::Quic => {
+                tokio::time::timeout(DEFAULT_DIAL_TIMEOUT, dial_quic(state, addr, expected))
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("QUIC dial timed out")))
+            }
+            TransportKind::TcpTls => dial_tcp(state, addr, expected)
+                .await
+                .map(|(peer, stream)| (peer, Link::TcpTls(addr), stream)),
+        };
+        match attempt {
+            Ok(dialed) => return Ok(dialed),
+            Err(e) => {
+                debug!("Dial to {} over {:?} failed: {:#}", addr, kind, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("no transport reaches {addr}")))
+}
+
+async fn dial_quic(
+    state: &DaemonState,
+    addr: SocketAddr,
+    expected: Option<[u8; 32]>,
+) -> anyhow::Result<([u8; 32], Link, BoxedStream)> {
+    let connection = state.peers.quic.connect(addr, SERVER_NAME).await?;
+    let (send, recv) = QuicNode::open_bi(&connection).await?;
+    let mut stream: BoxedStream = Box::new(tokio::io::join(recv, send));
+    match open_with_exchange(state, &mut stream, expected).await {
+        Ok(peer) => Ok((peer, Link::Quic(connection), stream)),
+        Err(e) => {
+            connection.close(0u32.into(), b"refused");
+            Err(e)
+        }
+    }
+}
+
+/// Dial a TCP/TLS stream to `addr` and open it with the capability
+/// exchange.
+async fn dial_tcp(
+    state: &DaemonState,
+    addr: SocketAddr,
+    expected: Option<[u8; 32]>,
+) -> anyhow::Result<([u8; 32], BoxedStream)> {
+    let mut stream = tokio::time::timeout(DEFAULT_DIAL_TIMEOUT, state.peers.tcp.dial(addr))
+        .await
+        .context("TCP/TLS dial timed out")??;
+    let peer = open_with_exchange(state, &mut stream, expected).await?;
+    Ok((peer, stream))
+}
+
+/// Send this node's capability exchange on a dialed stream and check the
+/// peer's answer.
+async fn open_with_exchange(
+    state: &DaemonState,
+    stream: &mut BoxedStream,
+    expected: Option<[u8; 32]>,
+) -> anyhow::Result<[u8; 32]> {
+    write_message(
+        stream,
+        &TypedMessage::CapabilityExchange(local_exchange(state).await),
+    )
+    .await?;
+    let TypedMessage::CapabilityExchange(theirs) = read_message(stream).await? else {
+        anyhow::bail!("expected capability exchange");
+    };
+    if expected.is_some_and(|peer| peer != theirs.node_id) {
+        anyhow::bail!("peer answered with another node ID");
+    }
+    record_exchange(state, &theirs).await
+}
+
+/// Open a stream to `peer` for `msg`, after checking the peer accepts it.
+async fn open_stream(
+    state: &DaemonState,
+    peer: &[u8; 32],
+    msg: &TypedMessage,
+) -> anyhow::Result<BoxedStream> {
+    let msg_type = msg.msg_type();
+    state
+        .peers
+        .capabilities
+        .lock()
+        .await
+        .check_send(peer, msg_type)?;
+    let link = state
+        .peers
+        .connections
+        .lock()
+        .await
+        .get(peer)
+        .cloned()
+        .with_context(|| format!("peer {} is not connected", hex::encode(&peer[..8])))?;
+    let mut stream: BoxedStream = match link {
+        Link::Quic(connection) => {
+            let (send, recv) =
+                QuicNode::open_lane_stream(&connection, Lane::for_msg_type(msg_type)).await?;
+            Box::new(tokio::io::join(recv, send))
+        }
+        Link::TcpTls(addr) => dial_tcp(state, addr, Some(*peer)).await?.1,
+    };
+    write_message(&mut stream, msg).await?;
+    Ok(stream)
+}
+
+/// Send `msg` to the connected `peer` without waiting for a reply.
+pub async fn send(state: &DaemonState, peer: &[u8; 32], msg: &TypedMessage) -> anyhow::Result<()> {
+    let mut stream = open_stream(state, peer, msg).await?;
+    tokio::io::AsyncWriteExt::shutdown(&mut stream).await?;
+    Ok(())
+}
+
+/// Send `msg` to the connected `peer` and wait for its reply.
+///
+/// An `Unsupported` reply is returned as
+/// [`TransportError::UnsupportedMessage`].
+pub async fn request(
+    state: &DaemonState,
+    peer: &[u8; 32],
+    msg: &TypedMessage,
+) -> anyhow::Result<TypedMessage> {
+    let mut stream = open_stream(state, peer, msg).await?;
+    match read_message(&mut stream).await? {
+        TypedMessage::Unsupported(unsupported) => {
+            Err(TransportError::UnsupportedMessage(unsupported.msg_type).into())
+        }
+        reply => Ok(reply),
+    }
+}
+
+/// Serve messages from `peer` on one stream until it closes or breaks the
+/// protocol.
+async fn serve_stream(state: Arc<DaemonState>, peer: [u8; 32], mut stream: BoxedStream) {
+    while let Ok(bytes) = read_frame(&mut stream, MAX_MESSAGE_SIZE).await {
+        state.keepalive.lock().await.on_received(&peer, now_secs());
+        let reply = match handle_frame(&state, peer, &bytes).await {
+            Ok(reply) => reply,
+            Err(violation) => {
+                debug!(
+                    "Protocol violation from {}: {}",
+                    hex::encode(&peer[..8]),
+                    violation
+                );
+                crate::misbehavior::report(&state, peer, Offense::ProtocolViolation).await;
+                break;
+            }
+        };
+        let Some(reply) = reply else { continue };
+        let written = match reply.to_bytes() {
+            Ok(bytes) => write_frame(&mut stream, &bytes).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            debug!("Failed to reply to {}: {}", hex::encode(&peer[..8]), e);
+            break;
+        }
+    }
+}
+
+/// Handle one frame from `peer`, returning the reply to write back.
+async fn handle_frame(
+    state: &Arc<DaemonState>,
+    peer: [u8; 32],
+    bytes: &[u8],
+) -> Result<Option<ProtocolMessage>, ProtocolViolation> {
+    let msg = ProtocolMessage::from_bytes_strict(bytes)?;
+    match capabilities::unsupported_reply(&msg) {
+        Ok(Some(reply)) => return Ok(Some(reply)),
+        Ok(None) => {}
+        Err(e) => {
+            warn!("Failed to build Unsupported reply: {}", e);
+            return Ok(None);
+        }
+    }
+    let typed = msg.decode_payload_strict()?;
+    let Some(reply) = dispatch(state, peer, typed).await else {
+        return Ok(None);
+    };
+    match ProtocolMessage::from_typed(&reply) {
+        Ok(reply) => Ok(Some(reply)),
+        Err(e) => {
+            warn!("Failed to encode reply: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Route a message from `peer` to its subsystem, returning the reply.
+pub async fn dispatch(
+    state: &Arc<DaemonState>,
+    peer: [u8; 32],
+    msg: TypedMessage,
+) -> Option<TypedMessage> {
+    match msg {
+        TypedMessage::Ping(ping) => Some(TypedMessage::Pong(Pong { nonce: ping.nonce })),
+        TypedMessage::Goodbye(goodbye) => {
+            debug!(
+                "Peer {} said goodbye ({})",
+                hex::encode(&peer[..8]),
+                goodbye.reason
+            );
+            state.peers.disconnect(&peer).await;
+            None
+        }
+        TypedMessage::DhtFindNode(find) => {
+            let remote = state.peers.remote_addr(&peer).await;
+            Some(TypedMessage::DhtFindNodeResponse(
+                crate::bootstrap::handle_find_node(state, peer, remote, &find).await,
+            ))
+        }
+        TypedMessage::WhisperDeposit(deposit) => {
+            if let Err(e) = crate::mailbox::handle_deposit(state, &deposit).await {
+                debug!("Refused Whisper deposit: {}", e);
+                crate::misbehavior::report_whisper(state, peer, &e).await;
+            }
+            None
+        }
+        TypedMessage::WhisperPoll(poll) => match crate::mailbox::handle_poll(state, &poll).await {
+            Ok(response) => Some(TypedMessage::WhisperPollResponse(response)),
+            Err(e) => {
+                debug!("Refused mailbox poll: {}", e);
+                crate::misbehavior::report_whisper(state, peer, &e).await;
+                None
+            }
+        },
+        TypedMessage::ServiceReceiptAckBatch(batch) => {
+            Some(TypedMessage::ServiceReceiptAckBatchAck(
+                crate::receipt_acks::handle_batch(state, peer, &batch).await,
+            ))
+        }
+        TypedMessage::ServiceReceiptAckBatchAck(reply) => {
+            if let Err(e) = crate::receipt_acks::handle_reply(state, &reply).await {
+                warn!("Failed to settle receipt acks: {}", e);
+            }
+            None
+        }
+        TypedMessage::ChunkResponse(response) => {
+            if let Err(e) = crate::downloads::receive_chunk(state, peer, &response).await {
+                debug!("Dropped chunk from {}: {}", hex::encode(&peer[..8]), e);
+            }
+            None
+        }
+        TypedMessage::WhisperAck(ack) => {
+            if let Err(e) = crate::delivery::handle_ack(state, &ack).await {
+                warn!("Failed to record Whisper ack: {}", e);
+            }
+            None
+        }
+        TypedMessage::GossipPublish(publish) => {
+            let forward = GossipForward {
+                topic: publish.topic,
+                data: publish.data,
+                ttl: publish.ttl,
+                gossip_msg_id: publish.gossip_msg_id,
+            };
+            crate::gossip::handle_forward(state, peer, &forward).await;
+            None
+        }
+        TypedMessage::GossipForward(forward) => {
+            crate::gossip::handle_forward(state, peer, &forward).await;
+            None
+        }
+        TypedMessage::GossipGraft(graft) => {
+            crate::gossip::handle_graft(state, peer, &graft).await;
+            None
+        }
+        TypedMessage::GossipPrune(prune) => {
+            state.gossip.lock().await.handle_prune(peer, &prune);
+            None
+        }
+        TypedMessage::StateSyncRequest(request) => Some(TypedMessage::StateSyncResponse(
+            crate::state_sync::handle_request(state, &request).await,
+        )),
+        TypedMessage::WhisperMailboxAck(ack) => {
+            if let Err(e) = crate::mailbox::handle_ack(state, &ack).await {
+                debug!("Refused mailbox ack: {}", e);
+                crate::misbehavior::report_whisper(state, peer, &e).await;
+            }
+            None
+        }
+        other => {
+            debug!(
+                "No handler for message type 0x{:04x} from {}",
+                other.msg_type(),
+                hex::encode(&peer[..8])
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_transport::proxy::Proxy;
+
+    #[tokio::test]
+    async fn test_dial_order_follows_fallback_and_proxy() {
+        let addr: SocketAddr = "192.0.2.7:4433".parse().expect("addr");
+        let peers = Peers::bind([1u8; 32], 0, 0).expect("bind");
+        assert_eq!(peers.dial_order(addr), &[TransportKind::Quic]);
+
+        let peers = peers.with_tcp_fallback(true);
+        assert_eq!(
+            peers.dial_order(addr),
+            &[TransportKind::Quic, TransportKind::TcpTls]
+        );
+
+        // QUIC cannot cross the proxy, so proxied peers get TCP/TLS only
+        let proxy = Proxy::socks5("127.0.0.1:9050".parse().expect("proxy addr"));
+        let peers = peers.with_egress(ProxyRules::all(proxy));
+        assert_eq!(peers.dial_order(addr), &[TransportKind::TcpTls]);
+    }
+}
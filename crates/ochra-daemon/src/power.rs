@@ -0,0 +1,374 @@
+//! Power profiles and low-power mode.
+//!
+//! Running the full relay stack keeps the radio and CPU busy, which drains
+//! laptop and mobile batteries. In low-power mode the daemon suspends the
+//! relay role, sends cover traffic at the slowest permitted rate, refreshes
+//! DHT buckets less often, and aligns background network wakeups to a shared
+//! batch window so the radio wakes once per window instead of once per task.
+//!
+//! The user picks a [`PowerMode`] over RPC. In [`PowerMode::Auto`] the
+//! battery and metered-network signals reported by the UI decide, subject to
+//! the `[power]` config triggers. Every change of the effective profile emits
+//! a `PowerProfileChanged` event.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_onion::cover::{CoverTrafficConfig, DEFAULT_COVER_INTERVAL_MS, MAX_COVER_INTERVAL_MS};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::time::Interval;
+use tracing::{error, info};
+
+use crate::config::PowerConfig;
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// DHT bucket refresh interval at full power (1 hour).
+pub const DHT_REFRESH_INTERVAL_SECS: u64 = 3600;
+
+/// DHT refresh interval multiplier in low-power mode.
+const LOW_POWER_DHT_REFRESH_FACTOR: u64 = 4;
+
+/// Width of the wakeup batch window in low-power mode.
+pub const LOW_POWER_WAKEUP_BATCH_SECS: u64 = 30;
+
+/// Settings key holding the mode chosen over RPC.
+const POWER_MODE_KEY: &str = "power_mode";
+
+/// Power mode chosen by the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerMode {
+    /// Follow the battery and metered-network signals from the UI.
+    Auto,
+    /// Always run at full power.
+    Normal,
+    /// Always run in low-power mode.
+    LowPower,
+}
+
+impl PowerMode {
+    /// Wire and settings name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Normal => "normal",
+            Self::LowPower => "low_power",
+        }
+    }
+
+    /// Parse a wire or settings name.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(Self::Auto),
+            "normal" => Some(Self::Normal),
+            "low_power" => Some(Self::LowPower),
+            _ => None,
+        }
+    }
+}
+
+/// Platform power state as reported by the UI.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PowerSignals {
+    /// Running on battery rather than mains power.
+    pub on_battery: bool,
+    /// The active network connection is metered.
+    pub metered: bool,
+}
+
+/// Settings derived from the effective power state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct PowerProfile {
+    /// Whether low-power mode is in effect.
+    pub low_power: bool,
+    /// Stop accepting relay work (mailbox deposits, circuit forwarding).
+    pub relay_suspended: bool,
+    /// Mean interval between cover packets.
+    pub cover_interval_ms: u64,
+    /// Interval between DHT bucket refreshes.
+    pub dht_refresh_secs: u64,
+    /// Background network wakeups are aligned to this window. 0 = unbatched.
+    pub wakeup_batch_secs: u64,
+}
+
+impl PowerProfile {
+    /// Full-power profile.
+    pub const NORMAL: Self = Self {
+        low_power: false,
+        relay_suspended: false,
+        cover_interval_ms: DEFAULT_COVER_INTERVAL_MS,
+        dht_refresh_secs: DHT_REFRESH_INTERVAL_SECS,
+        wakeup_batch_secs: 0,
+    };
+
+    /// Low-power profile.
+    pub const LOW_POWER: Self = Self {
+        low_power: true,
+        relay_suspended: true,
+        cover_interval_ms: MAX_COVER_INTERVAL_MS,
+        dht_refresh_secs: DHT_REFRESH_INTERVAL_SECS * LOW_POWER_DHT_REFRESH_FACTOR,
+        wakeup_batch_secs: LOW_POWER_WAKEUP_BATCH_SECS,
+    };
+
+    /// Cover traffic configuration for this profile.
+    pub fn cover_config(&self, enabled: bool) -> CoverTrafficConfig {
+        if enabled {
+            CoverTrafficConfig::new(self.cover_interval_ms)
+        } else {
+            CoverTrafficConfig::disabled()
+        }
+    }
+
+    /// DHT bucket refresh interval for this profile.
+    pub fn dht_refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.dht_refresh_secs)
+    }
+}
+
+/// Tracks the user's power mode and the platform signals.
+#[derive(Debug)]
+pub struct PowerManager {
+    mode: PowerMode,
+    signals: PowerSignals,
+    on_battery_trigger: bool,
+    on_metered_trigger: bool,
+}
+
+impl PowerManager {
+    /// Create a manager in `mode` with the auto triggers from `config`.
+    pub fn new(config: &PowerConfig, mode: PowerMode) -> Self {
+        Self {
+            mode,
+            signals: PowerSignals::default(),
+            on_battery_trigger: config.low_power_on_battery,
+            on_metered_trigger: config.low_power_on_metered,
+        }
+    }
+
+    /// The user's chosen mode.
+    pub fn mode(&self) -> PowerMode {
+        self.mode
+    }
+
+    /// The last reported platform signals.
+    pub fn signals(&self) -> PowerSignals {
+        self.signals
+    }
+
+    /// Whether low-power mode is in effect.
+    pub fn is_low_power(&self) -> bool {
+        match self.mode {
+            PowerMode::Normal => false,
+            PowerMode::LowPower => true,
+            PowerMode::Auto => {
+                (self.on_battery_trigger && self.signals.on_battery)
+                    || (self.on_metered_trigger && self.signals.metered)
+            }
+        }
+    }
+
+    /// The effective profile.
+    pub fn profile(&self) -> PowerProfile {
+        if self.is_low_power() {
+            PowerProfile::LOW_POWER
+        } else {
+            PowerProfile::NORMAL
+        }
+    }
+
+    /// Change the mode. Returns `true` if the effective profile changed.
+    pub fn set_mode(&mut self, mode: PowerMode) -> bool {
+        let before = self.is_low_power();
+        self.mode = mode;
+        before != self.is_low_power()
+    }
+
+    /// Record new platform signals. Returns `true` if the effective profile
+    /// changed.
+    pub fn report(&mut self, signals: PowerSignals) -> bool {
+        let before = self.is_low_power();
+        self.signals = signals;
+        before != self.is_low_power()
+    }
+}
+
+/// Build the manager, restoring a mode chosen over RPC in a previous run.
+pub fn load(conn: &rusqlite::Connection, config: &PowerConfig) -> Arc<Mutex<PowerManager>> {
+    let stored = ochra_db::queries::settings::get(conn, POWER_MODE_KEY).ok();
+    let mode = stored
+        .as_deref()
+        .or(Some(config.mode.as_str()))
+        .and_then(PowerMode::parse)
+        .unwrap_or(PowerMode::Auto);
+    Arc::new(Mutex::new(PowerManager::new(config, mode)))
+}
+
+/// Whether the relay role is currently suspended.
+pub async fn relay_suspended(state: &DaemonState) -> bool {
+    state.power.lock().await.profile().relay_suspended
+}
+
+/// Time until the next multiple of `window_secs` in Unix time.
+fn until_batch_boundary(now_ms: u64, window_secs: u64) -> Duration {
+    let window_ms = window_secs * 1000;
+    Duration::from_millis((window_ms - now_ms % window_ms) % window_ms)
+}
+
+/// Wait for the next tick of a background network loop.
+///
+/// In low-power mode the tick is deferred to the next batch boundary, so
+/// every loop due within the same window wakes together.
+pub async fn tick(state: &DaemonState, interval: &mut Interval) {
+    interval.tick().await;
+    let window = state.power.lock().await.profile().wakeup_batch_secs;
+    if window == 0 {
+        return;
+    }
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    tokio::time::sleep(until_batch_boundary(now_ms, window)).await;
+    interval.reset();
+}
+
+/// Emit `PowerProfileChanged` and apply the new profile. The DHT bucket
+/// refresher reads the profile on each pass.
+async fn on_profile_changed(state: &DaemonState, manager: &PowerManager) {
+    let profile = manager.profile();
+    info!(
+        "Power profile is now {}",
+        if profile.low_power {
+            "low-power"
+        } else {
+            "normal"
+        }
+    );
+    state
+        .cover
+        .lock()
+        .await
+        .set_config(profile.cover_config(state.config.privacy.cover_traffic_enabled));
+    // Would: tear down relayed circuits when the relay role is suspended
+    state.event_bus.emit(DaemonEvent::PowerProfileChanged {
+        mode: manager.mode().as_str().to_string(),
+        low_power: profile.low_power,
+        relay_suspended: profile.relay_suspended,
+    });
+}
+
+/// Switch the power mode and persist it.
+pub async fn set_mode(state: &Arc<DaemonState>, mode: PowerMode) {
+    {
+        let conn = state.db.lock().await;
+        if let Err(e) = ochra_db::queries::settings::set(&conn, POWER_MODE_KEY, mode.as_str()) {
+            error!("Failed to persist power mode: {}", e);
+        }
+    }
+    let mut manager = state.power.lock().await;
+    if manager.set_mode(mode) {
+        on_profile_changed(state, &manager).await;
+    }
+}
+
+/// Record platform signals reported by the UI.
+pub async fn report_signals(state: &Arc<DaemonState>, signals: PowerSignals) {
+    let mut manager = state.power.lock().await;
+    if manager.report(signals) {
+        on_profile_changed(state, &manager).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(mode: PowerMode) -> PowerManager {
+        PowerManager::new(&PowerConfig::default(), mode)
+    }
+
+    #[test]
+    fn test_auto_follows_signals() {
+        let mut power = manager(PowerMode::Auto);
+        assert_eq!(power.profile(), PowerProfile::NORMAL);
+
+        assert!(power.report(PowerSignals {
+            on_battery: true,
+            metered: false,
+        }));
+        assert_eq!(power.profile(), PowerProfile::LOW_POWER);
+
+        // Still low-power: only the reason changed.
+        assert!(!power.report(PowerSignals {
+            on_battery: false,
+            metered: true,
+        }));
+        assert!(power.report(PowerSignals::default()));
+        assert!(!power.is_low_power());
+    }
+
+    #[test]
+    fn test_explicit_mode_overrides_signals() {
+        let mut power = manager(PowerMode::Normal);
+        power.report(PowerSignals {
+            on_battery: true,
+            metered: true,
+        });
+        assert!(!power.is_low_power());
+
+        assert!(power.set_mode(PowerMode::LowPower));
+        assert!(!power.report(PowerSignals::default()));
+        assert!(power.is_low_power());
+    }
+
+    #[test]
+    fn test_disabled_triggers() {
+        let config = PowerConfig {
+            low_power_on_battery: false,
+            ..PowerConfig::default()
+        };
+        let mut power = PowerManager::new(&config, PowerMode::Auto);
+        assert!(!power.report(PowerSignals {
+            on_battery: true,
+            metered: false,
+        }));
+        assert!(power.report(PowerSignals {
+            on_battery: true,
+            metered: true,
+        }));
+    }
+
+    #[test]
+    fn test_low_power_profile() {
+        let low = PowerProfile::LOW_POWER;
+        assert!(low.relay_suspended);
+        assert!(low.cover_interval_ms > PowerProfile::NORMAL.cover_interval_ms);
+        assert!(low.dht_refresh_interval() > PowerProfile::NORMAL.dht_refresh_interval());
+        assert_eq!(
+            low.cover_config(true).mean_interval_ms,
+            MAX_COVER_INTERVAL_MS
+        );
+        assert!(!low.cover_config(false).enabled);
+    }
+
+    #[test]
+    fn test_until_batch_boundary() {
+        assert_eq!(until_batch_boundary(0, 30), Duration::ZERO);
+        assert_eq!(until_batch_boundary(1_000, 30), Duration::from_secs(29));
+        assert_eq!(until_batch_boundary(59_999, 30), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_load_restores_stored_mode() {
+        let conn = ochra_db::open_memory().expect("open db");
+        let config = PowerConfig::default();
+        let power = load(&conn, &config);
+        assert_eq!(power.try_lock().expect("lock").mode(), PowerMode::Auto);
+
+        ochra_db::queries::settings::set(&conn, POWER_MODE_KEY, "low_power").expect("set");
+        let power = load(&conn, &config);
+        assert_eq!(power.try_lock().expect("lock").mode(), PowerMode::LowPower);
+    }
+}
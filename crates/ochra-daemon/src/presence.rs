@@ -0,0 +1,34 @@
+//! Presence privacy settings (Section 7.4).
+//!
+//! The [`PresencePolicy`](ochra_whisper::presence::PresencePolicy) is loaded
+//! from the database at startup and consulted by the Whisper command
+//! handlers before sending typing indicators or read receipts.
+
+use crate::DaemonState;
+
+/// Load global settings and per-contact overrides into the policy.
+pub async fn load(state: &DaemonState) -> anyhow::Result<()> {
+    let (global, overrides) = {
+        let db = state.db.lock().await;
+        (
+            ochra_db::queries::presence::get_global(&db)?,
+            ochra_db::queries::presence::list_overrides(&db)?,
+        )
+    };
+    let mut policy = state.presence.lock().await;
+    policy.set_global(global);
+    for (contact, o) in overrides {
+        policy.set_override(contact, o);
+    }
+    Ok(())
+}
+
+/// JSON view of the settings that apply toward `contact`.
+pub async fn settings_json(state: &DaemonState, contact: Option<&[u8; 32]>) -> serde_json::Value {
+    let policy = state.presence.lock().await;
+    serde_json::json!({
+        "global": policy.global(),
+        "override": contact.and_then(|c| policy.override_for(c)),
+        "effective": policy.for_contact(contact),
+    })
+}
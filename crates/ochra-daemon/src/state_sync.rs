@@ -0,0 +1,558 @@
+//! Checkpointed state sync for relays (Section 12.11).
+//!
+//! A relay joining mid-epoch asks peers for the latest quorum-signed
+//! [`StateCheckpoint`], verifies it against the key schedule, then fetches
+//! the nullifier filter, the `EpochState`, and the relay directory snapshot
+//! in ranges from whichever peers hold them. Every component is checked
+//! against the checkpoint before anything is applied, so a peer can stall a
+//! sync but not poison it. Relays keep the components of the checkpoint
+//! they last synced to serve the next joiner.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use ochra_frost::checkpoint::{check_epoch_state, check_nullifiers, verify_checkpoint};
+use ochra_nullifier::bloom::{NullifierSet, BLOOM_SIZE};
+use ochra_onion::directory::{directory_digest, DirectorySnapshot, MAX_DIRECTORY_SIZE};
+use ochra_transport::capabilities::FEATURE_RELAY;
+use ochra_transport::messages::{
+    StateSyncRequest, StateSyncResponse, TypedMessage, SYNC_CHECKPOINT, SYNC_DIRECTORY,
+    SYNC_EPOCH_STATE, SYNC_NULLIFIERS,
+};
+use ochra_types::network::{EpochState, StateCheckpoint};
+use tracing::{debug, info};
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Bytes requested per range. A byte may take two bytes on the wire, so a
+/// response stays within the 64 KiB payload limit.
+pub const SYNC_CHUNK_BYTES: u32 = 16 * 1024;
+
+/// Relays asked in turn before a sync is given up until the next start.
+const SYNC_PEERS: usize = 3;
+
+/// Settings key holding the epoch of the last applied checkpoint.
+const SYNCED_EPOCH_KEY: &str = "state_sync_epoch";
+
+/// Components fetched after the checkpoint, in fetch order.
+const FETCHED: [u8; 3] = [SYNC_NULLIFIERS, SYNC_EPOCH_STATE, SYNC_DIRECTORY];
+
+/// Largest accepted size of each component.
+fn max_size(component: u8) -> Option<u64> {
+    match component {
+        SYNC_CHECKPOINT => Some(1024),
+        SYNC_NULLIFIERS => Some(BLOOM_SIZE as u64),
+        SYNC_EPOCH_STATE => Some(64 * 1024),
+        SYNC_DIRECTORY => Some(MAX_DIRECTORY_SIZE as u64),
+        _ => None,
+    }
+}
+
+/// The encoded components of a checkpoint, as served to syncing relays.
+pub struct CheckpointBundle {
+    pub checkpoint: StateCheckpoint,
+    components: BTreeMap<u8, Vec<u8>>,
+}
+
+impl CheckpointBundle {
+    /// Bundle a checkpoint with its encoded nullifier filter, `EpochState`,
+    /// and compressed directory snapshot.
+    pub fn new(
+        checkpoint: StateCheckpoint,
+        nullifiers: Vec<u8>,
+        epoch_state: Vec<u8>,
+        directory: Vec<u8>,
+    ) -> anyhow::Result<Self> {
+        let encoded = ochra_transport::cbor::to_vec(&checkpoint)?;
+        let components = BTreeMap::from([
+            (SYNC_CHECKPOINT, encoded),
+            (SYNC_NULLIFIERS, nullifiers),
+            (SYNC_EPOCH_STATE, epoch_state),
+            (SYNC_DIRECTORY, directory),
+        ]);
+        Ok(Self {
+            checkpoint,
+            components,
+        })
+    }
+
+    /// Answer a range request. The data is empty if the request names
+    /// another checkpoint or an unknown component.
+    pub fn serve(&self, request: &StateSyncRequest) -> StateSyncResponse {
+        let mut response = StateSyncResponse {
+            epoch: self.checkpoint.epoch,
+            component: request.component,
+            offset: request.offset,
+            total_size: 0,
+            data: Vec::new(),
+        };
+        if request.epoch != 0 && request.epoch != self.checkpoint.epoch {
+            return response;
+        }
+        let Some(component) = self.components.get(&request.component) else {
+            return response;
+        };
+        response.total_size = component.len() as u64;
+        let start = usize::try_from(request.offset)
+            .unwrap_or(usize::MAX)
+            .min(component.len());
+        let len = request.max_length.min(SYNC_CHUNK_BYTES) as usize;
+        let end = start.saturating_add(len).min(component.len());
+        response.data = component[start..end].to_vec();
+        response
+    }
+}
+
+/// A component being fetched.
+#[derive(Default)]
+struct Partial {
+    total: Option<u64>,
+    data: Vec<u8>,
+}
+
+impl Partial {
+    fn is_complete(&self) -> bool {
+        self.total == Some(self.data.len() as u64)
+    }
+}
+
+/// Fetch progress towards one verified checkpoint.
+pub struct SyncSession {
+    checkpoint: StateCheckpoint,
+    partials: BTreeMap<u8, Partial>,
+}
+
+/// State reconstructed from a checkpoint, ready to apply.
+pub struct SyncedState {
+    pub nullifiers: NullifierSet,
+    pub epoch_state: EpochState,
+    pub directory: DirectorySnapshot,
+    pub bundle: CheckpointBundle,
+}
+
+impl SyncSession {
+    /// Start fetching the components of `checkpoint` after checking its
+    /// signature under `group_pk`.
+    pub fn new(group_pk: &[u8; 32], checkpoint: StateCheckpoint) -> anyhow::Result<Self> {
+        verify_checkpoint(group_pk, &checkpoint)?;
+        Ok(Self {
+            checkpoint,
+            partials: FETCHED.iter().map(|c| (*c, Partial::default())).collect(),
+        })
+    }
+
+    /// Epoch of the checkpoint being fetched.
+    pub fn epoch(&self) -> u32 {
+        self.checkpoint.epoch
+    }
+
+    /// The next range to fetch, or `None` once every component is complete.
+    pub fn next_request(&self) -> Option<StateSyncRequest> {
+        let (component, partial) = self.partials.iter().find(|(_, p)| !p.is_complete())?;
+        Some(StateSyncRequest {
+            epoch: self.checkpoint.epoch,
+            component: *component,
+            offset: partial.data.len() as u64,
+            max_length: SYNC_CHUNK_BYTES,
+        })
+    }
+
+    /// Add a received range. Ranges must arrive in order.
+    pub fn receive(&mut self, response: &StateSyncResponse) -> anyhow::Result<()> {
+        if response.epoch != self.checkpoint.epoch {
+            anyhow::bail!("range belongs to checkpoint {}", response.epoch);
+        }
+        let max = max_size(response.component).unwrap_or(0);
+        let Some(partial) = self.partials.get_mut(&response.component) else {
+            anyhow::bail!("component {} is not fetched", response.component);
+        };
+        if response.total_size > max {
+            anyhow::bail!("component {} is too large", response.component);
+        }
+        if partial.total.is_some_and(|t| t != response.total_size) {
+            anyhow::bail!("component {} changed size", response.component);
+        }
+        if response.offset != partial.data.len() as u64 {
+            anyhow::bail!("range at {} is out of order", response.offset);
+        }
+        if response.data.is_empty() || response.data.len() > SYNC_CHUNK_BYTES as usize {
+            anyhow::bail!("range of {} bytes", response.data.len());
+        }
+        if response.offset + response.data.len() as u64 > response.total_size {
+            anyhow::bail!("range runs past the component");
+        }
+        partial.total = Some(response.total_size);
+        partial.data.extend_from_slice(&response.data);
+        Ok(())
+    }
+
+    /// Bytes received and bytes known to be needed so far.
+    pub fn progress(&self) -> (u64, u64) {
+        self.partials.values().fold((0, 0), |(got, total), p| {
+            (got + p.data.len() as u64, total + p.total.unwrap_or(0))
+        })
+    }
+
+    /// Check every component against the checkpoint and decode them.
+    pub fn finish(mut self) -> anyhow::Result<SyncedState> {
+        if self.next_request().is_some() {
+            anyhow::bail!("state sync is incomplete");
+        }
+        let mut take = |c: u8| self.partials.remove(&c).map(|p| p.data).unwrap_or_default();
+        let (bloom, encoded_state, compressed_dir) = (
+            take(SYNC_NULLIFIERS),
+            take(SYNC_EPOCH_STATE),
+            take(SYNC_DIRECTORY),
+        );
+        let checkpoint = self.checkpoint;
+
+        check_nullifiers(&checkpoint, &bloom)?;
+        if bloom.len() != BLOOM_SIZE {
+            anyhow::bail!("nullifier filter is {} bytes", bloom.len());
+        }
+        check_epoch_state(&checkpoint, &encoded_state)?;
+        let epoch_state: EpochState = ochra_transport::cbor::from_slice(&encoded_state)?;
+        if epoch_state.epoch != checkpoint.epoch
+            || epoch_state.holder_balances_root != checkpoint.vys_accumulator_root
+            || epoch_state.nullifier_bloom_hash != checkpoint.nullifier_bloom_hash
+        {
+            anyhow::bail!("epoch state disagrees with the checkpoint");
+        }
+        let directory = DirectorySnapshot::from_compressed(&compressed_dir)?;
+        if directory.relay_epoch != checkpoint.directory_epoch
+            || directory_digest(&directory.descriptors)? != checkpoint.directory_digest
+        {
+            anyhow::bail!("directory snapshot disagrees with the checkpoint");
+        }
+
+        let count = usize::try_from(checkpoint.nullifier_count)?;
+        let nullifiers = NullifierSet::from_bytes(&bloom, count);
+        let bundle = CheckpointBundle::new(checkpoint, bloom, encoded_state, compressed_dir)?;
+        Ok(SyncedState {
+            nullifiers,
+            epoch_state,
+            directory,
+            bundle,
+        })
+    }
+}
+
+/// Sync in progress and the bundle served to others.
+#[derive(Default)]
+pub struct SyncState {
+    pub session: Option<SyncSession>,
+    pub bundle: Option<CheckpointBundle>,
+}
+
+/// Epoch of the last checkpoint this node applied.
+pub async fn synced_epoch(state: &DaemonState) -> anyhow::Result<Option<u32>> {
+    let db = state.db.lock().await;
+    let epoch = ochra_db::queries::settings::get_u64(&db, SYNCED_EPOCH_KEY, u64::MAX)?;
+    Ok(u32::try_from(epoch).ok())
+}
+
+/// Sync progress for `get_state_sync_status`.
+pub async fn status(state: &DaemonState) -> anyhow::Result<serde_json::Value> {
+    let synced_epoch = synced_epoch(state).await?;
+    let sync = state.state_sync.lock().await;
+    let in_progress = sync.session.as_ref().map(|session| {
+        let (received, total) = session.progress();
+        serde_json::json!({
+            "epoch": session.epoch(),
+            "received_bytes": received,
+            "known_total_bytes": total,
+        })
+    });
+    Ok(serde_json::json!({
+        "synced_epoch": synced_epoch,
+        "serving_epoch": sync.bundle.as_ref().map(|b| b.checkpoint.epoch),
+        "in_progress": in_progress,
+    }))
+}
+
+/// Ask peers for a checkpoint if this relay has not applied a recent one.
+pub async fn start(state: Arc<DaemonState>) {
+    let current = crate::epoch::current_epoch();
+    match synced_epoch(&state).await {
+        Ok(Some(epoch)) if u64::from(epoch) + 1 >= current => return,
+        Ok(_) => {}
+        Err(e) => debug!("Failed to read state sync epoch: {}", e),
+    }
+    info!("Requesting a state checkpoint from peers");
+    let relays = state.peers.with_feature(FEATURE_RELAY).await;
+    for peer in relays.into_iter().take(SYNC_PEERS) {
+        // A sync cut short by one peer resumes at the next range
+        let resume = match &state.state_sync.lock().await.session {
+            Some(session) => session.next_request(),
+            None => None,
+        };
+        let first = resume.unwrap_or(StateSyncRequest {
+            epoch: 0,
+            component: SYNC_CHECKPOINT,
+            offset: 0,
+            max_length: SYNC_CHUNK_BYTES,
+        });
+        if let Err(e) = sync_from(&state, peer, first).await {
+            debug!("State sync from {} failed: {}", hex::encode(&peer[..8]), e);
+        }
+        let sync = state.state_sync.lock().await;
+        if sync.session.is_none() && sync.bundle.is_some() {
+            return;
+        }
+    }
+}
+
+/// Fetch ranges from `peer` until the sync completes or the peer has
+/// nothing more to offer.
+async fn sync_from(
+    state: &Arc<DaemonState>,
+    peer: [u8; 32],
+    first: StateSyncRequest,
+) -> anyhow::Result<()> {
+    let mut next = Some(first);
+    while let Some(request) = next {
+        let msg = TypedMessage::StateSyncRequest(request);
+        let response = match crate::peer::request(state, &peer, &msg).await? {
+            TypedMessage::StateSyncResponse(response) => response,
+            other => anyhow::bail!("unexpected reply type 0x{:04x}", other.msg_type()),
+        };
+        next = handle_response(state, &response).await?;
+    }
+    Ok(())
+}
+
+/// Verify a checkpoint offered by a peer and start fetching it, unless a
+/// checkpoint at least as new is already fetched or being fetched.
+async fn begin(state: &DaemonState, encoded: &[u8]) -> anyhow::Result<Option<StateSyncRequest>> {
+    let checkpoint: StateCheckpoint = ochra_transport::cbor::from_slice(encoded)?;
+    let epoch = checkpoint.epoch;
+    let group_pk = ochra_db::queries::key_schedule::for_epoch(&*state.db.lock().await, epoch)?
+        .map(|entry| entry.group_pk)
+        .ok_or_else(|| anyhow::anyhow!("no quorum key known for epoch {}", epoch))?;
+
+    let mut sync = state.state_sync.lock().await;
+    let known = sync.session.as_ref().map(SyncSession::epoch).into_iter();
+    if known
+        .chain(sync.bundle.as_ref().map(|b| b.checkpoint.epoch))
+        .any(|known| known >= epoch)
+    {
+        return Ok(None);
+    }
+    let session = SyncSession::new(&group_pk, checkpoint)?;
+    let next = session.next_request();
+    info!("Syncing state from checkpoint of epoch {}", epoch);
+    sync.session = Some(session);
+    Ok(next)
+}
+
+/// Handle a peer's state sync response, returning the next range to ask
+/// for.
+pub async fn handle_response(
+    state: &Arc<DaemonState>,
+    response: &StateSyncResponse,
+) -> anyhow::Result<Option<StateSyncRequest>> {
+    if response.component == SYNC_CHECKPOINT {
+        // The checkpoint always fits in one range.
+        if response.data.is_empty() || response.data.len() as u64 != response.total_size {
+            return Ok(None);
+        }
+        return begin(state, &response.data).await;
+    }
+    let synced = {
+        let mut sync = state.state_sync.lock().await;
+        let Some(session) = sync.session.as_mut() else {
+            return Ok(None);
+        };
+        session.receive(response)?;
+        if let Some(next) = session.next_request() {
+            return Ok(Some(next));
+        }
+        match sync.session.take() {
+            Some(session) => session.finish()?,
+            None => return Ok(None),
+        }
+    };
+    apply(state, synced).await?;
+    Ok(None)
+}
+
+/// Answer a syncing peer's range request.
+pub async fn handle_request(state: &DaemonState, request: &StateSyncRequest) -> StateSyncResponse {
+    match &state.state_sync.lock().await.bundle {
+        Some(bundle) => bundle.serve(request),
+        None => StateSyncResponse {
+            epoch: request.epoch,
+            component: request.component,
+            offset: request.offset,
+            total_size: 0,
+            data: Vec::new(),
+        },
+    }
+}
+
+/// Start from a verified checkpoint. Nullifiers already learned from
+/// gossip are kept, and the relay cache is replaced by the checkpoint's
+/// directory snapshot.
+async fn apply(state: &DaemonState, synced: SyncedState) -> anyhow::Result<()> {
+    let SyncedState {
+        nullifiers,
+        epoch_state,
+        directory,
+        bundle,
+    } = synced;
+    let epoch = bundle.checkpoint.epoch;
+    let nullifier_count = bundle.checkpoint.nullifier_count;
+
+    state.nullifiers.lock().await.union(&nullifiers);
+    crate::rewards::record_epoch_state(state, &epoch_state).await;
+    // The snapshot matched the checkpoint's directory digest in finish()
+    state.relays.lock().await.load_snapshot(&directory);
+    let relay_count = u32::try_from(directory.descriptors.len()).unwrap_or(u32::MAX);
+    ochra_db::queries::settings::set(
+        &*state.db.lock().await,
+        SYNCED_EPOCH_KEY,
+        &epoch.to_string(),
+    )?;
+    state.state_sync.lock().await.bundle = Some(bundle);
+
+    info!(
+        "Synced state from checkpoint of epoch {}: {} nullifiers, {} relays",
+        epoch, nullifier_count, relay_count
+    );
+    state.event_bus.emit(DaemonEvent::StateSynced {
+        epoch,
+        nullifier_count,
+        relay_count,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::KeyPair;
+    use ochra_frost::checkpoint::checkpoint_message;
+
+    struct Fixture {
+        quorum: KeyPair,
+        bundle: CheckpointBundle,
+    }
+
+    fn fixture() -> Fixture {
+        let quorum = KeyPair::generate();
+        let mut nullifiers = NullifierSet::new();
+        nullifiers.insert(&[5u8; 32]);
+        let bloom = nullifiers.as_bytes().to_vec();
+        let directory = DirectorySnapshot::build(300, Vec::new()).expect("snapshot");
+        let epoch_state = EpochState {
+            epoch: 12,
+            reward_per_token: 1,
+            total_vys_staked: 2,
+            fee_pool_balance: 3,
+            holder_balances_root: [4; 32],
+            nullifier_bloom_hash: nullifiers.state_hash(),
+            nullifier_smt_root: None,
+            posrv_rankings: Vec::new(),
+            quorum_sig: [0; 64],
+        };
+        let encoded_state = ochra_transport::cbor::to_vec(&epoch_state).expect("encode");
+        let mut checkpoint = StateCheckpoint {
+            epoch: 12,
+            nullifier_bloom_hash: nullifiers.state_hash(),
+            nullifier_count: 1,
+            vys_accumulator_root: [4; 32],
+            epoch_state_hash: ochra_crypto::blake3::hash(&encoded_state),
+            directory_epoch: 300,
+            directory_digest: directory.digest,
+            quorum_sig: [0; 64],
+        };
+        checkpoint.quorum_sig = quorum
+            .signing_key
+            .sign(&checkpoint_message(&checkpoint))
+            .to_bytes();
+        let bundle = CheckpointBundle::new(
+            checkpoint,
+            bloom,
+            encoded_state,
+            directory.to_compressed().expect("compress"),
+        )
+        .expect("bundle");
+        Fixture { quorum, bundle }
+    }
+
+    fn sync(session: &mut SyncSession, bundle: &CheckpointBundle) {
+        while let Some(request) = session.next_request() {
+            session
+                .receive(&bundle.serve(&request))
+                .expect("receive range");
+        }
+    }
+
+    #[test]
+    fn test_sync_from_peer_bundle() {
+        let Fixture { quorum, bundle } = fixture();
+        let pk = quorum.verifying_key.to_bytes();
+        let mut session = SyncSession::new(&pk, bundle.checkpoint.clone()).expect("session");
+        sync(&mut session, &bundle);
+        let (got, total) = session.progress();
+        assert_eq!(got, total);
+        assert!(total > BLOOM_SIZE as u64);
+
+        let synced = session.finish().expect("finish");
+        assert!(synced.nullifiers.contains(&[5u8; 32]));
+        assert_eq!(synced.epoch_state.epoch, 12);
+        assert_eq!(synced.directory.relay_epoch, 300);
+        // The synced relay can serve the next one.
+        let mut next = SyncSession::new(&pk, synced.bundle.checkpoint.clone()).expect("session");
+        sync(&mut next, &synced.bundle);
+        assert!(next.finish().is_ok());
+    }
+
+    #[test]
+    fn test_forged_checkpoint_rejected() {
+        let Fixture { bundle, .. } = fixture();
+        let other = KeyPair::generate().verifying_key.to_bytes();
+        assert!(SyncSession::new(&other, bundle.checkpoint.clone()).is_err());
+    }
+
+    #[test]
+    fn test_tampered_component_fails_verification() {
+        let Fixture { quorum, bundle } = fixture();
+        let pk = quorum.verifying_key.to_bytes();
+        let mut session = SyncSession::new(&pk, bundle.checkpoint.clone()).expect("session");
+        while let Some(request) = session.next_request() {
+            let mut response = bundle.serve(&request);
+            if request.component == SYNC_NULLIFIERS && request.offset == 0 {
+                response.data[0] ^= 1;
+            }
+            session.receive(&response).expect("receive range");
+        }
+        assert!(session.finish().is_err());
+    }
+
+    #[test]
+    fn test_out_of_order_and_foreign_ranges_rejected() {
+        let Fixture { quorum, bundle } = fixture();
+        let pk = quorum.verifying_key.to_bytes();
+        let mut session = SyncSession::new(&pk, bundle.checkpoint.clone()).expect("session");
+        let request = session.next_request().expect("request");
+        let skipped = StateSyncRequest {
+            offset: u64::from(SYNC_CHUNK_BYTES),
+            ..request.clone()
+        };
+        assert!(session.receive(&bundle.serve(&skipped)).is_err());
+        let wrong_epoch = StateSyncResponse {
+            epoch: 11,
+            ..bundle.serve(&request)
+        };
+        assert!(session.receive(&wrong_epoch).is_err());
+        let checkpoint_range = bundle.serve(&StateSyncRequest {
+            component: SYNC_CHECKPOINT,
+            ..request.clone()
+        });
+        assert!(session.receive(&checkpoint_range).is_err());
+        assert!(session.receive(&bundle.serve(&request)).is_ok());
+        assert!(session.finish().is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! Supply invariant audit at epoch boundaries.
+//!
+//! Each epoch's mint, burn, refund and escrow flows are reconciled against
+//! the previous epoch's closing supply. Reports are kept in `supply_audit`
+//! for auditors; a failed invariant is logged and emitted as a
+//! `SupplyInvariantViolated` event.
+//!
+//! The daemon sees no quorum mint transcripts, so the flows audited are
+//! the ones it holds: tokens entering its wallet count as issued and are
+//! checked against the incoming transactions recorded for the epoch, spent
+//! tokens count as burned, and the closing supply is the wallet balance.
+//! The node holds no escrows.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_db::queries::supply_audit::{self as db, SupplyAuditRow};
+use ochra_db::queries::wallet::LedgerFlows;
+use ochra_mint::supply::{check_epoch, EpochFlows, SupplyReport, SupplySnapshot};
+use ochra_types::events::DaemonEvent;
+use tracing::{error, info};
+
+use crate::{epoch, DaemonState};
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Decode a stored report.
+pub fn decode(row: &SupplyAuditRow) -> anyhow::Result<SupplyReport> {
+    Ok(serde_json::from_slice(&row.report)?)
+}
+
+/// Supply flows of the wallet ledger for one epoch.
+fn epoch_flows(epoch: u32, ledger: LedgerFlows) -> EpochFlows {
+    EpochFlows {
+        epoch,
+        minted_denominations: ledger.received,
+        declared_minted: ledger.recorded_incoming,
+        burned: ledger.spent,
+        closing: SupplySnapshot {
+            circulating: ledger.closing_balance,
+            escrowed: 0,
+        },
+        ..EpochFlows::default()
+    }
+}
+
+/// Check an epoch's flows, store the report and raise its violations.
+pub async fn record_epoch(
+    state: &Arc<DaemonState>,
+    flows: EpochFlows,
+) -> anyhow::Result<SupplyReport> {
+    let conn = state.db.lock().await;
+    let previous = match flows.epoch.checked_sub(1) {
+        Some(epoch) => db::get(&conn, epoch)?
+            .or(db::latest(&conn)?.filter(|row| row.epoch < flows.epoch))
+            .map(|row| decode(&row))
+            .transpose()?,
+        None => None,
+    };
+    let report = check_epoch(previous.as_ref(), flows);
+    db::upsert(
+        &conn,
+        &SupplyAuditRow {
+            epoch: report.epoch(),
+            violations: report.violations.len() as u32,
+            report: serde_json::to_vec(&report)?,
+            checked_at: now_secs(),
+        },
+    )?;
+    drop(conn);
+
+    if report.is_clean() {
+        info!(epoch = report.epoch(), "Supply audit passed");
+    }
+    for violation in &report.violations {
+        error!(
+            epoch = report.epoch(),
+            invariant = violation.invariant.as_str(),
+            expected = %violation.expected,
+            actual = %violation.actual,
+            "Supply invariant violated"
+        );
+        state.event_bus.emit(DaemonEvent::SupplyInvariantViolated {
+            epoch: report.epoch(),
+            invariant: violation.invariant.as_str().to_string(),
+            expected: violation.expected.to_string(),
+            actual: violation.actual.to_string(),
+        });
+    }
+    Ok(report)
+}
+
+/// Audit every closed epoch not yet audited, oldest first.
+///
+/// The first audit covers only the epoch that just closed. Epochs missed
+/// while the daemon was stopped are audited from the ledger afterwards, so
+/// the reports stay continuous. Returns how many epochs were audited.
+pub async fn audit_closed_epochs(state: &Arc<DaemonState>) -> anyhow::Result<u32> {
+    let Some(closed) = epoch::current_epoch().checked_sub(1) else {
+        return Ok(0);
+    };
+    let closed = u32::try_from(closed)?;
+    let latest = db::latest(&*state.db.lock().await)?.map(|row| row.epoch);
+    let first = match latest {
+        Some(latest) if latest >= closed => return Ok(0),
+        Some(latest) => latest + 1,
+        None => closed,
+    };
+
+    let duration = epoch::epoch_duration_secs();
+    for epoch in first..=closed {
+        let start = u64::from(epoch) * duration;
+        let ledger = ochra_db::queries::wallet::ledger_flows(
+            &*state.db.lock().await,
+            u64::from(epoch),
+            start,
+            start + duration,
+        )?;
+        record_epoch(state, epoch_flows(epoch, ledger)).await?;
+    }
+    Ok(closed - first + 1)
+}
+
+/// Audit each epoch as it closes until shutdown.
+pub async fn run_auditor(state: Arc<DaemonState>) {
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        if let Err(e) = audit_closed_epochs(&state).await {
+            error!("Failed to audit supply: {}", e);
+        }
+        let wait = Duration::from_secs(epoch::seconds_until_next_epoch());
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallet_ledger_reconciles_across_epochs() {
+        let opening = check_epoch(
+            None,
+            epoch_flows(
+                4,
+                LedgerFlows {
+                    received: vec![1_000],
+                    recorded_incoming: 1_000,
+                    closing_balance: 1_000,
+                    ..LedgerFlows::default()
+                },
+            ),
+        );
+        assert!(opening.is_clean());
+
+        let next = LedgerFlows {
+            received: vec![200, 300],
+            recorded_incoming: 500,
+            spent: 1_000,
+            closing_balance: 500,
+        };
+        let report = check_epoch(Some(&opening), epoch_flows(5, next.clone()));
+        assert!(report.is_clean(), "{:?}", report.violations);
+
+        // A token that appeared with no incoming transaction recorded
+        let unrecorded = LedgerFlows {
+            recorded_incoming: 200,
+            ..next
+        };
+        let report = check_epoch(Some(&opening), epoch_flows(5, unrecorded));
+        assert_eq!(report.violations.len(), 1);
+    }
+}
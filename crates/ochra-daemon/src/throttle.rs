@@ -0,0 +1,84 @@
+//! Unknown-sender throttling (Section 7.6).
+//!
+//! The recipient's strictness is persisted in settings and loaded into the
+//! [`ThrottleGate`](ochra_whisper::throttle::ThrottleGate), which every
+//! envelope polled from the mailbox passes before it is opened. Mailbox
+//! relays enforce the same strictness on deposits; it reaches them with
+//! each poll. Payment tickets are checked against the quorum key of the
+//! epoch their receipt names.
+
+use ochra_transport::messages::{MailboxEnvelope, SpamTicket};
+use ochra_types::whisper::ThrottleStrictness;
+use ochra_whisper::throttle::TicketCheck;
+use tracing::warn;
+
+use crate::DaemonState;
+
+/// Settings key holding the strictness.
+const STRICTNESS_KEY: &str = "whisper_strictness";
+
+/// Load the persisted strictness into the gate.
+pub async fn load(state: &DaemonState) -> anyhow::Result<()> {
+    let strictness = {
+        let db = state.db.lock().await;
+        match ochra_db::queries::settings::get(&db, STRICTNESS_KEY) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(ochra_db::DbError::NotFound(_)) => ThrottleStrictness::default(),
+            Err(e) => return Err(e.into()),
+        }
+    };
+    state.throttle.lock().await.set_strictness(strictness);
+    Ok(())
+}
+
+/// Persist and apply a new strictness.
+pub async fn set(state: &DaemonState, strictness: ThrottleStrictness) -> anyhow::Result<()> {
+    let json = serde_json::to_string(&strictness)?;
+    ochra_db::queries::settings::set(&*state.db.lock().await, STRICTNESS_KEY, &json)?;
+    state.throttle.lock().await.set_strictness(strictness);
+    // Would: republish the HandleDescriptor with the new strictness; mailbox
+    // relays pick it up from the next poll
+    Ok(())
+}
+
+/// The strictness in force.
+pub async fn strictness(state: &DaemonState) -> ThrottleStrictness {
+    state.throttle.lock().await.strictness()
+}
+
+/// Group key of the quorum that signed `ticket`'s payment receipt, if it is
+/// a payment and the key schedule covers its epoch.
+pub async fn quorum_key(state: &DaemonState, ticket: Option<&SpamTicket>) -> Option<[u8; 32]> {
+    let epoch = ticket.and_then(ochra_whisper::throttle::payment_epoch)?;
+    let db = state.db.lock().await;
+    match ochra_db::queries::key_schedule::for_epoch(&db, epoch) {
+        Ok(entry) => entry.map(|entry| entry.group_pk),
+        Err(e) => {
+            warn!(
+                "Failed to look up the quorum key for epoch {}: {}",
+                epoch, e
+            );
+            None
+        }
+    }
+}
+
+/// Check a polled mailbox envelope before opening it.
+///
+/// Relays hold envelopes for anyone, so every envelope must carry the
+/// ticket the strictness asks for.
+pub async fn admit_envelope(
+    state: &DaemonState,
+    mailbox_addr: &[u8; 32],
+    envelope: &MailboxEnvelope,
+) -> ochra_whisper::Result<TicketCheck> {
+    let quorum_key = quorum_key(state, envelope.ticket.as_ref()).await;
+    state.throttle.lock().await.admit(
+        false,
+        envelope.ticket.as_ref(),
+        mailbox_addr,
+        &envelope.envelope_id,
+        &envelope.sealed,
+        quorum_key.as_ref(),
+    )
+}
@@ -0,0 +1,168 @@
+//! Content tombstone propagation (Section 16.6).
+//!
+//! Tombstones issued by this node are recorded locally, advertised in the
+//! DHT, and gossiped. Tombstones received from peers are honoured only after
+//! the signature verifies and the signer is an authority of the Space; the
+//! content is then hidden from the catalog and its chunks purged from the
+//! ABR store, while the tombstone is kept as proof. Content held only as ABR
+//! chunks, with no catalog entry, is found by content hash in the ABR store
+//! and checked against the authorities of the Space the tombstone names.
+
+use std::sync::Arc;
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_storage::tombstone::{tombstone_record, Tombstone};
+use ochra_transport::gossip::GossipTopic;
+use tracing::debug;
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Gossip TTL for tombstones.
+const TOMBSTONE_GOSSIP_TTL: u8 = 6;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The Space holding `content_hash` and the keys allowed to tombstone
+/// content in it.
+///
+/// `None` if this node does not know the content.
+pub async fn authorities(
+    state: &DaemonState,
+    content_hash: &[u8; 32],
+) -> anyhow::Result<Option<([u8; 32], Vec<[u8; 32]>)>> {
+    let owner = ochra_db::queries::content::space_owner(&*state.db.lock().await, content_hash)?;
+    let Some(owner) = owner else {
+        return Ok(None);
+    };
+    let group_id: [u8; 32] = owner
+        .group_id
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("group id must be 32 bytes"))?;
+    let owner_pik: [u8; 32] = owner
+        .owner_pik
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("owner PIK must be 32 bytes"))?;
+    // Would: add the moderator PIKs from the Space's current SpaceManifest
+    Ok(Some((group_id, vec![owner_pik])))
+}
+
+/// The keys allowed to tombstone content in `group_id`, for content this
+/// node holds only as ABR chunks: the owner of a joined Space, or else the
+/// owner who signed the newest accepted policy.
+///
+/// Empty if this node knows nothing of the Space.
+async fn space_authorities(
+    state: &DaemonState,
+    group_id: &[u8; 32],
+) -> anyhow::Result<Vec<[u8; 32]>> {
+    let db = state.db.lock().await;
+    let owner = match ochra_db::queries::spaces::owner_pik(&db, group_id)? {
+        Some(owner) => Some(owner),
+        None => ochra_db::queries::group_policies::latest(&db, group_id)?.map(|row| row.signer_pk),
+    };
+    Ok(owner
+        .and_then(|owner| <[u8; 32]>::try_from(owner.as_slice()).ok())
+        .into_iter()
+        .collect())
+}
+
+/// Record a verified tombstone and apply it locally.
+///
+/// Returns `false` if the content was already tombstoned.
+pub async fn honour(
+    state: &DaemonState,
+    tombstone: Tombstone,
+    authorities: &[[u8; 32]],
+) -> anyhow::Result<bool> {
+    let (group_id, content_hash, signer_pik) = (
+        tombstone.group_id,
+        tombstone.content_hash,
+        tombstone.signer_pik,
+    );
+    let tombstone_id = tombstone.tombstone_id();
+    if !state
+        .tombstones
+        .lock()
+        .await
+        .accept(tombstone, authorities)?
+    {
+        return Ok(false);
+    }
+    ochra_db::queries::content::tombstone(&*state.db.lock().await, &content_hash, now_secs())?;
+    let mut abr = state.abr.lock().await;
+    let chunk_ids = abr.content_chunk_ids(&content_hash);
+    abr.apply_tombstone(tombstone_id, &chunk_ids);
+    drop(abr);
+    state.event_bus.emit(DaemonEvent::ContentTombstoned {
+        group_id,
+        content_hash,
+        tombstoned_by: signer_pik,
+    });
+    Ok(true)
+}
+
+/// Advertise a tombstone this node signed: DHT record plus gossip.
+pub async fn publish(
+    state: &DaemonState,
+    pik: &SigningKey,
+    tombstone: &Tombstone,
+) -> anyhow::Result<()> {
+    let _record = tombstone_record(pik, tombstone)?;
+    // Would: put _record into the DHT over Sphinx
+    let (_publish, targets) = state.gossip.lock().await.publish(
+        GossipTopic::Tombstones.topic_id(),
+        tombstone.to_bytes(),
+        TOMBSTONE_GOSSIP_TTL,
+    );
+    // Would: send _publish to `targets` over QUIC
+    debug!(
+        "Published tombstone for {} to {} peers",
+        hex::encode(tombstone.content_hash),
+        targets.len()
+    );
+    Ok(())
+}
+
+/// Handle a tombstone received over gossip.
+pub async fn handle_gossip(state: &Arc<DaemonState>, data: &[u8]) -> anyhow::Result<bool> {
+    let tombstone = Tombstone::from_bytes(data)?;
+    if let Some((group_id, authorities)) = authorities(state, &tombstone.content_hash).await? {
+        if tombstone.group_id != group_id {
+            anyhow::bail!("tombstone names a different Space than the content");
+        }
+        return honour(state, tombstone, &authorities).await;
+    }
+    let held = !state
+        .abr
+        .lock()
+        .await
+        .content_chunk_ids(&tombstone.content_hash)
+        .is_empty();
+    if !held {
+        debug!(
+            "Ignoring tombstone for unknown content {}",
+            hex::encode(tombstone.content_hash)
+        );
+        return Ok(false);
+    }
+    let authorities = space_authorities(state, &tombstone.group_id).await?;
+    if authorities.is_empty() {
+        // Would: fetch the SpaceManifest from the DHT to learn the Space's
+        // authorities
+        debug!(
+            "Ignoring tombstone for ABR chunks of {} from unknown Space {}",
+            hex::encode(tombstone.content_hash),
+            hex::encode(tombstone.group_id)
+        );
+        return Ok(false);
+    }
+    honour(state, tombstone, &authorities).await
+}
@@ -0,0 +1,31 @@
+//! Content update channel subscriptions.
+//!
+//! Tracks the best known head of every subscribed content lineage and emits
+//! a `ContentUpdated` event when an update channel record advertises a newer
+//! version.
+
+use std::sync::Arc;
+
+use ochra_dht::bep44::DhtRecord;
+use ochra_storage::versioning::UpdateNotice;
+
+use crate::events::DaemonEvent;
+use crate::DaemonState;
+
+/// Handle an update channel record fetched from the DHT.
+#[allow(dead_code)]
+pub async fn handle_channel_record(
+    state: &Arc<DaemonState>,
+    record: &DhtRecord,
+) -> ochra_storage::Result<Option<UpdateNotice>> {
+    let notice = state.content_updates.lock().await.observe(record)?;
+    if let Some(notice) = &notice {
+        state.event_bus.emit(DaemonEvent::ContentUpdated {
+            lineage_id: notice.lineage_id,
+            previous_content_hash: notice.previous_content_hash,
+            content_hash: notice.content_hash,
+            version: notice.version,
+        });
+    }
+    Ok(notice)
+}
@@ -0,0 +1,172 @@
+//! Protocol upgrade pipeline (Section 17).
+//!
+//! Upgrade manifests are fetched from the DHT, checked against the
+//! governance keyholder keys from config, and their platform artifact is
+//! staged under `<data_dir>/upgrades/`. Staged upgrades are applied on
+//! restart once their activation epoch arrives; a new version that does not
+//! confirm a clean boot is rolled back to the saved previous binary.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use ochra_crypto::upgrade::{self, BootOutcome, UpgradeStager, KEYHOLDER_COUNT};
+use ochra_dht::bep44::DhtRecord;
+use ochra_types::governance::{Platform, UpgradeManifest};
+use tracing::{error, info, warn};
+
+use crate::DaemonState;
+
+/// Open the upgrade staging directory in the data directory.
+///
+/// Unreadable rollout state is discarded rather than blocking startup.
+pub fn open(data_dir: &Path) -> ochra_crypto::Result<UpgradeStager> {
+    let dir = data_dir.join("upgrades");
+    UpgradeStager::open(&dir).or_else(|e| {
+        warn!("Discarding unreadable upgrade state: {}", e);
+        let _ = std::fs::remove_dir_all(&dir);
+        UpgradeStager::open(&dir)
+    })
+}
+
+/// The platform this binary was built for.
+pub fn current_platform() -> Option<Platform> {
+    if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some(Platform::MacosArm64)
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some(Platform::MacosX86_64)
+    } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some(Platform::WindowsX86_64)
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some(Platform::LinuxX86_64)
+    } else if cfg!(all(target_os = "android", target_arch = "aarch64")) {
+        Some(Platform::AndroidArm64)
+    } else if cfg!(all(target_os = "ios", target_arch = "aarch64")) {
+        Some(Platform::IosArm64)
+    } else {
+        None
+    }
+}
+
+/// Governance keyholder keys from config.
+fn keyholders(state: &DaemonState) -> Result<[[u8; 32]; KEYHOLDER_COUNT], String> {
+    let configured = &state.config.advanced.upgrade_keyholders;
+    if configured.len() != KEYHOLDER_COUNT {
+        return Err(format!(
+            "{} upgrade keyholders configured, {KEYHOLDER_COUNT} required",
+            configured.len()
+        ));
+    }
+    let mut keys = [[0u8; 32]; KEYHOLDER_COUNT];
+    for (slot, hex_key) in keys.iter_mut().zip(configured) {
+        let bytes = hex::decode(hex_key).map_err(|e| format!("bad keyholder key: {e}"))?;
+        *slot = bytes
+            .try_into()
+            .map_err(|_| "keyholder key must be 32 bytes".to_string())?;
+    }
+    Ok(keys)
+}
+
+/// Record this boot against any pending upgrade trial.
+pub async fn on_boot(state: &Arc<DaemonState>) {
+    let mut stager = state.upgrades.lock().await;
+    match stager.on_boot(env!("CARGO_PKG_VERSION")) {
+        Ok(BootOutcome::Normal) => {}
+        Ok(BootOutcome::Trial) => info!("Booting upgrade {} on trial", env!("CARGO_PKG_VERSION")),
+        Ok(BootOutcome::RollbackRequired { previous_binary }) => {
+            error!("Upgrade failed to confirm; restoring the previous binary");
+            let restored = std::env::current_exe().and_then(|exe| {
+                upgrade::install(&previous_binary, &exe)
+                    .map_err(std::io::Error::other)
+                    .map(|()| exe)
+            });
+            match restored {
+                Ok(exe) => restart(&exe),
+                Err(e) => error!("Failed to restore {:?}: {}", previous_binary, e),
+            }
+        }
+        Ok(BootOutcome::RolledBack { version }) => {
+            warn!("Upgrade trial failed; running previous version {}", version)
+        }
+        Err(e) => error!("Failed to record boot for upgrade trial: {}", e),
+    }
+}
+
+/// Replace this process with `exe`, passing the same arguments.
+///
+/// Returns only if the restart failed.
+fn restart(exe: &Path) {
+    let mut command = std::process::Command::new(exe);
+    command.args(std::env::args_os().skip(1));
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let e = command.exec();
+        error!("Failed to restart {:?}: {}", exe, e);
+    }
+    #[cfg(not(unix))]
+    match command.spawn() {
+        Ok(_) => std::process::exit(0),
+        Err(e) => error!("Failed to restart {:?}: {}", exe, e),
+    }
+}
+
+/// Mark a trial boot as good once startup has completed.
+pub async fn confirm_boot(state: &Arc<DaemonState>) {
+    if let Err(e) = state.upgrades.lock().await.confirm_boot() {
+        error!("Failed to confirm upgrade boot: {}", e);
+    }
+}
+
+/// Handle an upgrade manifest record fetched from the DHT.
+///
+/// Verifies the keyholder multisig, version monotonicity, and timelock,
+/// then applies the manifest's circuit key pins. Returns the manifest if it
+/// is a valid upgrade for this node.
+#[allow(dead_code)]
+pub async fn handle_manifest_record(
+    state: &Arc<DaemonState>,
+    record: &DhtRecord,
+) -> Result<UpgradeManifest, String> {
+    let manifest: UpgradeManifest =
+        ochra_transport::cbor::from_slice(record.value()).map_err(|e| e.to_string())?;
+    let keys = keyholders(state)?;
+    upgrade::verify_manifest(&manifest, &keys, env!("CARGO_PKG_VERSION"))
+        .map_err(|e| e.to_string())?;
+    let published_epoch = manifest.published_at / crate::epoch::epoch_duration_secs();
+    upgrade::check_timelock(&manifest, published_epoch).map_err(|e| e.to_string())?;
+
+    crate::circuits::handle_upgrade_manifest(state, &manifest)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if current_platform().is_some_and(|p| manifest.platform_hashes.iter().any(|h| h.platform == p))
+    {
+        // Would: fetch the platform artifact via ABR + Sphinx and stage_artifact() it
+        info!("Upgrade {} artifact pending download", manifest.version);
+    }
+    Ok(manifest)
+}
+
+/// Stage a downloaded artifact for a verified manifest.
+#[allow(dead_code)]
+pub async fn stage_artifact(
+    state: &Arc<DaemonState>,
+    manifest: &UpgradeManifest,
+    artifact: &[u8],
+) -> ochra_crypto::Result<()> {
+    let Some(platform) = current_platform() else {
+        return Err(ochra_crypto::CryptoError::Upgrade(
+            "no upgrade artifacts for this platform".to_string(),
+        ));
+    };
+    let staged = state
+        .upgrades
+        .lock()
+        .await
+        .stage(manifest, &platform, artifact)?;
+    info!(
+        "Staged upgrade {} for epoch {}",
+        staged.version, staged.activation_epoch
+    );
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Chunk serving with bounded upload slots (Section 14.8).
+//!
+//! Incoming `ChunkRequest`s go through the [`UploadManager`]: requesters
+//! holding a slot are served, the rest get a `ChunkQueued` reply with their
+//! queue position so they can back off. Slots are reassigned by a periodic
+//! rechoke that favours requesters who serve this node or acknowledge its
+//! service receipts. Outside the storage role every request is rejected;
+//! while the role drains, only requesters already holding a slot are served.
+//!
+//! [`UploadManager`]: ochra_storage::upload::UploadManager
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_storage::upload::{UploadDecision, RECHOKE_INTERVAL_SECS};
+use ochra_transport::messages::{ChunkQueued, ChunkRequest, TypedMessage};
+use tracing::debug;
+
+use crate::mode::Role;
+use crate::DaemonState;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Decide whether to serve a chunk request from `requester`.
+///
+/// Returns the `ChunkQueued` reply to send when the request is not served
+/// now, or `None` if the chunk should be sent.
+#[allow(dead_code)]
+pub async fn handle_chunk_request(
+    state: &Arc<DaemonState>,
+    requester: [u8; 32],
+    request: &ChunkRequest,
+) -> Option<TypedMessage> {
+    let decision = if crate::mode::accepts(state, Role::Storage).await {
+        state.uploads.lock().await.request(requester, now_secs())
+    } else {
+        // While draining, only requesters already holding a slot finish.
+        let draining = crate::mode::serves(state, Role::Storage).await;
+        let uploads = state.uploads.lock().await;
+        if draining && uploads.is_unchoked(&requester) {
+            UploadDecision::Serve
+        } else {
+            UploadDecision::Rejected
+        }
+    };
+    let (queue_position, retry_after_secs) = match decision {
+        UploadDecision::Serve => {
+            // Would: read the chunk from the ABR store and stream a
+            // ChunkResponse; the requester's acknowledgement arrives later
+            // in a ServiceReceiptAckBatch (see receipt_acks::handle_batch)
+            return None;
+        }
+        UploadDecision::Queued {
+            position,
+            retry_after_secs,
+        } => (position, retry_after_secs),
+        UploadDecision::Rejected => (0, ochra_storage::upload::MAX_RETRY_AFTER_SECS),
+    };
+    Some(TypedMessage::ChunkQueued(ChunkQueued {
+        chunk_hash: request.chunk_hash,
+        queue_position,
+        retry_after_secs,
+    }))
+}
+
+/// Credit a requester whose receipt acknowledgement for `bytes` verified.
+pub async fn record_receipt(state: &Arc<DaemonState>, requester: [u8; 32], bytes: u64) {
+    state.uploads.lock().await.record_receipt(requester, bytes);
+}
+
+/// Reassign upload slots until shutdown or the storage role stops.
+pub async fn run_rechoker(state: Arc<DaemonState>, generation: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(RECHOKE_INTERVAL_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                if !crate::mode::keep_running(&state, Role::Storage, generation).await {
+                    break;
+                }
+                let mut uploads = state.uploads.lock().await;
+                let Some(outcome) = uploads.rechoke(now_secs()) else {
+                    continue;
+                };
+                if !outcome.unchoked.is_empty() || !outcome.choked.is_empty() {
+                    debug!(
+                        "Rechoke: {} unchoked, {} choked, {} queued",
+                        outcome.unchoked.len(),
+                        outcome.choked.len(),
+                        uploads.queue_len()
+                    );
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Uptime attestation intake (Section 9.1).
+//!
+//! Attestations arrive over the `uptime-attestation` gossip topic. Each is
+//! checked against the epoch's `uptime` beacon seed and the active relay
+//! set, whose descriptors name the PIK that must have signed for each
+//! relay and auditor, then kept in the attestation set that uptime is
+//! scored from.
+
+use std::sync::Arc;
+
+use ochra_posrv::uptime::{ActiveRelay, UptimeAttestation};
+
+use crate::DaemonState;
+
+/// Epochs of attestations kept; the uptime window is the trailing 30.
+pub const WINDOW_EPOCHS: u64 = 30;
+
+/// Handle an attestation received over gossip. Returns `false` if it was
+/// already known.
+pub async fn handle_gossip(state: &Arc<DaemonState>, data: &[u8]) -> anyhow::Result<bool> {
+    let attestation: UptimeAttestation = ochra_transport::cbor::from_slice(data)?;
+    let epoch = attestation.challenge.epoch;
+    let beacon = crate::beacon::seed(state, b"uptime", u32::try_from(epoch)?, 0)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("no beacon known for epoch {}", epoch))?;
+    let relays: Vec<ActiveRelay> = state
+        .relays
+        .lock()
+        .await
+        .all()
+        .iter()
+        .map(ActiveRelay::from)
+        .collect();
+    ochra_posrv::uptime::verify_attestation(&attestation, &beacon, &relays)?;
+
+    let mut uptime = state.uptime.lock().await;
+    let inserted = uptime.insert(&attestation);
+    uptime.prune_before(epoch.saturating_sub(WINDOW_EPOCHS - 1));
+    Ok(inserted)
+}
+
+/// This node's uptime over the trailing window, if any auditor reported on
+/// it.
+pub async fn own_uptime(state: &DaemonState) -> Option<f64> {
+    let node_id = crate::peer::local_node_id(&*state.db.lock().await);
+    let current = crate::epoch::current_epoch();
+    state.uptime.lock().await.uptime_fraction(
+        &node_id,
+        current.saturating_sub(WINDOW_EPOCHS - 1)..=current,
+    )
+}
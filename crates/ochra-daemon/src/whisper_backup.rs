@@ -0,0 +1,403 @@
+//! Opt-in Whisper session backups (Section 7.10).
+//!
+//! Every message a live session queues records a checkpoint of the session
+//! into a [`CheckpointLog`] (see [`crate::delivery`]). Once
+//! the user enables backups, the exporter seals the log under the recovery
+//! key every `interval_secs` while the session is unlocked and the log has
+//! changed, and writes it to a local file. Dead-drop backups are refused
+//! until the daemon can publish to the DHT.
+//!
+//! The recovery key is derived once at enable time and kept in settings,
+//! wrapped under a key derived from the PIK, so exports do not need the
+//! passphrase. The passphrase only protects copies that leave the device.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_whisper::backup::{
+    self, BackupDestination, BackupPolicy, BackupScope, CheckpointLog, KdfParams,
+    RatchetCheckpoint, RecoveryKey, SealedBackup,
+};
+use ochra_whisper::WhisperError;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+
+use crate::DaemonState;
+
+/// Settings key holding the enabled backup, or `null`.
+const BACKUP_KEY: &str = "whisper_backup";
+
+/// File name of local backups in the data directory.
+const BACKUP_FILE: &str = "whisper-backup.bin";
+
+/// Export interval when the caller gives none.
+pub const DEFAULT_INTERVAL_SECS: u64 = 6 * 3600;
+
+/// Interval between checks for a due export.
+const EXPORT_CHECK_SECS: u64 = 60;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// In-memory checkpoints and the generation last exported.
+#[derive(Debug, Default)]
+pub struct BackupState {
+    pub log: CheckpointLog,
+    exported_generation: Option<u64>,
+}
+
+/// An enabled backup as stored in settings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredBackup {
+    pub policy: BackupPolicy,
+    kdf: KdfParams,
+    salt: [u8; 16],
+    /// Recovery key, encrypted under [`wrap_key`].
+    wrapped_key: Vec<u8>,
+    wrap_nonce: [u8; 12],
+    /// Sequence number of the last dead drop published.
+    pub seq: u64,
+    pub last_export_at: Option<u64>,
+}
+
+/// What one export produced.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportSummary {
+    pub destination: BackupDestination,
+    pub sessions: usize,
+    pub checkpoints: usize,
+    pub bytes: usize,
+    pub exported_at: u64,
+}
+
+fn wrap_key(pik: &SigningKey) -> [u8; 32] {
+    ochra_crypto::blake3::derive_key(
+        ochra_crypto::blake3::contexts::WHISPER_BACKUP_WRAP,
+        &pik.to_bytes(),
+    )
+}
+
+impl StoredBackup {
+    fn new(pik: &SigningKey, policy: BackupPolicy, key: &RecoveryKey) -> anyhow::Result<Self> {
+        let mut wrap_nonce = [0u8; 12];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut wrap_nonce);
+        let wrapped_key = ochra_crypto::chacha20::encrypt(
+            &wrap_key(pik),
+            &wrap_nonce,
+            key.expose(),
+            BACKUP_KEY.as_bytes(),
+        )?;
+        Ok(Self {
+            policy,
+            kdf: key.params(),
+            salt: key.salt(),
+            wrapped_key,
+            wrap_nonce,
+            seq: 0,
+            last_export_at: None,
+        })
+    }
+
+    fn recovery_key(&self, pik: &SigningKey) -> anyhow::Result<RecoveryKey> {
+        let key = ochra_crypto::chacha20::decrypt(
+            &wrap_key(pik),
+            &self.wrap_nonce,
+            &self.wrapped_key,
+            BACKUP_KEY.as_bytes(),
+        )?;
+        let key: [u8; 32] = key
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("recovery key must be 32 bytes"))?;
+        Ok(RecoveryKey::from_parts(key, self.salt, self.kdf))
+    }
+
+    /// Whether an export is due at `now` with the log at `generation`.
+    fn is_due(&self, now: u64, generation: u64, exported: Option<u64>) -> bool {
+        let elapsed = self
+            .last_export_at
+            .is_none_or(|at| now.saturating_sub(at) >= self.policy.interval_secs);
+        elapsed && exported != Some(generation)
+    }
+}
+
+/// The local backup file.
+pub fn local_path(state: &DaemonState) -> PathBuf {
+    state.config.data_dir().join(BACKUP_FILE)
+}
+
+/// The enabled backup, if any.
+pub async fn load(state: &DaemonState) -> anyhow::Result<Option<StoredBackup>> {
+    let db = state.db.lock().await;
+    match ochra_db::queries::settings::get(&db, BACKUP_KEY) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(ochra_db::DbError::NotFound(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn save(state: &DaemonState, stored: Option<&StoredBackup>) -> anyhow::Result<()> {
+    let json = serde_json::to_string(&stored)?;
+    ochra_db::queries::settings::set(&*state.db.lock().await, BACKUP_KEY, &json)?;
+    Ok(())
+}
+
+/// Apply the stored scope to the checkpoint log at startup.
+pub async fn init(state: &DaemonState) -> anyhow::Result<()> {
+    if let Some(stored) = load(state).await? {
+        state
+            .whisper_backup
+            .lock()
+            .await
+            .log
+            .set_scope(stored.policy.scope);
+    }
+    Ok(())
+}
+
+/// Record a session checkpoint for the next export.
+pub async fn checkpoint(state: &DaemonState, checkpoint: RatchetCheckpoint) {
+    state.whisper_backup.lock().await.log.record(checkpoint);
+}
+
+/// Drop a session's checkpoints, so a blocked peer's history is not kept.
+pub async fn forget(state: &DaemonState, session_id: &[u8; 16]) {
+    state.whisper_backup.lock().await.log.forget(session_id);
+}
+
+/// Derive the recovery key, store the policy, and export once.
+pub async fn enable(
+    state: &Arc<DaemonState>,
+    pik: &SigningKey,
+    passphrase: String,
+    policy: BackupPolicy,
+) -> anyhow::Result<ExportSummary> {
+    check_destination(policy.destination)?;
+    let pik_secret = pik.to_bytes();
+    let salt = ochra_crypto::argon2id::generate_salt();
+    let key = tokio::task::spawn_blocking(move || {
+        RecoveryKey::derive(&pik_secret, &passphrase, salt, backup::RECOVERY_KDF)
+    })
+    .await??;
+    let stored = StoredBackup::new(pik, policy, &key)?;
+    save(state, Some(&stored)).await?;
+    {
+        let mut backup_state = state.whisper_backup.lock().await;
+        backup_state.log.set_scope(policy.scope);
+        backup_state.exported_generation = None;
+    }
+    info!("Whisper session backups enabled ({:?})", policy.destination);
+    export(state, pik, stored).await
+}
+
+/// Stop exporting and remove the local backup.
+pub async fn disable(state: &DaemonState) -> anyhow::Result<bool> {
+    let Some(stored) = load(state).await? else {
+        return Ok(false);
+    };
+    save(state, None).await?;
+    // Nothing is ever published to a dead drop, so only a local file can
+    // hold a backup
+    if stored.policy.destination == BackupDestination::Local {
+        match tokio::fs::remove_file(local_path(state)).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    state.whisper_backup.lock().await.exported_generation = None;
+    info!("Whisper session backups disabled");
+    Ok(true)
+}
+
+/// Seal the current checkpoints and write them to the destination.
+pub async fn export(
+    state: &DaemonState,
+    pik: &SigningKey,
+    mut stored: StoredBackup,
+) -> anyhow::Result<ExportSummary> {
+    check_destination(stored.policy.destination)?;
+    let (checkpoints, sessions, generation) = {
+        let backup_state = state.whisper_backup.lock().await;
+        (
+            backup_state.log.snapshot(),
+            backup_state.log.session_count(),
+            backup_state.log.generation(),
+        )
+    };
+    let now = now_secs();
+    let count = checkpoints.len();
+    let key = stored.recovery_key(pik)?;
+    let sealed = backup::seal(&key, stored.policy.scope, checkpoints, now)?;
+    let bytes = sealed.to_bytes();
+
+    let path = local_path(state);
+    let tmp = path.with_extension("tmp");
+    tokio::fs::write(&tmp, &bytes).await?;
+    tokio::fs::rename(&tmp, &path).await?;
+    stored.last_export_at = Some(now);
+    save(state, Some(&stored)).await?;
+    state.whisper_backup.lock().await.exported_generation = Some(generation);
+    Ok(ExportSummary {
+        destination: stored.policy.destination,
+        sessions,
+        checkpoints: count,
+        bytes: bytes.len(),
+        exported_at: now,
+    })
+}
+
+/// Read a sealed backup from `path`, or from the local backup file.
+pub async fn read_local(
+    state: &DaemonState,
+    path: Option<PathBuf>,
+) -> anyhow::Result<SealedBackup> {
+    let bytes = tokio::fs::read(path.unwrap_or_else(|| local_path(state))).await?;
+    Ok(SealedBackup::from_bytes(&bytes)?)
+}
+
+/// Open `sealed`, load its checkpoints into the log, and reopen each
+/// session from its newest checkpoint.
+///
+/// Returns the scope it was made under and the checkpoints restored.
+pub async fn restore(
+    state: &DaemonState,
+    pik: &SigningKey,
+    passphrase: String,
+    sealed: SealedBackup,
+) -> anyhow::Result<(BackupScope, Vec<RatchetCheckpoint>)> {
+    let pik_secret = pik.to_bytes();
+    let (scope, checkpoints) =
+        tokio::task::spawn_blocking(move || backup::open(&sealed, &pik_secret, &passphrase))
+            .await??;
+    let mut newest: HashMap<[u8; 16], &RatchetCheckpoint> = HashMap::new();
+    {
+        let mut backup_state = state.whisper_backup.lock().await;
+        for checkpoint in &checkpoints {
+            backup_state.log.record(checkpoint.clone());
+            let entry = newest.entry(checkpoint.session_id).or_insert(checkpoint);
+            if checkpoint.next_sequence > entry.next_sequence {
+                *entry = checkpoint;
+            }
+        }
+    }
+    for checkpoint in newest.into_values() {
+        if let Err(e) = crate::delivery::resume_session(state, checkpoint).await {
+            warn!(
+                "Cannot resume Whisper session {}: {}",
+                hex::encode(checkpoint.session_id),
+                e
+            );
+        }
+    }
+    Ok((scope, checkpoints))
+}
+
+/// Refuse destinations the daemon cannot write to.
+fn check_destination(destination: BackupDestination) -> anyhow::Result<()> {
+    if destination == BackupDestination::DeadDrop {
+        return Err(
+            WhisperError::Backup("dead-drop backups cannot be published yet".to_string()).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Export if enabled, unlocked, due, and changed.
+async fn export_if_due(state: &Arc<DaemonState>) -> anyhow::Result<()> {
+    let Some(stored) = load(state).await? else {
+        return Ok(());
+    };
+    let (generation, exported) = {
+        let backup_state = state.whisper_backup.lock().await;
+        (
+            backup_state.log.generation(),
+            backup_state.exported_generation,
+        )
+    };
+    if !stored.is_due(now_secs(), generation, exported) {
+        return Ok(());
+    }
+    let Some(pik) = crate::audit::pik_signing_key(state).await? else {
+        return Ok(());
+    };
+    let summary = export(state, &pik, stored).await?;
+    debug!(
+        "Exported {} Whisper checkpoints for {} sessions",
+        summary.checkpoints, summary.sessions
+    );
+    Ok(())
+}
+
+/// Export due backups until shutdown.
+pub async fn run_exporter(state: Arc<DaemonState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(EXPORT_CHECK_SECS));
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    loop {
+        tokio::select! {
+            _ = crate::power::tick(&state, &mut interval) => {
+                if let Err(e) = export_if_due(&state).await {
+                    error!("Whisper backup export failed: {}", e);
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(pik: &SigningKey) -> StoredBackup {
+        let policy = BackupPolicy::new(
+            BackupScope::Latest,
+            BackupDestination::Local,
+            3600,
+            &BackupScope::Latest.exposure(),
+        )
+        .expect("policy");
+        let key = RecoveryKey::from_parts(
+            [9; 32],
+            [1; 16],
+            KdfParams {
+                m_cost: 1024,
+                t_cost: 1,
+                p_cost: 1,
+            },
+        );
+        StoredBackup::new(pik, policy, &key).expect("stored")
+    }
+
+    #[test]
+    fn test_recovery_key_unwraps_only_with_pik() {
+        let pik = SigningKey::from_bytes(&[3; 32]);
+        let stored = stored(&pik);
+        let round_trip: StoredBackup =
+            serde_json::from_str(&serde_json::to_string(&stored).expect("encode")).expect("decode");
+        assert_eq!(round_trip, stored);
+        let key = stored.recovery_key(&pik).expect("unwrap");
+        assert_eq!(key.expose(), &[9; 32]);
+        assert!(stored
+            .recovery_key(&SigningKey::from_bytes(&[4; 32]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_export_due_after_interval_and_change() {
+        let pik = SigningKey::from_bytes(&[3; 32]);
+        let mut stored = stored(&pik);
+        assert!(stored.is_due(1_000, 0, None));
+        stored.last_export_at = Some(1_000);
+        assert!(!stored.is_due(2_000, 5, Some(4)), "interval not elapsed");
+        assert!(!stored.is_due(5_000, 5, Some(5)), "nothing changed");
+        assert!(stored.is_due(5_000, 6, Some(5)));
+    }
+}
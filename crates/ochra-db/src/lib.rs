@@ -6,11 +6,12 @@
 //! ## Schema
 //!
 //! The schema follows Section 27 of the v5.5 spec exactly:
-//! - WAL mode mandatory
+//! - WAL mode mandatory, checkpointed by [`maintenance`]
 //! - Foreign keys enforced
 //! - All timestamps are Unix epoch seconds (u64)
 //! - Schema version stored in `PRAGMA user_version`
 
+pub mod maintenance;
 pub mod migrations;
 pub mod queries;
 pub mod schema;
@@ -19,7 +20,7 @@ use rusqlite::Connection;
 use std::path::Path;
 
 /// Current schema version.
-pub const SCHEMA_VERSION: u32 = 1;
+pub const SCHEMA_VERSION: u32 = 21;
 
 /// Database error types.
 #[derive(Debug, thiserror::Error)]
@@ -61,9 +62,13 @@ pub fn open_memory() -> Result<Connection> {
 }
 
 /// Configure SQLite pragmas.
+///
+/// `auto_vacuum` only takes effect on a new database; existing ones are
+/// converted by [`maintenance::incremental_vacuum`].
 fn configure(conn: &Connection) -> Result<()> {
     conn.execute_batch(
-        "PRAGMA journal_mode = WAL;
+        "PRAGMA auto_vacuum = INCREMENTAL;
+         PRAGMA journal_mode = WAL;
          PRAGMA foreign_keys = ON;
          PRAGMA busy_timeout = 5000;
          PRAGMA synchronous = NORMAL;
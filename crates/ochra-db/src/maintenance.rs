@@ -0,0 +1,412 @@
+//! Scheduled database maintenance.
+//!
+//! A long-running daemon accumulates WAL frames and free pages. The
+//! [`MaintenanceScheduler`] checkpoints the WAL on a fixed interval and runs
+//! the heavier incremental vacuum and integrity check when the connection
+//! has been idle, or once they are overdue regardless. Problems found are
+//! returned as [`Anomaly`] values for the caller to report.
+//!
+//! Databases created before `auto_vacuum = INCREMENTAL` was configured are
+//! converted by a full `VACUUM` on their first vacuum run. That rewrites the
+//! whole file, so it only runs on an idle connection, even when overdue.
+//! The scheduler counts startup as activity, so nothing heavy runs until the
+//! connection has been idle for [`IDLE_AFTER_SECS`].
+
+use rusqlite::Connection;
+
+use crate::Result;
+
+/// Seconds between WAL checkpoints.
+pub const CHECKPOINT_INTERVAL_SECS: u64 = 300;
+
+/// Seconds between incremental vacuums.
+pub const VACUUM_INTERVAL_SECS: u64 = 3600;
+
+/// Seconds between integrity checks.
+pub const INTEGRITY_INTERVAL_SECS: u64 = 86_400;
+
+/// Seconds without writes before the connection counts as idle.
+pub const IDLE_AFTER_SECS: u64 = 120;
+
+/// Multiple of its interval after which a task runs even while busy.
+pub const OVERDUE_FACTOR: u64 = 4;
+
+/// Free pages reclaimed per incremental vacuum.
+pub const VACUUM_PAGES: u32 = 1024;
+
+/// WAL frames left after a checkpoint that count as an anomaly.
+pub const WAL_WARN_FRAMES: i64 = 10_000;
+
+/// Integrity errors collected per check.
+pub const MAX_INTEGRITY_ERRORS: u32 = 100;
+
+/// `PRAGMA auto_vacuum` value for incremental mode.
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// A maintenance task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaintenanceTask {
+    /// `PRAGMA wal_checkpoint(TRUNCATE)`.
+    Checkpoint,
+    /// `PRAGMA incremental_vacuum`.
+    Vacuum,
+    /// `PRAGMA integrity_check` and `PRAGMA foreign_key_check`.
+    IntegrityCheck,
+}
+
+impl MaintenanceTask {
+    /// Every task, in the order a tick runs them.
+    pub const ALL: [MaintenanceTask; 3] = [
+        MaintenanceTask::Checkpoint,
+        MaintenanceTask::Vacuum,
+        MaintenanceTask::IntegrityCheck,
+    ];
+
+    /// Stable name for logs and events.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Checkpoint => "checkpoint",
+            Self::Vacuum => "vacuum",
+            Self::IntegrityCheck => "integrity_check",
+        }
+    }
+
+    fn interval(self) -> u64 {
+        match self {
+            Self::Checkpoint => CHECKPOINT_INTERVAL_SECS,
+            Self::Vacuum => VACUUM_INTERVAL_SECS,
+            Self::IntegrityCheck => INTEGRITY_INTERVAL_SECS,
+        }
+    }
+
+    /// Whether the task waits for an idle connection until overdue.
+    fn waits_for_idle(self) -> bool {
+        !matches!(self, Self::Checkpoint)
+    }
+}
+
+/// Something maintenance found wrong.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Anomaly {
+    /// `integrity_check` reported corruption.
+    #[error("integrity check failed: {}", errors.join("; "))]
+    Corruption { errors: Vec<String> },
+
+    /// Rows reference missing parents.
+    #[error("{count} foreign key violations")]
+    ForeignKeyViolations { count: u64 },
+
+    /// A checkpoint left the WAL large, usually because a reader held it.
+    #[error("WAL still holds {remaining_frames} frames after checkpoint")]
+    WalNotCheckpointed { remaining_frames: i64 },
+
+    /// The task itself failed.
+    #[error("{0}")]
+    Failed(String),
+}
+
+/// Outcome of one task run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    pub task: MaintenanceTask,
+    /// When the task ran.
+    pub ran_at: u64,
+    /// Free pages reclaimed, for vacuums.
+    pub freed_pages: u64,
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// Checkpoint and truncate the WAL.
+pub fn checkpoint(conn: &Connection) -> Result<Vec<Anomaly>> {
+    let (busy, log_frames, checkpointed): (i64, i64, i64) =
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+    // Not in WAL mode (in-memory databases) reports -1 frames.
+    let remaining_frames = log_frames - checkpointed;
+    if busy != 0 && log_frames >= 0 && remaining_frames >= WAL_WARN_FRAMES {
+        return Ok(vec![Anomaly::WalNotCheckpointed { remaining_frames }]);
+    }
+    Ok(Vec::new())
+}
+
+fn pragma_i64(conn: &Connection, name: &str) -> Result<i64> {
+    Ok(conn.pragma_query_value(None, name, |row| row.get(0))?)
+}
+
+/// Whether the next vacuum must first convert the database to incremental
+/// auto-vacuum with a full `VACUUM`.
+pub fn needs_full_vacuum(conn: &Connection) -> Result<bool> {
+    Ok(pragma_i64(conn, "auto_vacuum")? != AUTO_VACUUM_INCREMENTAL)
+}
+
+/// Reclaim up to `max_pages` free pages. Returns the number reclaimed.
+///
+/// Converts the database to incremental auto-vacuum first if needed.
+pub fn incremental_vacuum(conn: &Connection, max_pages: u32) -> Result<u64> {
+    let before = pragma_i64(conn, "freelist_count")?;
+    if needs_full_vacuum(conn)? {
+        conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")?;
+    } else {
+        // Each step frees one page, so the statement must run to completion.
+        let mut stmt = conn.prepare(&format!("PRAGMA incremental_vacuum({max_pages})"))?;
+        let mut rows = stmt.query([])?;
+        while rows.next()?.is_some() {}
+    }
+    let after = pragma_i64(conn, "freelist_count")?;
+    Ok(before.saturating_sub(after).max(0) as u64)
+}
+
+/// Check the database for corruption and dangling foreign keys.
+pub fn integrity_check(conn: &Connection) -> Result<Vec<Anomaly>> {
+    let mut anomalies = Vec::new();
+
+    let mut stmt = conn.prepare(&format!("PRAGMA integrity_check({MAX_INTEGRITY_ERRORS})"))?;
+    let errors = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if errors.iter().any(|e| e != "ok") {
+        anomalies.push(Anomaly::Corruption { errors });
+    }
+
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let count = stmt.query_map([], |_| Ok(()))?.count() as u64;
+    if count > 0 {
+        anomalies.push(Anomaly::ForeignKeyViolations { count });
+    }
+    Ok(anomalies)
+}
+
+/// Decides when each [`MaintenanceTask`] runs.
+#[derive(Debug, Default)]
+pub struct MaintenanceScheduler {
+    last_run: [Option<u64>; 3],
+    total_changes: Option<i64>,
+    last_write_at: u64,
+}
+
+impl MaintenanceScheduler {
+    /// Create a scheduler with every task due.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn index(task: MaintenanceTask) -> usize {
+        match task {
+            MaintenanceTask::Checkpoint => 0,
+            MaintenanceTask::Vacuum => 1,
+            MaintenanceTask::IntegrityCheck => 2,
+        }
+    }
+
+    /// Record the connection's write counter, noting activity if it moved
+    /// since the last observation. The first observation counts as
+    /// activity.
+    pub fn observe(&mut self, total_changes: i64, now: u64) {
+        if self.total_changes != Some(total_changes) {
+            self.last_write_at = now;
+        }
+        self.total_changes = Some(total_changes);
+    }
+
+    /// Whether nothing was written for [`IDLE_AFTER_SECS`].
+    pub fn is_idle(&self, now: u64) -> bool {
+        now.saturating_sub(self.last_write_at) >= IDLE_AFTER_SECS
+    }
+
+    /// When `task` last ran.
+    pub fn last_run(&self, task: MaintenanceTask) -> Option<u64> {
+        self.last_run[Self::index(task)]
+    }
+
+    /// Tasks due at `now`.
+    pub fn due(&self, now: u64) -> Vec<MaintenanceTask> {
+        let idle = self.is_idle(now);
+        MaintenanceTask::ALL
+            .into_iter()
+            .filter(|&task| {
+                let Some(last) = self.last_run(task) else {
+                    return idle || !task.waits_for_idle();
+                };
+                let elapsed = now.saturating_sub(last);
+                elapsed >= task.interval()
+                    && (idle
+                        || !task.waits_for_idle()
+                        || elapsed >= task.interval() * OVERDUE_FACTOR)
+            })
+            .collect()
+    }
+
+    /// Run every due task on `conn`. A failing task is reported as an
+    /// [`Anomaly::Failed`] rather than an error, and retried next interval.
+    /// A vacuum that needs the full conversion waits for an idle connection.
+    pub fn tick(&mut self, conn: &Connection, now: u64) -> Vec<MaintenanceReport> {
+        if let Ok(changes) = total_changes(conn) {
+            self.observe(changes, now);
+        }
+        let idle = self.is_idle(now);
+        let reports: Vec<MaintenanceReport> = self
+            .due(now)
+            .into_iter()
+            .filter(|&task| {
+                task != MaintenanceTask::Vacuum || idle || !needs_full_vacuum(conn).unwrap_or(true)
+            })
+            .map(|task| {
+                self.last_run[Self::index(task)] = Some(now);
+                run(conn, task, now)
+            })
+            .collect();
+        // Maintenance's own writes are not activity.
+        if let Ok(changes) = total_changes(conn) {
+            self.total_changes = Some(changes);
+        }
+        reports
+    }
+}
+
+fn total_changes(conn: &Connection) -> Result<i64> {
+    Ok(conn.query_row("SELECT total_changes()", [], |row| row.get(0))?)
+}
+
+/// Run `task` now.
+pub fn run(conn: &Connection, task: MaintenanceTask, now: u64) -> MaintenanceReport {
+    let mut freed_pages = 0;
+    let result = match task {
+        MaintenanceTask::Checkpoint => checkpoint(conn),
+        MaintenanceTask::Vacuum => incremental_vacuum(conn, VACUUM_PAGES).map(|freed| {
+            freed_pages = freed;
+            Vec::new()
+        }),
+        MaintenanceTask::IntegrityCheck => integrity_check(conn),
+    };
+    MaintenanceReport {
+        task,
+        ran_at: now,
+        freed_pages,
+        anomalies: result.unwrap_or_else(|e| vec![Anomaly::Failed(e.to_string())]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heavy_tasks_wait_for_idle() {
+        let mut scheduler = MaintenanceScheduler::new();
+        scheduler.observe(0, 900);
+        scheduler.observe(1, 1000);
+        assert_eq!(scheduler.due(1000), vec![MaintenanceTask::Checkpoint]);
+        assert_eq!(scheduler.due(1000 + IDLE_AFTER_SECS), MaintenanceTask::ALL);
+
+        for task in MaintenanceTask::ALL {
+            scheduler.last_run[MaintenanceScheduler::index(task)] = Some(2000);
+        }
+        scheduler.observe(2, 2000 + VACUUM_INTERVAL_SECS);
+        assert_eq!(
+            scheduler.due(2000 + VACUUM_INTERVAL_SECS),
+            vec![MaintenanceTask::Checkpoint]
+        );
+
+        // Overdue tasks run even while writes continue.
+        let overdue = 2000 + VACUUM_INTERVAL_SECS * OVERDUE_FACTOR;
+        scheduler.observe(3, overdue);
+        assert_eq!(
+            scheduler.due(overdue),
+            vec![MaintenanceTask::Checkpoint, MaintenanceTask::Vacuum]
+        );
+    }
+
+    #[test]
+    fn test_tick_runs_due_tasks_once() {
+        let conn = crate::open_memory().expect("open");
+        let mut scheduler = MaintenanceScheduler::new();
+        // Startup counts as activity: only the checkpoint runs at once
+        let reports = scheduler.tick(&conn, 1000);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].task, MaintenanceTask::Checkpoint);
+
+        let reports = scheduler.tick(&conn, 1000 + IDLE_AFTER_SECS);
+        assert_eq!(
+            reports.iter().map(|r| r.task).collect::<Vec<_>>(),
+            vec![MaintenanceTask::Vacuum, MaintenanceTask::IntegrityCheck]
+        );
+        assert!(reports.iter().all(|r| r.anomalies.is_empty()));
+
+        assert!(scheduler.tick(&conn, 1000 + IDLE_AFTER_SECS + 1).is_empty());
+        let next = scheduler.tick(&conn, 1000 + CHECKPOINT_INTERVAL_SECS);
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].task, MaintenanceTask::Checkpoint);
+    }
+
+    #[test]
+    fn test_full_vacuum_waits_for_idle_even_when_overdue() {
+        let conn = crate::open_memory().expect("open");
+        conn.execute_batch("PRAGMA auto_vacuum = NONE; VACUUM;")
+            .expect("disable auto-vacuum");
+        assert!(needs_full_vacuum(&conn).expect("mode"));
+        conn.execute_batch("CREATE TABLE filler (x INTEGER)")
+            .expect("create");
+
+        let mut scheduler = MaintenanceScheduler::new();
+        let overdue = VACUUM_INTERVAL_SECS * OVERDUE_FACTOR;
+        for task in MaintenanceTask::ALL {
+            scheduler.last_run[MaintenanceScheduler::index(task)] = Some(0);
+        }
+        scheduler.tick(&conn, overdue - 1);
+        conn.execute("INSERT INTO filler VALUES (1)", [])
+            .expect("write");
+        let reports = scheduler.tick(&conn, overdue);
+        assert!(reports.iter().all(|r| r.task != MaintenanceTask::Vacuum));
+        assert_eq!(scheduler.last_run(MaintenanceTask::Vacuum), Some(0));
+
+        let reports = scheduler.tick(&conn, overdue + IDLE_AFTER_SECS);
+        assert!(reports.iter().any(|r| r.task == MaintenanceTask::Vacuum));
+    }
+
+    #[test]
+    fn test_vacuum_reclaims_free_pages() {
+        let dir = std::env::temp_dir().join(format!("ochra-vacuum-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("dir");
+        let path = dir.join("test.db");
+        let _ = std::fs::remove_file(&path);
+        let conn = crate::open(&path).expect("open");
+        conn.execute_batch(
+            "CREATE TABLE filler (data BLOB);
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 200)
+             INSERT INTO filler SELECT zeroblob(4096) FROM n;
+             DELETE FROM filler;",
+        )
+        .expect("fill");
+        assert!(pragma_i64(&conn, "freelist_count").expect("count") > 0);
+
+        let freed = incremental_vacuum(&conn, VACUUM_PAGES).expect("vacuum");
+        assert!(freed > 0);
+        assert_eq!(pragma_i64(&conn, "freelist_count").expect("count"), 0);
+        assert_eq!(
+            pragma_i64(&conn, "auto_vacuum").expect("mode"),
+            AUTO_VACUUM_INCREMENTAL
+        );
+        assert!(checkpoint(&conn).expect("checkpoint").is_empty());
+        drop(conn);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_integrity_check_reports_dangling_keys() {
+        let conn = crate::open_memory().expect("open");
+        assert!(integrity_check(&conn).expect("check").is_empty());
+
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             CREATE TABLE parent (id INTEGER PRIMARY KEY);
+             CREATE TABLE child (parent_id INTEGER REFERENCES parent(id));
+             INSERT INTO child VALUES (7);",
+        )
+        .expect("insert");
+        assert_eq!(
+            integrity_check(&conn).expect("check"),
+            vec![Anomaly::ForeignKeyViolations { count: 1 }]
+        );
+    }
+}
@@ -22,6 +22,11 @@ pub fn run(conn: &Connection) -> Result<()> {
         // Insert default settings
         insert_default_settings(conn)?;
 
+        // Bring the v1 schema up to date
+        for version in 2..=SCHEMA_VERSION {
+            run_migration(conn, version)?;
+        }
+
         // Set version
         conn.pragma_update(None, "user_version", SCHEMA_VERSION)
             .map_err(DbError::Sqlite)?;
@@ -68,12 +73,72 @@ fn insert_default_settings(conn: &Connection) -> Result<()> {
 }
 
 /// Run a specific migration.
-fn run_migration(_conn: &Connection, version: u32) -> Result<()> {
-    // Future migrations go here:
-    // 2 => migration_v2(conn),
-    Err(DbError::Migration(format!(
-        "Unknown migration version: {version}"
-    )))
+fn run_migration(conn: &Connection, version: u32) -> Result<()> {
+    match version {
+        2 => conn
+            .execute_batch(schema::MIGRATION_V2)
+            .map_err(DbError::Sqlite),
+        3 => conn
+            .execute_batch(schema::MIGRATION_V3)
+            .map_err(DbError::Sqlite),
+        4 => conn
+            .execute_batch(schema::MIGRATION_V4)
+            .map_err(DbError::Sqlite),
+        5 => conn
+            .execute_batch(schema::MIGRATION_V5)
+            .map_err(DbError::Sqlite),
+        6 => conn
+            .execute_batch(schema::MIGRATION_V6)
+            .map_err(DbError::Sqlite),
+        7 => conn
+            .execute_batch(schema::MIGRATION_V7)
+            .map_err(DbError::Sqlite),
+        8 => conn
+            .execute_batch(schema::MIGRATION_V8)
+            .map_err(DbError::Sqlite),
+        9 => conn
+            .execute_batch(schema::MIGRATION_V9)
+            .map_err(DbError::Sqlite),
+        10 => conn
+            .execute_batch(schema::MIGRATION_V10)
+            .map_err(DbError::Sqlite),
+        11 => conn
+            .execute_batch(schema::MIGRATION_V11)
+            .map_err(DbError::Sqlite),
+        12 => conn
+            .execute_batch(schema::MIGRATION_V12)
+            .map_err(DbError::Sqlite),
+        13 => conn
+            .execute_batch(schema::MIGRATION_V13)
+            .map_err(DbError::Sqlite),
+        14 => conn
+            .execute_batch(schema::MIGRATION_V14)
+            .map_err(DbError::Sqlite),
+        15 => conn
+            .execute_batch(schema::MIGRATION_V15)
+            .map_err(DbError::Sqlite),
+        16 => conn
+            .execute_batch(schema::MIGRATION_V16)
+            .map_err(DbError::Sqlite),
+        17 => conn
+            .execute_batch(schema::MIGRATION_V17)
+            .map_err(DbError::Sqlite),
+        18 => conn
+            .execute_batch(schema::MIGRATION_V18)
+            .map_err(DbError::Sqlite),
+        19 => conn
+            .execute_batch(schema::MIGRATION_V19)
+            .map_err(DbError::Sqlite),
+        20 => conn
+            .execute_batch(schema::MIGRATION_V20)
+            .map_err(DbError::Sqlite),
+        21 => conn
+            .execute_batch(schema::MIGRATION_V21)
+            .map_err(DbError::Sqlite),
+        _ => Err(DbError::Migration(format!(
+            "Unknown migration version: {version}"
+        ))),
+    }
 }
 
 #[cfg(test)]
@@ -102,6 +167,34 @@ mod tests {
         run(&conn).expect("second run should be no-op");
     }
 
+    #[test]
+    fn test_upgrade_from_v1() {
+        let conn = Connection::open_in_memory().expect("open");
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .expect("pragma");
+        conn.execute_batch(schema::SCHEMA_V1).expect("v1 schema");
+        conn.pragma_update(None, "user_version", 1)
+            .expect("set version");
+
+        run(&conn).expect("migrate");
+
+        let version: u32 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .expect("version");
+        assert_eq!(version, SCHEMA_VERSION);
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='downloads'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("query");
+        assert_eq!(count, 1);
+        // Columns added by ALTER TABLE exist after upgrading.
+        conn.prepare("SELECT argon2id_params FROM pik")
+            .expect("pik params column");
+    }
+
     #[test]
     fn test_default_settings() {
         let conn = Connection::open_in_memory().expect("open");
@@ -145,6 +238,23 @@ mod tests {
             "settings",
             "kademlia_routing",
             "pending_timelocks",
+            "downloads",
+            "download_chunks",
+            "audit_log",
+            "audit_anchors",
+            "peer_standings",
+            "whisper_delivery",
+            "presence_overrides",
+            "quorum_key_schedule",
+            "bootstrap_peers",
+            "contact_keys",
+            "revoked_piks",
+            "mint_sessions",
+            "supply_audit",
+            "group_quotas",
+            "epoch_beacons",
+            "content_tags",
+            "receipt_ack_outbox",
         ];
 
         for table in &expected_tables {
@@ -1,7 +1,25 @@
 //! Database query functions organized by domain.
 
+pub mod announcements;
+pub mod audit;
+pub mod beacons;
+pub mod bootstrap_peers;
+pub mod contact_keys;
 pub mod contacts;
 pub mod content;
+pub mod downloads;
+pub mod group_policies;
+pub mod group_storage;
+pub mod key_schedule;
+pub mod mint_sessions;
+pub mod peer_standings;
+pub mod presence;
+pub mod receipt_acks;
+pub mod refunds;
+pub mod revocations;
+pub mod service_receipts;
 pub mod settings;
 pub mod spaces;
+pub mod supply_audit;
 pub mod wallet;
+pub mod whisper_delivery;
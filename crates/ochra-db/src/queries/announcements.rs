@@ -0,0 +1,154 @@
+//! Space announcements and read state (Section 8.12).
+//!
+//! Announcements are stored as signed bytes and re-verified by the caller
+//! on load; only the sequence number and whether it is a post are kept
+//! alongside for ordering and unread counts.
+
+use rusqlite::Connection;
+
+use crate::Result;
+
+/// A stored announcement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnnouncementRow {
+    pub seq: u64,
+    /// Encoded signed announcement.
+    pub signed_announcement: Vec<u8>,
+    /// Whether it is a post, as opposed to a pin change.
+    pub is_post: bool,
+    pub received_at: u64,
+    pub read_at: Option<u64>,
+}
+
+/// Store an accepted announcement. Returns `false` if its sequence number
+/// was already stored.
+pub fn insert(conn: &Connection, group_id: &[u8; 32], row: &AnnouncementRow) -> Result<bool> {
+    let changed = conn.execute(
+        "INSERT OR IGNORE INTO announcements
+         (group_id, seq, signed_announcement, is_post, received_at, read_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            group_id.as_slice(),
+            row.seq as i64,
+            row.signed_announcement,
+            row.is_post,
+            row.received_at as i64,
+            row.read_at.map(|t| t as i64),
+        ],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Every stored announcement of a Space, oldest first.
+pub fn list(conn: &Connection, group_id: &[u8; 32]) -> Result<Vec<AnnouncementRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT seq, signed_announcement, is_post, received_at, read_at FROM announcements
+         WHERE group_id = ?1 ORDER BY seq",
+    )?;
+    let rows = stmt
+        .query_map([group_id.as_slice()], |row| {
+            Ok(AnnouncementRow {
+                seq: row.get::<_, i64>(0)? as u64,
+                signed_announcement: row.get(1)?,
+                is_post: row.get(2)?,
+                received_at: row.get::<_, i64>(3)? as u64,
+                read_at: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Highest stored sequence number of a Space, or 0 if none.
+pub fn latest_seq(conn: &Connection, group_id: &[u8; 32]) -> Result<u64> {
+    let seq: Option<i64> = conn.query_row(
+        "SELECT MAX(seq) FROM announcements WHERE group_id = ?1",
+        [group_id.as_slice()],
+        |row| row.get(0),
+    )?;
+    Ok(seq.unwrap_or(0) as u64)
+}
+
+/// Mark unread posts read: the one numbered `seq`, or all if `None`.
+/// Returns how many were marked.
+pub fn mark_read(
+    conn: &Connection,
+    group_id: &[u8; 32],
+    seq: Option<u64>,
+    now: u64,
+) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE announcements SET read_at = ?3
+         WHERE group_id = ?1 AND read_at IS NULL AND (?2 IS NULL OR seq = ?2)",
+        rusqlite::params![group_id.as_slice(), seq.map(|s| s as i64), now as i64],
+    )?)
+}
+
+/// Number of unread posts in a Space.
+pub fn unread_count(conn: &Connection, group_id: &[u8; 32]) -> Result<u32> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM announcements
+         WHERE group_id = ?1 AND is_post = 1 AND read_at IS NULL",
+        [group_id.as_slice()],
+        |row| row.get(0),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUP: [u8; 32] = [1; 32];
+
+    fn test_db() -> Connection {
+        let conn = crate::open_memory().expect("open test db");
+        crate::queries::spaces::insert(&conn, &GROUP, "Space", "forum", "member", &[2; 32], 1)
+            .expect("space");
+        conn
+    }
+
+    fn row(seq: u64, is_post: bool) -> AnnouncementRow {
+        AnnouncementRow {
+            seq,
+            signed_announcement: vec![seq as u8; 4],
+            is_post,
+            received_at: 100 + seq,
+            read_at: None,
+        }
+    }
+
+    #[test]
+    fn test_insert_list_in_order() {
+        let conn = test_db();
+        assert_eq!(latest_seq(&conn, &GROUP).expect("latest"), 0);
+        for seq in [3, 1, 2] {
+            assert!(insert(&conn, &GROUP, &row(seq, true)).expect("insert"));
+        }
+        assert!(!insert(&conn, &GROUP, &row(2, true)).expect("duplicate"));
+
+        let seqs: Vec<u64> = list(&conn, &GROUP)
+            .expect("list")
+            .iter()
+            .map(|r| r.seq)
+            .collect();
+        assert_eq!(seqs, vec![1, 2, 3]);
+        assert_eq!(latest_seq(&conn, &GROUP).expect("latest"), 3);
+    }
+
+    #[test]
+    fn test_unread_counts_posts_only() {
+        let conn = test_db();
+        insert(&conn, &GROUP, &row(1, true)).expect("insert");
+        insert(&conn, &GROUP, &row(2, true)).expect("insert");
+        insert(&conn, &GROUP, &row(3, false)).expect("insert");
+        assert_eq!(unread_count(&conn, &GROUP).expect("count"), 2);
+
+        assert_eq!(mark_read(&conn, &GROUP, Some(1), 500).expect("mark"), 1);
+        assert_eq!(unread_count(&conn, &GROUP).expect("count"), 1);
+        assert_eq!(mark_read(&conn, &GROUP, None, 600).expect("mark"), 2);
+        assert_eq!(unread_count(&conn, &GROUP).expect("count"), 0);
+        let rows = list(&conn, &GROUP).expect("list");
+        assert_eq!(rows[0].read_at, Some(500));
+        assert_eq!(rows[1].read_at, Some(600));
+    }
+}
@@ -0,0 +1,284 @@
+//! Append-only audit log of security-relevant actions.
+//!
+//! Each entry commits to its predecessor:
+//!
+//! `entry_hash = BLAKE3::hash(LP("audit-entry") || LP(prev_hash) || LP(seq) || LP(timestamp) || LP(action) || LP(details))`
+//!
+//! where `LP` is the length-prefixed multi-field encoding and the first
+//! entry's `prev_hash` is all zeros. Rewriting or dropping any entry breaks
+//! every later hash; the daemon periodically anchors the chain head in the
+//! DHT so truncating the tail is detectable too.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::{DbError, Result};
+
+/// `prev_hash` of the first entry.
+pub const GENESIS_HASH: [u8; 32] = [0u8; 32];
+
+/// PIK unlocked with a password or biometric.
+pub const ACTION_UNLOCK: &str = "unlock";
+/// Social recovery initiated.
+pub const ACTION_RECOVERY_INITIATED: &str = "recovery_initiated";
+/// A guardian was nominated.
+pub const ACTION_GUARDIAN_NOMINATED: &str = "guardian_nominated";
+/// A guardian was replaced.
+pub const ACTION_GUARDIAN_REPLACED: &str = "guardian_replaced";
+/// A spend at or above the large-spend threshold.
+pub const ACTION_LARGE_SPEND: &str = "large_spend";
+
+/// A single audit log entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub action: String,
+    /// JSON-encoded action details.
+    pub details: String,
+    pub prev_hash: [u8; 32],
+    pub entry_hash: [u8; 32],
+}
+
+/// A chain head published to the DHT.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditAnchor {
+    pub seq: u64,
+    pub head_hash: [u8; 32],
+    pub anchored_at: u64,
+}
+
+/// Compute the hash of an entry from its contents.
+pub fn entry_hash(
+    prev_hash: &[u8; 32],
+    seq: u64,
+    timestamp: u64,
+    action: &str,
+    details: &str,
+) -> [u8; 32] {
+    ochra_crypto::blake3::hash(&ochra_crypto::blake3::encode_multi_field(&[
+        b"audit-entry",
+        prev_hash,
+        &seq.to_be_bytes(),
+        &timestamp.to_be_bytes(),
+        action.as_bytes(),
+        details.as_bytes(),
+    ]))
+}
+
+/// Append an entry to the chain.
+pub fn append(conn: &Connection, action: &str, details: &str, now: u64) -> Result<AuditEntry> {
+    let tx = conn.unchecked_transaction()?;
+    let (seq, prev_hash) = match head(&tx)? {
+        Some(h) => (h.seq + 1, h.entry_hash),
+        None => (1, GENESIS_HASH),
+    };
+    let hash = entry_hash(&prev_hash, seq, now, action, details);
+    tx.execute(
+        "INSERT INTO audit_log (seq, timestamp, action, details, prev_hash, entry_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            seq as i64,
+            now as i64,
+            action,
+            details,
+            prev_hash.as_slice(),
+            hash.as_slice(),
+        ],
+    )?;
+    tx.commit()?;
+    Ok(AuditEntry {
+        seq,
+        timestamp: now,
+        action: action.to_string(),
+        details: details.to_string(),
+        prev_hash,
+        entry_hash: hash,
+    })
+}
+
+/// The most recent entry.
+pub fn head(conn: &Connection) -> Result<Option<AuditEntry>> {
+    let row = conn
+        .query_row(
+            "SELECT seq, timestamp, action, details, prev_hash, entry_hash
+             FROM audit_log ORDER BY seq DESC LIMIT 1",
+            [],
+            map_row,
+        )
+        .optional()?;
+    Ok(row)
+}
+
+/// Entries with `seq > after_seq`, oldest first.
+pub fn list_after(conn: &Connection, after_seq: u64) -> Result<Vec<AuditEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT seq, timestamp, action, details, prev_hash, entry_hash
+         FROM audit_log WHERE seq > ?1 ORDER BY seq",
+    )?;
+    let rows = stmt
+        .query_map([after_seq as i64], map_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Check that `entries` form an unbroken chain starting after `prev_hash`.
+///
+/// Returns the hash of the last entry, or `prev_hash` if `entries` is empty.
+///
+/// # Errors
+///
+/// - [`DbError::Constraint`] naming the first entry that is out of sequence,
+///   does not link to its predecessor, or whose hash does not match its
+///   contents
+pub fn verify_chain(entries: &[AuditEntry], prev_hash: [u8; 32]) -> Result<[u8; 32]> {
+    let mut expected_prev = prev_hash;
+    let first_seq = entries.first().map(|e| e.seq).unwrap_or_default();
+    for (expected_seq, entry) in (first_seq..).zip(entries) {
+        if entry.seq != expected_seq {
+            return Err(DbError::Constraint(format!(
+                "audit entry {} out of sequence, expected {expected_seq}",
+                entry.seq
+            )));
+        }
+        if entry.prev_hash != expected_prev {
+            return Err(DbError::Constraint(format!(
+                "audit entry {} does not link to its predecessor",
+                entry.seq
+            )));
+        }
+        let computed = entry_hash(
+            &entry.prev_hash,
+            entry.seq,
+            entry.timestamp,
+            &entry.action,
+            &entry.details,
+        );
+        if computed != entry.entry_hash {
+            return Err(DbError::Constraint(format!(
+                "audit entry {} hash mismatch",
+                entry.seq
+            )));
+        }
+        expected_prev = entry.entry_hash;
+    }
+    Ok(expected_prev)
+}
+
+/// Record that the chain head at `seq` was published.
+pub fn record_anchor(conn: &Connection, anchor: &AuditAnchor) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO audit_anchors (seq, head_hash, anchored_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![
+            anchor.seq as i64,
+            anchor.head_hash.as_slice(),
+            anchor.anchored_at as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// The most recently published chain head.
+pub fn last_anchor(conn: &Connection) -> Result<Option<AuditAnchor>> {
+    let row = conn
+        .query_row(
+            "SELECT seq, head_hash, anchored_at FROM audit_anchors ORDER BY seq DESC LIMIT 1",
+            [],
+            |row| {
+                Ok(AuditAnchor {
+                    seq: row.get::<_, i64>(0)? as u64,
+                    head_hash: row.get(1)?,
+                    anchored_at: row.get::<_, i64>(2)? as u64,
+                })
+            },
+        )
+        .optional()?;
+    Ok(row)
+}
+
+fn map_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AuditEntry> {
+    Ok(AuditEntry {
+        seq: row.get::<_, i64>(0)? as u64,
+        timestamp: row.get::<_, i64>(1)? as u64,
+        action: row.get(2)?,
+        details: row.get(3)?,
+        prev_hash: row.get(4)?,
+        entry_hash: row.get(5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    #[test]
+    fn test_append_links_entries() {
+        let conn = test_db();
+        let first = append(&conn, ACTION_UNLOCK, "{}", 100).expect("append");
+        let second = append(&conn, ACTION_LARGE_SPEND, r#"{"amount":5}"#, 200).expect("append");
+
+        assert_eq!(first.seq, 1);
+        assert_eq!(first.prev_hash, GENESIS_HASH);
+        assert_eq!(second.seq, 2);
+        assert_eq!(second.prev_hash, first.entry_hash);
+        assert_eq!(head(&conn).expect("head"), Some(second));
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let conn = test_db();
+        for i in 0..4 {
+            append(&conn, ACTION_UNLOCK, "{}", i).expect("append");
+        }
+        let entries = list_after(&conn, 0).expect("list");
+        let head_hash = verify_chain(&entries, GENESIS_HASH).expect("verify");
+        assert_eq!(head_hash, entries[3].entry_hash);
+
+        // A suffix verifies from its predecessor's hash.
+        let suffix = list_after(&conn, 2).expect("list");
+        assert_eq!(
+            verify_chain(&suffix, entries[1].entry_hash).expect("verify"),
+            head_hash
+        );
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let conn = test_db();
+        for i in 0..3 {
+            append(&conn, ACTION_GUARDIAN_NOMINATED, "{}", i).expect("append");
+        }
+        conn.execute(
+            "UPDATE audit_log SET action = ?1 WHERE seq = 2",
+            [ACTION_UNLOCK],
+        )
+        .expect("tamper");
+        let entries = list_after(&conn, 0).expect("list");
+        assert!(matches!(
+            verify_chain(&entries, GENESIS_HASH),
+            Err(DbError::Constraint(_))
+        ));
+
+        // Dropping an entry breaks the sequence.
+        let mut gapped = entries.clone();
+        gapped.remove(1);
+        assert!(verify_chain(&gapped, GENESIS_HASH).is_err());
+    }
+
+    #[test]
+    fn test_anchor_roundtrip() {
+        let conn = test_db();
+        assert!(last_anchor(&conn).expect("anchor").is_none());
+        let entry = append(&conn, ACTION_RECOVERY_INITIATED, "{}", 10).expect("append");
+        let anchor = AuditAnchor {
+            seq: entry.seq,
+            head_hash: entry.entry_hash,
+            anchored_at: 20,
+        };
+        record_anchor(&conn, &anchor).expect("record");
+        assert_eq!(last_anchor(&conn).expect("anchor"), Some(anchor));
+    }
+}
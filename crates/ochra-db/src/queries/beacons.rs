@@ -0,0 +1,112 @@
+//! Verified epoch beacons (Section 12.10).
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::Result;
+
+/// A stored beacon.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeaconRow {
+    pub epoch: u32,
+    pub prev_beacon: [u8; 32],
+    pub quorum_sig: Vec<u8>,
+    pub received_at: u64,
+}
+
+/// Store a verified beacon. Returns `false` if one is already stored for
+/// the epoch, which is kept.
+pub fn insert(conn: &Connection, row: &BeaconRow) -> Result<bool> {
+    let changed = conn.execute(
+        "INSERT OR IGNORE INTO epoch_beacons (epoch, prev_beacon, quorum_sig, received_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            row.epoch,
+            row.prev_beacon.as_slice(),
+            row.quorum_sig,
+            row.received_at as i64
+        ],
+    )?;
+    Ok(changed > 0)
+}
+
+fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BeaconRow> {
+    Ok(BeaconRow {
+        epoch: row.get(0)?,
+        prev_beacon: row.get(1)?,
+        quorum_sig: row.get(2)?,
+        received_at: row.get::<_, i64>(3)? as u64,
+    })
+}
+
+/// Look up the beacon for an epoch.
+pub fn get(conn: &Connection, epoch: u32) -> Result<Option<BeaconRow>> {
+    Ok(conn
+        .query_row(
+            "SELECT epoch, prev_beacon, quorum_sig, received_at FROM epoch_beacons
+             WHERE epoch = ?1",
+            [epoch],
+            from_row,
+        )
+        .optional()?)
+}
+
+/// The `limit` most recent beacons, oldest first.
+pub fn recent(conn: &Connection, limit: u32) -> Result<Vec<BeaconRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT epoch, prev_beacon, quorum_sig, received_at FROM
+         (SELECT * FROM epoch_beacons ORDER BY epoch DESC LIMIT ?1)
+         ORDER BY epoch",
+    )?;
+    let rows = stmt
+        .query_map([limit], from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Delete beacons older than `epoch`. Returns the number removed.
+pub fn prune_before(conn: &Connection, epoch: u32) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM epoch_beacons WHERE epoch < ?1", [epoch])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    fn row(epoch: u32, sig: u8) -> BeaconRow {
+        BeaconRow {
+            epoch,
+            prev_beacon: [epoch as u8; 32],
+            quorum_sig: vec![sig; 64],
+            received_at: 1_000 + u64::from(epoch),
+        }
+    }
+
+    #[test]
+    fn test_first_beacon_wins() {
+        let conn = test_db();
+        assert!(insert(&conn, &row(5, 1)).expect("insert"));
+        assert!(!insert(&conn, &row(5, 2)).expect("duplicate"));
+        assert_eq!(get(&conn, 5).expect("get"), Some(row(5, 1)));
+        assert!(get(&conn, 6).expect("get").is_none());
+    }
+
+    #[test]
+    fn test_recent_and_prune() {
+        let conn = test_db();
+        for epoch in [4, 2, 3, 1] {
+            insert(&conn, &row(epoch, 0)).expect("insert");
+        }
+        let epochs: Vec<u32> = recent(&conn, 2)
+            .expect("recent")
+            .iter()
+            .map(|r| r.epoch)
+            .collect();
+        assert_eq!(epochs, vec![3, 4]);
+        assert_eq!(prune_before(&conn, 3).expect("prune"), 2);
+        assert_eq!(recent(&conn, 10).expect("recent").len(), 2);
+    }
+}
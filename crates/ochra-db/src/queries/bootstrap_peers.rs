@@ -0,0 +1,120 @@
+//! Last-known-good bootstrap peers.
+//!
+//! Peers are recorded after a successful session and offered first on the
+//! next start, ahead of hardcoded seeds, signed lists, and DNS. The last
+//! accepted signed bootstrap list version lives in `settings` under
+//! `bootstrap_list_version`.
+
+use rusqlite::Connection;
+
+use crate::queries::settings;
+use crate::Result;
+
+const KEY_LIST_VERSION: &str = "bootstrap_list_version";
+
+/// A peer that was reachable in an earlier session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BootstrapPeer {
+    pub pik_public_key: [u8; 32],
+    /// "ip:port".
+    pub addr: String,
+    pub last_success: u64,
+}
+
+/// Record a successful connection to a peer.
+pub fn record_success(
+    conn: &Connection,
+    pik_public_key: &[u8; 32],
+    addr: &str,
+    now: u64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO bootstrap_peers (pik_public_key, addr, last_success)
+         VALUES (?1, ?2, ?3)",
+        rusqlite::params![pik_public_key.as_slice(), addr, now as i64],
+    )?;
+    Ok(())
+}
+
+/// Up to `limit` peers, most recently reachable first.
+pub fn list_recent(conn: &Connection, limit: usize) -> Result<Vec<BootstrapPeer>> {
+    let mut stmt = conn.prepare(
+        "SELECT pik_public_key, addr, last_success FROM bootstrap_peers
+         ORDER BY last_success DESC LIMIT ?1",
+    )?;
+    let rows = stmt
+        .query_map([limit as i64], |row| {
+            Ok(BootstrapPeer {
+                pik_public_key: row.get(0)?,
+                addr: row.get(1)?,
+                last_success: row.get::<_, i64>(2)? as u64,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Keep only the `keep` most recently reachable peers. Returns how many
+/// were removed.
+pub fn prune(conn: &Connection, keep: usize) -> Result<usize> {
+    let n = conn.execute(
+        "DELETE FROM bootstrap_peers WHERE pik_public_key NOT IN
+         (SELECT pik_public_key FROM bootstrap_peers ORDER BY last_success DESC LIMIT ?1)",
+        [keep as i64],
+    )?;
+    Ok(n)
+}
+
+/// Version of the last signed bootstrap list accepted, or 0.
+pub fn list_version(conn: &Connection) -> Result<u32> {
+    let version = settings::get_u64(conn, KEY_LIST_VERSION, 0)?;
+    Ok(u32::try_from(version).unwrap_or(u32::MAX))
+}
+
+/// Record the version of an accepted signed bootstrap list.
+pub fn set_list_version(conn: &Connection, version: u32) -> Result<()> {
+    settings::set(conn, KEY_LIST_VERSION, &version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    #[test]
+    fn test_recent_first_and_refresh() {
+        let conn = test_db();
+        record_success(&conn, &[1u8; 32], "192.0.2.1:4433", 100).expect("record");
+        record_success(&conn, &[2u8; 32], "192.0.2.2:4433", 200).expect("record");
+        record_success(&conn, &[1u8; 32], "192.0.2.9:4433", 300).expect("record");
+
+        let peers = list_recent(&conn, 10).expect("list");
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].pik_public_key, [1u8; 32]);
+        assert_eq!(peers[0].addr, "192.0.2.9:4433");
+        assert_eq!(list_recent(&conn, 1).expect("list").len(), 1);
+    }
+
+    #[test]
+    fn test_prune_keeps_most_recent() {
+        let conn = test_db();
+        for i in 0..5u8 {
+            record_success(&conn, &[i; 32], "192.0.2.1:4433", u64::from(i)).expect("record");
+        }
+        assert_eq!(prune(&conn, 2).expect("prune"), 3);
+        let peers = list_recent(&conn, 10).expect("list");
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[1].pik_public_key, [3u8; 32]);
+    }
+
+    #[test]
+    fn test_list_version() {
+        let conn = test_db();
+        assert_eq!(list_version(&conn).expect("get"), 0);
+        set_list_version(&conn, 7).expect("set");
+        assert_eq!(list_version(&conn).expect("get"), 7);
+    }
+}
@@ -0,0 +1,176 @@
+//! Pinned contact keys and safety-number verification state.
+//!
+//! The first keys seen for a contact are pinned. Later observations are
+//! compared against the pin; a change replaces it, records when, and
+//! drops any earlier verification so the user must compare safety
+//! numbers again.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::{DbError, Result};
+
+/// Pinned keys for one contact.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContactKeys {
+    pub pik_public_key: [u8; 32],
+    pub x25519_public_key: [u8; 32],
+    pub first_seen: u64,
+    pub verified_at: Option<u64>,
+    pub key_changed_at: Option<u64>,
+}
+
+/// Result of comparing observed keys against the pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyObservation {
+    /// No keys were pinned; these are now.
+    New,
+    /// Matches the pinned keys.
+    Unchanged,
+    /// Differs from the pinned keys, which have been replaced.
+    Changed {
+        /// Whether the replaced keys had been verified.
+        was_verified: bool,
+    },
+}
+
+/// Compare observed keys with the pin for `contact_pik`, pinning or
+/// replacing as needed.
+pub fn observe(
+    conn: &Connection,
+    contact_pik: &[u8; 32],
+    pik_public_key: &[u8; 32],
+    x25519_public_key: &[u8; 32],
+    now: u64,
+) -> Result<KeyObservation> {
+    let Some(current) = get(conn, contact_pik)? else {
+        conn.execute(
+            "INSERT INTO contact_keys (contact_pik, pik_public_key, x25519_public_key, first_seen)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                contact_pik.as_slice(),
+                pik_public_key.as_slice(),
+                x25519_public_key.as_slice(),
+                now as i64,
+            ],
+        )?;
+        return Ok(KeyObservation::New);
+    };
+    if current.pik_public_key == *pik_public_key && current.x25519_public_key == *x25519_public_key
+    {
+        return Ok(KeyObservation::Unchanged);
+    }
+    conn.execute(
+        "UPDATE contact_keys SET pik_public_key = ?2, x25519_public_key = ?3,
+         verified_at = NULL, key_changed_at = ?4 WHERE contact_pik = ?1",
+        rusqlite::params![
+            contact_pik.as_slice(),
+            pik_public_key.as_slice(),
+            x25519_public_key.as_slice(),
+            now as i64,
+        ],
+    )?;
+    Ok(KeyObservation::Changed {
+        was_verified: current.verified_at.is_some(),
+    })
+}
+
+/// Pinned keys for a contact, if any.
+pub fn get(conn: &Connection, contact_pik: &[u8; 32]) -> Result<Option<ContactKeys>> {
+    let row = conn
+        .query_row(
+            "SELECT pik_public_key, x25519_public_key, first_seen, verified_at, key_changed_at
+             FROM contact_keys WHERE contact_pik = ?1",
+            [contact_pik.as_slice()],
+            |row| {
+                Ok(ContactKeys {
+                    pik_public_key: row.get(0)?,
+                    x25519_public_key: row.get(1)?,
+                    first_seen: row.get::<_, i64>(2)? as u64,
+                    verified_at: row.get::<_, Option<i64>>(3)?.map(|t| t as u64),
+                    key_changed_at: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+                })
+            },
+        )
+        .optional()?;
+    Ok(row)
+}
+
+/// Mark a contact's pinned keys as verified.
+pub fn mark_verified(conn: &Connection, contact_pik: &[u8; 32], now: u64) -> Result<()> {
+    let n = conn.execute(
+        "UPDATE contact_keys SET verified_at = ?2 WHERE contact_pik = ?1",
+        rusqlite::params![contact_pik.as_slice(), now as i64],
+    )?;
+    if n == 0 {
+        return Err(DbError::NotFound("contact keys".into()));
+    }
+    Ok(())
+}
+
+/// Clear a contact's verification.
+pub fn clear_verified(conn: &Connection, contact_pik: &[u8; 32]) -> Result<()> {
+    conn.execute(
+        "UPDATE contact_keys SET verified_at = NULL WHERE contact_pik = ?1",
+        [contact_pik.as_slice()],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queries::contacts;
+
+    fn test_db() -> Connection {
+        let conn = crate::open_memory().expect("open test db");
+        contacts::insert(&conn, &[1u8; 32], "Alice", &[0u8; 32], 100).expect("insert contact");
+        conn
+    }
+
+    #[test]
+    fn test_first_observation_pins() {
+        let conn = test_db();
+        let obs = observe(&conn, &[1u8; 32], &[2u8; 32], &[3u8; 32], 100).expect("observe");
+        assert_eq!(obs, KeyObservation::New);
+        let obs = observe(&conn, &[1u8; 32], &[2u8; 32], &[3u8; 32], 200).expect("observe");
+        assert_eq!(obs, KeyObservation::Unchanged);
+
+        let keys = get(&conn, &[1u8; 32]).expect("get").expect("pinned");
+        assert_eq!(keys.first_seen, 100);
+        assert_eq!(keys.verified_at, None);
+    }
+
+    #[test]
+    fn test_key_change_drops_verification() {
+        let conn = test_db();
+        observe(&conn, &[1u8; 32], &[2u8; 32], &[3u8; 32], 100).expect("observe");
+        mark_verified(&conn, &[1u8; 32], 150).expect("verify");
+
+        let obs = observe(&conn, &[1u8; 32], &[2u8; 32], &[4u8; 32], 200).expect("observe");
+        assert_eq!(obs, KeyObservation::Changed { was_verified: true });
+        let keys = get(&conn, &[1u8; 32]).expect("get").expect("pinned");
+        assert_eq!(keys.x25519_public_key, [4u8; 32]);
+        assert_eq!(keys.verified_at, None);
+        assert_eq!(keys.key_changed_at, Some(200));
+
+        let obs = observe(&conn, &[1u8; 32], &[5u8; 32], &[4u8; 32], 300).expect("observe");
+        assert_eq!(
+            obs,
+            KeyObservation::Changed {
+                was_verified: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_requires_pin_and_cascades() {
+        let conn = test_db();
+        assert!(matches!(
+            mark_verified(&conn, &[1u8; 32], 100),
+            Err(DbError::NotFound(_))
+        ));
+        observe(&conn, &[1u8; 32], &[2u8; 32], &[3u8; 32], 100).expect("observe");
+        contacts::remove(&conn, &[1u8; 32]).expect("remove");
+        assert!(get(&conn, &[1u8; 32]).expect("get").is_none());
+    }
+}
@@ -1,6 +1,7 @@
 //! Content catalog query functions (Section 27.3).
 
-use rusqlite::Connection;
+use ochra_types::content::{CatalogFacet, ContentTag, TagNamespace};
+use rusqlite::{Connection, OptionalExtension};
 
 use crate::Result;
 
@@ -51,21 +52,142 @@ pub fn list_by_space(conn: &Connection, group_id: &[u8; 32]) -> Result<Vec<Conte
     )?;
 
     let rows = stmt
-        .query_map([group_id.as_slice()], |row| {
-            Ok(ContentRow {
-                content_hash: row.get::<_, Vec<u8>>(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                pricing_json: row.get(3)?,
-                creator_pik: row.get::<_, Vec<u8>>(4)?,
-                total_size_bytes: row.get::<_, i64>(5)? as u64,
-                chunk_count: row.get::<_, i64>(6)? as u32,
-                published_at: row.get::<_, i64>(7)? as u64,
-                is_tombstoned: row.get(8)?,
+        .query_map([group_id.as_slice()], content_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(rows)
+}
+
+fn content_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ContentRow> {
+    Ok(ContentRow {
+        content_hash: row.get::<_, Vec<u8>>(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        pricing_json: row.get(3)?,
+        creator_pik: row.get::<_, Vec<u8>>(4)?,
+        total_size_bytes: row.get::<_, i64>(5)? as u64,
+        chunk_count: row.get::<_, i64>(6)? as u32,
+        published_at: row.get::<_, i64>(7)? as u64,
+        is_tombstoned: row.get(8)?,
+    })
+}
+
+/// Replace the discovery tags of a content item (Section 16.8).
+///
+/// `tags` must already be normalized; the display form is also kept in
+/// `content_catalog.tags` as a JSON array.
+pub fn set_tags(
+    conn: &Connection,
+    content_hash: &[u8; 32],
+    group_id: &[u8; 32],
+    tags: &[ContentTag],
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM content_tags WHERE content_hash = ?1",
+        [content_hash.as_slice()],
+    )?;
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO content_tags (content_hash, group_id, namespace, value)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                content_hash.as_slice(),
+                group_id.as_slice(),
+                tag.namespace.as_str(),
+                tag.value,
+            ],
+        )?;
+    }
+    let display: Vec<String> = tags.iter().map(ToString::to_string).collect();
+    conn.execute(
+        "UPDATE content_catalog SET tags = ?1 WHERE content_hash = ?2",
+        rusqlite::params![
+            serde_json::to_string(&display).unwrap_or_default(),
+            content_hash.as_slice()
+        ],
+    )?;
+    Ok(())
+}
+
+/// The discovery tags of a content item.
+pub fn tags(conn: &Connection, content_hash: &[u8; 32]) -> Result<Vec<ContentTag>> {
+    let mut stmt = conn.prepare(
+        "SELECT namespace, value FROM content_tags WHERE content_hash = ?1
+         ORDER BY namespace, value",
+    )?;
+    let rows = stmt
+        .query_map([content_hash.as_slice()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(namespace, value)| {
+            Some(ContentTag {
+                namespace: TagNamespace::parse(&namespace)?,
+                value,
             })
+        })
+        .collect())
+}
+
+/// Tag counts over a Space's live catalog, grouped by namespace in
+/// [`TagNamespace::ALL`] order, most used first.
+pub fn facets(conn: &Connection, group_id: &[u8; 32]) -> Result<Vec<CatalogFacet>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.namespace, t.value, COUNT(*)
+         FROM content_tags t JOIN content_catalog c ON c.content_hash = t.content_hash
+         WHERE t.group_id = ?1 AND c.is_tombstoned = 0
+         GROUP BY t.namespace, t.value",
+    )?;
+    let rows = stmt
+        .query_map([group_id.as_slice()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)? as u32,
+            ))
         })?
         .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut facets: Vec<CatalogFacet> = rows
+        .into_iter()
+        .filter_map(|(namespace, value, count)| {
+            Some(CatalogFacet {
+                namespace: TagNamespace::parse(&namespace)?,
+                value,
+                count,
+            })
+        })
+        .collect();
+    facets.sort_by(|a, b| {
+        a.namespace
+            .cmp(&b.namespace)
+            .then(b.count.cmp(&a.count))
+            .then_with(|| a.value.cmp(&b.value))
+    });
+    Ok(facets)
+}
 
+/// List the live content of a Space carrying a tag.
+pub fn list_by_tag(
+    conn: &Connection,
+    group_id: &[u8; 32],
+    tag: &ContentTag,
+) -> Result<Vec<ContentRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT c.content_hash, c.title, c.description, c.pricing, c.creator_pik,
+                c.total_size_bytes, c.chunk_count, c.published_at, c.is_tombstoned
+         FROM content_catalog c JOIN content_tags t ON t.content_hash = c.content_hash
+         WHERE c.group_id = ?1 AND c.is_tombstoned = 0
+           AND t.namespace = ?2 AND t.value = ?3
+         ORDER BY c.published_at DESC",
+    )?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![group_id.as_slice(), tag.namespace.as_str(), tag.value],
+            content_row,
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
     Ok(rows)
 }
 
@@ -78,6 +200,56 @@ pub fn tombstone(conn: &Connection, content_hash: &[u8; 32], tombstoned_at: u64)
     Ok(())
 }
 
+/// Whether a content item is in the catalog, tombstoned or not.
+pub fn exists(conn: &Connection, content_hash: &[u8; 32]) -> Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM content_catalog WHERE content_hash = ?1",
+            [content_hash.as_slice()],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+/// The key commitment of a content item, if it is in the catalog.
+pub fn key_commitment(conn: &Connection, content_hash: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+    Ok(conn
+        .query_row(
+            "SELECT key_commitment FROM content_catalog WHERE content_hash = ?1",
+            [content_hash.as_slice()],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Look up the Space a content item was published in and that Space's
+/// owner PIK.
+pub fn space_owner(conn: &Connection, content_hash: &[u8; 32]) -> Result<Option<ContentOwner>> {
+    let owner = conn
+        .query_row(
+            "SELECT c.group_id, s.owner_pik
+             FROM content_catalog c JOIN spaces s ON s.group_id = c.group_id
+             WHERE c.content_hash = ?1",
+            [content_hash.as_slice()],
+            |row| {
+                Ok(ContentOwner {
+                    group_id: row.get::<_, Vec<u8>>(0)?,
+                    owner_pik: row.get::<_, Vec<u8>>(1)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(owner)
+}
+
+/// The Space holding a content item and its owner.
+#[derive(Debug)]
+pub struct ContentOwner {
+    pub group_id: Vec<u8>,
+    pub owner_pik: Vec<u8>,
+}
+
 /// A raw content row.
 #[derive(Debug)]
 pub struct ContentRow {
@@ -154,9 +326,67 @@ mod tests {
         )
         .expect("insert");
 
+        let owner = space_owner(&conn, &[10u8; 32])
+            .expect("query")
+            .expect("owner");
+        assert_eq!(owner.group_id, vec![1u8; 32]);
+        assert_eq!(owner.owner_pik, vec![2u8; 32]);
+        assert!(space_owner(&conn, &[11u8; 32]).expect("query").is_none());
+
         tombstone(&conn, &[10u8; 32], 3000).expect("tombstone");
 
         let items = list_by_space(&conn, &[1u8; 32]).expect("list");
         assert_eq!(items.len(), 0, "Tombstoned items should not appear");
+        assert!(exists(&conn, &[10u8; 32]).expect("exists"));
+        assert!(!exists(&conn, &[11u8; 32]).expect("exists"));
+        assert_eq!(
+            key_commitment(&conn, &[10u8; 32]).expect("query"),
+            Some(vec![4u8; 32])
+        );
+    }
+
+    #[test]
+    fn test_tag_facets_skip_tombstoned() {
+        let conn = test_db();
+        let jazz = ContentTag::parse("genre:jazz").expect("tag");
+        let folk = ContentTag::parse("genre:folk").expect("tag");
+        let english = ContentTag::parse("language:en-US").expect("tag");
+        for (hash, tags) in [
+            ([10u8; 32], vec![jazz.clone(), english.clone()]),
+            ([11u8; 32], vec![jazz.clone()]),
+            ([12u8; 32], vec![folk.clone()]),
+        ] {
+            insert(
+                &conn, &hash, &[1u8; 32], "Item", None, "[]", &[3u8; 32], &[4u8; 32], 1, 1, 2000,
+            )
+            .expect("insert");
+            set_tags(&conn, &hash, &[1u8; 32], &tags).expect("tags");
+        }
+
+        let facets = facets(&conn, &[1u8; 32]).expect("facets");
+        let summary: Vec<(String, u32)> = facets
+            .iter()
+            .map(|f| (format!("{}:{}", f.namespace.as_str(), f.value), f.count))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("genre:jazz".to_string(), 2),
+                ("genre:folk".to_string(), 1),
+                ("language:en".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            tags(&conn, &[10u8; 32]).expect("tags"),
+            vec![jazz.clone(), english]
+        );
+
+        tombstone(&conn, &[11u8; 32], 3000).expect("tombstone");
+        assert_eq!(
+            list_by_tag(&conn, &[1u8; 32], &jazz).expect("list").len(),
+            1
+        );
+        let facets = super::facets(&conn, &[1u8; 32]).expect("facets");
+        assert_eq!(facets[0].count, 1);
     }
 }
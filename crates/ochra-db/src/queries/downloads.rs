@@ -0,0 +1,236 @@
+//! Resumable download query functions.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::{DbError, Result};
+
+/// Download is being fetched.
+pub const STATUS_ACTIVE: &str = "active";
+/// Download was paused by the user.
+pub const STATUS_PAUSED: &str = "paused";
+/// All chunks verified and assembled.
+pub const STATUS_COMPLETE: &str = "complete";
+
+/// Start tracking a download, or reactivate an existing one.
+///
+/// Per-chunk progress of an existing download is kept.
+pub fn start(
+    conn: &Connection,
+    content_hash: &[u8; 32],
+    destination: &str,
+    leaf_hashes: &[[u8; 32]],
+    total_size_bytes: u64,
+    now: u64,
+) -> Result<()> {
+    let leaves: Vec<u8> = leaf_hashes.concat();
+    conn.execute(
+        "INSERT INTO downloads
+         (content_hash, destination, leaf_hashes, total_size_bytes, status, started_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, 'active', ?5, ?5)
+         ON CONFLICT(content_hash) DO UPDATE SET
+            destination = excluded.destination,
+            status = 'active',
+            updated_at = excluded.updated_at",
+        rusqlite::params![
+            content_hash.as_slice(),
+            destination,
+            leaves,
+            total_size_bytes as i64,
+            now as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Set a download's status.
+pub fn set_status(
+    conn: &Connection,
+    content_hash: &[u8; 32],
+    status: &str,
+    now: u64,
+) -> Result<()> {
+    let updated = conn.execute(
+        "UPDATE downloads SET status = ?1, updated_at = ?2 WHERE content_hash = ?3",
+        rusqlite::params![status, now as i64, content_hash.as_slice()],
+    )?;
+    if updated == 0 {
+        return Err(DbError::NotFound("download".to_string()));
+    }
+    Ok(())
+}
+
+/// Record a verified chunk.
+pub fn record_chunk(
+    conn: &Connection,
+    content_hash: &[u8; 32],
+    chunk_index: u32,
+    provider: &[u8; 32],
+    now: u64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO download_chunks (content_hash, chunk_index, provider, completed_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            content_hash.as_slice(),
+            chunk_index as i64,
+            provider.as_slice(),
+            now as i64,
+        ],
+    )?;
+    conn.execute(
+        "UPDATE downloads SET updated_at = ?1 WHERE content_hash = ?2",
+        rusqlite::params![now as i64, content_hash.as_slice()],
+    )?;
+    Ok(())
+}
+
+/// Indices of verified chunks, in ascending order.
+pub fn completed_chunks(conn: &Connection, content_hash: &[u8; 32]) -> Result<Vec<u32>> {
+    let mut stmt = conn.prepare(
+        "SELECT chunk_index FROM download_chunks WHERE content_hash = ?1 ORDER BY chunk_index",
+    )?;
+    let rows = stmt
+        .query_map([content_hash.as_slice()], |row| {
+            Ok(row.get::<_, i64>(0)? as u32)
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Get a download by content hash.
+pub fn get(conn: &Connection, content_hash: &[u8; 32]) -> Result<Option<DownloadRow>> {
+    let row = conn
+        .query_row(
+            "SELECT content_hash, destination, leaf_hashes, total_size_bytes, status,
+                    started_at, updated_at
+             FROM downloads WHERE content_hash = ?1",
+            [content_hash.as_slice()],
+            map_row,
+        )
+        .optional()?;
+    Ok(row)
+}
+
+/// List downloads with the given status.
+pub fn list_by_status(conn: &Connection, status: &str) -> Result<Vec<DownloadRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT content_hash, destination, leaf_hashes, total_size_bytes, status,
+                started_at, updated_at
+         FROM downloads WHERE status = ?1 ORDER BY started_at",
+    )?;
+    let rows = stmt
+        .query_map([status], map_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Stop tracking a download and drop its chunk progress.
+pub fn remove(conn: &Connection, content_hash: &[u8; 32]) -> Result<()> {
+    conn.execute(
+        "DELETE FROM downloads WHERE content_hash = ?1",
+        [content_hash.as_slice()],
+    )?;
+    Ok(())
+}
+
+fn map_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DownloadRow> {
+    Ok(DownloadRow {
+        content_hash: row.get::<_, Vec<u8>>(0)?,
+        destination: row.get(1)?,
+        leaf_hashes: row.get::<_, Vec<u8>>(2)?,
+        total_size_bytes: row.get::<_, i64>(3)? as u64,
+        status: row.get(4)?,
+        started_at: row.get::<_, i64>(5)? as u64,
+        updated_at: row.get::<_, i64>(6)? as u64,
+    })
+}
+
+/// A raw download row.
+#[derive(Debug)]
+pub struct DownloadRow {
+    pub content_hash: Vec<u8>,
+    pub destination: String,
+    /// Concatenated 32-byte Merkle leaf hashes.
+    pub leaf_hashes: Vec<u8>,
+    pub total_size_bytes: u64,
+    pub status: String,
+    pub started_at: u64,
+    pub updated_at: u64,
+}
+
+impl DownloadRow {
+    /// Split the stored leaf hashes into 32-byte entries.
+    pub fn leaves(&self) -> Result<Vec<[u8; 32]>> {
+        if !self.leaf_hashes.len().is_multiple_of(32) {
+            return Err(DbError::Serialization(
+                "leaf_hashes is not a multiple of 32 bytes".to_string(),
+            ));
+        }
+        Ok(self
+            .leaf_hashes
+            .chunks_exact(32)
+            .map(|c| {
+                let mut leaf = [0u8; 32];
+                leaf.copy_from_slice(c);
+                leaf
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    #[test]
+    fn test_start_and_get() {
+        let conn = test_db();
+        let leaves = [[1u8; 32], [2u8; 32]];
+        start(&conn, &[9u8; 32], "/tmp/out", &leaves, 100, 1000).expect("start");
+
+        let row = get(&conn, &[9u8; 32]).expect("get").expect("row");
+        assert_eq!(row.status, STATUS_ACTIVE);
+        assert_eq!(row.leaves().expect("leaves"), leaves.to_vec());
+        assert!(get(&conn, &[8u8; 32]).expect("get").is_none());
+    }
+
+    #[test]
+    fn test_chunk_progress_survives_restart() {
+        let conn = test_db();
+        let hash = [9u8; 32];
+        start(&conn, &hash, "/tmp/out", &[[1u8; 32]; 4], 100, 1000).expect("start");
+        record_chunk(&conn, &hash, 2, &[5u8; 32], 1001).expect("chunk");
+        record_chunk(&conn, &hash, 0, &[6u8; 32], 1002).expect("chunk");
+        record_chunk(&conn, &hash, 2, &[6u8; 32], 1003).expect("duplicate ignored");
+
+        set_status(&conn, &hash, STATUS_PAUSED, 1004).expect("pause");
+        start(&conn, &hash, "/tmp/out", &[[1u8; 32]; 4], 100, 1005).expect("restart");
+
+        assert_eq!(completed_chunks(&conn, &hash).expect("chunks"), vec![0, 2]);
+        let row = get(&conn, &hash).expect("get").expect("row");
+        assert_eq!(row.status, STATUS_ACTIVE);
+        assert_eq!(row.started_at, 1000);
+    }
+
+    #[test]
+    fn test_list_by_status_and_remove() {
+        let conn = test_db();
+        start(&conn, &[1u8; 32], "/a", &[[1u8; 32]], 1, 1000).expect("start");
+        start(&conn, &[2u8; 32], "/b", &[[1u8; 32]], 1, 1000).expect("start");
+        set_status(&conn, &[2u8; 32], STATUS_PAUSED, 1001).expect("pause");
+        record_chunk(&conn, &[1u8; 32], 0, &[5u8; 32], 1002).expect("chunk");
+
+        assert_eq!(list_by_status(&conn, STATUS_ACTIVE).expect("list").len(), 1);
+        assert!(set_status(&conn, &[3u8; 32], STATUS_PAUSED, 1003).is_err());
+
+        remove(&conn, &[1u8; 32]).expect("remove");
+        assert!(completed_chunks(&conn, &[1u8; 32])
+            .expect("chunks")
+            .is_empty());
+        assert!(get(&conn, &[1u8; 32]).expect("get").is_none());
+    }
+}
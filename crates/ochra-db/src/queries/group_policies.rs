@@ -0,0 +1,111 @@
+//! Accepted Space policy history (Section 8.11).
+//!
+//! Policies are stored as signed bytes and re-verified by the caller on
+//! load, so this module never interprets them.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::Result;
+
+/// A stored policy version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyRow {
+    pub version: u64,
+    /// Encoded signed policy.
+    pub signed_policy: Vec<u8>,
+    /// PIK public key of the owner who signed it.
+    pub signer_pk: Vec<u8>,
+    pub received_at: u64,
+}
+
+/// Store an accepted policy version. Returns `false` if the version was
+/// already stored.
+pub fn insert(conn: &Connection, group_id: &[u8; 32], row: &PolicyRow) -> Result<bool> {
+    let changed = conn.execute(
+        "INSERT OR IGNORE INTO group_policies
+         (group_id, version, signed_policy, signer_pk, received_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            group_id.as_slice(),
+            row.version as i64,
+            row.signed_policy,
+            row.signer_pk,
+            row.received_at as i64,
+        ],
+    )?;
+    Ok(changed > 0)
+}
+
+fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PolicyRow> {
+    Ok(PolicyRow {
+        version: row.get::<_, i64>(0)? as u64,
+        signed_policy: row.get(1)?,
+        signer_pk: row.get(2)?,
+        received_at: row.get::<_, i64>(3)? as u64,
+    })
+}
+
+/// The newest stored policy of a Space.
+pub fn latest(conn: &Connection, group_id: &[u8; 32]) -> Result<Option<PolicyRow>> {
+    Ok(conn
+        .query_row(
+            "SELECT version, signed_policy, signer_pk, received_at FROM group_policies
+             WHERE group_id = ?1 ORDER BY version DESC LIMIT 1",
+            [group_id.as_slice()],
+            from_row,
+        )
+        .optional()?)
+}
+
+/// The `limit` newest policies of a Space, newest first.
+pub fn history(conn: &Connection, group_id: &[u8; 32], limit: u32) -> Result<Vec<PolicyRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT version, signed_policy, signer_pk, received_at FROM group_policies
+         WHERE group_id = ?1 ORDER BY version DESC LIMIT ?2",
+    )?;
+    let rows = stmt
+        .query_map(rusqlite::params![group_id.as_slice(), limit], from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUP: [u8; 32] = [1; 32];
+
+    fn test_db() -> Connection {
+        let conn = crate::open_memory().expect("open test db");
+        crate::queries::spaces::insert(&conn, &GROUP, "Space", "forum", "member", &[2; 32], 1)
+            .expect("space");
+        conn
+    }
+
+    fn row(version: u64) -> PolicyRow {
+        PolicyRow {
+            version,
+            signed_policy: vec![version as u8; 4],
+            signer_pk: vec![2; 32],
+            received_at: 100 + version,
+        }
+    }
+
+    #[test]
+    fn test_history_newest_first() {
+        let conn = test_db();
+        assert!(latest(&conn, &GROUP).expect("latest").is_none());
+        for version in [1, 3, 2] {
+            assert!(insert(&conn, &GROUP, &row(version)).expect("insert"));
+        }
+        assert!(!insert(&conn, &GROUP, &row(2)).expect("duplicate"));
+
+        assert_eq!(latest(&conn, &GROUP).expect("latest"), Some(row(3)));
+        let versions: Vec<u64> = history(&conn, &GROUP, 2)
+            .expect("history")
+            .iter()
+            .map(|r| r.version)
+            .collect();
+        assert_eq!(versions, vec![3, 2]);
+    }
+}
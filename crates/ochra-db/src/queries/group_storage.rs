@@ -0,0 +1,227 @@
+//! Per-Space storage accounting and quotas.
+//!
+//! Usage counts live (non-tombstoned) catalog entries, attributed to their
+//! creator. Pinned bytes are the ABR chunks this node has pinned for the
+//! Space's content.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::Result;
+
+/// Storage a Space's content uses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupUsage {
+    pub total_bytes: u64,
+    pub item_count: u32,
+    /// Bytes of the Space's chunks pinned in the local ABR store.
+    pub pinned_bytes: u64,
+    /// Contribution of each member, largest first.
+    pub members: Vec<MemberUsage>,
+}
+
+/// Storage one member's published content uses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberUsage {
+    pub pik_hash: Vec<u8>,
+    pub bytes: u64,
+    pub item_count: u32,
+}
+
+/// A Space's quota. `None` limits are unlimited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuotaRow {
+    pub max_total_bytes: Option<u64>,
+    pub max_member_bytes: Option<u64>,
+    pub max_item_bytes: Option<u64>,
+    pub updated_at: u64,
+}
+
+/// Storage used by a Space and each of its contributing members.
+pub fn usage(conn: &Connection, group_id: &[u8; 32]) -> Result<GroupUsage> {
+    let mut stmt = conn.prepare(
+        "SELECT creator_pik, SUM(total_size_bytes), COUNT(*)
+         FROM content_catalog
+         WHERE group_id = ?1 AND is_tombstoned = 0
+         GROUP BY creator_pik
+         ORDER BY SUM(total_size_bytes) DESC, creator_pik",
+    )?;
+    let members = stmt
+        .query_map([group_id.as_slice()], |row| {
+            Ok(MemberUsage {
+                pik_hash: row.get(0)?,
+                bytes: row.get::<_, i64>(1)? as u64,
+                item_count: row.get::<_, i64>(2)? as u32,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let pinned_bytes: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(a.size_bytes), 0)
+         FROM abr_chunks a JOIN content_catalog c ON c.content_hash = a.content_hash
+         WHERE c.group_id = ?1 AND c.is_tombstoned = 0 AND a.is_pinned = 1",
+        [group_id.as_slice()],
+        |row| row.get(0),
+    )?;
+
+    Ok(GroupUsage {
+        total_bytes: members.iter().map(|m| m.bytes).sum(),
+        item_count: members.iter().map(|m| m.item_count).sum(),
+        pinned_bytes: pinned_bytes as u64,
+        members,
+    })
+}
+
+/// Bytes of live content `pik_hash` has published in a Space.
+pub fn member_bytes(conn: &Connection, group_id: &[u8; 32], pik_hash: &[u8; 32]) -> Result<u64> {
+    let bytes: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(total_size_bytes), 0) FROM content_catalog
+         WHERE group_id = ?1 AND creator_pik = ?2 AND is_tombstoned = 0",
+        [group_id.as_slice(), pik_hash.as_slice()],
+        |row| row.get(0),
+    )?;
+    Ok(bytes as u64)
+}
+
+/// Bytes of live content in a Space.
+pub fn group_bytes(conn: &Connection, group_id: &[u8; 32]) -> Result<u64> {
+    let bytes: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(total_size_bytes), 0) FROM content_catalog
+         WHERE group_id = ?1 AND is_tombstoned = 0",
+        [group_id.as_slice()],
+        |row| row.get(0),
+    )?;
+    Ok(bytes as u64)
+}
+
+fn limit(value: Option<i64>) -> Option<u64> {
+    value.map(|v| v as u64)
+}
+
+/// The quota set for a Space, if any.
+pub fn get_quota(conn: &Connection, group_id: &[u8; 32]) -> Result<Option<QuotaRow>> {
+    Ok(conn
+        .query_row(
+            "SELECT max_total_bytes, max_member_bytes, max_item_bytes, updated_at
+             FROM group_quotas WHERE group_id = ?1",
+            [group_id.as_slice()],
+            |row| {
+                Ok(QuotaRow {
+                    max_total_bytes: limit(row.get(0)?),
+                    max_member_bytes: limit(row.get(1)?),
+                    max_item_bytes: limit(row.get(2)?),
+                    updated_at: row.get::<_, i64>(3)? as u64,
+                })
+            },
+        )
+        .optional()?)
+}
+
+/// Set or replace a Space's quota.
+pub fn set_quota(conn: &Connection, group_id: &[u8; 32], quota: &QuotaRow) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO group_quotas
+         (group_id, max_total_bytes, max_member_bytes, max_item_bytes, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            group_id.as_slice(),
+            quota.max_total_bytes.map(|v| v as i64),
+            quota.max_member_bytes.map(|v| v as i64),
+            quota.max_item_bytes.map(|v| v as i64),
+            quota.updated_at as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queries::{content, spaces};
+
+    const GROUP: [u8; 32] = [1; 32];
+
+    fn test_db() -> Connection {
+        let conn = crate::open_memory().expect("open test db");
+        spaces::insert(&conn, &GROUP, "Test", "storefront", "host", &[2; 32], 1000)
+            .expect("insert space");
+        conn
+    }
+
+    fn publish(conn: &Connection, tag: u8, creator: u8, size: u64) {
+        content::insert(
+            conn,
+            &[tag; 32],
+            &GROUP,
+            "Item",
+            None,
+            "[]",
+            &[creator; 32],
+            &[0; 32],
+            size,
+            1,
+            2000,
+        )
+        .expect("insert content");
+    }
+
+    #[test]
+    fn test_usage_by_member_excludes_tombstoned() {
+        let conn = test_db();
+        assert_eq!(usage(&conn, &GROUP).expect("usage"), GroupUsage::default());
+
+        publish(&conn, 10, 3, 100);
+        publish(&conn, 11, 3, 50);
+        publish(&conn, 12, 4, 400);
+        publish(&conn, 13, 4, 1_000);
+        content::tombstone(&conn, &[13; 32], 3000).expect("tombstone");
+
+        let u = usage(&conn, &GROUP).expect("usage");
+        assert_eq!((u.total_bytes, u.item_count), (550, 3));
+        assert_eq!(u.members.len(), 2);
+        assert_eq!(u.members[0].pik_hash, vec![4; 32]);
+        assert_eq!((u.members[0].bytes, u.members[0].item_count), (400, 1));
+        assert_eq!((u.members[1].bytes, u.members[1].item_count), (150, 2));
+        assert_eq!(member_bytes(&conn, &GROUP, &[3; 32]).expect("member"), 150);
+        assert_eq!(member_bytes(&conn, &GROUP, &[5; 32]).expect("member"), 0);
+        assert_eq!(group_bytes(&conn, &GROUP).expect("group"), 550);
+    }
+
+    #[test]
+    fn test_pinned_bytes_counts_only_pinned_chunks() {
+        let conn = test_db();
+        publish(&conn, 10, 3, 100);
+        for (chunk, pinned) in [(1u8, true), (2, true), (3, false)] {
+            conn.execute(
+                "INSERT INTO abr_chunks (chunk_id, content_hash, shard_index, size_bytes,
+                 auth_tag, stored_at, last_accessed, is_pinned, file_path)
+                 VALUES (?1, ?2, 0, 30, x'00', 0, 0, ?3, '')",
+                rusqlite::params![vec![chunk; 32], vec![10u8; 32], pinned],
+            )
+            .expect("insert chunk");
+        }
+        assert_eq!(usage(&conn, &GROUP).expect("usage").pinned_bytes, 60);
+    }
+
+    #[test]
+    fn test_quota_round_trip() {
+        let conn = test_db();
+        assert!(get_quota(&conn, &GROUP).expect("get").is_none());
+        let quota = QuotaRow {
+            max_total_bytes: Some(1 << 40),
+            max_member_bytes: None,
+            max_item_bytes: Some(1 << 30),
+            updated_at: 5000,
+        };
+        set_quota(&conn, &GROUP, &quota).expect("set");
+        assert_eq!(get_quota(&conn, &GROUP).expect("get"), Some(quota));
+
+        let cleared = QuotaRow {
+            max_total_bytes: None,
+            max_member_bytes: None,
+            max_item_bytes: None,
+            updated_at: 6000,
+        };
+        set_quota(&conn, &GROUP, &cleared).expect("replace");
+        assert_eq!(get_quota(&conn, &GROUP).expect("get"), Some(cleared));
+    }
+}
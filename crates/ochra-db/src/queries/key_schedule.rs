@@ -0,0 +1,106 @@
+//! Quorum key schedule query functions.
+//!
+//! Entries are stored as received; the chain of endorsements is checked by
+//! `ochra_frost::quorum::KeySchedule` before an entry is inserted.
+
+use ochra_types::network::QuorumKeyEntry;
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::Result;
+
+/// Store an entry. Fails if the activation epoch already has one.
+pub fn insert(conn: &Connection, entry: &QuorumKeyEntry) -> Result<()> {
+    conn.execute(
+        "INSERT INTO quorum_key_schedule
+         (activated_epoch, group_pk, threshold, quorum_size, endorsement)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            entry.activated_epoch,
+            entry.group_pk.as_slice(),
+            entry.threshold,
+            entry.quorum_size,
+            entry.endorsement.as_slice(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// All entries in activation order.
+pub fn list(conn: &Connection) -> Result<Vec<QuorumKeyEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT activated_epoch, group_pk, threshold, quorum_size, endorsement
+         FROM quorum_key_schedule ORDER BY activated_epoch",
+    )?;
+    let rows = stmt
+        .query_map([], from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// The entry in force during `epoch`, if the schedule reaches back that far.
+pub fn for_epoch(conn: &Connection, epoch: u32) -> Result<Option<QuorumKeyEntry>> {
+    let entry = conn
+        .query_row(
+            "SELECT activated_epoch, group_pk, threshold, quorum_size, endorsement
+             FROM quorum_key_schedule WHERE activated_epoch <= ?1
+             ORDER BY activated_epoch DESC LIMIT 1",
+            [epoch],
+            from_row,
+        )
+        .optional()?;
+    Ok(entry)
+}
+
+fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<QuorumKeyEntry> {
+    Ok(QuorumKeyEntry {
+        activated_epoch: row.get(0)?,
+        group_pk: row.get(1)?,
+        threshold: row.get(2)?,
+        quorum_size: row.get(3)?,
+        endorsement: row.get(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    fn entry(epoch: u32, key: u8) -> QuorumKeyEntry {
+        QuorumKeyEntry {
+            activated_epoch: epoch,
+            group_pk: [key; 32],
+            threshold: 67,
+            quorum_size: 100,
+            endorsement: [key; 64],
+        }
+    }
+
+    #[test]
+    fn test_insert_and_list() {
+        let conn = test_db();
+        insert(&conn, &entry(20, 2)).expect("insert");
+        insert(&conn, &entry(0, 1)).expect("insert");
+        assert_eq!(list(&conn).expect("list"), vec![entry(0, 1), entry(20, 2)]);
+    }
+
+    #[test]
+    fn test_for_epoch() {
+        let conn = test_db();
+        insert(&conn, &entry(10, 1)).expect("insert");
+        insert(&conn, &entry(20, 2)).expect("insert");
+        assert_eq!(for_epoch(&conn, 9).expect("query"), None);
+        assert_eq!(for_epoch(&conn, 19).expect("query"), Some(entry(10, 1)));
+        assert_eq!(for_epoch(&conn, 20).expect("query"), Some(entry(20, 2)));
+    }
+
+    #[test]
+    fn test_duplicate_epoch_rejected() {
+        let conn = test_db();
+        insert(&conn, &entry(10, 1)).expect("insert");
+        assert!(insert(&conn, &entry(10, 2)).is_err());
+    }
+}
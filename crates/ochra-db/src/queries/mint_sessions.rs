@@ -0,0 +1,158 @@
+//! Encrypted minting sessions kept for crash recovery.
+//!
+//! The session state holds blinding factors and is stored only as
+//! ciphertext; `status` and `deadline` stay in the clear so open sessions
+//! can be found without decrypting every row.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::{DbError, Result};
+
+/// A stored minting session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintSessionRow {
+    pub session_id: [u8; 16],
+    pub status: String,
+    pub deadline: u64,
+    pub nonce: [u8; 12],
+    pub encrypted_state: Vec<u8>,
+}
+
+/// Insert or replace a session.
+pub fn upsert(conn: &Connection, row: &MintSessionRow, now: u64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO mint_sessions (session_id, status, deadline, nonce, encrypted_state, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(session_id) DO UPDATE SET
+             status = excluded.status,
+             deadline = excluded.deadline,
+             nonce = excluded.nonce,
+             encrypted_state = excluded.encrypted_state,
+             updated_at = excluded.updated_at",
+        rusqlite::params![
+            row.session_id.as_slice(),
+            row.status,
+            row.deadline as i64,
+            row.nonce.as_slice(),
+            row.encrypted_state,
+            now as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Columns as read, before length checks.
+type RawRow = (Vec<u8>, String, i64, Vec<u8>, Vec<u8>);
+
+fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RawRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+    ))
+}
+
+fn decode(raw: RawRow) -> Result<MintSessionRow> {
+    let (session_id, status, deadline, nonce, encrypted_state) = raw;
+    Ok(MintSessionRow {
+        session_id: session_id
+            .try_into()
+            .map_err(|_| DbError::Serialization("mint session id must be 16 bytes".into()))?,
+        status,
+        deadline: deadline as u64,
+        nonce: nonce
+            .try_into()
+            .map_err(|_| DbError::Serialization("mint session nonce must be 12 bytes".into()))?,
+        encrypted_state,
+    })
+}
+
+/// Look up a session.
+pub fn get(conn: &Connection, session_id: &[u8; 16]) -> Result<Option<MintSessionRow>> {
+    conn.query_row(
+        "SELECT session_id, status, deadline, nonce, encrypted_state
+         FROM mint_sessions WHERE session_id = ?1",
+        [session_id.as_slice()],
+        from_row,
+    )
+    .optional()?
+    .map(decode)
+    .transpose()
+}
+
+/// Sessions not yet completed or refunded, oldest deadline first.
+pub fn list_open(conn: &Connection) -> Result<Vec<MintSessionRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT session_id, status, deadline, nonce, encrypted_state FROM mint_sessions
+         WHERE status NOT IN ('completed', 'refunded')
+         ORDER BY deadline",
+    )?;
+    let rows = stmt
+        .query_map([], from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    rows.into_iter().map(decode).collect()
+}
+
+/// Delete finished sessions last updated before `before`. Returns the
+/// number deleted.
+pub fn prune_finished(conn: &Connection, before: u64) -> Result<usize> {
+    let n = conn.execute(
+        "DELETE FROM mint_sessions
+         WHERE status IN ('completed', 'refunded') AND updated_at < ?1",
+        [before as i64],
+    )?;
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    fn row(id: u8, status: &str, deadline: u64) -> MintSessionRow {
+        MintSessionRow {
+            session_id: [id; 16],
+            status: status.to_string(),
+            deadline,
+            nonce: [id; 12],
+            encrypted_state: vec![id; 40],
+        }
+    }
+
+    #[test]
+    fn test_upsert_and_get() {
+        let conn = test_db();
+        assert!(get(&conn, &[1; 16]).expect("get").is_none());
+        upsert(&conn, &row(1, "blinded", 100), 10).expect("insert");
+        let mut updated = row(1, "submitted", 100);
+        updated.encrypted_state = vec![9; 40];
+        upsert(&conn, &updated, 20).expect("update");
+        assert_eq!(get(&conn, &[1; 16]).expect("get"), Some(updated));
+    }
+
+    #[test]
+    fn test_list_open_and_prune() {
+        let conn = test_db();
+        upsert(&conn, &row(1, "submitted", 300), 10).expect("insert");
+        upsert(&conn, &row(2, "blinded", 200), 10).expect("insert");
+        upsert(&conn, &row(3, "completed", 100), 10).expect("insert");
+        upsert(&conn, &row(4, "refunded", 100), 50).expect("insert");
+
+        let open: Vec<[u8; 16]> = list_open(&conn)
+            .expect("list")
+            .iter()
+            .map(|r| r.session_id)
+            .collect();
+        assert_eq!(open, vec![[2; 16], [1; 16]]);
+
+        assert_eq!(prune_finished(&conn, 20).expect("prune"), 1);
+        assert!(get(&conn, &[3; 16]).expect("get").is_none());
+        assert!(get(&conn, &[4; 16]).expect("get").is_some());
+        assert!(get(&conn, &[1; 16]).expect("get").is_some());
+    }
+}
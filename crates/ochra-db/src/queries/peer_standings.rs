@@ -0,0 +1,100 @@
+//! Persisted standings of greylisted and blacklisted peers.
+
+use rusqlite::Connection;
+
+use crate::Result;
+
+/// A restricted peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerStanding {
+    pub peer_id: [u8; 32],
+    pub score: f64,
+    /// "greylisted" or "blacklisted".
+    pub standing: String,
+    /// End of a blacklisting, or 0.
+    pub banned_until: u64,
+    pub updated_at: u64,
+}
+
+/// Insert or replace a peer's standing.
+pub fn upsert(conn: &Connection, standing: &PeerStanding) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO peer_standings (peer_id, score, standing, banned_until, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            standing.peer_id.as_slice(),
+            standing.score,
+            standing.standing,
+            standing.banned_until as i64,
+            standing.updated_at as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Remove a peer that is back in good standing.
+pub fn delete(conn: &Connection, peer_id: &[u8; 32]) -> Result<bool> {
+    let n = conn.execute(
+        "DELETE FROM peer_standings WHERE peer_id = ?1",
+        [peer_id.as_slice()],
+    )?;
+    Ok(n > 0)
+}
+
+/// All restricted peers.
+pub fn list(conn: &Connection) -> Result<Vec<PeerStanding>> {
+    let mut stmt = conn.prepare(
+        "SELECT peer_id, score, standing, banned_until, updated_at
+         FROM peer_standings ORDER BY peer_id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(PeerStanding {
+                peer_id: row.get(0)?,
+                score: row.get(1)?,
+                standing: row.get(2)?,
+                banned_until: row.get::<_, i64>(3)? as u64,
+                updated_at: row.get::<_, i64>(4)? as u64,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standing(peer: u8, standing: &str) -> PeerStanding {
+        PeerStanding {
+            peer_id: [peer; 32],
+            score: 60.0,
+            standing: standing.to_string(),
+            banned_until: 0,
+            updated_at: 100,
+        }
+    }
+
+    #[test]
+    fn test_upsert_and_list() {
+        let conn = crate::open_memory().expect("open test db");
+        upsert(&conn, &standing(2, "greylisted")).expect("upsert");
+        upsert(&conn, &standing(1, "greylisted")).expect("upsert");
+
+        let mut banned = standing(2, "blacklisted");
+        banned.banned_until = 5000;
+        upsert(&conn, &banned).expect("replace");
+
+        let rows = list(&conn).expect("list");
+        assert_eq!(rows, vec![standing(1, "greylisted"), banned]);
+    }
+
+    #[test]
+    fn test_delete() {
+        let conn = crate::open_memory().expect("open test db");
+        upsert(&conn, &standing(1, "greylisted")).expect("upsert");
+        assert!(delete(&conn, &[1; 32]).expect("delete"));
+        assert!(!delete(&conn, &[1; 32]).expect("delete"));
+        assert!(list(&conn).expect("list").is_empty());
+    }
+}
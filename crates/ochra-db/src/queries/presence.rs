@@ -0,0 +1,139 @@
+//! Presence privacy query functions.
+//!
+//! Global toggles live in `settings` under the `presence_*` keys and default
+//! to on; per-contact overrides live in `presence_overrides`.
+
+use ochra_types::whisper::{PresenceOverride, PresencePrivacy};
+use rusqlite::Connection;
+
+use crate::queries::settings;
+use crate::Result;
+
+const KEY_TYPING: &str = "presence_typing_indicators";
+const KEY_READ_RECEIPTS: &str = "presence_read_receipts";
+const KEY_ONLINE: &str = "presence_online";
+
+/// Load the global presence settings.
+pub fn get_global(conn: &Connection) -> Result<PresencePrivacy> {
+    Ok(PresencePrivacy {
+        typing_indicators: settings::get_bool(conn, KEY_TYPING, true)?,
+        read_receipts: settings::get_bool(conn, KEY_READ_RECEIPTS, true)?,
+        online_presence: settings::get_bool(conn, KEY_ONLINE, true)?,
+    })
+}
+
+/// Store the global presence settings.
+pub fn set_global(conn: &Connection, privacy: &PresencePrivacy) -> Result<()> {
+    settings::set(conn, KEY_TYPING, bool_str(privacy.typing_indicators))?;
+    settings::set(conn, KEY_READ_RECEIPTS, bool_str(privacy.read_receipts))?;
+    settings::set(conn, KEY_ONLINE, bool_str(privacy.online_presence))?;
+    Ok(())
+}
+
+/// Store a contact's override, or remove it if every field is unset.
+pub fn set_override(conn: &Connection, contact_pik: &[u8; 32], o: &PresenceOverride) -> Result<()> {
+    if o.is_empty() {
+        conn.execute(
+            "DELETE FROM presence_overrides WHERE contact_pik = ?1",
+            [contact_pik.as_slice()],
+        )?;
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO presence_overrides
+         (contact_pik, typing_indicators, read_receipts, online_presence)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            contact_pik.as_slice(),
+            o.typing_indicators,
+            o.read_receipts,
+            o.online_presence,
+        ],
+    )?;
+    Ok(())
+}
+
+/// All per-contact overrides.
+pub fn list_overrides(conn: &Connection) -> Result<Vec<([u8; 32], PresenceOverride)>> {
+    let mut stmt = conn.prepare(
+        "SELECT contact_pik, typing_indicators, read_receipts, online_presence
+         FROM presence_overrides ORDER BY contact_pik",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, [u8; 32]>(0)?,
+                PresenceOverride {
+                    typing_indicators: row.get(1)?,
+                    read_receipts: row.get(2)?,
+                    online_presence: row.get(3)?,
+                },
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+fn bool_str(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    #[test]
+    fn test_global_defaults_and_roundtrip() {
+        let conn = test_db();
+        assert_eq!(get_global(&conn).expect("get"), PresencePrivacy::default());
+
+        let quiet = PresencePrivacy {
+            typing_indicators: false,
+            read_receipts: false,
+            online_presence: true,
+        };
+        set_global(&conn, &quiet).expect("set");
+        assert_eq!(get_global(&conn).expect("get"), quiet);
+    }
+
+    #[test]
+    fn test_overrides_follow_contacts() {
+        let conn = test_db();
+        let contact = [5u8; 32];
+        crate::queries::contacts::insert(&conn, &contact, "Alice", &[1u8; 32], 100)
+            .expect("contact");
+        let o = PresenceOverride {
+            read_receipts: Some(false),
+            ..PresenceOverride::default()
+        };
+        set_override(&conn, &contact, &o).expect("set");
+        assert_eq!(list_overrides(&conn).expect("list"), vec![(contact, o)]);
+
+        crate::queries::contacts::remove(&conn, &contact).expect("remove");
+        assert!(list_overrides(&conn).expect("list").is_empty());
+        assert!(set_override(&conn, &[6u8; 32], &o).is_err());
+    }
+
+    #[test]
+    fn test_empty_override_deletes() {
+        let conn = test_db();
+        let contact = [5u8; 32];
+        crate::queries::contacts::insert(&conn, &contact, "Alice", &[1u8; 32], 100)
+            .expect("contact");
+        let o = PresenceOverride {
+            typing_indicators: Some(true),
+            ..PresenceOverride::default()
+        };
+        set_override(&conn, &contact, &o).expect("set");
+        set_override(&conn, &contact, &PresenceOverride::default()).expect("clear");
+        assert!(list_overrides(&conn).expect("list").is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+//! Outbox of service receipt acknowledgements owed to servers (Section 14.7).
+//!
+//! Each row is one encoded acknowledgement; its `ack_id` is the id the
+//! daemon's batcher reports back when the server settles it.
+
+use rusqlite::Connection;
+
+use crate::Result;
+
+/// A stored acknowledgement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AckRow {
+    pub ack_id: u64,
+    pub server_node_id: Vec<u8>,
+    /// CBOR-encoded `ServiceReceiptAck`.
+    pub ack: Vec<u8>,
+    pub queued_at: u64,
+}
+
+/// Store an acknowledgement and return its id.
+pub fn insert(
+    conn: &Connection,
+    server_node_id: &[u8; 32],
+    ack: &[u8],
+    queued_at: u64,
+) -> Result<u64> {
+    conn.execute(
+        "INSERT INTO receipt_ack_outbox (server_node_id, ack, queued_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![server_node_id.as_slice(), ack, queued_at as i64],
+    )?;
+    Ok(conn.last_insert_rowid() as u64)
+}
+
+/// Every acknowledgement not yet settled, oldest first.
+pub fn list(conn: &Connection) -> Result<Vec<AckRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT ack_id, server_node_id, ack, queued_at FROM receipt_ack_outbox ORDER BY ack_id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(AckRow {
+                ack_id: row.get::<_, i64>(0)? as u64,
+                server_node_id: row.get(1)?,
+                ack: row.get(2)?,
+                queued_at: row.get::<_, i64>(3)? as u64,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Delete settled acknowledgements. Returns how many were removed.
+pub fn settle(conn: &Connection, ack_ids: &[u64]) -> Result<usize> {
+    let mut removed = 0;
+    for id in ack_ids {
+        removed += conn.execute(
+            "DELETE FROM receipt_ack_outbox WHERE ack_id = ?1",
+            [*id as i64],
+        )?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_list_settle() {
+        let conn = crate::open_memory().expect("open");
+        let a = insert(&conn, &[1u8; 32], b"ack-a", 10).expect("insert");
+        let b = insert(&conn, &[2u8; 32], b"ack-b", 20).expect("insert");
+        assert_ne!(a, b);
+
+        let rows = list(&conn).expect("list");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].ack_id, a);
+        assert_eq!(rows[0].ack, b"ack-a".to_vec());
+        assert_eq!(rows[1].queued_at, 20);
+
+        assert_eq!(settle(&conn, &[a, 999]).expect("settle"), 1);
+        let rows = list(&conn).expect("list");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].ack_id, b);
+    }
+}
@@ -0,0 +1,63 @@
+//! Anonymous refunds granted by this node (Section 16.3).
+//!
+//! A row holds both tags of one approved claim. Each is unique, so a
+//! single insert checks and records them atomically.
+
+use rusqlite::Connection;
+
+use crate::Result;
+
+/// Whether a refund was granted under `nullifier_hash` or for the purchase
+/// with `purchase_commitment`.
+pub fn is_refunded(
+    conn: &Connection,
+    nullifier_hash: &[u8; 32],
+    purchase_commitment: &[u8; 32],
+) -> Result<bool> {
+    let found: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM refund_claims
+         WHERE nullifier_hash = ?1 OR purchase_commitment = ?2)",
+        [nullifier_hash.as_slice(), purchase_commitment.as_slice()],
+        |row| row.get(0),
+    )?;
+    Ok(found)
+}
+
+/// Record a granted refund. Returns `false`, recording nothing, if either
+/// tag was already recorded.
+pub fn record(
+    conn: &Connection,
+    nullifier_hash: &[u8; 32],
+    purchase_commitment: &[u8; 32],
+    epoch: u64,
+) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO refund_claims (nullifier_hash, purchase_commitment, epoch)
+         VALUES (?1, ?2, ?3)",
+        rusqlite::params![
+            nullifier_hash.as_slice(),
+            purchase_commitment.as_slice(),
+            epoch as i64
+        ],
+    )?;
+    Ok(inserted == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_once_per_tag() {
+        let conn = crate::open_memory().expect("open test db");
+        assert!(!is_refunded(&conn, &[1; 32], &[2; 32]).expect("check"));
+        assert!(record(&conn, &[1; 32], &[2; 32], 7).expect("record"));
+        assert!(is_refunded(&conn, &[1; 32], &[9; 32]).expect("check"));
+        assert!(is_refunded(&conn, &[9; 32], &[2; 32]).expect("check"));
+
+        // Either tag already used refuses the whole claim
+        assert!(!record(&conn, &[1; 32], &[3; 32], 8).expect("record"));
+        assert!(!record(&conn, &[4; 32], &[2; 32], 8).expect("record"));
+        assert!(!is_refunded(&conn, &[4; 32], &[3; 32]).expect("check"));
+    }
+}
@@ -0,0 +1,87 @@
+//! Verified PIK revocation certificates (Section 6.6).
+//!
+//! Certificates are verified before they are stored; this table only
+//! records which PIKs the node treats as invalid.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::Result;
+
+/// Record a verified revocation. Returns whether the PIK was newly revoked.
+pub fn insert(
+    conn: &Connection,
+    pik_hash: &[u8; 32],
+    certificate: &[u8],
+    revoked_at: u64,
+    received_at: u64,
+) -> Result<bool> {
+    let n = conn.execute(
+        "INSERT OR IGNORE INTO revoked_piks (pik_hash, certificate, revoked_at, received_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            pik_hash.as_slice(),
+            certificate,
+            revoked_at as i64,
+            received_at as i64,
+        ],
+    )?;
+    Ok(n > 0)
+}
+
+/// Whether a PIK is revoked.
+pub fn is_revoked(conn: &Connection, pik_hash: &[u8; 32]) -> Result<bool> {
+    Ok(certificate(conn, pik_hash)?.is_some())
+}
+
+/// The stored certificate revoking a PIK, if any.
+pub fn certificate(conn: &Connection, pik_hash: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+    let cert = conn
+        .query_row(
+            "SELECT certificate FROM revoked_piks WHERE pik_hash = ?1",
+            [pik_hash.as_slice()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(cert)
+}
+
+/// Hashes of every revoked PIK.
+pub fn list(conn: &Connection) -> Result<Vec<[u8; 32]>> {
+    let mut stmt = conn.prepare("SELECT pik_hash FROM revoked_piks")?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    #[test]
+    fn test_insert_once() {
+        let conn = test_db();
+        assert!(!is_revoked(&conn, &[1u8; 32]).expect("check"));
+        assert!(insert(&conn, &[1u8; 32], b"cert", 100, 200).expect("insert"));
+        assert!(!insert(&conn, &[1u8; 32], b"other", 100, 300).expect("insert"));
+        assert!(is_revoked(&conn, &[1u8; 32]).expect("check"));
+        assert_eq!(
+            certificate(&conn, &[1u8; 32]).expect("get"),
+            Some(b"cert".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let conn = test_db();
+        insert(&conn, &[1u8; 32], b"a", 100, 200).expect("insert");
+        insert(&conn, &[2u8; 32], b"b", 100, 200).expect("insert");
+        let mut revoked = list(&conn).expect("list");
+        revoked.sort();
+        assert_eq!(revoked, vec![[1u8; 32], [2u8; 32]]);
+    }
+}
@@ -0,0 +1,109 @@
+//! Service receipts this node holds as a server (Section 14.7).
+//!
+//! A receipt is stored once it carries both signatures, keyed by
+//! `BLAKE3(nonce)`, and stays unflushed until its batch is sealed for the
+//! quorum, so receipts survive a restart between serving and flushing.
+
+use ochra_types::network::ServiceReceipt;
+use rusqlite::Connection;
+
+use crate::Result;
+
+/// Store a receipt. Returns `false`, storing nothing, if its nonce is
+/// already known.
+pub fn insert(conn: &Connection, receipt: &ServiceReceipt) -> Result<bool> {
+    let receipt_id = ochra_crypto::blake3::hash(&receipt.nonce);
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO abr_service_receipts
+         (receipt_id, server_node_id, chunk_id, requester_circuit_id, requester_key,
+          bytes_served, timestamp, relay_epoch, nonce, requester_ack, server_sig)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![
+            receipt_id.as_slice(),
+            receipt.server_node_id.as_slice(),
+            receipt.chunk_id.as_slice(),
+            receipt.requester_circuit_id.as_slice(),
+            receipt.requester_key.as_slice(),
+            receipt.bytes_served,
+            receipt.timestamp as i64,
+            receipt.relay_epoch,
+            receipt.nonce.as_slice(),
+            receipt.requester_ack.as_slice(),
+            receipt.server_sig.as_slice(),
+        ],
+    )?;
+    Ok(inserted == 1)
+}
+
+/// Every receipt not yet flushed, oldest first.
+pub fn unflushed(conn: &Connection) -> Result<Vec<ServiceReceipt>> {
+    let mut stmt = conn.prepare(
+        "SELECT server_node_id, chunk_id, requester_circuit_id, requester_key,
+                bytes_served, timestamp, relay_epoch, nonce, requester_ack, server_sig
+         FROM abr_service_receipts WHERE flushed = 0 ORDER BY timestamp",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ServiceReceipt {
+                server_node_id: row.get(0)?,
+                chunk_id: row.get(1)?,
+                requester_circuit_id: row.get(2)?,
+                requester_key: row.get(3)?,
+                bytes_served: row.get(4)?,
+                timestamp: row.get::<_, i64>(5)? as u64,
+                relay_epoch: row.get(6)?,
+                nonce: row.get(7)?,
+                requester_ack: row.get(8)?,
+                server_sig: row.get(9)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Mark every receipt up to and including `relay_epoch` flushed. Returns
+/// how many were marked.
+pub fn mark_flushed(conn: &Connection, relay_epoch: u32) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE abr_service_receipts SET flushed = 1 WHERE flushed = 0 AND relay_epoch <= ?1",
+        [relay_epoch],
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(nonce: u8, epoch: u32) -> ServiceReceipt {
+        ServiceReceipt {
+            server_node_id: [1; 32],
+            chunk_id: [2; 32],
+            requester_circuit_id: [3; 16],
+            requester_key: [4; 32],
+            bytes_served: 1000,
+            timestamp: 1_700_000_000 + u64::from(nonce),
+            relay_epoch: epoch,
+            nonce: [nonce; 16],
+            requester_ack: [5; 64],
+            server_sig: [6; 64],
+        }
+    }
+
+    #[test]
+    fn test_insert_once_and_flush() {
+        let conn = crate::open_memory().expect("open test db");
+        assert!(insert(&conn, &receipt(1, 7)).expect("insert"));
+        assert!(!insert(&conn, &receipt(1, 7)).expect("insert"));
+        assert!(insert(&conn, &receipt(2, 8)).expect("insert"));
+
+        let stored = unflushed(&conn).expect("list");
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].requester_key, [4; 32]);
+        assert_eq!(stored[0].requester_ack, [5; 64]);
+
+        assert_eq!(mark_flushed(&conn, 7).expect("flush"), 1);
+        let stored = unflushed(&conn).expect("list");
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].nonce, [2; 16]);
+    }
+}
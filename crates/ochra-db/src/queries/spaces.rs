@@ -1,6 +1,6 @@
 //! Space query functions (Section 27.2).
 
-use rusqlite::Connection;
+use rusqlite::{Connection, OptionalExtension};
 
 use crate::Result;
 
@@ -53,6 +53,58 @@ pub fn list(conn: &Connection) -> Result<Vec<SpaceRow>> {
     Ok(rows)
 }
 
+/// This node's role in a space, if it is a member.
+pub fn my_role(conn: &Connection, group_id: &[u8; 32]) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT my_role FROM spaces WHERE group_id = ?1",
+            [group_id.as_slice()],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// The owner PIK public key of a space, if it is known.
+pub fn owner_pik(conn: &Connection, group_id: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+    Ok(conn
+        .query_row(
+            "SELECT owner_pik FROM spaces WHERE group_id = ?1",
+            [group_id.as_slice()],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Record the settings and owner of a space's newest accepted policy.
+pub fn apply_policy(
+    conn: &Connection,
+    group_id: &[u8; 32],
+    owner_pik: &[u8; 32],
+    publish_policy: &str,
+    invite_permission: &str,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE spaces SET owner_pik = ?1, publish_policy = ?2, invite_permission = ?3
+         WHERE group_id = ?4",
+        rusqlite::params![
+            owner_pik.as_slice(),
+            publish_policy,
+            invite_permission,
+            group_id.as_slice()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Set this node's role in a space.
+pub fn set_my_role(conn: &Connection, group_id: &[u8; 32], role: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE spaces SET my_role = ?1 WHERE group_id = ?2",
+        rusqlite::params![role, group_id.as_slice()],
+    )?;
+    Ok(())
+}
+
 /// Pin or unpin a space.
 pub fn set_pinned(conn: &Connection, group_id: &[u8; 32], pinned: bool) -> Result<()> {
     conn.execute(
@@ -101,6 +153,11 @@ mod tests {
         assert_eq!(spaces[0].name, "Test Space");
         assert_eq!(spaces[0].template, "storefront");
         assert_eq!(spaces[0].my_role, "host");
+        assert_eq!(
+            my_role(&conn, &[1u8; 32]).expect("role").as_deref(),
+            Some("host")
+        );
+        assert!(my_role(&conn, &[3u8; 32]).expect("role").is_none());
     }
 
     #[test]
@@ -0,0 +1,114 @@
+//! Per-epoch supply audit reports.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::Result;
+
+/// A stored audit report.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SupplyAuditRow {
+    pub epoch: u32,
+    /// Number of invariants that failed.
+    pub violations: u32,
+    /// Serialized report.
+    pub report: Vec<u8>,
+    pub checked_at: u64,
+}
+
+/// Insert or replace the report for an epoch.
+pub fn upsert(conn: &Connection, row: &SupplyAuditRow) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO supply_audit (epoch, violations, report, checked_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![row.epoch, row.violations, row.report, row.checked_at as i64],
+    )?;
+    Ok(())
+}
+
+fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SupplyAuditRow> {
+    Ok(SupplyAuditRow {
+        epoch: row.get(0)?,
+        violations: row.get(1)?,
+        report: row.get(2)?,
+        checked_at: row.get::<_, i64>(3)? as u64,
+    })
+}
+
+/// Look up the report for an epoch.
+pub fn get(conn: &Connection, epoch: u32) -> Result<Option<SupplyAuditRow>> {
+    Ok(conn
+        .query_row(
+            "SELECT epoch, violations, report, checked_at FROM supply_audit WHERE epoch = ?1",
+            [epoch],
+            from_row,
+        )
+        .optional()?)
+}
+
+/// The most recent report.
+pub fn latest(conn: &Connection) -> Result<Option<SupplyAuditRow>> {
+    Ok(conn
+        .query_row(
+            "SELECT epoch, violations, report, checked_at FROM supply_audit
+             ORDER BY epoch DESC LIMIT 1",
+            [],
+            from_row,
+        )
+        .optional()?)
+}
+
+/// Reports for epochs in `from..=to`, oldest first.
+pub fn range(conn: &Connection, from: u32, to: u32) -> Result<Vec<SupplyAuditRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT epoch, violations, report, checked_at FROM supply_audit
+         WHERE epoch BETWEEN ?1 AND ?2 ORDER BY epoch",
+    )?;
+    let rows = stmt
+        .query_map([from, to], from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    fn row(epoch: u32, violations: u32) -> SupplyAuditRow {
+        SupplyAuditRow {
+            epoch,
+            violations,
+            report: vec![epoch as u8; 8],
+            checked_at: 1_000 + u64::from(epoch),
+        }
+    }
+
+    #[test]
+    fn test_upsert_get_latest() {
+        let conn = test_db();
+        assert!(latest(&conn).expect("latest").is_none());
+        upsert(&conn, &row(5, 0)).expect("insert");
+        upsert(&conn, &row(7, 2)).expect("insert");
+        upsert(&conn, &row(5, 1)).expect("replace");
+        assert_eq!(get(&conn, 5).expect("get"), Some(row(5, 1)));
+        assert_eq!(latest(&conn).expect("latest"), Some(row(7, 2)));
+        assert!(get(&conn, 6).expect("get").is_none());
+    }
+
+    #[test]
+    fn test_range_is_inclusive_and_ordered() {
+        let conn = test_db();
+        for epoch in [4, 2, 3, 1] {
+            upsert(&conn, &row(epoch, 0)).expect("insert");
+        }
+        let epochs: Vec<u32> = range(&conn, 2, 3)
+            .expect("range")
+            .iter()
+            .map(|r| r.epoch)
+            .collect();
+        assert_eq!(epochs, vec![2, 3]);
+    }
+}
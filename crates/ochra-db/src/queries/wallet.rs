@@ -49,6 +49,29 @@ pub fn spend_token(conn: &Connection, token_id: &[u8], spent_at: u64) -> Result<
     Ok(())
 }
 
+/// List every held token, spent or not, oldest first.
+pub fn tokens(conn: &Connection) -> Result<Vec<TokenRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT token_id, amount, nullifier, minted_at, spent, spent_at
+         FROM wallet_tokens ORDER BY minted_at, token_id",
+    )?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(TokenRow {
+                token_id: row.get(0)?,
+                amount: row.get::<_, i64>(1)? as u64,
+                nullifier: row.get(2)?,
+                minted_at: row.get::<_, i64>(3)? as u64,
+                spent: row.get::<_, i64>(4)? != 0,
+                spent_at: row.get::<_, Option<i64>>(5)?.map(|t| t as u64),
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(rows)
+}
+
 /// Record a transaction in history.
 pub fn record_transaction(
     conn: &Connection,
@@ -94,6 +117,296 @@ pub fn recent_transactions(conn: &Connection, limit: u32) -> Result<Vec<TxRow>>
     Ok(rows)
 }
 
+/// What moved through the wallet during one epoch, for the supply audit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LedgerFlows {
+    /// Amount of every token that entered the wallet, oldest first.
+    pub received: Vec<u64>,
+    /// Incoming transactions recorded for the epoch.
+    pub recorded_incoming: u64,
+    /// Amount of the tokens spent.
+    pub spent: u64,
+    /// Unspent balance at the end of the epoch.
+    pub closing_balance: u64,
+}
+
+/// Wallet flows for `epoch`, which spans `[start, end)` in Unix seconds.
+pub fn ledger_flows(conn: &Connection, epoch: u64, start: u64, end: u64) -> Result<LedgerFlows> {
+    let (start, end) = (start as i64, end as i64);
+    let received = conn
+        .prepare(
+            "SELECT amount FROM wallet_tokens
+             WHERE minted_at >= ?1 AND minted_at < ?2 ORDER BY minted_at, token_id",
+        )?
+        .query_map([start, end], |row| Ok(row.get::<_, i64>(0)? as u64))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let recorded_incoming: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(amount), 0) FROM transaction_history
+         WHERE epoch = ?1 AND tx_type IN ('receive', 'refund', 'mint')",
+        [epoch as i64],
+        |row| row.get(0),
+    )?;
+    let spent: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(amount), 0) FROM wallet_tokens
+         WHERE spent = 1 AND spent_at >= ?1 AND spent_at < ?2",
+        [start, end],
+        |row| row.get(0),
+    )?;
+    let closing_balance: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(amount), 0) FROM wallet_tokens
+         WHERE minted_at < ?1 AND (spent = 0 OR spent_at >= ?1)",
+        [end],
+        |row| row.get(0),
+    )?;
+    Ok(LedgerFlows {
+        received,
+        recorded_incoming: recorded_incoming as u64,
+        spent: spent as u64,
+        closing_balance: closing_balance as u64,
+    })
+}
+
+/// Spend category of a transaction type, as shown on statements.
+///
+/// Categories describe what kind of flow a transaction was without naming
+/// the content or counterparty involved.
+pub fn category(tx_type: &str) -> &'static str {
+    match tx_type {
+        "purchase" => "content",
+        "send" => "transfer_out",
+        "receive" => "transfer_in",
+        "refund" => "refund",
+        "mint" => "earnings",
+        "fee" => "fee",
+        _ => "other",
+    }
+}
+
+/// Whether a transaction type adds to the balance.
+fn is_incoming(tx_type: &str) -> bool {
+    matches!(tx_type, "receive" | "refund" | "mint")
+}
+
+/// Statement output format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatementFormat {
+    Csv,
+    Json,
+}
+
+impl StatementFormat {
+    /// Parse a format name (`"csv"` or `"json"`).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Which transactions a statement covers and how much it reveals.
+#[derive(Clone, Debug, Default)]
+pub struct StatementOptions {
+    /// First epoch included, if bounded.
+    pub from_epoch: Option<u64>,
+    /// Last epoch included, if bounded.
+    pub to_epoch: Option<u64>,
+    /// Omit counterparty PIKs and content hashes.
+    pub redact_counterparties: bool,
+}
+
+/// One transaction on a statement.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct StatementLine {
+    pub tx_hash: String,
+    pub category: &'static str,
+    pub tx_type: String,
+    pub amount: u64,
+    pub epoch: u64,
+    pub timestamp: u64,
+    pub counterparty_pik: Option<String>,
+    pub content_hash: Option<String>,
+}
+
+/// Totals for one epoch, or for the whole statement.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct StatementTotals {
+    pub transactions: u64,
+    /// Received, refunded, and minted micro-seeds.
+    pub incoming: u64,
+    /// Purchases and transfers sent, excluding fees.
+    pub outgoing: u64,
+    /// Protocol fees paid (Section 11.3).
+    pub fees: u64,
+    /// `incoming - outgoing - fees`.
+    pub net: i64,
+}
+
+impl StatementTotals {
+    fn add(&mut self, tx_type: &str, amount: u64) {
+        self.transactions += 1;
+        if is_incoming(tx_type) {
+            self.incoming = self.incoming.saturating_add(amount);
+        } else if tx_type == "fee" {
+            self.fees = self.fees.saturating_add(amount);
+        } else {
+            self.outgoing = self.outgoing.saturating_add(amount);
+        }
+        self.net = self.incoming as i64 - self.outgoing as i64 - self.fees as i64;
+    }
+}
+
+/// Totals for a single epoch.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct EpochStatement {
+    pub epoch: u64,
+    #[serde(flatten)]
+    pub totals: StatementTotals,
+}
+
+/// Total amount per spend category.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct CategoryTotal {
+    pub category: &'static str,
+    pub transactions: u64,
+    pub amount: u64,
+}
+
+/// A transaction statement, oldest transaction first.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Statement {
+    pub lines: Vec<StatementLine>,
+    pub epochs: Vec<EpochStatement>,
+    pub categories: Vec<CategoryTotal>,
+    pub totals: StatementTotals,
+}
+
+/// CSV column names, in order.
+pub const STATEMENT_CSV_HEADER: &str =
+    "tx_hash,category,tx_type,amount,epoch,timestamp,counterparty_pik,content_hash";
+
+impl Statement {
+    /// Render as CSV, one transaction per row.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(STATEMENT_CSV_HEADER);
+        out.push('\n');
+        for line in &self.lines {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                line.tx_hash,
+                line.category,
+                csv_field(&line.tx_type),
+                line.amount,
+                line.epoch,
+                line.timestamp,
+                line.counterparty_pik.as_deref().unwrap_or(""),
+                line.content_hash.as_deref().unwrap_or(""),
+            ));
+        }
+        out
+    }
+
+    /// Render in the requested format.
+    pub fn render(&self, format: StatementFormat) -> Result<String> {
+        match format {
+            StatementFormat::Csv => Ok(self.to_csv()),
+            StatementFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| DbError::Serialization(e.to_string())),
+        }
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote, or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Build a statement of the transaction history.
+///
+/// Note ciphertexts are never exported.
+pub fn statement(conn: &Connection, opts: &StatementOptions) -> Result<Statement> {
+    let mut stmt = conn.prepare(
+        "SELECT tx_hash, tx_type, amount, epoch, timestamp, counterparty_pik, content_hash
+         FROM transaction_history
+         WHERE epoch >= ?1 AND epoch <= ?2
+         ORDER BY epoch, timestamp, tx_hash",
+    )?;
+    let from = opts.from_epoch.unwrap_or(0) as i64;
+    let to = opts.to_epoch.map_or(i64::MAX, |e| e as i64);
+
+    let lines = stmt
+        .query_map(rusqlite::params![from, to], |row| {
+            let tx_type: String = row.get(1)?;
+            let counterparty: Option<Vec<u8>> = row.get(5)?;
+            let content: Option<Vec<u8>> = row.get(6)?;
+            Ok(StatementLine {
+                tx_hash: hex::encode(row.get::<_, Vec<u8>>(0)?),
+                category: category(&tx_type),
+                tx_type,
+                amount: row.get::<_, i64>(2)? as u64,
+                epoch: row.get::<_, i64>(3)? as u64,
+                timestamp: row.get::<_, i64>(4)? as u64,
+                counterparty_pik: counterparty
+                    .filter(|_| !opts.redact_counterparties)
+                    .map(hex::encode),
+                content_hash: content
+                    .filter(|_| !opts.redact_counterparties)
+                    .map(hex::encode),
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut statement = Statement::default();
+    for line in &lines {
+        statement.totals.add(&line.tx_type, line.amount);
+        match statement.epochs.last_mut() {
+            Some(e) if e.epoch == line.epoch => e.totals.add(&line.tx_type, line.amount),
+            _ => {
+                let mut totals = StatementTotals::default();
+                totals.add(&line.tx_type, line.amount);
+                statement.epochs.push(EpochStatement {
+                    epoch: line.epoch,
+                    totals,
+                });
+            }
+        }
+        match statement
+            .categories
+            .iter_mut()
+            .find(|c| c.category == line.category)
+        {
+            Some(c) => {
+                c.transactions += 1;
+                c.amount = c.amount.saturating_add(line.amount);
+            }
+            None => statement.categories.push(CategoryTotal {
+                category: line.category,
+                transactions: 1,
+                amount: line.amount,
+            }),
+        }
+    }
+    statement.categories.sort_by_key(|c| c.category);
+    statement.lines = lines;
+    Ok(statement)
+}
+
+/// A raw wallet token row.
+#[derive(Debug)]
+pub struct TokenRow {
+    pub token_id: Vec<u8>,
+    pub amount: u64,
+    pub nullifier: Vec<u8>,
+    pub minted_at: u64,
+    pub spent: bool,
+    pub spent_at: Option<u64>,
+}
+
 /// A raw transaction row.
 #[derive(Debug)]
 pub struct TxRow {
@@ -143,6 +456,94 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_list_tokens() {
+        let conn = test_db();
+        insert_token(&conn, &[2u8; 16], 2000, &[20u8; 32], 200).expect("insert");
+        insert_token(&conn, &[1u8; 16], 1000, &[10u8; 32], 100).expect("insert");
+        spend_token(&conn, &[2u8; 16], 300).expect("spend");
+
+        let rows = tokens(&conn).expect("list");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].token_id, vec![1u8; 16]);
+        assert!(!rows[0].spent);
+        assert_eq!(rows[0].spent_at, None);
+        assert!(rows[1].spent);
+        assert_eq!(rows[1].spent_at, Some(300));
+        assert_eq!(rows[1].nullifier, vec![20u8; 32]);
+    }
+
+    fn seed_history(conn: &Connection) {
+        record_transaction(conn, &[1u8; 32], "mint", 5000, 1, 100).expect("record");
+        record_transaction(conn, &[2u8; 32], "purchase", 1200, 1, 110).expect("record");
+        record_transaction(conn, &[3u8; 32], "fee", 1, 1, 110).expect("record");
+        record_transaction(conn, &[4u8; 32], "send", 800, 2, 200).expect("record");
+        conn.execute(
+            "UPDATE transaction_history SET counterparty_pik = ?1, content_hash = ?2
+             WHERE tx_type = 'purchase'",
+            rusqlite::params![[7u8; 32].as_slice(), [8u8; 32].as_slice()],
+        )
+        .expect("annotate");
+    }
+
+    #[test]
+    fn test_statement_groups_by_epoch() {
+        let conn = test_db();
+        seed_history(&conn);
+
+        let st = statement(&conn, &StatementOptions::default()).expect("statement");
+        assert_eq!(st.lines.len(), 4);
+        assert_eq!(st.epochs.len(), 2);
+        assert_eq!(st.epochs[0].epoch, 1);
+        assert_eq!(st.epochs[0].totals.incoming, 5000);
+        assert_eq!(st.epochs[0].totals.outgoing, 1200);
+        assert_eq!(st.epochs[0].totals.fees, 1);
+        assert_eq!(st.epochs[0].totals.net, 3799);
+        assert_eq!(st.totals.net, 2999);
+        let content = st
+            .categories
+            .iter()
+            .find(|c| c.category == "content")
+            .expect("content category");
+        assert_eq!(content.amount, 1200);
+        assert_eq!(st.lines[1].content_hash, Some(hex::encode([8u8; 32])));
+    }
+
+    #[test]
+    fn test_statement_epoch_range_and_redaction() {
+        let conn = test_db();
+        seed_history(&conn);
+
+        let opts = StatementOptions {
+            from_epoch: Some(1),
+            to_epoch: Some(1),
+            redact_counterparties: true,
+        };
+        let st = statement(&conn, &opts).expect("statement");
+        assert_eq!(st.lines.len(), 3);
+        assert!(st
+            .lines
+            .iter()
+            .all(|l| l.counterparty_pik.is_none() && l.content_hash.is_none()));
+    }
+
+    #[test]
+    fn test_statement_render() {
+        let conn = test_db();
+        seed_history(&conn);
+        let st = statement(&conn, &StatementOptions::default()).expect("statement");
+
+        let csv = st.render(StatementFormat::Csv).expect("csv");
+        let mut rows = csv.lines();
+        assert_eq!(rows.next(), Some(STATEMENT_CSV_HEADER));
+        assert_eq!(rows.count(), 4);
+
+        let json = st.render(StatementFormat::Json).expect("json");
+        let value: serde_json::Value = serde_json::from_str(&json).expect("parse");
+        assert_eq!(value["totals"]["fees"], 1);
+        assert_eq!(value["epochs"][1]["outgoing"], 800);
+    }
+
     #[test]
     fn test_transaction_history() {
         let conn = test_db();
@@ -153,4 +554,25 @@ mod tests {
         assert_eq!(txs.len(), 2);
         assert_eq!(txs[0].tx_type, "mint"); // Most recent first
     }
+
+    #[test]
+    fn test_ledger_flows_for_epoch_window() {
+        let conn = test_db();
+        insert_token(&conn, &[1u8; 16], 1000, &[10u8; 32], 50).expect("insert");
+        insert_token(&conn, &[2u8; 16], 200, &[20u8; 32], 120).expect("insert");
+        insert_token(&conn, &[3u8; 16], 300, &[30u8; 32], 250).expect("insert");
+        spend_token(&conn, &[1u8; 16], 150).expect("spend");
+        record_transaction(&conn, &[1u8; 32], "receive", 200, 1, 120).expect("record");
+        record_transaction(&conn, &[2u8; 32], "purchase", 1000, 1, 150).expect("record");
+
+        let flows = ledger_flows(&conn, 1, 100, 200).expect("flows");
+        assert_eq!(flows.received, vec![200]);
+        assert_eq!(flows.recorded_incoming, 200);
+        assert_eq!(flows.spent, 1000);
+        assert_eq!(flows.closing_balance, 200);
+
+        // Spent after the window closed, so still held at its end
+        let flows = ledger_flows(&conn, 0, 0, 100).expect("flows");
+        assert_eq!(flows.closing_balance, 1000);
+    }
 }
@@ -0,0 +1,161 @@
+//! Whisper delivery state query functions.
+//!
+//! Only delivery metadata is stored here; message bodies never touch the
+//! database (Hard Rule 53).
+
+use ochra_types::whisper::WhisperDeliveryState;
+use rusqlite::Connection;
+
+use crate::{DbError, Result};
+
+/// Insert or update the delivery state of a message.
+pub fn record(
+    conn: &Connection,
+    session_id: &[u8; 16],
+    sequence: u64,
+    state: WhisperDeliveryState,
+    attempts: u32,
+    now: u64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO whisper_delivery (session_id, sequence, state, attempts, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+         ON CONFLICT(session_id, sequence) DO UPDATE SET
+            state = excluded.state,
+            attempts = excluded.attempts,
+            updated_at = excluded.updated_at",
+        rusqlite::params![
+            session_id.as_slice(),
+            sequence as i64,
+            state.as_str(),
+            attempts as i64,
+            now as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Delivery states of a session's messages, in sequence order.
+pub fn list_session(conn: &Connection, session_id: &[u8; 16]) -> Result<Vec<DeliveryRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT sequence, state, attempts, created_at, updated_at
+         FROM whisper_delivery WHERE session_id = ?1 ORDER BY sequence",
+    )?;
+    let rows = stmt
+        .query_map([session_id.as_slice()], |row| {
+            Ok((
+                row.get::<_, i64>(0)? as u64,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)? as u32,
+                row.get::<_, i64>(3)? as u64,
+                row.get::<_, i64>(4)? as u64,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    rows.into_iter()
+        .map(|(sequence, state, attempts, created_at, updated_at)| {
+            let state = WhisperDeliveryState::parse(&state)
+                .ok_or_else(|| DbError::Serialization(format!("unknown delivery state {state}")))?;
+            Ok(DeliveryRow {
+                sequence,
+                state,
+                attempts,
+                created_at,
+                updated_at,
+            })
+        })
+        .collect()
+}
+
+/// Mark every pending message failed.
+///
+/// Run at startup: the ciphertext needed for resends did not survive the
+/// restart.
+pub fn fail_pending(conn: &Connection, now: u64) -> Result<usize> {
+    let n = conn.execute(
+        "UPDATE whisper_delivery SET state = 'failed', updated_at = ?1
+         WHERE state IN ('queued', 'sent')",
+        [now as i64],
+    )?;
+    Ok(n)
+}
+
+/// Drop a closed session's delivery states.
+pub fn delete_session(conn: &Connection, session_id: &[u8; 16]) -> Result<usize> {
+    let n = conn.execute(
+        "DELETE FROM whisper_delivery WHERE session_id = ?1",
+        [session_id.as_slice()],
+    )?;
+    Ok(n)
+}
+
+/// A persisted delivery state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeliveryRow {
+    pub sequence: u64,
+    pub state: WhisperDeliveryState,
+    pub attempts: u32,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        crate::open_memory().expect("open test db")
+    }
+
+    #[test]
+    fn test_record_and_list() {
+        let conn = test_db();
+        let session = [1u8; 16];
+        record(&conn, &session, 2, WhisperDeliveryState::Queued, 0, 100).expect("record");
+        record(&conn, &session, 1, WhisperDeliveryState::Sent, 1, 100).expect("record");
+        record(&conn, &session, 1, WhisperDeliveryState::Delivered, 1, 105).expect("update");
+
+        let rows = list_session(&conn, &session).expect("list");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].sequence, 1);
+        assert_eq!(rows[0].state, WhisperDeliveryState::Delivered);
+        assert_eq!(rows[0].created_at, 100);
+        assert_eq!(rows[0].updated_at, 105);
+        assert!(list_session(&conn, &[2u8; 16]).expect("list").is_empty());
+    }
+
+    #[test]
+    fn test_fail_pending_on_restart() {
+        let conn = test_db();
+        let session = [1u8; 16];
+        record(&conn, &session, 1, WhisperDeliveryState::Read, 1, 100).expect("record");
+        record(&conn, &session, 2, WhisperDeliveryState::Sent, 3, 100).expect("record");
+        record(&conn, &session, 3, WhisperDeliveryState::Queued, 0, 100).expect("record");
+
+        assert_eq!(fail_pending(&conn, 200).expect("fail"), 2);
+        let states: Vec<_> = list_session(&conn, &session)
+            .expect("list")
+            .into_iter()
+            .map(|r| r.state)
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                WhisperDeliveryState::Read,
+                WhisperDeliveryState::Failed,
+                WhisperDeliveryState::Failed,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delete_session() {
+        let conn = test_db();
+        record(&conn, &[1u8; 16], 1, WhisperDeliveryState::Sent, 1, 100).expect("record");
+        record(&conn, &[2u8; 16], 1, WhisperDeliveryState::Sent, 1, 100).expect("record");
+
+        assert_eq!(delete_session(&conn, &[1u8; 16]).expect("delete"), 1);
+        assert!(list_session(&conn, &[1u8; 16]).expect("list").is_empty());
+        assert_eq!(list_session(&conn, &[2u8; 16]).expect("list").len(), 1);
+    }
+}
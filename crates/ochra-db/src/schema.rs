@@ -232,3 +232,271 @@ CREATE TABLE IF NOT EXISTS pending_timelocks (
     PRIMARY KEY (action, target_id)
 );
 "#;
+
+/// Migration to v2: resumable downloads.
+pub const MIGRATION_V2: &str = r#"
+CREATE TABLE IF NOT EXISTS downloads (
+    content_hash BLOB PRIMARY KEY,
+    destination TEXT NOT NULL,
+    leaf_hashes BLOB NOT NULL,
+    total_size_bytes INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active',
+    started_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS download_chunks (
+    content_hash BLOB NOT NULL REFERENCES downloads(content_hash) ON DELETE CASCADE,
+    chunk_index INTEGER NOT NULL,
+    provider BLOB NOT NULL,
+    completed_at INTEGER NOT NULL,
+    PRIMARY KEY (content_hash, chunk_index)
+);
+"#;
+
+/// Migration to v3: hash-chained audit log.
+pub const MIGRATION_V3: &str = r#"
+CREATE TABLE IF NOT EXISTS audit_log (
+    seq INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    details TEXT NOT NULL,
+    prev_hash BLOB NOT NULL,
+    entry_hash BLOB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS audit_anchors (
+    seq INTEGER PRIMARY KEY REFERENCES audit_log(seq),
+    head_hash BLOB NOT NULL,
+    anchored_at INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v4: greylisted and blacklisted peers.
+pub const MIGRATION_V4: &str = r#"
+CREATE TABLE IF NOT EXISTS peer_standings (
+    peer_id BLOB PRIMARY KEY,
+    score REAL NOT NULL,
+    standing TEXT NOT NULL,
+    banned_until INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v5: Whisper delivery states.
+///
+/// Holds delivery metadata only. Message bodies stay RAM-only (Hard Rule 53).
+pub const MIGRATION_V5: &str = r#"
+CREATE TABLE IF NOT EXISTS whisper_delivery (
+    session_id BLOB NOT NULL,
+    sequence INTEGER NOT NULL,
+    state TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (session_id, sequence)
+);
+"#;
+
+/// Migration to v6: per-contact presence privacy overrides.
+///
+/// NULL columns follow the global `presence_*` settings.
+pub const MIGRATION_V6: &str = r#"
+CREATE TABLE IF NOT EXISTS presence_overrides (
+    contact_pik BLOB PRIMARY KEY REFERENCES contacts(pik_hash) ON DELETE CASCADE,
+    typing_indicators INTEGER,
+    read_receipts INTEGER,
+    online_presence INTEGER
+);
+"#;
+
+/// Migration to v7: quorum key schedule for auditors (Section 12.8).
+pub const MIGRATION_V7: &str = r#"
+CREATE TABLE IF NOT EXISTS quorum_key_schedule (
+    activated_epoch INTEGER PRIMARY KEY,
+    group_pk BLOB NOT NULL,
+    threshold INTEGER NOT NULL,
+    quorum_size INTEGER NOT NULL,
+    endorsement BLOB NOT NULL
+);
+"#;
+
+/// Migration to v8: last-known-good bootstrap peers (Section 5.2).
+pub const MIGRATION_V8: &str = r#"
+CREATE TABLE IF NOT EXISTS bootstrap_peers (
+    pik_public_key BLOB PRIMARY KEY,
+    addr TEXT NOT NULL,
+    last_success INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_bootstrap_peers_success ON bootstrap_peers(last_success);
+"#;
+
+/// Migration to v9: contact key pinning and safety-number verification.
+///
+/// `verified_at` is cleared whenever a contact's keys change.
+pub const MIGRATION_V9: &str = r#"
+CREATE TABLE IF NOT EXISTS contact_keys (
+    contact_pik BLOB PRIMARY KEY REFERENCES contacts(pik_hash) ON DELETE CASCADE,
+    pik_public_key BLOB NOT NULL,
+    x25519_public_key BLOB NOT NULL,
+    first_seen INTEGER NOT NULL,
+    verified_at INTEGER,
+    key_changed_at INTEGER
+);
+"#;
+
+/// Migration to v10: verified PIK revocation certificates (Section 6.6).
+pub const MIGRATION_V10: &str = r#"
+CREATE TABLE IF NOT EXISTS revoked_piks (
+    pik_hash BLOB PRIMARY KEY,
+    certificate BLOB NOT NULL,
+    revoked_at INTEGER NOT NULL,
+    received_at INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v11: encrypted minting sessions for crash recovery.
+pub const MIGRATION_V11: &str = r#"
+CREATE TABLE IF NOT EXISTS mint_sessions (
+    session_id BLOB PRIMARY KEY,
+    status TEXT NOT NULL,
+    deadline INTEGER NOT NULL,
+    nonce BLOB NOT NULL,
+    encrypted_state BLOB NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v12: per-epoch supply audit reports.
+///
+/// `report` is the serialized reconciliation; `violations` is kept in the
+/// clear so failing epochs can be listed without decoding.
+pub const MIGRATION_V12: &str = r#"
+CREATE TABLE IF NOT EXISTS supply_audit (
+    epoch INTEGER PRIMARY KEY,
+    violations INTEGER NOT NULL,
+    report BLOB NOT NULL,
+    checked_at INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v13: owner-set storage quotas per Space.
+///
+/// A NULL limit is unlimited; a Space without a row has no quota.
+pub const MIGRATION_V13: &str = r#"
+CREATE TABLE IF NOT EXISTS group_quotas (
+    group_id BLOB PRIMARY KEY REFERENCES spaces(group_id) ON DELETE CASCADE,
+    max_total_bytes INTEGER,
+    max_member_bytes INTEGER,
+    max_item_bytes INTEGER,
+    updated_at INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v14: Argon2id parameters the PIK was wrapped with.
+///
+/// JSON `{m_cost, t_cost, p_cost}`; NULL for PIKs wrapped before
+/// calibration, at the fixed m=256MB, t=3, p=4.
+pub const MIGRATION_V14: &str = r#"
+ALTER TABLE pik ADD COLUMN argon2id_params TEXT;
+"#;
+
+/// Migration to v15: verified epoch beacons (Section 12.10).
+///
+/// The first valid beacon seen for an epoch is kept; later ones for the
+/// same epoch are ignored.
+pub const MIGRATION_V15: &str = r#"
+CREATE TABLE IF NOT EXISTS epoch_beacons (
+    epoch INTEGER PRIMARY KEY,
+    prev_beacon BLOB NOT NULL,
+    quorum_sig BLOB NOT NULL,
+    received_at INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v16: owner-signed Space policy history (Section 8.11).
+///
+/// Every accepted policy version is kept so members can audit changes;
+/// the highest version is the one in force.
+pub const MIGRATION_V16: &str = r#"
+CREATE TABLE IF NOT EXISTS group_policies (
+    group_id BLOB NOT NULL REFERENCES spaces(group_id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    signed_policy BLOB NOT NULL,
+    signer_pk BLOB NOT NULL,
+    received_at INTEGER NOT NULL,
+    PRIMARY KEY (group_id, version)
+);
+"#;
+
+/// Migration to v17: Space announcements (Section 8.12).
+///
+/// Announcements are stored signed so they can be served to members
+/// backfilling gaps; `read_at` drives the unread counts shown in the UI.
+pub const MIGRATION_V17: &str = r#"
+CREATE TABLE IF NOT EXISTS announcements (
+    group_id BLOB NOT NULL REFERENCES spaces(group_id) ON DELETE CASCADE,
+    seq INTEGER NOT NULL,
+    signed_announcement BLOB NOT NULL,
+    is_post INTEGER NOT NULL,
+    received_at INTEGER NOT NULL,
+    read_at INTEGER,
+    PRIMARY KEY (group_id, seq)
+);
+"#;
+
+/// Migration to v18: content discovery tags (Section 16.8).
+///
+/// One row per normalized tag of a catalog item, indexed by value so
+/// catalog facets and tag filters are computed without parsing
+/// `content_catalog.tags`.
+pub const MIGRATION_V18: &str = r#"
+CREATE TABLE IF NOT EXISTS content_tags (
+    content_hash BLOB NOT NULL REFERENCES content_catalog(content_hash) ON DELETE CASCADE,
+    group_id BLOB NOT NULL,
+    namespace TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (content_hash, namespace, value)
+);
+
+CREATE INDEX IF NOT EXISTS idx_content_tags_facet ON content_tags(group_id, namespace, value);
+"#;
+
+/// Migration to v19: service receipt acknowledgement outbox (Section 14.7).
+///
+/// Acknowledgements this node owes to servers are written here before they
+/// are batched and deleted only once the server settles them, so a crash
+/// between serving and flushing does not cost the server its receipts.
+pub const MIGRATION_V19: &str = r#"
+CREATE TABLE IF NOT EXISTS receipt_ack_outbox (
+    ack_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_node_id BLOB NOT NULL,
+    ack BLOB NOT NULL,
+    queued_at INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v20: granted anonymous refunds (Section 16.3).
+///
+/// A refund validator records each approved claim's nullifier hash and the
+/// purchase's refund commitment together, so neither can be refunded again,
+/// including after a restart.
+pub const MIGRATION_V20: &str = r#"
+CREATE TABLE IF NOT EXISTS refund_claims (
+    nullifier_hash BLOB PRIMARY KEY,
+    purchase_commitment BLOB NOT NULL UNIQUE,
+    epoch INTEGER NOT NULL
+);
+"#;
+
+/// Migration to v21: complete service receipts (Section 14.7).
+///
+/// Receipts are stored whole so that unflushed ones can be aggregated again
+/// after a restart and their requester acknowledgements re-verified.
+pub const MIGRATION_V21: &str = r#"
+ALTER TABLE abr_service_receipts ADD COLUMN server_node_id BLOB NOT NULL DEFAULT x'';
+ALTER TABLE abr_service_receipts ADD COLUMN requester_circuit_id BLOB NOT NULL DEFAULT x'';
+ALTER TABLE abr_service_receipts ADD COLUMN requester_key BLOB NOT NULL DEFAULT x'';
+ALTER TABLE abr_service_receipts ADD COLUMN nonce BLOB NOT NULL DEFAULT x'';
+"#;
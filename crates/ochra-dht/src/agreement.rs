@@ -0,0 +1,432 @@
+//! Bootstrap agreement across independent seed sources (Section 5.2).
+//!
+//! A single seed source can hand a new node nothing but attacker-run peers,
+//! and every lookup the node makes afterwards is then answered by the
+//! attacker (an eclipse). [`bootstrap_with_agreement`] takes the seed sets
+//! of several independent operators (see [`resolve_seed_sets`]) and, for
+//! each, contacts its seeds and runs a `FIND_NODE` for the local ID. The
+//! closest nodes each source leads to are its *view* of our neighbourhood.
+//!
+//! Honest sources lead to largely the same neighbourhood, so views are
+//! compared pairwise by overlap (shared node IDs as a percentage of the
+//! smaller view). The largest group of views that all overlap the same
+//! reference view by at least [`AgreementPolicy::min_overlap_pct`] is the
+//! consensus. The routing table is populated from the consensus only, and
+//! only if it spans at least [`AgreementPolicy::min_agreeing_sources`]
+//! sources. Views outside it are reported as [`DivergentView`]s: a sign
+//! that some source is steering nodes into a fake neighbourhood; the
+//! daemon surfaces them as a `BootstrapViewsDiverged` event.
+//!
+//! [`resolve_seed_sets`]: crate::seeds::resolve_seed_sets
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::bootstrap::{contact_seed, BootstrapConfig, BootstrapTransport};
+use crate::kademlia::{AddNodeResult, NodeId, NodeInfo, RoutingTable};
+use crate::seeds::ResolvedSeeds;
+use crate::{DhtError, Result, K};
+
+/// Default number of independent sources that must agree.
+pub const DEFAULT_MIN_AGREEING_SOURCES: usize = 2;
+
+/// Default overlap for two views to agree.
+pub const DEFAULT_MIN_OVERLAP_PCT: u8 = 50;
+
+/// How much agreement a bootstrap requires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgreementPolicy {
+    /// Sources whose views must agree before the routing table is accepted.
+    pub min_agreeing_sources: usize,
+    /// Percentage of the smaller view two views must share to agree.
+    pub min_overlap_pct: u8,
+}
+
+impl Default for AgreementPolicy {
+    fn default() -> Self {
+        Self {
+            min_agreeing_sources: DEFAULT_MIN_AGREEING_SOURCES,
+            min_overlap_pct: DEFAULT_MIN_OVERLAP_PCT,
+        }
+    }
+}
+
+/// A source whose view does not match the consensus.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DivergentView {
+    /// The source's operator.
+    pub operator: String,
+    /// Overlap with the consensus reference view, in percent.
+    pub overlap_pct: u8,
+}
+
+/// Outcome of [`bootstrap_with_agreement`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AgreementReport {
+    /// Whether enough sources agreed and the routing table was populated.
+    pub accepted: bool,
+    /// Operators whose views formed the consensus.
+    pub agreeing: Vec<String>,
+    /// Operators whose views did not.
+    pub divergent: Vec<DivergentView>,
+    /// Operators with no view: no seed answered or the lookup failed.
+    pub unavailable: Vec<String>,
+    /// Nodes added to the routing table.
+    pub peers_added: usize,
+}
+
+impl AgreementReport {
+    /// Whether any source's view diverged from the consensus.
+    pub fn diverged(&self) -> bool {
+        !self.divergent.is_empty()
+    }
+}
+
+/// One source's contacted seeds and the neighbourhood they led to.
+struct SourceView {
+    operator: String,
+    seeds: Vec<NodeInfo>,
+    closest: Vec<NodeInfo>,
+    ids: HashSet<NodeId>,
+}
+
+/// Overlap of two views as a percentage of the smaller one.
+fn overlap_pct(a: &HashSet<NodeId>, b: &HashSet<NodeId>) -> u8 {
+    let smaller = a.len().min(b.len());
+    if smaller == 0 {
+        return 0;
+    }
+    let shared = a.intersection(b).count();
+    (shared * 100 / smaller) as u8
+}
+
+/// Bootstrap from several independent seed sets, accepting the routing
+/// table only when enough of their views agree.
+///
+/// Retries and timeouts come from `config`; its `seed_nodes` are not used.
+///
+/// # Errors
+///
+/// - [`DhtError::BootstrapFailed`] if fewer seed sets are given than
+///   `policy.min_agreeing_sources`
+pub async fn bootstrap_with_agreement<T: BootstrapTransport>(
+    sets: &[ResolvedSeeds],
+    policy: &AgreementPolicy,
+    config: &BootstrapConfig,
+    routing_table: &mut RoutingTable,
+    transport: &T,
+) -> Result<AgreementReport> {
+    if sets.len() < policy.min_agreeing_sources {
+        return Err(DhtError::BootstrapFailed(format!(
+            "{} independent seed sources, {} required",
+            sets.len(),
+            policy.min_agreeing_sources
+        )));
+    }
+
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let local_id = *routing_table.local_id();
+    let mut views: Vec<SourceView> = Vec::new();
+    let mut unavailable = Vec::new();
+
+    for set in sets {
+        let operator = set.source.operator();
+        let mut seeds = Vec::new();
+        for seed in &set.seeds {
+            if let Some(info) = contact_seed(transport, seed, config.max_retries, timeout).await {
+                seeds.push(info);
+            }
+        }
+        if seeds.is_empty() {
+            warn!(operator = %operator, "No seed from source responded");
+            unavailable.push(operator);
+            continue;
+        }
+        match transport.find_node(local_id, seeds.clone(), timeout).await {
+            Ok(mut closest) => {
+                closest.retain(|n| n.node_id != local_id);
+                closest.sort_by_key(|n| RoutingTable::xor_distance(&n.node_id, &local_id));
+                closest.dedup_by_key(|n| n.node_id);
+                closest.truncate(K);
+                if closest.is_empty() {
+                    unavailable.push(operator);
+                    continue;
+                }
+                let ids = closest.iter().map(|n| n.node_id).collect();
+                views.push(SourceView {
+                    operator,
+                    seeds,
+                    closest,
+                    ids,
+                });
+            }
+            Err(e) => {
+                warn!(operator = %operator, error = %e, "Self-lookup via source failed");
+                unavailable.push(operator);
+            }
+        }
+    }
+
+    // The reference view is the one the most other views agree with.
+    let agreeing_with = |i: usize| -> Vec<usize> {
+        (0..views.len())
+            .filter(|&j| overlap_pct(&views[i].ids, &views[j].ids) >= policy.min_overlap_pct)
+            .collect()
+    };
+    let reference = (0..views.len()).max_by_key(|&i| (agreeing_with(i).len(), usize::MAX - i));
+
+    let mut report = AgreementReport {
+        accepted: false,
+        agreeing: Vec::new(),
+        divergent: Vec::new(),
+        unavailable,
+        peers_added: 0,
+    };
+    let Some(reference) = reference else {
+        return Ok(report);
+    };
+    let consensus = agreeing_with(reference);
+    for (i, view) in views.iter().enumerate() {
+        if consensus.contains(&i) {
+            report.agreeing.push(view.operator.clone());
+        } else {
+            report.divergent.push(DivergentView {
+                operator: view.operator.clone(),
+                overlap_pct: overlap_pct(&views[reference].ids, &view.ids),
+            });
+        }
+    }
+    if report.diverged() {
+        warn!(
+            divergent = ?report.divergent,
+            agreeing = ?report.agreeing,
+            "Seed sources lead to divergent views; possible eclipse attempt"
+        );
+    }
+
+    report.accepted = consensus.len() >= policy.min_agreeing_sources;
+    if !report.accepted {
+        return Ok(report);
+    }
+    for &i in &consensus {
+        for node in views[i].seeds.iter().chain(&views[i].closest) {
+            if matches!(
+                routing_table.add_node(node.clone()),
+                AddNodeResult::Inserted
+            ) {
+                report.peers_added += 1;
+            }
+        }
+    }
+    info!(
+        agreeing = report.agreeing.len(),
+        peers_added = report.peers_added,
+        "Bootstrap accepted by independent seed sources"
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+
+    use super::*;
+    use crate::bootstrap::SeedNode;
+    use crate::seeds::SeedSource;
+
+    /// Seeds answer pings; a lookup returns the neighbourhood assigned to
+    /// the first seed it starts from.
+    struct FakeNetwork {
+        neighbourhoods: HashMap<SocketAddr, Vec<NodeInfo>>,
+    }
+
+    impl BootstrapTransport for FakeNetwork {
+        async fn ping(
+            &self,
+            addr: SocketAddr,
+            _timeout: Duration,
+        ) -> std::result::Result<NodeInfo, Box<dyn std::error::Error + Send + Sync>> {
+            if self.neighbourhoods.contains_key(&addr) {
+                Ok(node(addr.port() as u8, addr))
+            } else {
+                Err("unreachable".into())
+            }
+        }
+
+        async fn find_node(
+            &self,
+            _target: NodeId,
+            initial_nodes: Vec<NodeInfo>,
+            _timeout: Duration,
+        ) -> std::result::Result<Vec<NodeInfo>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(self.neighbourhoods[&initial_nodes[0].addr].clone())
+        }
+    }
+
+    fn node(id: u8, addr: SocketAddr) -> NodeInfo {
+        NodeInfo {
+            node_id: [id; 32],
+            addr,
+            alt_addr: None,
+            pik_public_key: [id; 32],
+            x25519_public_key: [id; 32],
+        }
+    }
+
+    fn addr(host: u8, port: u16) -> SocketAddr {
+        SocketAddr::from(([198, 51, 100, host], port))
+    }
+
+    /// Nodes `ids`, one per /24 so diversity limits do not interfere.
+    fn neighbourhood(ids: impl IntoIterator<Item = u8>) -> Vec<NodeInfo> {
+        ids.into_iter()
+            .map(|id| node(id, SocketAddr::from(([10, id, 0, 1], 4433))))
+            .collect()
+    }
+
+    fn seed_set(domain: &str, seed_addr: SocketAddr) -> ResolvedSeeds {
+        ResolvedSeeds {
+            source: SeedSource::Dns {
+                name: format!("seeds.{domain}"),
+            },
+            seeds: vec![SeedNode {
+                addr: seed_addr,
+                pik_public_key: [0; 32],
+            }],
+            list_version: None,
+        }
+    }
+
+    fn config() -> BootstrapConfig {
+        BootstrapConfig {
+            max_retries: 1,
+            timeout_secs: 1,
+            ..BootstrapConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agreeing_sources_populate_table() {
+        let network = FakeNetwork {
+            neighbourhoods: HashMap::from([
+                (addr(1, 201), neighbourhood(10..20)),
+                (addr(2, 202), neighbourhood(12..22)),
+            ]),
+        };
+        let sets = vec![
+            seed_set("a.example", addr(1, 201)),
+            seed_set("b.example", addr(2, 202)),
+        ];
+        let mut table = RoutingTable::new([0xFF; 32]);
+        let report = bootstrap_with_agreement(
+            &sets,
+            &AgreementPolicy::default(),
+            &config(),
+            &mut table,
+            &network,
+        )
+        .await
+        .expect("bootstrap");
+
+        assert!(report.accepted);
+        assert!(!report.diverged());
+        assert_eq!(report.agreeing, vec!["a.example", "b.example"]);
+        // 12 distinct neighbours and 2 seeds.
+        assert_eq!(report.peers_added, 14);
+        assert_eq!(table.len(), 14);
+    }
+
+    #[tokio::test]
+    async fn test_divergent_source_excluded_and_reported() {
+        let network = FakeNetwork {
+            neighbourhoods: HashMap::from([
+                (addr(1, 201), neighbourhood(10..20)),
+                (addr(2, 202), neighbourhood(11..21)),
+                (addr(3, 203), neighbourhood(100..110)),
+            ]),
+        };
+        let sets = vec![
+            seed_set("a.example", addr(1, 201)),
+            seed_set("evil.example", addr(3, 203)),
+            seed_set("b.example", addr(2, 202)),
+        ];
+        let mut table = RoutingTable::new([0xFF; 32]);
+        let report = bootstrap_with_agreement(
+            &sets,
+            &AgreementPolicy::default(),
+            &config(),
+            &mut table,
+            &network,
+        )
+        .await
+        .expect("bootstrap");
+
+        assert!(report.accepted);
+        assert_eq!(
+            report.divergent,
+            vec![DivergentView {
+                operator: "evil.example".to_string(),
+                overlap_pct: 0,
+            }]
+        );
+        assert!(table
+            .find_closest(&[100; 32], K)
+            .iter()
+            .all(|n| !(100..110).contains(&n.node_id[0]) && n.node_id[0] != 203));
+    }
+
+    #[tokio::test]
+    async fn test_no_consensus_leaves_table_empty() {
+        let network = FakeNetwork {
+            neighbourhoods: HashMap::from([
+                (addr(1, 201), neighbourhood(10..20)),
+                (addr(3, 203), neighbourhood(100..110)),
+            ]),
+        };
+        let sets = vec![
+            seed_set("a.example", addr(1, 201)),
+            seed_set("evil.example", addr(3, 203)),
+            seed_set("down.example", addr(9, 209)),
+        ];
+        let mut table = RoutingTable::new([0xFF; 32]);
+        let report = bootstrap_with_agreement(
+            &sets,
+            &AgreementPolicy::default(),
+            &config(),
+            &mut table,
+            &network,
+        )
+        .await
+        .expect("bootstrap");
+
+        assert!(!report.accepted);
+        assert!(report.diverged());
+        assert_eq!(report.unavailable, vec!["down.example"]);
+        assert!(table.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_too_few_sources_rejected() {
+        let network = FakeNetwork {
+            neighbourhoods: HashMap::new(),
+        };
+        let sets = vec![seed_set("a.example", addr(1, 201))];
+        let mut table = RoutingTable::new([0xFF; 32]);
+        assert!(matches!(
+            bootstrap_with_agreement(
+                &sets,
+                &AgreementPolicy::default(),
+                &config(),
+                &mut table,
+                &network,
+            )
+            .await,
+            Err(DhtError::BootstrapFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,106 @@
+//! Epoch beacon records.
+//!
+//! The quorum publishes each epoch's [`EpochBeacon`] under its group key
+//! with salt [`BEACON_SALT`] and `seq` set to the epoch, so the record at
+//! the quorum's beacon address always holds the newest beacon. The record
+//! type registry only checks the shape; the beacon signature and chain are
+//! checked by `ochra_frost::beacon` against the key schedule.
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_types::network::EpochBeacon;
+
+use crate::bep44::{create_mutable_record, DhtRecord};
+use crate::{DhtError, Result};
+
+/// Salt of epoch beacon records.
+pub const BEACON_SALT: &[u8] = b"epoch-beacon";
+
+/// Publish `beacon` under the quorum key.
+///
+/// # Errors
+///
+/// - [`DhtError::Serialization`] if the beacon cannot be encoded
+pub fn create_beacon_record(quorum_key: &SigningKey, beacon: &EpochBeacon) -> Result<DhtRecord> {
+    let mut value = Vec::new();
+    ciborium::into_writer(beacon, &mut value)
+        .map_err(|e| DhtError::Serialization(e.to_string()))?;
+    create_mutable_record(quorum_key, BEACON_SALT, u64::from(beacon.epoch), value)
+}
+
+/// The beacon of a beacon record published by `quorum_pk`.
+///
+/// # Errors
+///
+/// - [`DhtError::Serialization`] if the record is not a beacon record from
+///   the quorum, or its sequence number is not the beacon's epoch
+pub fn beacon_from_record(record: &DhtRecord, quorum_pk: &[u8; 32]) -> Result<EpochBeacon> {
+    let DhtRecord::Mutable {
+        public_key,
+        salt,
+        seq,
+        value,
+        ..
+    } = record
+    else {
+        return Err(DhtError::Serialization("beacon records are mutable".into()));
+    };
+    if public_key != quorum_pk || salt.as_slice() != BEACON_SALT {
+        return Err(DhtError::Serialization(
+            "not the quorum's beacon record".into(),
+        ));
+    }
+    let beacon: EpochBeacon = ciborium::from_reader(value.as_slice())
+        .map_err(|e| DhtError::Serialization(e.to_string()))?;
+    if u64::from(beacon.epoch) != *seq {
+        return Err(DhtError::Serialization(format!(
+            "seq {seq} is not beacon epoch {}",
+            beacon.epoch
+        )));
+    }
+    Ok(beacon)
+}
+
+#[cfg(test)]
+mod tests {
+    use ochra_crypto::ed25519::KeyPair;
+
+    use super::*;
+    use crate::record_types::RecordTypeRegistry;
+
+    fn beacon(epoch: u32) -> EpochBeacon {
+        EpochBeacon {
+            epoch,
+            prev_beacon: [1; 32],
+            quorum_sig: [2; 64],
+        }
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let quorum = KeyPair::generate();
+        let pk = quorum.verifying_key.to_bytes();
+        let record = create_beacon_record(&quorum.signing_key, &beacon(9)).expect("record");
+        assert!(RecordTypeRegistry::standard().validate(&record).is_ok());
+        assert_eq!(beacon_from_record(&record, &pk).expect("beacon"), beacon(9));
+
+        let other = KeyPair::generate().verifying_key.to_bytes();
+        assert!(beacon_from_record(&record, &other).is_err());
+    }
+
+    #[test]
+    fn test_seq_must_be_epoch() {
+        let quorum = KeyPair::generate();
+        let mut value = Vec::new();
+        ciborium::into_writer(&beacon(9), &mut value).expect("encode");
+        let record =
+            create_mutable_record(&quorum.signing_key, BEACON_SALT, 10, value).expect("record");
+        assert!(beacon_from_record(&record, &quorum.verifying_key.to_bytes()).is_err());
+        assert!(matches!(
+            RecordTypeRegistry::standard().validate(&record),
+            Err(DhtError::InvalidRecord {
+                record_type: "epoch_beacon",
+                ..
+            })
+        ));
+    }
+}
@@ -9,10 +9,20 @@
 //!   Updates must have a strictly increasing sequence number.
 //!
 //! Records are stored in a [`RecordStore`] with automatic expiration.
+//! Puts from remote peers go through [`RecordStore::put_from`], which
+//! enforces the storage quotas described in [`crate::quota`]. Both check
+//! records of registered types against [`crate::record_types`]. Values
+//! large enough to shard go through [`RecordStore::put_value`] and come
+//! back whole from [`RecordStore::get_value`] (see [`crate::sharding`]).
 
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
+use crate::kademlia::NodeInfo;
+use crate::quota::{Admission, QuotaConfig, QuotaRejection, QuotaState};
+use crate::record_types::RecordTypeRegistry;
+use crate::sharding::{self, RecordShard, ShardManifest, ShardPlacement, ShardingPolicy};
 use crate::{DhtError, Result, MAX_RECORD_SIZE};
 
 /// Default record time-to-live (2 hours).
@@ -73,6 +83,14 @@ impl DhtRecord {
         self.value().len()
     }
 
+    /// The publisher key of a mutable record.
+    pub fn publisher(&self) -> Option<[u8; 32]> {
+        match self {
+            DhtRecord::Immutable { .. } => None,
+            DhtRecord::Mutable { public_key, .. } => Some(*public_key),
+        }
+    }
+
     /// Validate the record.
     ///
     /// For immutable records, checks the size constraint.
@@ -111,7 +129,7 @@ impl DhtRecord {
 }
 
 /// Build the byte string that is signed for mutable records: `salt || seq_be || value`.
-fn build_signed_data(salt: &[u8], seq: u64, value: &[u8]) -> Vec<u8> {
+pub(crate) fn build_signed_data(salt: &[u8], seq: u64, value: &[u8]) -> Vec<u8> {
     let mut data = Vec::with_capacity(salt.len() + 8 + value.len());
     data.extend_from_slice(salt);
     data.extend_from_slice(&seq.to_be_bytes());
@@ -128,6 +146,13 @@ struct StoreEntry {
     stored_at: Instant,
     /// Time-to-live duration for this entry.
     ttl: Duration,
+    /// Key the entry's bytes are charged to.
+    publisher: [u8; 32],
+    /// Whether this node stores the record as one of its closest nodes,
+    /// rather than as a cache. Replicated entries are never evicted for space.
+    replicated: bool,
+    /// Last time the entry was stored or read, for LRU eviction.
+    last_access: Cell<Instant>,
 }
 
 impl StoreEntry {
@@ -137,6 +162,9 @@ impl StoreEntry {
     }
 }
 
+/// Publisher charged for local immutable records.
+const LOCAL_PUBLISHER: [u8; 32] = [0u8; 32];
+
 /// In-memory record store with expiration support.
 ///
 /// Stores DHT records keyed by their storage key. Mutable records enforce
@@ -147,15 +175,16 @@ pub struct RecordStore {
     entries: HashMap<[u8; 32], StoreEntry>,
     /// Default TTL for new records.
     default_ttl: Duration,
+    /// Byte accounting and admission state.
+    quota: QuotaState,
+    /// Validators for records of known types.
+    record_types: RecordTypeRegistry,
 }
 
 impl RecordStore {
     /// Create a new record store with the default TTL.
     pub fn new() -> Self {
-        Self {
-            entries: HashMap::new(),
-            default_ttl: Duration::from_secs(DEFAULT_TTL_SECS),
-        }
+        Self::with_ttl(Duration::from_secs(DEFAULT_TTL_SECS))
     }
 
     /// Create a new record store with a custom default TTL.
@@ -163,49 +192,265 @@ impl RecordStore {
         Self {
             entries: HashMap::new(),
             default_ttl: ttl,
+            quota: QuotaState::new(QuotaConfig::default()),
+            record_types: RecordTypeRegistry::standard(),
         }
     }
 
-    /// Store a record. Validates the record before storing.
+    /// Create a new record store with the default TTL and custom quotas.
+    pub fn with_quota(config: QuotaConfig) -> Self {
+        let mut store = Self::new();
+        store.quota = QuotaState::new(config);
+        store
+    }
+
+    /// The record types this store validates, for registering more.
+    pub fn record_types_mut(&mut self) -> &mut RecordTypeRegistry {
+        &mut self.record_types
+    }
+
+    /// Store a local record. Validates the record, and its type's rules if
+    /// it has a registered type, before storing.
     ///
     /// For mutable records, enforces that the sequence number is strictly
-    /// greater than any existing record at the same key.
+    /// greater than any existing record at the same key. Local records count
+    /// towards the byte totals but are not subject to quotas and are never
+    /// evicted for space.
     pub fn put(&mut self, record: DhtRecord) -> Result<()> {
         record.validate()?;
+        self.record_types
+            .validate_update(&record, self.live(&record.storage_key()))?;
+        self.check_sequence(&record)?;
+        let publisher = record.publisher().unwrap_or(LOCAL_PUBLISHER);
+        self.insert(record, publisher, true, Instant::now());
+        Ok(())
+    }
+
+    /// Store a record put by remote peer `from`.
+    ///
+    /// `replicated` is true when this node is one of the closest nodes to
+    /// the key, false when it only caches the record.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::QuotaExceeded`] if `from` exceeded its put rate, a
+    ///   large value lacks valid admission, the publisher is over its cap,
+    ///   or not enough cached records can be evicted to make room
+    /// - [`DhtError::InvalidRecord`] if a record of a registered type is
+    ///   malformed
+    /// - any validation or sequence error from [`put`](Self::put)
+    pub fn put_from(
+        &mut self,
+        from: [u8; 32],
+        record: DhtRecord,
+        admission: &Admission,
+        replicated: bool,
+    ) -> Result<()> {
+        let now = Instant::now();
+        self.quota.take_put(from, now)?;
+        record.validate()?;
+        self.record_types
+            .validate_update(&record, self.live(&record.storage_key()))?;
+        self.check_sequence(&record)?;
 
         let key = record.storage_key();
+        let publisher = record.publisher().unwrap_or(from);
+        let size = record.value_len();
+        let existing = self
+            .entries
+            .get(&key)
+            .map(|e| (e.publisher, e.record.value_len()));
+
+        let mut publisher_used = self.quota.publisher_bytes(&publisher);
+        if let Some((old_publisher, old_size)) = existing {
+            if old_publisher == publisher {
+                publisher_used = publisher_used.saturating_sub(old_size);
+            }
+        }
+        let max = self.quota.config.max_bytes_per_publisher;
+        if publisher_used + size > max {
+            return Err(QuotaRejection::PublisherQuota {
+                used: publisher_used,
+                max,
+            }
+            .into());
+        }
 
-        // For mutable records, check sequence number ordering.
-        if let DhtRecord::Mutable { seq, .. } = &record {
-            if let Some(existing) = self.entries.get(&key) {
-                if !existing.is_expired() {
-                    if let DhtRecord::Mutable {
-                        seq: existing_seq, ..
-                    } = &existing.record
-                    {
-                        if *seq <= *existing_seq {
-                            return Err(DhtError::StaleSequence {
-                                got: *seq,
-                                have: *existing_seq,
-                            });
-                        }
-                    }
-                }
+        self.quota.admit(&record, admission)?;
+        self.make_room(&key, size)?;
+        self.insert(record, publisher, replicated, now);
+        Ok(())
+    }
+
+    /// Grant a single-use admission token for a large put.
+    pub fn grant_admission_token(&mut self, token: [u8; 32]) {
+        self.quota.grant_token(token);
+    }
+
+    /// Value bytes currently stored.
+    pub fn used_bytes(&self) -> usize {
+        self.quota.total_bytes()
+    }
+
+    /// Value bytes charged to `publisher`.
+    pub fn publisher_bytes(&self, publisher: &[u8; 32]) -> usize {
+        self.quota.publisher_bytes(publisher)
+    }
+
+    /// Evict least recently used cached records until `size` more bytes fit
+    /// under the global cap, counting the entry at `key` as replaced.
+    fn make_room(&mut self, key: &[u8; 32], size: usize) -> Result<()> {
+        let max = self.quota.config.max_total_bytes;
+        let replaced = self.entries.get(key).map_or(0, |e| e.record.value_len());
+        let needed = |total: usize| (total - replaced + size).saturating_sub(max);
+        if needed(self.quota.total_bytes()) == 0 {
+            return Ok(());
+        }
+
+        self.expire();
+        let mut candidates: Vec<([u8; 32], Instant, usize)> = self
+            .entries
+            .iter()
+            .filter(|(k, e)| !e.replicated && *k != key)
+            .map(|(k, e)| (*k, e.last_access.get(), e.record.value_len()))
+            .collect();
+        let evictable: usize = candidates.iter().map(|(_, _, len)| len).sum();
+        if needed(self.quota.total_bytes()) > evictable {
+            return Err(QuotaRejection::StoreFull.into());
+        }
+
+        candidates.sort_by_key(|(_, accessed, _)| *accessed);
+        let mut evicted = 0;
+        for (victim, _, _) in candidates {
+            if needed(self.quota.total_bytes()) == 0 {
+                break;
             }
+            self.remove(&victim);
+            evicted += 1;
         }
+        tracing::debug!("Evicted {evicted} cached DHT records for space");
+        Ok(())
+    }
 
+    /// Insert an entry, replacing and releasing any existing one.
+    fn insert(&mut self, record: DhtRecord, publisher: [u8; 32], replicated: bool, now: Instant) {
+        let key = record.storage_key();
+        self.remove(&key);
+        self.quota.charge(publisher, record.value_len());
         self.entries.insert(
             key,
             StoreEntry {
                 record,
-                stored_at: Instant::now(),
+                stored_at: now,
                 ttl: self.default_ttl,
+                publisher,
+                replicated,
+                last_access: Cell::new(now),
             },
         );
+    }
+
+    /// Remove an entry and release its bytes.
+    fn remove(&mut self, key: &[u8; 32]) -> Option<StoreEntry> {
+        let entry = self.entries.remove(key)?;
+        self.quota
+            .release(&entry.publisher, entry.record.value_len());
+        Some(entry)
+    }
 
+    /// The unexpired record stored under `key`.
+    fn live(&self, key: &[u8; 32]) -> Option<&DhtRecord> {
+        self.entries
+            .get(key)
+            .filter(|e| !e.is_expired())
+            .map(|e| &e.record)
+    }
+
+    /// Reject a mutable record whose sequence number does not advance.
+    fn check_sequence(&self, record: &DhtRecord) -> Result<()> {
+        if let DhtRecord::Mutable { seq, .. } = record {
+            if let Some(DhtRecord::Mutable {
+                seq: existing_seq, ..
+            }) = self.live(&record.storage_key())
+            {
+                if *seq <= *existing_seq {
+                    return Err(DhtError::StaleSequence {
+                        got: *seq,
+                        have: *existing_seq,
+                    });
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Sign and store a local value, sharding it under `policy`.
+    ///
+    /// Shards are stored as immutable records before the manifest. Returns
+    /// the value's storage key and, for a sharded value, the node among
+    /// `candidates` each shard should also be put to (see
+    /// [`place_shards`](crate::sharding::place_shards)).
+    ///
+    /// # Errors
+    ///
+    /// - any error from [`sign_value`](crate::sharding::sign_value) or
+    ///   [`put`](Self::put)
+    pub fn put_value(
+        &mut self,
+        signing_key: &ochra_crypto::ed25519::SigningKey,
+        salt: &[u8],
+        seq: u64,
+        value: Vec<u8>,
+        policy: &ShardingPolicy,
+        candidates: &[NodeInfo],
+    ) -> Result<([u8; 32], Vec<ShardPlacement>)> {
+        let (record, shards) = sharding::sign_value(signing_key, salt, seq, value, policy)?;
+        let key = record.storage_key();
+        let placements = match ShardManifest::from_bytes(record.value()) {
+            Some(manifest) if !shards.is_empty() => {
+                sharding::place_shards(&key, &manifest, candidates)
+            }
+            _ => Vec::new(),
+        };
+        for shard in &shards {
+            self.put(shard.to_record())?;
+        }
+        self.put(record)?;
+        Ok((key, placements))
+    }
+
+    /// The value stored under `key`, reassembled from the shards this
+    /// store holds if the record is a shard manifest, and checked against
+    /// the record's type.
+    ///
+    /// Returns `None` if the record does not exist or has expired.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InsufficientShards`] if fewer than `k` of the
+    ///   manifest's shards are held
+    /// - [`DhtError::InvalidRecord`] if the value is invalid for its type
+    pub fn get_value(&self, key: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+        let Some(record) = self.get(key) else {
+            return Ok(None);
+        };
+        let shards: Vec<RecordShard> = match ShardManifest::from_bytes(record.value()) {
+            Some(manifest) if matches!(record, DhtRecord::Mutable { .. }) => manifest
+                .shard_hashes
+                .iter()
+                .enumerate()
+                .filter_map(|(index, hash)| {
+                    self.get(hash).map(|shard| RecordShard {
+                        index: index as u8,
+                        data: shard.value().to_vec(),
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.record_types.resolve_value(record, &shards).map(Some)
+    }
+
     /// Retrieve a record by its storage key.
     ///
     /// Returns `None` if the record does not exist or has expired.
@@ -214,6 +459,7 @@ impl RecordStore {
             if entry.is_expired() {
                 None
             } else {
+                entry.last_access.set(Instant::now());
                 Some(&entry.record)
             }
         })
@@ -223,9 +469,17 @@ impl RecordStore {
     ///
     /// Returns the number of records removed.
     pub fn expire(&mut self) -> usize {
-        let before = self.entries.len();
-        self.entries.retain(|_, entry| !entry.is_expired());
-        let removed = before - self.entries.len();
+        let expired: Vec<[u8; 32]> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.is_expired())
+            .map(|(k, _)| *k)
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        self.quota.prune_buckets(Instant::now());
+        let removed = expired.len();
         if removed > 0 {
             tracing::debug!("Expired {removed} DHT records");
         }
@@ -452,4 +706,84 @@ mod tests {
         assert!(keys.contains(&k1));
         assert!(keys.contains(&k2));
     }
+
+    #[test]
+    fn test_record_store_rejects_malformed_typed_record() {
+        let mut store = RecordStore::new();
+        let kp = KeyPair::generate();
+        let record = create_mutable_record(
+            &kp.signing_key,
+            crate::record_types::HANDLE_DESCRIPTOR_SALT,
+            1,
+            b"not a descriptor".to_vec(),
+        )
+        .expect("create");
+        assert!(matches!(
+            store.put(record.clone()),
+            Err(DhtError::InvalidRecord { .. })
+        ));
+        assert!(matches!(
+            store.put_from([1; 32], record, &Admission::None, true),
+            Err(DhtError::InvalidRecord { .. })
+        ));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_sharded_value_round_trips_with_missing_shards() {
+        let mut store = RecordStore::new();
+        let kp = KeyPair::generate();
+        let policy = ShardingPolicy::default();
+        let value: Vec<u8> = (0..900u32).map(|i| (i * 7) as u8).collect();
+        let candidates: Vec<NodeInfo> = (1..=8u8)
+            .map(|i| NodeInfo {
+                node_id: [i; 32],
+                addr: std::net::SocketAddr::from(([10, i, 0, 1], 4433)),
+                alt_addr: None,
+                pik_public_key: [i; 32],
+                x25519_public_key: [i; 32],
+            })
+            .collect();
+
+        let (key, placements) = store
+            .put_value(
+                &kp.signing_key,
+                b"zzz",
+                1,
+                value.clone(),
+                &policy,
+                &candidates,
+            )
+            .expect("put");
+        assert_eq!(placements.len(), usize::from(policy.total_shards));
+        assert_eq!(store.len(), 1 + usize::from(policy.total_shards));
+        let manifest =
+            ShardManifest::from_bytes(store.get(&key).expect("manifest").value()).expect("sharded");
+
+        // Any k shards are enough
+        for hash in &manifest.shard_hashes[..4] {
+            store.remove(hash);
+        }
+        assert_eq!(store.get_value(&key).expect("get"), Some(value.clone()));
+        store.remove(&manifest.shard_hashes[4]);
+        assert!(matches!(
+            store.get_value(&key),
+            Err(DhtError::InsufficientShards { have: 3, need: 4 })
+        ));
+
+        // Small values are stored whole
+        let (key, placements) = store
+            .put_value(
+                &kp.signing_key,
+                b"small",
+                1,
+                b"tiny".to_vec(),
+                &policy,
+                &candidates,
+            )
+            .expect("put");
+        assert!(placements.is_empty());
+        assert_eq!(store.get_value(&key).expect("get"), Some(b"tiny".to_vec()));
+        assert_eq!(store.get_value(&[9; 32]).expect("get"), None);
+    }
 }
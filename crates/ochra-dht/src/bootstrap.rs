@@ -129,41 +129,9 @@ pub async fn bootstrap<T: BootstrapTransport>(
 
     // Phase 1: Contact seed nodes.
     for seed in &config.seed_nodes {
-        let node_id = ochra_crypto::blake3::hash(&seed.pik_public_key);
-
-        let mut connected = false;
-        for attempt in 0..config.max_retries {
-            debug!(
-                addr = %seed.addr,
-                attempt = attempt + 1,
-                "Pinging seed node"
-            );
-
-            match transport.ping(seed.addr, timeout).await {
-                Ok(peer_info) => {
-                    routing_table.add_node(peer_info);
-                    responsive_seeds += 1;
-                    connected = true;
-                    info!(addr = %seed.addr, "Seed node responded");
-                    break;
-                }
-                Err(e) => {
-                    warn!(
-                        addr = %seed.addr,
-                        attempt = attempt + 1,
-                        error = %e,
-                        "Seed node ping failed"
-                    );
-                }
-            }
-        }
-
-        if !connected {
-            warn!(
-                addr = %seed.addr,
-                node_id = hex::encode(node_id),
-                "Failed to reach seed node after all retries"
-            );
+        if let Some(peer_info) = contact_seed(transport, seed, config.max_retries, timeout).await {
+            routing_table.add_node(peer_info);
+            responsive_seeds += 1;
         }
     }
 
@@ -208,6 +176,46 @@ pub async fn bootstrap<T: BootstrapTransport>(
     Ok(result)
 }
 
+/// Ping a seed node up to `max_retries` times and return its [`NodeInfo`]
+/// once it answers.
+pub(crate) async fn contact_seed<T: BootstrapTransport>(
+    transport: &T,
+    seed: &SeedNode,
+    max_retries: u32,
+    timeout: Duration,
+) -> Option<NodeInfo> {
+    for attempt in 0..max_retries {
+        debug!(
+            addr = %seed.addr,
+            attempt = attempt + 1,
+            "Pinging seed node"
+        );
+
+        match transport.ping(seed.addr, timeout).await {
+            Ok(peer_info) => {
+                info!(addr = %seed.addr, "Seed node responded");
+                return Some(peer_info);
+            }
+            Err(e) => {
+                warn!(
+                    addr = %seed.addr,
+                    attempt = attempt + 1,
+                    error = %e,
+                    "Seed node ping failed"
+                );
+            }
+        }
+    }
+
+    let node_id = ochra_crypto::blake3::hash(&seed.pik_public_key);
+    warn!(
+        addr = %seed.addr,
+        node_id = hex::encode(node_id),
+        "Failed to reach seed node after all retries"
+    );
+    None
+}
+
 /// Transport trait for bootstrap network operations.
 ///
 /// Implementors provide the actual network I/O. This abstraction allows
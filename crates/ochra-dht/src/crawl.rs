@@ -0,0 +1,336 @@
+//! Network crawler for health snapshots.
+//!
+//! Operators walk the DHT outward from the bootstrap nodes to estimate how
+//! many nodes are online, which versions they run, and how full their
+//! routing tables are. The [`Crawler`] holds the crawl state and leaves the
+//! network I/O to the caller: take the next peer with [`Crawler::next`],
+//! run a capability exchange and a `FIND_NODE` against it, and feed the
+//! results back.
+//!
+//! ## Anonymity
+//!
+//! The [`CrawlReport`] contains only aggregate counts: no node IDs or
+//! addresses. Agent strings are reduced to `name/major.minor`, and versions
+//! seen on fewer than [`MIN_VERSION_COUNT`] nodes are folded into `"other"`
+//! so that a rare build cannot single out its operator.
+//!
+//! ## Opt-Out and Rate Limits
+//!
+//! Nodes that set [`CRAWL_OPT_OUT`] in their capability features are
+//! counted but not queried for neighbours, and their agent is not recorded.
+//! The crawler issues at most `queries_per_sec` queries and stops after
+//! `max_nodes` peers.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::kademlia::{leading_zeros, NodeId, RoutingTable};
+use crate::K;
+
+/// Capability feature bit asking crawlers not to walk this node.
+pub const CRAWL_OPT_OUT: u64 = 1 << 63;
+
+/// Default number of peers to contact before stopping.
+pub const DEFAULT_MAX_NODES: usize = 10_000;
+
+/// Default query rate.
+pub const DEFAULT_QUERIES_PER_SEC: u32 = 10;
+
+/// Versions seen on fewer nodes than this are reported as `"other"`.
+pub const MIN_VERSION_COUNT: u64 = 3;
+
+/// Longest agent name kept after normalization.
+const MAX_AGENT_LEN: usize = 32;
+
+/// Crawl limits.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CrawlConfig {
+    /// Peers to contact before stopping.
+    pub max_nodes: usize,
+    /// Maximum queries per second.
+    pub queries_per_sec: u32,
+}
+
+impl Default for CrawlConfig {
+    fn default() -> Self {
+        Self {
+            max_nodes: DEFAULT_MAX_NODES,
+            queries_per_sec: DEFAULT_QUERIES_PER_SEC,
+        }
+    }
+}
+
+/// A peer waiting to be contacted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrawlPeer {
+    /// Node ID, if learned from a neighbour list.
+    pub node_id: Option<NodeId>,
+    /// Address to contact.
+    pub addr: SocketAddr,
+}
+
+/// Aggregate network health snapshot.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrawlReport {
+    /// Unix timestamp the crawl started.
+    pub started_at: u64,
+    /// Unix timestamp the report was produced.
+    pub finished_at: u64,
+    /// Distinct nodes learned of, contacted or not.
+    pub nodes_discovered: u64,
+    /// Nodes that completed a capability exchange.
+    pub nodes_responsive: u64,
+    /// Nodes that could not be reached.
+    pub nodes_unreachable: u64,
+    /// Responsive nodes that asked not to be crawled.
+    pub nodes_opted_out: u64,
+    /// Responsive nodes per `name/major.minor` agent.
+    pub versions: BTreeMap<String, u64>,
+    /// Neighbours returned per bucket index, relative to the responder.
+    pub bucket_fill: BTreeMap<usize, u64>,
+    /// Mean neighbour list length as a fraction of K.
+    pub mean_response_fill: f64,
+}
+
+/// State of one crawl.
+#[derive(Debug)]
+pub struct Crawler {
+    config: CrawlConfig,
+    frontier: VecDeque<CrawlPeer>,
+    seen_ids: HashSet<NodeId>,
+    seen_addrs: HashSet<SocketAddr>,
+    contacted: usize,
+    responsive: u64,
+    unreachable: u64,
+    opted_out: u64,
+    versions: HashMap<String, u64>,
+    bucket_fill: BTreeMap<usize, u64>,
+    responses: u64,
+    neighbours_returned: u64,
+    next_query_ms: u64,
+    started_at: u64,
+}
+
+impl Crawler {
+    /// Start a crawl from the bootstrap addresses at `now_ms`.
+    pub fn new(config: CrawlConfig, seeds: &[SocketAddr], now_ms: u64) -> Self {
+        let mut crawler = Self {
+            config,
+            frontier: VecDeque::new(),
+            seen_ids: HashSet::new(),
+            seen_addrs: HashSet::new(),
+            contacted: 0,
+            responsive: 0,
+            unreachable: 0,
+            opted_out: 0,
+            versions: HashMap::new(),
+            bucket_fill: BTreeMap::new(),
+            responses: 0,
+            neighbours_returned: 0,
+            next_query_ms: now_ms,
+            started_at: now_ms / 1000,
+        };
+        for addr in seeds {
+            crawler.enqueue(CrawlPeer {
+                node_id: None,
+                addr: *addr,
+            });
+        }
+        crawler
+    }
+
+    /// The next peer to contact, or `None` if the rate limit applies or
+    /// the crawl is done.
+    pub fn next(&mut self, now_ms: u64) -> Option<CrawlPeer> {
+        if now_ms < self.next_query_ms || self.contacted >= self.config.max_nodes {
+            return None;
+        }
+        let peer = self.frontier.pop_front()?;
+        self.contacted += 1;
+        let interval = 1000 / u64::from(self.config.queries_per_sec.max(1));
+        self.next_query_ms = now_ms.max(self.next_query_ms) + interval;
+        Some(peer)
+    }
+
+    /// Milliseconds until [`next`](Self::next) may return a peer.
+    pub fn wait_ms(&self, now_ms: u64) -> u64 {
+        self.next_query_ms.saturating_sub(now_ms)
+    }
+
+    /// Whether there is nothing left to contact.
+    pub fn is_done(&self) -> bool {
+        self.frontier.is_empty() || self.contacted >= self.config.max_nodes
+    }
+
+    /// Record a peer that could not be reached.
+    pub fn record_unreachable(&mut self) {
+        self.unreachable += 1;
+    }
+
+    /// Record a peer's capability exchange.
+    ///
+    /// Returns `false` if the peer opted out; its neighbours must not be
+    /// requested.
+    pub fn record_hello(&mut self, node_id: NodeId, agent: &str, features: u64) -> bool {
+        self.seen_ids.insert(node_id);
+        self.responsive += 1;
+        if features & CRAWL_OPT_OUT != 0 {
+            self.opted_out += 1;
+            return false;
+        }
+        *self.versions.entry(normalize_agent(agent)).or_insert(0) += 1;
+        true
+    }
+
+    /// Record the neighbours `responder` returned and queue the new ones.
+    pub fn record_neighbours(&mut self, responder: &NodeId, neighbours: &[CrawlPeer]) {
+        self.responses += 1;
+        self.neighbours_returned += neighbours.len() as u64;
+        for peer in neighbours {
+            if let Some(id) = peer.node_id {
+                let distance = RoutingTable::xor_distance(responder, &id);
+                if let Some(bucket) = leading_zeros(&distance) {
+                    *self.bucket_fill.entry(bucket).or_insert(0) += 1;
+                }
+            }
+            self.enqueue(*peer);
+        }
+    }
+
+    /// The aggregate report so far.
+    pub fn report(&self, now_ms: u64) -> CrawlReport {
+        let mut versions = BTreeMap::new();
+        for (agent, count) in &self.versions {
+            let key = if *count < MIN_VERSION_COUNT {
+                "other".to_string()
+            } else {
+                agent.clone()
+            };
+            *versions.entry(key).or_insert(0) += count;
+        }
+        let mean_response_fill = if self.responses == 0 {
+            0.0
+        } else {
+            self.neighbours_returned as f64 / (self.responses as f64 * K as f64)
+        };
+        CrawlReport {
+            started_at: self.started_at,
+            finished_at: now_ms / 1000,
+            nodes_discovered: self.seen_ids.len().max(self.seen_addrs.len()) as u64,
+            nodes_responsive: self.responsive,
+            nodes_unreachable: self.unreachable,
+            nodes_opted_out: self.opted_out,
+            versions,
+            bucket_fill: self.bucket_fill.clone(),
+            mean_response_fill,
+        }
+    }
+
+    fn enqueue(&mut self, peer: CrawlPeer) {
+        let new_id = peer.node_id.is_none_or(|id| self.seen_ids.insert(id));
+        if self.seen_addrs.insert(peer.addr) && new_id {
+            self.frontier.push_back(peer);
+        }
+    }
+}
+
+/// Reduce an agent string to `name/major.minor`, or `"other"` if it does
+/// not look like one.
+pub fn normalize_agent(agent: &str) -> String {
+    let token = agent.split_whitespace().next().unwrap_or_default();
+    let Some((name, version)) = token.split_once('/') else {
+        return "other".to_string();
+    };
+    let name_ok = !name.is_empty()
+        && name.len() <= MAX_AGENT_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    let mut parts = version.split('.');
+    let (Some(major), Some(minor)) = (parts.next(), parts.next()) else {
+        return "other".to_string();
+    };
+    let numeric = |s: &str| !s.is_empty() && s.len() <= 4 && s.chars().all(|c| c.is_ascii_digit());
+    if !name_ok || !numeric(major) || !numeric(minor) {
+        return "other".to_string();
+    }
+    format!("{name}/{major}.{minor}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(n: u8) -> SocketAddr {
+        SocketAddr::from(([10, 0, 0, n], 4433))
+    }
+
+    fn peer(n: u8) -> CrawlPeer {
+        CrawlPeer {
+            node_id: Some([n; 32]),
+            addr: addr(n),
+        }
+    }
+
+    #[test]
+    fn test_crawl_walks_and_dedupes() {
+        let mut crawler = Crawler::new(CrawlConfig::default(), &[addr(1)], 0);
+        let seed = crawler.next(0).expect("seed");
+        assert_eq!(seed.addr, addr(1));
+        assert!(crawler.record_hello([1; 32], "ochra-daemon/0.1.0", 0));
+        crawler.record_neighbours(&[1; 32], &[peer(1), peer(2), peer(3)]);
+
+        // The seed itself is not queued again
+        let first = crawler.next(100).expect("peer");
+        assert_eq!(first.node_id, Some([2; 32]));
+        crawler.record_unreachable();
+        crawler.record_neighbours(&[3; 32], &[peer(2)]);
+        assert_eq!(crawler.next(200).expect("peer").node_id, Some([3; 32]));
+        assert!(crawler.is_done());
+
+        let report = crawler.report(1_000);
+        assert_eq!(report.nodes_discovered, 3);
+        assert_eq!(report.nodes_responsive, 1);
+        assert_eq!(report.nodes_unreachable, 1);
+        assert_eq!(report.versions.get("other"), Some(&1));
+    }
+
+    #[test]
+    fn test_rate_limit_and_max_nodes() {
+        let config = CrawlConfig {
+            max_nodes: 2,
+            queries_per_sec: 4,
+        };
+        let mut crawler = Crawler::new(config, &[addr(1), addr(2), addr(3)], 0);
+        assert!(crawler.next(0).is_some());
+        assert!(crawler.next(100).is_none());
+        assert_eq!(crawler.wait_ms(100), 150);
+        assert!(crawler.next(250).is_some());
+        assert!(crawler.next(10_000).is_none());
+        assert!(crawler.is_done());
+    }
+
+    #[test]
+    fn test_opt_out_is_respected() {
+        let mut crawler = Crawler::new(CrawlConfig::default(), &[addr(1)], 0);
+        crawler.next(0).expect("seed");
+        assert!(!crawler.record_hello([1; 32], "rare-build/9.9", CRAWL_OPT_OUT));
+        let report = crawler.report(0);
+        assert_eq!(report.nodes_opted_out, 1);
+        assert!(report.versions.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_agent() {
+        assert_eq!(normalize_agent("ochra-daemon/0.1.7"), "ochra-daemon/0.1");
+        assert_eq!(
+            normalize_agent("ochra-daemon/1.2 (linux)"),
+            "ochra-daemon/1.2"
+        );
+        assert_eq!(normalize_agent("Ochra/1.2"), "other");
+        assert_eq!(normalize_agent("ochra-daemon/dev"), "other");
+        assert_eq!(normalize_agent(""), "other");
+    }
+}
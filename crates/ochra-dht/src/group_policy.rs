@@ -0,0 +1,479 @@
+//! Owner-signed Space policy records (Section 8.11).
+//!
+//! A Space owner publishes the Space's [`GroupPolicy`] as a mutable record
+//! under their PIK, with salt `"group-policy" || group_id` and the policy
+//! version as sequence number. As with revocation certificates, the BEP 44
+//! signature is the policy's signature, so a stored record can be
+//! re-verified without the DHT.
+//!
+//! Member daemons feed every policy they fetch into a [`PolicyChain`],
+//! which accepts only newer versions from the current owner and drives
+//! ownership transfers: the owner names a successor with a timelock, may
+//! veto it by publishing a version without it, and once the timelock has
+//! passed the successor becomes the owner unless a [`TransferVeto`] hook
+//! holds it back.
+
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use ochra_types::space::{GroupPolicy, GroupSettings};
+use ochra_types::GroupId;
+
+use crate::bep44::{build_signed_data, DhtRecord};
+use crate::{DhtError, Result};
+
+/// Salt prefix of group policy records.
+pub const GROUP_POLICY_SALT: &[u8] = b"group-policy";
+
+/// Least time between naming a new owner and the transfer completing
+/// (7 days).
+pub const TRANSFER_TIMELOCK_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Salt of `group_id`'s policy record.
+pub fn policy_salt(group_id: &GroupId) -> Vec<u8> {
+    let mut salt = GROUP_POLICY_SALT.to_vec();
+    salt.extend_from_slice(group_id);
+    salt
+}
+
+/// DHT address of the policy record `owner_pk` publishes for `group_id`.
+pub fn policy_address(owner_pk: &[u8; 32], group_id: &GroupId) -> [u8; 32] {
+    let mut input = owner_pk.to_vec();
+    input.extend_from_slice(&policy_salt(group_id));
+    ochra_crypto::blake3::hash(&input)
+}
+
+/// A [`GroupPolicy`] with its owner's signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedGroupPolicy {
+    pub policy: GroupPolicy,
+    /// PIK public key of the owner who signed it.
+    pub owner_pk: [u8; 32],
+    /// CBOR encoding of `policy` covered by the signature.
+    encoded: Vec<u8>,
+    pub signature: [u8; 64],
+}
+
+impl SignedGroupPolicy {
+    /// Sign `policy` with the owner's PIK.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::Serialization`] if the policy cannot be encoded
+    pub fn sign(signing_key: &SigningKey, policy: GroupPolicy) -> Result<Self> {
+        let mut encoded = Vec::new();
+        ciborium::into_writer(&policy, &mut encoded)
+            .map_err(|e| DhtError::Serialization(e.to_string()))?;
+        let signed_data =
+            build_signed_data(&policy_salt(&policy.group_id), policy.version, &encoded);
+        Ok(Self {
+            owner_pk: signing_key.verifying_key().to_bytes(),
+            signature: signing_key.sign(&signed_data).to_bytes(),
+            policy,
+            encoded,
+        })
+    }
+
+    /// Check the owner's signature.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InvalidSignature`] if `owner_pk` did not sign it
+    pub fn verify(&self) -> Result<()> {
+        let signed_data = build_signed_data(
+            &policy_salt(&self.policy.group_id),
+            self.policy.version,
+            &self.encoded,
+        );
+        VerifyingKey::from_bytes(&self.owner_pk)?
+            .verify(&signed_data, &Signature::from_bytes(&self.signature))
+            .map_err(|_| DhtError::InvalidSignature)
+    }
+
+    /// The record publishing this policy at [`policy_address`].
+    pub fn to_record(&self) -> DhtRecord {
+        DhtRecord::Mutable {
+            public_key: self.owner_pk,
+            salt: policy_salt(&self.policy.group_id),
+            seq: self.policy.version,
+            value: self.encoded.clone(),
+            signature: self.signature,
+        }
+    }
+
+    /// Extract and verify the policy of a fetched record.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InvalidSignature`] if the record is not a policy record
+    ///   signed by its publisher
+    /// - [`DhtError::Serialization`] if the value is not a policy for the
+    ///   salt's Space at the record's sequence number
+    pub fn from_record(record: &DhtRecord) -> Result<Self> {
+        let DhtRecord::Mutable {
+            public_key,
+            salt,
+            seq,
+            value,
+            signature,
+        } = record
+        else {
+            return Err(DhtError::InvalidSignature);
+        };
+        let policy: GroupPolicy = ciborium::from_reader(value.as_slice())
+            .map_err(|e| DhtError::Serialization(e.to_string()))?;
+        if *salt != policy_salt(&policy.group_id) || *seq != policy.version {
+            return Err(DhtError::Serialization(
+                "salt or sequence does not match the policy".into(),
+            ));
+        }
+        let signed = Self {
+            policy,
+            owner_pk: *public_key,
+            encoded: value.clone(),
+            signature: *signature,
+        };
+        signed.verify()?;
+        Ok(signed)
+    }
+
+    /// Encode for storage: `owner_pk || signature || CBOR(policy)`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(96 + self.encoded.len());
+        out.extend_from_slice(&self.owner_pk);
+        out.extend_from_slice(&self.signature);
+        out.extend_from_slice(&self.encoded);
+        out
+    }
+
+    /// Decode and verify a policy encoded by [`to_bytes`](Self::to_bytes).
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::Serialization`] if the bytes are malformed
+    /// - [`DhtError::InvalidSignature`] if the signature does not verify
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < 96 {
+            return Err(DhtError::Serialization(format!(
+                "signed policy length {}",
+                data.len()
+            )));
+        }
+        let mut owner_pk = [0u8; 32];
+        owner_pk.copy_from_slice(&data[..32]);
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&data[32..96]);
+        let policy: GroupPolicy = ciborium::from_reader(&data[96..])
+            .map_err(|e| DhtError::Serialization(e.to_string()))?;
+        let signed = Self {
+            policy,
+            owner_pk,
+            encoded: data[96..].to_vec(),
+            signature,
+        };
+        signed.verify()?;
+        Ok(signed)
+    }
+}
+
+/// Decides whether an ownership transfer may complete. Member daemons use
+/// it to hold back transfers to PIKs they know are revoked.
+pub trait TransferVeto {
+    /// Whether the transfer of `group_id` to `new_owner_pk` must not
+    /// complete yet.
+    fn veto(&self, group_id: &GroupId, new_owner_pk: &[u8; 32]) -> bool;
+}
+
+impl<F> TransferVeto for F
+where
+    F: Fn(&GroupId, &[u8; 32]) -> bool,
+{
+    fn veto(&self, group_id: &GroupId, new_owner_pk: &[u8; 32]) -> bool {
+        self(group_id, new_owner_pk)
+    }
+}
+
+/// What accepting a policy, or the passage of time, changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolicyChange {
+    /// The settings changed.
+    Settings {
+        old: GroupSettings,
+        new: GroupSettings,
+    },
+    /// The owner named a successor.
+    TransferPending {
+        new_owner_pk: [u8; 32],
+        completes_at: u64,
+    },
+    /// The owner withdrew a pending transfer.
+    TransferVetoed,
+    /// The successor became the owner.
+    TransferCompleted { new_owner_pk: [u8; 32] },
+}
+
+/// Why a policy was refused.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyError {
+    #[error("policy signature does not verify")]
+    InvalidSignature,
+    #[error("policy is for another Space")]
+    WrongGroup,
+    #[error("policy not signed by the Space owner")]
+    NotOwner,
+    #[error("policy version {got} is not newer than {have}")]
+    Stale { got: u64, have: u64 },
+    #[error("ownership transfer completes before the {TRANSFER_TIMELOCK_SECS} s timelock")]
+    TimelockTooShort,
+    #[error("ownership transfer names the current owner")]
+    TransferToSelf,
+}
+
+/// The accepted policy history of one Space, as a member sees it.
+#[derive(Clone, Debug)]
+pub struct PolicyChain {
+    group_id: GroupId,
+    owner_pk: [u8; 32],
+    head: Option<SignedGroupPolicy>,
+}
+
+impl PolicyChain {
+    /// A chain for a Space owned by `owner_pk`, resuming from `head`, the
+    /// newest policy accepted before.
+    pub fn new(group_id: GroupId, owner_pk: [u8; 32], head: Option<SignedGroupPolicy>) -> Self {
+        Self {
+            group_id,
+            owner_pk,
+            head,
+        }
+    }
+
+    /// The current owner's PIK public key.
+    pub fn owner_pk(&self) -> [u8; 32] {
+        self.owner_pk
+    }
+
+    /// The newest accepted policy.
+    pub fn head(&self) -> Option<&SignedGroupPolicy> {
+        self.head.as_ref()
+    }
+
+    /// The policy in force: the newest accepted, or the initial one.
+    pub fn policy(&self) -> GroupPolicy {
+        self.head
+            .as_ref()
+            .map_or_else(|| GroupPolicy::initial(self.group_id), |h| h.policy.clone())
+    }
+
+    /// The next version the owner publishes.
+    pub fn next_version(&self) -> u64 {
+        self.head.as_ref().map_or(1, |h| h.policy.version + 1)
+    }
+
+    /// Complete a pending transfer whose timelock has passed, unless `veto`
+    /// holds it back.
+    pub fn advance(&mut self, now: u64, veto: &dyn TransferVeto) -> Option<PolicyChange> {
+        let transfer = self.head.as_ref()?.policy.pending_transfer.as_ref()?;
+        if transfer.new_owner_pik == self.owner_pk
+            || now < transfer.completes_at
+            || veto.veto(&self.group_id, &transfer.new_owner_pik)
+        {
+            return None;
+        }
+        self.owner_pk = transfer.new_owner_pik;
+        Some(PolicyChange::TransferCompleted {
+            new_owner_pk: self.owner_pk,
+        })
+    }
+
+    /// Accept a policy if it is a newer version signed by the owner.
+    ///
+    /// # Errors
+    ///
+    /// - [`PolicyError`] naming why the policy was refused; the chain is
+    ///   unchanged apart from completing a due transfer
+    pub fn apply(
+        &mut self,
+        signed: SignedGroupPolicy,
+        now: u64,
+        veto: &dyn TransferVeto,
+    ) -> std::result::Result<Vec<PolicyChange>, PolicyError> {
+        signed.verify().map_err(|_| PolicyError::InvalidSignature)?;
+        if signed.policy.group_id != self.group_id {
+            return Err(PolicyError::WrongGroup);
+        }
+        let mut changes: Vec<PolicyChange> = self.advance(now, veto).into_iter().collect();
+        if signed.owner_pk != self.owner_pk {
+            return Err(PolicyError::NotOwner);
+        }
+        let old = self.policy();
+        if self.head.is_some() && signed.policy.version <= old.version {
+            return Err(PolicyError::Stale {
+                got: signed.policy.version,
+                have: old.version,
+            });
+        }
+        let new = &signed.policy;
+        if let Some(transfer) = &new.pending_transfer {
+            if transfer.new_owner_pik == self.owner_pk {
+                return Err(PolicyError::TransferToSelf);
+            }
+            if transfer.completes_at < transfer.initiated_at + TRANSFER_TIMELOCK_SECS {
+                return Err(PolicyError::TimelockTooShort);
+            }
+        }
+
+        if old.settings != new.settings {
+            changes.push(PolicyChange::Settings {
+                old: old.settings.clone(),
+                new: new.settings.clone(),
+            });
+        }
+        // A transfer that already completed is not pending any more.
+        let pending = |p: &GroupPolicy| {
+            p.pending_transfer
+                .clone()
+                .filter(|t| t.new_owner_pik != self.owner_pk)
+        };
+        match (pending(&old), pending(new)) {
+            (old_transfer, Some(t)) if old_transfer.as_ref() != Some(&t) => {
+                changes.push(PolicyChange::TransferPending {
+                    new_owner_pk: t.new_owner_pik,
+                    completes_at: t.completes_at,
+                });
+            }
+            (Some(_), None) => changes.push(PolicyChange::TransferVetoed),
+            _ => {}
+        }
+        self.head = Some(signed);
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ochra_crypto::ed25519::KeyPair;
+    use ochra_types::space::{ContentRating, OwnershipTransferRecord, PublishPolicy};
+
+    use super::*;
+    use crate::record_types::RecordTypeRegistry;
+
+    const GROUP: GroupId = [7; 32];
+
+    fn no_veto(_: &GroupId, _: &[u8; 32]) -> bool {
+        false
+    }
+
+    fn policy(version: u64, pending_transfer: Option<OwnershipTransferRecord>) -> GroupPolicy {
+        GroupPolicy {
+            version,
+            pending_transfer,
+            issued_at: 1000,
+            ..GroupPolicy::initial(GROUP)
+        }
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let owner = KeyPair::generate();
+        let signed = SignedGroupPolicy::sign(&owner.signing_key, policy(3, None)).expect("sign");
+        let record = signed.to_record();
+        assert!(record.validate().is_ok());
+        assert!(RecordTypeRegistry::standard().validate(&record).is_ok());
+        assert_eq!(
+            SignedGroupPolicy::from_record(&record).expect("parse"),
+            signed
+        );
+        assert_eq!(
+            SignedGroupPolicy::from_bytes(&signed.to_bytes()).expect("decode"),
+            signed
+        );
+        assert_eq!(
+            record.storage_key(),
+            policy_address(&signed.owner_pk, &GROUP)
+        );
+
+        let mut bytes = signed.to_bytes();
+        bytes[40] ^= 1;
+        assert!(SignedGroupPolicy::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_chain_accepts_newer_owner_policies() {
+        let owner = KeyPair::generate();
+        let mut chain = PolicyChain::new(GROUP, owner.verifying_key.to_bytes(), None);
+        let mut next = policy(1, None);
+        next.settings.publish_policy = PublishPolicy::Everyone;
+        next.settings.max_content_rating = ContentRating::Teen;
+        let signed = SignedGroupPolicy::sign(&owner.signing_key, next).expect("sign");
+
+        let changes = chain.apply(signed.clone(), 1000, &no_veto).expect("apply");
+        assert!(matches!(
+            changes.as_slice(),
+            [PolicyChange::Settings { .. }]
+        ));
+        assert!(!chain.policy().settings.allows_rating(ContentRating::Mature));
+        assert_eq!(
+            chain.apply(signed, 1000, &no_veto),
+            Err(PolicyError::Stale { got: 1, have: 1 })
+        );
+
+        let stranger = KeyPair::generate();
+        let forged = SignedGroupPolicy::sign(&stranger.signing_key, policy(2, None)).expect("sign");
+        assert_eq!(
+            chain.apply(forged, 1000, &no_veto),
+            Err(PolicyError::NotOwner)
+        );
+    }
+
+    #[test]
+    fn test_transfer_veto_and_completion() {
+        let owner = KeyPair::generate();
+        let successor = KeyPair::generate().verifying_key.to_bytes();
+        let mut chain = PolicyChain::new(GROUP, owner.verifying_key.to_bytes(), None);
+        let transfer = OwnershipTransferRecord {
+            new_owner_pik: successor,
+            initiated_at: 1000,
+            completes_at: 1000 + TRANSFER_TIMELOCK_SECS,
+        };
+
+        let too_soon = OwnershipTransferRecord {
+            completes_at: 2000,
+            ..transfer.clone()
+        };
+        let rushed =
+            SignedGroupPolicy::sign(&owner.signing_key, policy(1, Some(too_soon))).expect("sign");
+        assert_eq!(
+            chain.apply(rushed, 1000, &no_veto),
+            Err(PolicyError::TimelockTooShort)
+        );
+
+        let named = SignedGroupPolicy::sign(&owner.signing_key, policy(1, Some(transfer.clone())))
+            .expect("sign");
+        assert_eq!(
+            chain.apply(named, 1000, &no_veto).expect("apply"),
+            vec![PolicyChange::TransferPending {
+                new_owner_pk: successor,
+                completes_at: transfer.completes_at,
+            }]
+        );
+        let withdrawn = SignedGroupPolicy::sign(&owner.signing_key, policy(2, None)).expect("sign");
+        assert_eq!(
+            chain.apply(withdrawn, 2000, &no_veto).expect("apply"),
+            vec![PolicyChange::TransferVetoed]
+        );
+
+        let renamed =
+            SignedGroupPolicy::sign(&owner.signing_key, policy(3, Some(transfer.clone())))
+                .expect("sign");
+        chain.apply(renamed, 3000, &no_veto).expect("apply");
+        let revoked = |_: &GroupId, pk: &[u8; 32]| *pk == successor;
+        assert_eq!(chain.advance(transfer.completes_at, &revoked), None);
+        assert_eq!(
+            chain.advance(transfer.completes_at, &no_veto),
+            Some(PolicyChange::TransferCompleted {
+                new_owner_pk: successor
+            })
+        );
+        assert_eq!(chain.owner_pk(), successor);
+        assert_eq!(chain.advance(transfer.completes_at + 1, &no_veto), None);
+    }
+}
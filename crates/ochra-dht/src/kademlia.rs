@@ -10,10 +10,25 @@
 //! entry is pinged. If the ping fails, the stale entry is evicted and the new
 //! node is inserted. If the ping succeeds, the new node is discarded (Kademlia
 //! preference for long-lived nodes).
-
-use std::collections::VecDeque;
-use std::net::SocketAddr;
-use std::time::Instant;
+//!
+//! ## IP Diversity
+//!
+//! To resist eclipse attacks, each bucket admits at most
+//! [`DiversityConfig::max_per_subnet`] entries from one /24 (IPv4) or /48
+//! (IPv6) and, when the caller knows it, at most
+//! [`DiversityConfig::max_per_asn`] entries from one autonomous system. A
+//! node that would exceed a limit is refused in favour of the existing,
+//! longer-lived entries. A dual-stack node (IPv4 `addr` plus IPv6 `alt_addr`,
+//! or the reverse) counts against the subnet of each address. When a full
+//! bucket needs an eviction candidate, the
+//! least-recently-seen entry of its most crowded subnet is offered first.
+//! Test networks on a single host can use [`DiversityConfig::unrestricted`].
+
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use ochra_sim::SimEnv;
 
 use serde::{Deserialize, Serialize};
 
@@ -30,21 +45,97 @@ pub struct NodeInfo {
     /// The node's network address.
     #[serde(with = "socket_addr_serde")]
     pub addr: SocketAddr,
+    /// A second address in the other IP family, for dual-stack nodes.
+    #[serde(default, with = "opt_socket_addr_serde")]
+    pub alt_addr: Option<SocketAddr>,
     /// The node's PIK public key (Ed25519 verifying key, 32 bytes).
     pub pik_public_key: [u8; 32],
     /// The node's X25519 public key for encrypted communication.
     pub x25519_public_key: [u8; 32],
 }
 
+impl NodeInfo {
+    /// Every address the node advertised, primary first.
+    pub fn addrs(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        std::iter::once(self.addr).chain(self.alt_addr)
+    }
+}
+
+/// Default maximum entries per bucket from one /24 or /48.
+pub const DEFAULT_MAX_PER_SUBNET: usize = 2;
+
+/// Default maximum entries per bucket from one autonomous system.
+pub const DEFAULT_MAX_PER_ASN: usize = 5;
+
+/// Per-bucket IP diversity limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiversityConfig {
+    /// Maximum entries per bucket from one /24 (IPv4) or /48 (IPv6).
+    pub max_per_subnet: usize,
+    /// Maximum entries per bucket from one AS, for nodes whose AS is known.
+    pub max_per_asn: usize,
+    /// Exempt loopback addresses from the limits.
+    pub exempt_loopback: bool,
+}
+
+impl Default for DiversityConfig {
+    fn default() -> Self {
+        Self {
+            max_per_subnet: DEFAULT_MAX_PER_SUBNET,
+            max_per_asn: DEFAULT_MAX_PER_ASN,
+            exempt_loopback: true,
+        }
+    }
+}
+
+impl DiversityConfig {
+    /// No diversity limits, for test networks sharing one subnet.
+    pub fn unrestricted() -> Self {
+        Self {
+            max_per_subnet: usize::MAX,
+            max_per_asn: usize::MAX,
+            exempt_loopback: true,
+        }
+    }
+}
+
+/// The /24 (IPv4) or /48 (IPv6) prefix an address belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Subnet {
+    V4([u8; 3]),
+    V6([u8; 6]),
+}
+
+impl Subnet {
+    pub(crate) fn of(ip: IpAddr) -> Self {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            v4 => v4,
+        };
+        match ip {
+            IpAddr::V4(v4) => {
+                let o = v4.octets();
+                Subnet::V4([o[0], o[1], o[2]])
+            }
+            IpAddr::V6(v6) => {
+                let o = v6.octets();
+                Subnet::V6([o[0], o[1], o[2], o[3], o[4], o[5]])
+            }
+        }
+    }
+}
+
 /// Runtime metadata for a node entry within a k-bucket.
 #[derive(Clone, Debug)]
 struct BucketEntry {
     /// The node information.
     info: NodeInfo,
-    /// When this node was last seen (for LRU eviction).
-    last_seen: Instant,
+    /// When this node was last seen (for LRU eviction), on the table's clock.
+    last_seen: Duration,
     /// Number of consecutive failed pings.
     failed_pings: u32,
+    /// Autonomous system of the node's address, if known.
+    asn: Option<u32>,
 }
 
 /// A single k-bucket holding up to K entries, ordered by last-seen time.
@@ -55,22 +146,22 @@ struct BucketEntry {
 struct KBucket {
     /// Entries ordered by last-seen time (front = oldest, back = newest).
     entries: VecDeque<BucketEntry>,
-    /// Last time this bucket was refreshed via a lookup.
-    last_refresh: Instant,
+    /// Last time this bucket was refreshed via a lookup, on the table's clock.
+    last_refresh: Duration,
 }
 
 impl KBucket {
     /// Create an empty k-bucket.
-    fn new() -> Self {
+    fn new(now: Duration) -> Self {
         Self {
             entries: VecDeque::with_capacity(K),
-            last_refresh: Instant::now(),
+            last_refresh: now,
         }
     }
 
-    /// Return whether this bucket is full.
-    fn is_full(&self) -> bool {
-        self.entries.len() >= K
+    /// Return whether this bucket holds `k` entries.
+    fn is_full(&self, k: usize) -> bool {
+        self.entries.len() >= k
     }
 
     /// Find an entry by node ID, returning its index if present.
@@ -79,9 +170,9 @@ impl KBucket {
     }
 
     /// Move an existing entry to the back (most-recently-seen) and update its timestamp.
-    fn touch(&mut self, index: usize) {
+    fn touch(&mut self, index: usize, now: Duration) {
         if let Some(mut entry) = self.entries.remove(index) {
-            entry.last_seen = Instant::now();
+            entry.last_seen = now;
             entry.failed_pings = 0;
             self.entries.push_back(entry);
         }
@@ -90,14 +181,72 @@ impl KBucket {
     /// Insert a new entry at the back (most-recently-seen position).
     ///
     /// Caller must ensure the bucket is not full.
-    fn insert(&mut self, info: NodeInfo) {
+    fn insert(&mut self, info: NodeInfo, asn: Option<u32>, now: Duration) {
         self.entries.push_back(BucketEntry {
             info,
-            last_seen: Instant::now(),
+            last_seen: now,
             failed_pings: 0,
+            asn,
         });
     }
 
+    /// Whether adding `info` in `asn` would exceed `config`, not counting
+    /// the entry `ignoring`.
+    ///
+    /// A dual-stack node counts against the subnet of each of its addresses.
+    fn violates_diversity(
+        &self,
+        config: &DiversityConfig,
+        info: &NodeInfo,
+        asn: Option<u32>,
+        ignoring: Option<&NodeId>,
+    ) -> bool {
+        if config.exempt_loopback && info.addrs().all(|a| a.ip().is_loopback()) {
+            return false;
+        }
+        let subnets: Vec<Subnet> = info.addrs().map(|a| Subnet::of(a.ip())).collect();
+        let others = self
+            .entries
+            .iter()
+            .filter(|e| ignoring != Some(&e.info.node_id));
+        let mut same_subnet = vec![0usize; subnets.len()];
+        let mut same_asn = 0;
+        for entry in others {
+            for (subnet, count) in subnets.iter().zip(same_subnet.iter_mut()) {
+                if entry.info.addrs().any(|a| Subnet::of(a.ip()) == *subnet) {
+                    *count += 1;
+                }
+            }
+            if asn.is_some() && entry.asn == asn {
+                same_asn += 1;
+            }
+        }
+        same_subnet.iter().any(|n| *n >= config.max_per_subnet) || same_asn >= config.max_per_asn
+    }
+
+    /// The entry to ping when the bucket is full: the least-recently-seen
+    /// entry of the most crowded subnet, or of the whole bucket if every
+    /// subnet has one entry.
+    fn eviction_candidate(&self) -> Option<&BucketEntry> {
+        let mut counts: HashMap<Subnet, usize> = HashMap::new();
+        for entry in &self.entries {
+            *counts.entry(Subnet::of(entry.info.addr.ip())).or_default() += 1;
+        }
+        let crowded = counts
+            .iter()
+            .filter(|(_, n)| **n > 1)
+            .max_by_key(|(_, n)| **n)
+            .map(|(subnet, _)| *subnet);
+        match crowded {
+            // Entries are ordered oldest-seen first.
+            Some(subnet) => self
+                .entries
+                .iter()
+                .find(|e| Subnet::of(e.info.addr.ip()) == subnet),
+            None => self.least_recently_seen(),
+        }
+    }
+
     /// Remove an entry by index.
     fn remove(&mut self, index: usize) -> Option<NodeInfo> {
         self.entries.remove(index).map(|e| e.info)
@@ -130,16 +279,58 @@ pub struct RoutingTable {
     local_id: NodeId,
     /// The 256 k-buckets.
     buckets: Vec<KBucket>,
+    /// Per-bucket IP diversity limits.
+    diversity: DiversityConfig,
+    /// Maximum entries per bucket.
+    bucket_size: usize,
+    /// Clock for last-seen and refresh times.
+    env: SimEnv,
 }
 
 impl RoutingTable {
     /// Create a new routing table for the given local node ID.
     pub fn new(local_id: NodeId) -> Self {
-        let mut buckets = Vec::with_capacity(NUM_BUCKETS);
-        for _ in 0..NUM_BUCKETS {
-            buckets.push(KBucket::new());
+        Self::with_diversity(local_id, DiversityConfig::default())
+    }
+
+    /// Create a new routing table with custom diversity limits.
+    pub fn with_diversity(local_id: NodeId, diversity: DiversityConfig) -> Self {
+        let env = SimEnv::real();
+        let now = env.now();
+        Self {
+            local_id,
+            buckets: (0..NUM_BUCKETS).map(|_| KBucket::new(now)).collect(),
+            diversity,
+            bucket_size: K,
+            env,
+        }
+    }
+
+    /// Hold up to `k` entries per bucket instead of [`K`], as set by the
+    /// network profile. Only call on an empty table.
+    pub fn with_bucket_size(mut self, k: usize) -> Self {
+        self.bucket_size = k.max(1);
+        self
+    }
+
+    /// Maximum entries per bucket.
+    pub fn bucket_size(&self) -> usize {
+        self.bucket_size
+    }
+
+    /// Use `env` for bucket refresh timing (e.g. a simulated clock).
+    ///
+    /// Every bucket counts as refreshed at the new clock's current time.
+    pub fn with_env(mut self, env: SimEnv) -> Self {
+        let now = env.now();
+        for bucket in &mut self.buckets {
+            bucket.last_refresh = now;
+            for entry in &mut bucket.entries {
+                entry.last_seen = now;
+            }
         }
-        Self { local_id, buckets }
+        self.env = env;
+        self
     }
 
     /// Return the local node's ID.
@@ -171,11 +362,19 @@ impl RoutingTable {
     ///
     /// Behavior follows Kademlia rules:
     /// - If the node is already in the table, move it to the most-recently-seen position.
+    /// - If the node would exceed the bucket's diversity limits, return
+    ///   [`AddNodeResult::DiversityLimited`].
     /// - If the appropriate bucket has room, insert the node.
     /// - If the bucket is full, return [`AddNodeResult::BucketFull`] with the
-    ///   least-recently-seen entry so the caller can ping it and decide whether
+    ///   eviction candidate so the caller can ping it and decide whether
     ///   to evict.
     pub fn add_node(&mut self, info: NodeInfo) -> AddNodeResult {
+        self.add_node_with_asn(info, None)
+    }
+
+    /// Add a node whose autonomous system is known, so the per-AS limit
+    /// applies. See [`add_node`](Self::add_node).
+    pub fn add_node_with_asn(&mut self, info: NodeInfo, asn: Option<u32>) -> AddNodeResult {
         if info.node_id == self.local_id {
             return AddNodeResult::Ignored;
         }
@@ -185,24 +384,30 @@ impl RoutingTable {
             None => return AddNodeResult::Ignored,
         };
 
+        let now = self.env.now();
         let bucket = &mut self.buckets[bucket_idx];
 
         // If already present, move to back (most-recently-seen).
         if let Some(idx) = bucket.find_index(&info.node_id) {
-            bucket.touch(idx);
+            bucket.touch(idx, now);
             return AddNodeResult::Updated;
         }
 
+        // Keep the existing entries if the newcomer crowds a subnet or AS.
+        if bucket.violates_diversity(&self.diversity, &info, asn, None) {
+            return AddNodeResult::DiversityLimited;
+        }
+
         // If bucket has room, insert.
-        if !bucket.is_full() {
-            bucket.insert(info);
+        if !bucket.is_full(self.bucket_size) {
+            bucket.insert(info, asn, now);
             return AddNodeResult::Inserted;
         }
 
-        // Bucket is full: return the LRS entry for the caller to ping.
-        match bucket.least_recently_seen() {
-            Some(lrs) => AddNodeResult::BucketFull {
-                least_recently_seen: lrs.info.clone(),
+        // Bucket is full: return the eviction candidate for the caller to ping.
+        match bucket.eviction_candidate() {
+            Some(candidate) => AddNodeResult::BucketFull {
+                least_recently_seen: candidate.info.clone(),
             },
             None => AddNodeResult::Ignored,
         }
@@ -213,17 +418,35 @@ impl RoutingTable {
     ///
     /// Call this after a failed ping to the LRS node returned by [`add_node`].
     pub fn evict_and_insert(&mut self, stale_id: &NodeId, new_node: NodeInfo) -> Result<()> {
+        self.evict_and_insert_with_asn(stale_id, new_node, None)
+    }
+
+    /// [`evict_and_insert`](Self::evict_and_insert) for a node whose
+    /// autonomous system is known.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::BucketFull`] if `stale_id` is not in the table
+    /// - [`DhtError::DiversityLimit`] if `new_node` would exceed the bucket's
+    ///   diversity limits even without the stale entry
+    pub fn evict_and_insert_with_asn(
+        &mut self,
+        stale_id: &NodeId,
+        new_node: NodeInfo,
+        asn: Option<u32>,
+    ) -> Result<()> {
         let bucket_idx = self.bucket_index(stale_id).ok_or(DhtError::BucketFull)?;
 
+        let now = self.env.now();
         let bucket = &mut self.buckets[bucket_idx];
 
-        if let Some(idx) = bucket.find_index(stale_id) {
-            bucket.remove(idx);
-            bucket.insert(new_node);
-            Ok(())
-        } else {
-            Err(DhtError::BucketFull)
+        let idx = bucket.find_index(stale_id).ok_or(DhtError::BucketFull)?;
+        if bucket.violates_diversity(&self.diversity, &new_node, asn, Some(stale_id)) {
+            return Err(DhtError::DiversityLimit);
         }
+        bucket.remove(idx);
+        bucket.insert(new_node, asn, now);
+        Ok(())
     }
 
     /// Mark the least-recently-seen entry in the bucket for `node_id` as having
@@ -234,6 +457,42 @@ impl RoutingTable {
         }
     }
 
+    /// Count a failed ping against `node_id` itself, e.g. a keepalive that
+    /// went unanswered. Returns its consecutive failures, or `None` if the
+    /// node is not in the table.
+    pub fn record_failed_ping(&mut self, node_id: &NodeId) -> Option<u32> {
+        let bucket_idx = self.bucket_index(node_id)?;
+        let bucket = &mut self.buckets[bucket_idx];
+        let idx = bucket.find_index(node_id)?;
+        let entry = bucket.entries.get_mut(idx)?;
+        entry.failed_pings += 1;
+        Some(entry.failed_pings)
+    }
+
+    /// Record an answer from `node_id`: clear its failed pings and move it
+    /// to most-recently-seen. Returns `false` if the node is not in the table.
+    pub fn record_ping_response(&mut self, node_id: &NodeId) -> bool {
+        let now = self.env.now();
+        let Some(bucket_idx) = self.bucket_index(node_id) else {
+            return false;
+        };
+        let bucket = &mut self.buckets[bucket_idx];
+        let Some(idx) = bucket.find_index(node_id) else {
+            return false;
+        };
+        bucket.touch(idx, now);
+        true
+    }
+
+    /// Consecutive failed pings of `node_id`, if it is in the table.
+    pub fn failed_pings(&self, node_id: &NodeId) -> Option<u32> {
+        let bucket = &self.buckets[self.bucket_index(node_id)?];
+        bucket
+            .find_index(node_id)
+            .and_then(|idx| bucket.entries.get(idx))
+            .map(|e| e.failed_pings)
+    }
+
     /// Remove a node from the routing table.
     pub fn remove_node(&mut self, node_id: &NodeId) -> Option<NodeInfo> {
         let bucket_idx = self.bucket_index(node_id)?;
@@ -257,7 +516,7 @@ impl RoutingTable {
         }
 
         // Sort by XOR distance (lexicographic byte comparison is correct for XOR distances).
-        all_nodes.sort_by(|a, b| a.1.cmp(&b.1));
+        all_nodes.sort_by_key(|(_, distance)| *distance);
 
         all_nodes
             .into_iter()
@@ -278,13 +537,13 @@ impl RoutingTable {
 
     /// Return bucket indices that need refreshing (haven't been touched
     /// within `refresh_interval`).
-    pub fn stale_buckets(&self, refresh_interval: std::time::Duration) -> Vec<usize> {
-        let now = Instant::now();
+    pub fn stale_buckets(&self, refresh_interval: Duration) -> Vec<usize> {
+        let now = self.env.now();
         self.buckets
             .iter()
             .enumerate()
             .filter(|(_, b)| {
-                !b.entries.is_empty() && now.duration_since(b.last_refresh) > refresh_interval
+                !b.entries.is_empty() && now.saturating_sub(b.last_refresh) > refresh_interval
             })
             .map(|(i, _)| i)
             .collect()
@@ -293,9 +552,25 @@ impl RoutingTable {
     /// Mark a bucket as refreshed.
     pub fn mark_bucket_refreshed(&mut self, bucket_idx: usize) {
         if bucket_idx < NUM_BUCKETS {
-            self.buckets[bucket_idx].last_refresh = Instant::now();
+            self.buckets[bucket_idx].last_refresh = self.env.now();
         }
     }
+
+    /// A random node ID that falls in `bucket_idx`, used as the target of
+    /// the lookup that refreshes the bucket.
+    pub fn random_id_in_bucket(&self, bucket_idx: usize) -> Option<NodeId> {
+        if bucket_idx >= NUM_BUCKETS {
+            return None;
+        }
+        let mut distance = [0u8; 32];
+        self.env.entropy().fill_bytes(&mut distance);
+        // Clear the bits above the bucket's, then set its leading bit
+        let (byte, bit) = (bucket_idx / 8, bucket_idx % 8);
+        distance[..byte].fill(0);
+        distance[byte] &= 0xFF >> bit;
+        distance[byte] |= 0x80 >> bit;
+        Some(Self::xor_distance(&self.local_id, &distance))
+    }
 }
 
 /// Result of attempting to add a node to the routing table.
@@ -310,9 +585,13 @@ pub enum AddNodeResult {
     /// The target bucket is full. Contains the least-recently-seen entry
     /// that should be pinged to check liveness.
     BucketFull {
-        /// The least-recently-seen node in the full bucket.
+        /// The least-recently-seen node in the full bucket, taken from its
+        /// most crowded subnet if any subnet has several entries.
         least_recently_seen: NodeInfo,
     },
+    /// The node was refused because its subnet or AS already has the
+    /// maximum number of entries in the bucket.
+    DiversityLimited,
 }
 
 /// Iterative `FIND_NODE` lookup state machine.
@@ -358,7 +637,7 @@ impl FindNodeLookup {
             })
             .collect();
 
-        candidates.sort_by(|a, b| a.distance.cmp(&b.distance));
+        candidates.sort_by_key(|c| c.distance);
 
         Self {
             target,
@@ -368,6 +647,12 @@ impl FindNodeLookup {
         }
     }
 
+    /// Converge on the `count` closest nodes instead of [`K`].
+    pub fn with_result_count(mut self, count: usize) -> Self {
+        self.result_count = count.max(1);
+        self
+    }
+
     /// Return the next batch of up to `ALPHA` un-queried nodes to send
     /// `FIND_NODE` requests to.
     ///
@@ -416,7 +701,7 @@ impl FindNodeLookup {
         }
 
         // Re-sort by distance.
-        self.candidates.sort_by(|a, b| a.distance.cmp(&b.distance));
+        self.candidates.sort_by_key(|c| c.distance);
 
         // Trim to reasonable size to avoid unbounded growth.
         self.candidates.truncate(self.result_count * 3);
@@ -446,7 +731,7 @@ impl FindNodeLookup {
 /// Compute the number of leading zero bits in a 256-bit value.
 ///
 /// Returns `None` if the value is all zeros (meaning the two node IDs are equal).
-fn leading_zeros(value: &[u8; 32]) -> Option<usize> {
+pub(crate) fn leading_zeros(value: &[u8; 32]) -> Option<usize> {
     for (i, byte) in value.iter().enumerate() {
         if *byte != 0 {
             return Some(i * 8 + byte.leading_zeros() as usize);
@@ -477,6 +762,35 @@ mod socket_addr_serde {
     }
 }
 
+/// Serde support for `Option<SocketAddr>` as an optional string.
+mod opt_socket_addr_serde {
+    use std::net::SocketAddr;
+
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(
+        addr: &Option<SocketAddr>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match addr {
+            Some(addr) => serializer.serialize_some(&addr.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<Option<SocketAddr>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| s.parse().map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -486,6 +800,7 @@ mod tests {
         NodeInfo {
             node_id: [id_byte; 32],
             addr: SocketAddr::from(([127, 0, 0, 1], 4433 + u16::from(id_byte))),
+            alt_addr: None,
             pik_public_key: [id_byte; 32],
             x25519_public_key: [id_byte; 32],
         }
@@ -495,6 +810,19 @@ mod tests {
         NodeInfo {
             node_id: id,
             addr: SocketAddr::from(([127, 0, 0, 1], 4433)),
+            alt_addr: None,
+            pik_public_key: [0u8; 32],
+            x25519_public_key: [0u8; 32],
+        }
+    }
+
+    fn make_node_at(id_byte: u8, ip: [u8; 4]) -> NodeInfo {
+        let mut id = [0x80u8; 32];
+        id[31] = id_byte;
+        NodeInfo {
+            node_id: id,
+            addr: SocketAddr::from((ip, 4433)),
+            alt_addr: None,
             pik_public_key: [0u8; 32],
             x25519_public_key: [0u8; 32],
         }
@@ -596,6 +924,22 @@ mod tests {
         assert!(removed.is_none());
     }
 
+    #[test]
+    fn test_per_node_ping_accounting() {
+        let mut table = RoutingTable::new([0x00u8; 32]);
+        let node = make_node(0x01);
+        table.add_node(node.clone());
+
+        assert_eq!(table.record_failed_ping(&node.node_id), Some(1));
+        assert_eq!(table.record_failed_ping(&node.node_id), Some(2));
+        assert_eq!(table.failed_pings(&node.node_id), Some(2));
+        assert!(table.record_ping_response(&node.node_id));
+        assert_eq!(table.failed_pings(&node.node_id), Some(0));
+
+        assert_eq!(table.record_failed_ping(&[0xFFu8; 32]), None);
+        assert!(!table.record_ping_response(&[0xFFu8; 32]));
+    }
+
     #[test]
     fn test_bucket_full() {
         let local_id = [0x00u8; 32];
@@ -620,6 +964,30 @@ mod tests {
         assert!(matches!(result, AddNodeResult::BucketFull { .. }));
     }
 
+    #[test]
+    fn test_profile_bucket_size() {
+        assert_eq!(
+            ochra_types::profile::NetworkProfile::mainnet().dht_k as usize,
+            K
+        );
+        let mut table = RoutingTable::new([0x00u8; 32]).with_bucket_size(4);
+        for i in 0..4 {
+            let mut id = [0x80u8; 32];
+            id[31] = i;
+            assert!(matches!(
+                table.add_node(make_node_with_id(id)),
+                AddNodeResult::Inserted
+            ));
+        }
+        let mut id = [0x80u8; 32];
+        id[31] = 4;
+        assert!(matches!(
+            table.add_node(make_node_with_id(id)),
+            AddNodeResult::BucketFull { .. }
+        ));
+        assert_eq!(table.bucket_size(), 4);
+    }
+
     #[test]
     fn test_evict_and_insert() {
         let local_id = [0x00u8; 32];
@@ -737,4 +1105,155 @@ mod tests {
         let table = RoutingTable::new([0u8; 32]);
         assert!(table.is_empty());
     }
+
+    #[test]
+    fn test_subnet_limit_per_bucket() {
+        let mut table = RoutingTable::new([0x00u8; 32]);
+        for i in 0..2 {
+            let result = table.add_node(make_node_at(i, [203, 0, 113, i + 1]));
+            assert!(matches!(result, AddNodeResult::Inserted));
+        }
+        let crowded = table.add_node(make_node_at(2, [203, 0, 113, 50]));
+        assert!(matches!(crowded, AddNodeResult::DiversityLimited));
+
+        // A different /24 in the same /16 is fine.
+        let other = table.add_node(make_node_at(3, [203, 0, 114, 1]));
+        assert!(matches!(other, AddNodeResult::Inserted));
+        assert_eq!(table.len(), 3);
+    }
+
+    #[test]
+    fn test_dual_stack_counts_both_subnets() {
+        let v6 = |host: u16| {
+            SocketAddr::from((
+                std::net::Ipv6Addr::new(0x2001, 0xdb8, 0x1, 0, 0, 0, 0, host),
+                4433,
+            ))
+        };
+        let mut table = RoutingTable::new([0x00u8; 32]);
+        for i in 0..2 {
+            let mut node = make_node_at(i, [198, 51, 100 + i, 1]);
+            node.alt_addr = Some(v6(u16::from(i) + 1));
+            assert!(matches!(table.add_node(node), AddNodeResult::Inserted));
+        }
+        // A fresh /24 does not help when the IPv6 address shares the /48.
+        let mut crowded = make_node_at(2, [192, 0, 2, 1]);
+        crowded.alt_addr = Some(v6(99));
+        assert!(matches!(
+            table.add_node(crowded),
+            AddNodeResult::DiversityLimited
+        ));
+
+        let ipv6_only = NodeInfo {
+            addr: v6(100),
+            ..make_node_at(3, [0, 0, 0, 0])
+        };
+        assert!(matches!(
+            table.add_node(ipv6_only),
+            AddNodeResult::DiversityLimited
+        ));
+    }
+
+    #[test]
+    fn test_node_info_alt_addr_serde() {
+        let mut node = make_node(1);
+        node.alt_addr = Some("[2001:db8::1]:4433".parse().expect("addr"));
+        let json = serde_json::to_string(&node).expect("serialize");
+        let restored: NodeInfo = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(restored.alt_addr, node.alt_addr);
+        assert_eq!(restored.addrs().count(), 2);
+    }
+
+    #[test]
+    fn test_asn_limit_when_known() {
+        let mut table = RoutingTable::with_diversity(
+            [0x00u8; 32],
+            DiversityConfig {
+                max_per_asn: 2,
+                ..DiversityConfig::default()
+            },
+        );
+        for i in 0..2 {
+            let result = table.add_node_with_asn(make_node_at(i, [198, 51, i, 1]), Some(64500));
+            assert!(matches!(result, AddNodeResult::Inserted));
+        }
+        let same_as = table.add_node_with_asn(make_node_at(2, [198, 51, 9, 1]), Some(64500));
+        assert!(matches!(same_as, AddNodeResult::DiversityLimited));
+
+        // Unknown AS is only subject to the subnet limit.
+        let unknown = table.add_node(make_node_at(3, [198, 51, 10, 1]));
+        assert!(matches!(unknown, AddNodeResult::Inserted));
+    }
+
+    #[test]
+    fn test_unrestricted_for_test_networks() {
+        let mut table = RoutingTable::with_diversity([0x00u8; 32], DiversityConfig::unrestricted());
+        for i in 0..5 {
+            let result = table.add_node(make_node_at(i, [10, 0, 0, i + 1]));
+            assert!(matches!(result, AddNodeResult::Inserted));
+        }
+    }
+
+    #[test]
+    fn test_eviction_candidate_from_crowded_subnet() {
+        let mut table = RoutingTable::new([0x00u8; 32]);
+        // K - 2 distinct subnets, then two entries sharing one subnet.
+        for i in 0..(K as u8 - 2) {
+            table.add_node(make_node_at(i, [100, 64, i, 1]));
+        }
+        let crowded_first = make_node_at(100, [192, 0, 2, 1]);
+        table.add_node(crowded_first.clone());
+        table.add_node(make_node_at(101, [192, 0, 2, 2]));
+        assert_eq!(table.len(), K);
+
+        let result = table.add_node(make_node_at(200, [172, 16, 0, 1]));
+        assert!(matches!(
+            result,
+            AddNodeResult::BucketFull { least_recently_seen }
+                if least_recently_seen.node_id == crowded_first.node_id
+        ));
+    }
+
+    #[test]
+    fn test_evict_and_insert_respects_diversity() {
+        let mut table = RoutingTable::new([0x00u8; 32]);
+        let stale = make_node_at(0, [100, 64, 0, 1]);
+        table.add_node(stale.clone());
+        table.add_node(make_node_at(1, [192, 0, 2, 1]));
+        table.add_node(make_node_at(2, [192, 0, 2, 2]));
+
+        let crowded = make_node_at(3, [192, 0, 2, 3]);
+        assert!(matches!(
+            table.evict_and_insert(&stale.node_id, crowded),
+            Err(DhtError::DiversityLimit)
+        ));
+        assert_eq!(table.len(), 3);
+    }
+
+    #[test]
+    fn test_stale_buckets_on_virtual_clock() {
+        let clock = ochra_sim::VirtualClock::new(0);
+        let mut table = RoutingTable::new([0u8; 32]).with_env(SimEnv::simulated(1, clock.clone()));
+        table.add_node(make_node(1));
+        let interval = Duration::from_secs(3600);
+        assert!(table.stale_buckets(interval).is_empty());
+
+        clock.advance(interval + Duration::from_secs(1));
+        let stale = table.stale_buckets(interval);
+        assert_eq!(stale.len(), 1);
+
+        table.mark_bucket_refreshed(stale[0]);
+        assert!(table.stale_buckets(interval).is_empty());
+    }
+
+    #[test]
+    fn test_random_id_in_bucket() {
+        let local = [0x5Au8; 32];
+        let table = RoutingTable::new(local);
+        for idx in [0, 7, 8, 100, NUM_BUCKETS - 1] {
+            let id = table.random_id_in_bucket(idx).expect("id");
+            assert_eq!(table.bucket_index(&id), Some(idx));
+        }
+        assert!(table.random_id_in_bucket(NUM_BUCKETS).is_none());
+    }
 }
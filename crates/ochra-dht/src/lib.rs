@@ -4,9 +4,23 @@
 //!
 //! This crate implements:
 //! - Kademlia routing table with XOR-distance metric (K=20, alpha=3, 256 buckets)
+//!   and per-bucket IP diversity limits
 //! - BEP 44 mutable and immutable record storage with signature validation
 //! - Multi-record chunking for payloads exceeding the 1000-byte DHT record limit
-//! - Bootstrap logic for joining the network via seed nodes
+//! - Reed-Solomon sharding of critical records across distinct buckets and subnets
+//! - PIK revocation certificates published at a well-known address per PIK
+//! - Bootstrap logic for joining the network via seed nodes, with cached,
+//!   hardcoded, signed-list, and DNS seed sources
+//! - Bootstrap agreement across independent seed operators, told apart by
+//!   the Public Suffix List, with divergent views reported as possible
+//!   eclipse attempts
+//! - Storage quotas, put rate limits, and large-value admission
+//! - A record type registry validating known record types on put
+//! - Time-locked records sealed to the quorum until a target epoch
+//! - Quorum-signed epoch beacon records
+//! - Owner-signed Space policy records and ownership transfer chains
+//! - Privacy levels for lookups routed through onion circuits
+//! - A rate-limited crawler producing anonymized network health reports
 //!
 //! ## Key Parameters
 //!
@@ -20,10 +34,22 @@
 //! | Ping timeout | 5 seconds |
 //! | Node ID derivation | `BLAKE3::hash(pik_public_key)[:32]` |
 
+pub mod agreement;
+pub mod beacon;
 pub mod bep44;
 pub mod bootstrap;
 pub mod chunking;
+pub mod crawl;
+pub mod group_policy;
 pub mod kademlia;
+pub mod private_lookup;
+pub mod public_suffix;
+pub mod quota;
+pub mod record_types;
+pub mod revocation;
+pub mod seeds;
+pub mod sharding;
+pub mod timelock;
 
 /// Kademlia bucket size: maximum contacts per bucket.
 pub const K: usize = 20;
@@ -57,6 +83,13 @@ pub enum DhtError {
     #[error("invalid record signature")]
     InvalidSignature,
 
+    /// A record of a registered type failed that type's validation.
+    #[error("invalid {record_type} record: {reason}")]
+    InvalidRecord {
+        record_type: &'static str,
+        reason: String,
+    },
+
     /// The record's sequence number is stale (a newer version exists).
     #[error("stale sequence number: got {got}, have {have}")]
     StaleSequence { got: u64, have: u64 },
@@ -72,14 +105,34 @@ pub enum DhtError {
     #[error("missing chunk {index} of {total}")]
     MissingChunk { index: u32, total: u32 },
 
+    /// Too few intact shards were fetched to rebuild a sharded record.
+    #[error("insufficient shards: have {have}, need {need}")]
+    InsufficientShards { have: usize, need: usize },
+
+    /// A sharding policy has an out-of-range shard count.
+    #[error("invalid sharding policy: {data_shards} of {total_shards} shards")]
+    InvalidShardingPolicy { data_shards: u8, total_shards: u8 },
+
     /// The routing table bucket is full and all entries are still alive.
     #[error("bucket full")]
     BucketFull,
 
+    /// The node would exceed the bucket's IP diversity limits.
+    #[error("routing table diversity limit reached")]
+    DiversityLimit,
+
     /// Bootstrap failed to discover any peers.
     #[error("bootstrap failed: {0}")]
     BootstrapFailed(String),
 
+    /// A lookup required a circuit and none was available.
+    #[error("no circuit available for {0} lookup")]
+    CircuitUnavailable(&'static str),
+
+    /// An exit refused a lookup because the circuit used its quota.
+    #[error("circuit lookup limit reached")]
+    CircuitLookupLimit,
+
     /// Network or I/O error.
     #[error("network error: {0}")]
     Network(String),
@@ -88,11 +141,34 @@ pub enum DhtError {
     #[error("serialization error: {0}")]
     Serialization(String),
 
+    /// A remote put was refused by the storage quota layer.
+    #[error("put rejected: {0}")]
+    QuotaExceeded(#[from] quota::QuotaRejection),
+
+    /// PoW error from ochra-pow.
+    #[error("pow error: {0}")]
+    Pow(#[from] ochra_pow::PowError),
+
     /// Cryptographic error from ochra-crypto.
     #[error("crypto error: {0}")]
     Crypto(#[from] ochra_crypto::CryptoError),
 }
 
+impl DhtError {
+    /// Machine-readable reason for a rejected put, as sent in
+    /// `DhtPutResponse.reason`.
+    pub fn reject_reason(&self) -> &'static str {
+        match self {
+            DhtError::RecordTooLarge { .. } => "value_too_large",
+            DhtError::InvalidSignature | DhtError::Crypto(_) => "invalid_sig",
+            DhtError::StaleSequence { .. } => "stale_seq",
+            DhtError::InvalidRecord { .. } => "invalid_record",
+            DhtError::QuotaExceeded(rejection) => rejection.reason(),
+            _ => "internal",
+        }
+    }
+}
+
 /// Convenience result type for DHT operations.
 pub type Result<T> = std::result::Result<T, DhtError>;
 
@@ -120,4 +196,24 @@ mod tests {
         assert!(err.to_string().contains("2000"));
         assert!(err.to_string().contains("1000"));
     }
+
+    #[test]
+    fn test_reject_reasons() {
+        assert_eq!(
+            DhtError::StaleSequence { got: 1, have: 2 }.reject_reason(),
+            "stale_seq"
+        );
+        assert_eq!(
+            DhtError::InvalidRecord {
+                record_type: "handle_descriptor",
+                reason: String::new(),
+            }
+            .reject_reason(),
+            "invalid_record"
+        );
+        assert_eq!(
+            DhtError::from(quota::QuotaRejection::StoreFull).reject_reason(),
+            "store_full"
+        );
+    }
 }
@@ -0,0 +1,238 @@
+//! Private DHT lookups routed through onion circuits.
+//!
+//! An iterative lookup contacts up to `K` nodes on its way to a key, and
+//! every one of them learns which key the querier is interested in. For
+//! lookups that reveal user activity (a handle being resolved, a Space
+//! manifest being fetched) the querier can instead wrap the query in a
+//! `DhtCircuitLookup` payload and send it through a Sphinx circuit. The
+//! exit relay runs the iterative lookup on its own routing table and
+//! returns the result in a `DhtCircuitLookupResponse`, so contacted nodes
+//! see the exit, not the querier.
+//!
+//! ## Privacy Levels
+//!
+//! Each lookup kind has a [`PrivacyLevel`]:
+//!
+//! | Level | With a circuit | Without a circuit |
+//! |---|---|---|
+//! | [`PrivacyLevel::Direct`] | direct | direct |
+//! | [`PrivacyLevel::Circuit`] | circuit | direct (fallback) |
+//! | [`PrivacyLevel::CircuitRequired`] | circuit | fails |
+//!
+//! Value lookups default to `Circuit`; node lookups, which mostly serve
+//! routing table maintenance, default to `Direct`.
+//!
+//! ## Exit Limits
+//!
+//! An exit performs lookups for clients it cannot identify, so each circuit
+//! may run at most [`MAX_LOOKUPS_PER_CIRCUIT`] lookups through a given exit
+//! before the exit refuses further queries on it.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{DhtError, Result};
+
+/// Maximum lookups an exit runs for one circuit.
+pub const MAX_LOOKUPS_PER_CIRCUIT: u32 = 64;
+
+/// Kind of DHT lookup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupKind {
+    /// Value lookup (`DhtGet`).
+    Get,
+    /// Node lookup (`DhtFindNode`).
+    FindNode,
+}
+
+impl LookupKind {
+    /// The snake_case name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "get",
+            Self::FindNode => "find_node",
+        }
+    }
+}
+
+/// How a lookup kind is routed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyLevel {
+    /// Always query nodes directly.
+    Direct,
+    /// Query through a circuit, falling back to direct when none is up.
+    Circuit,
+    /// Query only through a circuit.
+    CircuitRequired,
+}
+
+impl PrivacyLevel {
+    /// Parse a snake_case level name.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "direct" => Some(Self::Direct),
+            "circuit" => Some(Self::Circuit),
+            "circuit_required" => Some(Self::CircuitRequired),
+            _ => None,
+        }
+    }
+}
+
+/// Where a lookup is sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LookupRoute {
+    /// Iterative lookup from this node.
+    Direct,
+    /// Lookup delegated to a circuit's exit relay.
+    Circuit,
+}
+
+/// Privacy level for each lookup kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupPrivacy {
+    /// Level for value lookups.
+    pub get: PrivacyLevel,
+    /// Level for node lookups.
+    pub find_node: PrivacyLevel,
+}
+
+impl Default for LookupPrivacy {
+    fn default() -> Self {
+        Self {
+            get: PrivacyLevel::Circuit,
+            find_node: PrivacyLevel::Direct,
+        }
+    }
+}
+
+impl LookupPrivacy {
+    /// The level configured for `kind`.
+    pub fn level(&self, kind: LookupKind) -> PrivacyLevel {
+        match kind {
+            LookupKind::Get => self.get,
+            LookupKind::FindNode => self.find_node,
+        }
+    }
+
+    /// Choose a route for a lookup of `kind`.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::CircuitUnavailable`] if the level requires a circuit
+    ///   and none is available
+    pub fn route(&self, kind: LookupKind, circuit_available: bool) -> Result<LookupRoute> {
+        match (self.level(kind), circuit_available) {
+            (PrivacyLevel::Direct, _) | (PrivacyLevel::Circuit, false) => Ok(LookupRoute::Direct),
+            (_, true) => Ok(LookupRoute::Circuit),
+            (PrivacyLevel::CircuitRequired, false) => {
+                Err(DhtError::CircuitUnavailable(kind.as_str()))
+            }
+        }
+    }
+}
+
+/// Exit-side accounting of lookups run for each circuit.
+#[derive(Debug, Default)]
+pub struct ExitLookupLimiter {
+    counts: HashMap<[u8; 16], u32>,
+}
+
+impl ExitLookupLimiter {
+    /// Create an empty limiter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Admit one lookup on `circuit_id`.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::CircuitLookupLimit`] if the circuit has used its quota
+    pub fn admit(&mut self, circuit_id: &[u8; 16]) -> Result<()> {
+        let count = self.counts.entry(*circuit_id).or_insert(0);
+        if *count >= MAX_LOOKUPS_PER_CIRCUIT {
+            return Err(DhtError::CircuitLookupLimit);
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Forget a circuit that was torn down.
+    pub fn release(&mut self, circuit_id: &[u8; 16]) {
+        self.counts.remove(circuit_id);
+    }
+
+    /// Number of circuits being tracked.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Whether no circuits are being tracked.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_routes_and_fallback() {
+        let privacy = LookupPrivacy::default();
+        assert_eq!(
+            privacy.route(LookupKind::Get, true).expect("route"),
+            LookupRoute::Circuit
+        );
+        assert_eq!(
+            privacy.route(LookupKind::Get, false).expect("route"),
+            LookupRoute::Direct
+        );
+        assert_eq!(
+            privacy.route(LookupKind::FindNode, true).expect("route"),
+            LookupRoute::Direct
+        );
+    }
+
+    #[test]
+    fn test_required_circuit_fails_without_one() {
+        let privacy = LookupPrivacy {
+            get: PrivacyLevel::CircuitRequired,
+            ..LookupPrivacy::default()
+        };
+        assert_eq!(
+            privacy.route(LookupKind::Get, true).expect("route"),
+            LookupRoute::Circuit
+        );
+        assert!(matches!(
+            privacy.route(LookupKind::Get, false),
+            Err(DhtError::CircuitUnavailable("get"))
+        ));
+        assert_eq!(
+            PrivacyLevel::parse("circuit_required"),
+            Some(PrivacyLevel::CircuitRequired)
+        );
+        assert_eq!(PrivacyLevel::parse("onion"), None);
+    }
+
+    #[test]
+    fn test_exit_limiter() {
+        let mut limiter = ExitLookupLimiter::new();
+        let circuit = [1u8; 16];
+        for _ in 0..MAX_LOOKUPS_PER_CIRCUIT {
+            limiter.admit(&circuit).expect("admit");
+        }
+        assert!(matches!(
+            limiter.admit(&circuit),
+            Err(DhtError::CircuitLookupLimit)
+        ));
+        limiter.admit(&[2u8; 16]).expect("other circuit");
+
+        limiter.release(&circuit);
+        assert_eq!(limiter.len(), 1);
+        limiter.admit(&circuit).expect("admit after release");
+    }
+}
@@ -0,0 +1,118 @@
+//! Registrable domains by the Public Suffix List.
+//!
+//! Two hosts belong to the same operator when they share a registrable
+//! domain: the public suffix they sit under plus one more label. The
+//! suffix is not simply the last label, so `a.co.uk` and `b.co.uk` are two
+//! operators and `a.github.io` and `b.github.io` are too.
+//!
+//! Rules are read from `data/public_suffix_list.dat` in the upstream list's
+//! format. Among the rules matching a host, an exception (`!`) wins,
+//! otherwise the one with the most labels; a wildcard (`*.`) counts its
+//! `*` as a label. A host no rule matches has its last label as suffix.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+const LIST: &str = include_str!("../data/public_suffix_list.dat");
+
+/// Parsed suffix rules, without their `*.` or `!` markers.
+#[derive(Debug, Default)]
+struct Rules {
+    exact: HashSet<String>,
+    wildcard: HashSet<String>,
+    exception: HashSet<String>,
+}
+
+impl Rules {
+    fn parse(list: &str) -> Self {
+        let mut rules = Self::default();
+        for line in list.lines() {
+            let Some(rule) = line.split_whitespace().next() else {
+                continue;
+            };
+            if rule.starts_with("//") {
+                continue;
+            }
+            let rule = rule.to_ascii_lowercase();
+            if let Some(rest) = rule.strip_prefix("!") {
+                rules.exception.insert(rest.to_string());
+            } else if let Some(rest) = rule.strip_prefix("*.") {
+                rules.wildcard.insert(rest.to_string());
+            } else {
+                rules.exact.insert(rule);
+            }
+        }
+        rules
+    }
+
+    /// Number of labels in the public suffix of `labels`.
+    fn suffix_len(&self, labels: &[&str]) -> usize {
+        let mut longest = 1;
+        for start in 0..labels.len() {
+            let candidate = labels[start..].join(".");
+            let len = labels.len() - start;
+            if self.exception.contains(&candidate) {
+                return len - 1;
+            }
+            if self.exact.contains(&candidate) {
+                longest = longest.max(len);
+            }
+            if start + 1 < labels.len() && self.wildcard.contains(&labels[start + 1..].join(".")) {
+                longest = longest.max(len);
+            }
+        }
+        longest
+    }
+}
+
+fn rules() -> &'static Rules {
+    static RULES: OnceLock<Rules> = OnceLock::new();
+    RULES.get_or_init(|| Rules::parse(LIST))
+}
+
+/// The registrable domain of `host`, lowercased. IP addresses and hosts
+/// that are themselves public suffixes are returned whole.
+pub fn registrable_domain(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    let keep = rules().suffix_len(&labels) + 1;
+    if keep >= labels.len() {
+        return host;
+    }
+    labels[labels.len() - keep..].join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_label_suffixes() {
+        assert_eq!(registrable_domain("seeds.ochra.net"), "ochra.net");
+        assert_eq!(registrable_domain("a.co.uk"), "a.co.uk");
+        assert_eq!(registrable_domain("seeds.a.co.uk"), "a.co.uk");
+        assert_ne!(registrable_domain("a.co.uk"), registrable_domain("b.co.uk"));
+        assert_eq!(registrable_domain("alice.github.io"), "alice.github.io");
+        assert_eq!(registrable_domain("Seeds.Example.ORG."), "example.org");
+    }
+
+    #[test]
+    fn test_wildcards_and_exceptions() {
+        // *.ck makes every second-level name a suffix, except www.ck.
+        assert_eq!(registrable_domain("seeds.foo.ck"), "seeds.foo.ck");
+        assert_eq!(registrable_domain("seeds.www.ck"), "www.ck");
+        assert_eq!(registrable_domain("a.b.kawasaki.jp"), "a.b.kawasaki.jp");
+        assert_eq!(registrable_domain("a.city.kawasaki.jp"), "city.kawasaki.jp");
+    }
+
+    #[test]
+    fn test_suffixes_and_addresses_returned_whole() {
+        assert_eq!(registrable_domain("co.uk"), "co.uk");
+        assert_eq!(registrable_domain("localhost"), "localhost");
+        assert_eq!(registrable_domain("192.0.2.1"), "192.0.2.1");
+        assert_eq!(registrable_domain("2001:db8::1"), "2001:db8::1");
+    }
+}
@@ -0,0 +1,461 @@
+//! Storage quotas and put admission for remote records.
+//!
+//! A [`RecordStore`](crate::bep44::RecordStore) bounds what remote peers can
+//! make it store (see [`RecordStore::put_from`](crate::bep44::RecordStore::put_from)):
+//!
+//! - **Global byte cap.** When full, the least recently used records this
+//!   node merely caches are evicted; records it replicates as one of the
+//!   closest nodes are never evicted for space.
+//! - **Per-publisher byte cap.** Mutable records are charged to their public
+//!   key, immutable records to the peer that sent the put.
+//! - **Per-peer put rate.** A token bucket per sending peer.
+//! - **Admission for large values.** Values above a size threshold need an
+//!   Argon2id PoW bound to the record, or a single-use token this node granted.
+//!
+//! A rejected put yields a [`QuotaRejection`], whose [`reason`](QuotaRejection::reason)
+//! is returned to the peer in `DhtPutResponse.reason`.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use ochra_pow::argon2id_pow::{self, PowChallenge, PowSolution};
+
+use crate::bep44::DhtRecord;
+use crate::Result;
+
+/// Default global cap on stored value bytes (64 MiB).
+pub const DEFAULT_MAX_TOTAL_BYTES: usize = 64 * 1024 * 1024;
+
+/// Default cap on value bytes charged to one publisher (256 KiB).
+pub const DEFAULT_MAX_BYTES_PER_PUBLISHER: usize = 256 * 1024;
+
+/// Default size above which a value needs admission.
+pub const DEFAULT_LARGE_VALUE_THRESHOLD: usize = 512;
+
+/// Default admission PoW difficulty in leading zero bits.
+pub const DEFAULT_ADMISSION_DIFFICULTY: u32 = 8;
+
+/// Default number of puts a peer may send back to back.
+pub const DEFAULT_PUT_BURST: u32 = 32;
+
+/// Default sustained put rate per peer.
+pub const DEFAULT_PUTS_PER_SEC: f64 = 2.0;
+
+/// Domain prefix of the admission PoW challenge.
+const ADMISSION_PREFIX: &[u8] = b"dht-put";
+
+/// Quota parameters.
+#[derive(Clone, Debug)]
+pub struct QuotaConfig {
+    /// Global cap on stored value bytes.
+    pub max_total_bytes: usize,
+    /// Cap on value bytes charged to one publisher.
+    pub max_bytes_per_publisher: usize,
+    /// Values longer than this need admission.
+    pub large_value_threshold: usize,
+    /// Leading zero bits required of an admission PoW.
+    pub admission_difficulty: u32,
+    /// Puts a peer may send back to back.
+    pub put_burst: u32,
+    /// Sustained puts per second per peer.
+    pub puts_per_sec: f64,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+            max_bytes_per_publisher: DEFAULT_MAX_BYTES_PER_PUBLISHER,
+            large_value_threshold: DEFAULT_LARGE_VALUE_THRESHOLD,
+            admission_difficulty: DEFAULT_ADMISSION_DIFFICULTY,
+            put_burst: DEFAULT_PUT_BURST,
+            puts_per_sec: DEFAULT_PUTS_PER_SEC,
+        }
+    }
+}
+
+impl QuotaConfig {
+    /// Default quotas with the admission difficulty of `profile`.
+    pub fn for_network(profile: &ochra_types::profile::NetworkProfile) -> Self {
+        Self {
+            admission_difficulty: profile.dht_admission_difficulty,
+            ..Self::default()
+        }
+    }
+}
+
+/// Proof accompanying a put of a large value.
+#[derive(Clone, Debug)]
+pub enum Admission {
+    /// No proof; only accepted for values up to the threshold.
+    None,
+    /// Argon2id PoW over [`admission_challenge`].
+    Pow(PowSolution),
+    /// Single-use token granted by the storing node.
+    Token([u8; 32]),
+}
+
+/// Why a put was refused by the quota layer.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum QuotaRejection {
+    /// The sending peer exceeded its put rate.
+    #[error("put rate exceeded, retry in {retry_after_ms} ms")]
+    RateLimited { retry_after_ms: u64 },
+
+    /// The value is large and no admission proof was given.
+    #[error("values over {threshold} bytes need admission at difficulty {difficulty}")]
+    AdmissionRequired { threshold: usize, difficulty: u32 },
+
+    /// The admission PoW or token did not verify.
+    #[error("invalid admission proof")]
+    InvalidAdmission,
+
+    /// The publisher's byte cap would be exceeded.
+    #[error("publisher quota exceeded: {used} of {max} bytes used")]
+    PublisherQuota { used: usize, max: usize },
+
+    /// Not enough evictable space for the value.
+    #[error("store full")]
+    StoreFull,
+}
+
+impl QuotaRejection {
+    /// Machine-readable reason for `DhtPutResponse.reason`.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::RateLimited { .. } => "rate_limited",
+            Self::AdmissionRequired { .. } => "admission_required",
+            Self::InvalidAdmission => "invalid_admission",
+            Self::PublisherQuota { .. } => "publisher_quota",
+            Self::StoreFull => "store_full",
+        }
+    }
+}
+
+/// The PoW challenge for admitting `record`.
+///
+/// The solver binds `BLAKE3::hash(value)` as the content hash, so a proof
+/// is only valid for one key and value.
+pub fn admission_challenge(record: &DhtRecord, difficulty: u32) -> PowChallenge {
+    PowChallenge {
+        target_hash: record.storage_key(),
+        difficulty,
+        nonce_prefix: ADMISSION_PREFIX.to_vec(),
+    }
+}
+
+/// Solve the admission PoW for `record`.
+///
+/// # Errors
+///
+/// Returns [`DhtError::Pow`](crate::DhtError::Pow) if hashing fails.
+pub fn solve_admission(record: &DhtRecord, difficulty: u32) -> Result<Admission> {
+    let challenge = admission_challenge(record, difficulty);
+    let content_hash = ochra_crypto::blake3::hash(record.value());
+    Ok(Admission::Pow(argon2id_pow::solve_pow(
+        &challenge,
+        &content_hash,
+    )?))
+}
+
+/// Token bucket of one peer.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Byte accounting, rate buckets, and granted tokens of a record store.
+#[derive(Debug)]
+pub(crate) struct QuotaState {
+    pub(crate) config: QuotaConfig,
+    total_bytes: usize,
+    per_publisher: HashMap<[u8; 32], usize>,
+    buckets: HashMap<[u8; 32], Bucket>,
+    tokens: HashSet<[u8; 32]>,
+}
+
+impl QuotaState {
+    pub(crate) fn new(config: QuotaConfig) -> Self {
+        Self {
+            config,
+            total_bytes: 0,
+            per_publisher: HashMap::new(),
+            buckets: HashMap::new(),
+            tokens: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub(crate) fn publisher_bytes(&self, publisher: &[u8; 32]) -> usize {
+        self.per_publisher
+            .get(publisher)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn charge(&mut self, publisher: [u8; 32], size: usize) {
+        self.total_bytes += size;
+        *self.per_publisher.entry(publisher).or_default() += size;
+    }
+
+    pub(crate) fn release(&mut self, publisher: &[u8; 32], size: usize) {
+        self.total_bytes = self.total_bytes.saturating_sub(size);
+        if let Some(used) = self.per_publisher.get_mut(publisher) {
+            *used = used.saturating_sub(size);
+            if *used == 0 {
+                self.per_publisher.remove(publisher);
+            }
+        }
+    }
+
+    /// Take one put from `peer`'s bucket.
+    pub(crate) fn take_put(
+        &mut self,
+        peer: [u8; 32],
+        now: Instant,
+    ) -> std::result::Result<(), QuotaRejection> {
+        let burst = f64::from(self.config.put_burst);
+        let rate = self.config.puts_per_sec;
+        let bucket = self.buckets.entry(peer).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let retry_after_ms = if rate > 0.0 {
+            ((1.0 - bucket.tokens) / rate * 1000.0).ceil() as u64
+        } else {
+            u64::MAX
+        };
+        Err(QuotaRejection::RateLimited { retry_after_ms })
+    }
+
+    /// Drop buckets that have refilled completely.
+    pub(crate) fn prune_buckets(&mut self, now: Instant) {
+        let burst = f64::from(self.config.put_burst);
+        let rate = self.config.puts_per_sec;
+        self.buckets.retain(|_, b| {
+            let elapsed = now.saturating_duration_since(b.updated).as_secs_f64();
+            b.tokens + elapsed * rate < burst
+        });
+    }
+
+    pub(crate) fn grant_token(&mut self, token: [u8; 32]) {
+        self.tokens.insert(token);
+    }
+
+    /// Check the admission proof for `record`, consuming a token if used.
+    pub(crate) fn admit(
+        &mut self,
+        record: &DhtRecord,
+        admission: &Admission,
+    ) -> std::result::Result<(), QuotaRejection> {
+        if record.value_len() <= self.config.large_value_threshold {
+            return Ok(());
+        }
+        match admission {
+            Admission::None => Err(QuotaRejection::AdmissionRequired {
+                threshold: self.config.large_value_threshold,
+                difficulty: self.config.admission_difficulty,
+            }),
+            Admission::Pow(solution) => {
+                let challenge = admission_challenge(record, self.config.admission_difficulty);
+                let content_hash = ochra_crypto::blake3::hash(record.value());
+                if argon2id_pow::verify_pow_with_content(&challenge, &content_hash, solution) {
+                    Ok(())
+                } else {
+                    Err(QuotaRejection::InvalidAdmission)
+                }
+            }
+            Admission::Token(token) => {
+                if self.tokens.remove(token) {
+                    Ok(())
+                } else {
+                    Err(QuotaRejection::InvalidAdmission)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bep44::{create_immutable_record, create_mutable_record, RecordStore};
+    use crate::DhtError;
+    use ochra_crypto::ed25519::KeyPair;
+
+    fn config() -> QuotaConfig {
+        QuotaConfig {
+            max_total_bytes: 1000,
+            max_bytes_per_publisher: 600,
+            large_value_threshold: 400,
+            admission_difficulty: 0,
+            put_burst: 100,
+            puts_per_sec: 100.0,
+        }
+    }
+
+    fn rejection(result: Result<()>) -> Option<QuotaRejection> {
+        match result {
+            Err(DhtError::QuotaExceeded(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_admission_difficulty_from_profile() {
+        use ochra_types::profile::NetworkProfile;
+
+        let mainnet = QuotaConfig::for_network(&NetworkProfile::mainnet());
+        assert_eq!(mainnet.admission_difficulty, DEFAULT_ADMISSION_DIFFICULTY);
+        let testnet = QuotaConfig::for_network(&NetworkProfile::testnet());
+        assert!(testnet.admission_difficulty < DEFAULT_ADMISSION_DIFFICULTY);
+        assert_eq!(testnet.max_total_bytes, DEFAULT_MAX_TOTAL_BYTES);
+    }
+
+    #[test]
+    fn test_rate_limited_per_peer() {
+        let mut store = RecordStore::with_quota(QuotaConfig {
+            put_burst: 2,
+            puts_per_sec: 1.0,
+            ..config()
+        });
+        for i in 0..2u8 {
+            let record = create_immutable_record(vec![i]).expect("record");
+            store
+                .put_from([1; 32], record, &Admission::None, false)
+                .expect("put");
+        }
+        let record = create_immutable_record(vec![9]).expect("record");
+        let result = store.put_from([1; 32], record.clone(), &Admission::None, false);
+        assert!(matches!(
+            rejection(result),
+            Some(QuotaRejection::RateLimited { retry_after_ms }) if retry_after_ms > 0
+        ));
+        // Other peers have their own bucket.
+        store
+            .put_from([2; 32], record, &Admission::None, false)
+            .expect("put");
+    }
+
+    #[test]
+    fn test_publisher_quota() {
+        let mut store = RecordStore::with_quota(config());
+        let kp = KeyPair::generate();
+        let put = |store: &mut RecordStore, salt: &[u8], seq: u64| {
+            let record =
+                create_mutable_record(&kp.signing_key, salt, seq, vec![0; 300]).expect("record");
+            store.put_from([1; 32], record, &Admission::None, true)
+        };
+        put(&mut store, b"a", 1).expect("put");
+        put(&mut store, b"b", 1).expect("put");
+        assert!(matches!(
+            rejection(put(&mut store, b"c", 1)),
+            Some(QuotaRejection::PublisherQuota {
+                used: 600,
+                max: 600
+            })
+        ));
+        // Replacing an existing record only charges the difference.
+        put(&mut store, b"a", 2).expect("update");
+        assert_eq!(store.publisher_bytes(&kp.verifying_key.to_bytes()), 600);
+    }
+
+    #[test]
+    fn test_large_values_need_admission() {
+        let mut store = RecordStore::with_quota(config());
+        let record = create_immutable_record(vec![7; 500]).expect("record");
+        assert!(matches!(
+            rejection(store.put_from([1; 32], record.clone(), &Admission::None, false)),
+            Some(QuotaRejection::AdmissionRequired { threshold: 400, .. })
+        ));
+
+        let admission = solve_admission(&record, 0).expect("solve");
+        store
+            .put_from([1; 32], record.clone(), &admission, false)
+            .expect("put with pow");
+
+        // A proof for another record does not verify.
+        let other = create_immutable_record(vec![8; 500]).expect("record");
+        assert!(matches!(
+            rejection(store.put_from([2; 32], other, &admission, false)),
+            Some(QuotaRejection::InvalidAdmission)
+        ));
+    }
+
+    #[test]
+    fn test_admission_tokens_are_single_use() {
+        let mut store = RecordStore::with_quota(config());
+        store.grant_admission_token([5; 32]);
+        let token = Admission::Token([5; 32]);
+        let first = create_immutable_record(vec![1; 500]).expect("record");
+        store
+            .put_from([1; 32], first, &token, false)
+            .expect("put with token");
+        let second = create_immutable_record(vec![2; 500]).expect("record");
+        assert!(matches!(
+            rejection(store.put_from([2; 32], second, &token, false)),
+            Some(QuotaRejection::InvalidAdmission)
+        ));
+    }
+
+    #[test]
+    fn test_lru_eviction_spares_replicated() {
+        let mut store = RecordStore::with_quota(config());
+        let pinned = create_immutable_record(vec![1; 400]).expect("record");
+        let old = create_immutable_record(vec![2; 300]).expect("record");
+        let recent = create_immutable_record(vec![3; 300]).expect("record");
+        let (pinned_key, old_key, recent_key) = (
+            pinned.storage_key(),
+            old.storage_key(),
+            recent.storage_key(),
+        );
+        store
+            .put_from([1; 32], pinned, &Admission::None, true)
+            .expect("put");
+        store
+            .put_from([2; 32], old, &Admission::None, false)
+            .expect("put");
+        store
+            .put_from([3; 32], recent, &Admission::None, false)
+            .expect("put");
+        assert!(store.get(&recent_key).is_some());
+        assert_eq!(store.used_bytes(), 1000);
+
+        let incoming = create_immutable_record(vec![4; 300]).expect("record");
+        store
+            .put_from([4; 32], incoming, &Admission::None, false)
+            .expect("put");
+        assert!(store.get(&old_key).is_none());
+        assert!(store.get(&recent_key).is_some());
+        assert!(store.get(&pinned_key).is_some());
+        assert_eq!(store.used_bytes(), 1000);
+    }
+
+    #[test]
+    fn test_store_full_when_nothing_evictable() {
+        let mut store = RecordStore::with_quota(config());
+        for (peer, byte) in [([1; 32], 1u8), ([2; 32], 2)] {
+            let record = create_immutable_record(vec![byte; 400]).expect("record");
+            store
+                .put_from(peer, record, &Admission::None, true)
+                .expect("put");
+        }
+        let record = create_immutable_record(vec![3; 300]).expect("record");
+        assert!(matches!(
+            rejection(store.put_from([3; 32], record, &Admission::None, false)),
+            Some(QuotaRejection::StoreFull)
+        ));
+        assert_eq!(store.used_bytes(), 800);
+    }
+}
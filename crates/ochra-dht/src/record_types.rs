@@ -0,0 +1,713 @@
+//! Record type registry and per-type validation (Section 28.1).
+//!
+//! The store treats values as opaque bytes, so a record's type is taken
+//! from the salt of its mutable record: a [`RecordTypeRegistry`] maps salt
+//! prefixes to a [`RecordType`] and its validators, and the longest matching
+//! prefix wins. Records whose salt matches no prefix, and immutable records,
+//! stay opaque. A [`RecordStore`](crate::bep44::RecordStore) runs the
+//! validators on local puts and on every put from a peer, replication
+//! included, after the BEP 44 signature check, and shows them the value
+//! the put would replace.
+//!
+//! A value too large for one record is published as a shard manifest
+//! (Section 28.5), which only types marked [`RecordType::shardable`] may
+//! hold. Such a record only has to hold a well-formed manifest; readers
+//! get the value through [`RecordTypeRegistry::resolve_value`], which
+//! reassembles it and applies the type's validators.
+
+use std::marker::PhantomData;
+
+use ochra_crypto::ed25519::{DelegationCert, DelegationScope};
+use ochra_crypto::timelock::Sealed;
+use ochra_types::network::{EpochBeacon, RelayDescriptor};
+use ochra_types::space::GroupPolicy;
+use ochra_types::whisper::HandleDescriptor;
+use serde::de::DeserializeOwned;
+
+use crate::beacon::BEACON_SALT;
+use crate::bep44::DhtRecord;
+use crate::group_policy::{policy_salt, GROUP_POLICY_SALT};
+use crate::revocation::{RevocationCertificate, REVOCATION_SALT, REVOCATION_SEQ};
+use crate::sharding::{self, RecordShard, ShardManifest};
+use crate::timelock::{salt_epoch, TIMELOCK_SALT};
+use crate::{DhtError, Result};
+
+/// Salt prefix of relay descriptor records.
+pub const RELAY_DESCRIPTOR_SALT: &[u8] = b"relay";
+
+/// Salt prefix of handle descriptor records.
+pub const HANDLE_DESCRIPTOR_SALT: &[u8] = b"handle";
+
+/// Salt prefix of invite service descriptor records.
+pub const INVITE_DESCRIPTOR_SALT: &[u8] = b"invite";
+
+/// Network epoch containing a relay epoch.
+fn network_epoch(relay_epoch: u32) -> u32 {
+    let secs = u64::from(relay_epoch) * ochra_types::RELAY_EPOCH_DURATION_SECS;
+    u32::try_from(secs / ochra_types::EPOCH_DURATION_SECS).unwrap_or(u32::MAX)
+}
+
+/// A relay descriptor is published either by its PIK or by an operational
+/// subkey carrying a relay-scoped delegation from that PIK.
+fn relay_descriptor_publisher(
+    d: &RelayDescriptor,
+    r: &TypedRecord<'_>,
+) -> std::result::Result<(), String> {
+    let Some(encoded) = &d.delegation else {
+        if d.pik_hash != ochra_crypto::blake3::hash(r.public_key) {
+            return Err("descriptor not published by its PIK".to_string());
+        }
+        return Ok(());
+    };
+    let cert = DelegationCert::from_bytes(encoded).map_err(|e| e.to_string())?;
+    if cert.parent_hash() != d.pik_hash {
+        return Err("delegation not issued by the descriptor's PIK".to_string());
+    }
+    if &cert.subkey_pk != r.public_key {
+        return Err("descriptor not published by the delegated subkey".to_string());
+    }
+    cert.authorize(
+        DelegationScope::RELAY.union(DelegationScope::DHT_RECORDS),
+        network_epoch(d.relay_epoch),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Decoding and signer rules of handle descriptor records.
+///
+/// The standard registry applies only these; `ochra-whisper` registers a
+/// type that adds its registration proof check on top.
+pub fn handle_descriptor_schema() -> CborSchema<HandleDescriptor> {
+    CborSchema::<HandleDescriptor>::new()
+        .with_rule(|d, r| {
+            if &d.handle_signing_pk != r.public_key {
+                return Err("descriptor not published by its handle key".to_string());
+            }
+            Ok(())
+        })
+        .with_rule(|d, _| {
+            if d.handle.is_empty() || d.handle != d.handle.to_lowercase() {
+                return Err(format!("handle {:?} is not lowercase", d.handle));
+            }
+            Ok(())
+        })
+}
+
+/// The fields of a mutable record that validators see.
+#[derive(Clone, Copy, Debug)]
+pub struct TypedRecord<'a> {
+    /// Publisher's Ed25519 public key.
+    pub public_key: &'a [u8; 32],
+    pub salt: &'a [u8],
+    pub seq: u64,
+    pub value: &'a [u8],
+    /// Value already stored under the record's key, when validating a put
+    /// that would replace it. `None` for a new key, a stored shard
+    /// manifest, or a record checked outside a store.
+    pub previous: Option<&'a [u8]>,
+}
+
+/// A check applied to every record of a type.
+pub trait RecordValidator: Send + Sync {
+    /// Check a record, returning why it is malformed.
+    fn check(&self, record: &TypedRecord<'_>) -> std::result::Result<(), String>;
+}
+
+impl<F> RecordValidator for F
+where
+    F: Fn(&TypedRecord<'_>) -> std::result::Result<(), String> + Send + Sync,
+{
+    fn check(&self, record: &TypedRecord<'_>) -> std::result::Result<(), String> {
+        self(record)
+    }
+}
+
+/// Rejects values longer than a limit.
+#[derive(Clone, Copy, Debug)]
+pub struct MaxSize(pub usize);
+
+impl RecordValidator for MaxSize {
+    fn check(&self, record: &TypedRecord<'_>) -> std::result::Result<(), String> {
+        if record.value.len() > self.0 {
+            return Err(format!(
+                "value of {} bytes exceeds {}",
+                record.value.len(),
+                self.0
+            ));
+        }
+        Ok(())
+    }
+}
+
+type Rule<T> = Box<dyn Fn(&T, &TypedRecord<'_>) -> std::result::Result<(), String> + Send + Sync>;
+
+/// Requires the value to decode as CBOR `T`, then applies rules to the
+/// decoded value, such as binding it to the record's signer.
+pub struct CborSchema<T> {
+    rules: Vec<Rule<T>>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> CborSchema<T> {
+    /// A schema with no further rules.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            _value: PhantomData,
+        }
+    }
+
+    /// Add a rule on the decoded value.
+    pub fn with_rule(
+        mut self,
+        rule: impl Fn(&T, &TypedRecord<'_>) -> std::result::Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+}
+
+impl<T: DeserializeOwned> Default for CborSchema<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: DeserializeOwned> RecordValidator for CborSchema<T> {
+    fn check(&self, record: &TypedRecord<'_>) -> std::result::Result<(), String> {
+        let value: T = ciborium::from_reader(record.value)
+            .map_err(|e| format!("value does not decode: {e}"))?;
+        self.rules.iter().try_for_each(|rule| rule(&value, record))
+    }
+}
+
+/// A named record type and its validators.
+pub struct RecordType {
+    name: &'static str,
+    validators: Vec<Box<dyn RecordValidator>>,
+    shardable: bool,
+}
+
+impl RecordType {
+    /// A record type with no validators, whose values are never sharded.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            validators: Vec::new(),
+            shardable: false,
+        }
+    }
+
+    /// Allow records of this type to hold a shard manifest.
+    pub fn shardable(mut self) -> Self {
+        self.shardable = true;
+        self
+    }
+
+    /// Whether records of this type may hold a shard manifest.
+    pub fn allows_sharding(&self) -> bool {
+        self.shardable
+    }
+
+    /// Add a validator.
+    pub fn with(mut self, validator: impl RecordValidator + 'static) -> Self {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    /// The type's name, as reported in rejections.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn reject(&self, reason: impl Into<String>) -> DhtError {
+        DhtError::InvalidRecord {
+            record_type: self.name,
+            reason: reason.into(),
+        }
+    }
+
+    fn check(&self, record: &TypedRecord<'_>) -> Result<()> {
+        self.validators
+            .iter()
+            .try_for_each(|v| v.check(record))
+            .map_err(|reason| self.reject(reason))
+    }
+}
+
+/// Record types keyed by salt prefix.
+#[derive(Default)]
+pub struct RecordTypeRegistry {
+    types: Vec<(Vec<u8>, RecordType)>,
+}
+
+impl RecordTypeRegistry {
+    /// An empty registry, under which every record is opaque.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with the network's known record types: relay, handle
+    /// and invite descriptors, PIK revocations, epoch beacons, group
+    /// policies and time-locked values.
+    pub fn standard() -> Self {
+        let mut registry = Self::new();
+        registry.register(
+            RELAY_DESCRIPTOR_SALT,
+            RecordType::new("relay_descriptor").with(
+                CborSchema::<RelayDescriptor>::new()
+                    .with_rule(relay_descriptor_publisher)
+                    .with_rule(|d, r| {
+                        if u64::from(d.relay_epoch) != r.seq {
+                            return Err(format!(
+                                "seq {} is not relay epoch {}",
+                                r.seq, d.relay_epoch
+                            ));
+                        }
+                        Ok(())
+                    }),
+            ),
+        );
+        registry.register(
+            HANDLE_DESCRIPTOR_SALT,
+            RecordType::new("handle_descriptor")
+                .shardable()
+                .with(handle_descriptor_schema()),
+        );
+        registry.register(
+            INVITE_DESCRIPTOR_SALT,
+            RecordType::new("invite_descriptor")
+                .shardable()
+                .with(CborSchema::<ciborium::Value>::new()),
+        );
+        registry.register(
+            REVOCATION_SALT,
+            RecordType::new("pik_revocation").with(|r: &TypedRecord<'_>| {
+                if r.salt != REVOCATION_SALT || r.seq != REVOCATION_SEQ {
+                    return Err("not at the revocation salt and sequence".to_string());
+                }
+                let cert = RevocationCertificate::from_body(r.value, [0u8; 64])
+                    .map_err(|e| e.to_string())?;
+                if &cert.pik_public_key != r.public_key {
+                    return Err("certificate not published by the revoked PIK".to_string());
+                }
+                Ok(())
+            }),
+        );
+        registry.register(
+            BEACON_SALT,
+            RecordType::new("epoch_beacon").with(CborSchema::<EpochBeacon>::new().with_rule(
+                |b, r| {
+                    if r.salt != BEACON_SALT || u64::from(b.epoch) != r.seq {
+                        return Err(format!("seq {} is not beacon epoch {}", r.seq, b.epoch));
+                    }
+                    Ok(())
+                },
+            )),
+        );
+        registry.register(
+            GROUP_POLICY_SALT,
+            RecordType::new("group_policy").shardable().with(
+                CborSchema::<GroupPolicy>::new().with_rule(|p, r| {
+                    if r.salt != policy_salt(&p.group_id).as_slice() || p.version != r.seq {
+                        return Err(format!(
+                            "seq {} or salt does not match policy version {}",
+                            r.seq, p.version
+                        ));
+                    }
+                    Ok(())
+                }),
+            ),
+        );
+        registry.register(
+            TIMELOCK_SALT,
+            RecordType::new("timelock").with(|r: &TypedRecord<'_>| {
+                let epoch = salt_epoch(r.salt).ok_or("salt names no target epoch")?;
+                let sealed = Sealed::from_bytes(r.value).map_err(|e| e.to_string())?;
+                if sealed.target_epoch != epoch {
+                    return Err(format!(
+                        "sealed to epoch {} under a salt for epoch {epoch}",
+                        sealed.target_epoch
+                    ));
+                }
+                Ok(())
+            }),
+        );
+        registry
+    }
+
+    /// Register a record type for salts starting with `prefix`, replacing
+    /// any type already registered for it.
+    pub fn register(&mut self, prefix: &[u8], record_type: RecordType) {
+        self.types.retain(|(p, _)| p != prefix);
+        self.types.push((prefix.to_vec(), record_type));
+    }
+
+    /// The record type for a salt, by longest matching prefix.
+    pub fn classify(&self, salt: &[u8]) -> Option<&RecordType> {
+        self.types
+            .iter()
+            .filter(|(prefix, _)| salt.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, record_type)| record_type)
+    }
+
+    /// Validate a record against its type. A shard manifest stands in for
+    /// the value of a type that allows sharding.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InvalidRecord`] if a validator of the record's type
+    ///   rejects it, or it holds a shard manifest its type does not allow
+    pub fn validate(&self, record: &DhtRecord) -> Result<()> {
+        self.validate_update(record, None)
+    }
+
+    /// [`validate`](Self::validate) a put that would replace `stored`,
+    /// which validators see as [`TypedRecord::previous`].
+    ///
+    /// # Errors
+    ///
+    /// - as [`validate`](Self::validate)
+    pub fn validate_update(&self, record: &DhtRecord, stored: Option<&DhtRecord>) -> Result<()> {
+        let DhtRecord::Mutable {
+            public_key,
+            salt,
+            seq,
+            value,
+            ..
+        } = record
+        else {
+            return Ok(());
+        };
+        if ShardManifest::from_bytes(value).is_some() {
+            return match self.classify(salt) {
+                Some(record_type) if !record_type.allows_sharding() => {
+                    Err(record_type.reject("type does not allow sharding"))
+                }
+                _ => Ok(()),
+            };
+        }
+        let previous = stored
+            .map(DhtRecord::value)
+            .filter(|v| ShardManifest::from_bytes(v).is_none());
+        self.validate_value(&TypedRecord {
+            public_key,
+            salt,
+            seq: *seq,
+            value,
+            previous,
+        })
+    }
+
+    /// Validate a value against the type of its salt, for values
+    /// reassembled from shards.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InvalidRecord`] if a validator of the type rejects it
+    pub fn validate_value(&self, record: &TypedRecord<'_>) -> Result<()> {
+        match self.classify(record.salt) {
+            Some(record_type) => record_type.check(record),
+            None => Ok(()),
+        }
+    }
+
+    /// The value a reader should use: the record's own value, or the value
+    /// reassembled from `shards` if it holds a shard manifest, after
+    /// checking either against the record's type.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InvalidRecord`] if the record or the reassembled value
+    ///   is invalid for its type
+    /// - [`DhtError::InsufficientShards`] if fewer than `k` shards match
+    pub fn resolve_value(&self, record: &DhtRecord, shards: &[RecordShard]) -> Result<Vec<u8>> {
+        self.validate(record)?;
+        let DhtRecord::Mutable {
+            public_key,
+            salt,
+            seq,
+            value,
+            ..
+        } = record
+        else {
+            return Ok(record.value().to_vec());
+        };
+        let Some(manifest) = ShardManifest::from_bytes(value) else {
+            return Ok(value.clone());
+        };
+        let reassembled = sharding::reconstruct_record(&manifest, shards)?;
+        self.validate_value(&TypedRecord {
+            public_key,
+            salt,
+            seq: *seq,
+            value: &reassembled,
+            previous: None,
+        })?;
+        Ok(reassembled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ochra_crypto::ed25519::KeyPair;
+    use ochra_types::whisper::HandleStatus;
+
+    use super::*;
+    use crate::bep44::create_mutable_record;
+    use crate::revocation::RevocationReason;
+
+    fn cbor<T: serde::Serialize>(value: &T) -> Vec<u8> {
+        let mut out = Vec::new();
+        ciborium::into_writer(value, &mut out).expect("encode");
+        out
+    }
+
+    fn handle(kp: &KeyPair, name: &str) -> Vec<u8> {
+        cbor(&HandleDescriptor {
+            handle: name.to_string(),
+            handle_signing_pk: kp.verifying_key.to_bytes(),
+            intro_points: Vec::new(),
+            auth_key: [0; 32],
+            pq_auth_key: Vec::new(),
+            registered_at: 1,
+            refresh_at: 2,
+            pow_proof: Vec::new(),
+            status: HandleStatus::Active,
+            mailboxes: Vec::new(),
+            privacy: Default::default(),
+            strictness: Default::default(),
+            sig: [0; 64],
+        })
+    }
+
+    fn rejected_type(result: Result<()>) -> Option<&'static str> {
+        match result {
+            Err(DhtError::InvalidRecord { record_type, .. }) => Some(record_type),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_longest_prefix_wins_and_unknown_is_opaque() {
+        let mut registry = RecordTypeRegistry::new();
+        registry.register(b"a", RecordType::new("short"));
+        registry.register(b"ab", RecordType::new("long"));
+        assert_eq!(
+            registry.classify(b"abc").map(RecordType::name),
+            Some("long")
+        );
+        assert_eq!(
+            registry.classify(b"ax").map(RecordType::name),
+            Some("short")
+        );
+        assert!(registry.classify(b"zzz").is_none());
+
+        let kp = KeyPair::generate();
+        let opaque =
+            create_mutable_record(&kp.signing_key, b"zzz", 1, vec![0xff; 8]).expect("record");
+        assert!(RecordTypeRegistry::standard().validate(&opaque).is_ok());
+    }
+
+    #[test]
+    fn test_handle_descriptor_schema_and_signer() {
+        let registry = RecordTypeRegistry::standard();
+        let kp = KeyPair::generate();
+        let good = create_mutable_record(&kp.signing_key, b"handle", 1, handle(&kp, "alice"))
+            .expect("record");
+        assert!(registry.validate(&good).is_ok());
+
+        let garbage =
+            create_mutable_record(&kp.signing_key, b"handle", 2, vec![0xff; 16]).expect("record");
+        assert_eq!(
+            rejected_type(registry.validate(&garbage)),
+            Some("handle_descriptor")
+        );
+
+        let other = KeyPair::generate();
+        let foreign = create_mutable_record(&other.signing_key, b"handle", 1, handle(&kp, "alice"))
+            .expect("record");
+        assert!(registry.validate(&foreign).is_err());
+
+        let upper = create_mutable_record(&kp.signing_key, b"handle", 3, handle(&kp, "Alice"))
+            .expect("record");
+        assert!(registry.validate(&upper).is_err());
+    }
+
+    #[test]
+    fn test_shard_manifest_stands_in_for_value() {
+        let registry = RecordTypeRegistry::standard();
+        let kp = KeyPair::generate();
+        let policy = sharding::ShardingPolicy {
+            threshold: 0,
+            data_shards: 2,
+            total_shards: 3,
+        };
+
+        let (manifest, shards) =
+            sharding::encode_record(&policy, &handle(&kp, "alice")).expect("encode");
+        let record = create_mutable_record(&kp.signing_key, b"handle", 1, manifest.to_bytes())
+            .expect("record");
+        assert!(registry.validate(&record).is_ok());
+        assert_eq!(
+            registry
+                .resolve_value(&record, &shards[1..])
+                .expect("resolve"),
+            handle(&kp, "alice")
+        );
+
+        // The manifest is accepted, but the reassembled value is checked
+        let (manifest, shards) =
+            sharding::encode_record(&policy, &handle(&kp, "Alice")).expect("encode");
+        let record = create_mutable_record(&kp.signing_key, b"handle", 2, manifest.to_bytes())
+            .expect("record");
+        assert!(registry.validate(&record).is_ok());
+        assert_eq!(
+            rejected_type(registry.resolve_value(&record, &shards).map(drop)),
+            Some("handle_descriptor")
+        );
+
+        // Plain values pass through after validation
+        let plain = create_mutable_record(&kp.signing_key, b"handle", 3, handle(&kp, "bob"))
+            .expect("record");
+        assert_eq!(
+            registry.resolve_value(&plain, &[]).expect("resolve"),
+            handle(&kp, "bob")
+        );
+    }
+
+    #[test]
+    fn test_manifest_refused_for_unshardable_type() {
+        let registry = RecordTypeRegistry::standard();
+        let kp = KeyPair::generate();
+        let manifest = ShardManifest {
+            data_shards: 2,
+            total_size: 1_500,
+            shard_hashes: vec![[1; 32], [2; 32], [3; 32]],
+        };
+        let record = create_mutable_record(&kp.signing_key, b"relay", 1, manifest.to_bytes())
+            .expect("record");
+        assert_eq!(
+            rejected_type(registry.validate(&record)),
+            Some("relay_descriptor")
+        );
+
+        // Opaque records may hold a manifest
+        let opaque =
+            create_mutable_record(&kp.signing_key, b"zzz", 1, manifest.to_bytes()).expect("record");
+        assert!(registry.validate(&opaque).is_ok());
+    }
+
+    fn relay(pik: &KeyPair, relay_epoch: u32, delegation: Option<Vec<u8>>) -> Vec<u8> {
+        cbor(&RelayDescriptor {
+            node_id: [1; 32],
+            pik_hash: ochra_crypto::blake3::hash(&pik.verifying_key.to_bytes()),
+            x25519_pk: [2; 32],
+            mlkem768_ek: Vec::new(),
+            relay_epoch,
+            posrv_score: 1.0,
+            ip_addr: "127.0.0.1:4433".to_string(),
+            as_number: 1,
+            country_code: *b"US",
+            bandwidth_cap_mbps: 100,
+            uptime_epochs: 1,
+            exit_policy: Default::default(),
+            delegation,
+            sig: [0; 64],
+        })
+    }
+
+    #[test]
+    fn test_relay_descriptor_accepts_delegated_publisher() {
+        let registry = RecordTypeRegistry::standard();
+        let pik = KeyPair::generate();
+        let sub = KeyPair::generate();
+        // Relay epoch 48 is in network epoch 2.
+        let scope = DelegationScope::RELAY.union(DelegationScope::DHT_RECORDS);
+        let cert = DelegationCert::issue(&pik.signing_key, &sub.verifying_key, scope, 1, 2)
+            .expect("cert")
+            .to_bytes();
+
+        let direct = create_mutable_record(&pik.signing_key, b"relay", 48, relay(&pik, 48, None))
+            .expect("record");
+        assert!(registry.validate(&direct).is_ok());
+        let delegated = create_mutable_record(
+            &sub.signing_key,
+            b"relay",
+            48,
+            relay(&pik, 48, Some(cert.clone())),
+        )
+        .expect("record");
+        assert!(registry.validate(&delegated).is_ok());
+
+        // Subkey without a certificate, outside its validity, or with a
+        // certificate that does not cover relays.
+        let bare = create_mutable_record(&sub.signing_key, b"relay", 48, relay(&pik, 48, None))
+            .expect("record");
+        assert_eq!(
+            rejected_type(registry.validate(&bare)),
+            Some("relay_descriptor")
+        );
+        let expired =
+            create_mutable_record(&sub.signing_key, b"relay", 72, relay(&pik, 72, Some(cert)))
+                .expect("record");
+        assert!(registry.validate(&expired).is_err());
+        let receipts_only = DelegationCert::issue(
+            &pik.signing_key,
+            &sub.verifying_key,
+            DelegationScope::RECEIPTS,
+            1,
+            2,
+        )
+        .expect("cert");
+        let wrong_scope = create_mutable_record(
+            &sub.signing_key,
+            b"relay",
+            48,
+            relay(&pik, 48, Some(receipts_only.to_bytes())),
+        )
+        .expect("record");
+        assert!(registry.validate(&wrong_scope).is_err());
+    }
+
+    #[test]
+    fn test_revocation_must_match_publisher() {
+        let registry = RecordTypeRegistry::standard();
+        let kp = KeyPair::generate();
+        let cert =
+            RevocationCertificate::sign(&kp.signing_key, 10, RevocationReason::KeyCompromise);
+        assert!(registry.validate(&cert.to_record()).is_ok());
+
+        let wrong_seq = create_mutable_record(&kp.signing_key, REVOCATION_SALT, 1, cert.body())
+            .expect("record");
+        assert_eq!(
+            rejected_type(registry.validate(&wrong_seq)),
+            Some("pik_revocation")
+        );
+    }
+
+    #[test]
+    fn test_custom_validators_plug_in() {
+        let mut registry = RecordTypeRegistry::new();
+        registry.register(
+            b"note",
+            RecordType::new("note")
+                .with(MaxSize(4))
+                .with(|r: &TypedRecord<'_>| {
+                    if r.value.is_ascii() {
+                        Ok(())
+                    } else {
+                        Err("not ascii".to_string())
+                    }
+                }),
+        );
+        let kp = KeyPair::generate();
+        let record = |seq, value: &[u8]| {
+            create_mutable_record(&kp.signing_key, b"note", seq, value.to_vec()).expect("record")
+        };
+        assert!(registry.validate(&record(1, b"ok")).is_ok());
+        assert!(registry.validate(&record(2, b"too long")).is_err());
+        assert!(registry.validate(&record(3, &[0xff])).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! PIK revocation certificates (Section 6.6).
+//!
+//! A revocation certificate is signed by the PIK it revokes and published
+//! as a mutable record under the PIK's public key with the fixed salt
+//! [`REVOCATION_SALT`], so anyone holding the public key can derive its
+//! address with [`revocation_address`]. The record uses the maximum
+//! sequence number, so nothing can supersede it.
+//!
+//! The certificate signature is the record signature: it covers the BEP 44
+//! signed data for the certificate body at that salt and sequence number.
+//! An exported certificate can therefore be published by anyone, even
+//! after the PIK's private key is lost.
+//!
+//! Peers that learn of a revocation keep it in a [`RevocationSet`] and
+//! treat the PIK as invalid from then on.
+
+use std::collections::HashMap;
+
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+
+use crate::bep44::{build_signed_data, DhtRecord};
+use crate::{DhtError, Result};
+
+/// Salt of the mutable record holding a revocation certificate.
+pub const REVOCATION_SALT: &[u8] = b"pik-revocation";
+
+/// Sequence number of revocation records. Revocation is terminal.
+pub const REVOCATION_SEQ: u64 = u64::MAX;
+
+/// Certificate body length: public key, timestamp, reason.
+const BODY_LEN: usize = 32 + 8 + 1;
+
+/// Encoded certificate length: body and signature.
+pub const CERTIFICATE_LEN: usize = BODY_LEN + 64;
+
+/// Why a PIK was revoked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevocationReason {
+    /// No reason given.
+    Unspecified = 0,
+    /// The private key is known or suspected to be compromised.
+    KeyCompromise = 1,
+    /// Replaced by a recovered PIK.
+    Superseded = 2,
+}
+
+impl RevocationReason {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Unspecified),
+            1 => Some(Self::KeyCompromise),
+            2 => Some(Self::Superseded),
+            _ => None,
+        }
+    }
+}
+
+/// Address of the revocation record for a PIK.
+pub fn revocation_address(pik_public_key: &[u8; 32]) -> [u8; 32] {
+    let mut input = Vec::with_capacity(32 + REVOCATION_SALT.len());
+    input.extend_from_slice(pik_public_key);
+    input.extend_from_slice(REVOCATION_SALT);
+    ochra_crypto::blake3::hash(&input)
+}
+
+/// A self-signed statement that a PIK is no longer valid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RevocationCertificate {
+    /// The revoked PIK public key.
+    pub pik_public_key: [u8; 32],
+    /// Unix timestamp of the revocation.
+    pub revoked_at: u64,
+    /// Why the PIK was revoked.
+    pub reason: RevocationReason,
+    /// Ed25519 signature by the revoked PIK over
+    /// [`signing_message`](Self::signing_message).
+    pub signature: [u8; 64],
+}
+
+impl RevocationCertificate {
+    /// Create and sign a certificate revoking `signing_key`'s PIK.
+    pub fn sign(signing_key: &SigningKey, revoked_at: u64, reason: RevocationReason) -> Self {
+        let mut cert = Self {
+            pik_public_key: signing_key.verifying_key().to_bytes(),
+            revoked_at,
+            reason,
+            signature: [0u8; 64],
+        };
+        cert.signature = signing_key.sign(&cert.signing_message()).to_bytes();
+        cert
+    }
+
+    /// `pik_public_key || revoked_at_le || reason`: the record value.
+    pub(crate) fn body(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(BODY_LEN);
+        body.extend_from_slice(&self.pik_public_key);
+        body.extend_from_slice(&self.revoked_at.to_le_bytes());
+        body.push(self.reason as u8);
+        body
+    }
+
+    /// Bytes covered by the signature: the BEP 44 signed data of the
+    /// revocation record.
+    pub fn signing_message(&self) -> Vec<u8> {
+        build_signed_data(REVOCATION_SALT, REVOCATION_SEQ, &self.body())
+    }
+
+    /// BLAKE3 hash of the revoked PIK public key.
+    pub fn pik_hash(&self) -> [u8; 32] {
+        ochra_crypto::blake3::hash(&self.pik_public_key)
+    }
+
+    /// Check the signature.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InvalidSignature`] if the PIK did not sign it
+    pub fn verify(&self) -> Result<()> {
+        VerifyingKey::from_bytes(&self.pik_public_key)?
+            .verify(
+                &self.signing_message(),
+                &Signature::from_bytes(&self.signature),
+            )
+            .map_err(|_| DhtError::InvalidSignature)
+    }
+
+    /// Encode as the certificate body followed by the signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.body();
+        out.extend_from_slice(&self.signature);
+        out
+    }
+
+    /// Decode a certificate. The signature is not checked.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::Serialization`] on a bad length or unknown reason
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() != CERTIFICATE_LEN {
+            return Err(DhtError::Serialization(format!(
+                "revocation certificate length {}",
+                data.len()
+            )));
+        }
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&data[BODY_LEN..]);
+        Self::from_body(&data[..BODY_LEN], signature)
+    }
+
+    pub(crate) fn from_body(body: &[u8], signature: [u8; 64]) -> Result<Self> {
+        if body.len() != BODY_LEN {
+            return Err(DhtError::Serialization(format!(
+                "revocation body length {}",
+                body.len()
+            )));
+        }
+        let mut pik_public_key = [0u8; 32];
+        pik_public_key.copy_from_slice(&body[..32]);
+        let mut revoked_at = [0u8; 8];
+        revoked_at.copy_from_slice(&body[32..40]);
+        let reason = RevocationReason::from_byte(body[40]).ok_or_else(|| {
+            DhtError::Serialization(format!("unknown revocation reason {}", body[40]))
+        })?;
+        Ok(Self {
+            pik_public_key,
+            revoked_at: u64::from_le_bytes(revoked_at),
+            reason,
+            signature,
+        })
+    }
+
+    /// The DHT record publishing this certificate at
+    /// [`revocation_address`].
+    pub fn to_record(&self) -> DhtRecord {
+        DhtRecord::Mutable {
+            public_key: self.pik_public_key,
+            salt: REVOCATION_SALT.to_vec(),
+            seq: REVOCATION_SEQ,
+            value: self.body(),
+            signature: self.signature,
+        }
+    }
+
+    /// Extract and verify a certificate from a fetched revocation record.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InvalidSignature`] if the record is not a revocation
+    ///   record signed by the PIK
+    /// - [`DhtError::Serialization`] if the value is not a certificate body
+    pub fn from_record(record: &DhtRecord) -> Result<Self> {
+        let DhtRecord::Mutable {
+            public_key,
+            salt,
+            seq,
+            value,
+            signature,
+        } = record
+        else {
+            return Err(DhtError::InvalidSignature);
+        };
+        if salt.as_slice() != REVOCATION_SALT || *seq != REVOCATION_SEQ {
+            return Err(DhtError::InvalidSignature);
+        }
+        let cert = Self::from_body(value, *signature)?;
+        if &cert.pik_public_key != public_key {
+            return Err(DhtError::InvalidSignature);
+        }
+        cert.verify()?;
+        Ok(cert)
+    }
+}
+
+/// Verified revocations, keyed by PIK hash.
+#[derive(Clone, Debug, Default)]
+pub struct RevocationSet {
+    revoked: HashMap<[u8; 32], RevocationCertificate>,
+}
+
+impl RevocationSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verify and add a certificate. Returns whether the PIK was newly
+    /// revoked.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::InvalidSignature`] if the certificate does not verify
+    pub fn insert(&mut self, cert: RevocationCertificate) -> Result<bool> {
+        cert.verify()?;
+        Ok(self.revoked.insert(cert.pik_hash(), cert).is_none())
+    }
+
+    /// Whether the PIK with this hash is revoked.
+    pub fn is_revoked(&self, pik_hash: &[u8; 32]) -> bool {
+        self.revoked.contains_key(pik_hash)
+    }
+
+    /// The certificate revoking a PIK, if any.
+    pub fn get(&self, pik_hash: &[u8; 32]) -> Option<&RevocationCertificate> {
+        self.revoked.get(pik_hash)
+    }
+
+    /// Number of revoked PIKs.
+    pub fn len(&self) -> usize {
+        self.revoked.len()
+    }
+
+    /// Whether no PIKs are revoked.
+    pub fn is_empty(&self) -> bool {
+        self.revoked.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::KeyPair;
+
+    #[test]
+    fn test_certificate_round_trip() {
+        let key = KeyPair::generate();
+        let cert =
+            RevocationCertificate::sign(&key.signing_key, 1_000, RevocationReason::KeyCompromise);
+        cert.verify().expect("verify");
+
+        let decoded = RevocationCertificate::from_bytes(&cert.to_bytes()).expect("decode");
+        assert_eq!(decoded, cert);
+
+        let mut forged = cert.clone();
+        forged.revoked_at += 1;
+        assert!(matches!(forged.verify(), Err(DhtError::InvalidSignature)));
+    }
+
+    #[test]
+    fn test_record_at_well_known_address() {
+        let key = KeyPair::generate();
+        let pk = key.verifying_key.to_bytes();
+        let cert =
+            RevocationCertificate::sign(&key.signing_key, 1_000, RevocationReason::Superseded);
+
+        // Publishable without the private key
+        let record = RevocationCertificate::from_bytes(&cert.to_bytes())
+            .expect("decode")
+            .to_record();
+        record.validate().expect("valid BEP 44 record");
+        assert_eq!(record.storage_key(), revocation_address(&pk));
+        assert_eq!(
+            RevocationCertificate::from_record(&record).expect("parse"),
+            cert
+        );
+
+        // A record under another salt is not a revocation
+        let other = crate::bep44::create_mutable_record(
+            &key.signing_key,
+            b"profile",
+            REVOCATION_SEQ,
+            record.value().to_vec(),
+        )
+        .expect("record");
+        assert!(RevocationCertificate::from_record(&other).is_err());
+    }
+
+    #[test]
+    fn test_revocation_set() {
+        let key = KeyPair::generate();
+        let cert =
+            RevocationCertificate::sign(&key.signing_key, 1_000, RevocationReason::Unspecified);
+        let pik_hash = cert.pik_hash();
+
+        let mut set = RevocationSet::new();
+        assert!(!set.is_revoked(&pik_hash));
+        assert!(set.insert(cert.clone()).expect("insert"));
+        assert!(!set.insert(cert).expect("insert"));
+        assert!(set.is_revoked(&pik_hash));
+
+        let mut forged = RevocationCertificate::sign(
+            &KeyPair::generate().signing_key,
+            1,
+            RevocationReason::Unspecified,
+        );
+        forged.pik_public_key = [9u8; 32];
+        assert!(set.insert(forged).is_err());
+        assert_eq!(set.len(), 1);
+    }
+}
@@ -0,0 +1,505 @@
+//! Bootstrap seed sources (Section 5.2).
+//!
+//! A node that has no usable routing table needs seed nodes from somewhere.
+//! Sources are tried in the configured order and the first that yields any
+//! seeds wins; a source that fails or comes back empty falls through to the
+//! next:
+//!
+//! 1. [`SeedSource::Cached`]: last-known-good peers from the local database.
+//! 2. [`SeedSource::Hardcoded`]: seed nodes compiled in or configured.
+//! 3. [`SeedSource::SignedList`]: a JSON [`SignedBootstrapList`] fetched over
+//!    HTTPS and signed by the project key.
+//! 4. [`SeedSource::Dns`]: TXT records under a seed domain.
+//!
+//! DNS answers are unauthenticated, so each TXT record pins the seed's PIK
+//! public key and the seed is authenticated on connect like any other.
+//! Signed lists carry an expiry and a version; a list older than the last
+//! one accepted is refused, so a stale mirror cannot roll peers back.
+//!
+//! [`resolve_seed_sets`] instead collects seeds from every source, one set
+//! per operator ([`SeedSource::operator`]), for bootstraps that require
+//! independent sources to agree (see [`crate::agreement`]).
+//!
+//! Network I/O is left to the caller through [`SeedFetcher`].
+
+use std::future::Future;
+use std::net::SocketAddr;
+
+use ochra_crypto::ed25519::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::bootstrap::SeedNode;
+use crate::public_suffix::registrable_domain;
+use crate::{DhtError, Result};
+
+/// Prefix of a seed TXT record.
+pub const TXT_RECORD_PREFIX: &str = "ochra-seed=v1";
+
+/// Allowed clock skew for a signed list's issue time (1 hour).
+pub const MAX_ISSUE_SKEW_SECS: u64 = 3600;
+
+/// Maximum seeds accepted from a single source.
+pub const MAX_SEEDS_PER_SOURCE: usize = 64;
+
+/// A place to find bootstrap seeds.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SeedSource {
+    /// Peers cached from previous sessions.
+    Cached,
+    /// Fixed seed nodes.
+    Hardcoded,
+    /// A signed bootstrap list fetched from `url`.
+    SignedList {
+        /// HTTPS URL of the list.
+        url: String,
+    },
+    /// TXT records at `name`.
+    Dns {
+        /// Domain holding the records (e.g. "bootstrap.ochra.net").
+        name: String,
+    },
+}
+
+impl SeedSource {
+    /// Who controls the source: the registrable domain of a DNS name or
+    /// list URL under the Public Suffix List, or `cached` / `hardcoded`.
+    ///
+    /// Sources with the same operator are not independent.
+    pub fn operator(&self) -> String {
+        match self {
+            Self::Cached => "cached".to_string(),
+            Self::Hardcoded => "hardcoded".to_string(),
+            Self::SignedList { url } => {
+                let rest = url.split_once("://").map_or(url.as_str(), |(_, r)| r);
+                let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+                let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+                let host = match host.strip_prefix('[') {
+                    Some(v6) => v6.split(']').next().unwrap_or_default(),
+                    None => host.split(':').next().unwrap_or_default(),
+                };
+                registrable_domain(host)
+            }
+            Self::Dns { name } => registrable_domain(name),
+        }
+    }
+}
+
+/// A bootstrap list signed by the project key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedBootstrapList {
+    /// Monotonic list version.
+    pub version: u32,
+    /// Unix timestamp the list was issued.
+    pub issued_at: u64,
+    /// Unix timestamp after which the list is refused.
+    pub expires_at: u64,
+    /// Seed nodes.
+    pub seeds: Vec<SeedNode>,
+    /// Hex Ed25519 signature over [`signable`](Self::signable) by the
+    /// project key.
+    pub signature: String,
+}
+
+impl SignedBootstrapList {
+    /// Bytes covered by the signature.
+    pub fn signable(&self) -> Vec<u8> {
+        let version = self.version.to_le_bytes();
+        let issued_at = self.issued_at.to_le_bytes();
+        let expires_at = self.expires_at.to_le_bytes();
+        let addrs: Vec<String> = self.seeds.iter().map(|s| s.addr.to_string()).collect();
+        let mut fields: Vec<&[u8]> = vec![b"bootstrap-list", &version, &issued_at, &expires_at];
+        for (seed, addr) in self.seeds.iter().zip(&addrs) {
+            fields.push(addr.as_bytes());
+            fields.push(&seed.pik_public_key);
+        }
+        ochra_crypto::blake3::encode_multi_field(&fields)
+    }
+
+    /// Parse and verify a fetched list.
+    ///
+    /// `min_version` is the version of the last list accepted.
+    ///
+    /// # Errors
+    ///
+    /// - [`DhtError::Serialization`] if the body is not a list
+    /// - [`DhtError::InvalidSignature`] if the project key did not sign it
+    /// - [`DhtError::BootstrapFailed`] if it is expired, issued in the
+    ///   future, or older than `min_version`
+    pub fn verify(body: &[u8], project_pk: &[u8; 32], now: u64, min_version: u32) -> Result<Self> {
+        let list: Self = serde_json::from_slice(body)
+            .map_err(|e| DhtError::Serialization(format!("bootstrap list: {e}")))?;
+        let sig: [u8; 64] = hex::decode(&list.signature)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or(DhtError::InvalidSignature)?;
+        VerifyingKey::from_bytes(project_pk)?
+            .verify(&list.signable(), &Signature::from_bytes(&sig))
+            .map_err(|_| DhtError::InvalidSignature)?;
+
+        if now >= list.expires_at {
+            return Err(DhtError::BootstrapFailed(
+                "bootstrap list expired".to_string(),
+            ));
+        }
+        if list.issued_at > now + MAX_ISSUE_SKEW_SECS {
+            return Err(DhtError::BootstrapFailed(
+                "bootstrap list issued in the future".to_string(),
+            ));
+        }
+        if list.version < min_version {
+            return Err(DhtError::BootstrapFailed(format!(
+                "bootstrap list version {} older than {}",
+                list.version, min_version
+            )));
+        }
+        Ok(list)
+    }
+}
+
+/// Parse one seed TXT record.
+///
+/// Format: `ochra-seed=v1 addr=<ip:port> pk=<hex PIK public key>`.
+/// Returns `None` for records that are not seed records or are malformed.
+pub fn parse_txt_record(record: &str) -> Option<SeedNode> {
+    let mut fields = record.split_whitespace();
+    if fields.next()? != TXT_RECORD_PREFIX {
+        return None;
+    }
+    let mut addr: Option<SocketAddr> = None;
+    let mut pk: Option<[u8; 32]> = None;
+    for field in fields {
+        match field.split_once('=')? {
+            ("addr", value) => addr = value.parse().ok(),
+            ("pk", value) => pk = hex::decode(value).ok()?.try_into().ok(),
+            // Unknown keys are reserved for later versions
+            _ => {}
+        }
+    }
+    Some(SeedNode {
+        addr: addr?,
+        pik_public_key: pk?,
+    })
+}
+
+/// Network I/O for seed sources.
+pub trait SeedFetcher {
+    /// Resolve the TXT records at `name`.
+    fn resolve_txt(
+        &self,
+        name: &str,
+    ) -> impl Future<
+        Output = std::result::Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>>,
+    > + Send;
+
+    /// Fetch `url` over HTTPS and return the body.
+    fn fetch(
+        &self,
+        url: &str,
+    ) -> impl Future<Output = std::result::Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>>
+           + Send;
+}
+
+/// Inputs for [`resolve_seeds`] that do not come from the network.
+#[derive(Clone, Debug, Default)]
+pub struct SeedContext {
+    /// Last-known-good peers, most recent first.
+    pub cached: Vec<SeedNode>,
+    /// Fixed seed nodes.
+    pub hardcoded: Vec<SeedNode>,
+    /// Project key that signs bootstrap lists. Lists are skipped without one.
+    pub project_pk: Option<[u8; 32]>,
+    /// Version of the last bootstrap list accepted.
+    pub min_list_version: u32,
+}
+
+/// Seeds found by [`resolve_seeds`].
+#[derive(Clone, Debug)]
+pub struct ResolvedSeeds {
+    /// The source that produced them.
+    pub source: SeedSource,
+    /// The seeds.
+    pub seeds: Vec<SeedNode>,
+    /// Version of the accepted bootstrap list, for `SignedList` sources.
+    pub list_version: Option<u32>,
+}
+
+/// Try each source in order and return the first non-empty set of seeds.
+///
+/// # Errors
+///
+/// - [`DhtError::BootstrapFailed`] if every source fails or is empty
+pub async fn resolve_seeds<F: SeedFetcher>(
+    sources: &[SeedSource],
+    ctx: &SeedContext,
+    fetcher: &F,
+    now: u64,
+) -> Result<ResolvedSeeds> {
+    for source in sources {
+        let found = match source {
+            SeedSource::Cached => Ok((ctx.cached.clone(), None)),
+            SeedSource::Hardcoded => Ok((ctx.hardcoded.clone(), None)),
+            SeedSource::SignedList { url } => match ctx.project_pk {
+                Some(pk) => fetch_list(fetcher, url, &pk, now, ctx.min_list_version)
+                    .await
+                    .map(|list| (list.seeds, Some(list.version))),
+                None => {
+                    debug!(url = %url, "No project key; skipping bootstrap list");
+                    continue;
+                }
+            },
+            SeedSource::Dns { name } => match fetcher.resolve_txt(name).await {
+                Ok(records) => Ok((
+                    records.iter().filter_map(|r| parse_txt_record(r)).collect(),
+                    None,
+                )),
+                Err(e) => Err(DhtError::Network(e.to_string())),
+            },
+        };
+        match found {
+            Ok((mut seeds, list_version)) if !seeds.is_empty() => {
+                seeds.truncate(MAX_SEEDS_PER_SOURCE);
+                return Ok(ResolvedSeeds {
+                    source: source.clone(),
+                    seeds,
+                    list_version,
+                });
+            }
+            Ok(_) => debug!(?source, "Seed source returned no seeds"),
+            Err(e) => warn!(?source, error = %e, "Seed source failed"),
+        }
+    }
+    Err(DhtError::BootstrapFailed(
+        "no seed source produced any seeds".to_string(),
+    ))
+}
+
+/// Query every source and return the seeds of each that yields any, keeping
+/// only the first source per [`SeedSource::operator`].
+///
+/// Unlike [`resolve_seeds`] this never stops early; failures are logged
+/// and skipped, so the result may be empty.
+pub async fn resolve_seed_sets<F: SeedFetcher>(
+    sources: &[SeedSource],
+    ctx: &SeedContext,
+    fetcher: &F,
+    now: u64,
+) -> Vec<ResolvedSeeds> {
+    let mut sets: Vec<ResolvedSeeds> = Vec::new();
+    for source in sources {
+        let operator = source.operator();
+        if sets.iter().any(|set| set.source.operator() == operator) {
+            debug!(?source, operator = %operator, "Operator already has a seed set");
+            continue;
+        }
+        if let Ok(found) = resolve_seeds(std::slice::from_ref(source), ctx, fetcher, now).await {
+            sets.push(found);
+        }
+    }
+    sets
+}
+
+async fn fetch_list<F: SeedFetcher>(
+    fetcher: &F,
+    url: &str,
+    project_pk: &[u8; 32],
+    now: u64,
+    min_version: u32,
+) -> Result<SignedBootstrapList> {
+    let body = fetcher
+        .fetch(url)
+        .await
+        .map_err(|e| DhtError::Network(e.to_string()))?;
+    SignedBootstrapList::verify(&body, project_pk, now, min_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::KeyPair;
+
+    struct FakeFetcher {
+        txt: Vec<String>,
+        body: Option<Vec<u8>>,
+    }
+
+    impl SeedFetcher for FakeFetcher {
+        async fn resolve_txt(
+            &self,
+            _name: &str,
+        ) -> std::result::Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(self.txt.clone())
+        }
+
+        async fn fetch(
+            &self,
+            _url: &str,
+        ) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            self.body.clone().ok_or_else(|| "unreachable".into())
+        }
+    }
+
+    fn seed(n: u8) -> SeedNode {
+        SeedNode {
+            addr: SocketAddr::from(([192, 0, 2, n], 4433)),
+            pik_public_key: [n; 32],
+        }
+    }
+
+    fn signed_list(key: &KeyPair, version: u32, expires_at: u64) -> Vec<u8> {
+        let mut list = SignedBootstrapList {
+            version,
+            issued_at: 1_000,
+            expires_at,
+            seeds: vec![seed(1), seed(2)],
+            signature: String::new(),
+        };
+        list.signature = hex::encode(key.signing_key.sign(&list.signable()).to_bytes());
+        serde_json::to_vec(&list).expect("encode")
+    }
+
+    #[test]
+    fn test_signed_list_verification() {
+        let key = KeyPair::generate();
+        let pk = key.verifying_key.to_bytes();
+        let body = signed_list(&key, 3, 5_000);
+
+        let list = SignedBootstrapList::verify(&body, &pk, 2_000, 3).expect("verify");
+        assert_eq!(list.seeds.len(), 2);
+        assert!(matches!(
+            SignedBootstrapList::verify(&body, &pk, 2_000, 4),
+            Err(DhtError::BootstrapFailed(_))
+        ));
+        assert!(matches!(
+            SignedBootstrapList::verify(&body, &pk, 5_000, 0),
+            Err(DhtError::BootstrapFailed(_))
+        ));
+        let other = KeyPair::generate().verifying_key.to_bytes();
+        assert!(matches!(
+            SignedBootstrapList::verify(&body, &other, 2_000, 0),
+            Err(DhtError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_parse_txt_record() {
+        let pk = hex::encode([7u8; 32]);
+        let record = format!("ochra-seed=v1 addr=192.0.2.7:4433 pk={pk} ttl=3600");
+        let parsed = parse_txt_record(&record).expect("parse");
+        assert_eq!(parsed.pik_public_key, [7u8; 32]);
+        assert_eq!(parsed.addr, SocketAddr::from(([192, 0, 2, 7], 4433)));
+
+        assert!(parse_txt_record("v=spf1 -all").is_none());
+        assert!(parse_txt_record("ochra-seed=v1 addr=192.0.2.7:4433").is_none());
+        assert!(parse_txt_record(&format!("ochra-seed=v1 addr=nowhere pk={pk}")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sources_fall_through_in_order() {
+        let key = KeyPair::generate();
+        let ctx = SeedContext {
+            project_pk: Some(key.verifying_key.to_bytes()),
+            ..SeedContext::default()
+        };
+        let sources = vec![
+            SeedSource::Cached,
+            SeedSource::SignedList {
+                url: "https://example.invalid/seeds.json".to_string(),
+            },
+            SeedSource::Dns {
+                name: "bootstrap.example".to_string(),
+            },
+        ];
+
+        // Empty cache and unreachable list fall through to DNS
+        let fetcher = FakeFetcher {
+            txt: vec![format!(
+                "ochra-seed=v1 addr=192.0.2.9:4433 pk={}",
+                hex::encode([9u8; 32])
+            )],
+            body: None,
+        };
+        let found = resolve_seeds(&sources, &ctx, &fetcher, 2_000)
+            .await
+            .expect("resolve");
+        assert!(matches!(found.source, SeedSource::Dns { .. }));
+
+        // A valid list is preferred over DNS
+        let fetcher = FakeFetcher {
+            txt: Vec::new(),
+            body: Some(signed_list(&key, 1, 5_000)),
+        };
+        let found = resolve_seeds(&sources, &ctx, &fetcher, 2_000)
+            .await
+            .expect("resolve");
+        assert_eq!(found.list_version, Some(1));
+        assert_eq!(found.seeds.len(), 2);
+
+        // Nothing anywhere
+        let fetcher = FakeFetcher {
+            txt: Vec::new(),
+            body: None,
+        };
+        assert!(resolve_seeds(&sources, &ctx, &fetcher, 2_000)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_source_operator() {
+        let list = |url: &str| SeedSource::SignedList {
+            url: url.to_string(),
+        };
+        let dns = |name: &str| SeedSource::Dns {
+            name: name.to_string(),
+        };
+        assert_eq!(dns("bootstrap.ochra.net").operator(), "ochra.net");
+        assert_eq!(dns("Seeds.Example.ORG.").operator(), "example.org");
+        assert_ne!(
+            dns("seeds.a.co.uk").operator(),
+            dns("seeds.b.co.uk").operator()
+        );
+        assert_eq!(
+            list("https://user@mirror.ochra.net:8443/seeds.json").operator(),
+            "ochra.net"
+        );
+        assert_eq!(
+            list("https://[2001:db8::1]/s.json").operator(),
+            "2001:db8::1"
+        );
+        assert_eq!(list("https://192.0.2.1/s.json").operator(), "192.0.2.1");
+        assert_eq!(SeedSource::Cached.operator(), "cached");
+    }
+
+    #[tokio::test]
+    async fn test_seed_sets_one_per_operator() {
+        let ctx = SeedContext {
+            hardcoded: vec![seed(1)],
+            ..SeedContext::default()
+        };
+        let sources = vec![
+            SeedSource::Cached,
+            SeedSource::Hardcoded,
+            SeedSource::Dns {
+                name: "a.seeds.example".to_string(),
+            },
+            SeedSource::Dns {
+                name: "b.seeds.example".to_string(),
+            },
+            SeedSource::Dns {
+                name: "seeds.example.org".to_string(),
+            },
+        ];
+        let fetcher = FakeFetcher {
+            txt: vec![format!(
+                "ochra-seed=v1 addr=192.0.2.9:4433 pk={}",
+                hex::encode([9u8; 32])
+            )],
+            body: None,
+        };
+        let sets = resolve_seed_sets(&sources, &ctx, &fetcher, 2_000).await;
+        let operators: Vec<String> = sets.iter().map(|s| s.source.operator()).collect();
+        assert_eq!(operators, vec!["hardcoded", "seeds.example", "example.org"]);
+    }
+}
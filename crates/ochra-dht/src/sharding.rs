@@ -0,0 +1,604 @@
+//! Erasure-coded storage for critical DHT records.
+//!
+//! Replication keeps a record alive while any one of its replicas is
+//! reachable, but every replica sits near the same key and therefore in the
+//! same region of the key space. For records whose loss is expensive
+//! (Recovery Contact heartbeats, handle descriptors) the publisher can
+//! instead split the value into `k` data shards and `n - k` parity shards
+//! with a systematic Reed-Solomon code over GF(2^8). Any `k` of the `n`
+//! shards reconstruct the value.
+//!
+//! ## Layout
+//!
+//! - Each shard is stored as an immutable record, keyed by the BLAKE3 hash
+//!   of its bytes.
+//! - A [`ShardManifest`] is stored at the record's own key in place of the
+//!   value. It starts with [`SHARD_MANIFEST_MAGIC`] so that a reader can
+//!   tell it apart from a plain value and fetch the shards instead.
+//!
+//! ## Placement
+//!
+//! [`place_shards`] assigns each shard to a node so that no two shards
+//! share a node, a bucket (relative to the record key), or a /24 (/48)
+//! subnet. When the candidate pool is too small for that, the bucket and
+//! then the subnet constraint are relaxed before a shard is left unplaced.
+//!
+//! ## Reconstruction
+//!
+//! [`reconstruct_record`] accepts whatever shards a reader managed to
+//! fetch. Shards whose hash does not match the manifest are discarded, and
+//! the value is rebuilt from any `k` of the rest.
+//!
+//! ## Store path
+//!
+//! [`RecordStore::put_value`](crate::bep44::RecordStore::put_value) signs a
+//! value, sharding it when the policy says so, stores the manifest and
+//! shards, and returns where each shard should be replicated.
+//! [`RecordStore::get_value`](crate::bep44::RecordStore::get_value)
+//! reassembles a sharded value from whichever shards the store holds.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::bep44::{create_mutable_record, DhtRecord};
+use crate::kademlia::{leading_zeros, NodeId, NodeInfo, RoutingTable, Subnet};
+use crate::{DhtError, Result, MAX_RECORD_SIZE};
+
+/// Magic prefix of an encoded [`ShardManifest`] ("sharded format v1").
+pub const SHARD_MANIFEST_MAGIC: u16 = 0xCF02;
+
+/// Default value size at or above which records are sharded.
+pub const DEFAULT_SHARD_THRESHOLD: usize = 256;
+
+/// Default number of data shards.
+pub const DEFAULT_DATA_SHARDS: u8 = 4;
+
+/// Default number of data plus parity shards.
+pub const DEFAULT_TOTAL_SHARDS: u8 = 8;
+
+/// Upper bound on shards per record, so the manifest fits in one record.
+pub const MAX_TOTAL_SHARDS: u8 = 16;
+
+/// Encoded manifest header: magic, k, n, value size.
+const MANIFEST_HEADER_LEN: usize = 2 + 1 + 1 + 4;
+
+/// When and how records are sharded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardingPolicy {
+    /// Values of at least this many bytes are sharded.
+    pub threshold: usize,
+    /// Shards needed to reconstruct (`k`).
+    pub data_shards: u8,
+    /// Shards produced (`n`).
+    pub total_shards: u8,
+}
+
+impl Default for ShardingPolicy {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_SHARD_THRESHOLD,
+            data_shards: DEFAULT_DATA_SHARDS,
+            total_shards: DEFAULT_TOTAL_SHARDS,
+        }
+    }
+}
+
+impl ShardingPolicy {
+    /// Whether a value of `len` bytes should be sharded.
+    pub fn should_shard(&self, len: usize) -> bool {
+        len >= self.threshold
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.data_shards == 0
+            || self.data_shards >= self.total_shards
+            || self.total_shards > MAX_TOTAL_SHARDS
+        {
+            return Err(DhtError::InvalidShardingPolicy {
+                data_shards: self.data_shards,
+                total_shards: self.total_shards,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// One shard of an erasure-coded record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordShard {
+    /// Position in the manifest; indices below `k` hold the value itself.
+    pub index: u8,
+    /// Shard bytes, stored as an immutable record.
+    pub data: Vec<u8>,
+}
+
+impl RecordShard {
+    /// The immutable record holding this shard.
+    pub fn to_record(&self) -> DhtRecord {
+        DhtRecord::Immutable {
+            value: self.data.clone(),
+        }
+    }
+}
+
+/// The record stored at the original key of a sharded value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardManifest {
+    /// Shards needed to reconstruct.
+    pub data_shards: u8,
+    /// Size of the original value in bytes.
+    pub total_size: u32,
+    /// BLAKE3 hash (and immutable record key) of each shard, by index.
+    pub shard_hashes: Vec<[u8; 32]>,
+}
+
+impl ShardManifest {
+    /// Number of shards produced.
+    pub fn total_shards(&self) -> usize {
+        self.shard_hashes.len()
+    }
+
+    /// Encode as `magic || k || n || LE32(total_size) || hashes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(MANIFEST_HEADER_LEN + 32 * self.shard_hashes.len());
+        out.extend_from_slice(&SHARD_MANIFEST_MAGIC.to_le_bytes());
+        out.push(self.data_shards);
+        out.push(self.shard_hashes.len() as u8);
+        out.extend_from_slice(&self.total_size.to_le_bytes());
+        for hash in &self.shard_hashes {
+            out.extend_from_slice(hash);
+        }
+        out
+    }
+
+    /// Decode a manifest, or `None` if `bytes` is not one.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let header = bytes.get(..MANIFEST_HEADER_LEN)?;
+        if u16::from_le_bytes([header[0], header[1]]) != SHARD_MANIFEST_MAGIC {
+            return None;
+        }
+        let (data_shards, total_shards) = (header[2], header[3]);
+        let total_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let policy = ShardingPolicy {
+            threshold: 0,
+            data_shards,
+            total_shards,
+        };
+        if policy.validate().is_err() {
+            return None;
+        }
+        let body = &bytes[MANIFEST_HEADER_LEN..];
+        if body.len() != 32 * usize::from(total_shards) {
+            return None;
+        }
+        let shard_hashes = body
+            .chunks_exact(32)
+            .map(|c| {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(c);
+                hash
+            })
+            .collect();
+        Some(Self {
+            data_shards,
+            total_size,
+            shard_hashes,
+        })
+    }
+}
+
+/// Where one shard is stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShardPlacement {
+    /// Shard index.
+    pub index: u8,
+    /// Node chosen to store it.
+    pub node_id: NodeId,
+}
+
+/// Split `value` into `n` shards under `policy`.
+///
+/// # Errors
+///
+/// - [`DhtError::InvalidShardingPolicy`] if `k` or `n` is out of range
+/// - [`DhtError::RecordTooLarge`] if a shard would not fit in one record
+pub fn encode_record(
+    policy: &ShardingPolicy,
+    value: &[u8],
+) -> Result<(ShardManifest, Vec<RecordShard>)> {
+    policy.validate()?;
+    let k = usize::from(policy.data_shards);
+    let max = k * MAX_RECORD_SIZE;
+    if value.len() > max {
+        return Err(DhtError::RecordTooLarge {
+            size: value.len(),
+            max,
+        });
+    }
+
+    let shard_len = value.len().div_ceil(k).max(1);
+    let mut shards: Vec<Vec<u8>> = (0..k)
+        .map(|i| {
+            let start = (i * shard_len).min(value.len());
+            let end = ((i + 1) * shard_len).min(value.len());
+            let mut data = value[start..end].to_vec();
+            data.resize(shard_len, 0);
+            data
+        })
+        .collect();
+    for row in k..usize::from(policy.total_shards) {
+        let coeffs = parity_row(row, k);
+        let mut parity = vec![0u8; shard_len];
+        for (coeff, data) in coeffs.iter().zip(&shards) {
+            mul_add(&mut parity, *coeff, data);
+        }
+        shards.push(parity);
+    }
+
+    let manifest = ShardManifest {
+        data_shards: policy.data_shards,
+        total_size: value.len() as u32,
+        shard_hashes: shards
+            .iter()
+            .map(|s| ochra_crypto::blake3::hash(s))
+            .collect(),
+    };
+    let shards = shards
+        .into_iter()
+        .enumerate()
+        .map(|(i, data)| RecordShard {
+            index: i as u8,
+            data,
+        })
+        .collect();
+    Ok((manifest, shards))
+}
+
+/// Sign `value` as the mutable record at `salt`, replacing it with a
+/// manifest when `policy` says to shard it.
+///
+/// Returns the record and the shards to store as immutable records, none
+/// if the value is stored whole.
+///
+/// # Errors
+///
+/// - [`DhtError::InvalidShardingPolicy`] if `k` or `n` is out of range
+/// - [`DhtError::RecordTooLarge`] if the value, or a shard of it, does not
+///   fit in one record
+pub fn sign_value(
+    signing_key: &ochra_crypto::ed25519::SigningKey,
+    salt: &[u8],
+    seq: u64,
+    value: Vec<u8>,
+    policy: &ShardingPolicy,
+) -> Result<(DhtRecord, Vec<RecordShard>)> {
+    let (value, shards) = if policy.should_shard(value.len()) {
+        let (manifest, shards) = encode_record(policy, &value)?;
+        (manifest.to_bytes(), shards)
+    } else {
+        (value, Vec::new())
+    };
+    Ok((
+        create_mutable_record(signing_key, salt, seq, value)?,
+        shards,
+    ))
+}
+
+/// Rebuild a value from any `k` intact shards.
+///
+/// # Errors
+///
+/// - [`DhtError::InsufficientShards`] if fewer than `k` shards match the
+///   manifest
+pub fn reconstruct_record(manifest: &ShardManifest, shards: &[RecordShard]) -> Result<Vec<u8>> {
+    let k = usize::from(manifest.data_shards);
+    let mut seen = HashSet::new();
+    let mut usable: Vec<&RecordShard> = Vec::with_capacity(k);
+    for shard in shards {
+        let Some(expected) = manifest.shard_hashes.get(usize::from(shard.index)) else {
+            continue;
+        };
+        if ochra_crypto::blake3::hash(&shard.data) != *expected {
+            debug!("Discarding shard {} with mismatched hash", shard.index);
+            continue;
+        }
+        if seen.insert(shard.index) {
+            usable.push(shard);
+        }
+    }
+    if usable.len() < k {
+        return Err(DhtError::InsufficientShards {
+            have: usable.len(),
+            need: k,
+        });
+    }
+    // Data shards first, so the common case needs no decoding
+    usable.sort_by_key(|s| s.index);
+    usable.truncate(k);
+
+    let mut value = if usable.iter().all(|s| usize::from(s.index) < k) {
+        usable.iter().flat_map(|s| s.data.iter().copied()).collect()
+    } else {
+        let matrix: Vec<Vec<u8>> = usable
+            .iter()
+            .map(|s| generator_row(usize::from(s.index), k))
+            .collect();
+        let inverse = invert(matrix).ok_or_else(|| {
+            DhtError::Serialization("shard decoding matrix is singular".to_string())
+        })?;
+        let shard_len = usable[0].data.len();
+        let mut value = Vec::with_capacity(k * shard_len);
+        for row in &inverse {
+            let mut data = vec![0u8; shard_len];
+            for (coeff, shard) in row.iter().zip(&usable) {
+                mul_add(&mut data, *coeff, &shard.data);
+            }
+            value.extend_from_slice(&data);
+        }
+        value
+    };
+    value.truncate(manifest.total_size as usize);
+    Ok(value)
+}
+
+/// Assign each shard of `manifest` a storing node from `candidates`.
+///
+/// Each shard goes to the candidate closest to its key that shares no
+/// node, bucket (relative to `record_key`), or subnet with an earlier
+/// shard. The bucket, then the subnet, constraint is dropped if no such
+/// candidate is left; a shard is left out only when every candidate is
+/// already used.
+pub fn place_shards(
+    record_key: &[u8; 32],
+    manifest: &ShardManifest,
+    candidates: &[NodeInfo],
+) -> Vec<ShardPlacement> {
+    let mut used_nodes = HashSet::new();
+    let mut used_buckets = HashSet::new();
+    let mut used_subnets = HashSet::new();
+    let mut placements = Vec::with_capacity(manifest.total_shards());
+
+    for (index, shard_key) in manifest.shard_hashes.iter().enumerate() {
+        let mut ordered: Vec<&NodeInfo> = candidates
+            .iter()
+            .filter(|n| !used_nodes.contains(&n.node_id))
+            .collect();
+        ordered.sort_by_key(|n| RoutingTable::xor_distance(shard_key, &n.node_id));
+
+        let bucket_of =
+            |n: &NodeInfo| leading_zeros(&RoutingTable::xor_distance(record_key, &n.node_id));
+        let new_subnet = |n: &NodeInfo| {
+            n.addrs()
+                .all(|a| !used_subnets.contains(&Subnet::of(a.ip())))
+        };
+        let chosen = ordered
+            .iter()
+            .find(|n| !used_buckets.contains(&bucket_of(n)) && new_subnet(n))
+            .or_else(|| ordered.iter().find(|n| new_subnet(n)))
+            .or_else(|| ordered.first())
+            .copied();
+        let Some(node) = chosen else {
+            break;
+        };
+
+        used_nodes.insert(node.node_id);
+        used_buckets.insert(bucket_of(node));
+        used_subnets.extend(node.addrs().map(|a| Subnet::of(a.ip())));
+        placements.push(ShardPlacement {
+            index: index as u8,
+            node_id: node.node_id,
+        });
+    }
+    placements
+}
+
+/// Reduction polynomial x^8 + x^4 + x^3 + x^2 + 1.
+const GF_POLY: u16 = 0x11d;
+
+/// Exponent and logarithm tables for GF(2^8) with generator 2.
+const GF_TABLES: ([u8; 512], [u8; 256]) = build_gf_tables();
+
+const fn build_gf_tables() -> ([u8; 512], [u8; 256]) {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= GF_POLY;
+        }
+        i += 1;
+    }
+    (exp, log)
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    let (exp, log) = &GF_TABLES;
+    exp[usize::from(log[usize::from(a)]) + usize::from(log[usize::from(b)])]
+}
+
+/// Multiplicative inverse of a non-zero element.
+fn gf_inv(a: u8) -> u8 {
+    let (exp, log) = &GF_TABLES;
+    exp[255 - usize::from(log[usize::from(a)])]
+}
+
+/// `acc += coeff * data`, byte-wise.
+fn mul_add(acc: &mut [u8], coeff: u8, data: &[u8]) {
+    for (a, d) in acc.iter_mut().zip(data) {
+        *a ^= gf_mul(coeff, *d);
+    }
+}
+
+/// Cauchy coefficients `1 / (x_i + y_j)` with `x_i = row`, `y_j = j`.
+///
+/// Stacked under the identity, every `k`-row subset of these rows is
+/// invertible, which is what lets any `k` shards reconstruct.
+fn parity_row(row: usize, k: usize) -> Vec<u8> {
+    (0..k).map(|j| gf_inv((row ^ j) as u8)).collect()
+}
+
+/// Row `index` of the systematic generator matrix.
+fn generator_row(index: usize, k: usize) -> Vec<u8> {
+    if index < k {
+        (0..k).map(|j| u8::from(j == index)).collect()
+    } else {
+        parity_row(index, k)
+    }
+}
+
+/// Gauss-Jordan inversion over GF(2^8).
+fn invert(mut matrix: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<u8>> = (0..n)
+        .map(|i| (0..n).map(|j| u8::from(i == j)).collect())
+        .collect();
+    for col in 0..n {
+        let pivot = (col..n).find(|&r| matrix[r][col] != 0)?;
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let scale = gf_inv(matrix[col][col]);
+        for j in 0..n {
+            matrix[col][j] = gf_mul(matrix[col][j], scale);
+            inverse[col][j] = gf_mul(inverse[col][j], scale);
+        }
+        for r in 0..n {
+            let factor = matrix[r][col];
+            if r == col || factor == 0 {
+                continue;
+            }
+            for j in 0..n {
+                matrix[r][j] ^= gf_mul(factor, matrix[col][j]);
+                inverse[r][j] ^= gf_mul(factor, inverse[col][j]);
+            }
+        }
+    }
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    fn value(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + 3) as u8).collect()
+    }
+
+    fn node(id: u8, ip: [u8; 4]) -> NodeInfo {
+        let mut node_id = [0u8; 32];
+        node_id[0] = id;
+        node_id[31] = ip[3];
+        NodeInfo {
+            node_id,
+            addr: SocketAddr::from((ip, 4433)),
+            alt_addr: None,
+            pik_public_key: [id; 32],
+            x25519_public_key: [id; 32],
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_from_any_k_shards() {
+        let policy = ShardingPolicy::default();
+        let original = value(701);
+        let (manifest, shards) = encode_record(&policy, &original).expect("encode");
+        assert_eq!(shards.len(), 8);
+        assert!(shards.iter().all(|s| s.data.len() == 176));
+
+        // Every window of four consecutive shards, wrapping around
+        for start in 0..8 {
+            let subset: Vec<RecordShard> =
+                (0..4).map(|i| shards[(start + i) % 8].clone()).collect();
+            assert_eq!(
+                reconstruct_record(&manifest, &subset).expect("reconstruct"),
+                original
+            );
+        }
+        let parity_only = &shards[4..];
+        assert_eq!(
+            reconstruct_record(&manifest, parity_only).expect("parity only"),
+            original
+        );
+    }
+
+    #[test]
+    fn test_corrupt_and_missing_shards() {
+        let policy = ShardingPolicy::default();
+        let original = value(300);
+        let (manifest, shards) = encode_record(&policy, &original).expect("encode");
+
+        let mut fetched: Vec<RecordShard> = shards[3..].to_vec();
+        fetched[0].data[0] ^= 0xff;
+        assert_eq!(
+            reconstruct_record(&manifest, &fetched).expect("reconstruct"),
+            original
+        );
+
+        fetched.truncate(4);
+        assert!(matches!(
+            reconstruct_record(&manifest, &fetched),
+            Err(DhtError::InsufficientShards { have: 3, need: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_policy_and_manifest_encoding() {
+        let policy = ShardingPolicy::default();
+        assert!(!policy.should_shard(100));
+        assert!(policy.should_shard(DEFAULT_SHARD_THRESHOLD));
+        let bad = ShardingPolicy {
+            data_shards: 8,
+            ..policy
+        };
+        assert!(matches!(
+            encode_record(&bad, b"x"),
+            Err(DhtError::InvalidShardingPolicy { .. })
+        ));
+        assert!(matches!(
+            encode_record(&policy, &value(4 * MAX_RECORD_SIZE + 1)),
+            Err(DhtError::RecordTooLarge { .. })
+        ));
+
+        let (manifest, _) = encode_record(&policy, &value(512)).expect("encode");
+        let bytes = manifest.to_bytes();
+        assert!(bytes.len() <= MAX_RECORD_SIZE);
+        assert_eq!(ShardManifest::from_bytes(&bytes), Some(manifest));
+        assert_eq!(ShardManifest::from_bytes(b"plain record value"), None);
+    }
+
+    #[test]
+    fn test_placement_spreads_buckets_and_subnets() {
+        let (manifest, _) = encode_record(&ShardingPolicy::default(), &value(400)).expect("encode");
+        let key = [0u8; 32];
+        // Two nodes per bucket, each bucket in its own subnet
+        let candidates: Vec<NodeInfo> = (0..8u8)
+            .flat_map(|b| {
+                let id = 0x80u8 >> b;
+                [node(id, [10, b, 0, 1]), node(id, [10, b, 0, 2])]
+            })
+            .collect();
+        let placements = place_shards(&key, &manifest, &candidates);
+        assert_eq!(placements.len(), 8);
+        let buckets: HashSet<_> = placements
+            .iter()
+            .map(|p| leading_zeros(&RoutingTable::xor_distance(&key, &p.node_id)))
+            .collect();
+        assert_eq!(buckets.len(), 8);
+
+        // Too few candidates: every node used once, the rest unplaced
+        let placements = place_shards(&key, &manifest, &candidates[..3]);
+        assert_eq!(placements.len(), 3);
+        let nodes: HashSet<_> = placements.iter().map(|p| p.node_id).collect();
+        assert_eq!(nodes.len(), 3);
+    }
+}
@@ -0,0 +1,126 @@
+//! Time-locked records for scheduled reveals.
+//!
+//! A time-locked record holds a value sealed to the quorum's group key until
+//! a target epoch (see [`ochra_crypto::timelock`]). Its salt is
+//! `"timelock" || LE64(target_epoch) || label`, so the publisher can keep one
+//! record per reveal and readers know when to ask the quorum for a release.
+//! The record type registry checks that the value is a well-formed sealed
+//! value for the epoch in the salt; opening it needs the quorum's release,
+//! coordinated by `ochra-frost`.
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_crypto::timelock::Sealed;
+
+use crate::bep44::{create_mutable_record, DhtRecord};
+use crate::{DhtError, Result};
+
+/// Salt prefix of time-locked records.
+pub const TIMELOCK_SALT: &[u8] = b"timelock";
+
+/// Longest label that keeps the salt within BEP 44's 64 bytes.
+pub const MAX_LABEL_LEN: usize = 64 - TIMELOCK_SALT.len() - 8;
+
+/// The salt of a time-locked record.
+///
+/// # Errors
+///
+/// - [`DhtError::Serialization`] if `label` is longer than [`MAX_LABEL_LEN`]
+pub fn timelock_salt(target_epoch: u64, label: &[u8]) -> Result<Vec<u8>> {
+    if label.len() > MAX_LABEL_LEN {
+        return Err(DhtError::Serialization(format!(
+            "time-lock label of {} bytes exceeds {MAX_LABEL_LEN}",
+            label.len()
+        )));
+    }
+    let mut salt = Vec::with_capacity(TIMELOCK_SALT.len() + 8 + label.len());
+    salt.extend_from_slice(TIMELOCK_SALT);
+    salt.extend_from_slice(&target_epoch.to_le_bytes());
+    salt.extend_from_slice(label);
+    Ok(salt)
+}
+
+/// The target epoch named by a time-lock salt.
+pub fn salt_epoch(salt: &[u8]) -> Option<u64> {
+    let epoch = salt.strip_prefix(TIMELOCK_SALT)?.get(..8)?;
+    Some(u64::from_le_bytes(epoch.try_into().ok()?))
+}
+
+/// Publish `sealed` under `label`.
+///
+/// # Errors
+///
+/// - [`DhtError::Serialization`] if the label is too long
+/// - [`DhtError::RecordTooLarge`] if the sealed value does not fit a record
+pub fn create_timelock_record(
+    signing_key: &SigningKey,
+    label: &[u8],
+    seq: u64,
+    sealed: &Sealed,
+) -> Result<DhtRecord> {
+    let salt = timelock_salt(sealed.target_epoch, label)?;
+    create_mutable_record(signing_key, &salt, seq, sealed.to_bytes())
+}
+
+/// The sealed value of a time-locked record, checked against its salt.
+///
+/// # Errors
+///
+/// - [`DhtError::Serialization`] if the record is not a time-locked record
+///   or its value does not match the salt's epoch
+pub fn sealed_from_record(record: &DhtRecord) -> Result<Sealed> {
+    let DhtRecord::Mutable { salt, value, .. } = record else {
+        return Err(DhtError::Serialization(
+            "time-locked records are mutable".into(),
+        ));
+    };
+    let epoch =
+        salt_epoch(salt).ok_or_else(|| DhtError::Serialization("not a time-lock salt".into()))?;
+    let sealed = Sealed::from_bytes(value)?;
+    if sealed.target_epoch != epoch {
+        return Err(DhtError::Serialization(format!(
+            "sealed to epoch {} under a salt for epoch {epoch}",
+            sealed.target_epoch
+        )));
+    }
+    Ok(sealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use ochra_crypto::ed25519::KeyPair;
+
+    use super::*;
+    use crate::record_types::RecordTypeRegistry;
+
+    fn group_key() -> [u8; 32] {
+        KeyPair::generate().verifying_key.to_bytes()
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let kp = KeyPair::generate();
+        let sealed = ochra_crypto::timelock::seal(&group_key(), 40, b"reveal").expect("seal");
+        let record =
+            create_timelock_record(&kp.signing_key, b"vote-7", 1, &sealed).expect("record");
+        assert!(RecordTypeRegistry::standard().validate(&record).is_ok());
+        assert_eq!(sealed_from_record(&record).expect("sealed"), sealed);
+        assert!(timelock_salt(1, &[0; MAX_LABEL_LEN + 1]).is_err());
+    }
+
+    #[test]
+    fn test_epoch_must_match_salt() {
+        let kp = KeyPair::generate();
+        let sealed = ochra_crypto::timelock::seal(&group_key(), 40, b"reveal").expect("seal");
+        let salt = timelock_salt(39, b"early").expect("salt");
+        let record =
+            create_mutable_record(&kp.signing_key, &salt, 1, sealed.to_bytes()).expect("record");
+        assert!(sealed_from_record(&record).is_err());
+        assert!(matches!(
+            RecordTypeRegistry::standard().validate(&record),
+            Err(DhtError::InvalidRecord {
+                record_type: "timelock",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,374 @@
+//! Epoch randomness beacon.
+//!
+//! At each epoch boundary the quorum signs the epoch number chained with
+//! the previous beacon value, using an ordinary ROAST session over
+//! [`beacon_message`]. The beacon value is the hash of that signature, so
+//! nobody outside the quorum can predict it before the signature exists,
+//! and chaining makes every value depend on the whole history.
+//!
+//! FROST signatures are not unique: a different signing set or nonce gives
+//! a different valid signature for the same message. Consumers therefore
+//! keep the first valid beacon they see for an epoch, and a
+//! [`BeaconCache`] rejects a second, different one as a conflict, which is
+//! evidence that the quorum equivocated.
+//!
+//! Consumers do not use the value directly but derive a seed per purpose
+//! with [`derive_seed`], so sortition, storage challenges, and relay epochs
+//! never share randomness.
+
+use std::collections::BTreeMap;
+
+use ochra_crypto::blake3;
+use ochra_types::network::EpochBeacon;
+
+use crate::quorum::verify_group_signature;
+use crate::roast::RoastSession;
+use crate::{FrostCoordError, Result};
+
+/// Previous-beacon value chained into the first beacon.
+pub const GENESIS_BEACON: [u8; 32] = [0u8; 32];
+
+/// Epochs of beacons a [`BeaconCache`] keeps.
+pub const DEFAULT_CACHE_EPOCHS: usize = 32;
+
+/// Seed purpose: quorum and committee sortition.
+pub const PURPOSE_SORTITION: &[u8] = b"sortition";
+
+/// Seed purpose: zk-PoR storage challenges.
+pub const PURPOSE_POR_CHALLENGE: &[u8] = b"por-challenge";
+
+/// Seed purpose: per-relay-epoch randomness, indexed by relay epoch.
+pub const PURPOSE_RELAY_EPOCH: &[u8] = b"relay-epoch";
+
+/// Seed purpose: uptime attestation auditor selection.
+pub const PURPOSE_UPTIME: &[u8] = b"uptime";
+
+/// Message the quorum signs for `epoch`.
+///
+/// `"epoch-beacon" || epoch (u32 BE) || prev_beacon`
+pub fn beacon_message(epoch: u32, prev_beacon: &[u8; 32]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(12 + 4 + 32);
+    msg.extend_from_slice(b"epoch-beacon");
+    msg.extend_from_slice(&epoch.to_be_bytes());
+    msg.extend_from_slice(prev_beacon);
+    msg
+}
+
+/// The beacon value: `BLAKE3::hash("epoch-beacon-value" || quorum_sig)`.
+pub fn beacon_value(beacon: &EpochBeacon) -> [u8; 32] {
+    let mut input = Vec::with_capacity(18 + 64);
+    input.extend_from_slice(b"epoch-beacon-value");
+    input.extend_from_slice(&beacon.quorum_sig);
+    blake3::hash(&input)
+}
+
+/// A seed for one purpose, derived from a beacon value.
+///
+/// `BLAKE3::hash(enc("beacon-seed", purpose, value, LE64(index)))`. Use
+/// `index` to draw several independent seeds for the same purpose, e.g.
+/// one per relay epoch.
+pub fn derive_seed(value: &[u8; 32], purpose: &[u8], index: u64) -> [u8; 32] {
+    blake3::hash(&blake3::encode_multi_field(&[
+        b"beacon-seed",
+        purpose,
+        value,
+        &index.to_le_bytes(),
+    ]))
+}
+
+/// Start the quorum's signing session for `epoch`'s beacon.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::InsufficientSigners`] if fewer than `threshold`
+///   signers are eligible
+pub fn start_session(
+    epoch: u32,
+    prev_beacon: &[u8; 32],
+    signers: Vec<[u8; 32]>,
+    threshold: usize,
+) -> Result<RoastSession> {
+    RoastSession::start_signing(beacon_message(epoch, prev_beacon), signers, threshold)
+}
+
+/// Wrap the signature a beacon session produced.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::Beacon`] if the signature is not 64 bytes
+pub fn from_signature(epoch: u32, prev_beacon: &[u8; 32], signature: &[u8]) -> Result<EpochBeacon> {
+    let quorum_sig: [u8; 64] = signature
+        .try_into()
+        .map_err(|_| FrostCoordError::Beacon("beacon signature must be 64 bytes".to_string()))?;
+    Ok(EpochBeacon {
+        epoch,
+        prev_beacon: *prev_beacon,
+        quorum_sig,
+    })
+}
+
+/// Check a beacon's signature under the group key in force for its epoch
+/// and return its value. Chaining is checked by [`BeaconCache`].
+///
+/// # Errors
+///
+/// - [`FrostCoordError::KeySchedule`] if the signature does not verify
+pub fn verify_beacon(group_pk: &[u8; 32], beacon: &EpochBeacon) -> Result<[u8; 32]> {
+    verify_group_signature(
+        group_pk,
+        &beacon_message(beacon.epoch, &beacon.prev_beacon),
+        &beacon.quorum_sig,
+    )?;
+    Ok(beacon_value(beacon))
+}
+
+/// Recently verified beacons, checked against each other as they arrive.
+#[derive(Clone, Debug)]
+pub struct BeaconCache {
+    beacons: BTreeMap<u32, (EpochBeacon, [u8; 32])>,
+    capacity: usize,
+}
+
+impl Default for BeaconCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_EPOCHS)
+    }
+}
+
+impl BeaconCache {
+    /// Create a cache keeping the latest `capacity` epochs.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            beacons: BTreeMap::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Verify `beacon` and add it. Returns `false` if it is already cached.
+    ///
+    /// A beacon must chain to the cached value of the previous epoch, and
+    /// the cached beacon of the next epoch must chain to it. A gap in the
+    /// cache is accepted on the signature alone.
+    ///
+    /// # Errors
+    ///
+    /// - [`FrostCoordError::KeySchedule`] if the signature does not verify
+    /// - [`FrostCoordError::Beacon`] if the epoch already has a different
+    ///   beacon or the beacon does not chain to its neighbours
+    pub fn insert(&mut self, group_pk: &[u8; 32], beacon: EpochBeacon) -> Result<bool> {
+        let value = verify_beacon(group_pk, &beacon)?;
+        if let Some((cached, _)) = self.beacons.get(&beacon.epoch) {
+            if *cached == beacon {
+                return Ok(false);
+            }
+            return Err(FrostCoordError::Beacon(format!(
+                "conflicting beacons for epoch {}",
+                beacon.epoch
+            )));
+        }
+        let prev = beacon.epoch.checked_sub(1).and_then(|e| self.value(e));
+        if prev.is_some_and(|prev| prev != beacon.prev_beacon) {
+            return Err(FrostCoordError::Beacon(format!(
+                "beacon for epoch {} does not chain to epoch {}",
+                beacon.epoch,
+                beacon.epoch - 1
+            )));
+        }
+        let next = beacon
+            .epoch
+            .checked_add(1)
+            .and_then(|e| self.beacons.get(&e));
+        if next.is_some_and(|(next, _)| next.prev_beacon != value) {
+            return Err(FrostCoordError::Beacon(format!(
+                "epoch {} does not chain to beacon for epoch {}",
+                beacon.epoch + 1,
+                beacon.epoch
+            )));
+        }
+        self.beacons.insert(beacon.epoch, (beacon, value));
+        while self.beacons.len() > self.capacity {
+            self.beacons.pop_first();
+        }
+        Ok(true)
+    }
+
+    /// The beacon value of `epoch`, if cached.
+    pub fn value(&self, epoch: u32) -> Option<[u8; 32]> {
+        self.beacons.get(&epoch).map(|(_, value)| *value)
+    }
+
+    /// The cached beacon of `epoch`.
+    pub fn get(&self, epoch: u32) -> Option<&EpochBeacon> {
+        self.beacons.get(&epoch).map(|(beacon, _)| beacon)
+    }
+
+    /// The newest cached beacon and its value.
+    pub fn latest(&self) -> Option<(&EpochBeacon, [u8; 32])> {
+        self.beacons
+            .last_key_value()
+            .map(|(_, (beacon, value))| (beacon, *value))
+    }
+
+    /// A seed for `purpose` from `epoch`'s beacon, if cached.
+    pub fn seed(&self, epoch: u32, purpose: &[u8], index: u64) -> Option<[u8; 32]> {
+        self.value(epoch)
+            .map(|value| derive_seed(&value, purpose, index))
+    }
+
+    /// Number of cached epochs.
+    pub fn len(&self) -> usize {
+        self.beacons.len()
+    }
+
+    /// Whether no beacon is cached.
+    pub fn is_empty(&self) -> bool {
+        self.beacons.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ochra_crypto::ed25519::SigningKey;
+
+    use super::*;
+
+    /// Sign a beacon chain with a stand-in group key; FROST(Ed25519)
+    /// aggregates are plain Ed25519 signatures.
+    fn chain(key: &SigningKey, from: u32, count: u32) -> Vec<EpochBeacon> {
+        let mut prev = GENESIS_BEACON;
+        (from..from + count)
+            .map(|epoch| {
+                let sig = key.sign(&beacon_message(epoch, &prev)).to_bytes();
+                let beacon = from_signature(epoch, &prev, &sig).expect("beacon");
+                prev = beacon_value(&beacon);
+                beacon
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chain_verifies_and_caches() {
+        let key = SigningKey::generate();
+        let pk = key.verifying_key().to_bytes();
+        let beacons = chain(&key, 10, 4);
+
+        let mut cache = BeaconCache::default();
+        for beacon in &beacons {
+            assert!(cache.insert(&pk, beacon.clone()).expect("insert"));
+        }
+        assert!(!cache.insert(&pk, beacons[1].clone()).expect("repeat"));
+        assert_eq!(cache.len(), 4);
+
+        let (latest, value) = cache.latest().expect("latest");
+        assert_eq!(latest.epoch, 13);
+        assert_eq!(value, beacon_value(&beacons[3]));
+        assert_eq!(cache.value(12), Some(beacons[3].prev_beacon));
+    }
+
+    #[test]
+    fn test_forged_or_unchained_beacons_rejected() {
+        let key = SigningKey::generate();
+        let pk = key.verifying_key().to_bytes();
+        let beacons = chain(&key, 1, 3);
+
+        let mut cache = BeaconCache::default();
+        cache.insert(&pk, beacons[0].clone()).expect("first");
+        let other = SigningKey::generate();
+        assert!(cache
+            .insert(&other.verifying_key().to_bytes(), beacons[1].clone())
+            .is_err());
+
+        // Validly signed, but chained to the wrong previous value.
+        let wrong_prev = [7u8; 32];
+        let sig = key.sign(&beacon_message(2, &wrong_prev)).to_bytes();
+        let unchained = from_signature(2, &wrong_prev, &sig).expect("beacon");
+        assert!(matches!(
+            cache.insert(&pk, unchained),
+            Err(FrostCoordError::Beacon(_))
+        ));
+
+        // A gap is accepted on the signature; the filled gap must then fit.
+        cache.insert(&pk, beacons[2].clone()).expect("after gap");
+        cache.insert(&pk, beacons[1].clone()).expect("fills gap");
+    }
+
+    /// Sign with a 3-of-5 FROST quorum using the first three members.
+    fn frost_sign(
+        members: &[ochra_crypto::frost::FrostKeyPackage],
+        package: &ochra_crypto::frost::FrostPublicKeyPackage,
+        message: &[u8],
+    ) -> [u8; 64] {
+        use ochra_crypto::frost::{aggregate, round1, round2};
+
+        let mut commitments = std::collections::BTreeMap::new();
+        let mut nonces = Vec::new();
+        for member in &members[..3] {
+            let (n, c) = round1(member).expect("round1");
+            commitments.insert(c.identifier, c.commitments);
+            nonces.push(n);
+        }
+        let signing_package = frost_ed25519::SigningPackage::new(commitments, message);
+        let shares = members[..3]
+            .iter()
+            .zip(&nonces)
+            .map(|(member, n)| {
+                let share = round2(member, n, &signing_package).expect("round2");
+                (share.identifier, share.share)
+            })
+            .collect();
+        let sig = aggregate(&signing_package, &shares, package).expect("aggregate");
+        sig.inner
+            .serialize()
+            .expect("serialize")
+            .try_into()
+            .expect("64 bytes")
+    }
+
+    #[test]
+    fn test_second_signature_for_an_epoch_is_a_conflict() {
+        let (members, package) = ochra_crypto::frost::dkg(5, 3).expect("dkg");
+        let keys = crate::timelock::QuorumKeys::from_package(&package, 3).expect("keys");
+        let msg = beacon_message(5, &GENESIS_BEACON);
+
+        // Fresh nonces give a second valid signature for the same epoch.
+        let first = from_signature(5, &GENESIS_BEACON, &frost_sign(&members, &package, &msg))
+            .expect("beacon");
+        let second = from_signature(5, &GENESIS_BEACON, &frost_sign(&members, &package, &msg))
+            .expect("beacon");
+        assert_ne!(beacon_value(&first), beacon_value(&second));
+        assert!(verify_beacon(&keys.group_key, &second).is_ok());
+
+        let mut cache = BeaconCache::default();
+        assert!(cache.insert(&keys.group_key, first.clone()).expect("first"));
+        assert!(matches!(
+            cache.insert(&keys.group_key, second),
+            Err(FrostCoordError::Beacon(_))
+        ));
+        assert_eq!(cache.value(5), Some(beacon_value(&first)));
+    }
+
+    #[test]
+    fn test_seeds_are_separated_by_purpose_and_index() {
+        let value = [3u8; 32];
+        let sortition = derive_seed(&value, PURPOSE_SORTITION, 0);
+        assert_ne!(sortition, derive_seed(&value, PURPOSE_POR_CHALLENGE, 0));
+        assert_ne!(
+            derive_seed(&value, PURPOSE_RELAY_EPOCH, 1),
+            derive_seed(&value, PURPOSE_RELAY_EPOCH, 2)
+        );
+        assert_ne!(sortition, derive_seed(&[4u8; 32], PURPOSE_SORTITION, 0));
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let key = SigningKey::generate();
+        let pk = key.verifying_key().to_bytes();
+        let mut cache = BeaconCache::new(2);
+        for beacon in chain(&key, 1, 5) {
+            cache.insert(&pk, beacon).expect("insert");
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(3).is_none());
+        assert!(cache.seed(5, PURPOSE_UPTIME, 0).is_some());
+    }
+}
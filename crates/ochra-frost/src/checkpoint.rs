@@ -0,0 +1,171 @@
+//! Epoch state checkpoints.
+//!
+//! After each epoch's `EpochState` the quorum signs a [`StateCheckpoint`]
+//! committing to the nullifier Bloom filter, the VYS accumulator, the
+//! encoded `EpochState`, and the latest relay directory snapshot. A relay
+//! joining mid-epoch fetches the checkpoint and its components from any
+//! peers, checks each component against the checkpoint, and starts from
+//! that state instead of rebuilding it from gossip.
+//!
+//! The checkpoint is only as fresh as its epoch; gossip received since
+//! then is applied on top as usual.
+
+use ochra_types::network::StateCheckpoint;
+
+use crate::quorum::verify_group_signature;
+use crate::roast::RoastSession;
+use crate::{FrostCoordError, Result};
+
+/// Message the quorum signs for `checkpoint`; `quorum_sig` is ignored.
+///
+/// `"state-checkpoint" || LE32(epoch) || nullifier_bloom_hash ||
+/// LE64(nullifier_count) || vys_accumulator_root || epoch_state_hash ||
+/// LE32(directory_epoch) || directory_digest`
+pub fn checkpoint_message(checkpoint: &StateCheckpoint) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(16 + 4 + 32 + 8 + 32 + 32 + 4 + 32);
+    msg.extend_from_slice(b"state-checkpoint");
+    msg.extend_from_slice(&checkpoint.epoch.to_le_bytes());
+    msg.extend_from_slice(&checkpoint.nullifier_bloom_hash);
+    msg.extend_from_slice(&checkpoint.nullifier_count.to_le_bytes());
+    msg.extend_from_slice(&checkpoint.vys_accumulator_root);
+    msg.extend_from_slice(&checkpoint.epoch_state_hash);
+    msg.extend_from_slice(&checkpoint.directory_epoch.to_le_bytes());
+    msg.extend_from_slice(&checkpoint.directory_digest);
+    msg
+}
+
+/// Start the quorum's signing session for `checkpoint`.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::InsufficientSigners`] if fewer than `threshold`
+///   signers are eligible
+pub fn start_session(
+    checkpoint: &StateCheckpoint,
+    signers: Vec<[u8; 32]>,
+    threshold: usize,
+) -> Result<RoastSession> {
+    RoastSession::start_signing(checkpoint_message(checkpoint), signers, threshold)
+}
+
+/// Attach the signature a checkpoint session produced.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::Checkpoint`] if the signature is not 64 bytes
+pub fn with_signature(checkpoint: StateCheckpoint, signature: &[u8]) -> Result<StateCheckpoint> {
+    let quorum_sig: [u8; 64] = signature.try_into().map_err(|_| {
+        FrostCoordError::Checkpoint("checkpoint signature must be 64 bytes".to_string())
+    })?;
+    Ok(StateCheckpoint {
+        quorum_sig,
+        ..checkpoint
+    })
+}
+
+/// Check a checkpoint's signature under the group key in force for its
+/// epoch.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::KeySchedule`] if the signature does not verify
+pub fn verify_checkpoint(group_pk: &[u8; 32], checkpoint: &StateCheckpoint) -> Result<()> {
+    verify_group_signature(
+        group_pk,
+        &checkpoint_message(checkpoint),
+        &checkpoint.quorum_sig,
+    )
+}
+
+/// Check that the nullifier filter bytes match the checkpoint.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::Checkpoint`] if the hash differs
+pub fn check_nullifiers(checkpoint: &StateCheckpoint, bloom: &[u8]) -> Result<()> {
+    check_hash("nullifier filter", &checkpoint.nullifier_bloom_hash, bloom)
+}
+
+/// Check that the encoded `EpochState` matches the checkpoint.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::Checkpoint`] if the hash differs
+pub fn check_epoch_state(checkpoint: &StateCheckpoint, encoded: &[u8]) -> Result<()> {
+    check_hash("epoch state", &checkpoint.epoch_state_hash, encoded)
+}
+
+fn check_hash(what: &str, expected: &[u8; 32], data: &[u8]) -> Result<()> {
+    if ochra_crypto::blake3::hash(data) != *expected {
+        return Err(FrostCoordError::Checkpoint(format!(
+            "{what} does not match the checkpoint"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ochra_crypto::ed25519::SigningKey;
+
+    use super::*;
+
+    fn unsigned() -> StateCheckpoint {
+        StateCheckpoint {
+            epoch: 42,
+            nullifier_bloom_hash: ochra_crypto::blake3::hash(b"bloom"),
+            nullifier_count: 7,
+            vys_accumulator_root: [2; 32],
+            epoch_state_hash: ochra_crypto::blake3::hash(b"state"),
+            directory_epoch: 1000,
+            directory_digest: [3; 32],
+            quorum_sig: [0; 64],
+        }
+    }
+
+    #[test]
+    fn test_signed_checkpoint_verifies() {
+        let key = SigningKey::generate();
+        let pk = key.verifying_key().to_bytes();
+        let checkpoint = unsigned();
+        let sig = key.sign(&checkpoint_message(&checkpoint)).to_bytes();
+        let checkpoint = with_signature(checkpoint, &sig).expect("signed");
+        assert!(verify_checkpoint(&pk, &checkpoint).is_ok());
+
+        let tampered = StateCheckpoint {
+            nullifier_count: 8,
+            ..checkpoint.clone()
+        };
+        assert!(verify_checkpoint(&pk, &tampered).is_err());
+        let other = SigningKey::generate().verifying_key().to_bytes();
+        assert!(verify_checkpoint(&other, &checkpoint).is_err());
+        assert!(with_signature(unsigned(), &[0; 10]).is_err());
+    }
+
+    #[test]
+    fn test_components_checked_against_hashes() {
+        let checkpoint = unsigned();
+        assert!(check_nullifiers(&checkpoint, b"bloom").is_ok());
+        assert!(matches!(
+            check_nullifiers(&checkpoint, b"bloom!"),
+            Err(FrostCoordError::Checkpoint(_))
+        ));
+        assert!(check_epoch_state(&checkpoint, b"state").is_ok());
+        assert!(check_epoch_state(&checkpoint, b"bloom").is_err());
+    }
+
+    #[test]
+    fn test_signature_excluded_from_message() {
+        let checkpoint = unsigned();
+        let signed = StateCheckpoint {
+            quorum_sig: [9; 64],
+            ..checkpoint.clone()
+        };
+        assert_eq!(checkpoint_message(&checkpoint), checkpoint_message(&signed));
+        let later = StateCheckpoint {
+            epoch: 43,
+            ..checkpoint.clone()
+        };
+        assert_ne!(checkpoint_message(&checkpoint), checkpoint_message(&later));
+    }
+}
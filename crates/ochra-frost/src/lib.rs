@@ -9,10 +9,13 @@
 //!
 //! ## Modules
 //!
+//! - [`beacon`] — Quorum-signed per-epoch randomness beacon.
+//! - [`checkpoint`] — Quorum-signed epoch state checkpoints for state sync.
 //! - [`dkg`] — DKG ceremony coordination with multi-round state machine.
 //! - [`roast`] — ROAST wrapper for async liveness in signing.
-//! - [`quorum`] — Quorum membership management and selection.
+//! - [`quorum`] — Quorum membership management, selection, and key schedule.
 //! - [`reshare`] — Proactive secret resharing between quorums.
+//! - [`timelock`] — Threshold release of time-locked values.
 //!
 //! ## ROAST (Robust Asynchronous Schnorr Threshold)
 //!
@@ -20,10 +23,13 @@
 //! multiple concurrent signing sessions and selecting the first t-of-n
 //! signers that respond.
 
+pub mod beacon;
+pub mod checkpoint;
 pub mod dkg;
 pub mod quorum;
 pub mod reshare;
 pub mod roast;
+pub mod timelock;
 
 /// Default timeout for a signing round in seconds.
 pub const ROUND_TIMEOUT_SECS: u64 = 30;
@@ -80,6 +86,27 @@ pub enum FrostCoordError {
     /// Resharing error.
     #[error("reshare error: {0}")]
     Reshare(String),
+
+    /// A time-locked value's target epoch has not begun.
+    #[error("not releasable until epoch {target_epoch} (now {current_epoch})")]
+    NotYetReleasable {
+        /// Epoch the value is sealed to.
+        target_epoch: u64,
+        /// Current epoch.
+        current_epoch: u64,
+    },
+
+    /// Key schedule entry is malformed or not endorsed by the prior key.
+    #[error("key schedule error: {0}")]
+    KeySchedule(String),
+
+    /// An epoch beacon conflicts with or does not chain to a known beacon.
+    #[error("beacon error: {0}")]
+    Beacon(String),
+
+    /// A state checkpoint component does not match the checkpoint.
+    #[error("checkpoint error: {0}")]
+    Checkpoint(String),
 }
 
 /// Convenience result type for FROST coordination.
@@ -4,7 +4,17 @@
 //! threshold signing ceremonies. Quorums are selected from eligible
 //! nodes based on PoSrv scores, and churn is limited per epoch to
 //! maintain key continuity.
-
+//!
+//! ## Key Schedule
+//!
+//! Every DKG or reshare that changes the group key, threshold, or quorum
+//! size adds a [`QuorumKeyEntry`] to the [`KeySchedule`]. The outgoing
+//! group key endorses the new entry with a FROST signature over
+//! [`key_entry_message`], so an auditor holding the genesis key can walk
+//! the chain and find the key that signed any historical quorum output.
+
+use ochra_crypto::ed25519::{Signature, VerifyingKey};
+use ochra_types::network::QuorumKeyEntry;
 use serde::{Deserialize, Serialize};
 
 use crate::{FrostCoordError, Result};
@@ -157,6 +167,179 @@ pub fn compute_churn(current: &QuorumConfig, proposed: &[[u8; 32]]) -> (usize, u
     (added, removed)
 }
 
+/// Message the previous group key signs to endorse `entry`.
+///
+/// `"quorum-key-schedule" || activated_epoch (u32 BE) || group_pk ||
+/// threshold (u16 BE) || quorum_size (u16 BE)`
+pub fn key_entry_message(entry: &QuorumKeyEntry) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(19 + 4 + 32 + 4);
+    msg.extend_from_slice(b"quorum-key-schedule");
+    msg.extend_from_slice(&entry.activated_epoch.to_be_bytes());
+    msg.extend_from_slice(&entry.group_pk);
+    msg.extend_from_slice(&entry.threshold.to_be_bytes());
+    msg.extend_from_slice(&entry.quorum_size.to_be_bytes());
+    msg
+}
+
+/// Verify a FROST aggregate signature under a group key.
+///
+/// FROST(Ed25519) signatures are plain Ed25519 signatures under the group
+/// verifying key.
+pub fn verify_group_signature(group_pk: &[u8; 32], message: &[u8], sig: &[u8; 64]) -> Result<()> {
+    let vk = VerifyingKey::from_bytes(group_pk)
+        .map_err(|e| FrostCoordError::KeySchedule(format!("invalid group key: {e}")))?;
+    vk.verify(message, &Signature::from_bytes(sig))
+        .map_err(|_| FrostCoordError::KeySchedule("group signature does not verify".to_string()))
+}
+
+/// Signed history of quorum group keys, ordered by activation epoch.
+///
+/// A deserialized schedule has its chain checked from the genesis entry it
+/// carries; callers compare [`KeySchedule::genesis`] against their trust
+/// anchor.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "RawKeySchedule", into = "RawKeySchedule")]
+pub struct KeySchedule {
+    entries: Vec<QuorumKeyEntry>,
+}
+
+/// Wire form of a [`KeySchedule`], before its chain is checked.
+#[derive(Serialize, Deserialize)]
+struct RawKeySchedule {
+    entries: Vec<QuorumKeyEntry>,
+}
+
+impl TryFrom<RawKeySchedule> for KeySchedule {
+    type Error = FrostCoordError;
+
+    fn try_from(raw: RawKeySchedule) -> Result<Self> {
+        let anchor = raw
+            .entries
+            .first()
+            .map(|genesis| genesis.group_pk)
+            .ok_or_else(|| FrostCoordError::KeySchedule("empty key schedule".to_string()))?;
+        Self::from_entries(raw.entries, &anchor)
+    }
+}
+
+impl From<KeySchedule> for RawKeySchedule {
+    fn from(schedule: KeySchedule) -> Self {
+        Self {
+            entries: schedule.entries,
+        }
+    }
+}
+
+impl KeySchedule {
+    /// Start a schedule from the genesis entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `genesis` - First entry, endorsed by its own group key.
+    /// * `anchor_pk` - Genesis group key the caller already trusts.
+    pub fn new(genesis: QuorumKeyEntry, anchor_pk: &[u8; 32]) -> Result<Self> {
+        if &genesis.group_pk != anchor_pk {
+            return Err(FrostCoordError::KeySchedule(
+                "genesis key does not match the trust anchor".to_string(),
+            ));
+        }
+        check_entry_shape(&genesis)?;
+        verify_group_signature(
+            &genesis.group_pk,
+            &key_entry_message(&genesis),
+            &genesis.endorsement,
+        )?;
+        Ok(Self {
+            entries: vec![genesis],
+        })
+    }
+
+    /// Rebuild and verify an exported schedule.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - Entries in activation order, genesis first.
+    /// * `anchor_pk` - Genesis group key the caller already trusts.
+    pub fn from_entries(entries: Vec<QuorumKeyEntry>, anchor_pk: &[u8; 32]) -> Result<Self> {
+        let mut entries = entries.into_iter();
+        let genesis = entries
+            .next()
+            .ok_or_else(|| FrostCoordError::KeySchedule("empty key schedule".to_string()))?;
+        let mut schedule = Self::new(genesis, anchor_pk)?;
+        for entry in entries {
+            schedule.append(entry)?;
+        }
+        Ok(schedule)
+    }
+
+    /// Append an entry endorsed by the current group key.
+    pub fn append(&mut self, entry: QuorumKeyEntry) -> Result<()> {
+        let last = self
+            .latest()
+            .ok_or_else(|| FrostCoordError::KeySchedule("empty key schedule".to_string()))?;
+        if entry.activated_epoch <= last.activated_epoch {
+            return Err(FrostCoordError::KeySchedule(format!(
+                "entry for epoch {} does not follow epoch {}",
+                entry.activated_epoch, last.activated_epoch
+            )));
+        }
+        check_entry_shape(&entry)?;
+        verify_group_signature(
+            &last.group_pk,
+            &key_entry_message(&entry),
+            &entry.endorsement,
+        )?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// The genesis entry.
+    pub fn genesis(&self) -> Option<&QuorumKeyEntry> {
+        self.entries.first()
+    }
+
+    /// The most recent entry.
+    pub fn latest(&self) -> Option<&QuorumKeyEntry> {
+        self.entries.last()
+    }
+
+    /// The entry in force during `epoch`.
+    pub fn entry_for(&self, epoch: u32) -> Result<&QuorumKeyEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|e| e.activated_epoch <= epoch)
+            .ok_or_else(|| {
+                FrostCoordError::KeySchedule(format!("epoch {epoch} precedes the key schedule"))
+            })
+    }
+
+    /// Group key that signed quorum outputs in `epoch`.
+    pub fn verifying_key_for(&self, epoch: u32) -> Result<[u8; 32]> {
+        self.entry_for(epoch).map(|e| e.group_pk)
+    }
+
+    /// Verify a FROST aggregate signature produced during `epoch`.
+    pub fn verify(&self, epoch: u32, message: &[u8], sig: &[u8; 64]) -> Result<()> {
+        verify_group_signature(&self.verifying_key_for(epoch)?, message, sig)
+    }
+
+    /// All entries, genesis first, for export to auditors.
+    pub fn entries(&self) -> &[QuorumKeyEntry] {
+        &self.entries
+    }
+}
+
+fn check_entry_shape(entry: &QuorumKeyEntry) -> Result<()> {
+    if entry.threshold == 0 || entry.threshold > entry.quorum_size {
+        return Err(FrostCoordError::KeySchedule(format!(
+            "invalid threshold {} for {} members",
+            entry.threshold, entry.quorum_size
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,6 +348,97 @@ mod tests {
         [id; 32]
     }
 
+    fn endorsed(
+        signer: &ochra_crypto::ed25519::SigningKey,
+        epoch: u32,
+        group_pk: [u8; 32],
+    ) -> QuorumKeyEntry {
+        let mut entry = QuorumKeyEntry {
+            activated_epoch: epoch,
+            group_pk,
+            threshold: 67,
+            quorum_size: 100,
+            endorsement: [0u8; 64],
+        };
+        entry.endorsement = signer.sign(&key_entry_message(&entry)).to_bytes();
+        entry
+    }
+
+    #[test]
+    fn test_key_schedule_lookup_by_epoch() {
+        use ochra_crypto::ed25519::SigningKey;
+
+        let k0 = SigningKey::generate();
+        let k1 = SigningKey::generate();
+        let pk0 = k0.verifying_key().to_bytes();
+        let pk1 = k1.verifying_key().to_bytes();
+
+        let mut schedule = KeySchedule::new(endorsed(&k0, 10, pk0), &pk0).expect("genesis");
+        schedule.append(endorsed(&k0, 20, pk1)).expect("append");
+
+        assert!(schedule.verifying_key_for(9).is_err());
+        assert_eq!(schedule.verifying_key_for(10).expect("key"), pk0);
+        assert_eq!(schedule.verifying_key_for(19).expect("key"), pk0);
+        assert_eq!(schedule.verifying_key_for(25).expect("key"), pk1);
+
+        let output = b"epoch state 21";
+        let sig = k1.sign(output).to_bytes();
+        assert!(schedule.verify(21, output, &sig).is_ok());
+        assert!(schedule.verify(15, output, &sig).is_err());
+    }
+
+    #[test]
+    fn test_key_schedule_rejects_broken_chain() {
+        use ochra_crypto::ed25519::SigningKey;
+
+        let k0 = SigningKey::generate();
+        let k1 = SigningKey::generate();
+        let rogue = SigningKey::generate();
+        let pk0 = k0.verifying_key().to_bytes();
+        let pk1 = k1.verifying_key().to_bytes();
+
+        assert!(KeySchedule::new(endorsed(&k0, 0, pk0), &pk1).is_err());
+
+        let mut schedule = KeySchedule::new(endorsed(&k0, 0, pk0), &pk0).expect("genesis");
+        // Not endorsed by the outgoing key
+        assert!(schedule.append(endorsed(&rogue, 5, pk1)).is_err());
+        // Epochs must increase
+        assert!(schedule.append(endorsed(&k0, 0, pk1)).is_err());
+        schedule.append(endorsed(&k0, 5, pk1)).expect("append");
+
+        // An exported schedule round-trips through verification
+        let exported = schedule.entries().to_vec();
+        let imported = KeySchedule::from_entries(exported.clone(), &pk0).expect("import");
+        assert_eq!(imported.entries(), exported.as_slice());
+        let mut tampered = exported;
+        tampered[1].threshold = 1;
+        assert!(KeySchedule::from_entries(tampered, &pk0).is_err());
+    }
+
+    #[test]
+    fn test_key_schedule_deserialize_checks_chain() {
+        use ochra_crypto::ed25519::SigningKey;
+
+        let k0 = SigningKey::generate();
+        let rogue = SigningKey::generate();
+        let pk0 = k0.verifying_key().to_bytes();
+        let pk1 = SigningKey::generate().verifying_key().to_bytes();
+
+        let mut schedule = KeySchedule::new(endorsed(&k0, 0, pk0), &pk0).expect("genesis");
+        schedule.append(endorsed(&k0, 5, pk1)).expect("append");
+        let json = serde_json::to_string(&schedule).expect("serialize");
+        let restored: KeySchedule = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(restored.entries(), schedule.entries());
+        assert_eq!(restored.latest().map(|e| e.group_pk), Some(pk1));
+        assert_eq!(restored.genesis().map(|e| e.group_pk), Some(pk0));
+
+        assert!(serde_json::from_str::<KeySchedule>(r#"{"entries":[]}"#).is_err());
+        let mut forged = schedule.entries().to_vec();
+        forged[1] = endorsed(&rogue, 5, pk1);
+        let json = serde_json::json!({ "entries": forged }).to_string();
+        assert!(serde_json::from_str::<KeySchedule>(&json).is_err());
+    }
+
     #[test]
     fn test_quorum_config_creation() {
         let members = vec![node(1), node(2), node(3)];
@@ -13,12 +13,18 @@
 //! 2. If a signer fails to respond, they are removed from the responsive set.
 //! 3. A new session is started with a different subset.
 //! 4. The first session to collect t valid shares produces the signature.
+//!
+//! Each round of an attempt times out after [`ROUND_TIMEOUT_SECS`] on the
+//! session's [`SimEnv`] clock; [`RoastSession::expire_stalled`] fails stalled
+//! attempts and marks signers that withheld their shares as non-responsive.
 
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
+use ochra_sim::SimEnv;
 use serde::{Deserialize, Serialize};
 
-use crate::{FrostCoordError, Result, MAX_ROAST_SESSIONS};
+use crate::{FrostCoordError, Result, MAX_ROAST_SESSIONS, ROUND_TIMEOUT_SECS};
 
 /// A signature share from a participant.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -53,6 +59,8 @@ struct SigningAttempt {
     shares: HashMap<[u8; 32], SignatureShare>,
     /// Current state.
     state: SessionState,
+    /// When the current round started, on the session clock.
+    round_started: Duration,
 }
 
 /// ROAST session for coordinating asynchronous threshold signing.
@@ -72,6 +80,10 @@ pub struct RoastSession {
     attempts: Vec<SigningAttempt>,
     /// The final aggregated signature (if any attempt completed).
     final_signature: Option<Vec<u8>>,
+    /// Clock for round timeouts.
+    env: SimEnv,
+    /// How long a round may take before the attempt fails.
+    round_timeout: Duration,
 }
 
 impl RoastSession {
@@ -109,9 +121,23 @@ impl RoastSession {
             responsive_signers: signer_set,
             attempts: Vec::new(),
             final_signature: None,
+            env: SimEnv::real(),
+            round_timeout: Duration::from_secs(ROUND_TIMEOUT_SECS),
         })
     }
 
+    /// Time rounds on `env` (e.g. a simulated clock).
+    pub fn with_env(mut self, env: SimEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Override the round timeout.
+    pub fn with_round_timeout(mut self, timeout: Duration) -> Self {
+        self.round_timeout = timeout;
+        self
+    }
+
     /// Create a new signing attempt with the current responsive signers.
     ///
     /// Returns the index of the new attempt, or an error if the maximum
@@ -137,6 +163,7 @@ impl RoastSession {
             participants: self.responsive_signers.clone(),
             shares: HashMap::new(),
             state: SessionState::CollectingCommitments,
+            round_started: self.env.now(),
         });
 
         tracing::debug!(
@@ -166,6 +193,7 @@ impl RoastSession {
         }
 
         attempt.state = SessionState::CollectingShares;
+        attempt.round_started = self.env.now();
         Ok(())
     }
 
@@ -246,6 +274,54 @@ impl RoastSession {
         );
     }
 
+    /// Fail every attempt whose current round has outlived the timeout.
+    ///
+    /// Participants of a stalled share round that never sent a share are
+    /// marked non-responsive, so the next attempt excludes them. A stalled
+    /// commitment round is failed without blame, since commitments are
+    /// tracked by the caller. Returns the signers newly marked
+    /// non-responsive, sorted.
+    pub fn expire_stalled(&mut self) -> Vec<[u8; 32]> {
+        if self.final_signature.is_some() {
+            return Vec::new();
+        }
+        let now = self.env.now();
+        let mut silent = HashSet::new();
+        for attempt in &mut self.attempts {
+            let active = matches!(
+                attempt.state,
+                SessionState::CollectingCommitments | SessionState::CollectingShares
+            );
+            if !active || now.saturating_sub(attempt.round_started) <= self.round_timeout {
+                continue;
+            }
+            if attempt.state == SessionState::CollectingShares {
+                silent.extend(
+                    attempt
+                        .participants
+                        .iter()
+                        .filter(|p| !attempt.shares.contains_key(*p))
+                        .copied(),
+                );
+            }
+            attempt.state = SessionState::Failed;
+        }
+        let mut newly: Vec<[u8; 32]> = silent
+            .into_iter()
+            .filter(|s| self.responsive_signers.contains(s))
+            .collect();
+        newly.sort_unstable();
+        for signer in &newly {
+            self.mark_non_responsive(signer);
+        }
+        newly
+    }
+
+    /// State of an attempt, if it exists.
+    pub fn attempt_state(&self, attempt_index: usize) -> Option<&SessionState> {
+        self.attempts.get(attempt_index).map(|a| &a.state)
+    }
+
     /// Check if the session has completed (produced a signature).
     pub fn is_completed(&self) -> bool {
         self.final_signature.is_some()
@@ -433,4 +509,46 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_stalled_share_round_expires() {
+        let clock = ochra_sim::VirtualClock::new(0);
+        let mut session = RoastSession::start_signing(b"test".to_vec(), make_signers(4), 2)
+            .expect("start")
+            .with_env(SimEnv::simulated(1, clock.clone()));
+        let idx = session.new_attempt().expect("attempt");
+        session.advance_to_shares(idx).expect("advance");
+        let share = SignatureShare {
+            participant_id: node(1),
+            share: vec![1; 32],
+        };
+        session.receive_share(node(1), share).expect("share");
+
+        clock.advance(Duration::from_secs(ROUND_TIMEOUT_SECS));
+        assert!(
+            session.expire_stalled().is_empty(),
+            "Still within the round"
+        );
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(session.expire_stalled(), vec![node(2), node(3), node(4)]);
+        assert_eq!(session.attempt_state(idx), Some(&SessionState::Failed));
+        assert_eq!(session.responsive_count(), 1);
+        assert!(session.new_attempt().is_err());
+    }
+
+    #[test]
+    fn test_stalled_commitment_round_fails_without_blame() {
+        let clock = ochra_sim::VirtualClock::new(0);
+        let mut session = RoastSession::start_signing(b"test".to_vec(), make_signers(3), 2)
+            .expect("start")
+            .with_env(SimEnv::simulated(1, clock.clone()))
+            .with_round_timeout(Duration::from_secs(5));
+        let idx = session.new_attempt().expect("attempt");
+
+        clock.advance(Duration::from_secs(6));
+        assert!(session.expire_stalled().is_empty());
+        assert_eq!(session.attempt_state(idx), Some(&SessionState::Failed));
+        assert_eq!(session.responsive_count(), 3);
+    }
 }
@@ -0,0 +1,356 @@
+//! Time-lock release coordination.
+//!
+//! Values sealed with [`ochra_crypto::timelock`] open once the quorum
+//! releases the key for their capsule. A member contributes its share only
+//! after the value's target epoch has begun, and only for a capsule whose
+//! binding proves it was sealed for that epoch ([`release_share`]), so a
+//! request cannot lower the epoch of someone else's capsule. A
+//! [`ReleaseSession`] checks each incoming share against the member's
+//! verifying share and completes once `t` are in.
+//!
+//! The resulting [`TimeLockRelease`] carries the shares with their proofs,
+//! so consumers check it with [`verify_release`] instead of trusting
+//! whoever published it.
+
+use std::collections::BTreeMap;
+
+use ochra_crypto::frost::{FrostKeyPackage, FrostPublicKeyPackage};
+use ochra_crypto::timelock::{self, DecryptionShare, Sealed};
+
+use crate::{FrostCoordError, Result};
+
+/// A request to release the key of one sealed value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReleaseRequest {
+    pub capsule: [u8; 32],
+    pub target_epoch: u64,
+    /// The sealer's proof binding the capsule to `target_epoch`.
+    pub binding: [u8; 64],
+}
+
+impl From<&Sealed> for ReleaseRequest {
+    fn from(sealed: &Sealed) -> Self {
+        Self {
+            capsule: sealed.capsule,
+            target_epoch: sealed.target_epoch,
+            binding: sealed.binding,
+        }
+    }
+}
+
+/// The public keys a release is checked against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuorumKeys {
+    pub group_key: [u8; 32],
+    /// Verifying share by FROST identifier.
+    pub verifying_shares: BTreeMap<[u8; 32], [u8; 32]>,
+    pub threshold: usize,
+}
+
+fn crypto(e: impl std::fmt::Display) -> FrostCoordError {
+    FrostCoordError::Crypto(e.to_string())
+}
+
+fn bytes32(bytes: Vec<u8>) -> Result<[u8; 32]> {
+    bytes
+        .try_into()
+        .map_err(|_| FrostCoordError::Crypto("expected a 32-byte encoding".into()))
+}
+
+impl QuorumKeys {
+    /// Extract the keys from a FROST public key package.
+    pub fn from_package(package: &FrostPublicKeyPackage, threshold: usize) -> Result<Self> {
+        let group_key = bytes32(package.inner.verifying_key().serialize().map_err(crypto)?)?;
+        let verifying_shares = package
+            .inner
+            .verifying_shares()
+            .iter()
+            .map(|(id, share)| {
+                Ok((
+                    bytes32(id.serialize())?,
+                    bytes32(share.serialize().map_err(crypto)?)?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            group_key,
+            verifying_shares,
+            threshold,
+        })
+    }
+
+    fn check_share(&self, capsule: &[u8; 32], share: &DecryptionShare) -> Result<()> {
+        let verifying_share = self
+            .verifying_shares
+            .get(&share.identifier)
+            .ok_or_else(|| FrostCoordError::UnknownSigner(hex::encode(share.identifier)))?;
+        timelock::verify_share(verifying_share, capsule, share).map_err(crypto)
+    }
+}
+
+/// This member's decryption share for `request`, refused before the
+/// target epoch.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::NotYetReleasable`] if `current_epoch` is before
+///   the target epoch
+/// - [`FrostCoordError::Crypto`] if the capsule is malformed or its binding
+///   does not prove it was sealed for the requested epoch
+pub fn release_share(
+    key_package: &FrostKeyPackage,
+    request: &ReleaseRequest,
+    current_epoch: u64,
+) -> Result<DecryptionShare> {
+    if current_epoch < request.target_epoch {
+        return Err(FrostCoordError::NotYetReleasable {
+            target_epoch: request.target_epoch,
+            current_epoch,
+        });
+    }
+    timelock::decryption_share(
+        key_package,
+        &request.capsule,
+        request.target_epoch,
+        &request.binding,
+    )
+    .map_err(crypto)
+}
+
+/// A completed release: enough verified shares to open the value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeLockRelease {
+    pub request: ReleaseRequest,
+    pub shares: Vec<DecryptionShare>,
+}
+
+/// Collects decryption shares for one request.
+pub struct ReleaseSession {
+    request: ReleaseRequest,
+    keys: QuorumKeys,
+    shares: BTreeMap<[u8; 32], DecryptionShare>,
+}
+
+impl ReleaseSession {
+    /// Start collecting shares for `request`.
+    pub fn new(request: ReleaseRequest, keys: QuorumKeys) -> Self {
+        Self {
+            request,
+            keys,
+            shares: BTreeMap::new(),
+        }
+    }
+
+    /// Add a member's share. Returns whether the threshold is now met.
+    ///
+    /// # Errors
+    ///
+    /// - [`FrostCoordError::UnknownSigner`] if the share is not from a
+    ///   quorum member
+    /// - [`FrostCoordError::DuplicateContribution`] if the member already
+    ///   contributed
+    /// - [`FrostCoordError::Crypto`] if the share's proof does not hold
+    pub fn add_share(&mut self, share: DecryptionShare) -> Result<bool> {
+        if self.shares.contains_key(&share.identifier) {
+            return Err(FrostCoordError::DuplicateContribution(hex::encode(
+                share.identifier,
+            )));
+        }
+        self.keys.check_share(&self.request.capsule, &share)?;
+        self.shares.insert(share.identifier, share);
+        Ok(self.is_complete())
+    }
+
+    /// Whether enough shares have been collected.
+    pub fn is_complete(&self) -> bool {
+        self.shares.len() >= self.keys.threshold
+    }
+
+    /// The release, once the threshold is met.
+    ///
+    /// # Errors
+    ///
+    /// - [`FrostCoordError::InsufficientSigners`] if too few shares are in
+    pub fn release(&self) -> Result<TimeLockRelease> {
+        if !self.is_complete() {
+            return Err(FrostCoordError::InsufficientSigners {
+                required: self.keys.threshold,
+                available: self.shares.len(),
+            });
+        }
+        Ok(TimeLockRelease {
+            request: self.request,
+            shares: self
+                .shares
+                .values()
+                .take(self.keys.threshold)
+                .cloned()
+                .collect(),
+        })
+    }
+}
+
+/// Check a published release and return the key that opens its value.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::InsufficientSigners`] if it has fewer than `t`
+///   distinct shares
+/// - [`FrostCoordError::UnknownSigner`] or [`FrostCoordError::Crypto`] if
+///   a share is not a valid share of a quorum member
+pub fn verify_release(keys: &QuorumKeys, release: &TimeLockRelease) -> Result<[u8; 32]> {
+    let mut seen = BTreeMap::new();
+    for share in &release.shares {
+        keys.check_share(&release.request.capsule, share)?;
+        seen.insert(share.identifier, share.clone());
+    }
+    if seen.len() < keys.threshold {
+        return Err(FrostCoordError::InsufficientSigners {
+            required: keys.threshold,
+            available: seen.len(),
+        });
+    }
+    let shares: Vec<DecryptionShare> = seen.into_values().collect();
+    timelock::combine(&shares).map_err(crypto)
+}
+
+/// Verify a release for `sealed` and decrypt it.
+///
+/// # Errors
+///
+/// - [`FrostCoordError::Crypto`] if the release is for another value or
+///   does not open it
+/// - any error from [`verify_release`]
+pub fn open(keys: &QuorumKeys, sealed: &Sealed, release: &TimeLockRelease) -> Result<Vec<u8>> {
+    if release.request != ReleaseRequest::from(sealed) {
+        return Err(FrostCoordError::Crypto(
+            "release is for another sealed value".into(),
+        ));
+    }
+    let key = verify_release(keys, release)?;
+    timelock::open(&keys.group_key, sealed, &key).map_err(crypto)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::frost::dkg;
+
+    fn quorum() -> (Vec<FrostKeyPackage>, QuorumKeys) {
+        let (members, package) = dkg(5, 3).expect("dkg");
+        let keys = QuorumKeys::from_package(&package, 3).expect("keys");
+        (members, keys)
+    }
+
+    #[test]
+    fn test_release_after_target_epoch_opens_value() {
+        let (members, keys) = quorum();
+        let sealed = timelock::seal(&keys.group_key, 12, b"recovery hint").expect("seal");
+        let request = ReleaseRequest::from(&sealed);
+
+        let mut session = ReleaseSession::new(request, keys.clone());
+        assert!(session.release().is_err());
+        let mut complete = false;
+        for member in &members[1..4] {
+            complete = session
+                .add_share(release_share(member, &request, 12).expect("share"))
+                .expect("valid share");
+        }
+        assert!(complete);
+
+        let release = session.release().expect("release");
+        assert_eq!(
+            open(&keys, &sealed, &release).expect("open"),
+            b"recovery hint"
+        );
+    }
+
+    #[test]
+    fn test_members_refuse_before_target_epoch() {
+        let (members, _) = quorum();
+        let request = ReleaseRequest {
+            capsule: [0; 32],
+            target_epoch: 12,
+            binding: [0; 64],
+        };
+        assert!(matches!(
+            release_share(&members[0], &request, 11),
+            Err(FrostCoordError::NotYetReleasable {
+                target_epoch: 12,
+                current_epoch: 11
+            })
+        ));
+    }
+
+    #[test]
+    fn test_members_refuse_lowered_epoch() {
+        let (members, keys) = quorum();
+        let sealed = timelock::seal(&keys.group_key, 1000, b"sealed ballot").expect("seal");
+
+        // The capsule of a value sealed to epoch 1000, requested as if it
+        // were sealed to epoch 0, gets no share at epoch 5.
+        let lowered = ReleaseRequest {
+            target_epoch: 0,
+            ..ReleaseRequest::from(&sealed)
+        };
+        for member in &members {
+            assert!(matches!(
+                release_share(member, &lowered, 5),
+                Err(FrostCoordError::Crypto(_))
+            ));
+        }
+        assert!(matches!(
+            release_share(&members[0], &ReleaseRequest::from(&sealed), 5),
+            Err(FrostCoordError::NotYetReleasable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_session_rejects_duplicate_and_foreign_shares() {
+        let (members, keys) = quorum();
+        let sealed = timelock::seal(&keys.group_key, 1, b"x").expect("seal");
+        let request = ReleaseRequest::from(&sealed);
+        let mut session = ReleaseSession::new(request, keys);
+
+        let share = release_share(&members[0], &request, 1).expect("share");
+        assert!(!session.add_share(share.clone()).expect("first"));
+        assert!(matches!(
+            session.add_share(share),
+            Err(FrostCoordError::DuplicateContribution(_))
+        ));
+
+        // Another quorum's members refuse the capsule outright, and their
+        // shares of their own values do not count here.
+        let (outsiders, outsider_keys) = quorum();
+        assert!(release_share(&outsiders[1], &request, 1).is_err());
+        let theirs = timelock::seal(&outsider_keys.group_key, 1, b"y").expect("seal");
+        let foreign =
+            release_share(&outsiders[1], &ReleaseRequest::from(&theirs), 1).expect("share");
+        assert!(session.add_share(foreign).is_err());
+    }
+
+    #[test]
+    fn test_consumer_rejects_short_or_mismatched_release() {
+        let (members, keys) = quorum();
+        let sealed = timelock::seal(&keys.group_key, 3, b"tally").expect("seal");
+        let request = ReleaseRequest::from(&sealed);
+        let shares: Vec<DecryptionShare> = members[..3]
+            .iter()
+            .map(|m| release_share(m, &request, 3).expect("share"))
+            .collect();
+
+        let short = TimeLockRelease {
+            request,
+            shares: vec![shares[0].clone(), shares[0].clone(), shares[1].clone()],
+        };
+        assert!(matches!(
+            verify_release(&keys, &short),
+            Err(FrostCoordError::InsufficientSigners { .. })
+        ));
+
+        let other = timelock::seal(&keys.group_key, 3, b"other").expect("seal");
+        let release = TimeLockRelease { request, shares };
+        assert!(open(&keys, &other, &release).is_err());
+        assert_eq!(open(&keys, &sealed, &release).expect("open"), b"tally");
+    }
+}
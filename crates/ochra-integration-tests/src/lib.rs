@@ -3,6 +3,10 @@
 //! This crate has no library code — it only contains integration tests
 //! that exercise end-to-end protocol flows across multiple workspace crates.
 //!
+//! Multi-node scenarios run over the in-memory transport from
+//! `ochra-transport`'s `test-harness` feature, so nodes exchange real wire
+//! messages deterministically without sockets.
+//!
 //! Run all integration tests:
 //! ```sh
 //! cargo test -p ochra-integration-tests -- --ignored
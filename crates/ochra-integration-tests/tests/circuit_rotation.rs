@@ -41,6 +41,8 @@ fn make_relay(id_byte: u8, ip: &str, as_num: u32, country: [u8; 2], score: f32)
         country_code: country,
         bandwidth_cap_mbps: 100,
         uptime_epochs: 100,
+        exit_policy: Default::default(),
+        delegation: None,
         sig: [0u8; 64],
     }
 }
@@ -61,6 +63,8 @@ fn make_relay_with_dh(id_byte: u8) -> (RelayDescriptor, x25519::X25519StaticSecr
         country_code: [b'U', b'S'],
         bandwidth_cap_mbps: 100,
         uptime_epochs: 100,
+        exit_policy: Default::default(),
+        delegation: None,
         sig: [0u8; 64],
     };
     (descriptor, secret)
@@ -0,0 +1,660 @@
+//! Integration test: multi-node scenarios over the in-memory transport.
+//!
+//! Exercises protocol flows between several nodes that exchange real wire
+//! messages through `ochra_transport::memory::MemoryNetwork` instead of
+//! sockets:
+//! 1. DHT bootstrap via iterative FIND_NODE lookups
+//! 2. Whisper round trip through a mailbox relay, across a partition
+//! 3. Mint a token batch from a VOPRF mint and spend it over nullifier gossip
+//! 4. Guardian recovery with shares sealed to the recovering device
+//!
+//! Every node is driven from the test task, one message at a time, so
+//! delivery order is identical on every run.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use ochra_crypto::blake3;
+use ochra_crypto::ecies;
+use ochra_crypto::ed25519;
+use ochra_crypto::voprf::VoprfServerKey;
+use ochra_crypto::x25519::X25519StaticSecret;
+use ochra_dht::kademlia::{FindNodeLookup, NodeId, NodeInfo, RoutingTable};
+use ochra_dht::K;
+use ochra_guardian::dkg::{self, GuardianInfo};
+use ochra_guardian::recovery::{self, GuardianShare, VETO_WINDOW};
+use ochra_mint::voprf_mint::{BlindedToken, EvaluatedTokenBatch, MintClient, MintServer};
+use ochra_nullifier::bloom::NullifierSet;
+use ochra_nullifier::gossip::{self as nullifier_gossip, GossipMessage};
+use ochra_transport::gossip::GossipTopic;
+use ochra_transport::memory::{MemoryNetwork, MemoryTransport};
+use ochra_transport::messages::{
+    DhtFindNode, DhtFindNodeResponse, DhtNodeInfo, GossipPublish, MintRequest, MintResponse,
+    RecoveryComplete, RecoveryRequest, RecoveryResponse, RecoveryShare, TypedMessage, WhisperPoll,
+};
+use ochra_transport::transport::{Received, Transport};
+use ochra_types::whisper::ThrottleStrictness;
+use ochra_whisper::mailbox::{self, MailboxConfig, MailboxKey, MailboxStore};
+
+/// Simulated base timestamp.
+const BASE_TIME: u64 = 1_700_000_000;
+
+/// How long to wait for a message that should already be queued.
+const RECV_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Unwrap a message of the expected variant, failing the test otherwise.
+macro_rules! expect_message {
+    ($message:expr, $variant:ident) => {
+        match $message {
+            TypedMessage::$variant(inner) => Some(inner),
+            _ => None,
+        }
+        .expect(concat!("expected ", stringify!($variant)))
+    };
+}
+
+/// Receive the next message, failing the test if none arrives.
+async fn recv(node: &MemoryTransport) -> Received {
+    tokio::time::timeout(RECV_TIMEOUT, node.recv())
+        .await
+        .expect("a message should be queued")
+        .expect("message should decode")
+}
+
+/// Create a node with a deterministic identity in its own /24.
+fn make_node_info(id_byte: u8) -> NodeInfo {
+    let kp = ed25519::KeyPair::from_bytes(&[id_byte; 32]);
+    NodeInfo {
+        node_id: blake3::hash(kp.verifying_key.as_bytes()),
+        addr: SocketAddr::from(([10, id_byte, 0, 1], 4433)),
+        alt_addr: None,
+        pik_public_key: kp.verifying_key.to_bytes(),
+        x25519_public_key: [id_byte; 32],
+    }
+}
+
+/// A DHT node: its endpoint, identity and routing table.
+struct DhtNode {
+    info: NodeInfo,
+    transport: MemoryTransport,
+    table: RoutingTable,
+}
+
+impl DhtNode {
+    /// Answer every queued FIND_NODE, learning each requester.
+    ///
+    /// `directory` stands in for the keys a peer presents in its handshake.
+    async fn serve(&mut self, directory: &HashMap<NodeId, NodeInfo>) {
+        while let Some(request) = self.transport.try_recv().expect("decode") {
+            let TypedMessage::DhtFindNode(find) = request.message else {
+                continue;
+            };
+            if let Some(info) = directory.get(&request.from) {
+                self.table.add_node(info.clone());
+            }
+            let nodes = self
+                .table
+                .find_closest(&find.target, K)
+                .into_iter()
+                .filter(|n| n.node_id != request.from)
+                .map(|n| DhtNodeInfo {
+                    node_id: n.node_id,
+                    addr: n.addr.to_string(),
+                    alt_addr: n.alt_addr.map(|a| a.to_string()),
+                })
+                .collect();
+            let response = TypedMessage::DhtFindNodeResponse(DhtFindNodeResponse {
+                target: find.target,
+                nodes,
+            });
+            self.transport
+                .send(request.from, &response)
+                .await
+                .expect("send response");
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore]
+async fn memory_network_dht_bootstrap() {
+    // =========================================================
+    // Step 1: Create 6 nodes on one network; node 0 is the seed
+    // =========================================================
+    let network = MemoryNetwork::new();
+    let mut nodes: Vec<DhtNode> = (1..=6u8)
+        .map(|i| {
+            let info = make_node_info(i);
+            DhtNode {
+                transport: network.join(info.node_id).expect("join"),
+                table: RoutingTable::new(info.node_id),
+                info,
+            }
+        })
+        .collect();
+    let directory: HashMap<NodeId, NodeInfo> = nodes
+        .iter()
+        .map(|n| (n.info.node_id, n.info.clone()))
+        .collect();
+    let seed = nodes[0].info.clone();
+
+    // =========================================================
+    // Step 2: Each joiner runs an iterative lookup for its own ID
+    // =========================================================
+    for joiner in 1..nodes.len() {
+        let target = nodes[joiner].info.node_id;
+        nodes[joiner].table.add_node(seed.clone());
+        let mut lookup = FindNodeLookup::new(target, vec![seed.clone()]);
+
+        let mut rounds = 0;
+        while !lookup.is_complete() {
+            let queries = lookup.next_queries();
+            if queries.is_empty() {
+                break;
+            }
+            for peer in &queries {
+                let find = TypedMessage::DhtFindNode(DhtFindNode { target });
+                nodes[joiner]
+                    .transport
+                    .send(peer.node_id, &find)
+                    .await
+                    .expect("send find_node");
+                let responder = nodes
+                    .iter()
+                    .position(|n| n.info.node_id == peer.node_id)
+                    .expect("queried node exists");
+                nodes[responder].serve(&directory).await;
+            }
+            for _ in &queries {
+                let response = recv(&nodes[joiner].transport).await;
+                let TypedMessage::DhtFindNodeResponse(found) = response.message else {
+                    continue;
+                };
+                assert_eq!(found.target, target);
+                let learned: Vec<NodeInfo> = found
+                    .nodes
+                    .iter()
+                    .map(|n| {
+                        let addr: SocketAddr = n.addr.parse().expect("valid address");
+                        let info = directory.get(&n.node_id).expect("known node");
+                        assert_eq!(addr, info.addr, "Advertised address must match");
+                        info.clone()
+                    })
+                    .collect();
+                for info in &learned {
+                    nodes[joiner].table.add_node(info.clone());
+                }
+                lookup.add_responses(learned);
+            }
+            rounds += 1;
+            assert!(rounds <= 8, "Lookup should converge within a few rounds");
+        }
+    }
+
+    // =========================================================
+    // Step 3: Every node knows every other node
+    // =========================================================
+    for node in &nodes {
+        assert_eq!(
+            node.table.len(),
+            nodes.len() - 1,
+            "Each routing table should hold all other nodes"
+        );
+    }
+
+    // =========================================================
+    // Step 4: Closest-node answers agree across nodes
+    // =========================================================
+    let target = blake3::hash(b"lookup target");
+    let mut expected: Vec<NodeId> = directory.keys().copied().collect();
+    expected.sort_by_key(|id| RoutingTable::xor_distance(id, &target));
+    for node in &nodes {
+        let closest: Vec<NodeId> = node
+            .table
+            .find_closest(&target, 3)
+            .iter()
+            .map(|n| n.node_id)
+            .collect();
+        let mut want: Vec<NodeId> = expected
+            .iter()
+            .filter(|id| **id != node.info.node_id)
+            .copied()
+            .collect();
+        want.truncate(3);
+        assert_eq!(closest, want, "find_closest should be globally consistent");
+    }
+    assert!(network.dropped() == 0, "No messages should be dropped");
+}
+
+/// Relay side of the mailbox protocol: handle one queued request.
+async fn serve_mailbox(relay: &MemoryTransport, store: &mut MailboxStore, now: u64) {
+    let request = recv(relay).await;
+    match request.message {
+        TypedMessage::WhisperDeposit(deposit) => {
+            assert!(
+                store
+                    .deposit(&deposit, now, None)
+                    .expect("deposit accepted"),
+                "Deposit should be new"
+            );
+        }
+        TypedMessage::WhisperPoll(poll) => {
+            let response = store.poll(&poll, now).expect("poll accepted");
+            relay
+                .send(request.from, &TypedMessage::WhisperPollResponse(response))
+                .await
+                .expect("send poll response");
+        }
+        TypedMessage::WhisperMailboxAck(ack) => {
+            store.ack(&ack, now).expect("ack accepted");
+        }
+        // Anything else leaves the store untouched, which the caller checks.
+        _ => {}
+    }
+}
+
+/// Poll a mailbox through the relay, open every envelope and ack them.
+async fn collect_mail(
+    node: &MemoryTransport,
+    key: &MailboxKey,
+    relay: &MemoryTransport,
+    store: &mut MailboxStore,
+    now: u64,
+) -> Vec<Vec<u8>> {
+    let poll: WhisperPoll = key.poll(ThrottleStrictness::Off, now);
+    node.send(relay.local_id(), &TypedMessage::WhisperPoll(poll))
+        .await
+        .expect("send poll");
+    serve_mailbox(relay, store, now).await;
+
+    let response = recv(node).await;
+    let TypedMessage::WhisperPollResponse(response) = response.message else {
+        return Vec::new();
+    };
+    let opened = response
+        .envelopes
+        .iter()
+        .map(|e| key.open(e).expect("envelope opens"))
+        .collect();
+
+    let ids = response.envelopes.iter().map(|e| e.envelope_id).collect();
+    node.send(
+        relay.local_id(),
+        &TypedMessage::WhisperMailboxAck(key.ack(ids, now)),
+    )
+    .await
+    .expect("send ack");
+    serve_mailbox(relay, store, now).await;
+    opened
+}
+
+#[tokio::test]
+#[ignore]
+async fn memory_network_whisper_round_trip() {
+    // =========================================================
+    // Step 1: Alice, Bob and a relay holding both mailboxes
+    // =========================================================
+    let network = MemoryNetwork::new();
+    let alice = network.join([0xA1; 32]).expect("join alice");
+    let bob = network.join([0xB0; 32]).expect("join bob");
+    let relay = network.join([0x5E; 32]).expect("join relay");
+
+    let difficulty = 1;
+    let mut store = MailboxStore::new(MailboxConfig {
+        pow_difficulty: difficulty,
+        default_strictness: ThrottleStrictness::Off,
+        ..MailboxConfig::default()
+    });
+    let alice_key = MailboxKey::generate();
+    let bob_key = MailboxKey::generate();
+    let alice_entry = alice_key.entry([1; 16], relay.local_id());
+    let bob_entry = bob_key.entry([2; 16], relay.local_id());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("clock")
+        .as_secs();
+
+    // =========================================================
+    // Step 2: A deposit across a partition is lost
+    // =========================================================
+    network.partition(alice.local_id(), relay.local_id());
+    let lost = mailbox::build_deposit(
+        &bob_entry,
+        b"lost",
+        now,
+        3600,
+        difficulty,
+        ThrottleStrictness::Off,
+    )
+    .expect("build deposit");
+    alice
+        .send(relay.local_id(), &TypedMessage::WhisperDeposit(lost))
+        .await
+        .expect("send into partition");
+    assert_eq!(network.dropped(), 1);
+    assert!(relay.try_recv().expect("decode").is_none());
+    network.heal(alice.local_id(), relay.local_id());
+
+    // =========================================================
+    // Step 3: Alice deposits for Bob; Bob polls, opens and acks
+    // =========================================================
+    let hello = mailbox::build_deposit(
+        &bob_entry,
+        b"hello bob",
+        now,
+        3600,
+        difficulty,
+        ThrottleStrictness::Off,
+    )
+    .expect("build deposit");
+    alice
+        .send(relay.local_id(), &TypedMessage::WhisperDeposit(hello))
+        .await
+        .expect("send deposit");
+    serve_mailbox(&relay, &mut store, now).await;
+    assert_eq!(store.envelope_count(), 1);
+
+    let inbox = collect_mail(&bob, &bob_key, &relay, &mut store, now).await;
+    assert_eq!(inbox, vec![b"hello bob".to_vec()]);
+    assert_eq!(store.envelope_count(), 0, "Ack should delete the envelope");
+
+    // =========================================================
+    // Step 4: Bob replies through Alice's mailbox
+    // =========================================================
+    let reply = mailbox::build_deposit(
+        &alice_entry,
+        b"hi alice",
+        now,
+        3600,
+        difficulty,
+        ThrottleStrictness::Off,
+    )
+    .expect("build deposit");
+    bob.send(relay.local_id(), &TypedMessage::WhisperDeposit(reply))
+        .await
+        .expect("send reply");
+    serve_mailbox(&relay, &mut store, now).await;
+
+    let inbox = collect_mail(&alice, &alice_key, &relay, &mut store, now).await;
+    assert_eq!(inbox, vec![b"hi alice".to_vec()]);
+    assert_eq!(store.envelope_count(), 0);
+}
+
+#[tokio::test]
+#[ignore]
+async fn memory_network_mint_and_spend() {
+    // =========================================================
+    // Step 1: A client, a mint and two nullifier-gossip peers
+    // =========================================================
+    let network = MemoryNetwork::new();
+    let client = network.join([0xC1; 32]).expect("join client");
+    let mint = network.join([0x31; 32]).expect("join mint");
+    let peers: Vec<MemoryTransport> = [[0x71; 32], [0x72; 32]]
+        .into_iter()
+        .map(|id| network.join(id).expect("join peer"))
+        .collect();
+    let server_key = VoprfServerKey::generate().expect("mint key");
+    let key_epoch = 1;
+
+    // =========================================================
+    // Step 2: Client requests a batch of blinded tokens
+    // =========================================================
+    let denominations = vec![100_000_000u64, 50_000_000, 10_000_000];
+    let (blinded, states) = MintClient::blind_batch(&denominations).expect("blind batch");
+    // The minting proof is checked by the quorum; ochra-mint's own tests
+    // cover it, so this stand-in mint evaluates unconditionally.
+    let request = TypedMessage::MintRequest(MintRequest {
+        epoch: 7,
+        pik_commitment: [0; 32],
+        minted_amount: denominations.iter().sum(),
+        groth16_proof: Vec::new(),
+        receipt_merkle_root: [0; 32],
+        blinded_tokens: blinded.iter().map(|b| b.blinded_element.clone()).collect(),
+        denominations: denominations.clone(),
+    });
+    client
+        .send(mint.local_id(), &request)
+        .await
+        .expect("send mint request");
+
+    // =========================================================
+    // Step 3: The mint evaluates the batch and responds
+    // =========================================================
+    let received = recv(&mint).await;
+    let request = expect_message!(received.message, MintRequest);
+    let to_evaluate: Vec<BlindedToken> = request
+        .blinded_tokens
+        .iter()
+        .zip(&request.denominations)
+        .map(|(element, &denomination)| BlindedToken {
+            blinded_element: element.clone(),
+            denomination,
+        })
+        .collect();
+    let evaluated =
+        MintServer::evaluate_batch(&to_evaluate, &server_key, key_epoch).expect("evaluate");
+    let response = TypedMessage::MintResponse(MintResponse {
+        epoch: request.epoch,
+        signed_blinded_tokens: evaluated.evaluated_elements,
+        batch_proof: evaluated.proof,
+        key_epoch,
+        status: 0,
+    });
+    mint.send(received.from, &response)
+        .await
+        .expect("send mint response");
+
+    // =========================================================
+    // Step 4: Client verifies the batch proof and unblinds
+    // =========================================================
+    let response = expect_message!(recv(&client).await.message, MintResponse);
+    assert_eq!(response.status, 0);
+    let batch = EvaluatedTokenBatch {
+        evaluated_elements: response.signed_blinded_tokens,
+        proof: response.batch_proof,
+        key_epoch: response.key_epoch,
+    };
+    let tokens = MintClient::unblind_batch(&blinded, &batch, &states, &server_key.public_key())
+        .expect("batch proof should verify");
+    assert_eq!(tokens.len(), denominations.len());
+
+    // =========================================================
+    // Step 5: Spend a token by gossiping its nullifier
+    // =========================================================
+    let topic = GossipTopic::Nullifiers.topic_id();
+    let nullifier = tokens[0].nullifier();
+    let publish = |msg_id: u8| {
+        let gossip = nullifier_gossip::create_gossip_message(vec![nullifier], 7, client.local_id());
+        TypedMessage::GossipPublish(GossipPublish {
+            topic,
+            data: ochra_transport::cbor::to_vec(&gossip).expect("encode gossip"),
+            ttl: 3,
+            gossip_msg_id: [msg_id; 16],
+        })
+    };
+    for peer in &peers {
+        client
+            .send(peer.local_id(), &publish(1))
+            .await
+            .expect("publish nullifier");
+    }
+
+    let mut sets: Vec<NullifierSet> = peers.iter().map(|_| NullifierSet::new()).collect();
+    for (peer, set) in peers.iter().zip(sets.iter_mut()) {
+        let publish = expect_message!(recv(peer).await.message, GossipPublish);
+        assert_eq!(publish.topic, topic);
+        let gossip: GossipMessage =
+            ochra_transport::cbor::from_slice(&publish.data).expect("decode gossip");
+        assert_eq!(
+            nullifier_gossip::process_gossip(&gossip, set),
+            vec![nullifier]
+        );
+    }
+
+    // =========================================================
+    // Step 6: A second spend of the same token is a double spend
+    // =========================================================
+    client
+        .send(peers[0].local_id(), &publish(2))
+        .await
+        .expect("publish again");
+    let publish = expect_message!(recv(&peers[0]).await.message, GossipPublish);
+    let gossip: GossipMessage =
+        ochra_transport::cbor::from_slice(&publish.data).expect("decode gossip");
+    assert!(nullifier_gossip::process_gossip(&gossip, &mut sets[0]).is_empty());
+    assert!(
+        sets[0].insert_checked(&nullifier).is_err(),
+        "Re-spending a token must be rejected"
+    );
+    assert!(sets[1].insert_checked(&tokens[1].nullifier()).is_ok());
+}
+
+#[tokio::test]
+#[ignore]
+async fn memory_network_guardian_recovery() {
+    // =========================================================
+    // Step 1: Five guardians hold 3-of-5 shares from a DKG
+    // =========================================================
+    let network = MemoryNetwork::new();
+    let guardian_keys: Vec<ed25519::KeyPair> = (1..=5u8)
+        .map(|i| ed25519::KeyPair::from_bytes(&[i; 32]))
+        .collect();
+    let guardians: Vec<GuardianInfo> = guardian_keys
+        .iter()
+        .zip(1..)
+        .map(|(kp, i)| GuardianInfo {
+            pik_hash: blake3::hash(kp.verifying_key.as_bytes()),
+            display_name: format!("Guardian-{i}"),
+            public_key: kp.verifying_key.to_bytes(),
+        })
+        .collect();
+    let threshold = 3;
+    let mut ceremony = dkg::initiate_dkg(guardians.clone(), threshold).expect("DKG");
+    ceremony.process_shares().expect("DKG shares");
+    let guardian_nodes: Vec<MemoryTransport> = guardians
+        .iter()
+        .map(|g| network.join(g.pik_hash).expect("join guardian"))
+        .collect();
+
+    // =========================================================
+    // Step 2: A new device asks every guardian for recovery
+    // =========================================================
+    let device = network.join([0xDE; 32]).expect("join device");
+    let device_secret = X25519StaticSecret::random();
+    let session = [0x5A; 16];
+    let target_node_id = blake3::hash(b"lost identity");
+
+    // Guardian 4 is unreachable for the whole recovery.
+    network.partition(device.local_id(), guardian_nodes[4].local_id());
+
+    for guardian in &guardian_nodes {
+        let request = TypedMessage::RecoveryRequest(RecoveryRequest {
+            target_node_id,
+            recovery_session_id: session,
+            new_x25519_pk: device_secret.public_key().to_bytes(),
+        });
+        device
+            .send(guardian.local_id(), &request)
+            .await
+            .expect("send recovery request");
+    }
+    assert_eq!(network.dropped(), 1);
+
+    // =========================================================
+    // Step 3: Reachable guardians accept, then release shares
+    //         sealed to the device once the veto window passes
+    // =========================================================
+    let mut request = recovery::initiate_recovery(target_node_id.to_vec(), BASE_TIME);
+    let after_veto = BASE_TIME + VETO_WINDOW + 1;
+    for (index, guardian) in guardian_nodes.iter().enumerate().take(4) {
+        let asked = expect_message!(recv(guardian).await.message, RecoveryRequest);
+        assert_eq!(asked.recovery_session_id, session);
+
+        let ack = TypedMessage::RecoveryResponse(RecoveryResponse {
+            recovery_session_id: session,
+            guardian_node_id: guardian.local_id(),
+            accepted: true,
+        });
+        guardian
+            .send(device.local_id(), &ack)
+            .await
+            .expect("send response");
+
+        let share = ceremony.get_share(index).expect("guardian share");
+        let sealed = ecies::encrypt(
+            &ochra_crypto::x25519::X25519PublicKey::from_bytes(asked.new_x25519_pk),
+            share,
+        )
+        .expect("seal share");
+        let share = TypedMessage::RecoveryShare(RecoveryShare {
+            recovery_session_id: session,
+            guardian_node_id: guardian.local_id(),
+            encrypted_share: sealed.to_bytes(),
+        });
+        guardian
+            .send(device.local_id(), &share)
+            .await
+            .expect("send share");
+    }
+    assert!(guardian_nodes[4].try_recv().expect("decode").is_none());
+
+    // =========================================================
+    // Step 4: The device opens each share and meets the threshold
+    // =========================================================
+    let mut accepted = Vec::new();
+    for _ in 0..8 {
+        match recv(&device).await.message {
+            TypedMessage::RecoveryResponse(response) => {
+                assert!(response.accepted);
+                accepted.push(response.guardian_node_id);
+            }
+            TypedMessage::RecoveryShare(share) => {
+                let ct = ecies::EciesCiphertext::from_bytes(&share.encrypted_share)
+                    .expect("share ciphertext");
+                let shard_data = ecies::decrypt(&device_secret, &ct).expect("open share");
+                recovery::submit_share(
+                    &mut request,
+                    GuardianShare {
+                        guardian_id: share.guardian_node_id,
+                        shard_data,
+                    },
+                    after_veto,
+                )
+                .expect("submit share after veto window");
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(accepted.len(), 4);
+    assert!(recovery::has_enough_shares(&request, threshold as usize));
+    for (index, share) in request.guardian_shares.iter().enumerate() {
+        assert_eq!(share.guardian_id, guardians[index].pik_hash);
+        assert_eq!(
+            Some(share.shard_data.as_slice()),
+            ceremony.get_share(index),
+            "Opened share must match the DKG share"
+        );
+    }
+
+    // =========================================================
+    // Step 5: The device announces completion to its guardians
+    // =========================================================
+    let new_pik_hash = blake3::hash(b"recovered pik");
+    for guardian in &accepted {
+        let done = TypedMessage::RecoveryComplete(RecoveryComplete {
+            recovery_session_id: session,
+            success: true,
+            new_pik_hash: Some(new_pik_hash),
+        });
+        device.send(*guardian, &done).await.expect("send complete");
+    }
+    for guardian in guardian_nodes.iter().take(4) {
+        let message = recv(guardian).await.message;
+        assert!(matches!(
+            message,
+            TypedMessage::RecoveryComplete(RecoveryComplete { success: true, new_pik_hash: Some(h), .. })
+                if h == new_pik_hash
+        ));
+    }
+}
@@ -21,12 +21,16 @@ use ochra_dht::bep44::{self, DhtRecord, RecordStore};
 use ochra_dht::kademlia::{AddNodeResult, FindNodeLookup, NodeId, NodeInfo, RoutingTable};
 
 /// Create a test NodeInfo with a deterministic ID and address.
+///
+/// Each node sits in its own /24 so routing-table subnet diversity limits
+/// do not apply.
 fn make_node_info(id_byte: u8) -> NodeInfo {
     let kp = ed25519::KeyPair::from_bytes(&[id_byte; 32]);
     let node_id = blake3::hash(kp.verifying_key.as_bytes());
     NodeInfo {
         node_id,
-        addr: SocketAddr::from(([10, 0, 0, id_byte], 4433 + u16::from(id_byte))),
+        addr: SocketAddr::from(([10, 0, id_byte, 1], 4433 + u16::from(id_byte))),
+        alt_addr: None,
         pik_public_key: kp.verifying_key.to_bytes(),
         x25519_public_key: [id_byte; 32],
     }
@@ -37,6 +41,7 @@ fn make_node_with_id(id: NodeId, port: u16) -> NodeInfo {
     NodeInfo {
         node_id: id,
         addr: SocketAddr::from(([127, 0, 0, 1], port)),
+        alt_addr: None,
         pik_public_key: [0u8; 32],
         x25519_public_key: [0u8; 32],
     }
@@ -0,0 +1,66 @@
+//! Integration test: anonymous refunds recorded in the database.
+//!
+//! A refund validator backed by `refund_claims` refuses a second claim for
+//! the same purchase after the database is reopened, as after a restart.
+
+use ochra_crypto::voprf::VoprfServerKey;
+use ochra_db::queries::refunds;
+use ochra_mint::voprf_mint::MintClient;
+use ochra_nullifier::refund::RefundTree;
+use ochra_spend::refund::{build_claim, RefundLedger, RefundTicket, RefundValidator};
+use ochra_spend::{Result, SpendError};
+use rusqlite::Connection;
+
+/// `refund_claims` as a refund ledger.
+struct DbLedger(Connection);
+
+impl RefundLedger for DbLedger {
+    fn is_refunded(&self, nullifier_hash: &[u8; 32], purchase: &[u8; 32]) -> Result<bool> {
+        refunds::is_refunded(&self.0, nullifier_hash, purchase)
+            .map_err(|e| SpendError::Ledger(e.to_string()))
+    }
+
+    fn record(
+        &mut self,
+        nullifier_hash: &[u8; 32],
+        purchase: &[u8; 32],
+        epoch: u64,
+    ) -> Result<bool> {
+        refunds::record(&self.0, nullifier_hash, purchase, epoch)
+            .map_err(|e| SpendError::Ledger(e.to_string()))
+    }
+}
+
+#[test]
+fn refund_refused_after_restart() {
+    let path = std::env::temp_dir().join(format!(
+        "ochra-refunds-{}.db",
+        hex::encode(rand::random::<[u8; 8]>())
+    ));
+    let ticket = RefundTicket::generate([0xC0; 32], 1_000, 10);
+    let mut tree = RefundTree::new();
+    tree.add_commitment(ticket.commitment(), 10);
+    let root = tree.get_merkle_root();
+    let key = VoprfServerKey::generate().expect("key");
+
+    let (blinded, _) = MintClient::blind_batch(&[1_000]).expect("blind");
+    let claim = build_claim(&ticket, &tree, 1_000, blinded).expect("claim");
+    let mut validator = RefundValidator::new(DbLedger(ochra_db::open(&path).expect("open")));
+    validator
+        .process(&claim, &root, 11, &key, 1)
+        .expect("first claim");
+    drop(validator);
+
+    let mut validator = RefundValidator::new(DbLedger(ochra_db::open(&path).expect("reopen")));
+    let (blinded, _) = MintClient::blind_batch(&[500]).expect("blind");
+    let again = build_claim(&ticket, &tree, 500, blinded).expect("claim");
+    assert!(matches!(
+        validator.process(&again, &root, 12, &key, 1),
+        Err(SpendError::AlreadySpent)
+    ));
+
+    drop(validator);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+}
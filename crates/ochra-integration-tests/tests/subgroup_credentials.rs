@@ -0,0 +1,85 @@
+//! Integration test: subgroup membership credentials end to end.
+//!
+//! The Space owner issues blinded credentials to two members and hands the
+//! subgroup's credential key to a serving relay. The relay admits both
+//! members, and nothing the owner saw during issuance matches what the relay
+//! is shown, so the two together cannot tell which member is fetching.
+//! Removing a member cuts off their remaining credentials.
+
+use ochra_mls::credential::{
+    CredentialIssuer, CredentialKey, CredentialVerifier, CredentialWallet, MembershipCredential,
+};
+use ochra_mls::subgroup::{add_member, create_subgroup, remove_member};
+
+const NOW: u64 = 1_700_000_000;
+const OWNER: [u8; 32] = [1u8; 32];
+const ALICE: [u8; 32] = [2u8; 32];
+const BOB: [u8; 32] = [3u8; 32];
+
+#[test]
+fn relay_admits_members_it_cannot_identify() {
+    let mut subgroup = create_subgroup([0xAA; 32], [0xBB; 32], OWNER);
+    add_member(&mut subgroup, ALICE).expect("add alice");
+    add_member(&mut subgroup, BOB).expect("add bob");
+    let issuer = CredentialIssuer::new(CredentialKey::generate());
+
+    // The relay receives the key out of band, as raw bytes.
+    let relay_key = CredentialKey::from_bytes(issuer.key().to_bytes());
+    let mut relay = CredentialVerifier::new(subgroup.subgroup_id, relay_key, subgroup.epoch);
+
+    // Everything the owner sees, next to who asked.
+    let mut owner_log: Vec<([u8; 32], Vec<u8>)> = Vec::new();
+    let mut wallets = Vec::new();
+    for member in [ALICE, BOB] {
+        let mut wallet = CredentialWallet::new();
+        let request = wallet.request(subgroup.subgroup_id, 3).expect("request");
+        let issuance = issuer
+            .issue(&subgroup, &member, &request, NOW)
+            .expect("issue");
+        owner_log.extend(request.blinded.iter().map(|b| (member, b.clone())));
+        owner_log.extend(issuance.evaluated.iter().map(|e| (member, e.clone())));
+        let announced = issuer
+            .key()
+            .public_key(issuance.epoch, issuance.expires_at)
+            .expect("public key");
+        assert_eq!(wallet.receive(&issuance, &announced).expect("receive"), 3);
+        wallets.push((member, wallet));
+    }
+
+    for (_, wallet) in &mut wallets {
+        let (credential, token_key) = wallet.take(&subgroup.subgroup_id, NOW).expect("credential");
+        let wire = credential.to_bytes();
+        let challenge = relay.challenge();
+        let presentation = MembershipCredential::from_bytes(&wire)
+            .expect("decode")
+            .present(&token_key, &challenge);
+        relay
+            .verify(&presentation, &challenge, NOW)
+            .expect("member admitted");
+
+        for (_, seen) in &owner_log {
+            assert_ne!(seen.as_slice(), credential.token_pk.as_slice());
+            assert_ne!(seen.as_slice(), credential.tag.as_slice());
+        }
+    }
+
+    // Both members' credentials share an epoch and expiry, so the fields
+    // shown in the clear do not tell them apart either.
+    let (_, alice_wallet) = &mut wallets[0];
+    let (alice_cred, alice_key) = alice_wallet
+        .take(&subgroup.subgroup_id, NOW)
+        .expect("credential");
+    let (_, bob_wallet) = &mut wallets[1];
+    let (bob_cred, _) = bob_wallet
+        .take(&subgroup.subgroup_id, NOW)
+        .expect("credential");
+    assert_eq!(alice_cred.epoch, bob_cred.epoch);
+    assert_eq!(alice_cred.expires_at, bob_cred.expires_at);
+
+    remove_member(&mut subgroup, &ALICE).expect("remove alice");
+    relay.set_epoch(subgroup.epoch);
+    let challenge = relay.challenge();
+    assert!(relay
+        .verify(&alice_cred.present(&alice_key, &challenge), &challenge, NOW)
+        .is_err());
+}
@@ -7,6 +7,7 @@
 //! - [`invite`] - Invite link creation and parsing (`ochra://invite` URLs)
 //! - [`contact_exchange`] - Contact exchange token system for bidirectional contacts
 //! - [`rendezvous`] - Anonymous rendezvous protocol for introduction points
+//! - [`safety`] - Safety numbers and QR verification of contact keys
 //!
 //! ## Invite Flow
 //!
@@ -20,6 +21,7 @@
 pub mod contact_exchange;
 pub mod invite;
 pub mod rendezvous;
+pub mod safety;
 
 use ochra_crypto::blake3::{self, contexts};
 use ochra_crypto::chacha20;
@@ -74,6 +76,10 @@ pub enum InviteError {
     #[error("invalid token: {0}")]
     InvalidToken(String),
 
+    /// A scanned safety number does not match the keys held locally.
+    #[error("safety number mismatch")]
+    SafetyNumberMismatch,
+
     /// Cryptographic error from ochra-crypto.
     #[error("cryptographic error: {0}")]
     CryptoLib(#[from] ochra_crypto::CryptoError),
@@ -0,0 +1,193 @@
+//! Safety numbers for out-of-band contact verification.
+//!
+//! Contacts are trusted on first use when a token is redeemed. A safety
+//! number lets both parties confirm, in person or over another channel,
+//! that they hold each other's real PIK public keys rather than keys
+//! substituted in transit.
+//!
+//! Each party's half is 30 digits derived from an iterated hash of their
+//! PIK public key. The two halves are concatenated in sorted order, so
+//! both sides compute the same 60-digit number and can compare it as
+//! twelve groups of five digits.
+//!
+//! The QR form carries both full fingerprints. Scanning the peer's code
+//! checks it against the locally computed pair, with the halves swapped
+//! because the peer lists its own key first.
+
+use ochra_crypto::blake3;
+
+use crate::{InviteError, Result};
+
+/// Hash iterations per fingerprint. Slows down searching for a key whose
+/// safety number collides with a target.
+pub const FINGERPRINT_ITERATIONS: u32 = 5200;
+
+/// Digits contributed by each party.
+pub const DIGITS_PER_PARTY: usize = 30;
+
+/// QR payload format version.
+pub const QR_VERSION: u8 = 1;
+
+const QR_PAYLOAD_LEN: usize = 1 + 32 + 32;
+
+/// Iterated fingerprint of a PIK public key.
+pub fn fingerprint(pik_public_key: &[u8; 32]) -> [u8; 32] {
+    let mut digest = blake3::hash(&blake3::encode_multi_field(&[
+        b"safety-number",
+        pik_public_key,
+    ]));
+    for _ in 0..FINGERPRINT_ITERATIONS {
+        let mut input = [0u8; 64];
+        input[..32].copy_from_slice(&digest);
+        input[32..].copy_from_slice(pik_public_key);
+        digest = blake3::hash(&input);
+    }
+    digest
+}
+
+/// One party's 30 digits: six 5-byte chunks, each reduced mod 100000.
+fn fingerprint_digits(fingerprint: &[u8; 32]) -> String {
+    let mut digits = String::with_capacity(DIGITS_PER_PARTY);
+    for chunk in fingerprint[..30].chunks_exact(5) {
+        let value = chunk
+            .iter()
+            .fold(0u64, |acc, &byte| (acc << 8) | u64::from(byte));
+        digits.push_str(&format!("{:05}", value % 100_000));
+    }
+    digits
+}
+
+/// The safety number shared by two contacts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SafetyNumber {
+    digits: String,
+}
+
+impl SafetyNumber {
+    /// Compute the safety number for a pair of PIK public keys.
+    ///
+    /// The result does not depend on which side is local.
+    pub fn new(local_pik: &[u8; 32], remote_pik: &[u8; 32]) -> Self {
+        let local = fingerprint_digits(&fingerprint(local_pik));
+        let remote = fingerprint_digits(&fingerprint(remote_pik));
+        let digits = if local <= remote {
+            local + &remote
+        } else {
+            remote + &local
+        };
+        Self { digits }
+    }
+
+    /// The 60 digits without separators.
+    pub fn digits(&self) -> &str {
+        &self.digits
+    }
+
+    /// The digits in groups of five.
+    pub fn groups(&self) -> Vec<&str> {
+        (0..self.digits.len())
+            .step_by(5)
+            .map(|i| &self.digits[i..i + 5])
+            .collect()
+    }
+}
+
+impl std::fmt::Display for SafetyNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.groups().join(" "))
+    }
+}
+
+/// Encode the QR payload shown to the contact for scanning.
+///
+/// Format: `version || fingerprint(local) || fingerprint(remote)`,
+/// base64url without padding.
+pub fn qr_payload(local_pik: &[u8; 32], remote_pik: &[u8; 32]) -> String {
+    let mut payload = Vec::with_capacity(QR_PAYLOAD_LEN);
+    payload.push(QR_VERSION);
+    payload.extend_from_slice(&fingerprint(local_pik));
+    payload.extend_from_slice(&fingerprint(remote_pik));
+    base64::Engine::encode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload)
+}
+
+/// Check a QR payload scanned from the contact's device.
+///
+/// # Errors
+///
+/// - [`InviteError::Malformed`] if the payload cannot be decoded
+/// - [`InviteError::SafetyNumberMismatch`] if either fingerprint differs
+///   from the keys held locally
+pub fn verify_scanned(scanned: &str, local_pik: &[u8; 32], remote_pik: &[u8; 32]) -> Result<()> {
+    let payload =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, scanned)
+            .map_err(|e| InviteError::Malformed(format!("safety QR: {e}")))?;
+    if payload.len() != QR_PAYLOAD_LEN {
+        return Err(InviteError::Malformed(format!(
+            "safety QR length {}",
+            payload.len()
+        )));
+    }
+    if payload[0] != QR_VERSION {
+        return Err(InviteError::Malformed(format!(
+            "safety QR version {}",
+            payload[0]
+        )));
+    }
+    // The scanned code lists the contact's key first
+    if payload[1..33] != fingerprint(remote_pik) || payload[33..] != fingerprint(local_pik) {
+        return Err(InviteError::SafetyNumberMismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safety_number_is_symmetric() {
+        let alice = [1u8; 32];
+        let bob = [2u8; 32];
+        let a = SafetyNumber::new(&alice, &bob);
+        let b = SafetyNumber::new(&bob, &alice);
+        assert_eq!(a, b);
+        assert_eq!(a.digits().len(), 2 * DIGITS_PER_PARTY);
+        assert!(a.digits().chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(a.groups().len(), 12);
+        assert_eq!(a.to_string().len(), 60 + 11);
+    }
+
+    #[test]
+    fn test_substituted_key_changes_number() {
+        let alice = [1u8; 32];
+        let bob = [2u8; 32];
+        let mallory = [3u8; 32];
+        assert_ne!(
+            SafetyNumber::new(&alice, &bob),
+            SafetyNumber::new(&alice, &mallory)
+        );
+    }
+
+    #[test]
+    fn test_qr_round_trip() {
+        let alice = [1u8; 32];
+        let bob = [2u8; 32];
+        let shown_by_bob = qr_payload(&bob, &alice);
+        verify_scanned(&shown_by_bob, &alice, &bob).expect("verify");
+
+        // Alice holds a substituted key for Bob
+        assert!(matches!(
+            verify_scanned(&shown_by_bob, &alice, &[3u8; 32]),
+            Err(InviteError::SafetyNumberMismatch)
+        ));
+        // Alice scanned her own code
+        assert!(matches!(
+            verify_scanned(&qr_payload(&alice, &bob), &alice, &bob),
+            Err(InviteError::SafetyNumberMismatch)
+        ));
+        assert!(matches!(
+            verify_scanned("not base64!", &alice, &bob),
+            Err(InviteError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,381 @@
+//! Collateral Ratio simulation for governance what-if analysis.
+//!
+//! Replays a series of per-epoch oracle observations through the CR update
+//! rule (Section 11.2) under a given [`CrParameters`] set, producing the
+//! projected CR and mint-throttle trajectory. Running the same history under
+//! the live and a proposed parameter set shows what a change would have
+//! done before it is put to a vote.
+//!
+//! ## Update rule
+//!
+//! ```text
+//! value_delta    = clamp((1 - twap / target_rate) × 2, -1, 1)
+//! growth_delta   = clamp(-net_node_change_pct × 5, -1, 1)
+//! oracle_delta   = 0 (fresh) | 0.5 (stale ≥ 6h) | 1.0 (circuit breaker)
+//! velocity_delta = clamp(1 - velocity_ratio, -1, 1)
+//! raw_delta      = Σ weight_i × delta_i
+//! CR_new         = clamp(CR + clamp(raw_delta × 0.1, ±max_epoch_delta), min_cr, max_cr)
+//! ```
+//!
+//! While the circuit breaker is active the effective CR is shifted by
+//! `circuit_breaker_shift`; beyond `suspension_stale_hours` minting is
+//! suspended outright. The per-epoch mint allowance uses
+//! [`max_mintable`](crate::cr_throttle::max_mintable).
+
+use serde::{Deserialize, Serialize};
+
+use crate::cr_throttle::{max_mintable, MAX_CR, MIN_CR};
+use crate::{MintError, Result};
+
+/// Longest history accepted by [`simulate`] (ten years of daily epochs).
+pub const MAX_SIMULATION_EPOCHS: usize = 3650;
+
+/// Oracle staleness at which the Oracle Health input starts contributing.
+pub const STALE_WARNING_HOURS: u32 = 6;
+
+/// Tunable inputs of the CR update rule.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrParameters {
+    /// CR at the start of the simulated history.
+    pub initial_cr: f64,
+    /// Lower CR bound.
+    pub min_cr: f64,
+    /// Upper CR bound.
+    pub max_cr: f64,
+    /// Largest CR change in a single epoch.
+    pub max_epoch_delta: f64,
+    /// Weight of the Value Deviation input.
+    pub value_weight: f64,
+    /// Weight of the Network Growth input.
+    pub growth_weight: f64,
+    /// Weight of the Oracle Health input.
+    pub oracle_weight: f64,
+    /// Weight of the Spending Velocity input.
+    pub velocity_weight: f64,
+    /// Target Seed value in micro-units of the reference numeraire.
+    pub target_rate: u64,
+    /// Staleness that trips the circuit breaker.
+    pub circuit_breaker_stale_hours: u32,
+    /// CR shift applied while the circuit breaker is active.
+    pub circuit_breaker_shift: f64,
+    /// Staleness beyond which minting is suspended.
+    pub suspension_stale_hours: u32,
+    /// Mint request size used for the throttle trajectory, in micro-seeds.
+    pub base_mint_amount: u64,
+}
+
+impl Default for CrParameters {
+    fn default() -> Self {
+        Self {
+            initial_cr: 1.0,
+            min_cr: MIN_CR,
+            max_cr: MAX_CR,
+            max_epoch_delta: 0.1,
+            value_weight: 0.40,
+            growth_weight: 0.25,
+            oracle_weight: 0.20,
+            velocity_weight: 0.15,
+            target_rate: 100_000_000,
+            circuit_breaker_stale_hours: 12,
+            circuit_breaker_shift: 0.3,
+            suspension_stale_hours: 48,
+            base_mint_amount: 1_000_000,
+        }
+    }
+}
+
+impl CrParameters {
+    /// Check the parameters describe a usable rule.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::InvalidSimulation`] describing the first problem found
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: &str| Err(MintError::InvalidSimulation(msg.to_string()));
+        let values = [
+            self.initial_cr,
+            self.min_cr,
+            self.max_cr,
+            self.max_epoch_delta,
+            self.value_weight,
+            self.growth_weight,
+            self.oracle_weight,
+            self.velocity_weight,
+            self.circuit_breaker_shift,
+        ];
+        if values.iter().any(|v| !v.is_finite()) {
+            return invalid("parameters must be finite");
+        }
+        if self.min_cr <= 0.0 || self.min_cr > self.max_cr {
+            return invalid("require 0 < min_cr <= max_cr");
+        }
+        if !(self.min_cr..=self.max_cr).contains(&self.initial_cr) {
+            return invalid("initial_cr must lie within [min_cr, max_cr]");
+        }
+        if self.max_epoch_delta < 0.0 {
+            return invalid("max_epoch_delta must not be negative");
+        }
+        if [
+            self.value_weight,
+            self.growth_weight,
+            self.oracle_weight,
+            self.velocity_weight,
+        ]
+        .iter()
+        .any(|w| *w < 0.0)
+        {
+            return invalid("weights must not be negative");
+        }
+        if self.target_rate == 0 {
+            return invalid("target_rate must be positive");
+        }
+        if self.circuit_breaker_stale_hours > self.suspension_stale_hours {
+            return invalid("circuit breaker must trip before suspension");
+        }
+        Ok(())
+    }
+}
+
+/// Oracle and network readings for one historical epoch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EpochObservation {
+    /// Epoch number.
+    pub epoch: u32,
+    /// TWAP at the epoch boundary, same units as `target_rate`.
+    pub twap: u64,
+    /// Hours since the last fresh oracle attestation.
+    #[serde(default)]
+    pub stale_hours: u32,
+    /// Net change in node count as a fraction (0.02 = +2%).
+    #[serde(default)]
+    pub net_node_change_pct: f64,
+    /// Spending velocity relative to baseline (1.0 = baseline).
+    #[serde(default = "default_velocity_ratio")]
+    pub velocity_ratio: f64,
+}
+
+fn default_velocity_ratio() -> f64 {
+    1.0
+}
+
+/// Projected state after one simulated epoch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EpochProjection {
+    /// Epoch number.
+    pub epoch: u32,
+    /// Weighted input sum before scaling.
+    pub raw_delta: f64,
+    /// CR carried into the next epoch.
+    pub cr: f64,
+    /// CR minting is throttled against (includes the circuit breaker shift).
+    pub effective_cr: f64,
+    /// Whether the circuit breaker was active.
+    pub circuit_breaker: bool,
+    /// Whether minting was suspended for oracle staleness.
+    pub minting_suspended: bool,
+    /// Mint allowance for a `base_mint_amount` request.
+    pub max_mintable: u64,
+}
+
+/// Result of a simulation run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    /// Per-epoch projections, in observation order.
+    pub trajectory: Vec<EpochProjection>,
+    /// CR after the last epoch.
+    pub final_cr: f64,
+    /// Epochs in which minting was throttled below `base_mint_amount`.
+    pub throttled_epochs: u32,
+    /// Epochs in which minting was halted entirely.
+    pub halted_epochs: u32,
+}
+
+/// Replay `observations` under `params`.
+///
+/// # Errors
+///
+/// - [`MintError::InvalidSimulation`] if the parameters are invalid, the
+///   history is empty or longer than [`MAX_SIMULATION_EPOCHS`], or an
+///   observation carries a non-finite value
+pub fn simulate(
+    params: &CrParameters,
+    observations: &[EpochObservation],
+) -> Result<SimulationReport> {
+    params.validate()?;
+    if observations.is_empty() || observations.len() > MAX_SIMULATION_EPOCHS {
+        return Err(MintError::InvalidSimulation(format!(
+            "history must have 1..={MAX_SIMULATION_EPOCHS} epochs, got {}",
+            observations.len()
+        )));
+    }
+
+    let mut cr = params.initial_cr;
+    let mut trajectory = Vec::with_capacity(observations.len());
+    let mut throttled_epochs = 0;
+    let mut halted_epochs = 0;
+
+    for obs in observations {
+        if !obs.net_node_change_pct.is_finite() || !obs.velocity_ratio.is_finite() {
+            return Err(MintError::InvalidSimulation(format!(
+                "epoch {} has a non-finite input",
+                obs.epoch
+            )));
+        }
+        let circuit_breaker = obs.stale_hours >= params.circuit_breaker_stale_hours;
+        let minting_suspended = obs.stale_hours > params.suspension_stale_hours;
+
+        let raw_delta = raw_delta(params, obs, circuit_breaker);
+        let step = (raw_delta * 0.1).clamp(-params.max_epoch_delta, params.max_epoch_delta);
+        cr = (cr + step).clamp(params.min_cr, params.max_cr);
+
+        let effective_cr = if circuit_breaker {
+            (cr + params.circuit_breaker_shift).clamp(params.min_cr, params.max_cr)
+        } else {
+            cr
+        };
+        let allowance = if minting_suspended {
+            0
+        } else {
+            max_mintable(effective_cr, params.base_mint_amount)
+        };
+        if allowance == 0 {
+            halted_epochs += 1;
+        } else if allowance < params.base_mint_amount {
+            throttled_epochs += 1;
+        }
+
+        trajectory.push(EpochProjection {
+            epoch: obs.epoch,
+            raw_delta,
+            cr,
+            effective_cr,
+            circuit_breaker,
+            minting_suspended,
+            max_mintable: allowance,
+        });
+    }
+
+    Ok(SimulationReport {
+        trajectory,
+        final_cr: cr,
+        throttled_epochs,
+        halted_epochs,
+    })
+}
+
+/// Weighted sum of the four CR inputs for one epoch.
+fn raw_delta(params: &CrParameters, obs: &EpochObservation, circuit_breaker: bool) -> f64 {
+    let effective_rate = obs.twap as f64 / params.target_rate as f64;
+    let value_delta = ((1.0 - effective_rate) * 2.0).clamp(-1.0, 1.0);
+    let growth_delta = (-obs.net_node_change_pct * 5.0).clamp(-1.0, 1.0);
+    let oracle_delta = if circuit_breaker {
+        1.0
+    } else if obs.stale_hours >= STALE_WARNING_HOURS {
+        0.5
+    } else {
+        0.0
+    };
+    let velocity_delta = (1.0 - obs.velocity_ratio).clamp(-1.0, 1.0);
+
+    params.value_weight * value_delta
+        + params.growth_weight * growth_delta
+        + params.oracle_weight * oracle_delta
+        + params.velocity_weight * velocity_delta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(twaps: &[u64]) -> Vec<EpochObservation> {
+        twaps
+            .iter()
+            .enumerate()
+            .map(|(i, &twap)| EpochObservation {
+                epoch: i as u32,
+                twap,
+                stale_hours: 0,
+                net_node_change_pct: 0.0,
+                velocity_ratio: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_on_target_history_holds_cr() {
+        let params = CrParameters::default();
+        let report = simulate(&params, &history(&[100_000_000; 5])).expect("simulate");
+        assert_eq!(report.trajectory.len(), 5);
+        assert!((report.final_cr - 1.0).abs() < 1e-9);
+        assert_eq!(report.throttled_epochs, 0);
+        assert_eq!(report.halted_epochs, 0);
+    }
+
+    #[test]
+    fn test_step_limited_by_max_epoch_delta() {
+        let params = CrParameters {
+            max_epoch_delta: 0.01,
+            ..CrParameters::default()
+        };
+        // TWAP at zero maxes out the value input.
+        let report = simulate(&params, &history(&[0; 3])).expect("simulate");
+        for (i, p) in report.trajectory.iter().enumerate() {
+            assert!((p.cr - (1.0 + 0.01 * (i + 1) as f64)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_overvalued_seed_throttles_minting() {
+        // Seeds trading above target push CR down into the throttle band.
+        let params = CrParameters::default();
+        let report = simulate(&params, &history(&[200_000_000; 10])).expect("simulate");
+        assert!(report.final_cr < 1.0);
+        assert!(report.throttled_epochs > 0);
+        let last = report.trajectory.last().expect("trajectory");
+        assert!(last.max_mintable < params.base_mint_amount);
+    }
+
+    #[test]
+    fn test_circuit_breaker_and_suspension() {
+        let params = CrParameters::default();
+        let mut obs = history(&[100_000_000; 3]);
+        obs[1].stale_hours = 13;
+        obs[2].stale_hours = 49;
+        let report = simulate(&params, &obs).expect("simulate");
+
+        let cb = &report.trajectory[1];
+        assert!(cb.circuit_breaker && !cb.minting_suspended);
+        assert!((cb.effective_cr - (cb.cr + 0.3)).abs() < 1e-9);
+
+        let suspended = &report.trajectory[2];
+        assert!(suspended.minting_suspended);
+        assert_eq!(suspended.max_mintable, 0);
+        assert_eq!(report.halted_epochs, 1);
+    }
+
+    #[test]
+    fn test_invalid_inputs_rejected() {
+        let params = CrParameters::default();
+        assert!(simulate(&params, &[]).is_err());
+
+        let bad = CrParameters {
+            min_cr: 2.0,
+            max_cr: 1.0,
+            ..CrParameters::default()
+        };
+        assert!(simulate(&bad, &history(&[1])).is_err());
+
+        let mut obs = history(&[1]);
+        obs[0].velocity_ratio = f64::NAN;
+        assert!(simulate(&params, &obs).is_err());
+    }
+
+    #[test]
+    fn test_partial_parameters_use_defaults() {
+        let params: CrParameters =
+            serde_json::from_str(r#"{"max_epoch_delta": 0.05}"#).expect("deserialize");
+        assert!((params.max_epoch_delta - 0.05).abs() < f64::EPSILON);
+        assert!((params.value_weight - 0.40).abs() < f64::EPSILON);
+    }
+}
@@ -9,7 +9,7 @@
 //! The full Groth16 circuit will be implemented when the ZK infrastructure
 //! is complete.
 
-use ochra_crypto::blake3;
+use ochra_crypto::{blake3, groth16};
 use serde::{Deserialize, Serialize};
 
 use crate::{MintError, Result};
@@ -92,11 +92,46 @@ pub fn generate_minting_proof(input: &MintingProofInput) -> Result<SerializedPro
 ///
 /// `true` if the proof is valid, `false` otherwise.
 pub fn verify_minting_proof(proof: &SerializedProof, public_inputs: &MintingPublicInputs) -> bool {
+    expected_proof(public_inputs).is_some_and(|expected| proof.bytes == expected.to_vec())
+}
+
+/// Verify many minting proofs, `batch_size` at a time (0 selects
+/// [`groth16::DEFAULT_BATCH_SIZE`]).
+///
+/// Returns one result per proof, in order. Each batch is accepted by a
+/// single combined check; if it fails, its proofs are verified one by one to
+/// find the invalid ones. In v1 the combined check compares a digest of all
+/// expected stub proofs; with Groth16 proofs it becomes
+/// [`groth16::verify_batch`].
+pub fn verify_minting_proofs(
+    proofs: &[(SerializedProof, MintingPublicInputs)],
+    batch_size: usize,
+) -> Vec<bool> {
+    groth16::verify_in_batches(
+        proofs,
+        batch_size,
+        |batch| {
+            let mut expected = Vec::with_capacity(batch.len());
+            for (_, inputs) in batch {
+                match expected_proof(inputs) {
+                    Some(proof) => expected.push(proof),
+                    None => return false,
+                }
+            }
+            let expected: Vec<&[u8]> = expected.iter().map(|p| p.as_slice()).collect();
+            let provided: Vec<&[u8]> = batch.iter().map(|(p, _)| p.bytes.as_slice()).collect();
+            blake3::encode_multi_field(&expected) == blake3::encode_multi_field(&provided)
+        },
+        |(proof, inputs)| verify_minting_proof(proof, inputs),
+    )
+}
+
+/// Stub proof expected for the given public inputs, if they are valid.
+fn expected_proof(public_inputs: &MintingPublicInputs) -> Option<[u8; 32]> {
     if public_inputs.total_amount == 0 || public_inputs.receipt_merkle_root == [0u8; 32] {
-        return false;
+        return None;
     }
 
-    // Recompute the expected stub proof
     let amount_bytes = public_inputs.total_amount.to_le_bytes();
     let epoch_bytes = public_inputs.epoch.to_le_bytes();
     let fields = blake3::encode_multi_field(&[
@@ -104,9 +139,7 @@ pub fn verify_minting_proof(proof: &SerializedProof, public_inputs: &MintingPubl
         &amount_bytes,
         &epoch_bytes,
     ]);
-    let expected = blake3::hash(&fields);
-
-    proof.bytes == expected.to_vec()
+    Some(blake3::hash(&fields))
 }
 
 #[cfg(test)]
@@ -198,4 +231,31 @@ mod tests {
         };
         assert!(!verify_minting_proof(&proof, &inputs));
     }
+
+    #[test]
+    fn test_batch_verification_finds_invalid_proof() {
+        let mut proofs: Vec<(SerializedProof, MintingPublicInputs)> = (1..=5u64)
+            .map(|epoch| {
+                let input = MintingProofInput {
+                    receipt_merkle_root: [0xAA; 32],
+                    total_amount: 1_000,
+                    epoch,
+                };
+                let proof = generate_minting_proof(&input).expect("generate proof");
+                let inputs = MintingPublicInputs {
+                    receipt_merkle_root: input.receipt_merkle_root,
+                    total_amount: input.total_amount,
+                    epoch,
+                };
+                (proof, inputs)
+            })
+            .collect();
+        assert_eq!(verify_minting_proofs(&proofs, 2), vec![true; 5]);
+
+        proofs[2].1.total_amount = 2_000;
+        assert_eq!(
+            verify_minting_proofs(&proofs, 2),
+            vec![true, true, false, true, true]
+        );
+    }
 }
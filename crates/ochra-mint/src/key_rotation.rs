@@ -0,0 +1,404 @@
+//! Overlapping VOPRF key epochs.
+//!
+//! The quorum's VOPRF key can be rotated without invalidating outstanding
+//! tokens. Every token carries the [`KeyEpoch`] it was minted under, and the
+//! mint holds up to two keys:
+//!
+//! | Key | Evaluates new tokens | Accepts tokens |
+//! |-----|----------------------|----------------|
+//! | current | yes | always |
+//! | previous | no | until `retired_at + grace_period` |
+//!
+//! ## Rotation ceremony
+//!
+//! Rotation rides on quorum resharing (see `ochra_frost::reshare`). Once a
+//! [`ReshareCeremony`] completes, the new quorum installs a fresh key with
+//! [`MintKeyRing::rotate_after_reshare`] and publishes the returned
+//! [`VoprfKeyAnnouncement`] as a BEP 44 mutable record signed by the quorum
+//! key, salted with [`VOPRF_KEY_RECORD_SALT`] and sequenced by key epoch.
+//! Clients check the record with [`verify_key_record`] before trusting the
+//! new public key for batch proofs.
+
+use ochra_crypto::ed25519::SigningKey;
+use ochra_crypto::voprf::VoprfServerKey;
+use ochra_dht::bep44::{create_mutable_record, DhtRecord};
+use ochra_frost::reshare::{ReshareCeremony, ReshareState};
+
+use crate::voprf_mint::{BlindedToken, EvaluatedTokenBatch, MintServer};
+use crate::{KeyEpoch, MintError, Result};
+
+/// How long the previous key epoch's tokens stay redeemable (7 days).
+pub const DEFAULT_GRACE_PERIOD_SECS: u64 = 7 * 24 * 3600;
+
+/// Salt of the DHT record announcing the current VOPRF public key.
+pub const VOPRF_KEY_RECORD_SALT: &[u8] = b"voprf-key";
+
+/// Encoded size of a [`VoprfKeyAnnouncement`].
+pub const ANNOUNCEMENT_SIZE: usize = 4 + 32 + 8 + 8;
+
+/// The public half of a key epoch, as published in the DHT.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoprfKeyAnnouncement {
+    /// Key epoch identifier.
+    pub key_epoch: KeyEpoch,
+    /// VOPRF public key of the epoch.
+    pub public_key: [u8; 32],
+    /// Unix timestamp from which the key evaluates new tokens.
+    pub activated_at: u64,
+    /// Unix timestamp until which the previous epoch's tokens are accepted.
+    pub previous_accepted_until: Option<u64>,
+}
+
+impl VoprfKeyAnnouncement {
+    /// Fixed-size encoding used as the DHT record value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(ANNOUNCEMENT_SIZE);
+        out.extend_from_slice(&self.key_epoch.to_le_bytes());
+        out.extend_from_slice(&self.public_key);
+        out.extend_from_slice(&self.activated_at.to_le_bytes());
+        out.extend_from_slice(&self.previous_accepted_until.unwrap_or(0).to_le_bytes());
+        out
+    }
+
+    /// Decode an announcement produced by [`VoprfKeyAnnouncement::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != ANNOUNCEMENT_SIZE {
+            return Err(MintError::KeyRotation(format!(
+                "announcement is {} bytes, expected {ANNOUNCEMENT_SIZE}",
+                bytes.len()
+            )));
+        }
+        let mut epoch = [0u8; 4];
+        epoch.copy_from_slice(&bytes[..4]);
+        let mut public_key = [0u8; 32];
+        public_key.copy_from_slice(&bytes[4..36]);
+        let mut activated_at = [0u8; 8];
+        activated_at.copy_from_slice(&bytes[36..44]);
+        let mut until = [0u8; 8];
+        until.copy_from_slice(&bytes[44..52]);
+        let until = u64::from_le_bytes(until);
+
+        Ok(Self {
+            key_epoch: u32::from_le_bytes(epoch),
+            public_key,
+            activated_at: u64::from_le_bytes(activated_at),
+            previous_accepted_until: (until != 0).then_some(until),
+        })
+    }
+}
+
+/// Sign an announcement into a DHT record under the quorum key.
+///
+/// The record's sequence number is the key epoch, so the DHT only ever
+/// replaces an announcement with a newer epoch.
+pub fn publish_key_record(
+    quorum_key: &SigningKey,
+    announcement: &VoprfKeyAnnouncement,
+) -> Result<DhtRecord> {
+    create_mutable_record(
+        quorum_key,
+        VOPRF_KEY_RECORD_SALT,
+        u64::from(announcement.key_epoch),
+        announcement.to_bytes(),
+    )
+    .map_err(|e| MintError::KeyRotation(e.to_string()))
+}
+
+/// Verify a key announcement record against the quorum's public key.
+///
+/// # Errors
+///
+/// - [`MintError::KeyRotation`] if the record is immutable, signed by
+///   another key, carries the wrong salt, or its sequence number does not
+///   match the announced epoch
+pub fn verify_key_record(record: &DhtRecord, quorum_pk: &[u8; 32]) -> Result<VoprfKeyAnnouncement> {
+    let DhtRecord::Mutable {
+        public_key,
+        salt,
+        seq,
+        value,
+        ..
+    } = record
+    else {
+        return Err(MintError::KeyRotation(
+            "key announcement must be a mutable record".to_string(),
+        ));
+    };
+    if public_key != quorum_pk {
+        return Err(MintError::KeyRotation(
+            "key announcement not signed by the quorum".to_string(),
+        ));
+    }
+    if salt.as_slice() != VOPRF_KEY_RECORD_SALT {
+        return Err(MintError::KeyRotation(
+            "key announcement has the wrong salt".to_string(),
+        ));
+    }
+    record
+        .validate()
+        .map_err(|e| MintError::KeyRotation(e.to_string()))?;
+
+    let announcement = VoprfKeyAnnouncement::from_bytes(value)?;
+    if u64::from(announcement.key_epoch) != *seq {
+        return Err(MintError::KeyRotation(format!(
+            "record seq {seq} does not match key epoch {}",
+            announcement.key_epoch
+        )));
+    }
+    Ok(announcement)
+}
+
+/// A VOPRF key bound to its epoch.
+struct EpochKey {
+    epoch: KeyEpoch,
+    key: VoprfServerKey,
+    activated_at: u64,
+}
+
+/// The mint's current VOPRF key plus the previous one during its grace window.
+pub struct MintKeyRing {
+    current: EpochKey,
+    /// Previous key and the time it was retired.
+    previous: Option<(EpochKey, u64)>,
+    grace_period_secs: u64,
+}
+
+impl MintKeyRing {
+    /// Start a key ring at epoch 0.
+    pub fn new(key: VoprfServerKey, now: u64) -> Self {
+        Self {
+            current: EpochKey {
+                epoch: 0,
+                key,
+                activated_at: now,
+            },
+            previous: None,
+            grace_period_secs: DEFAULT_GRACE_PERIOD_SECS,
+        }
+    }
+
+    /// Override the grace window for the previous epoch.
+    pub fn with_grace_period(mut self, secs: u64) -> Self {
+        self.grace_period_secs = secs;
+        self
+    }
+
+    /// Epoch new tokens are minted under.
+    pub fn current_epoch(&self) -> KeyEpoch {
+        self.current.epoch
+    }
+
+    /// Announcement for the current epoch.
+    pub fn announcement(&self) -> VoprfKeyAnnouncement {
+        VoprfKeyAnnouncement {
+            key_epoch: self.current.epoch,
+            public_key: self.current.key.public_key(),
+            activated_at: self.current.activated_at,
+            previous_accepted_until: self
+                .previous
+                .as_ref()
+                .map(|(_, retired_at)| retired_at.saturating_add(self.grace_period_secs)),
+        }
+    }
+
+    /// Evaluate a batch of blinded tokens under the current key.
+    pub fn evaluate_batch(&self, blinded: &[BlindedToken]) -> Result<EvaluatedTokenBatch> {
+        MintServer::evaluate_batch(blinded, &self.current.key, self.current.epoch)
+    }
+
+    /// Key for redeeming a token minted under `epoch`.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::KeyEpochExpired`] if `epoch` is the previous epoch and
+    ///   its grace window has passed
+    /// - [`MintError::UnknownKeyEpoch`] for any other epoch
+    pub fn key_for(&self, epoch: KeyEpoch, now: u64) -> Result<&VoprfServerKey> {
+        if epoch == self.current.epoch {
+            return Ok(&self.current.key);
+        }
+        match &self.previous {
+            Some((prev, retired_at)) if prev.epoch == epoch => {
+                let until = retired_at.saturating_add(self.grace_period_secs);
+                if now <= until {
+                    Ok(&prev.key)
+                } else {
+                    Err(MintError::KeyEpochExpired {
+                        epoch,
+                        retired_at: until,
+                    })
+                }
+            }
+            _ => Err(MintError::UnknownKeyEpoch(epoch)),
+        }
+    }
+
+    /// Whether a token minted under `epoch` is still redeemable.
+    pub fn accepts(&self, epoch: KeyEpoch, now: u64) -> bool {
+        self.key_for(epoch, now).is_ok()
+    }
+
+    /// Drop the previous key once its grace window has passed.
+    ///
+    /// Returns whether a key was dropped.
+    pub fn prune(&mut self, now: u64) -> bool {
+        let expired = self
+            .previous
+            .as_ref()
+            .is_some_and(|(_, retired_at)| now > retired_at.saturating_add(self.grace_period_secs));
+        if expired {
+            self.previous = None;
+        }
+        expired
+    }
+
+    /// Install `new_key` as the next epoch and retire the current key.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::KeyRotation`] if the previous epoch is still inside its
+    ///   grace window (rotating would strand its tokens), the key is
+    ///   unchanged, or the epoch counter would overflow
+    pub fn rotate(&mut self, new_key: VoprfServerKey, now: u64) -> Result<VoprfKeyAnnouncement> {
+        self.prune(now);
+        if let Some((prev, _)) = &self.previous {
+            return Err(MintError::KeyRotation(format!(
+                "key epoch {} is still in its grace window",
+                prev.epoch
+            )));
+        }
+        if new_key.public_key() == self.current.key.public_key() {
+            return Err(MintError::KeyRotation(
+                "new key is identical to the current key".to_string(),
+            ));
+        }
+        let epoch = self
+            .current
+            .epoch
+            .checked_add(1)
+            .ok_or_else(|| MintError::KeyRotation("key epoch overflow".to_string()))?;
+
+        let retired = std::mem::replace(
+            &mut self.current,
+            EpochKey {
+                epoch,
+                key: new_key,
+                activated_at: now,
+            },
+        );
+        self.previous = Some((retired, now));
+
+        tracing::info!(key_epoch = epoch, "rotated VOPRF key");
+        Ok(self.announcement())
+    }
+
+    /// Rotate as the final step of a completed quorum reshare.
+    ///
+    /// `new_key` is the new quorum's freshly generated VOPRF key.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::KeyRotation`] if the ceremony has not completed, or
+    ///   for any reason [`MintKeyRing::rotate`] refuses
+    pub fn rotate_after_reshare(
+        &mut self,
+        ceremony: &ReshareCeremony,
+        new_key: VoprfServerKey,
+        now: u64,
+    ) -> Result<VoprfKeyAnnouncement> {
+        if ceremony.state() != ReshareState::Complete {
+            return Err(MintError::KeyRotation(format!(
+                "reshare ceremony is {}, not complete",
+                ceremony.state()
+            )));
+        }
+        self.rotate(new_key, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voprf_mint::MintClient;
+
+    const T0: u64 = 1_700_000_000;
+
+    fn key() -> VoprfServerKey {
+        VoprfServerKey::generate().expect("generate key")
+    }
+
+    #[test]
+    fn test_tokens_carry_key_epoch() {
+        let mut ring = MintKeyRing::new(key(), T0);
+        ring.rotate(key(), T0 + 10).expect("rotate");
+
+        let (blinded, states) = MintClient::blind_batch(&[100, 200]).expect("blind");
+        let evaluated = ring.evaluate_batch(&blinded).expect("evaluate");
+        let tokens = MintClient::unblind_batch(
+            &blinded,
+            &evaluated,
+            &states,
+            &ring.announcement().public_key,
+        )
+        .expect("unblind");
+        assert!(tokens.iter().all(|t| t.key_epoch == 1));
+    }
+
+    #[test]
+    fn test_previous_epoch_accepted_for_grace_window() {
+        let mut ring = MintKeyRing::new(key(), T0).with_grace_period(100);
+        let announcement = ring.rotate(key(), T0 + 1_000).expect("rotate");
+        assert_eq!(announcement.key_epoch, 1);
+        assert_eq!(announcement.previous_accepted_until, Some(T0 + 1_100));
+
+        assert!(ring.accepts(0, T0 + 1_100));
+        assert!(ring.accepts(1, T0 + 1_100));
+        assert!(matches!(
+            ring.key_for(0, T0 + 1_101),
+            Err(MintError::KeyEpochExpired { epoch: 0, .. })
+        ));
+        assert!(matches!(
+            ring.key_for(7, T0),
+            Err(MintError::UnknownKeyEpoch(7))
+        ));
+    }
+
+    #[test]
+    fn test_rotation_refused_inside_grace_window() {
+        let mut ring = MintKeyRing::new(key(), T0).with_grace_period(100);
+        ring.rotate(key(), T0).expect("rotate");
+        assert!(ring.rotate(key(), T0 + 50).is_err());
+
+        let announcement = ring.rotate(key(), T0 + 101).expect("rotate after grace");
+        assert_eq!(announcement.key_epoch, 2);
+        assert!(!ring.accepts(0, T0 + 101));
+        assert!(ring.accepts(1, T0 + 101));
+    }
+
+    #[test]
+    fn test_rotation_requires_completed_reshare() {
+        let mut ring = MintKeyRing::new(key(), T0);
+        let ceremony = ochra_frost::reshare::initiate_reshare(vec![[1; 32]], vec![[2; 32]], 1)
+            .expect("initiate");
+        assert!(ring.rotate_after_reshare(&ceremony, key(), T0).is_err());
+        assert_eq!(ring.current_epoch(), 0);
+    }
+
+    #[test]
+    fn test_key_record_roundtrip_and_verification() {
+        let quorum = SigningKey::generate();
+        let other = SigningKey::generate();
+        let mut ring = MintKeyRing::new(key(), T0);
+        let announcement = ring.rotate(key(), T0 + 5).expect("rotate");
+
+        let record = publish_key_record(&quorum, &announcement).expect("publish");
+        let quorum_pk = quorum.verifying_key().to_bytes();
+        assert_eq!(
+            verify_key_record(&record, &quorum_pk).expect("verify"),
+            announcement
+        );
+
+        let forged = publish_key_record(&other, &announcement).expect("publish");
+        assert!(verify_key_record(&forged, &quorum_pk).is_err());
+    }
+}
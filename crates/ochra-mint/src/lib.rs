@@ -11,14 +11,25 @@
 //! - [`voprf_mint`] — VOPRF blind token issuance protocol
 //! - [`groth16_mint`] — Minting circuit proof (Section 31.1)
 //! - [`cr_throttle`] — Collateral Ratio throttling
+//! - [`cr_simulation`] — CR what-if projections for governance
+//! - [`key_rotation`] — Overlapping VOPRF key epochs and rotation
+//! - [`session`] — Resumable minting sessions with deadlines
+//! - [`supply`] — Per-epoch supply accounting invariants
 
+pub mod cr_simulation;
 pub mod cr_throttle;
 pub mod groth16_mint;
+pub mod key_rotation;
+pub mod session;
+pub mod supply;
 pub mod voprf_mint;
 
 /// Denomination of a minted token in micro-seeds.
 pub type Denomination = u64;
 
+/// Identifier of the VOPRF key a token was minted under.
+pub type KeyEpoch = u32;
+
 /// Error types for minting operations.
 #[derive(Debug, thiserror::Error)]
 pub enum MintError {
@@ -58,6 +69,27 @@ pub enum MintError {
         max: f64,
     },
 
+    /// Token minted under a key epoch the mint does not know.
+    #[error("unknown VOPRF key epoch {0}")]
+    UnknownKeyEpoch(u32),
+
+    /// Token minted under a retired key whose grace window has passed.
+    #[error("VOPRF key epoch {epoch} retired at {retired_at}")]
+    KeyEpochExpired {
+        /// The token's key epoch.
+        epoch: u32,
+        /// When the grace window for that epoch ended.
+        retired_at: u64,
+    },
+
+    /// CR simulation input rejected.
+    #[error("invalid simulation: {0}")]
+    InvalidSimulation(String),
+
+    /// Key rotation could not proceed.
+    #[error("key rotation error: {0}")]
+    KeyRotation(String),
+
     /// Minting throttled due to insufficient collateral.
     #[error("minting throttled: requested {requested}, max allowed {max_allowed}")]
     Throttled {
@@ -0,0 +1,511 @@
+//! Resumable minting sessions.
+//!
+//! The blinding factors of a mint exist only on the client. If the client
+//! crashes between sending the blinded batch and unblinding the quorum's
+//! answer, the evaluation is useless without them and the collateral that
+//! paid for it is lost. A [`MintSession`] bundles everything needed to
+//! finish the mint so the caller can persist it (encrypted) before sending
+//! anything.
+//!
+//! ## Lifecycle
+//!
+//! 1. [`MintSession::start`] blinds the batch (`blinded`).
+//! 2. [`MintSession::mark_submitted`], persisted before the batch is sent
+//!    (`submitted`).
+//! 3. [`MintSession::complete`] unblinds the evaluation (`completed`), or
+//!    [`MintSession::refund`] releases the collateral after the deadline
+//!    (`refunded`).
+//!
+//! After a restart, [`MintSession::resume_action`] says what to do. Before
+//! the deadline the same [`SessionRequest`] is sent again. The quorum
+//! evaluates through [`MintServer::evaluate_session`], which answers a
+//! repeated session from its [`SessionLedger`] with the earlier evaluation,
+//! never evaluates a session past its deadline, and refuses collateral
+//! another session already spent. A refunded session's collateral can
+//! therefore only be minted again if the quorum never evaluated it.
+
+use std::collections::HashMap;
+
+use ochra_crypto::voprf::VoprfServerKey;
+use serde::{Deserialize, Serialize};
+
+use crate::voprf_mint::{
+    BlindedToken, EvaluatedTokenBatch, MintBlindState, MintClient, MintServer, UnblindedToken,
+};
+use crate::{Denomination, KeyEpoch, MintError, Result};
+
+/// Time allowed for the quorum to evaluate a session, in seconds.
+pub const MINT_SESSION_TTL_SECS: u64 = 3_600;
+
+/// How long the quorum keeps an answered session past its deadline, so a
+/// late resubmission still gets the earlier evaluation.
+pub const ANSWERED_RETENTION_SECS: u64 = 7 * 24 * 3_600;
+
+/// Key a client encrypts its stored sessions under, derived from the PIK.
+pub fn storage_key(pik_secret: &[u8; 32]) -> [u8; 32] {
+    ochra_crypto::blake3::derive_key(ochra_crypto::blake3::contexts::MINT_SESSION_KEY, pik_secret)
+}
+
+/// Where a session stands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MintSessionStatus {
+    /// Blinded but not yet sent.
+    Blinded,
+    /// Sent to the quorum; awaiting evaluation.
+    Submitted,
+    /// Tokens unblinded.
+    Completed,
+    /// Deadline passed without an evaluation; collateral released.
+    Refunded,
+}
+
+impl MintSessionStatus {
+    /// Wire name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Blinded => "blinded",
+            Self::Submitted => "submitted",
+            Self::Completed => "completed",
+            Self::Refunded => "refunded",
+        }
+    }
+
+    /// Whether the session needs no further work.
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Completed | Self::Refunded)
+    }
+}
+
+/// What to do with a session found after a restart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResumeAction {
+    /// Send the blinded batch again under the same session ID.
+    Resubmit,
+    /// The deadline has passed; call [`MintSession::refund`].
+    Refund,
+    /// Nothing left to do.
+    Done,
+}
+
+/// Plain copy of a token's blind state.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct BlindStateRecord {
+    pub(crate) serial: [u8; 32],
+    pub(crate) spend_secret: [u8; 32],
+    pub(crate) denomination: Denomination,
+    pub(crate) input: Vec<u8>,
+    pub(crate) blind_bytes: Vec<u8>,
+}
+
+/// A mint in progress, serializable for crash recovery.
+///
+/// Holds blinding factors and spend secrets: encrypt it at rest.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MintSession {
+    pub session_id: [u8; 16],
+    /// The batch sent to the quorum.
+    pub blinded: Vec<BlindedToken>,
+    states: Vec<BlindStateRecord>,
+    /// Collateral backing the mint (e.g. sealed receipt batch roots).
+    pub collateral_refs: Vec<[u8; 32]>,
+    pub created_at: u64,
+    /// Unix time after which the quorum will not evaluate the session.
+    pub deadline: u64,
+    pub status: MintSessionStatus,
+}
+
+impl MintSession {
+    /// Blind one token per amount and open a session.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::InvalidDenomination`] if any amount is zero
+    /// - [`MintError::Voprf`] if the batch is empty or too large
+    pub fn start(
+        amounts: &[Denomination],
+        collateral_refs: Vec<[u8; 32]>,
+        now: u64,
+    ) -> Result<Self> {
+        let (blinded, states) = MintClient::blind_batch(amounts)?;
+        let mut session_id = [0u8; 16];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut session_id);
+        Ok(Self {
+            session_id,
+            blinded,
+            states: states.iter().map(MintBlindState::to_record).collect(),
+            collateral_refs,
+            created_at: now,
+            deadline: now.saturating_add(MINT_SESSION_TTL_SECS),
+            status: MintSessionStatus::Blinded,
+        })
+    }
+
+    /// The request to send the quorum, the same on every resubmission.
+    pub fn request(&self) -> SessionRequest {
+        SessionRequest {
+            session_id: self.session_id,
+            deadline: self.deadline,
+            collateral_refs: self.collateral_refs.clone(),
+            blinded: self.blinded.clone(),
+        }
+    }
+
+    /// Total micro-seeds being minted.
+    pub fn total(&self) -> u64 {
+        self.blinded.iter().map(|t| t.denomination).sum()
+    }
+
+    /// Whether the quorum's deadline has passed.
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.deadline
+    }
+
+    /// Record that the batch was sent.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::InvalidState`] unless the session is blinded or
+    ///   already submitted
+    pub fn mark_submitted(&mut self) -> Result<()> {
+        self.expect_open("blinded or submitted")?;
+        self.status = MintSessionStatus::Submitted;
+        Ok(())
+    }
+
+    /// What to do with this session after a restart.
+    pub fn resume_action(&self, now: u64) -> ResumeAction {
+        if self.status.is_final() {
+            ResumeAction::Done
+        } else if self.is_expired(now) {
+            ResumeAction::Refund
+        } else {
+            ResumeAction::Resubmit
+        }
+    }
+
+    /// Verify and unblind the quorum's evaluation.
+    ///
+    /// An evaluation is accepted even if it arrives after the deadline: the
+    /// quorum only signs before it, so holding one means the collateral was
+    /// spent on this session.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::InvalidState`] if the session is completed or refunded
+    /// - [`MintError::VerificationFailed`] if the batch proof is invalid
+    pub fn complete(
+        &mut self,
+        evaluated: &EvaluatedTokenBatch,
+        public_key: &[u8; 32],
+    ) -> Result<Vec<UnblindedToken>> {
+        self.expect_open("blinded or submitted")?;
+        let states: Vec<MintBlindState> = self
+            .states
+            .iter()
+            .map(MintBlindState::from_record)
+            .collect();
+        let tokens = MintClient::unblind_batch(&self.blinded, evaluated, &states, public_key)?;
+        self.status = MintSessionStatus::Completed;
+        Ok(tokens)
+    }
+
+    /// Give up on the session and return the collateral to release.
+    ///
+    /// A session never sent can be refunded at once; a submitted one only
+    /// after its deadline.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::InvalidState`] if the session is final, or submitted
+    ///   and still within its deadline
+    pub fn refund(&mut self, now: u64) -> Result<Vec<[u8; 32]>> {
+        self.expect_open("blinded or expired")?;
+        if self.status == MintSessionStatus::Submitted && !self.is_expired(now) {
+            return Err(MintError::InvalidState {
+                expected: "blinded or expired",
+                actual: self.status.as_str(),
+            });
+        }
+        self.status = MintSessionStatus::Refunded;
+        Ok(std::mem::take(&mut self.collateral_refs))
+    }
+
+    fn expect_open(&self, expected: &'static str) -> Result<()> {
+        if self.status.is_final() {
+            return Err(MintError::InvalidState {
+                expected,
+                actual: self.status.as_str(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A session as sent to the quorum.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionRequest {
+    pub session_id: [u8; 16],
+    /// The client's deadline; the quorum will not evaluate after it.
+    pub deadline: u64,
+    pub collateral_refs: Vec<[u8; 32]>,
+    pub blinded: Vec<BlindedToken>,
+}
+
+impl SessionRequest {
+    fn batch_digest(&self) -> [u8; 32] {
+        let denominations: Vec<[u8; 8]> = self
+            .blinded
+            .iter()
+            .map(|t| t.denomination.to_le_bytes())
+            .collect();
+        let mut fields: Vec<&[u8]> = vec![b"mint-session-batch"];
+        for (token, denomination) in self.blinded.iter().zip(&denominations) {
+            fields.push(&token.blinded_element);
+            fields.push(denomination);
+        }
+        for collateral in &self.collateral_refs {
+            fields.push(collateral);
+        }
+        ochra_crypto::blake3::hash(&ochra_crypto::blake3::encode_multi_field(&fields))
+    }
+}
+
+/// An evaluation the quorum has given.
+#[derive(Debug)]
+struct Answered {
+    batch_digest: [u8; 32],
+    deadline: u64,
+    evaluated: EvaluatedTokenBatch,
+}
+
+/// Quorum-side record of answered sessions and the collateral they spent.
+#[derive(Debug, Default)]
+pub struct SessionLedger {
+    answered: HashMap<[u8; 16], Answered>,
+    /// Spent collateral and the session that spent it.
+    spent: HashMap<[u8; 32], [u8; 16]>,
+}
+
+impl SessionLedger {
+    /// Create an empty ledger.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of answered sessions held.
+    pub fn len(&self) -> usize {
+        self.answered.len()
+    }
+
+    /// Whether no session is held.
+    pub fn is_empty(&self) -> bool {
+        self.answered.is_empty()
+    }
+
+    /// Forget sessions more than [`ANSWERED_RETENTION_SECS`] past their
+    /// deadline. Their collateral stays spent.
+    pub fn prune(&mut self, now: u64) {
+        self.answered
+            .retain(|_, answered| now < answered.deadline.saturating_add(ANSWERED_RETENTION_SECS));
+    }
+}
+
+impl MintServer {
+    /// Evaluate a session at most once.
+    ///
+    /// A repeated session gets its earlier evaluation, even past the
+    /// deadline. A new session is evaluated only before its deadline and if
+    /// none of its collateral was spent by another session.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::InvalidState`] if the session was answered for a
+    ///   different batch, its deadline has passed or lies beyond
+    ///   [`MINT_SESSION_TTL_SECS`], or its collateral is already spent
+    /// - any error from [`MintServer::evaluate_batch`]
+    pub fn evaluate_session(
+        ledger: &mut SessionLedger,
+        request: &SessionRequest,
+        server_key: &VoprfServerKey,
+        key_epoch: KeyEpoch,
+        now: u64,
+    ) -> Result<EvaluatedTokenBatch> {
+        let batch_digest = request.batch_digest();
+        if let Some(answered) = ledger.answered.get(&request.session_id) {
+            if answered.batch_digest != batch_digest {
+                return Err(MintError::InvalidState {
+                    expected: "the session's batch",
+                    actual: "a different batch",
+                });
+            }
+            return Ok(answered.evaluated.clone());
+        }
+        if now >= request.deadline {
+            return Err(MintError::InvalidState {
+                expected: "before deadline",
+                actual: "expired",
+            });
+        }
+        if request.deadline > now.saturating_add(MINT_SESSION_TTL_SECS) {
+            return Err(MintError::InvalidState {
+                expected: "deadline within the session TTL",
+                actual: "deadline too far ahead",
+            });
+        }
+        if request
+            .collateral_refs
+            .iter()
+            .any(|c| ledger.spent.contains_key(c))
+        {
+            return Err(MintError::InvalidState {
+                expected: "unspent collateral",
+                actual: "spent collateral",
+            });
+        }
+        let evaluated = Self::evaluate_batch(&request.blinded, server_key, key_epoch)?;
+        for collateral in &request.collateral_refs {
+            ledger.spent.insert(*collateral, request.session_id);
+        }
+        ledger.answered.insert(
+            request.session_id,
+            Answered {
+                batch_digest,
+                deadline: request.deadline,
+                evaluated: evaluated.clone(),
+            },
+        );
+        Ok(evaluated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_survives_serialization_and_completes() {
+        let server_key = VoprfServerKey::generate().expect("generate key");
+        let mut session = MintSession::start(&[10, 20], vec![[1u8; 32]], 1_000).expect("start");
+        session.mark_submitted().expect("submit");
+        assert_eq!(session.total(), 30);
+
+        // Crash: only the persisted bytes survive.
+        let bytes = serde_json::to_vec(&session).expect("serialize");
+        let mut restored: MintSession = serde_json::from_slice(&bytes).expect("deserialize");
+        assert_eq!(restored.resume_action(1_500), ResumeAction::Resubmit);
+
+        let evaluated =
+            MintServer::evaluate_batch(&restored.blinded, &server_key, 0).expect("evaluate");
+        let tokens = restored
+            .complete(&evaluated, &server_key.public_key())
+            .expect("complete");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(restored.status, MintSessionStatus::Completed);
+        assert_eq!(restored.resume_action(1_500), ResumeAction::Done);
+        assert!(restored.refund(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_submitted_session_refunds_only_after_deadline() {
+        let mut session = MintSession::start(&[5], vec![[7u8; 32]], 0).expect("start");
+        session.mark_submitted().expect("submit");
+        assert!(matches!(
+            session.refund(MINT_SESSION_TTL_SECS - 1),
+            Err(MintError::InvalidState { .. })
+        ));
+        assert_eq!(
+            session.resume_action(MINT_SESSION_TTL_SECS),
+            ResumeAction::Refund
+        );
+        let released = session.refund(MINT_SESSION_TTL_SECS).expect("refund");
+        assert_eq!(released, vec![[7u8; 32]]);
+        assert!(session.mark_submitted().is_err());
+    }
+
+    #[test]
+    fn test_unsent_session_refunds_immediately() {
+        let mut session = MintSession::start(&[5], vec![[3u8; 32]], 0).expect("start");
+        assert_eq!(session.refund(1).expect("refund"), vec![[3u8; 32]]);
+        assert_eq!(session.status, MintSessionStatus::Refunded);
+    }
+
+    #[test]
+    fn test_wrong_evaluation_leaves_session_open() {
+        let server_key = VoprfServerKey::generate().expect("generate key");
+        let other_key = VoprfServerKey::generate().expect("generate key");
+        let mut session = MintSession::start(&[1], Vec::new(), 0).expect("start");
+        let evaluated =
+            MintServer::evaluate_batch(&session.blinded, &server_key, 0).expect("evaluate");
+        assert!(session
+            .complete(&evaluated, &other_key.public_key())
+            .is_err());
+        assert_eq!(session.status, MintSessionStatus::Blinded);
+        assert!(session
+            .complete(&evaluated, &server_key.public_key())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_quorum_answers_a_session_once() {
+        let server_key = VoprfServerKey::generate().expect("generate key");
+        let mut ledger = SessionLedger::new();
+        let mut session = MintSession::start(&[10], vec![[4u8; 32]], 0).expect("start");
+        session.mark_submitted().expect("submit");
+        let request = session.request();
+
+        let first = MintServer::evaluate_session(&mut ledger, &request, &server_key, 0, 10)
+            .expect("evaluate");
+        // The client crashed before reading the answer and resubmits,
+        // this time after the deadline
+        let again = MintServer::evaluate_session(
+            &mut ledger,
+            &request,
+            &server_key,
+            0,
+            MINT_SESSION_TTL_SECS + 5,
+        )
+        .expect("repeat");
+        assert_eq!(first.evaluated_elements, again.evaluated_elements);
+        assert_eq!(first.proof, again.proof);
+        assert!(session.complete(&again, &server_key.public_key()).is_ok());
+
+        // A different batch under the same session is refused
+        let mut forged = request.clone();
+        forged.blinded[0].denomination = 1_000;
+        assert!(MintServer::evaluate_session(&mut ledger, &forged, &server_key, 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_quorum_refuses_expired_sessions_and_spent_collateral() {
+        let server_key = VoprfServerKey::generate().expect("generate key");
+        let mut ledger = SessionLedger::new();
+        let expired = MintSession::start(&[1], vec![[5u8; 32]], 0).expect("start");
+        assert!(MintServer::evaluate_session(
+            &mut ledger,
+            &expired.request(),
+            &server_key,
+            0,
+            MINT_SESSION_TTL_SECS
+        )
+        .is_err());
+
+        let spender = MintSession::start(&[1], vec![[6u8; 32]], 0).expect("start");
+        MintServer::evaluate_session(&mut ledger, &spender.request(), &server_key, 0, 1)
+            .expect("evaluate");
+        // The client refunded after losing the answer; the released
+        // collateral cannot mint a second time
+        let reuse = MintSession::start(&[1], vec![[6u8; 32]], 100).expect("start");
+        assert!(
+            MintServer::evaluate_session(&mut ledger, &reuse.request(), &server_key, 0, 101)
+                .is_err()
+        );
+
+        ledger.prune(MINT_SESSION_TTL_SECS + ANSWERED_RETENTION_SECS);
+        assert!(ledger.is_empty());
+        assert!(
+            MintServer::evaluate_session(&mut ledger, &reuse.request(), &server_key, 0, 101)
+                .is_err()
+        );
+    }
+}
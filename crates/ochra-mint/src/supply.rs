@@ -0,0 +1,284 @@
+//! Per-epoch supply accounting invariants.
+//!
+//! Total supply is every Seed in existence: tokens circulating plus value
+//! locked in macro-transaction escrows. Minting and refund issuance add to
+//! it, burns remove from it, and escrow only moves value between the two
+//! buckets. At each epoch boundary [`check_epoch`] reconciles the epoch's
+//! flows against the supply snapshots and reports every invariant that
+//! does not hold:
+//!
+//! - the denominations of issued tokens sum to the amount the quorum
+//!   declared minted
+//! - `Δsupply = minted + refunded − burned`
+//! - `Δescrowed = escrow_locked − escrow_released`
+//! - the report follows the previous epoch's report with no gap
+//!
+//! Amounts are micro-seeds. Deltas are computed in `i128` so no
+//! combination of `u64` inputs can overflow.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Denomination;
+
+/// Supply at an epoch boundary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplySnapshot {
+    /// Spendable tokens outstanding.
+    pub circulating: u64,
+    /// Value locked in open escrows.
+    pub escrowed: u64,
+}
+
+impl SupplySnapshot {
+    /// Circulating plus escrowed.
+    pub fn total(&self) -> i128 {
+        i128::from(self.circulating) + i128::from(self.escrowed)
+    }
+}
+
+/// Everything that moved supply during one epoch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochFlows {
+    pub epoch: u32,
+    /// Denomination of every token the quorum issued.
+    pub minted_denominations: Vec<Denomination>,
+    /// Amount the quorum declared minted for the epoch.
+    pub declared_minted: u64,
+    /// Value destroyed: nullifiers spent with no reissue.
+    pub burned: u64,
+    /// Value reissued by anonymous refunds.
+    pub refunded: u64,
+    /// Value moved into escrow by macro transactions.
+    pub escrow_locked: u64,
+    /// Value leaving escrow, either finalized or timed out.
+    pub escrow_released: u64,
+    /// Supply at the end of the epoch.
+    pub closing: SupplySnapshot,
+}
+
+impl EpochFlows {
+    /// Sum of issued denominations.
+    pub fn minted(&self) -> i128 {
+        self.minted_denominations
+            .iter()
+            .map(|d| i128::from(*d))
+            .sum()
+    }
+}
+
+/// Which invariant failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Invariant {
+    /// Issued denominations do not sum to the declared amount.
+    MintedDenominations,
+    /// `Δsupply ≠ minted + refunded − burned`.
+    Conservation,
+    /// `Δescrowed ≠ escrow_locked − escrow_released`.
+    EscrowBalance,
+    /// The previous report is not for the preceding epoch.
+    Continuity,
+}
+
+impl Invariant {
+    /// Wire name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MintedDenominations => "minted_denominations",
+            Self::Conservation => "conservation",
+            Self::EscrowBalance => "escrow_balance",
+            Self::Continuity => "continuity",
+        }
+    }
+}
+
+/// A failed invariant with the two sides that disagreed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    pub invariant: Invariant,
+    pub expected: i128,
+    pub actual: i128,
+}
+
+/// Audit result for one epoch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplyReport {
+    pub flows: EpochFlows,
+    /// Supply at the start of the epoch, if the previous epoch was
+    /// audited.
+    pub opening: Option<SupplySnapshot>,
+    pub violations: Vec<Violation>,
+}
+
+impl SupplyReport {
+    /// The audited epoch.
+    pub fn epoch(&self) -> u32 {
+        self.flows.epoch
+    }
+
+    /// Whether every invariant held.
+    pub fn is_clean(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Reconcile an epoch's flows against the previous epoch's report.
+///
+/// Without a previous report (the first audited epoch) only the minted
+/// denominations are checked. A previous report for any epoch but the
+/// preceding one is a [`Invariant::Continuity`] violation, and the
+/// balance checks are skipped since the opening supply is unknown.
+pub fn check_epoch(previous: Option<&SupplyReport>, flows: EpochFlows) -> SupplyReport {
+    let mut violations = Vec::new();
+
+    let minted = flows.minted();
+    if minted != i128::from(flows.declared_minted) {
+        violations.push(Violation {
+            invariant: Invariant::MintedDenominations,
+            expected: i128::from(flows.declared_minted),
+            actual: minted,
+        });
+    }
+
+    let opening = match previous {
+        Some(prev) if u64::from(prev.epoch()) + 1 == u64::from(flows.epoch) => {
+            Some(prev.flows.closing)
+        }
+        Some(prev) => {
+            violations.push(Violation {
+                invariant: Invariant::Continuity,
+                expected: i128::from(flows.epoch) - 1,
+                actual: i128::from(prev.epoch()),
+            });
+            None
+        }
+        None => None,
+    };
+
+    if let Some(opening) = opening {
+        let expected = minted + i128::from(flows.refunded) - i128::from(flows.burned);
+        let actual = flows.closing.total() - opening.total();
+        if expected != actual {
+            violations.push(Violation {
+                invariant: Invariant::Conservation,
+                expected,
+                actual,
+            });
+        }
+
+        let expected = i128::from(flows.escrow_locked) - i128::from(flows.escrow_released);
+        let actual = i128::from(flows.closing.escrowed) - i128::from(opening.escrowed);
+        if expected != actual {
+            violations.push(Violation {
+                invariant: Invariant::EscrowBalance,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    SupplyReport {
+        flows,
+        opening,
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genesis() -> SupplyReport {
+        check_epoch(
+            None,
+            EpochFlows {
+                epoch: 10,
+                minted_denominations: vec![500, 500],
+                declared_minted: 1_000,
+                closing: SupplySnapshot {
+                    circulating: 900,
+                    escrowed: 100,
+                },
+                ..EpochFlows::default()
+            },
+        )
+    }
+
+    /// Mint 300, burn 50, refund 20, lock 200 and release 150 of escrow.
+    fn balanced() -> EpochFlows {
+        EpochFlows {
+            epoch: 11,
+            minted_denominations: vec![100, 200],
+            declared_minted: 300,
+            burned: 50,
+            refunded: 20,
+            escrow_locked: 200,
+            escrow_released: 150,
+            closing: SupplySnapshot {
+                circulating: 1_120,
+                escrowed: 150,
+            },
+        }
+    }
+
+    #[test]
+    fn test_balanced_epoch_is_clean() {
+        let first = genesis();
+        assert!(first.is_clean());
+        assert_eq!(first.opening, None);
+
+        let report = check_epoch(Some(&first), balanced());
+        assert!(report.is_clean(), "{:?}", report.violations);
+        assert_eq!(report.opening, Some(first.flows.closing));
+    }
+
+    #[test]
+    fn test_unbacked_supply_breaks_conservation() {
+        let mut flows = balanced();
+        flows.closing.circulating += 7;
+        let report = check_epoch(Some(&genesis()), flows);
+        assert_eq!(
+            report.violations,
+            vec![Violation {
+                invariant: Invariant::Conservation,
+                expected: 270,
+                actual: 277,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_escrow_leak_and_mint_mismatch_reported() {
+        let mut flows = balanced();
+        // Escrow released straight into circulation without being recorded.
+        flows.closing.escrowed -= 30;
+        flows.closing.circulating += 30;
+        flows.declared_minted = 250;
+        let report = check_epoch(Some(&genesis()), flows);
+        let failed: Vec<Invariant> = report.violations.iter().map(|v| v.invariant).collect();
+        assert_eq!(
+            failed,
+            vec![Invariant::MintedDenominations, Invariant::EscrowBalance]
+        );
+    }
+
+    #[test]
+    fn test_gap_skips_balance_checks() {
+        let mut flows = balanced();
+        flows.epoch = 13;
+        flows.closing.circulating = u64::MAX;
+        let report = check_epoch(Some(&genesis()), flows);
+        assert_eq!(report.opening, None);
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].invariant, Invariant::Continuity);
+        assert_eq!(report.violations[0].expected, 12);
+    }
+
+    #[test]
+    fn test_report_round_trips_through_json() {
+        let report = check_epoch(Some(&genesis()), balanced());
+        let json = serde_json::to_string(&report).expect("serialize");
+        let back: SupplyReport = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(back, report);
+    }
+}
@@ -9,12 +9,19 @@
 //! 1. Client: `blind(amount)` -> `(BlindedToken, BlindState)`
 //! 2. Server: `evaluate(blinded, server_key)` -> `EvaluatedToken`
 //! 3. Client: `unblind(evaluated, state)` -> `UnblindedToken`
+//!
+//! Minting many denominations at once uses `blind_batch`, `evaluate_batch`
+//! and `unblind_batch`, which exchange the whole set in one round trip
+//! under a single batch proof.
 
 use ochra_crypto::blake3;
-use ochra_crypto::voprf::{self, BlindState, BlindedElement, EvaluatedElement, VoprfServerKey};
+use ochra_crypto::voprf::{
+    self, BatchProof, BlindState, BlindedElement, EvaluatedElement, VoprfServerKey,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::{Denomination, MintError, Result};
+use crate::session::BlindStateRecord;
+use crate::{Denomination, KeyEpoch, MintError, Result};
 
 /// A blinded token ready to be sent to the server for evaluation.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -30,6 +37,19 @@ pub struct BlindedToken {
 pub struct EvaluatedToken {
     /// The evaluated element bytes.
     pub evaluated_element: Vec<u8>,
+    /// Key epoch the server evaluated under.
+    pub key_epoch: KeyEpoch,
+}
+
+/// A batch of evaluated tokens returned by the server.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EvaluatedTokenBatch {
+    /// The evaluated element bytes, in request order.
+    pub evaluated_elements: Vec<Vec<u8>>,
+    /// Proof covering every evaluation in the batch.
+    pub proof: Vec<u8>,
+    /// Key epoch the server evaluated under.
+    pub key_epoch: KeyEpoch,
 }
 
 /// An unblinded token — the final minted token held by the client.
@@ -43,6 +63,8 @@ pub struct UnblindedToken {
     pub denomination: Denomination,
     /// Spend secret (random, client-held).
     pub spend_secret: [u8; 32],
+    /// Key epoch the token was minted under.
+    pub key_epoch: KeyEpoch,
 }
 
 /// Client-side blind state preserved between `blind` and `unblind` calls.
@@ -57,6 +79,32 @@ pub struct MintBlindState {
     spend_secret: [u8; 32],
 }
 
+impl MintBlindState {
+    /// Plain copy for persistence.
+    pub(crate) fn to_record(&self) -> BlindStateRecord {
+        BlindStateRecord {
+            serial: self.serial,
+            spend_secret: self.spend_secret,
+            denomination: self.denomination,
+            input: self.blind_state.input.clone(),
+            blind_bytes: self.blind_state.blind_bytes.clone(),
+        }
+    }
+
+    /// Rebuild from a record made by [`MintBlindState::to_record`].
+    pub(crate) fn from_record(record: &BlindStateRecord) -> Self {
+        Self {
+            serial: record.serial,
+            blind_state: BlindState {
+                input: record.input.clone(),
+                blind_bytes: record.blind_bytes.clone(),
+            },
+            denomination: record.denomination,
+            spend_secret: record.spend_secret,
+        }
+    }
+}
+
 /// Client-side VOPRF minting operations.
 pub struct MintClient;
 
@@ -85,9 +133,7 @@ impl MintClient {
         let mut spend_secret = [0u8; 32];
         rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut spend_secret);
 
-        // Build VOPRF input: serial || denomination
-        let denom_bytes = amount.to_le_bytes();
-        let input = blake3::encode_multi_field(&[&serial[..], &denom_bytes]);
+        let input = token_input(&serial, amount);
 
         // Blind
         let (blinded_element, blind_state) =
@@ -126,12 +172,119 @@ impl MintClient {
             voprf_output: output.bytes,
             denomination: state.denomination,
             spend_secret: state.spend_secret,
+            key_epoch: evaluated.key_epoch,
         })
     }
+
+    /// Blind one token per amount for a single batched request.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::InvalidDenomination`] if any amount is zero
+    /// - [`MintError::Voprf`] if the batch is empty or too large
+    pub fn blind_batch(
+        amounts: &[Denomination],
+    ) -> Result<(Vec<BlindedToken>, Vec<MintBlindState>)> {
+        if amounts.is_empty() || amounts.len() > voprf::MAX_BATCH_SIZE {
+            return Err(MintError::Voprf(format!(
+                "batch size {} outside 1..={}",
+                amounts.len(),
+                voprf::MAX_BATCH_SIZE
+            )));
+        }
+        amounts
+            .iter()
+            .map(|&amount| Self::blind(amount))
+            .collect::<Result<Vec<_>>>()
+            .map(|pairs| pairs.into_iter().unzip())
+    }
+
+    /// Verify a batched evaluation and unblind every token in it.
+    ///
+    /// `blinded` and `states` are the outputs of [`MintClient::blind_batch`]
+    /// for this request. The batch is rejected as a whole if the proof does
+    /// not verify against the mint's `public_key`.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::VerificationFailed`] if the batch proof is invalid
+    /// - [`MintError::Voprf`] if the batch sizes disagree
+    pub fn unblind_batch(
+        blinded: &[BlindedToken],
+        evaluated: &EvaluatedTokenBatch,
+        states: &[MintBlindState],
+        public_key: &[u8; 32],
+    ) -> Result<Vec<UnblindedToken>> {
+        check_batch_lengths(blinded, evaluated, states)?;
+        let blinded_elements = to_blinded_elements(blinded);
+        let evaluated_elements: Vec<EvaluatedElement> = evaluated
+            .evaluated_elements
+            .iter()
+            .map(|bytes| EvaluatedElement {
+                bytes: bytes.clone(),
+            })
+            .collect();
+        let proof = BatchProof {
+            bytes: evaluated.proof.clone(),
+        };
+        voprf::verify_batch_proof(&blinded_elements, &evaluated_elements, &proof, public_key)
+            .map_err(|_| MintError::VerificationFailed)?;
+        unblind_all(evaluated, states)
+    }
+}
+
+/// VOPRF input of a token: `encode_multi_field([serial, LE64(denomination)])`.
+fn token_input(serial: &[u8; 32], denomination: Denomination) -> Vec<u8> {
+    blake3::encode_multi_field(&[&serial[..], &denomination.to_le_bytes()])
+}
+
+fn check_batch_lengths(
+    blinded: &[BlindedToken],
+    evaluated: &EvaluatedTokenBatch,
+    states: &[MintBlindState],
+) -> Result<()> {
+    if states.len() != blinded.len() || evaluated.evaluated_elements.len() != blinded.len() {
+        return Err(MintError::Voprf(format!(
+            "batch length mismatch: {} states, {} blinded, {} evaluated",
+            states.len(),
+            blinded.len(),
+            evaluated.evaluated_elements.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Unblind every token of an already verified batch.
+fn unblind_all(
+    evaluated: &EvaluatedTokenBatch,
+    states: &[MintBlindState],
+) -> Result<Vec<UnblindedToken>> {
+    states
+        .iter()
+        .zip(&evaluated.evaluated_elements)
+        .map(|(state, bytes)| {
+            MintClient::unblind(
+                &EvaluatedToken {
+                    evaluated_element: bytes.clone(),
+                    key_epoch: evaluated.key_epoch,
+                },
+                state,
+            )
+        })
+        .collect()
+}
+
+fn to_blinded_elements(blinded: &[BlindedToken]) -> Vec<BlindedElement> {
+    blinded
+        .iter()
+        .map(|b| BlindedElement {
+            bytes: b.blinded_element.clone(),
+        })
+        .collect()
 }
 
 impl MintServer {
-    /// Evaluate a blinded token using the server key.
+    /// Evaluate a blinded token using the server key of `key_epoch`.
     ///
     /// The server computes the VOPRF evaluation without learning the
     /// client's token serial.
@@ -139,7 +292,11 @@ impl MintServer {
     /// # Errors
     ///
     /// - [`MintError::Voprf`] if the evaluation fails
-    pub fn evaluate(blinded: &BlindedToken, server_key: &VoprfServerKey) -> Result<EvaluatedToken> {
+    pub fn evaluate(
+        blinded: &BlindedToken,
+        server_key: &VoprfServerKey,
+        key_epoch: KeyEpoch,
+    ) -> Result<EvaluatedToken> {
         let blinded_element = BlindedElement {
             bytes: blinded.blinded_element.clone(),
         };
@@ -150,6 +307,39 @@ impl MintServer {
 
         Ok(EvaluatedToken {
             evaluated_element: evaluated.bytes,
+            key_epoch,
+        })
+    }
+
+    /// Check that a redeemed token was minted under `server_key`, by
+    /// recomputing its VOPRF output from the serial and denomination.
+    pub fn verify_token(token: &UnblindedToken, server_key: &VoprfServerKey) -> bool {
+        voprf::evaluate_direct(server_key, &token_input(&token.serial, token.denomination))
+            .is_ok_and(|output| output.bytes == token.voprf_output)
+    }
+
+    /// Evaluate a batch of blinded tokens under a single proof.
+    ///
+    /// # Errors
+    ///
+    /// - [`MintError::InvalidDenomination`] if any token has a zero amount
+    /// - [`MintError::Voprf`] if the batch is empty or too large
+    pub fn evaluate_batch(
+        blinded: &[BlindedToken],
+        server_key: &VoprfServerKey,
+        key_epoch: KeyEpoch,
+    ) -> Result<EvaluatedTokenBatch> {
+        if let Some(token) = blinded.iter().find(|t| t.denomination == 0) {
+            return Err(MintError::InvalidDenomination(token.denomination));
+        }
+        let (evaluated, proof) = server_key
+            .evaluate_batch(&to_blinded_elements(blinded))
+            .map_err(|e| MintError::Voprf(e.to_string()))?;
+
+        Ok(EvaluatedTokenBatch {
+            evaluated_elements: evaluated.into_iter().map(|e| e.bytes).collect(),
+            proof: proof.bytes,
+            key_epoch,
         })
     }
 }
@@ -190,7 +380,7 @@ mod tests {
         assert_eq!(blinded.denomination, denomination);
 
         // Server evaluates
-        let evaluated = MintServer::evaluate(&blinded, &server_key).expect("evaluate");
+        let evaluated = MintServer::evaluate(&blinded, &server_key, 0).expect("evaluate");
 
         // Client unblinds
         let token = MintClient::unblind(&evaluated, &state).expect("unblind");
@@ -208,7 +398,7 @@ mod tests {
     fn test_nullifier_deterministic() {
         let server_key = VoprfServerKey::generate().expect("generate key");
         let (blinded, state) = MintClient::blind(100).expect("blind");
-        let evaluated = MintServer::evaluate(&blinded, &server_key).expect("evaluate");
+        let evaluated = MintServer::evaluate(&blinded, &server_key, 0).expect("evaluate");
         let token = MintClient::unblind(&evaluated, &state).expect("unblind");
 
         let n1 = token.nullifier();
@@ -220,7 +410,7 @@ mod tests {
     fn test_commitment_deterministic() {
         let server_key = VoprfServerKey::generate().expect("generate key");
         let (blinded, state) = MintClient::blind(100).expect("blind");
-        let evaluated = MintServer::evaluate(&blinded, &server_key).expect("evaluate");
+        let evaluated = MintServer::evaluate(&blinded, &server_key, 0).expect("evaluate");
         let token = MintClient::unblind(&evaluated, &state).expect("unblind");
 
         let c1 = token.commitment();
@@ -229,6 +419,52 @@ mod tests {
         assert_ne!(c1, [0u8; 32]);
     }
 
+    #[test]
+    fn test_batch_mint_round_trip() {
+        let server_key = VoprfServerKey::generate().expect("generate key");
+        let amounts = [1u64, 10, 100, 1_000];
+
+        let (blinded, states) = MintClient::blind_batch(&amounts).expect("blind_batch");
+        let evaluated = MintServer::evaluate_batch(&blinded, &server_key, 0).expect("evaluate");
+        assert_eq!(evaluated.evaluated_elements.len(), amounts.len());
+
+        let tokens =
+            MintClient::unblind_batch(&blinded, &evaluated, &states, &server_key.public_key())
+                .expect("unblind_batch");
+        let minted: Vec<u64> = tokens.iter().map(|t| t.denomination).collect();
+        assert_eq!(minted, amounts);
+    }
+
+    #[test]
+    fn test_batch_rejects_wrong_mint_key() {
+        let server_key = VoprfServerKey::generate().expect("generate key");
+        let other_key = VoprfServerKey::generate().expect("generate key");
+        let (blinded, states) = MintClient::blind_batch(&[5, 6]).expect("blind_batch");
+        let evaluated = MintServer::evaluate_batch(&blinded, &server_key, 0).expect("evaluate");
+
+        let result =
+            MintClient::unblind_batch(&blinded, &evaluated, &states, &other_key.public_key());
+        assert!(matches!(result, Err(MintError::VerificationFailed)));
+        assert!(MintClient::blind_batch(&[5, 0]).is_err());
+        let pk = server_key.public_key();
+        assert!(MintClient::unblind_batch(&blinded, &evaluated, &states[..1], &pk).is_err());
+    }
+
+    #[test]
+    fn test_server_verifies_redeemed_token() {
+        let server_key = VoprfServerKey::generate().expect("generate key");
+        let (blinded, state) = MintClient::blind(300).expect("blind");
+        let evaluated = MintServer::evaluate(&blinded, &server_key, 0).expect("evaluate");
+        let token = MintClient::unblind(&evaluated, &state).expect("unblind");
+        assert!(MintServer::verify_token(&token, &server_key));
+
+        let mut inflated = token.clone();
+        inflated.denomination = 3_000;
+        assert!(!MintServer::verify_token(&inflated, &server_key));
+        let other = VoprfServerKey::generate().expect("generate key");
+        assert!(!MintServer::verify_token(&token, &other));
+    }
+
     #[test]
     fn test_different_tokens_different_nullifiers() {
         let server_key = VoprfServerKey::generate().expect("generate key");
@@ -236,8 +472,8 @@ mod tests {
         let (b1, s1) = MintClient::blind(100).expect("blind1");
         let (b2, s2) = MintClient::blind(100).expect("blind2");
 
-        let e1 = MintServer::evaluate(&b1, &server_key).expect("eval1");
-        let e2 = MintServer::evaluate(&b2, &server_key).expect("eval2");
+        let e1 = MintServer::evaluate(&b1, &server_key, 0).expect("eval1");
+        let e2 = MintServer::evaluate(&b2, &server_key, 0).expect("eval2");
 
         let t1 = MintClient::unblind(&e1, &s1).expect("unblind1");
         let t2 = MintClient::unblind(&e2, &s2).expect("unblind2");
@@ -0,0 +1,547 @@
+//! Space announcements.
+//!
+//! Announcements are the owner's broadcast channel, separate from chat. Each
+//! is an [`Announcement`] signed by the owner's PIK, so any member can relay
+//! it and every member can check it came from the owner. On the wire it is
+//! sealed with the Space's MLS group key and gossiped on the Space's
+//! announcement topic.
+//!
+//! ## Ordering
+//!
+//! The owner numbers announcements `1, 2, 3, …`. A member who sees a gap
+//! was offline or missed a gossip message, and asks any online member for
+//! the missing numbers with an [`AnnouncementBackfillRequest`]; because the
+//! owner's signature travels with each announcement, the answer needs no
+//! trust in the member giving it.
+//!
+//! ## Pinning
+//!
+//! A post may be pinned when made, and pinned or unpinned later with a
+//! [`AnnouncementOp::SetPinned`] announcement. [`AnnouncementFeed::items`]
+//! lists pinned posts first, most recently pinned first, then the rest
+//! newest first.
+
+use std::collections::BTreeMap;
+
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::group::{GroupState, MlsCiphertext};
+use crate::{MlsError, Result};
+
+/// Domain separator of the owner signature.
+const SIGNATURE_DOMAIN: &[u8] = b"space-announcement";
+
+/// MLS application message subtype of an [`AnnouncementBackfillRequest`].
+pub const MSG_BACKFILL_REQUEST: u8 = 0x03;
+
+/// MLS application message subtype of an [`AnnouncementBackfillResponse`].
+pub const MSG_BACKFILL_RESPONSE: u8 = 0x04;
+
+/// Maximum title length in bytes.
+pub const MAX_TITLE_LEN: usize = 200;
+
+/// Maximum body length in bytes.
+pub const MAX_BODY_LEN: usize = 8 * 1024;
+
+/// Maximum announcements requested or returned in one backfill exchange.
+pub const MAX_BACKFILL: usize = 64;
+
+/// What an announcement does.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnnouncementOp {
+    /// A new post.
+    Post {
+        title: String,
+        body: String,
+        pinned: bool,
+    },
+    /// Pin or unpin an earlier post.
+    SetPinned { target: u64, pinned: bool },
+}
+
+/// An owner-signed announcement body.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Announcement {
+    /// Space the announcement belongs to.
+    pub group_id: [u8; 32],
+    /// Position in the Space's announcement sequence, from 1.
+    pub seq: u64,
+    pub op: AnnouncementOp,
+    pub issued_at: u64,
+}
+
+impl Announcement {
+    fn validate(&self) -> Result<()> {
+        if self.seq == 0 {
+            return Err(invalid("sequence numbers start at 1"));
+        }
+        match &self.op {
+            AnnouncementOp::Post { title, body, .. } => {
+                if title.trim().is_empty() {
+                    return Err(invalid("title is empty"));
+                }
+                if title.len() > MAX_TITLE_LEN {
+                    return Err(invalid(&format!("title exceeds {MAX_TITLE_LEN} bytes")));
+                }
+                if body.len() > MAX_BODY_LEN {
+                    return Err(invalid(&format!("body exceeds {MAX_BODY_LEN} bytes")));
+                }
+            }
+            AnnouncementOp::SetPinned { target, .. } => {
+                if *target == 0 || *target >= self.seq {
+                    return Err(invalid("pin target must be an earlier announcement"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid(detail: &str) -> MlsError {
+    MlsError::InvalidAnnouncement(detail.to_string())
+}
+
+/// An announcement with the owner's signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedAnnouncement {
+    pub announcement: Announcement,
+    /// The signing owner's PIK public key.
+    pub owner_pk: [u8; 32],
+    /// CBOR of `announcement`, as signed.
+    encoded: Vec<u8>,
+    pub signature: [u8; 64],
+}
+
+impl SignedAnnouncement {
+    /// Sign `announcement` with the owner's PIK.
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::InvalidAnnouncement`] if the announcement is malformed
+    pub fn sign(owner_key: &SigningKey, announcement: Announcement) -> Result<Self> {
+        announcement.validate()?;
+        let mut encoded = Vec::new();
+        ciborium::into_writer(&announcement, &mut encoded)
+            .map_err(|e| invalid(&format!("encode: {e}")))?;
+        Ok(Self {
+            owner_pk: owner_key.verifying_key().to_bytes(),
+            signature: owner_key.sign(&signing_message(&encoded)).to_bytes(),
+            announcement,
+            encoded,
+        })
+    }
+
+    /// Check the owner's signature.
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::InvalidAnnouncement`] if `owner_pk` did not sign it
+    pub fn verify(&self) -> Result<()> {
+        VerifyingKey::from_bytes(&self.owner_pk)
+            .and_then(|pk| {
+                pk.verify(
+                    &signing_message(&self.encoded),
+                    &Signature::from_bytes(&self.signature),
+                )
+            })
+            .map_err(|_| invalid("bad owner signature"))
+    }
+
+    /// Encode as `owner_pk || signature || CBOR(announcement)`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(96 + self.encoded.len());
+        out.extend_from_slice(&self.owner_pk);
+        out.extend_from_slice(&self.signature);
+        out.extend_from_slice(&self.encoded);
+        out
+    }
+
+    /// Decode and verify an announcement encoded by [`Self::to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::InvalidAnnouncement`] if it is truncated, malformed or
+    ///   not signed by the owner key it carries
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < 96 {
+            return Err(invalid("truncated"));
+        }
+        let mut owner_pk = [0u8; 32];
+        owner_pk.copy_from_slice(&data[..32]);
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&data[32..96]);
+        let encoded = data[96..].to_vec();
+        let announcement: Announcement = ciborium::from_reader(encoded.as_slice())
+            .map_err(|e| invalid(&format!("decode: {e}")))?;
+        announcement.validate()?;
+        let signed = Self {
+            announcement,
+            owner_pk,
+            encoded,
+            signature,
+        };
+        signed.verify()?;
+        Ok(signed)
+    }
+
+    /// Encrypt for the Space's members under the current group epoch.
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::MemberNotFound`] if `sender_id` is not in the group
+    /// - [`MlsError::Encryption`] if encryption fails
+    pub fn seal(&self, group: &mut GroupState, sender_id: &[u8; 32]) -> Result<MlsCiphertext> {
+        if group.group_id() != &self.announcement.group_id {
+            return Err(invalid("announcement is for another Space"));
+        }
+        group.encrypt_message(sender_id, &self.to_bytes())
+    }
+
+    /// Decrypt and verify an announcement sealed with [`Self::seal`].
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::InvalidEpoch`] or [`MlsError::Encryption`] if it cannot
+    ///   be decrypted
+    /// - [`MlsError::InvalidAnnouncement`] if it is malformed, badly signed
+    ///   or for another Space
+    pub fn open(group: &GroupState, ciphertext: &MlsCiphertext) -> Result<Self> {
+        let signed = Self::from_bytes(&group.decrypt_message(ciphertext)?)?;
+        if &signed.announcement.group_id != group.group_id() {
+            return Err(invalid("announcement is for another Space"));
+        }
+        Ok(signed)
+    }
+}
+
+fn signing_message(encoded: &[u8]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(SIGNATURE_DOMAIN.len() + encoded.len());
+    msg.extend_from_slice(SIGNATURE_DOMAIN);
+    msg.extend_from_slice(encoded);
+    msg
+}
+
+/// Ask a Space member for announcements this node is missing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnouncementBackfillRequest {
+    /// [`MSG_BACKFILL_REQUEST`].
+    pub msg_type: u8,
+    pub group_id: [u8; 32],
+    /// Highest sequence number held with no gaps below it.
+    pub after_seq: u64,
+    /// Sequence numbers above `after_seq` that are missing.
+    pub missing: Vec<u64>,
+}
+
+/// Announcements returned for an [`AnnouncementBackfillRequest`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnouncementBackfillResponse {
+    /// [`MSG_BACKFILL_RESPONSE`].
+    pub msg_type: u8,
+    /// Announcements in [`SignedAnnouncement::to_bytes`] form.
+    pub announcements: Vec<Vec<u8>>,
+}
+
+/// A post as shown to members, with pinning applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeedItem {
+    pub seq: u64,
+    pub title: String,
+    pub body: String,
+    pub pinned: bool,
+    pub issued_at: u64,
+}
+
+/// The announcements of one Space that this node holds.
+pub struct AnnouncementFeed {
+    group_id: [u8; 32],
+    entries: BTreeMap<u64, SignedAnnouncement>,
+}
+
+impl AnnouncementFeed {
+    /// An empty feed for `group_id`.
+    pub fn new(group_id: [u8; 32]) -> Self {
+        Self {
+            group_id,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Add an announcement signed by `owner_pk`, the Space's current owner.
+    ///
+    /// Returns `false` if the feed already holds it.
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::InvalidAnnouncement`] if it is for another Space, not
+    ///   signed by `owner_pk`, or reuses a sequence number for different
+    ///   content
+    pub fn insert(&mut self, signed: SignedAnnouncement, owner_pk: &[u8; 32]) -> Result<bool> {
+        if signed.announcement.group_id != self.group_id {
+            return Err(invalid("announcement is for another Space"));
+        }
+        if &signed.owner_pk != owner_pk {
+            return Err(invalid("not signed by the Space owner"));
+        }
+        signed.verify()?;
+        self.restore(signed)
+    }
+
+    /// Add an announcement accepted earlier, possibly under a previous
+    /// owner.
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::InvalidAnnouncement`] if it reuses a held sequence
+    ///   number for different content
+    pub fn restore(&mut self, signed: SignedAnnouncement) -> Result<bool> {
+        let seq = signed.announcement.seq;
+        match self.entries.get(&seq) {
+            Some(held) if held == &signed => Ok(false),
+            Some(_) => Err(invalid(&format!("conflicting announcement {seq}"))),
+            None => {
+                self.entries.insert(seq, signed);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Highest sequence number held, or 0 if none.
+    pub fn latest_seq(&self) -> u64 {
+        self.entries.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// Highest sequence number with every earlier one held.
+    pub fn contiguous_seq(&self) -> u64 {
+        let mut seq = 0;
+        while self.entries.contains_key(&(seq + 1)) {
+            seq += 1;
+        }
+        seq
+    }
+
+    /// Up to `limit` missing sequence numbers below the highest held.
+    pub fn missing(&self, limit: usize) -> Vec<u64> {
+        (self.contiguous_seq() + 1..self.latest_seq())
+            .filter(|seq| !self.entries.contains_key(seq))
+            .take(limit)
+            .collect()
+    }
+
+    /// A request for the gaps in this feed and anything newer.
+    ///
+    /// Sent when a gap is seen and whenever the node comes back online,
+    /// since it cannot know what was posted while it was away.
+    pub fn backfill_request(&self) -> AnnouncementBackfillRequest {
+        AnnouncementBackfillRequest {
+            msg_type: MSG_BACKFILL_REQUEST,
+            group_id: self.group_id,
+            after_seq: self.contiguous_seq(),
+            missing: self.missing(MAX_BACKFILL),
+        }
+    }
+
+    /// Answer a member's backfill request from this feed.
+    ///
+    /// Returns the requested announcements, then any held above the
+    /// requester's highest, up to [`MAX_BACKFILL`] in total.
+    pub fn answer(&self, request: &AnnouncementBackfillRequest) -> AnnouncementBackfillResponse {
+        let requested = request
+            .missing
+            .iter()
+            .filter_map(|seq| self.entries.get(seq))
+            .filter(|s| s.announcement.seq > request.after_seq);
+        let max_missing = request.missing.iter().max().copied().unwrap_or(0);
+        let newer = self
+            .entries
+            .range(request.after_seq.max(max_missing) + 1..)
+            .map(|(_, s)| s);
+        AnnouncementBackfillResponse {
+            msg_type: MSG_BACKFILL_RESPONSE,
+            announcements: requested
+                .chain(newer)
+                .take(MAX_BACKFILL)
+                .map(SignedAnnouncement::to_bytes)
+                .collect(),
+        }
+    }
+
+    /// Posts in display order: pinned first, most recently pinned first,
+    /// then the rest newest first.
+    pub fn items(&self) -> Vec<FeedItem> {
+        // seq of the announcement that last set each post's pin state
+        let mut pins: BTreeMap<u64, (bool, u64)> = BTreeMap::new();
+        for (seq, signed) in &self.entries {
+            match &signed.announcement.op {
+                AnnouncementOp::Post { pinned, .. } => {
+                    pins.entry(*seq).or_insert((*pinned, *seq));
+                }
+                AnnouncementOp::SetPinned { target, pinned } => {
+                    pins.insert(*target, (*pinned, *seq));
+                }
+            }
+        }
+        let mut items: Vec<(u64, FeedItem)> = self
+            .entries
+            .iter()
+            .filter_map(|(seq, signed)| {
+                let AnnouncementOp::Post { title, body, .. } = &signed.announcement.op else {
+                    return None;
+                };
+                let (pinned, pinned_by) = pins.get(seq).copied().unwrap_or((false, *seq));
+                Some((
+                    pinned_by,
+                    FeedItem {
+                        seq: *seq,
+                        title: title.clone(),
+                        body: body.clone(),
+                        pinned,
+                        issued_at: signed.announcement.issued_at,
+                    },
+                ))
+            })
+            .collect();
+        items.sort_by(|(a_pin, a), (b_pin, b)| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| match a.pinned {
+                    true => b_pin.cmp(a_pin),
+                    false => std::cmp::Ordering::Equal,
+                })
+                .then_with(|| b.seq.cmp(&a.seq))
+        });
+        items.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{create_group, KeyPackage};
+
+    const GROUP: [u8; 32] = [1u8; 32];
+
+    fn post(owner: &SigningKey, seq: u64, title: &str, pinned: bool) -> SignedAnnouncement {
+        SignedAnnouncement::sign(
+            owner,
+            Announcement {
+                group_id: GROUP,
+                seq,
+                op: AnnouncementOp::Post {
+                    title: title.to_string(),
+                    body: "body".to_string(),
+                    pinned,
+                },
+                issued_at: seq * 10,
+            },
+        )
+        .expect("sign")
+    }
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let owner = SigningKey::from_bytes(&[7u8; 32]);
+        let mut group = create_group(
+            GROUP,
+            KeyPackage {
+                member_id: [2u8; 32],
+                init_key: [3u8; 32],
+                signing_key: [4u8; 32],
+            },
+        );
+        let signed = post(&owner, 1, "Welcome", false);
+        let sealed = signed.seal(&mut group, &[2u8; 32]).expect("seal");
+        assert_eq!(
+            SignedAnnouncement::open(&group, &sealed).expect("open"),
+            signed
+        );
+
+        let mut tampered = signed.to_bytes();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(SignedAnnouncement::from_bytes(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_feed_rejects_non_owner_and_conflicts() {
+        let owner = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        let owner_pk = owner.verifying_key().to_bytes();
+        let mut feed = AnnouncementFeed::new(GROUP);
+
+        assert!(feed
+            .insert(post(&other, 1, "Hi", false), &owner_pk)
+            .is_err());
+        assert!(feed
+            .insert(post(&owner, 1, "Hi", false), &owner_pk)
+            .expect("insert"));
+        assert!(!feed
+            .insert(post(&owner, 1, "Hi", false), &owner_pk)
+            .expect("duplicate"));
+        assert!(feed
+            .insert(post(&owner, 1, "Bye", false), &owner_pk)
+            .is_err());
+    }
+
+    #[test]
+    fn test_backfill_fills_gaps() {
+        let owner = SigningKey::from_bytes(&[7u8; 32]);
+        let owner_pk = owner.verifying_key().to_bytes();
+        let mut full = AnnouncementFeed::new(GROUP);
+        let mut partial = AnnouncementFeed::new(GROUP);
+        for seq in 1..=5 {
+            full.insert(post(&owner, seq, "t", false), &owner_pk)
+                .expect("insert");
+        }
+        for seq in [1, 3] {
+            partial
+                .insert(post(&owner, seq, "t", false), &owner_pk)
+                .expect("insert");
+        }
+        assert_eq!(partial.contiguous_seq(), 1);
+        assert_eq!(partial.missing(10), vec![2]);
+
+        for bytes in full.answer(&partial.backfill_request()).announcements {
+            let signed = SignedAnnouncement::from_bytes(&bytes).expect("decode");
+            partial.insert(signed, &owner_pk).expect("insert");
+        }
+        assert_eq!(partial.contiguous_seq(), 5);
+        assert!(partial.missing(10).is_empty());
+        assert!(full
+            .answer(&partial.backfill_request())
+            .announcements
+            .is_empty());
+    }
+
+    #[test]
+    fn test_items_order_pinned_first() {
+        let owner = SigningKey::from_bytes(&[7u8; 32]);
+        let owner_pk = owner.verifying_key().to_bytes();
+        let mut feed = AnnouncementFeed::new(GROUP);
+        for signed in [
+            post(&owner, 1, "rules", true),
+            post(&owner, 2, "old news", false),
+            post(&owner, 3, "news", false),
+        ] {
+            feed.insert(signed, &owner_pk).expect("insert");
+        }
+        let pin = SignedAnnouncement::sign(
+            &owner,
+            Announcement {
+                group_id: GROUP,
+                seq: 4,
+                op: AnnouncementOp::SetPinned {
+                    target: 2,
+                    pinned: true,
+                },
+                issued_at: 40,
+            },
+        )
+        .expect("sign");
+        feed.insert(pin, &owner_pk).expect("insert");
+
+        let order: Vec<(u64, bool)> = feed.items().iter().map(|i| (i.seq, i.pinned)).collect();
+        assert_eq!(order, vec![(2, true), (1, true), (3, false)]);
+    }
+}
@@ -0,0 +1,697 @@
+//! Subgroup membership credentials.
+//!
+//! Relays that store or serve a subgroup's content are not members of it, yet
+//! must refuse outsiders. A member proves membership with a
+//! [`MembershipCredential`]: a short-lived VOPRF token, issued by the
+//! subgroup's credential issuer (the Space owner), stating that whoever holds
+//! a given *token key* may access the subgroup.
+//!
+//! ## Blinded issuance
+//!
+//! Token keys are fresh Ed25519 keys generated per credential. The member
+//! never shows one to the issuer: it sends a [`CredentialRequest`] of
+//! blinded VOPRF elements over the token keys, and the issuer, having checked
+//! that the requester is a current member, evaluates them under the key for
+//! the current epoch and expiry bucket and proves the whole batch was
+//! evaluated under that key. The member unblinds each evaluation into the
+//! credential's tag. The issuer sees only blinded elements next to the
+//! member's identity, so neither it nor a relay it colludes with can link a
+//! presented credential to the member it was issued to.
+//!
+//! Expiry times are rounded up to [`EXPIRY_BUCKET_SECS`] and every
+//! credential issued in the same epoch and bucket is evaluated under the
+//! same key, so those credentials look alike. Members check each batch
+//! against the bucket's public key as announced to the whole subgroup, which
+//! stops the issuer tagging one member with a key of its own. Members
+//! request credentials in batches and use each for one relay session so
+//! sessions cannot be linked to each other.
+//!
+//! ## Presentation
+//!
+//! The relay sends a random challenge; the member answers with a
+//! [`Presentation`] carrying the credential and a token-key signature over
+//! the challenge. The relay holds the subgroup's [`CredentialKey`], so it
+//! recomputes the tag for the credential's token key, epoch, and expiry, and
+//! checks that the challenge was one it issued and has not been answered
+//! before.
+//!
+//! ## Revocation
+//!
+//! Credentials carry the subgroup epoch they were issued in. Removing a
+//! member advances the epoch; once relays learn the new epoch
+//! ([`CredentialVerifier::set_epoch`]) credentials from earlier epochs stop
+//! verifying, so a removed member loses access without waiting for expiry.
+
+use std::collections::{HashMap, HashSet};
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use ochra_crypto::voprf::{self, BatchProof, BlindState, BlindedElement, EvaluatedElement};
+
+use crate::subgroup::Subgroup;
+use crate::{MlsError, Result};
+
+/// How long a credential stays valid after issue (6 hours).
+pub const CREDENTIAL_VALIDITY_SECS: u64 = 6 * 3600;
+
+/// Expiry times are rounded up to a multiple of this (1 hour).
+pub const EXPIRY_BUCKET_SECS: u64 = 3600;
+
+/// Maximum credentials issued per request.
+pub const MAX_CREDENTIALS_PER_REQUEST: usize = 16;
+
+/// Encoded size of a [`MembershipCredential`].
+pub const CREDENTIAL_SIZE: usize = 32 + 8 + 32 + 8 + 32;
+
+/// A subgroup's credential secret, held by the issuer and serving relays.
+///
+/// Each epoch and expiry bucket gets its own VOPRF key derived from it.
+#[derive(Clone)]
+pub struct CredentialKey([u8; 32]);
+
+impl CredentialKey {
+    /// Generate a fresh credential secret.
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut secret);
+        Self(secret)
+    }
+
+    /// Restore a credential secret.
+    pub fn from_bytes(secret: [u8; 32]) -> Self {
+        Self(secret)
+    }
+
+    /// The raw secret, for handing to a serving relay.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    fn bucket_key(&self, epoch: u64, expires_at: u64) -> Result<voprf::VoprfServerKey> {
+        let mut material = Vec::with_capacity(48);
+        material.extend_from_slice(&self.0);
+        material.extend_from_slice(&epoch.to_le_bytes());
+        material.extend_from_slice(&expires_at.to_le_bytes());
+        let derived = blake3::derive_key(blake3::contexts::MEMBERSHIP_CREDENTIAL_KEY, &material);
+        voprf::VoprfServerKey::from_bytes(&derived)
+            .map_err(|e| MlsError::KeyDerivation(e.to_string()))
+    }
+
+    /// The public key members check batches for `(epoch, expires_at)`
+    /// against.
+    pub fn public_key(&self, epoch: u64, expires_at: u64) -> Result<[u8; 32]> {
+        Ok(self.bucket_key(epoch, expires_at)?.public_key())
+    }
+}
+
+/// Expiry given to credentials issued at `now`.
+pub fn expiry_bucket(now: u64) -> u64 {
+    (now + CREDENTIAL_VALIDITY_SECS).div_ceil(EXPIRY_BUCKET_SECS) * EXPIRY_BUCKET_SECS
+}
+
+/// VOPRF input for a token key in a subgroup.
+fn token_input(subgroup_id: &[u8; 32], token_pk: &[u8; 32]) -> Vec<u8> {
+    blake3::encode_multi_field(&[b"membership-credential", subgroup_id, token_pk])
+}
+
+/// A grant of subgroup access to a token key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MembershipCredential {
+    /// Subgroup the credential grants access to.
+    pub subgroup_id: [u8; 32],
+    /// Subgroup epoch at issue.
+    pub epoch: u64,
+    /// Public half of the holder's one-off token key.
+    pub token_pk: [u8; 32],
+    /// Unix timestamp after which the credential is invalid.
+    pub expires_at: u64,
+    /// VOPRF output over the token key under the `(epoch, expires_at)` key.
+    pub tag: [u8; 32],
+}
+
+impl MembershipCredential {
+    /// Encode to the fixed-size wire form.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(CREDENTIAL_SIZE);
+        out.extend_from_slice(&self.subgroup_id);
+        out.extend_from_slice(&self.epoch.to_le_bytes());
+        out.extend_from_slice(&self.token_pk);
+        out.extend_from_slice(&self.expires_at.to_le_bytes());
+        out.extend_from_slice(&self.tag);
+        out
+    }
+
+    /// Decode from the fixed-size wire form.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != CREDENTIAL_SIZE {
+            return Err(MlsError::InvalidCredential(format!(
+                "expected {CREDENTIAL_SIZE} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        let take32 = |at: usize| -> [u8; 32] {
+            let mut out = [0u8; 32];
+            out.copy_from_slice(&bytes[at..at + 32]);
+            out
+        };
+        let take8 = |at: usize| -> u64 {
+            let mut out = [0u8; 8];
+            out.copy_from_slice(&bytes[at..at + 8]);
+            u64::from_le_bytes(out)
+        };
+        Ok(Self {
+            subgroup_id: take32(0),
+            epoch: take8(32),
+            token_pk: take32(40),
+            expires_at: take8(72),
+            tag: take32(80),
+        })
+    }
+
+    /// Prove possession of the token key by signing a relay's challenge.
+    pub fn present(&self, token_key: &SigningKey, challenge: &[u8; 32]) -> Presentation {
+        Presentation {
+            credential: self.clone(),
+            challenge_sig: token_key.sign(&challenge_message(challenge)).to_bytes(),
+        }
+    }
+}
+
+/// A credential plus proof that the presenter holds its token key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Presentation {
+    /// The credential being presented.
+    pub credential: MembershipCredential,
+    /// Token-key signature over `"credential-challenge" || challenge`.
+    pub challenge_sig: [u8; 64],
+}
+
+fn challenge_message(challenge: &[u8; 32]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(20 + 32);
+    msg.extend_from_slice(b"credential-challenge");
+    msg.extend_from_slice(challenge);
+    msg
+}
+
+/// Blinded token keys a member sends to the issuer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CredentialRequest {
+    pub subgroup_id: [u8; 32],
+    /// Blinded VOPRF elements, one per requested credential.
+    pub blinded: Vec<Vec<u8>>,
+}
+
+/// The issuer's evaluation of a [`CredentialRequest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CredentialIssuance {
+    pub subgroup_id: [u8; 32],
+    pub epoch: u64,
+    pub expires_at: u64,
+    /// Evaluated elements, in request order.
+    pub evaluated: Vec<Vec<u8>>,
+    /// Composite proof that every element was evaluated under the
+    /// `(epoch, expires_at)` key.
+    pub proof: Vec<u8>,
+}
+
+struct PendingRequest {
+    keys: Vec<SigningKey>,
+    states: Vec<BlindState>,
+    blinded: Vec<BlindedElement>,
+}
+
+/// Member side: one-off token keys waiting for, or holding, credentials.
+#[derive(Default)]
+pub struct CredentialWallet {
+    /// At most one outstanding request per subgroup.
+    pending: HashMap<[u8; 32], PendingRequest>,
+    ready: Vec<(MembershipCredential, SigningKey)>,
+}
+
+impl CredentialWallet {
+    /// Create an empty wallet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate `count` fresh token keys for `subgroup_id` and return them
+    /// blinded, to send to the issuer.
+    ///
+    /// Replaces any request for the subgroup still waiting for an answer.
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::InvalidCredential`] if `count` is zero
+    pub fn request(&mut self, subgroup_id: [u8; 32], count: usize) -> Result<CredentialRequest> {
+        let count = count.min(MAX_CREDENTIALS_PER_REQUEST);
+        let keys: Vec<SigningKey> = (0..count).map(|_| SigningKey::generate()).collect();
+        let inputs: Vec<Vec<u8>> = keys
+            .iter()
+            .map(|k| token_input(&subgroup_id, &k.verifying_key().to_bytes()))
+            .collect();
+        let inputs: Vec<&[u8]> = inputs.iter().map(Vec::as_slice).collect();
+        let (blinded, states) =
+            voprf::blind_batch(&inputs).map_err(|e| MlsError::InvalidCredential(e.to_string()))?;
+        let request = CredentialRequest {
+            subgroup_id,
+            blinded: blinded.iter().map(|b| b.bytes.clone()).collect(),
+        };
+        self.pending.insert(
+            subgroup_id,
+            PendingRequest {
+                keys,
+                states,
+                blinded,
+            },
+        );
+        Ok(request)
+    }
+
+    /// Store the credentials in an issuance answering this wallet's request.
+    ///
+    /// `public_key` is the key announced to the subgroup for the issuance's
+    /// epoch and expiry. Returns how many credentials were stored.
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::InvalidCredential`] if no request for the subgroup is
+    ///   outstanding, or the batch proof does not verify against
+    ///   `public_key`; the request stays outstanding
+    pub fn receive(
+        &mut self,
+        issuance: &CredentialIssuance,
+        public_key: &[u8; 32],
+    ) -> Result<usize> {
+        let pending = self.pending.remove(&issuance.subgroup_id).ok_or_else(|| {
+            MlsError::InvalidCredential("no request outstanding for the subgroup".to_string())
+        })?;
+        let evaluated: Vec<EvaluatedElement> = issuance
+            .evaluated
+            .iter()
+            .map(|bytes| EvaluatedElement {
+                bytes: bytes.clone(),
+            })
+            .collect();
+        let proof = BatchProof {
+            bytes: issuance.proof.clone(),
+        };
+        let outputs = match voprf::finalize_batch(
+            &pending.states,
+            &pending.blinded,
+            &evaluated,
+            &proof,
+            public_key,
+        ) {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                self.pending.insert(issuance.subgroup_id, pending);
+                return Err(MlsError::InvalidCredential(e.to_string()));
+            }
+        };
+        let mut stored = 0;
+        for (key, output) in pending.keys.into_iter().zip(outputs) {
+            let Ok(tag) = <[u8; 32]>::try_from(output.bytes.as_slice()) else {
+                continue;
+            };
+            let credential = MembershipCredential {
+                subgroup_id: issuance.subgroup_id,
+                epoch: issuance.epoch,
+                token_pk: key.verifying_key().to_bytes(),
+                expires_at: issuance.expires_at,
+                tag,
+            };
+            self.ready.push((credential, key));
+            stored += 1;
+        }
+        Ok(stored)
+    }
+
+    /// Take an unexpired credential for one relay session.
+    ///
+    /// Expired credentials are discarded along the way.
+    pub fn take(
+        &mut self,
+        subgroup_id: &[u8; 32],
+        now: u64,
+    ) -> Option<(MembershipCredential, SigningKey)> {
+        self.ready.retain(|(c, _)| c.expires_at > now);
+        let idx = self
+            .ready
+            .iter()
+            .position(|(c, _)| &c.subgroup_id == subgroup_id)?;
+        Some(self.ready.swap_remove(idx))
+    }
+
+    /// Number of unused credentials held for `subgroup_id`.
+    pub fn available(&self, subgroup_id: &[u8; 32], now: u64) -> usize {
+        self.ready
+            .iter()
+            .filter(|(c, _)| &c.subgroup_id == subgroup_id && c.expires_at > now)
+            .count()
+    }
+}
+
+/// Owner side: evaluates blinded credential requests from current members.
+pub struct CredentialIssuer {
+    key: CredentialKey,
+}
+
+impl CredentialIssuer {
+    /// Create an issuer with the subgroup's credential secret.
+    pub fn new(key: CredentialKey) -> Self {
+        Self { key }
+    }
+
+    /// The key relays verify credentials with.
+    pub fn key(&self) -> &CredentialKey {
+        &self.key
+    }
+
+    /// Evaluate `request` for `member_id`.
+    ///
+    /// # Errors
+    ///
+    /// - [`MlsError::MemberNotFound`] if `member_id` is not in the subgroup
+    /// - [`MlsError::InvalidCredential`] if the request is for another
+    ///   subgroup, is empty or larger than [`MAX_CREDENTIALS_PER_REQUEST`],
+    ///   or carries an invalid element
+    pub fn issue(
+        &self,
+        subgroup: &Subgroup,
+        member_id: &[u8; 32],
+        request: &CredentialRequest,
+        now: u64,
+    ) -> Result<CredentialIssuance> {
+        if !subgroup.has_member(member_id) {
+            return Err(MlsError::MemberNotFound(hex::encode(member_id)));
+        }
+        if request.subgroup_id != subgroup.subgroup_id {
+            return Err(MlsError::InvalidCredential(
+                "request is for another subgroup".to_string(),
+            ));
+        }
+        let count = request.blinded.len();
+        if count == 0 || count > MAX_CREDENTIALS_PER_REQUEST {
+            return Err(MlsError::InvalidCredential(format!(
+                "request for {count} credentials outside 1..={MAX_CREDENTIALS_PER_REQUEST}"
+            )));
+        }
+        let expires_at = expiry_bucket(now);
+        let blinded: Vec<BlindedElement> = request
+            .blinded
+            .iter()
+            .map(|bytes| BlindedElement {
+                bytes: bytes.clone(),
+            })
+            .collect();
+        let (evaluated, proof) = self
+            .key
+            .bucket_key(subgroup.epoch, expires_at)?
+            .evaluate_batch(&blinded)
+            .map_err(|e| MlsError::InvalidCredential(e.to_string()))?;
+        Ok(CredentialIssuance {
+            subgroup_id: subgroup.subgroup_id,
+            epoch: subgroup.epoch,
+            expires_at,
+            evaluated: evaluated.into_iter().map(|e| e.bytes).collect(),
+            proof: proof.bytes,
+        })
+    }
+}
+
+/// Relay side: checks presentations for one subgroup.
+pub struct CredentialVerifier {
+    subgroup_id: [u8; 32],
+    key: CredentialKey,
+    min_epoch: u64,
+    /// Challenges sent and not yet answered.
+    outstanding: HashSet<[u8; 32]>,
+}
+
+impl CredentialVerifier {
+    /// Create a verifier for `subgroup_id` holding its credential key.
+    pub fn new(subgroup_id: [u8; 32], key: CredentialKey, epoch: u64) -> Self {
+        Self {
+            subgroup_id,
+            key,
+            min_epoch: epoch,
+            outstanding: HashSet::new(),
+        }
+    }
+
+    /// Accept only credentials issued in `epoch` or later.
+    ///
+    /// The epoch never moves backwards.
+    pub fn set_epoch(&mut self, epoch: u64) {
+        self.min_epoch = self.min_epoch.max(epoch);
+    }
+
+    /// Generate a fresh challenge for a member opening a session.
+    pub fn challenge(&mut self) -> [u8; 32] {
+        let mut challenge = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut challenge);
+        self.outstanding.insert(challenge);
+        challenge
+    }
+
+    /// Verify a presentation answering `challenge`.
+    ///
+    /// The challenge is consumed whether or not verification succeeds.
+    pub fn verify(
+        &mut self,
+        presentation: &Presentation,
+        challenge: &[u8; 32],
+        now: u64,
+    ) -> Result<()> {
+        if !self.outstanding.remove(challenge) {
+            return Err(MlsError::InvalidCredential(
+                "unknown or reused challenge".to_string(),
+            ));
+        }
+        let credential = &presentation.credential;
+        if credential.subgroup_id != self.subgroup_id {
+            return Err(MlsError::InvalidCredential(
+                "credential is for another subgroup".to_string(),
+            ));
+        }
+        if credential.expires_at <= now {
+            return Err(MlsError::InvalidCredential(
+                "credential expired".to_string(),
+            ));
+        }
+        if credential.epoch < self.min_epoch {
+            return Err(MlsError::InvalidCredential(
+                "credential predates the current epoch".to_string(),
+            ));
+        }
+        let expected = voprf::evaluate_direct(
+            &self
+                .key
+                .bucket_key(credential.epoch, credential.expires_at)?,
+            &token_input(&credential.subgroup_id, &credential.token_pk),
+        )
+        .map_err(|e| MlsError::InvalidCredential(e.to_string()))?;
+        if expected.bytes != credential.tag {
+            return Err(MlsError::InvalidCredential(
+                "credential tag does not verify".to_string(),
+            ));
+        }
+        VerifyingKey::from_bytes(&credential.token_pk)
+            .and_then(|vk| {
+                vk.verify(
+                    &challenge_message(challenge),
+                    &Signature::from_bytes(&presentation.challenge_sig),
+                )
+            })
+            .map_err(|_| MlsError::InvalidCredential("signature verification failed".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subgroup::{add_member, create_subgroup, remove_member};
+
+    const NOW: u64 = 1_700_000_000;
+    const OWNER: [u8; 32] = [1u8; 32];
+    const MEMBER: [u8; 32] = [2u8; 32];
+
+    fn setup() -> (Subgroup, CredentialIssuer, CredentialWallet) {
+        let mut subgroup = create_subgroup([0xAA; 32], [0xBB; 32], OWNER);
+        add_member(&mut subgroup, MEMBER).expect("add");
+        let issuer = CredentialIssuer::new(CredentialKey::generate());
+        let mut wallet = CredentialWallet::new();
+        let request = wallet.request(subgroup.subgroup_id, 4).expect("request");
+        let issuance = issuer
+            .issue(&subgroup, &MEMBER, &request, NOW)
+            .expect("issue");
+        let pk = issuer
+            .key()
+            .public_key(issuance.epoch, issuance.expires_at)
+            .expect("public key");
+        assert_eq!(wallet.receive(&issuance, &pk).expect("receive"), 4);
+        (subgroup, issuer, wallet)
+    }
+
+    fn relay(subgroup: &Subgroup, issuer: &CredentialIssuer) -> CredentialVerifier {
+        CredentialVerifier::new(subgroup.subgroup_id, issuer.key().clone(), subgroup.epoch)
+    }
+
+    #[test]
+    fn test_issue_present_verify() {
+        let (subgroup, issuer, mut wallet) = setup();
+        let mut relay = relay(&subgroup, &issuer);
+
+        let (credential, key) = wallet.take(&subgroup.subgroup_id, NOW).expect("credential");
+        assert_eq!(credential.expires_at % EXPIRY_BUCKET_SECS, 0);
+        let challenge = relay.challenge();
+        let presentation = credential.present(&key, &challenge);
+        relay.verify(&presentation, &challenge, NOW).expect("valid");
+
+        // A challenge cannot be answered twice.
+        assert!(relay.verify(&presentation, &challenge, NOW).is_err());
+        assert_eq!(wallet.available(&subgroup.subgroup_id, NOW), 3);
+    }
+
+    #[test]
+    fn test_issuer_never_sees_token_keys() {
+        let (subgroup, issuer, _) = setup();
+        let mut wallet = CredentialWallet::new();
+        let request = wallet.request(subgroup.subgroup_id, 2).expect("request");
+        let issuance = issuer
+            .issue(&subgroup, &MEMBER, &request, NOW)
+            .expect("issue");
+        let pk = issuer
+            .key()
+            .public_key(issuance.epoch, issuance.expires_at)
+            .expect("pk");
+        wallet.receive(&issuance, &pk).expect("receive");
+
+        let (credential, _) = wallet.take(&subgroup.subgroup_id, NOW).expect("credential");
+        for blinded in request.blinded.iter().chain(&issuance.evaluated) {
+            assert_ne!(blinded.as_slice(), credential.token_pk.as_slice());
+            assert_ne!(blinded.as_slice(), credential.tag.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_non_member_refused() {
+        let (subgroup, issuer, mut wallet) = setup();
+        let request = wallet.request(subgroup.subgroup_id, 1).expect("request");
+        assert!(matches!(
+            issuer.issue(&subgroup, &[9u8; 32], &request, NOW),
+            Err(MlsError::MemberNotFound(_))
+        ));
+        let empty = CredentialRequest {
+            subgroup_id: subgroup.subgroup_id,
+            blinded: Vec::new(),
+        };
+        assert!(issuer.issue(&subgroup, &MEMBER, &empty, NOW).is_err());
+    }
+
+    #[test]
+    fn test_batch_under_unannounced_key_refused() {
+        let (subgroup, issuer, mut wallet) = setup();
+        let request = wallet.request(subgroup.subgroup_id, 2).expect("request");
+
+        // An issuer tagging this member with a key of its own.
+        let tagging = CredentialIssuer::new(CredentialKey::generate());
+        let issuance = tagging
+            .issue(&subgroup, &MEMBER, &request, NOW)
+            .expect("issue");
+        let announced = issuer
+            .key()
+            .public_key(issuance.epoch, issuance.expires_at)
+            .expect("pk");
+        assert!(wallet.receive(&issuance, &announced).is_err());
+        assert_eq!(wallet.available(&subgroup.subgroup_id, NOW), 4);
+    }
+
+    #[test]
+    fn test_forged_and_stolen_credentials_rejected() {
+        let (subgroup, issuer, mut wallet) = setup();
+        let mut relay = relay(&subgroup, &issuer);
+        let (credential, key) = wallet.take(&subgroup.subgroup_id, NOW).expect("credential");
+
+        // Issued under a different key.
+        let rogue = CredentialIssuer::new(CredentialKey::generate());
+        let mut rogue_wallet = CredentialWallet::new();
+        let request = rogue_wallet
+            .request(subgroup.subgroup_id, 1)
+            .expect("request");
+        let issuance = rogue
+            .issue(&subgroup, &MEMBER, &request, NOW)
+            .expect("issue");
+        let pk = rogue
+            .key()
+            .public_key(issuance.epoch, issuance.expires_at)
+            .expect("pk");
+        rogue_wallet.receive(&issuance, &pk).expect("receive");
+        let (forged, forged_key) = rogue_wallet
+            .take(&subgroup.subgroup_id, NOW)
+            .expect("credential");
+        let challenge = relay.challenge();
+        assert!(relay
+            .verify(&forged.present(&forged_key, &challenge), &challenge, NOW)
+            .is_err());
+
+        // Genuine tag moved to another token key.
+        let mut moved = credential.clone();
+        moved.token_pk = SigningKey::generate().verifying_key().to_bytes();
+        let challenge = relay.challenge();
+        assert!(relay
+            .verify(&moved.present(&key, &challenge), &challenge, NOW)
+            .is_err());
+
+        // Genuine credential presented without its token key.
+        let challenge = relay.challenge();
+        assert!(relay
+            .verify(
+                &credential.present(&SigningKey::generate(), &challenge),
+                &challenge,
+                NOW
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_expiry_and_epoch_revocation() {
+        let (mut subgroup, issuer, mut wallet) = setup();
+        let mut relay = relay(&subgroup, &issuer);
+        let (credential, key) = wallet.take(&subgroup.subgroup_id, NOW).expect("credential");
+
+        let challenge = relay.challenge();
+        let late = credential.expires_at;
+        assert!(relay
+            .verify(&credential.present(&key, &challenge), &challenge, late)
+            .is_err());
+
+        // Extending the expiry changes the key the tag must verify under.
+        let mut extended = credential.clone();
+        extended.expires_at += EXPIRY_BUCKET_SECS;
+        let challenge = relay.challenge();
+        assert!(relay
+            .verify(&extended.present(&key, &challenge), &challenge, late)
+            .is_err());
+
+        remove_member(&mut subgroup, &MEMBER).expect("remove");
+        relay.set_epoch(subgroup.epoch);
+        let challenge = relay.challenge();
+        assert!(relay
+            .verify(&credential.present(&key, &challenge), &challenge, NOW)
+            .is_err());
+    }
+
+    #[test]
+    fn test_wire_roundtrip() {
+        let (subgroup, _, mut wallet) = setup();
+        let (credential, _) = wallet.take(&subgroup.subgroup_id, NOW).expect("credential");
+        let bytes = credential.to_bytes();
+        assert_eq!(bytes.len(), CREDENTIAL_SIZE);
+        assert_eq!(
+            MembershipCredential::from_bytes(&bytes).expect("decode"),
+            credential
+        );
+        assert!(MembershipCredential::from_bytes(&bytes[1..]).is_err());
+    }
+}
@@ -3,6 +3,7 @@
 //! Provides group creation, member management, key rotation, and
 //! message encryption/decryption using the MLS key schedule.
 
+use ochra_crypto::secret::SecretBytes;
 use ochra_crypto::{blake3, chacha20};
 use serde::{Deserialize, Serialize};
 
@@ -51,9 +52,9 @@ pub struct MlsCiphertext {
 #[derive(Clone, Debug)]
 pub struct GroupSecret {
     /// The group epoch secret (32 bytes).
-    pub epoch_secret: [u8; 32],
+    pub epoch_secret: SecretBytes<32>,
     /// Encryption key derived from the epoch secret.
-    pub encryption_key: [u8; 32],
+    pub encryption_key: SecretBytes<32>,
     /// Nonce base for this epoch.
     pub nonce_base: [u8; 12],
 }
@@ -145,7 +146,7 @@ impl GroupState {
         let nonce = self.next_nonce();
 
         let ciphertext = chacha20::encrypt(
-            &self.secret.encryption_key,
+            self.secret.encryption_key.expose(),
             &nonce,
             plaintext,
             &self.group_id,
@@ -178,7 +179,7 @@ impl GroupState {
         }
 
         chacha20::decrypt(
-            &self.secret.encryption_key,
+            self.secret.encryption_key.expose(),
             &ciphertext.nonce,
             &ciphertext.ciphertext,
             &self.group_id,
@@ -267,7 +268,7 @@ pub fn add_member(
     let welcome = Welcome {
         group_id: group.group_id,
         epoch: group.epoch,
-        encrypted_group_secret: group.secret.epoch_secret.to_vec(),
+        encrypted_group_secret: group.secret.epoch_secret.expose().to_vec(),
         member_ids: group.member_ids(),
     };
 
@@ -328,7 +329,8 @@ fn derive_initial_secret(group_id: &[u8; 32], creator: &KeyPackage) -> GroupSecr
 /// Derive the next epoch secret from the current secret and change data.
 fn derive_next_secret(current: &GroupSecret, change_data: &[u8], epoch: u64) -> GroupSecret {
     let epoch_bytes = epoch.to_le_bytes();
-    let input = blake3::encode_multi_field(&[&current.epoch_secret, change_data, &epoch_bytes]);
+    let input =
+        blake3::encode_multi_field(&[current.epoch_secret.expose(), change_data, &epoch_bytes]);
     let epoch_secret = blake3::derive_key(blake3::contexts::GROUP_SETTINGS_KEY, &input);
     derive_group_secret_from_epoch(&epoch_secret)
 }
@@ -341,8 +343,8 @@ fn derive_group_secret_from_epoch(epoch_secret: &[u8; 32]) -> GroupSecret {
     nonce_base.copy_from_slice(&nonce_full[..12]);
 
     GroupSecret {
-        epoch_secret: *epoch_secret,
-        encryption_key,
+        epoch_secret: SecretBytes::new(*epoch_secret),
+        encryption_key: SecretBytes::new(encryption_key),
         nonce_base,
     }
 }
@@ -416,9 +418,9 @@ mod tests {
         let kp = make_key_package(1);
         let mut group = create_group([0xAA; 32], kp);
 
-        let old_secret = group.current_secret().epoch_secret;
+        let old_secret = group.current_secret().epoch_secret.clone();
         group.update_keys().expect("update");
-        let new_secret = group.current_secret().epoch_secret;
+        let new_secret = group.current_secret().epoch_secret.clone();
 
         assert_eq!(group.epoch(), 1);
         assert_ne!(old_secret, new_secret);
@@ -429,10 +431,10 @@ mod tests {
         let kp1 = make_key_package(1);
         let kp2 = make_key_package(2);
         let group = create_group([0xAA; 32], kp1);
-        let secret_before = group.current_secret().epoch_secret;
+        let secret_before = group.current_secret().epoch_secret.clone();
 
         let (group, _) = add_member(group, kp2).expect("add");
-        let secret_after = group.current_secret().epoch_secret;
+        let secret_after = group.current_secret().epoch_secret.clone();
 
         assert_ne!(secret_before, secret_after);
     }
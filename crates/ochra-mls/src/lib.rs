@@ -8,6 +8,8 @@
 //!
 //! ## Modules
 //!
+//! - [`announcement`] — Owner-signed Space announcements with ordering and backfill.
+//! - [`credential`] — Unlinkable membership credentials checked by relays.
 //! - [`group`] — MLS group lifecycle: create, add/remove members, encrypt/decrypt.
 //! - [`ratchet`] — Double Ratchet for group key derivation using BLAKE3 KDF.
 //! - [`subgroup`] — Subgroup/Channel management within a parent group.
@@ -19,6 +21,8 @@
 //! - **KeyPackage**: A member's public key material used for group joins.
 //! - **Welcome**: An encrypted message allowing a new member to join the group.
 
+pub mod announcement;
+pub mod credential;
 pub mod group;
 pub mod ratchet;
 pub mod subgroup;
@@ -60,6 +64,14 @@ pub enum MlsError {
     /// Subgroup error.
     #[error("subgroup error: {0}")]
     Subgroup(String),
+
+    /// Membership credential is malformed, expired, or fails verification.
+    #[error("invalid membership credential: {0}")]
+    InvalidCredential(String),
+
+    /// Announcement is malformed, badly signed, or conflicts with one held.
+    #[error("invalid announcement: {0}")]
+    InvalidAnnouncement(String),
 }
 
 /// Convenience result type for MLS operations.
@@ -14,6 +14,7 @@
 //! KDF domain separator (mapped to `RATCHET_CHAIN_KEY`).
 
 use ochra_crypto::blake3;
+use ochra_crypto::secret::SecretBytes;
 use serde::{Deserialize, Serialize};
 
 use crate::Result;
@@ -22,7 +23,7 @@ use crate::Result;
 #[derive(Clone, Debug)]
 pub struct MessageKey {
     /// The 32-byte encryption key.
-    pub key: [u8; 32],
+    pub key: SecretBytes<32>,
     /// A 12-byte nonce derived alongside the key.
     pub nonce: [u8; 12],
     /// The ratchet step that produced this key.
@@ -36,7 +37,7 @@ pub struct MessageKey {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RatchetState {
     /// The current chain key (32 bytes).
-    chain_key: [u8; 32],
+    chain_key: SecretBytes<32>,
     /// Current ratchet step (number of advances).
     step: u64,
 }
@@ -52,7 +53,10 @@ impl RatchetState {
     /// * `root_secret` - The 32-byte root secret to initialize the chain.
     pub fn new(root_secret: [u8; 32]) -> Self {
         let chain_key = blake3::derive_key(blake3::contexts::RATCHET_CHAIN_KEY, &root_secret);
-        Self { chain_key, step: 0 }
+        Self {
+            chain_key: SecretBytes::new(chain_key),
+            step: 0,
+        }
     }
 
     /// Derive the message key for the current step without advancing.
@@ -62,7 +66,7 @@ impl RatchetState {
     /// A [`MessageKey`] for encrypting a single message at the current step.
     pub fn derive_message_key(&self) -> MessageKey {
         let step_bytes = self.step.to_le_bytes();
-        let input = blake3::encode_multi_field(&[&self.chain_key, &step_bytes]);
+        let input = blake3::encode_multi_field(&[self.chain_key.expose(), &step_bytes]);
 
         let key = blake3::derive_key(blake3::contexts::RATCHET_MSG_KEY, &input);
 
@@ -71,7 +75,7 @@ impl RatchetState {
         nonce.copy_from_slice(&nonce_full[..12]);
 
         MessageKey {
-            key,
+            key: SecretBytes::new(key),
             nonce,
             step: self.step,
         }
@@ -87,11 +91,11 @@ impl RatchetState {
     /// A new [`RatchetState`] at the next step.
     pub fn advance(&self) -> Result<RatchetState> {
         let step_bytes = self.step.to_le_bytes();
-        let input = blake3::encode_multi_field(&[&self.chain_key, &step_bytes]);
+        let input = blake3::encode_multi_field(&[self.chain_key.expose(), &step_bytes]);
         let new_chain_key = blake3::derive_key(blake3::contexts::RATCHET_CHAIN_KEY, &input);
 
         Ok(RatchetState {
-            chain_key: new_chain_key,
+            chain_key: SecretBytes::new(new_chain_key),
             step: self.step + 1,
         })
     }
@@ -115,7 +119,7 @@ impl RatchetState {
 
     /// Get the current chain key (for diagnostics; do not expose in production).
     pub fn chain_key(&self) -> &[u8; 32] {
-        &self.chain_key
+        self.chain_key.expose()
     }
 }
 
@@ -5,6 +5,7 @@
 //! be a subset of the parent group's members.
 
 use ochra_crypto::blake3;
+use ochra_crypto::secret::SecretBytes;
 use serde::{Deserialize, Serialize};
 
 use crate::{MlsError, Result, MAX_GROUP_SIZE};
@@ -21,7 +22,7 @@ pub struct Subgroup {
     /// Current epoch for this subgroup's key schedule.
     pub epoch: u64,
     /// Subgroup-specific epoch secret.
-    epoch_secret: [u8; 32],
+    epoch_secret: SecretBytes<32>,
 }
 
 /// Create a new subgroup within a parent group.
@@ -47,7 +48,7 @@ pub fn create_subgroup(
         parent_group_id,
         members: vec![creator_id],
         epoch: 0,
-        epoch_secret,
+        epoch_secret: SecretBytes::new(epoch_secret),
     }
 }
 
@@ -75,8 +76,12 @@ pub fn add_member(subgroup: &mut Subgroup, member_id: [u8; 32]) -> Result<()> {
 
     // Derive new epoch secret.
     let epoch_bytes = subgroup.epoch.to_le_bytes();
-    let input = blake3::encode_multi_field(&[&subgroup.epoch_secret, &member_id, &epoch_bytes]);
-    subgroup.epoch_secret = blake3::derive_key(blake3::contexts::GROUP_SETTINGS_KEY, &input);
+    let input =
+        blake3::encode_multi_field(&[subgroup.epoch_secret.expose(), &member_id, &epoch_bytes]);
+    subgroup.epoch_secret = SecretBytes::new(blake3::derive_key(
+        blake3::contexts::GROUP_SETTINGS_KEY,
+        &input,
+    ));
 
     tracing::debug!(
         subgroup_id = hex::encode(subgroup.subgroup_id),
@@ -110,8 +115,12 @@ pub fn remove_member(subgroup: &mut Subgroup, member_id: &[u8; 32]) -> Result<()
 
     // Derive new epoch secret excluding the removed member.
     let epoch_bytes = subgroup.epoch.to_le_bytes();
-    let input = blake3::encode_multi_field(&[&subgroup.epoch_secret, member_id, &epoch_bytes]);
-    subgroup.epoch_secret = blake3::derive_key(blake3::contexts::GROUP_SETTINGS_KEY, &input);
+    let input =
+        blake3::encode_multi_field(&[subgroup.epoch_secret.expose(), member_id, &epoch_bytes]);
+    subgroup.epoch_secret = SecretBytes::new(blake3::derive_key(
+        blake3::contexts::GROUP_SETTINGS_KEY,
+        &input,
+    ));
 
     tracing::debug!(
         subgroup_id = hex::encode(subgroup.subgroup_id),
@@ -136,7 +145,7 @@ impl Subgroup {
 
     /// Get the current epoch secret (for key derivation).
     pub fn epoch_secret(&self) -> &[u8; 32] {
-        &self.epoch_secret
+        self.epoch_secret.expose()
     }
 }
 
@@ -113,6 +113,17 @@ impl NullifierSet {
         }
     }
 
+    /// Add every nullifier present in `other`.
+    ///
+    /// The count becomes the sum of both counts, an upper bound when the
+    /// sets share nullifiers.
+    pub fn union(&mut self, other: &NullifierSet) {
+        for (byte, other) in self.bit_array.iter_mut().zip(&other.bit_array) {
+            *byte |= other;
+        }
+        self.count += other.count;
+    }
+
     /// Return the estimated false positive rate at the current load.
     pub fn false_positive_rate(&self) -> f64 {
         let k = NUM_HASH_FNS as f64;
@@ -151,6 +162,21 @@ impl Default for NullifierSet {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_union_keeps_both_sets() {
+        let mut local = NullifierSet::new();
+        local.insert(&[1u8; 32]);
+        let mut synced = NullifierSet::new();
+        synced.insert(&[2u8; 32]);
+        synced.insert(&[3u8; 32]);
+
+        local.union(&synced);
+        assert!(local.contains(&[1u8; 32]));
+        assert!(local.contains(&[2u8; 32]));
+        assert!(local.contains(&[3u8; 32]));
+        assert_eq!(local.count(), 3);
+    }
+
     #[test]
     fn test_insert_and_contains() {
         let mut set = NullifierSet::new();
@@ -11,10 +11,12 @@
 //! - [`bloom`] — Bloom filter nullifier set
 //! - [`gossip`] — Nullifier gossip protocol
 //! - [`refund`] — Refund commitment tree
+//! - [`smt`] — Sparse Merkle tree accumulator with non-membership proofs
 
 pub mod bloom;
 pub mod gossip;
 pub mod refund;
+pub mod smt;
 
 /// A nullifier value (32-byte hash).
 pub type Nullifier = [u8; 32];
@@ -39,6 +41,10 @@ pub enum NullifierError {
     #[error("invalid gossip message: {0}")]
     InvalidGossip(String),
 
+    /// A non-membership proof did not verify against the accumulator root.
+    #[error("invalid nullifier non-membership proof")]
+    InvalidProof,
+
     /// Refund tree error.
     #[error("refund tree error: {0}")]
     RefundError(String),
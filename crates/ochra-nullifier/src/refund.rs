@@ -3,6 +3,7 @@
 //! The refund tree tracks refund commitments for tokens that need to be
 //! returned (e.g., escrow timeouts, disputed transactions). Each commitment
 //! is a 32-byte hash, and the tree provides a Merkle root for epoch snapshots.
+//! A [`RefundPath`] proves a commitment's membership under that root.
 
 use ochra_crypto::blake3;
 use serde::{Deserialize, Serialize};
@@ -18,6 +19,32 @@ pub struct RefundEntry {
     pub epoch: u64,
 }
 
+/// Sibling hashes from a commitment's leaf up to the refund tree root.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefundPath {
+    /// Position of the leaf in the tree.
+    pub index: u64,
+    /// Sibling at each level, leaf level first.
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl RefundPath {
+    /// Whether this path leads from `commitment` to `root`.
+    pub fn verify(&self, commitment: &[u8; 32], root: &[u8; 32]) -> bool {
+        let mut node = blake3::merkle_leaf(commitment);
+        let mut index = self.index;
+        for sibling in &self.siblings {
+            node = if index & 1 == 0 {
+                blake3::merkle_inner(&node, sibling)
+            } else {
+                blake3::merkle_inner(sibling, &node)
+            };
+            index >>= 1;
+        }
+        index == 0 && node == *root
+    }
+}
+
 /// A tree of refund commitments providing a Merkle root for epoch snapshots.
 pub struct RefundTree {
     /// The list of refund commitment entries.
@@ -77,6 +104,38 @@ impl RefundTree {
         layer[0]
     }
 
+    /// Membership path for `commitment` under the current root.
+    ///
+    /// Returns `None` if the commitment is not in the tree.
+    pub fn path(&self, commitment: &[u8; 32]) -> Option<RefundPath> {
+        let position = self
+            .commitments
+            .iter()
+            .position(|e| &e.commitment == commitment)?;
+
+        let mut layer: Vec<[u8; 32]> = self
+            .commitments
+            .iter()
+            .map(|entry| blake3::merkle_leaf(&entry.commitment))
+            .collect();
+        let mut index = position;
+        let mut siblings = Vec::new();
+        while layer.len() > 1 {
+            // Odd node: its sibling is itself, as in get_merkle_root
+            let sibling = layer.get(index ^ 1).unwrap_or(&layer[index]);
+            siblings.push(*sibling);
+            layer = layer
+                .chunks(2)
+                .map(|pair| blake3::merkle_inner(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            index /= 2;
+        }
+        Some(RefundPath {
+            index: position as u64,
+            siblings,
+        })
+    }
+
     /// Prune all entries from the given epoch or earlier.
     ///
     /// # Arguments
@@ -191,6 +250,25 @@ mod tests {
         assert_eq!(epoch2.len(), 2);
     }
 
+    #[test]
+    fn test_paths_verify_against_root() {
+        let mut tree = RefundTree::new();
+        for i in 0..5u8 {
+            tree.add_commitment([i; 32], 1);
+        }
+        let root = tree.get_merkle_root();
+        for i in 0..5u8 {
+            let path = tree.path(&[i; 32]).expect("path");
+            assert!(path.verify(&[i; 32], &root));
+            assert!(!path.verify(&[9; 32], &root));
+        }
+        assert!(tree.path(&[9; 32]).is_none());
+
+        let mut path = tree.path(&[4; 32]).expect("path");
+        path.index = 0;
+        assert!(!path.verify(&[4; 32], &root));
+    }
+
     #[test]
     fn test_derive_refund_commitment_deterministic() {
         let c1 = derive_refund_commitment(&[0xAA; 32], 1000);
@@ -0,0 +1,382 @@
+//! Sparse Merkle tree nullifier accumulator (Section 12.4).
+//!
+//! The Bloom filter admits false positives, so an honest spend can be
+//! flagged as a double-spend. Quorum members, who hold the full
+//! NullifierSet, can also maintain this accumulator and publish its root in
+//! the signed [`EpochState`](ochra_types::network::EpochState). A spender
+//! whose nullifier hits the Bloom filter attaches a [`NullifierProof`] of
+//! non-membership against that root; the Bloom filter remains the fast
+//! pre-check and the proof is only consulted when it says "present".
+//!
+//! ## Layout
+//!
+//! The tree is keyed by the nullifier's bits, most significant first, and
+//! kept compact: a subtree holding a single nullifier is collapsed into one
+//! leaf at the highest depth where it is alone.
+//!
+//! - Empty subtree: 32 zero bytes
+//! - Leaf: `BLAKE3::merkle_leaf(nullifier)`
+//! - Inner node: `BLAKE3::merkle_inner(left, right)`
+
+use serde::{Deserialize, Serialize};
+
+use crate::bloom::NullifierSet;
+use crate::{Nullifier, NullifierError, Result};
+
+/// Hash of an empty subtree.
+pub const EMPTY_HASH: [u8; 32] = [0u8; 32];
+
+/// Depth of the tree (one level per nullifier bit).
+pub const TREE_DEPTH: usize = 256;
+
+/// Bit `depth` of a key, most significant bit first.
+fn bit(key: &Nullifier, depth: usize) -> bool {
+    (key[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+/// Fold a subtree hash up to the root along `key`'s path.
+fn fold(key: &Nullifier, mut hash: [u8; 32], siblings: &[[u8; 32]]) -> [u8; 32] {
+    for (depth, sibling) in siblings.iter().enumerate().rev() {
+        hash = if bit(key, depth) {
+            ochra_crypto::blake3::merkle_inner(sibling, &hash)
+        } else {
+            ochra_crypto::blake3::merkle_inner(&hash, sibling)
+        };
+    }
+    hash
+}
+
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Leaf(Nullifier),
+    Inner {
+        hash: [u8; 32],
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+}
+
+impl Node {
+    fn hash(&self) -> [u8; 32] {
+        match self {
+            Node::Empty => EMPTY_HASH,
+            Node::Leaf(key) => ochra_crypto::blake3::merkle_leaf(key),
+            Node::Inner { hash, .. } => *hash,
+        }
+    }
+
+    fn inner(left: Node, right: Node) -> Node {
+        Node::Inner {
+            hash: ochra_crypto::blake3::merkle_inner(&left.hash(), &right.hash()),
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Build the subtree at `depth` holding two distinct keys.
+    fn split(a: Nullifier, b: Nullifier, depth: usize) -> Node {
+        match (bit(&a, depth), bit(&b, depth)) {
+            (false, false) => Node::inner(Node::split(a, b, depth + 1), Node::Empty),
+            (true, true) => Node::inner(Node::Empty, Node::split(a, b, depth + 1)),
+            (false, true) => Node::inner(Node::Leaf(a), Node::Leaf(b)),
+            (true, false) => Node::inner(Node::Leaf(b), Node::Leaf(a)),
+        }
+    }
+
+    fn insert(&mut self, key: Nullifier, depth: usize) -> bool {
+        match self {
+            Node::Empty => {
+                *self = Node::Leaf(key);
+                true
+            }
+            Node::Leaf(existing) if *existing == key => false,
+            Node::Leaf(existing) => {
+                *self = Node::split(*existing, key, depth);
+                true
+            }
+            Node::Inner { hash, left, right } => {
+                let inserted = if bit(&key, depth) {
+                    right.insert(key, depth + 1)
+                } else {
+                    left.insert(key, depth + 1)
+                };
+                if inserted {
+                    *hash = ochra_crypto::blake3::merkle_inner(&left.hash(), &right.hash());
+                }
+                inserted
+            }
+        }
+    }
+}
+
+/// Membership or non-membership proof for one nullifier.
+///
+/// The path descends from the root along the nullifier's bits until it
+/// reaches an empty subtree or a leaf.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullifierProof {
+    /// Sibling hashes from the root down.
+    pub siblings: Vec<[u8; 32]>,
+    /// Nullifier in the leaf the path ended at, or `None` for an empty
+    /// subtree.
+    pub terminal: Option<Nullifier>,
+}
+
+impl NullifierProof {
+    /// Verify that `nullifier` is not in the tree with root `root`.
+    pub fn verify_absent(&self, root: &[u8; 32], nullifier: &Nullifier) -> bool {
+        if self.siblings.len() > TREE_DEPTH {
+            return false;
+        }
+        let terminal = match &self.terminal {
+            None => EMPTY_HASH,
+            Some(other) => {
+                // The leaf must sit on the nullifier's path and hold a
+                // different key.
+                let on_path = (0..self.siblings.len()).all(|d| bit(other, d) == bit(nullifier, d));
+                if other == nullifier || !on_path {
+                    return false;
+                }
+                ochra_crypto::blake3::merkle_leaf(other)
+            }
+        };
+        fold(nullifier, terminal, &self.siblings) == *root
+    }
+
+    /// Verify that `nullifier` is in the tree with root `root`.
+    pub fn verify_present(&self, root: &[u8; 32], nullifier: &Nullifier) -> bool {
+        self.siblings.len() <= TREE_DEPTH
+            && self.terminal.as_ref() == Some(nullifier)
+            && fold(
+                nullifier,
+                ochra_crypto::blake3::merkle_leaf(nullifier),
+                &self.siblings,
+            ) == *root
+    }
+}
+
+/// Sparse Merkle tree over every spent nullifier.
+#[derive(Debug)]
+pub struct SparseMerkleTree {
+    root: Node,
+    count: usize,
+}
+
+impl SparseMerkleTree {
+    /// Create an empty tree.
+    pub fn new() -> Self {
+        Self {
+            root: Node::Empty,
+            count: 0,
+        }
+    }
+
+    /// Insert a nullifier. Returns `false` if it was already present.
+    pub fn insert(&mut self, nullifier: &Nullifier) -> bool {
+        let inserted = self.root.insert(*nullifier, 0);
+        if inserted {
+            self.count += 1;
+        }
+        inserted
+    }
+
+    /// Insert a nullifier, rejecting one already spent.
+    ///
+    /// # Errors
+    ///
+    /// - [`NullifierError::DoubleSpend`] if the nullifier is already present
+    pub fn insert_checked(&mut self, nullifier: &Nullifier) -> Result<()> {
+        if self.insert(nullifier) {
+            Ok(())
+        } else {
+            Err(NullifierError::DoubleSpend)
+        }
+    }
+
+    /// Whether the nullifier has been spent. Unlike the Bloom filter, this
+    /// is exact.
+    pub fn contains(&self, nullifier: &Nullifier) -> bool {
+        self.proof(nullifier).terminal.as_ref() == Some(nullifier)
+    }
+
+    /// Current root hash.
+    pub fn root(&self) -> [u8; 32] {
+        self.root.hash()
+    }
+
+    /// Number of nullifiers in the tree.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Build the proof for a nullifier: membership if it is present,
+    /// non-membership otherwise.
+    pub fn proof(&self, nullifier: &Nullifier) -> NullifierProof {
+        let mut siblings = Vec::new();
+        let mut node = &self.root;
+        loop {
+            match node {
+                Node::Empty => {
+                    return NullifierProof {
+                        siblings,
+                        terminal: None,
+                    }
+                }
+                Node::Leaf(key) => {
+                    return NullifierProof {
+                        siblings,
+                        terminal: Some(*key),
+                    }
+                }
+                Node::Inner { left, right, .. } => {
+                    let (next, sibling) = if bit(nullifier, siblings.len()) {
+                        (right, left)
+                    } else {
+                        (left, right)
+                    };
+                    siblings.push(sibling.hash());
+                    node = next;
+                }
+            }
+        }
+    }
+}
+
+impl Default for SparseMerkleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check an incoming spend against the local Bloom filter.
+///
+/// A Bloom miss is definitive and the spend is accepted. A hit is treated
+/// as a double-spend unless the spender attached a non-membership proof
+/// against a quorum-signed accumulator root, which settles the false
+/// positive.
+///
+/// # Errors
+///
+/// - [`NullifierError::DoubleSpend`] on a Bloom hit with no proof attached
+/// - [`NullifierError::InvalidProof`] if the attached proof does not verify
+pub fn check_spend(
+    bloom: &NullifierSet,
+    nullifier: &Nullifier,
+    contested: Option<(&NullifierProof, &[u8; 32])>,
+) -> Result<()> {
+    if !bloom.contains(nullifier) {
+        return Ok(());
+    }
+    match contested {
+        None => Err(NullifierError::DoubleSpend),
+        Some((proof, root)) if proof.verify_absent(root, nullifier) => {
+            tracing::debug!("bloom false positive settled by non-membership proof");
+            Ok(())
+        }
+        Some(_) => Err(NullifierError::InvalidProof),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(first: u8, last: u8) -> Nullifier {
+        let mut k = [0u8; 32];
+        k[0] = first;
+        k[31] = last;
+        k
+    }
+
+    #[test]
+    fn test_proofs_verify_against_root() {
+        let mut tree = SparseMerkleTree::new();
+        assert_eq!(tree.root(), EMPTY_HASH);
+        let spent = [key(0x00, 1), key(0x00, 2), key(0x80, 0), key(0xC0, 0)];
+        for k in &spent {
+            assert!(tree.insert(k));
+        }
+        assert!(!tree.insert(&spent[0]));
+        assert_eq!(tree.len(), 4);
+        let root = tree.root();
+
+        for k in &spent {
+            let proof = tree.proof(k);
+            assert!(proof.verify_present(&root, k));
+            assert!(!proof.verify_absent(&root, k));
+            assert!(tree.contains(k));
+        }
+        for k in [key(0x00, 3), key(0x40, 0), key(0xFF, 0xFF)] {
+            let proof = tree.proof(&k);
+            assert!(proof.verify_absent(&root, &k));
+            assert!(!proof.verify_present(&root, &k));
+            assert!(!tree.contains(&k));
+        }
+    }
+
+    #[test]
+    fn test_root_is_independent_of_insertion_order() {
+        let keys = [key(0x10, 0), key(0x11, 0), key(0xF0, 1), key(0xF0, 2)];
+        let mut a = SparseMerkleTree::new();
+        let mut b = SparseMerkleTree::new();
+        for k in &keys {
+            a.insert(k);
+        }
+        for k in keys.iter().rev() {
+            b.insert(k);
+        }
+        assert_eq!(a.root(), b.root());
+    }
+
+    #[test]
+    fn test_stale_or_forged_proof_rejected() {
+        let mut tree = SparseMerkleTree::new();
+        tree.insert(&key(0x00, 1));
+        let absent = key(0x80, 0);
+        let proof = tree.proof(&absent);
+        assert!(proof.verify_absent(&tree.root(), &absent));
+
+        // Spent after the proof was made.
+        tree.insert(&absent);
+        assert!(!proof.verify_absent(&tree.root(), &absent));
+
+        // A leaf off the nullifier's path does not prove absence.
+        let forged = NullifierProof {
+            siblings: vec![EMPTY_HASH],
+            terminal: Some(key(0x00, 1)),
+        };
+        assert!(!forged.verify_absent(&tree.root(), &key(0x80, 9)));
+    }
+
+    #[test]
+    fn test_check_spend_settles_bloom_false_positive() {
+        let mut bloom = NullifierSet::new();
+        let mut tree = SparseMerkleTree::new();
+        let spent = key(0x01, 0);
+        let honest = key(0x02, 0);
+        tree.insert(&spent);
+        bloom.insert(&spent);
+        // Stand in for a false positive on the honest nullifier.
+        bloom.insert(&honest);
+        let root = tree.root();
+
+        assert!(check_spend(&bloom, &key(0x03, 0), None).is_ok());
+        assert!(matches!(
+            check_spend(&bloom, &honest, None),
+            Err(NullifierError::DoubleSpend)
+        ));
+        let proof = tree.proof(&honest);
+        assert!(check_spend(&bloom, &honest, Some((&proof, &root))).is_ok());
+        let proof = tree.proof(&spent);
+        assert!(matches!(
+            check_spend(&bloom, &spent, Some((&proof, &root))),
+            Err(NullifierError::InvalidProof)
+        ));
+    }
+}
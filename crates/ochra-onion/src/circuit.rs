@@ -20,6 +20,7 @@
 use std::time::Instant;
 
 use ochra_crypto::blake3::contexts;
+use ochra_crypto::secret::SecretBytes;
 use ochra_crypto::x25519::{X25519PublicKey, X25519StaticSecret};
 use ochra_types::network::RelayDescriptor;
 
@@ -29,11 +30,11 @@ use crate::{OnionError, Result, CIRCUIT_HOPS, CIRCUIT_LIFETIME_SECS};
 #[derive(Clone)]
 pub struct HopKeys {
     /// Symmetric encryption key for this hop (32 bytes).
-    pub hop_key: [u8; 32],
+    pub hop_key: SecretBytes<32>,
     /// MAC key for this hop (32 bytes).
-    pub hop_mac: [u8; 32],
+    pub hop_mac: SecretBytes<32>,
     /// Pad key for XOR stream generation (32 bytes).
-    pub hop_pad: [u8; 32],
+    pub hop_pad: SecretBytes<32>,
     /// Nonce for AEAD operations at this hop (12 bytes).
     pub hop_nonce: [u8; 12],
 }
@@ -62,6 +63,8 @@ pub struct Circuit {
     ephemeral_pk: X25519PublicKey,
     /// When this circuit was created.
     created_at: Instant,
+    /// Seconds until the circuit must be rotated.
+    lifetime_secs: u64,
     /// Circuit identifier (random 16 bytes).
     circuit_id: [u8; 16],
 }
@@ -97,9 +100,10 @@ impl Circuit {
         &self.hops[2]
     }
 
-    /// Check whether this circuit has expired (exceeded its 10-minute lifetime).
+    /// Check whether this circuit has expired (exceeded its lifetime, 10
+    /// minutes on mainnet).
     pub fn is_expired(&self) -> bool {
-        self.created_at.elapsed().as_secs() >= CIRCUIT_LIFETIME_SECS
+        self.created_at.elapsed().as_secs() >= self.lifetime_secs
     }
 
     /// Return the age of this circuit in seconds.
@@ -110,7 +114,7 @@ impl Circuit {
     /// Return the remaining lifetime in seconds (0 if expired).
     pub fn remaining_secs(&self) -> u64 {
         let elapsed = self.created_at.elapsed().as_secs();
-        CIRCUIT_LIFETIME_SECS.saturating_sub(elapsed)
+        self.lifetime_secs.saturating_sub(elapsed)
     }
 }
 
@@ -118,6 +122,8 @@ impl Circuit {
 pub struct CircuitBuilder {
     /// Selected relay descriptors for the circuit hops.
     relays: Vec<RelayDescriptor>,
+    /// Lifetime of the built circuit in seconds.
+    lifetime_secs: u64,
 }
 
 impl CircuitBuilder {
@@ -125,9 +131,17 @@ impl CircuitBuilder {
     pub fn new() -> Self {
         Self {
             relays: Vec::with_capacity(CIRCUIT_HOPS),
+            lifetime_secs: CIRCUIT_LIFETIME_SECS,
         }
     }
 
+    /// Rotate the circuit after `secs` instead of
+    /// [`CIRCUIT_LIFETIME_SECS`], as set by the network profile.
+    pub fn lifetime(mut self, secs: u64) -> Self {
+        self.lifetime_secs = secs;
+        self
+    }
+
     /// Add a relay to the circuit path.
     ///
     /// Relays must be added in order: entry, middle, exit.
@@ -181,6 +195,7 @@ impl CircuitBuilder {
             ephemeral_secret,
             ephemeral_pk,
             created_at: Instant::now(),
+            lifetime_secs: self.lifetime_secs,
             circuit_id,
         })
     }
@@ -209,9 +224,9 @@ pub fn derive_hop_keys(shared_secret: &[u8; 32]) -> HopKeys {
     hop_nonce.copy_from_slice(&nonce_full[..12]);
 
     HopKeys {
-        hop_key,
-        hop_mac,
-        hop_pad,
+        hop_key: SecretBytes::new(hop_key),
+        hop_mac: SecretBytes::new(hop_mac),
+        hop_pad: SecretBytes::new(hop_pad),
         hop_nonce,
     }
 }
@@ -240,6 +255,8 @@ mod tests {
             country_code: [b'U', b'S'],
             bandwidth_cap_mbps: 100,
             uptime_epochs: 100,
+            exit_policy: Default::default(),
+            delegation: None,
             sig: [0u8; 64],
         }
     }
@@ -346,6 +363,21 @@ mod tests {
         assert!(circuit.remaining_secs() > 0);
     }
 
+    #[test]
+    fn test_circuit_lifetime_from_profile() {
+        assert_eq!(
+            ochra_types::profile::NetworkProfile::mainnet().circuit_lifetime_secs,
+            CIRCUIT_LIFETIME_SECS
+        );
+        let mut builder = CircuitBuilder::new().lifetime(0);
+        for id in 1..=3 {
+            builder = builder.add_relay(make_relay_descriptor(id)).expect("add");
+        }
+        let circuit = builder.build().expect("build");
+        assert!(circuit.is_expired());
+        assert_eq!(circuit.remaining_secs(), 0);
+    }
+
     #[test]
     fn test_circuit_id_unique() {
         let r1a = make_relay_descriptor(1);
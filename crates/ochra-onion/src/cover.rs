@@ -22,6 +22,8 @@
 use std::time::Duration;
 
 use ochra_crypto::blake3;
+use ochra_crypto::secret::SecretBytes;
+use ochra_sim::SimEnv;
 use tracing::debug;
 
 use crate::{Result, SPHINX_PACKET_SIZE};
@@ -83,7 +85,9 @@ pub struct CoverTrafficGenerator {
     /// Configuration for timing and enablement.
     config: CoverTrafficConfig,
     /// Shared secret with the exit node, used for cover token derivation.
-    exit_shared_secret: [u8; 32],
+    exit_shared_secret: SecretBytes<32>,
+    /// Source of the Poisson timing randomness.
+    env: SimEnv,
 }
 
 impl CoverTrafficGenerator {
@@ -96,10 +100,17 @@ impl CoverTrafficGenerator {
     pub fn new(config: CoverTrafficConfig, exit_shared_secret: [u8; 32]) -> Self {
         Self {
             config,
-            exit_shared_secret,
+            exit_shared_secret: SecretBytes::new(exit_shared_secret),
+            env: SimEnv::real(),
         }
     }
 
+    /// Draw timing randomness from `env` (e.g. a seeded simulation).
+    pub fn with_env(mut self, env: SimEnv) -> Self {
+        self.env = env;
+        self
+    }
+
     /// Return whether cover traffic generation is enabled.
     pub fn is_enabled(&self) -> bool {
         self.config.enabled
@@ -110,8 +121,7 @@ impl CoverTrafficGenerator {
     /// Draws from an exponential distribution (Poisson inter-arrival time)
     /// using the configured mean interval.
     pub fn next_delay(&self) -> Duration {
-        let u: f64 =
-            rand::Rng::gen_range(&mut rand::thread_rng(), f64::EPSILON..(1.0 - f64::EPSILON));
+        let u = self.env.entropy().next_f64();
         let delay_ms = next_cover_delay_ms(self.config.mean_interval_ms, u);
         Duration::from_millis(delay_ms)
     }
@@ -122,7 +132,7 @@ impl CoverTrafficGenerator {
     /// exit shared secret, followed by pseudo-random padding. The entire
     /// packet is indistinguishable from real traffic at the network level.
     pub fn generate_packet(&self) -> Result<Vec<u8>> {
-        let cover_token = derive_cover_token(&self.exit_shared_secret);
+        let cover_token = derive_cover_token(self.exit_shared_secret.expose());
 
         let mut packet = vec![0u8; SPHINX_PACKET_SIZE];
 
@@ -145,7 +155,7 @@ impl CoverTrafficGenerator {
 
     /// Return the cover token for this generator's exit secret.
     pub fn cover_token(&self) -> [u8; 32] {
-        derive_cover_token(&self.exit_shared_secret)
+        derive_cover_token(self.exit_shared_secret.expose())
     }
 
     /// Update the configuration (e.g., change interval).
@@ -155,7 +165,7 @@ impl CoverTrafficGenerator {
 
     /// Update the exit shared secret (e.g., after circuit rotation).
     pub fn set_exit_secret(&mut self, secret: [u8; 32]) {
-        self.exit_shared_secret = secret;
+        self.exit_shared_secret = SecretBytes::new(secret);
     }
 }
 
@@ -312,6 +322,18 @@ mod tests {
         assert!(delay.as_millis() <= u128::from(MAX_COVER_INTERVAL_MS));
     }
 
+    #[test]
+    fn test_seeded_delays_reproducible() {
+        let delays = |seed| {
+            let env = ochra_sim::SimEnv::simulated(seed, ochra_sim::VirtualClock::new(0));
+            let gen =
+                CoverTrafficGenerator::new(CoverTrafficConfig::default(), [0u8; 32]).with_env(env);
+            (0..8).map(|_| gen.next_delay()).collect::<Vec<_>>()
+        };
+        assert_eq!(delays(1), delays(1));
+        assert_ne!(delays(1), delays(2));
+    }
+
     #[test]
     fn test_generator_update_config() {
         let config = CoverTrafficConfig::default();
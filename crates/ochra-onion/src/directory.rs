@@ -0,0 +1,514 @@
+//! Relay directory consensus snapshots and epoch diffs.
+//!
+//! Fetching thousands of individual relay descriptors from the DHT at every
+//! epoch boundary is slow. Instead, the quorum periodically publishes a
+//! FROST-signed snapshot of every live [`RelayDescriptor`], plus a diff
+//! between each pair of consecutive relay epochs. A circuit builder
+//! bootstraps its [`RelayCache`](crate::relay::RelayCache) from the most
+//! recent snapshot and then stays current by applying diffs.
+//!
+//! ## Directory Digest
+//!
+//! The directory digest commits to the full descriptor set:
+//!
+//! ```text
+//! descriptor_hash = BLAKE3::hash(CBOR(descriptor))
+//! digest          = BLAKE3::hash(descriptor_hash_0 || descriptor_hash_1 || ...)
+//! ```
+//!
+//! where descriptors are ordered by `node_id`. Both snapshots and diffs are
+//! signed over the digests they produce, so a diff applied to the wrong base
+//! is detected before the cache is modified.
+//!
+//! ## Wire Format
+//!
+//! Snapshots and diffs are CBOR-encoded and compressed with zstd (level 3),
+//! matching the catalog snapshot format of Section 16.8. They are stored in
+//! the DHT at:
+//!
+//! | Record | Key |
+//! |---|---|
+//! | Snapshot | `BLAKE3::hash("relay-directory" \|\| LE32(relay_epoch))` |
+//! | Diff | `BLAKE3::hash("relay-directory-diff" \|\| LE32(from_epoch))` |
+//!
+//! In v1 the daemon does not take part in quorum signing, so it neither
+//! signs nor stores snapshots and diffs in the DHT. Snapshots reach new
+//! relays through checkpointed state sync instead, which loads them with
+//! [`RelayCache::load_snapshot`](crate::relay::RelayCache::load_snapshot)
+//! and serves them on to the next joiner.
+
+use std::collections::HashMap;
+
+use ochra_types::network::RelayDescriptor;
+use serde::{Deserialize, Serialize};
+
+use crate::{OnionError, Result};
+
+/// zstd compression level for directory snapshots and diffs.
+pub const DIRECTORY_COMPRESSION_LEVEL: i32 = 3;
+
+/// Maximum decompressed size of a directory snapshot or diff (5 MB).
+pub const MAX_DIRECTORY_SIZE: usize = 5 * 1024 * 1024;
+
+/// Number of relay epochs between full snapshot publications.
+///
+/// Between snapshots, only diffs are published.
+pub const SNAPSHOT_INTERVAL_EPOCHS: u32 = 24;
+
+/// A quorum-signed snapshot of every live relay descriptor.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DirectorySnapshot {
+    /// Relay epoch this snapshot was taken at.
+    pub relay_epoch: u32,
+    /// All live descriptors, ordered by `node_id`.
+    pub descriptors: Vec<RelayDescriptor>,
+    /// Directory digest over `descriptors`.
+    pub digest: [u8; 32],
+    /// FROST group signature over [`DirectorySnapshot::signing_message`].
+    pub quorum_sig: Vec<u8>,
+}
+
+/// A quorum-signed diff between two consecutive directory states.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DirectoryDiff {
+    /// Relay epoch the diff applies on top of.
+    pub from_epoch: u32,
+    /// Relay epoch the diff produces.
+    pub to_epoch: u32,
+    /// Directory digest of the base state.
+    pub base_digest: [u8; 32],
+    /// Directory digest after applying the diff.
+    pub result_digest: [u8; 32],
+    /// Descriptors that were added or changed, ordered by `node_id`.
+    pub upserted: Vec<RelayDescriptor>,
+    /// Node IDs of relays that dropped out of the directory.
+    pub removed: Vec<[u8; 32]>,
+    /// FROST group signature over [`DirectoryDiff::signing_message`].
+    pub quorum_sig: Vec<u8>,
+}
+
+impl DirectorySnapshot {
+    /// Build an unsigned snapshot from a set of descriptors.
+    ///
+    /// Descriptors are sorted by `node_id`; duplicates keep the entry with the
+    /// highest `relay_epoch`.
+    pub fn build(relay_epoch: u32, descriptors: Vec<RelayDescriptor>) -> Result<Self> {
+        let descriptors = normalize(descriptors);
+        let digest = directory_digest(&descriptors)?;
+        Ok(Self {
+            relay_epoch,
+            descriptors,
+            digest,
+            quorum_sig: Vec::new(),
+        })
+    }
+
+    /// Bytes signed by the quorum: `"relay-directory" || LE32(relay_epoch) || digest`.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(15 + 4 + 32);
+        msg.extend_from_slice(b"relay-directory");
+        msg.extend_from_slice(&self.relay_epoch.to_le_bytes());
+        msg.extend_from_slice(&self.digest);
+        msg
+    }
+
+    /// Verify the digest and the quorum signature.
+    pub fn verify(&self, quorum_pk: &[u8; 32]) -> Result<()> {
+        if directory_digest(&self.descriptors)? != self.digest {
+            return Err(OnionError::Directory(
+                "snapshot digest mismatch".to_string(),
+            ));
+        }
+        verify_quorum_sig(quorum_pk, &self.signing_message(), &self.quorum_sig)
+    }
+
+    /// Encode as zstd-compressed CBOR.
+    pub fn to_compressed(&self) -> Result<Vec<u8>> {
+        compress(self)
+    }
+
+    /// Decode from zstd-compressed CBOR.
+    pub fn from_compressed(data: &[u8]) -> Result<Self> {
+        decompress(data)
+    }
+
+    /// DHT key this snapshot is published under.
+    pub fn dht_key(&self) -> [u8; 32] {
+        snapshot_dht_key(self.relay_epoch)
+    }
+}
+
+impl DirectoryDiff {
+    /// Compute the unsigned diff between two directory states.
+    pub fn compute(
+        from_epoch: u32,
+        base: &[RelayDescriptor],
+        to_epoch: u32,
+        target: &[RelayDescriptor],
+    ) -> Result<Self> {
+        let base = normalize(base.to_vec());
+        let target = normalize(target.to_vec());
+
+        let mut base_hashes: HashMap<[u8; 32], [u8; 32]> = HashMap::with_capacity(base.len());
+        for desc in &base {
+            base_hashes.insert(desc.node_id, descriptor_hash(desc)?);
+        }
+
+        let mut upserted = Vec::new();
+        for desc in &target {
+            let changed = match base_hashes.get(&desc.node_id) {
+                Some(old) => *old != descriptor_hash(desc)?,
+                None => true,
+            };
+            if changed {
+                upserted.push(desc.clone());
+            }
+        }
+
+        let removed: Vec<[u8; 32]> = base
+            .iter()
+            .filter(|b| !target.iter().any(|t| t.node_id == b.node_id))
+            .map(|b| b.node_id)
+            .collect();
+
+        Ok(Self {
+            from_epoch,
+            to_epoch,
+            base_digest: directory_digest(&base)?,
+            result_digest: directory_digest(&target)?,
+            upserted,
+            removed,
+            quorum_sig: Vec::new(),
+        })
+    }
+
+    /// Bytes signed by the quorum:
+    /// `"relay-directory-diff" || LE32(from) || LE32(to) || base_digest || result_digest`.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(20 + 8 + 64);
+        msg.extend_from_slice(b"relay-directory-diff");
+        msg.extend_from_slice(&self.from_epoch.to_le_bytes());
+        msg.extend_from_slice(&self.to_epoch.to_le_bytes());
+        msg.extend_from_slice(&self.base_digest);
+        msg.extend_from_slice(&self.result_digest);
+        msg
+    }
+
+    /// Verify the quorum signature.
+    ///
+    /// The digests themselves are checked when the diff is applied.
+    pub fn verify(&self, quorum_pk: &[u8; 32]) -> Result<()> {
+        if self.to_epoch <= self.from_epoch {
+            return Err(OnionError::Directory(format!(
+                "diff epochs not increasing: {} -> {}",
+                self.from_epoch, self.to_epoch
+            )));
+        }
+        verify_quorum_sig(quorum_pk, &self.signing_message(), &self.quorum_sig)
+    }
+
+    /// Apply this diff to a descriptor set, returning the resulting set.
+    ///
+    /// Fails without modifying anything if either digest does not match.
+    pub fn apply(&self, base: &[RelayDescriptor]) -> Result<Vec<RelayDescriptor>> {
+        let base = normalize(base.to_vec());
+        if directory_digest(&base)? != self.base_digest {
+            return Err(OnionError::Directory(
+                "diff base digest mismatch".to_string(),
+            ));
+        }
+
+        let mut by_id: HashMap<[u8; 32], RelayDescriptor> =
+            base.into_iter().map(|d| (d.node_id, d)).collect();
+        for node_id in &self.removed {
+            by_id.remove(node_id);
+        }
+        for desc in &self.upserted {
+            by_id.insert(desc.node_id, desc.clone());
+        }
+
+        let result = normalize(by_id.into_values().collect());
+        if directory_digest(&result)? != self.result_digest {
+            return Err(OnionError::Directory(
+                "diff result digest mismatch".to_string(),
+            ));
+        }
+        Ok(result)
+    }
+
+    /// Encode as zstd-compressed CBOR.
+    pub fn to_compressed(&self) -> Result<Vec<u8>> {
+        compress(self)
+    }
+
+    /// Decode from zstd-compressed CBOR.
+    pub fn from_compressed(data: &[u8]) -> Result<Self> {
+        decompress(data)
+    }
+
+    /// DHT key this diff is published under.
+    pub fn dht_key(&self) -> [u8; 32] {
+        diff_dht_key(self.from_epoch)
+    }
+}
+
+/// DHT key for the snapshot taken at `relay_epoch`.
+///
+/// `BLAKE3::hash("relay-directory" || LE32(relay_epoch))`
+pub fn snapshot_dht_key(relay_epoch: u32) -> [u8; 32] {
+    let mut input = Vec::with_capacity(15 + 4);
+    input.extend_from_slice(b"relay-directory");
+    input.extend_from_slice(&relay_epoch.to_le_bytes());
+    ochra_crypto::blake3::hash(&input)
+}
+
+/// DHT key for the diff starting at `from_epoch`.
+///
+/// `BLAKE3::hash("relay-directory-diff" || LE32(from_epoch))`
+pub fn diff_dht_key(from_epoch: u32) -> [u8; 32] {
+    let mut input = Vec::with_capacity(20 + 4);
+    input.extend_from_slice(b"relay-directory-diff");
+    input.extend_from_slice(&from_epoch.to_le_bytes());
+    ochra_crypto::blake3::hash(&input)
+}
+
+/// Relay epoch of the most recent full snapshot at or before `relay_epoch`.
+pub fn latest_snapshot_epoch(relay_epoch: u32) -> u32 {
+    relay_epoch - (relay_epoch % SNAPSHOT_INTERVAL_EPOCHS)
+}
+
+/// Compute the directory digest over a descriptor set.
+///
+/// The set is normalized (sorted by `node_id`) before hashing.
+pub fn directory_digest(descriptors: &[RelayDescriptor]) -> Result<[u8; 32]> {
+    let mut hashes: Vec<([u8; 32], [u8; 32])> = Vec::with_capacity(descriptors.len());
+    for desc in descriptors {
+        hashes.push((desc.node_id, descriptor_hash(desc)?));
+    }
+    hashes.sort_by_key(|(node_id, _)| *node_id);
+
+    let mut input = Vec::with_capacity(hashes.len() * 32);
+    for (_, h) in &hashes {
+        input.extend_from_slice(h);
+    }
+    Ok(ochra_crypto::blake3::hash(&input))
+}
+
+/// Hash a single descriptor: `BLAKE3::hash(CBOR(descriptor))`.
+fn descriptor_hash(desc: &RelayDescriptor) -> Result<[u8; 32]> {
+    let mut buf = Vec::new();
+    ciborium::into_writer(desc, &mut buf)
+        .map_err(|e| OnionError::Directory(format!("descriptor encoding failed: {e}")))?;
+    Ok(ochra_crypto::blake3::hash(&buf))
+}
+
+/// Sort by `node_id`, keeping the newest descriptor for duplicate IDs.
+fn normalize(mut descriptors: Vec<RelayDescriptor>) -> Vec<RelayDescriptor> {
+    descriptors.sort_by(|a, b| {
+        a.node_id
+            .cmp(&b.node_id)
+            .then(b.relay_epoch.cmp(&a.relay_epoch))
+    });
+    descriptors.dedup_by(|later, earlier| later.node_id == earlier.node_id);
+    descriptors
+}
+
+/// Verify an Ed25519-compatible FROST group signature.
+fn verify_quorum_sig(quorum_pk: &[u8; 32], message: &[u8], sig: &[u8]) -> Result<()> {
+    let sig: [u8; 64] = sig
+        .try_into()
+        .map_err(|_| OnionError::Directory("quorum signature must be 64 bytes".to_string()))?;
+    let vk = ochra_crypto::ed25519::VerifyingKey::from_bytes(quorum_pk)?;
+    vk.verify(message, &ochra_crypto::ed25519::Signature::from_bytes(&sig))?;
+    Ok(())
+}
+
+fn compress<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut cbor = Vec::new();
+    ciborium::into_writer(value, &mut cbor)
+        .map_err(|e| OnionError::Directory(format!("CBOR encoding failed: {e}")))?;
+    if cbor.len() > MAX_DIRECTORY_SIZE {
+        return Err(OnionError::Directory(format!(
+            "directory too large: {} bytes",
+            cbor.len()
+        )));
+    }
+    zstd::encode_all(cbor.as_slice(), DIRECTORY_COMPRESSION_LEVEL)
+        .map_err(|e| OnionError::Directory(format!("compression failed: {e}")))
+}
+
+fn decompress<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T> {
+    use std::io::Read;
+
+    let decoder = zstd::Decoder::new(data)
+        .map_err(|e| OnionError::Directory(format!("decompression failed: {e}")))?;
+    // Read one byte past the limit so oversized payloads are detected.
+    let mut cbor = Vec::new();
+    decoder
+        .take(MAX_DIRECTORY_SIZE as u64 + 1)
+        .read_to_end(&mut cbor)
+        .map_err(|e| OnionError::Directory(format!("decompression failed: {e}")))?;
+    if cbor.len() > MAX_DIRECTORY_SIZE {
+        return Err(OnionError::Directory(
+            "directory exceeds maximum size".to_string(),
+        ));
+    }
+    ciborium::from_reader(cbor.as_slice())
+        .map_err(|e| OnionError::Directory(format!("CBOR decoding failed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::KeyPair;
+
+    fn make_relay(id: u8, epoch: u32, score: f32) -> RelayDescriptor {
+        RelayDescriptor {
+            node_id: [id; 32],
+            pik_hash: [id; 32],
+            x25519_pk: [id; 32],
+            mlkem768_ek: vec![id; 1184],
+            relay_epoch: epoch,
+            posrv_score: score,
+            ip_addr: format!("10.0.{id}.1:4433"),
+            as_number: u32::from(id) * 100,
+            country_code: [b'U', b'S'],
+            bandwidth_cap_mbps: 100,
+            uptime_epochs: 10,
+            exit_policy: Default::default(),
+            delegation: None,
+            sig: [0u8; 64],
+        }
+    }
+
+    fn signed_snapshot(
+        kp: &KeyPair,
+        epoch: u32,
+        relays: Vec<RelayDescriptor>,
+    ) -> DirectorySnapshot {
+        let mut snap = DirectorySnapshot::build(epoch, relays).expect("build snapshot");
+        snap.quorum_sig = kp
+            .signing_key
+            .sign(&snap.signing_message())
+            .to_bytes()
+            .to_vec();
+        snap
+    }
+
+    #[test]
+    fn test_snapshot_sign_verify() {
+        let kp = KeyPair::generate();
+        let snap = signed_snapshot(
+            &kp,
+            24,
+            vec![make_relay(2, 24, 1.0), make_relay(1, 24, 2.0)],
+        );
+        assert_eq!(snap.descriptors[0].node_id, [1u8; 32]);
+        snap.verify(&kp.verifying_key.to_bytes()).expect("verify");
+
+        let other = KeyPair::generate();
+        assert!(snap.verify(&other.verifying_key.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_tampered_descriptor_rejected() {
+        let kp = KeyPair::generate();
+        let mut snap = signed_snapshot(&kp, 24, vec![make_relay(1, 24, 1.0)]);
+        snap.descriptors[0].posrv_score = 99.0;
+        assert!(snap.verify(&kp.verifying_key.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_compression_roundtrip() {
+        let kp = KeyPair::generate();
+        let relays: Vec<RelayDescriptor> = (1..=50).map(|i| make_relay(i, 24, 1.0)).collect();
+        let snap = signed_snapshot(&kp, 24, relays);
+
+        let bytes = snap.to_compressed().expect("compress");
+        let restored = DirectorySnapshot::from_compressed(&bytes).expect("decompress");
+        assert_eq!(restored.descriptors.len(), 50);
+        assert_eq!(restored.digest, snap.digest);
+        restored
+            .verify(&kp.verifying_key.to_bytes())
+            .expect("verify restored");
+    }
+
+    #[test]
+    fn test_diff_compute_and_apply() {
+        let base = vec![
+            make_relay(1, 24, 1.0),
+            make_relay(2, 24, 1.0),
+            make_relay(3, 24, 1.0),
+        ];
+        let target = vec![
+            make_relay(1, 24, 1.0),
+            make_relay(3, 25, 2.0),
+            make_relay(4, 25, 1.0),
+        ];
+
+        let diff = DirectoryDiff::compute(24, &base, 25, &target).expect("compute diff");
+        assert_eq!(diff.removed, vec![[2u8; 32]]);
+        assert_eq!(diff.upserted.len(), 2);
+
+        let result = diff.apply(&base).expect("apply diff");
+        assert_eq!(
+            directory_digest(&result).expect("digest"),
+            directory_digest(&target).expect("digest")
+        );
+    }
+
+    #[test]
+    fn test_diff_wrong_base_rejected() {
+        let base = vec![make_relay(1, 24, 1.0)];
+        let target = vec![make_relay(1, 24, 1.0), make_relay(2, 25, 1.0)];
+        let diff = DirectoryDiff::compute(24, &base, 25, &target).expect("compute diff");
+
+        let other_base = vec![make_relay(5, 24, 1.0)];
+        assert!(diff.apply(&other_base).is_err());
+    }
+
+    #[test]
+    fn test_diff_signature_and_epochs() {
+        let kp = KeyPair::generate();
+        let mut diff =
+            DirectoryDiff::compute(24, &[], 25, &[make_relay(1, 25, 1.0)]).expect("compute diff");
+        diff.quorum_sig = kp
+            .signing_key
+            .sign(&diff.signing_message())
+            .to_bytes()
+            .to_vec();
+        diff.verify(&kp.verifying_key.to_bytes()).expect("verify");
+
+        let bytes = diff.to_compressed().expect("compress");
+        let restored = DirectoryDiff::from_compressed(&bytes).expect("decompress");
+        restored
+            .verify(&kp.verifying_key.to_bytes())
+            .expect("verify restored");
+
+        diff.to_epoch = 24;
+        assert!(diff.verify(&kp.verifying_key.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_normalize_keeps_newest_duplicate() {
+        let snap =
+            DirectorySnapshot::build(30, vec![make_relay(1, 28, 1.0), make_relay(1, 29, 3.0)])
+                .expect("build");
+        assert_eq!(snap.descriptors.len(), 1);
+        assert_eq!(snap.descriptors[0].relay_epoch, 29);
+    }
+
+    #[test]
+    fn test_dht_keys_and_snapshot_epoch() {
+        assert_ne!(snapshot_dht_key(24), diff_dht_key(24));
+        assert_ne!(snapshot_dht_key(24), snapshot_dht_key(48));
+        assert_eq!(latest_snapshot_epoch(24), 24);
+        assert_eq!(latest_snapshot_epoch(47), 24);
+        assert_eq!(latest_snapshot_epoch(5), 0);
+    }
+
+    #[test]
+    fn test_garbage_rejected() {
+        assert!(DirectorySnapshot::from_compressed(b"not zstd").is_err());
+    }
+}
@@ -0,0 +1,727 @@
+//! AS number and country lookup for relay addresses.
+//!
+//! Relay selection keeps hops in different autonomous systems and, when
+//! asked, different countries (see [`crate::relay`]). The values a relay
+//! puts in its own descriptor are not trustworthy, so the cache resolves
+//! them from the relay's address through a [`GeoResolver`]:
+//!
+//! - [`MmdbResolver`] reads MaxMind DB files supplied by the operator (for
+//!   example GeoLite2-ASN and GeoLite2-Country). Each file contributes the
+//!   fields it has; later files fill gaps left by earlier ones.
+//! - [`NoGeoResolver`] is used when no database is configured. Relays then
+//!   keep their self-reported values.
+//!
+//! Addresses a database has no entry for resolve to [`UNKNOWN_AS`] and
+//! [`UNKNOWN_COUNTRY`], which selection does not constrain on, so a missing
+//! or partial database narrows diversity to the subnet rule rather than
+//! failing circuit construction. Relays are re-resolved on every cache
+//! update and every [`GEO_REFRESH_INTERVAL_SECS`], picking up a database
+//! the operator has replaced (see [`MmdbResolver::reload`]).
+//!
+//! ## MaxMind DB format
+//!
+//! The reader supports the documented v2 format: a binary search tree of
+//! 24-, 28-, or 32-bit records over the address bits, a 16-byte separator,
+//! a data section of typed values, and a metadata map after the
+//! `\xAB\xCD\xEFMaxMind.com` marker at the end of the file.
+
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use tracing::{debug, warn};
+
+use crate::{OnionError, Result};
+
+/// AS number for relays whose AS is not known (AS 0 is reserved).
+pub const UNKNOWN_AS: u32 = 0;
+
+/// Country code for relays whose country is not known (ISO "ZZ").
+pub const UNKNOWN_COUNTRY: [u8; 2] = *b"ZZ";
+
+/// Interval between re-resolutions of every cached relay (6 hours).
+pub const GEO_REFRESH_INTERVAL_SECS: u64 = 6 * 3600;
+
+/// Marker preceding the metadata map.
+const METADATA_MARKER: &[u8] = b"\xAB\xCD\xEFMaxMind.com";
+
+/// Metadata is searched for in this many trailing bytes.
+const METADATA_MAX_SIZE: usize = 128 * 1024;
+
+/// Size of the separator between the search tree and the data section.
+const DATA_SECTION_SEPARATOR: usize = 16;
+
+/// Deepest nesting of maps and arrays decoded.
+const MAX_DECODE_DEPTH: usize = 16;
+
+/// What a resolver knows about an address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GeoInfo {
+    /// Autonomous system number.
+    pub as_number: Option<u32>,
+    /// ISO 3166-1 alpha-2 country code, uppercase.
+    pub country_code: Option<[u8; 2]>,
+}
+
+impl GeoInfo {
+    /// Fill fields this one lacks from `other`.
+    fn or(self, other: GeoInfo) -> GeoInfo {
+        GeoInfo {
+            as_number: self.as_number.or(other.as_number),
+            country_code: self.country_code.or(other.country_code),
+        }
+    }
+}
+
+/// Looks up the AS and country of an address.
+pub trait GeoResolver: Send + Sync {
+    /// Resolve `ip`. Fields the resolver has no data for are `None`.
+    fn resolve(&self, ip: IpAddr) -> GeoInfo;
+
+    /// Whether the resolver has any database at all. When it does not,
+    /// relays keep their self-reported AS and country.
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// A resolver with no database.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoGeoResolver;
+
+impl GeoResolver for NoGeoResolver {
+    fn resolve(&self, _ip: IpAddr) -> GeoInfo {
+        GeoInfo::default()
+    }
+
+    fn is_available(&self) -> bool {
+        false
+    }
+}
+
+/// A resolver backed by MaxMind DB files.
+pub struct MmdbResolver {
+    paths: Vec<PathBuf>,
+    databases: RwLock<Vec<Mmdb>>,
+}
+
+impl MmdbResolver {
+    /// Open the databases at `paths`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OnionError::Geo`] if a file cannot be read or is not a
+    /// MaxMind DB.
+    pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        let paths: Vec<PathBuf> = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+        let databases = load_all(&paths)?;
+        Ok(Self {
+            paths,
+            databases: RwLock::new(databases),
+        })
+    }
+
+    /// Re-read the files, for when the operator has replaced them. On
+    /// error the databases already loaded stay in use.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OnionError::Geo`] if a file cannot be read or parsed.
+    pub fn reload(&self) -> Result<()> {
+        let databases = load_all(&self.paths)?;
+        let mut current = self
+            .databases
+            .write()
+            .map_err(|_| OnionError::Geo("database lock poisoned".to_string()))?;
+        *current = databases;
+        Ok(())
+    }
+
+    /// `database_type` of each loaded file, in order.
+    pub fn database_types(&self) -> Vec<String> {
+        self.databases
+            .read()
+            .map(|dbs| dbs.iter().map(|db| db.database_type.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl GeoResolver for MmdbResolver {
+    fn resolve(&self, ip: IpAddr) -> GeoInfo {
+        let Ok(databases) = self.databases.read() else {
+            return GeoInfo::default();
+        };
+        databases.iter().fold(GeoInfo::default(), |info, db| {
+            let record = db.lookup(ip).unwrap_or_else(|e| {
+                warn!(error = %e, "Corrupt GeoIP record");
+                None
+            });
+            info.or(record.map(|v| geo_from_record(&v)).unwrap_or_default())
+        })
+    }
+
+    fn is_available(&self) -> bool {
+        self.databases.read().is_ok_and(|dbs| !dbs.is_empty())
+    }
+}
+
+fn load_all(paths: &[PathBuf]) -> Result<Vec<Mmdb>> {
+    paths
+        .iter()
+        .map(|path| {
+            let bytes = std::fs::read(path)
+                .map_err(|e| OnionError::Geo(format!("{}: {e}", path.display())))?;
+            let db = Mmdb::parse(bytes)
+                .map_err(|e| OnionError::Geo(format!("{}: {e}", path.display())))?;
+            debug!(
+                path = %path.display(),
+                database_type = %db.database_type,
+                "Loaded GeoIP database"
+            );
+            Ok(db)
+        })
+        .collect()
+}
+
+/// Pull the AS number and country out of a GeoLite2/GeoIP2 record.
+fn geo_from_record(record: &Value) -> GeoInfo {
+    let as_number = record
+        .get("autonomous_system_number")
+        .and_then(Value::as_u64)
+        .and_then(|n| u32::try_from(n).ok())
+        .filter(|&n| n != UNKNOWN_AS);
+    let country_code = ["country", "registered_country"]
+        .iter()
+        .find_map(|key| record.get(key)?.get("iso_code")?.as_str())
+        .and_then(|code| <[u8; 2]>::try_from(code.as_bytes()).ok())
+        .map(|code| code.map(|b| b.to_ascii_uppercase()));
+    GeoInfo {
+        as_number,
+        country_code,
+    }
+}
+
+/// A decoded data section value.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    String(String),
+    Double(f64),
+    Bytes(Vec<u8>),
+    Uint(u128),
+    Int(i32),
+    Map(Vec<(String, Value)>),
+    Array(Vec<Value>),
+    Bool(bool),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Uint(n) => u64::try_from(*n).ok(),
+            Value::Int(n) => u64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// One parsed MaxMind DB file.
+struct Mmdb {
+    bytes: Vec<u8>,
+    node_count: u32,
+    record_size: u16,
+    ip_version: u16,
+    database_type: String,
+    /// Start of the data section.
+    data_start: usize,
+    /// Node reached after 96 zero bits, where IPv4 lookups start in an
+    /// IPv6 tree.
+    ipv4_start: u32,
+}
+
+impl Mmdb {
+    fn parse(bytes: Vec<u8>) -> std::result::Result<Self, String> {
+        let search_from = bytes.len().saturating_sub(METADATA_MAX_SIZE);
+        let marker = bytes[search_from..]
+            .windows(METADATA_MARKER.len())
+            .rposition(|w| w == METADATA_MARKER)
+            .ok_or("no metadata marker")?;
+        let meta_start = search_from + marker + METADATA_MARKER.len();
+        let (metadata, _) = Decoder {
+            section: &bytes[meta_start..],
+        }
+        .decode(0, 0)?;
+
+        let field = |key: &str| metadata.get(key).and_then(Value::as_u64);
+        let node_count = field("node_count")
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or("metadata lacks node_count")?;
+        let record_size = field("record_size")
+            .and_then(|n| u16::try_from(n).ok())
+            .ok_or("metadata lacks record_size")?;
+        let ip_version = field("ip_version")
+            .and_then(|n| u16::try_from(n).ok())
+            .ok_or("metadata lacks ip_version")?;
+        if !matches!(record_size, 24 | 28 | 32) {
+            return Err(format!("unsupported record size {record_size}"));
+        }
+        if !matches!(ip_version, 4 | 6) {
+            return Err(format!("unsupported IP version {ip_version}"));
+        }
+        let database_type = metadata
+            .get("database_type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let tree_size = node_count as usize * usize::from(record_size) / 4;
+        let data_start = tree_size + DATA_SECTION_SEPARATOR;
+        if data_start > meta_start {
+            return Err("search tree overruns the file".to_string());
+        }
+
+        let mut db = Self {
+            bytes,
+            node_count,
+            record_size,
+            ip_version,
+            database_type,
+            data_start,
+            ipv4_start: 0,
+        };
+        if ip_version == 6 {
+            let mut node = 0;
+            for _ in 0..96 {
+                if node >= node_count {
+                    break;
+                }
+                node = db.record(node, false)?;
+            }
+            db.ipv4_start = node;
+        }
+        Ok(db)
+    }
+
+    /// The left (`right == false`) or right record of `node`.
+    fn record(&self, node: u32, right: bool) -> std::result::Result<u32, String> {
+        let node_bytes = usize::from(self.record_size) / 4;
+        let start = node as usize * node_bytes;
+        let b = self
+            .bytes
+            .get(start..start + node_bytes)
+            .ok_or("node outside the search tree")?;
+        let be = |bytes: &[u8]| bytes.iter().fold(0u32, |acc, &x| (acc << 8) | u32::from(x));
+        Ok(match (self.record_size, right) {
+            (24, false) => be(&b[0..3]),
+            (24, true) => be(&b[3..6]),
+            (28, false) => (u32::from(b[3] & 0xF0) << 20) | be(&b[0..3]),
+            (28, true) => (u32::from(b[3] & 0x0F) << 24) | be(&b[4..7]),
+            (_, false) => be(&b[0..4]),
+            (_, true) => be(&b[4..8]),
+        })
+    }
+
+    /// The record for `ip`, if the database has one.
+    ///
+    /// Errors if a record points outside the tree or the data section.
+    fn lookup(&self, ip: IpAddr) -> std::result::Result<Option<Value>, String> {
+        let (bits, mut node): (Vec<bool>, u32) = match ip {
+            IpAddr::V4(v4) => {
+                let start = if self.ip_version == 6 {
+                    self.ipv4_start
+                } else {
+                    0
+                };
+                (to_bits(&v4.octets()), start)
+            }
+            IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+                Some(v4) => return self.lookup(IpAddr::V4(v4)),
+                None if self.ip_version == 4 => return Ok(None),
+                None => (to_bits(&v6.octets()), 0),
+            },
+        };
+        for bit in bits {
+            if node >= self.node_count {
+                break;
+            }
+            node = self.record(node, bit)?;
+        }
+        if node <= self.node_count {
+            // Equal to node_count: no data for this address.
+            return Ok(None);
+        }
+        let offset = ((node - self.node_count) as usize)
+            .checked_sub(DATA_SECTION_SEPARATOR)
+            .ok_or("record points into the data section separator")?;
+        let section = self
+            .bytes
+            .get(self.data_start..)
+            .ok_or("data section outside the file")?;
+        let (value, _) = (Decoder { section }).decode(offset, 0)?;
+        Ok(Some(value))
+    }
+}
+
+fn to_bits(octets: &[u8]) -> Vec<bool> {
+    octets
+        .iter()
+        .flat_map(|&b| (0..8).rev().map(move |i| b >> i & 1 == 1))
+        .collect()
+}
+
+/// Decodes values from a data section (or the metadata, which uses the
+/// same encoding).
+struct Decoder<'a> {
+    section: &'a [u8],
+}
+
+impl Decoder<'_> {
+    fn bytes(&self, at: usize, len: usize) -> std::result::Result<&[u8], String> {
+        self.section
+            .get(at..at + len)
+            .ok_or_else(|| "value runs past the data section".to_string())
+    }
+
+    fn be(&self, at: usize, len: usize) -> std::result::Result<u128, String> {
+        Ok(self
+            .bytes(at, len)?
+            .iter()
+            .fold(0u128, |acc, &b| (acc << 8) | u128::from(b)))
+    }
+
+    /// Decode the value at `at`, returning it and the offset after it.
+    fn decode(&self, at: usize, depth: usize) -> std::result::Result<(Value, usize), String> {
+        if depth > MAX_DECODE_DEPTH {
+            return Err("values nested too deeply".to_string());
+        }
+        let ctrl = *self.bytes(at, 1)?.first().ok_or("empty")?;
+        let mut pos = at + 1;
+        let mut kind = ctrl >> 5;
+        if kind == 1 {
+            // Pointer: the value lives elsewhere; decoding continues after
+            // the pointer itself.
+            let ss = (ctrl >> 3) & 0x3;
+            let vvv = u128::from(ctrl & 0x7);
+            let len = usize::from(ss) + 1;
+            let raw = self.be(pos, len)?;
+            let target = match ss {
+                0 => (vvv << 8) | raw,
+                1 => ((vvv << 16) | raw) + 2048,
+                2 => ((vvv << 24) | raw) + 526_336,
+                _ => raw,
+            };
+            let (value, _) = self.decode(target as usize, depth + 1)?;
+            return Ok((value, pos + len));
+        }
+        if kind == 0 {
+            kind = 7 + *self.bytes(pos, 1)?.first().ok_or("empty")?;
+            pos += 1;
+        }
+        let mut size = usize::from(ctrl & 0x1F);
+        match size {
+            29 => {
+                size = 29 + self.be(pos, 1)? as usize;
+                pos += 1;
+            }
+            30 => {
+                size = 285 + self.be(pos, 2)? as usize;
+                pos += 2;
+            }
+            31 => {
+                size = 65_821 + self.be(pos, 3)? as usize;
+                pos += 3;
+            }
+            _ => {}
+        }
+
+        Ok(match kind {
+            2 => {
+                let s = std::str::from_utf8(self.bytes(pos, size)?)
+                    .map_err(|_| "invalid UTF-8 string")?;
+                (Value::String(s.to_string()), pos + size)
+            }
+            3 => {
+                let raw: [u8; 8] = self.bytes(pos, 8)?.try_into().map_err(|_| "bad double")?;
+                (Value::Double(f64::from_be_bytes(raw)), pos + 8)
+            }
+            4 => (Value::Bytes(self.bytes(pos, size)?.to_vec()), pos + size),
+            5 | 6 | 9 | 10 => {
+                let max = match kind {
+                    5 => 2,
+                    6 => 4,
+                    9 => 8,
+                    _ => 16,
+                };
+                if size > max {
+                    return Err(format!("unsigned integer of {size} bytes"));
+                }
+                (Value::Uint(self.be(pos, size)?), pos + size)
+            }
+            7 => {
+                let mut entries = Vec::with_capacity(size.min(64));
+                for _ in 0..size {
+                    let (key, next) = self.decode(pos, depth + 1)?;
+                    let Value::String(key) = key else {
+                        return Err("map key is not a string".to_string());
+                    };
+                    let (value, next) = self.decode(next, depth + 1)?;
+                    entries.push((key, value));
+                    pos = next;
+                }
+                (Value::Map(entries), pos)
+            }
+            8 => {
+                if size > 4 {
+                    return Err(format!("int32 of {size} bytes"));
+                }
+                // Shorter encodings are zero-padded, not sign-extended.
+                let value = self.be(pos, size)? as u32 as i32;
+                (Value::Int(value), pos + size)
+            }
+            11 => {
+                let mut items = Vec::with_capacity(size.min(64));
+                for _ in 0..size {
+                    let (value, next) = self.decode(pos, depth + 1)?;
+                    items.push(value);
+                    pos = next;
+                }
+                (Value::Array(items), pos)
+            }
+            14 => (Value::Bool(size != 0), pos),
+            15 => {
+                let raw: [u8; 4] = self.bytes(pos, 4)?.try_into().map_err(|_| "bad float")?;
+                (Value::Double(f64::from(f32::from_be_bytes(raw))), pos + 4)
+            }
+            other => return Err(format!("unsupported data type {other}")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a control byte (and extended type byte) for a small value.
+    fn ctrl(kind: u8, size: usize) -> Vec<u8> {
+        assert!(size < 29);
+        if kind <= 7 {
+            vec![(kind << 5) | size as u8]
+        } else {
+            vec![size as u8, kind - 7]
+        }
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut out = ctrl(2, s.len());
+        out.extend_from_slice(s.as_bytes());
+        out
+    }
+
+    fn uint(kind: u8, n: u32) -> Vec<u8> {
+        let bytes: Vec<u8> = n
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|&b| b == 0)
+            .collect();
+        let mut out = ctrl(kind, bytes.len());
+        out.extend(bytes);
+        out
+    }
+
+    fn map(entries: Vec<(&str, Vec<u8>)>) -> Vec<u8> {
+        let mut out = ctrl(7, entries.len());
+        for (key, value) in entries {
+            out.extend(string(key));
+            out.extend(value);
+        }
+        out
+    }
+
+    /// Build a 24-bit-record IPv4 database mapping each prefix to a value.
+    fn build_db(prefixes: &[([u8; 4], u8, Vec<u8>)]) -> Vec<u8> {
+        // Trie nodes: [left, right], each a node index or a data marker.
+        #[derive(Clone, Copy)]
+        enum Rec {
+            Empty,
+            Node(u32),
+            Data(usize),
+        }
+        let mut nodes: Vec<[Rec; 2]> = vec![[Rec::Empty, Rec::Empty]];
+        let mut data = Vec::new();
+        for (addr, len, value) in prefixes {
+            let offset = data.len();
+            data.extend_from_slice(value);
+            let bits = to_bits(addr);
+            let mut node = 0usize;
+            for (i, &bit) in bits.iter().take(usize::from(*len)).enumerate() {
+                let side = usize::from(bit);
+                if i + 1 == usize::from(*len) {
+                    nodes[node][side] = Rec::Data(offset);
+                } else {
+                    node = match nodes[node][side] {
+                        Rec::Node(n) => n as usize,
+                        _ => {
+                            nodes.push([Rec::Empty, Rec::Empty]);
+                            let n = nodes.len() - 1;
+                            nodes[node][side] = Rec::Node(n as u32);
+                            n
+                        }
+                    };
+                }
+            }
+        }
+        let node_count = nodes.len() as u32;
+        let mut out = Vec::new();
+        for pair in &nodes {
+            for rec in pair {
+                let value = match *rec {
+                    Rec::Empty => node_count,
+                    Rec::Node(n) => n,
+                    Rec::Data(offset) => node_count + 16 + offset as u32,
+                };
+                out.extend_from_slice(&value.to_be_bytes()[1..]);
+            }
+        }
+        out.extend_from_slice(&[0u8; 16]);
+        out.extend(data);
+        out.extend_from_slice(METADATA_MARKER);
+        out.extend(map(vec![
+            ("node_count", uint(6, node_count)),
+            ("record_size", uint(5, 24)),
+            ("ip_version", uint(5, 4)),
+            ("database_type", string("Test-ASN-Country")),
+        ]));
+        out
+    }
+
+    fn write_db(name: &str, bytes: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ochra-geo-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("mkdir");
+        let path = dir.join("test.mmdb");
+        std::fs::write(&path, bytes).expect("write");
+        path
+    }
+
+    fn sample_db() -> Vec<u8> {
+        build_db(&[
+            (
+                [10, 1, 2, 0],
+                24,
+                map(vec![
+                    ("autonomous_system_number", uint(6, 64_500)),
+                    ("country", map(vec![("iso_code", string("de"))])),
+                ]),
+            ),
+            (
+                [192, 0, 2, 0],
+                24,
+                map(vec![(
+                    "registered_country",
+                    map(vec![("iso_code", string("JP"))]),
+                )]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_mmdb_lookup() {
+        let path = write_db("lookup", &sample_db());
+        let resolver = MmdbResolver::open(&[&path]).expect("open");
+        assert!(resolver.is_available());
+        assert_eq!(resolver.database_types(), vec!["Test-ASN-Country"]);
+
+        let info = resolver.resolve("10.1.2.77".parse().expect("ip"));
+        assert_eq!(info.as_number, Some(64_500));
+        assert_eq!(info.country_code, Some(*b"DE"));
+
+        let info = resolver.resolve("::ffff:192.0.2.1".parse().expect("ip"));
+        assert_eq!(info.as_number, None);
+        assert_eq!(info.country_code, Some(*b"JP"));
+
+        assert_eq!(
+            resolver.resolve("10.1.3.1".parse().expect("ip")),
+            GeoInfo::default()
+        );
+        assert_eq!(
+            resolver.resolve("2001:db8::1".parse().expect("ip")),
+            GeoInfo::default()
+        );
+    }
+
+    #[test]
+    fn test_later_database_fills_gaps() {
+        let first = write_db("first", &sample_db());
+        let second = write_db(
+            "second",
+            &build_db(&[(
+                [192, 0, 2, 0],
+                24,
+                map(vec![
+                    ("autonomous_system_number", uint(6, 64_511)),
+                    ("country", map(vec![("iso_code", string("US"))])),
+                ]),
+            )]),
+        );
+        let resolver = MmdbResolver::open(&[&first, &second]).expect("open");
+        let info = resolver.resolve("192.0.2.9".parse().expect("ip"));
+        assert_eq!(info.as_number, Some(64_511));
+        assert_eq!(info.country_code, Some(*b"JP"));
+    }
+
+    #[test]
+    fn test_invalid_database_rejected() {
+        let path = write_db("invalid", b"not a database");
+        assert!(matches!(
+            MmdbResolver::open(&[&path]),
+            Err(OnionError::Geo(_))
+        ));
+        assert!(MmdbResolver::open(&[Path::new("/nonexistent/geo.mmdb")]).is_err());
+    }
+
+    #[test]
+    fn test_record_into_separator_is_an_error() {
+        let mut db = Mmdb::parse(sample_db()).expect("parse");
+        // Point 10.0.0.0/1's first record just past the tree, inside the
+        // separator.
+        let bad = db.node_count + 5;
+        db.bytes[0..3].copy_from_slice(&bad.to_be_bytes()[1..]);
+        assert!(db.lookup("10.1.2.77".parse().expect("ip")).is_err());
+    }
+
+    #[test]
+    fn test_decode_pointer_and_negative_int() {
+        // [string "ab", pointer -> 0, int32 -1]
+        let mut section = string("ab");
+        section.extend([0x20, 0x00]);
+        section.extend(ctrl(8, 4));
+        section.extend([0xFF; 4]);
+        let decoder = Decoder { section: &section };
+        let (value, next) = decoder.decode(3, 0).expect("pointer");
+        assert_eq!(value, Value::String("ab".to_string()));
+        assert_eq!(next, 5);
+        let (value, _) = decoder.decode(5, 0).expect("int");
+        assert_eq!(value, Value::Int(-1));
+    }
+
+    #[test]
+    fn test_no_geo_resolver() {
+        assert!(!NoGeoResolver.is_available());
+        assert_eq!(
+            NoGeoResolver.resolve("10.0.0.1".parse().expect("ip")),
+            GeoInfo::default()
+        );
+    }
+}
@@ -0,0 +1,472 @@
+//! End-to-end circuit health monitoring and failover.
+//!
+//! A circuit that silently stops forwarding leaves the node sending into a
+//! void until the 10-minute rotation replaces it. The [`CircuitMonitor`]
+//! sends echo cells through each tracked circuit and tears it down after
+//! [`FAILOVER_THRESHOLD`] consecutive failures, so a replacement can be built
+//! straight away.
+//!
+//! ## Echo Probes
+//!
+//! An [`EchoCell`] names the hop that should answer it (`depth`, 0 = entry).
+//! A healthy circuit is probed end-to-end (to the exit) every
+//! [`ECHO_INTERVAL_MS`]. When an end-to-end probe fails, the monitor walks
+//! the circuit from the entry outward with shallower probes, sent without
+//! waiting for the interval. The first hop that fails to answer becomes the
+//! suspect; if every hop before the exit answers, the exit is the suspect.
+//! The suspect relay is reported on failover so the replacement circuit can
+//! avoid it.
+//!
+//! ## Status
+//!
+//! | Consecutive failures | Status |
+//! |---|---|
+//! | 0 | [`HealthStatus::Healthy`] |
+//! | 1 .. [`FAILOVER_THRESHOLD`] | [`HealthStatus::Degraded`] |
+//! | >= [`FAILOVER_THRESHOLD`] | [`HealthStatus::Failed`] |
+//!
+//! Any failed probe counts toward the threshold; only a successful
+//! end-to-end echo resets it.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circuit::Circuit;
+use crate::CIRCUIT_HOPS;
+
+/// Interval between end-to-end probes of a healthy circuit (milliseconds).
+pub const ECHO_INTERVAL_MS: u64 = 30_000;
+
+/// An echo not answered within this long counts as failed (milliseconds).
+pub const ECHO_TIMEOUT_MS: u64 = 5_000;
+
+/// Consecutive failed probes after which a circuit is torn down.
+pub const FAILOVER_THRESHOLD: u32 = 3;
+
+/// Depth of the exit hop.
+const EXIT_DEPTH: u8 = (CIRCUIT_HOPS - 1) as u8;
+
+/// Echo cell payload carried through a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EchoCell {
+    /// Random identifier echoed back by the answering hop.
+    pub probe_id: [u8; 8],
+    /// Hop that should answer (0 = entry).
+    pub depth: u8,
+}
+
+/// An echo probe the caller should send.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EchoProbe {
+    /// Circuit to send the probe through.
+    pub circuit_id: [u8; 16],
+    /// The cell to send.
+    pub cell: EchoCell,
+}
+
+/// Health of a tracked circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// The last end-to-end echo succeeded.
+    Healthy,
+    /// Recent probes failed, below the failover threshold.
+    Degraded,
+    /// The failover threshold was reached; the circuit should be replaced.
+    Failed,
+}
+
+/// Probe counters for one hop.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HopCounters {
+    /// Echoes that passed through or were answered by this hop.
+    pub echoes: u32,
+    /// Probes addressed to this hop that went unanswered.
+    pub failures: u32,
+}
+
+/// A status transition reported by the monitor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HealthChange {
+    /// A healthy circuit started failing probes.
+    Degraded {
+        /// The circuit concerned.
+        circuit_id: [u8; 16],
+        /// Consecutive failed probes so far.
+        consecutive_failures: u32,
+    },
+    /// A degraded circuit answered an end-to-end echo again.
+    Recovered {
+        /// The circuit concerned.
+        circuit_id: [u8; 16],
+    },
+    /// The circuit reached the failover threshold and is no longer tracked.
+    Failed {
+        /// The circuit concerned.
+        circuit_id: [u8; 16],
+        /// Hop blamed for the failures, if localization finished.
+        suspect_hop: Option<u8>,
+        /// Node ID of the suspect relay.
+        suspect_relay: Option<[u8; 32]>,
+    },
+}
+
+/// Probe state for one circuit.
+#[derive(Clone, Debug)]
+pub struct CircuitHealth {
+    /// Relay node IDs, entry first.
+    pub relays: [[u8; 32]; CIRCUIT_HOPS],
+    /// Per-hop probe counters.
+    pub hops: [HopCounters; CIRCUIT_HOPS],
+    /// Failed probes since the last successful end-to-end echo.
+    pub consecutive_failures: u32,
+    /// Round trip of the last successful end-to-end echo (milliseconds).
+    pub last_rtt_ms: Option<u64>,
+    /// Hop currently blamed for failures.
+    pub suspect_hop: Option<u8>,
+    /// Probe awaiting an answer, with its send time.
+    pending: Option<(EchoCell, u64)>,
+    /// Next hop to probe while localizing a failure.
+    localize_depth: Option<u8>,
+    /// Time the last probe was sent.
+    last_probe_ms: Option<u64>,
+}
+
+impl CircuitHealth {
+    fn new(relays: [[u8; 32]; CIRCUIT_HOPS]) -> Self {
+        Self {
+            relays,
+            hops: [HopCounters::default(); CIRCUIT_HOPS],
+            consecutive_failures: 0,
+            last_rtt_ms: None,
+            suspect_hop: None,
+            pending: None,
+            localize_depth: None,
+            last_probe_ms: None,
+        }
+    }
+
+    /// Current status.
+    pub fn status(&self) -> HealthStatus {
+        match self.consecutive_failures {
+            0 => HealthStatus::Healthy,
+            n if n < FAILOVER_THRESHOLD => HealthStatus::Degraded,
+            _ => HealthStatus::Failed,
+        }
+    }
+
+    fn is_due(&self, now_ms: u64) -> bool {
+        if self.pending.is_some() {
+            return false;
+        }
+        if self.localize_depth.is_some() {
+            return true;
+        }
+        self.last_probe_ms
+            .is_none_or(|at| now_ms.saturating_sub(at) >= ECHO_INTERVAL_MS)
+    }
+
+    /// Record a failed probe at `depth`.
+    fn on_failure(&mut self, depth: u8) {
+        if let Some(hop) = self.hops.get_mut(usize::from(depth)) {
+            hop.failures = hop.failures.saturating_add(1);
+        }
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if depth == EXIT_DEPTH && self.localize_depth.is_none() {
+            self.localize_depth = Some(0);
+        } else {
+            self.suspect_hop = Some(depth);
+            self.localize_depth = None;
+        }
+    }
+
+    /// Record an answered probe at `depth`.
+    fn on_echo(&mut self, depth: u8, rtt_ms: u64) {
+        for hop in self.hops.iter_mut().take(usize::from(depth) + 1) {
+            hop.echoes = hop.echoes.saturating_add(1);
+        }
+        if depth == EXIT_DEPTH {
+            self.consecutive_failures = 0;
+            self.suspect_hop = None;
+            self.localize_depth = None;
+            self.last_rtt_ms = Some(rtt_ms);
+        } else if depth + 1 == EXIT_DEPTH {
+            // Everything up to the exit answers: blame the exit.
+            self.suspect_hop = Some(EXIT_DEPTH);
+            self.localize_depth = None;
+        } else {
+            self.localize_depth = Some(depth + 1);
+        }
+    }
+}
+
+/// Tracks the health of the node's active circuits.
+#[derive(Debug, Default)]
+pub struct CircuitMonitor {
+    circuits: HashMap<[u8; 16], CircuitHealth>,
+}
+
+impl CircuitMonitor {
+    /// Create an empty monitor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start monitoring a newly built circuit.
+    pub fn track(&mut self, circuit: &Circuit) {
+        let mut relays = [[0u8; 32]; CIRCUIT_HOPS];
+        for (slot, hop) in relays.iter_mut().zip(circuit.hops()) {
+            *slot = hop.node_id;
+        }
+        self.track_relays(*circuit.circuit_id(), relays);
+    }
+
+    /// Start monitoring a circuit given its ID and relay node IDs.
+    pub fn track_relays(&mut self, circuit_id: [u8; 16], relays: [[u8; 32]; CIRCUIT_HOPS]) {
+        self.circuits.insert(circuit_id, CircuitHealth::new(relays));
+    }
+
+    /// Stop monitoring a circuit (rotated or torn down).
+    pub fn untrack(&mut self, circuit_id: &[u8; 16]) -> Option<CircuitHealth> {
+        self.circuits.remove(circuit_id)
+    }
+
+    /// Health of a tracked circuit.
+    pub fn get(&self, circuit_id: &[u8; 16]) -> Option<&CircuitHealth> {
+        self.circuits.get(circuit_id)
+    }
+
+    /// All tracked circuits.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8; 16], &CircuitHealth)> {
+        self.circuits.iter()
+    }
+
+    /// Number of tracked circuits.
+    pub fn len(&self) -> usize {
+        self.circuits.len()
+    }
+
+    /// Whether no circuits are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.circuits.is_empty()
+    }
+
+    /// Number of tracked circuits with [`HealthStatus::Healthy`].
+    pub fn healthy_count(&self) -> usize {
+        self.circuits
+            .values()
+            .filter(|c| c.status() == HealthStatus::Healthy)
+            .count()
+    }
+
+    /// Start every probe that is due and return them for sending.
+    pub fn start_due_probes(&mut self, now_ms: u64) -> Vec<EchoProbe> {
+        let mut probes = Vec::new();
+        for (circuit_id, health) in &mut self.circuits {
+            if !health.is_due(now_ms) {
+                continue;
+            }
+            let cell = EchoCell {
+                probe_id: rand::random(),
+                depth: health.localize_depth.unwrap_or(EXIT_DEPTH),
+            };
+            health.pending = Some((cell, now_ms));
+            health.last_probe_ms = Some(now_ms);
+            probes.push(EchoProbe {
+                circuit_id: *circuit_id,
+                cell,
+            });
+        }
+        probes
+    }
+
+    /// Record an echo answer.
+    ///
+    /// Answers that do not match the circuit's outstanding probe are ignored.
+    pub fn on_echo(
+        &mut self,
+        circuit_id: &[u8; 16],
+        cell: &EchoCell,
+        now_ms: u64,
+    ) -> Option<HealthChange> {
+        let health = self.circuits.get_mut(circuit_id)?;
+        let (sent, sent_ms) = health.pending?;
+        if sent != *cell {
+            return None;
+        }
+        health.pending = None;
+        let before = health.status();
+        health.on_echo(cell.depth, now_ms.saturating_sub(sent_ms));
+        (before != HealthStatus::Healthy && health.status() == HealthStatus::Healthy).then_some(
+            HealthChange::Recovered {
+                circuit_id: *circuit_id,
+            },
+        )
+    }
+
+    /// Fail probes older than [`ECHO_TIMEOUT_MS`] and report transitions.
+    ///
+    /// Circuits that reach [`HealthStatus::Failed`] are removed; the caller
+    /// tears them down and builds replacements.
+    pub fn expire(&mut self, now_ms: u64) -> Vec<HealthChange> {
+        let mut changes = Vec::new();
+        for (circuit_id, health) in &mut self.circuits {
+            let Some((cell, sent_ms)) = health.pending else {
+                continue;
+            };
+            if now_ms.saturating_sub(sent_ms) < ECHO_TIMEOUT_MS {
+                continue;
+            }
+            health.pending = None;
+            let before = health.status();
+            health.on_failure(cell.depth);
+            match health.status() {
+                HealthStatus::Degraded if before == HealthStatus::Healthy => {
+                    changes.push(HealthChange::Degraded {
+                        circuit_id: *circuit_id,
+                        consecutive_failures: health.consecutive_failures,
+                    });
+                }
+                HealthStatus::Failed => changes.push(HealthChange::Failed {
+                    circuit_id: *circuit_id,
+                    suspect_hop: health.suspect_hop,
+                    suspect_relay: health
+                        .suspect_hop
+                        .and_then(|h| health.relays.get(usize::from(h)).copied()),
+                }),
+                _ => {}
+            }
+        }
+        for change in &changes {
+            if let HealthChange::Failed { circuit_id, .. } = change {
+                self.circuits.remove(circuit_id);
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CID: [u8; 16] = [9; 16];
+
+    fn monitor() -> CircuitMonitor {
+        let mut m = CircuitMonitor::new();
+        m.track_relays(CID, [[1; 32], [2; 32], [3; 32]]);
+        m
+    }
+
+    /// Send the next due probe and let it time out.
+    fn fail_next(m: &mut CircuitMonitor, now: &mut u64) -> (EchoCell, Vec<HealthChange>) {
+        let probes = m.start_due_probes(*now);
+        assert_eq!(probes.len(), 1);
+        *now += ECHO_TIMEOUT_MS;
+        (probes[0].cell, m.expire(*now))
+    }
+
+    #[test]
+    fn test_healthy_circuit_probes_end_to_end_on_interval() {
+        let mut m = monitor();
+        let probes = m.start_due_probes(0);
+        assert_eq!(probes.len(), 1);
+        assert_eq!(probes[0].cell.depth, EXIT_DEPTH);
+        assert!(m.start_due_probes(1).is_empty());
+
+        assert_eq!(m.on_echo(&CID, &probes[0].cell, 120), None);
+        let health = m.get(&CID).expect("tracked");
+        assert_eq!(health.last_rtt_ms, Some(120));
+        assert_eq!(health.hops[0].echoes, 1);
+        assert_eq!(health.hops[2].echoes, 1);
+        assert!(m.start_due_probes(ECHO_INTERVAL_MS - 1).is_empty());
+        assert_eq!(m.start_due_probes(ECHO_INTERVAL_MS).len(), 1);
+    }
+
+    #[test]
+    fn test_mismatched_echo_is_ignored() {
+        let mut m = monitor();
+        let probes = m.start_due_probes(0);
+        let mut forged = probes[0].cell;
+        forged.probe_id[0] ^= 1;
+        assert_eq!(m.on_echo(&CID, &forged, 10), None);
+        assert_eq!(m.get(&CID).and_then(|h| h.last_rtt_ms), None);
+    }
+
+    #[test]
+    fn test_failure_localizes_to_middle_hop_and_fails_over() {
+        let mut m = monitor();
+        let mut now = 0;
+
+        let (cell, changes) = fail_next(&mut m, &mut now);
+        assert_eq!(cell.depth, EXIT_DEPTH);
+        assert_eq!(
+            changes,
+            vec![HealthChange::Degraded {
+                circuit_id: CID,
+                consecutive_failures: 1
+            }]
+        );
+
+        // Localization starts at the entry, which answers.
+        let probes = m.start_due_probes(now);
+        assert_eq!(probes[0].cell.depth, 0);
+        assert_eq!(m.on_echo(&CID, &probes[0].cell, now + 30), None);
+
+        // The middle hop does not.
+        let (cell, changes) = fail_next(&mut m, &mut now);
+        assert_eq!(cell.depth, 1);
+        assert!(changes.is_empty());
+        assert_eq!(m.get(&CID).and_then(|h| h.suspect_hop), Some(1));
+
+        now += ECHO_INTERVAL_MS;
+        let (_, changes) = fail_next(&mut m, &mut now);
+        assert_eq!(
+            changes,
+            vec![HealthChange::Failed {
+                circuit_id: CID,
+                suspect_hop: Some(1),
+                suspect_relay: Some([2; 32]),
+            }]
+        );
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn test_exit_blamed_when_inner_hops_answer() {
+        let mut m = monitor();
+        let mut now = 0;
+        fail_next(&mut m, &mut now);
+        for depth in 0..EXIT_DEPTH {
+            let probes = m.start_due_probes(now);
+            assert_eq!(probes[0].cell.depth, depth);
+            m.on_echo(&CID, &probes[0].cell, now + 10);
+        }
+        let health = m.get(&CID).expect("tracked");
+        assert_eq!(health.suspect_hop, Some(EXIT_DEPTH));
+        assert_eq!(health.hops[2].failures, 1);
+        assert_eq!(health.status(), HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn test_end_to_end_success_recovers() {
+        let mut m = monitor();
+        let mut now = 0;
+        fail_next(&mut m, &mut now);
+        // Localization finds the inner hops answering, then the next
+        // end-to-end echo succeeds.
+        let probes = m.start_due_probes(now);
+        m.on_echo(&CID, &probes[0].cell, now + 10);
+        let probes = m.start_due_probes(now + 10);
+        m.on_echo(&CID, &probes[0].cell, now + 20);
+        now += ECHO_INTERVAL_MS + 20;
+        let probes = m.start_due_probes(now);
+        assert_eq!(probes[0].cell.depth, EXIT_DEPTH);
+        assert_eq!(
+            m.on_echo(&CID, &probes[0].cell, now + 50),
+            Some(HealthChange::Recovered { circuit_id: CID })
+        );
+        assert_eq!(m.healthy_count(), 1);
+    }
+}
@@ -0,0 +1,329 @@
+//! Stream isolation: which traffic may share a circuit.
+//!
+//! Traffic that shares a circuit shares an exit and a timing pattern, so an
+//! observer at the exit (or anyone correlating its output) can link it. If a
+//! user's Whisper session and their content downloads ride the same circuit,
+//! the two activities become attributable to one person. The
+//! [`CircuitIsolator`] maps each stream's [`StreamContext`] to an
+//! [`IsolationKey`] and only hands out circuits bound to the same key,
+//! mirroring Tor's stream isolation.
+//!
+//! ## Modes
+//!
+//! Each user-facing kind of traffic has an [`IsolationMode`]:
+//!
+//! | Mode | Circuits shared by |
+//! |---|---|
+//! | [`IsolationMode::Shared`] | every kind also set to `Shared` |
+//! | [`IsolationMode::PerKind`] | all contexts of the same kind |
+//! | [`IsolationMode::PerContext`] | one contact, group, or download |
+//!
+//! Background traffic (DHT lookups, directory fetches, announcements) is
+//! always kept on its own circuits, apart from user traffic.
+//!
+//! A circuit is bound to the first key that uses it and never carries
+//! another key's streams, even after the first context goes idle; it is only
+//! released when torn down.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OnionError, Result};
+
+/// Kind of traffic a stream carries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextKind {
+    /// Whisper messages with one contact.
+    Whisper,
+    /// Traffic for one group (Space).
+    Group,
+    /// Chunk fetches for one content download.
+    Download,
+    /// Network housekeeping not linked to user activity.
+    Background,
+}
+
+/// The application context a stream belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StreamContext {
+    /// A Whisper session with the contact identified by `contact`.
+    Whisper {
+        /// Contact identifier (session or PIK hash).
+        contact: [u8; 32],
+    },
+    /// Traffic for the group `group_id`.
+    Group {
+        /// Group identifier.
+        group_id: [u8; 32],
+    },
+    /// Fetches for the content `content_hash`.
+    Download {
+        /// Content hash of the download.
+        content_hash: [u8; 32],
+    },
+    /// Background network traffic.
+    Background,
+}
+
+impl StreamContext {
+    /// The kind of traffic.
+    pub fn kind(&self) -> ContextKind {
+        match self {
+            Self::Whisper { .. } => ContextKind::Whisper,
+            Self::Group { .. } => ContextKind::Group,
+            Self::Download { .. } => ContextKind::Download,
+            Self::Background => ContextKind::Background,
+        }
+    }
+
+    fn scope(&self) -> Option<[u8; 32]> {
+        match self {
+            Self::Whisper { contact } => Some(*contact),
+            Self::Group { group_id } => Some(*group_id),
+            Self::Download { content_hash } => Some(*content_hash),
+            Self::Background => None,
+        }
+    }
+}
+
+/// How finely one kind of traffic is isolated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IsolationMode {
+    /// Share circuits with every other `Shared` kind.
+    Shared,
+    /// One set of circuits for the whole kind.
+    PerKind,
+    /// One set of circuits per contact, group, or download.
+    PerContext,
+}
+
+impl IsolationMode {
+    /// Parse a config string: "shared" | "per_kind" | "per_context".
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "shared" => Some(Self::Shared),
+            "per_kind" => Some(Self::PerKind),
+            "per_context" => Some(Self::PerContext),
+            _ => None,
+        }
+    }
+}
+
+/// Isolation mode for each user-facing kind of traffic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IsolationPolicy {
+    /// Whisper sessions.
+    pub whisper: IsolationMode,
+    /// Group traffic.
+    pub groups: IsolationMode,
+    /// Content downloads.
+    pub downloads: IsolationMode,
+}
+
+impl Default for IsolationPolicy {
+    /// Whisper and groups are isolated per context; downloads share one set
+    /// of circuits, since a circuit per download multiplies build cost for
+    /// little gain when purchases are already unlinkable.
+    fn default() -> Self {
+        Self {
+            whisper: IsolationMode::PerContext,
+            groups: IsolationMode::PerContext,
+            downloads: IsolationMode::PerKind,
+        }
+    }
+}
+
+impl IsolationPolicy {
+    /// The mode applied to `kind`. Background traffic is always per kind.
+    pub fn mode(&self, kind: ContextKind) -> IsolationMode {
+        match kind {
+            ContextKind::Whisper => self.whisper,
+            ContextKind::Group => self.groups,
+            ContextKind::Download => self.downloads,
+            ContextKind::Background => IsolationMode::PerKind,
+        }
+    }
+}
+
+/// Streams with equal keys may share a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IsolationKey {
+    kind: Option<ContextKind>,
+    scope: Option<[u8; 32]>,
+}
+
+impl IsolationKey {
+    /// The key of the shared circuit pool.
+    pub const SHARED: Self = Self {
+        kind: None,
+        scope: None,
+    };
+}
+
+/// Binds circuits to isolation keys.
+#[derive(Debug, Default)]
+pub struct CircuitIsolator {
+    policy: IsolationPolicy,
+    /// Current circuit for each key.
+    by_key: HashMap<IsolationKey, [u8; 16]>,
+    /// Key each live circuit is bound to.
+    by_circuit: HashMap<[u8; 16], IsolationKey>,
+}
+
+impl CircuitIsolator {
+    /// Create an isolator applying `policy`.
+    pub fn new(policy: IsolationPolicy) -> Self {
+        Self {
+            policy,
+            ..Self::default()
+        }
+    }
+
+    /// The policy in force.
+    pub fn policy(&self) -> &IsolationPolicy {
+        &self.policy
+    }
+
+    /// Change the policy.
+    ///
+    /// Circuits already bound keep their keys; new streams are keyed under
+    /// the new policy and so never reuse a circuit bound under the old one
+    /// unless the keys coincide.
+    pub fn set_policy(&mut self, policy: IsolationPolicy) {
+        self.policy = policy;
+    }
+
+    /// The isolation key for a stream in `ctx`.
+    pub fn key_for(&self, ctx: &StreamContext) -> IsolationKey {
+        let kind = ctx.kind();
+        match self.policy.mode(kind) {
+            IsolationMode::Shared => IsolationKey::SHARED,
+            IsolationMode::PerKind => IsolationKey {
+                kind: Some(kind),
+                scope: None,
+            },
+            IsolationMode::PerContext => IsolationKey {
+                kind: Some(kind),
+                scope: ctx.scope(),
+            },
+        }
+    }
+
+    /// The circuit a stream in `ctx` should use, if one is bound.
+    ///
+    /// `None` means the caller must build a new circuit and [`bind`] it.
+    ///
+    /// [`bind`]: CircuitIsolator::bind
+    pub fn circuit_for(&self, ctx: &StreamContext) -> Option<[u8; 16]> {
+        self.by_key.get(&self.key_for(ctx)).copied()
+    }
+
+    /// Bind `circuit_id` to the key of `ctx`.
+    ///
+    /// The circuit becomes the key's current circuit, replacing any previous
+    /// one (which stays bound to the key until released). Fails if the
+    /// circuit already carries another key's streams.
+    pub fn bind(&mut self, ctx: &StreamContext, circuit_id: [u8; 16]) -> Result<IsolationKey> {
+        let key = self.key_for(ctx);
+        if let Some(bound) = self.by_circuit.get(&circuit_id) {
+            if *bound != key {
+                return Err(OnionError::IsolationViolation(format!(
+                    "circuit {} is bound to another context",
+                    hex::encode(circuit_id)
+                )));
+            }
+        }
+        self.by_circuit.insert(circuit_id, key);
+        self.by_key.insert(key, circuit_id);
+        Ok(key)
+    }
+
+    /// Forget a circuit that was torn down or rotated out.
+    pub fn release(&mut self, circuit_id: &[u8; 16]) {
+        if let Some(key) = self.by_circuit.remove(circuit_id) {
+            if self.by_key.get(&key) == Some(circuit_id) {
+                self.by_key.remove(&key);
+            }
+        }
+    }
+
+    /// Number of live circuits bound to a key.
+    pub fn bound_circuits(&self) -> usize {
+        self.by_circuit.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: StreamContext = StreamContext::Whisper { contact: [1; 32] };
+    const BOB: StreamContext = StreamContext::Whisper { contact: [2; 32] };
+    const SPACE: StreamContext = StreamContext::Group { group_id: [3; 32] };
+
+    #[test]
+    fn test_per_context_separates_contacts() {
+        let mut iso = CircuitIsolator::new(IsolationPolicy::default());
+        iso.bind(&ALICE, [1; 16]).expect("bind");
+        assert_eq!(iso.circuit_for(&ALICE), Some([1; 16]));
+        assert_eq!(iso.circuit_for(&BOB), None);
+        assert!(iso.bind(&BOB, [1; 16]).is_err());
+        iso.bind(&BOB, [2; 16]).expect("bind");
+        assert_eq!(iso.circuit_for(&BOB), Some([2; 16]));
+    }
+
+    #[test]
+    fn test_per_kind_shares_within_kind_only() {
+        let policy = IsolationPolicy {
+            whisper: IsolationMode::PerKind,
+            ..IsolationPolicy::default()
+        };
+        let mut iso = CircuitIsolator::new(policy);
+        iso.bind(&ALICE, [1; 16]).expect("bind");
+        assert_eq!(iso.circuit_for(&BOB), Some([1; 16]));
+        assert_eq!(iso.circuit_for(&SPACE), None);
+        assert!(iso.bind(&SPACE, [1; 16]).is_err());
+    }
+
+    #[test]
+    fn test_shared_mode_never_includes_background() {
+        let policy = IsolationPolicy {
+            whisper: IsolationMode::Shared,
+            groups: IsolationMode::Shared,
+            downloads: IsolationMode::Shared,
+        };
+        let mut iso = CircuitIsolator::new(policy);
+        iso.bind(&ALICE, [1; 16]).expect("bind");
+        assert_eq!(iso.circuit_for(&SPACE), Some([1; 16]));
+        assert_eq!(iso.circuit_for(&StreamContext::Background), None);
+        assert!(iso.bind(&StreamContext::Background, [1; 16]).is_err());
+    }
+
+    #[test]
+    fn test_release_and_rotation() {
+        let mut iso = CircuitIsolator::new(IsolationPolicy::default());
+        iso.bind(&ALICE, [1; 16]).expect("bind");
+        iso.bind(&ALICE, [2; 16]).expect("rotate");
+        assert_eq!(iso.circuit_for(&ALICE), Some([2; 16]));
+        assert_eq!(iso.bound_circuits(), 2);
+
+        // Releasing the old circuit leaves the current one in place.
+        iso.release(&[1; 16]);
+        assert_eq!(iso.circuit_for(&ALICE), Some([2; 16]));
+        iso.release(&[2; 16]);
+        assert_eq!(iso.circuit_for(&ALICE), None);
+        assert_eq!(iso.bound_circuits(), 0);
+    }
+
+    #[test]
+    fn test_mode_parse() {
+        assert_eq!(
+            IsolationMode::parse("per_kind"),
+            Some(IsolationMode::PerKind)
+        );
+        assert_eq!(IsolationMode::parse("strict"), None);
+    }
+}
@@ -0,0 +1,252 @@
+//! Opportunistic round-trip latency measurement for cached relays.
+//!
+//! Relay selection weighs PoSrv score and network diversity, neither of which
+//! says anything about how far away a relay is. For interactive traffic
+//! (Whisper) a distant entry hop adds its round trip to every message, so the
+//! node keeps a smoothed RTT estimate per relay and lets
+//! [`SelectionConstraints`](crate::relay::SelectionConstraints) prefer nearby
+//! entry hops.
+//!
+//! ## Probing
+//!
+//! Probes are only sent over connections the node already holds: the
+//! [`RttProber`] never dials a relay just to time it. Each round it picks up
+//! to [`MAX_PROBES_PER_ROUND`] connected relays whose last probe is older
+//! than [`PROBE_INTERVAL_MS`], the caller sends a transport `Ping` carrying
+//! the returned nonce, and the matching `Pong` is fed back through
+//! [`RttProber::on_pong`], which folds the sample into the
+//! [`RelayCache`](crate::relay::RelayCache).
+//!
+//! ## Smoothing
+//!
+//! Samples are combined with an exponentially weighted moving average
+//! (`ewma = ewma + alpha * (sample - ewma)`, `alpha` = [`RTT_EWMA_ALPHA`]).
+//! Estimates not refreshed within [`LATENCY_STALE_MS`] are dropped, since a
+//! relay that has moved or lost its connection should not keep its old
+//! standing.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::relay::RelayCache;
+
+/// Weight of a new sample in the RTT moving average.
+pub const RTT_EWMA_ALPHA: f64 = 0.25;
+
+/// Minimum interval between probes of the same relay (milliseconds).
+pub const PROBE_INTERVAL_MS: u64 = 60_000;
+
+/// A probe without a pong after this long is abandoned (milliseconds).
+pub const PROBE_TIMEOUT_MS: u64 = 10_000;
+
+/// Maximum number of probes started per round.
+pub const MAX_PROBES_PER_ROUND: usize = 8;
+
+/// Estimates older than this are discarded (milliseconds).
+pub const LATENCY_STALE_MS: u64 = 600_000;
+
+/// Entry-hop RTT target for interactive (Whisper) circuits (milliseconds).
+pub const INTERACTIVE_ENTRY_RTT_MS: u32 = 150;
+
+/// Smoothed round-trip time estimate for one relay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LatencyEstimate {
+    /// Moving average of observed RTTs (milliseconds).
+    pub ewma_ms: f64,
+    /// Number of samples folded into the average.
+    pub samples: u32,
+    /// Time of the most recent sample (milliseconds since the Unix epoch).
+    pub last_sample_ms: u64,
+}
+
+impl LatencyEstimate {
+    /// Start an estimate from a first sample.
+    pub fn new(rtt_ms: f64, now_ms: u64) -> Self {
+        Self {
+            ewma_ms: rtt_ms,
+            samples: 1,
+            last_sample_ms: now_ms,
+        }
+    }
+
+    /// Fold a new sample into the average.
+    pub fn observe(&mut self, rtt_ms: f64, now_ms: u64) {
+        self.ewma_ms += RTT_EWMA_ALPHA * (rtt_ms - self.ewma_ms);
+        self.samples = self.samples.saturating_add(1);
+        self.last_sample_ms = now_ms;
+    }
+
+    /// Whether the estimate has been refreshed recently enough to use.
+    pub fn is_fresh(&self, now_ms: u64) -> bool {
+        now_ms.saturating_sub(self.last_sample_ms) <= LATENCY_STALE_MS
+    }
+}
+
+/// A ping awaiting its pong.
+#[derive(Clone, Copy, Debug)]
+struct PendingProbe {
+    node_id: [u8; 32],
+    sent_ms: u64,
+}
+
+/// Schedules RTT probes over existing relay connections and matches pongs.
+#[derive(Debug, Default)]
+pub struct RttProber {
+    /// Outstanding probes keyed by ping nonce.
+    pending: HashMap<[u8; 8], PendingProbe>,
+    /// Time each relay was last probed.
+    last_probe: HashMap<[u8; 32], u64>,
+}
+
+impl RttProber {
+    /// Create a prober with no probe history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Relays that should be probed now.
+    ///
+    /// Only relays in `cache` with an open connection (`connected`) are
+    /// considered. Relays never probed come first, then the least recently
+    /// probed, up to [`MAX_PROBES_PER_ROUND`].
+    pub fn due_probes(
+        &self,
+        cache: &RelayCache,
+        connected: &HashSet<[u8; 32]>,
+        now_ms: u64,
+    ) -> Vec<[u8; 32]> {
+        let in_flight: HashSet<[u8; 32]> = self.pending.values().map(|p| p.node_id).collect();
+        let mut due: Vec<(Option<u64>, [u8; 32])> = cache
+            .all()
+            .iter()
+            .map(|r| r.node_id)
+            .filter(|id| connected.contains(id) && !in_flight.contains(id))
+            .filter_map(|id| match self.last_probe.get(&id) {
+                Some(&at) if now_ms.saturating_sub(at) < PROBE_INTERVAL_MS => None,
+                last => Some((last.copied(), id)),
+            })
+            .collect();
+        due.sort_by_key(|(last, _)| *last);
+        due.truncate(MAX_PROBES_PER_ROUND);
+        due.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Record that a ping with `nonce` was sent to `node_id`.
+    pub fn start_probe(&mut self, node_id: [u8; 32], nonce: [u8; 8], now_ms: u64) {
+        self.pending.insert(
+            nonce,
+            PendingProbe {
+                node_id,
+                sent_ms: now_ms,
+            },
+        );
+        self.last_probe.insert(node_id, now_ms);
+    }
+
+    /// Match a pong nonce to its probe and record the RTT in `cache`.
+    ///
+    /// Returns the measured RTT in milliseconds, or `None` if the nonce does
+    /// not belong to an outstanding probe.
+    pub fn on_pong(&mut self, nonce: &[u8; 8], now_ms: u64, cache: &mut RelayCache) -> Option<u64> {
+        let probe = self.pending.remove(nonce)?;
+        let rtt = now_ms.saturating_sub(probe.sent_ms);
+        cache.record_rtt(&probe.node_id, rtt as f64, now_ms);
+        Some(rtt)
+    }
+
+    /// Abandon probes older than [`PROBE_TIMEOUT_MS`], returning their relays.
+    pub fn expire(&mut self, now_ms: u64) -> Vec<[u8; 32]> {
+        let mut expired = Vec::new();
+        self.pending.retain(|_, p| {
+            let live = now_ms.saturating_sub(p.sent_ms) < PROBE_TIMEOUT_MS;
+            if !live {
+                expired.push(p.node_id);
+            }
+            live
+        });
+        expired
+    }
+
+    /// Number of probes awaiting a pong.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_types::network::RelayDescriptor;
+
+    fn relay(id: u8) -> RelayDescriptor {
+        RelayDescriptor {
+            node_id: [id; 32],
+            pik_hash: [id; 32],
+            x25519_pk: [id; 32],
+            mlkem768_ek: vec![],
+            relay_epoch: 1,
+            posrv_score: 1.0,
+            ip_addr: format!("10.0.{id}.1:4433"),
+            as_number: u32::from(id),
+            country_code: *b"US",
+            bandwidth_cap_mbps: 100,
+            uptime_epochs: 10,
+            exit_policy: Default::default(),
+            delegation: None,
+            sig: [0u8; 64],
+        }
+    }
+
+    #[test]
+    fn test_ewma_converges_toward_samples() {
+        let mut est = LatencyEstimate::new(200.0, 0);
+        est.observe(100.0, 1);
+        assert!((est.ewma_ms - 175.0).abs() < 1e-9);
+        for t in 2..40 {
+            est.observe(100.0, t);
+        }
+        assert!(est.ewma_ms < 101.0);
+        assert_eq!(est.samples, 40);
+        assert!(est.is_fresh(39 + LATENCY_STALE_MS));
+        assert!(!est.is_fresh(40 + LATENCY_STALE_MS));
+    }
+
+    #[test]
+    fn test_due_probes_only_connected_and_not_recent() {
+        let cache = RelayCache::from_descriptors((1..=4).map(relay).collect());
+        let connected: HashSet<[u8; 32]> = [[1u8; 32], [2u8; 32], [3u8; 32]].into();
+        let mut prober = RttProber::new();
+        prober.start_probe([2u8; 32], [0; 8], 1_000);
+        prober.expire(1_000 + PROBE_TIMEOUT_MS);
+
+        let due = prober.due_probes(&cache, &connected, 2_000);
+        assert_eq!(due, vec![[1u8; 32], [3u8; 32]]);
+
+        let due = prober.due_probes(&cache, &connected, 1_000 + PROBE_INTERVAL_MS);
+        assert_eq!(due.len(), 3);
+        assert_eq!(due[2], [2u8; 32]);
+    }
+
+    #[test]
+    fn test_pong_records_rtt_in_cache() {
+        let mut cache = RelayCache::from_descriptors(vec![relay(1)]);
+        let mut prober = RttProber::new();
+        prober.start_probe([1u8; 32], [7; 8], 5_000);
+        assert_eq!(prober.pending_count(), 1);
+
+        assert_eq!(prober.on_pong(&[9; 8], 5_040, &mut cache), None);
+        assert_eq!(prober.on_pong(&[7; 8], 5_040, &mut cache), Some(40));
+        assert_eq!(prober.pending_count(), 0);
+        let est = cache.latency(&[1u8; 32]).expect("estimate");
+        assert!((est.ewma_ms - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_expire_drops_timed_out_probes() {
+        let mut prober = RttProber::new();
+        prober.start_probe([1u8; 32], [1; 8], 0);
+        prober.start_probe([2u8; 32], [2; 8], 5_000);
+        let expired = prober.expire(PROBE_TIMEOUT_MS);
+        assert_eq!(expired, vec![[1u8; 32]]);
+        assert_eq!(prober.pending_count(), 1);
+    }
+}
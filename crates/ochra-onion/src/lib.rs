@@ -5,8 +5,13 @@
 //! This crate implements Sphinx-based onion routing with 3-hop circuits:
 //!
 //! - [`circuit`] - Circuit construction, hop key derivation, and rotation
+//! - [`directory`] - Quorum-signed relay directory snapshots and epoch diffs
+//! - [`geo`] - AS and country lookup of relay addresses from MaxMind DB files
 //! - [`relay`] - Relay selection with PoSrv-weighted random sampling
+//! - [`latency`] - Opportunistic RTT probing of cached relays
 //! - [`cover`] - Cover traffic generation using Poisson timing
+//! - [`health`] - End-to-end circuit echo probes and failover
+//! - [`isolation`] - Stream isolation policies keyed by application context
 //! - [`nat`] - NAT traversal helpers
 //!
 //! ## Key Parameters
@@ -20,6 +25,11 @@
 
 pub mod circuit;
 pub mod cover;
+pub mod directory;
+pub mod geo;
+pub mod health;
+pub mod isolation;
+pub mod latency;
 pub mod nat;
 pub mod relay;
 
@@ -59,6 +69,18 @@ pub enum OnionError {
     #[error("sphinx error: {0}")]
     Sphinx(String),
 
+    /// A stream was routed onto a circuit bound to another context.
+    #[error("stream isolation violation: {0}")]
+    IsolationViolation(String),
+
+    /// Relay directory snapshot or diff was invalid.
+    #[error("relay directory error: {0}")]
+    Directory(String),
+
+    /// A GeoIP database could not be loaded.
+    #[error("GeoIP database error: {0}")]
+    Geo(String),
+
     /// NAT traversal failed.
     #[error("NAT traversal failed: {0}")]
     NatTraversal(String),
@@ -5,9 +5,18 @@
 //!
 //! ## Selection Constraints
 //!
-//! - No two relays in the same `/24` subnet
+//! - No two relays in the same `/24` (IPv4) or `/48` (IPv6) subnet
 //! - No relay sharing an AS number with the source or destination
 //! - Geographic diversity (prefer relays in different country codes)
+//! - AS numbers and countries come from a [`GeoResolver`] when one is set
+//!   (see [`RelayCache::set_geo_resolver`]); relays with an unknown AS or
+//!   country are not constrained on it
+//! - No relay whose PIK has been revoked (see [`RelayCache::revoke_pik`])
+//! - Optional entry-hop latency target (prefer relays whose measured RTT is
+//!   under [`SelectionConstraints::max_entry_rtt_ms`]; see [`crate::latency`])
+//! - An exit whose published exit policy permits the circuit's intended
+//!   traffic (see [`SelectionConstraints::exit_classes`]). The entry and
+//!   middle hops never take the last compatible exit.
 //!
 //! ## PoSrv Weighting
 //!
@@ -15,12 +24,16 @@
 //! which reflects their Proof of Service and Routing contribution to the
 //! network.
 
-use std::collections::HashSet;
-use std::net::Ipv4Addr;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
-use ochra_types::network::RelayDescriptor;
+use ochra_types::network::{ExitClass, RelayDescriptor};
 use tracing::debug;
 
+use crate::directory::{DirectoryDiff, DirectorySnapshot};
+use crate::geo::{GeoResolver, GEO_REFRESH_INTERVAL_SECS, UNKNOWN_AS, UNKNOWN_COUNTRY};
+use crate::latency::{LatencyEstimate, INTERACTIVE_ENTRY_RTT_MS};
 use crate::{OnionError, Result, CIRCUIT_HOPS};
 
 /// Selects relays for circuit construction with constraint enforcement.
@@ -36,27 +49,167 @@ pub struct SelectionConstraints {
     pub excluded_as_numbers: HashSet<u32>,
     /// When true, try to pick relays from different countries.
     pub preferred_diversity: bool,
+    /// Preferred upper bound on the entry hop's measured RTT (milliseconds).
+    ///
+    /// Soft: relays with an estimate at or under the target are preferred
+    /// for the entry hop, and the other relays are used only when none
+    /// qualify. Relays never measured do not qualify.
+    pub max_entry_rtt_ms: Option<u32>,
+    /// Traffic classes the circuit will carry to its exit. The exit hop is
+    /// limited to relays whose exit policy permits all of them; empty
+    /// accepts any relay that serves as an exit.
+    pub exit_classes: Vec<ExitClass>,
+    /// Whether that traffic asks the exit to store data, which rules out
+    /// exits with `no_storage`.
+    pub exit_stores: bool,
+    /// Smallest per-circuit exit bandwidth in kbit/s. 0 = any.
+    pub min_exit_kbps: u32,
+}
+
+impl SelectionConstraints {
+    /// Constraints for interactive (Whisper) circuits: country diversity and
+    /// an entry hop under [`INTERACTIVE_ENTRY_RTT_MS`].
+    pub fn interactive() -> Self {
+        Self {
+            preferred_diversity: true,
+            max_entry_rtt_ms: Some(INTERACTIVE_ENTRY_RTT_MS),
+            ..Self::default()
+        }
+    }
+
+    /// These constraints with the exit hop limited to relays that deliver
+    /// `classes` (and store data when `stores` is set).
+    pub fn with_exit_traffic(mut self, classes: &[ExitClass], stores: bool) -> Self {
+        self.exit_classes = classes.to_vec();
+        self.exit_stores = stores;
+        self
+    }
+
+    /// Whether `relay`'s exit policy fits the circuit's intended traffic.
+    pub fn permits_exit(&self, relay: &RelayDescriptor) -> bool {
+        let policy = &relay.exit_policy;
+        policy.is_exit()
+            && self
+                .exit_classes
+                .iter()
+                .all(|class| policy.allowed_classes.contains(class))
+            && !(self.exit_stores && policy.no_storage)
+            && policy.circuit_limit_kbps(relay.bandwidth_cap_mbps) >= self.min_exit_kbps
+    }
 }
 
 /// Cached relay descriptors for selection.
 pub struct RelayCache {
     /// All known relay descriptors.
     relays: Vec<RelayDescriptor>,
+    /// Relay epoch of the directory state the cache was last synced to.
+    directory_epoch: Option<u32>,
+    /// Smoothed RTT estimates keyed by relay node ID.
+    latency: HashMap<[u8; 32], LatencyEstimate>,
+    /// PIK hashes with a verified revocation certificate.
+    revoked_piks: HashSet<[u8; 32]>,
+    /// Source of relay AS numbers and countries.
+    geo: Option<Arc<dyn GeoResolver>>,
+    /// Unix seconds of the last full re-resolution.
+    geo_resolved_at: Option<u64>,
 }
 
 impl RelayCache {
     /// Create a new empty relay cache.
     pub fn new() -> Self {
-        Self { relays: Vec::new() }
+        Self {
+            relays: Vec::new(),
+            directory_epoch: None,
+            latency: HashMap::new(),
+            revoked_piks: HashSet::new(),
+            geo: None,
+            geo_resolved_at: None,
+        }
     }
 
     /// Create a relay cache from a list of descriptors.
     pub fn from_descriptors(relays: Vec<RelayDescriptor>) -> Self {
-        Self { relays }
+        Self {
+            relays,
+            directory_epoch: None,
+            latency: HashMap::new(),
+            revoked_piks: HashSet::new(),
+            geo: None,
+            geo_resolved_at: None,
+        }
+    }
+
+    /// Replace the cache contents with a verified directory snapshot.
+    pub fn bootstrap_from_snapshot(
+        &mut self,
+        snapshot: &DirectorySnapshot,
+        quorum_pk: &[u8; 32],
+    ) -> Result<()> {
+        snapshot.verify(quorum_pk)?;
+        self.load_snapshot(snapshot);
+        Ok(())
+    }
+
+    /// Replace the cache contents with a snapshot the caller has already
+    /// authenticated, such as one matching a verified state checkpoint's
+    /// directory digest.
+    pub fn load_snapshot(&mut self, snapshot: &DirectorySnapshot) {
+        self.relays = snapshot.descriptors.clone();
+        self.directory_epoch = Some(snapshot.relay_epoch);
+        self.retain_known_latency();
+        self.resolve_geo_all();
+        debug!(
+            "Bootstrapped relay cache from epoch {} snapshot ({} relays)",
+            snapshot.relay_epoch,
+            self.relays.len()
+        );
+    }
+
+    /// Apply a verified directory diff on top of the current directory state.
+    ///
+    /// The diff must start at the cache's current directory epoch. Descriptors
+    /// added locally via [`RelayCache::add`] since the last sync make the base
+    /// digest mismatch; in that case the cache is left untouched and the
+    /// caller should re-bootstrap from a snapshot.
+    pub fn apply_diff(&mut self, diff: &DirectoryDiff, quorum_pk: &[u8; 32]) -> Result<()> {
+        diff.verify(quorum_pk)?;
+        match self.directory_epoch {
+            Some(epoch) if epoch == diff.from_epoch => {}
+            Some(epoch) => {
+                return Err(OnionError::Directory(format!(
+                    "diff starts at epoch {}, cache is at epoch {}",
+                    diff.from_epoch, epoch
+                )))
+            }
+            None => {
+                return Err(OnionError::Directory(
+                    "cache has no directory snapshot".to_string(),
+                ))
+            }
+        }
+        self.relays = diff.apply(&self.relays)?;
+        self.directory_epoch = Some(diff.to_epoch);
+        self.retain_known_latency();
+        self.resolve_geo_all();
+        Ok(())
+    }
+
+    /// Relay epoch of the directory state the cache was last synced to.
+    pub fn directory_epoch(&self) -> Option<u32> {
+        self.directory_epoch
     }
 
     /// Add a relay descriptor to the cache.
-    pub fn add(&mut self, relay: RelayDescriptor) {
+    ///
+    /// Descriptors from revoked PIKs are dropped.
+    pub fn add(&mut self, mut relay: RelayDescriptor) {
+        if self.revoked_piks.contains(&relay.pik_hash) {
+            debug!("Ignoring descriptor from revoked relay PIK");
+            return;
+        }
+        if let Some(geo) = &self.geo {
+            apply_geo(geo.as_ref(), &mut relay);
+        }
         // Replace if same node_id already exists.
         if let Some(existing) = self.relays.iter_mut().find(|r| r.node_id == relay.node_id) {
             *existing = relay;
@@ -68,6 +221,100 @@ impl RelayCache {
     /// Remove a relay by node ID.
     pub fn remove(&mut self, node_id: &[u8; 32]) {
         self.relays.retain(|r| &r.node_id != node_id);
+        self.latency.remove(node_id);
+    }
+
+    /// Resolve relay AS numbers and countries through `resolver` from now
+    /// on, starting with every cached relay.
+    ///
+    /// Without a database ([`GeoResolver::is_available`] false) relays keep
+    /// their self-reported values.
+    pub fn set_geo_resolver(&mut self, resolver: Arc<dyn GeoResolver>, now_secs: u64) {
+        self.geo = Some(resolver);
+        self.refresh_geo(now_secs);
+    }
+
+    /// Whether a GeoIP database backs the cached AS numbers and countries.
+    pub fn geo_available(&self) -> bool {
+        self.geo.as_ref().is_some_and(|g| g.is_available())
+    }
+
+    /// Whether [`GEO_REFRESH_INTERVAL_SECS`] has passed since the last
+    /// re-resolution.
+    pub fn geo_refresh_due(&self, now_secs: u64) -> bool {
+        self.geo.is_some()
+            && self
+                .geo_resolved_at
+                .is_none_or(|at| now_secs.saturating_sub(at) >= GEO_REFRESH_INTERVAL_SECS)
+    }
+
+    /// Re-resolve every cached relay. Returns how many changed.
+    pub fn refresh_geo(&mut self, now_secs: u64) -> usize {
+        self.geo_resolved_at = Some(now_secs);
+        let changed = self.resolve_geo_all();
+        if changed > 0 {
+            debug!("Re-resolved AS/country of {} relays", changed);
+        }
+        changed
+    }
+
+    fn resolve_geo_all(&mut self) -> usize {
+        let Some(geo) = &self.geo else {
+            return 0;
+        };
+        self.relays
+            .iter_mut()
+            .map(|r| apply_geo(geo.as_ref(), r))
+            .filter(|&changed| changed)
+            .count()
+    }
+
+    /// Treat relays operated by a revoked PIK as invalid.
+    ///
+    /// Their descriptors stay in the directory state, so later diffs still
+    /// apply, but they are never selected and never re-added.
+    pub fn revoke_pik(&mut self, pik_hash: [u8; 32]) {
+        self.revoked_piks.insert(pik_hash);
+    }
+
+    /// Whether a relay PIK has been revoked.
+    pub fn is_revoked(&self, pik_hash: &[u8; 32]) -> bool {
+        self.revoked_piks.contains(pik_hash)
+    }
+
+    /// Fold an RTT sample for a cached relay into its moving average.
+    ///
+    /// Samples for relays not in the cache are ignored.
+    pub fn record_rtt(&mut self, node_id: &[u8; 32], rtt_ms: f64, now_ms: u64) {
+        if !self.relays.iter().any(|r| &r.node_id == node_id) {
+            return;
+        }
+        self.latency
+            .entry(*node_id)
+            .and_modify(|e| e.observe(rtt_ms, now_ms))
+            .or_insert_with(|| LatencyEstimate::new(rtt_ms, now_ms));
+    }
+
+    /// The RTT estimate for a relay, if it has been measured.
+    pub fn latency(&self, node_id: &[u8; 32]) -> Option<&LatencyEstimate> {
+        self.latency.get(node_id)
+    }
+
+    /// Smoothed RTT for a relay in milliseconds, if it has been measured.
+    pub fn rtt_ms(&self, node_id: &[u8; 32]) -> Option<f64> {
+        self.latency.get(node_id).map(|e| e.ewma_ms)
+    }
+
+    /// Drop RTT estimates not refreshed within
+    /// [`LATENCY_STALE_MS`](crate::latency::LATENCY_STALE_MS).
+    pub fn prune_stale_latency(&mut self, now_ms: u64) {
+        self.latency.retain(|_, e| e.is_fresh(now_ms));
+    }
+
+    /// Drop RTT estimates for relays no longer in the cache.
+    fn retain_known_latency(&mut self) {
+        let known: HashSet<[u8; 32]> = self.relays.iter().map(|r| r.node_id).collect();
+        self.latency.retain(|id, _| known.contains(id));
     }
 
     /// Return all cached relay descriptors.
@@ -127,9 +374,10 @@ impl RelaySelector {
             });
         }
 
-        // Filter out relays in excluded AS numbers.
+        // Filter out revoked relays and relays in excluded AS numbers.
         let mut candidates: Vec<&RelayDescriptor> = available
             .iter()
+            .filter(|r| !cache.is_revoked(&r.pik_hash))
             .filter(|r| !self.constraints.excluded_as_numbers.contains(&r.as_number))
             .collect();
 
@@ -140,18 +388,40 @@ impl RelaySelector {
             });
         }
 
-        let mut selected: Vec<RelayDescriptor> = Vec::with_capacity(CIRCUIT_HOPS);
-        let mut used_subnets: HashSet<[u8; 3]> = HashSet::new();
+        let mut used_subnets: HashSet<Subnet> = HashSet::new();
         let mut used_as: HashSet<u32> = HashSet::new();
         let mut used_countries: HashSet<[u8; 2]> = HashSet::new();
 
+        let exit_idx = CIRCUIT_HOPS - 1;
+        let mut selected: Vec<RelayDescriptor> = Vec::with_capacity(CIRCUIT_HOPS);
         for hop_idx in 0..CIRCUIT_HOPS {
+            let exits: Vec<&RelayDescriptor> = candidates
+                .iter()
+                .copied()
+                .filter(|r| self.constraints.permits_exit(r))
+                .collect();
+            let hop_candidates: Vec<&RelayDescriptor> = match exits.as_slice() {
+                [] => {
+                    return Err(OnionError::ConstraintViolation(
+                        "no relay's exit policy permits the circuit's traffic".to_string(),
+                    ))
+                }
+                _ if hop_idx == exit_idx => exits,
+                // Keep the last compatible exit out of the earlier hops.
+                [last] => candidates
+                    .iter()
+                    .copied()
+                    .filter(|r| r.node_id != last.node_id)
+                    .collect(),
+                _ => candidates.clone(),
+            };
+
             // Filter candidates for this hop.
-            let eligible: Vec<&&RelayDescriptor> = candidates
+            let eligible: Vec<&&RelayDescriptor> = hop_candidates
                 .iter()
                 .filter(|r| {
-                    // Subnet constraint: no two relays in same /24.
-                    let subnet = extract_subnet_24(&r.ip_addr);
+                    // Subnet constraint: no two relays in same /24 or /48.
+                    let subnet = extract_subnet(&r.ip_addr);
                     if let Some(s) = subnet {
                         if used_subnets.contains(&s) {
                             return false;
@@ -175,12 +445,12 @@ impl RelaySelector {
                 })
                 .collect();
 
-            if eligible.is_empty() {
+            let pool = if eligible.is_empty() {
                 // Fall back: drop geographic diversity constraint.
-                let fallback: Vec<&&RelayDescriptor> = candidates
+                let fallback: Vec<&&RelayDescriptor> = hop_candidates
                     .iter()
                     .filter(|r| {
-                        let subnet = extract_subnet_24(&r.ip_addr);
+                        let subnet = extract_subnet(&r.ip_addr);
                         if let Some(s) = subnet {
                             if used_subnets.contains(&s) {
                                 return false;
@@ -199,19 +469,39 @@ impl RelaySelector {
                         hop_idx
                     )));
                 }
-
-                let chosen = weighted_select(&fallback)?;
-                record_selection(chosen, &mut used_subnets, &mut used_as, &mut used_countries);
-                selected.push(chosen.clone());
-                let chosen_id = chosen.node_id;
-                candidates.retain(|r| r.node_id != chosen_id);
+                fallback
             } else {
-                let chosen = weighted_select(&eligible)?;
-                record_selection(chosen, &mut used_subnets, &mut used_as, &mut used_countries);
-                selected.push(chosen.clone());
-                let chosen_id = chosen.node_id;
-                candidates.retain(|r| r.node_id != chosen_id);
-            }
+                eligible
+            };
+
+            // Latency target (soft): narrow the entry hop to measured relays
+            // under the target when any exist.
+            let pool = match self.constraints.max_entry_rtt_ms {
+                Some(target) if hop_idx == 0 => {
+                    let fast: Vec<&&RelayDescriptor> = pool
+                        .iter()
+                        .copied()
+                        .filter(|r| {
+                            cache
+                                .rtt_ms(&r.node_id)
+                                .is_some_and(|rtt| rtt <= f64::from(target))
+                        })
+                        .collect();
+                    if fast.is_empty() {
+                        debug!("No entry relay under {} ms RTT, ignoring target", target);
+                        pool
+                    } else {
+                        fast
+                    }
+                }
+                _ => pool,
+            };
+
+            let chosen = weighted_select(&pool)?;
+            record_selection(chosen, &mut used_subnets, &mut used_as, &mut used_countries);
+            selected.push(chosen.clone());
+            let chosen_id = chosen.node_id;
+            candidates.retain(|r| r.node_id != chosen_id);
         }
 
         debug!("Selected {} relays for circuit", selected.len());
@@ -229,25 +519,75 @@ impl Default for RelaySelector {
 /// Record a selected relay's properties for constraint tracking.
 fn record_selection(
     relay: &RelayDescriptor,
-    used_subnets: &mut HashSet<[u8; 3]>,
+    used_subnets: &mut HashSet<Subnet>,
     used_as: &mut HashSet<u32>,
     used_countries: &mut HashSet<[u8; 2]>,
 ) {
-    if let Some(subnet) = extract_subnet_24(&relay.ip_addr) {
+    if let Some(subnet) = extract_subnet(&relay.ip_addr) {
         used_subnets.insert(subnet);
     }
-    used_as.insert(relay.as_number);
-    used_countries.insert(relay.country_code);
+    if relay.as_number != UNKNOWN_AS {
+        used_as.insert(relay.as_number);
+    }
+    if relay.country_code != UNKNOWN_COUNTRY {
+        used_countries.insert(relay.country_code);
+    }
 }
 
-/// Extract the /24 subnet prefix from an IP address string.
+/// Overwrite a relay's AS and country with what `geo` resolves for its
+/// address, unknown where the database has no entry. Returns whether
+/// either changed.
+fn apply_geo(geo: &dyn GeoResolver, relay: &mut RelayDescriptor) -> bool {
+    if !geo.is_available() {
+        return false;
+    }
+    let info = parse_ip(&relay.ip_addr)
+        .map(|ip| geo.resolve(ip))
+        .unwrap_or_default();
+    let as_number = info.as_number.unwrap_or(UNKNOWN_AS);
+    let country_code = info.country_code.unwrap_or(UNKNOWN_COUNTRY);
+    let changed = relay.as_number != as_number || relay.country_code != country_code;
+    relay.as_number = as_number;
+    relay.country_code = country_code;
+    changed
+}
+
+/// The /24 (IPv4) or /48 (IPv6) prefix of a relay address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Subnet {
+    V4([u8; 3]),
+    V6([u8; 6]),
+}
+
+/// Extract the subnet prefix from an address string.
 ///
-/// Parses addresses like "1.2.3.4:4433" and returns the first 3 octets.
-fn extract_subnet_24(addr_str: &str) -> Option<[u8; 3]> {
-    let ip_part = addr_str.split(':').next()?;
-    let ip: Ipv4Addr = ip_part.parse().ok()?;
-    let octets = ip.octets();
-    Some([octets[0], octets[1], octets[2]])
+/// Accepts socket addresses ("1.2.3.4:4433", "[2001:db8::1]:4433") or bare
+/// IPs. IPv4-mapped IPv6 addresses are treated as IPv4.
+fn extract_subnet(addr_str: &str) -> Option<Subnet> {
+    Some(match parse_ip(addr_str)? {
+        IpAddr::V4(v4) => {
+            let o = v4.octets();
+            Subnet::V4([o[0], o[1], o[2]])
+        }
+        IpAddr::V6(v6) => {
+            let o = v6.octets();
+            Subnet::V6([o[0], o[1], o[2], o[3], o[4], o[5]])
+        }
+    })
+}
+
+/// Parse the IP of a socket address or bare IP, unmapping IPv4-mapped
+/// IPv6 addresses.
+fn parse_ip(addr_str: &str) -> Option<IpAddr> {
+    let ip = addr_str
+        .parse::<SocketAddr>()
+        .map(|a| a.ip())
+        .or_else(|_| addr_str.parse::<IpAddr>())
+        .ok()?;
+    Some(match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        v4 => v4,
+    })
 }
 
 /// Select a relay using PoSrv-weighted random sampling.
@@ -301,15 +641,56 @@ mod tests {
             country_code: country,
             bandwidth_cap_mbps: 100,
             uptime_epochs: 100,
+            exit_policy: Default::default(),
+            delegation: None,
             sig: [0u8; 64],
         }
     }
 
     #[test]
-    fn test_extract_subnet_24() {
-        assert_eq!(extract_subnet_24("192.168.1.100:4433"), Some([192, 168, 1]));
-        assert_eq!(extract_subnet_24("10.0.0.1:4433"), Some([10, 0, 0]));
-        assert_eq!(extract_subnet_24("invalid"), None);
+    fn test_extract_subnet() {
+        assert_eq!(
+            extract_subnet("192.168.1.100:4433"),
+            Some(Subnet::V4([192, 168, 1]))
+        );
+        assert_eq!(
+            extract_subnet("10.0.0.1:4433"),
+            Some(Subnet::V4([10, 0, 0]))
+        );
+        assert_eq!(extract_subnet("invalid"), None);
+    }
+
+    #[test]
+    fn test_extract_subnet_ipv6() {
+        let expected = Some(Subnet::V6([0x20, 0x01, 0x0d, 0xb8, 0x00, 0x01]));
+        assert_eq!(extract_subnet("[2001:db8:1::5]:4433"), expected);
+        assert_eq!(extract_subnet("2001:db8:1:ffff::9"), expected);
+        // IPv4-mapped addresses share the IPv4 /24.
+        assert_eq!(
+            extract_subnet("[::ffff:10.0.0.1]:4433"),
+            Some(Subnet::V4([10, 0, 0]))
+        );
+    }
+
+    #[test]
+    fn test_select_relays_ipv6_subnet_constraint() {
+        // Two relays share a /48; only one may be picked.
+        let cache = RelayCache::from_descriptors(vec![
+            make_relay(1, "[2001:db8:1::1]:4433", 100, [b'U', b'S'], 1.0),
+            make_relay(2, "[2001:db8:1:2::1]:4433", 200, [b'D', b'E'], 1.0),
+            make_relay(3, "[2001:db8:2::1]:4433", 300, [b'J', b'P'], 1.0),
+            make_relay(4, "10.0.3.1:4433", 400, [b'G', b'B'], 1.0),
+        ]);
+
+        let selector = RelaySelector::new();
+        for _ in 0..20 {
+            let selected = selector.select_relays(&cache).expect("select relays");
+            let shared = selected
+                .iter()
+                .filter(|r| r.node_id == [1; 32] || r.node_id == [2; 32])
+                .count();
+            assert!(shared <= 1, "Two relays from one /48 selected");
+        }
     }
 
     #[test]
@@ -341,6 +722,31 @@ mod tests {
         assert!((cache.all()[0].posrv_score - 5.0).abs() < f32::EPSILON);
     }
 
+    #[test]
+    fn test_revoked_relays_are_not_added_or_selected() {
+        let mut cache = RelayCache::from_descriptors(vec![
+            make_relay(1, "10.0.1.1:4433", 100, [b'U', b'S'], 1.0),
+            make_relay(2, "10.0.2.1:4433", 200, [b'D', b'E'], 1.0),
+            make_relay(3, "10.0.3.1:4433", 300, [b'F', b'R'], 1.0),
+        ]);
+        let selector = RelaySelector::new();
+        assert!(selector.select_relays(&cache).is_ok());
+
+        cache.revoke_pik([2u8; 32]);
+        assert!(matches!(
+            selector.select_relays(&cache),
+            Err(OnionError::InsufficientRelays { need: 3, have: 2 })
+        ));
+
+        cache.add(make_relay(4, "10.0.4.1:4433", 400, [b'J', b'P'], 1.0));
+        let mut revoked = make_relay(5, "10.0.5.1:4433", 500, [b'B', b'R'], 1.0);
+        revoked.pik_hash = [2u8; 32];
+        cache.add(revoked);
+        assert_eq!(cache.len(), 4);
+        let selected = selector.select_relays(&cache).expect("select");
+        assert!(selected.iter().all(|r| r.pik_hash != [2u8; 32]));
+    }
+
     #[test]
     fn test_select_relays_success() {
         let cache = RelayCache::from_descriptors(vec![
@@ -383,9 +789,9 @@ mod tests {
 
         let mut subnets = HashSet::new();
         for relay in &selected {
-            let subnet = extract_subnet_24(&relay.ip_addr);
+            let subnet = extract_subnet(&relay.ip_addr);
             if let Some(s) = subnet {
-                assert!(subnets.insert(s), "Duplicate /24 subnet in selected relays");
+                assert!(subnets.insert(s), "Duplicate subnet in selected relays");
             }
         }
     }
@@ -421,4 +827,247 @@ mod tests {
         let filtered = cache.filter_by_min_score(1.0);
         assert_eq!(filtered.len(), 2);
     }
+
+    #[test]
+    fn test_bootstrap_from_snapshot_and_apply_diff() {
+        let kp = ochra_crypto::ed25519::KeyPair::generate();
+        let quorum_pk = kp.verifying_key.to_bytes();
+
+        let base = vec![
+            make_relay(1, "10.0.1.1:4433", 100, [b'U', b'S'], 1.0),
+            make_relay(2, "10.0.2.1:4433", 200, [b'D', b'E'], 2.0),
+        ];
+        let mut snapshot = DirectorySnapshot::build(24, base.clone()).expect("build snapshot");
+        snapshot.quorum_sig = kp
+            .signing_key
+            .sign(&snapshot.signing_message())
+            .to_bytes()
+            .to_vec();
+
+        let mut cache = RelayCache::new();
+        cache
+            .bootstrap_from_snapshot(&snapshot, &quorum_pk)
+            .expect("bootstrap");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.directory_epoch(), Some(24));
+
+        let target = vec![
+            make_relay(2, "10.0.2.1:4433", 200, [b'D', b'E'], 2.0),
+            make_relay(3, "10.0.3.1:4433", 300, [b'J', b'P'], 3.0),
+        ];
+        let mut diff = DirectoryDiff::compute(24, &base, 25, &target).expect("compute diff");
+        diff.quorum_sig = kp
+            .signing_key
+            .sign(&diff.signing_message())
+            .to_bytes()
+            .to_vec();
+
+        cache.apply_diff(&diff, &quorum_pk).expect("apply diff");
+        assert_eq!(cache.directory_epoch(), Some(25));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.all().iter().any(|r| r.node_id == [3u8; 32]));
+
+        // Re-applying the same diff fails: the cache is no longer at epoch 24.
+        assert!(cache.apply_diff(&diff, &quorum_pk).is_err());
+    }
+
+    #[test]
+    fn test_record_rtt_ignores_unknown_and_clears_on_remove() {
+        let mut cache = RelayCache::new();
+        cache.add(make_relay(1, "10.0.1.1:4433", 100, *b"US", 1.0));
+        cache.record_rtt(&[2u8; 32], 50.0, 0);
+        assert!(cache.rtt_ms(&[2u8; 32]).is_none());
+
+        cache.record_rtt(&[1u8; 32], 80.0, 0);
+        cache.record_rtt(&[1u8; 32], 40.0, 1);
+        assert_eq!(cache.latency(&[1u8; 32]).map(|e| e.samples), Some(2));
+        cache.prune_stale_latency(1 + crate::latency::LATENCY_STALE_MS);
+        assert!(cache.rtt_ms(&[1u8; 32]).is_some());
+
+        cache.remove(&[1u8; 32]);
+        assert!(cache.rtt_ms(&[1u8; 32]).is_none());
+    }
+
+    #[test]
+    fn test_entry_latency_target_prefers_fast_relay() {
+        let relays: Vec<RelayDescriptor> = (1..=6)
+            .map(|i| {
+                make_relay(
+                    i,
+                    &format!("10.0.{i}.1:4433"),
+                    100 + u32::from(i),
+                    *b"US",
+                    1.0,
+                )
+            })
+            .collect();
+        let mut cache = RelayCache::from_descriptors(relays);
+        cache.record_rtt(&[4u8; 32], 40.0, 0);
+        cache.record_rtt(&[5u8; 32], 400.0, 0);
+
+        let selector = RelaySelector::with_constraints(SelectionConstraints::interactive());
+        for _ in 0..20 {
+            let selected = selector.select_relays(&cache).expect("select");
+            assert_eq!(selected[0].node_id, [4u8; 32]);
+        }
+    }
+
+    #[test]
+    fn test_entry_latency_target_falls_back_when_unmeasured() {
+        let relays: Vec<RelayDescriptor> = (1..=4)
+            .map(|i| {
+                make_relay(
+                    i,
+                    &format!("10.0.{i}.1:4433"),
+                    100 + u32::from(i),
+                    *b"US",
+                    1.0,
+                )
+            })
+            .collect();
+        let mut cache = RelayCache::from_descriptors(relays);
+        cache.record_rtt(&[1u8; 32], 900.0, 0);
+
+        let constraints = SelectionConstraints {
+            max_entry_rtt_ms: Some(150),
+            ..SelectionConstraints::default()
+        };
+        let selected = RelaySelector::with_constraints(constraints)
+            .select_relays(&cache)
+            .expect("select");
+        assert_eq!(selected.len(), CIRCUIT_HOPS);
+    }
+
+    fn relays_with_one_dht_exit() -> Vec<RelayDescriptor> {
+        (1..=5)
+            .map(|i| {
+                let mut relay = make_relay(
+                    i,
+                    &format!("10.0.{i}.1:4433"),
+                    100 + u32::from(i),
+                    *b"US",
+                    1.0,
+                );
+                relay.exit_policy.allowed_classes = if i == 5 {
+                    vec![ExitClass::Dht]
+                } else {
+                    Vec::new()
+                };
+                relay
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_exit_hop_respects_exit_policy() {
+        let cache = RelayCache::from_descriptors(relays_with_one_dht_exit());
+        let selector = RelaySelector::with_constraints(
+            SelectionConstraints::default().with_exit_traffic(&[ExitClass::Dht], false),
+        );
+        for _ in 0..20 {
+            let selected = selector.select_relays(&cache).expect("select");
+            assert_eq!(selected.len(), CIRCUIT_HOPS);
+            assert_eq!(selected[CIRCUIT_HOPS - 1].node_id, [5u8; 32]);
+        }
+    }
+
+    #[test]
+    fn test_no_compatible_exit_fails() {
+        let mut relays = relays_with_one_dht_exit();
+        let selector = RelaySelector::with_constraints(
+            SelectionConstraints::default().with_exit_traffic(&[ExitClass::Content], false),
+        );
+        let cache = RelayCache::from_descriptors(relays.clone());
+        assert!(matches!(
+            selector.select_relays(&cache),
+            Err(OnionError::ConstraintViolation(_))
+        ));
+
+        // A storing circuit cannot use a no-storage exit.
+        relays[4].exit_policy.no_storage = true;
+        let cache = RelayCache::from_descriptors(relays);
+        let selector = RelaySelector::with_constraints(
+            SelectionConstraints::default().with_exit_traffic(&[ExitClass::Dht], true),
+        );
+        assert!(selector.select_relays(&cache).is_err());
+    }
+
+    #[test]
+    fn test_min_exit_bandwidth() {
+        let mut relays = relays_with_one_dht_exit();
+        relays[4].exit_policy.max_circuit_kbps = 500;
+        let cache = RelayCache::from_descriptors(relays);
+        let constraints = SelectionConstraints {
+            min_exit_kbps: 1000,
+            ..SelectionConstraints::default()
+        };
+        assert!(RelaySelector::with_constraints(constraints)
+            .select_relays(&cache)
+            .is_err());
+    }
+
+    /// Resolves 10.0.<n>.x to AS 65000+n in DE; nothing else.
+    struct FakeGeo;
+
+    impl GeoResolver for FakeGeo {
+        fn resolve(&self, ip: IpAddr) -> crate::geo::GeoInfo {
+            match ip {
+                IpAddr::V4(v4) if v4.octets()[0] == 10 => crate::geo::GeoInfo {
+                    as_number: Some(65_000 + u32::from(v4.octets()[2])),
+                    country_code: Some(*b"DE"),
+                },
+                _ => crate::geo::GeoInfo::default(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_geo_resolver_overrides_self_reported_values() {
+        let mut cache = RelayCache::from_descriptors(vec![
+            make_relay(1, "10.0.1.1:4433", 7, *b"US", 1.0),
+            make_relay(2, "192.0.2.1:4433", 7, *b"US", 1.0),
+        ]);
+        assert!(!cache.geo_refresh_due(0));
+        cache.set_geo_resolver(Arc::new(FakeGeo), 1_000);
+        assert!(cache.geo_available());
+
+        assert_eq!(cache.all()[0].as_number, 65_001);
+        assert_eq!(cache.all()[0].country_code, *b"DE");
+        assert_eq!(cache.all()[1].as_number, UNKNOWN_AS);
+        assert_eq!(cache.all()[1].country_code, UNKNOWN_COUNTRY);
+
+        cache.add(make_relay(3, "10.0.3.1:4433", 7, *b"US", 1.0));
+        assert_eq!(cache.all()[2].as_number, 65_003);
+
+        assert!(!cache.geo_refresh_due(1_000 + GEO_REFRESH_INTERVAL_SECS - 1));
+        assert!(cache.geo_refresh_due(1_000 + GEO_REFRESH_INTERVAL_SECS));
+        assert_eq!(cache.refresh_geo(1_000 + GEO_REFRESH_INTERVAL_SECS), 0);
+    }
+
+    #[test]
+    fn test_no_geo_database_keeps_self_reported_values() {
+        let mut cache =
+            RelayCache::from_descriptors(vec![make_relay(1, "10.0.1.1:4433", 7, *b"US", 1.0)]);
+        cache.set_geo_resolver(Arc::new(crate::geo::NoGeoResolver), 0);
+        assert!(!cache.geo_available());
+        assert_eq!(cache.all()[0].as_number, 7);
+        assert_eq!(cache.all()[0].country_code, *b"US");
+    }
+
+    #[test]
+    fn test_unknown_as_and_country_do_not_block_selection() {
+        // No database entry for any relay: all resolve to unknown, and
+        // selection falls back to the subnet rule alone.
+        let relays: Vec<RelayDescriptor> = (1..=3)
+            .map(|i| make_relay(i, &format!("192.0.{i}.1:4433"), 7, *b"US", 1.0))
+            .collect();
+        let mut cache = RelayCache::from_descriptors(relays);
+        cache.set_geo_resolver(Arc::new(FakeGeo), 0);
+        let selector = RelaySelector::with_constraints(SelectionConstraints {
+            preferred_diversity: true,
+            ..SelectionConstraints::default()
+        });
+        let selected = selector.select_relays(&cache).expect("select");
+        assert_eq!(selected.len(), CIRCUIT_HOPS);
+    }
 }
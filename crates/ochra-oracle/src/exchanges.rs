@@ -0,0 +1,269 @@
+//! Exchange ticker sources and the reference price feed (Section 11.7).
+//!
+//! Each of the five target exchanges publishes a BTC/USD (or stablecoin)
+//! ticker on a public REST endpoint. [`Exchange::ticker_url`] names it and
+//! [`Exchange::parse_price`] extracts the price, in micro-dollars, from its
+//! JSON response. Fetching is left to the caller so that connections follow
+//! the node's egress rules.
+//!
+//! [`ReferenceFeed`] takes the median of one poll once at least
+//! [`MIN_ANSWERS`] exchanges answered and folds it into the TWAP as
+//! `0.8 × TWAP_old + 0.2 × median`. Polls with fewer answers leave the TWAP
+//! unchanged, and after [`CIRCUIT_BREAKER_SECS`] without an accepted poll the
+//! feed reports its circuit breaker active.
+
+use serde_json::Value;
+
+use crate::circuit_breaker::CircuitBreaker;
+use crate::{OracleError, Result};
+
+/// Answers needed for a poll to update the TWAP (3 of 5).
+pub const MIN_ANSWERS: usize = 3;
+
+/// Seconds without an accepted poll before the circuit breaker trips (12 hours).
+pub const CIRCUIT_BREAKER_SECS: u64 = 12 * 3600;
+
+/// Micro-dollars per dollar.
+const MICROS: u64 = 1_000_000;
+
+/// A target exchange, in priority order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exchange {
+    /// Kraken `/0/public/Ticker`, 24h VWAP.
+    Kraken,
+    /// Coinbase `/v2/prices/spot`, spot price.
+    Coinbase,
+    /// Bitstamp `/v2/ticker/`, VWAP.
+    Bitstamp,
+    /// Gemini `/v1/pubticker/`, last price.
+    Gemini,
+    /// OKX `/api/v5/market/ticker`, last price.
+    Okx,
+}
+
+impl Exchange {
+    /// Every target exchange, in priority order.
+    pub const ALL: [Exchange; 5] = [
+        Exchange::Kraken,
+        Exchange::Coinbase,
+        Exchange::Bitstamp,
+        Exchange::Gemini,
+        Exchange::Okx,
+    ];
+
+    /// Exchange name, as used in oracle attestations.
+    pub fn name(self) -> &'static str {
+        match self {
+            Exchange::Kraken => "kraken",
+            Exchange::Coinbase => "coinbase",
+            Exchange::Bitstamp => "bitstamp",
+            Exchange::Gemini => "gemini",
+            Exchange::Okx => "okx",
+        }
+    }
+
+    /// HTTPS URL of the exchange's BTC/USD ticker.
+    pub fn ticker_url(self) -> &'static str {
+        match self {
+            Exchange::Kraken => "https://api.kraken.com/0/public/Ticker?pair=XBTUSD",
+            Exchange::Coinbase => "https://api.coinbase.com/v2/prices/BTC-USD/spot",
+            Exchange::Bitstamp => "https://www.bitstamp.net/api/v2/ticker/btcusd/",
+            Exchange::Gemini => "https://api.gemini.com/v1/pubticker/btcusd",
+            Exchange::Okx => "https://www.okx.com/api/v5/market/ticker?instId=BTC-USDT",
+        }
+    }
+
+    /// Extract the price, in micro-dollars, from a ticker response.
+    ///
+    /// # Errors
+    ///
+    /// - [`OracleError::MalformedTicker`] if the body is not the exchange's
+    ///   ticker JSON or the price is not a positive decimal
+    pub fn parse_price(self, body: &[u8]) -> Result<u64> {
+        let malformed =
+            |what: &str| OracleError::MalformedTicker(format!("{}: {what}", self.name()));
+        let json: Value = serde_json::from_slice(body).map_err(|e| malformed(&e.to_string()))?;
+        let price = match self {
+            // result.<pair>.p = [today's VWAP, last 24 hours' VWAP]
+            Exchange::Kraken => json["result"]
+                .as_object()
+                .and_then(|pairs| pairs.values().next())
+                .map(|pair| &pair["p"][1]),
+            Exchange::Coinbase => Some(&json["data"]["amount"]),
+            Exchange::Bitstamp => Some(&json["vwap"]),
+            Exchange::Gemini => Some(&json["last"]),
+            Exchange::Okx => Some(&json["data"][0]["last"]),
+        }
+        .and_then(Value::as_str)
+        .ok_or_else(|| malformed("no price field"))?;
+        parse_micros(price).ok_or_else(|| malformed(&format!("bad price {price:?}")))
+    }
+}
+
+/// Parse a positive decimal string into micro-units, truncating digits past
+/// the sixth decimal place.
+fn parse_micros(s: &str) -> Option<u64> {
+    let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+    if whole.is_empty() && frac.is_empty() {
+        return None;
+    }
+    if !whole
+        .bytes()
+        .chain(frac.bytes())
+        .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let frac_digits = &frac[..frac.len().min(6)];
+    let frac: u64 = format!("{frac_digits:0<6}").parse().ok()?;
+    let micros = whole.checked_mul(MICROS)?.checked_add(frac)?;
+    (micros > 0).then_some(micros)
+}
+
+/// Reference price TWAP with circuit breaker.
+#[derive(Debug, Clone)]
+pub struct ReferenceFeed {
+    /// Current TWAP in micro-dollars, if any poll has been accepted.
+    twap: Option<u64>,
+    /// Tracks the time of the last accepted poll.
+    breaker: CircuitBreaker,
+}
+
+impl ReferenceFeed {
+    /// Create an empty feed at time `now`.
+    pub fn new(now: u64) -> Self {
+        Self {
+            twap: None,
+            breaker: CircuitBreaker::with_threshold(now, CIRCUIT_BREAKER_SECS),
+        }
+    }
+
+    /// Fold one poll's prices into the TWAP and return the new TWAP.
+    ///
+    /// # Errors
+    ///
+    /// - [`OracleError::InsufficientObservations`] if fewer than
+    ///   [`MIN_ANSWERS`] exchanges answered; the TWAP is left unchanged
+    pub fn record_poll(&mut self, prices: &[u64], now: u64) -> Result<u64> {
+        if prices.len() < MIN_ANSWERS {
+            return Err(OracleError::InsufficientObservations {
+                required: MIN_ANSWERS,
+                available: prices.len(),
+            });
+        }
+        let mut sorted = prices.to_vec();
+        sorted.sort_unstable();
+        let mid = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            ((u128::from(sorted[mid - 1]) + u128::from(sorted[mid])) / 2) as u64
+        } else {
+            sorted[mid]
+        };
+        let twap = match self.twap {
+            Some(old) => ((u128::from(old) * 4 + u128::from(median)) / 5) as u64,
+            None => median,
+        };
+        self.twap = Some(twap);
+        self.breaker.record_update(now);
+        Ok(twap)
+    }
+
+    /// Current TWAP in micro-dollars, if any poll has been accepted.
+    pub fn twap(&self) -> Option<u64> {
+        self.twap
+    }
+
+    /// Seconds since the last accepted poll (or since the feed was created).
+    pub fn stale_secs(&self, now: u64) -> u64 {
+        now.saturating_sub(self.breaker.last_update_time())
+    }
+
+    /// Whether the circuit breaker is active at `now`.
+    pub fn is_circuit_breaker_active(&self, now: u64) -> bool {
+        self.breaker.check_staleness(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exchange_tickers() {
+        let kraken = br#"{"error":[],"result":{"XXBTZUSD":{"p":["64000.1","64123.456789"]}}}"#;
+        assert_eq!(
+            Exchange::Kraken.parse_price(kraken).expect("kraken"),
+            64_123_456_789
+        );
+        let coinbase = br#"{"data":{"amount":"64100.5","base":"BTC","currency":"USD"}}"#;
+        assert_eq!(
+            Exchange::Coinbase.parse_price(coinbase).expect("coinbase"),
+            64_100_500_000
+        );
+        let bitstamp = br#"{"last":"64050","vwap":"64020"}"#;
+        assert_eq!(
+            Exchange::Bitstamp.parse_price(bitstamp).expect("bitstamp"),
+            64_020_000_000
+        );
+        let gemini = br#"{"bid":"64000","last":"64010.25"}"#;
+        assert_eq!(
+            Exchange::Gemini.parse_price(gemini).expect("gemini"),
+            64_010_250_000
+        );
+        let okx = br#"{"code":"0","data":[{"instId":"BTC-USDT","last":"63990.1"}]}"#;
+        assert_eq!(Exchange::Okx.parse_price(okx).expect("okx"), 63_990_100_000);
+
+        assert!(Exchange::Gemini.parse_price(br#"{"last":"-5"}"#).is_err());
+        assert!(Exchange::Gemini.parse_price(br#"{"last":"0"}"#).is_err());
+        assert!(Exchange::Gemini.parse_price(br#"{"last":64010}"#).is_err());
+        assert!(Exchange::Okx.parse_price(b"<html>").is_err());
+    }
+
+    #[test]
+    fn test_parse_micros() {
+        assert_eq!(parse_micros("1.5"), Some(1_500_000));
+        assert_eq!(parse_micros(".25"), Some(250_000));
+        assert_eq!(parse_micros("2."), Some(2_000_000));
+        assert_eq!(parse_micros("0.12345678"), Some(123_456));
+        assert_eq!(parse_micros("."), None);
+        assert_eq!(parse_micros("1e5"), None);
+        assert_eq!(parse_micros("99999999999999999999"), None);
+    }
+
+    #[test]
+    fn test_feed_median_and_ewma() {
+        let mut feed = ReferenceFeed::new(1000);
+        assert!(matches!(
+            feed.record_poll(&[100, 200], 1000),
+            Err(OracleError::InsufficientObservations {
+                required: 3,
+                available: 2
+            })
+        ));
+        assert_eq!(feed.twap(), None);
+
+        assert_eq!(feed.record_poll(&[300, 100, 200], 2000).expect("poll"), 200);
+        // Median 400 of four answers; 0.8 × 200 + 0.2 × 400 = 240
+        assert_eq!(
+            feed.record_poll(&[500, 300, 1000, 0], 3000).expect("poll"),
+            240
+        );
+        assert_eq!(feed.stale_secs(3600), 600);
+    }
+
+    #[test]
+    fn test_feed_circuit_breaker() {
+        let mut feed = ReferenceFeed::new(0);
+        feed.record_poll(&[100, 100, 100], 1000).expect("poll");
+        assert!(!feed.is_circuit_breaker_active(1000 + CIRCUIT_BREAKER_SECS));
+        assert!(feed.is_circuit_breaker_active(1001 + CIRCUIT_BREAKER_SECS));
+        // A short poll does not reset staleness
+        assert!(feed.record_poll(&[100], 2000).is_err());
+        assert_eq!(feed.stale_secs(2000), 1000);
+    }
+}
@@ -11,10 +11,12 @@
 //! - [`twap`] — TWAP (Time-Weighted Average Price) calculation
 //! - [`denomination`] — Denomination formula (Section 11.9)
 //! - [`circuit_breaker`] — Circuit breaker and emergency pause
+//! - [`exchanges`] — Exchange ticker parsing and the reference price feed
 //! - [`stub`] — Hardcoded rate oracle for v1
 
 pub mod circuit_breaker;
 pub mod denomination;
+pub mod exchanges;
 pub mod stub;
 pub mod twap;
 
@@ -64,6 +66,10 @@ pub enum OracleError {
     #[error("oracle is paused")]
     Paused,
 
+    /// An exchange ticker response could not be parsed.
+    #[error("malformed ticker: {0}")]
+    MalformedTicker(String),
+
     /// Invalid denomination parameters.
     #[error("invalid denomination: {0}")]
     InvalidDenomination(String),
@@ -7,11 +7,15 @@
 //!
 //! ## Modules
 //!
+//! - [`params`] — Governance-signed scoring parameters with effective epochs.
 //! - [`scoring`] — PoSrv scoring formula with sigmoid normalization.
 //! - [`sybilguard`] — SybilGuard trust graph for random-walk-based Sybil resistance.
+//! - [`uptime`] — Uptime attestation via epoch beacon challenges.
 
+pub mod params;
 pub mod scoring;
 pub mod sybilguard;
+pub mod uptime;
 
 /// Error types for PoSrv operations.
 #[derive(Debug, thiserror::Error)]
@@ -31,6 +35,18 @@ pub enum PoSrvError {
     /// Invalid graph operation.
     #[error("graph error: {0}")]
     GraphError(String),
+
+    /// Scoring parameters failed validation.
+    #[error("invalid scoring parameters: {0}")]
+    InvalidParams(String),
+
+    /// Scoring parameters are not signed by enough keyholders.
+    #[error("invalid parameter signature: {0}")]
+    InvalidSignature(String),
+
+    /// An uptime attestation does not match its epoch beacon.
+    #[error("invalid uptime attestation: {0}")]
+    InvalidAttestation(String),
 }
 
 /// Convenience result type for PoSrv operations.
@@ -0,0 +1,381 @@
+//! Governance-distributed PoSrv scoring parameters.
+//!
+//! The component weights and the sigmoid used to normalize GBs served are
+//! carried in a [`ScoringParams`] record signed by the governance
+//! keyholders (3-of-5, as for upgrade manifests). Each record names the
+//! epoch from which it applies, so every relay switches to new parameters at
+//! the same epoch boundary and scores stay comparable across the network.
+//!
+//! ## Validation
+//!
+//! A record is rejected unless:
+//!
+//! - each weight lies in [`WEIGHT_RANGE`] and the weights sum to 1.0,
+//! - the sigmoid midpoint lies in [`MIDPOINT_RANGE`] (GB),
+//! - the sigmoid steepness lies in [`STEEPNESS_RANGE`] (per GB),
+//! - its version is newer than every record already accepted, and
+//! - its effective epoch is after the epoch in which it is received.
+
+use ochra_crypto::ed25519::{Signature, VerifyingKey};
+use ochra_crypto::upgrade::{KEYHOLDER_COUNT, UPGRADE_THRESHOLD};
+use ochra_types::governance::MultisigEntry;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::{sigmoid, SIGMOID_DIVISOR, W_GBS_SERVED, W_TRUST, W_UPTIME, W_ZK_POR};
+use crate::{PoSrvError, Result};
+
+/// Accepted range for each component weight.
+pub const WEIGHT_RANGE: std::ops::RangeInclusive<f64> = 0.05..=0.70;
+
+/// Accepted range for the sigmoid midpoint, in GB.
+pub const MIDPOINT_RANGE: std::ops::RangeInclusive<f64> = 0.0..=10_000.0;
+
+/// Accepted range for the sigmoid steepness, per GB.
+pub const STEEPNESS_RANGE: std::ops::RangeInclusive<f64> = 0.0001..=1.0;
+
+/// Tolerance on the weight sum.
+const WEIGHT_SUM_TOLERANCE: f64 = 1e-9;
+
+/// Parameters of the PoSrv scoring formula.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoringParams {
+    /// Monotonic record version.
+    pub version: u32,
+    /// First epoch scored with these parameters.
+    pub effective_epoch: u64,
+    /// Weight of normalized GBs served.
+    pub w_gbs_served: f64,
+    /// Weight of the uptime fraction.
+    pub w_uptime: f64,
+    /// Weight of the zk-PoR pass rate.
+    pub w_zk_por: f64,
+    /// Weight of the SybilGuard trust weight.
+    pub w_trust: f64,
+    /// GBs served that normalize to 0.5.
+    pub sigmoid_midpoint: f64,
+    /// Slope of the sigmoid, per GB.
+    pub sigmoid_steepness: f64,
+    /// Keyholder signatures over [`signing_message`].
+    pub multisig_sigs: Vec<MultisigEntry>,
+}
+
+/// The genesis parameters, matching the `scoring` constants.
+static GENESIS: ScoringParams = ScoringParams {
+    version: 0,
+    effective_epoch: 0,
+    w_gbs_served: W_GBS_SERVED,
+    w_uptime: W_UPTIME,
+    w_zk_por: W_ZK_POR,
+    w_trust: W_TRUST,
+    sigmoid_midpoint: 0.0,
+    sigmoid_steepness: 1.0 / SIGMOID_DIVISOR,
+    multisig_sigs: Vec::new(),
+};
+
+impl Default for ScoringParams {
+    /// The genesis parameters, matching the `scoring` constants.
+    fn default() -> Self {
+        GENESIS.clone()
+    }
+}
+
+impl ScoringParams {
+    /// Normalize GBs served to (0, 1):
+    /// `1 / (1 + exp(-(gbs - midpoint) * steepness))`.
+    pub fn normalize_gbs(&self, gbs_served: f64) -> f64 {
+        sigmoid((gbs_served - self.sigmoid_midpoint) * self.sigmoid_steepness)
+    }
+
+    /// Check every parameter against its validation range.
+    pub fn validate(&self) -> Result<()> {
+        let weights = [
+            ("w_gbs_served", self.w_gbs_served),
+            ("w_uptime", self.w_uptime),
+            ("w_zk_por", self.w_zk_por),
+            ("w_trust", self.w_trust),
+        ];
+        for (name, weight) in weights {
+            if !WEIGHT_RANGE.contains(&weight) {
+                return Err(PoSrvError::InvalidParams(format!(
+                    "{name} {weight} outside {WEIGHT_RANGE:?}"
+                )));
+            }
+        }
+        let sum: f64 = weights.iter().map(|(_, w)| w).sum();
+        if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+            return Err(PoSrvError::InvalidParams(format!(
+                "weights sum to {sum}, expected 1.0"
+            )));
+        }
+        if !MIDPOINT_RANGE.contains(&self.sigmoid_midpoint) {
+            return Err(PoSrvError::InvalidParams(format!(
+                "sigmoid_midpoint {} outside {MIDPOINT_RANGE:?}",
+                self.sigmoid_midpoint
+            )));
+        }
+        if !STEEPNESS_RANGE.contains(&self.sigmoid_steepness) {
+            return Err(PoSrvError::InvalidParams(format!(
+                "sigmoid_steepness {} outside {STEEPNESS_RANGE:?}",
+                self.sigmoid_steepness
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Bytes each keyholder signs: a fixed prefix followed by every field
+/// except the signatures, little-endian.
+pub fn signing_message(params: &ScoringParams) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(14 + 4 + 8 * 7);
+    msg.extend_from_slice(b"scoring-params");
+    msg.extend_from_slice(&params.version.to_le_bytes());
+    msg.extend_from_slice(&params.effective_epoch.to_le_bytes());
+    for value in [
+        params.w_gbs_served,
+        params.w_uptime,
+        params.w_zk_por,
+        params.w_trust,
+        params.sigmoid_midpoint,
+        params.sigmoid_steepness,
+    ] {
+        msg.extend_from_slice(&value.to_le_bytes());
+    }
+    msg
+}
+
+/// Verify the keyholder multisig on a parameter record.
+///
+/// # Errors
+///
+/// - [`PoSrvError::InvalidSignature`] if a signature names an unknown or
+///   repeated keyholder, or fewer than [`UPGRADE_THRESHOLD`] verify
+pub fn verify_params(
+    params: &ScoringParams,
+    keyholders: &[[u8; 32]; KEYHOLDER_COUNT],
+) -> Result<()> {
+    let msg = signing_message(params);
+    let mut seen = [false; KEYHOLDER_COUNT];
+    let mut valid = 0;
+    for entry in &params.multisig_sigs {
+        let index = usize::from(entry.keyholder_index);
+        let Some(key) = keyholders.get(index) else {
+            return Err(PoSrvError::InvalidSignature(format!(
+                "unknown keyholder index {index}"
+            )));
+        };
+        if std::mem::replace(&mut seen[index], true) {
+            return Err(PoSrvError::InvalidSignature(format!(
+                "duplicate signature from keyholder {index}"
+            )));
+        }
+        let Ok(vk) = VerifyingKey::from_bytes(key) else {
+            continue;
+        };
+        if vk.verify(&msg, &Signature::from_bytes(&entry.sig)).is_ok() {
+            valid += 1;
+        }
+    }
+    if valid < UPGRADE_THRESHOLD {
+        return Err(PoSrvError::InvalidSignature(format!(
+            "{valid} valid keyholder signatures, {UPGRADE_THRESHOLD} required"
+        )));
+    }
+    Ok(())
+}
+
+/// Accepted parameter records, ordered by effective epoch.
+#[derive(Clone, Debug)]
+pub struct ScoringSchedule {
+    records: Vec<ScoringParams>,
+}
+
+impl Default for ScoringSchedule {
+    fn default() -> Self {
+        Self {
+            records: vec![ScoringParams::default()],
+        }
+    }
+}
+
+impl ScoringSchedule {
+    /// Parameters in force during `epoch`.
+    pub fn params_for_epoch(&self, epoch: u64) -> &ScoringParams {
+        self.records
+            .iter()
+            .rev()
+            .find(|p| p.effective_epoch <= epoch)
+            .or_else(|| self.records.first())
+            .unwrap_or(&GENESIS)
+    }
+
+    /// The most recently accepted record, possibly not yet in force.
+    pub fn latest(&self) -> &ScoringParams {
+        self.records.last().unwrap_or(&GENESIS)
+    }
+
+    /// Verify and schedule a record received during `current_epoch`.
+    ///
+    /// # Errors
+    ///
+    /// - [`PoSrvError::InvalidSignature`] if the multisig does not verify
+    /// - [`PoSrvError::InvalidParams`] if a parameter is out of range, the
+    ///   version is not newer than the latest record, or the effective epoch
+    ///   is not after `current_epoch` and every scheduled record
+    pub fn accept(
+        &mut self,
+        params: ScoringParams,
+        keyholders: &[[u8; 32]; KEYHOLDER_COUNT],
+        current_epoch: u64,
+    ) -> Result<()> {
+        verify_params(&params, keyholders)?;
+        params.validate()?;
+        let latest = self.latest();
+        if params.version <= latest.version {
+            return Err(PoSrvError::InvalidParams(format!(
+                "version {} is not newer than {}",
+                params.version, latest.version
+            )));
+        }
+        if params.effective_epoch <= current_epoch.max(latest.effective_epoch) {
+            return Err(PoSrvError::InvalidParams(format!(
+                "effective epoch {} is not in the future",
+                params.effective_epoch
+            )));
+        }
+        self.records.push(params);
+        Ok(())
+    }
+
+    /// Drop records superseded before `epoch`, keeping the one in force.
+    pub fn prune(&mut self, epoch: u64) {
+        let in_force = self
+            .records
+            .iter()
+            .rposition(|p| p.effective_epoch <= epoch)
+            .unwrap_or(0);
+        self.records.drain(..in_force);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::SigningKey;
+
+    fn keyholders() -> [SigningKey; KEYHOLDER_COUNT] {
+        std::array::from_fn(|_| SigningKey::generate())
+    }
+
+    fn public_keys(keys: &[SigningKey; KEYHOLDER_COUNT]) -> [[u8; 32]; KEYHOLDER_COUNT] {
+        std::array::from_fn(|i| keys[i].verifying_key().to_bytes())
+    }
+
+    fn signed(keys: &[SigningKey], version: u32, effective_epoch: u64) -> ScoringParams {
+        let mut params = ScoringParams {
+            version,
+            effective_epoch,
+            w_gbs_served: 0.40,
+            w_uptime: 0.25,
+            w_zk_por: 0.25,
+            w_trust: 0.10,
+            sigmoid_midpoint: 50.0,
+            sigmoid_steepness: 0.02,
+            multisig_sigs: Vec::new(),
+        };
+        let msg = signing_message(&params);
+        params.multisig_sigs = [0u8, 2, 4]
+            .iter()
+            .map(|&i| MultisigEntry {
+                keyholder_index: i,
+                sig: keys[usize::from(i)].sign(&msg).to_bytes(),
+            })
+            .collect();
+        params
+    }
+
+    #[test]
+    fn test_default_matches_constants() {
+        let params = ScoringParams::default();
+        params.validate().expect("genesis params are valid");
+        assert!((params.normalize_gbs(0.0) - 0.5).abs() < f64::EPSILON);
+        let expected = 1.0 / (1.0 + (-2.0_f64).exp());
+        assert!((params.normalize_gbs(200.0) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_midpoint_shifts_sigmoid() {
+        let params = ScoringParams {
+            sigmoid_midpoint: 100.0,
+            ..ScoringParams::default()
+        };
+        assert!((params.normalize_gbs(100.0) - 0.5).abs() < f64::EPSILON);
+        assert!(params.normalize_gbs(0.0) < 0.5);
+    }
+
+    #[test]
+    fn test_validation_ranges() {
+        let mut params = ScoringParams {
+            w_gbs_served: 0.75,
+            w_uptime: 0.05,
+            w_zk_por: 0.10,
+            w_trust: 0.10,
+            ..ScoringParams::default()
+        };
+        assert!(params.validate().is_err());
+        params.w_gbs_served = 0.50;
+        params.w_uptime = 0.30;
+        params.validate().expect("valid");
+        params.w_trust = 0.15;
+        assert!(params.validate().is_err(), "weights must sum to 1");
+        params.w_trust = 0.10;
+        params.sigmoid_steepness = 0.0;
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn test_multisig_threshold() {
+        let keys = keyholders();
+        let pks = public_keys(&keys);
+        let mut params = signed(&keys, 1, 10);
+        verify_params(&params, &pks).expect("3-of-5 verifies");
+
+        params.multisig_sigs.truncate(2);
+        assert!(verify_params(&params, &pks).is_err());
+
+        let mut tampered = signed(&keys, 1, 10);
+        tampered.w_trust = 0.15;
+        tampered.w_zk_por = 0.20;
+        assert!(verify_params(&tampered, &pks).is_err());
+    }
+
+    #[test]
+    fn test_schedule_switches_at_effective_epoch() {
+        let keys = keyholders();
+        let pks = public_keys(&keys);
+        let mut schedule = ScoringSchedule::default();
+        schedule
+            .accept(signed(&keys, 1, 10), &pks, 5)
+            .expect("accept");
+
+        assert_eq!(schedule.params_for_epoch(9).version, 0);
+        assert_eq!(schedule.params_for_epoch(10).version, 1);
+        assert_eq!(schedule.latest().version, 1);
+
+        schedule.prune(12);
+        assert_eq!(schedule.params_for_epoch(0).version, 1);
+    }
+
+    #[test]
+    fn test_schedule_rejects_stale_records() {
+        let keys = keyholders();
+        let pks = public_keys(&keys);
+        let mut schedule = ScoringSchedule::default();
+
+        assert!(schedule.accept(signed(&keys, 1, 5), &pks, 5).is_err());
+        schedule
+            .accept(signed(&keys, 2, 10), &pks, 5)
+            .expect("accept");
+        assert!(schedule.accept(signed(&keys, 2, 20), &pks, 5).is_err());
+        assert!(schedule.accept(signed(&keys, 3, 8), &pks, 5).is_err());
+    }
+}
@@ -12,9 +12,14 @@
 //!
 //! GB served is normalized to [0, 1] using a sigmoid function:
 //! `1 / (1 + exp(-gbs / 100))`.
+//!
+//! These constants are the genesis parameters. Governance may replace them
+//! from a given epoch with a signed [`ScoringParams`] record; score with
+//! [`compute_posrv_breakdown_with`] to apply the record in force.
 
 use serde::{Deserialize, Serialize};
 
+use crate::params::ScoringParams;
 use crate::{PoSrvError, Result};
 
 /// Weight for GBs served component.
@@ -89,6 +94,14 @@ pub fn compute_posrv(input: &PoSrvInput) -> Result<f64> {
 ///
 /// A [`PoSrvBreakdown`] with individual component scores and the composite.
 pub fn compute_posrv_breakdown(input: &PoSrvInput) -> Result<PoSrvBreakdown> {
+    compute_posrv_breakdown_with(input, &ScoringParams::default())
+}
+
+/// Compute the full PoSrv score breakdown under governance parameters.
+pub fn compute_posrv_breakdown_with(
+    input: &PoSrvInput,
+    params: &ScoringParams,
+) -> Result<PoSrvBreakdown> {
     // Validate input ranges.
     validate_fraction("uptime_fraction", input.uptime_fraction)?;
     validate_fraction("zkpor_pass_rate", input.zkpor_pass_rate)?;
@@ -101,14 +114,13 @@ pub fn compute_posrv_breakdown(input: &PoSrvInput) -> Result<PoSrvBreakdown> {
         });
     }
 
-    // Normalize GBs served using sigmoid: 1 / (1 + exp(-gbs / 100)).
-    let gbs_served_normalized = sigmoid(input.gbs_served / SIGMOID_DIVISOR);
+    let gbs_served_normalized = params.normalize_gbs(input.gbs_served);
 
     // Weighted sum.
-    let composite = W_GBS_SERVED * gbs_served_normalized
-        + W_UPTIME * input.uptime_fraction
-        + W_ZK_POR * input.zkpor_pass_rate
-        + W_TRUST * input.trust_weight;
+    let composite = params.w_gbs_served * gbs_served_normalized
+        + params.w_uptime * input.uptime_fraction
+        + params.w_zk_por * input.zkpor_pass_rate
+        + params.w_trust * input.trust_weight;
 
     let quorum_eligible = composite >= QUORUM_THRESHOLD;
 
@@ -137,7 +149,7 @@ pub fn rank_nodes(scores: &[PoSrvBreakdown]) -> Vec<usize> {
 }
 
 /// Standard sigmoid function: 1 / (1 + exp(-x)).
-fn sigmoid(x: f64) -> f64 {
+pub(crate) fn sigmoid(x: f64) -> f64 {
     1.0 / (1.0 + (-x).exp())
 }
 
@@ -297,6 +309,28 @@ mod tests {
         assert!((score - breakdown.composite).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_governance_params_change_score() {
+        let input = PoSrvInput {
+            gbs_served: 0.0,
+            uptime_fraction: 1.0,
+            zkpor_pass_rate: 0.0,
+            trust_weight: 0.0,
+        };
+        let params = ScoringParams {
+            w_gbs_served: 0.20,
+            w_uptime: 0.50,
+            w_zk_por: 0.20,
+            w_trust: 0.10,
+            ..ScoringParams::default()
+        };
+        let default = compute_posrv_breakdown(&input).expect("default");
+        let governed = compute_posrv_breakdown_with(&input, &params).expect("governed");
+        // 0.2*0.5 + 0.5*1.0 = 0.6 versus 0.4*0.5 + 0.3*1.0 = 0.5
+        assert!((default.composite - 0.5).abs() < 0.001);
+        assert!((governed.composite - 0.6).abs() < 0.001);
+    }
+
     #[test]
     fn test_rank_nodes() {
         let scores = vec![
@@ -0,0 +1,593 @@
+//! Uptime attestation via epoch beacon challenges.
+//!
+//! Each epoch is divided into [`BEACON_SLOTS_PER_EPOCH`] slots. For every
+//! relay and slot, the epoch beacon selects [`AUDITORS_PER_CHALLENGE`]
+//! auditors from the active relay set with [`select_auditors`]. Each auditor
+//! sends the relay a [`BeaconChallenge`] whose nonce is fixed by the beacon,
+//! so neither side can pick it. A live relay signs the challenge
+//! ([`respond`]); the auditor then signs an [`UptimeAttestation`] recording
+//! whether a valid response arrived within [`RESPONSE_WINDOW_SECS`] and
+//! gossips it.
+//!
+//! Node IDs are not keys. Responses and attestations are signed with the
+//! signer's PIK and carry its public key, which must hash to the `pik_hash`
+//! in the signer's relay descriptor ([`ActiveRelay`]).
+//!
+//! ## Uptime component
+//!
+//! [`AttestationSet`] collects verified attestations. A slot counts as up
+//! when at least half of the auditors that reported on it saw a response;
+//! slots nobody reported on are ignored rather than counted as down, so an
+//! offline auditor does not cost the relay uptime. Up to
+//! [`MISSED_BEACON_ALLOWANCE`] missed slots per epoch are forgiven to absorb
+//! restarts and transient packet loss.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::RangeInclusive;
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use ochra_types::network::RelayDescriptor;
+use serde::{Deserialize, Serialize};
+
+use crate::{PoSrvError, Result};
+
+/// Beacon challenge slots per epoch (one per relay epoch).
+pub const BEACON_SLOTS_PER_EPOCH: u32 = 24;
+
+/// Auditors challenging each relay per slot.
+pub const AUDITORS_PER_CHALLENGE: usize = 3;
+
+/// Time a relay has to answer a challenge.
+pub const RESPONSE_WINDOW_SECS: u64 = 60;
+
+/// Missed slots forgiven per epoch.
+pub const MISSED_BEACON_ALLOWANCE: u32 = 2;
+
+/// A member of the active relay set, from its descriptor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActiveRelay {
+    /// Node ID auditors are selected and challenged by.
+    pub node_id: [u8; 32],
+    /// Hash of the PIK that signs for the node.
+    pub pik_hash: [u8; 32],
+}
+
+impl From<&RelayDescriptor> for ActiveRelay {
+    fn from(descriptor: &RelayDescriptor) -> Self {
+        Self {
+            node_id: descriptor.node_id,
+            pik_hash: descriptor.pik_hash,
+        }
+    }
+}
+
+/// A liveness challenge from an auditor to a relay.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconChallenge {
+    /// Epoch the challenge belongs to.
+    pub epoch: u64,
+    /// Slot within the epoch, in `0..BEACON_SLOTS_PER_EPOCH`.
+    pub slot: u32,
+    /// Relay being challenged.
+    pub relay_id: [u8; 32],
+    /// Auditor issuing the challenge.
+    pub auditor_id: [u8; 32],
+    /// Beacon-derived nonce, see [`challenge_nonce`].
+    pub nonce: [u8; 32],
+}
+
+impl BeaconChallenge {
+    /// Build the challenge `auditor_id` sends `relay_id` in `slot`.
+    pub fn new(
+        beacon: &[u8; 32],
+        epoch: u64,
+        slot: u32,
+        relay_id: [u8; 32],
+        auditor_id: [u8; 32],
+    ) -> Self {
+        Self {
+            epoch,
+            slot,
+            relay_id,
+            auditor_id,
+            nonce: challenge_nonce(beacon, epoch, slot, &relay_id),
+        }
+    }
+
+    fn encode(&self, prefix: &[u8]) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(prefix.len() + 8 + 4 + 32 * 3);
+        msg.extend_from_slice(prefix);
+        msg.extend_from_slice(&self.epoch.to_le_bytes());
+        msg.extend_from_slice(&self.slot.to_le_bytes());
+        msg.extend_from_slice(&self.relay_id);
+        msg.extend_from_slice(&self.auditor_id);
+        msg.extend_from_slice(&self.nonce);
+        msg
+    }
+}
+
+/// `BLAKE3::hash("uptime-challenge" || beacon || epoch || slot || relay_id)`.
+pub fn challenge_nonce(beacon: &[u8; 32], epoch: u64, slot: u32, relay_id: &[u8; 32]) -> [u8; 32] {
+    let mut input = Vec::with_capacity(16 + 32 + 8 + 4 + 32);
+    input.extend_from_slice(b"uptime-challenge");
+    input.extend_from_slice(beacon);
+    input.extend_from_slice(&epoch.to_le_bytes());
+    input.extend_from_slice(&slot.to_le_bytes());
+    input.extend_from_slice(relay_id);
+    blake3::hash(&input)
+}
+
+/// Auditors for `relay_id` in `slot`: the [`AUDITORS_PER_CHALLENGE`]
+/// candidates (other than the relay) with the lowest
+/// `BLAKE3::hash(nonce || candidate)`.
+pub fn select_auditors(
+    beacon: &[u8; 32],
+    epoch: u64,
+    slot: u32,
+    relay_id: &[u8; 32],
+    candidates: &[[u8; 32]],
+) -> Vec<[u8; 32]> {
+    let nonce = challenge_nonce(beacon, epoch, slot, relay_id);
+    let mut ranked: Vec<([u8; 32], [u8; 32])> = candidates
+        .iter()
+        .filter(|c| *c != relay_id)
+        .map(|c| {
+            let mut input = [0u8; 64];
+            input[..32].copy_from_slice(&nonce);
+            input[32..].copy_from_slice(c);
+            (blake3::hash(&input), *c)
+        })
+        .collect();
+    ranked.sort_unstable();
+    ranked.dedup_by_key(|(_, c)| *c);
+    ranked
+        .into_iter()
+        .take(AUDITORS_PER_CHALLENGE)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+/// A relay's signed answer to a challenge.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconResponse {
+    /// The challenge answered.
+    pub challenge: BeaconChallenge,
+    /// Relay's PIK public key.
+    pub relay_pik: [u8; 32],
+    /// PIK signature over `"uptime-response" || challenge`.
+    pub relay_sig: Vec<u8>,
+}
+
+/// Answer a challenge with the relay's PIK.
+pub fn respond(challenge: &BeaconChallenge, relay_pik: &SigningKey) -> BeaconResponse {
+    BeaconResponse {
+        challenge: challenge.clone(),
+        relay_pik: relay_pik.verifying_key().to_bytes(),
+        relay_sig: relay_pik
+            .sign(&challenge.encode(b"uptime-response"))
+            .to_bytes()
+            .to_vec(),
+    }
+}
+
+/// An auditor's signed record of one challenge, gossiped to the network.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UptimeAttestation {
+    /// The challenge issued.
+    pub challenge: BeaconChallenge,
+    /// Relay's PIK public key from its response; `None` if it did not
+    /// answer.
+    pub relay_pik: Option<[u8; 32]>,
+    /// Relay signature from its response; empty if it did not answer.
+    pub relay_sig: Vec<u8>,
+    /// Auditor's PIK public key.
+    pub auditor_pik: [u8; 32],
+    /// Auditor PIK signature over `"uptime-attestation" || challenge ||
+    /// relay_pik || relay_sig` (`relay_pik` omitted if absent).
+    pub auditor_sig: Vec<u8>,
+}
+
+impl UptimeAttestation {
+    /// Whether the relay answered.
+    pub fn responded(&self) -> bool {
+        !self.relay_sig.is_empty()
+    }
+
+    fn signing_message(
+        challenge: &BeaconChallenge,
+        relay_pik: Option<&[u8; 32]>,
+        relay_sig: &[u8],
+    ) -> Vec<u8> {
+        let mut msg = challenge.encode(b"uptime-attestation");
+        if let Some(pik) = relay_pik {
+            msg.extend_from_slice(pik);
+        }
+        msg.extend_from_slice(relay_sig);
+        msg
+    }
+}
+
+/// Record the outcome of a challenge. `response` is what arrived within
+/// [`RESPONSE_WINDOW_SECS`], if anything; a response to another challenge,
+/// from a PIK other than `relay_pik_hash`, or with a bad signature is
+/// recorded as no response.
+pub fn attest(
+    challenge: &BeaconChallenge,
+    response: Option<&BeaconResponse>,
+    relay_pik_hash: &[u8; 32],
+    auditor_pik: &SigningKey,
+) -> UptimeAttestation {
+    let answered = response
+        .filter(|r| r.challenge == *challenge && verify_response(r, relay_pik_hash).is_ok());
+    let relay_pik = answered.map(|r| r.relay_pik);
+    let relay_sig = answered.map(|r| r.relay_sig.clone()).unwrap_or_default();
+    let auditor_sig = auditor_pik
+        .sign(&UptimeAttestation::signing_message(
+            challenge,
+            relay_pik.as_ref(),
+            &relay_sig,
+        ))
+        .to_bytes()
+        .to_vec();
+    UptimeAttestation {
+        challenge: challenge.clone(),
+        relay_pik,
+        relay_sig,
+        auditor_pik: auditor_pik.verifying_key().to_bytes(),
+        auditor_sig,
+    }
+}
+
+/// Check that `pik` is the key behind `pik_hash`, then verify its signature.
+fn verify_sig(pik: &[u8; 32], pik_hash: &[u8; 32], msg: &[u8], sig: &[u8]) -> Result<()> {
+    if blake3::hash(pik) != *pik_hash {
+        return Err(PoSrvError::InvalidSignature(
+            "signing key is not the descriptor's PIK".into(),
+        ));
+    }
+    let sig: [u8; 64] = sig
+        .try_into()
+        .map_err(|_| PoSrvError::InvalidSignature("signature is not 64 bytes".into()))?;
+    VerifyingKey::from_bytes(pik)
+        .and_then(|vk| vk.verify(msg, &Signature::from_bytes(&sig)))
+        .map_err(|e| PoSrvError::InvalidSignature(e.to_string()))
+}
+
+/// Verify a relay's response, signed by the PIK whose hash its descriptor
+/// publishes as `relay_pik_hash`.
+pub fn verify_response(response: &BeaconResponse, relay_pik_hash: &[u8; 32]) -> Result<()> {
+    verify_sig(
+        &response.relay_pik,
+        relay_pik_hash,
+        &response.challenge.encode(b"uptime-response"),
+        &response.relay_sig,
+    )
+}
+
+/// Verify a gossiped attestation against the epoch beacon and the relay set
+/// auditors were drawn from.
+///
+/// # Errors
+///
+/// - [`PoSrvError::InvalidAttestation`] if the slot is out of range, the
+///   nonce does not match the beacon, the relay is not in `relays`, or the
+///   auditor was not selected
+/// - [`PoSrvError::InvalidSignature`] if the auditor or relay signature
+///   fails or was not made by the PIK in its descriptor
+pub fn verify_attestation(
+    attestation: &UptimeAttestation,
+    beacon: &[u8; 32],
+    relays: &[ActiveRelay],
+) -> Result<()> {
+    let c = &attestation.challenge;
+    if c.slot >= BEACON_SLOTS_PER_EPOCH {
+        return Err(PoSrvError::InvalidAttestation(format!(
+            "slot {} out of range",
+            c.slot
+        )));
+    }
+    if c.nonce != challenge_nonce(beacon, c.epoch, c.slot, &c.relay_id) {
+        return Err(PoSrvError::InvalidAttestation(
+            "nonce does not match the epoch beacon".into(),
+        ));
+    }
+    let pik_hash_of = |node_id: &[u8; 32]| {
+        relays
+            .iter()
+            .find(|r| r.node_id == *node_id)
+            .map(|r| r.pik_hash)
+    };
+    let Some(relay_pik_hash) = pik_hash_of(&c.relay_id) else {
+        return Err(PoSrvError::InvalidAttestation(
+            "relay is not in the active set".into(),
+        ));
+    };
+    let candidates: Vec<[u8; 32]> = relays.iter().map(|r| r.node_id).collect();
+    let auditor_pik_hash = pik_hash_of(&c.auditor_id)
+        .filter(|_| {
+            select_auditors(beacon, c.epoch, c.slot, &c.relay_id, &candidates)
+                .contains(&c.auditor_id)
+        })
+        .ok_or_else(|| {
+            PoSrvError::InvalidAttestation("auditor was not selected for this slot".into())
+        })?;
+    verify_sig(
+        &attestation.auditor_pik,
+        &auditor_pik_hash,
+        &UptimeAttestation::signing_message(
+            c,
+            attestation.relay_pik.as_ref(),
+            &attestation.relay_sig,
+        ),
+        &attestation.auditor_sig,
+    )?;
+    if attestation.responded() {
+        let Some(relay_pik) = &attestation.relay_pik else {
+            return Err(PoSrvError::InvalidSignature(
+                "relay signature without its PIK".into(),
+            ));
+        };
+        verify_sig(
+            relay_pik,
+            &relay_pik_hash,
+            &c.encode(b"uptime-response"),
+            &attestation.relay_sig,
+        )?;
+    }
+    Ok(())
+}
+
+/// Auditor reports for one slot.
+#[derive(Clone, Debug, Default)]
+struct SlotReports {
+    up: HashSet<[u8; 32]>,
+    down: HashSet<[u8; 32]>,
+}
+
+impl SlotReports {
+    fn is_up(&self) -> bool {
+        self.up.len() >= self.down.len()
+    }
+}
+
+/// Verified attestations, indexed by relay, epoch, and slot.
+#[derive(Clone, Debug, Default)]
+pub struct AttestationSet {
+    relays: HashMap<[u8; 32], BTreeMap<(u64, u32), SlotReports>>,
+}
+
+impl AttestationSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a verified attestation. Returns `false` if this auditor
+    /// already reported on the slot.
+    pub fn insert(&mut self, attestation: &UptimeAttestation) -> bool {
+        let c = &attestation.challenge;
+        let slot = self
+            .relays
+            .entry(c.relay_id)
+            .or_default()
+            .entry((c.epoch, c.slot))
+            .or_default();
+        if slot.up.contains(&c.auditor_id) || slot.down.contains(&c.auditor_id) {
+            return false;
+        }
+        if attestation.responded() {
+            slot.up.insert(c.auditor_id);
+        } else {
+            slot.down.insert(c.auditor_id);
+        }
+        true
+    }
+
+    /// Uptime fraction of `relay_id` over `epochs`, or `None` if no slot in
+    /// the range was reported on.
+    pub fn uptime_fraction(&self, relay_id: &[u8; 32], epochs: RangeInclusive<u64>) -> Option<f64> {
+        let slots = self.relays.get(relay_id)?;
+        let mut observed = 0u64;
+        let mut credited = 0u64;
+        let mut epoch_counts: BTreeMap<u64, (u32, u32)> = BTreeMap::new();
+        for (&(epoch, _), reports) in slots.range((*epochs.start(), 0)..=(*epochs.end(), u32::MAX))
+        {
+            let (seen, up) = epoch_counts.entry(epoch).or_default();
+            *seen += 1;
+            if reports.is_up() {
+                *up += 1;
+            }
+        }
+        for (seen, up) in epoch_counts.into_values() {
+            let forgiven = (seen - up).min(MISSED_BEACON_ALLOWANCE);
+            observed += u64::from(seen);
+            credited += u64::from(up + forgiven);
+        }
+        (observed > 0).then(|| credited as f64 / observed as f64)
+    }
+
+    /// Drop attestations for epochs before `epoch`.
+    pub fn prune_before(&mut self, epoch: u64) {
+        for slots in self.relays.values_mut() {
+            *slots = slots.split_off(&(epoch, 0));
+        }
+        self.relays.retain(|_, slots| !slots.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEACON: [u8; 32] = [5u8; 32];
+
+    /// A relay whose node ID is unrelated to its PIK, as in a real
+    /// descriptor.
+    struct Node {
+        pik: SigningKey,
+        relay: ActiveRelay,
+    }
+
+    fn node(id: u8) -> Node {
+        let pik = SigningKey::generate();
+        let relay = ActiveRelay {
+            node_id: [id; 32],
+            pik_hash: blake3::hash(&pik.verifying_key().to_bytes()),
+        };
+        Node { pik, relay }
+    }
+
+    struct Network {
+        relay: Node,
+        auditors: Vec<Node>,
+        active: Vec<ActiveRelay>,
+        candidates: Vec<[u8; 32]>,
+    }
+
+    impl Network {
+        fn relay_id(&self) -> [u8; 32] {
+            self.relay.relay.node_id
+        }
+    }
+
+    fn network() -> Network {
+        let relay = node(0xEE);
+        let auditors: Vec<Node> = (1..=6).map(node).collect();
+        let mut active: Vec<ActiveRelay> = auditors.iter().map(|n| n.relay).collect();
+        active.push(relay.relay);
+        let candidates = active.iter().map(|r| r.node_id).collect();
+        Network {
+            relay,
+            auditors,
+            active,
+            candidates,
+        }
+    }
+
+    /// Attestations from every selected auditor for one slot.
+    fn run_slot(net: &Network, epoch: u64, slot: u32, online: bool) -> Vec<UptimeAttestation> {
+        let relay_id = net.relay_id();
+        select_auditors(&BEACON, epoch, slot, &relay_id, &net.candidates)
+            .into_iter()
+            .map(|auditor_id| {
+                let auditor = net
+                    .auditors
+                    .iter()
+                    .find(|n| n.relay.node_id == auditor_id)
+                    .expect("selected auditor is known");
+                let challenge = BeaconChallenge::new(&BEACON, epoch, slot, relay_id, auditor_id);
+                let response = online.then(|| respond(&challenge, &net.relay.pik));
+                attest(
+                    &challenge,
+                    response.as_ref(),
+                    &net.relay.relay.pik_hash,
+                    &auditor.pik,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_auditor_selection() {
+        let net = network();
+        let relay_id = net.relay_id();
+        let a = select_auditors(&BEACON, 1, 0, &relay_id, &net.candidates);
+        assert_eq!(a.len(), AUDITORS_PER_CHALLENGE);
+        assert!(!a.contains(&relay_id));
+        assert_eq!(
+            a,
+            select_auditors(&BEACON, 1, 0, &relay_id, &net.candidates)
+        );
+        let other_slots: Vec<_> = (1..8)
+            .map(|slot| select_auditors(&BEACON, 1, slot, &relay_id, &net.candidates))
+            .collect();
+        assert!(other_slots.iter().any(|b| *b != a), "selection rotates");
+    }
+
+    #[test]
+    fn test_attestations_verify() {
+        let net = network();
+        for att in run_slot(&net, 3, 4, true)
+            .iter()
+            .chain(&run_slot(&net, 3, 5, false))
+        {
+            verify_attestation(att, &BEACON, &net.active).expect("verify");
+        }
+        let att = &run_slot(&net, 3, 4, true)[0];
+        assert!(att.responded());
+        assert!(verify_attestation(att, &[6u8; 32], &net.active).is_err());
+    }
+
+    #[test]
+    fn test_response_checked_against_descriptor_pik() {
+        let net = network();
+        let challenge = BeaconChallenge::new(&BEACON, 3, 4, net.relay_id(), [1; 32]);
+        let response = respond(&challenge, &net.relay.pik);
+        verify_response(&response, &net.relay.relay.pik_hash).expect("honest relay");
+
+        // A valid signature from some other key is not the relay's
+        let impostor = respond(&challenge, &SigningKey::generate());
+        assert!(verify_response(&impostor, &net.relay.relay.pik_hash).is_err());
+        let auditor = &net.auditors[0];
+        let att = attest(
+            &challenge,
+            Some(&impostor),
+            &net.relay.relay.pik_hash,
+            &auditor.pik,
+        );
+        assert!(!att.responded());
+    }
+
+    #[test]
+    fn test_forged_attestations_rejected() {
+        let net = network();
+        let mut att = run_slot(&net, 3, 4, false).remove(0);
+        att.relay_sig = vec![1u8; 64];
+        assert!(verify_attestation(&att, &BEACON, &net.active).is_err());
+
+        // Signed by a key other than the auditor's descriptor PIK
+        let mut att = run_slot(&net, 3, 4, false).remove(0);
+        let stranger = SigningKey::generate();
+        let forged = attest(&att.challenge, None, &net.relay.relay.pik_hash, &stranger);
+        att.auditor_pik = forged.auditor_pik;
+        att.auditor_sig = forged.auditor_sig;
+        assert!(verify_attestation(&att, &BEACON, &net.active).is_err());
+
+        let outsider = node(0x77);
+        let challenge = BeaconChallenge::new(&BEACON, 3, 4, net.relay_id(), outsider.relay.node_id);
+        let att = attest(&challenge, None, &net.relay.relay.pik_hash, &outsider.pik);
+        assert!(verify_attestation(&att, &BEACON, &net.active).is_err());
+    }
+
+    #[test]
+    fn test_uptime_with_missed_beacon_tolerance() {
+        let net = network();
+        let relay_id = net.relay_id();
+        let mut set = AttestationSet::new();
+        for slot in 0..BEACON_SLOTS_PER_EPOCH {
+            // Offline for 6 slots: 2 are forgiven.
+            let online = slot >= 6;
+            for att in run_slot(&net, 10, slot, online) {
+                assert!(set.insert(&att));
+            }
+        }
+        let uptime = set.uptime_fraction(&relay_id, 10..=10).expect("observed");
+        assert!((uptime - 20.0 / 24.0).abs() < 1e-9);
+        assert_eq!(set.uptime_fraction(&relay_id, 11..=12), None);
+    }
+
+    #[test]
+    fn test_unreported_slots_ignored_and_duplicates_dropped() {
+        let net = network();
+        let relay_id = net.relay_id();
+        let mut set = AttestationSet::new();
+        let atts = run_slot(&net, 2, 0, true);
+        assert!(set.insert(&atts[0]));
+        assert!(!set.insert(&atts[0]));
+        assert_eq!(set.uptime_fraction(&relay_id, 0..=5), Some(1.0));
+
+        set.prune_before(3);
+        assert_eq!(set.uptime_fraction(&relay_id, 0..=5), None);
+    }
+}
@@ -12,8 +12,16 @@
 //! | Iterations | 1       |
 //! | Parallelism| 1       |
 //! | Output     | 32 bytes|
+//!
+//! These are the minimum. A solver may spend more per attempt (see
+//! [`KdfProfile::Pow`]); every [`PowSolution`] declares the parameters it
+//! was made with, and verifiers reject parameters outside the profile's
+//! bounds before recomputing. Costlier parameters earn a
+//! [`difficulty_credit`] of `floor(log2(m * t / (m0 * t0)))` bits, so the
+//! expected total work never drops below the default's while hosts with
+//! memory to spare make each attempt harder to parallelize.
 
-use ochra_crypto::argon2id;
+use ochra_crypto::argon2id::{Argon2Params, KdfProfile};
 use serde::{Deserialize, Serialize};
 
 use crate::{PowError, Result};
@@ -51,6 +59,37 @@ pub struct PowSolution {
     pub nonce: [u8; NONCE_LEN],
     /// The resulting Argon2id hash.
     pub hash: [u8; POW_OUTPUT_LEN],
+    /// Argon2id parameters the hash was computed with.
+    #[serde(default = "default_params")]
+    pub params: Argon2Params,
+}
+
+/// The network default PoW parameters (the table above).
+pub fn default_params() -> Argon2Params {
+    Argon2Params {
+        m_cost: POW_M_COST,
+        t_cost: POW_T_COST,
+        p_cost: POW_P_COST,
+    }
+}
+
+/// Leading zero bits waived for a proof made with `params`.
+///
+/// Each attempt costs `m * t` relative to the default; the credit is the
+/// whole number of doublings of that cost, so it never outweighs the extra
+/// work.
+pub fn difficulty_credit(params: &Argon2Params) -> u32 {
+    let base = u64::from(POW_M_COST) * u64::from(POW_T_COST);
+    let cost = u64::from(params.m_cost) * u64::from(params.t_cost);
+    if cost < base {
+        return 0;
+    }
+    (cost / base).ilog2()
+}
+
+/// Whether `hash` meets `difficulty` for a proof made with `params`.
+fn meets_difficulty(hash: &[u8], difficulty: u32, params: &Argon2Params) -> bool {
+    count_leading_zero_bits(hash) + difficulty_credit(params) >= difficulty
 }
 
 /// Solve a PoW challenge by finding a nonce whose Argon2id hash meets
@@ -65,6 +104,23 @@ pub struct PowSolution {
 ///
 /// With high difficulty, this may take a very long time.
 pub fn solve_pow(challenge: &PowChallenge, content_hash: &[u8; 32]) -> Result<PowSolution> {
+    solve_pow_with(challenge, content_hash, default_params())
+}
+
+/// [`solve_pow`] with calibrated parameters.
+///
+/// # Errors
+///
+/// - [`PowError::Argon2`] if `params` are outside [`KdfProfile::Pow`]'s bounds
+pub fn solve_pow_with(
+    challenge: &PowChallenge,
+    content_hash: &[u8; 32],
+    params: Argon2Params,
+) -> Result<PowSolution> {
+    params
+        .validate(KdfProfile::Pow)
+        .map_err(|e| PowError::Argon2(e.to_string()))?;
+
     // Build the data to hash: nonce_prefix || target_hash || content_hash
     let mut data = Vec::with_capacity(
         challenge.nonce_prefix.len() + challenge.target_hash.len() + content_hash.len(),
@@ -75,28 +131,26 @@ pub fn solve_pow(challenge: &PowChallenge, content_hash: &[u8; 32]) -> Result<Po
 
     loop {
         let nonce = random_nonce();
-        let hash_vec = argon2id::derive_key_custom(
-            &data,
-            &nonce,
-            POW_M_COST,
-            POW_T_COST,
-            POW_P_COST,
-            POW_OUTPUT_LEN,
-        )
-        .map_err(|e| PowError::Argon2(e.to_string()))?;
+        let hash_vec = params
+            .derive(&data, &nonce, POW_OUTPUT_LEN)
+            .map_err(|e| PowError::Argon2(e.to_string()))?;
 
-        let leading = count_leading_zero_bits(&hash_vec);
-        if leading >= challenge.difficulty {
+        if meets_difficulty(&hash_vec, challenge.difficulty, &params) {
             let mut hash = [0u8; POW_OUTPUT_LEN];
             hash.copy_from_slice(&hash_vec);
-            return Ok(PowSolution { nonce, hash });
+            return Ok(PowSolution {
+                nonce,
+                hash,
+                params,
+            });
         }
     }
 }
 
 /// Verify a PoW solution against a challenge.
 ///
-/// Recomputes the Argon2id hash and checks the difficulty target.
+/// Recomputes the Argon2id hash with the declared parameters and checks
+/// the difficulty target.
 ///
 /// # Returns
 ///
@@ -109,20 +163,47 @@ pub fn verify_pow(challenge: &PowChallenge, solution: &PowSolution) -> bool {
     // Note: content_hash is embedded in the target_hash for verification
     // In a full implementation, verify_pow would also take content_hash.
     // For v1, the target_hash is the binding commitment.
+    if solution.params.validate(KdfProfile::Pow).is_err() {
+        return false;
+    }
 
-    let hash_result = argon2id::derive_key_custom(
-        &data,
-        &solution.nonce,
-        POW_M_COST,
-        POW_T_COST,
-        POW_P_COST,
-        POW_OUTPUT_LEN,
+    match solution
+        .params
+        .derive(&data, &solution.nonce, POW_OUTPUT_LEN)
+    {
+        Ok(hash_vec) => meets_difficulty(&hash_vec, challenge.difficulty, &solution.params),
+        Err(_) => false,
+    }
+}
+
+/// Verify a PoW solution produced by [`solve_pow`] for a specific content hash.
+///
+/// Unlike [`verify_pow`], the recomputation includes `content_hash`, matching
+/// the preimage used by the solver, and the claimed hash must equal the
+/// recomputed one. Declared parameters outside [`KdfProfile::Pow`]'s bounds
+/// are rejected without hashing.
+pub fn verify_pow_with_content(
+    challenge: &PowChallenge,
+    content_hash: &[u8; 32],
+    solution: &PowSolution,
+) -> bool {
+    let mut data = Vec::with_capacity(
+        challenge.nonce_prefix.len() + challenge.target_hash.len() + content_hash.len(),
     );
+    data.extend_from_slice(&challenge.nonce_prefix);
+    data.extend_from_slice(&challenge.target_hash);
+    data.extend_from_slice(content_hash);
+    if solution.params.validate(KdfProfile::Pow).is_err() {
+        return false;
+    }
 
-    match hash_result {
+    match solution
+        .params
+        .derive(&data, &solution.nonce, POW_OUTPUT_LEN)
+    {
         Ok(hash_vec) => {
-            let leading = count_leading_zero_bits(&hash_vec);
-            leading >= challenge.difficulty
+            hash_vec == solution.hash
+                && meets_difficulty(&hash_vec, challenge.difficulty, &solution.params)
         }
         Err(_) => false,
     }
@@ -165,6 +246,27 @@ mod tests {
         assert_eq!(solution.hash.len(), POW_OUTPUT_LEN);
     }
 
+    #[test]
+    fn test_verify_with_content_roundtrip() {
+        let challenge = PowChallenge {
+            target_hash: [0xAA; 32],
+            difficulty: 1,
+            nonce_prefix: b"test".to_vec(),
+        };
+        let content_hash = [0xBB; 32];
+        let solution = solve_pow(&challenge, &content_hash).expect("solve");
+        assert!(verify_pow_with_content(
+            &challenge,
+            &content_hash,
+            &solution
+        ));
+        assert!(!verify_pow_with_content(&challenge, &[0xCC; 32], &solution));
+
+        let mut forged = solution.clone();
+        forged.hash = [0u8; POW_OUTPUT_LEN];
+        assert!(!verify_pow_with_content(&challenge, &content_hash, &forged));
+    }
+
     #[test]
     fn test_count_leading_zero_bits() {
         assert_eq!(count_leading_zero_bits(&[0x00, 0x00, 0xFF]), 16);
@@ -179,6 +281,53 @@ mod tests {
     fn test_pow_m_cost() {
         // 16 MB = 16384 KiB
         assert_eq!(POW_M_COST, 16_384);
+        assert_eq!(default_params(), KdfProfile::Pow.default_params());
+    }
+
+    #[test]
+    fn test_declared_params_are_verified() {
+        let challenge = PowChallenge {
+            target_hash: [0xAA; 32],
+            difficulty: 0,
+            nonce_prefix: vec![],
+        };
+        let content_hash = [0xBB; 32];
+        let stronger = Argon2Params {
+            m_cost: 2 * POW_M_COST,
+            t_cost: 2,
+            p_cost: 2,
+        };
+        let solution = solve_pow_with(&challenge, &content_hash, stronger).expect("solve");
+        assert_eq!(solution.params, stronger);
+        assert!(verify_pow_with_content(
+            &challenge,
+            &content_hash,
+            &solution
+        ));
+
+        // Claiming other parameters changes the hash.
+        let mut relabeled = solution.clone();
+        relabeled.params = default_params();
+        assert!(!verify_pow_with_content(
+            &challenge,
+            &content_hash,
+            &relabeled
+        ));
+
+        assert_eq!(difficulty_credit(&default_params()), 0);
+        assert_eq!(difficulty_credit(&stronger), 2);
+        let (_, max) = KdfProfile::Pow.bounds();
+        assert_eq!(difficulty_credit(&max), 3);
+
+        // Parameters below the floor are refused outright.
+        let cheap = Argon2Params {
+            m_cost: 1024,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        assert!(solve_pow_with(&challenge, &content_hash, cheap).is_err());
+        relabeled.params = cheap;
+        assert!(!verify_pow(&challenge, &relabeled));
     }
 
     #[test]
@@ -9,8 +9,10 @@
 //!
 //! - [`argon2id_pow`] — Publishing PoW using Argon2id
 //! - [`zk_por`] — zk-PoR circuit interface (Section 31.2)
+//! - [`por_witness`] — zk-PoR witness builder and proving pipeline
 
 pub mod argon2id_pow;
+pub mod por_witness;
 pub mod zk_por;
 
 /// Error types for Proof-of-Work operations.
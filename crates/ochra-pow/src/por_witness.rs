@@ -0,0 +1,507 @@
+//! zk-PoR witness builder (Section 31.2).
+//!
+//! Builds the private witness for the zk-PoR circuit from the chunks a node
+//! stores, and drives proof generation with progress reporting.
+//!
+//! ## Commitments
+//!
+//! All hashing is Poseidon so the values match the circuit:
+//!
+//! - `data_commitment` — Poseidon sponge (zk-PoR domain) over the chunk data
+//!   in 31-byte little-endian limbs, followed by the data length. Cached in
+//!   [`ChunkCommitments`] for the tree's other leaves; challenged chunks are
+//!   recommitted from their bytes for every proof.
+//! - `auth_tag = Poseidon(chunk_id, data_commitment)`
+//! - `leaf = Poseidon(chunk_id, auth_tag)`
+//! - `node_merkle_root` — binary Poseidon Merkle tree over the leaves in
+//!   chunk ID order, padded with zero leaves to a power of two.
+//!
+//! Byte strings enter the field reduced modulo the BLS12-381 scalar order.
+//!
+//! ## Sampling
+//!
+//! `indices[i] = Poseidon(vrf_beacon, Poseidon(node_secret, i)) mod
+//! total_chunks_stored` for `i` in `0..min(32, total_chunks_stored)`.
+
+use std::collections::HashMap;
+
+use ark_bls12_381::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ochra_crypto::poseidon;
+use ochra_types::governance::CircuitId;
+
+use crate::zk_por::{self, PorProofInput, SerializedProof};
+use crate::{PowError, Result};
+
+/// Minimum number of stored chunks a node must prove (`MIN_CHUNKS`).
+pub const MIN_CHUNKS: u32 = 10;
+
+/// Maximum number of chunks challenged per proof.
+pub const MAX_CHALLENGES: u32 = 32;
+
+/// Data bytes packed into each field element.
+const BYTES_PER_ELEMENT: usize = 31;
+
+fn to_field(bytes: &[u8; 32]) -> Fr {
+    Fr::from_le_bytes_mod_order(bytes)
+}
+
+/// Poseidon commitment to a chunk's data.
+pub fn data_commitment(data: &[u8]) -> [u8; 32] {
+    let mut sponge = poseidon::PoseidonSponge::for_circuit(CircuitId::ZkPor);
+    let mut elements: Vec<Fr> = data
+        .chunks(BYTES_PER_ELEMENT)
+        .map(Fr::from_le_bytes_mod_order)
+        .collect();
+    elements.push(Fr::from(data.len() as u64));
+    // A fresh sponge is always absorbing.
+    let _ = sponge.absorb(&elements);
+    poseidon::field_to_bytes(&sponge.squeeze())
+}
+
+/// Poseidon auth tag binding a chunk ID to its data commitment.
+pub fn auth_tag(chunk_id: &[u8; 32], data_commitment: &[u8; 32]) -> [u8; 32] {
+    let tag = poseidon::hash(to_field(chunk_id), to_field(data_commitment));
+    poseidon::field_to_bytes(&tag)
+}
+
+fn leaf(chunk_id: &[u8; 32], auth_tag: &[u8; 32]) -> Fr {
+    poseidon::hash(to_field(chunk_id), to_field(auth_tag))
+}
+
+/// Reduce a field element modulo `n`.
+fn reduce(f: &Fr, n: u32) -> u32 {
+    let n = u128::from(n);
+    let rem = f
+        .into_bigint()
+        .to_bytes_be()
+        .iter()
+        .fold(0u128, |acc, byte| ((acc << 8) | u128::from(*byte)) % n);
+    // rem < n <= u32::MAX
+    rem as u32
+}
+
+/// Challenged chunk positions for a node in one epoch.
+pub fn challenge_indices(vrf_beacon: &[u8; 32], node_secret: &[u8; 32], total: u32) -> Vec<u32> {
+    if total == 0 {
+        return Vec::new();
+    }
+    let beacon = to_field(vrf_beacon);
+    let secret = to_field(node_secret);
+    (0..total.min(MAX_CHALLENGES))
+        .map(|i| {
+            let prf = poseidon::hash(beacon, poseidon::hash(secret, Fr::from(u64::from(i))));
+            reduce(&prf, total)
+        })
+        .collect()
+}
+
+/// Cache of per-chunk data commitments, so unchallenged chunks are not
+/// rehashed for every proof.
+#[derive(Debug, Default)]
+pub struct ChunkCommitments {
+    commitments: HashMap<[u8; 32], [u8; 32]>,
+}
+
+impl ChunkCommitments {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Commit to a chunk's data.
+    pub fn insert(&mut self, chunk_id: [u8; 32], data: &[u8]) {
+        self.commitments.insert(chunk_id, data_commitment(data));
+    }
+
+    /// Record a commitment computed elsewhere.
+    pub fn insert_commitment(&mut self, chunk_id: [u8; 32], commitment: [u8; 32]) {
+        self.commitments.insert(chunk_id, commitment);
+    }
+
+    /// Commitment for a chunk, if cached.
+    pub fn get(&self, chunk_id: &[u8; 32]) -> Option<&[u8; 32]> {
+        self.commitments.get(chunk_id)
+    }
+
+    /// Drop commitments for chunks no longer stored.
+    pub fn retain(&mut self, mut stored: impl FnMut(&[u8; 32]) -> bool) {
+        self.commitments.retain(|id, _| stored(id));
+    }
+
+    /// Number of cached commitments.
+    pub fn len(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.commitments.is_empty()
+    }
+}
+
+/// Stage of proof generation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PorStage {
+    /// Committing to chunk data not yet cached.
+    Committing,
+    /// Opening challenged chunks against the Merkle tree.
+    Sampling,
+    /// Running the prover.
+    Proving,
+}
+
+impl PorStage {
+    /// Wire name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Committing => "committing",
+            Self::Sampling => "sampling",
+            Self::Proving => "proving",
+        }
+    }
+}
+
+/// Progress report from the witness builder and prover.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PorProgress {
+    pub stage: PorStage,
+    pub done: u32,
+    pub total: u32,
+}
+
+/// One challenged chunk with its opening.
+#[derive(Clone, Debug)]
+pub struct ChallengedChunk {
+    /// Position in the chunk ID order.
+    pub index: u32,
+    pub chunk_id: [u8; 32],
+    pub data_commitment: [u8; 32],
+    pub auth_tag: [u8; 32],
+    /// Sibling hashes from the leaf up.
+    pub merkle_path: Vec<[u8; 32]>,
+}
+
+/// Full witness for one zk-PoR proof.
+#[derive(Clone)]
+pub struct PorWitness {
+    /// Poseidon Merkle root over the node's stored chunks.
+    pub node_merkle_root: [u8; 32],
+    pub challenge_epoch: u32,
+    pub vrf_beacon: [u8; 32],
+    pub total_chunks_stored: u32,
+    pub challenged: Vec<ChallengedChunk>,
+    node_secret: [u8; 32],
+}
+
+impl PorWitness {
+    /// Public inputs in circuit order: `node_merkle_root`,
+    /// `challenge_epoch`, `vrf_beacon`, `min_chunks_threshold`.
+    pub fn public_inputs(&self) -> Vec<Fr> {
+        vec![
+            to_field(&self.node_merkle_root),
+            Fr::from(u64::from(self.challenge_epoch)),
+            to_field(&self.vrf_beacon),
+            Fr::from(u64::from(MIN_CHUNKS)),
+        ]
+    }
+
+    /// Evaluate the circuit's constraints over this witness.
+    pub fn check(&self) -> bool {
+        if self.total_chunks_stored < MIN_CHUNKS {
+            return false;
+        }
+        let indices = challenge_indices(
+            &self.vrf_beacon,
+            &self.node_secret,
+            self.total_chunks_stored,
+        );
+        if indices.len() != self.challenged.len() {
+            return false;
+        }
+        let root = to_field(&self.node_merkle_root);
+        indices.iter().zip(&self.challenged).all(|(&index, chunk)| {
+            if chunk.index != index
+                || auth_tag(&chunk.chunk_id, &chunk.data_commitment) != chunk.auth_tag
+            {
+                return false;
+            }
+            let mut node = leaf(&chunk.chunk_id, &chunk.auth_tag);
+            let mut position = index;
+            for sibling in &chunk.merkle_path {
+                let sibling = to_field(sibling);
+                node = if position & 1 == 0 {
+                    poseidon::hash(node, sibling)
+                } else {
+                    poseidon::hash(sibling, node)
+                };
+                position >>= 1;
+            }
+            position == 0 && node == root
+        })
+    }
+
+    /// Inputs for the v1 stub prover in [`zk_por`]: the challenged leaves
+    /// under the Poseidon root.
+    pub fn proof_input(&self) -> PorProofInput {
+        PorProofInput {
+            chunk_merkle_root: self.node_merkle_root,
+            chunk_indices: self.challenged.iter().map(|c| c.index).collect(),
+            chunk_hashes: self
+                .challenged
+                .iter()
+                .map(|c| poseidon::field_to_bytes(&leaf(&c.chunk_id, &c.auth_tag)))
+                .collect(),
+        }
+    }
+}
+
+/// Collects a node's stored chunks and builds the witness for an epoch.
+pub struct WitnessBuilder {
+    node_secret: [u8; 32],
+    chunks: Vec<([u8; 32], [u8; 32])>,
+}
+
+impl WitnessBuilder {
+    /// Create a builder for the node holding `node_secret`.
+    pub fn new(node_secret: [u8; 32]) -> Self {
+        Self {
+            node_secret,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Add a stored chunk and its data commitment.
+    pub fn add_chunk(&mut self, chunk_id: [u8; 32], data_commitment: [u8; 32]) {
+        self.chunks.push((chunk_id, data_commitment));
+    }
+
+    /// Number of chunks added.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Whether no chunks have been added.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Build the Merkle tree, sample the challenged chunks, and open them.
+    ///
+    /// # Errors
+    ///
+    /// - [`PowError::ProofError`] if fewer than [`MIN_CHUNKS`] distinct
+    ///   chunks were added
+    pub fn build(
+        mut self,
+        challenge_epoch: u32,
+        vrf_beacon: [u8; 32],
+        progress: &mut impl FnMut(PorProgress),
+    ) -> Result<PorWitness> {
+        self.chunks.sort_unstable_by_key(|(id, _)| *id);
+        self.chunks.dedup_by_key(|(id, _)| *id);
+        let total = u32::try_from(self.chunks.len())
+            .map_err(|_| PowError::ProofError("too many chunks".to_string()))?;
+        if total < MIN_CHUNKS {
+            return Err(PowError::ProofError(format!(
+                "{total} chunks stored, at least {MIN_CHUNKS} required"
+            )));
+        }
+
+        let tags: Vec<[u8; 32]> = self
+            .chunks
+            .iter()
+            .map(|(id, commitment)| auth_tag(id, commitment))
+            .collect();
+        let mut level: Vec<Fr> = self
+            .chunks
+            .iter()
+            .zip(&tags)
+            .map(|((id, _), tag)| leaf(id, tag))
+            .collect();
+        level.resize(level.len().next_power_of_two(), Fr::from(0u64));
+        let mut levels = vec![level];
+        while let Some(below) = levels.last().filter(|l| l.len() > 1) {
+            let above = below
+                .chunks_exact(2)
+                .map(|pair| poseidon::hash(pair[0], pair[1]))
+                .collect();
+            levels.push(above);
+        }
+        let root = levels
+            .last()
+            .and_then(|l| l.first())
+            .copied()
+            .unwrap_or_else(|| Fr::from(0u64));
+
+        let indices = challenge_indices(&vrf_beacon, &self.node_secret, total);
+        let count = indices.len() as u32;
+        let mut challenged = Vec::with_capacity(indices.len());
+        for (done, index) in indices.into_iter().enumerate() {
+            let position = index as usize;
+            let (chunk_id, data_commitment) = self.chunks[position];
+            let merkle_path = levels[..levels.len() - 1]
+                .iter()
+                .enumerate()
+                .map(|(height, nodes)| poseidon::field_to_bytes(&nodes[(position >> height) ^ 1]))
+                .collect();
+            challenged.push(ChallengedChunk {
+                index,
+                chunk_id,
+                data_commitment,
+                auth_tag: tags[position],
+                merkle_path,
+            });
+            progress(PorProgress {
+                stage: PorStage::Sampling,
+                done: done as u32 + 1,
+                total: count,
+            });
+        }
+
+        Ok(PorWitness {
+            node_merkle_root: poseidon::field_to_bytes(&root),
+            challenge_epoch,
+            vrf_beacon,
+            total_chunks_stored: total,
+            challenged,
+            node_secret: self.node_secret,
+        })
+    }
+}
+
+/// Generate the proof for a witness.
+///
+/// Blocks for the whole proving time; run it off the async runtime. The
+/// witness is checked against the circuit's constraints first. In v1 the
+/// proof comes from the stub prover in [`zk_por`]; with the Section 31.2
+/// circuit keys it is a Groth16 proof over [`PorWitness::public_inputs`].
+///
+/// # Errors
+///
+/// - [`PowError::ProofError`] if the witness does not satisfy the circuit
+pub fn prove(
+    witness: &PorWitness,
+    progress: &mut impl FnMut(PorProgress),
+) -> Result<SerializedProof> {
+    progress(PorProgress {
+        stage: PorStage::Proving,
+        done: 0,
+        total: 1,
+    });
+    if !witness.check() {
+        return Err(PowError::ProofError(
+            "witness does not satisfy the zk-PoR constraints".to_string(),
+        ));
+    }
+    let proof = zk_por::generate_por_proof(&witness.proof_input())?;
+    progress(PorProgress {
+        stage: PorStage::Proving,
+        done: 1,
+        total: 1,
+    });
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder(chunks: u8) -> WitnessBuilder {
+        let mut builder = WitnessBuilder::new([0x5E; 32]);
+        for i in 0..chunks {
+            let id = [i; 32];
+            builder.add_chunk(id, data_commitment(&[i; 100]));
+        }
+        builder
+    }
+
+    #[test]
+    fn test_witness_satisfies_constraints_and_proves() {
+        let mut reports = Vec::new();
+        let witness = builder(13)
+            .build(7, [0xB3; 32], &mut |p| reports.push(p))
+            .expect("build witness");
+        assert_eq!(witness.total_chunks_stored, 13);
+        assert_eq!(witness.challenged.len(), 13);
+        assert!(witness.check());
+        assert_eq!(witness.public_inputs().len(), 4);
+
+        let proof = prove(&witness, &mut |p| reports.push(p)).expect("prove");
+        let input = witness.proof_input();
+        assert!(zk_por::verify_por_proof(
+            &proof,
+            &zk_por::PorPublicInputs {
+                chunk_merkle_root: input.chunk_merkle_root,
+                chunk_indices: input.chunk_indices,
+                chunk_hashes: input.chunk_hashes,
+            }
+        ));
+        assert_eq!(
+            reports.last(),
+            Some(&PorProgress {
+                stage: PorStage::Proving,
+                done: 1,
+                total: 1
+            })
+        );
+        assert_eq!(
+            reports
+                .iter()
+                .filter(|p| p.stage == PorStage::Sampling)
+                .count(),
+            13
+        );
+    }
+
+    #[test]
+    fn test_tampered_witness_fails_check() {
+        let witness = builder(10)
+            .build(1, [0x01; 32], &mut |_| {})
+            .expect("build witness");
+
+        let mut wrong_data = witness.clone();
+        wrong_data.challenged[0].data_commitment = data_commitment(b"not the chunk");
+        assert!(!wrong_data.check());
+        assert!(prove(&wrong_data, &mut |_| {}).is_err());
+
+        let mut wrong_path = witness.clone();
+        wrong_path.challenged[0].merkle_path[0] = [0x77; 32];
+        assert!(!wrong_path.check());
+
+        let mut wrong_beacon = witness;
+        wrong_beacon.vrf_beacon = [0x02; 32];
+        assert!(!wrong_beacon.check());
+    }
+
+    #[test]
+    fn test_too_few_chunks_rejected() {
+        assert!(builder(9).build(1, [0x01; 32], &mut |_| {}).is_err());
+        // Duplicates count once.
+        let mut dup = builder(9);
+        dup.add_chunk([0u8; 32], data_commitment(&[0u8; 100]));
+        assert!(dup.build(1, [0x01; 32], &mut |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_challenge_indices_in_range_and_beacon_dependent() {
+        let a = challenge_indices(&[1; 32], &[2; 32], 1_000);
+        let b = challenge_indices(&[3; 32], &[2; 32], 1_000);
+        assert_eq!(a.len(), MAX_CHALLENGES as usize);
+        assert!(a.iter().all(|&i| i < 1_000));
+        assert_ne!(a, b);
+        assert_eq!(a, challenge_indices(&[1; 32], &[2; 32], 1_000));
+        assert_eq!(challenge_indices(&[1; 32], &[2; 32], 5).len(), 5);
+    }
+
+    #[test]
+    fn test_data_commitment_binds_length() {
+        assert_ne!(data_commitment(&[0u8; 31]), data_commitment(&[0u8; 32]));
+        let mut cache = ChunkCommitments::new();
+        cache.insert([1; 32], b"chunk");
+        cache.insert([2; 32], b"other");
+        cache.retain(|id| *id == [1; 32]);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&[1; 32]), Some(&data_commitment(b"chunk")));
+    }
+}
@@ -6,7 +6,7 @@
 //! In v1, this is a stub that produces deterministic placeholder proofs.
 //! The full Groth16 circuit will be implemented with the ZK infrastructure.
 
-use ochra_crypto::blake3;
+use ochra_crypto::{blake3, groth16};
 use serde::{Deserialize, Serialize};
 
 use crate::{PowError, Result};
@@ -89,17 +89,51 @@ pub fn generate_por_proof(input: &PorProofInput) -> Result<SerializedProof> {
 ///
 /// `true` if the proof is valid, `false` otherwise.
 pub fn verify_por_proof(proof: &SerializedProof, public_inputs: &PorPublicInputs) -> bool {
+    expected_proof(public_inputs).is_some_and(|expected| proof.bytes == expected.to_vec())
+}
+
+/// Verify many PoR proofs, `batch_size` at a time (0 selects
+/// [`groth16::DEFAULT_BATCH_SIZE`]).
+///
+/// Returns one result per proof, in order. A batch that fails its combined
+/// check is re-verified proof by proof to find the invalid ones. In v1 the
+/// combined check compares a digest of all expected stub proofs; with
+/// Groth16 proofs it becomes [`groth16::verify_batch`].
+pub fn verify_por_proofs(
+    proofs: &[(SerializedProof, PorPublicInputs)],
+    batch_size: usize,
+) -> Vec<bool> {
+    groth16::verify_in_batches(
+        proofs,
+        batch_size,
+        |batch| {
+            let mut expected = Vec::with_capacity(batch.len());
+            for (_, inputs) in batch {
+                match expected_proof(inputs) {
+                    Some(proof) => expected.push(proof),
+                    None => return false,
+                }
+            }
+            let expected: Vec<&[u8]> = expected.iter().map(|p| p.as_slice()).collect();
+            let provided: Vec<&[u8]> = batch.iter().map(|(p, _)| p.bytes.as_slice()).collect();
+            blake3::encode_multi_field(&expected) == blake3::encode_multi_field(&provided)
+        },
+        |(proof, inputs)| verify_por_proof(proof, inputs),
+    )
+}
+
+/// Stub proof expected for the given public inputs, if they are valid.
+fn expected_proof(public_inputs: &PorPublicInputs) -> Option<[u8; 32]> {
     if public_inputs.chunk_indices.len() != public_inputs.chunk_hashes.len() {
-        return false;
+        return None;
     }
     if public_inputs.chunk_indices.is_empty() {
-        return false;
+        return None;
     }
     if public_inputs.chunk_merkle_root == [0u8; 32] {
-        return false;
+        return None;
     }
 
-    // Recompute expected stub proof
     let mut hasher_input = Vec::new();
     hasher_input.extend_from_slice(&public_inputs.chunk_merkle_root);
     for idx in &public_inputs.chunk_indices {
@@ -108,9 +142,7 @@ pub fn verify_por_proof(proof: &SerializedProof, public_inputs: &PorPublicInputs
     for hash in &public_inputs.chunk_hashes {
         hasher_input.extend_from_slice(hash);
     }
-
-    let expected = blake3::hash(&hasher_input);
-    proof.bytes == expected.to_vec()
+    Some(blake3::hash(&hasher_input))
 }
 
 #[cfg(test)]
@@ -200,4 +232,28 @@ mod tests {
         let proof2 = generate_por_proof(&input).expect("proof2");
         assert_eq!(proof1.bytes, proof2.bytes);
     }
+
+    #[test]
+    fn test_batch_verification_finds_invalid_proof() {
+        let mut proofs: Vec<(SerializedProof, PorPublicInputs)> = (1..=4u32)
+            .map(|i| {
+                let input = PorProofInput {
+                    chunk_merkle_root: [0xCC; 32],
+                    chunk_indices: vec![i],
+                    chunk_hashes: vec![[i as u8; 32]],
+                };
+                let proof = generate_por_proof(&input).expect("generate proof");
+                let inputs = PorPublicInputs {
+                    chunk_merkle_root: input.chunk_merkle_root,
+                    chunk_indices: input.chunk_indices,
+                    chunk_hashes: input.chunk_hashes,
+                };
+                (proof, inputs)
+            })
+            .collect();
+        assert_eq!(verify_por_proofs(&proofs, 0), vec![true; 4]);
+
+        proofs[0].0.bytes[0] ^= 0xFF;
+        assert_eq!(verify_por_proofs(&proofs, 3), vec![false, true, true, true]);
+    }
 }
@@ -0,0 +1,112 @@
+//! Real and virtual clocks.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// A source of time.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Monotonic time since the clock was created.
+    fn now(&self) -> Duration;
+
+    /// Wall-clock Unix time in seconds.
+    fn unix_secs(&self) -> u64;
+}
+
+/// The operating system clock.
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    /// Create a clock starting now.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn unix_secs(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// Clones share the same time, so a test can keep one handle and advance
+/// the clock seen by every component it was handed to.
+#[derive(Clone, Debug)]
+pub struct VirtualClock {
+    /// Milliseconds since the clock was created.
+    elapsed_ms: Arc<AtomicU64>,
+    /// Unix time at creation.
+    start_unix_secs: u64,
+}
+
+impl VirtualClock {
+    /// Create a clock at Unix time `start_unix_secs`.
+    pub fn new(start_unix_secs: u64) -> Self {
+        Self {
+            elapsed_ms: Arc::new(AtomicU64::new(0)),
+            start_unix_secs,
+        }
+    }
+
+    /// Move the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let ms = u64::try_from(by.as_millis()).unwrap_or(u64::MAX);
+        self.elapsed_ms.fetch_add(ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        Duration::from_millis(self.elapsed_ms.load(Ordering::SeqCst))
+    }
+
+    fn unix_secs(&self) -> u64 {
+        self.start_unix_secs + self.now().as_secs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_clock_advances_all_handles() {
+        let clock = VirtualClock::new(1_000);
+        let shared = clock.clone();
+        assert_eq!(shared.now(), Duration::ZERO);
+
+        clock.advance(Duration::from_millis(2_500));
+        assert_eq!(shared.now(), Duration::from_millis(2_500));
+        assert_eq!(shared.unix_secs(), 1_002);
+    }
+
+    #[test]
+    fn test_system_clock_is_monotonic() {
+        let clock = SystemClock::new();
+        let a = clock.now();
+        let b = clock.now();
+        assert!(b >= a);
+        assert!(clock.unix_secs() > 1_600_000_000);
+    }
+}
@@ -0,0 +1,215 @@
+//! Wallet token inventory audit.
+//!
+//! The wallet balance is the sum of the held tokens not marked spent. That
+//! flag is local state: a spend made from another device holding the same
+//! wallet, or a crash between broadcasting a spend and recording it, leaves
+//! it out of step with the network. [`audit_tokens`] checks every held token
+//! against the network nullifier set (the local Bloom filter replica,
+//! Section 10.4) and reports where the two disagree.
+//!
+//! ## Discrepancies
+//!
+//! - [`Discrepancy::SpentOnNetwork`]: the token is unspent locally but its
+//!   nullifier is in the set. The Bloom filter has false positives, so this
+//!   is only a suspicion: [`WalletAudit::to_retire`] marks the token spent
+//!   only once the caller has confirmed the spend against an authoritative
+//!   source.
+//! - [`Discrepancy::SpendNotPublished`]: the token is spent locally but its
+//!   nullifier is absent. The Bloom filter has no false negatives, so the
+//!   spend never reached the network and should be gossiped again.
+//! - [`Discrepancy::MalformedNullifier`]: the stored nullifier is not 32
+//!   bytes. The token cannot be spent and is left for manual inspection.
+
+use serde::{Deserialize, Serialize};
+
+/// A token held by the wallet, as stored locally.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeldToken {
+    /// Local token identifier.
+    pub token_id: Vec<u8>,
+    /// Value in micro-seeds.
+    pub amount: u64,
+    /// Stored nullifier bytes.
+    pub nullifier: Vec<u8>,
+    /// Whether the wallet has marked the token spent.
+    pub spent: bool,
+}
+
+/// A disagreement between the wallet and the nullifier set.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Discrepancy {
+    /// Unspent locally, but the nullifier has been published.
+    SpentOnNetwork {
+        /// Local token identifier.
+        token_id: Vec<u8>,
+        /// Value in micro-seeds.
+        amount: u64,
+        /// The nullifier found in the set.
+        nullifier: [u8; 32],
+    },
+    /// Spent locally, but the nullifier has not been published.
+    SpendNotPublished {
+        /// Local token identifier.
+        token_id: Vec<u8>,
+        /// The nullifier to publish.
+        nullifier: [u8; 32],
+    },
+    /// The stored nullifier is not 32 bytes.
+    MalformedNullifier {
+        /// Local token identifier.
+        token_id: Vec<u8>,
+    },
+}
+
+/// Result of auditing the wallet's token inventory.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletAudit {
+    /// Number of tokens examined.
+    pub tokens_checked: usize,
+    /// Balance implied by the local spent flags, in micro-seeds.
+    pub recorded_balance: u64,
+    /// Balance once tokens spent on the network are excluded.
+    pub reconciled_balance: u64,
+    /// Every disagreement found.
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl WalletAudit {
+    /// Whether the wallet agrees with the nullifier set.
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    /// Tokens to mark spent locally: those found spent on the network whose
+    /// nullifier `confirmed` establishes as spent. A Bloom hit alone is
+    /// never enough.
+    pub fn to_retire<'a, F>(&'a self, confirmed: F) -> impl Iterator<Item = &'a [u8]>
+    where
+        F: Fn(&[u8; 32]) -> bool + 'a,
+    {
+        self.discrepancies.iter().filter_map(move |d| match d {
+            Discrepancy::SpentOnNetwork {
+                token_id,
+                nullifier,
+                ..
+            } if confirmed(nullifier) => Some(token_id.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Nullifiers of local spends that never reached the network.
+    pub fn to_republish(&self) -> impl Iterator<Item = &[u8; 32]> {
+        self.discrepancies.iter().filter_map(|d| match d {
+            Discrepancy::SpendNotPublished { nullifier, .. } => Some(nullifier),
+            _ => None,
+        })
+    }
+}
+
+/// Audit `tokens` against the nullifier set queried by `published`.
+pub fn audit_tokens<F>(tokens: &[HeldToken], published: F) -> WalletAudit
+where
+    F: Fn(&[u8; 32]) -> bool,
+{
+    let mut audit = WalletAudit {
+        tokens_checked: tokens.len(),
+        ..WalletAudit::default()
+    };
+    for token in tokens {
+        if !token.spent {
+            audit.recorded_balance = audit.recorded_balance.saturating_add(token.amount);
+        }
+        let Ok(nullifier) = <[u8; 32]>::try_from(token.nullifier.as_slice()) else {
+            audit.discrepancies.push(Discrepancy::MalformedNullifier {
+                token_id: token.token_id.clone(),
+            });
+            if !token.spent {
+                audit.reconciled_balance = audit.reconciled_balance.saturating_add(token.amount);
+            }
+            continue;
+        };
+        match (token.spent, published(&nullifier)) {
+            (false, true) => audit.discrepancies.push(Discrepancy::SpentOnNetwork {
+                token_id: token.token_id.clone(),
+                amount: token.amount,
+                nullifier,
+            }),
+            (false, false) => {
+                audit.reconciled_balance = audit.reconciled_balance.saturating_add(token.amount);
+            }
+            (true, false) => audit.discrepancies.push(Discrepancy::SpendNotPublished {
+                token_id: token.token_id.clone(),
+                nullifier,
+            }),
+            (true, true) => {}
+        }
+    }
+    audit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(id: u8, amount: u64, spent: bool) -> HeldToken {
+        HeldToken {
+            token_id: vec![id; 16],
+            amount,
+            nullifier: vec![id; 32],
+            spent,
+        }
+    }
+
+    #[test]
+    fn test_clean_wallet() {
+        let tokens = [token(1, 1000, false), token(2, 500, true)];
+        let audit = audit_tokens(&tokens, |n| n[0] == 2);
+        assert!(audit.is_clean());
+        assert_eq!(audit.tokens_checked, 2);
+        assert_eq!(audit.recorded_balance, 1000);
+        assert_eq!(audit.reconciled_balance, 1000);
+    }
+
+    #[test]
+    fn test_spent_on_network_is_retired() {
+        let tokens = [token(1, 1000, false), token(2, 700, false)];
+        let audit = audit_tokens(&tokens, |n| n[0] == 2);
+        assert_eq!(audit.recorded_balance, 1700);
+        assert_eq!(audit.reconciled_balance, 1000);
+        let retire: Vec<&[u8]> = audit.to_retire(|n| n[0] == 2).collect();
+        assert_eq!(retire, vec![[2u8; 16].as_slice()]);
+    }
+
+    #[test]
+    fn test_unconfirmed_bloom_hit_is_kept() {
+        let tokens = [token(2, 700, false)];
+        let audit = audit_tokens(&tokens, |_| true);
+        assert!(!audit.is_clean());
+        assert_eq!(audit.to_retire(|_| false).count(), 0);
+    }
+
+    #[test]
+    fn test_unpublished_spend_is_flagged() {
+        let tokens = [token(3, 400, true)];
+        let audit = audit_tokens(&tokens, |_| false);
+        assert_eq!(audit.reconciled_balance, 0);
+        let republish: Vec<&[u8; 32]> = audit.to_republish().collect();
+        assert_eq!(republish, vec![&[3u8; 32]]);
+        assert_eq!(audit.to_retire(|_| true).count(), 0);
+    }
+
+    #[test]
+    fn test_malformed_nullifier_keeps_balance() {
+        let mut bad = token(4, 250, false);
+        bad.nullifier.truncate(31);
+        let audit = audit_tokens(&[bad], |_| true);
+        assert_eq!(
+            audit.discrepancies,
+            vec![Discrepancy::MalformedNullifier {
+                token_id: vec![4; 16]
+            }]
+        );
+        assert_eq!(audit.reconciled_balance, 250);
+    }
+}
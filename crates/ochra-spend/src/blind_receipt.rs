@@ -3,8 +3,16 @@
 //! Blind receipts are privacy-preserving proofs of purchase. The receipt
 //! contains a blinded content hash so that the receipt issuer cannot link
 //! the receipt to the specific content purchased.
+//!
+//! Besides single-item receipts there are two multi-purpose receipt types:
+//!
+//! - [`BundleReceipt`] — one purchase covering several content items. Each
+//!   item hash is blinded separately so access to any one item can be shown.
+//! - [`SubscriptionReceipt`] — access for a range of epochs. Each renewal
+//!   issues a new receipt chained to the previous one by `previous_receipt_id`.
 
 use ochra_crypto::blake3;
+use ochra_types::MAX_BUNDLE_ITEMS;
 use serde::{Deserialize, Serialize};
 
 use crate::{Result, SpendError};
@@ -90,6 +98,219 @@ pub fn verify_receipt(receipt: &BlindReceipt) -> bool {
     true
 }
 
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn blind(content_hash: &[u8; 32]) -> [u8; 32] {
+    let mut blind_factor = [0u8; 32];
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut blind_factor);
+    let mut blind_input = [0u8; 64];
+    blind_input[..32].copy_from_slice(content_hash);
+    blind_input[32..].copy_from_slice(&blind_factor);
+    blake3::hash(&blind_input)
+}
+
+/// A blind receipt covering every item of a bundle purchase.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BundleReceipt {
+    /// Unique receipt identifier.
+    pub receipt_id: [u8; 32],
+    /// Blinded hash of each bundled content item, in bundle order.
+    pub blinded_content_hashes: Vec<[u8; 32]>,
+    /// The amount paid for the whole bundle in micro-seeds.
+    pub amount: u64,
+    /// Unix timestamp of issuance.
+    pub issued_at: u64,
+}
+
+/// Generate a receipt for a bundle purchase.
+///
+/// # Errors
+///
+/// - [`SpendError::InvalidReceipt`] if fewer than two or more than
+///   [`MAX_BUNDLE_ITEMS`] items are given, any item is zero or repeated,
+///   or the amount is zero
+pub fn generate_bundle_receipt(content_hashes: &[[u8; 32]], amount: u64) -> Result<BundleReceipt> {
+    if content_hashes.len() < 2 || content_hashes.len() > MAX_BUNDLE_ITEMS {
+        return Err(SpendError::InvalidReceipt(format!(
+            "bundle must hold 2-{MAX_BUNDLE_ITEMS} items"
+        )));
+    }
+    for (i, hash) in content_hashes.iter().enumerate() {
+        if hash == &[0u8; 32] || content_hashes[..i].contains(hash) {
+            return Err(SpendError::InvalidReceipt(
+                "bundle items must be distinct and non-zero".to_string(),
+            ));
+        }
+    }
+    if amount == 0 {
+        return Err(SpendError::InvalidReceipt(
+            "amount must be non-zero".to_string(),
+        ));
+    }
+
+    let blinded_content_hashes: Vec<[u8; 32]> = content_hashes.iter().map(blind).collect();
+    let amount_bytes = amount.to_le_bytes();
+    let blinded: Vec<u8> = blinded_content_hashes.concat();
+    let fields = blake3::encode_multi_field(&[b"bundle", &blinded, &amount_bytes]);
+    let receipt_id = blake3::hash(&fields);
+
+    Ok(BundleReceipt {
+        receipt_id,
+        blinded_content_hashes,
+        amount,
+        issued_at: now_secs(),
+    })
+}
+
+/// Verify the basic well-formedness of a bundle receipt.
+pub fn verify_bundle_receipt(receipt: &BundleReceipt) -> bool {
+    receipt.receipt_id != [0u8; 32]
+        && receipt.amount > 0
+        && (2..=MAX_BUNDLE_ITEMS).contains(&receipt.blinded_content_hashes.len())
+        && receipt
+            .blinded_content_hashes
+            .iter()
+            .all(|h| h != &[0u8; 32])
+}
+
+/// A blind receipt for one period of a subscription.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SubscriptionReceipt {
+    /// Unique receipt identifier.
+    pub receipt_id: [u8; 32],
+    /// BLAKE3 hash of the blinded content hash (stable across renewals).
+    pub blinded_content_hash: [u8; 32],
+    /// First epoch covered.
+    pub valid_from_epoch: u64,
+    /// First epoch no longer covered.
+    pub valid_until_epoch: u64,
+    /// Receipt this one renews (`None` for the first period).
+    pub previous_receipt_id: Option<[u8; 32]>,
+    /// The amount paid for this period in micro-seeds.
+    pub amount: u64,
+    /// Unix timestamp of issuance.
+    pub issued_at: u64,
+}
+
+impl SubscriptionReceipt {
+    /// Whether the receipt grants access during `epoch`.
+    pub fn is_active(&self, epoch: u64) -> bool {
+        (self.valid_from_epoch..self.valid_until_epoch).contains(&epoch)
+    }
+
+    /// Number of epochs in this receipt's period.
+    pub fn period_epochs(&self) -> u64 {
+        self.valid_until_epoch.saturating_sub(self.valid_from_epoch)
+    }
+}
+
+fn subscription_receipt(
+    blinded_content_hash: [u8; 32],
+    amount: u64,
+    valid_from_epoch: u64,
+    period_epochs: u16,
+    previous_receipt_id: Option<[u8; 32]>,
+) -> Result<SubscriptionReceipt> {
+    if amount == 0 {
+        return Err(SpendError::InvalidReceipt(
+            "amount must be non-zero".to_string(),
+        ));
+    }
+    if period_epochs == 0 {
+        return Err(SpendError::InvalidReceipt(
+            "subscription period must be non-zero".to_string(),
+        ));
+    }
+    let valid_until_epoch = valid_from_epoch.saturating_add(u64::from(period_epochs));
+    let previous = previous_receipt_id.unwrap_or([0u8; 32]);
+    let fields = blake3::encode_multi_field(&[
+        b"subscription",
+        &blinded_content_hash,
+        &valid_from_epoch.to_le_bytes(),
+        &valid_until_epoch.to_le_bytes(),
+        &previous,
+        &amount.to_le_bytes(),
+    ]);
+    Ok(SubscriptionReceipt {
+        receipt_id: blake3::hash(&fields),
+        blinded_content_hash,
+        valid_from_epoch,
+        valid_until_epoch,
+        previous_receipt_id,
+        amount,
+        issued_at: now_secs(),
+    })
+}
+
+/// Generate the first receipt of a subscription starting at `start_epoch`.
+///
+/// # Errors
+///
+/// - [`SpendError::InvalidReceipt`] if the content hash is all zeros, or the
+///   amount or period is zero
+pub fn generate_subscription_receipt(
+    content_hash: &[u8; 32],
+    amount: u64,
+    period_epochs: u16,
+    start_epoch: u64,
+) -> Result<SubscriptionReceipt> {
+    if content_hash == &[0u8; 32] {
+        return Err(SpendError::InvalidReceipt(
+            "content hash must be non-zero".to_string(),
+        ));
+    }
+    subscription_receipt(
+        blind(content_hash),
+        amount,
+        start_epoch,
+        period_epochs,
+        None,
+    )
+}
+
+/// Issue the renewal receipt following `previous`.
+///
+/// Renewing before expiry extends from the end of the current period;
+/// renewing after a lapse starts at `current_epoch`.
+///
+/// # Errors
+///
+/// - [`SpendError::InvalidReceipt`] if the amount is zero or `previous` is
+///   malformed
+pub fn renew_subscription(
+    previous: &SubscriptionReceipt,
+    amount: u64,
+    current_epoch: u64,
+) -> Result<SubscriptionReceipt> {
+    if !verify_subscription_receipt(previous) {
+        return Err(SpendError::InvalidReceipt(
+            "previous subscription receipt is malformed".to_string(),
+        ));
+    }
+    let period = u16::try_from(previous.period_epochs())
+        .map_err(|_| SpendError::InvalidReceipt("subscription period out of range".to_string()))?;
+    subscription_receipt(
+        previous.blinded_content_hash,
+        amount,
+        previous.valid_until_epoch.max(current_epoch),
+        period,
+        Some(previous.receipt_id),
+    )
+}
+
+/// Verify the basic well-formedness of a subscription receipt.
+pub fn verify_subscription_receipt(receipt: &SubscriptionReceipt) -> bool {
+    receipt.receipt_id != [0u8; 32]
+        && receipt.blinded_content_hash != [0u8; 32]
+        && receipt.amount > 0
+        && receipt.valid_until_epoch > receipt.valid_from_epoch
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,4 +373,47 @@ mod tests {
         };
         assert!(!verify_receipt(&receipt));
     }
+
+    #[test]
+    fn test_bundle_receipt() {
+        let items = [[0xAA; 32], [0xBB; 32], [0xCC; 32]];
+        let receipt = generate_bundle_receipt(&items, 5000).expect("bundle");
+        assert_eq!(receipt.blinded_content_hashes.len(), 3);
+        assert!(verify_bundle_receipt(&receipt));
+
+        assert!(generate_bundle_receipt(&items[..1], 5000).is_err());
+        assert!(generate_bundle_receipt(&[[0xAA; 32], [0xAA; 32]], 5000).is_err());
+        assert!(generate_bundle_receipt(&items, 0).is_err());
+    }
+
+    #[test]
+    fn test_subscription_receipt_period() {
+        let receipt = generate_subscription_receipt(&[0xAA; 32], 1000, 30, 100).expect("sub");
+        assert!(verify_subscription_receipt(&receipt));
+        assert!(receipt.is_active(100));
+        assert!(receipt.is_active(129));
+        assert!(!receipt.is_active(130));
+        assert!(!receipt.is_active(99));
+
+        assert!(generate_subscription_receipt(&[0xAA; 32], 1000, 0, 100).is_err());
+        assert!(generate_subscription_receipt(&[0u8; 32], 1000, 30, 100).is_err());
+    }
+
+    #[test]
+    fn test_subscription_renewal_chain() {
+        let first = generate_subscription_receipt(&[0xAA; 32], 1000, 30, 100).expect("sub");
+
+        // Early renewal extends from the end of the current period.
+        let second = renew_subscription(&first, 1000, 120).expect("renew");
+        assert_eq!(second.valid_from_epoch, 130);
+        assert_eq!(second.valid_until_epoch, 160);
+        assert_eq!(second.previous_receipt_id, Some(first.receipt_id));
+        assert_eq!(second.blinded_content_hash, first.blinded_content_hash);
+        assert_ne!(second.receipt_id, first.receipt_id);
+
+        // A lapsed subscription restarts at the current epoch.
+        let third = renew_subscription(&second, 1000, 200).expect("renew");
+        assert_eq!(third.valid_from_epoch, 200);
+        assert!(!third.is_active(170));
+    }
 }
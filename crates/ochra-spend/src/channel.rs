@@ -0,0 +1,682 @@
+//! Unidirectional payment channels for repeated micro-purchases.
+//!
+//! A buyer who expects to make many small purchases from one seller can
+//! lock `capacity` micro-seeds in a channel once, then pay for each item
+//! with a [`BalanceUpdate`]: a signature over the running total paid so
+//! far. The seller checks the signature locally and delivers, so a
+//! purchase costs one Ed25519 verification instead of a full spend.
+//!
+//! ## Opening
+//!
+//! The buyer signs a [`ChannelOpen`] with [`open_channel`] and spends one
+//! token into its escrow: the quorum checks the redeemed token and its
+//! nullifier with [`check_escrow`], then signs
+//! [`ChannelOpen::escrow_message`]. The seller accepts the channel only
+//! after [`verify_open`] checks both signatures.
+//!
+//! ## Closing
+//!
+//! Either party may close with the latest state:
+//!
+//! - The seller closes with [`close_by_seller`], which settles at once. The
+//!   seller only holds buyer-signed updates, and submitting an older one
+//!   can only cost the seller money.
+//! - The buyer closes with [`close_by_buyer`], which opens a
+//!   [`CHANNEL_DISPUTE_WINDOW`] during which the seller may
+//!   [`challenge`](PendingClose::challenge) with a newer update. After the
+//!   window, [`PendingClose::settle`] pays the highest total seen.
+//!
+//! A buyer whose channel expired without any update closes the same way
+//! with no update and is refunded the whole capacity.
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use ochra_crypto::voprf::VoprfServerKey;
+use ochra_mint::voprf_mint::{MintServer, UnblindedToken};
+use ochra_nullifier::bloom::NullifierSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, SpendError};
+
+/// Seconds a buyer-initiated close waits for a newer update.
+pub const CHANNEL_DISPUTE_WINDOW: u64 = 3600;
+
+/// Default channel lifetime in seconds (7 days).
+pub const DEFAULT_CHANNEL_DURATION: u64 = 7 * 86_400;
+
+/// A funded channel from a buyer to a seller.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelOpen {
+    /// Channel identifier, see [`derive_channel_id`].
+    pub channel_id: [u8; 32],
+    /// Buyer's channel signing key.
+    pub buyer_pk: [u8; 32],
+    /// Seller's public key.
+    pub seller_pk: [u8; 32],
+    /// Micro-seeds locked in escrow.
+    pub capacity: u64,
+    /// Nullifier of the tokens locked in escrow.
+    pub nullifier: [u8; 32],
+    /// Unix timestamp the channel was opened.
+    pub opened_at: u64,
+    /// Unix timestamp after which no new updates are accepted.
+    pub expires_at: u64,
+    /// Buyer's signature over [`ChannelOpen::signing_message`] (64 bytes).
+    pub buyer_sig: Vec<u8>,
+    /// Quorum signature over [`ChannelOpen::escrow_message`], attesting that
+    /// the token behind `nullifier` was spent into escrow (64 bytes).
+    pub escrow_sig: Vec<u8>,
+}
+
+impl ChannelOpen {
+    /// Bytes covered by the buyer's signature.
+    pub fn signing_message(&self) -> Vec<u8> {
+        self.message(b"channel-open")
+    }
+
+    /// Bytes covered by the quorum's escrow signature.
+    pub fn escrow_message(&self) -> Vec<u8> {
+        self.message(b"channel-escrow")
+    }
+
+    fn message(&self, tag: &[u8]) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(tag.len() + 32 * 4 + 8 * 3);
+        msg.extend_from_slice(tag);
+        msg.extend_from_slice(&self.channel_id);
+        msg.extend_from_slice(&self.buyer_pk);
+        msg.extend_from_slice(&self.seller_pk);
+        msg.extend_from_slice(&self.capacity.to_le_bytes());
+        msg.extend_from_slice(&self.nullifier);
+        msg.extend_from_slice(&self.opened_at.to_le_bytes());
+        msg.extend_from_slice(&self.expires_at.to_le_bytes());
+        msg
+    }
+}
+
+/// A buyer-signed running total.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceUpdate {
+    /// Channel the update belongs to.
+    pub channel_id: [u8; 32],
+    /// Strictly increasing per channel, starting at 1.
+    pub sequence: u64,
+    /// Total micro-seeds paid to the seller so far.
+    pub paid_total: u64,
+    /// Item this increment pays for (content hash or tier receipt ID).
+    pub purchase: [u8; 32],
+    /// Signature by the buyer's channel key (64 bytes).
+    pub sig: Vec<u8>,
+}
+
+impl BalanceUpdate {
+    /// Bytes covered by the signature.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(14 + 32 + 8 + 8 + 32);
+        msg.extend_from_slice(b"channel-update");
+        msg.extend_from_slice(&self.channel_id);
+        msg.extend_from_slice(&self.sequence.to_le_bytes());
+        msg.extend_from_slice(&self.paid_total.to_le_bytes());
+        msg.extend_from_slice(&self.purchase);
+        msg
+    }
+}
+
+/// Final division of a channel's capacity.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelSettlement {
+    /// The channel settled.
+    pub channel_id: [u8; 32],
+    /// Micro-seeds released to the seller.
+    pub paid_to_seller: u64,
+    /// Micro-seeds returned to the buyer.
+    pub refunded_to_buyer: u64,
+    /// Sequence of the update settled on, or 0 if none.
+    pub final_sequence: u64,
+}
+
+/// Derive a channel ID from the escrow nullifier, seller, and capacity.
+pub fn derive_channel_id(nullifier: &[u8; 32], seller_pk: &[u8; 32], capacity: u64) -> [u8; 32] {
+    let capacity_bytes = capacity.to_le_bytes();
+    let fields = blake3::encode_multi_field(&[
+        b"payment-channel",
+        nullifier.as_slice(),
+        seller_pk.as_slice(),
+        &capacity_bytes,
+    ]);
+    blake3::hash(&fields)
+}
+
+/// Open a channel to `seller_pk` funded by `token`, signed by the buyer's
+/// channel key and lasting `duration` seconds.
+///
+/// The channel's capacity is the token's denomination. It is not usable
+/// until the quorum has checked the token with [`check_escrow`] and its
+/// signature is set as `escrow_sig`.
+///
+/// # Errors
+///
+/// - [`SpendError::EscrowError`] if the duration is zero
+pub fn open_channel(
+    buyer_key: &SigningKey,
+    seller_pk: [u8; 32],
+    token: &UnblindedToken,
+    now: u64,
+    duration: u64,
+) -> Result<ChannelOpen> {
+    if duration == 0 {
+        return Err(SpendError::EscrowError(
+            "channel duration must be non-zero".to_string(),
+        ));
+    }
+    let nullifier = token.nullifier();
+    let mut open = ChannelOpen {
+        channel_id: derive_channel_id(&nullifier, &seller_pk, token.denomination),
+        buyer_pk: buyer_key.verifying_key().to_bytes(),
+        seller_pk,
+        capacity: token.denomination,
+        nullifier,
+        opened_at: now,
+        expires_at: now.saturating_add(duration),
+        buyer_sig: Vec::new(),
+        escrow_sig: Vec::new(),
+    };
+    open.buyer_sig = buyer_key.sign(&open.signing_message()).to_bytes().to_vec();
+    Ok(open)
+}
+
+/// Quorum check of a channel's escrow spend, before it signs
+/// [`ChannelOpen::escrow_message`].
+///
+/// `token` is the redeemed token funding the channel and `mint_key` the
+/// VOPRF key of its key epoch.
+///
+/// # Errors
+///
+/// - [`SpendError::InvalidProof`] if the buyer's signature fails, the token
+///   was not minted under `mint_key`, or it does not match the channel
+/// - [`SpendError::AlreadySpent`] if the token's nullifier is in `spent`
+pub fn check_escrow(
+    open: &ChannelOpen,
+    token: &UnblindedToken,
+    mint_key: &VoprfServerKey,
+    spent: &NullifierSet,
+) -> Result<()> {
+    verify_signature(&open.buyer_pk, &open.signing_message(), &open.buyer_sig)?;
+    if token.nullifier() != open.nullifier
+        || token.denomination != open.capacity
+        || derive_channel_id(&open.nullifier, &open.seller_pk, open.capacity) != open.channel_id
+    {
+        return Err(SpendError::InvalidProof(
+            "token does not fund this channel".to_string(),
+        ));
+    }
+    if !MintServer::verify_token(token, mint_key) {
+        return Err(SpendError::InvalidProof(
+            "escrow token was not minted".to_string(),
+        ));
+    }
+    if spent.contains(&open.nullifier) {
+        return Err(SpendError::AlreadySpent);
+    }
+    Ok(())
+}
+
+/// Seller check of a channel before accepting updates on it: the channel
+/// ID, the buyer's signature, and the quorum's escrow signature under
+/// `quorum_pk`.
+///
+/// # Errors
+///
+/// - [`SpendError::InvalidProof`] if any of them fails
+pub fn verify_open(open: &ChannelOpen, quorum_pk: &[u8; 32]) -> Result<()> {
+    if derive_channel_id(&open.nullifier, &open.seller_pk, open.capacity) != open.channel_id {
+        return Err(SpendError::InvalidProof(
+            "channel ID does not match its escrow".to_string(),
+        ));
+    }
+    verify_signature(&open.buyer_pk, &open.signing_message(), &open.buyer_sig)?;
+    verify_signature(quorum_pk, &open.escrow_message(), &open.escrow_sig)
+}
+
+/// Verify a 64-byte Ed25519 signature by `pk` over `msg`.
+fn verify_signature(pk: &[u8; 32], msg: &[u8], sig: &[u8]) -> Result<()> {
+    let sig: [u8; 64] = sig
+        .try_into()
+        .map_err(|_| SpendError::InvalidProof("signature must be 64 bytes".to_string()))?;
+    let vk = VerifyingKey::from_bytes(pk).map_err(|e| SpendError::InvalidProof(e.to_string()))?;
+    vk.verify(msg, &Signature::from_bytes(&sig))
+        .map_err(|_| SpendError::InvalidProof("channel signature invalid".to_string()))
+}
+
+/// Check that `update` is well-formed and signed by the channel's buyer.
+///
+/// # Errors
+///
+/// - [`SpendError::EscrowError`] if it names another channel or has
+///   sequence 0
+/// - [`SpendError::InsufficientBalance`] if it pays more than the capacity
+/// - [`SpendError::InvalidProof`] if the signature does not verify
+pub fn verify_update(open: &ChannelOpen, update: &BalanceUpdate) -> Result<()> {
+    if update.channel_id != open.channel_id {
+        return Err(SpendError::EscrowError(
+            "update is for another channel".to_string(),
+        ));
+    }
+    if update.sequence == 0 {
+        return Err(SpendError::EscrowError(
+            "update sequence must start at 1".to_string(),
+        ));
+    }
+    if update.paid_total > open.capacity {
+        return Err(SpendError::InsufficientBalance {
+            available: open.capacity,
+            required: update.paid_total,
+        });
+    }
+    verify_signature(&open.buyer_pk, &update.signing_message(), &update.sig)
+}
+
+/// Buyer side of a channel.
+#[derive(Debug)]
+pub struct ChannelPayer {
+    open: ChannelOpen,
+    key: SigningKey,
+    sequence: u64,
+    paid_total: u64,
+}
+
+impl ChannelPayer {
+    /// Start paying through `open` with the buyer's channel key.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::EscrowError`] if `key` is not the channel's buyer key
+    pub fn new(open: ChannelOpen, key: SigningKey) -> Result<Self> {
+        if key.verifying_key().to_bytes() != open.buyer_pk {
+            return Err(SpendError::EscrowError(
+                "signing key does not match channel buyer".to_string(),
+            ));
+        }
+        Ok(Self {
+            open,
+            key,
+            sequence: 0,
+            paid_total: 0,
+        })
+    }
+
+    /// Sign an update paying `amount` more for `purchase`.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::EscrowTimeout`] if the channel has expired
+    /// - [`SpendError::InsufficientBalance`] if the capacity would be exceeded
+    pub fn pay(&mut self, amount: u64, purchase: [u8; 32], now: u64) -> Result<BalanceUpdate> {
+        if now >= self.open.expires_at {
+            return Err(SpendError::EscrowTimeout {
+                expired_at: self.open.expires_at,
+            });
+        }
+        let paid_total = self
+            .paid_total
+            .checked_add(amount)
+            .filter(|total| *total <= self.open.capacity)
+            .ok_or(SpendError::InsufficientBalance {
+                available: self.remaining(),
+                required: amount,
+            })?;
+
+        let mut update = BalanceUpdate {
+            channel_id: self.open.channel_id,
+            sequence: self.sequence + 1,
+            paid_total,
+            purchase,
+            sig: Vec::new(),
+        };
+        update.sig = self.key.sign(&update.signing_message()).to_bytes().to_vec();
+        self.sequence = update.sequence;
+        self.paid_total = paid_total;
+        Ok(update)
+    }
+
+    /// Micro-seeds still available in the channel.
+    pub fn remaining(&self) -> u64 {
+        self.open.capacity - self.paid_total
+    }
+
+    /// The channel being paid through.
+    pub fn channel(&self) -> &ChannelOpen {
+        &self.open
+    }
+}
+
+/// Seller side of a channel.
+#[derive(Debug)]
+pub struct ChannelPayee {
+    open: ChannelOpen,
+    latest: Option<BalanceUpdate>,
+}
+
+impl ChannelPayee {
+    /// Start accepting payments on `open`, once [`verify_open`] passes
+    /// under `quorum_pk`.
+    ///
+    /// # Errors
+    ///
+    /// - any error from [`verify_open`]
+    pub fn new(open: ChannelOpen, quorum_pk: &[u8; 32]) -> Result<Self> {
+        verify_open(&open, quorum_pk)?;
+        Ok(Self { open, latest: None })
+    }
+
+    /// Verify and keep an update, returning the amount it adds.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::EscrowTimeout`] if the channel has expired
+    /// - [`SpendError::EscrowError`] if the update does not advance the
+    ///   sequence and total
+    /// - any error from [`verify_update`]
+    pub fn accept(&mut self, update: BalanceUpdate, now: u64) -> Result<u64> {
+        if now >= self.open.expires_at {
+            return Err(SpendError::EscrowTimeout {
+                expired_at: self.open.expires_at,
+            });
+        }
+        verify_update(&self.open, &update)?;
+        let (sequence, paid) = self
+            .latest
+            .as_ref()
+            .map_or((0, 0), |u| (u.sequence, u.paid_total));
+        if update.sequence <= sequence || update.paid_total <= paid {
+            return Err(SpendError::EscrowError(format!(
+                "update {} does not advance channel past {sequence}",
+                update.sequence
+            )));
+        }
+        let increment = update.paid_total - paid;
+        self.latest = Some(update);
+        Ok(increment)
+    }
+
+    /// Total paid so far.
+    pub fn paid_total(&self) -> u64 {
+        self.latest.as_ref().map_or(0, |u| u.paid_total)
+    }
+
+    /// The newest update accepted.
+    pub fn latest(&self) -> Option<&BalanceUpdate> {
+        self.latest.as_ref()
+    }
+
+    /// The channel being paid through.
+    pub fn channel(&self) -> &ChannelOpen {
+        &self.open
+    }
+}
+
+/// Close a channel as the seller with the latest update, settling at once.
+///
+/// # Errors
+///
+/// - any error from [`verify_update`]
+pub fn close_by_seller(open: &ChannelOpen, latest: &BalanceUpdate) -> Result<ChannelSettlement> {
+    verify_update(open, latest)?;
+    Ok(settlement(open, Some(latest)))
+}
+
+/// A buyer-initiated close waiting out the dispute window.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingClose {
+    /// Channel being closed.
+    pub channel_id: [u8; 32],
+    /// Newest update submitted so far.
+    pub update: Option<BalanceUpdate>,
+    /// Unix timestamp the close becomes final.
+    pub closes_at: u64,
+}
+
+/// Start closing a channel as the buyer.
+///
+/// Without an update the buyer must wait until the channel has expired,
+/// since the seller may hold updates the buyer no longer has.
+///
+/// # Errors
+///
+/// - [`SpendError::EscrowError`] if no update is given before expiry
+/// - any error from [`verify_update`]
+pub fn close_by_buyer(
+    open: &ChannelOpen,
+    update: Option<BalanceUpdate>,
+    now: u64,
+) -> Result<PendingClose> {
+    match &update {
+        Some(update) => verify_update(open, update)?,
+        None if now < open.expires_at => {
+            return Err(SpendError::EscrowError(format!(
+                "closing without an update requires expiry (expires at {})",
+                open.expires_at
+            )));
+        }
+        None => {}
+    }
+    Ok(PendingClose {
+        channel_id: open.channel_id,
+        update,
+        closes_at: now.saturating_add(CHANNEL_DISPUTE_WINDOW),
+    })
+}
+
+impl PendingClose {
+    /// Replace the submitted state with a newer update.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::EscrowTimeout`] if the dispute window has passed
+    /// - [`SpendError::EscrowError`] if the update is not newer
+    /// - any error from [`verify_update`]
+    pub fn challenge(&mut self, open: &ChannelOpen, newer: BalanceUpdate, now: u64) -> Result<()> {
+        if now >= self.closes_at {
+            return Err(SpendError::EscrowTimeout {
+                expired_at: self.closes_at,
+            });
+        }
+        verify_update(open, &newer)?;
+        let current = self.update.as_ref().map_or(0, |u| u.sequence);
+        if newer.sequence <= current {
+            return Err(SpendError::EscrowError(format!(
+                "challenge sequence {} is not newer than {current}",
+                newer.sequence
+            )));
+        }
+        self.update = Some(newer);
+        Ok(())
+    }
+
+    /// Settle once the dispute window has passed.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::EscrowError`] if the window is still open
+    pub fn settle(&self, open: &ChannelOpen, now: u64) -> Result<ChannelSettlement> {
+        if now < self.closes_at {
+            return Err(SpendError::EscrowError(format!(
+                "dispute window open until {}",
+                self.closes_at
+            )));
+        }
+        Ok(settlement(open, self.update.as_ref()))
+    }
+}
+
+fn settlement(open: &ChannelOpen, update: Option<&BalanceUpdate>) -> ChannelSettlement {
+    let paid = update.map_or(0, |u| u.paid_total);
+    ChannelSettlement {
+        channel_id: open.channel_id,
+        paid_to_seller: paid,
+        refunded_to_buyer: open.capacity - paid,
+        final_sequence: update.map_or(0, |u| u.sequence),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_000_000;
+
+    fn quorum() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn mint(key: &VoprfServerKey, amount: u64) -> UnblindedToken {
+        use ochra_mint::voprf_mint::MintClient;
+        let (blinded, state) = MintClient::blind(amount).expect("blind");
+        let evaluated = MintServer::evaluate(&blinded, key, 0).expect("evaluate");
+        MintClient::unblind(&evaluated, &state).expect("unblind")
+    }
+
+    fn channel(capacity: u64) -> (ChannelOpen, SigningKey) {
+        let mint_key = VoprfServerKey::generate().expect("mint key");
+        let token = mint(&mint_key, capacity);
+        let key = SigningKey::from_bytes(&[5u8; 32]);
+        let mut open =
+            open_channel(&key, [9u8; 32], &token, NOW, DEFAULT_CHANNEL_DURATION).expect("open");
+        check_escrow(&open, &token, &mint_key, &NullifierSet::new()).expect("escrow");
+        open.escrow_sig = quorum().sign(&open.escrow_message()).to_bytes().to_vec();
+        (open, key)
+    }
+
+    fn payee(open: ChannelOpen) -> ChannelPayee {
+        ChannelPayee::new(open, &quorum().verifying_key().to_bytes()).expect("payee")
+    }
+
+    #[test]
+    fn test_repeated_purchases() {
+        let (open, key) = channel(1_000);
+        let mut payer = ChannelPayer::new(open.clone(), key).expect("payer");
+        let mut payee = payee(open);
+
+        for i in 0..5u8 {
+            let update = payer.pay(100, [i; 32], NOW + 1).expect("pay");
+            assert_eq!(payee.accept(update, NOW + 1).expect("accept"), 100);
+        }
+        assert_eq!(payee.paid_total(), 500);
+        assert_eq!(payer.remaining(), 500);
+        assert!(matches!(
+            payer.pay(501, [0; 32], NOW + 1),
+            Err(SpendError::InsufficientBalance { available: 500, .. })
+        ));
+        assert!(matches!(
+            payer.pay(1, [0; 32], NOW + DEFAULT_CHANNEL_DURATION),
+            Err(SpendError::EscrowTimeout { .. })
+        ));
+    }
+
+    #[test]
+    fn test_payee_rejects_replay_and_forgery() {
+        let (open, key) = channel(1_000);
+        let mut payer = ChannelPayer::new(open.clone(), key).expect("payer");
+        let mut payee = payee(open.clone());
+
+        let first = payer.pay(100, [1; 32], NOW).expect("pay");
+        payee.accept(first.clone(), NOW).expect("accept");
+        assert!(payee.accept(first, NOW).is_err());
+
+        let mut forged = payer.pay(100, [2; 32], NOW).expect("pay");
+        forged.paid_total = 900;
+        assert!(matches!(
+            payee.accept(forged, NOW),
+            Err(SpendError::InvalidProof(_))
+        ));
+
+        let other = SigningKey::from_bytes(&[6u8; 32]);
+        assert!(ChannelPayer::new(open, other).is_err());
+    }
+
+    #[test]
+    fn test_seller_close_settles_immediately() {
+        let (open, key) = channel(1_000);
+        let mut payer = ChannelPayer::new(open.clone(), key).expect("payer");
+        payer.pay(250, [1; 32], NOW).expect("pay");
+        let latest = payer.pay(150, [2; 32], NOW).expect("pay");
+
+        let settlement = close_by_seller(&open, &latest).expect("close");
+        assert_eq!(settlement.paid_to_seller, 400);
+        assert_eq!(settlement.refunded_to_buyer, 600);
+        assert_eq!(settlement.final_sequence, 2);
+    }
+
+    #[test]
+    fn test_buyer_close_with_stale_state_is_challenged() {
+        let (open, key) = channel(1_000);
+        let mut payer = ChannelPayer::new(open.clone(), key).expect("payer");
+        let stale = payer.pay(100, [1; 32], NOW).expect("pay");
+        let latest = payer.pay(300, [2; 32], NOW).expect("pay");
+
+        let mut pending = close_by_buyer(&open, Some(stale.clone()), NOW).expect("close");
+        assert!(pending.settle(&open, NOW + 1).is_err());
+        pending
+            .challenge(&open, latest, NOW + 10)
+            .expect("challenge");
+        assert!(pending.challenge(&open, stale, NOW + 20).is_err());
+
+        let settlement = pending
+            .settle(&open, NOW + CHANNEL_DISPUTE_WINDOW)
+            .expect("settle");
+        assert_eq!(settlement.paid_to_seller, 400);
+        assert_eq!(settlement.refunded_to_buyer, 600);
+    }
+
+    #[test]
+    fn test_buyer_refund_after_expiry() {
+        let (open, _) = channel(1_000);
+        assert!(close_by_buyer(&open, None, NOW).is_err());
+
+        let pending = close_by_buyer(&open, None, open.expires_at).expect("close");
+        let settlement = pending
+            .settle(&open, open.expires_at + CHANNEL_DISPUTE_WINDOW)
+            .expect("settle");
+        assert_eq!(settlement.paid_to_seller, 0);
+        assert_eq!(settlement.refunded_to_buyer, 1_000);
+    }
+
+    #[test]
+    fn test_open_requires_escrow_spend_and_signatures() {
+        let mint_key = VoprfServerKey::generate().expect("mint key");
+        let token = mint(&mint_key, 1_000);
+        let key = SigningKey::from_bytes(&[5u8; 32]);
+        let mut open =
+            open_channel(&key, [9u8; 32], &token, NOW, DEFAULT_CHANNEL_DURATION).expect("open");
+        assert_eq!(open.capacity, 1_000);
+
+        // The quorum refuses tokens it did not mint or that are spent
+        let other_mint = VoprfServerKey::generate().expect("mint key");
+        assert!(matches!(
+            check_escrow(&open, &token, &other_mint, &NullifierSet::new()),
+            Err(SpendError::InvalidProof(_))
+        ));
+        let mut spent = NullifierSet::new();
+        spent.insert(&token.nullifier());
+        assert!(matches!(
+            check_escrow(&open, &token, &mint_key, &spent),
+            Err(SpendError::AlreadySpent)
+        ));
+        let mut inflated = open.clone();
+        inflated.capacity = 5_000;
+        assert!(check_escrow(&inflated, &token, &mint_key, &NullifierSet::new()).is_err());
+
+        // The seller needs the quorum's escrow signature
+        let quorum_pk = quorum().verifying_key().to_bytes();
+        assert!(verify_open(&open, &quorum_pk).is_err());
+        open.escrow_sig = quorum().sign(&open.escrow_message()).to_bytes().to_vec();
+        assert!(verify_open(&open, &quorum_pk).is_ok());
+        let mut forged = open.clone();
+        forged.expires_at += 1;
+        assert!(matches!(
+            ChannelPayee::new(forged, &quorum_pk),
+            Err(SpendError::InvalidProof(_))
+        ));
+        assert!(verify_open(&open, &[3u8; 32]).is_err());
+    }
+}
@@ -7,14 +7,24 @@
 //!
 //! ## Modules
 //!
+//! - [`audit`] — Wallet token inventory audit against the nullifier set
 //! - [`micro`] — Micro transactions (< 5 Seeds)
 //! - [`macro_tx`] — Macro transactions (>= 5 Seeds) with escrow
 //! - [`blind_receipt`] — Blind receipt token system
+//! - [`channel`] — Unidirectional payment channels for repeated micro-purchases
+//! - [`pricing`] — Pricing tier validation, including bundles and subscriptions
+//! - [`refund`] — Anonymous refund claims and blind token re-issuance
+//! - [`reissue`] — Blind receipt re-issuance for device migration
 //! - [`transfer`] — P2P transfer notes
 
+pub mod audit;
 pub mod blind_receipt;
+pub mod channel;
 pub mod macro_tx;
 pub mod micro;
+pub mod pricing;
+pub mod refund;
+pub mod reissue;
 pub mod transfer;
 
 /// Error types for spend operations.
@@ -48,6 +58,14 @@ pub enum SpendError {
         expired_at: u64,
     },
 
+    /// Pricing tiers are malformed.
+    #[error("invalid pricing: {0}")]
+    InvalidPricing(String),
+
+    /// A refund claim was rejected.
+    #[error("refund rejected: {0}")]
+    RefundRejected(String),
+
     /// Invalid receipt.
     #[error("invalid receipt: {0}")]
     InvalidReceipt(String),
@@ -60,6 +78,10 @@ pub enum SpendError {
     #[error("crypto error: {0}")]
     CryptoError(String),
 
+    /// The ledger of granted refunds could not be read or written.
+    #[error("refund ledger error: {0}")]
+    Ledger(String),
+
     /// Amount below minimum threshold.
     #[error("amount {amount} is below minimum {minimum}")]
     BelowMinimum {
@@ -0,0 +1,176 @@
+//! Pricing tier validation (Section 22.3).
+//!
+//! Content carries 1-4 pricing tiers. Besides one-shot permanent and rental
+//! purchases, a tier may be:
+//!
+//! - **Bundle** — one purchase unlocks this item plus `bundle_hashes`, and
+//!   yields a single [`BundleReceipt`](crate::blind_receipt::BundleReceipt).
+//! - **Subscription** — access lasts `renewal_epochs` epochs and is extended
+//!   by a fresh [`SubscriptionReceipt`](crate::blind_receipt::SubscriptionReceipt)
+//!   each period.
+
+use std::collections::HashSet;
+
+use ochra_types::content::{PricingTier, TierType};
+use ochra_types::{ContentHash, MAX_BUNDLE_ITEMS, MAX_PRICING_TIERS};
+
+use crate::{Result, SpendError};
+
+/// Validate the pricing tiers of `content_hash`.
+///
+/// # Errors
+///
+/// - [`SpendError::InvalidPricing`] if the tier count is out of range or any
+///   tier's fields do not match its type.
+pub fn validate_tiers(content_hash: &ContentHash, tiers: &[PricingTier]) -> Result<()> {
+    if tiers.is_empty() || tiers.len() > MAX_PRICING_TIERS {
+        return Err(SpendError::InvalidPricing(format!(
+            "expected 1-{MAX_PRICING_TIERS} tiers, got {}",
+            tiers.len()
+        )));
+    }
+    for (i, tier) in tiers.iter().enumerate() {
+        validate_tier(content_hash, tier)
+            .map_err(|reason| SpendError::InvalidPricing(format!("tier {i}: {reason}")))?;
+    }
+    Ok(())
+}
+
+fn validate_tier(
+    content_hash: &ContentHash,
+    tier: &PricingTier,
+) -> std::result::Result<(), String> {
+    if tier.tier_type != TierType::Rental && tier.rental_days.is_some() {
+        return Err("rental_days is only valid for rental tiers".to_string());
+    }
+    if tier.tier_type != TierType::Bundle && !tier.bundle_hashes.is_empty() {
+        return Err("bundle_hashes is only valid for bundle tiers".to_string());
+    }
+    if tier.tier_type != TierType::Subscription && tier.renewal_epochs.is_some() {
+        return Err("renewal_epochs is only valid for subscription tiers".to_string());
+    }
+
+    match tier.tier_type {
+        TierType::Permanent => Ok(()),
+        TierType::Rental => match tier.rental_days {
+            Some(days) if days > 0 => Ok(()),
+            _ => Err("rental tiers need rental_days > 0".to_string()),
+        },
+        TierType::Bundle => {
+            if tier.bundle_hashes.is_empty() {
+                return Err("bundle tiers need at least one other item".to_string());
+            }
+            if tier.bundle_hashes.len() + 1 > MAX_BUNDLE_ITEMS {
+                return Err(format!("bundles hold at most {MAX_BUNDLE_ITEMS} items"));
+            }
+            let mut seen = HashSet::new();
+            for hash in &tier.bundle_hashes {
+                if hash == content_hash || hash == &[0u8; 32] || !seen.insert(*hash) {
+                    return Err(
+                        "bundle items must be distinct, non-zero, other content".to_string()
+                    );
+                }
+            }
+            Ok(())
+        }
+        TierType::Subscription => {
+            if tier.price_seeds == 0 {
+                return Err("subscription tiers must have a price".to_string());
+            }
+            match tier.renewal_epochs {
+                Some(epochs) if epochs > 0 => Ok(()),
+                _ => Err("subscription tiers need renewal_epochs > 0".to_string()),
+            }
+        }
+    }
+}
+
+/// All content unlocked by purchasing `tier` of `content_hash`, sorted.
+pub fn unlocked_content(content_hash: &ContentHash, tier: &PricingTier) -> Vec<ContentHash> {
+    let mut items = vec![*content_hash];
+    if tier.tier_type == TierType::Bundle {
+        items.extend_from_slice(&tier.bundle_hashes);
+    }
+    items.sort_unstable();
+    items.dedup();
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tier(tier_type: TierType) -> PricingTier {
+        PricingTier {
+            tier_type,
+            price_seeds: 1000,
+            rental_days: None,
+            bundle_hashes: Vec::new(),
+            renewal_epochs: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_tiers() {
+        let mut rental = tier(TierType::Rental);
+        rental.rental_days = Some(7);
+        let mut bundle = tier(TierType::Bundle);
+        bundle.bundle_hashes = vec![[2u8; 32], [3u8; 32]];
+        let mut sub = tier(TierType::Subscription);
+        sub.renewal_epochs = Some(30);
+
+        let tiers = [tier(TierType::Permanent), rental, bundle, sub];
+        assert!(validate_tiers(&[1u8; 32], &tiers).is_ok());
+    }
+
+    #[test]
+    fn test_tier_count_limits() {
+        assert!(validate_tiers(&[1u8; 32], &[]).is_err());
+        let five = vec![tier(TierType::Permanent); 5];
+        assert!(validate_tiers(&[1u8; 32], &five).is_err());
+    }
+
+    #[test]
+    fn test_invalid_bundles() {
+        let mut bundle = tier(TierType::Bundle);
+        assert!(validate_tiers(&[1u8; 32], std::slice::from_ref(&bundle)).is_err());
+
+        bundle.bundle_hashes = vec![[1u8; 32]];
+        assert!(validate_tiers(&[1u8; 32], std::slice::from_ref(&bundle)).is_err());
+
+        bundle.bundle_hashes = vec![[2u8; 32], [2u8; 32]];
+        assert!(validate_tiers(&[1u8; 32], std::slice::from_ref(&bundle)).is_err());
+
+        bundle.bundle_hashes = (0..MAX_BUNDLE_ITEMS as u8).map(|i| [i + 2; 32]).collect();
+        assert!(validate_tiers(&[1u8; 32], &[bundle]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_subscriptions_and_stray_fields() {
+        let sub = tier(TierType::Subscription);
+        assert!(validate_tiers(&[1u8; 32], &[sub]).is_err());
+
+        let mut free = tier(TierType::Subscription);
+        free.renewal_epochs = Some(30);
+        free.price_seeds = 0;
+        assert!(validate_tiers(&[1u8; 32], &[free]).is_err());
+
+        let mut permanent = tier(TierType::Permanent);
+        permanent.renewal_epochs = Some(30);
+        assert!(validate_tiers(&[1u8; 32], &[permanent]).is_err());
+    }
+
+    #[test]
+    fn test_unlocked_content() {
+        let mut bundle = tier(TierType::Bundle);
+        bundle.bundle_hashes = vec![[3u8; 32], [2u8; 32]];
+        assert_eq!(
+            unlocked_content(&[1u8; 32], &bundle),
+            vec![[1u8; 32], [2u8; 32], [3u8; 32]]
+        );
+        assert_eq!(
+            unlocked_content(&[1u8; 32], &tier(TierType::Permanent)),
+            vec![[1u8; 32]]
+        );
+    }
+}
@@ -0,0 +1,412 @@
+//! Anonymous refunds (Section 16.3).
+//!
+//! ## Lifecycle
+//!
+//! 1. At purchase the buyer generates a [`RefundTicket`] and publishes only
+//!    its commitment, which the quorum adds to the refund commitment tree.
+//! 2. To claim, the buyer blinds fresh tokens worth the refund with
+//!    `MintClient::blind_batch` and builds a [`RefundClaim`] bound to the
+//!    FROST-attested tree root.
+//! 3. The seller or quorum checks the claim with [`RefundValidator`]:
+//!    membership under the attested root, amount within the price, the
+//!    30-epoch refund window, and an unused refund nullifier. It then
+//!    evaluates the blinded tokens without learning their serials.
+//! 4. The buyer unblinds the evaluation with `MintClient::unblind_batch` and
+//!    marks the purchase refunded.
+//!
+//! When a claim is approved, its refund nullifier hash and the purchase's
+//! commitment are recorded together in a [`RefundLedger`], so a purchase
+//! can be refunded at most once. The ledger must outlive the validator;
+//! the node keeps it in the `refund_claims` table. The buyer's identity
+//! never appears in the commitment or the claim.
+//!
+//! In v1, the claim carries the commitment opening and its Merkle path. The
+//! refund circuit (Section 31.3) replaces them with a Groth16 proof over the
+//! same public inputs.
+
+use ochra_crypto::blake3;
+use ochra_crypto::voprf::VoprfServerKey;
+use ochra_mint::voprf_mint::{BlindedToken, EvaluatedTokenBatch, MintServer};
+use ochra_mint::KeyEpoch;
+use ochra_nullifier::refund::{RefundPath, RefundTree};
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, SpendError};
+
+/// Epochs after purchase during which a refund may be claimed.
+pub const REFUND_WINDOW_EPOCHS: u64 = 30;
+
+/// Buyer-held secrets for refunding one purchase.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefundTicket {
+    /// Revealed (hashed) on claim to prevent double refunds.
+    pub refund_nullifier: [u8; 32],
+    /// Hides the commitment's other fields.
+    pub refund_secret: [u8; 32],
+    /// Content purchased.
+    pub content_hash: [u8; 32],
+    /// Price paid in micro-seeds.
+    pub price: u64,
+    /// Epoch of the purchase.
+    pub epoch: u64,
+}
+
+impl RefundTicket {
+    /// Generate a ticket with fresh secrets for a purchase.
+    pub fn generate(content_hash: [u8; 32], price: u64, epoch: u64) -> Self {
+        let mut refund_nullifier = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut refund_nullifier);
+        let mut refund_secret = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut refund_secret);
+        Self {
+            refund_nullifier,
+            refund_secret,
+            content_hash,
+            price,
+            epoch,
+        }
+    }
+
+    /// The commitment added to the refund tree at purchase.
+    pub fn commitment(&self) -> [u8; 32] {
+        refund_commitment(
+            &self.refund_nullifier,
+            &self.refund_secret,
+            &self.content_hash,
+            self.price,
+            self.epoch,
+        )
+    }
+
+    /// Hash of the refund nullifier, published when the refund is claimed.
+    pub fn nullifier_hash(&self) -> [u8; 32] {
+        refund_nullifier_hash(&self.refund_nullifier)
+    }
+}
+
+/// `BLAKE3::derive_key("Ochra v1 refund-commitment", refund_nullifier ||
+/// refund_secret || content_hash || LE64(price) || LE64(epoch))`.
+pub fn refund_commitment(
+    refund_nullifier: &[u8; 32],
+    refund_secret: &[u8; 32],
+    content_hash: &[u8; 32],
+    price: u64,
+    epoch: u64,
+) -> [u8; 32] {
+    let price_bytes = price.to_le_bytes();
+    let epoch_bytes = epoch.to_le_bytes();
+    let input = blake3::encode_multi_field(&[
+        refund_nullifier.as_slice(),
+        refund_secret.as_slice(),
+        content_hash.as_slice(),
+        &price_bytes,
+        &epoch_bytes,
+    ]);
+    blake3::derive_key(blake3::contexts::REFUND_COMMITMENT, &input)
+}
+
+/// Double-refund tag for a refund nullifier.
+pub fn refund_nullifier_hash(refund_nullifier: &[u8; 32]) -> [u8; 32] {
+    blake3::hash(&blake3::encode_multi_field(&[
+        b"refund-nullifier",
+        refund_nullifier.as_slice(),
+    ]))
+}
+
+/// A request to refund a purchase.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefundClaim {
+    /// Refund tree root the claim is proven against.
+    pub tree_root: [u8; 32],
+    /// Double-refund tag.
+    pub nullifier_hash: [u8; 32],
+    /// Content being refunded.
+    pub content_hash: [u8; 32],
+    /// Amount requested in micro-seeds.
+    pub refund_amount: u64,
+    /// Commitment opening (v1; replaced by a proof with the refund circuit).
+    pub ticket: RefundTicket,
+    /// Path from the commitment to `tree_root`.
+    pub path: RefundPath,
+    /// Tokens to issue, blinded; denominations sum to `refund_amount`.
+    pub blinded_tokens: Vec<BlindedToken>,
+}
+
+/// Build a claim for `refund_amount` against the current refund tree.
+///
+/// # Errors
+///
+/// - [`SpendError::RefundRejected`] if the commitment is not in the tree,
+///   the amount is zero or above the price, or the blinded tokens do not
+///   add up to the amount
+pub fn build_claim(
+    ticket: &RefundTicket,
+    tree: &RefundTree,
+    refund_amount: u64,
+    blinded_tokens: Vec<BlindedToken>,
+) -> Result<RefundClaim> {
+    check_amounts(ticket.price, refund_amount, &blinded_tokens)?;
+    let path = tree.path(&ticket.commitment()).ok_or_else(|| {
+        SpendError::RefundRejected("purchase is not in the refund tree".to_string())
+    })?;
+    Ok(RefundClaim {
+        tree_root: tree.get_merkle_root(),
+        nullifier_hash: ticket.nullifier_hash(),
+        content_hash: ticket.content_hash,
+        refund_amount,
+        ticket: ticket.clone(),
+        path,
+        blinded_tokens,
+    })
+}
+
+fn check_amounts(price: u64, refund_amount: u64, blinded: &[BlindedToken]) -> Result<()> {
+    if refund_amount == 0 || refund_amount > price {
+        return Err(SpendError::RefundRejected(format!(
+            "refund amount {refund_amount} outside 1..={price}"
+        )));
+    }
+    let total = blinded
+        .iter()
+        .try_fold(0u64, |acc, t| acc.checked_add(t.denomination));
+    if total != Some(refund_amount) {
+        return Err(SpendError::RefundRejected(
+            "blinded tokens do not add up to the refund amount".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// An approved refund and the blind evaluation of its tokens.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefundIssuance {
+    /// Double-refund tag now marked used.
+    pub nullifier_hash: [u8; 32],
+    /// Content refunded.
+    pub content_hash: [u8; 32],
+    /// Amount refunded in micro-seeds.
+    pub refund_amount: u64,
+    /// Evaluated tokens for the buyer to unblind.
+    pub tokens: EvaluatedTokenBatch,
+}
+
+/// Refunds already granted.
+pub trait RefundLedger {
+    /// Whether a refund was granted under `nullifier_hash` or for the
+    /// purchase with commitment `purchase`.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::Ledger`] if the ledger cannot be read
+    fn is_refunded(&self, nullifier_hash: &[u8; 32], purchase: &[u8; 32]) -> Result<bool>;
+
+    /// Record a granted refund, checking and recording both tags in one
+    /// step. Returns `false`, recording nothing, if either was already
+    /// recorded.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::Ledger`] if the ledger cannot be written
+    fn record(
+        &mut self,
+        nullifier_hash: &[u8; 32],
+        purchase: &[u8; 32],
+        epoch: u64,
+    ) -> Result<bool>;
+}
+
+/// Seller- or quorum-side refund processing.
+#[derive(Debug)]
+pub struct RefundValidator<L> {
+    ledger: L,
+}
+
+impl<L: RefundLedger> RefundValidator<L> {
+    /// Create a validator recording refunds in `ledger`.
+    pub fn new(ledger: L) -> Self {
+        Self { ledger }
+    }
+
+    /// The ledger of granted refunds.
+    pub fn ledger(&self) -> &L {
+        &self.ledger
+    }
+
+    /// Validate a claim and blindly issue its tokens.
+    ///
+    /// `attested_root` is the FROST-attested refund tree root the claim must
+    /// be proven against.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::AlreadySpent`] if the purchase was already refunded
+    /// - [`SpendError::RefundRejected`] if the claim does not verify or is
+    ///   outside the refund window
+    /// - [`SpendError::CryptoError`] if token evaluation fails
+    /// - [`SpendError::Ledger`] if the ledger fails
+    pub fn process(
+        &mut self,
+        claim: &RefundClaim,
+        attested_root: &[u8; 32],
+        current_epoch: u64,
+        server_key: &VoprfServerKey,
+        key_epoch: KeyEpoch,
+    ) -> Result<RefundIssuance> {
+        let ticket = &claim.ticket;
+        let purchase = ticket.commitment();
+        if self.ledger.is_refunded(&claim.nullifier_hash, &purchase)? {
+            return Err(SpendError::AlreadySpent);
+        }
+        if claim.tree_root != *attested_root || !claim.path.verify(&purchase, attested_root) {
+            return Err(SpendError::RefundRejected(
+                "purchase not proven under the attested refund root".to_string(),
+            ));
+        }
+        if ticket.nullifier_hash() != claim.nullifier_hash
+            || ticket.content_hash != claim.content_hash
+        {
+            return Err(SpendError::RefundRejected(
+                "claim does not match its commitment".to_string(),
+            ));
+        }
+        if current_epoch.saturating_sub(ticket.epoch) > REFUND_WINDOW_EPOCHS {
+            return Err(SpendError::RefundRejected(format!(
+                "purchase epoch {} is outside the refund window",
+                ticket.epoch
+            )));
+        }
+        check_amounts(ticket.price, claim.refund_amount, &claim.blinded_tokens)?;
+
+        let tokens = MintServer::evaluate_batch(&claim.blinded_tokens, server_key, key_epoch)
+            .map_err(|e| SpendError::CryptoError(e.to_string()))?;
+        // A concurrent claim may have been approved since the check above;
+        // the evaluation is discarded unless this one is recorded first
+        if !self
+            .ledger
+            .record(&claim.nullifier_hash, &purchase, current_epoch)?
+        {
+            return Err(SpendError::AlreadySpent);
+        }
+        Ok(RefundIssuance {
+            nullifier_hash: claim.nullifier_hash,
+            content_hash: claim.content_hash,
+            refund_amount: claim.refund_amount,
+            tokens,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use ochra_mint::voprf_mint::MintClient;
+
+    const CONTENT: [u8; 32] = [0xC0; 32];
+
+    #[derive(Debug, Default)]
+    struct MemoryLedger {
+        nullifiers: HashSet<[u8; 32]>,
+        purchases: HashSet<[u8; 32]>,
+    }
+
+    impl RefundLedger for MemoryLedger {
+        fn is_refunded(&self, nullifier_hash: &[u8; 32], purchase: &[u8; 32]) -> Result<bool> {
+            Ok(self.nullifiers.contains(nullifier_hash) || self.purchases.contains(purchase))
+        }
+
+        fn record(
+            &mut self,
+            nullifier_hash: &[u8; 32],
+            purchase: &[u8; 32],
+            _epoch: u64,
+        ) -> Result<bool> {
+            if self.is_refunded(nullifier_hash, purchase)? {
+                return Ok(false);
+            }
+            self.nullifiers.insert(*nullifier_hash);
+            self.purchases.insert(*purchase);
+            Ok(true)
+        }
+    }
+
+    fn refunded(validator: &RefundValidator<MemoryLedger>, ticket: &RefundTicket) -> bool {
+        validator
+            .ledger()
+            .is_refunded(&ticket.nullifier_hash(), &ticket.commitment())
+            .expect("ledger")
+    }
+
+    fn purchased(price: u64, epoch: u64) -> (RefundTicket, RefundTree) {
+        let ticket = RefundTicket::generate(CONTENT, price, epoch);
+        let mut tree = RefundTree::new();
+        tree.add_commitment([0x01; 32], epoch);
+        tree.add_commitment(ticket.commitment(), epoch);
+        tree.add_commitment([0x02; 32], epoch);
+        (ticket, tree)
+    }
+
+    #[test]
+    fn test_refund_round_trip() {
+        let (ticket, tree) = purchased(1_000, 10);
+        let key = VoprfServerKey::generate().expect("key");
+        let (blinded, states) = MintClient::blind_batch(&[600, 400]).expect("blind");
+        let claim = build_claim(&ticket, &tree, 1_000, blinded.clone()).expect("claim");
+
+        let mut validator = RefundValidator::new(MemoryLedger::default());
+        let issuance = validator
+            .process(&claim, &tree.get_merkle_root(), 12, &key, 1)
+            .expect("process");
+        assert!(refunded(&validator, &ticket));
+
+        let tokens =
+            MintClient::unblind_batch(&blinded, &issuance.tokens, &states, &key.public_key())
+                .expect("unblind");
+        assert_eq!(tokens.iter().map(|t| t.denomination).sum::<u64>(), 1_000);
+
+        assert!(matches!(
+            validator.process(&claim, &tree.get_merkle_root(), 12, &key, 1),
+            Err(SpendError::AlreadySpent)
+        ));
+    }
+
+    #[test]
+    fn test_claim_amount_checks() {
+        let (ticket, tree) = purchased(1_000, 10);
+        let (blinded, _) = MintClient::blind_batch(&[1_001]).expect("blind");
+        assert!(build_claim(&ticket, &tree, 1_001, blinded).is_err());
+        let (blinded, _) = MintClient::blind_batch(&[300]).expect("blind");
+        assert!(build_claim(&ticket, &tree, 500, blinded).is_err());
+
+        let stranger = RefundTicket::generate(CONTENT, 1_000, 10);
+        let (blinded, _) = MintClient::blind_batch(&[500]).expect("blind");
+        assert!(build_claim(&stranger, &tree, 500, blinded).is_err());
+    }
+
+    #[test]
+    fn test_validator_rejects_bad_claims() {
+        let (ticket, tree) = purchased(1_000, 10);
+        let root = tree.get_merkle_root();
+        let key = VoprfServerKey::generate().expect("key");
+        let (blinded, _) = MintClient::blind_batch(&[500]).expect("blind");
+        let claim = build_claim(&ticket, &tree, 500, blinded).expect("claim");
+        let mut validator = RefundValidator::new(MemoryLedger::default());
+
+        // Outside the window
+        assert!(validator
+            .process(&claim, &root, 10 + REFUND_WINDOW_EPOCHS + 1, &key, 1)
+            .is_err());
+        // Root the quorum never attested
+        assert!(validator.process(&claim, &[0xEE; 32], 11, &key, 1).is_err());
+        // Price inflated after the fact
+        let mut inflated = claim.clone();
+        inflated.ticket.price = 10_000;
+        assert!(validator.process(&inflated, &root, 11, &key, 1).is_err());
+
+        assert!(!refunded(&validator, &ticket));
+        validator
+            .process(&claim, &root, 11, &key, 1)
+            .expect("valid claim still accepted");
+    }
+}
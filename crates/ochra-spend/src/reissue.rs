@@ -0,0 +1,336 @@
+//! Blind receipt re-issuance for device migration.
+//!
+//! A purchase is bound to a locally held `receipt_secret`; the public
+//! `receipt_id` is derived from it (Section 16.2). To move a purchase to a
+//! new device, the holder asks the seller (or settlement quorum) to retire
+//! the old receipt and accept a new one bound to a fresh secret.
+//!
+//! ## Proving knowledge of the secret
+//!
+//! Every receipt has a *re-issue key*: an Ed25519 key pair whose seed is
+//! `BLAKE3::derive_key("Ochra v1 receipt-reissue-key", receipt_secret)`. Its
+//! public half is stored alongside the receipt when it is issued. A re-issue
+//! request is signed with the private half, which proves knowledge of the
+//! secret without revealing it; the seller never sees the secret or learns
+//! anything that links the old and new secrets beyond the request itself.
+//!
+//! ## Re-issuance flow
+//!
+//! 1. The new device builds a [`ReissueRequest`] with
+//!    [`build_reissue_request`], generating a fresh secret.
+//! 2. The verifier checks it with [`ReissueRegistry::process`]: the old
+//!    receipt must be known and live, the signature must verify under its
+//!    re-issue key, and the request epoch must be current.
+//! 3. The old `receipt_id` is revoked and the new one registered, so a copy
+//!    of the old secret left on a lost device stops working.
+
+use std::collections::{HashMap, HashSet};
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, SpendError};
+
+/// Maximum times a purchase may be moved to a new secret.
+pub const MAX_REISSUE_GENERATIONS: u32 = 16;
+
+/// Accepted distance, in epochs, between the request and the verifier.
+pub const REISSUE_EPOCH_TOLERANCE: u64 = 1;
+
+/// Derive the public receipt ID for a purchase.
+///
+/// `receipt_id = BLAKE3::derive_key("Ochra v1 receipt-dht-address",
+/// receipt_secret || content_hash || tier_index)`.
+pub fn receipt_id(receipt_secret: &[u8; 32], content_hash: &[u8; 32], tier_index: u8) -> [u8; 32] {
+    let mut input = Vec::with_capacity(32 + 32 + 1);
+    input.extend_from_slice(receipt_secret);
+    input.extend_from_slice(content_hash);
+    input.push(tier_index);
+    blake3::derive_key(blake3::contexts::RECEIPT_DHT_ADDRESS, &input)
+}
+
+/// Derive the re-issue signing key for a receipt secret.
+pub fn reissue_signing_key(receipt_secret: &[u8; 32]) -> SigningKey {
+    SigningKey::from_bytes(&blake3::derive_key(
+        blake3::contexts::RECEIPT_REISSUE_KEY,
+        receipt_secret,
+    ))
+}
+
+/// Public re-issue key stored with a receipt when it is issued.
+pub fn reissue_public_key(receipt_secret: &[u8; 32]) -> [u8; 32] {
+    reissue_signing_key(receipt_secret)
+        .verifying_key()
+        .to_bytes()
+}
+
+/// A request to move a purchase to a fresh receipt secret.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReissueRequest {
+    /// Receipt being retired.
+    pub old_receipt_id: [u8; 32],
+    /// Receipt replacing it.
+    pub new_receipt_id: [u8; 32],
+    /// Re-issue public key of the new receipt.
+    pub new_reissue_pk: [u8; 32],
+    /// Epoch the request was made in.
+    pub epoch: u64,
+    /// Signature by the old receipt's re-issue key (64 bytes).
+    pub sig: Vec<u8>,
+}
+
+impl ReissueRequest {
+    /// Bytes covered by the signature.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(15 + 32 * 3 + 8);
+        msg.extend_from_slice(b"receipt-reissue");
+        msg.extend_from_slice(&self.old_receipt_id);
+        msg.extend_from_slice(&self.new_receipt_id);
+        msg.extend_from_slice(&self.new_reissue_pk);
+        msg.extend_from_slice(&self.epoch.to_le_bytes());
+        msg
+    }
+}
+
+/// Build a re-issue request, returning it with the fresh receipt secret.
+///
+/// The new secret must be stored locally before the request is sent.
+pub fn build_reissue_request(
+    old_secret: &[u8; 32],
+    content_hash: &[u8; 32],
+    tier_index: u8,
+    epoch: u64,
+) -> (ReissueRequest, [u8; 32]) {
+    let mut new_secret = [0u8; 32];
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut new_secret);
+
+    let mut request = ReissueRequest {
+        old_receipt_id: receipt_id(old_secret, content_hash, tier_index),
+        new_receipt_id: receipt_id(&new_secret, content_hash, tier_index),
+        new_reissue_pk: reissue_public_key(&new_secret),
+        epoch,
+        sig: Vec::new(),
+    };
+    request.sig = reissue_signing_key(old_secret)
+        .sign(&request.signing_message())
+        .to_bytes()
+        .to_vec();
+    (request, new_secret)
+}
+
+/// Confirmation that a receipt was re-issued.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReissueGrant {
+    /// Retired receipt.
+    pub old_receipt_id: [u8; 32],
+    /// Receipt now granting access.
+    pub new_receipt_id: [u8; 32],
+    /// How many times this purchase has been re-issued.
+    pub generation: u32,
+}
+
+/// A live receipt known to the verifier.
+#[derive(Clone, Debug)]
+struct LiveReceipt {
+    reissue_pk: [u8; 32],
+    generation: u32,
+}
+
+/// Verifier-side record of live and revoked receipts.
+#[derive(Debug, Default)]
+pub struct ReissueRegistry {
+    live: HashMap<[u8; 32], LiveReceipt>,
+    revoked: HashSet<[u8; 32]>,
+}
+
+impl ReissueRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a newly issued receipt and its re-issue public key.
+    pub fn register(&mut self, receipt_id: [u8; 32], reissue_pk: [u8; 32]) -> Result<()> {
+        if self.live.contains_key(&receipt_id) || self.revoked.contains(&receipt_id) {
+            return Err(SpendError::InvalidReceipt(
+                "receipt already registered".to_string(),
+            ));
+        }
+        self.live.insert(
+            receipt_id,
+            LiveReceipt {
+                reissue_pk,
+                generation: 0,
+            },
+        );
+        Ok(())
+    }
+
+    /// Whether a receipt currently grants access.
+    pub fn is_live(&self, receipt_id: &[u8; 32]) -> bool {
+        self.live.contains_key(receipt_id)
+    }
+
+    /// Whether a receipt was retired by re-issuance.
+    pub fn is_revoked(&self, receipt_id: &[u8; 32]) -> bool {
+        self.revoked.contains(receipt_id)
+    }
+
+    /// Verify a re-issue request and, if valid, retire the old receipt.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpendError::InvalidReceipt`] if the old receipt is unknown or
+    ///   revoked, the new receipt ID is already in use, the epoch is out of
+    ///   range, or the generation limit is reached
+    /// - [`SpendError::InvalidProof`] if the signature does not verify
+    pub fn process(
+        &mut self,
+        request: &ReissueRequest,
+        current_epoch: u64,
+    ) -> Result<ReissueGrant> {
+        if request.epoch.abs_diff(current_epoch) > REISSUE_EPOCH_TOLERANCE {
+            return Err(SpendError::InvalidReceipt(format!(
+                "request epoch {} is not current (now {current_epoch})",
+                request.epoch
+            )));
+        }
+        let old = self.live.get(&request.old_receipt_id).ok_or_else(|| {
+            SpendError::InvalidReceipt("old receipt is unknown or revoked".to_string())
+        })?;
+        if old.generation >= MAX_REISSUE_GENERATIONS {
+            return Err(SpendError::InvalidReceipt(
+                "re-issue limit reached".to_string(),
+            ));
+        }
+        if self.live.contains_key(&request.new_receipt_id)
+            || self.revoked.contains(&request.new_receipt_id)
+        {
+            return Err(SpendError::InvalidReceipt(
+                "new receipt ID already in use".to_string(),
+            ));
+        }
+        let sig: [u8; 64] = request
+            .sig
+            .as_slice()
+            .try_into()
+            .map_err(|_| SpendError::InvalidProof("signature must be 64 bytes".to_string()))?;
+        let vk = VerifyingKey::from_bytes(&old.reissue_pk)
+            .map_err(|e| SpendError::InvalidProof(e.to_string()))?;
+        vk.verify(&request.signing_message(), &Signature::from_bytes(&sig))
+            .map_err(|_| SpendError::InvalidProof("re-issue signature invalid".to_string()))?;
+
+        let generation = old.generation + 1;
+        self.live.remove(&request.old_receipt_id);
+        self.revoked.insert(request.old_receipt_id);
+        self.live.insert(
+            request.new_receipt_id,
+            LiveReceipt {
+                reissue_pk: request.new_reissue_pk,
+                generation,
+            },
+        );
+        Ok(ReissueGrant {
+            old_receipt_id: request.old_receipt_id,
+            new_receipt_id: request.new_receipt_id,
+            generation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: [u8; 32] = [0xAA; 32];
+
+    fn registered(secret: &[u8; 32]) -> ReissueRegistry {
+        let mut registry = ReissueRegistry::new();
+        registry
+            .register(receipt_id(secret, &CONTENT, 0), reissue_public_key(secret))
+            .expect("register");
+        registry
+    }
+
+    #[test]
+    fn test_receipt_id_matches_spec_layout() {
+        let id = receipt_id(&[0u8; 32], &[0u8; 32], 0);
+        let input = [0u8; 65];
+        assert_eq!(
+            id,
+            blake3::derive_key(blake3::contexts::RECEIPT_DHT_ADDRESS, &input)
+        );
+        assert_ne!(id, receipt_id(&[0u8; 32], &[0u8; 32], 1));
+    }
+
+    #[test]
+    fn test_reissue_moves_access_to_new_secret() {
+        let old_secret = [7u8; 32];
+        let mut registry = registered(&old_secret);
+
+        let (request, new_secret) = build_reissue_request(&old_secret, &CONTENT, 0, 10);
+        assert_ne!(new_secret, old_secret);
+        let grant = registry.process(&request, 10).expect("process");
+
+        assert_eq!(grant.generation, 1);
+        assert_eq!(grant.new_receipt_id, receipt_id(&new_secret, &CONTENT, 0));
+        assert!(registry.is_live(&grant.new_receipt_id));
+        assert!(registry.is_revoked(&grant.old_receipt_id));
+        assert!(!registry.is_live(&grant.old_receipt_id));
+    }
+
+    #[test]
+    fn test_old_secret_cannot_reissue_twice() {
+        let old_secret = [7u8; 32];
+        let mut registry = registered(&old_secret);
+        let (first, new_secret) = build_reissue_request(&old_secret, &CONTENT, 0, 10);
+        registry.process(&first, 10).expect("first");
+
+        // A lost device holding the old secret is locked out.
+        let (second, _) = build_reissue_request(&old_secret, &CONTENT, 0, 10);
+        assert!(registry.process(&second, 10).is_err());
+
+        // The new secret can re-issue again.
+        let (third, _) = build_reissue_request(&new_secret, &CONTENT, 0, 11);
+        assert_eq!(registry.process(&third, 11).expect("third").generation, 2);
+    }
+
+    #[test]
+    fn test_wrong_secret_rejected() {
+        let mut registry = registered(&[7u8; 32]);
+        let (mut request, _) = build_reissue_request(&[8u8; 32], &CONTENT, 0, 10);
+        // Point the forged request at the real receipt.
+        request.old_receipt_id = receipt_id(&[7u8; 32], &CONTENT, 0);
+        assert!(matches!(
+            registry.process(&request, 10),
+            Err(SpendError::InvalidProof(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_request_and_stale_epoch_rejected() {
+        let old_secret = [7u8; 32];
+        let mut registry = registered(&old_secret);
+
+        let (mut request, _) = build_reissue_request(&old_secret, &CONTENT, 0, 10);
+        request.new_receipt_id = [9u8; 32];
+        assert!(registry.process(&request, 10).is_err());
+
+        let (stale, _) = build_reissue_request(&old_secret, &CONTENT, 0, 5);
+        assert!(registry.process(&stale, 10).is_err());
+        assert!(registry.is_live(&receipt_id(&old_secret, &CONTENT, 0)));
+    }
+
+    #[test]
+    fn test_duplicate_registration_rejected() {
+        let secret = [7u8; 32];
+        let mut registry = registered(&secret);
+        assert!(registry
+            .register(
+                receipt_id(&secret, &CONTENT, 0),
+                reissue_public_key(&secret)
+            )
+            .is_err());
+    }
+}
@@ -14,8 +14,25 @@
 //!
 //! This favors recently stored and frequently accessed chunks while naturally
 //! aging out stale entries.
+//!
+//! ## Deduplication
+//!
+//! Chunks are content-addressed, so an identical chunk shared by several
+//! published files is stored once. [`AbrStore::store_for_content`] records
+//! which content (manifest) references each chunk; a chunk already held only
+//! gains a reference. Pinning a content protects every chunk it references
+//! from eviction, even when another content shares the chunk, and
+//! [`AbrStore::release_content`] drops a chunk only once no content
+//! references it. Evicting a shared chunk removes it from every referencing
+//! content at once.
+//!
+//! ## Tombstones
+//!
+//! Chunks of tombstoned content are purged with [`AbrStore::apply_tombstone`]
+//! and remembered alongside the tombstone's identifier, so the store refuses
+//! to serve or re-store them.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
@@ -38,6 +55,17 @@ pub struct ChunkMeta {
     pub data_size: u64,
 }
 
+/// Space saved by chunk deduplication.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DedupStats {
+    /// Chunks referenced by at least one content.
+    pub shared_chunks: u64,
+    /// Content-to-chunk references across all contents.
+    pub references: u64,
+    /// Bytes that would be stored again without deduplication.
+    pub saved_bytes: u64,
+}
+
 /// Entry in the ABR store containing metadata and data.
 #[derive(Clone, Debug)]
 struct StoreEntry {
@@ -58,6 +86,14 @@ pub struct AbrStore {
     capacity_bytes: u64,
     /// Current total bytes stored.
     used_bytes: u64,
+    /// Tombstoned chunk IDs and the tombstone that removed each.
+    tombstoned: HashMap<[u8; 32], [u8; 32]>,
+    /// Contents referencing each chunk.
+    chunk_refs: HashMap<[u8; 32], HashSet<[u8; 32]>>,
+    /// Chunks referenced by each content.
+    content_chunks: HashMap<[u8; 32], HashSet<[u8; 32]>>,
+    /// Contents protected from eviction.
+    pinned: HashSet<[u8; 32]>,
 }
 
 impl AbrStore {
@@ -71,6 +107,10 @@ impl AbrStore {
             entries: HashMap::new(),
             capacity_bytes,
             used_bytes: 0,
+            tombstoned: HashMap::new(),
+            chunk_refs: HashMap::new(),
+            content_chunks: HashMap::new(),
+            pinned: HashSet::new(),
         }
     }
 
@@ -92,6 +132,9 @@ impl AbrStore {
         data: Vec<u8>,
         current_time: u64,
     ) -> Result<()> {
+        if self.tombstoned.contains_key(&chunk_id) {
+            return Err(StorageError::Tombstoned(hex::encode(chunk_id)));
+        }
         let data_size = data.len() as u64;
 
         // If updating an existing chunk, remove the old entry first.
@@ -101,13 +144,12 @@ impl AbrStore {
 
         // Evict chunks until we have enough space.
         while self.used_bytes + data_size > self.capacity_bytes {
-            if self.entries.is_empty() {
+            if self.evict_lfu(current_time).is_err() {
                 return Err(StorageError::AllocationExceeded {
                     used: self.used_bytes,
                     limit: self.capacity_bytes,
                 });
             }
-            self.evict_lfu(current_time)?;
         }
 
         let meta = ChunkMeta {
@@ -134,6 +176,118 @@ impl AbrStore {
         Ok(())
     }
 
+    /// Store a chunk on behalf of `content_hash`, sharing it if already held.
+    ///
+    /// Returns `true` if the data was written, `false` if the chunk was
+    /// already stored and only gained a reference.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::Tombstoned`] if the chunk was tombstoned
+    /// - [`StorageError::AllocationExceeded`] if no space can be freed
+    pub fn store_for_content(
+        &mut self,
+        content_hash: [u8; 32],
+        chunk_id: [u8; 32],
+        shard_index: u8,
+        data: Vec<u8>,
+        current_time: u64,
+    ) -> Result<bool> {
+        let written = if self.entries.contains_key(&chunk_id) {
+            false
+        } else {
+            self.store_chunk(chunk_id, shard_index, data, current_time)?;
+            true
+        };
+        self.chunk_refs
+            .entry(chunk_id)
+            .or_default()
+            .insert(content_hash);
+        self.content_chunks
+            .entry(content_hash)
+            .or_default()
+            .insert(chunk_id);
+        Ok(written)
+    }
+
+    /// Drop `content_hash`'s references, removing chunks no other content
+    /// references. Returns the number of chunks removed.
+    pub fn release_content(&mut self, content_hash: &[u8; 32]) -> usize {
+        self.pinned.remove(content_hash);
+        let Some(chunks) = self.content_chunks.remove(content_hash) else {
+            return 0;
+        };
+        let mut removed = 0;
+        for chunk_id in chunks {
+            let unreferenced = self.chunk_refs.get_mut(&chunk_id).is_some_and(|refs| {
+                refs.remove(content_hash);
+                refs.is_empty()
+            });
+            if unreferenced {
+                self.chunk_refs.remove(&chunk_id);
+                if let Some(entry) = self.entries.remove(&chunk_id) {
+                    self.used_bytes = self.used_bytes.saturating_sub(entry.meta.data_size);
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    /// Protect every chunk `content_hash` references from eviction.
+    ///
+    /// Returns `false` if the store holds no chunks for the content.
+    pub fn pin_content(&mut self, content_hash: [u8; 32]) -> bool {
+        if !self.content_chunks.contains_key(&content_hash) {
+            return false;
+        }
+        self.pinned.insert(content_hash);
+        true
+    }
+
+    /// Allow `content_hash`'s chunks to be evicted again, unless another
+    /// pinned content shares them. Returns whether it was pinned.
+    pub fn unpin_content(&mut self, content_hash: &[u8; 32]) -> bool {
+        self.pinned.remove(content_hash)
+    }
+
+    /// Whether `chunk_id` is referenced by a pinned content.
+    pub fn is_pinned(&self, chunk_id: &[u8; 32]) -> bool {
+        self.chunk_refs
+            .get(chunk_id)
+            .is_some_and(|refs| refs.iter().any(|c| self.pinned.contains(c)))
+    }
+
+    /// Number of contents referencing `chunk_id`.
+    pub fn ref_count(&self, chunk_id: &[u8; 32]) -> usize {
+        self.chunk_refs.get(chunk_id).map_or(0, HashSet::len)
+    }
+
+    /// Chunks stored for `content_hash`.
+    pub fn content_chunk_ids(&self, content_hash: &[u8; 32]) -> Vec<[u8; 32]> {
+        self.content_chunks
+            .get(content_hash)
+            .map(|chunks| chunks.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Space saved by storing shared chunks once.
+    pub fn dedup_stats(&self) -> DedupStats {
+        let mut stats = DedupStats::default();
+        for (chunk_id, refs) in &self.chunk_refs {
+            let Some(entry) = self.entries.get(chunk_id) else {
+                continue;
+            };
+            let extra = refs.len().saturating_sub(1) as u64;
+            if extra > 0 {
+                stats.shared_chunks += 1;
+            }
+            stats.references += refs.len() as u64;
+            stats.saved_bytes += extra * entry.meta.data_size;
+        }
+        stats
+    }
+
     /// Retrieve a chunk's data and update its access count.
     ///
     /// # Arguments
@@ -141,6 +295,9 @@ impl AbrStore {
     /// * `chunk_id` - The 32-byte chunk identifier.
     /// * `current_time` - The current Unix timestamp (used to update dynamic age).
     pub fn get_chunk(&mut self, chunk_id: &[u8; 32], current_time: u64) -> Result<&[u8]> {
+        if self.tombstoned.contains_key(chunk_id) {
+            return Err(StorageError::Tombstoned(hex::encode(chunk_id)));
+        }
         let entry = self
             .entries
             .get_mut(chunk_id)
@@ -152,6 +309,18 @@ impl AbrStore {
         Ok(&entry.data)
     }
 
+    /// Read a chunk's data without updating its access count, for storage
+    /// proofs that must not skew eviction.
+    pub fn peek_chunk(&self, chunk_id: &[u8; 32]) -> Result<&[u8]> {
+        if self.tombstoned.contains_key(chunk_id) {
+            return Err(StorageError::Tombstoned(hex::encode(chunk_id)));
+        }
+        self.entries
+            .get(chunk_id)
+            .map(|e| e.data.as_slice())
+            .ok_or_else(|| StorageError::ChunkNotFound(hex::encode(chunk_id)))
+    }
+
     /// Get the metadata for a stored chunk without updating access counts.
     pub fn get_meta(&self, chunk_id: &[u8; 32]) -> Result<&ChunkMeta> {
         self.entries
@@ -160,7 +329,7 @@ impl AbrStore {
             .ok_or_else(|| StorageError::ChunkNotFound(hex::encode(chunk_id)))
     }
 
-    /// Evict the chunk with the lowest LFU-DA score.
+    /// Evict the unpinned chunk with the lowest LFU-DA score.
     ///
     /// The LFU-DA score is:
     /// ```text
@@ -174,6 +343,7 @@ impl AbrStore {
         let victim_id = self
             .entries
             .iter()
+            .filter(|(id, _)| !self.is_pinned(id))
             .min_by(|a, b| {
                 let score_a = lfu_da_score(&a.1.meta, current_time);
                 let score_b = lfu_da_score(&b.1.meta, current_time);
@@ -182,10 +352,9 @@ impl AbrStore {
                     .unwrap_or(std::cmp::Ordering::Equal)
             })
             .map(|(id, _)| *id)
-            .ok_or_else(|| {
-                StorageError::ChunkNotFound("store is empty, nothing to evict".to_string())
-            })?;
+            .ok_or_else(|| StorageError::ChunkNotFound("no unpinned chunk to evict".to_string()))?;
 
+        self.drop_refs(&victim_id);
         if let Some(entry) = self.entries.remove(&victim_id) {
             self.used_bytes = self.used_bytes.saturating_sub(entry.meta.data_size);
 
@@ -224,6 +393,50 @@ impl AbrStore {
     pub fn chunk_ids(&self) -> Vec<[u8; 32]> {
         self.entries.keys().copied().collect()
     }
+
+    /// Purge the chunks of tombstoned content and refuse them from now on.
+    ///
+    /// # Arguments
+    ///
+    /// * `tombstone_id` - Identifier of the verified tombstone.
+    /// * `chunk_ids` - Chunk IDs of the tombstoned content.
+    ///
+    /// Returns the number of stored chunks that were purged.
+    pub fn apply_tombstone(&mut self, tombstone_id: [u8; 32], chunk_ids: &[[u8; 32]]) -> usize {
+        let mut purged = 0;
+        for chunk_id in chunk_ids {
+            if let Some(entry) = self.entries.remove(chunk_id) {
+                self.used_bytes = self.used_bytes.saturating_sub(entry.meta.data_size);
+                purged += 1;
+            }
+            self.drop_refs(chunk_id);
+            self.tombstoned.insert(*chunk_id, tombstone_id);
+        }
+        tracing::debug!(
+            tombstone_id = hex::encode(tombstone_id),
+            purged,
+            "applied content tombstone to ABR store"
+        );
+        purged
+    }
+
+    /// The tombstone that removed `chunk_id`, if any.
+    pub fn tombstone_of(&self, chunk_id: &[u8; 32]) -> Option<&[u8; 32]> {
+        self.tombstoned.get(chunk_id)
+    }
+
+    /// Remove a departing chunk from every content that referenced it.
+    fn drop_refs(&mut self, chunk_id: &[u8; 32]) {
+        for content_hash in self.chunk_refs.remove(chunk_id).unwrap_or_default() {
+            if let Some(chunks) = self.content_chunks.get_mut(&content_hash) {
+                chunks.remove(chunk_id);
+                if chunks.is_empty() {
+                    self.content_chunks.remove(&content_hash);
+                    self.pinned.remove(&content_hash);
+                }
+            }
+        }
+    }
 }
 
 /// Compute the LFU-DA eviction score for a chunk.
@@ -271,6 +484,10 @@ mod tests {
 
         let meta = store.get_meta(&chunk_id).expect("meta");
         assert_eq!(meta.access_count, 3);
+
+        assert_eq!(store.peek_chunk(&chunk_id).expect("peek").len(), 100);
+        let meta = store.get_meta(&chunk_id).expect("meta");
+        assert_eq!(meta.access_count, 3);
     }
 
     #[test]
@@ -380,6 +597,96 @@ mod tests {
         assert!(score_new > score_old);
     }
 
+    #[test]
+    fn test_apply_tombstone_purges_and_blocks() {
+        let mut store = AbrStore::new(1000);
+        store
+            .store_chunk([1u8; 32], 0, vec![0; 100], 10)
+            .expect("store");
+        store
+            .store_chunk([2u8; 32], 1, vec![0; 100], 10)
+            .expect("store");
+
+        let purged = store.apply_tombstone([9u8; 32], &[[1u8; 32], [3u8; 32]]);
+        assert_eq!(purged, 1);
+        assert_eq!(store.used_bytes(), 100);
+        assert_eq!(store.tombstone_of(&[1u8; 32]), Some(&[9u8; 32]));
+        assert!(matches!(
+            store.get_chunk(&[1u8; 32], 11),
+            Err(StorageError::Tombstoned(_))
+        ));
+        assert!(store.store_chunk([3u8; 32], 0, vec![0; 10], 11).is_err());
+        assert!(store.get_chunk(&[2u8; 32], 11).is_ok());
+    }
+
+    #[test]
+    fn test_shared_chunks_are_stored_once() {
+        let mut store = AbrStore::new(1000);
+        let (file_a, file_b) = ([0xA0u8; 32], [0xB0u8; 32]);
+        let shared = [1u8; 32];
+
+        assert!(store
+            .store_for_content(file_a, shared, 0, vec![0; 100], 10)
+            .expect("store"));
+        assert!(store
+            .store_for_content(file_a, [2u8; 32], 1, vec![0; 100], 10)
+            .expect("store"));
+        assert!(!store
+            .store_for_content(file_b, shared, 0, vec![0; 100], 11)
+            .expect("store"));
+        assert_eq!(store.used_bytes(), 200);
+        assert_eq!(store.ref_count(&shared), 2);
+        assert_eq!(
+            store.dedup_stats(),
+            DedupStats {
+                shared_chunks: 1,
+                references: 3,
+                saved_bytes: 100,
+            }
+        );
+
+        // Releasing one file keeps the chunk the other still references
+        assert_eq!(store.release_content(&file_a), 1);
+        assert!(store.contains(&shared));
+        assert!(!store.contains(&[2u8; 32]));
+        assert_eq!(store.release_content(&file_b), 1);
+        assert_eq!(store.used_bytes(), 0);
+    }
+
+    #[test]
+    fn test_pinned_content_survives_eviction() {
+        let mut store = AbrStore::new(300);
+        let (pinned, other) = ([0xA0u8; 32], [0xB0u8; 32]);
+        store
+            .store_for_content(pinned, [1u8; 32], 0, vec![0; 100], 10)
+            .expect("store");
+        store
+            .store_for_content(other, [1u8; 32], 0, vec![0; 100], 10)
+            .expect("store");
+        store
+            .store_for_content(other, [2u8; 32], 1, vec![0; 100], 10)
+            .expect("store");
+        assert!(store.pin_content(pinned));
+        assert!(!store.pin_content([0xC0u8; 32]));
+
+        // Chunk 1 is pinned through its first referencing file only
+        let _ = store.get_chunk(&[2u8; 32], 11).expect("get");
+        store
+            .store_chunk([3u8; 32], 2, vec![0; 200], 12)
+            .expect("store");
+        assert!(store.contains(&[1u8; 32]));
+        assert!(!store.contains(&[2u8; 32]));
+        assert_eq!(store.content_chunk_ids(&other), vec![[1u8; 32]]);
+
+        // Nothing left that may be evicted
+        assert!(matches!(
+            store.store_chunk([4u8; 32], 0, vec![0; 250], 13),
+            Err(StorageError::AllocationExceeded { .. })
+        ));
+        assert!(store.unpin_content(&pinned));
+        assert!(!store.is_pinned(&[1u8; 32]));
+    }
+
     #[test]
     fn test_used_bytes_tracking() {
         let mut store = AbrStore::new(1024 * 1024);
@@ -0,0 +1,267 @@
+//! Provider announcement scheduling.
+//!
+//! A node that holds chunks (as creator, buyer, or ABR host) must keep
+//! advertising them, or its `chunk-loc` provider records expire and buyers
+//! can no longer find it (Section 14.8). The [`AnnounceScheduler`] tracks
+//! every locally held chunk and decides which ones to re-announce on each
+//! tick:
+//!
+//! - A chunk is *due* when it has never been announced or its record expires
+//!   within [`REFRESH_MARGIN_SECS`].
+//! - Due chunks are ordered rarest first (lowest known provider count), then
+//!   by soonest expiry, so scarce content stays discoverable.
+//! - The batch size shrinks as upload bandwidth utilization approaches the
+//!   limit. At saturation only chunks this node is the sole known provider
+//!   for are announced; everything else is deferred to a later tick.
+
+use std::collections::HashMap;
+
+use ochra_crypto::blake3;
+
+/// Provider record time-to-live (matches the DHT record TTL).
+pub const PROVIDER_RECORD_TTL_SECS: u64 = 7200;
+
+/// Re-announce this long before a provider record expires.
+pub const REFRESH_MARGIN_SECS: u64 = 900;
+
+/// Maximum chunks announced per tick at low utilization.
+pub const MAX_ANNOUNCES_PER_TICK: usize = 64;
+
+/// Utilization below which the full batch is announced.
+pub const BANDWIDTH_LOW_WATERMARK: f64 = 0.7;
+
+/// Utilization at or above which announcements are deferred.
+pub const BANDWIDTH_HIGH_WATERMARK: f64 = 0.95;
+
+/// DHT key of a chunk's provider list: `BLAKE3::hash("chunk-loc" || chunk_id)`.
+pub fn chunk_loc_key(chunk_id: &[u8; 32]) -> [u8; 32] {
+    let mut input = Vec::with_capacity(9 + 32);
+    input.extend_from_slice(b"chunk-loc");
+    input.extend_from_slice(chunk_id);
+    blake3::hash(&input)
+}
+
+/// Number of chunks to announce this tick at the given upload utilization.
+///
+/// `utilization` is the fraction of the upload limit in use (0.0-1.0).
+pub fn announce_budget(utilization: f64) -> usize {
+    if utilization < BANDWIDTH_LOW_WATERMARK {
+        return MAX_ANNOUNCES_PER_TICK;
+    }
+    if utilization >= BANDWIDTH_HIGH_WATERMARK {
+        return 0;
+    }
+    let headroom = (BANDWIDTH_HIGH_WATERMARK - utilization)
+        / (BANDWIDTH_HIGH_WATERMARK - BANDWIDTH_LOW_WATERMARK);
+    ((MAX_ANNOUNCES_PER_TICK as f64 * headroom).ceil() as usize).max(1)
+}
+
+/// Announcement state of a tracked chunk.
+#[derive(Clone, Debug, Default)]
+struct Tracked {
+    /// When the chunk was last announced.
+    last_announced: Option<u64>,
+    /// Providers seen in the last `chunk-loc` lookup, including this node.
+    provider_count: Option<u32>,
+}
+
+/// Schedules provider re-announcements for locally held chunks.
+#[derive(Debug)]
+pub struct AnnounceScheduler {
+    chunks: HashMap<[u8; 32], Tracked>,
+    ttl_secs: u64,
+}
+
+impl AnnounceScheduler {
+    /// Create a scheduler using [`PROVIDER_RECORD_TTL_SECS`].
+    pub fn new() -> Self {
+        Self::with_ttl(PROVIDER_RECORD_TTL_SECS)
+    }
+
+    /// Create a scheduler with a custom record TTL.
+    pub fn with_ttl(ttl_secs: u64) -> Self {
+        Self {
+            chunks: HashMap::new(),
+            ttl_secs,
+        }
+    }
+
+    /// Start announcing chunks. Already tracked chunks are unchanged.
+    pub fn track<I: IntoIterator<Item = [u8; 32]>>(&mut self, chunk_ids: I) {
+        for chunk_id in chunk_ids {
+            self.chunks.entry(chunk_id).or_default();
+        }
+    }
+
+    /// Stop announcing a chunk (evicted or deleted).
+    pub fn untrack(&mut self, chunk_id: &[u8; 32]) -> bool {
+        self.chunks.remove(chunk_id).is_some()
+    }
+
+    /// Record the provider count observed for a chunk.
+    pub fn set_provider_count(&mut self, chunk_id: &[u8; 32], count: u32) {
+        if let Some(tracked) = self.chunks.get_mut(chunk_id) {
+            tracked.provider_count = Some(count);
+        }
+    }
+
+    fn expires_at(&self, tracked: &Tracked) -> u64 {
+        tracked
+            .last_announced
+            .map_or(0, |t| t.saturating_add(self.ttl_secs))
+    }
+
+    fn is_due(&self, tracked: &Tracked, now: u64) -> bool {
+        tracked.last_announced.is_none()
+            || self.expires_at(tracked).saturating_sub(REFRESH_MARGIN_SECS) <= now
+    }
+
+    fn is_sole_provider(tracked: &Tracked) -> bool {
+        tracked.provider_count.is_some_and(|c| c <= 1)
+    }
+
+    /// All due chunks, rarest first.
+    ///
+    /// Chunks with an unknown provider count are treated as rare, since
+    /// nothing suggests anyone else holds them.
+    pub fn due(&self, now: u64) -> Vec<[u8; 32]> {
+        let mut due: Vec<(&[u8; 32], &Tracked)> = self
+            .chunks
+            .iter()
+            .filter(|(_, t)| self.is_due(t, now))
+            .collect();
+        due.sort_by_key(|(id, t)| (t.provider_count.unwrap_or(0), self.expires_at(t), **id));
+        due.into_iter().map(|(id, _)| *id).collect()
+    }
+
+    /// Select the chunks to announce this tick.
+    ///
+    /// Selected chunks are not marked as announced; call
+    /// [`AnnounceScheduler::mark_announced`] once the announcement is sent.
+    pub fn next_batch(&self, now: u64, utilization: f64) -> Vec<[u8; 32]> {
+        let budget = announce_budget(utilization);
+        let due = self.due(now);
+        if budget > 0 {
+            return due.into_iter().take(budget).collect();
+        }
+        due.into_iter()
+            .filter(|id| self.chunks.get(id).is_some_and(Self::is_sole_provider))
+            .collect()
+    }
+
+    /// Record a successful announcement.
+    pub fn mark_announced(&mut self, chunk_ids: &[[u8; 32]], now: u64) {
+        for chunk_id in chunk_ids {
+            if let Some(tracked) = self.chunks.get_mut(chunk_id) {
+                tracked.last_announced = Some(now);
+            }
+        }
+    }
+
+    /// Earliest time any tracked chunk becomes due, if any are tracked.
+    pub fn next_due_at(&self) -> Option<u64> {
+        self.chunks
+            .values()
+            .map(|t| match t.last_announced {
+                None => 0,
+                Some(_) => self.expires_at(t).saturating_sub(REFRESH_MARGIN_SECS),
+            })
+            .min()
+    }
+
+    /// Record TTL advertised with announcements.
+    pub fn ttl_secs(&self) -> u64 {
+        self.ttl_secs
+    }
+
+    /// Number of tracked chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Whether no chunks are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+impl Default for AnnounceScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_chunks_are_due() {
+        let mut sched = AnnounceScheduler::new();
+        sched.track([[1u8; 32], [2u8; 32]]);
+        assert_eq!(sched.due(0).len(), 2);
+        assert_eq!(sched.next_due_at(), Some(0));
+    }
+
+    #[test]
+    fn test_refresh_before_expiry() {
+        let mut sched = AnnounceScheduler::new();
+        sched.track([[1u8; 32]]);
+        sched.mark_announced(&[[1u8; 32]], 1000);
+
+        let refresh_at = 1000 + PROVIDER_RECORD_TTL_SECS - REFRESH_MARGIN_SECS;
+        assert!(sched.due(refresh_at - 1).is_empty());
+        assert_eq!(sched.due(refresh_at), vec![[1u8; 32]]);
+        assert_eq!(sched.next_due_at(), Some(refresh_at));
+    }
+
+    #[test]
+    fn test_rarest_chunks_first() {
+        let mut sched = AnnounceScheduler::new();
+        sched.track([[1u8; 32], [2u8; 32], [3u8; 32]]);
+        sched.set_provider_count(&[1u8; 32], 8);
+        sched.set_provider_count(&[2u8; 32], 1);
+        sched.set_provider_count(&[3u8; 32], 3);
+
+        assert_eq!(sched.due(0), vec![[2u8; 32], [3u8; 32], [1u8; 32]]);
+    }
+
+    #[test]
+    fn test_budget_shrinks_with_utilization() {
+        assert_eq!(announce_budget(0.0), MAX_ANNOUNCES_PER_TICK);
+        let mid = announce_budget(0.85);
+        assert!(mid > 0 && mid < MAX_ANNOUNCES_PER_TICK);
+        assert!(announce_budget(0.94) >= 1);
+        assert_eq!(announce_budget(BANDWIDTH_HIGH_WATERMARK), 0);
+        assert_eq!(announce_budget(1.5), 0);
+    }
+
+    #[test]
+    fn test_batch_limited_by_budget() {
+        let mut sched = AnnounceScheduler::new();
+        sched.track((0..100u8).map(|i| [i; 32]));
+        assert_eq!(sched.next_batch(0, 0.0).len(), MAX_ANNOUNCES_PER_TICK);
+        assert!(sched.next_batch(0, 0.9).len() < MAX_ANNOUNCES_PER_TICK);
+    }
+
+    #[test]
+    fn test_saturated_only_announces_sole_provider_chunks() {
+        let mut sched = AnnounceScheduler::new();
+        sched.track([[1u8; 32], [2u8; 32], [3u8; 32]]);
+        sched.set_provider_count(&[1u8; 32], 1);
+        sched.set_provider_count(&[2u8; 32], 5);
+
+        assert_eq!(sched.next_batch(0, 1.0), vec![[1u8; 32]]);
+    }
+
+    #[test]
+    fn test_untrack_and_chunk_loc_key() {
+        let mut sched = AnnounceScheduler::new();
+        sched.track([[1u8; 32]]);
+        assert!(sched.untrack(&[1u8; 32]));
+        assert!(sched.is_empty());
+        assert_eq!(sched.next_due_at(), None);
+
+        assert_ne!(chunk_loc_key(&[1u8; 32]), chunk_loc_key(&[2u8; 32]));
+    }
+}
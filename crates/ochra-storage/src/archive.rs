@@ -0,0 +1,657 @@
+//! Signed, encrypted Space content archives (Section 16.9).
+//!
+//! A Space owner can export everything their node holds for a Space, the
+//! catalog entries, the chunks behind them and Space metadata, into one
+//! archive file for offline safekeeping, and later import it to restore
+//! the content and republish the chunks.
+//!
+//! ## Format
+//!
+//! The archive is a sequence of records sealed as one streaming ECIES
+//! stream ([`ochra_crypto::ecies_stream`]) to the recipient's X25519 key:
+//!
+//! ```text
+//! record  = tag (1) || LE32(len) || body
+//! header  = 0x01: version (1) || group_id (32) || signer_pk (32) || LE64(exported_at)
+//! content = 0x02: JSON ArchivedContent
+//! chunk   = 0x03: content_hash (32) || chunk_id (32) || shard_index (1) || data
+//! meta    = 0x04: LE16(key_len) || key || value
+//! end     = 0xFF: Ed25519 signature (64)
+//! ```
+//!
+//! The header comes first and the end record last; every chunk follows the
+//! content record it belongs to. The signature is by `signer_pk` over
+//! `"space-archive" || transcript`, where the transcript chains the BLAKE3
+//! hash of every record before the end record:
+//! `t_0 = 0^32`, `t_i = BLAKE3(t_{i-1} || BLAKE3(record_i))`.
+//!
+//! Records are written and read one at a time, so neither side holds more
+//! than one chunk in memory. A reader only reports the archive as verified
+//! once it has reached the end record; callers that act on entries before
+//! then must be prepared to undo them, or read the archive twice.
+
+use std::collections::HashSet;
+use std::io::{Read, Write};
+
+use ochra_crypto::blake3;
+use ochra_crypto::ecies_stream::{
+    StreamDecryptor, StreamEncryptor, DEFAULT_CHUNK_SIZE, HEADER_SIZE,
+};
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use ochra_crypto::x25519::{X25519PublicKey, X25519StaticSecret};
+use ochra_crypto::CryptoError;
+use ochra_types::content::PricingTier;
+use ochra_types::{ContentHash, GroupId};
+use serde::{Deserialize, Serialize};
+
+use crate::chunker::CHUNK_SIZE;
+use crate::{Result, StorageError};
+
+/// Archive format version.
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// Largest record body a reader accepts: one chunk plus its framing.
+pub const MAX_RECORD_LEN: usize = CHUNK_SIZE + 1024;
+
+const TAG_HEADER: u8 = 0x01;
+const TAG_CONTENT: u8 = 0x02;
+const TAG_CHUNK: u8 = 0x03;
+const TAG_METADATA: u8 = 0x04;
+const TAG_END: u8 = 0xFF;
+
+const HEADER_BODY_LEN: usize = 1 + 32 + 32 + 8;
+const CHUNK_PREFIX_LEN: usize = 32 + 32 + 1;
+
+/// Who exported an archive, for which Space, and when.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveHeader {
+    pub version: u8,
+    pub group_id: GroupId,
+    /// PIK public key that signs the archive.
+    pub signer_pk: [u8; 32],
+    pub exported_at: u64,
+}
+
+/// A catalog entry as carried in an archive.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedContent {
+    pub content_hash: ContentHash,
+    pub title: String,
+    pub description: Option<String>,
+    /// Normalized `namespace:value` tags.
+    pub tags: Vec<String>,
+    pub pricing: Vec<PricingTier>,
+    pub creator_pik: [u8; 32],
+    pub key_commitment: [u8; 32],
+    pub total_size_bytes: u64,
+    pub chunk_count: u32,
+    pub published_at: u64,
+}
+
+/// A stored chunk of archived content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedChunk {
+    pub content_hash: ContentHash,
+    pub chunk_id: [u8; 32],
+    pub shard_index: u8,
+    pub data: Vec<u8>,
+}
+
+/// One record of an archive after the header.
+#[derive(Clone, Debug)]
+pub enum ArchiveEntry {
+    Content(ArchivedContent),
+    Chunk(ArchivedChunk),
+    /// Opaque Space metadata, such as the signed group policy.
+    Metadata {
+        key: String,
+        value: Vec<u8>,
+    },
+}
+
+/// Records written by an [`ArchiveWriter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ArchiveSummary {
+    pub contents: usize,
+    pub chunks: usize,
+    pub metadata: usize,
+    /// Archive file size in bytes.
+    pub bytes: u64,
+}
+
+fn invalid(msg: impl Into<String>) -> StorageError {
+    StorageError::InvalidArchive(msg.into())
+}
+
+fn io_error(e: std::io::Error) -> StorageError {
+    StorageError::Io(e.to_string())
+}
+
+fn crypto_error(e: CryptoError) -> StorageError {
+    match e {
+        CryptoError::AeadDecryption => invalid("wrong key or corrupted archive"),
+        e => invalid(e.to_string()),
+    }
+}
+
+fn chain(transcript: &[u8; 32], record: &[u8]) -> [u8; 32] {
+    let mut input = [0u8; 64];
+    input[..32].copy_from_slice(transcript);
+    input[32..].copy_from_slice(&blake3::hash(record));
+    blake3::hash(&input)
+}
+
+fn signing_message(transcript: &[u8; 32]) -> Vec<u8> {
+    blake3::encode_multi_field(&[b"space-archive", transcript])
+}
+
+/// Streams an archive to `W`.
+pub struct ArchiveWriter<W: Write> {
+    writer: W,
+    encryptor: StreamEncryptor,
+    signing_key: SigningKey,
+    /// Plaintext not yet sealed, always shorter than one stream chunk
+    /// between calls.
+    pending: Vec<u8>,
+    transcript: [u8; 32],
+    contents: HashSet<ContentHash>,
+    summary: ArchiveSummary,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Start an archive of `group_id` sealed to `recipient_pk` and signed
+    /// by `signing_key`.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::Io`] if the header cannot be written
+    pub fn new(
+        mut writer: W,
+        recipient_pk: &X25519PublicKey,
+        signing_key: SigningKey,
+        group_id: GroupId,
+        exported_at: u64,
+    ) -> Result<Self> {
+        let encryptor =
+            StreamEncryptor::new(recipient_pk, DEFAULT_CHUNK_SIZE).map_err(crypto_error)?;
+        writer.write_all(encryptor.header()).map_err(io_error)?;
+
+        let mut body = Vec::with_capacity(HEADER_BODY_LEN);
+        body.push(ARCHIVE_VERSION);
+        body.extend_from_slice(&group_id);
+        body.extend_from_slice(&signing_key.verifying_key().to_bytes());
+        body.extend_from_slice(&exported_at.to_le_bytes());
+
+        let mut archive = Self {
+            writer,
+            encryptor,
+            signing_key,
+            pending: Vec::with_capacity(DEFAULT_CHUNK_SIZE),
+            transcript: [0u8; 32],
+            contents: HashSet::new(),
+            summary: ArchiveSummary {
+                bytes: HEADER_SIZE as u64,
+                ..ArchiveSummary::default()
+            },
+        };
+        archive.write_record(TAG_HEADER, &[&body])?;
+        Ok(archive)
+    }
+
+    /// Add a catalog entry. Its chunks may follow.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::Io`] if writing fails
+    pub fn add_content(&mut self, content: &ArchivedContent) -> Result<()> {
+        let body = serde_json::to_vec(content).map_err(|e| invalid(e.to_string()))?;
+        self.write_record(TAG_CONTENT, &[&body])?;
+        self.contents.insert(content.content_hash);
+        self.summary.contents += 1;
+        Ok(())
+    }
+
+    /// Add a chunk of content already added with
+    /// [`add_content`](Self::add_content).
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::InvalidArchive`] if the content was not added or
+    ///   the chunk is larger than [`CHUNK_SIZE`]
+    /// - [`StorageError::Io`] if writing fails
+    pub fn add_chunk(
+        &mut self,
+        content_hash: &ContentHash,
+        chunk_id: &[u8; 32],
+        shard_index: u8,
+        data: &[u8],
+    ) -> Result<()> {
+        if !self.contents.contains(content_hash) {
+            return Err(invalid(format!(
+                "chunk of content {} before its catalog entry",
+                hex::encode(content_hash)
+            )));
+        }
+        if data.len() > MAX_RECORD_LEN - CHUNK_PREFIX_LEN {
+            return Err(invalid(format!("chunk of {} bytes", data.len())));
+        }
+        self.write_record(TAG_CHUNK, &[content_hash, chunk_id, &[shard_index], data])?;
+        self.summary.chunks += 1;
+        Ok(())
+    }
+
+    /// Add a metadata entry.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::InvalidArchive`] if the key is over 255 bytes
+    /// - [`StorageError::Io`] if writing fails
+    pub fn add_metadata(&mut self, key: &str, value: &[u8]) -> Result<()> {
+        if key.len() > 255 || value.len() > MAX_RECORD_LEN - 2 - key.len() {
+            return Err(invalid(format!("metadata entry {key} too large")));
+        }
+        let key_len = (key.len() as u16).to_le_bytes();
+        self.write_record(TAG_METADATA, &[&key_len, key.as_bytes(), value])?;
+        self.summary.metadata += 1;
+        Ok(())
+    }
+
+    /// Sign the archive, seal the last stream frame and flush.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::Io`] if writing fails
+    pub fn finish(mut self) -> Result<ArchiveSummary> {
+        let sig = self
+            .signing_key
+            .sign(&signing_message(&self.transcript))
+            .to_bytes();
+        self.write_record(TAG_END, &[&sig])?;
+        let frame = self.encryptor.finish(&self.pending).map_err(crypto_error)?;
+        self.writer.write_all(&frame).map_err(io_error)?;
+        self.writer.flush().map_err(io_error)?;
+        self.summary.bytes += frame.len() as u64;
+        Ok(self.summary)
+    }
+
+    fn write_record(&mut self, tag: u8, parts: &[&[u8]]) -> Result<()> {
+        let len: usize = parts.iter().map(|p| p.len()).sum();
+        let mut record = Vec::with_capacity(5 + len);
+        record.push(tag);
+        record.extend_from_slice(&(len as u32).to_le_bytes());
+        for part in parts {
+            record.extend_from_slice(part);
+        }
+        if tag != TAG_END {
+            self.transcript = chain(&self.transcript, &record);
+        }
+
+        self.pending.extend_from_slice(&record);
+        let chunk_size = self.encryptor.chunk_size();
+        let full = self.pending.len() / chunk_size * chunk_size;
+        for chunk in self.pending[..full].chunks(chunk_size) {
+            let frame = self.encryptor.encrypt_chunk(chunk).map_err(crypto_error)?;
+            self.writer.write_all(&frame).map_err(io_error)?;
+            self.summary.bytes += frame.len() as u64;
+        }
+        self.pending.drain(..full);
+        Ok(())
+    }
+}
+
+/// Reads and verifies an archive from `R`.
+pub struct ArchiveReader<R: Read> {
+    reader: R,
+    /// `None` once the final stream frame has been opened.
+    decryptor: Option<StreamDecryptor>,
+    plaintext: Vec<u8>,
+    pos: usize,
+    transcript: [u8; 32],
+    header: ArchiveHeader,
+    contents: HashSet<ContentHash>,
+    verified: bool,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    /// Open an archive sealed to `recipient_sk` and read its header.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::InvalidArchive`] if the archive is not sealed to
+    ///   this key, is corrupted, or has an unknown version
+    /// - [`StorageError::Io`] if reading fails
+    pub fn open(mut reader: R, recipient_sk: &X25519StaticSecret) -> Result<Self> {
+        let mut stream_header = [0u8; HEADER_SIZE];
+        if read_full(&mut reader, &mut stream_header)? < HEADER_SIZE {
+            return Err(invalid("not an archive"));
+        }
+        let decryptor = StreamDecryptor::new(recipient_sk, &stream_header).map_err(crypto_error)?;
+        let mut archive = Self {
+            reader,
+            decryptor: Some(decryptor),
+            plaintext: Vec::new(),
+            pos: 0,
+            transcript: [0u8; 32],
+            header: ArchiveHeader {
+                version: 0,
+                group_id: [0u8; 32],
+                signer_pk: [0u8; 32],
+                exported_at: 0,
+            },
+            contents: HashSet::new(),
+            verified: false,
+        };
+
+        let (tag, body) = archive.read_record()?;
+        if tag != TAG_HEADER || body.len() != HEADER_BODY_LEN {
+            return Err(invalid("missing header"));
+        }
+        if body[0] != ARCHIVE_VERSION {
+            return Err(invalid(format!("unknown version {}", body[0])));
+        }
+        archive.header = ArchiveHeader {
+            version: body[0],
+            group_id: body[1..33].try_into().unwrap_or_default(),
+            signer_pk: body[33..65].try_into().unwrap_or_default(),
+            exported_at: u64::from_le_bytes(body[65..73].try_into().unwrap_or_default()),
+        };
+        Ok(archive)
+    }
+
+    /// The archive header. Its signer is only proven once
+    /// [`is_verified`](Self::is_verified).
+    pub fn header(&self) -> &ArchiveHeader {
+        &self.header
+    }
+
+    /// Whether the end record has been read and its signature verified.
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
+
+    /// The next entry, or `None` once the signature has been verified at
+    /// the end of the archive.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::InvalidArchive`] on a malformed, truncated,
+    ///   reordered or forged archive, or a bad signature
+    /// - [`StorageError::Io`] if reading fails
+    pub fn next_entry(&mut self) -> Result<Option<ArchiveEntry>> {
+        if self.verified {
+            return Ok(None);
+        }
+        let (tag, body) = self.read_record()?;
+        match tag {
+            TAG_CONTENT => {
+                let content: ArchivedContent =
+                    serde_json::from_slice(&body).map_err(|e| invalid(e.to_string()))?;
+                self.contents.insert(content.content_hash);
+                Ok(Some(ArchiveEntry::Content(content)))
+            }
+            TAG_CHUNK => {
+                if body.len() < CHUNK_PREFIX_LEN {
+                    return Err(invalid("short chunk record"));
+                }
+                let content_hash: ContentHash = body[..32].try_into().unwrap_or_default();
+                if !self.contents.contains(&content_hash) {
+                    return Err(invalid(format!(
+                        "chunk of content {} before its catalog entry",
+                        hex::encode(content_hash)
+                    )));
+                }
+                Ok(Some(ArchiveEntry::Chunk(ArchivedChunk {
+                    content_hash,
+                    chunk_id: body[32..64].try_into().unwrap_or_default(),
+                    shard_index: body[64],
+                    data: body[CHUNK_PREFIX_LEN..].to_vec(),
+                })))
+            }
+            TAG_METADATA => {
+                if body.len() < 2 {
+                    return Err(invalid("short metadata record"));
+                }
+                let key_len = usize::from(u16::from_le_bytes([body[0], body[1]]));
+                let key = body
+                    .get(2..2 + key_len)
+                    .and_then(|k| std::str::from_utf8(k).ok())
+                    .ok_or_else(|| invalid("bad metadata key"))?
+                    .to_string();
+                Ok(Some(ArchiveEntry::Metadata {
+                    key,
+                    value: body[2 + key_len..].to_vec(),
+                }))
+            }
+            TAG_END => {
+                let sig: [u8; 64] = body
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| invalid("bad signature record"))?;
+                VerifyingKey::from_bytes(&self.header.signer_pk)
+                    .and_then(|pk| {
+                        pk.verify(
+                            &signing_message(&self.transcript),
+                            &Signature::from_bytes(&sig),
+                        )
+                    })
+                    .map_err(|_| invalid("signature verification failed"))?;
+                if self.fill(1)? {
+                    return Err(invalid("data after end record"));
+                }
+                self.verified = true;
+                Ok(None)
+            }
+            tag => Err(invalid(format!("unknown record type 0x{tag:02x}"))),
+        }
+    }
+
+    fn read_record(&mut self) -> Result<(u8, Vec<u8>)> {
+        if !self.fill(5)? {
+            return Err(invalid("archive truncated"));
+        }
+        let tag = self.plaintext[self.pos];
+        let len = u32::from_le_bytes(
+            self.plaintext[self.pos + 1..self.pos + 5]
+                .try_into()
+                .unwrap_or_default(),
+        ) as usize;
+        if len > MAX_RECORD_LEN {
+            return Err(invalid(format!("record of {len} bytes")));
+        }
+        if !self.fill(5 + len)? {
+            return Err(invalid("archive truncated"));
+        }
+        let record = &self.plaintext[self.pos..self.pos + 5 + len];
+        if tag != TAG_END {
+            self.transcript = chain(&self.transcript, record);
+        }
+        let body = record[5..].to_vec();
+        self.pos += 5 + len;
+        Ok((tag, body))
+    }
+
+    /// Decrypt frames until `n` unread bytes are buffered. Returns `false`
+    /// if the stream ends first.
+    fn fill(&mut self, n: usize) -> Result<bool> {
+        while self.plaintext.len() - self.pos < n {
+            let Some(decryptor) = self.decryptor.as_mut() else {
+                return Ok(false);
+            };
+            self.plaintext.drain(..self.pos);
+            self.pos = 0;
+            let mut frame = vec![0u8; decryptor.frame_size()];
+            let read = read_full(&mut self.reader, &mut frame)?;
+            if read < frame.len() {
+                let last = self
+                    .decryptor
+                    .take()
+                    .map(|d| d.finish(&frame[..read]))
+                    .transpose()
+                    .map_err(crypto_error)?
+                    .unwrap_or_default();
+                self.plaintext.extend_from_slice(&last);
+            } else {
+                let chunk = decryptor.decrypt_chunk(&frame).map_err(crypto_error)?;
+                self.plaintext.extend_from_slice(&chunk);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Fill `buf` from `reader`, returning how many bytes were read before EOF.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(io_error(e)),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(byte: u8) -> ArchivedContent {
+        ArchivedContent {
+            content_hash: [byte; 32],
+            title: format!("item {byte}"),
+            description: None,
+            tags: vec!["language:en".to_string()],
+            pricing: Vec::new(),
+            creator_pik: [9; 32],
+            key_commitment: [8; 32],
+            total_size_bytes: 100,
+            chunk_count: 1,
+            published_at: 1_700_000_000,
+        }
+    }
+
+    fn sample_archive(sk: &X25519StaticSecret, signer: &SigningKey) -> (Vec<u8>, ArchiveSummary) {
+        let mut out = Vec::new();
+        let mut writer =
+            ArchiveWriter::new(&mut out, &sk.public_key(), signer.clone(), [7; 32], 42)
+                .expect("writer");
+        writer.add_content(&content(1)).expect("content");
+        writer
+            .add_chunk(&[1; 32], &[2; 32], 3, &vec![0xAB; 100_000])
+            .expect("chunk");
+        writer.add_content(&content(4)).expect("content");
+        writer
+            .add_chunk(&[4; 32], &[5; 32], 0, b"small")
+            .expect("chunk");
+        writer.add_metadata("space_name", b"Films").expect("meta");
+        let summary = writer.finish().expect("finish");
+        (out, summary)
+    }
+
+    fn read_all(bytes: &[u8], sk: &X25519StaticSecret) -> Result<Vec<ArchiveEntry>> {
+        let mut reader = ArchiveReader::open(bytes, sk)?;
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry()? {
+            entries.push(entry);
+        }
+        assert!(reader.is_verified());
+        Ok(entries)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sk = X25519StaticSecret::random();
+        let signer = SigningKey::generate();
+        let (bytes, summary) = sample_archive(&sk, &signer);
+        assert_eq!(summary.contents, 2);
+        assert_eq!(summary.chunks, 2);
+        assert_eq!(summary.metadata, 1);
+        assert_eq!(summary.bytes, bytes.len() as u64);
+
+        let reader = ArchiveReader::open(bytes.as_slice(), &sk).expect("open");
+        assert_eq!(reader.header().group_id, [7; 32]);
+        assert_eq!(reader.header().signer_pk, signer.verifying_key().to_bytes());
+        assert_eq!(reader.header().exported_at, 42);
+
+        let entries = read_all(&bytes, &sk).expect("read");
+        assert_eq!(entries.len(), 5);
+        assert!(matches!(
+            &entries[1],
+            ArchiveEntry::Chunk(chunk)
+                if chunk.chunk_id == [2; 32]
+                    && chunk.shard_index == 3
+                    && chunk.data == vec![0xAB; 100_000]
+        ));
+        assert!(matches!(
+            &entries[4],
+            ArchiveEntry::Metadata { key, value } if key == "space_name" && value == b"Films"
+        ));
+    }
+
+    #[test]
+    fn test_wrong_key_rejected() {
+        let sk = X25519StaticSecret::random();
+        let (bytes, _) = sample_archive(&sk, &SigningKey::generate());
+        let other = X25519StaticSecret::random();
+        assert!(matches!(
+            ArchiveReader::open(bytes.as_slice(), &other),
+            Err(StorageError::InvalidArchive(_))
+        ));
+    }
+
+    #[test]
+    fn test_truncated_and_tampered_rejected() {
+        let sk = X25519StaticSecret::random();
+        let (bytes, _) = sample_archive(&sk, &SigningKey::generate());
+
+        let truncated = &bytes[..bytes.len() - 100];
+        assert!(read_all(truncated, &sk).is_err());
+
+        let mut tampered = bytes.clone();
+        tampered[HEADER_SIZE + 200] ^= 1;
+        assert!(read_all(&tampered, &sk).is_err());
+    }
+
+    #[test]
+    fn test_signature_must_match_header_signer() {
+        // Re-seal a valid record stream with the header naming a different
+        // signer: the stream decrypts but the signature fails.
+        let sk = X25519StaticSecret::random();
+        let signer = SigningKey::generate();
+        let (bytes, _) = sample_archive(&sk, &signer);
+        let mut plaintext = Vec::new();
+        ochra_crypto::ecies_stream::decrypt_stream(&sk, bytes.as_slice(), &mut plaintext)
+            .expect("decrypt");
+        let impostor = SigningKey::generate().verifying_key().to_bytes();
+        plaintext[5 + 1 + 32..5 + 1 + 64].copy_from_slice(&impostor);
+        let mut resealed = Vec::new();
+        ochra_crypto::ecies_stream::encrypt_stream(
+            &sk.public_key(),
+            DEFAULT_CHUNK_SIZE,
+            plaintext.as_slice(),
+            &mut resealed,
+        )
+        .expect("encrypt");
+        assert!(matches!(
+            read_all(&resealed, &sk),
+            Err(StorageError::InvalidArchive(msg)) if msg.contains("signature")
+        ));
+    }
+
+    #[test]
+    fn test_chunk_requires_content() {
+        let sk = X25519StaticSecret::random();
+        let mut out = Vec::new();
+        let mut writer = ArchiveWriter::new(
+            &mut out,
+            &sk.public_key(),
+            SigningKey::generate(),
+            [7; 32],
+            0,
+        )
+        .expect("writer");
+        assert!(writer.add_chunk(&[1; 32], &[2; 32], 0, b"x").is_err());
+    }
+}
@@ -0,0 +1,616 @@
+//! Multi-source chunk download planning.
+//!
+//! A [`DownloadPlan`] tracks which chunks of one content item are complete,
+//! which are in flight and from which provider, and hands out new chunk
+//! assignments so different chunks are fetched from different providers
+//! concurrently.
+//!
+//! ## Verification
+//!
+//! The plan is built from the content's leaf hashes, which are checked
+//! against the Merkle root (`content_hash`) up front. Every fetched chunk is
+//! then verified against its leaf hash before it is accepted, so a chunk is
+//! only ever marked complete once it is known to belong to the content.
+//!
+//! ## Scheduling
+//!
+//! Missing chunks are handed out rarest-first: chunks advertised by the
+//! fewest usable providers are requested before common ones, so a provider
+//! that leaves takes as little unique data with it as possible. Ties go to
+//! the lower index.
+//!
+//! Each provider has its own request window, starting at
+//! [`INITIAL_WINDOW`]. A verified chunk widens it by one, up to
+//! [`MAX_IN_FLIGHT_PER_PROVIDER`]; a failure or timeout halves it (down to
+//! one), so fast providers are kept pipelined and slow ones are not
+//! flooded.
+//!
+//! Once every missing chunk is in flight and at most [`ENDGAME_MAX_REMAINING`]
+//! remain, the plan enters endgame: chunks whose latest request has been
+//! outstanding for [`ENDGAME_DUPLICATE_AFTER_SECS`] are also requested from
+//! other providers with spare capacity, up to [`ENDGAME_MAX_COPIES`]
+//! requests per chunk. The first verified copy wins
+//! and the other requests are dropped, so one slow provider cannot hold up
+//! the end of a download.
+//!
+//! ## Resumption
+//!
+//! The plan itself is in-memory. Callers persist completed chunk indices and
+//! rebuild the plan with [`DownloadPlan::resume`] after a pause or restart;
+//! in-flight assignments are never persisted and are simply re-issued.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use ochra_crypto::blake3;
+
+use crate::chunker::{build_merkle_root, CHUNK_SIZE};
+use crate::{Result, StorageError};
+
+/// Maximum concurrent chunk requests per provider.
+pub const MAX_IN_FLIGHT_PER_PROVIDER: usize = 16;
+
+/// Request window a provider starts with.
+pub const INITIAL_WINDOW: usize = 4;
+
+/// Remaining chunks at or below which endgame mode may start.
+pub const ENDGAME_MAX_REMAINING: usize = 8;
+
+/// Maximum concurrent requests for one chunk in endgame mode.
+pub const ENDGAME_MAX_COPIES: usize = 3;
+
+/// Seconds a request must be outstanding before endgame duplicates it.
+pub const ENDGAME_DUPLICATE_AFTER_SECS: u64 = 5;
+
+/// Failures after which a provider is no longer assigned chunks.
+pub const MAX_PROVIDER_FAILURES: u32 = 3;
+
+/// Seconds after which an unanswered chunk request is re-assigned.
+pub const CHUNK_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// A chunk assigned to a provider.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkAssignment {
+    /// Chunk index within the content.
+    pub index: u32,
+    /// Leaf hash of the chunk (used as the `ChunkRequest` hash).
+    pub chunk_id: [u8; 32],
+    /// Provider node ID.
+    pub provider: [u8; 32],
+    /// Whether the chunk is also requested from another provider (endgame).
+    pub duplicate: bool,
+}
+
+/// Per-provider bookkeeping.
+#[derive(Clone, Debug)]
+struct ProviderState {
+    /// Chunk indices this provider advertises.
+    chunks: HashSet<u32>,
+    /// Number of requests currently outstanding.
+    in_flight: usize,
+    /// Maximum requests to keep outstanding.
+    window: usize,
+    /// Failed or unverifiable responses.
+    failures: u32,
+}
+
+impl Default for ProviderState {
+    fn default() -> Self {
+        Self {
+            chunks: HashSet::new(),
+            in_flight: 0,
+            window: INITIAL_WINDOW,
+            failures: 0,
+        }
+    }
+}
+
+impl ProviderState {
+    fn usable(&self) -> bool {
+        self.failures < MAX_PROVIDER_FAILURES
+    }
+
+    fn has_capacity(&self) -> bool {
+        self.usable() && self.in_flight < self.window
+    }
+}
+
+/// An outstanding chunk request.
+#[derive(Clone, Debug)]
+struct InFlight {
+    provider: [u8; 32],
+    requested_at: u64,
+}
+
+/// Download progress for one content item.
+#[derive(Debug)]
+pub struct DownloadPlan {
+    content_hash: [u8; 32],
+    leaves: Vec<[u8; 32]>,
+    completed: BTreeSet<u32>,
+    in_flight: HashMap<u32, Vec<InFlight>>,
+    providers: HashMap<[u8; 32], ProviderState>,
+}
+
+impl DownloadPlan {
+    /// Start a new download, verifying `leaves` against `content_hash`.
+    pub fn new(content_hash: [u8; 32], leaves: Vec<[u8; 32]>) -> Result<Self> {
+        if leaves.is_empty() {
+            return Err(StorageError::EmptyContent);
+        }
+        if build_merkle_root(&leaves) != content_hash {
+            return Err(StorageError::MerkleVerification);
+        }
+        Ok(Self {
+            content_hash,
+            leaves,
+            completed: BTreeSet::new(),
+            in_flight: HashMap::new(),
+            providers: HashMap::new(),
+        })
+    }
+
+    /// Rebuild a download from persisted progress.
+    pub fn resume(
+        content_hash: [u8; 32],
+        leaves: Vec<[u8; 32]>,
+        completed: &[u32],
+    ) -> Result<Self> {
+        let mut plan = Self::new(content_hash, leaves)?;
+        for &index in completed {
+            if index as usize >= plan.leaves.len() {
+                return Err(StorageError::ChunkNotFound(format!(
+                    "persisted chunk index {index} out of range"
+                )));
+            }
+            plan.completed.insert(index);
+        }
+        Ok(plan)
+    }
+
+    /// The content's Merkle root.
+    pub fn content_hash(&self) -> &[u8; 32] {
+        &self.content_hash
+    }
+
+    /// Total number of chunks.
+    pub fn chunk_count(&self) -> u32 {
+        self.leaves.len() as u32
+    }
+
+    /// Leaf hashes of all chunks.
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        &self.leaves
+    }
+
+    /// Byte offset of a chunk within the assembled content.
+    pub fn chunk_offset(index: u32) -> u64 {
+        u64::from(index) * CHUNK_SIZE as u64
+    }
+
+    /// Register a provider advertising the given chunk indices.
+    pub fn add_provider(&mut self, provider: [u8; 32], chunks: &[u32]) {
+        let count = self.chunk_count();
+        let state = self.providers.entry(provider).or_default();
+        state
+            .chunks
+            .extend(chunks.iter().copied().filter(|&i| i < count));
+    }
+
+    /// Register a provider advertising every chunk.
+    pub fn add_full_provider(&mut self, provider: [u8; 32]) {
+        let all: Vec<u32> = (0..self.chunk_count()).collect();
+        self.add_provider(provider, &all);
+    }
+
+    /// Remove a provider, releasing its outstanding requests.
+    pub fn remove_provider(&mut self, provider: &[u8; 32]) {
+        self.providers.remove(provider);
+        for requests in self.in_flight.values_mut() {
+            requests.retain(|f| f.provider != *provider);
+        }
+        self.in_flight.retain(|_, requests| !requests.is_empty());
+    }
+
+    /// Number of usable providers.
+    pub fn provider_count(&self) -> usize {
+        self.providers.values().filter(|p| p.usable()).count()
+    }
+
+    /// Number of usable providers advertising chunk `index`.
+    pub fn availability(&self, index: u32) -> usize {
+        self.providers
+            .values()
+            .filter(|p| p.usable() && p.chunks.contains(&index))
+            .count()
+    }
+
+    /// Current request window of a provider.
+    pub fn window(&self, provider: &[u8; 32]) -> Option<usize> {
+        self.providers.get(provider).map(|p| p.window)
+    }
+
+    /// Whether the plan is in endgame mode.
+    ///
+    /// True once every missing chunk has been requested and no more than
+    /// [`ENDGAME_MAX_REMAINING`] remain.
+    pub fn is_endgame(&self) -> bool {
+        let remaining = self.leaves.len() - self.completed.len();
+        remaining > 0
+            && remaining <= ENDGAME_MAX_REMAINING
+            && (0..self.chunk_count())
+                .all(|i| self.completed.contains(&i) || self.in_flight.contains_key(&i))
+    }
+
+    /// Assign missing chunks to providers with spare capacity.
+    ///
+    /// Unrequested chunks are handed out rarest-first and spread across
+    /// providers, so concurrent requests go to different peers whenever
+    /// possible. In endgame mode, stalled chunks are then duplicated to
+    /// other providers.
+    pub fn next_requests(&mut self, now: u64) -> Vec<ChunkAssignment> {
+        let mut assignments = Vec::new();
+        let mut missing: Vec<u32> = (0..self.chunk_count())
+            .filter(|i| !self.completed.contains(i) && !self.in_flight.contains_key(i))
+            .collect();
+        missing.sort_by_key(|&i| (self.availability(i), i));
+
+        for index in missing {
+            if let Some(assignment) = self.assign(index, now) {
+                assignments.push(assignment);
+            }
+        }
+
+        if self.is_endgame() {
+            let mut outstanding: Vec<u32> = self
+                .in_flight
+                .iter()
+                .filter(|(_, requests)| {
+                    requests
+                        .iter()
+                        .all(|f| now.saturating_sub(f.requested_at) >= ENDGAME_DUPLICATE_AFTER_SECS)
+                })
+                .map(|(&i, _)| i)
+                .collect();
+            outstanding.sort_by_key(|&i| (self.in_flight.get(&i).map_or(0, Vec::len), i));
+            for index in outstanding {
+                while self.in_flight.get(&index).map_or(0, Vec::len) < ENDGAME_MAX_COPIES {
+                    let Some(assignment) = self.assign(index, now) else {
+                        break;
+                    };
+                    assignments.push(assignment);
+                }
+            }
+        }
+        assignments
+    }
+
+    /// Request chunk `index` from the least loaded provider that has it and
+    /// is not already asked for it.
+    fn assign(&mut self, index: u32, now: u64) -> Option<ChunkAssignment> {
+        let requests = self.in_flight.get(&index);
+        let provider = self
+            .providers
+            .iter()
+            .filter(|(id, p)| {
+                p.has_capacity()
+                    && p.chunks.contains(&index)
+                    && !requests.is_some_and(|r| r.iter().any(|f| f.provider == **id))
+            })
+            .min_by_key(|(id, p)| (p.in_flight, p.failures, **id))
+            .map(|(id, _)| *id)?;
+        if let Some(state) = self.providers.get_mut(&provider) {
+            state.in_flight += 1;
+        }
+        let requests = self.in_flight.entry(index).or_default();
+        let duplicate = !requests.is_empty();
+        requests.push(InFlight {
+            provider,
+            requested_at: now,
+        });
+        Some(ChunkAssignment {
+            index,
+            chunk_id: self.leaves[index as usize],
+            provider,
+            duplicate,
+        })
+    }
+
+    /// Accept a fetched chunk after verifying it against its leaf hash.
+    ///
+    /// Returns `true` if the chunk was newly completed. A chunk that fails
+    /// verification is released for re-assignment and counts as a failure
+    /// against the provider. Once a chunk is verified, any duplicate
+    /// requests for it are dropped.
+    pub fn accept(&mut self, index: u32, provider: &[u8; 32], data: &[u8]) -> Result<bool> {
+        let leaf = self
+            .leaves
+            .get(index as usize)
+            .ok_or_else(|| StorageError::ChunkNotFound(format!("chunk index {index}")))?;
+        if blake3::merkle_leaf(data) != *leaf {
+            self.release(index, provider, true);
+            self.shrink_window(provider);
+            return Err(StorageError::MerkleVerification);
+        }
+        if self.release(index, provider, false) {
+            if let Some(state) = self.providers.get_mut(provider) {
+                state.window = (state.window + 1).min(MAX_IN_FLIGHT_PER_PROVIDER);
+            }
+        }
+        for other in self.in_flight.remove(&index).unwrap_or_default() {
+            if let Some(state) = self.providers.get_mut(&other.provider) {
+                state.in_flight = state.in_flight.saturating_sub(1);
+            }
+        }
+        Ok(self.completed.insert(index))
+    }
+
+    /// Record a failed request so the chunk can be re-assigned.
+    pub fn fail(&mut self, index: u32, provider: &[u8; 32]) {
+        self.release(index, provider, true);
+        self.shrink_window(provider);
+    }
+
+    /// Release requests older than `timeout_secs`, counting them as failures.
+    ///
+    /// A provider's window is halved once per pass however many of its
+    /// requests timed out, since they were most likely lost together.
+    ///
+    /// Returns the number of requests released.
+    pub fn expire_requests(&mut self, now: u64, timeout_secs: u64) -> usize {
+        let expired: Vec<(u32, [u8; 32])> = self
+            .in_flight
+            .iter()
+            .flat_map(|(&i, requests)| requests.iter().map(move |f| (i, f)))
+            .filter(|(_, f)| now.saturating_sub(f.requested_at) >= timeout_secs)
+            .map(|(i, f)| (i, f.provider))
+            .collect();
+        let mut slow = HashSet::new();
+        for (index, provider) in &expired {
+            self.release(*index, provider, true);
+            slow.insert(*provider);
+        }
+        for provider in &slow {
+            self.shrink_window(provider);
+        }
+        expired.len()
+    }
+
+    /// Drop all outstanding requests (used when pausing).
+    pub fn clear_in_flight(&mut self) {
+        self.in_flight.clear();
+        for state in self.providers.values_mut() {
+            state.in_flight = 0;
+        }
+    }
+
+    /// Drop `provider`'s request for chunk `index`, returning whether it was
+    /// outstanding.
+    fn release(&mut self, index: u32, provider: &[u8; 32], failed: bool) -> bool {
+        let mut outstanding = false;
+        if let Some(requests) = self.in_flight.get_mut(&index) {
+            let before = requests.len();
+            requests.retain(|f| f.provider != *provider);
+            outstanding = requests.len() < before;
+            if requests.is_empty() {
+                self.in_flight.remove(&index);
+            }
+        }
+        if let Some(state) = self.providers.get_mut(provider) {
+            if outstanding {
+                state.in_flight = state.in_flight.saturating_sub(1);
+            }
+            if failed {
+                state.failures += 1;
+            }
+        }
+        outstanding
+    }
+
+    /// Halve a provider's window after a failure.
+    fn shrink_window(&mut self, provider: &[u8; 32]) {
+        if let Some(state) = self.providers.get_mut(provider) {
+            state.window = (state.window / 2).max(1);
+        }
+    }
+
+    /// Indices of chunks not yet completed.
+    pub fn missing(&self) -> Vec<u32> {
+        (0..self.chunk_count())
+            .filter(|i| !self.completed.contains(i))
+            .collect()
+    }
+
+    /// Number of completed chunks.
+    pub fn completed_count(&self) -> u32 {
+        self.completed.len() as u32
+    }
+
+    /// Number of outstanding requests.
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight.values().map(Vec::len).sum()
+    }
+
+    /// Whether every chunk has been verified.
+    pub fn is_complete(&self) -> bool {
+        self.completed.len() == self.leaves.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> (Vec<Vec<u8>>, Vec<[u8; 32]>, [u8; 32]) {
+        let chunks: Vec<Vec<u8>> = (0..n).map(|i| vec![i; 64]).collect();
+        let leaves: Vec<[u8; 32]> = chunks.iter().map(|c| blake3::merkle_leaf(c)).collect();
+        let root = build_merkle_root(&leaves);
+        (chunks, leaves, root)
+    }
+
+    #[test]
+    fn test_new_rejects_wrong_root() {
+        let (_, leaves, _) = leaves(4);
+        assert!(matches!(
+            DownloadPlan::new([0u8; 32], leaves),
+            Err(StorageError::MerkleVerification)
+        ));
+        assert!(DownloadPlan::new([0u8; 32], Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_spreads_chunks_across_providers() {
+        let (_, leaves, root) = leaves(4);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        plan.add_full_provider([1u8; 32]);
+        plan.add_full_provider([2u8; 32]);
+
+        let assignments = plan.next_requests(0);
+        assert_eq!(assignments.len(), 4);
+        let from_first = assignments
+            .iter()
+            .filter(|a| a.provider == [1u8; 32])
+            .count();
+        assert_eq!(from_first, 2);
+
+        // Everything is in flight; nothing more to hand out.
+        assert!(plan.next_requests(0).is_empty());
+    }
+
+    #[test]
+    fn test_only_assigns_advertised_chunks() {
+        let (_, leaves, root) = leaves(4);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        plan.add_provider([1u8; 32], &[1, 3, 99]);
+
+        let indices: Vec<u32> = plan.next_requests(0).iter().map(|a| a.index).collect();
+        assert_eq!(indices, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_accept_verifies_chunk() {
+        let (chunks, leaves, root) = leaves(2);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        let provider = [1u8; 32];
+        plan.add_full_provider(provider);
+        plan.next_requests(0);
+
+        assert!(plan.accept(0, &provider, b"garbage").is_err());
+        assert!(plan.accept(0, &provider, &chunks[0]).expect("accept"));
+        assert!(plan.accept(1, &provider, &chunks[1]).expect("accept"));
+        assert!(plan.is_complete());
+        assert_eq!(plan.in_flight_count(), 0);
+    }
+
+    #[test]
+    fn test_failing_provider_is_excluded() {
+        let (_, leaves, root) = leaves(1);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        let bad = [1u8; 32];
+        plan.add_full_provider(bad);
+
+        for _ in 0..MAX_PROVIDER_FAILURES {
+            let a = plan.next_requests(0);
+            assert_eq!(a.len(), 1);
+            plan.fail(a[0].index, &bad);
+        }
+        assert!(plan.next_requests(0).is_empty());
+        assert_eq!(plan.provider_count(), 0);
+
+        plan.add_full_provider([2u8; 32]);
+        assert_eq!(plan.next_requests(0)[0].provider, [2u8; 32]);
+    }
+
+    #[test]
+    fn test_expired_requests_are_reassigned() {
+        let (_, leaves, root) = leaves(2);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        plan.add_full_provider([1u8; 32]);
+        assert_eq!(plan.next_requests(100).len(), 2);
+
+        assert_eq!(plan.expire_requests(110, CHUNK_REQUEST_TIMEOUT_SECS), 0);
+        assert_eq!(plan.expire_requests(130, CHUNK_REQUEST_TIMEOUT_SECS), 2);
+        assert_eq!(plan.next_requests(130).len(), 2);
+    }
+
+    #[test]
+    fn test_resume_skips_completed_chunks() {
+        let (_, leaves, root) = leaves(4);
+        let mut plan = DownloadPlan::resume(root, leaves.clone(), &[0, 2]).expect("resume");
+        assert_eq!(plan.completed_count(), 2);
+        assert_eq!(plan.missing(), vec![1, 3]);
+
+        plan.add_full_provider([1u8; 32]);
+        let indices: Vec<u32> = plan.next_requests(0).iter().map(|a| a.index).collect();
+        assert_eq!(indices, vec![1, 3]);
+
+        assert!(DownloadPlan::resume(root, leaves, &[7]).is_err());
+    }
+
+    #[test]
+    fn test_clear_in_flight_on_pause() {
+        let (_, leaves, root) = leaves(3);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        plan.add_full_provider([1u8; 32]);
+        assert_eq!(plan.next_requests(0).len(), 3);
+        plan.clear_in_flight();
+        assert_eq!(plan.in_flight_count(), 0);
+        assert_eq!(plan.next_requests(0).len(), 3);
+    }
+
+    #[test]
+    fn test_rarest_chunks_requested_first() {
+        let (_, leaves, root) = leaves(4);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        plan.add_full_provider([1u8; 32]);
+        plan.add_provider([2u8; 32], &[0, 1, 2]);
+        plan.add_provider([3u8; 32], &[0, 1]);
+
+        assert_eq!(plan.availability(3), 1);
+        let indices: Vec<u32> = plan.next_requests(0).iter().map(|a| a.index).collect();
+        assert_eq!(indices, vec![3, 2, 0, 1]);
+    }
+
+    #[test]
+    fn test_window_grows_on_success_and_halves_on_failure() {
+        let (chunks, leaves, root) = leaves(12);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        let provider = [1u8; 32];
+        plan.add_full_provider(provider);
+
+        let first = plan.next_requests(0);
+        assert_eq!(first.len(), INITIAL_WINDOW);
+        for a in &first {
+            plan.accept(a.index, &provider, &chunks[a.index as usize])
+                .expect("accept");
+        }
+        assert_eq!(plan.window(&provider), Some(INITIAL_WINDOW + 4));
+        assert_eq!(plan.next_requests(1).len(), INITIAL_WINDOW + 4);
+
+        assert_eq!(plan.expire_requests(100, CHUNK_REQUEST_TIMEOUT_SECS), 8);
+        assert_eq!(plan.window(&provider), Some((INITIAL_WINDOW + 4) / 2));
+    }
+
+    #[test]
+    fn test_endgame_duplicates_stalled_chunks() {
+        let (chunks, leaves, root) = leaves(2);
+        let mut plan = DownloadPlan::new(root, leaves).expect("plan");
+        let (slow, fast) = ([1u8; 32], [2u8; 32]);
+        plan.add_provider(slow, &[0, 1]);
+        let first = plan.next_requests(0);
+        assert_eq!(first.len(), 2);
+        assert!(plan.is_endgame());
+
+        plan.add_full_provider(fast);
+        assert!(plan.next_requests(1).is_empty());
+        let dups = plan.next_requests(ENDGAME_DUPLICATE_AFTER_SECS);
+        assert_eq!(dups.len(), 2);
+        assert!(dups.iter().all(|a| a.duplicate && a.provider == fast));
+        assert_eq!(plan.in_flight_count(), 4);
+
+        // The first verified copy wins and the duplicate is dropped.
+        assert!(plan.accept(0, &fast, &chunks[0]).expect("accept"));
+        assert_eq!(plan.in_flight_count(), 2);
+        assert!(!plan.accept(0, &slow, &chunks[0]).expect("late copy"));
+        assert_eq!(plan.window(&slow), Some(INITIAL_WINDOW));
+    }
+}
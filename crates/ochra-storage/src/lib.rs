@@ -10,13 +10,29 @@
 //!
 //! - [`chunker`] — 4 MB chunk splitting with Merkle tree verification.
 //! - [`reed_solomon`] — Reed-Solomon k=4, n=8 erasure coding.
-//! - [`abr`] — ABR store with LFU-DA eviction policy.
+//! - [`download`] — Verified multi-source chunk download planning.
+//! - [`abr`] — ABR store with LFU-DA eviction and chunk deduplication.
+//! - [`archive`] — Signed, encrypted Space content archives.
+//! - [`announce`] — Provider re-announcement scheduling.
 //! - [`earning`] — Storage earning level configuration.
+//! - [`quota`] — Group storage quota policies and publish checks.
+//! - [`receipts`] — Service receipt aggregation and sampled batch verification.
+//! - [`tombstone`] — Signed content tombstones and their verification.
+//! - [`upload`] — Upload slots and reciprocity-aware choking for chunk serving.
+//! - [`versioning`] — Signed manifest revisions and update channels.
 
 pub mod abr;
+pub mod announce;
+pub mod archive;
 pub mod chunker;
+pub mod download;
 pub mod earning;
+pub mod quota;
+pub mod receipts;
 pub mod reed_solomon;
+pub mod tombstone;
+pub mod upload;
+pub mod versioning;
 
 /// Error types for storage operations.
 #[derive(Debug, thiserror::Error)]
@@ -45,6 +61,19 @@ pub enum StorageError {
     #[error("storage allocation exceeded: used {used} of {limit} bytes")]
     AllocationExceeded { used: u64, limit: u64 },
 
+    /// Publishing would exceed a group storage quota.
+    #[error("{scope} storage quota exceeded: {used} + {requested} bytes over limit of {limit}")]
+    QuotaExceeded {
+        scope: quota::QuotaScope,
+        used: u64,
+        requested: u64,
+        limit: u64,
+    },
+
+    /// Group storage quota policy is malformed.
+    #[error("invalid quota policy: {0}")]
+    InvalidQuota(String),
+
     /// I/O error during storage operations.
     #[error("I/O error: {0}")]
     Io(String),
@@ -52,6 +81,34 @@ pub enum StorageError {
     /// Shard index out of range.
     #[error("shard index out of range: {index}, max {max}")]
     ShardIndexOutOfRange { index: usize, max: usize },
+
+    /// Manifest revision is malformed or does not fit its lineage.
+    #[error("invalid manifest revision: {0}")]
+    InvalidRevision(String),
+
+    /// Service receipt is malformed, misattributed, or badly signed.
+    #[error("invalid service receipt: {0}")]
+    InvalidReceipt(String),
+
+    /// Manifest revision signature verification failed.
+    #[error("invalid revision signature")]
+    InvalidSignature,
+
+    /// Tombstone is malformed or not signed by a Space authority.
+    #[error("invalid tombstone: {0}")]
+    InvalidTombstone(String),
+
+    /// Chunk belongs to tombstoned content.
+    #[error("chunk is tombstoned: {0}")]
+    Tombstoned(String),
+
+    /// Space archive is malformed, truncated, or fails verification.
+    #[error("invalid archive: {0}")]
+    InvalidArchive(String),
+
+    /// Update channel DHT record error.
+    #[error("dht error: {0}")]
+    Dht(String),
 }
 
 /// Convenience result type for storage operations.
@@ -0,0 +1,226 @@
+//! Group storage quotas.
+//!
+//! A Space's owner can cap how much storage its content consumes: in total,
+//! per contributing member, and per item. Usage is the size of the Space's
+//! live (non-tombstoned) content, attributed to the member who published
+//! it. A publish is checked against every configured limit before any
+//! chunking work starts; unset limits do not apply.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, StorageError};
+
+/// Limits a Space owner has set. `None` means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaPolicy {
+    /// Total bytes of live content in the Space.
+    pub max_total_bytes: Option<u64>,
+    /// Bytes of live content any one member may have published.
+    pub max_member_bytes: Option<u64>,
+    /// Size of a single item.
+    pub max_item_bytes: Option<u64>,
+}
+
+/// Which limit a publish would break.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaScope {
+    Group,
+    Member,
+    Item,
+}
+
+impl QuotaScope {
+    /// Wire name of the scope.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Group => "group",
+            Self::Member => "member",
+            Self::Item => "item",
+        }
+    }
+}
+
+impl std::fmt::Display for QuotaScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Bytes a Space and one of its members currently use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub group_bytes: u64,
+    pub member_bytes: u64,
+}
+
+impl QuotaPolicy {
+    /// Whether no limit is set.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_total_bytes.is_none()
+            && self.max_member_bytes.is_none()
+            && self.max_item_bytes.is_none()
+    }
+
+    /// Check that the policy is coherent: no limit is zero, and the member
+    /// and item limits do not exceed the total.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::InvalidQuota`] describing the first problem found
+    pub fn validate(&self) -> Result<()> {
+        let limits = [
+            ("max_total_bytes", self.max_total_bytes),
+            ("max_member_bytes", self.max_member_bytes),
+            ("max_item_bytes", self.max_item_bytes),
+        ];
+        if let Some((name, _)) = limits.iter().find(|(_, limit)| *limit == Some(0)) {
+            return Err(StorageError::InvalidQuota(format!(
+                "{name} must be positive"
+            )));
+        }
+        if let Some(total) = self.max_total_bytes {
+            for (name, limit) in &limits[1..] {
+                if limit.is_some_and(|l| l > total) {
+                    return Err(StorageError::InvalidQuota(format!(
+                        "{name} exceeds max_total_bytes"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a publish of `size` bytes by a member with `usage`.
+    ///
+    /// Limits are checked item first, then member, then group, so the
+    /// error names the narrowest limit the publish breaks.
+    ///
+    /// # Errors
+    ///
+    /// - [`StorageError::QuotaExceeded`] with the broken limit
+    pub fn check_publish(&self, usage: QuotaUsage, size: u64) -> Result<()> {
+        let checks = [
+            (QuotaScope::Item, 0, self.max_item_bytes),
+            (
+                QuotaScope::Member,
+                usage.member_bytes,
+                self.max_member_bytes,
+            ),
+            (QuotaScope::Group, usage.group_bytes, self.max_total_bytes),
+        ];
+        for (scope, used, limit) in checks {
+            let Some(limit) = limit else { continue };
+            if used.saturating_add(size) > limit {
+                return Err(StorageError::QuotaExceeded {
+                    scope,
+                    used,
+                    requested: size,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Bytes `usage` may still publish before a limit is hit, or `None`
+    /// if unlimited.
+    pub fn remaining(&self, usage: QuotaUsage) -> Option<u64> {
+        [
+            self.max_item_bytes,
+            self.max_member_bytes
+                .map(|l| l.saturating_sub(usage.member_bytes)),
+            self.max_total_bytes
+                .map(|l| l.saturating_sub(usage.group_bytes)),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1 << 30;
+
+    fn policy() -> QuotaPolicy {
+        QuotaPolicy {
+            max_total_bytes: Some(10 * GB),
+            max_member_bytes: Some(4 * GB),
+            max_item_bytes: Some(2 * GB),
+        }
+    }
+
+    fn usage(group_bytes: u64, member_bytes: u64) -> QuotaUsage {
+        QuotaUsage {
+            group_bytes,
+            member_bytes,
+        }
+    }
+
+    #[test]
+    fn test_unlimited_policy_allows_everything() {
+        let policy = QuotaPolicy::default();
+        assert!(policy.is_unlimited());
+        assert!(policy.check_publish(usage(u64::MAX, u64::MAX), 1).is_ok());
+        assert_eq!(policy.remaining(usage(0, 0)), None);
+    }
+
+    #[test]
+    fn test_publish_up_to_limit_is_allowed() {
+        assert!(policy()
+            .check_publish(usage(8 * GB, 2 * GB), 2 * GB)
+            .is_ok());
+        assert_eq!(policy().remaining(usage(8 * GB, 3 * GB)), Some(GB));
+    }
+
+    #[test]
+    fn test_narrowest_broken_limit_is_reported() {
+        let p = policy();
+        assert!(matches!(
+            p.check_publish(usage(0, 0), 3 * GB),
+            Err(StorageError::QuotaExceeded {
+                scope: QuotaScope::Item,
+                used: 0,
+                ..
+            })
+        ));
+        assert!(matches!(
+            p.check_publish(usage(9 * GB, 3 * GB), 2 * GB),
+            Err(StorageError::QuotaExceeded {
+                scope: QuotaScope::Member,
+                used,
+                limit,
+                ..
+            }) if used == 3 * GB && limit == 4 * GB
+        ));
+        assert!(matches!(
+            p.check_publish(usage(9 * GB, GB), 2 * GB),
+            Err(StorageError::QuotaExceeded {
+                scope: QuotaScope::Group,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_incoherent_policies() {
+        assert!(policy().validate().is_ok());
+        assert!(QuotaPolicy::default().validate().is_ok());
+        let zero = QuotaPolicy {
+            max_item_bytes: Some(0),
+            ..policy()
+        };
+        assert!(matches!(
+            zero.validate(),
+            Err(StorageError::InvalidQuota(_))
+        ));
+        let member_over_total = QuotaPolicy {
+            max_member_bytes: Some(11 * GB),
+            ..policy()
+        };
+        assert!(member_over_total.validate().is_err());
+    }
+}
@@ -0,0 +1,493 @@
+//! Service receipt aggregation and sampled batch verification (Section 14.7).
+//!
+//! A serving node collects one [`ServiceReceipt`] per chunk served. Rather
+//! than submitting every receipt, the [`ReceiptAggregator`] groups them per
+//! `(server_node_id, relay_epoch)` and seals each group into a
+//! [`SealedBatch`]: the receipts ordered by leaf hash and a
+//! [`BatchCommitment`] carrying the Merkle root over them together with the
+//! receipt count, total bytes, and number of distinct chunks.
+//!
+//! Only the commitment goes to the quorum. The quorum then picks
+//! [`SAMPLE_SIZE`] leaf positions with [`sample_indices`], seeded by a value
+//! the submitter could not predict when sealing (the epoch beacon), and the
+//! submitter answers with the receipts at those positions and their Merkle
+//! proofs. [`verify_samples`] checks each opening against the root, the
+//! batch's server and epoch, and both signatures; a batch padded with
+//! forged receipts fails as soon as one of them is sampled.
+//!
+//! ## Receipt signatures
+//!
+//! The requester acknowledges `"service-receipt" || server_node_id ||
+//! chunk_id || requester_circuit_id || requester_key || bytes_served ||
+//! timestamp || relay_epoch || nonce` (integers little-endian) with its
+//! ephemeral circuit key `requester_key`; the server signs the same bytes
+//! followed by `requester_ack` with its PIK.
+
+use std::collections::{BTreeMap, HashSet};
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, VerifyingKey};
+use ochra_types::network::ServiceReceipt;
+use serde::{Deserialize, Serialize};
+
+use crate::chunker::{build_merkle_root, generate_merkle_proof, MerkleProof};
+use crate::{Result, StorageError};
+
+/// Receipts opened per batch during quorum verification.
+pub const SAMPLE_SIZE: usize = 16;
+
+/// Bytes the requester acknowledges.
+pub fn ack_message(receipt: &ServiceReceipt) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(15 + 32 + 32 + 16 + 32 + 4 + 8 + 4 + 16);
+    msg.extend_from_slice(b"service-receipt");
+    msg.extend_from_slice(&receipt.server_node_id);
+    msg.extend_from_slice(&receipt.chunk_id);
+    msg.extend_from_slice(&receipt.requester_circuit_id);
+    msg.extend_from_slice(&receipt.requester_key);
+    msg.extend_from_slice(&receipt.bytes_served.to_le_bytes());
+    msg.extend_from_slice(&receipt.timestamp.to_le_bytes());
+    msg.extend_from_slice(&receipt.relay_epoch.to_le_bytes());
+    msg.extend_from_slice(&receipt.nonce);
+    msg
+}
+
+/// Bytes the server signs: the acknowledged bytes followed by the ack.
+pub fn server_message(receipt: &ServiceReceipt) -> Vec<u8> {
+    let mut msg = ack_message(receipt);
+    msg.extend_from_slice(&receipt.requester_ack);
+    msg
+}
+
+/// Merkle leaf committing to a complete receipt.
+pub fn receipt_leaf(receipt: &ServiceReceipt) -> [u8; 32] {
+    let mut data = server_message(receipt);
+    data.extend_from_slice(&receipt.server_sig);
+    blake3::merkle_leaf(&data)
+}
+
+/// Verify the requester's acknowledgement on a receipt.
+pub fn verify_requester_ack(receipt: &ServiceReceipt) -> Result<()> {
+    let vk = VerifyingKey::from_bytes(&receipt.requester_key)
+        .map_err(|_| StorageError::InvalidReceipt("malformed requester key".into()))?;
+    vk.verify(
+        &ack_message(receipt),
+        &Signature::from_bytes(&receipt.requester_ack),
+    )
+    .map_err(|_| StorageError::InvalidReceipt("bad requester acknowledgement".into()))
+}
+
+/// Verify the server signature on a receipt.
+pub fn verify_server_sig(receipt: &ServiceReceipt, server_key: &[u8; 32]) -> Result<()> {
+    let vk = VerifyingKey::from_bytes(server_key)
+        .map_err(|_| StorageError::InvalidReceipt("malformed server key".into()))?;
+    vk.verify(
+        &server_message(receipt),
+        &Signature::from_bytes(&receipt.server_sig),
+    )
+    .map_err(|_| StorageError::InvalidReceipt("bad server signature".into()))
+}
+
+/// Verify both signatures on a receipt.
+pub fn verify_receipt(receipt: &ServiceReceipt, server_key: &[u8; 32]) -> Result<()> {
+    verify_requester_ack(receipt)?;
+    verify_server_sig(receipt, server_key)
+}
+
+/// Public summary of a sealed batch, submitted to the quorum.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchCommitment {
+    /// Node that served the chunks.
+    pub server_node_id: [u8; 32],
+    /// Relay epoch the receipts belong to.
+    pub relay_epoch: u32,
+    /// Number of receipts (Merkle leaves).
+    pub receipt_count: u32,
+    /// Sum of `bytes_served`.
+    pub total_bytes: u64,
+    /// Number of distinct chunks served.
+    pub distinct_chunks: u32,
+    /// Merkle root over the receipt leaves, in leaf order.
+    pub merkle_root: [u8; 32],
+}
+
+/// A sampled receipt with its inclusion proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SampleOpening {
+    /// Leaf position in the batch.
+    pub index: u32,
+    /// The receipt at that position.
+    pub receipt: ServiceReceipt,
+    /// Merkle proof from the receipt leaf to the batch root.
+    pub proof: MerkleProof,
+}
+
+/// A batch of receipts held by the submitter until the quorum samples it.
+#[derive(Clone, Debug)]
+pub struct SealedBatch {
+    /// The commitment submitted to the quorum.
+    pub commitment: BatchCommitment,
+    receipts: Vec<ServiceReceipt>,
+    leaves: Vec<[u8; 32]>,
+}
+
+impl SealedBatch {
+    fn seal(receipts: Vec<ServiceReceipt>) -> Option<Self> {
+        let first = receipts.first()?;
+        let (server_node_id, relay_epoch) = (first.server_node_id, first.relay_epoch);
+        let mut ordered: Vec<([u8; 32], ServiceReceipt)> = receipts
+            .into_iter()
+            .map(|r| (receipt_leaf(&r), r))
+            .collect();
+        ordered.sort_by_key(|(leaf, _)| *leaf);
+        let (leaves, receipts): (Vec<_>, Vec<_>) = ordered.into_iter().unzip();
+
+        let distinct: HashSet<&[u8; 32]> = receipts.iter().map(|r| &r.chunk_id).collect();
+        let commitment = BatchCommitment {
+            server_node_id,
+            relay_epoch,
+            receipt_count: leaves.len() as u32,
+            total_bytes: receipts.iter().map(|r| u64::from(r.bytes_served)).sum(),
+            distinct_chunks: distinct.len() as u32,
+            merkle_root: build_merkle_root(&leaves),
+        };
+        Some(Self {
+            commitment,
+            receipts,
+            leaves,
+        })
+    }
+
+    /// The receipts in leaf order.
+    pub fn receipts(&self) -> &[ServiceReceipt] {
+        &self.receipts
+    }
+
+    /// Open the receipts at `indices`.
+    pub fn open(&self, indices: &[u32]) -> Result<Vec<SampleOpening>> {
+        indices
+            .iter()
+            .map(|&index| {
+                let i = index as usize;
+                let receipt = self
+                    .receipts
+                    .get(i)
+                    .ok_or(StorageError::MerkleVerification)?
+                    .clone();
+                Ok(SampleOpening {
+                    index,
+                    receipt,
+                    proof: generate_merkle_proof(&self.leaves, i)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Buffers receipts and seals them into per-(server, epoch) batches.
+///
+/// A receipt is accepted once: repeats of a `(server, epoch, nonce)` are
+/// dropped, as is anything for a relay epoch already sealed with
+/// [`ReceiptAggregator::seal_through`].
+#[derive(Debug, Default)]
+pub struct ReceiptAggregator {
+    pending: BTreeMap<([u8; 32], u32), Vec<ServiceReceipt>>,
+    seen: HashSet<([u8; 32], u32, [u8; 16])>,
+    sealed_through: Option<u32>,
+}
+
+impl ReceiptAggregator {
+    /// Create an empty aggregator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffer a receipt. Returns `false` if it repeats one already added or
+    /// belongs to a relay epoch that has been sealed.
+    pub fn add(&mut self, receipt: ServiceReceipt) -> bool {
+        if self
+            .sealed_through
+            .is_some_and(|sealed| receipt.relay_epoch <= sealed)
+        {
+            return false;
+        }
+        if !self
+            .seen
+            .insert((receipt.server_node_id, receipt.relay_epoch, receipt.nonce))
+        {
+            return false;
+        }
+        self.pending
+            .entry((receipt.server_node_id, receipt.relay_epoch))
+            .or_default()
+            .push(receipt);
+        true
+    }
+
+    /// Number of buffered receipts.
+    pub fn pending_count(&self) -> usize {
+        self.pending.values().map(Vec::len).sum()
+    }
+
+    /// Seal every group for relay epochs up to and including `relay_epoch`
+    /// and stop accepting receipts for them.
+    pub fn seal_through(&mut self, relay_epoch: u32) -> Vec<SealedBatch> {
+        self.sealed_through = Some(
+            self.sealed_through
+                .map_or(relay_epoch, |s| s.max(relay_epoch)),
+        );
+        self.seen.retain(|(_, epoch, _)| *epoch > relay_epoch);
+        let keys: Vec<([u8; 32], u32)> = self
+            .pending
+            .keys()
+            .filter(|(_, epoch)| *epoch <= relay_epoch)
+            .copied()
+            .collect();
+        self.seal_keys(keys)
+    }
+
+    /// Seal every buffered group now. Receipts arriving later for the same
+    /// epochs go into new batches.
+    pub fn seal_all(&mut self) -> Vec<SealedBatch> {
+        let keys: Vec<([u8; 32], u32)> = self.pending.keys().copied().collect();
+        self.seal_keys(keys)
+    }
+
+    fn seal_keys(&mut self, keys: Vec<([u8; 32], u32)>) -> Vec<SealedBatch> {
+        keys.into_iter()
+            .filter_map(|key| self.pending.remove(&key))
+            .filter_map(SealedBatch::seal)
+            .collect()
+    }
+}
+
+/// Leaf positions the quorum samples from a batch.
+///
+/// Positions are drawn from `BLAKE3::hash(seed || merkle_root || counter)`
+/// and are distinct; every position is returned when the batch holds no
+/// more than [`SAMPLE_SIZE`] receipts.
+pub fn sample_indices(commitment: &BatchCommitment, seed: &[u8; 32]) -> Vec<u32> {
+    let count = commitment.receipt_count;
+    if count as usize <= SAMPLE_SIZE {
+        return (0..count).collect();
+    }
+    let mut picked = Vec::with_capacity(SAMPLE_SIZE);
+    let mut counter = 0u32;
+    while picked.len() < SAMPLE_SIZE {
+        let mut input = Vec::with_capacity(32 + 32 + 4);
+        input.extend_from_slice(seed);
+        input.extend_from_slice(&commitment.merkle_root);
+        input.extend_from_slice(&counter.to_le_bytes());
+        let digest = blake3::hash(&input);
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&digest[..8]);
+        let index = (u64::from_le_bytes(word) % u64::from(count)) as u32;
+        if !picked.contains(&index) {
+            picked.push(index);
+        }
+        counter += 1;
+    }
+    picked
+}
+
+/// Verify the openings of a batch against its commitment.
+///
+/// # Errors
+///
+/// - [`StorageError::MerkleVerification`] if the openings are not exactly
+///   the sampled positions or a proof does not lead to the root
+/// - [`StorageError::InvalidReceipt`] if a receipt names another server or
+///   epoch, or its requester acknowledgement or server signature fails
+pub fn verify_samples(
+    commitment: &BatchCommitment,
+    seed: &[u8; 32],
+    openings: &[SampleOpening],
+    server_key: &[u8; 32],
+) -> Result<()> {
+    let expected = sample_indices(commitment, seed);
+    if openings.len() != expected.len()
+        || openings.iter().zip(&expected).any(|(o, &i)| o.index != i)
+    {
+        return Err(StorageError::MerkleVerification);
+    }
+    for opening in openings {
+        let receipt = &opening.receipt;
+        if receipt.server_node_id != commitment.server_node_id
+            || receipt.relay_epoch != commitment.relay_epoch
+        {
+            return Err(StorageError::InvalidReceipt(
+                "sampled receipt belongs to another batch".into(),
+            ));
+        }
+        if !proof_matches(
+            &commitment.merkle_root,
+            &receipt_leaf(receipt),
+            &opening.proof,
+            opening.index,
+        ) {
+            return Err(StorageError::MerkleVerification);
+        }
+        verify_receipt(receipt, server_key)?;
+    }
+    Ok(())
+}
+
+/// Check a Merkle proof, binding it to the leaf position `index`.
+fn proof_matches(root: &[u8; 32], leaf: &[u8; 32], proof: &MerkleProof, index: u32) -> bool {
+    let mut current = *leaf;
+    let mut position = index;
+    for (sibling, is_left) in &proof.siblings {
+        if *is_left != (position & 1 == 1) {
+            return false;
+        }
+        current = if *is_left {
+            blake3::merkle_inner(sibling, &current)
+        } else {
+            blake3::merkle_inner(&current, sibling)
+        };
+        position >>= 1;
+    }
+    current == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::SigningKey;
+
+    fn receipt(server: &SigningKey, chunk: u8, nonce: u8, epoch: u32) -> ServiceReceipt {
+        let requester = SigningKey::generate();
+        let mut r = ServiceReceipt {
+            server_node_id: server.verifying_key().to_bytes(),
+            chunk_id: [chunk; 32],
+            requester_circuit_id: [9; 16],
+            requester_key: requester.verifying_key().to_bytes(),
+            bytes_served: 1000,
+            timestamp: 1_700_000_000,
+            relay_epoch: epoch,
+            nonce: [nonce; 16],
+            requester_ack: [0; 64],
+            server_sig: [0; 64],
+        };
+        r.requester_ack = requester.sign(&ack_message(&r)).to_bytes();
+        r.server_sig = server.sign(&server_message(&r)).to_bytes();
+        r
+    }
+
+    #[test]
+    fn test_groups_by_server_and_epoch() {
+        let a = SigningKey::generate();
+        let b = SigningKey::generate();
+        let mut agg = ReceiptAggregator::new();
+        assert!(agg.add(receipt(&a, 1, 1, 7)));
+        assert!(agg.add(receipt(&a, 1, 2, 7)));
+        assert!(agg.add(receipt(&a, 2, 3, 8)));
+        assert!(agg.add(receipt(&b, 3, 1, 7)));
+        assert!(!agg.add(receipt(&a, 4, 1, 7)), "duplicate nonce");
+        assert_eq!(agg.pending_count(), 4);
+
+        let batches = agg.seal_through(7);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(agg.pending_count(), 1);
+        assert!(!agg.add(receipt(&a, 5, 5, 7)), "epoch already sealed");
+        let ours = batches
+            .iter()
+            .find(|b| b.commitment.server_node_id == a.verifying_key().to_bytes())
+            .expect("batch for a");
+        assert_eq!(ours.commitment.receipt_count, 2);
+        assert_eq!(ours.commitment.total_bytes, 2000);
+        assert_eq!(ours.commitment.distinct_chunks, 1);
+    }
+
+    #[test]
+    fn test_sampled_batch_verifies() {
+        let server = SigningKey::generate();
+        let mut agg = ReceiptAggregator::new();
+        for i in 0..40u8 {
+            agg.add(receipt(&server, i % 5, i, 3));
+        }
+        let batch = agg.seal_all().pop().expect("batch");
+        let seed = [42u8; 32];
+        let indices = sample_indices(&batch.commitment, &seed);
+        assert_eq!(indices.len(), SAMPLE_SIZE);
+
+        let openings = batch.open(&indices).expect("open");
+        let key = server.verifying_key().to_bytes();
+        verify_samples(&batch.commitment, &seed, &openings, &key).expect("verify");
+    }
+
+    #[test]
+    fn test_small_batch_opens_everything() {
+        let server = SigningKey::generate();
+        let mut agg = ReceiptAggregator::new();
+        agg.add(receipt(&server, 1, 1, 3));
+        agg.add(receipt(&server, 2, 2, 3));
+        let batch = agg.seal_all().pop().expect("batch");
+        assert_eq!(sample_indices(&batch.commitment, &[0; 32]), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_forged_receipt_detected() {
+        let server = SigningKey::generate();
+        let mut agg = ReceiptAggregator::new();
+        for i in 0..4u8 {
+            agg.add(receipt(&server, i, i, 3));
+        }
+        let mut forged = receipt(&server, 9, 99, 3);
+        forged.server_sig = [7; 64];
+        agg.add(forged);
+        let batch = agg.seal_all().pop().expect("batch");
+        let seed = [1u8; 32];
+        let openings = batch
+            .open(&sample_indices(&batch.commitment, &seed))
+            .expect("open");
+        let key = server.verifying_key().to_bytes();
+        assert!(verify_samples(&batch.commitment, &seed, &openings, &key).is_err());
+    }
+
+    #[test]
+    fn test_opening_at_wrong_position_rejected() {
+        let server = SigningKey::generate();
+        let mut agg = ReceiptAggregator::new();
+        for i in 0..4u8 {
+            agg.add(receipt(&server, i, i, 3));
+        }
+        let batch = agg.seal_all().pop().expect("batch");
+        let seed = [1u8; 32];
+        let mut openings = batch
+            .open(&sample_indices(&batch.commitment, &seed))
+            .expect("open");
+        openings.swap(0, 1);
+        openings[0].index = 0;
+        openings[1].index = 1;
+        let key = server.verifying_key().to_bytes();
+        assert!(verify_samples(&batch.commitment, &seed, &openings, &key).is_err());
+    }
+
+    #[test]
+    fn test_forged_requester_ack_detected() {
+        let server = SigningKey::generate();
+        let mut agg = ReceiptAggregator::new();
+        for i in 0..4u8 {
+            agg.add(receipt(&server, i, i, 3));
+        }
+        // The server signs over an acknowledgement nobody gave.
+        let mut forged = receipt(&server, 9, 99, 3);
+        forged.requester_ack = [7; 64];
+        forged.server_sig = server.sign(&server_message(&forged)).to_bytes();
+        assert!(matches!(
+            verify_requester_ack(&forged),
+            Err(StorageError::InvalidReceipt(_))
+        ));
+        agg.add(forged);
+        let batch = agg.seal_all().pop().expect("batch");
+        let seed = [1u8; 32];
+        let openings = batch
+            .open(&sample_indices(&batch.commitment, &seed))
+            .expect("open");
+        let key = server.verifying_key().to_bytes();
+        assert!(matches!(
+            verify_samples(&batch.commitment, &seed, &openings, &key),
+            Err(StorageError::InvalidReceipt(_))
+        ));
+    }
+}
@@ -0,0 +1,425 @@
+//! Signed content tombstones (Section 16.6).
+//!
+//! A Host or Moderator removes content from a Space by signing a
+//! [`Tombstone`] naming the content hash and a reason code. The tombstone is
+//! published to the DHT and gossiped so nodes that never saw the Space's MLS
+//! traffic (ABR nodes in particular) learn of it.
+//!
+//! ## Verification
+//!
+//! A tombstone is honoured only if its signature verifies and the signer is
+//! one of the Space's authorities: the Host or a listed Moderator
+//! ([`space_authorities`]). Anyone can sign a tombstone; only authorised ones
+//! reach the [`TombstoneRegistry`].
+//!
+//! ## DHT record
+//!
+//! The signer advertises the tombstone in a BEP 44 mutable record keyed by
+//! `BLAKE3::hash(signer_pik || "tombstone" || content_hash)`, with the
+//! issue time as sequence number. Nodes that know a Space's authorities can
+//! look the record up for any content hash.
+//!
+//! ## Retention
+//!
+//! Honouring a tombstone drops the content's chunks from the ABR store, but
+//! the tombstone itself is kept so the node can show why it no longer serves
+//! them and refuse to store them again.
+
+use std::collections::HashMap;
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use ochra_dht::bep44::{create_mutable_record, DhtRecord};
+use ochra_types::space::SpaceManifest;
+use ochra_types::{ContentHash, GroupId, Hash};
+
+use crate::{Result, StorageError};
+
+/// Salt prefix for tombstone DHT records.
+pub const TOMBSTONE_SALT_PREFIX: &[u8] = b"tombstone";
+
+/// Encoded size of a [`Tombstone`].
+pub const TOMBSTONE_SIZE: usize = 32 + 32 + 1 + 8 + 32 + 64;
+
+/// Why content was tombstoned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum TombstoneReason {
+    /// Removed at the Host's discretion.
+    Removed = 0,
+    /// Spam.
+    Spam = 1,
+    /// Offensive content.
+    Offensive = 2,
+    /// Broken or unplayable content.
+    Broken = 3,
+    /// Legal request (e.g. copyright).
+    Legal = 4,
+    /// Any other reason.
+    Other = 5,
+}
+
+impl TombstoneReason {
+    /// Decode a wire reason code.
+    pub fn from_u8(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Removed),
+            1 => Some(Self::Spam),
+            2 => Some(Self::Offensive),
+            3 => Some(Self::Broken),
+            4 => Some(Self::Legal),
+            5 => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// Parse an RPC string: "removed" | "spam" | "offensive" | "broken" |
+    /// "legal" | "other".
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "removed" => Some(Self::Removed),
+            "spam" => Some(Self::Spam),
+            "offensive" => Some(Self::Offensive),
+            "broken" => Some(Self::Broken),
+            "legal" => Some(Self::Legal),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+/// A signed statement that content was removed from a Space.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tombstone {
+    /// Space the content was published in.
+    pub group_id: GroupId,
+    /// Content being removed.
+    pub content_hash: ContentHash,
+    /// Reason code.
+    pub reason: TombstoneReason,
+    /// Unix timestamp of issue.
+    pub issued_at: u64,
+    /// Signer's PIK public key (Host or Moderator).
+    pub signer_pik: [u8; 32],
+    /// Signature over [`Tombstone::signing_message`].
+    pub sig: [u8; 64],
+}
+
+impl Tombstone {
+    /// Create and sign a tombstone.
+    pub fn new(
+        signing_key: &SigningKey,
+        group_id: GroupId,
+        content_hash: ContentHash,
+        reason: TombstoneReason,
+        issued_at: u64,
+    ) -> Self {
+        let mut tombstone = Self {
+            group_id,
+            content_hash,
+            reason,
+            issued_at,
+            signer_pik: signing_key.verifying_key().to_bytes(),
+            sig: [0u8; 64],
+        };
+        tombstone.sig = signing_key.sign(&tombstone.signing_message()).to_bytes();
+        tombstone
+    }
+
+    /// Bytes covered by the signature.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(TOMBSTONE_SIZE);
+        msg.extend_from_slice(b"content-tombstone");
+        msg.extend_from_slice(&self.group_id);
+        msg.extend_from_slice(&self.content_hash);
+        msg.push(self.reason as u8);
+        msg.extend_from_slice(&self.issued_at.to_le_bytes());
+        msg.extend_from_slice(&self.signer_pik);
+        msg
+    }
+
+    /// Identifier of this tombstone, recorded against purged chunks.
+    pub fn tombstone_id(&self) -> Hash {
+        blake3::hash(&self.to_bytes())
+    }
+
+    /// Verify the signature.
+    pub fn verify(&self) -> Result<()> {
+        let vk = VerifyingKey::from_bytes(&self.signer_pik)
+            .map_err(|_| StorageError::InvalidSignature)?;
+        vk.verify(&self.signing_message(), &Signature::from_bytes(&self.sig))
+            .map_err(|_| StorageError::InvalidSignature)
+    }
+
+    /// Verify the signature and that the signer is one of `authorities`.
+    pub fn verify_authorized(&self, authorities: &[[u8; 32]]) -> Result<()> {
+        self.verify()?;
+        if !authorities.contains(&self.signer_pik) {
+            return Err(StorageError::InvalidTombstone(
+                "signer is not a Host or Moderator of the Space".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Encode to the fixed-size wire form used in DHT records and gossip.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(TOMBSTONE_SIZE);
+        out.extend_from_slice(&self.group_id);
+        out.extend_from_slice(&self.content_hash);
+        out.push(self.reason as u8);
+        out.extend_from_slice(&self.issued_at.to_le_bytes());
+        out.extend_from_slice(&self.signer_pik);
+        out.extend_from_slice(&self.sig);
+        out
+    }
+
+    /// Decode from the fixed-size wire form.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != TOMBSTONE_SIZE {
+            return Err(StorageError::InvalidTombstone(format!(
+                "expected {TOMBSTONE_SIZE} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        let take32 = |at: usize| -> [u8; 32] {
+            let mut out = [0u8; 32];
+            out.copy_from_slice(&bytes[at..at + 32]);
+            out
+        };
+        let reason = TombstoneReason::from_u8(bytes[64]).ok_or_else(|| {
+            StorageError::InvalidTombstone(format!("unknown reason code {}", bytes[64]))
+        })?;
+        let mut issued_at = [0u8; 8];
+        issued_at.copy_from_slice(&bytes[65..73]);
+        let mut sig = [0u8; 64];
+        sig.copy_from_slice(&bytes[105..169]);
+        Ok(Self {
+            group_id: take32(0),
+            content_hash: take32(32),
+            reason,
+            issued_at: u64::from_le_bytes(issued_at),
+            signer_pik: take32(73),
+            sig,
+        })
+    }
+}
+
+/// The keys allowed to tombstone content in a Space: the Host and every
+/// Moderator.
+pub fn space_authorities(manifest: &SpaceManifest) -> Vec<[u8; 32]> {
+    let mut authorities = Vec::with_capacity(1 + manifest.moderator_piks.len());
+    authorities.push(manifest.host_pik);
+    authorities.extend_from_slice(&manifest.moderator_piks);
+    authorities
+}
+
+fn tombstone_salt(content_hash: &ContentHash) -> Vec<u8> {
+    let mut salt = Vec::with_capacity(TOMBSTONE_SALT_PREFIX.len() + 32);
+    salt.extend_from_slice(TOMBSTONE_SALT_PREFIX);
+    salt.extend_from_slice(content_hash);
+    salt
+}
+
+/// DHT storage key of `signer_pik`'s tombstone for `content_hash`.
+pub fn tombstone_key(signer_pik: &[u8; 32], content_hash: &ContentHash) -> [u8; 32] {
+    let mut input = Vec::with_capacity(32 + TOMBSTONE_SALT_PREFIX.len() + 32);
+    input.extend_from_slice(signer_pik);
+    input.extend_from_slice(&tombstone_salt(content_hash));
+    blake3::hash(&input)
+}
+
+/// Build the DHT record advertising `tombstone`.
+pub fn tombstone_record(signing_key: &SigningKey, tombstone: &Tombstone) -> Result<DhtRecord> {
+    if signing_key.verifying_key().to_bytes() != tombstone.signer_pik {
+        return Err(StorageError::InvalidTombstone(
+            "tombstone record must be signed by the tombstone signer".to_string(),
+        ));
+    }
+    create_mutable_record(
+        signing_key,
+        &tombstone_salt(&tombstone.content_hash),
+        tombstone.issued_at,
+        tombstone.to_bytes(),
+    )
+    .map_err(|e| StorageError::Dht(e.to_string()))
+}
+
+/// Validate a tombstone DHT record and extract the tombstone.
+///
+/// Only the signature is checked; the caller still decides whether the
+/// signer is authorised.
+pub fn parse_tombstone_record(record: &DhtRecord) -> Result<Tombstone> {
+    record
+        .validate()
+        .map_err(|e| StorageError::Dht(e.to_string()))?;
+    let DhtRecord::Mutable {
+        public_key,
+        salt,
+        seq,
+        value,
+        ..
+    } = record
+    else {
+        return Err(StorageError::Dht(
+            "tombstone must be a mutable record".to_string(),
+        ));
+    };
+    let tombstone = Tombstone::from_bytes(value)?;
+    tombstone.verify()?;
+    if tombstone.signer_pik != *public_key
+        || *salt != tombstone_salt(&tombstone.content_hash)
+        || *seq != tombstone.issued_at
+    {
+        return Err(StorageError::InvalidTombstone(
+            "tombstone record does not match its tombstone".to_string(),
+        ));
+    }
+    Ok(tombstone)
+}
+
+/// Verified tombstones, kept as proof after the content is purged.
+#[derive(Debug, Default)]
+pub struct TombstoneRegistry {
+    tombstones: HashMap<ContentHash, Tombstone>,
+}
+
+impl TombstoneRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verify `tombstone` against the Space's `authorities` and record it.
+    ///
+    /// Returns `true` if the content was not already tombstoned. A later
+    /// tombstone for the same content is a no-op; the first one is kept as
+    /// the proof.
+    pub fn accept(&mut self, tombstone: Tombstone, authorities: &[[u8; 32]]) -> Result<bool> {
+        tombstone.verify_authorized(authorities)?;
+        if self.tombstones.contains_key(&tombstone.content_hash) {
+            return Ok(false);
+        }
+        tracing::debug!(
+            content_hash = hex::encode(tombstone.content_hash),
+            reason = ?tombstone.reason,
+            "accepted content tombstone"
+        );
+        self.tombstones.insert(tombstone.content_hash, tombstone);
+        Ok(true)
+    }
+
+    /// Whether `content_hash` has been tombstoned.
+    pub fn is_tombstoned(&self, content_hash: &ContentHash) -> bool {
+        self.tombstones.contains_key(content_hash)
+    }
+
+    /// The tombstone proving `content_hash` was removed.
+    pub fn proof(&self, content_hash: &ContentHash) -> Option<&Tombstone> {
+        self.tombstones.get(content_hash)
+    }
+
+    /// All recorded tombstones.
+    pub fn iter(&self) -> impl Iterator<Item = &Tombstone> {
+        self.tombstones.values()
+    }
+
+    /// Number of recorded tombstones.
+    pub fn len(&self) -> usize {
+        self.tombstones.len()
+    }
+
+    /// Whether no tombstones are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.tombstones.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::KeyPair;
+
+    const GROUP: GroupId = [7u8; 32];
+    const CONTENT: ContentHash = [9u8; 32];
+
+    fn tombstone(kp: &KeyPair) -> Tombstone {
+        Tombstone::new(
+            &kp.signing_key,
+            GROUP,
+            CONTENT,
+            TombstoneReason::Spam,
+            1_700_000_000,
+        )
+    }
+
+    #[test]
+    fn test_sign_verify_roundtrip() {
+        let kp = KeyPair::generate();
+        let t = tombstone(&kp);
+        t.verify().expect("valid");
+        let bytes = t.to_bytes();
+        assert_eq!(bytes.len(), TOMBSTONE_SIZE);
+        let decoded = Tombstone::from_bytes(&bytes).expect("decode");
+        assert_eq!(decoded, t);
+        assert_eq!(decoded.tombstone_id(), t.tombstone_id());
+    }
+
+    #[test]
+    fn test_tampered_tombstone_rejected() {
+        let kp = KeyPair::generate();
+        let mut t = tombstone(&kp);
+        t.content_hash = [10u8; 32];
+        assert!(t.verify().is_err());
+
+        let mut bytes = tombstone(&kp).to_bytes();
+        bytes[64] = 200;
+        assert!(Tombstone::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_registry_requires_authority() {
+        let host = KeyPair::generate();
+        let outsider = KeyPair::generate();
+        let authorities = [host.verifying_key.to_bytes()];
+        let mut registry = TombstoneRegistry::new();
+
+        assert!(registry.accept(tombstone(&outsider), &authorities).is_err());
+        assert!(!registry.is_tombstoned(&CONTENT));
+
+        assert!(registry
+            .accept(tombstone(&host), &authorities)
+            .expect("authorised"));
+        assert!(registry.is_tombstoned(&CONTENT));
+    }
+
+    #[test]
+    fn test_registry_keeps_first_proof() {
+        let host = KeyPair::generate();
+        let moderator = KeyPair::generate();
+        let authorities = [
+            host.verifying_key.to_bytes(),
+            moderator.verifying_key.to_bytes(),
+        ];
+        let mut registry = TombstoneRegistry::new();
+        let first = tombstone(&moderator);
+        assert!(registry.accept(first.clone(), &authorities).expect("first"));
+        assert!(!registry
+            .accept(tombstone(&host), &authorities)
+            .expect("second"));
+        assert_eq!(registry.proof(&CONTENT), Some(&first));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_dht_record_roundtrip() {
+        let kp = KeyPair::generate();
+        let t = tombstone(&kp);
+        let record = tombstone_record(&kp.signing_key, &t).expect("record");
+        assert_eq!(record.storage_key(), tombstone_key(&t.signer_pik, &CONTENT));
+        assert_eq!(parse_tombstone_record(&record).expect("parse"), t);
+
+        let other = KeyPair::generate();
+        assert!(tombstone_record(&other.signing_key, &t).is_err());
+    }
+}
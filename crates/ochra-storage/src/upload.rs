@@ -0,0 +1,354 @@
+//! Upload slot management and choking for chunk serving.
+//!
+//! A node that answers every `ChunkRequest` at once splits its upload
+//! bandwidth so thinly that no requester finishes quickly, and a burst of
+//! requests can starve the node's own traffic. The [`UploadManager`] serves
+//! only the requesters holding one of a bounded number of upload slots
+//! ("unchoked"); everyone else is queued and told their queue position, so
+//! they can back off or try another provider instead of re-sending.
+//!
+//! ## Choking
+//!
+//! Every [`RECHOKE_INTERVAL_SECS`] the manager re-ranks the requesters that
+//! asked for something within [`INTEREST_TTL_SECS`] by reciprocity credit
+//! and unchokes the top [`REGULAR_SLOTS`]. Credit is earned by serving
+//! chunks to this node and by acknowledging service receipts for chunks
+//! this node served (the receipts the node is paid for), and halves at
+//! every rechoke so it reflects recent behaviour.
+//!
+//! One further slot is an optimistic unchoke, rotated every
+//! [`OPTIMISTIC_ROTATION_SECS`] to the choked requester that has waited
+//! longest. It lets newcomers with no credit get started and earn some.
+//!
+//! Requesters are identified by a 32-byte key: the node ID on a direct
+//! connection, or a stable per-circuit key for requests arriving over
+//! Sphinx.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Upload slots assigned by reciprocity rank.
+pub const REGULAR_SLOTS: usize = 4;
+
+/// Seconds between rechoke passes.
+pub const RECHOKE_INTERVAL_SECS: u64 = 10;
+
+/// Seconds between optimistic unchoke rotations.
+pub const OPTIMISTIC_ROTATION_SECS: u64 = 30;
+
+/// Seconds after its last request that a requester stops competing for a
+/// slot.
+pub const INTEREST_TTL_SECS: u64 = 60;
+
+/// Maximum number of queued requesters; later requests are rejected.
+pub const MAX_QUEUE_LEN: usize = 64;
+
+/// Upper bound on the back-off suggested to a queued requester.
+pub const MAX_RETRY_AFTER_SECS: u32 = 120;
+
+/// What to do with an incoming chunk request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadDecision {
+    /// The requester holds a slot: serve the chunk.
+    Serve,
+    /// The requester is choked and queued.
+    Queued {
+        /// 1-based position in the queue.
+        position: u32,
+        /// Suggested seconds before asking again.
+        retry_after_secs: u32,
+    },
+    /// The queue is full.
+    Rejected,
+}
+
+/// Slot changes made by a rechoke pass.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RechokeOutcome {
+    /// Requesters that gained a slot.
+    pub unchoked: Vec<[u8; 32]>,
+    /// Requesters that lost their slot.
+    pub choked: Vec<[u8; 32]>,
+}
+
+/// Per-requester bookkeeping.
+#[derive(Clone, Debug, Default)]
+struct PeerState {
+    /// Decaying reciprocity credit in bytes.
+    credit: f64,
+    /// Time of the requester's last chunk request.
+    last_request: Option<u64>,
+}
+
+/// Bounded upload slots with reciprocity-aware choking.
+#[derive(Debug, Default)]
+pub struct UploadManager {
+    peers: HashMap<[u8; 32], PeerState>,
+    /// Requesters holding a regular slot.
+    unchoked: HashSet<[u8; 32]>,
+    /// Requester holding the optimistic slot.
+    optimistic: Option<[u8; 32]>,
+    /// Choked requesters in arrival order.
+    queue: VecDeque<[u8; 32]>,
+    last_rechoke: Option<u64>,
+    last_rotation: Option<u64>,
+}
+
+impl UploadManager {
+    /// Create a manager with no requesters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `peer` currently holds a slot.
+    pub fn is_unchoked(&self, peer: &[u8; 32]) -> bool {
+        self.unchoked.contains(peer) || self.optimistic.as_ref() == Some(peer)
+    }
+
+    /// Number of queued requesters.
+    pub fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Number of occupied slots.
+    pub fn active_slots(&self) -> usize {
+        self.unchoked.len() + usize::from(self.optimistic.is_some())
+    }
+
+    /// Decide how to handle a chunk request from `peer`.
+    ///
+    /// Free slots are handed out immediately; otherwise the requester joins
+    /// (or keeps its place in) the queue.
+    pub fn request(&mut self, peer: [u8; 32], now: u64) -> UploadDecision {
+        self.peers.entry(peer).or_default().last_request = Some(now);
+        if self.is_unchoked(&peer) {
+            return UploadDecision::Serve;
+        }
+        if self.unchoked.len() < REGULAR_SLOTS {
+            self.queue.retain(|p| *p != peer);
+            self.unchoked.insert(peer);
+            return UploadDecision::Serve;
+        }
+        let position = match self.queue.iter().position(|p| *p == peer) {
+            Some(i) => i,
+            None if self.queue.len() >= MAX_QUEUE_LEN => return UploadDecision::Rejected,
+            None => {
+                self.queue.push_back(peer);
+                self.queue.len() - 1
+            }
+        };
+        UploadDecision::Queued {
+            position: (position + 1) as u32,
+            retry_after_secs: retry_after(position),
+        }
+    }
+
+    /// Credit `peer` for serving `bytes` to this node.
+    pub fn record_served_to_us(&mut self, peer: [u8; 32], bytes: u64) {
+        self.peers.entry(peer).or_default().credit += bytes as f64;
+    }
+
+    /// Credit `peer` for acknowledging a receipt for `bytes` served to it.
+    pub fn record_receipt(&mut self, peer: [u8; 32], bytes: u64) {
+        self.peers.entry(peer).or_default().credit += bytes as f64;
+    }
+
+    /// Re-rank requesters and reassign slots if a rechoke is due.
+    ///
+    /// Returns `None` when called before [`RECHOKE_INTERVAL_SECS`] has
+    /// passed since the previous pass.
+    pub fn rechoke(&mut self, now: u64) -> Option<RechokeOutcome> {
+        if self
+            .last_rechoke
+            .is_some_and(|at| now.saturating_sub(at) < RECHOKE_INTERVAL_SECS)
+        {
+            return None;
+        }
+        self.last_rechoke = Some(now);
+
+        // Forget requesters that lost interest.
+        let interested = |p: &PeerState| {
+            p.last_request
+                .is_some_and(|at| now.saturating_sub(at) < INTEREST_TTL_SECS)
+        };
+        let idle: HashSet<[u8; 32]> = self
+            .peers
+            .iter()
+            .filter(|(_, p)| !interested(p))
+            .map(|(id, _)| *id)
+            .collect();
+        self.queue.retain(|p| !idle.contains(p));
+
+        let mut ranked: Vec<([u8; 32], f64)> = self
+            .peers
+            .iter()
+            .filter(|(_, p)| interested(p))
+            .map(|(id, p)| (*id, p.credit))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        let next: HashSet<[u8; 32]> = ranked
+            .iter()
+            .take(REGULAR_SLOTS)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let rotate = self
+            .last_rotation
+            .is_none_or(|at| now.saturating_sub(at) >= OPTIMISTIC_ROTATION_SECS);
+        let mut optimistic = self
+            .optimistic
+            .filter(|p| !rotate && !idle.contains(p) && !next.contains(p));
+        if optimistic.is_none() {
+            optimistic = self.queue.iter().find(|p| !next.contains(*p)).copied();
+            self.last_rotation = Some(now);
+        }
+
+        let before: HashSet<[u8; 32]> = self
+            .unchoked
+            .iter()
+            .copied()
+            .chain(self.optimistic)
+            .collect();
+        let after: HashSet<[u8; 32]> = next.iter().copied().chain(optimistic).collect();
+        self.queue.retain(|p| !after.contains(p));
+        // Requesters losing their slot but still interested wait at the back.
+        let mut demoted: Vec<[u8; 32]> = before
+            .difference(&after)
+            .filter(|p| !idle.contains(*p) && !self.queue.contains(*p))
+            .copied()
+            .collect();
+        demoted.sort();
+        self.queue.extend(demoted);
+        self.unchoked = next;
+        self.optimistic = optimistic;
+
+        for peer in self.peers.values_mut() {
+            peer.credit /= 2.0;
+        }
+        self.peers
+            .retain(|id, p| !idle.contains(id) || p.credit >= 1.0);
+        let mut outcome = RechokeOutcome {
+            unchoked: after.difference(&before).copied().collect(),
+            choked: before.difference(&after).copied().collect(),
+        };
+        outcome.unchoked.sort();
+        outcome.choked.sort();
+        Some(outcome)
+    }
+}
+
+/// Suggested back-off for the requester at 0-based queue `position`: one
+/// rechoke interval per slot-sized group ahead of it.
+fn retry_after(position: usize) -> u32 {
+    let rounds = (position / REGULAR_SLOTS + 1) as u64;
+    u32::try_from(rounds * RECHOKE_INTERVAL_SECS)
+        .unwrap_or(MAX_RETRY_AFTER_SECS)
+        .min(MAX_RETRY_AFTER_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(i: u8) -> [u8; 32] {
+        [i; 32]
+    }
+
+    #[test]
+    fn test_free_slots_served_then_queued() {
+        let mut m = UploadManager::new();
+        for i in 0..REGULAR_SLOTS as u8 {
+            assert_eq!(m.request(peer(i), 0), UploadDecision::Serve);
+        }
+        assert_eq!(
+            m.request(peer(10), 0),
+            UploadDecision::Queued {
+                position: 1,
+                retry_after_secs: RECHOKE_INTERVAL_SECS as u32,
+            }
+        );
+        // Asking again keeps the same place.
+        assert!(matches!(
+            m.request(peer(10), 1),
+            UploadDecision::Queued { position: 1, .. }
+        ));
+        assert_eq!(m.queue_len(), 1);
+        assert_eq!(m.active_slots(), REGULAR_SLOTS);
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let mut m = UploadManager::new();
+        for i in 0..(REGULAR_SLOTS + MAX_QUEUE_LEN) as u8 {
+            m.request(peer(i), 0);
+        }
+        assert_eq!(m.request(peer(250), 0), UploadDecision::Rejected);
+        assert!(matches!(
+            m.request(peer(REGULAR_SLOTS as u8 + 40), 0),
+            UploadDecision::Queued {
+                position: 41,
+                retry_after_secs: 110,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_rechoke_prefers_reciprocating_peers() {
+        let mut m = UploadManager::new();
+        for i in 0..8 {
+            m.request(peer(i), 0);
+        }
+        m.record_served_to_us(peer(6), 4_000_000);
+        m.record_receipt(peer(7), 1_000_000);
+
+        let outcome = m.rechoke(1).expect("due");
+        assert!(m.is_unchoked(&peer(6)));
+        assert!(m.is_unchoked(&peer(7)));
+        assert!(outcome.unchoked.contains(&peer(6)));
+        assert_eq!(m.active_slots(), REGULAR_SLOTS + 1);
+        assert_eq!(outcome.choked, vec![peer(2), peer(3)]);
+        // Demoted requesters wait behind those already queued.
+        assert_eq!(m.queue_len(), 3);
+        assert!(m.rechoke(2).is_none());
+    }
+
+    #[test]
+    fn test_optimistic_slot_rotates_to_longest_waiting() {
+        let mut m = UploadManager::new();
+        for i in 0..7 {
+            m.request(peer(i), 0);
+        }
+        // Queue: 4, 5, 6. Peers 0..4 keep regular slots by credit.
+        for i in 0..4 {
+            m.record_served_to_us(peer(i), 1_000_000);
+        }
+        m.rechoke(0);
+        assert!(m.is_unchoked(&peer(4)));
+        assert!(!m.is_unchoked(&peer(5)));
+
+        for i in 0..7 {
+            m.request(peer(i), 20);
+            m.record_served_to_us(peer(i), if i < 4 { 1_000_000 } else { 0 });
+        }
+        m.rechoke(OPTIMISTIC_ROTATION_SECS);
+        assert!(m.is_unchoked(&peer(5)));
+        assert!(!m.is_unchoked(&peer(4)));
+        assert!(matches!(
+            m.request(peer(4), 31),
+            UploadDecision::Queued { position: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_idle_requesters_drop_out() {
+        let mut m = UploadManager::new();
+        for i in 0..6 {
+            m.request(peer(i), 0);
+        }
+        m.request(peer(0), INTEREST_TTL_SECS);
+        let outcome = m.rechoke(INTEREST_TTL_SECS).expect("due");
+        assert!(m.is_unchoked(&peer(0)));
+        assert_eq!(m.active_slots(), 1);
+        assert_eq!(m.queue_len(), 0);
+        assert_eq!(outcome.choked.len(), 3);
+    }
+}
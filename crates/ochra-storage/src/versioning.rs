@@ -0,0 +1,689 @@
+//! Content manifest revisions and update channels.
+//!
+//! Published content is immutable: every publish produces a new Merkle root
+//! (Section 16.5). Versioning links those roots into a *lineage* of signed
+//! revisions so buyers of any version can discover the latest one.
+//!
+//! ## Revisions
+//!
+//! A [`ManifestRevision`] binds a `content_hash` to a lineage and version
+//! number, and points at the revision it replaces via `previous`:
+//!
+//! | Field | Genesis (v1) | Later versions |
+//! |-------|--------------|----------------|
+//! | `lineage_id` | its own `content_hash` | inherited from v1 |
+//! | `version` | 1 | predecessor + 1 |
+//! | `previous` | `None` | predecessor's [`ManifestRevision::revision_hash`] |
+//!
+//! Every revision is signed by the creator's PIK, and all revisions in a
+//! lineage must share that key.
+//!
+//! ## Conflict rules
+//!
+//! Two revisions are concurrent when they claim the same version (for example
+//! when the creator publishes from two devices at once). Both are kept, and
+//! the head is chosen deterministically so every node agrees without relying
+//! on clocks:
+//!
+//! 1. The highest version wins.
+//! 2. At equal version, the lexicographically smallest revision hash wins.
+//!
+//! A revision built on a losing branch still wins once its version exceeds
+//! the other branch.
+//!
+//! ## Update channels
+//!
+//! The creator advertises the lineage head in a BEP 44 mutable record keyed
+//! by `BLAKE3::hash(creator_pik || "update-channel" || lineage_id)`, with the
+//! version as sequence number. Subscribers poll the record and feed it to
+//! [`UpdateSubscriptions::observe`], which reports newer versions.
+
+use std::collections::HashMap;
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use ochra_dht::bep44::{create_mutable_record, DhtRecord};
+use ochra_types::{ContentHash, Hash};
+
+use crate::{Result, StorageError};
+
+/// Salt prefix for update channel DHT records.
+pub const UPDATE_CHANNEL_SALT_PREFIX: &[u8] = b"update-channel";
+
+/// Encoded size of a [`ManifestRevision`].
+pub const REVISION_SIZE: usize = 32 + 4 + 32 + 1 + 32 + 8 + 32 + 64;
+
+/// A signed revision in a content lineage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestRevision {
+    /// Content hash of version 1.
+    pub lineage_id: ContentHash,
+    /// Version number, starting at 1.
+    pub version: u32,
+    /// Merkle root of this version's content.
+    pub content_hash: ContentHash,
+    /// Revision hash of the predecessor (`None` for version 1).
+    pub previous: Option<Hash>,
+    /// Unix timestamp of publication.
+    pub published_at: u64,
+    /// Creator's PIK public key.
+    pub creator_pik: [u8; 32],
+    /// Creator's PIK signature over [`ManifestRevision::signing_message`].
+    pub sig: [u8; 64],
+}
+
+impl ManifestRevision {
+    /// Create the first revision of a new lineage.
+    pub fn genesis(signing_key: &SigningKey, content_hash: ContentHash, published_at: u64) -> Self {
+        Self::signed(
+            signing_key,
+            content_hash,
+            1,
+            content_hash,
+            None,
+            published_at,
+        )
+    }
+
+    /// Create a revision replacing `self`.
+    pub fn successor(
+        &self,
+        signing_key: &SigningKey,
+        content_hash: ContentHash,
+        published_at: u64,
+    ) -> Result<Self> {
+        if signing_key.verifying_key().to_bytes() != self.creator_pik {
+            return Err(StorageError::InvalidRevision(
+                "successor must be signed by the lineage creator".to_string(),
+            ));
+        }
+        let version = self
+            .version
+            .checked_add(1)
+            .ok_or_else(|| StorageError::InvalidRevision("version overflow".to_string()))?;
+        Ok(Self::signed(
+            signing_key,
+            self.lineage_id,
+            version,
+            content_hash,
+            Some(self.revision_hash()),
+            published_at,
+        ))
+    }
+
+    fn signed(
+        signing_key: &SigningKey,
+        lineage_id: ContentHash,
+        version: u32,
+        content_hash: ContentHash,
+        previous: Option<Hash>,
+        published_at: u64,
+    ) -> Self {
+        let mut revision = Self {
+            lineage_id,
+            version,
+            content_hash,
+            previous,
+            published_at,
+            creator_pik: signing_key.verifying_key().to_bytes(),
+            sig: [0u8; 64],
+        };
+        revision.sig = signing_key.sign(&revision.signing_message()).to_bytes();
+        revision
+    }
+
+    /// Bytes covered by the creator's signature.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(REVISION_SIZE);
+        msg.extend_from_slice(b"manifest-revision");
+        msg.extend_from_slice(&self.lineage_id);
+        msg.extend_from_slice(&self.version.to_le_bytes());
+        msg.extend_from_slice(&self.content_hash);
+        match &self.previous {
+            Some(prev) => {
+                msg.push(1);
+                msg.extend_from_slice(prev);
+            }
+            None => msg.push(0),
+        }
+        msg.extend_from_slice(&self.published_at.to_le_bytes());
+        msg.extend_from_slice(&self.creator_pik);
+        msg
+    }
+
+    /// Identifier of this revision, referenced by its successor.
+    pub fn revision_hash(&self) -> Hash {
+        let mut input = self.signing_message();
+        input.extend_from_slice(&self.sig);
+        blake3::hash(&input)
+    }
+
+    /// Verify the creator's signature and the genesis invariants.
+    pub fn verify(&self) -> Result<()> {
+        if self.version == 0 {
+            return Err(StorageError::InvalidRevision(
+                "version must start at 1".to_string(),
+            ));
+        }
+        if (self.version == 1) != self.previous.is_none() {
+            return Err(StorageError::InvalidRevision(
+                "only version 1 may omit its predecessor".to_string(),
+            ));
+        }
+        if self.version == 1 && self.lineage_id != self.content_hash {
+            return Err(StorageError::InvalidRevision(
+                "lineage id must equal the version 1 content hash".to_string(),
+            ));
+        }
+        let vk = VerifyingKey::from_bytes(&self.creator_pik)
+            .map_err(|_| StorageError::InvalidSignature)?;
+        vk.verify(&self.signing_message(), &Signature::from_bytes(&self.sig))
+            .map_err(|_| StorageError::InvalidSignature)
+    }
+
+    /// Whether `self` replaces `other` as lineage head under the conflict rules.
+    pub fn supersedes(&self, other: &ManifestRevision) -> bool {
+        match self.version.cmp(&other.version) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal => self.revision_hash() < other.revision_hash(),
+        }
+    }
+
+    /// Encode to the fixed-size wire form used in update channel records.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(REVISION_SIZE);
+        out.extend_from_slice(&self.lineage_id);
+        out.extend_from_slice(&self.version.to_le_bytes());
+        out.extend_from_slice(&self.content_hash);
+        out.push(u8::from(self.previous.is_some()));
+        out.extend_from_slice(&self.previous.unwrap_or([0u8; 32]));
+        out.extend_from_slice(&self.published_at.to_le_bytes());
+        out.extend_from_slice(&self.creator_pik);
+        out.extend_from_slice(&self.sig);
+        out
+    }
+
+    /// Decode from the fixed-size wire form.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != REVISION_SIZE {
+            return Err(StorageError::InvalidRevision(format!(
+                "expected {REVISION_SIZE} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        let take32 = |at: usize| -> [u8; 32] {
+            let mut out = [0u8; 32];
+            out.copy_from_slice(&bytes[at..at + 32]);
+            out
+        };
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[32..36]);
+        let previous = match bytes[68] {
+            0 => None,
+            1 => Some(take32(69)),
+            _ => {
+                return Err(StorageError::InvalidRevision(
+                    "invalid predecessor flag".to_string(),
+                ))
+            }
+        };
+        let mut published_at = [0u8; 8];
+        published_at.copy_from_slice(&bytes[101..109]);
+        let mut sig = [0u8; 64];
+        sig.copy_from_slice(&bytes[141..205]);
+        Ok(Self {
+            lineage_id: take32(0),
+            version: u32::from_le_bytes(version),
+            content_hash: take32(36),
+            previous,
+            published_at: u64::from_le_bytes(published_at),
+            creator_pik: take32(109),
+            sig,
+        })
+    }
+}
+
+/// All known revisions of one lineage.
+#[derive(Debug)]
+pub struct ManifestLineage {
+    revisions: HashMap<Hash, ManifestRevision>,
+    head: Hash,
+}
+
+impl ManifestLineage {
+    /// Start a lineage from its verified version 1 revision.
+    pub fn new(genesis: ManifestRevision) -> Result<Self> {
+        genesis.verify()?;
+        if genesis.version != 1 {
+            return Err(StorageError::InvalidRevision(
+                "lineage must start at version 1".to_string(),
+            ));
+        }
+        let head = genesis.revision_hash();
+        let mut revisions = HashMap::new();
+        revisions.insert(head, genesis);
+        Ok(Self { revisions, head })
+    }
+
+    /// The lineage identifier (version 1 content hash).
+    pub fn lineage_id(&self) -> ContentHash {
+        self.head().lineage_id
+    }
+
+    /// Insert a revision whose predecessor is already known.
+    ///
+    /// Returns `true` if the revision became the new head.
+    pub fn insert(&mut self, revision: ManifestRevision) -> Result<bool> {
+        revision.verify()?;
+        let current = self.head();
+        if revision.lineage_id != current.lineage_id {
+            return Err(StorageError::InvalidRevision(
+                "revision belongs to another lineage".to_string(),
+            ));
+        }
+        if revision.creator_pik != current.creator_pik {
+            return Err(StorageError::InvalidRevision(
+                "revision signed by a different creator".to_string(),
+            ));
+        }
+        let hash = revision.revision_hash();
+        if self.revisions.contains_key(&hash) {
+            return Ok(false);
+        }
+        let prev_hash = revision.previous.ok_or_else(|| {
+            StorageError::InvalidRevision("lineage already has a version 1".to_string())
+        })?;
+        let prev = self.revisions.get(&prev_hash).ok_or_else(|| {
+            StorageError::InvalidRevision("unknown predecessor revision".to_string())
+        })?;
+        if prev.version.checked_add(1) != Some(revision.version) {
+            return Err(StorageError::InvalidRevision(format!(
+                "version {} does not follow predecessor version {}",
+                revision.version, prev.version
+            )));
+        }
+
+        let becomes_head = revision.supersedes(current);
+        self.revisions.insert(hash, revision);
+        if becomes_head {
+            self.head = hash;
+        }
+        Ok(becomes_head)
+    }
+
+    /// The current head revision.
+    pub fn head(&self) -> &ManifestRevision {
+        // The head hash always refers to an inserted revision.
+        &self.revisions[&self.head]
+    }
+
+    /// Whether `content_hash` is any version of this lineage.
+    pub fn contains(&self, content_hash: &ContentHash) -> bool {
+        self.revisions
+            .values()
+            .any(|r| &r.content_hash == content_hash)
+    }
+
+    /// Resolve any version's content hash to the latest content hash.
+    ///
+    /// Owning any version of a lineage entitles the holder to fetch the latest.
+    pub fn latest_for(&self, content_hash: &ContentHash) -> Option<ContentHash> {
+        self.contains(content_hash)
+            .then(|| self.head().content_hash)
+    }
+
+    /// Revisions that compete with the head at the same version.
+    pub fn conflicts(&self) -> Vec<&ManifestRevision> {
+        let head = self.head();
+        self.revisions
+            .iter()
+            .filter(|(hash, r)| **hash != self.head && r.version == head.version)
+            .map(|(_, r)| r)
+            .collect()
+    }
+
+    /// Number of known revisions.
+    pub fn len(&self) -> usize {
+        self.revisions.len()
+    }
+
+    /// Whether the lineage holds no revisions (never true once constructed).
+    pub fn is_empty(&self) -> bool {
+        self.revisions.is_empty()
+    }
+}
+
+/// DHT salt for a lineage's update channel.
+pub fn channel_salt(lineage_id: &ContentHash) -> Vec<u8> {
+    let mut salt = Vec::with_capacity(UPDATE_CHANNEL_SALT_PREFIX.len() + 32);
+    salt.extend_from_slice(UPDATE_CHANNEL_SALT_PREFIX);
+    salt.extend_from_slice(lineage_id);
+    salt
+}
+
+/// DHT storage key of a lineage's update channel.
+pub fn channel_key(creator_pik: &[u8; 32], lineage_id: &ContentHash) -> [u8; 32] {
+    let mut input = Vec::with_capacity(32 + UPDATE_CHANNEL_SALT_PREFIX.len() + 32);
+    input.extend_from_slice(creator_pik);
+    input.extend_from_slice(&channel_salt(lineage_id));
+    blake3::hash(&input)
+}
+
+/// Build the update channel record advertising `head`.
+///
+/// The sequence number is the head's version, so the DHT only accepts
+/// strictly newer versions. Same-version conflicts are settled by
+/// subscribers, not by the DHT.
+pub fn channel_record(signing_key: &SigningKey, head: &ManifestRevision) -> Result<DhtRecord> {
+    if signing_key.verifying_key().to_bytes() != head.creator_pik {
+        return Err(StorageError::InvalidRevision(
+            "update channel must be signed by the lineage creator".to_string(),
+        ));
+    }
+    create_mutable_record(
+        signing_key,
+        &channel_salt(&head.lineage_id),
+        u64::from(head.version),
+        head.to_bytes(),
+    )
+    .map_err(|e| StorageError::Dht(e.to_string()))
+}
+
+/// Validate an update channel record and extract the advertised revision.
+pub fn parse_channel_record(record: &DhtRecord) -> Result<ManifestRevision> {
+    record
+        .validate()
+        .map_err(|e| StorageError::Dht(e.to_string()))?;
+    let DhtRecord::Mutable {
+        public_key,
+        salt,
+        seq,
+        value,
+        ..
+    } = record
+    else {
+        return Err(StorageError::Dht(
+            "update channel must be a mutable record".to_string(),
+        ));
+    };
+    let revision = ManifestRevision::from_bytes(value)?;
+    revision.verify()?;
+    if revision.creator_pik != *public_key
+        || *salt != channel_salt(&revision.lineage_id)
+        || *seq != u64::from(revision.version)
+    {
+        return Err(StorageError::InvalidRevision(
+            "update channel record does not match its revision".to_string(),
+        ));
+    }
+    Ok(revision)
+}
+
+/// Notice that a subscribed lineage has a newer version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateNotice {
+    /// Lineage identifier.
+    pub lineage_id: ContentHash,
+    /// Previously known content hash.
+    pub previous_content_hash: ContentHash,
+    /// Newly advertised content hash.
+    pub content_hash: ContentHash,
+    /// Newly advertised version.
+    pub version: u32,
+}
+
+/// Update channel subscriptions, tracking the best known head per lineage.
+///
+/// Subscribers only see channel heads, so intermediate revisions may be
+/// skipped; a head is accepted if it is signed by the lineage creator and
+/// supersedes the known head.
+#[derive(Debug, Default)]
+pub struct UpdateSubscriptions {
+    heads: HashMap<ContentHash, ManifestRevision>,
+}
+
+impl UpdateSubscriptions {
+    /// Create an empty subscription set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to a lineage starting from a known revision.
+    ///
+    /// Re-subscribing keeps whichever head is newer.
+    pub fn subscribe(&mut self, known: ManifestRevision) -> Result<()> {
+        known.verify()?;
+        match self.heads.get(&known.lineage_id) {
+            Some(current) if !known.supersedes(current) => {}
+            _ => {
+                self.heads.insert(known.lineage_id, known);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop tracking a lineage. Returns `true` if it was subscribed.
+    pub fn unsubscribe(&mut self, lineage_id: &ContentHash) -> bool {
+        self.heads.remove(lineage_id).is_some()
+    }
+
+    /// Process an update channel record.
+    ///
+    /// Returns a notice if the record advertises a newer head for a
+    /// subscribed lineage. Records for unsubscribed lineages are ignored.
+    pub fn observe(&mut self, record: &DhtRecord) -> Result<Option<UpdateNotice>> {
+        let revision = parse_channel_record(record)?;
+        let Some(current) = self.heads.get(&revision.lineage_id) else {
+            return Ok(None);
+        };
+        if revision.creator_pik != current.creator_pik {
+            return Err(StorageError::InvalidRevision(
+                "update channel signed by a different creator".to_string(),
+            ));
+        }
+        if !revision.supersedes(current) {
+            return Ok(None);
+        }
+        let notice = UpdateNotice {
+            lineage_id: revision.lineage_id,
+            previous_content_hash: current.content_hash,
+            content_hash: revision.content_hash,
+            version: revision.version,
+        };
+        self.heads.insert(revision.lineage_id, revision);
+        Ok(Some(notice))
+    }
+
+    /// The best known head of a lineage.
+    pub fn head(&self, lineage_id: &ContentHash) -> Option<&ManifestRevision> {
+        self.heads.get(lineage_id)
+    }
+
+    /// Find the subscribed lineage that `content_hash` is the head of, or
+    /// that was started by it.
+    pub fn lineage_of(&self, content_hash: &ContentHash) -> Option<&ManifestRevision> {
+        self.heads.get(content_hash).or_else(|| {
+            self.heads
+                .values()
+                .find(|r| &r.content_hash == content_hash)
+        })
+    }
+
+    /// DHT keys of all subscribed update channels.
+    pub fn channel_keys(&self) -> Vec<[u8; 32]> {
+        self.heads
+            .values()
+            .map(|r| channel_key(&r.creator_pik, &r.lineage_id))
+            .collect()
+    }
+
+    /// Number of subscribed lineages.
+    pub fn len(&self) -> usize {
+        self.heads.len()
+    }
+
+    /// Whether no lineages are subscribed.
+    pub fn is_empty(&self) -> bool {
+        self.heads.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_crypto::ed25519::KeyPair;
+
+    fn content(n: u8) -> ContentHash {
+        blake3::hash(&[n])
+    }
+
+    #[test]
+    fn test_genesis_and_successor_verify() {
+        let kp = KeyPair::generate();
+        let v1 = ManifestRevision::genesis(&kp.signing_key, content(1), 100);
+        v1.verify().expect("v1 valid");
+        assert_eq!(v1.lineage_id, content(1));
+
+        let v2 = v1
+            .successor(&kp.signing_key, content(2), 200)
+            .expect("successor");
+        v2.verify().expect("v2 valid");
+        assert_eq!(v2.version, 2);
+        assert_eq!(v2.lineage_id, content(1));
+        assert_eq!(v2.previous, Some(v1.revision_hash()));
+    }
+
+    #[test]
+    fn test_tampered_revision_rejected() {
+        let kp = KeyPair::generate();
+        let v1 = ManifestRevision::genesis(&kp.signing_key, content(1), 100);
+        let mut v2 = v1
+            .successor(&kp.signing_key, content(2), 200)
+            .expect("successor");
+        v2.content_hash = content(9);
+        assert!(matches!(v2.verify(), Err(StorageError::InvalidSignature)));
+
+        let other = KeyPair::generate();
+        assert!(v1.successor(&other.signing_key, content(3), 300).is_err());
+    }
+
+    #[test]
+    fn test_lineage_latest_for_any_version() {
+        let kp = KeyPair::generate();
+        let v1 = ManifestRevision::genesis(&kp.signing_key, content(1), 100);
+        let v2 = v1.successor(&kp.signing_key, content(2), 200).expect("v2");
+        let v3 = v2.successor(&kp.signing_key, content(3), 300).expect("v3");
+
+        let mut lineage = ManifestLineage::new(v1).expect("lineage");
+        assert!(lineage.insert(v2).expect("insert v2"));
+        assert!(lineage.insert(v3).expect("insert v3"));
+        assert_eq!(lineage.len(), 3);
+
+        assert_eq!(lineage.latest_for(&content(1)), Some(content(3)));
+        assert_eq!(lineage.latest_for(&content(2)), Some(content(3)));
+        assert_eq!(lineage.latest_for(&content(7)), None);
+    }
+
+    #[test]
+    fn test_lineage_rejects_orphan_and_foreign_revisions() {
+        let kp = KeyPair::generate();
+        let v1 = ManifestRevision::genesis(&kp.signing_key, content(1), 100);
+        let v2 = v1.successor(&kp.signing_key, content(2), 200).expect("v2");
+        let v3 = v2.successor(&kp.signing_key, content(3), 300).expect("v3");
+        let mut lineage = ManifestLineage::new(v1).expect("lineage");
+
+        // v3 before v2: predecessor unknown.
+        assert!(lineage.insert(v3).is_err());
+
+        let other = ManifestRevision::genesis(&kp.signing_key, content(5), 100);
+        assert!(lineage.insert(other).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_revisions_resolve_deterministically() {
+        let kp = KeyPair::generate();
+        let v1 = ManifestRevision::genesis(&kp.signing_key, content(1), 100);
+        let a = v1.successor(&kp.signing_key, content(2), 200).expect("a");
+        let b = v1.successor(&kp.signing_key, content(3), 200).expect("b");
+        let winner = if a.revision_hash() < b.revision_hash() {
+            a.clone()
+        } else {
+            b.clone()
+        };
+
+        let mut first = ManifestLineage::new(v1.clone()).expect("lineage");
+        first.insert(a.clone()).expect("a");
+        first.insert(b.clone()).expect("b");
+        let mut second = ManifestLineage::new(v1).expect("lineage");
+        second.insert(b.clone()).expect("b");
+        second.insert(a.clone()).expect("a");
+
+        assert_eq!(first.head(), &winner);
+        assert_eq!(second.head(), &winner);
+        assert_eq!(first.conflicts().len(), 1);
+
+        // Building on the losing branch wins once it is a version ahead.
+        let loser = if winner == a { b } else { a };
+        let v3 = loser
+            .successor(&kp.signing_key, content(4), 300)
+            .expect("v3");
+        assert!(first.insert(v3).expect("v3"));
+        assert_eq!(first.head().content_hash, content(4));
+        assert!(first.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_revision_roundtrip_bytes() {
+        let kp = KeyPair::generate();
+        let v1 = ManifestRevision::genesis(&kp.signing_key, content(1), 100);
+        let v2 = v1.successor(&kp.signing_key, content(2), 200).expect("v2");
+        for rev in [v1, v2] {
+            let bytes = rev.to_bytes();
+            assert_eq!(bytes.len(), REVISION_SIZE);
+            assert_eq!(ManifestRevision::from_bytes(&bytes).expect("decode"), rev);
+        }
+        assert!(ManifestRevision::from_bytes(&[0u8; 10]).is_err());
+    }
+
+    #[test]
+    fn test_channel_record_roundtrip() {
+        let kp = KeyPair::generate();
+        let v1 = ManifestRevision::genesis(&kp.signing_key, content(1), 100);
+        let record = channel_record(&kp.signing_key, &v1).expect("record");
+        assert_eq!(
+            record.storage_key(),
+            channel_key(&v1.creator_pik, &v1.lineage_id)
+        );
+        assert_eq!(parse_channel_record(&record).expect("parse"), v1);
+
+        let other = KeyPair::generate();
+        assert!(channel_record(&other.signing_key, &v1).is_err());
+    }
+
+    #[test]
+    fn test_subscriptions_notify_on_newer_version() {
+        let kp = KeyPair::generate();
+        let v1 = ManifestRevision::genesis(&kp.signing_key, content(1), 100);
+        let v2 = v1.successor(&kp.signing_key, content(2), 200).expect("v2");
+        let v3 = v2.successor(&kp.signing_key, content(3), 300).expect("v3");
+
+        let mut subs = UpdateSubscriptions::new();
+        subs.subscribe(v1.clone()).expect("subscribe");
+
+        // Intermediate versions may be skipped.
+        let record = channel_record(&kp.signing_key, &v3).expect("record");
+        let notice = subs.observe(&record).expect("observe").expect("notice");
+        assert_eq!(notice.version, 3);
+        assert_eq!(notice.previous_content_hash, content(1));
+        assert_eq!(notice.content_hash, content(3));
+
+        // Replaying an older head is not news.
+        let stale = channel_record(&kp.signing_key, &v2).expect("record");
+        assert!(subs.observe(&stale).expect("observe").is_none());
+        assert_eq!(subs.head(&v1.lineage_id).map(|r| r.version), Some(3));
+
+        assert!(subs.unsubscribe(&v1.lineage_id));
+        assert!(subs.observe(&record).expect("observe").is_none());
+    }
+}
@@ -4,9 +4,19 @@
 //! This binary is the ground truth for all cryptographic interoperability.
 //!
 //! Usage:
-//!   ochra-testvec              # Generate test_vectors.json
-//!   ochra-testvec --verify     # Verify test vectors match expected values
-
+//!   ochra-testvec                        # Generate test_vectors.json
+//!   ochra-testvec --verify               # Verify test vectors match expected values
+//!   ochra-testvec --check-interop <path> # Check vectors produced by another implementation
+
+mod sample;
+
+use ochra_crypto::x25519::X25519StaticSecret;
+use ochra_transport::messages::{TypedMessage, ALL_MESSAGE_TYPES};
+use ochra_transport::sphinx::{
+    self, HopInfo, HopKeys, SphinxBuildParams, EPH_PK_SIZE, HEADER_SIZE, NUM_HOPS,
+    ROUTING_INFO_SIZE,
+};
+use ochra_transport::wire::{ProtocolMessage, PROTOCOL_VERSION};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -135,6 +145,42 @@ fn generate_ed25519_vectors() -> BTreeMap<String, TestVector> {
         },
     );
 
+    // Delegation certificate from the all-zeros PIK to the all-ones subkey
+    let subkey = ochra_crypto::ed25519::KeyPair::from_bytes(&[1u8; 32]);
+    let scope = ochra_crypto::ed25519::DelegationScope::RELAY
+        .union(ochra_crypto::ed25519::DelegationScope::DHT_RECORDS);
+    let cert = ochra_crypto::ed25519::DelegationCert::issue(
+        &kp.signing_key,
+        &subkey.verifying_key,
+        scope,
+        100,
+        106,
+    )
+    .expect("delegation cert");
+    vectors.insert(
+        "delegation_cert".to_string(),
+        TestVector {
+            description: "DelegationCert::issue(pik = 0x00*32, subkey = pk(0x01*32), scope = relay|dht_records, epochs 100..=106)".to_string(),
+            inputs: BTreeMap::from([
+                ("pik_secret_key".to_string(), hex::encode([0u8; 32])),
+                (
+                    "subkey_public_key".to_string(),
+                    hex::encode(subkey.verifying_key.to_bytes()),
+                ),
+                ("scope".to_string(), scope.0.to_string()),
+                ("valid_from_epoch".to_string(), "100".to_string()),
+                ("valid_until_epoch".to_string(), "106".to_string()),
+            ]),
+            outputs: BTreeMap::from([
+                (
+                    "signing_message".to_string(),
+                    hex::encode(cert.signing_message()),
+                ),
+                ("certificate".to_string(), hex::encode(cert.to_bytes())),
+            ]),
+        },
+    );
+
     vectors
 }
 
@@ -242,6 +288,39 @@ fn generate_ecies_vector() -> BTreeMap<String, TestVector> {
         },
     );
 
+    let chunk_size = 16usize;
+    let stream_plaintext = b"Ochra streaming ECIES test payload";
+    let mut encryptor = ochra_crypto::ecies_stream::StreamEncryptor::new_deterministic(
+        &recipient_pk,
+        chunk_size,
+        &randomness,
+    )
+    .expect("stream encryptor");
+    let mut stream = encryptor.header().to_vec();
+    let mut chunks = stream_plaintext.chunks_exact(chunk_size);
+    for chunk in &mut chunks {
+        stream.extend(encryptor.encrypt_chunk(chunk).expect("stream chunk"));
+    }
+    stream.extend(encryptor.finish(chunks.remainder()).expect("stream final"));
+
+    vectors.insert(
+        "ecies_stream".to_string(),
+        TestVector {
+            description: "Streaming ECIES with 16-byte chunks, deterministic ephemeral key"
+                .to_string(),
+            inputs: BTreeMap::from([
+                (
+                    "recipient_pk".to_string(),
+                    hex::encode(recipient_pk.to_bytes()),
+                ),
+                ("plaintext".to_string(), hex::encode(stream_plaintext)),
+                ("randomness".to_string(), hex::encode(randomness)),
+                ("chunk_size".to_string(), chunk_size.to_string()),
+            ]),
+            outputs: BTreeMap::from([("stream".to_string(), hex::encode(&stream))]),
+        },
+    );
+
     vectors
 }
 
@@ -343,6 +422,166 @@ fn generate_bloom_filter_vector() -> BTreeMap<String, TestVector> {
     vectors
 }
 
+fn generate_sphinx_vectors() -> BTreeMap<String, TestVector> {
+    let mut vectors = BTreeMap::new();
+
+    // Fixed relay and ephemeral secrets: hop i uses 0x1i*32 / 0x2i*32.
+    let hop_secrets: [X25519StaticSecret; NUM_HOPS] =
+        std::array::from_fn(|i| X25519StaticSecret::from_bytes([0x11 + i as u8; 32]));
+    let eph_secret_bytes: [[u8; 32]; NUM_HOPS] = std::array::from_fn(|i| [0x21 + i as u8; 32]);
+    let hop_publics: [_; NUM_HOPS] = std::array::from_fn(|i| hop_secrets[i].public_key());
+    let hop_infos: [HopInfo; NUM_HOPS] = std::array::from_fn(|i| HopInfo {
+        node_id: [0x31 + i as u8; 32],
+        next_hop_pk: hop_publics.get(i + 1).map_or([0u8; 32], |pk| pk.to_bytes()),
+        circuit_id: [0xC1; 16],
+        hop_index: i as u8,
+    });
+    let plaintext = b"Ochra sphinx test vector".to_vec();
+
+    // Per-hop shared secrets and derived keys.
+    let mut key_inputs = BTreeMap::new();
+    let mut key_outputs = BTreeMap::new();
+    for i in 0..NUM_HOPS {
+        let eph = X25519StaticSecret::from_bytes(eph_secret_bytes[i]);
+        let shared = eph.diffie_hellman(&hop_publics[i]);
+        let keys = HopKeys::derive(shared.as_bytes());
+        key_inputs.insert(format!("eph_secret_{i}"), hex::encode(eph_secret_bytes[i]));
+        key_inputs.insert(
+            format!("hop_public_key_{i}"),
+            hex::encode(hop_publics[i].to_bytes()),
+        );
+        key_outputs.insert(format!("shared_secret_{i}"), hex::encode(shared.as_bytes()));
+        key_outputs.insert(format!("hop_key_{i}"), hex::encode(keys.hop_key));
+        key_outputs.insert(format!("hop_mac_{i}"), hex::encode(keys.hop_mac));
+        key_outputs.insert(format!("hop_pad_{i}"), hex::encode(keys.hop_pad));
+        key_outputs.insert(format!("hop_nonce_{i}"), hex::encode(keys.hop_nonce));
+    }
+    vectors.insert(
+        "sphinx_per_hop_keys".to_string(),
+        TestVector {
+            description: "Sphinx per-hop keys: S = X25519(eph_secret, hop_pk); hop_key/mac/pad = derive_key(\"Ochra v1 sphinx-hop-*\", S), hop_nonce = derive_key(\"Ochra v1 sphinx-hop-nonce\", S)[:12]".to_string(),
+            inputs: key_inputs.clone(),
+            outputs: key_outputs,
+        },
+    );
+
+    // Full packet built with the same ephemeral secrets.
+    let params = SphinxBuildParams {
+        hop_public_keys: hop_publics.clone(),
+        hop_infos: hop_infos.clone(),
+        plaintext: plaintext.clone(),
+    };
+    let eph_secrets = eph_secret_bytes.map(X25519StaticSecret::from_bytes);
+    let packet = sphinx::build_packet_with_secrets(params, eph_secrets).expect("build packet");
+    // The header MAC is keyed for the entry relay, which must accept it.
+    sphinx::process_packet(&packet, &hop_secrets[0], 0).expect("entry hop accepts packet");
+
+    let routing_start = 2 + NUM_HOPS * EPH_PK_SIZE;
+    let mac_start = routing_start + NUM_HOPS * ROUTING_INFO_SIZE;
+    let mut inputs = key_inputs;
+    for (i, info) in hop_infos.iter().enumerate() {
+        inputs.insert(format!("hop_info_{i}"), hex::encode(info.to_bytes()));
+    }
+    inputs.insert("plaintext".to_string(), hex::encode(&plaintext));
+    vectors.insert(
+        "sphinx_packet_header".to_string(),
+        TestVector {
+            description: format!(
+                "Sphinx v1 header layout [version:1][flags:1][eph_pks:96][routing_infos:249][mac:16][reserved:17] ({HEADER_SIZE} bytes); payload hashed with BLAKE3"
+            ),
+            inputs,
+            outputs: BTreeMap::from([
+                ("header".to_string(), hex::encode(&packet.data[..HEADER_SIZE])),
+                (
+                    "routing_infos".to_string(),
+                    hex::encode(&packet.data[routing_start..mac_start]),
+                ),
+                (
+                    "mac".to_string(),
+                    hex::encode(&packet.data[mac_start..mac_start + 16]),
+                ),
+                (
+                    "payload_hash".to_string(),
+                    hex::encode(ochra_crypto::blake3::hash(&packet.data[HEADER_SIZE..])),
+                ),
+            ]),
+        },
+    );
+
+    vectors
+}
+
+/// Seed for the CBOR sample stream.
+const CBOR_SAMPLE_SEED: [u8; 32] = [0x5A; 32];
+
+/// Fixed envelope fields for the CBOR envelope vectors.
+const CBOR_SAMPLE_MSG_ID: [u8; 16] = [0x4D; 16];
+const CBOR_SAMPLE_TIMESTAMP: u64 = 1_700_000_000;
+
+fn generate_cbor_vectors() -> BTreeMap<String, TestVector> {
+    let mut vectors = BTreeMap::new();
+    for (name, msg) in sample::sample_all_variants::<TypedMessage>(CBOR_SAMPLE_SEED) {
+        let msg_type = msg.msg_type();
+        let envelope = ProtocolMessage {
+            version: PROTOCOL_VERSION,
+            msg_type,
+            msg_id: CBOR_SAMPLE_MSG_ID,
+            timestamp: CBOR_SAMPLE_TIMESTAMP,
+            payload: msg.to_cbor().expect("encode payload"),
+        };
+        // Every sample must survive strict decoding unchanged.
+        let decoded = envelope.decode_payload_strict().expect("strict decode");
+        let reencoded = decoded.to_cbor().expect("re-encode payload");
+        assert_eq!(
+            reencoded, envelope.payload,
+            "{name} must re-encode identically"
+        );
+
+        vectors.insert(
+            format!("cbor_{}", snake_case(name)),
+            TestVector {
+                description: format!(
+                    "CBOR encoding of TypedMessage::{name} (msg_type 0x{msg_type:04X}) and its ProtocolMessage envelope"
+                ),
+                inputs: BTreeMap::from([
+                    ("msg_type".to_string(), format!("0x{msg_type:04X}")),
+                    (
+                        "message".to_string(),
+                        serde_json::to_string(&msg).expect("serialize message"),
+                    ),
+                    ("msg_id".to_string(), hex::encode(CBOR_SAMPLE_MSG_ID)),
+                    ("timestamp".to_string(), CBOR_SAMPLE_TIMESTAMP.to_string()),
+                ]),
+                outputs: BTreeMap::from([
+                    ("payload".to_string(), hex::encode(&envelope.payload)),
+                    (
+                        "envelope".to_string(),
+                        hex::encode(envelope.to_bytes().expect("encode envelope")),
+                    ),
+                ]),
+            },
+        );
+    }
+    assert_eq!(
+        vectors.len(),
+        ALL_MESSAGE_TYPES.len(),
+        "every message type needs a CBOR vector"
+    );
+
+    vectors
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
 fn generate_all_vectors() -> TestVectors {
     let mut all_vectors = BTreeMap::new();
 
@@ -353,6 +592,8 @@ fn generate_all_vectors() -> TestVectors {
     all_vectors.extend(generate_ecies_vector());
     all_vectors.extend(generate_ratchet_vectors());
     all_vectors.extend(generate_bloom_filter_vector());
+    all_vectors.extend(generate_sphinx_vectors());
+    all_vectors.extend(generate_cbor_vectors());
 
     TestVectors {
         version: "1.0".to_string(),
@@ -384,10 +625,78 @@ fn verify_vectors(vectors: &TestVectors) -> bool {
     all_pass
 }
 
+/// Check vectors produced by another implementation against ours.
+///
+/// Every vector the other file contains must match the regenerated outputs
+/// exactly. Vectors we do not know are reported but not failed, so another
+/// implementation may carry extras; vectors it does not cover are listed so
+/// gaps are visible.
+fn check_interop(theirs: &TestVectors) -> bool {
+    let ours = generate_all_vectors();
+    let mut all_pass = true;
+    let mut checked = 0usize;
+
+    for (name, vector) in &theirs.vectors {
+        let Some(expected) = ours.vectors.get(name) else {
+            eprintln!("UNKNOWN: {name}");
+            continue;
+        };
+        checked += 1;
+        let mismatched: Vec<&String> = expected
+            .outputs
+            .iter()
+            .filter(|(key, value)| vector.outputs.get(*key) != Some(value))
+            .map(|(key, _)| key)
+            .collect();
+        if mismatched.is_empty() {
+            eprintln!("PASS: {name}");
+        } else {
+            eprintln!("FAIL: {name}");
+            for key in mismatched {
+                eprintln!("  {key}: expected {}", expected.outputs[key]);
+                eprintln!(
+                    "  {key}: actual   {}",
+                    vector.outputs.get(key).map_or("<missing>", String::as_str)
+                );
+            }
+            all_pass = false;
+        }
+    }
+
+    let uncovered: Vec<&String> = ours
+        .vectors
+        .keys()
+        .filter(|name| !theirs.vectors.contains_key(*name))
+        .collect();
+    eprintln!(
+        "{checked} of {} vectors checked against {}",
+        ours.vectors.len(),
+        theirs.generated_by
+    );
+    for name in uncovered {
+        eprintln!("NOT COVERED: {name}");
+    }
+
+    all_pass && checked > 0
+}
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
-    if args.iter().any(|a| a == "--verify") {
+    if let Some(pos) = args.iter().position(|a| a == "--check-interop") {
+        let Some(path) = args.get(pos + 1) else {
+            eprintln!("Usage: ochra-testvec --check-interop <path>");
+            std::process::exit(2);
+        };
+        let content = std::fs::read_to_string(path).expect("read interop vectors");
+        let vectors: TestVectors = serde_json::from_str(&content).expect("valid JSON");
+        if check_interop(&vectors) {
+            eprintln!("Interop vectors from {path} match.");
+        } else {
+            eprintln!("Interop check FAILED for {path}.");
+            std::process::exit(1);
+        }
+    } else if args.iter().any(|a| a == "--verify") {
         // Verify mode: load existing vectors and check
         let path = "tests/fixtures/test_vectors.json";
         match std::fs::read_to_string(path) {
@@ -0,0 +1,468 @@
+//! Per-peer message-type capabilities.
+//!
+//! Each peer lists the message types it handles in its
+//! [`CapabilityExchange`]. The [`CapabilityRegistry`] records those lists,
+//! refuses to send a type the peer did not advertise, and builds the
+//! [`Unsupported`] reply for incoming types this node does not handle.
+//!
+//! CapabilityExchange, Goodbye, and Unsupported may always be sent, since
+//! they are needed before (or instead of) a completed exchange.
+//!
+//! The `features` bitmask advertises the roles a node has taken on
+//! ([`FEATURE_RELAY`], [`FEATURE_STORAGE`], [`FEATURE_QUORUM_CANDIDATE`]),
+//! so peers only route relay or storage work to nodes that accept it.
+//!
+//! Each exchange carries its network's protocol magic; the registry refuses
+//! peers from another network (Section 2.5).
+//!
+//! A peer that keeps its PIK offline attaches a delegation certificate for
+//! its operational key. The registry checks it was issued by the PIK behind
+//! the peer's node ID, and [`PeerCapabilities::verify_operational`] then
+//! checks subkey signatures against the delegated scope.
+
+use std::collections::{BTreeSet, HashMap};
+
+use ochra_crypto::ed25519::{DelegationCert, DelegationScope, Signature};
+use ochra_types::profile::MAINNET_MAGIC;
+
+use crate::messages::{
+    CapabilityExchange, TypedMessage, Unsupported, UnsupportedReason, ALL_MESSAGE_TYPES,
+    MSG_CAPABILITY_EXCHANGE, MSG_GOODBYE, MSG_UNSUPPORTED,
+};
+use crate::wire::{ProtocolMessage, PROTOCOL_VERSION};
+use crate::TransportError;
+
+/// Message types that may be sent to any peer.
+pub const ALWAYS_ALLOWED: [u16; 3] = [MSG_CAPABILITY_EXCHANGE, MSG_GOODBYE, MSG_UNSUPPORTED];
+
+/// Feature bit: relays onion circuits and holds Whisper mailboxes.
+pub const FEATURE_RELAY: u64 = 1 << 0;
+
+/// Feature bit: stores and serves chunks.
+pub const FEATURE_STORAGE: u64 = 1 << 1;
+
+/// Feature bit: eligible for selection into the minting quorum.
+pub const FEATURE_QUORUM_CANDIDATE: u64 = 1 << 2;
+
+/// What a peer advertised in its capability exchange.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerCapabilities {
+    pub protocol_version: u8,
+    /// Feature bitmask.
+    pub features: u64,
+    pub agent: String,
+    /// The peer's delegated operational key, already checked against its
+    /// node ID.
+    pub operational_key: Option<DelegationCert>,
+    supported: BTreeSet<u16>,
+}
+
+impl PeerCapabilities {
+    /// Capabilities from a received exchange.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ProtocolViolation`] if the exchange carries
+    /// a delegation that is malformed, badly signed, or not issued by the
+    /// PIK behind `node_id`.
+    pub fn from_exchange(exchange: &CapabilityExchange) -> Result<Self, TransportError> {
+        let operational_key = exchange
+            .delegation
+            .as_deref()
+            .map(|encoded| {
+                let cert = DelegationCert::from_bytes(encoded)
+                    .and_then(|cert| cert.verify().map(|()| cert))
+                    .map_err(|e| TransportError::ProtocolViolation(e.to_string()))?;
+                if cert.parent_hash() != exchange.node_id {
+                    return Err(TransportError::ProtocolViolation(
+                        "delegation not issued by the peer's PIK".to_string(),
+                    ));
+                }
+                Ok(cert)
+            })
+            .transpose()?;
+        Ok(Self {
+            protocol_version: exchange.protocol_version,
+            features: exchange.features,
+            agent: exchange.agent.clone(),
+            operational_key,
+            supported: exchange.supported_messages.iter().copied().collect(),
+        })
+    }
+
+    /// Verify a signature the peer made with its operational key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Crypto`] if the peer has no operational
+    /// key, the key is not delegated `scope` in `epoch`, or the signature
+    /// does not verify.
+    pub fn verify_operational(
+        &self,
+        scope: DelegationScope,
+        epoch: u32,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), TransportError> {
+        let cert = self
+            .operational_key
+            .as_ref()
+            .ok_or_else(|| TransportError::Crypto("peer has no operational key".to_string()))?;
+        cert.verify_delegated(scope, epoch, message, signature)
+            .map_err(|e| TransportError::Crypto(e.to_string()))
+    }
+
+    /// Whether the peer accepts `msg_type`.
+    pub fn supports(&self, msg_type: u16) -> bool {
+        ALWAYS_ALLOWED.contains(&msg_type) || self.supported.contains(&msg_type)
+    }
+
+    /// Whether the peer set every bit of `feature`.
+    pub fn has_feature(&self, feature: u64) -> bool {
+        self.features & feature == feature
+    }
+
+    /// Advertised message types, ascending.
+    pub fn supported_messages(&self) -> impl Iterator<Item = u16> + '_ {
+        self.supported.iter().copied()
+    }
+}
+
+/// This node's capability exchange on the network with `network_magic`,
+/// advertising every known message type.
+pub fn local_exchange(
+    node_id: [u8; 32],
+    features: u64,
+    agent: &str,
+    network_magic: u32,
+) -> CapabilityExchange {
+    CapabilityExchange {
+        protocol_version: PROTOCOL_VERSION,
+        node_id,
+        features,
+        agent: agent.to_string(),
+        supported_messages: ALL_MESSAGE_TYPES.to_vec(),
+        delegation: None,
+        network_magic,
+    }
+}
+
+/// Whether this node handles incoming `msg_type`.
+pub fn handles(msg_type: u16) -> bool {
+    ALL_MESSAGE_TYPES.contains(&msg_type)
+}
+
+/// Build the [`Unsupported`] reply to `msg`, or `None` if this node handles
+/// its type.
+///
+/// # Errors
+///
+/// Returns [`TransportError::Serialization`] if the reply cannot be encoded.
+pub fn unsupported_reply(msg: &ProtocolMessage) -> Result<Option<ProtocolMessage>, TransportError> {
+    if handles(msg.msg_type) {
+        return Ok(None);
+    }
+    let reply = TypedMessage::Unsupported(Unsupported {
+        msg_id: msg.msg_id,
+        msg_type: msg.msg_type,
+        reason: UnsupportedReason::UnknownType as u8,
+    });
+    ProtocolMessage::from_typed(&reply).map(Some)
+}
+
+/// Capabilities of every peer that has completed an exchange.
+#[derive(Debug)]
+pub struct CapabilityRegistry {
+    network_magic: u32,
+    peers: HashMap<[u8; 32], PeerCapabilities>,
+}
+
+impl Default for CapabilityRegistry {
+    fn default() -> Self {
+        Self::for_network(MAINNET_MAGIC)
+    }
+}
+
+impl CapabilityRegistry {
+    /// Create an empty registry for mainnet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty registry accepting peers with `network_magic`.
+    pub fn for_network(network_magic: u32) -> Self {
+        Self {
+            network_magic,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Record a peer's capability exchange, replacing any earlier one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ProtocolViolation`] if the peer speaks a
+    /// different protocol version, is on another network, or sent an
+    /// invalid delegation.
+    pub fn record(
+        &mut self,
+        peer: [u8; 32],
+        exchange: &CapabilityExchange,
+    ) -> Result<&PeerCapabilities, TransportError> {
+        if exchange.protocol_version != PROTOCOL_VERSION {
+            return Err(TransportError::ProtocolViolation(format!(
+                "peer protocol version {}, expected {PROTOCOL_VERSION}",
+                exchange.protocol_version
+            )));
+        }
+        if exchange.network_magic != self.network_magic {
+            return Err(TransportError::ProtocolViolation(format!(
+                "peer network magic {:#010x}, expected {:#010x}",
+                exchange.network_magic, self.network_magic
+            )));
+        }
+        let caps = PeerCapabilities::from_exchange(exchange)?;
+        Ok(match self.peers.entry(peer) {
+            std::collections::hash_map::Entry::Occupied(mut e) => {
+                e.insert(caps);
+                e.into_mut()
+            }
+            std::collections::hash_map::Entry::Vacant(e) => e.insert(caps),
+        })
+    }
+
+    /// Drop a disconnected peer.
+    pub fn forget(&mut self, peer: &[u8; 32]) -> Option<PeerCapabilities> {
+        self.peers.remove(peer)
+    }
+
+    /// A peer's advertised capabilities.
+    pub fn get(&self, peer: &[u8; 32]) -> Option<&PeerCapabilities> {
+        self.peers.get(peer)
+    }
+
+    /// Whether `peer` accepts `msg_type`. Peers without an exchange only
+    /// accept the [`ALWAYS_ALLOWED`] types.
+    pub fn supports(&self, peer: &[u8; 32], msg_type: u16) -> bool {
+        match self.peers.get(peer) {
+            Some(caps) => caps.supports(msg_type),
+            None => ALWAYS_ALLOWED.contains(&msg_type),
+        }
+    }
+
+    /// Peers that accept `msg_type`.
+    pub fn peers_supporting(&self, msg_type: u16) -> Vec<[u8; 32]> {
+        self.peers
+            .iter()
+            .filter(|(_, caps)| caps.supports(msg_type))
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    /// Peers advertising `feature`.
+    pub fn peers_with_feature(&self, feature: u64) -> Vec<[u8; 32]> {
+        self.peers
+            .iter()
+            .filter(|(_, caps)| caps.has_feature(feature))
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    /// Check that `msg_type` may be sent to `peer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::UnsupportedMessage`] if the peer did not
+    /// advertise the type.
+    pub fn check_send(&self, peer: &[u8; 32], msg_type: u16) -> Result<(), TransportError> {
+        if self.supports(peer, msg_type) {
+            Ok(())
+        } else {
+            Err(TransportError::UnsupportedMessage(msg_type))
+        }
+    }
+
+    /// Number of peers with recorded capabilities.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Whether no peer has completed an exchange.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{Ping, MSG_DHT_GET, MSG_PING};
+
+    fn exchange(supported: Vec<u16>) -> CapabilityExchange {
+        CapabilityExchange {
+            protocol_version: PROTOCOL_VERSION,
+            node_id: [1; 32],
+            features: 0,
+            agent: "test/0.1".to_string(),
+            supported_messages: supported,
+            delegation: None,
+            network_magic: MAINNET_MAGIC,
+        }
+    }
+
+    #[test]
+    fn test_send_gated_on_advertised_types() {
+        let mut registry = CapabilityRegistry::new();
+        let peer = [7u8; 32];
+        registry
+            .record(peer, &exchange(vec![MSG_PING]))
+            .expect("record");
+
+        assert!(registry.check_send(&peer, MSG_PING).is_ok());
+        assert!(matches!(
+            registry.check_send(&peer, MSG_DHT_GET),
+            Err(TransportError::UnsupportedMessage(MSG_DHT_GET))
+        ));
+        // Control messages are always allowed.
+        assert!(registry.check_send(&peer, MSG_GOODBYE).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_peer_only_gets_control_messages() {
+        let registry = CapabilityRegistry::new();
+        let peer = [9u8; 32];
+        assert!(registry.check_send(&peer, MSG_CAPABILITY_EXCHANGE).is_ok());
+        assert!(registry.check_send(&peer, MSG_PING).is_err());
+    }
+
+    #[test]
+    fn test_version_mismatch_rejected() {
+        let mut registry = CapabilityRegistry::new();
+        let mut ex = exchange(vec![MSG_PING]);
+        ex.protocol_version = PROTOCOL_VERSION + 1;
+        assert!(registry.record([1; 32], &ex).is_err());
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_other_network_rejected() {
+        use ochra_types::profile::{DEVNET_MAGIC, TESTNET_MAGIC};
+
+        let mut registry = CapabilityRegistry::for_network(TESTNET_MAGIC);
+        let mut ex = exchange(vec![MSG_PING]);
+        assert!(matches!(
+            registry.record([1; 32], &ex),
+            Err(TransportError::ProtocolViolation(_))
+        ));
+        ex.network_magic = DEVNET_MAGIC;
+        assert!(registry.record([1; 32], &ex).is_err());
+        ex.network_magic = TESTNET_MAGIC;
+        assert!(registry.record([1; 32], &ex).is_ok());
+    }
+
+    #[test]
+    fn test_peers_supporting_and_forget() {
+        let mut registry = CapabilityRegistry::new();
+        registry
+            .record([1; 32], &exchange(vec![MSG_PING, MSG_DHT_GET]))
+            .expect("record");
+        registry
+            .record([2; 32], &exchange(vec![MSG_PING]))
+            .expect("record");
+
+        assert_eq!(registry.peers_supporting(MSG_DHT_GET), vec![[1u8; 32]]);
+        assert_eq!(registry.peers_supporting(MSG_PING).len(), 2);
+
+        registry.forget(&[1; 32]);
+        assert!(registry.peers_supporting(MSG_DHT_GET).is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_reply_for_unknown_type() {
+        let known =
+            ProtocolMessage::from_typed(&TypedMessage::Ping(Ping { nonce: [0; 8] })).expect("msg");
+        assert!(unsupported_reply(&known).expect("reply").is_none());
+
+        let unknown = ProtocolMessage::from_raw_payload(0x0fff, vec![0xa0]).expect("msg");
+        let reply = unsupported_reply(&unknown)
+            .expect("reply")
+            .expect("unsupported");
+        assert_eq!(reply.msg_type, MSG_UNSUPPORTED);
+        let decoded = reply.decode_payload_strict().expect("decode");
+        assert!(matches!(
+            decoded,
+            TypedMessage::Unsupported(Unsupported { msg_type: 0x0fff, msg_id, reason: 0 })
+                if msg_id == unknown.msg_id
+        ));
+    }
+
+    #[test]
+    fn test_local_exchange_advertises_all_types() {
+        let ex = local_exchange([3; 32], 0, "ochra-daemon/test", MAINNET_MAGIC);
+        let caps = PeerCapabilities::from_exchange(&ex).expect("caps");
+        for msg_type in ALL_MESSAGE_TYPES {
+            assert!(caps.supports(*msg_type));
+            assert!(crate::strict::max_payload_size(*msg_type).is_some());
+        }
+    }
+
+    #[test]
+    fn test_feature_bits() {
+        let mut registry = CapabilityRegistry::new();
+        let mut relay = exchange(Vec::new());
+        relay.features = FEATURE_RELAY | FEATURE_STORAGE;
+        registry.record([1; 32], &relay).expect("record");
+        registry
+            .record([2; 32], &exchange(Vec::new()))
+            .expect("record");
+
+        let caps = registry.get(&[1; 32]).expect("caps");
+        assert!(caps.has_feature(FEATURE_RELAY));
+        assert!(caps.has_feature(FEATURE_RELAY | FEATURE_STORAGE));
+        assert!(!caps.has_feature(FEATURE_STORAGE | FEATURE_QUORUM_CANDIDATE));
+        assert_eq!(registry.peers_with_feature(FEATURE_RELAY), vec![[1; 32]]);
+        assert!(registry
+            .peers_with_feature(FEATURE_QUORUM_CANDIDATE)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_operational_key_must_be_delegated_by_peer_pik() {
+        use ochra_crypto::ed25519::{derive_node_id, KeyPair};
+
+        let pik = KeyPair::generate();
+        let sub = KeyPair::generate();
+        let cert = DelegationCert::issue(
+            &pik.signing_key,
+            &sub.verifying_key,
+            DelegationScope::TRANSPORT,
+            10,
+            20,
+        )
+        .expect("cert");
+        let node_id = derive_node_id(&pik.verifying_key);
+        let mut ex = exchange(vec![MSG_PING]);
+        ex.node_id = node_id;
+        ex.delegation = Some(cert.to_bytes());
+
+        let mut registry = CapabilityRegistry::new();
+        let caps = registry.record(node_id, &ex).expect("record");
+        assert_eq!(caps.operational_key, Some(cert));
+        let sig = sub.signing_key.sign(b"hello");
+        assert!(caps
+            .verify_operational(DelegationScope::TRANSPORT, 15, b"hello", &sig)
+            .is_ok());
+        assert!(caps
+            .verify_operational(DelegationScope::RELAY, 15, b"hello", &sig)
+            .is_err());
+
+        // A certificate from some other PIK is a protocol violation.
+        ex.node_id = [1; 32];
+        assert!(matches!(
+            registry.record([1; 32], &ex),
+            Err(TransportError::ProtocolViolation(_))
+        ));
+        ex.delegation = Some(vec![0; 8]);
+        assert!(registry.record([1; 32], &ex).is_err());
+    }
+}
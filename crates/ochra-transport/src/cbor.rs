@@ -4,7 +4,17 @@
 //! deserialization of protocol payloads to/from CBOR (RFC 8949). All message
 //! payloads in the Ochra protocol are CBOR-encoded before being placed into
 //! the [`ProtocolMessage`](crate::wire::ProtocolMessage) envelope.
+//!
+//! ## Canonical encoding
+//!
+//! Anything that is signed is encoded with the RFC 8949 core deterministic
+//! encoding (Section 4.2.1) via [`to_canonical_vec`]: shortest-form integers,
+//! lengths, and floats, definite lengths only, and map entries sorted by the
+//! bytewise order of their encoded keys. Two encoders then always agree on
+//! the bytes a signature covers. [`from_canonical_slice`] rejects signed input
+//! that is not in this form instead of silently re-encoding it.
 
+use ciborium::Value;
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::TransportError;
@@ -57,6 +67,80 @@ pub fn from_slice_named<T: DeserializeOwned>(
     })
 }
 
+/// Serialize a value with the core deterministic encoding.
+///
+/// # Errors
+///
+/// Returns [`TransportError::Serialization`] if the value cannot be
+/// serialized or a map has two entries with the same key.
+pub fn to_canonical_vec<T: Serialize>(value: &T) -> Result<Vec<u8>, TransportError> {
+    let value = Value::serialized(value)
+        .map_err(|e| TransportError::Serialization(format!("CBOR serialization failed: {e}")))?;
+    encode_value(&canonicalize(value)?)
+}
+
+/// Whether `data` is exactly one item in the core deterministic encoding.
+pub fn is_canonical(data: &[u8]) -> bool {
+    let Ok(value) = ciborium::from_reader::<Value, _>(data) else {
+        return false;
+    };
+    // Decoding normalises integer widths and indefinite lengths, so any
+    // non-canonical input re-encodes to different bytes
+    canonicalize(value)
+        .and_then(|v| encode_value(&v))
+        .is_ok_and(|encoded| encoded == data)
+}
+
+/// Deserialize a value that must be canonically encoded.
+///
+/// # Errors
+///
+/// Returns [`TransportError::Deserialization`] if the bytes are not in the
+/// core deterministic encoding or do not deserialize into the target type.
+pub fn from_canonical_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, TransportError> {
+    if !is_canonical(data) {
+        return Err(TransportError::Deserialization(
+            "CBOR is not canonically encoded".to_string(),
+        ));
+    }
+    from_slice(data)
+}
+
+fn encode_value(value: &Value) -> Result<Vec<u8>, TransportError> {
+    let mut buf = Vec::new();
+    ciborium::into_writer(value, &mut buf)
+        .map_err(|e| TransportError::Serialization(format!("CBOR serialization failed: {e}")))?;
+    Ok(buf)
+}
+
+/// Sort every map by encoded key, recursively.
+fn canonicalize(value: Value) -> Result<Value, TransportError> {
+    Ok(match value {
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(canonicalize)
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map(entries) => {
+            let mut keyed = Vec::with_capacity(entries.len());
+            for (k, v) in entries {
+                let k = canonicalize(k)?;
+                keyed.push((encode_value(&k)?, k, canonicalize(v)?));
+            }
+            keyed.sort_by(|a, b| a.0.cmp(&b.0));
+            if keyed.windows(2).any(|w| w[0].0 == w[1].0) {
+                return Err(TransportError::Serialization(
+                    "duplicate CBOR map key".to_string(),
+                ));
+            }
+            Value::Map(keyed.into_iter().map(|(_, k, v)| (k, v)).collect())
+        }
+        Value::Tag(tag, inner) => Value::Tag(tag, Box::new(canonicalize(*inner)?)),
+        other => other,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,6 +179,55 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Unsorted {
+        zeta: u64,
+        alpha: Vec<u8>,
+        mid: std::collections::HashMap<String, i32>,
+    }
+
+    #[test]
+    fn test_canonical_sorts_keys_and_round_trips() {
+        let value = Unsorted {
+            zeta: 500,
+            alpha: vec![1, 2, 3],
+            mid: [
+                ("bb".to_string(), -1),
+                ("a".to_string(), 2),
+                ("c".to_string(), 3),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let canonical = to_canonical_vec(&value).expect("encode");
+        assert!(is_canonical(&canonical));
+        assert_eq!(to_canonical_vec(&value).expect("encode"), canonical);
+        let restored: Unsorted = from_canonical_slice(&canonical).expect("decode");
+        assert_eq!(restored, value);
+
+        // Declaration order puts "zeta" before "alpha"
+        let plain = to_vec(&value).expect("encode");
+        assert!(!is_canonical(&plain));
+        assert!(from_canonical_slice::<Unsorted>(&plain).is_err());
+    }
+
+    #[test]
+    fn test_non_canonical_encodings_rejected() {
+        // 1 encoded in two bytes instead of one
+        assert!(!is_canonical(&[0x18, 0x01]));
+        assert!(is_canonical(&[0x01]));
+        // Indefinite-length array [1]
+        assert!(!is_canonical(&[0x9f, 0x01, 0xff]));
+        // 1.0 as a double rather than a half-float
+        assert!(!is_canonical(&[0xfb, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0]));
+        assert!(is_canonical(&[0xf9, 0x3c, 0x00]));
+        // Trailing bytes
+        assert!(!is_canonical(&[0x01, 0x01]));
+        // Map keys out of order: {"b": 1, "a": 2}
+        assert!(!is_canonical(&[0xa2, 0x61, b'b', 0x01, 0x61, b'a', 0x02]));
+        assert!(is_canonical(&[0xa2, 0x61, b'a', 0x02, 0x61, b'b', 0x01]));
+    }
+
     #[test]
     fn test_cbor_is_compact() {
         let ping = Ping { nonce: [0; 8] };
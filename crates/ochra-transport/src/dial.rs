@@ -0,0 +1,615 @@
+//! Outbound dial scheduling with per-subsystem quotas.
+//!
+//! DHT lookups, chunk fetches and circuit builds each open connections
+//! on their own schedule. Left alone, a burst in one of them can use up
+//! the process's sockets and starve the others. Every outbound dial
+//! therefore first takes a [`DialPermit`] from a shared
+//! [`DialScheduler`]:
+//!
+//! - At most [`DialQuotas::global`] dials are in flight at once, and at
+//!   most the subsystem's own quota for each [`DialSubsystem`].
+//! - A dial that cannot start yet waits in its subsystem's queue, ordered
+//!   by [`DialPriority`] and then arrival. Queues are bounded; a full
+//!   queue fails fast with [`TransportError::DialQueueFull`].
+//! - When a slot frees up, the waiting subsystem with the most urgent
+//!   head dial goes next. Ties go to the subsystem using the smallest
+//!   fraction of its quota, then round robin, so a busy subsystem cannot
+//!   keep the others waiting.
+//!
+//! Callers report each dial's outcome on its permit, and per-subsystem
+//! [`DialStats`] (in flight, queued, success rate) are kept for metrics.
+//! Dropping a permit frees its slot; dropping it without reporting counts
+//! the dial as cancelled. Dropping a waiting `acquire` future leaves the
+//! queue cleanly.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::TransportError;
+
+/// Default bound on each subsystem's wait queue.
+pub const DEFAULT_DIAL_QUEUE_LEN: usize = 256;
+
+/// A part of the daemon that opens outbound connections.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialSubsystem {
+    /// DHT lookups and record stores.
+    Dht,
+    /// Chunk fetches and content transfer.
+    Content,
+    /// Onion circuit builds.
+    Circuit,
+    /// Gossip mesh peers.
+    Gossip,
+    /// Everything else (bootstrap lists, oracle sources).
+    Other,
+}
+
+impl DialSubsystem {
+    /// All subsystems.
+    pub const ALL: [DialSubsystem; 5] = [
+        DialSubsystem::Dht,
+        DialSubsystem::Content,
+        DialSubsystem::Circuit,
+        DialSubsystem::Gossip,
+        DialSubsystem::Other,
+    ];
+
+    /// Lowercase name, for metrics and errors.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DialSubsystem::Dht => "dht",
+            DialSubsystem::Content => "content",
+            DialSubsystem::Circuit => "circuit",
+            DialSubsystem::Gossip => "gossip",
+            DialSubsystem::Other => "other",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How urgently a dial should start. Variants are ordered most urgent
+/// first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialPriority {
+    /// A user is waiting on the result.
+    High,
+    /// Ordinary background work.
+    Normal,
+    /// Speculative or maintenance dials.
+    Low,
+}
+
+/// Concurrent dial limits, overall and per subsystem.
+///
+/// Subsystem quotas may add up to more than the global limit; the global
+/// limit is what bounds socket use, and the per-subsystem quotas keep any
+/// one subsystem from taking all of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DialQuotas {
+    pub global: usize,
+    pub dht: usize,
+    pub content: usize,
+    pub circuit: usize,
+    pub gossip: usize,
+    pub other: usize,
+}
+
+impl Default for DialQuotas {
+    fn default() -> Self {
+        Self {
+            global: 64,
+            dht: 24,
+            content: 32,
+            circuit: 16,
+            gossip: 8,
+            other: 8,
+        }
+    }
+}
+
+impl DialQuotas {
+    /// Quota of one subsystem.
+    pub fn of(&self, subsystem: DialSubsystem) -> usize {
+        match subsystem {
+            DialSubsystem::Dht => self.dht,
+            DialSubsystem::Content => self.content,
+            DialSubsystem::Circuit => self.circuit,
+            DialSubsystem::Gossip => self.gossip,
+            DialSubsystem::Other => self.other,
+        }
+    }
+
+    /// Check that every limit is non-zero and no subsystem quota exceeds
+    /// the global limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Internal`] describing the problem.
+    pub fn validate(&self) -> Result<(), TransportError> {
+        if self.global == 0 {
+            return Err(TransportError::Internal(
+                "global dial limit must be non-zero".to_string(),
+            ));
+        }
+        for subsystem in DialSubsystem::ALL {
+            let quota = self.of(subsystem);
+            if quota == 0 {
+                return Err(TransportError::Internal(format!(
+                    "{} dial quota must be non-zero",
+                    subsystem.as_str()
+                )));
+            }
+            if quota > self.global {
+                return Err(TransportError::Internal(format!(
+                    "{} dial quota {quota} exceeds global limit {}",
+                    subsystem.as_str(),
+                    self.global
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counters for one subsystem.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DialStats {
+    /// Dials currently holding a permit.
+    pub in_flight: usize,
+    /// Dials waiting for a permit.
+    pub queued: usize,
+    /// Permits handed out.
+    pub granted: u64,
+    /// Dials reported as succeeded.
+    pub succeeded: u64,
+    /// Dials reported as failed.
+    pub failed: u64,
+    /// Permits dropped without an outcome, or waits abandoned.
+    pub cancelled: u64,
+    /// Dials refused because the queue was full.
+    pub rejected: u64,
+}
+
+impl DialStats {
+    /// Fraction of reported dials that succeeded, if any were reported.
+    pub fn success_rate(&self) -> Option<f64> {
+        let reported = self.succeeded + self.failed;
+        if reported == 0 {
+            None
+        } else {
+            Some(self.succeeded as f64 / reported as f64)
+        }
+    }
+}
+
+struct Waiter {
+    priority: DialPriority,
+    id: u64,
+    tx: oneshot::Sender<DialPermit>,
+}
+
+#[derive(Default)]
+struct SubsystemState {
+    waiters: VecDeque<Waiter>,
+    stats: DialStats,
+}
+
+struct Inner {
+    subsystems: [SubsystemState; 5],
+    in_flight: usize,
+    cursor: usize,
+    next_id: u64,
+}
+
+/// Global dial scheduler shared by every subsystem.
+pub struct DialScheduler {
+    quotas: DialQuotas,
+    queue_len: usize,
+    inner: Mutex<Inner>,
+}
+
+impl Default for DialScheduler {
+    fn default() -> Self {
+        Self::with_quotas(DialQuotas::default(), DEFAULT_DIAL_QUEUE_LEN)
+    }
+}
+
+impl DialScheduler {
+    /// Create a scheduler with the given limits and per-subsystem queue
+    /// bound.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Internal`] if the quotas are invalid or
+    /// the queue bound is zero.
+    pub fn new(quotas: DialQuotas, queue_len: usize) -> Result<Self, TransportError> {
+        quotas.validate()?;
+        if queue_len == 0 {
+            return Err(TransportError::Internal(
+                "dial queue bound must be non-zero".to_string(),
+            ));
+        }
+        Ok(Self::with_quotas(quotas, queue_len))
+    }
+
+    fn with_quotas(quotas: DialQuotas, queue_len: usize) -> Self {
+        Self {
+            quotas,
+            queue_len,
+            inner: Mutex::new(Inner {
+                subsystems: Default::default(),
+                in_flight: 0,
+                cursor: 0,
+                next_id: 0,
+            }),
+        }
+    }
+
+    /// The configured limits.
+    pub fn quotas(&self) -> DialQuotas {
+        self.quotas
+    }
+
+    /// Dials in flight across all subsystems.
+    pub fn in_flight(&self) -> usize {
+        self.lock().in_flight
+    }
+
+    /// Current counters for one subsystem.
+    pub fn stats(&self, subsystem: DialSubsystem) -> DialStats {
+        let inner = self.lock();
+        let state = &inner.subsystems[subsystem.index()];
+        DialStats {
+            queued: state.waiters.len(),
+            ..state.stats
+        }
+    }
+
+    /// Wait for a permit to dial on behalf of `subsystem`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::DialQueueFull`] if the subsystem already
+    /// has the maximum number of dials waiting.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        subsystem: DialSubsystem,
+        priority: DialPriority,
+    ) -> Result<DialPermit, TransportError> {
+        let (id, rx) = {
+            let mut inner = self.lock();
+            let index = subsystem.index();
+            let state = &inner.subsystems[index];
+            if state.waiters.is_empty()
+                && inner.in_flight < self.quotas.global
+                && state.stats.in_flight < self.quotas.of(subsystem)
+            {
+                inner.in_flight += 1;
+                let stats = &mut inner.subsystems[index].stats;
+                stats.in_flight += 1;
+                stats.granted += 1;
+                return Ok(DialPermit::new(Arc::clone(self), subsystem));
+            }
+            if state.waiters.len() >= self.queue_len {
+                inner.subsystems[index].stats.rejected += 1;
+                return Err(TransportError::DialQueueFull(subsystem.as_str()));
+            }
+            let id = inner.next_id;
+            inner.next_id += 1;
+            let (tx, rx) = oneshot::channel();
+            let waiters = &mut inner.subsystems[index].waiters;
+            let at = waiters
+                .iter()
+                .position(|w| w.priority > priority)
+                .unwrap_or(waiters.len());
+            waiters.insert(at, Waiter { priority, id, tx });
+            (id, rx)
+        };
+        let mut wait = Wait {
+            scheduler: self,
+            subsystem,
+            id,
+            rx: Some(rx),
+        };
+        let rx = wait.rx.as_mut().ok_or_else(|| {
+            TransportError::Internal("dial wait polled after completion".to_string())
+        })?;
+        let permit = rx
+            .await
+            .map_err(|_| TransportError::Internal("dial scheduler dropped".to_string()))?;
+        wait.rx = None;
+        Ok(permit)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn release(self: &Arc<Self>, subsystem: DialSubsystem, outcome: Option<bool>) {
+        let mut inner = self.lock();
+        inner.in_flight = inner.in_flight.saturating_sub(1);
+        let stats = &mut inner.subsystems[subsystem.index()].stats;
+        stats.in_flight = stats.in_flight.saturating_sub(1);
+        match outcome {
+            Some(true) => stats.succeeded += 1,
+            Some(false) => stats.failed += 1,
+            None => stats.cancelled += 1,
+        }
+        self.grant_waiting(&mut inner);
+    }
+
+    /// Hand out permits to waiters while there is room.
+    fn grant_waiting(self: &Arc<Self>, inner: &mut Inner) {
+        while inner.in_flight < self.quotas.global {
+            let Some(index) = self.next_subsystem(inner) else {
+                return;
+            };
+            let subsystem = DialSubsystem::ALL[index];
+            inner.cursor = (index + 1) % DialSubsystem::ALL.len();
+            let Some(waiter) = inner.subsystems[index].waiters.pop_front() else {
+                continue;
+            };
+            let state = &mut inner.subsystems[index];
+            match waiter.tx.send(DialPermit::new(Arc::clone(self), subsystem)) {
+                Ok(()) => {
+                    state.stats.in_flight += 1;
+                    state.stats.granted += 1;
+                    inner.in_flight += 1;
+                }
+                Err(mut permit) => {
+                    // The waiter went away between its last poll and now.
+                    // Defuse so dropping the permit here does not re-enter
+                    // the scheduler.
+                    permit.subsystem = None;
+                    state.stats.cancelled += 1;
+                }
+            }
+        }
+    }
+
+    /// The waiting subsystem under quota that should dial next.
+    fn next_subsystem(&self, inner: &Inner) -> Option<usize> {
+        let count = DialSubsystem::ALL.len();
+        (0..count)
+            .map(|offset| (inner.cursor + offset) % count)
+            .filter_map(|index| {
+                let subsystem = DialSubsystem::ALL[index];
+                let state = &inner.subsystems[index];
+                let quota = self.quotas.of(subsystem);
+                let head = state.waiters.front()?;
+                (state.stats.in_flight < quota).then_some((index, head.priority, state, quota))
+            })
+            .min_by(|a, b| {
+                // Compare in_flight / quota without floating point.
+                let load_a = a.2.stats.in_flight * b.3;
+                let load_b = b.2.stats.in_flight * a.3;
+                a.1.cmp(&b.1).then(load_a.cmp(&load_b))
+            })
+            .map(|(index, ..)| index)
+    }
+
+    fn abandon(&self, subsystem: DialSubsystem, id: u64) {
+        let mut inner = self.lock();
+        let state = &mut inner.subsystems[subsystem.index()];
+        if let Some(at) = state.waiters.iter().position(|w| w.id == id) {
+            state.waiters.remove(at);
+            state.stats.cancelled += 1;
+        }
+    }
+}
+
+/// Removes an abandoned waiter from its queue.
+struct Wait<'a> {
+    scheduler: &'a Arc<DialScheduler>,
+    subsystem: DialSubsystem,
+    id: u64,
+    rx: Option<oneshot::Receiver<DialPermit>>,
+}
+
+impl Drop for Wait<'_> {
+    fn drop(&mut self) {
+        if let Some(mut rx) = self.rx.take() {
+            self.scheduler.abandon(self.subsystem, self.id);
+            // A permit may have been sent after the last poll; take it out
+            // so its slot is released here, outside the scheduler lock.
+            drop(rx.try_recv());
+        }
+    }
+}
+
+/// Permission to make one outbound dial. Releases its slot on drop.
+pub struct DialPermit {
+    scheduler: Arc<DialScheduler>,
+    /// `None` once defused (never handed to a caller).
+    subsystem: Option<DialSubsystem>,
+    outcome: Option<bool>,
+}
+
+impl DialPermit {
+    fn new(scheduler: Arc<DialScheduler>, subsystem: DialSubsystem) -> Self {
+        Self {
+            scheduler,
+            subsystem: Some(subsystem),
+            outcome: None,
+        }
+    }
+
+    /// Record that the dial connected.
+    pub fn succeeded(mut self) {
+        self.outcome = Some(true);
+    }
+
+    /// Record that the dial failed.
+    pub fn failed(mut self) {
+        self.outcome = Some(false);
+    }
+}
+
+impl Drop for DialPermit {
+    fn drop(&mut self) {
+        if let Some(subsystem) = self.subsystem.take() {
+            self.scheduler.release(subsystem, self.outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(global: usize, quota: usize, queue_len: usize) -> Arc<DialScheduler> {
+        let quotas = DialQuotas {
+            global,
+            dht: quota,
+            content: quota,
+            circuit: quota,
+            gossip: quota,
+            other: quota,
+        };
+        Arc::new(DialScheduler::new(quotas, queue_len).expect("valid quotas"))
+    }
+
+    #[test]
+    fn test_default_quotas_valid() {
+        assert!(DialQuotas::default().validate().is_ok());
+        let zero = DialQuotas {
+            gossip: 0,
+            ..Default::default()
+        };
+        assert!(zero.validate().is_err());
+        let over = DialQuotas {
+            content: 65,
+            ..Default::default()
+        };
+        assert!(over.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subsystem_quota_enforced() {
+        let dials = scheduler(8, 2, 4);
+        let a = dials
+            .acquire(DialSubsystem::Dht, DialPriority::Normal)
+            .await
+            .expect("permit");
+        let _b = dials
+            .acquire(DialSubsystem::Dht, DialPriority::Normal)
+            .await
+            .expect("permit");
+        let mut third = Box::pin(dials.acquire(DialSubsystem::Dht, DialPriority::Normal));
+        assert!(futures_poll(&mut third).is_none());
+        assert_eq!(dials.stats(DialSubsystem::Dht).queued, 1);
+
+        // Other subsystems are unaffected by the DHT quota.
+        let _c = dials
+            .acquire(DialSubsystem::Content, DialPriority::Normal)
+            .await
+            .expect("permit");
+
+        a.succeeded();
+        let third = third.await.expect("granted after release");
+        let stats = dials.stats(DialSubsystem::Dht);
+        assert_eq!(stats.in_flight, 2);
+        assert_eq!(stats.queued, 0);
+        assert_eq!(stats.succeeded, 1);
+        third.failed();
+        assert_eq!(dials.stats(DialSubsystem::Dht).success_rate(), Some(0.5));
+    }
+
+    #[tokio::test]
+    async fn test_queue_full_rejected() {
+        let dials = scheduler(1, 1, 1);
+        let _held = dials
+            .acquire(DialSubsystem::Gossip, DialPriority::Normal)
+            .await
+            .expect("permit");
+        let mut waiting = Box::pin(dials.acquire(DialSubsystem::Gossip, DialPriority::Normal));
+        assert!(futures_poll(&mut waiting).is_none());
+        let result = dials
+            .acquire(DialSubsystem::Gossip, DialPriority::Normal)
+            .await;
+        assert!(matches!(
+            result,
+            Err(TransportError::DialQueueFull("gossip"))
+        ));
+        assert_eq!(dials.stats(DialSubsystem::Gossip).rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_priority_and_fairness_when_global_full() {
+        let dials = scheduler(3, 3, 8);
+        let mut held = Vec::new();
+        for _ in 0..2 {
+            held.push(
+                dials
+                    .acquire(DialSubsystem::Content, DialPriority::Normal)
+                    .await
+                    .expect("permit"),
+            );
+        }
+        let circuit = dials
+            .acquire(DialSubsystem::Circuit, DialPriority::Normal)
+            .await
+            .expect("permit");
+
+        let mut content = Box::pin(dials.acquire(DialSubsystem::Content, DialPriority::Normal));
+        let mut gossip = Box::pin(dials.acquire(DialSubsystem::Gossip, DialPriority::Normal));
+        let mut dht_high = Box::pin(dials.acquire(DialSubsystem::Dht, DialPriority::High));
+        assert!(futures_poll(&mut content).is_none());
+        assert!(futures_poll(&mut gossip).is_none());
+        assert!(futures_poll(&mut dht_high).is_none());
+
+        // The most urgent waiter goes first.
+        drop(circuit);
+        let dht = futures_poll(&mut dht_high).and_then(Result::ok);
+        assert!(dht.is_some());
+        assert!(futures_poll(&mut content).is_none());
+
+        // At equal priority, gossip (using none of its quota) goes before
+        // content (using two thirds of it).
+        drop(dht);
+        let gossip_permit = futures_poll(&mut gossip).and_then(Result::ok);
+        assert!(gossip_permit.is_some());
+        assert!(futures_poll(&mut content).is_none());
+        assert_eq!(dials.in_flight(), 3);
+        assert_eq!(dials.stats(DialSubsystem::Content).queued, 1);
+        assert_eq!(dials.stats(DialSubsystem::Circuit).cancelled, 1);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_wait_leaves_queue() {
+        let dials = scheduler(1, 1, 4);
+        let held = dials
+            .acquire(DialSubsystem::Other, DialPriority::Normal)
+            .await
+            .expect("permit");
+        let mut waiting = Box::pin(dials.acquire(DialSubsystem::Other, DialPriority::Normal));
+        assert!(futures_poll(&mut waiting).is_none());
+        drop(waiting);
+        assert_eq!(dials.stats(DialSubsystem::Other).queued, 0);
+
+        drop(held);
+        assert_eq!(dials.in_flight(), 0);
+        let stats = dials.stats(DialSubsystem::Other);
+        assert_eq!(stats.cancelled, 2);
+        assert_eq!(stats.success_rate(), None);
+    }
+
+    /// Poll a future once with a no-op waker.
+    fn futures_poll<F: std::future::Future + Unpin>(fut: &mut F) -> Option<F::Output> {
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        match std::pin::Pin::new(fut).poll(&mut cx) {
+            std::task::Poll::Ready(out) => Some(out),
+            std::task::Poll::Pending => None,
+        }
+    }
+}
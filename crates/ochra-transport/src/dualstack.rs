@@ -0,0 +1,221 @@
+//! IPv4/IPv6 dual-stack sockets and happy-eyeballs dialing.
+//!
+//! Nodes may advertise one address per IP family. [`bind_udp`] binds a single
+//! socket that serves both families when given the IPv6 unspecified address,
+//! and [`happy_eyeballs`] races connection attempts across a node's addresses
+//! in the style of RFC 8305: attempts start in [`dial_order`], staggered by
+//! [`CONNECTION_ATTEMPT_DELAY`], and the first to succeed wins.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::Duration;
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::{Result, TransportError};
+
+/// Delay before starting the next connection attempt (RFC 8305 §5).
+pub const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Bind a UDP socket at `addr`.
+///
+/// Binding the IPv6 unspecified address (`[::]`) clears `IPV6_V6ONLY`, so the
+/// socket also receives IPv4 traffic as IPv4-mapped addresses, regardless of
+/// the platform default.
+///
+/// # Errors
+///
+/// Returns [`TransportError::Io`] if the socket cannot be created or bound.
+pub fn bind_udp(addr: SocketAddr) -> Result<std::net::UdpSocket> {
+    let io_err = |e: std::io::Error| TransportError::Io(e.to_string());
+    let socket =
+        Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP)).map_err(io_err)?;
+    if addr.is_ipv6() && addr.ip().is_unspecified() {
+        socket.set_only_v6(false).map_err(io_err)?;
+    }
+    socket.bind(&addr.into()).map_err(io_err)?;
+    socket.set_nonblocking(true).map_err(io_err)?;
+    Ok(socket.into())
+}
+
+/// Order `addrs` for dialing: families alternate, starting with IPv6 when
+/// `prefer_ipv6` is set. Order within a family is kept.
+pub fn dial_order(addrs: &[SocketAddr], prefer_ipv6: bool) -> Vec<SocketAddr> {
+    let (first, second): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addrs.iter().partition(|a| a.is_ipv6() == prefer_ipv6);
+    let mut ordered = Vec::with_capacity(addrs.len());
+    let (mut first, mut second) = (first.into_iter(), second.into_iter());
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+    ordered
+}
+
+/// Connect to the first reachable address, racing staggered attempts.
+///
+/// `addrs` are tried in the given order (see [`dial_order`]). Each attempt
+/// gets `delay` to succeed before the next one starts alongside it; a failed
+/// attempt starts the next one immediately. Attempts still running when one
+/// succeeds are dropped.
+///
+/// # Errors
+///
+/// Returns the last attempt's error if every address fails, or
+/// [`TransportError::Connection`] if `addrs` is empty.
+pub async fn happy_eyeballs<T, F, Fut>(
+    addrs: &[SocketAddr],
+    delay: Duration,
+    mut connect: F,
+) -> Result<(SocketAddr, T)>
+where
+    F: FnMut(SocketAddr) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    enum Event<T> {
+        Finished(usize, Result<T>),
+        Stagger,
+    }
+
+    let mut pending = addrs.iter().copied();
+    let mut running: Vec<(SocketAddr, Pin<Box<Fut>>)> = Vec::new();
+    let mut last_err = None;
+
+    loop {
+        if running.is_empty() {
+            match pending.next() {
+                Some(addr) => running.push((addr, Box::pin(connect(addr)))),
+                None => {
+                    return Err(last_err.unwrap_or_else(|| {
+                        TransportError::Connection("no addresses to dial".to_string())
+                    }))
+                }
+            }
+        }
+
+        let more = pending.len() > 0;
+        let event = {
+            let first_done = std::future::poll_fn(|cx| {
+                for (i, (_, attempt)) in running.iter_mut().enumerate() {
+                    if let Poll::Ready(result) = attempt.as_mut().poll(cx) {
+                        return Poll::Ready(Event::Finished(i, result));
+                    }
+                }
+                Poll::Pending
+            });
+            tokio::select! {
+                event = first_done => event,
+                _ = tokio::time::sleep(delay), if more => Event::Stagger,
+            }
+        };
+
+        match event {
+            Event::Finished(i, Ok(conn)) => return Ok((running[i].0, conn)),
+            Event::Finished(i, Err(e)) => {
+                let (addr, _) = running.swap_remove(i);
+                tracing::debug!(%addr, error = %e, "connection attempt failed");
+                last_err = Some(e);
+                if let Some(next) = pending.next() {
+                    running.push((next, Box::pin(connect(next))));
+                }
+            }
+            Event::Stagger => {
+                if let Some(next) = pending.next() {
+                    running.push((next, Box::pin(connect(next))));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v4(port: u16) -> SocketAddr {
+        SocketAddr::from(([192, 0, 2, 1], port))
+    }
+
+    fn v6(port: u16) -> SocketAddr {
+        SocketAddr::from((
+            std::net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            port,
+        ))
+    }
+
+    #[test]
+    fn test_dial_order_interleaves_families() {
+        let addrs = [v4(1), v4(2), v6(3), v4(4)];
+        assert_eq!(dial_order(&addrs, true), vec![v6(3), v4(1), v4(2), v4(4)]);
+        assert_eq!(dial_order(&addrs, false), vec![v4(1), v6(3), v4(2), v4(4)]);
+        assert!(dial_order(&[], true).is_empty());
+    }
+
+    #[test]
+    fn test_bind_udp_dual_stack() {
+        let socket = bind_udp("[::]:0".parse().expect("addr")).expect("bind [::]");
+        let port = socket.local_addr().expect("local addr").port();
+
+        // IPv4 traffic reaches the IPv6 socket.
+        let sender = std::net::UdpSocket::bind("127.0.0.1:0").expect("bind v4");
+        sender.send_to(b"hello", ("127.0.0.1", port)).expect("send");
+        socket.set_nonblocking(false).expect("blocking");
+        socket
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .expect("timeout");
+        let mut buf = [0u8; 8];
+        let (n, from) = socket.recv_from(&mut buf).expect("recv");
+        assert_eq!(&buf[..n], b"hello");
+        assert!(matches!(from.ip(), std::net::IpAddr::V6(ip) if ip.to_ipv4_mapped().is_some()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_happy_eyeballs_falls_back_after_delay() {
+        // The preferred address hangs; the second wins after one delay.
+        let started = tokio::time::Instant::now();
+        let (addr, value) =
+            happy_eyeballs(&[v6(1), v4(2)], CONNECTION_ATTEMPT_DELAY, |a| async move {
+                if a.is_ipv6() {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                }
+                Ok(a.port())
+            })
+            .await
+            .expect("connect");
+        assert_eq!((addr, value), (v4(2), 2));
+        assert_eq!(started.elapsed(), CONNECTION_ATTEMPT_DELAY);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_happy_eyeballs_failure_starts_next_immediately() {
+        let started = tokio::time::Instant::now();
+        let (addr, _) = happy_eyeballs(&[v6(1), v4(2)], CONNECTION_ATTEMPT_DELAY, |a| async move {
+            if a.is_ipv6() {
+                Err(TransportError::Connection("unreachable".to_string()))
+            } else {
+                Ok(())
+            }
+        })
+        .await
+        .expect("connect");
+        assert_eq!(addr, v4(2));
+        assert_eq!(started.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_happy_eyeballs_all_fail() {
+        let result: Result<(SocketAddr, ())> =
+            happy_eyeballs(&[v4(1), v6(2)], CONNECTION_ATTEMPT_DELAY, |a| async move {
+                Err(TransportError::Connection(format!("{a} refused")))
+            })
+            .await;
+        assert!(matches!(result, Err(TransportError::Connection(_))));
+        let empty: Result<(SocketAddr, ())> =
+            happy_eyeballs(&[], CONNECTION_ATTEMPT_DELAY, |_| async { Ok(()) }).await;
+        assert!(empty.is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Exit policy enforcement at the final Sphinx hop (Section 4.9).
+//!
+//! A relay publishes an [`ExitPolicy`] in its descriptor. When it is the
+//! exit of a circuit, every delivered [`ProtocolMessage`] passes through an
+//! [`ExitGate`] in [`process_packet_once`] before it is acted on:
+//!
+//! - the message type must fall in an allowed [`ExitClass`] (connection
+//!   control messages are never delivered at an exit);
+//! - with `no_storage`, messages that ask the exit to keep data
+//!   ([`stores_data`]) are refused;
+//! - each circuit gets a token bucket holding one second of its
+//!   [`ExitPolicy::circuit_limit_kbps`], and messages that overdraw it are
+//!   refused.
+//!
+//! The exit cannot see a circuit's ID, so callers key the gate by whatever
+//! circuit handle they track (for example the rendezvous cookie or the
+//! reply block tag). Circuits idle for [`EXIT_CIRCUIT_IDLE_MS`] are dropped
+//! by [`ExitGate::prune`].
+//!
+//! [`process_packet_once`]: crate::replay::process_packet_once
+
+use std::collections::HashMap;
+
+use ochra_types::network::{ExitClass, ExitPolicy};
+
+use crate::messages::*;
+use crate::sphinx::ProcessResult;
+use crate::wire::ProtocolMessage;
+use crate::TransportError;
+
+/// Circuits with no traffic for this long are forgotten (10 minutes, one
+/// circuit lifetime).
+pub const EXIT_CIRCUIT_IDLE_MS: u64 = 600_000;
+
+/// The exit class a message type belongs to, or `None` for connection
+/// control messages.
+pub fn exit_class(msg_type: u16) -> Option<ExitClass> {
+    match msg_type {
+        MSG_CHUNK_REQUEST..=0x001F => Some(ExitClass::Content),
+        MSG_DHT_GET..=0x002F => Some(ExitClass::Dht),
+        MSG_ESTABLISH_INTRO..=0x003F => Some(ExitClass::Rendezvous),
+        MSG_MLS_WELCOME..=0x004F => Some(ExitClass::Group),
+        MSG_FROST_DKG_ROUND1..=0x005F => Some(ExitClass::Quorum),
+        MSG_GOSSIP_PUBLISH..=0x006F => Some(ExitClass::Gossip),
+        MSG_WHISPER_SEND..=0x007F => Some(ExitClass::Whisper),
+        MSG_ORACLE_REQUEST..=0x008F => Some(ExitClass::Oracle),
+        MSG_RECOVERY_REQUEST..=0x009F => Some(ExitClass::Recovery),
+        MSG_STATE_SYNC_REQUEST..=0x00AF => Some(ExitClass::StateSync),
+        _ => None,
+    }
+}
+
+/// Whether a message type asks the receiver to store data on the
+/// sender's behalf.
+pub fn stores_data(msg_type: u16) -> bool {
+    matches!(msg_type, MSG_DHT_PUT | MSG_WHISPER_DEPOSIT)
+}
+
+/// Per-circuit byte budget.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    /// Bytes available now.
+    tokens: f64,
+    /// When `tokens` was last refilled.
+    updated_ms: u64,
+}
+
+/// Applies this relay's exit policy to delivered messages.
+pub struct ExitGate {
+    policy: ExitPolicy,
+    /// Per-circuit limit in bytes per millisecond.
+    bytes_per_ms: f64,
+    /// Bucket capacity in bytes (one second of traffic).
+    burst_bytes: f64,
+    circuits: HashMap<[u8; 16], Bucket>,
+}
+
+impl ExitGate {
+    /// Create a gate for `policy` on a relay capped at `bandwidth_cap_mbps`.
+    pub fn new(policy: ExitPolicy, bandwidth_cap_mbps: u16) -> Self {
+        // kbit/s = 1000 bits per second = 1/8 byte per millisecond.
+        let bytes_per_ms = f64::from(policy.circuit_limit_kbps(bandwidth_cap_mbps)) / 8.0;
+        Self {
+            policy,
+            bytes_per_ms,
+            burst_bytes: bytes_per_ms * 1000.0,
+            circuits: HashMap::new(),
+        }
+    }
+
+    /// The policy being enforced.
+    pub fn policy(&self) -> &ExitPolicy {
+        &self.policy
+    }
+
+    /// Admit or refuse a message delivered on `circuit`, charging its
+    /// encoded size to the circuit's budget when admitted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ExitRefused`] if the policy forbids the
+    /// message's class or storage, or the circuit is over its bandwidth.
+    pub fn admit(
+        &mut self,
+        circuit: [u8; 16],
+        msg: &ProtocolMessage,
+        now_ms: u64,
+    ) -> Result<(), TransportError> {
+        let class = exit_class(msg.msg_type).ok_or_else(|| {
+            TransportError::ExitRefused(format!("message type 0x{:04x}", msg.msg_type))
+        })?;
+        if !self.policy.allowed_classes.contains(&class) {
+            return Err(TransportError::ExitRefused(format!(
+                "{} traffic",
+                class.as_str()
+            )));
+        }
+        if self.policy.no_storage && stores_data(msg.msg_type) {
+            return Err(TransportError::ExitRefused(format!(
+                "storage request 0x{:04x}",
+                msg.msg_type
+            )));
+        }
+
+        let size = msg.payload.len() as f64;
+        let bucket = self.circuits.entry(circuit).or_insert(Bucket {
+            tokens: self.burst_bytes,
+            updated_ms: now_ms,
+        });
+        let elapsed = now_ms.saturating_sub(bucket.updated_ms) as f64;
+        bucket.tokens = (bucket.tokens + elapsed * self.bytes_per_ms).min(self.burst_bytes);
+        bucket.updated_ms = now_ms;
+        if size > bucket.tokens {
+            return Err(TransportError::ExitRefused(
+                "circuit bandwidth limit".to_string(),
+            ));
+        }
+        bucket.tokens -= size;
+        Ok(())
+    }
+
+    /// Decode the plaintext of a final-hop [`ProcessResult`] and admit it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::InvalidPacket`] for a forwarding result,
+    /// any decoding error from [`ProtocolMessage::from_bytes`], or the
+    /// errors of [`admit`](Self::admit).
+    pub fn deliver(
+        &mut self,
+        circuit: [u8; 16],
+        result: ProcessResult,
+        now_ms: u64,
+    ) -> Result<ProtocolMessage, TransportError> {
+        let ProcessResult::Deliver { plaintext } = result else {
+            return Err(TransportError::InvalidPacket(
+                "not a final-hop packet".to_string(),
+            ));
+        };
+        // The envelope is followed by the packet's padding, which the
+        // decoder leaves unread.
+        let msg = ProtocolMessage::from_bytes(&plaintext)?;
+        self.admit(circuit, &msg, now_ms)?;
+        Ok(msg)
+    }
+
+    /// Forget a circuit's budget (on teardown).
+    pub fn forget(&mut self, circuit: &[u8; 16]) {
+        self.circuits.remove(circuit);
+    }
+
+    /// Drop circuits idle for [`EXIT_CIRCUIT_IDLE_MS`].
+    pub fn prune(&mut self, now_ms: u64) {
+        self.circuits
+            .retain(|_, b| now_ms.saturating_sub(b.updated_ms) < EXIT_CIRCUIT_IDLE_MS);
+    }
+
+    /// Number of circuits being tracked.
+    pub fn circuit_count(&self) -> usize {
+        self.circuits.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(msg_type: u16, len: usize) -> ProtocolMessage {
+        ProtocolMessage::from_raw_payload(msg_type, vec![0u8; len]).expect("message")
+    }
+
+    #[test]
+    fn test_exit_class_ranges() {
+        assert_eq!(exit_class(MSG_CHUNK_REQUEST), Some(ExitClass::Content));
+        assert_eq!(exit_class(MSG_DHT_PUT), Some(ExitClass::Dht));
+        assert_eq!(exit_class(MSG_WHISPER_DEPOSIT), Some(ExitClass::Whisper));
+        assert_eq!(
+            exit_class(MSG_STATE_SYNC_RESPONSE),
+            Some(ExitClass::StateSync)
+        );
+        assert_eq!(exit_class(MSG_PING), None);
+    }
+
+    #[test]
+    fn test_default_policy_admits_everything_but_control() {
+        let mut gate = ExitGate::new(ExitPolicy::default(), 100);
+        assert!(gate.admit([1; 16], &msg(MSG_DHT_PUT, 100), 0).is_ok());
+        assert!(gate
+            .admit([1; 16], &msg(MSG_ORACLE_REQUEST, 100), 0)
+            .is_ok());
+        assert!(matches!(
+            gate.admit([1; 16], &msg(MSG_PING, 10), 0),
+            Err(TransportError::ExitRefused(_))
+        ));
+    }
+
+    #[test]
+    fn test_disallowed_class_refused() {
+        let policy = ExitPolicy {
+            allowed_classes: vec![ExitClass::Dht],
+            ..ExitPolicy::default()
+        };
+        let mut gate = ExitGate::new(policy, 100);
+        assert!(gate.admit([1; 16], &msg(MSG_DHT_GET, 10), 0).is_ok());
+        assert!(gate.admit([1; 16], &msg(MSG_CHUNK_REQUEST, 10), 0).is_err());
+    }
+
+    #[test]
+    fn test_no_storage_refuses_puts() {
+        let policy = ExitPolicy {
+            no_storage: true,
+            ..ExitPolicy::default()
+        };
+        let mut gate = ExitGate::new(policy, 100);
+        assert!(gate.admit([1; 16], &msg(MSG_DHT_GET, 10), 0).is_ok());
+        assert!(gate.admit([1; 16], &msg(MSG_DHT_PUT, 10), 0).is_err());
+        assert!(gate
+            .admit([1; 16], &msg(MSG_WHISPER_DEPOSIT, 10), 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_circuit_bandwidth_limit() {
+        // 80 kbit/s = 10 bytes/ms, 10_000 byte burst.
+        let policy = ExitPolicy {
+            max_circuit_kbps: 80,
+            ..ExitPolicy::default()
+        };
+        let mut gate = ExitGate::new(policy, 100);
+        assert!(gate.admit([1; 16], &msg(MSG_DHT_GET, 6000), 0).is_ok());
+        assert!(gate.admit([1; 16], &msg(MSG_DHT_GET, 6000), 0).is_err());
+        // Other circuits have their own budget.
+        assert!(gate.admit([2; 16], &msg(MSG_DHT_GET, 6000), 0).is_ok());
+        // 200 ms refills 2000 bytes.
+        assert!(gate.admit([1; 16], &msg(MSG_DHT_GET, 6000), 200).is_ok());
+    }
+
+    #[test]
+    fn test_prune_idle_circuits() {
+        let mut gate = ExitGate::new(ExitPolicy::default(), 100);
+        gate.admit([1; 16], &msg(MSG_DHT_GET, 10), 0)
+            .expect("admit");
+        gate.admit([2; 16], &msg(MSG_DHT_GET, 10), EXIT_CIRCUIT_IDLE_MS)
+            .expect("admit");
+        gate.prune(EXIT_CIRCUIT_IDLE_MS);
+        assert_eq!(gate.circuit_count(), 1);
+    }
+}
@@ -0,0 +1,764 @@
+//! GossipSub-style mesh management for gossip topics.
+//!
+//! Each subscribed topic maintains a *mesh*: a bounded set of peers to which
+//! full messages are eagerly forwarded. The mesh is kept between `D_LO` and
+//! `D_HI` peers by a periodic heartbeat that issues GRAFT and PRUNE control
+//! messages ([`MSG_GOSSIP_GRAFT`](crate::messages::MSG_GOSSIP_GRAFT),
+//! [`MSG_GOSSIP_PRUNE`](crate::messages::MSG_GOSSIP_PRUNE)).
+//!
+//! ## Duplicate Suppression
+//!
+//! Every forwarded message carries a 16-byte `gossip_msg_id`. The router keeps
+//! a seen-message cache with a fixed TTL so the same message is delivered to
+//! the application and re-forwarded at most once.
+//!
+//! ## Peer Scoring
+//!
+//! Peers earn score for first deliveries of valid messages and lose score for
+//! invalid ones. Scores decay at every heartbeat. Peers with a negative score
+//! are pruned from every mesh and are not re-grafted; peers below the
+//! graylist threshold are ignored entirely. Scores only steer the mesh; they
+//! are not part of a node's PoSrv score.
+//!
+//! ## Topics
+//!
+//! | Topic | Carries |
+//! |---|---|
+//! | [`GossipTopic::Nullifiers`] | Nullifier batches (Section 10.4) |
+//! | [`GossipTopic::EpochState`] | FROST-signed `EpochState` |
+//! | [`GossipTopic::RelayDescriptors`] | `RelayDescriptor` announcements |
+//! | [`GossipTopic::UptimeAttestations`] | Auditor-signed uptime attestations |
+//! | [`GossipTopic::Tombstones`] | Signed content tombstones (Section 16.6) |
+//! | [`GossipTopic::EpochBeacons`] | Quorum-signed `EpochBeacon` (Section 12.10) |
+//! | [`GossipTopic::KeySchedule`] | The quorum key schedule (Section 12.8) |
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+use tracing::debug;
+
+use crate::messages::{GossipForward, GossipGraft, GossipPrune, GossipPublish};
+
+/// Target mesh degree per topic.
+pub const MESH_D: usize = 6;
+
+/// Lower bound on mesh degree before the heartbeat grafts new peers.
+pub const MESH_D_LO: usize = 4;
+
+/// Upper bound on mesh degree before the heartbeat prunes excess peers.
+pub const MESH_D_HI: usize = 12;
+
+/// Heartbeat interval in milliseconds.
+pub const HEARTBEAT_INTERVAL_MS: u64 = 1000;
+
+/// How long a message ID stays in the seen cache (2 minutes).
+pub const SEEN_TTL_SECS: u64 = 120;
+
+/// How long a pruned peer must wait before grafting the same topic again.
+pub const PRUNE_BACKOFF_SECS: u64 = 60;
+
+/// Score below which a peer is ignored entirely.
+pub const GRAYLIST_THRESHOLD: f64 = -20.0;
+
+/// Score awarded for the first delivery of a valid message.
+const FIRST_DELIVERY_WEIGHT: f64 = 1.0;
+
+/// Score deducted for each invalid message.
+const INVALID_MESSAGE_WEIGHT: f64 = -10.0;
+
+/// Score deducted for each replayed Sphinx packet.
+const REPLAY_WEIGHT: f64 = -5.0;
+
+/// Multiplicative score decay applied at each heartbeat.
+const SCORE_DECAY: f64 = 0.9;
+
+/// Cap on positive score so long-lived peers cannot bank unlimited credit.
+const SCORE_CAP: f64 = 100.0;
+
+/// Prune reason: the mesh has more than `D_HI` peers.
+pub const PRUNE_REASON_OVERSUBSCRIBED: u8 = 1;
+/// Prune reason: the peer's score fell below zero.
+pub const PRUNE_REASON_LOW_SCORE: u8 = 2;
+/// Prune reason: the local node unsubscribed from the topic.
+pub const PRUNE_REASON_UNSUBSCRIBED: u8 = 3;
+/// Prune reason: the peer grafted while still in backoff.
+pub const PRUNE_REASON_BACKOFF: u8 = 4;
+
+/// Well-known gossip topics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GossipTopic {
+    /// Nullifier batches.
+    Nullifiers,
+    /// FROST-signed epoch state.
+    EpochState,
+    /// Relay descriptor announcements.
+    RelayDescriptors,
+    /// Auditor-signed uptime attestations.
+    UptimeAttestations,
+    /// Signed content tombstones.
+    Tombstones,
+    /// Quorum-signed epoch beacons.
+    EpochBeacons,
+    /// The quorum key schedule, endorsed entry by entry.
+    KeySchedule,
+}
+
+impl GossipTopic {
+    /// All well-known topics.
+    pub const ALL: [GossipTopic; 7] = [
+        GossipTopic::Nullifiers,
+        GossipTopic::EpochState,
+        GossipTopic::RelayDescriptors,
+        GossipTopic::UptimeAttestations,
+        GossipTopic::Tombstones,
+        GossipTopic::EpochBeacons,
+        GossipTopic::KeySchedule,
+    ];
+
+    /// Topic name used for ID derivation.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GossipTopic::Nullifiers => "nullifier",
+            GossipTopic::EpochState => "epoch-state",
+            GossipTopic::RelayDescriptors => "relay-descriptor",
+            GossipTopic::UptimeAttestations => "uptime-attestation",
+            GossipTopic::Tombstones => "tombstone",
+            GossipTopic::EpochBeacons => "epoch-beacon",
+            GossipTopic::KeySchedule => "quorum-key-schedule",
+        }
+    }
+
+    /// 32-byte topic identifier: `BLAKE3::hash("gossip-topic" || name)`.
+    pub fn topic_id(&self) -> [u8; 32] {
+        let name = self.name().as_bytes();
+        let mut input = Vec::with_capacity(12 + name.len());
+        input.extend_from_slice(b"gossip-topic");
+        input.extend_from_slice(name);
+        ochra_crypto::blake3::hash(&input)
+    }
+
+    /// Look up a well-known topic by its identifier.
+    pub fn from_topic_id(topic_id: &[u8; 32]) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| &t.topic_id() == topic_id)
+    }
+}
+
+/// Topic carrying a Space's sealed announcements:
+/// `BLAKE3::hash("gossip-topic" || "announcements" || group_id)`.
+///
+/// Only members subscribe, so the mesh for a Space is formed among its
+/// members.
+pub fn announcement_topic_id(group_id: &[u8; 32]) -> [u8; 32] {
+    let mut input = Vec::with_capacity(12 + 13 + 32);
+    input.extend_from_slice(b"gossip-topic");
+    input.extend_from_slice(b"announcements");
+    input.extend_from_slice(group_id);
+    ochra_crypto::blake3::hash(&input)
+}
+
+/// Mesh tuning parameters.
+#[derive(Clone, Debug)]
+pub struct MeshConfig {
+    /// Target mesh degree.
+    pub d: usize,
+    /// Graft below this degree.
+    pub d_lo: usize,
+    /// Prune above this degree.
+    pub d_hi: usize,
+    /// Seen-message cache TTL.
+    pub seen_ttl: Duration,
+    /// Backoff after a prune.
+    pub prune_backoff: Duration,
+}
+
+impl Default for MeshConfig {
+    fn default() -> Self {
+        Self {
+            d: MESH_D,
+            d_lo: MESH_D_LO,
+            d_hi: MESH_D_HI,
+            seen_ttl: Duration::from_secs(SEEN_TTL_SECS),
+            prune_backoff: Duration::from_secs(PRUNE_BACKOFF_SECS),
+        }
+    }
+}
+
+/// Per-peer delivery counters and score.
+#[derive(Clone, Debug, Default)]
+pub struct PeerScore {
+    /// Valid messages this peer delivered first.
+    pub first_deliveries: u64,
+    /// Messages this peer delivered after another peer already had.
+    pub duplicate_deliveries: u64,
+    /// Messages from this peer that failed validation.
+    pub invalid_messages: u64,
+    /// Sphinx packets from this peer that were replays.
+    pub replayed_packets: u64,
+    /// Current decayed score.
+    pub score: f64,
+}
+
+impl PeerScore {
+    /// Fraction of this peer's deliveries that were valid, in [0.0, 1.0].
+    ///
+    /// Peers with no deliveries report 1.0.
+    pub fn delivery_ratio(&self) -> f64 {
+        let total = self.first_deliveries + self.duplicate_deliveries + self.invalid_messages;
+        if total == 0 {
+            return 1.0;
+        }
+        (self.first_deliveries + self.duplicate_deliveries) as f64 / total as f64
+    }
+}
+
+/// Control message emitted by the router for a specific peer.
+#[derive(Clone, Debug)]
+pub enum GossipControl {
+    /// Ask `peer` to add us to its mesh for the topic.
+    Graft {
+        /// Destination peer.
+        peer: [u8; 32],
+        /// Graft payload.
+        msg: GossipGraft,
+    },
+    /// Tell `peer` we removed it from our mesh for the topic.
+    Prune {
+        /// Destination peer.
+        peer: [u8; 32],
+        /// Prune payload.
+        msg: GossipPrune,
+    },
+}
+
+/// Result of receiving a gossip message.
+#[derive(Clone, Debug)]
+pub enum ReceiveOutcome {
+    /// Message was already seen; nothing to do.
+    Duplicate,
+    /// Message failed validation and was dropped.
+    Invalid,
+    /// Sender is graylisted or the topic is not subscribed.
+    Ignored,
+    /// Message is new and valid: deliver locally and forward.
+    Accepted {
+        /// Message to forward (TTL already decremented), or `None` if TTL expired.
+        forward: Option<GossipForward>,
+        /// Mesh peers to forward to.
+        to: Vec<[u8; 32]>,
+    },
+}
+
+/// GossipSub-style router managing meshes for subscribed topics.
+pub struct GossipRouter {
+    /// Mesh parameters.
+    config: MeshConfig,
+    /// Topics the local node is subscribed to.
+    subscriptions: HashSet<[u8; 32]>,
+    /// Known peers and the topics they are subscribed to.
+    peer_topics: HashMap<[u8; 32], HashSet<[u8; 32]>>,
+    /// Mesh peers per topic.
+    mesh: HashMap<[u8; 32], HashSet<[u8; 32]>>,
+    /// Prune backoff expiry per `(topic, peer)`.
+    backoff: HashMap<([u8; 32], [u8; 32]), Instant>,
+    /// Seen message IDs and when they were first seen.
+    seen: HashMap<[u8; 16], Instant>,
+    /// Per-peer scores.
+    scores: HashMap<[u8; 32], PeerScore>,
+}
+
+impl GossipRouter {
+    /// Create a router with the given mesh parameters.
+    pub fn new(config: MeshConfig) -> Self {
+        Self {
+            config,
+            subscriptions: HashSet::new(),
+            peer_topics: HashMap::new(),
+            mesh: HashMap::new(),
+            backoff: HashMap::new(),
+            seen: HashMap::new(),
+            scores: HashMap::new(),
+        }
+    }
+
+    /// Subscribe to a topic. The mesh is populated on the next heartbeat.
+    pub fn subscribe(&mut self, topic: [u8; 32]) {
+        self.subscriptions.insert(topic);
+        self.mesh.entry(topic).or_default();
+    }
+
+    /// Unsubscribe from a topic, pruning every mesh peer.
+    pub fn unsubscribe(&mut self, topic: &[u8; 32]) -> Vec<GossipControl> {
+        self.subscriptions.remove(topic);
+        let peers = self.mesh.remove(topic).unwrap_or_default();
+        peers
+            .into_iter()
+            .map(|peer| prune(peer, *topic, PRUNE_REASON_UNSUBSCRIBED))
+            .collect()
+    }
+
+    /// Whether the local node is subscribed to `topic`.
+    pub fn is_subscribed(&self, topic: &[u8; 32]) -> bool {
+        self.subscriptions.contains(topic)
+    }
+
+    /// Register a connected peer and the topics it advertises.
+    pub fn add_peer(&mut self, peer: [u8; 32], topics: impl IntoIterator<Item = [u8; 32]>) {
+        self.peer_topics.entry(peer).or_default().extend(topics);
+        self.scores.entry(peer).or_default();
+    }
+
+    /// Forget a disconnected peer.
+    pub fn remove_peer(&mut self, peer: &[u8; 32]) {
+        self.peer_topics.remove(peer);
+        for members in self.mesh.values_mut() {
+            members.remove(peer);
+        }
+    }
+
+    /// Current mesh peers for a topic.
+    pub fn mesh_peers(&self, topic: &[u8; 32]) -> Vec<[u8; 32]> {
+        self.mesh
+            .get(topic)
+            .map(|m| m.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Current score of a peer (0.0 if unknown).
+    pub fn peer_score(&self, peer: &[u8; 32]) -> f64 {
+        self.scores.get(peer).map(|s| s.score).unwrap_or(0.0)
+    }
+
+    /// Penalize peers for replayed Sphinx packets, as drained from a
+    /// [`ReplayCache`](crate::replay::ReplayCache).
+    pub fn record_replays(&mut self, replays: &[([u8; 32], u64)]) {
+        for (peer, count) in replays {
+            let score = self.scores.entry(*peer).or_default();
+            score.replayed_packets += count;
+            score.score += REPLAY_WEIGHT * *count as f64;
+        }
+    }
+
+    /// Handle an incoming GRAFT. Returns a PRUNE if the graft is refused.
+    pub fn handle_graft(&mut self, peer: [u8; 32], graft: &GossipGraft) -> Option<GossipControl> {
+        self.handle_graft_at(peer, graft, Instant::now())
+    }
+
+    fn handle_graft_at(
+        &mut self,
+        peer: [u8; 32],
+        graft: &GossipGraft,
+        now: Instant,
+    ) -> Option<GossipControl> {
+        let topic = graft.topic;
+        if !self.subscriptions.contains(&topic) {
+            return Some(prune(peer, topic, PRUNE_REASON_UNSUBSCRIBED));
+        }
+        if self
+            .backoff
+            .get(&(topic, peer))
+            .is_some_and(|until| *until > now)
+        {
+            return Some(prune(peer, topic, PRUNE_REASON_BACKOFF));
+        }
+        if self.peer_score(&peer) < 0.0 {
+            return Some(prune(peer, topic, PRUNE_REASON_LOW_SCORE));
+        }
+
+        self.peer_topics.entry(peer).or_default().insert(topic);
+        self.scores.entry(peer).or_default();
+        let members = self.mesh.entry(topic).or_default();
+        if members.len() >= self.config.d_hi && !members.contains(&peer) {
+            return Some(prune(peer, topic, PRUNE_REASON_OVERSUBSCRIBED));
+        }
+        members.insert(peer);
+        None
+    }
+
+    /// Handle an incoming PRUNE: drop the peer from the mesh and back off.
+    pub fn handle_prune(&mut self, peer: [u8; 32], msg: &GossipPrune) {
+        if let Some(members) = self.mesh.get_mut(&msg.topic) {
+            members.remove(&peer);
+        }
+        self.backoff.insert(
+            (msg.topic, peer),
+            Instant::now() + self.config.prune_backoff,
+        );
+    }
+
+    /// Publish a locally originated message to the topic mesh.
+    ///
+    /// If the mesh is empty, the message fans out to every known subscriber.
+    pub fn publish(
+        &mut self,
+        topic: [u8; 32],
+        data: Vec<u8>,
+        ttl: u8,
+    ) -> (GossipPublish, Vec<[u8; 32]>) {
+        let mut gossip_msg_id = [0u8; 16];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut gossip_msg_id);
+        self.seen.insert(gossip_msg_id, Instant::now());
+
+        let mut targets = self.mesh_peers(&topic);
+        if targets.is_empty() {
+            targets = self.topic_candidates(&topic);
+        }
+
+        (
+            GossipPublish {
+                topic,
+                data,
+                ttl,
+                gossip_msg_id,
+            },
+            targets,
+        )
+    }
+
+    /// Receive a forwarded message from `from`.
+    ///
+    /// `validate` is called once for new messages; its verdict feeds the
+    /// sender's score.
+    pub fn receive<F>(&mut self, from: [u8; 32], msg: &GossipForward, validate: F) -> ReceiveOutcome
+    where
+        F: FnOnce(&[u8]) -> bool,
+    {
+        if self.peer_score(&from) < GRAYLIST_THRESHOLD || !self.subscriptions.contains(&msg.topic) {
+            return ReceiveOutcome::Ignored;
+        }
+
+        if self.seen.contains_key(&msg.gossip_msg_id) {
+            self.scores.entry(from).or_default().duplicate_deliveries += 1;
+            return ReceiveOutcome::Duplicate;
+        }
+        self.seen.insert(msg.gossip_msg_id, Instant::now());
+
+        let score = self.scores.entry(from).or_default();
+        if !validate(&msg.data) {
+            score.invalid_messages += 1;
+            score.score += INVALID_MESSAGE_WEIGHT;
+            return ReceiveOutcome::Invalid;
+        }
+        score.first_deliveries += 1;
+        score.score = (score.score + FIRST_DELIVERY_WEIGHT).min(SCORE_CAP);
+
+        let forward = (msg.ttl > 1).then(|| GossipForward {
+            topic: msg.topic,
+            data: msg.data.clone(),
+            ttl: msg.ttl - 1,
+            gossip_msg_id: msg.gossip_msg_id,
+        });
+        let to = if forward.is_some() {
+            self.mesh_peers(&msg.topic)
+                .into_iter()
+                .filter(|p| *p != from)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        ReceiveOutcome::Accepted { forward, to }
+    }
+
+    /// Run one heartbeat: expire caches, decay scores, and rebalance meshes.
+    pub fn heartbeat(&mut self) -> Vec<GossipControl> {
+        self.heartbeat_at(Instant::now())
+    }
+
+    fn heartbeat_at(&mut self, now: Instant) -> Vec<GossipControl> {
+        let seen_ttl = self.config.seen_ttl;
+        self.seen
+            .retain(|_, at| now.saturating_duration_since(*at) < seen_ttl);
+        self.backoff.retain(|_, until| *until > now);
+        for score in self.scores.values_mut() {
+            score.score *= SCORE_DECAY;
+        }
+
+        let mut controls = Vec::new();
+        let topics: Vec<[u8; 32]> = self.subscriptions.iter().copied().collect();
+        for topic in topics {
+            // Drop negative-score peers.
+            let low: Vec<[u8; 32]> = self
+                .mesh_peers(&topic)
+                .into_iter()
+                .filter(|p| self.peer_score(p) < 0.0)
+                .collect();
+            for peer in low {
+                self.remove_from_mesh(&topic, &peer, now);
+                controls.push(prune(peer, topic, PRUNE_REASON_LOW_SCORE));
+            }
+
+            let degree = self.mesh.get(&topic).map_or(0, HashSet::len);
+            if degree < self.config.d_lo {
+                let mut candidates: Vec<[u8; 32]> = self
+                    .topic_candidates(&topic)
+                    .into_iter()
+                    .filter(|p| {
+                        self.peer_score(p) >= 0.0
+                            && !self.backoff.contains_key(&(topic, *p))
+                            && !self.mesh.get(&topic).is_some_and(|m| m.contains(p))
+                    })
+                    .collect();
+                candidates.shuffle(&mut rand::thread_rng());
+                candidates.sort_by(|a, b| self.peer_score(b).total_cmp(&self.peer_score(a)));
+                for peer in candidates.into_iter().take(self.config.d - degree) {
+                    self.mesh.entry(topic).or_default().insert(peer);
+                    controls.push(GossipControl::Graft {
+                        peer,
+                        msg: GossipGraft { topic },
+                    });
+                }
+            } else if degree > self.config.d_hi {
+                let mut members = self.mesh_peers(&topic);
+                members.shuffle(&mut rand::thread_rng());
+                members.sort_by(|a, b| self.peer_score(b).total_cmp(&self.peer_score(a)));
+                for peer in members.into_iter().skip(self.config.d) {
+                    self.remove_from_mesh(&topic, &peer, now);
+                    controls.push(prune(peer, topic, PRUNE_REASON_OVERSUBSCRIBED));
+                }
+            }
+        }
+
+        if !controls.is_empty() {
+            debug!(
+                "Gossip heartbeat emitted {} control messages",
+                controls.len()
+            );
+        }
+        controls
+    }
+
+    /// Known peers subscribed to `topic`, excluding graylisted ones.
+    fn topic_candidates(&self, topic: &[u8; 32]) -> Vec<[u8; 32]> {
+        self.peer_topics
+            .iter()
+            .filter(|(peer, topics)| {
+                topics.contains(topic) && self.peer_score(peer) >= GRAYLIST_THRESHOLD
+            })
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    fn remove_from_mesh(&mut self, topic: &[u8; 32], peer: &[u8; 32], now: Instant) {
+        if let Some(members) = self.mesh.get_mut(topic) {
+            members.remove(peer);
+        }
+        self.backoff
+            .insert((*topic, *peer), now + self.config.prune_backoff);
+    }
+}
+
+impl Default for GossipRouter {
+    fn default() -> Self {
+        Self::new(MeshConfig::default())
+    }
+}
+
+fn prune(peer: [u8; 32], topic: [u8; 32], reason: u8) -> GossipControl {
+    GossipControl::Prune {
+        peer,
+        msg: GossipPrune { topic, reason },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topic() -> [u8; 32] {
+        GossipTopic::Nullifiers.topic_id()
+    }
+
+    fn router_with_peers(n: u8) -> GossipRouter {
+        let mut router = GossipRouter::default();
+        router.subscribe(topic());
+        for i in 1..=n {
+            router.add_peer([i; 32], [topic()]);
+        }
+        router
+    }
+
+    fn fwd_msg(id: u8, ttl: u8) -> GossipForward {
+        GossipForward {
+            topic: topic(),
+            data: vec![id],
+            ttl,
+            gossip_msg_id: [id; 16],
+        }
+    }
+
+    #[test]
+    fn test_topic_ids_distinct_and_reversible() {
+        let ids: HashSet<[u8; 32]> = GossipTopic::ALL.iter().map(|t| t.topic_id()).collect();
+        assert_eq!(ids.len(), GossipTopic::ALL.len());
+        for t in GossipTopic::ALL {
+            assert_eq!(GossipTopic::from_topic_id(&t.topic_id()), Some(t));
+        }
+        assert_eq!(GossipTopic::from_topic_id(&[0u8; 32]), None);
+
+        let space_topic = announcement_topic_id(&[1u8; 32]);
+        assert!(!ids.contains(&space_topic));
+        assert_ne!(space_topic, announcement_topic_id(&[2u8; 32]));
+        assert_eq!(GossipTopic::from_topic_id(&space_topic), None);
+    }
+
+    #[test]
+    fn test_heartbeat_grafts_up_to_d() {
+        let mut router = router_with_peers(10);
+        let controls = router.heartbeat();
+        let grafts = controls
+            .iter()
+            .filter(|c| matches!(c, GossipControl::Graft { .. }))
+            .count();
+        assert_eq!(grafts, MESH_D);
+        assert_eq!(router.mesh_peers(&topic()).len(), MESH_D);
+
+        // A second heartbeat at D does nothing.
+        assert!(router.heartbeat().is_empty());
+    }
+
+    #[test]
+    fn test_heartbeat_prunes_oversubscribed_mesh() {
+        let mut router = router_with_peers(20);
+        for i in 1..=20 {
+            router.mesh.entry(topic()).or_default().insert([i; 32]);
+        }
+        let controls = router.heartbeat();
+        let prunes = controls
+            .iter()
+            .filter(|c| matches!(c, GossipControl::Prune { .. }))
+            .count();
+        assert_eq!(prunes, 20 - MESH_D);
+        assert_eq!(router.mesh_peers(&topic()).len(), MESH_D);
+    }
+
+    #[test]
+    fn test_receive_dedup_and_forward() {
+        let mut router = router_with_peers(8);
+        router.heartbeat();
+        let from = router.mesh_peers(&topic())[0];
+
+        let ReceiveOutcome::Accepted { forward, to } =
+            router.receive(from, &fwd_msg(1, 5), |_| true)
+        else {
+            unreachable!("new valid message should be accepted");
+        };
+        let fwd = forward.expect("forward");
+        assert_eq!(fwd.ttl, 4);
+        assert_eq!(to.len(), MESH_D - 1);
+        assert!(!to.contains(&from));
+
+        assert!(matches!(
+            router.receive([2; 32], &fwd_msg(1, 5), |_| true),
+            ReceiveOutcome::Duplicate
+        ));
+    }
+
+    #[test]
+    fn test_ttl_exhausted_not_forwarded() {
+        let mut router = router_with_peers(8);
+        router.heartbeat();
+        let ReceiveOutcome::Accepted { forward, to } =
+            router.receive([1; 32], &fwd_msg(9, 1), |_| true)
+        else {
+            unreachable!("new valid message should be accepted");
+        };
+        assert!(forward.is_none());
+        assert!(to.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_messages_lower_score_and_prune() {
+        let mut router = router_with_peers(8);
+        router.heartbeat();
+        let bad = router.mesh_peers(&topic())[0];
+
+        assert!(matches!(
+            router.receive(bad, &fwd_msg(1, 5), |_| false),
+            ReceiveOutcome::Invalid
+        ));
+        assert!(router.peer_score(&bad) < 0.0);
+
+        let controls = router.heartbeat();
+        assert!(controls.iter().any(|c| matches!(
+            c,
+            GossipControl::Prune { peer, msg } if *peer == bad && msg.reason == PRUNE_REASON_LOW_SCORE
+        )));
+        assert!(!router.mesh_peers(&topic()).contains(&bad));
+    }
+
+    #[test]
+    fn test_replays_lower_score() {
+        let mut router = router_with_peers(2);
+        let peer = router
+            .mesh_peers(&topic())
+            .first()
+            .copied()
+            .unwrap_or([1u8; 32]);
+        router.record_replays(&[(peer, 3)]);
+        assert!(router.peer_score(&peer) < 0.0);
+    }
+
+    #[test]
+    fn test_graylisted_peer_ignored() {
+        let mut router = router_with_peers(1);
+        for i in 0..3 {
+            router.receive([1; 32], &fwd_msg(i, 5), |_| false);
+        }
+        assert!(router.peer_score(&[1; 32]) < GRAYLIST_THRESHOLD);
+        assert!(matches!(
+            router.receive([1; 32], &fwd_msg(50, 5), |_| true),
+            ReceiveOutcome::Ignored
+        ));
+    }
+
+    #[test]
+    fn test_graft_rules() {
+        let mut router = router_with_peers(0);
+        assert!(router
+            .handle_graft([1; 32], &GossipGraft { topic: topic() })
+            .is_none());
+        assert!(router.mesh_peers(&topic()).contains(&[1; 32]));
+
+        // Unsubscribed topic is refused.
+        let refused = router.handle_graft([1; 32], &GossipGraft { topic: [9; 32] });
+        assert!(matches!(
+            refused,
+            Some(GossipControl::Prune { msg, .. }) if msg.reason == PRUNE_REASON_UNSUBSCRIBED
+        ));
+
+        // A peer that pruned us is in backoff.
+        router.handle_prune(
+            [2; 32],
+            &GossipPrune {
+                topic: topic(),
+                reason: PRUNE_REASON_OVERSUBSCRIBED,
+            },
+        );
+        let refused = router.handle_graft([2; 32], &GossipGraft { topic: topic() });
+        assert!(matches!(
+            refused,
+            Some(GossipControl::Prune { msg, .. }) if msg.reason == PRUNE_REASON_BACKOFF
+        ));
+    }
+
+    #[test]
+    fn test_seen_cache_expires() {
+        let mut router = router_with_peers(2);
+        router.receive([1; 32], &fwd_msg(1, 5), |_| true);
+        router.heartbeat_at(Instant::now() + Duration::from_secs(SEEN_TTL_SECS + 1));
+        assert!(matches!(
+            router.receive([2; 32], &fwd_msg(1, 5), |_| true),
+            ReceiveOutcome::Accepted { .. }
+        ));
+    }
+
+    #[test]
+    fn test_unsubscribe_prunes_mesh() {
+        let mut router = router_with_peers(8);
+        router.heartbeat();
+        let controls = router.unsubscribe(&topic());
+        assert_eq!(controls.len(), MESH_D);
+        assert!(!router.is_subscribed(&topic()));
+    }
+}
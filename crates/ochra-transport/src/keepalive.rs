@@ -0,0 +1,396 @@
+//! Adaptive keepalive and dead peer detection (Section 4.6).
+//!
+//! A NAT drops an idle UDP mapping after a timeout that varies from under
+//! a minute to several minutes, and a connection behind it then dies
+//! silently. Pinging every few seconds would keep any mapping alive but
+//! wastes traffic, so the [`KeepaliveManager`] learns each path's timeout
+//! instead.
+//!
+//! ## Learning
+//!
+//! A path starts with [`KeepaliveConfig::min_interval_secs`] assumed safe.
+//! Each keepalive is a probe: it is sent once the path has been quiet for
+//! the probe interval, and a pong proves the mapping survived that long.
+//! The interval doubles after every answered probe until one goes
+//! unanswered, which bounds the timeout from above. The search then
+//! bisects between the longest answered and shortest unanswered interval
+//! until they are within [`KeepaliveConfig::resolution_secs`], and the
+//! path settles on the answered bound less 10%. A path that never loses a
+//! probe settles on [`KeepaliveConfig::max_interval_secs`], which must stay
+//! below the connection idle timeout.
+//!
+//! Received traffic resets the quiet period, so a busy path sends no
+//! keepalives at all.
+//!
+//! ## Dead peers
+//!
+//! An unanswered probe is retried at once. If the retry is answered, only
+//! the mapping had expired and the probe's interval becomes the upper
+//! bound. After [`KeepaliveConfig::dead_after_misses`] consecutive misses
+//! the peer is reported [`KeepaliveEvent::Dead`] and forgotten. Every miss
+//! is reported too, so the caller can count it against the node in the
+//! routing table.
+
+use std::collections::BTreeMap;
+
+/// Default interval a path is assumed to survive, in seconds.
+pub const DEFAULT_MIN_INTERVAL_SECS: u64 = 15;
+
+/// Default longest keepalive interval, in seconds.
+pub const DEFAULT_MAX_INTERVAL_SECS: u64 = 120;
+
+/// Default precision of a learned timeout, in seconds.
+pub const DEFAULT_RESOLUTION_SECS: u64 = 5;
+
+/// Default time to wait for a pong, matching the DHT ping timeout.
+pub const DEFAULT_PONG_TIMEOUT_SECS: u64 = 5;
+
+/// Default consecutive misses before a peer is declared dead.
+pub const DEFAULT_DEAD_AFTER_MISSES: u32 = 3;
+
+/// Keepalive tuning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// Interval every path is assumed to survive; the search never goes
+    /// below it.
+    pub min_interval_secs: u64,
+    /// Longest interval probed.
+    pub max_interval_secs: u64,
+    /// The search stops once the bounds are this close.
+    pub resolution_secs: u64,
+    /// How long a ping waits for its pong.
+    pub pong_timeout_secs: u64,
+    /// Consecutive misses before a peer is dead.
+    pub dead_after_misses: u32,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            min_interval_secs: DEFAULT_MIN_INTERVAL_SECS,
+            max_interval_secs: DEFAULT_MAX_INTERVAL_SECS,
+            resolution_secs: DEFAULT_RESOLUTION_SECS,
+            pong_timeout_secs: DEFAULT_PONG_TIMEOUT_SECS,
+            dead_after_misses: DEFAULT_DEAD_AFTER_MISSES,
+        }
+    }
+}
+
+/// Something the caller should act on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeepaliveEvent {
+    /// A ping went unanswered; `misses` are consecutive.
+    Missed { peer: [u8; 32], misses: u32 },
+    /// The peer missed too many pings and is no longer tracked.
+    Dead { peer: [u8; 32] },
+    /// The path's search converged on a keepalive interval.
+    Learned { peer: [u8; 32], interval_secs: u64 },
+}
+
+/// A ping awaiting its pong.
+#[derive(Clone, Copy, Debug)]
+struct Probe {
+    sent_at: u64,
+    /// Quiet period the ping tested, or `None` for a retry.
+    interval: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+struct PathState {
+    last_received: u64,
+    /// Longest quiet period known to survive.
+    good: u64,
+    /// Shortest quiet period known to fail.
+    bad: Option<u64>,
+    awaiting: Option<Probe>,
+    misses: u32,
+    /// Interval of the probe that started the current run of misses.
+    failed: Option<u64>,
+}
+
+/// Per-peer keepalive schedules and NAT timeout searches.
+#[derive(Debug)]
+pub struct KeepaliveManager {
+    config: KeepaliveConfig,
+    paths: BTreeMap<[u8; 32], PathState>,
+}
+
+impl KeepaliveManager {
+    /// Create a manager tracking no peers.
+    pub fn new(config: KeepaliveConfig) -> Self {
+        Self {
+            config,
+            paths: BTreeMap::new(),
+        }
+    }
+
+    /// Start keeping a connection to `peer` alive. Tracking a peer again
+    /// restarts its search, as after it migrated.
+    pub fn track(&mut self, peer: [u8; 32], now: u64) {
+        self.paths.insert(
+            peer,
+            PathState {
+                last_received: now,
+                good: self.config.min_interval_secs,
+                bad: None,
+                awaiting: None,
+                misses: 0,
+                failed: None,
+            },
+        );
+    }
+
+    /// Stop tracking `peer`, e.g. once its connection is closed.
+    pub fn untrack(&mut self, peer: &[u8; 32]) {
+        self.paths.remove(peer);
+    }
+
+    /// Number of tracked peers.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Whether no peers are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Record traffic received from `peer`, which proves the path alive and
+    /// restarts its quiet period.
+    pub fn on_received(&mut self, peer: &[u8; 32], now: u64) {
+        if let Some(path) = self.paths.get_mut(peer) {
+            path.last_received = now;
+            if path.awaiting.is_none() {
+                path.misses = 0;
+                path.failed = None;
+            }
+        }
+    }
+
+    /// Peers to ping now. Each is awaiting a pong until [`on_pong`] or a
+    /// timeout in [`poll`].
+    ///
+    /// [`on_pong`]: Self::on_pong
+    /// [`poll`]: Self::poll
+    pub fn due(&mut self, now: u64) -> Vec<[u8; 32]> {
+        let config = self.config;
+        let mut due = Vec::new();
+        for (peer, path) in &mut self.paths {
+            if path.awaiting.is_some() {
+                continue;
+            }
+            let probe = if path.misses > 0 {
+                Some(Probe {
+                    sent_at: now,
+                    interval: None,
+                })
+            } else {
+                let interval = path.next_interval(&config);
+                (now.saturating_sub(path.last_received) >= interval).then_some(Probe {
+                    sent_at: now,
+                    interval: Some(interval),
+                })
+            };
+            if let Some(probe) = probe {
+                path.awaiting = Some(probe);
+                due.push(*peer);
+            }
+        }
+        due
+    }
+
+    /// Record a pong from `peer`. Returns [`KeepaliveEvent::Learned`] if
+    /// this answer completed the path's search.
+    pub fn on_pong(&mut self, peer: &[u8; 32], now: u64) -> Option<KeepaliveEvent> {
+        let config = self.config;
+        let path = self.paths.get_mut(peer)?;
+        let probe = path.awaiting.take()?;
+        let was_converged = path.converged(&config);
+        path.last_received = now;
+        path.misses = 0;
+        match (probe.interval, path.failed.take()) {
+            // The mapping had expired but the peer is alive.
+            (None, Some(failed)) => {
+                path.bad = Some(path.bad.map_or(failed, |bad| bad.min(failed)));
+                if failed <= path.good {
+                    // A once-safe interval failed; search again from the floor.
+                    path.good = config.min_interval_secs;
+                }
+            }
+            (Some(interval), _) if interval > path.good => path.good = interval,
+            _ => {}
+        }
+        (!was_converged && path.converged(&config)).then(|| KeepaliveEvent::Learned {
+            peer: *peer,
+            interval_secs: path.next_interval(&config),
+        })
+    }
+
+    /// Time out pings whose pong is overdue, reporting each miss and any
+    /// peer that has now missed too many.
+    pub fn poll(&mut self, now: u64) -> Vec<KeepaliveEvent> {
+        let config = self.config;
+        let mut events = Vec::new();
+        for (peer, path) in &mut self.paths {
+            let Some(probe) = path.awaiting else {
+                continue;
+            };
+            if now.saturating_sub(probe.sent_at) < config.pong_timeout_secs {
+                continue;
+            }
+            path.awaiting = None;
+            path.misses += 1;
+            if path.failed.is_none() {
+                path.failed = probe.interval;
+            }
+            events.push(if path.misses >= config.dead_after_misses {
+                KeepaliveEvent::Dead { peer: *peer }
+            } else {
+                KeepaliveEvent::Missed {
+                    peer: *peer,
+                    misses: path.misses,
+                }
+            });
+        }
+        for event in &events {
+            if let KeepaliveEvent::Dead { peer } = event {
+                self.paths.remove(peer);
+            }
+        }
+        events
+    }
+
+    /// The quiet period after which `peer` is next pinged.
+    pub fn interval(&self, peer: &[u8; 32]) -> Option<u64> {
+        self.paths
+            .get(peer)
+            .map(|path| path.next_interval(&self.config))
+    }
+
+    /// The learned keepalive interval of `peer`, once its search converged.
+    pub fn learned_interval(&self, peer: &[u8; 32]) -> Option<u64> {
+        self.paths
+            .get(peer)
+            .filter(|path| path.converged(&self.config))
+            .map(|path| path.next_interval(&self.config))
+    }
+}
+
+impl PathState {
+    fn converged(&self, config: &KeepaliveConfig) -> bool {
+        match self.bad {
+            Some(bad) => bad.saturating_sub(self.good) <= config.resolution_secs,
+            None => self.good >= config.max_interval_secs,
+        }
+    }
+
+    fn next_interval(&self, config: &KeepaliveConfig) -> u64 {
+        if self.converged(config) {
+            return match self.bad {
+                Some(_) => (self.good - self.good / 10).max(config.min_interval_secs),
+                None => config.max_interval_secs,
+            };
+        }
+        match self.bad {
+            Some(bad) => (self.good + bad) / 2,
+            None => (self.good * 2).min(config.max_interval_secs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEER: [u8; 32] = [7; 32];
+
+    /// Drive one probe from `now`: wait out the interval, ping, and either
+    /// answer or let it time out and answer the retry.
+    fn probe(manager: &mut KeepaliveManager, now: &mut u64, survives: bool) -> u64 {
+        let interval = manager.interval(&PEER).expect("tracked");
+        *now += interval;
+        assert_eq!(manager.due(*now), vec![PEER]);
+        if !survives {
+            *now += DEFAULT_PONG_TIMEOUT_SECS;
+            assert_eq!(
+                manager.poll(*now),
+                vec![KeepaliveEvent::Missed {
+                    peer: PEER,
+                    misses: 1
+                }]
+            );
+            assert_eq!(manager.due(*now), vec![PEER], "retried at once");
+        }
+        manager.on_pong(&PEER, *now);
+        interval
+    }
+
+    #[test]
+    fn test_learns_nat_timeout_by_binary_search() {
+        let nat_timeout = 50;
+        let mut manager = KeepaliveManager::new(KeepaliveConfig::default());
+        let mut now = 0;
+        manager.track(PEER, now);
+
+        let mut probed = Vec::new();
+        while manager.learned_interval(&PEER).is_none() {
+            let interval = manager.interval(&PEER).expect("tracked");
+            probed.push(probe(&mut manager, &mut now, interval < nat_timeout));
+        }
+        assert_eq!(probed, vec![30, 60, 45, 52, 48]);
+        // Bounds 48..52; settle 10% under the longest answered interval.
+        assert_eq!(manager.learned_interval(&PEER), Some(44));
+    }
+
+    #[test]
+    fn test_learned_event_and_max_interval() {
+        let mut manager = KeepaliveManager::new(KeepaliveConfig::default());
+        let mut now = 0;
+        manager.track(PEER, now);
+        probe(&mut manager, &mut now, true);
+        probe(&mut manager, &mut now, true);
+        now += 120;
+        assert_eq!(manager.due(now), vec![PEER]);
+        assert_eq!(
+            manager.on_pong(&PEER, now),
+            Some(KeepaliveEvent::Learned {
+                peer: PEER,
+                interval_secs: DEFAULT_MAX_INTERVAL_SECS
+            })
+        );
+
+        // Received traffic defers the next keepalive.
+        manager.on_received(&PEER, now + 100);
+        assert!(manager.due(now + 150).is_empty());
+        assert_eq!(manager.due(now + 220), vec![PEER]);
+    }
+
+    #[test]
+    fn test_dead_after_consecutive_misses() {
+        let mut manager = KeepaliveManager::new(KeepaliveConfig::default());
+        manager.track(PEER, 0);
+        let mut now = 30;
+        let mut events = Vec::new();
+        for _ in 0..DEFAULT_DEAD_AFTER_MISSES {
+            assert_eq!(manager.due(now), vec![PEER]);
+            now += DEFAULT_PONG_TIMEOUT_SECS;
+            events.extend(manager.poll(now));
+        }
+        assert_eq!(
+            events,
+            vec![
+                KeepaliveEvent::Missed {
+                    peer: PEER,
+                    misses: 1
+                },
+                KeepaliveEvent::Missed {
+                    peer: PEER,
+                    misses: 2
+                },
+                KeepaliveEvent::Dead { peer: PEER },
+            ]
+        );
+        assert!(manager.is_empty());
+        assert!(manager.on_pong(&PEER, now).is_none());
+    }
+}
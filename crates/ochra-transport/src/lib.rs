@@ -6,10 +6,33 @@
 //! including:
 //!
 //! - **QUIC/TLS 1.3** connection management via [`quic`]
+//! - **Dual-stack** IPv4/IPv6 sockets and happy-eyeballs dialing via [`dualstack`]
+//! - **Pluggable transports** (TCP/TLS) with fallback when QUIC is blocked via
+//!   [`pluggable`]
+//! - **Proxy egress** (SOCKS5, HTTP CONNECT) with per-destination rules via
+//!   [`proxy`]
 //! - **Sphinx packets** for sender-anonymous 3-hop onion routing via [`sphinx`]
+//! - **Replay suppression** for relayed Sphinx packets via [`replay`]
+//! - **Exit policy enforcement** at the final Sphinx hop via [`exit`]
+//! - **Receipt acknowledgement batching** into Sphinx payloads via
+//!   [`receipt_batch`]
 //! - **Wire protocol** message envelope (CBOR-serialized) via [`wire`]
 //! - **CBOR serialization** helpers via [`cbor`]
+//! - **Gossip mesh** management for gossip topics via [`gossip`]
 //! - **Message types** for all protocol message payloads via [`messages`]
+//! - **Strict decoding** of untrusted wire input via [`strict`]
+//! - **Peer capabilities** and message-type gating via [`capabilities`]
+//! - **Misbehavior tracking** with greylisting and blacklisting via [`misbehavior`]
+//! - **0-RTT resumption** with session tickets per node and replay limits via
+//!   [`resumption`]
+//! - **Connection migration** across network changes via [`migration`]
+//! - **Adaptive keepalive** with per-path NAT timeout learning and dead peer
+//!   detection via [`keepalive`]
+//! - **Priority lanes** with QUIC stream priorities and bandwidth shares via [`qos`]
+//! - **Dial scheduling** with global and per-subsystem concurrency limits via
+//!   [`dial`]
+//! - **Transport trait** for node-addressed messaging via [`transport`], with an
+//!   in-memory network for tests behind the `test-harness` feature
 //!
 //! ## Architecture
 //!
@@ -29,10 +52,28 @@
 //! UDP socket
 //! ```
 
+pub mod capabilities;
 pub mod cbor;
+pub mod dial;
+pub mod dualstack;
+pub mod exit;
+pub mod gossip;
+pub mod keepalive;
+#[cfg(any(test, feature = "test-harness"))]
+pub mod memory;
 pub mod messages;
+pub mod migration;
+pub mod misbehavior;
+pub mod pluggable;
+pub mod proxy;
+pub mod qos;
 pub mod quic;
+pub mod receipt_batch;
+pub mod replay;
+pub mod resumption;
 pub mod sphinx;
+pub mod strict;
+pub mod transport;
 pub mod wire;
 
 /// Error types for transport operations.
@@ -50,10 +91,18 @@ pub enum TransportError {
     #[error("protocol violation: {0}")]
     ProtocolViolation(String),
 
+    /// The peer did not advertise support for this message type.
+    #[error("message type 0x{0:04x} not supported by peer")]
+    UnsupportedMessage(u16),
+
     /// Invalid or malformed Sphinx packet.
     #[error("invalid packet: {0}")]
     InvalidPacket(String),
 
+    /// Sphinx packet already processed within the replay window.
+    #[error("replayed packet")]
+    ReplayedPacket,
+
     /// MAC verification failed on a Sphinx header.
     #[error("MAC verification failed")]
     MacVerification,
@@ -74,6 +123,22 @@ pub enum TransportError {
     #[error("I/O error: {0}")]
     Io(String),
 
+    /// A proxy URL or rule is malformed.
+    #[error("invalid proxy: {0}")]
+    InvalidProxy(String),
+
+    /// The relay's exit policy refuses a delivered message.
+    #[error("exit policy refuses {0}")]
+    ExitRefused(String),
+
+    /// A priority lane's send queue is full.
+    #[error("{0} lane queue full")]
+    LaneFull(&'static str),
+
+    /// A subsystem's outbound dial queue is full.
+    #[error("{0} dial queue full")]
+    DialQueueFull(&'static str),
+
     /// Internal error (should not occur in normal operation).
     #[error("internal error: {0}")]
     Internal(String),
@@ -104,5 +169,7 @@ mod tests {
         let _e8 = TransportError::Connection("conn".into());
         let _e9 = TransportError::Io("io".into());
         let _e10 = TransportError::Internal("int".into());
+        let _e11 = TransportError::UnsupportedMessage(0x0099);
+        let _e12 = TransportError::ReplayedPacket;
     }
 }
@@ -0,0 +1,271 @@
+//! In-memory [`Transport`] for multi-node tests.
+//!
+//! A [`MemoryNetwork`] routes messages between [`MemoryTransport`] endpoints
+//! inside one process, with no sockets. Each message is encoded to wire
+//! bytes on send and strictly decoded on receipt, exactly as over QUIC.
+//! Every endpoint has a single FIFO inbox, so a test that drives its nodes
+//! from one task sees the same delivery order on every run.
+//!
+//! Links can be cut with [`MemoryNetwork::partition`] to exercise failure
+//! handling; messages sent across a cut link are silently dropped.
+//!
+//! Available with the `test-harness` feature.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::sync::mpsc;
+
+use crate::messages::TypedMessage;
+use crate::transport::{Received, Transport};
+use crate::wire::ProtocolMessage;
+use crate::{Result, TransportError};
+
+/// Raw frame in flight: sender and encoded envelope.
+type Frame = ([u8; 32], Vec<u8>);
+
+#[derive(Debug, Default)]
+struct NetworkState {
+    inboxes: HashMap<[u8; 32], mpsc::UnboundedSender<Frame>>,
+    /// Cut links, stored with the smaller node ID first.
+    partitions: HashSet<([u8; 32], [u8; 32])>,
+    delivered: u64,
+    dropped: u64,
+}
+
+fn link(a: [u8; 32], b: [u8; 32]) -> ([u8; 32], [u8; 32]) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// A simulated network connecting [`MemoryTransport`] endpoints.
+///
+/// Cloning yields another handle to the same network.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryNetwork {
+    state: Arc<Mutex<NetworkState>>,
+}
+
+impl MemoryNetwork {
+    /// Create an empty network.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, NetworkState>> {
+        self.state
+            .lock()
+            .map_err(|_| TransportError::Internal("memory network poisoned".to_string()))
+    }
+
+    /// Attach a node to the network.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Connection`] if `node_id` is already attached.
+    pub fn join(&self, node_id: [u8; 32]) -> Result<MemoryTransport> {
+        let mut state = self.lock()?;
+        if state.inboxes.contains_key(&node_id) {
+            return Err(TransportError::Connection(
+                "node already joined the network".to_string(),
+            ));
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        state.inboxes.insert(node_id, tx);
+        Ok(MemoryTransport {
+            node_id,
+            network: self.clone(),
+            inbox: tokio::sync::Mutex::new(rx),
+        })
+    }
+
+    /// Detach a node. Messages already queued for it are still readable.
+    pub fn leave(&self, node_id: &[u8; 32]) -> bool {
+        self.lock()
+            .map(|mut state| state.inboxes.remove(node_id).is_some())
+            .unwrap_or(false)
+    }
+
+    /// Cut the link between `a` and `b` in both directions.
+    pub fn partition(&self, a: [u8; 32], b: [u8; 32]) {
+        if let Ok(mut state) = self.lock() {
+            state.partitions.insert(link(a, b));
+        }
+    }
+
+    /// Restore a link cut by [`partition`](Self::partition).
+    pub fn heal(&self, a: [u8; 32], b: [u8; 32]) {
+        if let Ok(mut state) = self.lock() {
+            state.partitions.remove(&link(a, b));
+        }
+    }
+
+    /// Number of messages delivered to an inbox so far.
+    pub fn delivered(&self) -> u64 {
+        self.lock().map(|s| s.delivered).unwrap_or(0)
+    }
+
+    /// Number of messages dropped on cut links so far.
+    pub fn dropped(&self) -> u64 {
+        self.lock().map(|s| s.dropped).unwrap_or(0)
+    }
+
+    fn route(&self, from: [u8; 32], to: [u8; 32], bytes: Vec<u8>) -> Result<()> {
+        let mut state = self.lock()?;
+        let Some(inbox) = state.inboxes.get(&to) else {
+            return Err(TransportError::Connection(
+                "destination not on the network".to_string(),
+            ));
+        };
+        if state.partitions.contains(&link(from, to)) {
+            state.dropped += 1;
+            return Ok(());
+        }
+        if inbox.send((from, bytes)).is_err() {
+            return Err(TransportError::Connection(
+                "destination inbox closed".to_string(),
+            ));
+        }
+        state.delivered += 1;
+        Ok(())
+    }
+}
+
+/// A node's endpoint on a [`MemoryNetwork`].
+#[derive(Debug)]
+pub struct MemoryTransport {
+    node_id: [u8; 32],
+    network: MemoryNetwork,
+    inbox: tokio::sync::Mutex<mpsc::UnboundedReceiver<Frame>>,
+}
+
+impl MemoryTransport {
+    /// The network this endpoint is attached to.
+    pub fn network(&self) -> &MemoryNetwork {
+        &self.network
+    }
+
+    /// Take the next queued message without waiting.
+    ///
+    /// Returns `Ok(None)` if the inbox is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ProtocolViolation`] if the queued message
+    /// fails strict decoding.
+    pub fn try_recv(&self) -> Result<Option<Received>> {
+        let Ok(mut inbox) = self.inbox.try_lock() else {
+            return Ok(None);
+        };
+        match inbox.try_recv() {
+            Ok((from, bytes)) => decode(from, &bytes).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+fn decode(from: [u8; 32], bytes: &[u8]) -> Result<Received> {
+    let envelope = ProtocolMessage::from_bytes_strict(bytes)?;
+    let message = envelope.decode_payload_strict()?;
+    Ok(Received {
+        from,
+        msg_id: envelope.msg_id,
+        message,
+    })
+}
+
+impl Transport for MemoryTransport {
+    fn local_id(&self) -> [u8; 32] {
+        self.node_id
+    }
+
+    async fn send(&self, to: [u8; 32], message: &TypedMessage) -> Result<()> {
+        let bytes = ProtocolMessage::from_typed(message)?.to_bytes()?;
+        self.network.route(self.node_id, to, bytes)
+    }
+
+    async fn recv(&self) -> Result<Received> {
+        let frame = self.inbox.lock().await.recv().await;
+        let Some((from, bytes)) = frame else {
+            return Err(TransportError::Connection("inbox closed".to_string()));
+        };
+        decode(from, &bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::Ping;
+
+    const A: [u8; 32] = [1; 32];
+    const B: [u8; 32] = [2; 32];
+
+    fn ping(nonce: u8) -> TypedMessage {
+        TypedMessage::Ping(Ping { nonce: [nonce; 8] })
+    }
+
+    #[tokio::test]
+    async fn test_messages_arrive_in_order() {
+        let network = MemoryNetwork::new();
+        let a = network.join(A).expect("join a");
+        let b = network.join(B).expect("join b");
+
+        for nonce in 0..3 {
+            a.send(B, &ping(nonce)).await.expect("send");
+        }
+        for nonce in 0..3 {
+            let received = b.recv().await.expect("recv");
+            assert_eq!(received.from, A);
+            assert!(
+                matches!(received.message, TypedMessage::Ping(Ping { nonce: n }) if n == [nonce; 8])
+            );
+        }
+        assert_eq!(network.delivered(), 3);
+        assert!(b.try_recv().expect("try_recv").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_destination_fails() {
+        let network = MemoryNetwork::new();
+        let a = network.join(A).expect("join");
+        assert!(matches!(
+            a.send(B, &ping(0)).await,
+            Err(TransportError::Connection(_))
+        ));
+        assert!(network.join(A).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_partition_drops_until_healed() {
+        let network = MemoryNetwork::new();
+        let a = network.join(A).expect("join a");
+        let b = network.join(B).expect("join b");
+
+        network.partition(B, A);
+        a.send(B, &ping(1)).await.expect("send");
+        assert!(b.try_recv().expect("try_recv").is_none());
+        assert_eq!(network.dropped(), 1);
+
+        network.heal(A, B);
+        a.send(B, &ping(2)).await.expect("send");
+        let received = b.try_recv().expect("try_recv").expect("message");
+        assert!(matches!(
+            received.message,
+            TypedMessage::Ping(Ping { nonce: [2, ..] })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_leave_detaches_node() {
+        let network = MemoryNetwork::new();
+        let a = network.join(A).expect("join a");
+        let _b = network.join(B).expect("join b");
+        assert!(network.leave(&B));
+        assert!(a.send(B, &ping(0)).await.is_err());
+        assert!(!network.leave(&B));
+    }
+}
@@ -4,8 +4,11 @@
 //! struct here. These structs are serialized to CBOR for inclusion in
 //! [`ProtocolMessage`](crate::wire::ProtocolMessage) envelopes.
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::TransportError;
+
 // ---------------------------------------------------------------------------
 // Message type constants (Section 4.3)
 // ---------------------------------------------------------------------------
@@ -18,6 +21,8 @@ pub const MSG_PING: u16 = 0x0002;
 pub const MSG_PONG: u16 = 0x0003;
 /// Message type for goodbye (0x0004).
 pub const MSG_GOODBYE: u16 = 0x0004;
+/// Message type for unsupported-message error (0x0005).
+pub const MSG_UNSUPPORTED: u16 = 0x0005;
 
 /// Message type for chunk request (0x0010).
 pub const MSG_CHUNK_REQUEST: u16 = 0x0010;
@@ -27,6 +32,12 @@ pub const MSG_CHUNK_RESPONSE: u16 = 0x0011;
 pub const MSG_CHUNK_ADVERTISE: u16 = 0x0012;
 /// Message type for service receipt acknowledgement (0x0013).
 pub const MSG_SERVICE_RECEIPT_ACK: u16 = 0x0013;
+/// Message type for chunk request queued (0x0014).
+pub const MSG_CHUNK_QUEUED: u16 = 0x0014;
+/// Message type for a batch of service receipt acknowledgements (0x0015).
+pub const MSG_SERVICE_RECEIPT_ACK_BATCH: u16 = 0x0015;
+/// Message type for the reply to an acknowledgement batch (0x0016).
+pub const MSG_SERVICE_RECEIPT_ACK_BATCH_ACK: u16 = 0x0016;
 
 /// Message type for DHT get (0x0020).
 pub const MSG_DHT_GET: u16 = 0x0020;
@@ -40,6 +51,10 @@ pub const MSG_DHT_PUT_RESPONSE: u16 = 0x0023;
 pub const MSG_DHT_FIND_NODE: u16 = 0x0024;
 /// Message type for DHT find node response (0x0025).
 pub const MSG_DHT_FIND_NODE_RESPONSE: u16 = 0x0025;
+/// Message type for DHT circuit lookup (0x0026).
+pub const MSG_DHT_CIRCUIT_LOOKUP: u16 = 0x0026;
+/// Message type for DHT circuit lookup response (0x0027).
+pub const MSG_DHT_CIRCUIT_LOOKUP_RESPONSE: u16 = 0x0027;
 
 /// Message type for establish introduction (0x0030).
 pub const MSG_ESTABLISH_INTRO: u16 = 0x0030;
@@ -83,6 +98,10 @@ pub const MSG_QUORUM_PROPOSAL: u16 = 0x0054;
 pub const MSG_QUORUM_VOTE: u16 = 0x0055;
 /// Message type for quorum result (0x0056).
 pub const MSG_QUORUM_RESULT: u16 = 0x0056;
+/// Message type for mint request (0x0057).
+pub const MSG_MINT_REQUEST: u16 = 0x0057;
+/// Message type for mint response (0x0058).
+pub const MSG_MINT_RESPONSE: u16 = 0x0058;
 
 /// Message type for gossip publish (0x0060).
 pub const MSG_GOSSIP_PUBLISH: u16 = 0x0060;
@@ -90,6 +109,8 @@ pub const MSG_GOSSIP_PUBLISH: u16 = 0x0060;
 pub const MSG_GOSSIP_FORWARD: u16 = 0x0061;
 /// Message type for gossip prune (0x0062).
 pub const MSG_GOSSIP_PRUNE: u16 = 0x0062;
+/// Message type for gossip graft (0x0063).
+pub const MSG_GOSSIP_GRAFT: u16 = 0x0063;
 
 /// Message type for whisper send (0x0070).
 pub const MSG_WHISPER_SEND: u16 = 0x0070;
@@ -97,6 +118,14 @@ pub const MSG_WHISPER_SEND: u16 = 0x0070;
 pub const MSG_WHISPER_DELIVER: u16 = 0x0071;
 /// Message type for whisper ack (0x0072).
 pub const MSG_WHISPER_ACK: u16 = 0x0072;
+/// Message type for whisper mailbox deposit (0x0073).
+pub const MSG_WHISPER_DEPOSIT: u16 = 0x0073;
+/// Message type for whisper mailbox poll (0x0074).
+pub const MSG_WHISPER_POLL: u16 = 0x0074;
+/// Message type for whisper mailbox poll response (0x0075).
+pub const MSG_WHISPER_POLL_RESPONSE: u16 = 0x0075;
+/// Message type for whisper mailbox delivery ack (0x0076).
+pub const MSG_WHISPER_MAILBOX_ACK: u16 = 0x0076;
 
 /// Message type for oracle request (0x0080).
 pub const MSG_ORACLE_REQUEST: u16 = 0x0080;
@@ -114,6 +143,81 @@ pub const MSG_RECOVERY_SHARE: u16 = 0x0092;
 /// Message type for recovery complete (0x0093).
 pub const MSG_RECOVERY_COMPLETE: u16 = 0x0093;
 
+/// Message type for state sync request (0x00A0).
+pub const MSG_STATE_SYNC_REQUEST: u16 = 0x00A0;
+/// Message type for state sync response (0x00A1).
+pub const MSG_STATE_SYNC_RESPONSE: u16 = 0x00A1;
+
+/// Every message type this implementation understands.
+pub const ALL_MESSAGE_TYPES: &[u16] = &[
+    MSG_CAPABILITY_EXCHANGE,
+    MSG_PING,
+    MSG_PONG,
+    MSG_GOODBYE,
+    MSG_UNSUPPORTED,
+    MSG_CHUNK_REQUEST,
+    MSG_CHUNK_RESPONSE,
+    MSG_CHUNK_ADVERTISE,
+    MSG_SERVICE_RECEIPT_ACK,
+    MSG_CHUNK_QUEUED,
+    MSG_SERVICE_RECEIPT_ACK_BATCH,
+    MSG_SERVICE_RECEIPT_ACK_BATCH_ACK,
+    MSG_DHT_GET,
+    MSG_DHT_GET_RESPONSE,
+    MSG_DHT_PUT,
+    MSG_DHT_PUT_RESPONSE,
+    MSG_DHT_FIND_NODE,
+    MSG_DHT_FIND_NODE_RESPONSE,
+    MSG_DHT_CIRCUIT_LOOKUP,
+    MSG_DHT_CIRCUIT_LOOKUP_RESPONSE,
+    MSG_ESTABLISH_INTRO,
+    MSG_ESTABLISH_INTRO_ACK,
+    MSG_INTRODUCE1,
+    MSG_INTRODUCE2,
+    MSG_RENDEZVOUS_JOIN,
+    MSG_RENDEZVOUS_JOINED,
+    MSG_RENDEZVOUS_RELAY,
+    MSG_RENDEZVOUS_TEARDOWN,
+    MSG_MLS_WELCOME,
+    MSG_MLS_COMMIT,
+    MSG_MLS_APPLICATION,
+    MSG_MLS_PROPOSAL,
+    MSG_MLS_KEY_PACKAGE,
+    MSG_FROST_DKG_ROUND1,
+    MSG_FROST_DKG_ROUND2,
+    MSG_FROST_SIGN_REQUEST,
+    MSG_FROST_SIGN_SHARE,
+    MSG_QUORUM_PROPOSAL,
+    MSG_QUORUM_VOTE,
+    MSG_QUORUM_RESULT,
+    MSG_MINT_REQUEST,
+    MSG_MINT_RESPONSE,
+    MSG_GOSSIP_PUBLISH,
+    MSG_GOSSIP_FORWARD,
+    MSG_GOSSIP_PRUNE,
+    MSG_GOSSIP_GRAFT,
+    MSG_WHISPER_SEND,
+    MSG_WHISPER_DELIVER,
+    MSG_WHISPER_ACK,
+    MSG_WHISPER_DEPOSIT,
+    MSG_WHISPER_POLL,
+    MSG_WHISPER_POLL_RESPONSE,
+    MSG_WHISPER_MAILBOX_ACK,
+    MSG_ORACLE_REQUEST,
+    MSG_ORACLE_RESPONSE,
+    MSG_ORACLE_ATTESTATION,
+    MSG_RECOVERY_REQUEST,
+    MSG_RECOVERY_RESPONSE,
+    MSG_RECOVERY_SHARE,
+    MSG_RECOVERY_COMPLETE,
+    MSG_STATE_SYNC_REQUEST,
+    MSG_STATE_SYNC_RESPONSE,
+];
+
+/// Message types carrying signatures over their encoding. Their payloads
+/// must use canonical CBOR (see [`crate::cbor::to_canonical_vec`]).
+pub const CANONICAL_MESSAGE_TYPES: &[u16] = &[MSG_DHT_PUT, MSG_QUORUM_PROPOSAL];
+
 // ---------------------------------------------------------------------------
 // 0x0001 Capability Exchange
 // ---------------------------------------------------------------------------
@@ -131,6 +235,18 @@ pub struct CapabilityExchange {
     pub agent: String,
     /// Supported message types the peer is willing to handle.
     pub supported_messages: Vec<u16>,
+    /// Encoded delegation certificate for the operational key the peer
+    /// signs transport messages with, if it uses one (Section 6.9).
+    #[serde(default)]
+    pub delegation: Option<Vec<u8>>,
+    /// Protocol magic of the sender's network (Section 2.5). Absent from
+    /// peers that predate network profiles, which are all on mainnet.
+    #[serde(default = "mainnet_magic")]
+    pub network_magic: u32,
+}
+
+fn mainnet_magic() -> u32 {
+    ochra_types::profile::MAINNET_MAGIC
 }
 
 // ---------------------------------------------------------------------------
@@ -196,6 +312,44 @@ pub struct Goodbye {
     pub detail: Option<String>,
 }
 
+// ---------------------------------------------------------------------------
+// 0x0005 Unsupported
+// ---------------------------------------------------------------------------
+
+/// Why a message was refused with [`Unsupported`].
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum UnsupportedReason {
+    /// The message type is not in this node's registry.
+    UnknownType = 0,
+    /// The type is known but this node does not handle it.
+    NotHandled = 1,
+    /// Application-defined reason.
+    Other = 255,
+}
+
+impl UnsupportedReason {
+    /// Convert a raw byte to an `UnsupportedReason`.
+    pub fn from_u8(val: u8) -> Self {
+        match val {
+            0 => Self::UnknownType,
+            1 => Self::NotHandled,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Error reply to a message whose type the receiver does not support.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Unsupported {
+    /// `msg_id` of the refused message.
+    pub msg_id: [u8; 16],
+    /// `msg_type` of the refused message.
+    pub msg_type: u16,
+    /// Reason code ([`UnsupportedReason`]).
+    pub reason: u8,
+}
+
 // ---------------------------------------------------------------------------
 // 0x0010-0x0013 Chunk messages
 // ---------------------------------------------------------------------------
@@ -240,12 +394,57 @@ pub struct ServiceReceiptAck {
     pub chunk_hash: [u8; 32],
     /// Bytes received so far.
     pub bytes_received: u64,
-    /// Ed25519 signature from the requester acknowledging receipt.
+    /// Circuit the chunk arrived on.
+    pub circuit_id: [u8; 16],
+    /// Ephemeral circuit key that made `ack_signature`.
+    pub requester_key: [u8; 32],
+    /// When the chunk was received (Unix seconds).
+    pub timestamp: u64,
+    /// Relay epoch the chunk was received in.
+    pub relay_epoch: u32,
+    /// Random nonce making the resulting receipt unique.
+    pub nonce: [u8; 16],
+    /// Ed25519 signature from the requester over the receipt's
+    /// acknowledged bytes (Section 14.7).
     pub ack_signature: Vec<u8>,
 }
 
+/// Several service receipt acknowledgements for one server, packed into a
+/// single Sphinx payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceReceiptAckBatch {
+    /// Sender-chosen identifier, echoed in the reply.
+    pub batch_id: u64,
+    /// The acknowledgements, in the order the reply's positions refer to.
+    pub acks: Vec<ServiceReceiptAck>,
+}
+
+/// Reply to a [`ServiceReceiptAckBatch`]. Acknowledgements not listed as
+/// settled are sent again in a later batch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceReceiptAckBatchAck {
+    /// The batch being answered.
+    pub batch_id: u64,
+    /// Positions in the batch the server stored, or rejected for good.
+    pub settled: Vec<u32>,
+}
+
+/// Reply to a chunk request the server is not serving yet (upload slots
+/// full). The requester should wait `retry_after_secs` or try another
+/// provider.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChunkQueued {
+    /// BLAKE3 hash of the requested chunk.
+    pub chunk_hash: [u8; 32],
+    /// 1-based position in the server's upload queue; 0 if the queue is
+    /// full and the request was dropped.
+    pub queue_position: u32,
+    /// Suggested seconds before asking again.
+    pub retry_after_secs: u32,
+}
+
 // ---------------------------------------------------------------------------
-// 0x0020-0x0025 DHT messages
+// 0x0020-0x0027 DHT messages
 // ---------------------------------------------------------------------------
 
 /// DHT get request payload.
@@ -277,6 +476,19 @@ pub struct DhtPut {
     pub ttl_secs: u32,
     /// Ed25519 signature over key || value.
     pub signature: Vec<u8>,
+    /// Admission proof for values above the storing node's large-value
+    /// threshold.
+    #[serde(default)]
+    pub admission: Option<DhtPutAdmission>,
+}
+
+/// Admission proof attached to a large DHT put.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DhtPutAdmission {
+    /// Argon2id PoW bound to the record's key and value.
+    Pow { nonce: [u8; 16], hash: [u8; 32] },
+    /// Single-use token granted by the storing node.
+    Token([u8; 32]),
 }
 
 /// DHT put response payload.
@@ -286,6 +498,11 @@ pub struct DhtPutResponse {
     pub key: [u8; 32],
     /// Whether the put was accepted.
     pub accepted: bool,
+    /// If rejected, why: "stale_seq", "invalid_sig", "value_too_large",
+    /// "invalid_record", "rate_limited", "admission_required",
+    /// "invalid_admission", "publisher_quota", or "store_full".
+    #[serde(default)]
+    pub reason: Option<String>,
 }
 
 /// DHT find-node request payload.
@@ -311,6 +528,53 @@ pub struct DhtNodeInfo {
     pub node_id: [u8; 32],
     /// Socket address of the node ("ip:port").
     pub addr: String,
+    /// Address in the other IP family for dual-stack nodes ("[v6]:port" or
+    /// "ip:port"). Absent from peers that predate multi-address support.
+    #[serde(default)]
+    pub alt_addr: Option<String>,
+}
+
+/// Lookup delegated to a circuit's exit relay, carried as a Sphinx payload.
+///
+/// The exit runs the iterative lookup itself and answers on the same
+/// circuit, so the nodes it contacts never see the querier.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DhtCircuitLookup {
+    /// Random identifier echoed in the response.
+    pub query_id: [u8; 16],
+    /// The lookup to perform.
+    pub query: CircuitQuery,
+}
+
+/// Lookup carried by a [`DhtCircuitLookup`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitQuery {
+    /// Fetch the value stored under `key`.
+    Get {
+        /// The key to look up.
+        key: [u8; 32],
+    },
+    /// Find the nodes closest to `target`.
+    FindNode {
+        /// Target node ID.
+        target: [u8; 32],
+    },
+}
+
+/// Result of a [`DhtCircuitLookup`], returned over the same circuit.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DhtCircuitLookupResponse {
+    /// The request's `query_id`.
+    pub query_id: [u8; 16],
+    /// The value, for a `Get` that found one.
+    pub value: Option<Vec<u8>>,
+    /// Closest nodes the exit found.
+    pub nodes: Vec<DhtNodeInfo>,
+    /// Why the exit refused the lookup ("rate_limited", "timeout"), if it
+    /// did.
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -447,7 +711,7 @@ pub struct MlsKeyPackage {
 }
 
 // ---------------------------------------------------------------------------
-// 0x0050-0x0056 FROST / Quorum messages
+// 0x0050-0x0058 FROST / Quorum / Mint messages
 // ---------------------------------------------------------------------------
 
 /// FROST DKG round 1 package payload.
@@ -503,12 +767,35 @@ pub struct QuorumProposal {
     pub proposal_id: [u8; 16],
     /// The epoch this proposal pertains to.
     pub epoch: u32,
-    /// CBOR-encoded proposal body.
+    /// Canonical CBOR-encoded proposal body (see
+    /// [`QuorumProposal::encode_body`]).
     pub body: Vec<u8>,
     /// Ed25519 signature from the proposer.
     pub proposer_signature: Vec<u8>,
 }
 
+impl QuorumProposal {
+    /// Encode a proposal body canonically, so the proposer signature covers
+    /// bytes every quorum member reproduces.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Serialization`] if encoding fails.
+    pub fn encode_body<T: Serialize>(body: &T) -> Result<Vec<u8>, TransportError> {
+        crate::cbor::to_canonical_vec(body)
+    }
+
+    /// Decode the body, rejecting non-canonical encodings.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Deserialization`] if the body is not
+    /// canonical CBOR of type `T`.
+    pub fn decode_body<T: DeserializeOwned>(&self) -> Result<T, TransportError> {
+        crate::cbor::from_canonical_slice(&self.body)
+    }
+}
+
 /// Quorum vote payload.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QuorumVote {
@@ -533,6 +820,44 @@ pub struct QuorumResult {
     pub quorum_signature: Vec<u8>,
 }
 
+/// Mint request payload (client to quorum).
+///
+/// Carries every blinded token for the minted amount, so one request mints
+/// the whole set of denominations. A single-token mint is a batch of one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MintRequest {
+    /// Epoch the receipts were earned in.
+    pub epoch: u32,
+    /// Poseidon commitment to the PIK hash.
+    pub pik_commitment: [u8; 32],
+    /// Total amount being minted in micro-seeds.
+    pub minted_amount: u64,
+    /// Groth16 minting proof (192 bytes).
+    pub groth16_proof: Vec<u8>,
+    /// Merkle root of the service receipts.
+    pub receipt_merkle_root: [u8; 32],
+    /// VOPRF blinded elements, one per token.
+    pub blinded_tokens: Vec<Vec<u8>>,
+    /// Denomination of each token, parallel to `blinded_tokens`.
+    pub denominations: Vec<u64>,
+}
+
+/// Mint response payload (quorum to client).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MintResponse {
+    /// Epoch echoed from the request.
+    pub epoch: u32,
+    /// FROST-signed VOPRF evaluations, in request order.
+    pub signed_blinded_tokens: Vec<Vec<u8>>,
+    /// Single proof covering every evaluation in the batch.
+    pub batch_proof: Vec<u8>,
+    /// VOPRF key epoch the tokens were evaluated under.
+    pub key_epoch: u32,
+    /// 0x00 = ok, 0x01 = proof invalid, 0x02 = epoch mismatch,
+    /// 0x03 = batch invalid.
+    pub status: u8,
+}
+
 // ---------------------------------------------------------------------------
 // 0x0060-0x0062 Gossip messages
 // ---------------------------------------------------------------------------
@@ -572,8 +897,15 @@ pub struct GossipPrune {
     pub reason: u8,
 }
 
+/// Gossip graft payload (asks a peer to add the sender to its topic mesh).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GossipGraft {
+    /// Topic to graft onto.
+    pub topic: [u8; 32],
+}
+
 // ---------------------------------------------------------------------------
-// 0x0070-0x0072 Whisper messages
+// 0x0070-0x0076 Whisper messages
 // ---------------------------------------------------------------------------
 
 /// Whisper send payload (end-to-end encrypted direct message).
@@ -615,6 +947,97 @@ pub struct WhisperAck {
     pub acked_counter: u32,
 }
 
+/// Whisper mailbox deposit payload (sender to the recipient's mailbox relay).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WhisperDeposit {
+    /// Mailbox address (BLAKE3 hash of the recipient's mailbox key).
+    pub mailbox_addr: [u8; 32],
+    /// Random envelope identifier chosen by the sender.
+    pub envelope_id: [u8; 16],
+    /// Envelope sealed to the recipient (opaque to the relay).
+    pub sealed: Vec<u8>,
+    /// Unix timestamp after which the relay discards the envelope.
+    pub expires_at: u64,
+    /// Anti-spam Argon2id PoW nonce.
+    pub pow_nonce: [u8; 16],
+    /// Anti-spam Argon2id PoW output.
+    pub pow_hash: [u8; 32],
+    /// Throttle ticket required by the recipient's strictness.
+    #[serde(default)]
+    pub ticket: Option<SpamTicket>,
+}
+
+/// What an unknown sender spends to reach a Whisper recipient.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpamTicket {
+    /// Argon2id puzzle solved over the envelope.
+    Puzzle {
+        /// PoW nonce.
+        nonce: [u8; 16],
+        /// PoW output.
+        hash: [u8; 32],
+    },
+    /// Seeds transferred to the recipient, with the quorum's receipt.
+    Payment {
+        /// Transfer transaction hash.
+        tx_hash: [u8; 32],
+        /// Amount transferred, in micro-seeds.
+        amount_micro_seeds: u64,
+        /// Epoch whose quorum settled the transfer.
+        epoch: u32,
+        /// FROST signature by that quorum over the payment receipt.
+        quorum_sig: Vec<u8>,
+    },
+}
+
+/// A stored envelope returned by a mailbox poll.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MailboxEnvelope {
+    /// Envelope identifier.
+    pub envelope_id: [u8; 16],
+    /// Sealed envelope bytes.
+    pub sealed: Vec<u8>,
+    /// Unix timestamp at which the relay accepted the deposit.
+    pub deposited_at: u64,
+    /// Throttle ticket the deposit carried, for the recipient to re-check.
+    #[serde(default)]
+    pub ticket: Option<SpamTicket>,
+}
+
+/// Whisper mailbox poll payload (recipient to relay, via an anonymous circuit).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WhisperPoll {
+    /// Recipient's Ed25519 mailbox public key.
+    pub mailbox_pk: [u8; 32],
+    /// Unix timestamp of the poll (freshness check).
+    pub timestamp: u64,
+    /// Ed25519 signature by the mailbox key (64 bytes).
+    pub sig: Vec<u8>,
+    /// Strictness the relay applies to later deposits.
+    #[serde(default)]
+    pub strictness: ochra_types::whisper::ThrottleStrictness,
+}
+
+/// Whisper mailbox poll response payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WhisperPollResponse {
+    /// Envelopes currently held for the mailbox.
+    pub envelopes: Vec<MailboxEnvelope>,
+}
+
+/// Whisper mailbox ack payload (deletes delivered envelopes).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WhisperMailboxAck {
+    /// Recipient's Ed25519 mailbox public key.
+    pub mailbox_pk: [u8; 32],
+    /// Envelopes that were delivered and may be deleted.
+    pub envelope_ids: Vec<[u8; 16]>,
+    /// Unix timestamp of the ack (freshness check).
+    pub timestamp: u64,
+    /// Ed25519 signature by the mailbox key (64 bytes).
+    pub sig: Vec<u8>,
+}
+
 // ---------------------------------------------------------------------------
 // 0x0080-0x0082 Oracle messages
 // ---------------------------------------------------------------------------
@@ -704,6 +1127,48 @@ pub struct RecoveryComplete {
     pub new_pik_hash: Option<[u8; 32]>,
 }
 
+// ---------------------------------------------------------------------------
+// State sync messages (0x00A0-0x00AF)
+// ---------------------------------------------------------------------------
+
+/// State sync component: the signed checkpoint itself.
+pub const SYNC_CHECKPOINT: u8 = 0;
+/// State sync component: the nullifier Bloom filter bytes.
+pub const SYNC_NULLIFIERS: u8 = 1;
+/// State sync component: the encoded `EpochState`.
+pub const SYNC_EPOCH_STATE: u8 = 2;
+/// State sync component: the compressed relay directory snapshot.
+pub const SYNC_DIRECTORY: u8 = 3;
+
+/// State sync request payload: a range of one checkpoint component.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSyncRequest {
+    /// Checkpoint epoch, or 0 for the peer's latest.
+    pub epoch: u32,
+    /// Component, one of the `SYNC_*` constants.
+    pub component: u8,
+    /// Byte offset within the component.
+    pub offset: u64,
+    /// Maximum bytes to return.
+    pub max_length: u32,
+}
+
+/// State sync response payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateSyncResponse {
+    /// Checkpoint epoch the data belongs to.
+    pub epoch: u32,
+    /// Component, one of the `SYNC_*` constants.
+    pub component: u8,
+    /// Offset corresponding to the request.
+    pub offset: u64,
+    /// Total component size in bytes.
+    pub total_size: u64,
+    /// The component data (or portion thereof); empty if the peer has no
+    /// checkpoint for the epoch.
+    pub data: Vec<u8>,
+}
+
 // ---------------------------------------------------------------------------
 // Typed message enum
 // ---------------------------------------------------------------------------
@@ -722,6 +1187,8 @@ pub enum TypedMessage {
     Pong(Pong),
     /// Goodbye (0x0004).
     Goodbye(Goodbye),
+    /// Unsupported (0x0005).
+    Unsupported(Unsupported),
 
     /// Chunk request (0x0010).
     ChunkRequest(ChunkRequest),
@@ -731,6 +1198,12 @@ pub enum TypedMessage {
     ChunkAdvertise(ChunkAdvertise),
     /// Service receipt ack (0x0013).
     ServiceReceiptAck(ServiceReceiptAck),
+    /// Chunk request queued (0x0014).
+    ChunkQueued(ChunkQueued),
+    /// Service receipt ack batch (0x0015).
+    ServiceReceiptAckBatch(ServiceReceiptAckBatch),
+    /// Service receipt ack batch reply (0x0016).
+    ServiceReceiptAckBatchAck(ServiceReceiptAckBatchAck),
 
     /// DHT get (0x0020).
     DhtGet(DhtGet),
@@ -744,6 +1217,10 @@ pub enum TypedMessage {
     DhtFindNode(DhtFindNode),
     /// DHT find node response (0x0025).
     DhtFindNodeResponse(DhtFindNodeResponse),
+    /// DHT circuit lookup (0x0026).
+    DhtCircuitLookup(DhtCircuitLookup),
+    /// DHT circuit lookup response (0x0027).
+    DhtCircuitLookupResponse(DhtCircuitLookupResponse),
 
     /// Establish intro (0x0030).
     EstablishIntro(EstablishIntro),
@@ -787,6 +1264,10 @@ pub enum TypedMessage {
     QuorumVote(QuorumVote),
     /// Quorum result (0x0056).
     QuorumResult(QuorumResult),
+    /// Mint request (0x0057).
+    MintRequest(MintRequest),
+    /// Mint response (0x0058).
+    MintResponse(MintResponse),
 
     /// Gossip publish (0x0060).
     GossipPublish(GossipPublish),
@@ -794,6 +1275,8 @@ pub enum TypedMessage {
     GossipForward(GossipForward),
     /// Gossip prune (0x0062).
     GossipPrune(GossipPrune),
+    /// Gossip graft (0x0063).
+    GossipGraft(GossipGraft),
 
     /// Whisper send (0x0070).
     WhisperSend(WhisperSend),
@@ -801,6 +1284,14 @@ pub enum TypedMessage {
     WhisperDeliver(WhisperDeliver),
     /// Whisper ack (0x0072).
     WhisperAck(WhisperAck),
+    /// Whisper mailbox deposit (0x0073).
+    WhisperDeposit(WhisperDeposit),
+    /// Whisper mailbox poll (0x0074).
+    WhisperPoll(WhisperPoll),
+    /// Whisper mailbox poll response (0x0075).
+    WhisperPollResponse(WhisperPollResponse),
+    /// Whisper mailbox ack (0x0076).
+    WhisperMailboxAck(WhisperMailboxAck),
 
     /// Oracle request (0x0080).
     OracleRequest(OracleRequest),
@@ -817,6 +1308,11 @@ pub enum TypedMessage {
     RecoveryShare(RecoveryShare),
     /// Recovery complete (0x0093).
     RecoveryComplete(RecoveryComplete),
+
+    /// State sync request (0x00A0).
+    StateSyncRequest(StateSyncRequest),
+    /// State sync response (0x00A1).
+    StateSyncResponse(StateSyncResponse),
 }
 
 impl TypedMessage {
@@ -827,16 +1323,22 @@ impl TypedMessage {
             Self::Ping(_) => MSG_PING,
             Self::Pong(_) => MSG_PONG,
             Self::Goodbye(_) => MSG_GOODBYE,
+            Self::Unsupported(_) => MSG_UNSUPPORTED,
             Self::ChunkRequest(_) => MSG_CHUNK_REQUEST,
             Self::ChunkResponse(_) => MSG_CHUNK_RESPONSE,
             Self::ChunkAdvertise(_) => MSG_CHUNK_ADVERTISE,
             Self::ServiceReceiptAck(_) => MSG_SERVICE_RECEIPT_ACK,
+            Self::ChunkQueued(_) => MSG_CHUNK_QUEUED,
+            Self::ServiceReceiptAckBatch(_) => MSG_SERVICE_RECEIPT_ACK_BATCH,
+            Self::ServiceReceiptAckBatchAck(_) => MSG_SERVICE_RECEIPT_ACK_BATCH_ACK,
             Self::DhtGet(_) => MSG_DHT_GET,
             Self::DhtGetResponse(_) => MSG_DHT_GET_RESPONSE,
             Self::DhtPut(_) => MSG_DHT_PUT,
             Self::DhtPutResponse(_) => MSG_DHT_PUT_RESPONSE,
             Self::DhtFindNode(_) => MSG_DHT_FIND_NODE,
             Self::DhtFindNodeResponse(_) => MSG_DHT_FIND_NODE_RESPONSE,
+            Self::DhtCircuitLookup(_) => MSG_DHT_CIRCUIT_LOOKUP,
+            Self::DhtCircuitLookupResponse(_) => MSG_DHT_CIRCUIT_LOOKUP_RESPONSE,
             Self::EstablishIntro(_) => MSG_ESTABLISH_INTRO,
             Self::EstablishIntroAck(_) => MSG_ESTABLISH_INTRO_ACK,
             Self::Introduce1(_) => MSG_INTRODUCE1,
@@ -857,12 +1359,19 @@ impl TypedMessage {
             Self::QuorumProposal(_) => MSG_QUORUM_PROPOSAL,
             Self::QuorumVote(_) => MSG_QUORUM_VOTE,
             Self::QuorumResult(_) => MSG_QUORUM_RESULT,
+            Self::MintRequest(_) => MSG_MINT_REQUEST,
+            Self::MintResponse(_) => MSG_MINT_RESPONSE,
             Self::GossipPublish(_) => MSG_GOSSIP_PUBLISH,
             Self::GossipForward(_) => MSG_GOSSIP_FORWARD,
             Self::GossipPrune(_) => MSG_GOSSIP_PRUNE,
+            Self::GossipGraft(_) => MSG_GOSSIP_GRAFT,
             Self::WhisperSend(_) => MSG_WHISPER_SEND,
             Self::WhisperDeliver(_) => MSG_WHISPER_DELIVER,
             Self::WhisperAck(_) => MSG_WHISPER_ACK,
+            Self::WhisperDeposit(_) => MSG_WHISPER_DEPOSIT,
+            Self::WhisperPoll(_) => MSG_WHISPER_POLL,
+            Self::WhisperPollResponse(_) => MSG_WHISPER_POLL_RESPONSE,
+            Self::WhisperMailboxAck(_) => MSG_WHISPER_MAILBOX_ACK,
             Self::OracleRequest(_) => MSG_ORACLE_REQUEST,
             Self::OracleResponse(_) => MSG_ORACLE_RESPONSE,
             Self::OracleAttestation(_) => MSG_ORACLE_ATTESTATION,
@@ -870,6 +1379,27 @@ impl TypedMessage {
             Self::RecoveryResponse(_) => MSG_RECOVERY_RESPONSE,
             Self::RecoveryShare(_) => MSG_RECOVERY_SHARE,
             Self::RecoveryComplete(_) => MSG_RECOVERY_COMPLETE,
+            Self::StateSyncRequest(_) => MSG_STATE_SYNC_REQUEST,
+            Self::StateSyncResponse(_) => MSG_STATE_SYNC_RESPONSE,
+        }
+    }
+
+    /// Whether this message must be canonically encoded.
+    pub fn requires_canonical(&self) -> bool {
+        CANONICAL_MESSAGE_TYPES.contains(&self.msg_type())
+    }
+
+    /// Encode as a wire payload: canonical CBOR for the
+    /// [`CANONICAL_MESSAGE_TYPES`], plain CBOR otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Serialization`] if encoding fails.
+    pub fn to_cbor(&self) -> Result<Vec<u8>, TransportError> {
+        if self.requires_canonical() {
+            crate::cbor::to_canonical_vec(self)
+        } else {
+            crate::cbor::to_vec(self)
         }
     }
 }
@@ -888,6 +1418,30 @@ mod tests {
         assert_eq!(ping.nonce, restored.nonce);
     }
 
+    #[test]
+    fn test_quorum_proposal_body_is_canonical() {
+        let body: std::collections::BTreeMap<String, u64> =
+            [("epoch".to_string(), 7), ("a".to_string(), 1)]
+                .into_iter()
+                .collect();
+        let proposal = QuorumProposal {
+            proposal_id: [1; 16],
+            epoch: 7,
+            body: QuorumProposal::encode_body(&body).expect("encode"),
+            proposer_signature: vec![0; 64],
+        };
+        let decoded: std::collections::BTreeMap<String, u64> =
+            proposal.decode_body().expect("decode");
+        assert_eq!(decoded, body);
+
+        // 7 encoded as a two-byte integer
+        let padded = QuorumProposal {
+            body: vec![0x18, 0x07],
+            ..proposal
+        };
+        assert!(padded.decode_body::<u64>().is_err());
+    }
+
     #[test]
     fn test_goodbye_reason_from_u8() {
         assert_eq!(GoodbyeReason::from_u8(0), GoodbyeReason::Normal);
@@ -922,12 +1476,36 @@ mod tests {
             features: 0x0001,
             agent: "ochra-test/0.1".to_string(),
             supported_messages: vec![MSG_PING, MSG_PONG, MSG_GOODBYE],
+            delegation: None,
+            network_magic: ochra_types::profile::TESTNET_MAGIC,
         };
         let json = serde_json::to_string(&cap).expect("serialize");
         let restored: CapabilityExchange = serde_json::from_str(&json).expect("deserialize");
         assert_eq!(restored.protocol_version, 5);
         assert_eq!(restored.agent, "ochra-test/0.1");
         assert_eq!(restored.supported_messages.len(), 3);
+        assert_eq!(restored.network_magic, ochra_types::profile::TESTNET_MAGIC);
+    }
+
+    #[test]
+    fn test_mint_request_carries_batch() {
+        let request = MintRequest {
+            epoch: 7,
+            pik_commitment: [1; 32],
+            minted_amount: 111,
+            groth16_proof: vec![0; 192],
+            receipt_merkle_root: [2; 32],
+            blinded_tokens: vec![vec![3; 32], vec![4; 32], vec![5; 32]],
+            denominations: vec![100, 10, 1],
+        };
+        let json = serde_json::to_string(&request).expect("serialize");
+        let restored: MintRequest = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(restored.blinded_tokens.len(), 3);
+        assert_eq!(restored.denominations, vec![100, 10, 1]);
+        assert_eq!(
+            TypedMessage::MintRequest(restored).msg_type(),
+            MSG_MINT_REQUEST
+        );
     }
 
     #[test]
@@ -935,9 +1513,19 @@ mod tests {
         let info = DhtNodeInfo {
             node_id: [0xBB; 32],
             addr: "127.0.0.1:9735".to_string(),
+            alt_addr: Some("[2001:db8::1]:9735".to_string()),
         };
         let json = serde_json::to_string(&info).expect("serialize");
         let restored: DhtNodeInfo = serde_json::from_str(&json).expect("deserialize");
         assert_eq!(restored.addr, "127.0.0.1:9735");
+        assert_eq!(restored.alt_addr.as_deref(), Some("[2001:db8::1]:9735"));
+    }
+
+    #[test]
+    fn test_dht_node_info_without_alt_addr() {
+        // Single-address encodings from older peers still decode.
+        let json = r#"{"node_id":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"addr":"10.0.0.1:4433"}"#;
+        let restored: DhtNodeInfo = serde_json::from_str(json).expect("deserialize");
+        assert_eq!(restored.alt_addr, None);
     }
 }
@@ -0,0 +1,304 @@
+//! Connection migration across network changes.
+//!
+//! When a laptop moves between Wi-Fi networks its local address changes
+//! and, without migration, every QUIC connection is lost and every onion
+//! circuit has to be rebuilt. QUIC lets a connection survive this: the
+//! endpoint switches to a new UDP socket ([`QuicNode::rebind`]) and the
+//! peer validates the new path (`PATH_CHALLENGE` / `PATH_RESPONSE`) before
+//! sending on it. Servers accept migrating clients; see
+//! [`QuicNode::new`](crate::quic::QuicNode::new).
+//!
+//! ## Flow
+//!
+//! 1. The platform reports a [`NetworkChange`] through a
+//!    [`NetworkMonitor`].
+//! 2. The node rebinds its endpoint and calls
+//!    [`MigrationTracker::begin`], which returns the peers to probe.
+//! 3. Each peer is probed with [`QuicNode::validate_path`]; successes are
+//!    recorded with [`MigrationTracker::on_validated`].
+//! 4. [`MigrationTracker::poll`] reports the [`MigrationOutcome`] once
+//!    every probe has answered or [`PATH_VALIDATION_TIMEOUT_MS`] has
+//!    passed. Circuits whose entry hop is in `resumed` keep running; only
+//!    those through a `lost` peer are rebuilt.
+//!
+//! Peers can migrate too. [`MigrationTracker::observe_remote`] reports
+//! when a connection's remote address changes under it.
+//!
+//! [`QuicNode::rebind`]: crate::quic::QuicNode::rebind
+//! [`QuicNode::validate_path`]: crate::quic::QuicNode::validate_path
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+/// How long a peer has to answer a path probe after a rebind.
+pub const PATH_VALIDATION_TIMEOUT_MS: u64 = 5_000;
+
+/// Buffered notifications before the platform's sender waits.
+const CHANGE_CHANNEL_CAPACITY: usize = 16;
+
+/// A change in the host's network connectivity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkChange {
+    /// An interface came up, went down, or changed address.
+    InterfacesChanged,
+    /// Connectivity was lost.
+    Offline,
+    /// Connectivity returned after [`NetworkChange::Offline`].
+    Online,
+}
+
+impl NetworkChange {
+    /// Whether the endpoint should move to a new socket.
+    pub fn needs_rebind(self) -> bool {
+        matches!(self, Self::InterfacesChanged | Self::Online)
+    }
+
+    /// Parse a wire name.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "interfaces_changed" => Some(Self::InterfacesChanged),
+            "offline" => Some(Self::Offline),
+            "online" => Some(Self::Online),
+            _ => None,
+        }
+    }
+}
+
+/// Source of network change notifications on a platform.
+pub trait NetworkMonitor: Send {
+    /// Wait for the next change. Returns `None` once the source is gone.
+    fn next_change(&mut self) -> impl Future<Output = Option<NetworkChange>> + Send;
+}
+
+/// Sending half of a [`ChannelMonitor`], held by whatever observes the
+/// platform (an OS watcher, or the UI over RPC).
+#[derive(Clone, Debug)]
+pub struct NetworkChangeNotifier {
+    tx: mpsc::Sender<NetworkChange>,
+}
+
+impl NetworkChangeNotifier {
+    /// Report a change. Returns `false` if the monitor has been dropped or
+    /// its buffer is full.
+    pub fn notify(&self, change: NetworkChange) -> bool {
+        self.tx.try_send(change).is_ok()
+    }
+}
+
+/// A [`NetworkMonitor`] fed through a [`NetworkChangeNotifier`].
+#[derive(Debug)]
+pub struct ChannelMonitor {
+    rx: mpsc::Receiver<NetworkChange>,
+}
+
+impl NetworkMonitor for ChannelMonitor {
+    async fn next_change(&mut self) -> Option<NetworkChange> {
+        self.rx.recv().await
+    }
+}
+
+/// Create a connected notifier and monitor.
+pub fn channel() -> (NetworkChangeNotifier, ChannelMonitor) {
+    let (tx, rx) = mpsc::channel(CHANGE_CHANNEL_CAPACITY);
+    (NetworkChangeNotifier { tx }, ChannelMonitor { rx })
+}
+
+/// A peer whose remote address changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerMigrated {
+    pub peer: [u8; 32],
+    pub from: SocketAddr,
+    pub to: SocketAddr,
+}
+
+/// Result of one local migration.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationOutcome {
+    /// Peers whose connection answered on the new path.
+    pub resumed: Vec<[u8; 32]>,
+    /// Peers that did not answer in time. They are no longer tracked.
+    pub lost: Vec<[u8; 32]>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PeerPath {
+    remote: SocketAddr,
+    validated: bool,
+}
+
+/// Tracks connected peers' paths through local and remote migrations.
+#[derive(Debug, Default)]
+pub struct MigrationTracker {
+    peers: HashMap<[u8; 32], PeerPath>,
+    /// Start of the migration in progress.
+    started_ms: Option<u64>,
+}
+
+impl MigrationTracker {
+    /// Create an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a connected peer.
+    pub fn track(&mut self, peer: [u8; 32], remote: SocketAddr) {
+        self.peers.insert(
+            peer,
+            PeerPath {
+                remote,
+                validated: true,
+            },
+        );
+    }
+
+    /// Stop tracking a peer whose connection closed.
+    pub fn untrack(&mut self, peer: &[u8; 32]) {
+        self.peers.remove(peer);
+    }
+
+    /// Number of tracked peers.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Whether no peers are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Whether a local migration is awaiting path validation.
+    pub fn is_migrating(&self) -> bool {
+        self.started_ms.is_some()
+    }
+
+    /// Record a connection's current remote address, reporting when the
+    /// peer has moved.
+    pub fn observe_remote(&mut self, peer: [u8; 32], remote: SocketAddr) -> Option<PeerMigrated> {
+        let path = self.peers.get_mut(&peer)?;
+        if path.remote == remote {
+            return None;
+        }
+        let from = std::mem::replace(&mut path.remote, remote);
+        Some(PeerMigrated {
+            peer,
+            from,
+            to: remote,
+        })
+    }
+
+    /// Start a local migration after the endpoint has been rebound.
+    ///
+    /// Every tracked peer must now be re-validated. Returns the peers to
+    /// probe. A migration already in progress is restarted.
+    pub fn begin(&mut self, now_ms: u64) -> Vec<[u8; 32]> {
+        self.started_ms = Some(now_ms);
+        for path in self.peers.values_mut() {
+            path.validated = false;
+        }
+        self.peers.keys().copied().collect()
+    }
+
+    /// Record that a peer answered on the new path.
+    pub fn on_validated(&mut self, peer: &[u8; 32]) -> bool {
+        match self.peers.get_mut(peer) {
+            Some(path) if self.started_ms.is_some() => {
+                path.validated = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Finish the migration in progress once every peer has answered or
+    /// the validation timeout has passed.
+    pub fn poll(&mut self, now_ms: u64) -> Option<MigrationOutcome> {
+        let started = self.started_ms?;
+        let all_validated = self.peers.values().all(|p| p.validated);
+        if !all_validated && now_ms.saturating_sub(started) < PATH_VALIDATION_TIMEOUT_MS {
+            return None;
+        }
+        self.started_ms = None;
+        let mut outcome = MigrationOutcome::default();
+        self.peers.retain(|peer, path| {
+            if path.validated {
+                outcome.resumed.push(*peer);
+            } else {
+                outcome.lost.push(*peer);
+            }
+            path.validated
+        });
+        Some(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([192, 0, 2, 1], port))
+    }
+
+    #[test]
+    fn test_migration_resumes_validated_and_drops_silent_peers() {
+        let mut tracker = MigrationTracker::new();
+        tracker.track([1u8; 32], addr(1));
+        tracker.track([2u8; 32], addr(2));
+        assert!(tracker.poll(0).is_none());
+
+        let mut probes = tracker.begin(1_000);
+        probes.sort();
+        assert_eq!(probes, vec![[1u8; 32], [2u8; 32]]);
+        assert!(tracker.on_validated(&[1u8; 32]));
+        assert!(tracker.poll(2_000).is_none());
+
+        let outcome = tracker
+            .poll(1_000 + PATH_VALIDATION_TIMEOUT_MS)
+            .expect("timed out");
+        assert_eq!(outcome.resumed, vec![[1u8; 32]]);
+        assert_eq!(outcome.lost, vec![[2u8; 32]]);
+        assert_eq!(tracker.len(), 1);
+        assert!(!tracker.is_migrating());
+    }
+
+    #[test]
+    fn test_migration_finishes_early_when_all_validated() {
+        let mut tracker = MigrationTracker::new();
+        tracker.track([1u8; 32], addr(1));
+        tracker.begin(0);
+        tracker.on_validated(&[1u8; 32]);
+        let outcome = tracker.poll(1).expect("complete");
+        assert_eq!(outcome.resumed.len(), 1);
+        assert!(outcome.lost.is_empty());
+        assert!(!tracker.on_validated(&[1u8; 32]));
+    }
+
+    #[test]
+    fn test_observe_remote_reports_peer_migration() {
+        let mut tracker = MigrationTracker::new();
+        tracker.track([1u8; 32], addr(1));
+        assert!(tracker.observe_remote([1u8; 32], addr(1)).is_none());
+        let moved = tracker.observe_remote([1u8; 32], addr(9)).expect("moved");
+        assert_eq!((moved.from, moved.to), (addr(1), addr(9)));
+        assert!(tracker.observe_remote([7u8; 32], addr(1)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_channel_monitor_delivers_changes() {
+        let (notifier, mut monitor) = channel();
+        assert!(notifier.notify(NetworkChange::InterfacesChanged));
+        let change = monitor.next_change().await.expect("change");
+        assert!(change.needs_rebind());
+        assert_eq!(
+            NetworkChange::parse("offline"),
+            Some(NetworkChange::Offline)
+        );
+        drop(notifier);
+        assert!(monitor.next_change().await.is_none());
+    }
+}
@@ -0,0 +1,361 @@
+//! Per-peer misbehavior tracking shared across subsystems.
+//!
+//! Transport, DHT, and gossip report offenses (bad signatures, replayed
+//! packets, protocol violations, invalid messages) to one
+//! [`MisbehaviorManager`]. Each offense adds its weight to the peer's score,
+//! which halves every [`MisbehaviorConfig::half_life_secs`]:
+//!
+//! | Standing | Condition | Effect |
+//! |---|---|---|
+//! | [`Standing::Good`] | score below the greylist threshold | none |
+//! | [`Standing::Greylisted`] | score at or above the greylist threshold | not used for routing, relaying, or gossip |
+//! | [`Standing::Blacklisted`] | score reached the blacklist threshold | connections refused until the ban expires |
+//!
+//! A greylisted peer returns to good standing once its score decays; a
+//! blacklisted peer returns when the ban expires. Times are Unix seconds so
+//! the list can be persisted and restored across restarts.
+
+use std::collections::HashMap;
+
+/// Default score at which a peer is greylisted.
+pub const DEFAULT_GREYLIST_THRESHOLD: f64 = 50.0;
+
+/// Default score at which a peer is blacklisted.
+pub const DEFAULT_BLACKLIST_THRESHOLD: f64 = 100.0;
+
+/// Default score half-life (1 hour).
+pub const DEFAULT_HALF_LIFE_SECS: u64 = 3600;
+
+/// Default blacklist duration (24 hours).
+pub const DEFAULT_BAN_SECS: u64 = 86_400;
+
+/// Scores below this are forgotten.
+const FORGET_BELOW: f64 = 1.0;
+
+/// Kinds of misbehavior.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Offense {
+    /// A signature on a record or message did not verify.
+    InvalidSignature,
+    /// A Sphinx packet was replayed.
+    ReplayAttempt,
+    /// A message violated the wire protocol.
+    ProtocolViolation,
+    /// A well-formed message failed validation (e.g. a bad gossip payload).
+    InvalidMessage,
+}
+
+impl Offense {
+    /// Score added per occurrence.
+    pub fn weight(&self) -> f64 {
+        match self {
+            Offense::InvalidSignature => 25.0,
+            Offense::ReplayAttempt => 10.0,
+            Offense::ProtocolViolation => 20.0,
+            Offense::InvalidMessage => 10.0,
+        }
+    }
+}
+
+/// A peer's standing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Standing {
+    /// No restrictions.
+    Good,
+    /// Not used for routing, relaying, or gossip.
+    Greylisted,
+    /// Connections refused.
+    Blacklisted,
+}
+
+impl Standing {
+    /// Lowercase name, as persisted and reported over RPC.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Standing::Good => "good",
+            Standing::Greylisted => "greylisted",
+            Standing::Blacklisted => "blacklisted",
+        }
+    }
+
+    /// Parse a name produced by [`as_str`](Self::as_str).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "good" => Some(Standing::Good),
+            "greylisted" => Some(Standing::Greylisted),
+            "blacklisted" => Some(Standing::Blacklisted),
+            _ => None,
+        }
+    }
+
+    /// Whether the peer may connect at all.
+    pub fn may_connect(&self) -> bool {
+        *self != Standing::Blacklisted
+    }
+
+    /// Whether the peer may be used for DHT routing, relaying, and gossip.
+    pub fn may_route(&self) -> bool {
+        *self == Standing::Good
+    }
+}
+
+/// Thresholds and timing.
+#[derive(Clone, Debug)]
+pub struct MisbehaviorConfig {
+    /// Score at which a peer is greylisted.
+    pub greylist_threshold: f64,
+    /// Score at which a peer is blacklisted.
+    pub blacklist_threshold: f64,
+    /// Time for a score to halve.
+    pub half_life_secs: u64,
+    /// How long a blacklisting lasts.
+    pub ban_secs: u64,
+}
+
+impl Default for MisbehaviorConfig {
+    fn default() -> Self {
+        Self {
+            greylist_threshold: DEFAULT_GREYLIST_THRESHOLD,
+            blacklist_threshold: DEFAULT_BLACKLIST_THRESHOLD,
+            half_life_secs: DEFAULT_HALF_LIFE_SECS,
+            ban_secs: DEFAULT_BAN_SECS,
+        }
+    }
+}
+
+/// A peer's misbehavior record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerRecord {
+    pub peer: [u8; 32],
+    /// Offense score as of `updated_at`.
+    pub score: f64,
+    pub standing: Standing,
+    /// End of the blacklisting, or 0 if not blacklisted.
+    pub banned_until: u64,
+    pub updated_at: u64,
+}
+
+/// Offense scores and standings of every misbehaving peer.
+#[derive(Debug, Default)]
+pub struct MisbehaviorManager {
+    config: MisbehaviorConfig,
+    peers: HashMap<[u8; 32], PeerRecord>,
+}
+
+impl MisbehaviorManager {
+    /// Create an empty manager.
+    pub fn new(config: MisbehaviorConfig) -> Self {
+        Self {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Score of `record` decayed to `now`.
+    fn decayed(&self, record: &PeerRecord, now: u64) -> f64 {
+        if self.config.half_life_secs == 0 {
+            return 0.0;
+        }
+        let elapsed = now.saturating_sub(record.updated_at) as f64;
+        record.score * 0.5f64.powf(elapsed / self.config.half_life_secs as f64)
+    }
+
+    /// Standing implied by a decayed score and ban.
+    fn standing_for(&self, score: f64, banned_until: u64, now: u64) -> Standing {
+        if banned_until > now {
+            Standing::Blacklisted
+        } else if score >= self.config.greylist_threshold {
+            Standing::Greylisted
+        } else {
+            Standing::Good
+        }
+    }
+
+    /// Record an offense by `peer` and return its new standing.
+    pub fn record(&mut self, peer: [u8; 32], offense: Offense, now: u64) -> Standing {
+        let existing = self.peers.get(&peer);
+        let mut score = existing.map_or(0.0, |r| self.decayed(r, now)) + offense.weight();
+        let mut banned_until = existing.map_or(0, |r| r.banned_until);
+        if score >= self.config.blacklist_threshold && banned_until <= now {
+            banned_until = now + self.config.ban_secs;
+            // Start afresh once the ban ends.
+            score = 0.0;
+        }
+        let standing = self.standing_for(score, banned_until, now);
+        self.peers.insert(
+            peer,
+            PeerRecord {
+                peer,
+                score,
+                standing,
+                banned_until,
+                updated_at: now,
+            },
+        );
+        standing
+    }
+
+    /// Current standing of `peer`.
+    pub fn standing(&self, peer: &[u8; 32], now: u64) -> Standing {
+        match self.peers.get(peer) {
+            Some(record) => self.standing_for(self.decayed(record, now), record.banned_until, now),
+            None => Standing::Good,
+        }
+    }
+
+    /// Apply decay and ban expiry, forgetting peers that have recovered.
+    ///
+    /// Returns the peers whose standing changed.
+    pub fn tick(&mut self, now: u64) -> Vec<([u8; 32], Standing)> {
+        let mut changed = Vec::new();
+        let peers: Vec<[u8; 32]> = self.peers.keys().copied().collect();
+        for peer in peers {
+            let Some(record) = self.peers.get(&peer) else {
+                continue;
+            };
+            let score = self.decayed(record, now);
+            let standing = self.standing_for(score, record.banned_until, now);
+            if standing != record.standing {
+                changed.push((peer, standing));
+            }
+            if standing == Standing::Good && score < FORGET_BELOW {
+                self.peers.remove(&peer);
+            } else if let Some(record) = self.peers.get_mut(&peer) {
+                record.score = score;
+                record.standing = standing;
+                record.updated_at = now;
+            }
+        }
+        changed
+    }
+
+    /// Clear a peer's record.
+    pub fn pardon(&mut self, peer: &[u8; 32]) -> Option<PeerRecord> {
+        self.peers.remove(peer)
+    }
+
+    /// Greylisted and blacklisted peers as of `now`.
+    pub fn restricted(&self, now: u64) -> Vec<PeerRecord> {
+        let mut records: Vec<PeerRecord> = self
+            .peers
+            .values()
+            .filter_map(|r| {
+                let score = self.decayed(r, now);
+                let standing = self.standing_for(score, r.banned_until, now);
+                (standing != Standing::Good).then(|| PeerRecord {
+                    score,
+                    standing,
+                    ..r.clone()
+                })
+            })
+            .collect();
+        records.sort_by_key(|r| r.peer);
+        records
+    }
+
+    /// Restore records persisted by a previous run.
+    pub fn restore(&mut self, records: impl IntoIterator<Item = PeerRecord>) {
+        for record in records {
+            self.peers.insert(record.peer, record);
+        }
+    }
+
+    /// Number of tracked peers.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Whether no peer is tracked.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEER: [u8; 32] = [7; 32];
+
+    #[test]
+    fn test_offenses_escalate_to_blacklist() {
+        let mut manager = MisbehaviorManager::default();
+        assert_eq!(
+            manager.record(PEER, Offense::InvalidSignature, 0),
+            Standing::Good
+        );
+        assert_eq!(
+            manager.record(PEER, Offense::InvalidSignature, 0),
+            Standing::Greylisted
+        );
+        assert!(!manager.standing(&PEER, 0).may_route());
+        assert!(manager.standing(&PEER, 0).may_connect());
+
+        manager.record(PEER, Offense::ProtocolViolation, 0);
+        manager.record(PEER, Offense::InvalidSignature, 0);
+        let standing = manager.record(PEER, Offense::InvalidSignature, 0);
+        assert_eq!(standing, Standing::Blacklisted);
+        assert!(!standing.may_connect());
+    }
+
+    #[test]
+    fn test_greylist_decays() {
+        let mut manager = MisbehaviorManager::default();
+        for _ in 0..6 {
+            manager.record(PEER, Offense::ReplayAttempt, 0);
+        }
+        assert_eq!(manager.standing(&PEER, 0), Standing::Greylisted);
+        assert_eq!(
+            manager.standing(&PEER, DEFAULT_HALF_LIFE_SECS),
+            Standing::Good
+        );
+
+        let changed = manager.tick(DEFAULT_HALF_LIFE_SECS);
+        assert_eq!(changed, vec![(PEER, Standing::Good)]);
+    }
+
+    #[test]
+    fn test_ban_expires() {
+        let mut manager = MisbehaviorManager::default();
+        for _ in 0..4 {
+            manager.record(PEER, Offense::InvalidSignature, 100);
+        }
+        assert_eq!(manager.standing(&PEER, 100), Standing::Blacklisted);
+        assert_eq!(
+            manager.standing(&PEER, 100 + DEFAULT_BAN_SECS - 1),
+            Standing::Blacklisted
+        );
+        assert_eq!(
+            manager.tick(100 + DEFAULT_BAN_SECS),
+            vec![(PEER, Standing::Good)]
+        );
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn test_restricted_roundtrips_through_restore() {
+        let mut manager = MisbehaviorManager::default();
+        for _ in 0..2 {
+            manager.record(PEER, Offense::InvalidSignature, 10);
+        }
+        manager.record([1; 32], Offense::InvalidMessage, 10);
+
+        let restricted = manager.restricted(10);
+        assert_eq!(restricted.len(), 1);
+        assert_eq!(restricted[0].standing, Standing::Greylisted);
+
+        let mut restored = MisbehaviorManager::default();
+        restored.restore(restricted);
+        assert_eq!(restored.standing(&PEER, 10), Standing::Greylisted);
+        assert!(restored.pardon(&PEER).is_some());
+        assert_eq!(restored.standing(&PEER, 10), Standing::Good);
+    }
+
+    #[test]
+    fn test_standing_names() {
+        for standing in [Standing::Good, Standing::Greylisted, Standing::Blacklisted] {
+            assert_eq!(Standing::parse(standing.as_str()), Some(standing));
+        }
+        assert_eq!(Standing::parse("banned"), None);
+    }
+}
@@ -0,0 +1,498 @@
+//! Pluggable transports for networks that block UDP/QUIC.
+//!
+//! A [`PluggableTransport`] dials a peer and yields a duplex byte stream that
+//! carries the same length-prefixed wire frames as a QUIC stream
+//! (`[length:4 LE][data:length]`). Two transports are provided:
+//!
+//! - [`QuicNode`] — the normal QUIC/TLS 1.3 path, one bidirectional stream
+//!   per dial.
+//! - [`TcpTlsTransport`] — TLS 1.3 over TCP with the `ochra/5` ALPN,
+//!   optionally through a SOCKS5 or HTTP proxy (see [`proxy`](crate::proxy)),
+//!   such as Tor or the I2P SOCKS tunnel.
+//!   Peers accept it with a [`TcpTlsListener`] on the TCP port matching their
+//!   QUIC UDP port.
+//!
+//! [`FallbackDialer`] tries the primary transport first and falls back to the
+//! secondary when it fails or times out, remembering per peer which one
+//! worked so later dials go straight to it.
+//!
+//! As with QUIC, TLS certificates are self-signed; peer identity is
+//! established by the PIK exchange over the stream.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rustls::pki_types::ServerName;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+pub use crate::proxy::ProxyTarget;
+use crate::proxy::{Proxy, ProxyRules};
+use crate::quic::{generate_self_signed_cert, QuicNode, SkipServerVerification, ALPN_OCHRA_V5};
+use crate::{Result, TransportError};
+
+/// Default time allowed for a single dial attempt before falling back.
+pub const DEFAULT_DIAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS server name presented when dialing (certificates are self-signed).
+const TLS_SERVER_NAME: &str = "ochra-node";
+
+/// Which transport carried a connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportKind {
+    /// QUIC over UDP.
+    Quic,
+    /// TLS 1.3 over TCP, possibly through a proxy.
+    TcpTls,
+}
+
+/// A duplex byte stream carrying length-prefixed wire frames.
+pub trait WireStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> WireStream for T {}
+
+/// A boxed [`WireStream`] returned by any transport.
+pub type BoxedStream = Box<dyn WireStream>;
+
+/// A way of reaching a peer.
+pub trait PluggableTransport: Send + Sync {
+    /// Which transport this is.
+    fn kind(&self) -> TransportKind;
+
+    /// Open a stream to the peer at `addr`.
+    fn dial(&self, addr: SocketAddr) -> impl Future<Output = Result<BoxedStream>> + Send;
+}
+
+impl PluggableTransport for QuicNode {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Quic
+    }
+
+    async fn dial(&self, addr: SocketAddr) -> Result<BoxedStream> {
+        let connection = self.connect(addr, TLS_SERVER_NAME).await?;
+        // The streams keep the connection open until both are dropped.
+        let (send, recv) = Self::open_bi(&connection).await?;
+        Ok(Box::new(tokio::io::join(recv, send)))
+    }
+}
+
+/// Write one length-prefixed frame.
+///
+/// # Errors
+///
+/// Returns [`TransportError::InvalidPacket`] if `data` does not fit a 4-byte
+/// length prefix, or [`TransportError::Io`] if the write fails.
+pub async fn write_frame<W: AsyncWrite + Unpin + ?Sized>(
+    stream: &mut W,
+    data: &[u8],
+) -> Result<()> {
+    let len = u32::try_from(data.len()).map_err(|_| {
+        TransportError::InvalidPacket("message too large for 4-byte length prefix".to_string())
+    })?;
+    stream
+        .write_all(&len.to_le_bytes())
+        .await
+        .map_err(|e| TransportError::Io(e.to_string()))?;
+    stream
+        .write_all(data)
+        .await
+        .map_err(|e| TransportError::Io(e.to_string()))?;
+    stream
+        .flush()
+        .await
+        .map_err(|e| TransportError::Io(e.to_string()))
+}
+
+/// Read one length-prefixed frame of at most `max_size` bytes.
+///
+/// # Errors
+///
+/// Returns [`TransportError::Io`] if the read fails, or
+/// [`TransportError::InvalidPacket`] if the length exceeds `max_size`.
+pub async fn read_frame<R: AsyncRead + Unpin + ?Sized>(
+    stream: &mut R,
+    max_size: usize,
+) -> Result<Vec<u8>> {
+    let mut len_buf = [0u8; 4];
+    stream
+        .read_exact(&mut len_buf)
+        .await
+        .map_err(|e| TransportError::Io(e.to_string()))?;
+    let len = u32::from_le_bytes(len_buf) as usize;
+    if len > max_size {
+        return Err(TransportError::InvalidPacket(format!(
+            "message length {len} exceeds maximum {max_size}"
+        )));
+    }
+    let mut buf = vec![0u8; len];
+    stream
+        .read_exact(&mut buf)
+        .await
+        .map_err(|e| TransportError::Io(e.to_string()))?;
+    Ok(buf)
+}
+
+// ---------------------------------------------------------------------------
+// TCP/TLS
+// ---------------------------------------------------------------------------
+
+/// TLS 1.3 over TCP, for networks where UDP is blocked.
+#[derive(Clone)]
+pub struct TcpTlsTransport {
+    connector: TlsConnector,
+    proxy: ProxyRules,
+}
+
+impl TcpTlsTransport {
+    /// A direct TCP/TLS transport.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Tls`] if TLS configuration fails.
+    pub fn new() -> Result<Self> {
+        let provider = rustls::crypto::ring::default_provider();
+        let mut tls_config = rustls::ClientConfig::builder_with_provider(Arc::new(provider))
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(|e| TransportError::Tls(format!("client TLS version config failed: {e}")))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+            .with_no_client_auth();
+        tls_config.alpn_protocols = vec![ALPN_OCHRA_V5.to_vec()];
+
+        Ok(Self {
+            connector: TlsConnector::from(Arc::new(tls_config)),
+            proxy: ProxyRules::direct(),
+        })
+    }
+
+    /// Route every connection through `proxy`.
+    pub fn with_proxy(self, proxy: Proxy) -> Self {
+        self.with_proxy_rules(ProxyRules::all(proxy))
+    }
+
+    /// Pick the proxy for each connection by destination.
+    pub fn with_proxy_rules(mut self, rules: ProxyRules) -> Self {
+        self.proxy = rules;
+        self
+    }
+
+    /// The proxy rules in use.
+    pub fn proxy_rules(&self) -> &ProxyRules {
+        &self.proxy
+    }
+
+    /// Open a stream to `target`, which may be a hostname when a proxy is
+    /// configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Connection`] if the TCP connection or proxy
+    /// request fails, or [`TransportError::Tls`] if the TLS handshake fails.
+    pub async fn dial_target(&self, target: &ProxyTarget) -> Result<BoxedStream> {
+        let tcp = self.proxy.connect(target).await?;
+        tcp.set_nodelay(true)
+            .map_err(|e| TransportError::Io(e.to_string()))?;
+
+        let server_name = ServerName::try_from(TLS_SERVER_NAME)
+            .map_err(|e| TransportError::Tls(e.to_string()))?;
+        let tls = self
+            .connector
+            .connect(server_name, tcp)
+            .await
+            .map_err(|e| TransportError::Tls(format!("TLS handshake failed: {e}")))?;
+
+        tracing::debug!(
+            ?target,
+            proxied = self.proxy.route(target).is_some(),
+            "TCP/TLS stream established"
+        );
+        Ok(Box::new(tls))
+    }
+}
+
+impl PluggableTransport for TcpTlsTransport {
+    fn kind(&self) -> TransportKind {
+        TransportKind::TcpTls
+    }
+
+    async fn dial(&self, addr: SocketAddr) -> Result<BoxedStream> {
+        self.dial_target(&ProxyTarget::Addr(addr)).await
+    }
+}
+
+/// Accepts TCP/TLS streams from peers that cannot reach us over QUIC.
+pub struct TcpTlsListener {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+}
+
+impl TcpTlsListener {
+    /// Listen on `addr` with a fresh self-signed certificate.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Tls`] if TLS configuration fails, or
+    /// [`TransportError::Io`] if the socket cannot be bound.
+    pub async fn bind(addr: SocketAddr) -> Result<Self> {
+        let (cert_der, key_der) = generate_self_signed_cert()?;
+        let provider = rustls::crypto::ring::default_provider();
+        let mut tls_config = rustls::ServerConfig::builder_with_provider(Arc::new(provider))
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(|e| TransportError::Tls(format!("server TLS version config failed: {e}")))?
+            .with_no_client_auth()
+            .with_single_cert(vec![cert_der], key_der)
+            .map_err(|e| TransportError::Tls(format!("server TLS config failed: {e}")))?;
+        tls_config.alpn_protocols = vec![ALPN_OCHRA_V5.to_vec()];
+
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| TransportError::Io(e.to_string()))?;
+        tracing::info!(local_addr = ?listener.local_addr().ok(), "TCP/TLS listener started");
+
+        Ok(Self {
+            listener,
+            acceptor: TlsAcceptor::from(Arc::new(tls_config)),
+        })
+    }
+
+    /// The local address the listener is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Io`] if the address cannot be read.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener
+            .local_addr()
+            .map_err(|e| TransportError::Io(e.to_string()))
+    }
+
+    /// Accept the next stream and complete its TLS handshake.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Io`] if accepting fails, or
+    /// [`TransportError::Tls`] if the handshake fails.
+    pub async fn accept(&self) -> Result<(BoxedStream, SocketAddr)> {
+        let (tcp, remote) = self
+            .listener
+            .accept()
+            .await
+            .map_err(|e| TransportError::Io(e.to_string()))?;
+        tcp.set_nodelay(true)
+            .map_err(|e| TransportError::Io(e.to_string()))?;
+        let tls = self
+            .acceptor
+            .accept(tcp)
+            .await
+            .map_err(|e| TransportError::Tls(format!("TLS handshake failed: {e}")))?;
+        Ok((Box::new(tls), remote))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Fallback
+// ---------------------------------------------------------------------------
+
+/// Dials with a primary transport and falls back to a secondary one.
+///
+/// The transport that last succeeded for a peer is tried first on the next
+/// dial, so a peer behind a UDP block costs one timeout, not one per dial.
+pub struct FallbackDialer<P, S> {
+    primary: P,
+    secondary: S,
+    dial_timeout: Duration,
+    preferred: Mutex<HashMap<SocketAddr, TransportKind>>,
+}
+
+impl<P: PluggableTransport, S: PluggableTransport> FallbackDialer<P, S> {
+    /// Try `primary` first, then `secondary`.
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            dial_timeout: DEFAULT_DIAL_TIMEOUT,
+            preferred: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Override the per-attempt dial timeout.
+    pub fn with_dial_timeout(mut self, timeout: Duration) -> Self {
+        self.dial_timeout = timeout;
+        self
+    }
+
+    /// The transport that last succeeded for `addr`.
+    pub fn preferred(&self, addr: SocketAddr) -> Option<TransportKind> {
+        self.lock().get(&addr).copied()
+    }
+
+    /// Open a stream to `addr` over whichever transport works.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last transport's error if both fail.
+    pub async fn dial(&self, addr: SocketAddr) -> Result<(TransportKind, BoxedStream)> {
+        if self.preferred(addr) == Some(self.secondary.kind()) {
+            if let Ok(stream) = self.attempt(&self.secondary, addr).await {
+                return Ok((self.secondary.kind(), stream));
+            }
+            return self
+                .attempt(&self.primary, addr)
+                .await
+                .map(|stream| (self.primary.kind(), stream));
+        }
+
+        match self.attempt(&self.primary, addr).await {
+            Ok(stream) => Ok((self.primary.kind(), stream)),
+            Err(e) => {
+                tracing::debug!(%addr, error = %e, kind = ?self.primary.kind(), "dial failed, falling back");
+                self.attempt(&self.secondary, addr)
+                    .await
+                    .map(|stream| (self.secondary.kind(), stream))
+            }
+        }
+    }
+
+    async fn attempt<T: PluggableTransport>(
+        &self,
+        transport: &T,
+        addr: SocketAddr,
+    ) -> Result<BoxedStream> {
+        let stream = tokio::time::timeout(self.dial_timeout, transport.dial(addr))
+            .await
+            .map_err(|_| TransportError::Connection(format!("dial to {addr} timed out")))??;
+        self.lock().insert(addr, transport.kind());
+        Ok(stream)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SocketAddr, TransportKind>> {
+        // A poisoned map only holds dial preferences; keep using it.
+        self.preferred.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpStream;
+
+    /// A transport that always fails.
+    struct Blocked;
+
+    impl PluggableTransport for Blocked {
+        fn kind(&self) -> TransportKind {
+            TransportKind::Quic
+        }
+
+        async fn dial(&self, addr: SocketAddr) -> Result<BoxedStream> {
+            Err(TransportError::Connection(format!("{addr} unreachable")))
+        }
+    }
+
+    /// Accept one stream on `listener` and echo one frame back.
+    fn spawn_echo(listener: TcpTlsListener) {
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.expect("accept");
+            let frame = read_frame(&mut stream, 1024).await.expect("read frame");
+            write_frame(&mut stream, &frame).await.expect("write frame");
+        });
+    }
+
+    /// Minimal SOCKS5 server: accept one CONNECT and splice it to `upstream`.
+    async fn spawn_socks5(upstream: SocketAddr) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind proxy");
+        let addr = listener.local_addr().expect("proxy addr");
+        tokio::spawn(async move {
+            let (mut client, _) = listener.accept().await.expect("accept proxy");
+            let mut greeting = [0u8; 3];
+            client.read_exact(&mut greeting).await.expect("greeting");
+            client.write_all(&[0x05, 0x00]).await.expect("choice");
+            let mut head = [0u8; 5];
+            client.read_exact(&mut head).await.expect("request head");
+            // Domain-name request: [5, 1, 0, 3, len][host][port:2].
+            let mut rest = vec![0u8; usize::from(head[4]) + 2];
+            client.read_exact(&mut rest).await.expect("request tail");
+            let mut server = TcpStream::connect(upstream).await.expect("upstream");
+            client
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await
+                .expect("reply");
+            let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_round_trip() {
+        let listener = TcpTlsListener::bind("127.0.0.1:0".parse().expect("addr"))
+            .await
+            .expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        spawn_echo(listener);
+
+        let transport = TcpTlsTransport::new().expect("transport");
+        let mut stream = transport.dial(addr).await.expect("dial");
+        write_frame(&mut stream, b"ochra over tcp")
+            .await
+            .expect("write");
+        let echoed = read_frame(&mut stream, 1024).await.expect("read");
+        assert_eq!(echoed, b"ochra over tcp");
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_through_socks5() {
+        let listener = TcpTlsListener::bind("127.0.0.1:0".parse().expect("addr"))
+            .await
+            .expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        spawn_echo(listener);
+        let proxy = Proxy::socks5(spawn_socks5(addr).await);
+
+        let transport = TcpTlsTransport::new().expect("transport").with_proxy(proxy);
+        let target = ProxyTarget::Host("peer.onion".to_string(), addr.port());
+        let mut stream = transport.dial_target(&target).await.expect("dial");
+        write_frame(&mut stream, b"via proxy").await.expect("write");
+        assert_eq!(
+            read_frame(&mut stream, 1024).await.expect("read"),
+            b"via proxy"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fallback_remembers_working_transport() {
+        let listener = TcpTlsListener::bind("127.0.0.1:0".parse().expect("addr"))
+            .await
+            .expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        spawn_echo(listener);
+
+        let dialer = FallbackDialer::new(Blocked, TcpTlsTransport::new().expect("transport"))
+            .with_dial_timeout(Duration::from_secs(5));
+        assert_eq!(dialer.preferred(addr), None);
+
+        let (kind, mut stream) = dialer.dial(addr).await.expect("dial");
+        assert_eq!(kind, TransportKind::TcpTls);
+        assert_eq!(dialer.preferred(addr), Some(TransportKind::TcpTls));
+        write_frame(&mut stream, b"fallback").await.expect("write");
+        assert_eq!(
+            read_frame(&mut stream, 1024).await.expect("read"),
+            b"fallback"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_rejects_oversized() {
+        let (mut a, mut b) = tokio::io::duplex(64);
+        write_frame(&mut a, &[0u8; 32]).await.expect("write");
+        assert!(matches!(
+            read_frame(&mut b, 16).await,
+            Err(TransportError::InvalidPacket(_))
+        ));
+    }
+}
@@ -0,0 +1,370 @@
+//! Message priority lanes and per-lane bandwidth shares.
+//!
+//! Every message type maps to one of four [`Lane`]s, in priority order:
+//!
+//! | Lane | Traffic | QUIC stream priority |
+//! |---|---|---|
+//! | Control | capability exchange, ping, goodbye, gossip control | 30 |
+//! | Whisper | Whisper, rendezvous, MLS | 20 |
+//! | Dht | DHT, quorum, minting, gossip payloads, oracle, recovery | 10 |
+//! | Bulk | chunk transfer | 0 |
+//!
+//! Stream priority alone lets a busy higher lane starve the ones below
+//! it, so outgoing messages also pass through a [`LaneScheduler`]: a
+//! deficit round robin over the lanes, weighted by [`LaneShares`]. When
+//! every lane is backlogged each gets its share of the bytes sent; an
+//! idle lane's share goes to the others. Each lane's queue is bounded,
+//! and queue depths are exposed through [`LaneStats`] so callers can
+//! report congestion.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::messages::*;
+use crate::TransportError;
+
+/// Bytes of quantum per percentage point of share, per round.
+pub const QUANTUM_BYTES_PER_PERCENT: usize = 1500;
+
+/// Default per-lane queue bound (4 MiB).
+pub const DEFAULT_LANE_QUEUE_BYTES: usize = 4 * 1024 * 1024;
+
+/// A lane is reported congested once its queue is this many percent full.
+pub const CONGESTION_THRESHOLD_PERCENT: usize = 75;
+
+/// A traffic class with its own queue and bandwidth share.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lane {
+    /// Connection control.
+    Control,
+    /// Interactive messaging.
+    Whisper,
+    /// DHT and record traffic.
+    Dht,
+    /// Content chunk transfer.
+    Bulk,
+}
+
+impl Lane {
+    /// All lanes, highest priority first.
+    pub const ALL: [Lane; 4] = [Lane::Control, Lane::Whisper, Lane::Dht, Lane::Bulk];
+
+    /// The lane a message type travels in.
+    pub fn for_msg_type(msg_type: u16) -> Self {
+        match msg_type {
+            MSG_CAPABILITY_EXCHANGE..=MSG_UNSUPPORTED | MSG_GOSSIP_PRUNE | MSG_GOSSIP_GRAFT => {
+                Lane::Control
+            }
+            MSG_WHISPER_SEND..=MSG_WHISPER_MAILBOX_ACK
+            | MSG_ESTABLISH_INTRO..=MSG_RENDEZVOUS_TEARDOWN
+            | MSG_MLS_WELCOME..=MSG_MLS_KEY_PACKAGE => Lane::Whisper,
+            MSG_CHUNK_REQUEST..=MSG_SERVICE_RECEIPT_ACK_BATCH_ACK
+            | MSG_STATE_SYNC_REQUEST
+            | MSG_STATE_SYNC_RESPONSE => Lane::Bulk,
+            _ => Lane::Dht,
+        }
+    }
+
+    /// QUIC send-stream priority (higher is sent first).
+    pub fn stream_priority(self) -> i32 {
+        match self {
+            Lane::Control => 30,
+            Lane::Whisper => 20,
+            Lane::Dht => 10,
+            Lane::Bulk => 0,
+        }
+    }
+
+    /// Lowercase name, for metrics and errors.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Lane::Control => "control",
+            Lane::Whisper => "whisper",
+            Lane::Dht => "dht",
+            Lane::Bulk => "bulk",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Percentage of bandwidth each lane gets when all are backlogged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaneShares {
+    pub control: u8,
+    pub whisper: u8,
+    pub dht: u8,
+    pub bulk: u8,
+}
+
+impl Default for LaneShares {
+    fn default() -> Self {
+        Self {
+            control: 10,
+            whisper: 35,
+            dht: 25,
+            bulk: 30,
+        }
+    }
+}
+
+impl LaneShares {
+    /// Share of one lane.
+    pub fn of(&self, lane: Lane) -> u8 {
+        match lane {
+            Lane::Control => self.control,
+            Lane::Whisper => self.whisper,
+            Lane::Dht => self.dht,
+            Lane::Bulk => self.bulk,
+        }
+    }
+
+    /// Check that every lane has a share and the shares sum to 100.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Internal`] describing the problem.
+    pub fn validate(&self) -> Result<(), TransportError> {
+        if Lane::ALL.iter().any(|&lane| self.of(lane) == 0) {
+            return Err(TransportError::Internal(
+                "every lane needs a non-zero share".to_string(),
+            ));
+        }
+        let total: u32 = Lane::ALL.iter().map(|&lane| u32::from(self.of(lane))).sum();
+        if total != 100 {
+            return Err(TransportError::Internal(format!(
+                "lane shares sum to {total}, not 100"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Queue depth and throughput counters for one lane.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaneStats {
+    pub queued_messages: usize,
+    pub queued_bytes: usize,
+    pub sent_messages: u64,
+    pub sent_bytes: u64,
+    /// Messages refused because the queue was full.
+    pub dropped_messages: u64,
+}
+
+#[derive(Default)]
+struct LaneQueue {
+    queue: VecDeque<(u16, Vec<u8>)>,
+    deficit: usize,
+    /// Whether the lane has received its quantum for the current visit.
+    credited: bool,
+    stats: LaneStats,
+}
+
+/// Weighted deficit round robin over the four lanes.
+pub struct LaneScheduler {
+    shares: LaneShares,
+    max_queue_bytes: usize,
+    lanes: [LaneQueue; 4],
+    /// Lane the scheduler is currently visiting.
+    cursor: usize,
+}
+
+impl LaneScheduler {
+    /// Create a scheduler.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Internal`] if `shares` is invalid.
+    pub fn new(shares: LaneShares, max_queue_bytes: usize) -> Result<Self, TransportError> {
+        shares.validate()?;
+        Ok(Self {
+            shares,
+            max_queue_bytes,
+            lanes: Default::default(),
+            cursor: 0,
+        })
+    }
+
+    /// Queue an encoded message for sending. Returns its lane.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::LaneFull`] if the lane's queue cannot take
+    /// `data`; the message is dropped and counted.
+    pub fn enqueue(&mut self, msg_type: u16, data: Vec<u8>) -> Result<Lane, TransportError> {
+        let lane = Lane::for_msg_type(msg_type);
+        let max = self.max_queue_bytes;
+        let q = &mut self.lanes[lane.index()];
+        if q.stats.queued_bytes + data.len() > max {
+            q.stats.dropped_messages += 1;
+            return Err(TransportError::LaneFull(lane.as_str()));
+        }
+        q.stats.queued_messages += 1;
+        q.stats.queued_bytes += data.len();
+        q.queue.push_back((msg_type, data));
+        Ok(lane)
+    }
+
+    /// Next message to send, as `(lane, msg_type, data)`.
+    pub fn dequeue(&mut self) -> Option<(Lane, u16, Vec<u8>)> {
+        if self.lanes.iter().all(|q| q.queue.is_empty()) {
+            return None;
+        }
+        loop {
+            let lane = Lane::ALL[self.cursor];
+            let quantum = usize::from(self.shares.of(lane)) * QUANTUM_BYTES_PER_PERCENT;
+            let q = &mut self.lanes[lane.index()];
+            let Some(front_len) = q.queue.front().map(|(_, data)| data.len()) else {
+                // An idle lane does not bank credit
+                q.deficit = 0;
+                q.credited = false;
+                self.cursor = (self.cursor + 1) % Lane::ALL.len();
+                continue;
+            };
+            if !q.credited {
+                q.deficit += quantum;
+                q.credited = true;
+            }
+            if front_len <= q.deficit {
+                let (msg_type, data) = q.queue.pop_front()?;
+                q.deficit -= data.len();
+                q.stats.queued_messages -= 1;
+                q.stats.queued_bytes -= data.len();
+                q.stats.sent_messages += 1;
+                q.stats.sent_bytes += data.len() as u64;
+                return Some((lane, msg_type, data));
+            }
+            q.credited = false;
+            self.cursor = (self.cursor + 1) % Lane::ALL.len();
+        }
+    }
+
+    /// Counters for one lane.
+    pub fn stats(&self, lane: Lane) -> LaneStats {
+        self.lanes[lane.index()].stats
+    }
+
+    /// Whether a lane's queue is past [`CONGESTION_THRESHOLD_PERCENT`].
+    pub fn is_congested(&self, lane: Lane) -> bool {
+        self.stats(lane).queued_bytes * 100 >= self.max_queue_bytes * CONGESTION_THRESHOLD_PERCENT
+    }
+
+    /// Total bytes waiting across all lanes.
+    pub fn queued_bytes(&self) -> usize {
+        self.lanes.iter().map(|q| q.stats.queued_bytes).sum()
+    }
+}
+
+impl Default for LaneScheduler {
+    fn default() -> Self {
+        Self {
+            shares: LaneShares::default(),
+            max_queue_bytes: DEFAULT_LANE_QUEUE_BYTES,
+            lanes: Default::default(),
+            cursor: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lane_mapping_and_priorities() {
+        assert_eq!(Lane::for_msg_type(MSG_PING), Lane::Control);
+        assert_eq!(Lane::for_msg_type(MSG_GOSSIP_GRAFT), Lane::Control);
+        assert_eq!(Lane::for_msg_type(MSG_WHISPER_SEND), Lane::Whisper);
+        assert_eq!(Lane::for_msg_type(MSG_RENDEZVOUS_RELAY), Lane::Whisper);
+        assert_eq!(Lane::for_msg_type(MSG_DHT_PUT), Lane::Dht);
+        assert_eq!(Lane::for_msg_type(MSG_GOSSIP_PUBLISH), Lane::Dht);
+        assert_eq!(Lane::for_msg_type(MSG_CHUNK_RESPONSE), Lane::Bulk);
+        let priorities: Vec<i32> = Lane::ALL.iter().map(|l| l.stream_priority()).collect();
+        assert!(priorities.windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn test_whisper_not_starved_by_bulk() {
+        let mut sched = LaneScheduler::default();
+        for _ in 0..100 {
+            sched
+                .enqueue(MSG_CHUNK_RESPONSE, vec![0; 16 * 1024])
+                .expect("enqueue");
+        }
+        sched
+            .enqueue(MSG_WHISPER_SEND, vec![0; 512])
+            .expect("enqueue");
+
+        // The Whisper message goes out before most of the bulk backlog
+        let position = (0..)
+            .map_while(|_| sched.dequeue())
+            .position(|(lane, _, _)| lane == Lane::Whisper)
+            .expect("whisper sent");
+        assert!(position < 5, "whisper sent at position {position}");
+    }
+
+    #[test]
+    fn test_backlogged_lanes_get_their_shares() {
+        let mut sched = LaneScheduler::default();
+        for _ in 0..2000 {
+            sched
+                .enqueue(MSG_WHISPER_SEND, vec![0; 1000])
+                .expect("enqueue");
+            sched.enqueue(MSG_DHT_GET, vec![0; 1000]).expect("enqueue");
+            sched
+                .enqueue(MSG_CHUNK_RESPONSE, vec![0; 1000])
+                .expect("enqueue");
+        }
+        // Ten full rounds of 135 kB
+        let mut sent = [0usize; 4];
+        for _ in 0..1350 {
+            let (lane, _, data) = sched.dequeue().expect("backlogged");
+            sent[lane.index()] += data.len();
+        }
+        // Control is idle, so 35:25:30 split the link
+        let total: usize = sent.iter().sum();
+        let whisper = sent[Lane::Whisper.index()] * 90 / total;
+        let dht = sent[Lane::Dht.index()] * 90 / total;
+        let bulk = sent[Lane::Bulk.index()] * 90 / total;
+        assert!((34..=36).contains(&whisper), "whisper {whisper}");
+        assert!((24..=26).contains(&dht), "dht {dht}");
+        assert!((29..=31).contains(&bulk), "bulk {bulk}");
+    }
+
+    #[test]
+    fn test_queue_bound_and_metrics() {
+        let mut sched = LaneScheduler::new(LaneShares::default(), 4096).expect("scheduler");
+        sched
+            .enqueue(MSG_CHUNK_RESPONSE, vec![0; 2000])
+            .expect("enqueue");
+        assert!(!sched.is_congested(Lane::Bulk));
+        sched
+            .enqueue(MSG_CHUNK_RESPONSE, vec![0; 1500])
+            .expect("enqueue");
+        assert!(sched.is_congested(Lane::Bulk));
+        assert!(matches!(
+            sched.enqueue(MSG_CHUNK_RESPONSE, vec![0; 1000]),
+            Err(TransportError::LaneFull("bulk"))
+        ));
+        let stats = sched.stats(Lane::Bulk);
+        assert_eq!(stats.queued_messages, 2);
+        assert_eq!(stats.dropped_messages, 1);
+
+        sched.dequeue().expect("dequeue");
+        sched.dequeue().expect("dequeue");
+        let stats = sched.stats(Lane::Bulk);
+        assert_eq!(stats.queued_bytes, 0);
+        assert_eq!(stats.sent_bytes, 3500);
+        assert!(sched.dequeue().is_none());
+
+        let bad = LaneShares {
+            control: 0,
+            ..LaneShares::default()
+        };
+        assert!(LaneScheduler::new(bad, 4096).is_err());
+    }
+}
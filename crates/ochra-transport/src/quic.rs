@@ -14,14 +14,39 @@
 //! 2. The client connects to the server using a client endpoint.
 //! 3. After QUIC handshake, both sides exchange [`CapabilityExchange`](crate::messages::CapabilityExchange)
 //!    messages on a bidirectional stream.
-//! 4. Subsequent messages use additional bidirectional streams.
+//! 4. Subsequent messages use additional bidirectional streams, opened with
+//!    [`QuicNode::open_lane_stream`] so each carries its
+//!    [`Lane`](crate::qos::Lane)'s stream priority.
+//!
+//! ## Migration
+//!
+//! Connections survive a change of local address: [`QuicNode::rebind`]
+//! moves the endpoint to a new socket and [`QuicNode::validate_path`]
+//! confirms each peer still answers. Incoming migrations are accepted. See
+//! [`migration`](crate::migration).
+//!
+//! ## Resumption
+//!
+//! Connections to a known node ([`QuicNode::connect_0rtt`]) store TLS
+//! session tickets under its node ID and, on reconnect, may carry an
+//! idempotent first request in 0-RTT data ([`QuicNode::send_early`]).
+//! See [`resumption`](crate::resumption) for the replay restrictions.
 
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use quinn::{ClientConfig, Connection, Endpoint, Incoming, RecvStream, SendStream, ServerConfig};
+use quinn::{
+    ClientConfig, Connection, Endpoint, Incoming, RecvStream, SendStream, ServerConfig,
+    ZeroRttAccepted,
+};
 use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
 
+use crate::dualstack;
+use crate::messages::{Ping, Pong, TypedMessage};
+use crate::migration::PATH_VALIDATION_TIMEOUT_MS;
+use crate::qos::Lane;
+use crate::resumption::{self, SessionTicketStore};
+use crate::wire::{ProtocolMessage, MAX_MESSAGE_SIZE};
 use crate::TransportError;
 
 /// ALPN protocol identifier for Ochra protocol version 5.
@@ -44,6 +69,16 @@ pub struct QuicConfig {
     pub max_bi_streams: u32,
 }
 
+impl QuicConfig {
+    /// Listen on `port` for both IPv4 and IPv6 with one dual-stack socket.
+    pub fn dual_stack(port: u16) -> Self {
+        Self {
+            bind_addr: SocketAddr::from((std::net::Ipv6Addr::UNSPECIFIED, port)),
+            ..Self::default()
+        }
+    }
+}
+
 impl Default for QuicConfig {
     fn default() -> Self {
         Self {
@@ -61,13 +96,18 @@ impl Default for QuicConfig {
 pub struct QuicNode {
     /// The underlying Quinn endpoint.
     endpoint: Endpoint,
-    /// The local address this node is bound to.
-    local_addr: SocketAddr,
+    /// The local address this node is bound to; changes on rebind.
+    local_addr: std::sync::Mutex<SocketAddr>,
+    /// TLS session tickets for resuming connections to known nodes.
+    tickets: Arc<SessionTicketStore>,
 }
 
 impl QuicNode {
     /// Create a new QUIC node bound to the configured address.
     ///
+    /// Binding `[::]` (see [`QuicConfig::dual_stack`]) accepts both IPv4 and
+    /// IPv6 peers on one socket.
+    ///
     /// Generates a self-signed TLS certificate for the server side.
     /// The certificate is not used for identity verification in v1; PIK exchange
     /// handles authentication after the QUIC connection is established.
@@ -79,10 +119,17 @@ impl QuicNode {
     pub fn new(config: QuicConfig) -> Result<Self, TransportError> {
         let (server_config, _cert_der) =
             build_server_config(config.idle_timeout_ms, config.max_bi_streams)?;
-        let client_config = build_client_config()?;
-
-        let mut endpoint = Endpoint::server(server_config, config.bind_addr)
-            .map_err(|e| TransportError::Io(e.to_string()))?;
+        let tickets = SessionTicketStore::new();
+        let client_config = build_client_config(tickets.clone())?;
+
+        let socket = dualstack::bind_udp(config.bind_addr)?;
+        let mut endpoint = Endpoint::new(
+            quinn::EndpointConfig::default(),
+            Some(server_config),
+            socket,
+            Arc::new(quinn::TokioRuntime),
+        )
+        .map_err(|e| TransportError::Io(e.to_string()))?;
 
         endpoint.set_default_client_config(client_config);
 
@@ -94,13 +141,81 @@ impl QuicNode {
 
         Ok(Self {
             endpoint,
-            local_addr,
+            local_addr: std::sync::Mutex::new(local_addr),
+            tickets,
         })
     }
 
     /// Get the local socket address this node is bound to.
     pub fn local_addr(&self) -> SocketAddr {
-        self.local_addr
+        // A poisoned lock still holds a bound address.
+        *self.local_addr.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// TLS session tickets held for known nodes.
+    pub fn session_tickets(&self) -> &Arc<SessionTicketStore> {
+        &self.tickets
+    }
+
+    /// Move the endpoint to a new UDP socket bound to `addr`, migrating
+    /// every open connection to it.
+    ///
+    /// Call this when the host's network changes. The old socket is kept
+    /// for packets already in flight. Peers validate the new path before
+    /// using it; confirm each connection with
+    /// [`validate_path`](Self::validate_path). A fixed port is still held by
+    /// the old socket, so rebinding usually takes port 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Io`] if the socket cannot be bound. The
+    /// endpoint keeps its old socket in that case.
+    pub fn rebind(&self, addr: SocketAddr) -> Result<SocketAddr, TransportError> {
+        let socket = dualstack::bind_udp(addr)?;
+        self.endpoint
+            .rebind(socket)
+            .map_err(|e| TransportError::Io(e.to_string()))?;
+        let local_addr = self
+            .endpoint
+            .local_addr()
+            .map_err(|e| TransportError::Io(e.to_string()))?;
+        *self.local_addr.lock().unwrap_or_else(|e| e.into_inner()) = local_addr;
+        tracing::info!(%local_addr, "QUIC endpoint rebound");
+        Ok(local_addr)
+    }
+
+    /// Confirm a connection still works after a migration by exchanging a
+    /// ping on the control lane. Returns the round-trip time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Connection`] if no matching pong arrives
+    /// within [`PATH_VALIDATION_TIMEOUT_MS`], or any error from opening the
+    /// stream or exchanging the messages.
+    pub async fn validate_path(
+        connection: &Connection,
+    ) -> Result<std::time::Duration, TransportError> {
+        let started = std::time::Instant::now();
+        let nonce: [u8; 8] = rand::random();
+        let exchange = async {
+            let (mut send, mut recv) = Self::open_lane_stream(connection, Lane::Control).await?;
+            let ping = ProtocolMessage::from_typed(&TypedMessage::Ping(Ping { nonce }))?;
+            Self::send_message(&mut send, &ping.to_bytes()?).await?;
+            let (_, reply) = Self::recv_protocol_message(&mut recv).await?;
+            match reply {
+                TypedMessage::Pong(Pong { nonce: echoed }) if echoed == nonce => Ok(()),
+                _ => Err(TransportError::ProtocolViolation(
+                    "path probe answered without matching pong".to_string(),
+                )),
+            }
+        };
+        tokio::time::timeout(
+            std::time::Duration::from_millis(PATH_VALIDATION_TIMEOUT_MS),
+            exchange,
+        )
+        .await
+        .map_err(|_| TransportError::Connection("path validation timed out".to_string()))??;
+        Ok(started.elapsed())
     }
 
     /// Accept the next incoming QUIC connection.
@@ -140,6 +255,89 @@ impl QuicNode {
         Ok(connection)
     }
 
+    /// Connect to the known node `node_id`, resuming in 0-RTT when a
+    /// session ticket is held for it.
+    ///
+    /// Returns the connection and, for a 0-RTT connection, a future that
+    /// resolves once the handshake completes: `true` if the server accepted
+    /// the early data, `false` if it was rejected and must be resent. Only
+    /// [`send_early`](Self::send_early) may be used before then. Without a
+    /// ticket this waits for the full handshake and returns `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Connection`] if the connection cannot be established.
+    pub async fn connect_0rtt(
+        &self,
+        addr: SocketAddr,
+        node_id: &[u8; 32],
+    ) -> Result<(Connection, Option<ZeroRttAccepted>), TransportError> {
+        let connecting = self
+            .endpoint
+            .connect(addr, &resumption::server_name_for(node_id))
+            .map_err(|e| TransportError::Connection(e.to_string()))?;
+        match connecting.into_0rtt() {
+            Ok((connection, accepted)) => {
+                tracing::debug!(remote = %addr, "QUIC connection resumed in 0-RTT");
+                Ok((connection, Some(accepted)))
+            }
+            Err(connecting) => {
+                let connection = connecting
+                    .await
+                    .map_err(|e| TransportError::Connection(e.to_string()))?;
+                Ok((connection, None))
+            }
+        }
+    }
+
+    /// Accept an incoming connection without waiting for the handshake to
+    /// finish, so 0-RTT requests can be answered straight away.
+    ///
+    /// Streams from such a connection report
+    /// [`RecvStream::is_0rtt`]; every message read from one must pass an
+    /// [`EarlyDataFilter`](crate::resumption::EarlyDataFilter) before it is
+    /// dispatched.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Connection`] if the connection is refused.
+    pub fn accept_0rtt(incoming: Incoming) -> Result<Connection, TransportError> {
+        let connecting = incoming
+            .accept()
+            .map_err(|e| TransportError::Connection(e.to_string()))?;
+        match connecting.into_0rtt() {
+            Ok((connection, _)) => Ok(connection),
+            Err(_) => Err(TransportError::Connection(
+                "incoming connection refused 0.5-RTT".to_string(),
+            )),
+        }
+    }
+
+    /// Send an idempotent request on a new stream, allowed before the
+    /// handshake of a 0-RTT connection completes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ProtocolViolation`] if the message type is
+    /// not in [`ZERO_RTT_MESSAGE_TYPES`](crate::resumption::ZERO_RTT_MESSAGE_TYPES),
+    /// or any error from opening the stream or sending.
+    pub async fn send_early(
+        connection: &Connection,
+        msg: &TypedMessage,
+    ) -> Result<(SendStream, RecvStream), TransportError> {
+        let msg_type = msg.msg_type();
+        if !resumption::is_zero_rtt_safe(msg_type) {
+            return Err(TransportError::ProtocolViolation(format!(
+                "message type 0x{msg_type:04x} not allowed in 0-RTT data"
+            )));
+        }
+        let (mut send, recv) =
+            Self::open_lane_stream(connection, Lane::for_msg_type(msg_type)).await?;
+        let bytes = ProtocolMessage::from_typed(msg)?.to_bytes()?;
+        Self::send_message(&mut send, &bytes).await?;
+        Ok((send, recv))
+    }
+
     /// Open a new bidirectional stream on an existing connection.
     ///
     /// # Errors
@@ -154,6 +352,23 @@ impl QuicNode {
             .map_err(|e| TransportError::Connection(e.to_string()))
     }
 
+    /// Open a bidirectional stream whose send side carries `lane`'s
+    /// priority, so higher lanes are flushed first when the connection is
+    /// congested.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Connection`] if the stream cannot be opened.
+    pub async fn open_lane_stream(
+        connection: &Connection,
+        lane: Lane,
+    ) -> Result<(SendStream, RecvStream), TransportError> {
+        let (send, recv) = Self::open_bi(connection).await?;
+        send.set_priority(lane.stream_priority())
+            .map_err(|e| TransportError::Connection(e.to_string()))?;
+        Ok((send, recv))
+    }
+
     /// Accept the next bidirectional stream on an existing connection.
     ///
     /// # Errors
@@ -223,6 +438,25 @@ impl QuicNode {
         Ok(buf)
     }
 
+    /// Receive a protocol message and strictly decode its payload.
+    ///
+    /// The envelope and payload are checked against the strict decoding
+    /// limits before the caller can dispatch on the returned message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::Io`] or [`TransportError::InvalidPacket`]
+    /// as for [`recv_message`](Self::recv_message), and
+    /// [`TransportError::ProtocolViolation`] if decoding is rejected.
+    pub async fn recv_protocol_message(
+        stream: &mut RecvStream,
+    ) -> Result<(ProtocolMessage, TypedMessage), TransportError> {
+        let bytes = Self::recv_message(stream, MAX_MESSAGE_SIZE).await?;
+        let msg = ProtocolMessage::from_bytes_strict(&bytes)?;
+        let typed = msg.decode_payload_strict()?;
+        Ok((msg, typed))
+    }
+
     /// Gracefully close the endpoint.
     ///
     /// All active connections will be closed with the given error code and reason.
@@ -248,7 +482,7 @@ impl QuicNode {
 /// # Errors
 ///
 /// Returns [`TransportError::Tls`] if certificate generation fails.
-fn generate_self_signed_cert(
+pub(crate) fn generate_self_signed_cert(
 ) -> Result<(CertificateDer<'static>, PrivateKeyDer<'static>), TransportError> {
     // Generate an Ed25519 keypair; the algorithm is determined by the KeyPair.
     let key_pair = rcgen::KeyPair::generate_for(&rcgen::PKCS_ED25519)
@@ -287,6 +521,9 @@ fn build_server_config(
         .map_err(|e| TransportError::Tls(format!("server TLS config failed: {e}")))?;
 
     tls_config.alpn_protocols = vec![ALPN_OCHRA_V5.to_vec()];
+    // QUIC requires exactly 0 or u32::MAX. The default stateful session
+    // cache makes each ticket single-use, so replayed 0-RTT is refused.
+    tls_config.max_early_data_size = u32::MAX;
 
     let mut transport = quinn::TransportConfig::default();
     transport.max_idle_timeout(Some(
@@ -300,6 +537,8 @@ fn build_server_config(
             .map_err(|e| TransportError::Tls(format!("QUIC server crypto config failed: {e}")))?,
     ));
     server_config.transport_config(Arc::new(transport));
+    // Peers moving between networks keep their connections.
+    server_config.migration(true);
 
     Ok((server_config, cert_der))
 }
@@ -312,7 +551,7 @@ fn build_server_config(
 /// # Errors
 ///
 /// Returns [`TransportError::Tls`] if TLS configuration fails.
-fn build_client_config() -> Result<ClientConfig, TransportError> {
+fn build_client_config(tickets: Arc<SessionTicketStore>) -> Result<ClientConfig, TransportError> {
     let provider = rustls::crypto::ring::default_provider();
     let mut tls_config = rustls::ClientConfig::builder_with_provider(Arc::new(provider))
         .with_protocol_versions(&[&rustls::version::TLS13])
@@ -322,6 +561,8 @@ fn build_client_config() -> Result<ClientConfig, TransportError> {
         .with_no_client_auth();
 
     tls_config.alpn_protocols = vec![ALPN_OCHRA_V5.to_vec()];
+    tls_config.resumption = rustls::client::Resumption::store(tickets);
+    tls_config.enable_early_data = true;
 
     let client_config = ClientConfig::new(Arc::new(
         quinn::crypto::rustls::QuicClientConfig::try_from(tls_config)
@@ -337,7 +578,7 @@ fn build_client_config() -> Result<ClientConfig, TransportError> {
 /// node authentication is performed via PIK exchange after the QUIC
 /// connection is established. TLS is used solely for transport encryption.
 #[derive(Debug)]
-struct SkipServerVerification;
+pub(crate) struct SkipServerVerification;
 
 impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
     fn verify_server_cert(
@@ -409,6 +650,172 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_dual_stack_accepts_both_families() {
+        let server = QuicNode::new(QuicConfig::dual_stack(0)).expect("dual-stack node");
+        let port = server.local_addr().port();
+        let accept = tokio::spawn(async move {
+            for _ in 0..2 {
+                let incoming = server.accept().await.expect("incoming");
+                incoming.await.expect("handshake");
+            }
+        });
+
+        for client_bind in ["127.0.0.1:0", "[::1]:0"] {
+            let client = QuicNode::new(QuicConfig {
+                bind_addr: client_bind.parse().expect("addr"),
+                ..QuicConfig::default()
+            })
+            .expect("client node");
+            let target = if client_bind.starts_with('[') {
+                SocketAddr::from((std::net::Ipv6Addr::LOCALHOST, port))
+            } else {
+                SocketAddr::from(([127, 0, 0, 1], port))
+            };
+            client.connect(target, "ochra-node").await.expect("connect");
+        }
+        accept.await.expect("accept task");
+    }
+
+    #[tokio::test]
+    async fn test_rebind_migrates_open_connection() {
+        let server = QuicNode::new(QuicConfig {
+            bind_addr: "127.0.0.1:0".parse().expect("addr"),
+            ..QuicConfig::default()
+        })
+        .expect("server node");
+        let server_addr = server.local_addr();
+        let (remote_tx, remote_rx) = tokio::sync::oneshot::channel();
+        let responder = tokio::spawn(async move {
+            let connection = server
+                .accept()
+                .await
+                .expect("incoming")
+                .await
+                .expect("handshake");
+            let mut remotes = vec![connection.remote_address()];
+            for _ in 0..2 {
+                let (mut send, mut recv) = QuicNode::accept_bi(&connection).await.expect("stream");
+                let (_, msg) = QuicNode::recv_protocol_message(&mut recv)
+                    .await
+                    .expect("ping");
+                let TypedMessage::Ping(Ping { nonce }) = msg else {
+                    return;
+                };
+                let pong =
+                    ProtocolMessage::from_typed(&TypedMessage::Pong(Pong { nonce })).expect("pong");
+                QuicNode::send_message(&mut send, &pong.to_bytes().expect("encode"))
+                    .await
+                    .expect("send pong");
+                send.finish().expect("finish");
+                remotes.push(connection.remote_address());
+            }
+            let _ = remote_tx.send(remotes);
+            connection.closed().await;
+        });
+
+        let client = QuicNode::new(QuicConfig {
+            bind_addr: "127.0.0.1:0".parse().expect("addr"),
+            ..QuicConfig::default()
+        })
+        .expect("client node");
+        let connection = client
+            .connect(server_addr, "ochra-node")
+            .await
+            .expect("connect");
+        QuicNode::validate_path(&connection)
+            .await
+            .expect("first probe");
+
+        let old_addr = client.local_addr();
+        let new_addr = client
+            .rebind("127.0.0.1:0".parse().expect("addr"))
+            .expect("rebind");
+        assert_ne!(old_addr, new_addr);
+        QuicNode::validate_path(&connection)
+            .await
+            .expect("probe after rebind");
+
+        let remotes = remote_rx.await.expect("remotes");
+        assert_eq!(remotes[0], old_addr);
+        assert_eq!(remotes[2], new_addr);
+        connection.close(0u32.into(), b"done");
+        responder.abort();
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_resumes_in_0rtt() {
+        let server = QuicNode::new(QuicConfig {
+            bind_addr: "127.0.0.1:0".parse().expect("addr"),
+            ..QuicConfig::default()
+        })
+        .expect("server node");
+        let server_addr = server.local_addr();
+        let (early_tx, mut early_rx) = tokio::sync::mpsc::channel(2);
+        let responder = tokio::spawn(async move {
+            while let Some(incoming) = server.accept().await {
+                let connection = QuicNode::accept_0rtt(incoming).expect("accept");
+                let Ok((mut send, mut recv)) = QuicNode::accept_bi(&connection).await else {
+                    continue;
+                };
+                let is_0rtt = recv.is_0rtt();
+                let (_, msg) = QuicNode::recv_protocol_message(&mut recv)
+                    .await
+                    .expect("ping");
+                let TypedMessage::Ping(Ping { nonce }) = msg else {
+                    return;
+                };
+                let pong =
+                    ProtocolMessage::from_typed(&TypedMessage::Pong(Pong { nonce })).expect("pong");
+                QuicNode::send_message(&mut send, &pong.to_bytes().expect("encode"))
+                    .await
+                    .expect("send pong");
+                send.finish().expect("finish");
+                let _ = early_tx.send(is_0rtt).await;
+                tokio::spawn(async move { connection.closed().await });
+            }
+        });
+
+        let client = QuicNode::new(QuicConfig {
+            bind_addr: "127.0.0.1:0".parse().expect("addr"),
+            ..QuicConfig::default()
+        })
+        .expect("client node");
+        let node_id = [7u8; 32];
+        let (first, accepted) = client
+            .connect_0rtt(server_addr, &node_id)
+            .await
+            .expect("first connect");
+        assert!(accepted.is_none());
+        QuicNode::validate_path(&first).await.expect("first ping");
+        assert_eq!(early_rx.recv().await, Some(false));
+        assert!(client.session_tickets().has_ticket(&node_id));
+        first.close(0u32.into(), b"done");
+
+        let (second, accepted) = client
+            .connect_0rtt(server_addr, &node_id)
+            .await
+            .expect("reconnect");
+        let accepted = accepted.expect("0-RTT attempted");
+        let goodbye = TypedMessage::Goodbye(crate::messages::Goodbye {
+            reason: 0,
+            detail: None,
+        });
+        assert!(QuicNode::send_early(&second, &goodbye).await.is_err());
+        let (_send, mut recv) =
+            QuicNode::send_early(&second, &TypedMessage::Ping(Ping { nonce: [9; 8] }))
+                .await
+                .expect("early ping");
+        let (_, reply) = QuicNode::recv_protocol_message(&mut recv)
+            .await
+            .expect("pong");
+        assert!(matches!(reply, TypedMessage::Pong(Pong { nonce }) if nonce == [9; 8]));
+        assert!(accepted.await);
+        assert_eq!(early_rx.recv().await, Some(true));
+        second.close(0u32.into(), b"done");
+        responder.abort();
+    }
+
     #[test]
     fn test_build_server_config_succeeds() {
         let result = build_server_config(DEFAULT_IDLE_TIMEOUT_MS, DEFAULT_MAX_BI_STREAMS);
@@ -417,7 +824,7 @@ mod tests {
 
     #[test]
     fn test_build_client_config_succeeds() {
-        let result = build_client_config();
+        let result = build_client_config(SessionTicketStore::new());
         assert!(result.is_ok());
     }
 }
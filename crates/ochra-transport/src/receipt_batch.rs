@@ -0,0 +1,405 @@
+//! Batched service receipt acknowledgements (Section 14.7).
+//!
+//! A requester acknowledges every chunk it is served, but sending each
+//! [`ServiceReceiptAck`] in its own Sphinx packet would spend a whole
+//! 8 KB packet on ~110 bytes. The [`AckBatcher`] queues acknowledgements
+//! per server and packs as many as fit into one
+//! [`ServiceReceiptAckBatch`] of at most [`MAX_PLAINTEXT_SIZE`] encoded
+//! bytes. A queue is flushed as soon as it fills a payload, or once its
+//! oldest acknowledgement has waited [`FlushPolicy::max_delay_secs`].
+//!
+//! A batch stays in flight until the server answers with a
+//! [`ServiceReceiptAckBatchAck`]. Only the positions it lists as settled
+//! are released; the rest go back to the front of the queue, as does a
+//! whole batch that is not answered within
+//! [`FlushPolicy::retry_after_secs`]. Each acknowledgement carries a
+//! caller-assigned id so the caller can keep a durable copy and delete it
+//! once settled; after a crash, [`AckBatcher::push`] the stored copies
+//! again and they are resent.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::messages::{
+    ServiceReceiptAck, ServiceReceiptAckBatch, ServiceReceiptAckBatchAck, TypedMessage,
+};
+use crate::sphinx::MAX_PLAINTEXT_SIZE;
+use crate::TransportError;
+
+/// Default seconds an acknowledgement may wait for a fuller batch.
+pub const DEFAULT_MAX_DELAY_SECS: u64 = 60;
+
+/// Default seconds before an unanswered batch is resent.
+pub const DEFAULT_RETRY_AFTER_SECS: u64 = 300;
+
+/// When queued acknowledgements are flushed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlushPolicy {
+    /// Longest an acknowledgement waits before its queue is flushed.
+    pub max_delay_secs: u64,
+    /// How long a sent batch waits for its reply before being resent.
+    pub retry_after_secs: u64,
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        Self {
+            max_delay_secs: DEFAULT_MAX_DELAY_SECS,
+            retry_after_secs: DEFAULT_RETRY_AFTER_SECS,
+        }
+    }
+}
+
+/// An acknowledgement waiting to be sent.
+#[derive(Clone, Debug)]
+struct Queued {
+    id: u64,
+    ack: ServiceReceiptAck,
+    encoded_len: usize,
+    queued_at: u64,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    server: [u8; 32],
+    acks: Vec<Queued>,
+    sent_at: u64,
+}
+
+/// A batch ready to be sealed into a Sphinx packet for `server`.
+#[derive(Clone, Debug)]
+pub struct OutgoingBatch {
+    /// Node the acknowledgements are for.
+    pub server: [u8; 32],
+    /// The message to send.
+    pub batch: ServiceReceiptAckBatch,
+    /// Caller ids of the acknowledgements, parallel to `batch.acks`.
+    pub ids: Vec<u64>,
+}
+
+/// Per-server queues of acknowledgements and the batches awaiting replies.
+#[derive(Debug)]
+pub struct AckBatcher {
+    policy: FlushPolicy,
+    queues: BTreeMap<[u8; 32], VecDeque<Queued>>,
+    in_flight: HashMap<u64, InFlight>,
+    next_batch_id: u64,
+    /// Encoded size of a batch with no acknowledgements.
+    empty_len: usize,
+}
+
+impl AckBatcher {
+    /// Create an empty batcher.
+    pub fn new(policy: FlushPolicy) -> Self {
+        let empty = TypedMessage::ServiceReceiptAckBatch(ServiceReceiptAckBatch {
+            batch_id: u64::MAX,
+            acks: Vec::new(),
+        });
+        Self {
+            policy,
+            queues: BTreeMap::new(),
+            in_flight: HashMap::new(),
+            next_batch_id: 0,
+            empty_len: empty.to_cbor().map_or(1, |b| b.len()),
+        }
+    }
+
+    /// Queue an acknowledgement for `server` under the caller's `id`.
+    ///
+    /// Returns the batches to send if the queue now fills a payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ProtocolViolation`] if the acknowledgement
+    /// alone does not fit in a Sphinx payload.
+    pub fn push(
+        &mut self,
+        server: [u8; 32],
+        id: u64,
+        ack: ServiceReceiptAck,
+        now: u64,
+    ) -> Result<Vec<OutgoingBatch>, TransportError> {
+        let encoded_len = crate::cbor::to_vec(&ack)?.len();
+        if self.batch_len(1, encoded_len) > MAX_PLAINTEXT_SIZE {
+            return Err(TransportError::ProtocolViolation(format!(
+                "receipt ack of {encoded_len} bytes does not fit a Sphinx payload"
+            )));
+        }
+        self.queues.entry(server).or_default().push_back(Queued {
+            id,
+            ack,
+            encoded_len,
+            queued_at: now,
+        });
+        Ok(self.drain(server, now, false))
+    }
+
+    /// Batches due now: queues whose oldest acknowledgement has waited
+    /// `max_delay_secs`, including unanswered batches put back after
+    /// `retry_after_secs`.
+    pub fn poll(&mut self, now: u64) -> Vec<OutgoingBatch> {
+        let expired: Vec<u64> = self
+            .in_flight
+            .iter()
+            .filter(|(_, f)| now.saturating_sub(f.sent_at) >= self.policy.retry_after_secs)
+            .map(|(id, _)| *id)
+            .collect();
+        for batch_id in expired {
+            if let Some(flight) = self.in_flight.remove(&batch_id) {
+                self.requeue(flight.server, flight.acks);
+            }
+        }
+
+        let due: Vec<[u8; 32]> =
+            self.queues
+                .iter()
+                .filter(|(_, q)| {
+                    q.iter().map(|a| a.queued_at).min().is_some_and(|oldest| {
+                        now.saturating_sub(oldest) >= self.policy.max_delay_secs
+                    })
+                })
+                .map(|(server, _)| *server)
+                .collect();
+        due.into_iter()
+            .flat_map(|server| self.drain(server, now, true))
+            .collect()
+    }
+
+    /// Every queued acknowledgement, batched regardless of age, e.g. at
+    /// shutdown.
+    pub fn flush_all(&mut self, now: u64) -> Vec<OutgoingBatch> {
+        let servers: Vec<[u8; 32]> = self.queues.keys().copied().collect();
+        servers
+            .into_iter()
+            .flat_map(|server| self.drain(server, now, true))
+            .collect()
+    }
+
+    /// Apply a server's reply. Returns the ids of the settled
+    /// acknowledgements; the others are queued again. A reply for an unknown
+    /// batch settles nothing.
+    pub fn acknowledge(&mut self, reply: &ServiceReceiptAckBatchAck) -> Vec<u64> {
+        let Some(flight) = self.in_flight.remove(&reply.batch_id) else {
+            return Vec::new();
+        };
+        let (settled, unsettled): (Vec<_>, Vec<_>) = flight
+            .acks
+            .into_iter()
+            .enumerate()
+            .partition(|(i, _)| reply.settled.contains(&(*i as u32)));
+        self.requeue(
+            flight.server,
+            unsettled.into_iter().map(|(_, a)| a).collect(),
+        );
+        settled.into_iter().map(|(_, a)| a.id).collect()
+    }
+
+    /// Acknowledgements queued or awaiting a reply.
+    pub fn pending_count(&self) -> usize {
+        self.queues.values().map(VecDeque::len).sum::<usize>()
+            + self.in_flight.values().map(|f| f.acks.len()).sum::<usize>()
+    }
+
+    /// Put acknowledgements back at the front of `server`'s queue, keeping
+    /// their original queue times so they are due again immediately.
+    fn requeue(&mut self, server: [u8; 32], acks: Vec<Queued>) {
+        if acks.is_empty() {
+            return;
+        }
+        let queue = self.queues.entry(server).or_default();
+        for ack in acks.into_iter().rev() {
+            queue.push_front(ack);
+        }
+    }
+
+    /// Cut full batches off the front of `server`'s queue, and with
+    /// `partial` the remainder too.
+    fn drain(&mut self, server: [u8; 32], now: u64, partial: bool) -> Vec<OutgoingBatch> {
+        let mut out = Vec::new();
+        while let Some(queue) = self.queues.get_mut(&server) {
+            let mut count = 0;
+            let mut bytes = 0;
+            while let Some(next) = queue.get(count) {
+                if batch_len(self.empty_len, count + 1, bytes + next.encoded_len)
+                    > MAX_PLAINTEXT_SIZE
+                {
+                    break;
+                }
+                count += 1;
+                bytes += next.encoded_len;
+            }
+            let full = count < queue.len();
+            if count == 0 || !(full || partial) {
+                break;
+            }
+            let acks: Vec<Queued> = queue.drain(..count).collect();
+            if queue.is_empty() {
+                self.queues.remove(&server);
+            }
+            out.push(self.send(server, acks, now));
+        }
+        out
+    }
+
+    fn send(&mut self, server: [u8; 32], acks: Vec<Queued>, now: u64) -> OutgoingBatch {
+        let batch_id = self.next_batch_id;
+        self.next_batch_id += 1;
+        let batch = OutgoingBatch {
+            server,
+            batch: ServiceReceiptAckBatch {
+                batch_id,
+                acks: acks.iter().map(|a| a.ack.clone()).collect(),
+            },
+            ids: acks.iter().map(|a| a.id).collect(),
+        };
+        self.in_flight.insert(
+            batch_id,
+            InFlight {
+                server,
+                acks,
+                sent_at: now,
+            },
+        );
+        batch
+    }
+
+    fn batch_len(&self, count: usize, ack_bytes: usize) -> usize {
+        batch_len(self.empty_len, count, ack_bytes)
+    }
+}
+
+/// Encoded size of a batch of `count` acknowledgements totalling
+/// `ack_bytes`: the empty batch with its one-byte array header replaced.
+fn batch_len(empty_len: usize, count: usize, ack_bytes: usize) -> usize {
+    let header = match count {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        _ => 5,
+    };
+    empty_len - 1 + header + ack_bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ack(i: u8) -> ServiceReceiptAck {
+        ServiceReceiptAck {
+            chunk_hash: [i; 32],
+            bytes_received: 4 * 1024 * 1024,
+            circuit_id: [i; 16],
+            requester_key: [i; 32],
+            timestamp: 1_700_000_000,
+            relay_epoch: 7,
+            nonce: [i; 16],
+            ack_signature: vec![i; 64],
+        }
+    }
+
+    fn encoded(batch: &OutgoingBatch) -> usize {
+        TypedMessage::ServiceReceiptAckBatch(batch.batch.clone())
+            .to_cbor()
+            .expect("encode")
+            .len()
+    }
+
+    #[test]
+    fn test_fills_sphinx_payload() {
+        let mut batcher = AckBatcher::new(FlushPolicy::default());
+        let mut sent = Vec::new();
+        for i in 0..200u64 {
+            sent.extend(batcher.push([1; 32], i, ack(i as u8), 0).expect("fits"));
+        }
+        assert!(!sent.is_empty());
+        let first = &sent[0];
+        assert!(encoded(first) <= MAX_PLAINTEXT_SIZE);
+        // The next acknowledgement would not have fit.
+        let mut bigger = first.batch.clone();
+        bigger.acks.push(ack(first.ids.len() as u8));
+        assert!(
+            TypedMessage::ServiceReceiptAckBatch(bigger)
+                .to_cbor()
+                .expect("encode")
+                .len()
+                > MAX_PLAINTEXT_SIZE
+        );
+        assert_eq!(first.ids, (0..first.ids.len() as u64).collect::<Vec<_>>());
+
+        let sent_count: usize = sent.iter().map(|b| b.ids.len()).sum();
+        let rest = batcher.flush_all(0);
+        assert_eq!(
+            sent_count + rest.iter().map(|b| b.ids.len()).sum::<usize>(),
+            200
+        );
+        assert!(rest.iter().all(|b| encoded(b) <= MAX_PLAINTEXT_SIZE));
+    }
+
+    #[test]
+    fn test_flushes_after_max_delay() {
+        let policy = FlushPolicy {
+            max_delay_secs: 30,
+            retry_after_secs: 100,
+        };
+        let mut batcher = AckBatcher::new(policy);
+        assert!(batcher
+            .push([1; 32], 1, ack(1), 10)
+            .expect("push")
+            .is_empty());
+        assert!(batcher
+            .push([2; 32], 2, ack(2), 25)
+            .expect("push")
+            .is_empty());
+        assert!(batcher.poll(39).is_empty());
+
+        let due = batcher.poll(40);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].server, [1; 32]);
+        assert_eq!(batcher.poll(55).len(), 1);
+        assert_eq!(batcher.pending_count(), 2, "both still awaiting replies");
+    }
+
+    #[test]
+    fn test_partial_acknowledgement_requeues_rest() {
+        let mut batcher = AckBatcher::new(FlushPolicy::default());
+        for i in 0..3u64 {
+            batcher
+                .push([1; 32], 10 + i, ack(i as u8), 0)
+                .expect("push");
+        }
+        let batch = batcher.flush_all(0).pop().expect("batch");
+        assert_eq!(batch.ids, vec![10, 11, 12]);
+
+        let settled = batcher.acknowledge(&ServiceReceiptAckBatchAck {
+            batch_id: batch.batch.batch_id,
+            settled: vec![0, 2],
+        });
+        assert_eq!(settled, vec![10, 12]);
+        assert_eq!(batcher.pending_count(), 1);
+
+        // The unsettled ack kept its queue time, so it is due at once.
+        let resent = batcher.poll(DEFAULT_MAX_DELAY_SECS).pop().expect("resent");
+        assert_eq!(resent.ids, vec![11]);
+        assert_ne!(resent.batch.batch_id, batch.batch.batch_id);
+        assert!(batcher
+            .acknowledge(&ServiceReceiptAckBatchAck {
+                batch_id: batch.batch.batch_id,
+                settled: vec![1],
+            })
+            .is_empty());
+    }
+
+    #[test]
+    fn test_unanswered_batch_is_resent() {
+        let policy = FlushPolicy {
+            max_delay_secs: 10,
+            retry_after_secs: 60,
+        };
+        let mut batcher = AckBatcher::new(policy);
+        batcher.push([1; 32], 1, ack(1), 0).expect("push");
+        let first = batcher.poll(10).pop().expect("batch");
+        assert!(batcher.poll(69).is_empty());
+        let retry = batcher.poll(70).pop().expect("retry");
+        assert_eq!(retry.ids, first.ids);
+        assert_eq!(batcher.pending_count(), 1);
+    }
+}
@@ -0,0 +1,419 @@
+//! Sphinx packet replay suppression.
+//!
+//! A relay must process each Sphinx packet at most once, or an observer can
+//! replay a packet and watch where the copies go. Each packet is identified
+//! at a hop by its replay tag:
+//!
+//! `tag = BLAKE3::hash("sphinx-replay" || eph_pk[hop] || mac)`
+//!
+//! Tags are remembered for [`REPLAY_WINDOW_SECS`], two relay epochs: after
+//! that the relay key the packet was built for has rotated out and a replay
+//! fails decryption anyway.
+//!
+//! One [`ReplayCache`] is shared by every connection of a relay
+//! ([`SharedReplayCache`]). A tag is only recorded once the packet's MAC has
+//! verified, so a forged header cannot block the genuine packet. Replay
+//! attempts are counted per sending peer and drained by the peer scoring
+//! layer via [`ReplayCache::drain_replays`].
+//!
+//! [`process_packet_once`] is a relay's only way into packet processing,
+//! so a packet it is the exit for is always passed through the relay's
+//! [`ExitGate`] before its message is handed back.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ochra_crypto::x25519::X25519StaticSecret;
+
+use crate::exit::ExitGate;
+use crate::sphinx::{
+    process_packet, ProcessResult, SphinxPacket, EPH_PK_SIZE, NUM_HOPS, OFF_EPH_PKS, OFF_MAC,
+};
+use crate::wire::ProtocolMessage;
+use crate::TransportError;
+
+/// How long a replay tag is remembered (two relay epochs).
+pub const REPLAY_WINDOW_SECS: u64 = 2 * ochra_types::RELAY_EPOCH_DURATION_SECS;
+
+/// Default cap on remembered tags; the oldest are evicted first.
+pub const MAX_REPLAY_ENTRIES: usize = 1 << 20;
+
+/// Replay cache parameters.
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayConfig {
+    /// How long a tag is remembered.
+    pub window: Duration,
+    /// Maximum number of remembered tags.
+    pub max_entries: usize,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(REPLAY_WINDOW_SECS),
+            max_entries: MAX_REPLAY_ENTRIES,
+        }
+    }
+}
+
+/// Per-peer packet counters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Packets from this peer seen for the first time.
+    pub fresh: u64,
+    /// Packets from this peer that were replays.
+    pub replays: u64,
+}
+
+/// Compute the replay tag of a packet at `hop_index`.
+///
+/// # Errors
+///
+/// Returns [`TransportError::InvalidPacket`] if `hop_index` is out of range.
+pub fn replay_tag(packet: &SphinxPacket, hop_index: usize) -> Result<[u8; 32], TransportError> {
+    if hop_index >= NUM_HOPS {
+        return Err(TransportError::InvalidPacket(format!(
+            "invalid hop index {hop_index}, max is {}",
+            NUM_HOPS - 1
+        )));
+    }
+    let pk_start = OFF_EPH_PKS + hop_index * EPH_PK_SIZE;
+    let mut input = Vec::with_capacity(13 + EPH_PK_SIZE + 16);
+    input.extend_from_slice(b"sphinx-replay");
+    input.extend_from_slice(&packet.data[pk_start..pk_start + EPH_PK_SIZE]);
+    input.extend_from_slice(&packet.data[OFF_MAC..OFF_MAC + 16]);
+    Ok(ochra_crypto::blake3::hash(&input))
+}
+
+/// Time-bounded set of seen replay tags with per-peer counters.
+#[derive(Debug)]
+pub struct ReplayCache {
+    config: ReplayConfig,
+    tags: HashMap<[u8; 32], Instant>,
+    /// Tags in insertion order, for expiry and eviction.
+    order: VecDeque<([u8; 32], Instant)>,
+    stats: HashMap<[u8; 32], ReplayStats>,
+    /// Replays per peer not yet applied to peer scores.
+    unscored: HashMap<[u8; 32], u64>,
+}
+
+impl Default for ReplayCache {
+    fn default() -> Self {
+        Self::new(ReplayConfig::default())
+    }
+}
+
+/// A replay cache shared across connections.
+pub type SharedReplayCache = Arc<Mutex<ReplayCache>>;
+
+impl ReplayCache {
+    /// Create an empty cache.
+    pub fn new(config: ReplayConfig) -> Self {
+        Self {
+            config,
+            tags: HashMap::new(),
+            order: VecDeque::new(),
+            stats: HashMap::new(),
+            unscored: HashMap::new(),
+        }
+    }
+
+    /// Create an empty cache for sharing across connections.
+    pub fn shared(config: ReplayConfig) -> SharedReplayCache {
+        Arc::new(Mutex::new(Self::new(config)))
+    }
+
+    /// Whether `tag` was seen within the window.
+    pub fn contains(&self, tag: &[u8; 32], now: Instant) -> bool {
+        self.tags
+            .get(tag)
+            .is_some_and(|seen| now.duration_since(*seen) < self.config.window)
+    }
+
+    /// Record `tag` as received from `from`.
+    ///
+    /// Returns `false` (and counts a replay against `from`) if the tag was
+    /// already seen within the window.
+    pub fn insert(&mut self, tag: [u8; 32], from: [u8; 32], now: Instant) -> bool {
+        self.expire(now);
+        if self.contains(&tag, now) {
+            self.record_replay(from);
+            return false;
+        }
+        while self.order.len() >= self.config.max_entries {
+            let Some((old, _)) = self.order.pop_front() else {
+                break;
+            };
+            self.tags.remove(&old);
+        }
+        self.tags.insert(tag, now);
+        self.order.push_back((tag, now));
+        self.stats.entry(from).or_default().fresh += 1;
+        true
+    }
+
+    /// Count a replay attempt against `from`.
+    pub fn record_replay(&mut self, from: [u8; 32]) {
+        self.stats.entry(from).or_default().replays += 1;
+        *self.unscored.entry(from).or_default() += 1;
+    }
+
+    /// Forget tags older than the window.
+    pub fn expire(&mut self, now: Instant) {
+        while let Some((tag, seen)) = self.order.front().copied() {
+            if now.duration_since(seen) < self.config.window {
+                break;
+            }
+            self.order.pop_front();
+            self.tags.remove(&tag);
+        }
+    }
+
+    /// Counters for a peer.
+    pub fn stats(&self, peer: &[u8; 32]) -> ReplayStats {
+        self.stats.get(peer).copied().unwrap_or_default()
+    }
+
+    /// Replay attempts across all peers.
+    pub fn total_replays(&self) -> u64 {
+        self.stats.values().map(|s| s.replays).sum()
+    }
+
+    /// Take the replay counts recorded since the last drain, for peer
+    /// scoring.
+    pub fn drain_replays(&mut self) -> Vec<([u8; 32], u64)> {
+        self.unscored.drain().collect()
+    }
+
+    /// Number of remembered tags.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Whether no tags are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+/// A relay's exit gate and the circuit a packet arrived on.
+pub struct ExitContext<'a> {
+    pub gate: &'a Mutex<ExitGate>,
+    /// Circuit handle the gate keys its bandwidth budget by.
+    pub circuit: [u8; 16],
+    pub now_ms: u64,
+}
+
+/// What a relay does with a processed packet.
+pub enum RelayStep {
+    /// Forward the rewritten packet to the next relay.
+    Forward {
+        next_node_id: [u8; 32],
+        packet: Box<SphinxPacket>,
+    },
+    /// Act on a message the exit policy admitted.
+    Deliver(ProtocolMessage),
+}
+
+/// Process a packet received from `from`, refusing replays and, at the
+/// final hop, anything the exit policy does not admit.
+///
+/// # Errors
+///
+/// Returns [`TransportError::ReplayedPacket`] if the packet was already
+/// processed within the window, any error from
+/// [`process_packet`](crate::sphinx::process_packet), or any error from
+/// [`ExitGate::deliver`] at the final hop.
+pub fn process_packet_once(
+    cache: &Mutex<ReplayCache>,
+    exit: ExitContext<'_>,
+    from: [u8; 32],
+    packet: &SphinxPacket,
+    our_secret: &X25519StaticSecret,
+    hop_index: usize,
+) -> Result<RelayStep, TransportError> {
+    let tag = replay_tag(packet, hop_index)?;
+    let lock = || {
+        cache
+            .lock()
+            .map_err(|_| TransportError::Internal("replay cache poisoned".to_string()))
+    };
+
+    // Cheap rejection before the DH and decryption.
+    {
+        let mut cache = lock()?;
+        if cache.contains(&tag, Instant::now()) {
+            cache.record_replay(from);
+            return Err(TransportError::ReplayedPacket);
+        }
+    }
+
+    let result = process_packet(packet, our_secret, hop_index)?;
+
+    // Another connection may have raced us with the same packet.
+    if !lock()?.insert(tag, from, Instant::now()) {
+        return Err(TransportError::ReplayedPacket);
+    }
+    step(result, exit)
+}
+
+/// Pass a final-hop result through the exit gate.
+fn step(result: ProcessResult, exit: ExitContext<'_>) -> Result<RelayStep, TransportError> {
+    match result {
+        ProcessResult::Forward {
+            next_node_id,
+            packet,
+        } => Ok(RelayStep::Forward {
+            next_node_id,
+            packet,
+        }),
+        deliver @ ProcessResult::Deliver { .. } => {
+            let mut gate = exit
+                .gate
+                .lock()
+                .map_err(|_| TransportError::Internal("exit gate poisoned".to_string()))?;
+            Ok(RelayStep::Deliver(gate.deliver(
+                exit.circuit,
+                deliver,
+                exit.now_ms,
+            )?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphinx::{build_packet, HopInfo, SphinxBuildParams};
+    use ochra_crypto::x25519::X25519PublicKey;
+    use ochra_types::network::{ExitClass, ExitPolicy};
+
+    fn exit(gate: &Mutex<ExitGate>) -> ExitContext<'_> {
+        ExitContext {
+            gate,
+            circuit: [0xAA; 16],
+            now_ms: 0,
+        }
+    }
+
+    fn test_packet() -> (SphinxPacket, X25519StaticSecret) {
+        let secrets: Vec<X25519StaticSecret> = (0..NUM_HOPS)
+            .map(|_| X25519StaticSecret::random())
+            .collect();
+        let pks: Vec<X25519PublicKey> = secrets.iter().map(|s| s.public_key()).collect();
+        let hop_info = |i: usize| HopInfo {
+            node_id: [i as u8; 32],
+            next_hop_pk: pks.get(i + 1).map(|pk| pk.to_bytes()).unwrap_or_default(),
+            circuit_id: [0xAA; 16],
+            hop_index: i as u8,
+        };
+        let packet = build_packet(SphinxBuildParams {
+            hop_public_keys: [pks[0].clone(), pks[1].clone(), pks[2].clone()],
+            hop_infos: [hop_info(0), hop_info(1), hop_info(2)],
+            plaintext: b"hello".to_vec(),
+        })
+        .expect("build");
+        let first = secrets.into_iter().next().expect("secret");
+        (packet, first)
+    }
+
+    #[test]
+    fn test_second_copy_is_rejected() {
+        let (packet, secret) = test_packet();
+        let cache = ReplayCache::shared(ReplayConfig::default());
+        let gate = Mutex::new(ExitGate::new(ExitPolicy::default(), 100));
+        let peer = [1u8; 32];
+
+        assert!(process_packet_once(&cache, exit(&gate), peer, &packet, &secret, 0).is_ok());
+        assert!(matches!(
+            process_packet_once(&cache, exit(&gate), [2u8; 32], &packet, &secret, 0),
+            Err(TransportError::ReplayedPacket)
+        ));
+
+        let cache = cache.lock().expect("lock");
+        assert_eq!(cache.stats(&peer).fresh, 1);
+        assert_eq!(cache.stats(&[2u8; 32]).replays, 1);
+        assert_eq!(cache.total_replays(), 1);
+    }
+
+    #[test]
+    fn test_forged_mac_does_not_poison_cache() {
+        let (packet, secret) = test_packet();
+        let cache = ReplayCache::shared(ReplayConfig::default());
+        let gate = Mutex::new(ExitGate::new(ExitPolicy::default(), 100));
+
+        // Same ephemeral key and MAC, so the same tag, but a tampered header.
+        let mut forged = SphinxPacket { data: packet.data };
+        forged.data[OFF_MAC - 1] ^= 0xff;
+        assert!(process_packet_once(&cache, exit(&gate), [9u8; 32], &forged, &secret, 0).is_err());
+        assert!(process_packet_once(&cache, exit(&gate), [1u8; 32], &packet, &secret, 0).is_ok());
+    }
+
+    #[test]
+    fn test_exit_hop_applies_policy() {
+        let put = ProtocolMessage::from_raw_payload(crate::messages::MSG_DHT_PUT, vec![0; 10])
+            .expect("message");
+        let delivered = || ProcessResult::Deliver {
+            plaintext: put.to_bytes().expect("encode"),
+        };
+
+        let refusing = Mutex::new(ExitGate::new(
+            ExitPolicy {
+                allowed_classes: vec![ExitClass::Whisper],
+                ..ExitPolicy::default()
+            },
+            100,
+        ));
+        assert!(matches!(
+            step(delivered(), exit(&refusing)),
+            Err(TransportError::ExitRefused(_))
+        ));
+
+        let open = Mutex::new(ExitGate::new(ExitPolicy::default(), 100));
+        assert!(matches!(
+            step(delivered(), exit(&open)),
+            Ok(RelayStep::Deliver(msg)) if msg.msg_type == crate::messages::MSG_DHT_PUT
+        ));
+    }
+
+    #[test]
+    fn test_tags_expire_after_window() {
+        let mut cache = ReplayCache::new(ReplayConfig {
+            window: Duration::from_secs(10),
+            max_entries: 16,
+        });
+        let start = Instant::now();
+        assert!(cache.insert([1; 32], [0; 32], start));
+        assert!(!cache.insert([1; 32], [0; 32], start + Duration::from_secs(5)));
+        assert!(cache.insert([1; 32], [0; 32], start + Duration::from_secs(11)));
+    }
+
+    #[test]
+    fn test_capacity_evicts_oldest() {
+        let mut cache = ReplayCache::new(ReplayConfig {
+            window: Duration::from_secs(60),
+            max_entries: 2,
+        });
+        let now = Instant::now();
+        for i in 0..3u8 {
+            assert!(cache.insert([i; 32], [0; 32], now));
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&[0; 32], now));
+        assert!(cache.contains(&[2; 32], now));
+    }
+
+    #[test]
+    fn test_drain_replays_for_scoring() {
+        let mut cache = ReplayCache::default();
+        let now = Instant::now();
+        cache.insert([1; 32], [7; 32], now);
+        cache.insert([1; 32], [8; 32], now);
+        cache.insert([1; 32], [8; 32], now);
+
+        assert_eq!(cache.drain_replays(), vec![([8u8; 32], 2)]);
+        assert!(cache.drain_replays().is_empty());
+        assert_eq!(cache.stats(&[8; 32]).replays, 2);
+    }
+}
@@ -0,0 +1,349 @@
+//! TLS session resumption and replay-safe 0-RTT.
+//!
+//! Reconnecting to a known relay normally costs a full handshake round trip
+//! before the first request. With a stored TLS 1.3 session ticket the
+//! client can resume and send its first request in the opening flight
+//! (0-RTT), which matters for mobile nodes that reconnect often.
+//!
+//! ## Tickets
+//!
+//! Tickets are held in a [`SessionTicketStore`] keyed by the peer's node
+//! ID. Connections to a known node use [`server_name_for`] as the TLS
+//! server name so the ticket lands under that node; connections to an
+//! unknown address use the generic name and never resume. At most
+//! [`TICKETS_PER_NODE`] tickets are kept per node and the least recently
+//! used node is evicted beyond [`MAX_TICKETED_NODES`].
+//!
+//! ## Replay Safety
+//!
+//! 0-RTT data can be captured and replayed by an on-path attacker, so it is
+//! restricted three ways:
+//!
+//! 1. Only the idempotent types in [`ZERO_RTT_MESSAGE_TYPES`] (Ping and
+//!    DhtGet) may be sent early; the client refuses anything else.
+//! 2. Server tickets are stateful and single-use: a replayed ClientHello
+//!    finds its ticket already consumed and its early data is rejected.
+//! 3. The server passes every message read from a 0-RTT stream through an
+//!    [`EarlyDataFilter`], which rejects other types, messages older than
+//!    [`EARLY_DATA_MAX_AGE_SECS`], and repeated message IDs.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use rustls::client::{ClientSessionStore, Tls12ClientSessionValue, Tls13ClientSessionValue};
+use rustls::pki_types::ServerName;
+use rustls::NamedGroup;
+
+use crate::messages::{MSG_DHT_GET, MSG_PING};
+use crate::wire::ProtocolMessage;
+use crate::TransportError;
+
+/// Message types that may be sent in 0-RTT data.
+pub const ZERO_RTT_MESSAGE_TYPES: &[u16] = &[MSG_PING, MSG_DHT_GET];
+
+/// Nodes whose tickets are kept.
+pub const MAX_TICKETED_NODES: usize = 256;
+
+/// Tickets kept per node. Servers issue two per handshake.
+pub const TICKETS_PER_NODE: usize = 2;
+
+/// Maximum clock difference accepted for a message read from 0-RTT data.
+pub const EARLY_DATA_MAX_AGE_SECS: u64 = 10;
+
+/// Domain suffix of per-node TLS server names.
+const NODE_NAME_SUFFIX: &str = ".node.ochra";
+
+/// Whether `msg_type` may be sent in 0-RTT data.
+pub fn is_zero_rtt_safe(msg_type: u16) -> bool {
+    ZERO_RTT_MESSAGE_TYPES.contains(&msg_type)
+}
+
+/// TLS server name for connections to `node_id`.
+///
+/// The node ID is split over two labels, since one DNS label holds at most
+/// 63 characters.
+pub fn server_name_for(node_id: &[u8; 32]) -> String {
+    format!(
+        "{}.{}{NODE_NAME_SUFFIX}",
+        hex::encode(&node_id[..16]),
+        hex::encode(&node_id[16..])
+    )
+}
+
+/// The node ID encoded in a per-node server name.
+fn node_id_of(server_name: &ServerName<'_>) -> Option<[u8; 32]> {
+    let ServerName::DnsName(name) = server_name else {
+        return None;
+    };
+    let labels = name.as_ref().strip_suffix(NODE_NAME_SUFFIX)?;
+    let (high, low) = labels.split_once('.')?;
+    let bytes = hex::decode(format!("{high}{low}")).ok()?;
+    bytes.try_into().ok()
+}
+
+#[derive(Debug, Default)]
+struct NodeTickets {
+    kx_hint: Option<NamedGroup>,
+    tls12: Option<Tls12ClientSessionValue>,
+    tls13: VecDeque<Tls13ClientSessionValue>,
+}
+
+#[derive(Debug, Default)]
+struct StoreInner {
+    nodes: HashMap<[u8; 32], NodeTickets>,
+    /// Nodes from least to most recently stored.
+    order: VecDeque<[u8; 32]>,
+}
+
+impl StoreInner {
+    fn entry(&mut self, node_id: [u8; 32]) -> &mut NodeTickets {
+        self.order.retain(|n| *n != node_id);
+        self.order.push_back(node_id);
+        if self.order.len() > MAX_TICKETED_NODES {
+            if let Some(evicted) = self.order.pop_front() {
+                self.nodes.remove(&evicted);
+            }
+        }
+        self.nodes.entry(node_id).or_default()
+    }
+}
+
+/// Client-side TLS session storage keyed by node ID.
+#[derive(Debug, Default)]
+pub struct SessionTicketStore {
+    inner: Mutex<StoreInner>,
+}
+
+impl SessionTicketStore {
+    /// Create an empty store.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StoreInner> {
+        // A poisoned store only loses resumption, never correctness.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether a TLS 1.3 ticket is held for `node_id`.
+    pub fn has_ticket(&self, node_id: &[u8; 32]) -> bool {
+        self.lock()
+            .nodes
+            .get(node_id)
+            .is_some_and(|t| !t.tls13.is_empty())
+    }
+
+    /// Drop every ticket for `node_id`, e.g. after it is blacklisted.
+    pub fn forget(&self, node_id: &[u8; 32]) {
+        let mut inner = self.lock();
+        inner.nodes.remove(node_id);
+        inner.order.retain(|n| n != node_id);
+    }
+
+    /// Number of nodes with stored session state.
+    pub fn len(&self) -> usize {
+        self.lock().nodes.len()
+    }
+
+    /// Whether no session state is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ClientSessionStore for SessionTicketStore {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        if let Some(node_id) = node_id_of(&server_name) {
+            self.lock().entry(node_id).kx_hint = Some(group);
+        }
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        let node_id = node_id_of(server_name)?;
+        self.lock().nodes.get(&node_id)?.kx_hint
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: Tls12ClientSessionValue) {
+        if let Some(node_id) = node_id_of(&server_name) {
+            self.lock().entry(node_id).tls12 = Some(value);
+        }
+    }
+
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        let node_id = node_id_of(server_name)?;
+        self.lock().nodes.get(&node_id)?.tls12.clone()
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        if let Some(node_id) = node_id_of(server_name) {
+            if let Some(tickets) = self.lock().nodes.get_mut(&node_id) {
+                tickets.tls12 = None;
+            }
+        }
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: ServerName<'static>,
+        value: Tls13ClientSessionValue,
+    ) {
+        if let Some(node_id) = node_id_of(&server_name) {
+            let mut inner = self.lock();
+            let tickets = &mut inner.entry(node_id).tls13;
+            if tickets.len() == TICKETS_PER_NODE {
+                tickets.pop_front();
+            }
+            tickets.push_back(value);
+        }
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &ServerName<'static>,
+    ) -> Option<Tls13ClientSessionValue> {
+        let node_id = node_id_of(server_name)?;
+        // Newest first; each ticket is single-use on the server.
+        self.lock().nodes.get_mut(&node_id)?.tls13.pop_back()
+    }
+}
+
+/// Server-side check on messages read from 0-RTT streams.
+#[derive(Debug, Default)]
+pub struct EarlyDataFilter {
+    /// Message IDs seen in early data, with their timestamps.
+    seen: HashMap<[u8; 16], u64>,
+}
+
+impl EarlyDataFilter {
+    /// Create an empty filter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Admit a message. Messages from streams not opened in 0-RTT
+    /// (`is_0rtt` false) always pass.
+    ///
+    /// # Errors
+    ///
+    /// - [`TransportError::ProtocolViolation`] if the type may not be sent
+    ///   early or its timestamp is more than [`EARLY_DATA_MAX_AGE_SECS`]
+    ///   from `now_secs`
+    /// - [`TransportError::ReplayedPacket`] if the message ID was already
+    ///   seen in early data
+    pub fn admit(
+        &mut self,
+        is_0rtt: bool,
+        msg: &ProtocolMessage,
+        now_secs: u64,
+    ) -> Result<(), TransportError> {
+        if !is_0rtt {
+            return Ok(());
+        }
+        if !is_zero_rtt_safe(msg.msg_type) {
+            return Err(TransportError::ProtocolViolation(format!(
+                "message type 0x{:04x} not allowed in 0-RTT data",
+                msg.msg_type
+            )));
+        }
+        if msg.timestamp.abs_diff(now_secs) > EARLY_DATA_MAX_AGE_SECS {
+            return Err(TransportError::ProtocolViolation(
+                "stale 0-RTT message".to_string(),
+            ));
+        }
+        // Anything older than the window would fail the timestamp check.
+        self.seen
+            .retain(|_, ts| ts.abs_diff(now_secs) <= EARLY_DATA_MAX_AGE_SECS);
+        if self.seen.insert(msg.msg_id, msg.timestamp).is_some() {
+            return Err(TransportError::ReplayedPacket);
+        }
+        Ok(())
+    }
+
+    /// Number of message IDs remembered.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Whether no message IDs are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{Goodbye, Ping, TypedMessage};
+
+    fn msg(typed: TypedMessage, timestamp: u64) -> ProtocolMessage {
+        let mut msg = ProtocolMessage::from_typed(&typed).expect("msg");
+        msg.timestamp = timestamp;
+        msg
+    }
+
+    #[test]
+    fn test_server_name_round_trips_node_id() {
+        let node_id = [0xabu8; 32];
+        let name = server_name_for(&node_id);
+        let parsed = ServerName::try_from(name).expect("valid DNS name");
+        assert_eq!(node_id_of(&parsed), Some(node_id));
+        let generic = ServerName::try_from("ochra-node").expect("valid DNS name");
+        assert_eq!(node_id_of(&generic), None);
+    }
+
+    #[test]
+    fn test_store_keys_by_node_and_evicts() {
+        let store = SessionTicketStore::new();
+        for i in 0..=MAX_TICKETED_NODES {
+            let mut node_id = [0u8; 32];
+            node_id[..8].copy_from_slice(&(i as u64).to_le_bytes());
+            let name = ServerName::try_from(server_name_for(&node_id)).expect("name");
+            store.set_kx_hint(name, NamedGroup::X25519);
+        }
+        assert_eq!(store.len(), MAX_TICKETED_NODES);
+        let first = ServerName::try_from(server_name_for(&[0u8; 32])).expect("name");
+        assert_eq!(store.kx_hint(&first), None);
+
+        let generic = ServerName::try_from("ochra-node").expect("name");
+        store.set_kx_hint(generic.clone(), NamedGroup::X25519);
+        assert_eq!(store.kx_hint(&generic), None);
+        assert!(!store.has_ticket(&[1u8; 32]));
+    }
+
+    #[test]
+    fn test_filter_allows_only_idempotent_types() {
+        let mut filter = EarlyDataFilter::new();
+        let goodbye = msg(
+            TypedMessage::Goodbye(Goodbye {
+                reason: 0,
+                detail: None,
+            }),
+            100,
+        );
+        assert!(filter.admit(false, &goodbye, 100).is_ok());
+        assert!(matches!(
+            filter.admit(true, &goodbye, 100),
+            Err(TransportError::ProtocolViolation(_))
+        ));
+        let ping = msg(TypedMessage::Ping(Ping { nonce: [1; 8] }), 100);
+        assert!(filter.admit(true, &ping, 105).is_ok());
+    }
+
+    #[test]
+    fn test_filter_rejects_replayed_and_stale_messages() {
+        let mut filter = EarlyDataFilter::new();
+        let ping = msg(TypedMessage::Ping(Ping { nonce: [1; 8] }), 100);
+        filter.admit(true, &ping, 100).expect("first");
+        assert!(matches!(
+            filter.admit(true, &ping, 101),
+            Err(TransportError::ReplayedPacket)
+        ));
+        assert!(matches!(
+            filter.admit(true, &ping, 100 + EARLY_DATA_MAX_AGE_SECS + 1),
+            Err(TransportError::ProtocolViolation(_))
+        ));
+        let fresh = msg(TypedMessage::Ping(Ping { nonce: [2; 8] }), 200);
+        filter.admit(true, &fresh, 200).expect("fresh");
+        assert_eq!(filter.len(), 1);
+    }
+}
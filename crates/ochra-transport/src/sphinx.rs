@@ -69,9 +69,9 @@ pub const FLAG_NONE: u8 = 0x00;
 // Header field offsets
 const OFF_VERSION: usize = 0;
 const OFF_FLAGS: usize = 1;
-const OFF_EPH_PKS: usize = 2;
+pub(crate) const OFF_EPH_PKS: usize = 2;
 const OFF_ROUTING: usize = OFF_EPH_PKS + (NUM_HOPS * EPH_PK_SIZE); // 98
-const OFF_MAC: usize = OFF_ROUTING + (NUM_HOPS * ROUTING_INFO_SIZE); // 347
+pub(crate) const OFF_MAC: usize = OFF_ROUTING + (NUM_HOPS * ROUTING_INFO_SIZE); // 347
 /// Start of the reserved field (currently unused but reserved for ML-KEM extension).
 #[allow(dead_code)]
 const OFF_RESERVED: usize = OFF_MAC + 16; // 363
@@ -208,6 +208,24 @@ pub enum ProcessResult {
 ///
 /// Returns [`TransportError::Crypto`] if encryption fails.
 pub fn build_packet(params: SphinxBuildParams) -> Result<SphinxPacket, TransportError> {
+    let eph_secrets = std::array::from_fn(|_| X25519StaticSecret::random());
+    build_packet_with_secrets(params, eph_secrets)
+}
+
+/// Build a Sphinx packet with caller-chosen per-hop ephemeral secrets.
+///
+/// Identical to [`build_packet`] except that the ephemeral X25519 secrets
+/// are supplied instead of drawn at random, which makes the output
+/// reproducible. Only test vector generation should call this; reusing an
+/// ephemeral secret links packets.
+///
+/// # Errors
+///
+/// Same as [`build_packet`].
+pub fn build_packet_with_secrets(
+    params: SphinxBuildParams,
+    eph_secrets: [X25519StaticSecret; NUM_HOPS],
+) -> Result<SphinxPacket, TransportError> {
     if params.plaintext.len() > MAX_PLAINTEXT_SIZE {
         return Err(TransportError::InvalidPacket(format!(
             "plaintext too large: {} bytes, max {MAX_PLAINTEXT_SIZE}",
@@ -215,19 +233,16 @@ pub fn build_packet(params: SphinxBuildParams) -> Result<SphinxPacket, Transport
         )));
     }
 
-    // Generate ephemeral keys for each hop and compute shared secrets.
-    let mut eph_secrets = Vec::with_capacity(NUM_HOPS);
+    // Compute each hop's shared secret from its ephemeral key.
     let mut eph_publics = Vec::with_capacity(NUM_HOPS);
     let mut hop_keys_all = Vec::with_capacity(NUM_HOPS);
 
-    for i in 0..NUM_HOPS {
-        let eph_secret = X25519StaticSecret::random();
+    for (eph_secret, hop_pk) in eph_secrets.iter().zip(&params.hop_public_keys) {
         let eph_public = eph_secret.public_key();
-        let shared = eph_secret.diffie_hellman(&params.hop_public_keys[i]);
+        let shared = eph_secret.diffie_hellman(hop_pk);
         let keys = HopKeys::derive(shared.as_bytes());
 
         eph_publics.push(eph_public);
-        eph_secrets.push(eph_secret);
         hop_keys_all.push(keys);
     }
 
@@ -669,6 +684,40 @@ mod tests {
         assert!(build_packet(params).is_err());
     }
 
+    #[test]
+    fn test_build_packet_with_secrets_deterministic() {
+        let hop_pubs: Vec<_> = (1..=NUM_HOPS as u8)
+            .map(|i| X25519StaticSecret::from_bytes([i; 32]).public_key())
+            .collect();
+        let build = || {
+            let params = SphinxBuildParams {
+                hop_public_keys: [
+                    hop_pubs[0].clone(),
+                    hop_pubs[1].clone(),
+                    hop_pubs[2].clone(),
+                ],
+                hop_infos: std::array::from_fn(|i| HopInfo {
+                    node_id: [i as u8; 32],
+                    next_hop_pk: [0; 32],
+                    circuit_id: [0xEE; 16],
+                    hop_index: i as u8,
+                }),
+                plaintext: b"vector".to_vec(),
+            };
+            let secrets =
+                std::array::from_fn(|i| X25519StaticSecret::from_bytes([0x40 + i as u8; 32]));
+            build_packet_with_secrets(params, secrets).expect("build packet")
+        };
+        let a = build();
+        let b = build();
+        assert_eq!(a.data, b.data);
+        let eph0 = X25519StaticSecret::from_bytes([0x40; 32]).public_key();
+        assert_eq!(
+            &a.data[OFF_EPH_PKS..OFF_EPH_PKS + EPH_PK_SIZE],
+            &eph0.to_bytes()
+        );
+    }
+
     #[test]
     fn test_validate_packet() {
         let good = [0u8; PACKET_SIZE];
@@ -0,0 +1,518 @@
+//! Strict decoding of untrusted wire input.
+//!
+//! Bytes from peers are walked once with a bounded CBOR scanner before
+//! [`ciborium`] sees them. The scan rejects, without allocating:
+//!
+//! - payloads larger than their message type allows
+//! - arrays, maps, and strings longer than [`DecodeLimits`] permit, or
+//!   longer than the bytes remaining
+//! - nesting deeper than [`DecodeLimits::max_depth`]
+//! - indefinite-length items, reserved encodings, and trailing bytes
+//! - non-canonical payloads for the signed [`CANONICAL_MESSAGE_TYPES`]
+//!
+//! Every rejection is a [`ProtocolViolation`] naming the offending field as
+//! a path such as `payload.MintRequest.blinded_tokens[3]`.
+
+use serde::de::DeserializeOwned;
+
+use crate::messages::*;
+use crate::wire::MAX_PAYLOAD_SIZE;
+use crate::TransportError;
+
+/// Payload size limit for small control messages.
+const CONTROL_PAYLOAD_SIZE: usize = 1024;
+
+/// Payload size limit for DHT and quorum messages.
+const RECORD_PAYLOAD_SIZE: usize = 8192;
+
+/// Structural limits applied while scanning CBOR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum number of elements in an array or entries in a map.
+    pub max_collection_len: usize,
+    /// Maximum length of a byte or text string.
+    pub max_string_len: usize,
+    /// Maximum nesting depth of arrays, maps, and tags.
+    pub max_depth: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_collection_len: MAX_PAYLOAD_SIZE,
+            max_string_len: MAX_PAYLOAD_SIZE,
+            max_depth: 16,
+        }
+    }
+}
+
+/// What was wrong with the input.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ViolationKind {
+    #[error("unknown message type 0x{0:04x}")]
+    UnknownMessageType(u16),
+    #[error("{size} bytes exceeds limit of {max}")]
+    TooLarge { size: usize, max: usize },
+    #[error("{len} elements exceeds limit of {max}")]
+    CollectionTooLong { len: u64, max: usize },
+    #[error("string of {len} bytes exceeds limit of {max}")]
+    StringTooLong { len: u64, max: usize },
+    #[error("nesting exceeds depth limit of {max}")]
+    TooDeep { max: usize },
+    #[error("indefinite-length item")]
+    IndefiniteLength,
+    #[error("truncated input")]
+    Truncated,
+    #[error("{0} trailing bytes")]
+    TrailingBytes(usize),
+    #[error("payload is 0x{actual:04x}, envelope says 0x{expected:04x}")]
+    TypeMismatch { expected: u16, actual: u16 },
+    #[error("malformed: {0}")]
+    Malformed(String),
+    #[error("not canonically encoded")]
+    NonCanonical,
+}
+
+/// A rejected input, with the path of the field at fault.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{field}: {kind}")]
+pub struct ProtocolViolation {
+    /// Path of the offending field, e.g. `payload.DhtPut.value[12]`.
+    pub field: String,
+    pub kind: ViolationKind,
+}
+
+impl ProtocolViolation {
+    fn new(field: impl Into<String>, kind: ViolationKind) -> Self {
+        Self {
+            field: field.into(),
+            kind,
+        }
+    }
+}
+
+impl From<ProtocolViolation> for TransportError {
+    fn from(v: ProtocolViolation) -> Self {
+        TransportError::ProtocolViolation(v.to_string())
+    }
+}
+
+/// Maximum payload size for a message type, or `None` if the type is
+/// unknown.
+pub fn max_payload_size(msg_type: u16) -> Option<usize> {
+    let max = match msg_type {
+        MSG_CAPABILITY_EXCHANGE..=MSG_UNSUPPORTED => CONTROL_PAYLOAD_SIZE,
+        MSG_CHUNK_REQUEST | MSG_SERVICE_RECEIPT_ACK | MSG_CHUNK_QUEUED => CONTROL_PAYLOAD_SIZE,
+        MSG_SERVICE_RECEIPT_ACK_BATCH => RECORD_PAYLOAD_SIZE,
+        MSG_SERVICE_RECEIPT_ACK_BATCH_ACK => CONTROL_PAYLOAD_SIZE,
+        MSG_CHUNK_RESPONSE | MSG_CHUNK_ADVERTISE => MAX_PAYLOAD_SIZE,
+        MSG_DHT_GET | MSG_DHT_PUT_RESPONSE | MSG_DHT_FIND_NODE => CONTROL_PAYLOAD_SIZE,
+        MSG_DHT_GET_RESPONSE | MSG_DHT_PUT | MSG_DHT_FIND_NODE_RESPONSE => RECORD_PAYLOAD_SIZE,
+        MSG_DHT_CIRCUIT_LOOKUP => CONTROL_PAYLOAD_SIZE,
+        MSG_DHT_CIRCUIT_LOOKUP_RESPONSE => RECORD_PAYLOAD_SIZE,
+        MSG_ESTABLISH_INTRO..=MSG_RENDEZVOUS_TEARDOWN => MAX_PAYLOAD_SIZE,
+        MSG_MLS_WELCOME..=MSG_MLS_KEY_PACKAGE => MAX_PAYLOAD_SIZE,
+        MSG_FROST_DKG_ROUND1..=MSG_QUORUM_RESULT => RECORD_PAYLOAD_SIZE,
+        MSG_MINT_REQUEST | MSG_MINT_RESPONSE => MAX_PAYLOAD_SIZE,
+        MSG_GOSSIP_PUBLISH | MSG_GOSSIP_FORWARD => MAX_PAYLOAD_SIZE,
+        MSG_GOSSIP_PRUNE | MSG_GOSSIP_GRAFT => CONTROL_PAYLOAD_SIZE,
+        MSG_WHISPER_SEND..=MSG_WHISPER_MAILBOX_ACK => MAX_PAYLOAD_SIZE,
+        MSG_ORACLE_REQUEST..=MSG_ORACLE_ATTESTATION => RECORD_PAYLOAD_SIZE,
+        MSG_RECOVERY_REQUEST..=MSG_RECOVERY_COMPLETE => RECORD_PAYLOAD_SIZE,
+        MSG_STATE_SYNC_REQUEST => CONTROL_PAYLOAD_SIZE,
+        MSG_STATE_SYNC_RESPONSE => MAX_PAYLOAD_SIZE,
+        _ => return None,
+    };
+    Some(max)
+}
+
+/// Check that `data` is exactly one well-formed CBOR item within `limits`.
+///
+/// `root` names the item in error paths.
+///
+/// # Errors
+///
+/// - [`ProtocolViolation`] describing the first limit or encoding rule the
+///   input breaks
+pub fn scan(data: &[u8], limits: &DecodeLimits, root: &str) -> Result<(), ProtocolViolation> {
+    let mut scanner = Scanner {
+        data,
+        pos: 0,
+        limits,
+    };
+    let mut path = root.to_string();
+    scanner.item(&mut path, 0)?;
+    let trailing = data.len() - scanner.pos;
+    if trailing > 0 {
+        return Err(ProtocolViolation::new(
+            root,
+            ViolationKind::TrailingBytes(trailing),
+        ));
+    }
+    Ok(())
+}
+
+/// Scan `data` within `max_size` and `limits`, then deserialize it.
+///
+/// # Errors
+///
+/// - [`ProtocolViolation`] if the scan fails or the input does not match
+///   the schema of `T`
+pub fn decode<T: DeserializeOwned>(
+    data: &[u8],
+    max_size: usize,
+    limits: &DecodeLimits,
+    root: &str,
+) -> Result<T, ProtocolViolation> {
+    if data.len() > max_size {
+        return Err(ProtocolViolation::new(
+            root,
+            ViolationKind::TooLarge {
+                size: data.len(),
+                max: max_size,
+            },
+        ));
+    }
+    scan(data, limits, root)?;
+    ciborium::from_reader(data)
+        .map_err(|e| ProtocolViolation::new(root, ViolationKind::Malformed(e.to_string())))
+}
+
+/// Decode a payload as the message type named by its envelope.
+///
+/// # Errors
+///
+/// - [`ViolationKind::UnknownMessageType`] if `msg_type` is not registered
+/// - [`ViolationKind::TypeMismatch`] if the payload decodes as another type
+/// - any scan or schema violation from [`decode`]
+pub fn decode_payload(
+    msg_type: u16,
+    payload: &[u8],
+    limits: &DecodeLimits,
+) -> Result<TypedMessage, ProtocolViolation> {
+    let max = max_payload_size(msg_type).ok_or_else(|| {
+        ProtocolViolation::new("msg_type", ViolationKind::UnknownMessageType(msg_type))
+    })?;
+    let msg: TypedMessage = decode(payload, max, limits, "payload")?;
+    if CANONICAL_MESSAGE_TYPES.contains(&msg_type) && !crate::cbor::is_canonical(payload) {
+        return Err(ProtocolViolation::new(
+            "payload",
+            ViolationKind::NonCanonical,
+        ));
+    }
+    if msg.msg_type() != msg_type {
+        return Err(ProtocolViolation::new(
+            "payload",
+            ViolationKind::TypeMismatch {
+                expected: msg_type,
+                actual: msg.msg_type(),
+            },
+        ));
+    }
+    Ok(msg)
+}
+
+/// Bounded single-pass CBOR walker.
+struct Scanner<'a> {
+    data: &'a [u8],
+    pos: usize,
+    limits: &'a DecodeLimits,
+}
+
+impl Scanner<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(&mut self, n: usize, path: &str) -> Result<&[u8], ProtocolViolation> {
+        if n > self.remaining() {
+            return Err(ProtocolViolation::new(path, ViolationKind::Truncated));
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    /// Read an item head, returning `(major type, argument)`.
+    fn head(&mut self, path: &str) -> Result<(u8, u64), ProtocolViolation> {
+        let initial = self.take(1, path)?[0];
+        let major = initial >> 5;
+        let info = initial & 0x1f;
+        let arg = match info {
+            0..=23 => u64::from(info),
+            24 => u64::from(self.take(1, path)?[0]),
+            25 => {
+                let b = self.take(2, path)?;
+                u64::from(u16::from_be_bytes([b[0], b[1]]))
+            }
+            26 => {
+                let b = self.take(4, path)?;
+                u64::from(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            }
+            27 => {
+                let b = self.take(8, path)?;
+                u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+            }
+            31 => {
+                return Err(ProtocolViolation::new(
+                    path,
+                    ViolationKind::IndefiniteLength,
+                ))
+            }
+            _ => {
+                return Err(ProtocolViolation::new(
+                    path,
+                    ViolationKind::Malformed(format!("reserved additional info {info}")),
+                ))
+            }
+        };
+        Ok((major, arg))
+    }
+
+    fn collection_len(
+        &self,
+        len: u64,
+        min_item_bytes: usize,
+        path: &str,
+    ) -> Result<usize, ProtocolViolation> {
+        let max = self.limits.max_collection_len;
+        match usize::try_from(len) {
+            Ok(n) if n <= max => {
+                // Each item takes at least one byte.
+                if n.saturating_mul(min_item_bytes) > self.remaining() {
+                    return Err(ProtocolViolation::new(path, ViolationKind::Truncated));
+                }
+                Ok(n)
+            }
+            _ => Err(ProtocolViolation::new(
+                path,
+                ViolationKind::CollectionTooLong { len, max },
+            )),
+        }
+    }
+
+    fn item(&mut self, path: &mut String, depth: usize) -> Result<(), ProtocolViolation> {
+        let (major, arg) = self.head(path)?;
+        match major {
+            // Unsigned and negative integers.
+            0 | 1 => Ok(()),
+            // Byte and text strings.
+            2 | 3 => {
+                let max = self.limits.max_string_len;
+                let len = usize::try_from(arg)
+                    .ok()
+                    .filter(|&n| n <= max)
+                    .ok_or_else(|| {
+                        ProtocolViolation::new(
+                            path.as_str(),
+                            ViolationKind::StringTooLong { len: arg, max },
+                        )
+                    })?;
+                let bytes = self.take(len, path)?;
+                if major == 3 && std::str::from_utf8(bytes).is_err() {
+                    return Err(ProtocolViolation::new(
+                        path.as_str(),
+                        ViolationKind::Malformed("invalid UTF-8".to_string()),
+                    ));
+                }
+                Ok(())
+            }
+            // Arrays.
+            4 => {
+                self.enter(path, depth)?;
+                let len = self.collection_len(arg, 1, path)?;
+                let base = path.len();
+                for i in 0..len {
+                    path.push_str(&format!("[{i}]"));
+                    self.item(path, depth + 1)?;
+                    path.truncate(base);
+                }
+                Ok(())
+            }
+            // Maps; text keys extend the path.
+            5 => {
+                self.enter(path, depth)?;
+                let len = self.collection_len(arg, 2, path)?;
+                let base = path.len();
+                for _ in 0..len {
+                    let key_start = self.pos;
+                    self.item(path, depth + 1)?;
+                    let key = self.text_at(key_start);
+                    path.push_str(&match key {
+                        Some(name) => format!(".{name}"),
+                        None => "{}".to_string(),
+                    });
+                    self.item(path, depth + 1)?;
+                    path.truncate(base);
+                }
+                Ok(())
+            }
+            // Tags wrap a single item.
+            6 => {
+                self.enter(path, depth)?;
+                self.item(path, depth + 1)
+            }
+            // Simple values and floats; the head already consumed them.
+            _ => Ok(()),
+        }
+    }
+
+    fn enter(&self, path: &str, depth: usize) -> Result<(), ProtocolViolation> {
+        let max = self.limits.max_depth;
+        if depth >= max {
+            return Err(ProtocolViolation::new(path, ViolationKind::TooDeep { max }));
+        }
+        Ok(())
+    }
+
+    /// The text string that was just scanned starting at `start`, if any.
+    fn text_at(&self, start: usize) -> Option<&str> {
+        let initial = self.data.get(start)?;
+        if initial >> 5 != 3 {
+            return None;
+        }
+        let header = match initial & 0x1f {
+            0..=23 => 1,
+            24 => 2,
+            25 => 3,
+            26 => 5,
+            _ => 9,
+        };
+        std::str::from_utf8(self.data.get(start + header..self.pos)?).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::cbor;
+    use crate::wire::ProtocolMessage;
+
+    fn mint_request(tokens: usize) -> TypedMessage {
+        TypedMessage::MintRequest(MintRequest {
+            epoch: 1,
+            pik_commitment: [1; 32],
+            minted_amount: 10,
+            groth16_proof: vec![0; 192],
+            receipt_merkle_root: [2; 32],
+            blinded_tokens: vec![vec![3; 32]; tokens],
+            denominations: vec![1; tokens],
+        })
+    }
+
+    #[test]
+    fn test_valid_payload_decodes() {
+        let msg = mint_request(4);
+        let payload = cbor::to_vec(&msg).expect("encode");
+        let decoded =
+            decode_payload(MSG_MINT_REQUEST, &payload, &DecodeLimits::default()).expect("decode");
+        assert_eq!(decoded.msg_type(), MSG_MINT_REQUEST);
+    }
+
+    #[test]
+    fn test_signed_messages_must_be_canonical() {
+        let msg = TypedMessage::DhtPut(DhtPut {
+            key: [1; 32],
+            value: vec![2; 16],
+            ttl_secs: 3600,
+            signature: vec![3; 64],
+            admission: None,
+        });
+        let canonical = msg.to_cbor().expect("encode");
+        decode_payload(MSG_DHT_PUT, &canonical, &DecodeLimits::default()).expect("decode");
+
+        // Field declaration order is not canonical key order
+        let plain = cbor::to_vec(&msg).expect("encode");
+        assert_ne!(plain, canonical);
+        let err = decode_payload(MSG_DHT_PUT, &plain, &DecodeLimits::default())
+            .expect_err("non-canonical");
+        assert_eq!(err.kind, ViolationKind::NonCanonical);
+    }
+
+    #[test]
+    fn test_violation_names_field() {
+        let payload = cbor::to_vec(&mint_request(3)).expect("encode");
+        let limits = DecodeLimits {
+            max_collection_len: 32,
+            ..DecodeLimits::default()
+        };
+        let err = decode_payload(MSG_MINT_REQUEST, &payload, &limits).expect_err("too long");
+        assert_eq!(err.field, "payload.MintRequest.groth16_proof");
+        assert!(matches!(
+            err.kind,
+            ViolationKind::CollectionTooLong { len: 192, max: 32 }
+        ));
+    }
+
+    #[test]
+    fn test_per_type_size_and_mismatch() {
+        let ping = cbor::to_vec(&TypedMessage::Ping(Ping { nonce: [0; 8] })).expect("encode");
+        let err = decode_payload(MSG_PONG, &ping, &DecodeLimits::default()).expect_err("mismatch");
+        assert!(matches!(
+            err.kind,
+            ViolationKind::TypeMismatch {
+                expected: MSG_PONG,
+                actual: MSG_PING
+            }
+        ));
+
+        let big = cbor::to_vec(&mint_request(64)).expect("encode");
+        let err = decode_payload(MSG_PING, &big, &DecodeLimits::default()).expect_err("too big");
+        assert!(matches!(err.kind, ViolationKind::TooLarge { .. }));
+
+        let err = decode_payload(0x7777, &ping, &DecodeLimits::default()).expect_err("unknown");
+        assert_eq!(err.field, "msg_type");
+    }
+
+    #[test]
+    fn test_hostile_headers_rejected_without_allocation() {
+        let limits = DecodeLimits::default();
+        // Array claiming u64::MAX elements.
+        let err = scan(
+            &[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            &limits,
+            "x",
+        )
+        .expect_err("huge array");
+        assert!(matches!(err.kind, ViolationKind::CollectionTooLong { .. }));
+        // Byte string claiming more bytes than are present.
+        let err = scan(&[0x59, 0x10, 0x00, 0x01], &limits, "x").expect_err("short");
+        assert_eq!(err.kind, ViolationKind::Truncated);
+        // Indefinite-length array.
+        let err = scan(&[0x9f, 0x01, 0xff], &limits, "x").expect_err("indefinite");
+        assert_eq!(err.kind, ViolationKind::IndefiniteLength);
+        // Trailing garbage.
+        let err = scan(&[0x01, 0x02], &limits, "x").expect_err("trailing");
+        assert_eq!(err.kind, ViolationKind::TrailingBytes(1));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let nested = [vec![0x81u8; 40], vec![0x00]].concat();
+        let err = scan(&nested, &DecodeLimits::default(), "payload").expect_err("deep");
+        assert_eq!(err.kind, ViolationKind::TooDeep { max: 16 });
+        assert!(err.field.starts_with("payload[0][0]"));
+    }
+
+    #[test]
+    fn test_mutated_input_never_panics() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x0c4a);
+        let envelope = ProtocolMessage::from_typed(&mint_request(8))
+            .expect("envelope")
+            .to_bytes()
+            .expect("encode");
+        for _ in 0..2000 {
+            let mut input = envelope.clone();
+            for _ in 0..rng.gen_range(1..8) {
+                let i = rng.gen_range(0..input.len());
+                input[i] = rng.gen();
+            }
+            input.truncate(rng.gen_range(0..=input.len()));
+            if let Ok(msg) = ProtocolMessage::from_bytes_strict(&input) {
+                let _ = msg.decode_payload_strict();
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Node-addressed message transport.
+//!
+//! [`Transport`] is the narrow interface protocol logic needs from the
+//! network: send a typed message to a peer by node ID, and receive the next
+//! message addressed to this node. Every message crosses the transport as a
+//! CBOR [`ProtocolMessage`](crate::wire::ProtocolMessage) and is decoded in
+//! strict mode on receipt, so code written against the trait exercises the
+//! real wire encoding whatever carries the bytes.
+//!
+//! With the `test-harness` feature, [`memory`](crate::memory) provides an
+//! in-memory implementation for deterministic multi-node tests.
+
+use std::future::Future;
+
+use crate::messages::TypedMessage;
+use crate::Result;
+
+/// A message received from a peer.
+#[derive(Clone, Debug)]
+pub struct Received {
+    /// Node ID of the sender.
+    pub from: [u8; 32],
+    /// Message ID from the envelope.
+    pub msg_id: [u8; 16],
+    /// The decoded message.
+    pub message: TypedMessage,
+}
+
+/// Sends and receives typed messages between nodes.
+pub trait Transport: Send + Sync {
+    /// Node ID of this endpoint.
+    fn local_id(&self) -> [u8; 32];
+
+    /// Send `message` to the node `to`.
+    ///
+    /// Delivery is not acknowledged; a successful return means the message
+    /// was handed to the network.
+    fn send(&self, to: [u8; 32], message: &TypedMessage)
+        -> impl Future<Output = Result<()>> + Send;
+
+    /// Wait for the next message addressed to this node.
+    fn recv(&self) -> impl Future<Output = Result<Received>> + Send;
+}
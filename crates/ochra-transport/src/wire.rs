@@ -20,6 +20,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::cbor;
 use crate::messages::TypedMessage;
+use crate::strict::{self, DecodeLimits, ProtocolViolation};
 use crate::TransportError;
 
 /// Current Ochra protocol version.
@@ -29,6 +30,10 @@ pub const PROTOCOL_VERSION: u8 = 5;
 /// Slightly less than the Sphinx packet body to leave room for overhead.
 pub const MAX_PAYLOAD_SIZE: usize = 65536;
 
+/// Maximum encoded envelope size. Payload bytes encode as CBOR integers of
+/// up to two bytes each, plus the fixed envelope fields.
+pub const MAX_MESSAGE_SIZE: usize = 2 * MAX_PAYLOAD_SIZE + 256;
+
 /// Protocol message envelope.
 ///
 /// All messages exchanged between Ochra peers are wrapped in this envelope.
@@ -58,7 +63,7 @@ impl ProtocolMessage {
     /// Returns [`TransportError::Serialization`] if the payload cannot be
     /// CBOR-serialized.
     pub fn from_typed(msg: &TypedMessage) -> Result<Self, TransportError> {
-        let payload = cbor::to_vec(msg)?;
+        let payload = msg.to_cbor()?;
         let mut msg_id = [0u8; 16];
         rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut msg_id);
 
@@ -113,6 +118,17 @@ impl ProtocolMessage {
         cbor::from_slice(&self.payload)
     }
 
+    /// Decode the payload in strict mode, before dispatching it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ProtocolViolation`] if the message type is unknown, the
+    /// payload exceeds that type's size limit or the default
+    /// [`DecodeLimits`], or it decodes as a different message type.
+    pub fn decode_payload_strict(&self) -> Result<TypedMessage, ProtocolViolation> {
+        strict::decode_payload(self.msg_type, &self.payload, &DecodeLimits::default())
+    }
+
     /// Serialize this protocol message to CBOR bytes for transmission.
     ///
     /// # Errors
@@ -137,6 +153,23 @@ impl ProtocolMessage {
         Ok(msg)
     }
 
+    /// Deserialize an envelope from untrusted bytes in strict mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ProtocolViolation`] if the bytes exceed
+    /// [`MAX_MESSAGE_SIZE`], break the default [`DecodeLimits`], do not match
+    /// the envelope schema, or fail [`validate`](Self::validate).
+    pub fn from_bytes_strict(data: &[u8]) -> Result<Self, ProtocolViolation> {
+        let msg: Self =
+            strict::decode(data, MAX_MESSAGE_SIZE, &DecodeLimits::default(), "envelope")?;
+        msg.validate().map_err(|e| ProtocolViolation {
+            field: "envelope".to_string(),
+            kind: strict::ViolationKind::Malformed(e.to_string()),
+        })?;
+        Ok(msg)
+    }
+
     /// Validate the protocol message envelope.
     ///
     /// # Errors
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TagNamespace } from "./TagNamespace";
+
+/**
+ * Tag counts for one value of a Space's catalog.
+ */
+export type CatalogFacet = { namespace: TagNamespace, value: string, 
+/**
+ * Live (not tombstoned) items carrying the tag.
+ */
+count: number, };
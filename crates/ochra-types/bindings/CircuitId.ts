@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Groth16 circuit (Section 31).
+ */
+export type CircuitId = "minting" | "zk-por" | "refund" | "content-key";
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CircuitId } from "./CircuitId";
+
+/**
+ * A specific key version of a circuit.
+ */
+export type CircuitVersion = { circuit: CircuitId, 
+/**
+ * Increments with every Phase 2 ceremony output.
+ */
+version: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `remove_contact`, `get_safety_number` and
+ * `unverify_contact`.
+ */
+export type ContactPikParams = { contact_pik: string, };
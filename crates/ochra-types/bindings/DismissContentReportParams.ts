@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `dismiss_content_report`.
+ */
+export type DismissContentReportParams = { group_id: string, content_hash: string, };
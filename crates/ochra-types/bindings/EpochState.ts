@@ -13,6 +13,10 @@ holder_balances_root: string,
  * BLAKE3 of Bloom filter snapshot.
  */
 nullifier_bloom_hash: string, 
+/**
+ * Sparse Merkle nullifier accumulator root, if the quorum maintains one.
+ */
+nullifier_smt_root: string | null, 
 /**
  * Top 100 (or quorum size).
  */
@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExitClass } from "./ExitClass";
+
+/**
+ * A relay's exit policy (Section 4.9).
+ *
+ * The default accepts every class at any rate, which is what a relay
+ * that predates exit policies does.
+ */
+export type ExitPolicy = { 
+/**
+ * Classes the relay delivers as an exit. Empty = never an exit.
+ */
+allowed_classes: Array<ExitClass>, 
+/**
+ * Bandwidth one circuit may use through the exit, in kbit/s.
+ * 0 = limited only by `bandwidth_cap_mbps`.
+ */
+max_circuit_kbps: number, 
+/**
+ * Refuse payloads that ask the exit to store data, such as DHT puts
+ * and Whisper mailbox deposits.
+ */
+no_storage: boolean, };
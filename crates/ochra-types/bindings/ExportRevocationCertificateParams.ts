@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `export_revocation_certificate`: "unspecified" (default),
+ * "key_compromise" or "superseded".
+ */
+export type ExportRevocationCertificateParams = { reason: string | null, };
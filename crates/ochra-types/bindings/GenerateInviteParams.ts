@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `generate_invite`.
+ */
+export type GenerateInviteParams = { group_id: string, 
+/**
+ * Omitted = unlimited.
+ */
+uses: number | null, 
+/**
+ * Default 7.
+ */
+ttl_days: number | null, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of methods that take only a Space: `leave_group`,
+ * `get_active_invites`, `get_group_members`,
+ * `veto_ownership_transfer`, `get_group_policy`, `get_announcements`,
+ * `update_group_profile`, `set_group_notification_settings`,
+ * `get_space_stats`, `get_group_storage`, `get_space_activity` and
+ * `get_content_reports`.
+ */
+export type GroupIdParams = { group_id: string, };
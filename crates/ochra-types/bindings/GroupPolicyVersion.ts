@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupPolicy } from "./GroupPolicy";
+
+/**
+ * An entry of `get_group_policy_history`.
+ */
+export type GroupPolicyVersion = { version: bigint, signer_pik: string, received_at: bigint, policy: GroupPolicy, };
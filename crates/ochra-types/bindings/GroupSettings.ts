@@ -1,8 +1,14 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContentRating } from "./ContentRating";
 import type { InvitePermission } from "./InvitePermission";
+import type { JoinRule } from "./JoinRule";
 import type { PublishPolicy } from "./PublishPolicy";
 
 /**
  * Space settings (Section 22.2).
  */
-export type GroupSettings = { invite_permission: InvitePermission, publish_policy: PublishPolicy, };
+export type GroupSettings = { invite_permission: InvitePermission, publish_policy: PublishPolicy, join_rule: JoinRule, 
+/**
+ * Most mature rating content published to the Space may carry.
+ */
+max_content_rating: ContentRating, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `update_group_settings`.
+ */
+export type GroupSettingsUpdated = { updated: boolean, 
+/**
+ * Policy version the settings were published as.
+ */
+version: bigint, };
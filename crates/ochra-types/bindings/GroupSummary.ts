@@ -5,4 +5,8 @@ import type { SpaceTemplate } from "./SpaceTemplate";
 /**
  * Summary of a Space for listing views (Section 22.2).
  */
-export type GroupSummary = { group_id: string, name: string, icon: string | null, template: SpaceTemplate, is_host: boolean, role: MemberRole, member_count: number, last_activity_at: bigint, unread: boolean, pinned: boolean, };
+export type GroupSummary = { group_id: string, name: string, icon: string | null, template: SpaceTemplate, is_host: boolean, role: MemberRole, member_count: number, last_activity_at: bigint, unread: boolean, 
+/**
+ * Unread owner announcements (Section 8.12).
+ */
+unread_announcements: number, pinned: boolean, };
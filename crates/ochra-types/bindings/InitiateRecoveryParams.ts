@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `initiate_recovery`.
+ */
+export type InitiateRecoveryParams = { 
+/**
+ * Hex-encoded shares.
+ */
+guardian_shares: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How invitees become members (Section 8.11).
+ */
+export type JoinRule = "invite" | "approval";
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SectionType } from "./SectionType";
+import type { SpaceTemplate } from "./SpaceTemplate";
+
+/**
+ * A layout manifest problem (Section 22.9).
+ *
+ * Serialized with a `code` tag so the UI can point at the offending section.
+ */
+export type LayoutError = { "code": "unsupported_version", version: number, } | { "code": "no_sections" } | { "code": "too_many_sections", count: number, max: number, } | { "code": "section_not_allowed", section: number, section_type: SectionType, layout_type: SpaceTemplate, } | { "code": "title_too_long", section: number, len: number, max: number, } | { "code": "invalid_max_items", section: number, max_items: number, max: number, } | { "code": "too_many_filter_tags", section: number, count: number, max: number, } | { "code": "too_many_pinned", section: number, count: number, max: number, } | { "code": "unknown_content", section: number, content_hash: string, } | { "code": "css_too_large", len: number, max: number, } | { "code": "css_forbidden", construct: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `update_group_layout_manifest`.
+ */
+export type LayoutManifestUpdated = { updated: boolean, layout_manifest_hash: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RenderableLayout } from "./RenderableLayout";
+
+/**
+ * Result of `preview_layout_manifest`.
+ */
+export type LayoutPreview = { layout: RenderableLayout, schema_version: number, 
+/**
+ * Schema version the manifest was migrated from, if it was.
+ */
+migrated_from: number | null, };
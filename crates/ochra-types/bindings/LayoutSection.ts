@@ -4,4 +4,8 @@ import type { SectionType } from "./SectionType";
 /**
  * Layout section (Section 22.9).
  */
-export type LayoutSection = { section_type: SectionType, title: string | null, max_items: number | null, filter_tags: Array<string> | null, };
+export type LayoutSection = { section_type: SectionType, title: string | null, max_items: number | null, filter_tags: Array<string> | null, 
+/**
+ * Content shown first in the section, in order (schema 2).
+ */
+pinned_content: string[], };
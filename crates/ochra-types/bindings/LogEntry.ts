@@ -1,7 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { LogLevel } from "./LogLevel";
+import type { LogSubsystem } from "./LogSubsystem";
 
 /**
  * Log entry (Section 22.5).
  */
-export type LogEntry = { timestamp: bigint, level: LogLevel, module: string, message: string, };
+export type LogEntry = { timestamp: bigint, level: LogLevel, module: string, message: string, subsystem: LogSubsystem, 
+/**
+ * Set when the entry was logged while serving an RPC request.
+ */
+correlation_id: string | null, 
+/**
+ * Structured fields recorded alongside the message.
+ */
+fields: { [key in string]?: string }, };
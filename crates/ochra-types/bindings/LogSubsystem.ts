@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Subsystem whose log ring holds an entry.
+ */
+export type LogSubsystem = "transport" | "dht" | "onion" | "economy" | "daemon";
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Store-and-forward mailbox advertised in a handle descriptor.
+ */
+export type MailboxEntry = { 
+/**
+ * Linked device that owns the mailbox.
+ */
+device_id: string, 
+/**
+ * Relay holding the mailbox.
+ */
+relay_node_id: string, 
+/**
+ * Ed25519 key that addresses and authorizes the mailbox.
+ */
+mailbox_pk: string, 
+/**
+ * X25519 key envelopes are sealed to.
+ */
+seal_pk: string, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `get_operational_key` and `rotate_operational_key`.
+ */
+export type OperationalKey = { subkey_pk: string, scope: Array<string>, valid_from_epoch: number, valid_until_epoch: number, 
+/**
+ * Hex-encoded delegation certificate.
+ */
+certificate: string, 
+/**
+ * Whether the certificate covers this epoch and the subkey is in the
+ * key store; null from `rotate_operational_key`.
+ */
+usable: boolean | null, };
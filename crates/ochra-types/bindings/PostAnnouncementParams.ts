@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `post_announcement`.
+ */
+export type PostAnnouncementParams = { group_id: string, title: string, body: string | null, pinned: boolean | null, };
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Presence signals a node sends to Whisper peers (Section 7.4).
+ *
+ * Advertised in the handle descriptor so peers know which signals will
+ * never arrive.
+ */
+export type PresencePrivacy = { typing_indicators: boolean, read_receipts: boolean, 
+/**
+ * Active/away state; typing indicators also require it.
+ */
+online_presence: boolean, };
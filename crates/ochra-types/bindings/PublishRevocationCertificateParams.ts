@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `publish_revocation_certificate`.
+ */
+export type PublishRevocationCertificateParams = { 
+/**
+ * Hex-encoded certificate.
+ */
+certificate: string, };
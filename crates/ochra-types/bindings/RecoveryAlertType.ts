@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Stage of a recovery attempt reported to the account owner.
+ */
+export type RecoveryAlertType = "recovery_initiated" | "recovery_vetoed" | "recovery_complete";
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `replace_guardian`.
+ */
+export type ReplaceGuardianParams = { old_pik: string, new_pik: string, };
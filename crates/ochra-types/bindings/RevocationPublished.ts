@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `publish_revocation_certificate`.
+ */
+export type RevocationPublished = { published: boolean, dht_address: string, };
@@ -0,0 +1,34 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Timing breakdown of one served RPC call (Section 22.5).
+ */
+export type SlowCall = { 
+/**
+ * Correlation ID of the request, as recorded on its log entries.
+ */
+correlation_id: string, method: string, 
+/**
+ * Unix time the call was received.
+ */
+started_at: bigint, total_us: bigint, 
+/**
+ * Checking whether the session is unlocked.
+ */
+auth_us: bigint, 
+/**
+ * Waiting for and holding the database.
+ */
+db_us: bigint, 
+/**
+ * Opening outbound connections, including waiting for a dial permit.
+ */
+network_us: bigint, 
+/**
+ * Time not attributed to any phase.
+ */
+other_us: bigint, 
+/**
+ * JSON-RPC error code, if the call failed.
+ */
+error_code: number | null, };
@@ -0,0 +1,36 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Quorum-signed summary of the network state at an epoch, for relays
+ * syncing mid-epoch (Section 12.11).
+ */
+export type StateCheckpoint = { epoch: number, 
+/**
+ * BLAKE3 of the nullifier Bloom filter bytes.
+ */
+nullifier_bloom_hash: string, 
+/**
+ * Nullifiers inserted into the filter.
+ */
+nullifier_count: bigint, 
+/**
+ * VYS accumulator root (`holder_balances_root` of the epoch's
+ * `EpochState`).
+ */
+vys_accumulator_root: string, 
+/**
+ * BLAKE3 of the epoch's encoded `EpochState`.
+ */
+epoch_state_hash: string, 
+/**
+ * Relay epoch of the directory snapshot.
+ */
+directory_epoch: number, 
+/**
+ * Directory digest of the relay descriptor snapshot.
+ */
+directory_digest: string, 
+/**
+ * FROST signature over the fields above.
+ */
+quorum_sig: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How much an unknown sender must spend to reach a Whisper recipient
+ * (Section 7.6).
+ *
+ * Each level names an Argon2id puzzle difficulty and a Seed payment that
+ * may be attached instead.
+ */
+export type ThrottleStrictness = "off" | "low" | "standard" | "high";
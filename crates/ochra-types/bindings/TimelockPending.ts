@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `initiate_recovery` and `transfer_group_ownership`.
+ */
+export type TimelockPending = { 
+/**
+ * Always "pending".
+ */
+status: string, veto_window_ends: bigint, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `transfer_group_ownership`.
+ */
+export type TransferGroupOwnershipParams = { group_id: string, new_owner_pik: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Params of `verify_contact`.
+ */
+export type VerifyContactParams = { contact_pik: string, scanned_qr: string | null, };
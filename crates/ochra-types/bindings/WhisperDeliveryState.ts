@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Delivery state of an outbound Whisper message (Section 7.4).
+ *
+ * States only move forward: `queued → sent → delivered → read`. A message
+ * whose retries run out becomes `failed`; a late ack can still move it to
+ * `delivered` or `read`.
+ */
+export type WhisperDeliveryState = "queued" | "sent" | "delivered" | "read" | "failed";
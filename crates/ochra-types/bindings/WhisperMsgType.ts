@@ -3,4 +3,4 @@
 /**
  * Whisper message types (Section 22.4).
  */
-export type WhisperMsgType = { "type": "text" } | { "type": "seed_transfer", tx_hash: string, amount: bigint, } | { "type": "typing" } | { "type": "read_ack" };
+export type WhisperMsgType = { "type": "text" } | { "type": "seed_transfer", tx_hash: string, amount: bigint, } | { "type": "typing" } | { "type": "read_ack" } | { "type": "attachment", content_hash: string, size: bigint, };
@@ -0,0 +1,98 @@
+//! Export the TypeScript bindings consumed by the UI.
+//!
+//! Usage:
+//!   export-bindings [dir]           # Write bindings and index.ts (default: <repo>/bindings)
+//!   export-bindings --check [dir]   # Fail if the bindings in dir are out of date
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use ochra_types::bindings;
+
+/// Read every `.ts` file in `dir`, keyed by file name.
+fn read_bindings(dir: &Path) -> std::io::Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "ts") {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            files.insert(name, std::fs::read_to_string(&path)?);
+        }
+    }
+    Ok(files)
+}
+
+/// Compare `dir` against a fresh export, returning the files that differ.
+fn check(dir: &Path) -> Result<Vec<String>, bindings::BindingsError> {
+    let fresh_dir = std::env::temp_dir().join(format!("ochra-bindings-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&fresh_dir);
+    bindings::export_all(&fresh_dir)?;
+    let fresh = read_bindings(&fresh_dir)?;
+    let _ = std::fs::remove_dir_all(&fresh_dir);
+    let current = read_bindings(dir).unwrap_or_default();
+
+    let mut stale: Vec<String> = fresh
+        .iter()
+        .filter(|(name, content)| current.get(*name) != Some(content))
+        .map(|(name, _)| name.clone())
+        .collect();
+    stale.extend(
+        current
+            .keys()
+            .filter(|name| !fresh.contains_key(*name))
+            .map(|name| format!("{name} (no longer exported)")),
+    );
+    Ok(stale)
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let check_mode = args.first().is_some_and(|a| a == "--check");
+    if check_mode {
+        args.remove(0);
+    }
+    let dir = args
+        .first()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../bindings"));
+
+    if check_mode {
+        match check(&dir) {
+            Ok(stale) if stale.is_empty() => {
+                println!("Bindings in {} are up to date", dir.display());
+                ExitCode::SUCCESS
+            }
+            Ok(stale) => {
+                eprintln!("Bindings in {} are out of date:", dir.display());
+                for name in stale {
+                    eprintln!("  {name}");
+                }
+                eprintln!("Run `cargo run -p ochra-types --bin export-bindings` to regenerate.");
+                ExitCode::FAILURE
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                ExitCode::FAILURE
+            }
+        }
+    } else {
+        match bindings::export_all(&dir) {
+            Ok(()) => {
+                println!(
+                    "Exported {} types to {}",
+                    bindings::REGISTRY.len(),
+                    dir.display()
+                );
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! TypeScript binding export.
+//!
+//! [`REGISTRY`] lists every type deriving `ts_rs::TS`. The `export-bindings`
+//! binary writes each of them, plus an `index.ts` barrel re-exporting all of
+//! them, to the repository's `bindings/` directory for the UI to import.
+//! A type that derives `TS` without being registered is reported by
+//! [`unregistered`], so the UI cannot silently miss a daemon type.
+
+use std::path::Path;
+
+use ts_rs::{ExportError, TS};
+
+/// Source directory of this crate, scanned for `TS` derives.
+pub const SOURCE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+/// Name of the barrel file.
+pub const INDEX_FILE: &str = "index.ts";
+
+/// Errors from exporting bindings.
+#[derive(Debug, thiserror::Error)]
+pub enum BindingsError {
+    /// ts-rs failed to render or write a type.
+    #[error("export failed: {0}")]
+    Export(#[from] ExportError),
+    /// Reading sources or writing the barrel failed.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Types derive `TS` but are missing from [`REGISTRY`].
+    #[error("types missing from the bindings registry: {}", .0.join(", "))]
+    Unregistered(Vec<String>),
+}
+
+/// A type exported to TypeScript.
+pub struct Binding {
+    /// TypeScript type name, also the file stem.
+    pub name: &'static str,
+    export: fn(&Path) -> Result<(), ExportError>,
+}
+
+impl Binding {
+    /// Write this type and its dependencies to `dir`.
+    pub fn export_to(&self, dir: &Path) -> Result<(), ExportError> {
+        (self.export)(dir)
+    }
+}
+
+fn export<T: TS + 'static>(dir: &Path) -> Result<(), ExportError> {
+    T::export_all_to(dir)
+}
+
+macro_rules! registry {
+    ($($module:ident :: $ty:ident),* $(,)?) => {
+        /// Every type deriving `TS`, by module.
+        pub const REGISTRY: &[Binding] = &[
+            $(Binding {
+                name: stringify!($ty),
+                export: export::<crate::$module::$ty>,
+            },)*
+        ];
+    };
+}
+
+registry![
+    content::ContentManifest,
+    content::PricingTier,
+    content::TierType,
+    content::PurchaseRecord,
+    content::ReceiptInfo,
+    content::AccessStatus,
+    content::EarningsReport,
+    content::ContentEarning,
+    content::RefundStatus,
+    content::RefundState,
+    content::FlushStats,
+    content::MpcSession,
+    content::MpcStatus,
+    content::RevenueSplitChangeProposal,
+    content::TagNamespace,
+    content::ContentTag,
+    content::CatalogFacet,
+    diagnostics::CircuitMetrics,
+    diagnostics::NatStatus,
+    diagnostics::CoverTrafficMetrics,
+    diagnostics::CoverTrafficMode,
+    diagnostics::UpdateStatus,
+    diagnostics::LogEntry,
+    diagnostics::LogLevel,
+    diagnostics::LogSubsystem,
+    diagnostics::SlowCall,
+    diagnostics::PorSubmissionStatus,
+    diagnostics::PorStatus,
+    events::Event,
+    events::DaemonEvent,
+    events::MemberLeftReason,
+    events::TransferCancelReason,
+    events::RecoveryAlertType,
+    events::WhisperSessionEndReason,
+    governance::RevenueSplit,
+    governance::UpgradeManifest,
+    governance::CircuitId,
+    governance::CircuitVersion,
+    governance::CircuitArtifact,
+    governance::PlatformHash,
+    governance::Platform,
+    governance::MultisigEntry,
+    governance::RollbackManifest,
+    governance::GenesisManifest,
+    governance::GenesisAllocation,
+    identity::PikMeta,
+    identity::Contact,
+    identity::PeerProfile,
+    identity::MemberRole,
+    identity::GuardianStatus,
+    identity::ProfileKeyExchange,
+    identity::TimelockStatus,
+    identity::TimelockAction,
+    identity::ContactExchangeToken,
+    layout::LayoutConfig,
+    layout::LayoutSection,
+    layout::SectionType,
+    layout::LayoutError,
+    layout::RenderableLayout,
+    layout::RenderedSection,
+    layout::NotificationSettings,
+    layout::DownloadProgress,
+    layout::DownloadState,
+    network::ServiceReceipt,
+    network::RelayDescriptor,
+    network::ExitClass,
+    network::ExitPolicy,
+    network::EpochState,
+    network::QuorumKeyEntry,
+    network::EpochBeacon,
+    network::StateCheckpoint,
+    network::PoSrvEntry,
+    network::NullifierGossipMsg,
+    profile::NetworkKind,
+    profile::NetworkProfile,
+    rpc::FieldError,
+    rpc::PasswordParams,
+    rpc::ChangePasswordParams,
+    rpc::RotateOperationalKeyParams,
+    rpc::UpdateDisplayNameParams,
+    rpc::ExportRevocationCertificateParams,
+    rpc::PublishRevocationCertificateParams,
+    rpc::NominateGuardianParams,
+    rpc::ReplaceGuardianParams,
+    rpc::InitiateRecoveryParams,
+    rpc::VetoRecoveryParams,
+    rpc::AddContactParams,
+    rpc::GenerateContactTokenParams,
+    rpc::ContactPikParams,
+    rpc::VerifyContactParams,
+    rpc::PikCreated,
+    rpc::Authenticated,
+    rpc::MyPik,
+    rpc::OperationalKey,
+    rpc::BiometricEnrollment,
+    rpc::RevocationCertificateExport,
+    rpc::RevocationPublished,
+    rpc::TimelockPending,
+    rpc::ContactAdded,
+    rpc::ContactToken,
+    rpc::ContactListEntry,
+    rpc::SafetyNumberInfo,
+    rpc::CreateGroupParams,
+    rpc::JoinGroupParams,
+    rpc::GroupIdParams,
+    rpc::GroupMemberParams,
+    rpc::GenerateInviteParams,
+    rpc::RevokeInviteParams,
+    rpc::TransferGroupOwnershipParams,
+    rpc::UpdateGroupSettingsParams,
+    rpc::GroupPolicyHistoryParams,
+    rpc::PostAnnouncementParams,
+    rpc::PinAnnouncementParams,
+    rpc::MarkAnnouncementsReadParams,
+    rpc::CreateSubgroupParams,
+    rpc::SubgroupIdParams,
+    rpc::PreviewLayoutManifestParams,
+    rpc::UpdateGroupLayoutManifestParams,
+    rpc::SetGroupQuotaParams,
+    rpc::DismissContentReportParams,
+    rpc::ReportContentParams,
+    rpc::OwnerTombstoneContentParams,
+    rpc::ExportSpaceArchiveParams,
+    rpc::ImportSpaceArchiveParams,
+    rpc::GroupCreated,
+    rpc::GroupSettingsUpdated,
+    rpc::GroupPolicyInfo,
+    rpc::GroupPolicyVersion,
+    rpc::AnnouncementPosted,
+    rpc::AnnouncementsMarked,
+    rpc::LayoutPreview,
+    rpc::LayoutManifestUpdated,
+    space::GroupSummary,
+    space::SpaceTemplate,
+    space::GroupSettings,
+    space::InvitePermission,
+    space::PublishPolicy,
+    space::JoinRule,
+    space::ContentRating,
+    space::GroupPolicy,
+    space::InviteInfo,
+    space::AnnouncementInfo,
+    space::SpaceStats,
+    space::EarningsTrend,
+    space::ActivityEvent,
+    space::ContentReport,
+    space::SingleReport,
+    space::ReportReason,
+    space::SpaceManifest,
+    space::OwnershipTransferRecord,
+    space::CatalogDiffRequest,
+    space::CatalogDiffResponse,
+    whisper::HandleDescriptor,
+    whisper::MailboxEntry,
+    whisper::PresencePrivacy,
+    whisper::ThrottleStrictness,
+    whisper::PresenceOverride,
+    whisper::IntroPointEntry,
+    whisper::HandleStatus,
+    whisper::HandleRegistration,
+    whisper::HandleInfo,
+    whisper::WhisperMessage,
+    whisper::WhisperMsgType,
+    whisper::RelayReceipt,
+    whisper::WhisperTarget,
+    whisper::WhisperSessionSummary,
+    whisper::SessionState,
+    whisper::WhisperDeliveryState,
+    whisper::WhisperCounterparty,
+    whisper::ThrottleStatus,
+    whisper::IdentityReveal,
+    whisper::IdentityProof,
+    whisper::DeprecationTombstone,
+    whisper::HandleTransition,
+    whisper::HandleTransitionKind,
+    whisper::WhisperPing,
+];
+
+/// Names of the types in `source` that derive `TS`.
+pub fn derived_types(source: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("#[derive(") {
+        rest = &rest[start + "#[derive(".len()..];
+        let Some(end) = rest.find(")]") else {
+            break;
+        };
+        let derives_ts = rest[..end]
+            .split(',')
+            .map(str::trim)
+            .any(|d| d == "TS" || d.ends_with("::TS"));
+        rest = &rest[end..];
+        if !derives_ts {
+            continue;
+        }
+        let mut item = rest
+            .split_whitespace()
+            .skip_while(|t| *t != "struct" && *t != "enum");
+        if let Some(ident) = item.nth(1) {
+            let name: String = ident
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Types deriving `TS` in the `.rs` files of `src_dir` and its submodule
+/// directories that are missing from [`REGISTRY`], sorted. Test modules at
+/// the end of a file are skipped.
+pub fn unregistered(src_dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut missing = Vec::new();
+    for entry in std::fs::read_dir(src_dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            missing.extend(unregistered(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            let source = std::fs::read_to_string(&path)?;
+            let source = source
+                .split_once("#[cfg(test)]")
+                .map_or(source.as_str(), |(items, _)| items);
+            missing.extend(
+                derived_types(source)
+                    .into_iter()
+                    .filter(|name| !REGISTRY.iter().any(|b| b.name == name)),
+            );
+        }
+    }
+    missing.sort();
+    Ok(missing)
+}
+
+/// The `index.ts` barrel re-exporting every registered type.
+pub fn index_ts() -> String {
+    let mut names: Vec<&str> = REGISTRY.iter().map(|b| b.name).collect();
+    names.sort_unstable();
+    let mut out = String::from(
+        "// This file was generated by `cargo run -p ochra-types --bin export-bindings`. \
+         Do not edit this file manually.\n",
+    );
+    for name in names {
+        out.push_str(&format!("export type {{ {name} }} from \"./{name}\";\n"));
+    }
+    out
+}
+
+/// Check the registry against this crate's sources, then write every
+/// registered type and the barrel to `dir`.
+pub fn export_all(dir: &Path) -> Result<(), BindingsError> {
+    let missing = unregistered(Path::new(SOURCE_DIR))?;
+    if !missing.is_empty() {
+        return Err(BindingsError::Unregistered(missing));
+    }
+    std::fs::create_dir_all(dir)?;
+    for binding in REGISTRY {
+        binding.export_to(dir)?;
+    }
+    std::fs::write(dir.join(INDEX_FILE), index_ts())?;
+    Ok(())
+}
@@ -5,6 +5,10 @@ use serde_with::serde_as;
 
 use crate::{Bytes, ContentHash, GroupId, Hash};
 
+pub mod tags;
+
+pub use tags::{CatalogFacet, ContentTag, TagNamespace};
+
 /// Content manifest (Section 22.3).
 #[serde_as]
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
@@ -15,7 +19,7 @@ pub struct ContentManifest {
     pub content_hash: ContentHash,
     pub title: String,
     pub description: Option<String>,
-    /// Max 5 tags.
+    /// Max 5 tags, normalized `namespace:value` form (see [`tags`]).
     pub tags: Vec<String>,
     /// Max 4, min 1.
     pub pricing: Vec<PricingTier>,
@@ -49,6 +53,13 @@ pub struct PricingTier {
     pub price_seeds: u64,
     /// Days for rental tiers.
     pub rental_days: Option<u16>,
+    /// Other content unlocked together with this item (bundle tiers).
+    #[serde(default)]
+    #[ts(type = "Array<string>")]
+    pub bundle_hashes: Vec<ContentHash>,
+    /// Epochs per renewal period (subscription tiers).
+    #[serde(default)]
+    pub renewal_epochs: Option<u16>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
@@ -57,6 +68,10 @@ pub struct PricingTier {
 pub enum TierType {
     Permanent,
     Rental,
+    /// One purchase unlocks several content items.
+    Bundle,
+    /// Access renewed every `renewal_epochs` epochs.
+    Subscription,
 }
 
 /// Purchase record (Section 22.3).
@@ -0,0 +1,292 @@
+//! Discovery tag taxonomy (Section 16.8).
+//!
+//! Tags are `namespace:value` pairs. Free text is normalized so that
+//! "Jazz", " jazz " and "JAZZ" land in the same catalog facet: values are
+//! case-folded, runs of spaces and underscores become a single `-`, and
+//! anything outside letters, digits and `-` is rejected. A tag without a
+//! namespace is a `topic`. Language values are reduced to their ISO 639
+//! primary subtag, so "en-US", "en_GB" and "English" all match
+//! `language:en`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::MAX_CONTENT_TAGS;
+
+/// Maximum length of a normalized tag value, in characters.
+pub const MAX_TAG_VALUE_CHARS: usize = 32;
+
+/// Common language names accepted in place of their ISO 639-1 code.
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("arabic", "ar"),
+    ("chinese", "zh"),
+    ("dutch", "nl"),
+    ("english", "en"),
+    ("french", "fr"),
+    ("german", "de"),
+    ("hindi", "hi"),
+    ("italian", "it"),
+    ("japanese", "ja"),
+    ("korean", "ko"),
+    ("polish", "pl"),
+    ("portuguese", "pt"),
+    ("russian", "ru"),
+    ("spanish", "es"),
+    ("turkish", "tr"),
+];
+
+/// The namespace of a discovery tag.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ts_rs::TS,
+)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TagNamespace {
+    /// Style or category, e.g. `genre:jazz`.
+    Genre,
+    /// Media kind, e.g. `format:ebook`.
+    Format,
+    /// Content language as an ISO 639 code, e.g. `language:en`.
+    Language,
+    /// Anything else; the namespace of an unprefixed tag.
+    Topic,
+}
+
+impl TagNamespace {
+    /// Every namespace, in facet display order.
+    pub const ALL: [Self; 4] = [Self::Genre, Self::Format, Self::Language, Self::Topic];
+
+    /// The namespace's prefix.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Genre => "genre",
+            Self::Format => "format",
+            Self::Language => "language",
+            Self::Topic => "topic",
+        }
+    }
+
+    /// Parse a prefix, ignoring case and surrounding whitespace.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        Self::ALL.into_iter().find(|ns| ns.as_str() == s)
+    }
+}
+
+/// A normalized discovery tag.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct ContentTag {
+    pub namespace: TagNamespace,
+    pub value: String,
+}
+
+impl ContentTag {
+    /// Normalize a raw tag such as `"Genre: Free Jazz"` or `"en-US"`.
+    pub fn parse(raw: &str) -> Result<Self, TagError> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Err(TagError::Empty);
+        }
+        let (namespace, value) = match raw.split_once(':') {
+            Some((ns, value)) => (
+                TagNamespace::parse(ns)
+                    .ok_or_else(|| TagError::UnknownNamespace(ns.to_string()))?,
+                value,
+            ),
+            None => (TagNamespace::Topic, raw),
+        };
+        let value = match namespace {
+            TagNamespace::Language => normalize_language(value)?,
+            _ => normalize_value(value)?,
+        };
+        Ok(Self { namespace, value })
+    }
+
+    /// Whether this tag matches a search term, normalized the same way.
+    ///
+    /// An unprefixed term matches a tag of any namespace with that value,
+    /// so a search for "jazz" finds `genre:jazz` as well as `topic:jazz`.
+    pub fn matches(&self, term: &str) -> bool {
+        let Ok(query) = Self::parse(term) else {
+            return false;
+        };
+        if term.contains(':') {
+            return *self == query;
+        }
+        if self.value == query.value {
+            return true;
+        }
+        self.namespace == TagNamespace::Language
+            && normalize_language(term).is_ok_and(|code| code == self.value)
+    }
+}
+
+impl fmt::Display for ContentTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace.as_str(), self.value)
+    }
+}
+
+/// Normalize the tags of a publish: parse each, drop duplicates, and
+/// enforce [`MAX_CONTENT_TAGS`]. Order is preserved.
+pub fn normalize_tags<S: AsRef<str>>(raw: &[S]) -> Result<Vec<ContentTag>, TagError> {
+    let mut tags: Vec<ContentTag> = Vec::with_capacity(raw.len());
+    for tag in raw {
+        let tag = ContentTag::parse(tag.as_ref())?;
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    if tags.len() > MAX_CONTENT_TAGS {
+        return Err(TagError::TooMany(tags.len()));
+    }
+    Ok(tags)
+}
+
+/// Case-fold and join words with `-`.
+fn normalize_value(value: &str) -> Result<String, TagError> {
+    let mut out = String::with_capacity(value.len());
+    let mut pending_dash = false;
+    for c in value.trim().chars() {
+        if c.is_whitespace() || c == '_' || c == '-' {
+            pending_dash = !out.is_empty();
+        } else if c.is_alphanumeric() {
+            if pending_dash {
+                out.push('-');
+                pending_dash = false;
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            return Err(TagError::InvalidValue(value.to_string()));
+        }
+    }
+    if out.is_empty() {
+        return Err(TagError::Empty);
+    }
+    if out.chars().count() > MAX_TAG_VALUE_CHARS {
+        return Err(TagError::InvalidValue(value.to_string()));
+    }
+    Ok(out)
+}
+
+/// Reduce a language name or BCP 47 locale to its primary subtag.
+fn normalize_language(value: &str) -> Result<String, TagError> {
+    let value = normalize_value(value)?;
+    if let Some((_, code)) = LANGUAGE_NAMES.iter().find(|(name, _)| *name == value) {
+        return Ok((*code).to_string());
+    }
+    let primary = value.split('-').next().unwrap_or_default();
+    if (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_lowercase()) {
+        Ok(primary.to_string())
+    } else {
+        Err(TagError::InvalidLanguage(value))
+    }
+}
+
+/// Tag counts for one value of a Space's catalog.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct CatalogFacet {
+    pub namespace: TagNamespace,
+    pub value: String,
+    /// Live (not tombstoned) items carrying the tag.
+    pub count: u32,
+}
+
+/// Errors normalizing discovery tags.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum TagError {
+    #[error("empty tag")]
+    Empty,
+    #[error("unknown tag namespace: {0}")]
+    UnknownNamespace(String),
+    #[error("invalid tag value: {0}")]
+    InvalidValue(String),
+    #[error("invalid language tag: {0}")]
+    InvalidLanguage(String),
+    #[error("{0} tags exceeds the maximum of {MAX_CONTENT_TAGS}")]
+    TooMany(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(namespace: TagNamespace, value: &str) -> ContentTag {
+        ContentTag {
+            namespace,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_normalization() {
+        for raw in [
+            "Genre: Free Jazz",
+            "genre:free_jazz",
+            " GENRE:free  -  jazz ",
+        ] {
+            assert_eq!(
+                ContentTag::parse(raw),
+                Ok(tag(TagNamespace::Genre, "free-jazz"))
+            );
+        }
+        assert_eq!(
+            ContentTag::parse("Éducation"),
+            Ok(tag(TagNamespace::Topic, "éducation"))
+        );
+        assert_eq!(
+            ContentTag::parse("genre:free-jazz").map(|t| t.to_string()),
+            Ok("genre:free-jazz".to_string())
+        );
+        assert!(matches!(
+            ContentTag::parse("mood:calm"),
+            Err(TagError::UnknownNamespace(_))
+        ));
+        assert!(matches!(
+            ContentTag::parse("topic:c++"),
+            Err(TagError::InvalidValue(_))
+        ));
+        assert_eq!(ContentTag::parse("genre:  "), Err(TagError::Empty));
+    }
+
+    #[test]
+    fn test_language_tags_match_by_locale() {
+        for raw in ["language:en-US", "language:EN_gb", "language:English"] {
+            assert_eq!(
+                ContentTag::parse(raw),
+                Ok(tag(TagNamespace::Language, "en"))
+            );
+        }
+        assert!(matches!(
+            ContentTag::parse("language:klingon"),
+            Err(TagError::InvalidLanguage(_))
+        ));
+
+        let english = tag(TagNamespace::Language, "en");
+        assert!(english.matches("en-AU"));
+        assert!(english.matches("english"));
+        assert!(english.matches("language:en-US"));
+        assert!(!english.matches("topic:en"));
+        assert!(!english.matches("fr"));
+        assert!(tag(TagNamespace::Genre, "jazz").matches("Jazz"));
+    }
+
+    #[test]
+    fn test_normalize_tags_dedupes_and_limits() {
+        let tags = normalize_tags(&["Jazz", "topic:jazz", "format:ebook"]).expect("valid");
+        assert_eq!(
+            tags,
+            vec![
+                tag(TagNamespace::Topic, "jazz"),
+                tag(TagNamespace::Format, "ebook")
+            ]
+        );
+        assert_eq!(
+            normalize_tags(&["a", "b", "c", "d", "e", "f"]),
+            Err(TagError::TooMany(6))
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! Diagnostics structures (Section 22.5).
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use crate::Hash;
@@ -9,6 +11,7 @@ use crate::Hash;
 #[ts(export)]
 pub struct CircuitMetrics {
     pub active_circuits: u32,
+    pub degraded_circuits: u32,
     pub circuits_rotated_24h: u32,
     pub avg_latency_ms: u32,
     pub relay_count_known: u32,
@@ -59,16 +62,25 @@ pub struct UpdateStatus {
 }
 
 /// Log entry (Section 22.5).
-#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct LogEntry {
     pub timestamp: u64,
     pub level: LogLevel,
     pub module: String,
     pub message: String,
+    #[serde(default)]
+    pub subsystem: LogSubsystem,
+    /// Set when the entry was logged while serving an RPC request.
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+    /// Structured fields recorded alongside the message.
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+/// Log severity, least severe first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 #[serde(rename_all = "snake_case")]
 pub enum LogLevel {
@@ -78,6 +90,53 @@ pub enum LogLevel {
     Error,
 }
 
+/// Subsystem whose log ring holds an entry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSubsystem {
+    Transport,
+    Dht,
+    Onion,
+    Economy,
+    /// Everything else the daemon logs.
+    #[default]
+    Daemon,
+}
+
+impl LogSubsystem {
+    /// Every subsystem, in ring order.
+    pub const ALL: [LogSubsystem; 5] = [
+        LogSubsystem::Transport,
+        LogSubsystem::Dht,
+        LogSubsystem::Onion,
+        LogSubsystem::Economy,
+        LogSubsystem::Daemon,
+    ];
+}
+
+/// Timing breakdown of one served RPC call (Section 22.5).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct SlowCall {
+    /// Correlation ID of the request, as recorded on its log entries.
+    pub correlation_id: String,
+    pub method: String,
+    /// Unix time the call was received.
+    pub started_at: u64,
+    pub total_us: u64,
+    /// Checking whether the session is unlocked.
+    pub auth_us: u64,
+    /// Waiting for and holding the database.
+    pub db_us: u64,
+    /// Opening outbound connections, including waiting for a dial permit.
+    pub network_us: u64,
+    /// Time not attributed to any phase.
+    pub other_us: u64,
+    /// JSON-RPC error code, if the call failed.
+    pub error_code: Option<i32>,
+}
+
 /// PoR submission status (Section 22.5).
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
@@ -92,6 +151,8 @@ pub struct PorSubmissionStatus {
 #[ts(export)]
 #[serde(rename_all = "snake_case")]
 pub enum PorStatus {
+    /// Proof generated locally; no quorum submission path exists yet.
+    ProvedNotSubmitted,
     Submitted,
     Verified,
     Failed,
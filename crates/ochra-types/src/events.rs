@@ -1,60 +1,645 @@
 //! Event types for daemon-to-UI notification (Section 23).
 //!
-//! All events are emitted via the JSON-RPC event subscription channel.
+//! All events are emitted via the JSON-RPC event subscription channel as
+//! `{event_type, timestamp, payload}`. [`DaemonEvent`] is adjacently tagged
+//! so its variant name is the `event_type` and its fields are the `payload`.
+//! Hashes and identifiers are hex strings on the wire.
 
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::content::TierType;
+use crate::identity::MemberRole;
+use crate::layout::DownloadProgress;
+use crate::space::{GroupSettings, ReportReason};
+use crate::whisper::{HandleTransitionKind, WhisperCounterparty, WhisperDeliveryState};
+use crate::{ContentHash, GroupId, Hash, TxHash, WhisperSessionId};
 
 /// Envelope for all daemon events.
-#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct Event {
-    pub event_type: EventType,
     pub timestamp: u64,
-    #[ts(type = "any")]
-    pub payload: serde_json::Value,
+    #[serde(flatten)]
+    pub event: DaemonEvent,
+}
+
+/// Every event the daemon emits (Section 23).
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(tag = "event_type", content = "payload")]
+pub enum DaemonEvent {
+    // Space & Content events (Section 23.1)
+    MemberJoined {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        pik_hash: Hash,
+        display_name: String,
+        role: MemberRole,
+    },
+    MemberLeft {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        pik_hash: Hash,
+        reason: MemberLeftReason,
+    },
+    ContentPublished {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        title: String,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        creator_pik: Hash,
+        pricing_summary: String,
+    },
+    ContentPurchased {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        tier_type: TierType,
+        price_paid: u64,
+        epoch: u64,
+    },
+    /// A subscribed content lineage advertised a newer version.
+    ContentUpdated {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        lineage_id: ContentHash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        previous_content_hash: ContentHash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        version: u32,
+    },
+    CreatorGranted {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        target_pik: Hash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        granted_by: Hash,
+    },
+    CreatorRevoked {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        target_pik: Hash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        revoked_by: Hash,
+    },
+    ModeratorGranted {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        target_pik: Hash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        granted_by: Hash,
+    },
+    ModeratorRevoked {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        target_pik: Hash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        revoked_by: Hash,
+    },
+    ContentTombstoned {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        tombstoned_by: Hash,
+    },
+    ContentReported {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        reporter_hash: Hash,
+        reason: ReportReason,
+    },
+    SettingsChanged {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        changed_by: Hash,
+        old_settings: GroupSettings,
+        new_settings: GroupSettings,
+    },
+    OwnershipTransferPending {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        new_owner_pik: Hash,
+        completes_at: u64,
+    },
+    OwnershipTransferCompleted {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        new_owner_pik: Hash,
+    },
+    OwnershipTransferCanceled {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        reason: TransferCancelReason,
+    },
+    /// A new owner announcement was received (Section 8.12).
+    AnnouncementReceived {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        seq: u64,
+        title: String,
+        pinned: bool,
+    },
+    /// The owner pinned or unpinned an announcement.
+    AnnouncementPinChanged {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        seq: u64,
+        pinned: bool,
+    },
+    /// Progress of a content download (Section 22.9).
+    DownloadProgress(DownloadProgress),
+
+    // Economy events (Section 23.2)
+    EpochEarningsSummary {
+        epoch: u64,
+        total_earned: u64,
+        abr_earned: u64,
+        creator_earned: u64,
+        host_earned: u64,
+    },
+    RefundReceived {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        refund_amount: u64,
+        epoch: u64,
+    },
+    EscrowTimeout {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        refund_amount: u64,
+        epoch: u64,
+    },
+    VysRewardsClaimed {
+        amount: u64,
+        epoch: u64,
+    },
+    FundsReceived {
+        #[serde_as(as = "Option<serde_with::hex::Hex>")]
+        #[ts(type = "string | null")]
+        sender_pik: Option<Hash>,
+        amount: u64,
+        note: Option<String>,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        tx_hash: TxHash,
+    },
+    FundsSent {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        recipient_pik: Hash,
+        amount: u64,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        tx_hash: TxHash,
+    },
+    MintingComplete {
+        epoch: u64,
+        seeds_minted: u64,
+        receipts_processed: u32,
+    },
+    CollateralRatioChanged {
+        old_cr: f32,
+        new_cr: f32,
+        epoch: u64,
+    },
+
+    // System events (Section 23.3)
+    LayoutManifestUpdated {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        updated_by: Hash,
+    },
+    RecoveryContactAlert {
+        alert_type: RecoveryAlertType,
+        epoch: u64,
+    },
+    /// A recovery was initiated; it can be vetoed until `veto_window_ends`.
+    RecoveryVetoWindow {
+        veto_window_ends: u64,
+    },
+    RecoveryContactHealthAlert {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        contact_pik: Hash,
+        days_since_heartbeat: u16,
+    },
+    /// A contact's pinned keys changed; their safety number must be
+    /// compared again.
+    KeyChanged {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        contact_pik: Hash,
+        was_verified: bool,
+    },
+    /// A contact published a revocation certificate for their PIK.
+    ContactRevoked {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        contact_pik: Hash,
+        revoked_at: u64,
+    },
+    #[serde(rename = "OTAUpdateAvailable")]
+    OtaUpdateAvailable {
+        version: String,
+        activation_epoch: u64,
+        is_mandatory: bool,
+    },
+    AccessExpiringSoon {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        title: String,
+        expires_at: u64,
+        hours_remaining: u16,
+    },
+    InviteExpiringSoon {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        invite_hash: Hash,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        group_id: GroupId,
+        expires_at: u64,
+        hours_remaining: u16,
+    },
+    DiskPressureAlert {
+        free_space_pct: u8,
+        eviction_triggered: bool,
+    },
+    CircuitBreakerActivated {
+        stale_hours: u16,
+        cr_shift: f32,
+    },
+    CircuitBreakerDeactivated {
+        oracle_restored_at: u64,
+    },
+    /// An epoch's supply audit found an invariant that does not hold.
+    /// Amounts are signed micro-seed deltas in decimal.
+    SupplyInvariantViolated {
+        epoch: u32,
+        invariant: String,
+        expected: String,
+        actual: String,
+    },
+    /// An onion circuit started failing echo probes. While no circuit is
+    /// healthy, traffic rides a circuit that may be dropping it.
+    OnionCircuitDegraded {
+        consecutive_failures: u32,
+        healthy_circuits: u32,
+    },
+    /// A failed onion circuit was torn down for a replacement.
+    OnionCircuitFailover {
+        suspect_hop: Option<u8>,
+        healthy_circuits: u32,
+    },
+    /// A degraded onion circuit answered end-to-end again.
+    OnionCircuitRecovered {
+        healthy_circuits: u32,
+    },
+    /// Connections moved to a new local address after a network change.
+    ConnectionsMigrated {
+        resumed_connections: u32,
+        lost_connections: u32,
+        rebuilt_circuits: u32,
+    },
+    /// Independent bootstrap seed sources led to different views of the
+    /// network, a sign of an eclipse attempt. Divergent sources' peers were
+    /// not used; with `accepted` false the bootstrap was refused.
+    BootstrapViewsDiverged {
+        agreeing_sources: Vec<String>,
+        divergent_sources: Vec<String>,
+        accepted: bool,
+    },
+    /// A relay started from a verified quorum checkpoint.
+    StateSynced {
+        epoch: u32,
+        nullifier_count: u64,
+        relay_count: u32,
+    },
+    /// Database maintenance found a problem: corruption, dangling foreign
+    /// keys, a WAL that will not checkpoint, or a task that failed.
+    DatabaseAnomaly {
+        task: String,
+        detail: String,
+    },
+    DaemonStarted {
+        version: String,
+        epoch: u64,
+        posrv_score: f32,
+    },
+    DaemonShuttingDown {
+        reason: String,
+    },
+    /// The operating mode changed, or the roles it dropped finished
+    /// draining (`draining` empty).
+    NodeModeChanged {
+        mode: String,
+        previous: String,
+        draining: Vec<String>,
+    },
+    PowerProfileChanged {
+        mode: String,
+        low_power: bool,
+        relay_suspended: bool,
+    },
+    ZkPorSubmitted {
+        epoch: u64,
+        status: String,
+        proving_time_ms: u32,
+    },
+    /// Progress of zk-PoR proof generation; `stage` is `committing`,
+    /// `sampling`, or `proving`.
+    ZkPorProgress {
+        epoch: u64,
+        stage: String,
+        done: u32,
+        total: u32,
+    },
+
+    // Whisper events (Section 23.4)
+    WhisperSessionStarted {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        session_id: WhisperSessionId,
+        counterparty: WhisperCounterparty,
+    },
+    WhisperReceived {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        session_id: WhisperSessionId,
+        sequence: u64,
+        msg_type: String,
+        timestamp: u64,
+    },
+    WhisperSessionEnded {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        session_id: WhisperSessionId,
+        reason: WhisperSessionEndReason,
+    },
+    WhisperSeedTransferReceived {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        session_id: WhisperSessionId,
+        amount: u64,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        tx_hash: TxHash,
+    },
+    WhisperIdentityRevealed {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        session_id: WhisperSessionId,
+        counterparty: WhisperCounterparty,
+    },
+    WhisperThrottleChanged {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        session_id: WhisperSessionId,
+        new_tier: String,
+        total_cost: u8,
+    },
+    WhisperBackgroundGraceStarted {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        session_id: WhisperSessionId,
+        grace_seconds: u32,
+    },
+    /// Progress of a Whisper attachment transfer.
+    WhisperAttachmentProgress {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        content_hash: ContentHash,
+        received_chunks: u32,
+        total_chunks: u32,
+        received_bytes: u64,
+        complete: bool,
+    },
+    /// An outbound Whisper message changed delivery state.
+    WhisperDeliveryStateChanged {
+        #[serde_as(as = "serde_with::hex::Hex")]
+        #[ts(type = "string")]
+        session_id: WhisperSessionId,
+        sequence: u64,
+        state: WhisperDeliveryState,
+    },
+    HandleDeprecated {
+        handle: String,
+        successor_handle: Option<String>,
+    },
+    HandleExpiring {
+        handle: String,
+        expires_at: u64,
+    },
+    /// A resolved handle has moved; contacts should be warned.
+    HandleTransitionDetected {
+        old_handle: String,
+        new_handle: String,
+        kind: HandleTransitionKind,
+        grace_until: u64,
+    },
+    WhisperPingReceived {
+        timestamp: u64,
+    },
+}
+
+impl DaemonEvent {
+    /// The wire `event_type` of this event.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::MemberJoined { .. } => "MemberJoined",
+            Self::MemberLeft { .. } => "MemberLeft",
+            Self::ContentPublished { .. } => "ContentPublished",
+            Self::ContentPurchased { .. } => "ContentPurchased",
+            Self::ContentUpdated { .. } => "ContentUpdated",
+            Self::CreatorGranted { .. } => "CreatorGranted",
+            Self::CreatorRevoked { .. } => "CreatorRevoked",
+            Self::ModeratorGranted { .. } => "ModeratorGranted",
+            Self::ModeratorRevoked { .. } => "ModeratorRevoked",
+            Self::ContentTombstoned { .. } => "ContentTombstoned",
+            Self::ContentReported { .. } => "ContentReported",
+            Self::SettingsChanged { .. } => "SettingsChanged",
+            Self::OwnershipTransferPending { .. } => "OwnershipTransferPending",
+            Self::OwnershipTransferCompleted { .. } => "OwnershipTransferCompleted",
+            Self::OwnershipTransferCanceled { .. } => "OwnershipTransferCanceled",
+            Self::AnnouncementReceived { .. } => "AnnouncementReceived",
+            Self::AnnouncementPinChanged { .. } => "AnnouncementPinChanged",
+            Self::DownloadProgress(_) => "DownloadProgress",
+            Self::EpochEarningsSummary { .. } => "EpochEarningsSummary",
+            Self::RefundReceived { .. } => "RefundReceived",
+            Self::EscrowTimeout { .. } => "EscrowTimeout",
+            Self::VysRewardsClaimed { .. } => "VysRewardsClaimed",
+            Self::FundsReceived { .. } => "FundsReceived",
+            Self::FundsSent { .. } => "FundsSent",
+            Self::MintingComplete { .. } => "MintingComplete",
+            Self::CollateralRatioChanged { .. } => "CollateralRatioChanged",
+            Self::LayoutManifestUpdated { .. } => "LayoutManifestUpdated",
+            Self::RecoveryContactAlert { .. } => "RecoveryContactAlert",
+            Self::RecoveryVetoWindow { .. } => "RecoveryVetoWindow",
+            Self::RecoveryContactHealthAlert { .. } => "RecoveryContactHealthAlert",
+            Self::KeyChanged { .. } => "KeyChanged",
+            Self::ContactRevoked { .. } => "ContactRevoked",
+            Self::OtaUpdateAvailable { .. } => "OTAUpdateAvailable",
+            Self::AccessExpiringSoon { .. } => "AccessExpiringSoon",
+            Self::InviteExpiringSoon { .. } => "InviteExpiringSoon",
+            Self::DiskPressureAlert { .. } => "DiskPressureAlert",
+            Self::CircuitBreakerActivated { .. } => "CircuitBreakerActivated",
+            Self::CircuitBreakerDeactivated { .. } => "CircuitBreakerDeactivated",
+            Self::SupplyInvariantViolated { .. } => "SupplyInvariantViolated",
+            Self::OnionCircuitDegraded { .. } => "OnionCircuitDegraded",
+            Self::OnionCircuitFailover { .. } => "OnionCircuitFailover",
+            Self::OnionCircuitRecovered { .. } => "OnionCircuitRecovered",
+            Self::ConnectionsMigrated { .. } => "ConnectionsMigrated",
+            Self::BootstrapViewsDiverged { .. } => "BootstrapViewsDiverged",
+            Self::StateSynced { .. } => "StateSynced",
+            Self::DatabaseAnomaly { .. } => "DatabaseAnomaly",
+            Self::DaemonStarted { .. } => "DaemonStarted",
+            Self::DaemonShuttingDown { .. } => "DaemonShuttingDown",
+            Self::NodeModeChanged { .. } => "NodeModeChanged",
+            Self::PowerProfileChanged { .. } => "PowerProfileChanged",
+            Self::ZkPorSubmitted { .. } => "ZkPorSubmitted",
+            Self::ZkPorProgress { .. } => "ZkPorProgress",
+            Self::WhisperSessionStarted { .. } => "WhisperSessionStarted",
+            Self::WhisperReceived { .. } => "WhisperReceived",
+            Self::WhisperSessionEnded { .. } => "WhisperSessionEnded",
+            Self::WhisperSeedTransferReceived { .. } => "WhisperSeedTransferReceived",
+            Self::WhisperIdentityRevealed { .. } => "WhisperIdentityRevealed",
+            Self::WhisperThrottleChanged { .. } => "WhisperThrottleChanged",
+            Self::WhisperBackgroundGraceStarted { .. } => "WhisperBackgroundGraceStarted",
+            Self::WhisperAttachmentProgress { .. } => "WhisperAttachmentProgress",
+            Self::WhisperDeliveryStateChanged { .. } => "WhisperDeliveryStateChanged",
+            Self::HandleDeprecated { .. } => "HandleDeprecated",
+            Self::HandleExpiring { .. } => "HandleExpiring",
+            Self::HandleTransitionDetected { .. } => "HandleTransitionDetected",
+            Self::WhisperPingReceived { .. } => "WhisperPingReceived",
+        }
+    }
+
+    /// Space the event concerns, if any.
+    pub fn group_id(&self) -> Option<&GroupId> {
+        match self {
+            Self::MemberJoined { group_id, .. }
+            | Self::MemberLeft { group_id, .. }
+            | Self::ContentPublished { group_id, .. }
+            | Self::ContentPurchased { group_id, .. }
+            | Self::CreatorGranted { group_id, .. }
+            | Self::CreatorRevoked { group_id, .. }
+            | Self::ModeratorGranted { group_id, .. }
+            | Self::ModeratorRevoked { group_id, .. }
+            | Self::ContentTombstoned { group_id, .. }
+            | Self::ContentReported { group_id, .. }
+            | Self::SettingsChanged { group_id, .. }
+            | Self::OwnershipTransferPending { group_id, .. }
+            | Self::OwnershipTransferCompleted { group_id, .. }
+            | Self::OwnershipTransferCanceled { group_id, .. }
+            | Self::AnnouncementReceived { group_id, .. }
+            | Self::AnnouncementPinChanged { group_id, .. }
+            | Self::LayoutManifestUpdated { group_id, .. }
+            | Self::InviteExpiringSoon { group_id, .. } => Some(group_id),
+            _ => None,
+        }
+    }
+}
+
+/// Why a member left a Space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberLeftReason {
+    Voluntary,
+    Kicked,
+}
+
+/// Why an ownership transfer was canceled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferCancelReason {
+    Vetoed,
+    Timeout,
+}
+
+/// Stage of a recovery attempt reported to the account owner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAlertType {
+    RecoveryInitiated,
+    RecoveryVetoed,
+    RecoveryComplete,
 }
 
-/// All event types (Section 23).
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+/// Why a Whisper session ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 #[serde(rename_all = "snake_case")]
-pub enum EventType {
-    // Space & Content events
-    NewContent,
-    ContentTombstoned,
-    MemberJoined,
-    MemberLeft,
-    RoleChanged,
-    PurchaseComplete,
-    EarningsUpdate,
-    NewReport,
-    InviteRedeemed,
-
-    // Economy events
-    BalanceUpdate,
-    ReceiptFlushed,
-    MintingComplete,
-    RefundProcessed,
-    TransferReceived,
-
-    // Whisper events
-    WhisperMessage,
-    WhisperSessionStart,
-    WhisperSessionEnd,
-    WhisperTyping,
-    WhisperReadAck,
-    IdentityRevealed,
-
-    // Network events
-    CircuitRotated,
-    EpochTransition,
-    PeerConnected,
-    PeerDisconnected,
-    PorChallengeReceived,
-
-    // System events
-    UpdateAvailable,
-    GuardianHeartbeat,
-    DaemonStatus,
-    ErrorOccurred,
+pub enum WhisperSessionEndReason {
+    Closed,
+    Timeout,
+    Offline,
+    Blocked,
+    GraceExpired,
 }
@@ -29,6 +29,47 @@ pub struct UpgradeManifest {
     /// 3-of-5 minimum.
     pub multisig_sigs: Vec<MultisigEntry>,
     pub published_at: u64,
+    /// Groth16 keys this release may use.
+    #[serde(default)]
+    pub circuit_artifacts: Vec<CircuitArtifact>,
+    /// Key versions whose proofs must no longer be accepted.
+    #[serde(default)]
+    pub revoked_circuits: Vec<CircuitVersion>,
+}
+
+/// Groth16 circuit (Section 31).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "kebab-case")]
+pub enum CircuitId {
+    Minting,
+    ZkPor,
+    Refund,
+    ContentKey,
+}
+
+/// A specific key version of a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct CircuitVersion {
+    pub circuit: CircuitId,
+    /// Increments with every Phase 2 ceremony output.
+    pub version: u32,
+}
+
+/// Pinned Groth16 key hashes for one circuit version.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct CircuitArtifact {
+    pub circuit: CircuitId,
+    pub version: u32,
+    /// BLAKE3 of the compressed verifying key.
+    #[ts(type = "string")]
+    pub vk_hash: Hash,
+    /// BLAKE3 of the compressed proving key.
+    #[ts(type = "string")]
+    pub pk_hash: Hash,
+    pub pk_size_bytes: u64,
 }
 
 /// Platform-specific binary hash (Section 22.8).
@@ -55,7 +96,7 @@ pub enum Platform {
 
 /// Multisig entry (Section 22.8).
 #[serde_as]
-#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct MultisigEntry {
     /// Keyholder index (0-4).
@@ -50,6 +50,19 @@ pub enum MemberRole {
     Member,
 }
 
+impl MemberRole {
+    /// Parse a stored role: "host" | "creator" | "moderator" | "member".
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "host" => Some(Self::Host),
+            "creator" => Some(Self::Creator),
+            "moderator" => Some(Self::Moderator),
+            "member" => Some(Self::Member),
+            _ => None,
+        }
+    }
+}
+
 /// Guardian status for recovery contacts (Section 22.1).
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
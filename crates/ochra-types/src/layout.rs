@@ -1,20 +1,67 @@
 //! Configuration & Layout structures (Section 22.9).
+//!
+//! Layout manifests are validated with [`LayoutConfig::validate`] before
+//! they are previewed or published, and manifests written under an older
+//! schema are upgraded with [`LayoutConfig::migrate`].
+
+use std::collections::HashSet;
 
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 
+use crate::space::SpaceTemplate;
 use crate::ContentHash;
 
+/// Current layout manifest schema version.
+pub const LAYOUT_SCHEMA_VERSION: u32 = 2;
+
+/// Maximum sections in one layout.
+pub const MAX_LAYOUT_SECTIONS: usize = 12;
+
+/// Maximum `max_items` for a section.
+pub const MAX_SECTION_ITEMS: u32 = 100;
+
+/// Maximum section title length in Unicode scalar values.
+pub const MAX_SECTION_TITLE_CHARS: usize = 80;
+
+/// Maximum filter tags per section.
+pub const MAX_SECTION_FILTER_TAGS: usize = 5;
+
+/// Maximum pinned content items per section.
+pub const MAX_PINNED_CONTENT: usize = 24;
+
+/// Maximum custom CSS size in bytes.
+pub const MAX_CUSTOM_CSS_BYTES: usize = 8 * 1024;
+
+/// CSS constructs that could load external resources or run code.
+const FORBIDDEN_CSS: [&str; 6] = [
+    "@import",
+    "@font-face",
+    "url(",
+    "expression(",
+    "javascript:",
+    "behavior:",
+];
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
 /// Layout configuration for a Space (Section 22.9).
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct LayoutConfig {
-    pub layout_type: super::space::SpaceTemplate,
+    /// Schema version; manifests without one are version 1.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    pub layout_type: SpaceTemplate,
     pub sections: Vec<LayoutSection>,
     /// Sandboxed CSS subset.
     pub custom_css: Option<String>,
 }
 
 /// Layout section (Section 22.9).
+#[serde_as]
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct LayoutSection {
@@ -22,9 +69,14 @@ pub struct LayoutSection {
     pub title: Option<String>,
     pub max_items: Option<u32>,
     pub filter_tags: Option<Vec<String>>,
+    /// Content shown first in the section, in order (schema 2).
+    #[serde(default)]
+    #[serde_as(as = "Vec<serde_with::hex::Hex>")]
+    #[ts(type = "string[]")]
+    pub pinned_content: Vec<ContentHash>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 #[serde(rename_all = "snake_case")]
 pub enum SectionType {
@@ -35,6 +87,217 @@ pub enum SectionType {
     Categories,
 }
 
+impl SectionType {
+    /// Whether templates of `layout_type` may use this section.
+    pub fn allowed_in(self, layout_type: SpaceTemplate) -> bool {
+        use SectionType::*;
+        match layout_type {
+            SpaceTemplate::Storefront => true,
+            SpaceTemplate::Forum => matches!(self, List | Featured | Categories),
+            SpaceTemplate::Newsfeed => matches!(self, Hero | List | Featured),
+            SpaceTemplate::Gallery => matches!(self, Hero | Grid | Featured | Categories),
+            SpaceTemplate::Library => matches!(self, Grid | List | Categories),
+        }
+    }
+}
+
+/// A layout manifest problem (Section 22.9).
+///
+/// Serialized with a `code` tag so the UI can point at the offending section.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS, thiserror::Error)]
+#[ts(export)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum LayoutError {
+    #[error("unsupported layout schema version {version}")]
+    UnsupportedVersion { version: u32 },
+    #[error("layout has no sections")]
+    NoSections,
+    #[error("layout has {count} sections, max {max}")]
+    TooManySections { count: usize, max: usize },
+    #[error("section {section}: {section_type:?} is not allowed in a {layout_type:?} layout")]
+    SectionNotAllowed {
+        section: usize,
+        section_type: SectionType,
+        layout_type: SpaceTemplate,
+    },
+    #[error("section {section}: title is {len} characters, max {max}")]
+    TitleTooLong {
+        section: usize,
+        len: usize,
+        max: usize,
+    },
+    #[error("section {section}: max_items must be 1..={max}, got {max_items}")]
+    InvalidMaxItems {
+        section: usize,
+        max_items: u32,
+        max: u32,
+    },
+    #[error("section {section}: {count} filter tags, max {max}")]
+    TooManyFilterTags {
+        section: usize,
+        count: usize,
+        max: usize,
+    },
+    #[error("section {section}: {count} pinned items, max {max}")]
+    TooManyPinned {
+        section: usize,
+        count: usize,
+        max: usize,
+    },
+    #[error("section {section}: pinned content {content_hash} is not in the catalog")]
+    UnknownContent {
+        section: usize,
+        content_hash: String,
+    },
+    #[error("custom CSS is {len} bytes, max {max}")]
+    CssTooLarge { len: usize, max: usize },
+    #[error("custom CSS uses forbidden construct {construct}")]
+    CssForbidden { construct: String },
+}
+
+impl LayoutConfig {
+    /// Upgrade a manifest to [`LAYOUT_SCHEMA_VERSION`].
+    ///
+    /// Version 1 manifests had no `max_items` bound and allowed duplicate or
+    /// mixed-case filter tags: `max_items` is clamped to
+    /// [`MAX_SECTION_ITEMS`] (0 becomes unset), tags are lowercased and
+    /// deduplicated, and empty tag lists are dropped.
+    pub fn migrate(mut self) -> Result<Self, LayoutError> {
+        match self.schema_version {
+            1 => {
+                for section in &mut self.sections {
+                    section.max_items = section
+                        .max_items
+                        .filter(|&n| n > 0)
+                        .map(|n| n.min(MAX_SECTION_ITEMS));
+                    section.filter_tags = section.filter_tags.take().and_then(|tags| {
+                        let mut seen = HashSet::new();
+                        let tags: Vec<String> = tags
+                            .into_iter()
+                            .map(|t| t.trim().to_lowercase())
+                            .filter(|t| !t.is_empty() && seen.insert(t.clone()))
+                            .collect();
+                        (!tags.is_empty()).then_some(tags)
+                    });
+                }
+                self.schema_version = LAYOUT_SCHEMA_VERSION;
+                Ok(self)
+            }
+            LAYOUT_SCHEMA_VERSION => Ok(self),
+            version => Err(LayoutError::UnsupportedVersion { version }),
+        }
+    }
+
+    /// Check the manifest against the component whitelist, size limits, and
+    /// the Space's catalog.
+    ///
+    /// `catalog` holds the content hashes pinned sections may reference.
+    /// Returns every problem found, or an empty list if the manifest is
+    /// valid.
+    pub fn validate(&self, catalog: &HashSet<ContentHash>) -> Vec<LayoutError> {
+        let mut errors = Vec::new();
+        if self.schema_version != LAYOUT_SCHEMA_VERSION {
+            errors.push(LayoutError::UnsupportedVersion {
+                version: self.schema_version,
+            });
+        }
+        if self.sections.is_empty() {
+            errors.push(LayoutError::NoSections);
+        }
+        if self.sections.len() > MAX_LAYOUT_SECTIONS {
+            errors.push(LayoutError::TooManySections {
+                count: self.sections.len(),
+                max: MAX_LAYOUT_SECTIONS,
+            });
+        }
+        for (i, section) in self.sections.iter().enumerate() {
+            section.validate(i, self.layout_type, catalog, &mut errors);
+        }
+        if let Some(css) = &self.custom_css {
+            if css.len() > MAX_CUSTOM_CSS_BYTES {
+                errors.push(LayoutError::CssTooLarge {
+                    len: css.len(),
+                    max: MAX_CUSTOM_CSS_BYTES,
+                });
+            }
+            let lowered = css.to_lowercase();
+            for construct in FORBIDDEN_CSS {
+                if lowered.contains(construct) {
+                    errors.push(LayoutError::CssForbidden {
+                        construct: construct.to_string(),
+                    });
+                }
+            }
+        }
+        errors
+    }
+}
+
+impl LayoutSection {
+    fn validate(
+        &self,
+        section: usize,
+        layout_type: SpaceTemplate,
+        catalog: &HashSet<ContentHash>,
+        errors: &mut Vec<LayoutError>,
+    ) {
+        if !self.section_type.allowed_in(layout_type) {
+            errors.push(LayoutError::SectionNotAllowed {
+                section,
+                section_type: self.section_type,
+                layout_type,
+            });
+        }
+        if let Some(title) = &self.title {
+            let len = title.chars().count();
+            if len > MAX_SECTION_TITLE_CHARS {
+                errors.push(LayoutError::TitleTooLong {
+                    section,
+                    len,
+                    max: MAX_SECTION_TITLE_CHARS,
+                });
+            }
+        }
+        if let Some(max_items) = self.max_items {
+            if max_items == 0 || max_items > MAX_SECTION_ITEMS {
+                errors.push(LayoutError::InvalidMaxItems {
+                    section,
+                    max_items,
+                    max: MAX_SECTION_ITEMS,
+                });
+            }
+        }
+        if let Some(tags) = &self.filter_tags {
+            if tags.len() > MAX_SECTION_FILTER_TAGS {
+                errors.push(LayoutError::TooManyFilterTags {
+                    section,
+                    count: tags.len(),
+                    max: MAX_SECTION_FILTER_TAGS,
+                });
+            }
+        }
+        if self.pinned_content.len() > MAX_PINNED_CONTENT {
+            errors.push(LayoutError::TooManyPinned {
+                section,
+                count: self.pinned_content.len(),
+                max: MAX_PINNED_CONTENT,
+            });
+        }
+        for hash in &self.pinned_content {
+            if !catalog.contains(hash) {
+                errors.push(LayoutError::UnknownContent {
+                    section,
+                    content_hash: hex_string(hash),
+                });
+            }
+        }
+    }
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
 /// Rendered layout for the UI (Section 22.9).
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
@@ -67,7 +330,7 @@ pub struct NotificationSettings {
 }
 
 /// Download progress (Section 22.9).
-#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct DownloadProgress {
     #[ts(type = "string")]
@@ -3,6 +3,7 @@
 //! Shared domain types used across the Ochra workspace.
 //! All structures correspond 1:1 with Section 22 of the v5.5 Unified Technical Specification.
 
+pub mod bindings;
 pub mod content;
 pub mod diagnostics;
 pub mod events;
@@ -10,6 +11,8 @@ pub mod governance;
 pub mod identity;
 pub mod layout;
 pub mod network;
+pub mod profile;
+pub mod rpc;
 pub mod space;
 pub mod whisper;
 
@@ -38,6 +41,9 @@ pub const SPHINX_PACKET_SIZE: usize = 8192;
 /// Maximum pricing tiers per content item.
 pub const MAX_PRICING_TIERS: usize = 4;
 
+/// Maximum content items in a bundle tier, including the item itself.
+pub const MAX_BUNDLE_ITEMS: usize = 16;
+
 /// Maximum tags per content item.
 pub const MAX_CONTENT_TAGS: usize = 5;
 
@@ -55,18 +61,128 @@ mod tests {
     #[test]
     #[ignore] // Run manually to generate bindings
     fn export_ts_bindings() {
-        use ts_rs::TS;
         let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../bindings");
-        std::fs::create_dir_all(&dir).expect("create bindings dir");
-        // Export all types
-        crate::events::Event::export_all_to(&dir).expect("export Event");
-        crate::identity::PikMeta::export_all_to(&dir).expect("export PikMeta");
-        crate::space::GroupSummary::export_all_to(&dir).expect("export GroupSummary");
-        crate::content::ContentManifest::export_all_to(&dir).expect("export ContentManifest");
-        crate::whisper::HandleDescriptor::export_all_to(&dir).expect("export HandleDescriptor");
-        crate::network::ServiceReceipt::export_all_to(&dir).expect("export ServiceReceipt");
-        crate::governance::UpgradeManifest::export_all_to(&dir).expect("export UpgradeManifest");
-        crate::layout::LayoutConfig::export_all_to(&dir).expect("export LayoutConfig");
-        crate::diagnostics::CircuitMetrics::export_all_to(&dir).expect("export CircuitMetrics");
+        crate::bindings::export_all(&dir).expect("export bindings");
+    }
+
+    #[test]
+    fn test_bindings_registry_complete() {
+        let missing =
+            crate::bindings::unregistered(std::path::Path::new(crate::bindings::SOURCE_DIR))
+                .expect("scan sources");
+        assert!(
+            missing.is_empty(),
+            "add these types to bindings::REGISTRY: {missing:?}"
+        );
+    }
+
+    #[test]
+    fn test_derived_types_scanner() {
+        let source = "#[derive(Debug, Serialize)]\npub struct Plain;\n\
+                      #[derive(\n    Clone,\n    ts_rs::TS,\n)]\n#[ts(export)]\npub struct Exported {}\n\
+                      #[derive(TS)]\nenum Private { A }\n";
+        assert_eq!(
+            crate::bindings::derived_types(source),
+            vec!["Exported".to_string(), "Private".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_index_ts_lists_every_type() {
+        let index = crate::bindings::index_ts();
+        for binding in crate::bindings::REGISTRY {
+            let line = format!("export type {{ {0} }} from \"./{0}\";", binding.name);
+            assert!(index.contains(&line), "missing {}", binding.name);
+        }
+    }
+
+    #[test]
+    fn test_layout_v1_migration() {
+        use crate::layout::{LayoutConfig, LAYOUT_SCHEMA_VERSION, MAX_SECTION_ITEMS};
+
+        let v1 = serde_json::json!({
+            "layout_type": "storefront",
+            "sections": [{
+                "section_type": "grid",
+                "title": null,
+                "max_items": 5000,
+                "filter_tags": ["Music", "music ", ""],
+            }, {
+                "section_type": "list",
+                "title": "All",
+                "max_items": 0,
+                "filter_tags": [],
+            }],
+            "custom_css": null,
+        });
+        let config: LayoutConfig = serde_json::from_value(v1).expect("parse v1");
+        assert_eq!(config.schema_version, 1);
+
+        let migrated = config.migrate().expect("migrate");
+        assert_eq!(migrated.schema_version, LAYOUT_SCHEMA_VERSION);
+        assert_eq!(migrated.sections[0].max_items, Some(MAX_SECTION_ITEMS));
+        assert_eq!(
+            migrated.sections[0].filter_tags,
+            Some(vec!["music".to_string()])
+        );
+        assert_eq!(migrated.sections[1].max_items, None);
+        assert_eq!(migrated.sections[1].filter_tags, None);
+        assert!(migrated.validate(&Default::default()).is_empty());
+    }
+
+    #[test]
+    fn test_layout_validation_errors() {
+        use crate::layout::{LayoutConfig, LayoutError, LayoutSection, SectionType};
+        use crate::space::SpaceTemplate;
+
+        let known = [1u8; 32];
+        let section = |section_type, pinned_content| LayoutSection {
+            section_type,
+            title: None,
+            max_items: None,
+            filter_tags: None,
+            pinned_content,
+        };
+        let config = LayoutConfig {
+            schema_version: crate::layout::LAYOUT_SCHEMA_VERSION,
+            layout_type: SpaceTemplate::Forum,
+            sections: vec![
+                section(SectionType::List, vec![known]),
+                section(SectionType::Grid, vec![known, [2u8; 32]]),
+            ],
+            custom_css: Some("body { background: URL(https://x) }".to_string()),
+        };
+        let errors = config.validate(&[known].into_iter().collect());
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(matches!(
+            errors[0],
+            LayoutError::SectionNotAllowed { section: 1, .. }
+        ));
+        assert!(matches!(
+            errors[1],
+            LayoutError::UnknownContent { section: 1, .. }
+        ));
+        assert!(matches!(errors[2], LayoutError::CssForbidden { .. }));
+
+        let json = serde_json::to_value(&errors[1]).expect("serialize");
+        assert_eq!(json["code"], "unknown_content");
+        assert_eq!(json["content_hash"], "02".repeat(32));
+    }
+
+    #[test]
+    fn test_layout_unknown_schema_rejected() {
+        use crate::layout::{LayoutConfig, LayoutError};
+
+        let config: LayoutConfig = serde_json::from_value(serde_json::json!({
+            "schema_version": 9,
+            "layout_type": "gallery",
+            "sections": [],
+            "custom_css": null,
+        }))
+        .expect("parse");
+        assert!(matches!(
+            config.migrate(),
+            Err(LayoutError::UnsupportedVersion { version: 9 })
+        ));
     }
 }
@@ -17,6 +17,8 @@ pub struct ServiceReceipt {
     #[serde_as(as = "serde_with::Bytes")]
     #[ts(type = "string")]
     pub requester_circuit_id: [u8; 16],
+    #[ts(type = "string")]
+    pub requester_key: [u8; 32],
     pub bytes_served: u32,
     pub timestamp: u64,
     pub relay_epoch: u32,
@@ -52,11 +54,146 @@ pub struct RelayDescriptor {
     pub country_code: [u8; 2],
     pub bandwidth_cap_mbps: u16,
     pub uptime_epochs: u32,
+    /// What the relay delivers when it is the exit hop (Section 4.9).
+    /// Descriptors without one accept all traffic.
+    #[serde(default)]
+    pub exit_policy: ExitPolicy,
+    /// Encoded delegation certificate when the descriptor is published by
+    /// an operational subkey rather than the PIK itself (Section 6.9).
+    #[serde(default)]
+    #[serde_as(as = "Option<serde_with::Bytes>")]
+    #[ts(type = "string | null")]
+    pub delegation: Option<Vec<u8>>,
     #[serde_as(as = "serde_with::Bytes")]
     #[ts(type = "string")]
     pub sig: [u8; 64],
 }
 
+/// A class of payload delivered at a circuit's exit, one per message type
+/// range (Section 26.1).
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ts_rs::TS,
+)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitClass {
+    /// Chunk requests and service receipts (0x0010–0x001F).
+    Content,
+    /// DHT gets, puts and circuit lookups (0x0020–0x002F).
+    Dht,
+    /// Introduction points and rendezvous (0x0030–0x003F).
+    Rendezvous,
+    /// MLS group messages (0x0040–0x004F).
+    Group,
+    /// FROST, quorum and mint messages (0x0050–0x005F).
+    Quorum,
+    /// Gossip mesh messages (0x0060–0x006F).
+    Gossip,
+    /// Whisper delivery and mailboxes (0x0070–0x007F).
+    Whisper,
+    /// Oracle requests and attestations (0x0080–0x008F).
+    Oracle,
+    /// Social recovery (0x0090–0x009F).
+    Recovery,
+    /// Checkpoint state sync (0x00A0–0x00AF).
+    StateSync,
+}
+
+impl ExitClass {
+    /// Every class, in message type order.
+    pub const ALL: [Self; 10] = [
+        Self::Content,
+        Self::Dht,
+        Self::Rendezvous,
+        Self::Group,
+        Self::Quorum,
+        Self::Gossip,
+        Self::Whisper,
+        Self::Oracle,
+        Self::Recovery,
+        Self::StateSync,
+    ];
+
+    /// The snake_case name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Content => "content",
+            Self::Dht => "dht",
+            Self::Rendezvous => "rendezvous",
+            Self::Group => "group",
+            Self::Quorum => "quorum",
+            Self::Gossip => "gossip",
+            Self::Whisper => "whisper",
+            Self::Oracle => "oracle",
+            Self::Recovery => "recovery",
+            Self::StateSync => "state_sync",
+        }
+    }
+
+    /// Parse a snake_case name.
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|class| class.as_str() == s)
+    }
+}
+
+/// A relay's exit policy (Section 4.9).
+///
+/// The default accepts every class at any rate, which is what a relay
+/// that predates exit policies does.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct ExitPolicy {
+    /// Classes the relay delivers as an exit. Empty = never an exit.
+    #[serde(default = "all_exit_classes")]
+    pub allowed_classes: Vec<ExitClass>,
+    /// Bandwidth one circuit may use through the exit, in kbit/s.
+    /// 0 = limited only by `bandwidth_cap_mbps`.
+    #[serde(default)]
+    pub max_circuit_kbps: u32,
+    /// Refuse payloads that ask the exit to store data, such as DHT puts
+    /// and Whisper mailbox deposits.
+    #[serde(default)]
+    pub no_storage: bool,
+}
+
+fn all_exit_classes() -> Vec<ExitClass> {
+    ExitClass::ALL.to_vec()
+}
+
+impl Default for ExitPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_classes: all_exit_classes(),
+            max_circuit_kbps: 0,
+            no_storage: false,
+        }
+    }
+}
+
+impl ExitPolicy {
+    /// Whether the relay delivers `class`, including payloads that store
+    /// data when `stores` is set.
+    pub fn permits(&self, class: ExitClass, stores: bool) -> bool {
+        self.allowed_classes.contains(&class) && !(stores && self.no_storage)
+    }
+
+    /// Whether the relay serves as an exit at all.
+    pub fn is_exit(&self) -> bool {
+        !self.allowed_classes.is_empty()
+    }
+
+    /// The per-circuit bandwidth limit in kbit/s, given the relay's
+    /// overall `bandwidth_cap_mbps`.
+    pub fn circuit_limit_kbps(&self, bandwidth_cap_mbps: u16) -> u32 {
+        let cap_kbps = u32::from(bandwidth_cap_mbps) * 1000;
+        if self.max_circuit_kbps == 0 {
+            cap_kbps
+        } else {
+            self.max_circuit_kbps.min(cap_kbps)
+        }
+    }
+}
+
 /// Epoch state (Section 22.10).
 #[serde_as]
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
@@ -72,6 +209,10 @@ pub struct EpochState {
     /// BLAKE3 of Bloom filter snapshot.
     #[ts(type = "string")]
     pub nullifier_bloom_hash: Hash,
+    /// Sparse Merkle nullifier accumulator root, if the quorum maintains one.
+    #[serde(default)]
+    #[ts(type = "string | null")]
+    pub nullifier_smt_root: Option<Hash>,
     /// Top 100 (or quorum size).
     pub posrv_rankings: Vec<PoSrvEntry>,
     /// FROST group signature.
@@ -80,6 +221,71 @@ pub struct EpochState {
     pub quorum_sig: [u8; 64],
 }
 
+/// Quorum group key in force from an epoch (Section 12.8).
+///
+/// Entries form the quorum key schedule: each one is endorsed by the group
+/// key of the entry before it, and the genesis entry by its own key.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct QuorumKeyEntry {
+    pub activated_epoch: u32,
+    /// FROST group verifying key.
+    #[ts(type = "string")]
+    pub group_pk: [u8; 32],
+    pub threshold: u16,
+    pub quorum_size: u16,
+    /// FROST signature by the previous entry's group key.
+    #[serde_as(as = "serde_with::Bytes")]
+    #[ts(type = "string")]
+    pub endorsement: [u8; 64],
+}
+
+/// Quorum-signed randomness beacon for one epoch (Section 12.10).
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct EpochBeacon {
+    pub epoch: u32,
+    /// Beacon value of the previous epoch, or zeros for the first beacon.
+    #[ts(type = "string")]
+    pub prev_beacon: Hash,
+    /// FROST signature over the epoch and previous beacon.
+    #[serde_as(as = "serde_with::Bytes")]
+    #[ts(type = "string")]
+    pub quorum_sig: [u8; 64],
+}
+
+/// Quorum-signed summary of the network state at an epoch, for relays
+/// syncing mid-epoch (Section 12.11).
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct StateCheckpoint {
+    pub epoch: u32,
+    /// BLAKE3 of the nullifier Bloom filter bytes.
+    #[ts(type = "string")]
+    pub nullifier_bloom_hash: Hash,
+    /// Nullifiers inserted into the filter.
+    pub nullifier_count: u64,
+    /// VYS accumulator root (`holder_balances_root` of the epoch's
+    /// `EpochState`).
+    #[ts(type = "string")]
+    pub vys_accumulator_root: Hash,
+    /// BLAKE3 of the epoch's encoded `EpochState`.
+    #[ts(type = "string")]
+    pub epoch_state_hash: Hash,
+    /// Relay epoch of the directory snapshot.
+    pub directory_epoch: u32,
+    /// Directory digest of the relay descriptor snapshot.
+    #[ts(type = "string")]
+    pub directory_digest: Hash,
+    /// FROST signature over the fields above.
+    #[serde_as(as = "serde_with::Bytes")]
+    #[ts(type = "string")]
+    pub quorum_sig: [u8; 64],
+}
+
 /// PoSrv ranking entry (Section 22.10).
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
@@ -0,0 +1,381 @@
+//! Network profiles (Section 2.5).
+//!
+//! A [`NetworkProfile`] gathers the protocol parameters that differ between
+//! the main network and test networks. Mainnet and testnet parameters are
+//! compiled in; a devnet starts from the testnet values and may override
+//! any of them at runtime.
+//!
+//! Each network has its own protocol magic, carried in every capability
+//! exchange, and its own bootstrap seeds, so a node can neither connect to
+//! nor bootstrap from another network.
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::Hash;
+
+/// Protocol magic of the main network ("OCHR").
+pub const MAINNET_MAGIC: u32 = 0x4F43_4852;
+
+/// Protocol magic of the public test network ("OCTS").
+pub const TESTNET_MAGIC: u32 = 0x4F43_5453;
+
+/// Protocol magic of local development networks ("OCDV").
+pub const DEVNET_MAGIC: u32 = 0x4F43_4456;
+
+/// Key signing mainnet bootstrap lists. None until the project publishes
+/// one; a node then uses signed lists only with a configured key.
+pub const MAINNET_BOOTSTRAP_LIST_KEY: Option<Hash> = None;
+
+/// Key signing testnet bootstrap lists. None until one is published.
+pub const TESTNET_BOOTSTRAP_LIST_KEY: Option<Hash> = None;
+
+/// Genesis group key of the mainnet quorum. None until the genesis quorum
+/// has formed; until then no key schedule is accepted.
+pub const MAINNET_QUORUM_GENESIS_KEY: Option<Hash> = None;
+
+/// Genesis group key of the testnet quorum. None until it has formed.
+pub const TESTNET_QUORUM_GENESIS_KEY: Option<Hash> = None;
+
+/// Which network a node belongs to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkKind {
+    #[default]
+    Mainnet,
+    Testnet,
+    Devnet,
+}
+
+impl NetworkKind {
+    /// Stable name, as used in config files and data directories.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+            Self::Devnet => "devnet",
+        }
+    }
+
+    /// Parse a network name; empty means mainnet.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "" | "mainnet" => Some(Self::Mainnet),
+            "testnet" => Some(Self::Testnet),
+            "devnet" => Some(Self::Devnet),
+            _ => None,
+        }
+    }
+}
+
+/// Protocol parameters of one network.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct NetworkProfile {
+    pub kind: NetworkKind,
+    /// Sent in every capability exchange; peers with another magic are
+    /// refused.
+    pub magic: u32,
+    pub epoch_duration_secs: u64,
+    pub relay_epoch_duration_secs: u64,
+    /// Kademlia bucket size.
+    pub dht_k: u32,
+    /// Leading zero bits of a Whisper mailbox deposit PoW.
+    pub deposit_pow_difficulty: u32,
+    /// Leading zero bits of a large DHT put's admission PoW.
+    pub dht_admission_difficulty: u32,
+    pub circuit_lifetime_secs: u64,
+    /// Seed nodes as `host:port`.
+    pub bootstrap_nodes: Vec<String>,
+    /// Domains whose TXT records list seed nodes.
+    pub dns_seeds: Vec<String>,
+    /// Ed25519 key signing bootstrap lists; without one, lists are not
+    /// used.
+    #[serde_as(as = "Option<serde_with::hex::Hex>")]
+    #[ts(type = "string | null")]
+    pub bootstrap_list_key: Option<Hash>,
+    /// Genesis quorum group key the key schedule is checked from; without
+    /// one, no quorum key is trusted.
+    #[serde_as(as = "Option<serde_with::hex::Hex>")]
+    #[ts(type = "string | null")]
+    pub quorum_genesis_key: Option<Hash>,
+}
+
+impl Default for NetworkProfile {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+impl NetworkProfile {
+    /// The main network.
+    pub fn mainnet() -> Self {
+        Self {
+            kind: NetworkKind::Mainnet,
+            magic: MAINNET_MAGIC,
+            epoch_duration_secs: crate::EPOCH_DURATION_SECS,
+            relay_epoch_duration_secs: crate::RELAY_EPOCH_DURATION_SECS,
+            dht_k: 20,
+            deposit_pow_difficulty: 4,
+            dht_admission_difficulty: 8,
+            circuit_lifetime_secs: 600,
+            bootstrap_nodes: vec![
+                "198.51.100.1:4433".to_string(),
+                "198.51.100.2:4433".to_string(),
+            ],
+            dns_seeds: vec!["bootstrap.ochra.net".to_string()],
+            bootstrap_list_key: MAINNET_BOOTSTRAP_LIST_KEY,
+            quorum_genesis_key: MAINNET_QUORUM_GENESIS_KEY,
+        }
+    }
+
+    /// The public test network: mainnet parameters with hour-long epochs
+    /// and cheaper proofs of work.
+    pub fn testnet() -> Self {
+        Self {
+            kind: NetworkKind::Testnet,
+            magic: TESTNET_MAGIC,
+            epoch_duration_secs: 3600,
+            relay_epoch_duration_secs: 600,
+            deposit_pow_difficulty: 2,
+            dht_admission_difficulty: 4,
+            bootstrap_nodes: vec![
+                "203.0.113.1:4433".to_string(),
+                "203.0.113.2:4433".to_string(),
+            ],
+            dns_seeds: vec!["bootstrap.testnet.ochra.net".to_string()],
+            bootstrap_list_key: TESTNET_BOOTSTRAP_LIST_KEY,
+            quorum_genesis_key: TESTNET_QUORUM_GENESIS_KEY,
+            ..Self::mainnet()
+        }
+    }
+
+    /// A local development network: testnet parameters with no seeds, to
+    /// be overridden.
+    pub fn devnet() -> Self {
+        Self {
+            kind: NetworkKind::Devnet,
+            magic: DEVNET_MAGIC,
+            bootstrap_nodes: Vec::new(),
+            dns_seeds: Vec::new(),
+            bootstrap_list_key: None,
+            quorum_genesis_key: None,
+            ..Self::testnet()
+        }
+    }
+
+    /// The compiled-in profile of `kind`.
+    pub fn of(kind: NetworkKind) -> Self {
+        match kind {
+            NetworkKind::Mainnet => Self::mainnet(),
+            NetworkKind::Testnet => Self::testnet(),
+            NetworkKind::Devnet => Self::devnet(),
+        }
+    }
+
+    /// The profile of `kind` with `overrides` applied.
+    ///
+    /// # Errors
+    ///
+    /// - [`ProfileError::OverridesNotAllowed`] if any override is set and
+    ///   `kind` is not devnet
+    /// - [`ProfileError::Invalid`] if the result is unusable
+    pub fn resolve(kind: NetworkKind, overrides: &ProfileOverrides) -> Result<Self, ProfileError> {
+        let mut profile = Self::of(kind);
+        if overrides.is_empty() {
+            return Ok(profile);
+        }
+        if kind != NetworkKind::Devnet {
+            return Err(ProfileError::OverridesNotAllowed(kind));
+        }
+        let o = overrides.clone();
+        profile.epoch_duration_secs = o.epoch_duration_secs.unwrap_or(profile.epoch_duration_secs);
+        profile.relay_epoch_duration_secs = o
+            .relay_epoch_duration_secs
+            .unwrap_or(profile.relay_epoch_duration_secs);
+        profile.dht_k = o.dht_k.unwrap_or(profile.dht_k);
+        profile.deposit_pow_difficulty = o
+            .deposit_pow_difficulty
+            .unwrap_or(profile.deposit_pow_difficulty);
+        profile.dht_admission_difficulty = o
+            .dht_admission_difficulty
+            .unwrap_or(profile.dht_admission_difficulty);
+        profile.circuit_lifetime_secs = o
+            .circuit_lifetime_secs
+            .unwrap_or(profile.circuit_lifetime_secs);
+        profile.bootstrap_nodes = o.bootstrap_nodes.unwrap_or(profile.bootstrap_nodes);
+        profile.dns_seeds = o.dns_seeds.unwrap_or(profile.dns_seeds);
+        profile.bootstrap_list_key = o.bootstrap_list_key.or(profile.bootstrap_list_key);
+        profile.quorum_genesis_key = o.quorum_genesis_key.or(profile.quorum_genesis_key);
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    /// Check the parameters are usable together.
+    ///
+    /// # Errors
+    ///
+    /// - [`ProfileError::Invalid`] naming the first bad parameter
+    pub fn validate(&self) -> Result<(), ProfileError> {
+        let invalid = |reason: &str| Err(ProfileError::Invalid(reason.to_string()));
+        if self.relay_epoch_duration_secs == 0 {
+            return invalid("relay_epoch_duration_secs must be positive");
+        }
+        if self.epoch_duration_secs == 0
+            || !self
+                .epoch_duration_secs
+                .is_multiple_of(self.relay_epoch_duration_secs)
+        {
+            return invalid("epoch_duration_secs must be a multiple of relay_epoch_duration_secs");
+        }
+        if self.dht_k == 0 {
+            return invalid("dht_k must be positive");
+        }
+        if self.circuit_lifetime_secs == 0 {
+            return invalid("circuit_lifetime_secs must be positive");
+        }
+        if self.deposit_pow_difficulty > 32 || self.dht_admission_difficulty > 32 {
+            return invalid("PoW difficulties must be at most 32 bits");
+        }
+        Ok(())
+    }
+
+    /// The network epoch containing Unix time `now`.
+    pub fn epoch_at(&self, now: u64) -> u64 {
+        now / self.epoch_duration_secs
+    }
+
+    /// The relay epoch containing Unix time `now`.
+    pub fn relay_epoch_at(&self, now: u64) -> u64 {
+        now / self.relay_epoch_duration_secs
+    }
+}
+
+/// Devnet parameter overrides; unset fields keep the profile's value.
+#[serde_as]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileOverrides {
+    pub epoch_duration_secs: Option<u64>,
+    pub relay_epoch_duration_secs: Option<u64>,
+    pub dht_k: Option<u32>,
+    pub deposit_pow_difficulty: Option<u32>,
+    pub dht_admission_difficulty: Option<u32>,
+    pub circuit_lifetime_secs: Option<u64>,
+    pub bootstrap_nodes: Option<Vec<String>>,
+    pub dns_seeds: Option<Vec<String>>,
+    #[serde_as(as = "Option<serde_with::hex::Hex>")]
+    pub bootstrap_list_key: Option<Hash>,
+    #[serde_as(as = "Option<serde_with::hex::Hex>")]
+    pub quorum_genesis_key: Option<Hash>,
+}
+
+impl ProfileOverrides {
+    /// Whether no parameter is overridden.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Errors resolving a network profile.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ProfileError {
+    #[error("{} parameters cannot be overridden", .0.as_str())]
+    OverridesNotAllowed(NetworkKind),
+    #[error("invalid network profile: {0}")]
+    Invalid(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_networks_are_distinct() {
+        let profiles = [
+            NetworkProfile::mainnet(),
+            NetworkProfile::testnet(),
+            NetworkProfile::devnet(),
+        ];
+        for (i, a) in profiles.iter().enumerate() {
+            a.validate().expect("compiled-in profile is valid");
+            for b in &profiles[i + 1..] {
+                assert_ne!(a.magic, b.magic);
+                assert!(a
+                    .bootstrap_nodes
+                    .iter()
+                    .all(|n| !b.bootstrap_nodes.contains(n)));
+                assert!(
+                    a.bootstrap_list_key.is_none() || a.bootstrap_list_key != b.bootstrap_list_key
+                );
+                assert!(
+                    a.quorum_genesis_key.is_none() || a.quorum_genesis_key != b.quorum_genesis_key
+                );
+            }
+        }
+        assert_eq!(
+            NetworkProfile::mainnet().epoch_duration_secs,
+            crate::EPOCH_DURATION_SECS
+        );
+    }
+
+    #[test]
+    fn test_overrides_only_on_devnet() {
+        let overrides = ProfileOverrides {
+            epoch_duration_secs: Some(120),
+            relay_epoch_duration_secs: Some(60),
+            bootstrap_nodes: Some(vec!["127.0.0.1:4433".to_string()]),
+            ..ProfileOverrides::default()
+        };
+        assert_eq!(
+            NetworkProfile::resolve(NetworkKind::Mainnet, &overrides),
+            Err(ProfileError::OverridesNotAllowed(NetworkKind::Mainnet))
+        );
+        assert_eq!(
+            NetworkProfile::resolve(NetworkKind::Testnet, &ProfileOverrides::default()),
+            Ok(NetworkProfile::testnet())
+        );
+
+        let devnet = NetworkProfile::resolve(NetworkKind::Devnet, &overrides).expect("devnet");
+        assert_eq!(devnet.epoch_duration_secs, 120);
+        assert_eq!(devnet.epoch_at(245), 2);
+        assert_eq!(devnet.relay_epoch_at(245), 4);
+        assert_eq!(devnet.magic, DEVNET_MAGIC);
+        assert_eq!(devnet.dht_k, NetworkProfile::testnet().dht_k);
+    }
+
+    #[test]
+    fn test_rejects_unusable_overrides() {
+        let overrides = ProfileOverrides {
+            epoch_duration_secs: Some(100),
+            relay_epoch_duration_secs: Some(60),
+            ..ProfileOverrides::default()
+        };
+        assert!(matches!(
+            NetworkProfile::resolve(NetworkKind::Devnet, &overrides),
+            Err(ProfileError::Invalid(_))
+        ));
+        let overrides = ProfileOverrides {
+            dht_k: Some(0),
+            ..ProfileOverrides::default()
+        };
+        assert!(NetworkProfile::resolve(NetworkKind::Devnet, &overrides).is_err());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let profile = NetworkProfile::testnet();
+        let json = serde_json::to_value(&profile).expect("serialize");
+        assert_eq!(json["kind"], "testnet");
+        assert_eq!(json["bootstrap_list_key"], serde_json::Value::Null);
+        let back: NetworkProfile = serde_json::from_value(json).expect("deserialize");
+        assert_eq!(back, profile);
+
+        let overrides: ProfileOverrides =
+            serde_json::from_str(r#"{"dht_k": 8}"#).expect("overrides");
+        assert_eq!(overrides.dht_k, Some(8));
+        assert!(!overrides.is_empty());
+    }
+}
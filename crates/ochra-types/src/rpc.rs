@@ -0,0 +1,754 @@
+//! Typed JSON-RPC parameters and results (Section 21).
+//!
+//! Each RPC method that takes parameters has a params struct here, and
+//! methods with structured results have a result struct. Both are exported
+//! to TypeScript, so the UI builds requests from the same definitions the
+//! daemon parses them with.
+//!
+//! Params structs are declared through `rpc_params!`, which also
+//! implements [`RpcParams`] for them. [`parse_params`] checks every field
+//! before deserializing and reports each problem as a [`FieldError`] with
+//! the field's path, e.g. `group_id` or `settings.publish_policy`. A field
+//! is required unless its type is an `Option`.
+//!
+//! 32-byte identifiers (PIK hashes, group ids, content hashes) travel as
+//! hex strings and are typed [`Hex32`].
+
+use std::fmt;
+
+use serde::de::{DeserializeOwned, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::layout::{LayoutConfig, RenderableLayout};
+use crate::space::{GroupPolicy, GroupSettings};
+
+/// A problem with one parameter.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct FieldError {
+    /// Dotted path to the field; empty for the params object itself.
+    pub path: String,
+    pub message: String,
+}
+
+impl FieldError {
+    /// An error at `path`.
+    pub fn new(path: &str, message: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// A field that failed to deserialize. A field missing from a nested
+    /// object is reported at its own path.
+    fn invalid(path: &str, error: &serde_json::Error) -> Self {
+        let message = error.to_string();
+        match message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.strip_suffix('`'))
+        {
+            Some(nested) => Self::new(&format!("{path}.{nested}"), "required"),
+            None => Self::new(path, &message),
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Parameters of an RPC method, checked field by field.
+pub trait RpcParams: DeserializeOwned {
+    /// Problems with the fields of `params`, in declaration order.
+    fn field_errors(params: &Map<String, Value>) -> Vec<FieldError>;
+}
+
+/// Parse RPC params into `T`. Absent params are an empty object.
+///
+/// # Errors
+///
+/// Returns every missing or malformed field.
+pub fn parse_params<T: RpcParams>(params: &Value) -> Result<T, Vec<FieldError>> {
+    let empty = Value::Object(Map::new());
+    let params = if params.is_null() { &empty } else { params };
+    let Some(fields) = params.as_object() else {
+        return Err(vec![FieldError::new("", "params must be an object")]);
+    };
+    let errors = T::field_errors(fields);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    T::deserialize(params).map_err(|e| vec![FieldError::invalid("", &e)])
+}
+
+fn check_field<T: DeserializeOwned>(
+    name: &str,
+    value: Option<&Value>,
+    errors: &mut Vec<FieldError>,
+) {
+    let value = value.unwrap_or(&Value::Null);
+    if let Err(e) = T::deserialize(value) {
+        errors.push(if value.is_null() {
+            FieldError::new(name, "required")
+        } else {
+            FieldError::invalid(name, &e)
+        });
+    }
+}
+
+/// Declare params structs and implement [`RpcParams`] for them.
+macro_rules! rpc_params {
+    ($(
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                pub $field:ident: $ty:ty,
+            )*
+        }
+    )*) => {$(
+        $(#[$meta])*
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl RpcParams for $name {
+            fn field_errors(params: &Map<String, Value>) -> Vec<FieldError> {
+                #[allow(unused_mut)]
+                let mut errors = Vec::new();
+                $(check_field::<$ty>(stringify!($field), params.get(stringify!($field)), &mut errors);)*
+                errors
+            }
+        }
+    )*};
+}
+
+/// 32 bytes, hex-encoded on the wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Hex32(pub [u8; 32]);
+
+impl Serialize for Hex32 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hex: String = self.0.iter().map(|b| format!("{b:02x}")).collect();
+        serializer.serialize_str(&hex)
+    }
+}
+
+impl<'de> Deserialize<'de> for Hex32 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HexVisitor;
+
+        impl Visitor<'_> for HexVisitor {
+            type Value = Hex32;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("32 hex-encoded bytes")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<Hex32, E> {
+                let invalid = || E::invalid_value(serde::de::Unexpected::Str(s), &self);
+                if s.len() != 64 || !s.is_ascii() {
+                    return Err(invalid());
+                }
+                let mut bytes = [0u8; 32];
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+                }
+                Ok(Hex32(bytes))
+            }
+        }
+
+        deserializer.deserialize_str(HexVisitor)
+    }
+}
+
+impl From<[u8; 32]> for Hex32 {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+// Identity, Contacts & Recovery (Section 21.1)
+
+rpc_params! {
+    /// Params of `init_pik` and `authenticate`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct PasswordParams {
+        pub password: String,
+    }
+
+    /// Params of `change_password`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct ChangePasswordParams {
+        pub old: String,
+        pub new: String,
+    }
+
+    /// Params of `rotate_operational_key`. Omitted scope = every scope.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct RotateOperationalKeyParams {
+        pub scope: Option<Vec<String>>,
+        /// 1-365; default 7.
+        pub validity_epochs: Option<u32>,
+    }
+
+    /// Params of `update_display_name`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct UpdateDisplayNameParams {
+        pub new_name: String,
+    }
+
+    /// Params of `export_revocation_certificate`: "unspecified" (default),
+    /// "key_compromise" or "superseded".
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct ExportRevocationCertificateParams {
+        pub reason: Option<String>,
+    }
+
+    /// Params of `publish_revocation_certificate`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct PublishRevocationCertificateParams {
+        /// Hex-encoded certificate.
+        pub certificate: String,
+    }
+
+    /// Params of `nominate_guardian`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct NominateGuardianParams {
+        #[ts(type = "string")]
+        pub contact_pik: Hex32,
+    }
+
+    /// Params of `replace_guardian`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct ReplaceGuardianParams {
+        #[ts(type = "string")]
+        pub old_pik: Hex32,
+        #[ts(type = "string")]
+        pub new_pik: Hex32,
+    }
+
+    /// Params of `initiate_recovery`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct InitiateRecoveryParams {
+        /// Hex-encoded shares.
+        pub guardian_shares: Vec<String>,
+    }
+
+    /// Params of `veto_recovery`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct VetoRecoveryParams {
+        pub auth_payload: String,
+    }
+
+    /// Params of `add_contact`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct AddContactParams {
+        pub token: String,
+    }
+
+    /// Params of `generate_contact_token`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct GenerateContactTokenParams {
+        /// Default 24.
+        pub ttl_hours: Option<u16>,
+    }
+
+    /// Params of `remove_contact`, `get_safety_number` and
+    /// `unverify_contact`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct ContactPikParams {
+        #[ts(type = "string")]
+        pub contact_pik: Hex32,
+    }
+
+    /// Params of `verify_contact`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct VerifyContactParams {
+        #[ts(type = "string")]
+        pub contact_pik: Hex32,
+        pub scanned_qr: Option<String>,
+    }
+}
+
+/// Result of `init_pik`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct PikCreated {
+    #[ts(type = "string")]
+    pub pik_hash: Hex32,
+    pub created: bool,
+}
+
+/// Result of `authenticate` and `authenticate_biometric`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct Authenticated {
+    pub authenticated: bool,
+}
+
+/// Result of `get_my_pik`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct MyPik {
+    #[ts(type = "string")]
+    pub pik_hash: Hex32,
+}
+
+/// Result of `get_operational_key` and `rotate_operational_key`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct OperationalKey {
+    #[ts(type = "string")]
+    pub subkey_pk: Hex32,
+    pub scope: Vec<String>,
+    pub valid_from_epoch: u32,
+    pub valid_until_epoch: u32,
+    /// Hex-encoded delegation certificate.
+    pub certificate: String,
+    /// Whether the certificate covers this epoch and the subkey is in the
+    /// key store; null from `rotate_operational_key`.
+    pub usable: Option<bool>,
+}
+
+/// Result of `enroll_biometric`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct BiometricEnrollment {
+    pub enrolled: bool,
+    pub hardware_backed: bool,
+}
+
+/// Result of `export_revocation_certificate`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct RevocationCertificateExport {
+    /// Hex-encoded certificate.
+    pub certificate: String,
+    #[ts(type = "string")]
+    pub dht_address: Hex32,
+}
+
+/// Result of `publish_revocation_certificate`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct RevocationPublished {
+    pub published: bool,
+    #[ts(type = "string")]
+    pub dht_address: Hex32,
+}
+
+/// Result of `initiate_recovery` and `transfer_group_ownership`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct TimelockPending {
+    /// Always "pending".
+    pub status: String,
+    pub veto_window_ends: u64,
+}
+
+/// Result of `add_contact`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct ContactAdded {
+    #[ts(type = "string")]
+    pub pik_hash: Hex32,
+    pub display_name: String,
+}
+
+/// Result of `generate_contact_token`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct ContactToken {
+    pub token: String,
+}
+
+/// An entry of `get_contacts`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct ContactListEntry {
+    #[ts(type = "string")]
+    pub pik_hash: Hex32,
+    pub display_name: String,
+    pub added_at: u64,
+    pub is_blocked: bool,
+    pub verified: bool,
+}
+
+/// Result of `get_safety_number`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct SafetyNumberInfo {
+    pub safety_number: String,
+    pub qr_payload: String,
+    pub verified: bool,
+    pub verified_at: Option<u64>,
+    pub key_changed_at: Option<u64>,
+}
+
+// Network, Spaces & Subgroups (Section 21.2)
+
+rpc_params! {
+    /// Params of `create_group`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct CreateGroupParams {
+        pub name: String,
+        /// Default "storefront".
+        pub template: Option<String>,
+    }
+
+    /// Params of `join_group`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct JoinGroupParams {
+        pub invite_uri: String,
+    }
+
+    /// Params of methods that take only a Space: `leave_group`,
+    /// `get_active_invites`, `get_group_members`,
+    /// `veto_ownership_transfer`, `get_group_policy`, `get_announcements`,
+    /// `update_group_profile`, `set_group_notification_settings`,
+    /// `get_space_stats`, `get_group_storage`, `get_space_activity` and
+    /// `get_content_reports`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct GroupIdParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+    }
+
+    /// Params of `kick_member` and the role grant and revoke methods.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct GroupMemberParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        #[ts(type = "string")]
+        pub target_pik: Hex32,
+    }
+
+    /// Params of `generate_invite`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct GenerateInviteParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        /// Omitted = unlimited.
+        pub uses: Option<u32>,
+        /// Default 7.
+        pub ttl_days: Option<u8>,
+    }
+
+    /// Params of `revoke_invite`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct RevokeInviteParams {
+        #[ts(type = "string")]
+        pub invite_hash: Hex32,
+    }
+
+    /// Params of `transfer_group_ownership`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct TransferGroupOwnershipParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        #[ts(type = "string")]
+        pub new_owner_pik: Hex32,
+    }
+
+    /// Params of `update_group_settings`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct UpdateGroupSettingsParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        pub settings: GroupSettings,
+    }
+
+    /// Params of `get_group_policy_history`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct GroupPolicyHistoryParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        /// Default 50.
+        pub limit: Option<u32>,
+    }
+
+    /// Params of `post_announcement`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct PostAnnouncementParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        pub title: String,
+        pub body: Option<String>,
+        pub pinned: Option<bool>,
+    }
+
+    /// Params of `pin_announcement`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct PinAnnouncementParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        pub seq: u64,
+        pub pinned: bool,
+    }
+
+    /// Params of `mark_announcements_read`. Omitted seq = all.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct MarkAnnouncementsReadParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        pub seq: Option<u64>,
+    }
+
+    /// Params of `create_subgroup`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct CreateSubgroupParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        pub name: String,
+    }
+
+    /// Params of `get_subgroup_members`, `mls_grant_subgroup_access` and
+    /// `mls_revoke_subgroup_access`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct SubgroupIdParams {
+        #[ts(type = "string")]
+        pub subgroup_id: Hex32,
+    }
+
+    /// Params of `preview_layout_manifest`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct PreviewLayoutManifestParams {
+        pub config: LayoutConfig,
+        /// Space whose catalog pinned content is checked against.
+        #[ts(type = "string | null")]
+        pub group_id: Option<Hex32>,
+    }
+
+    /// Params of `update_group_layout_manifest`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct UpdateGroupLayoutManifestParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        pub config: LayoutConfig,
+    }
+
+    /// Params of `set_group_quota`. Omitted limits are unlimited.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct SetGroupQuotaParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        pub max_total_bytes: Option<u64>,
+        pub max_member_bytes: Option<u64>,
+        pub max_item_bytes: Option<u64>,
+    }
+
+    /// Params of `dismiss_content_report`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct DismissContentReportParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        #[ts(type = "string")]
+        pub content_hash: Hex32,
+    }
+
+    /// Params of `report_content`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct ReportContentParams {
+        #[ts(type = "string")]
+        pub content_hash: Hex32,
+        pub reason: String,
+    }
+
+    /// Params of `owner_tombstone_content`. Default reason "removed".
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct OwnerTombstoneContentParams {
+        #[ts(type = "string")]
+        pub content_hash: Hex32,
+        pub reason: Option<String>,
+    }
+
+    /// Params of `export_space_archive`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct ExportSpaceArchiveParams {
+        #[ts(type = "string")]
+        pub group_id: Hex32,
+        pub path: String,
+    }
+
+    /// Params of `import_space_archive`.
+    #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+    #[ts(export)]
+    pub struct ImportSpaceArchiveParams {
+        pub path: String,
+    }
+}
+
+/// Result of `create_group`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct GroupCreated {
+    #[ts(type = "string")]
+    pub group_id: Hex32,
+}
+
+/// Result of `update_group_settings`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct GroupSettingsUpdated {
+    pub updated: bool,
+    /// Policy version the settings were published as.
+    pub version: u64,
+}
+
+/// Result of `get_group_policy`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct GroupPolicyInfo {
+    #[ts(type = "string")]
+    pub owner_pik: Hex32,
+    pub policy: GroupPolicy,
+}
+
+/// An entry of `get_group_policy_history`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct GroupPolicyVersion {
+    pub version: u64,
+    #[ts(type = "string")]
+    pub signer_pik: Hex32,
+    pub received_at: u64,
+    pub policy: GroupPolicy,
+}
+
+/// Result of `post_announcement`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct AnnouncementPosted {
+    pub seq: u64,
+}
+
+/// Result of `mark_announcements_read`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct AnnouncementsMarked {
+    pub marked: u32,
+    pub unread: u32,
+}
+
+/// Result of `preview_layout_manifest`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct LayoutPreview {
+    pub layout: RenderableLayout,
+    pub schema_version: u32,
+    /// Schema version the manifest was migrated from, if it was.
+    pub migrated_from: Option<u32>,
+}
+
+/// Result of `update_group_layout_manifest`.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct LayoutManifestUpdated {
+    pub updated: bool,
+    #[ts(type = "string")]
+    pub layout_manifest_hash: Hex32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUP: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+
+    #[test]
+    fn test_parse_params() {
+        let params = serde_json::json!({"group_id": GROUP, "title": "hi", "pinned": true});
+        let parsed: PostAnnouncementParams = parse_params(&params).expect("valid params");
+        assert_eq!(parsed.group_id, Hex32([1; 32]));
+        assert_eq!(parsed.body, None);
+        assert_eq!(parsed.pinned, Some(true));
+        assert_eq!(
+            serde_json::to_value(parsed.group_id).expect("serialize"),
+            serde_json::json!(GROUP)
+        );
+    }
+
+    #[test]
+    fn test_every_bad_field_reported() {
+        let params = serde_json::json!({"group_id": "abc", "pinned": "yes"});
+        let errors = parse_params::<PinAnnouncementParams>(&params).expect_err("invalid");
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["group_id", "seq", "pinned"]);
+        assert_eq!(errors[1].message, "required");
+        assert!(errors[0].message.contains("32 hex-encoded bytes"));
+    }
+
+    #[test]
+    fn test_nested_field_path() {
+        let params = serde_json::json!({
+            "group_id": GROUP,
+            "settings": {"invite_permission": "host_only"},
+        });
+        let errors = parse_params::<UpdateGroupSettingsParams>(&params).expect_err("invalid");
+        assert_eq!(
+            errors,
+            [FieldError::new("settings.publish_policy", "required")]
+        );
+        assert_eq!(errors[0].to_string(), "settings.publish_policy: required");
+    }
+
+    #[test]
+    fn test_absent_and_non_object_params() {
+        let parsed: GenerateContactTokenParams =
+            parse_params(&Value::Null).expect("all fields optional");
+        assert_eq!(parsed.ttl_hours, None);
+
+        let errors = parse_params::<PasswordParams>(&Value::Null).expect_err("missing");
+        assert_eq!(errors, [FieldError::new("password", "required")]);
+
+        let errors = parse_params::<PasswordParams>(&serde_json::json!([1])).expect_err("array");
+        assert_eq!(errors[0].path, "");
+    }
+}
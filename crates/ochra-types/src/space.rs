@@ -3,6 +3,7 @@
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
+use crate::identity::MemberRole;
 use crate::{ContentHash, GroupId, Hash};
 
 /// Summary of a Space for listing views (Section 22.2).
@@ -20,11 +21,14 @@ pub struct GroupSummary {
     pub member_count: u32,
     pub last_activity_at: u64,
     pub unread: bool,
+    /// Unread owner announcements (Section 8.12).
+    #[serde(default)]
+    pub unread_announcements: u32,
     pub pinned: bool,
 }
 
 /// Space template types.
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 #[serde(rename_all = "snake_case")]
 pub enum SpaceTemplate {
@@ -36,29 +40,121 @@ pub enum SpaceTemplate {
 }
 
 /// Space settings (Section 22.2).
-#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct GroupSettings {
     pub invite_permission: InvitePermission,
     pub publish_policy: PublishPolicy,
+    #[serde(default)]
+    pub join_rule: JoinRule,
+    /// Most mature rating content published to the Space may carry.
+    #[serde(default)]
+    pub max_content_rating: ContentRating,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+impl GroupSettings {
+    /// Whether a member with `role` may generate invites.
+    pub fn may_invite(&self, role: &MemberRole) -> bool {
+        *role == MemberRole::Host || self.invite_permission == InvitePermission::Anyone
+    }
+
+    /// Whether a member with `role` may publish content.
+    pub fn may_publish(&self, role: &MemberRole) -> bool {
+        matches!(role, MemberRole::Host | MemberRole::Creator)
+            || self.publish_policy == PublishPolicy::Everyone
+    }
+
+    /// Whether content rated `rating` may be published.
+    pub fn allows_rating(&self, rating: ContentRating) -> bool {
+        rating <= self.max_content_rating
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 #[serde(rename_all = "snake_case")]
 pub enum InvitePermission {
     Anyone,
+    #[default]
     HostOnly,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 #[serde(rename_all = "snake_case")]
 pub enum PublishPolicy {
+    #[default]
     CreatorsOnly,
     Everyone,
 }
 
+/// How invitees become members (Section 8.11).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinRule {
+    /// A valid invite admits its holder.
+    #[default]
+    Invite,
+    /// Invitees wait until the Host admits them.
+    Approval,
+}
+
+/// Audience rating of content, from least to most mature.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ts_rs::TS,
+)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentRating {
+    General,
+    Teen,
+    #[default]
+    Mature,
+}
+
+impl ContentRating {
+    /// Parse an RPC string: "general" | "teen" | "mature".
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "general" => Some(Self::General),
+            "teen" => Some(Self::Teen),
+            "mature" => Some(Self::Mature),
+            _ => None,
+        }
+    }
+}
+
+/// Owner-signed Space policy, published as a mutable DHT record so member
+/// daemons enforce the same settings (Section 8.11).
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct GroupPolicy {
+    #[serde_as(as = "serde_with::hex::Hex")]
+    #[ts(type = "string")]
+    pub group_id: GroupId,
+    /// Increases with every change; the record's sequence number.
+    pub version: u64,
+    pub settings: GroupSettings,
+    /// Ownership transfer awaiting its timelock, if any.
+    pub pending_transfer: Option<OwnershipTransferRecord>,
+    pub issued_at: u64,
+}
+
+impl GroupPolicy {
+    /// The policy of a Space whose owner has published none.
+    pub fn initial(group_id: GroupId) -> Self {
+        Self {
+            group_id,
+            version: 0,
+            settings: GroupSettings::default(),
+            pending_transfer: None,
+            issued_at: 0,
+        }
+    }
+}
+
 /// Invite information (Section 22.2).
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
@@ -74,6 +170,19 @@ pub struct InviteInfo {
     pub is_expired: bool,
 }
 
+/// An owner announcement as shown in a Space (Section 8.12).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct AnnouncementInfo {
+    /// Position in the Space's announcement sequence.
+    pub seq: u64,
+    pub title: String,
+    pub body: String,
+    pub pinned: bool,
+    pub issued_at: u64,
+    pub read: bool,
+}
+
 /// Space statistics (Section 22.2).
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
@@ -180,9 +289,11 @@ pub struct SpaceManifest {
 }
 
 /// Ownership transfer record (Section 22.11).
-#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct OwnershipTransferRecord {
+    #[serde_as(as = "serde_with::hex::Hex")]
     #[ts(type = "string")]
     pub new_owner_pik: [u8; 32],
     pub initiated_at: u64,
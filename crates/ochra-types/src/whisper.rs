@@ -23,11 +23,113 @@ pub struct HandleDescriptor {
     #[ts(type = "string")]
     pub pow_proof: Vec<u8>,
     pub status: HandleStatus,
+    /// Store-and-forward mailboxes for offline delivery, one per linked device.
+    #[serde(default)]
+    pub mailboxes: Vec<MailboxEntry>,
+    /// Which presence signals the handle owner sends.
+    #[serde(default)]
+    pub privacy: PresencePrivacy,
+    /// What unknown senders must attach to reach the handle owner.
+    #[serde(default)]
+    pub strictness: ThrottleStrictness,
     #[serde_as(as = "serde_with::Bytes")]
     #[ts(type = "string")]
     pub sig: [u8; 64],
 }
 
+/// Store-and-forward mailbox advertised in a handle descriptor.
+#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct MailboxEntry {
+    /// Linked device that owns the mailbox.
+    #[serde(default)]
+    #[ts(type = "string")]
+    pub device_id: [u8; 16],
+    /// Relay holding the mailbox.
+    #[ts(type = "string")]
+    pub relay_node_id: [u8; 32],
+    /// Ed25519 key that addresses and authorizes the mailbox.
+    #[ts(type = "string")]
+    pub mailbox_pk: [u8; 32],
+    /// X25519 key envelopes are sealed to.
+    #[ts(type = "string")]
+    pub seal_pk: [u8; 32],
+}
+
+/// Presence signals a node sends to Whisper peers (Section 7.4).
+///
+/// Advertised in the handle descriptor so peers know which signals will
+/// never arrive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct PresencePrivacy {
+    pub typing_indicators: bool,
+    pub read_receipts: bool,
+    /// Active/away state; typing indicators also require it.
+    pub online_presence: bool,
+}
+
+impl Default for PresencePrivacy {
+    fn default() -> Self {
+        Self {
+            typing_indicators: true,
+            read_receipts: true,
+            online_presence: true,
+        }
+    }
+}
+
+impl PresencePrivacy {
+    /// Apply a per-contact override on top of these settings.
+    pub fn with_override(self, o: &PresenceOverride) -> Self {
+        Self {
+            typing_indicators: o.typing_indicators.unwrap_or(self.typing_indicators),
+            read_receipts: o.read_receipts.unwrap_or(self.read_receipts),
+            online_presence: o.online_presence.unwrap_or(self.online_presence),
+        }
+    }
+
+    /// Whether typing indicators are actually sent.
+    pub fn sends_typing(&self) -> bool {
+        self.typing_indicators && self.online_presence
+    }
+}
+
+/// How much an unknown sender must spend to reach a Whisper recipient
+/// (Section 7.6).
+///
+/// Each level names an Argon2id puzzle difficulty and a Seed payment that
+/// may be attached instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleStrictness {
+    /// Unknown senders attach nothing.
+    Off,
+    Low,
+    #[default]
+    Standard,
+    High,
+}
+
+/// Per-contact presence override; `None` fields follow the global setting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct PresenceOverride {
+    pub typing_indicators: Option<bool>,
+    pub read_receipts: Option<bool>,
+    pub online_presence: Option<bool>,
+}
+
+impl PresenceOverride {
+    /// Whether every field follows the global setting.
+    pub fn is_empty(&self) -> bool {
+        self.typing_indicators.is_none()
+            && self.read_receipts.is_none()
+            && self.online_presence.is_none()
+    }
+}
+
 /// Introduction point entry (Section 22.4).
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
@@ -101,6 +203,12 @@ pub enum WhisperMsgType {
     },
     Typing,
     ReadAck,
+    /// Body carries an attachment manifest and key.
+    Attachment {
+        #[ts(type = "string")]
+        content_hash: [u8; 32],
+        size: u64,
+    },
 }
 
 /// Relay receipt for anti-spam accounting (Section 22.4).
@@ -155,8 +263,54 @@ pub enum SessionState {
     Locked,
 }
 
+/// Delivery state of an outbound Whisper message (Section 7.4).
+///
+/// States only move forward: `queued → sent → delivered → read`. A message
+/// whose retries run out becomes `failed`; a late ack can still move it to
+/// `delivered` or `read`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum WhisperDeliveryState {
+    Queued,
+    Sent,
+    Delivered,
+    Read,
+    Failed,
+}
+
+impl WhisperDeliveryState {
+    /// Stable lowercase name, as stored and serialized.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Sent => "sent",
+            Self::Delivered => "delivered",
+            Self::Read => "read",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Parse a name produced by [`Self::as_str`].
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "queued" => Some(Self::Queued),
+            "sent" => Some(Self::Sent),
+            "delivered" => Some(Self::Delivered),
+            "read" => Some(Self::Read),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+
+    /// Whether the message still awaits a delivery ack.
+    pub fn is_pending(self) -> bool {
+        matches!(self, Self::Queued | Self::Sent)
+    }
+}
+
 /// Whisper counterparty info (Section 22.4).
-#[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export)]
 pub struct WhisperCounterparty {
     pub revealed_handle: Option<String>,
@@ -225,6 +379,41 @@ pub struct DeprecationTombstone {
     pub sig: [u8; 64],
 }
 
+/// Signed link from a handle to its successor (Section 7.2).
+///
+/// Published at the old handle's transition address so resolvers can follow
+/// a rename or an ownership transfer. Signed by `old_signing_pk`.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+pub struct HandleTransition {
+    pub old_handle: String,
+    #[ts(type = "string")]
+    pub old_signing_pk: [u8; 32],
+    /// Equal to `old_handle` when only the owner key changes.
+    pub new_handle: String,
+    #[ts(type = "string")]
+    pub new_signing_pk: [u8; 32],
+    pub kind: HandleTransitionKind,
+    pub issued_at: u64,
+    /// Until then the old handle still resolves.
+    pub grace_until: u64,
+    #[serde_as(as = "serde_with::Bytes")]
+    #[ts(type = "string")]
+    pub sig: [u8; 64],
+}
+
+/// Why a handle moved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleTransitionKind {
+    /// Same owner, new handle or new key.
+    Rename,
+    /// A different person now holds the successor.
+    Transfer,
+}
+
 /// Whisper ping for missed messages (Section 22.4).
 #[serde_as]
 #[derive(Clone, Debug, Serialize, Deserialize, ts_rs::TS)]
@@ -0,0 +1,329 @@
+//! Reward projection for relay operators.
+//!
+//! Projects a node's VYS rewards for the coming epochs from the signed
+//! epoch history. The amount distributed in each past epoch is recovered
+//! from the reward-per-token accumulator (Section 11.8):
+//!
+//! ```text
+//! distributed_e = (rewardPerToken_e - rewardPerToken_{e-1}) * totalVYSStaked_e / 1e18
+//! ```
+//!
+//! The node's expected reward per epoch is its VYS share times the mean of
+//! those amounts. Bounds are a 90% interval from their spread, assuming
+//! epochs are independent, so the interval of a k-epoch total widens with
+//! `sqrt(k)`. With fewer than [`MIN_SAMPLES`] epochs the spread is taken as
+//! [`FALLBACK_SPREAD`] of the mean.
+//!
+//! Fees already collected in the epoch in progress are a floor for the
+//! first projected epoch, since that pool only grows until the boundary.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, VysError};
+
+/// Fixed-point scale of `rewardPerToken`.
+pub const REWARD_PER_TOKEN_SCALE: f64 = 1e18;
+
+/// Epoch states kept for estimation.
+pub const HISTORY_EPOCHS: usize = 30;
+
+/// Furthest an estimate projects.
+pub const MAX_HORIZON: u32 = 30;
+
+/// z-score of the two-sided 90% interval.
+pub const CONFIDENCE_Z: f64 = 1.645;
+
+/// Epochs of history needed to use their observed spread.
+pub const MIN_SAMPLES: usize = 3;
+
+/// Spread, as a fraction of the mean, assumed with too little history.
+pub const FALLBACK_SPREAD: f64 = 0.5;
+
+/// The accumulator fields of one signed epoch state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochObservation {
+    pub epoch: u64,
+    pub reward_per_token: u128,
+    pub total_vys_staked: u64,
+    pub fee_pool_balance: u64,
+}
+
+/// Recent epoch states, oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct EpochHistory {
+    observations: VecDeque<EpochObservation>,
+}
+
+impl EpochHistory {
+    /// Create an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an epoch state. Returns `false` if it is not newer than the
+    /// latest one recorded.
+    pub fn record(&mut self, observation: EpochObservation) -> bool {
+        if self
+            .latest()
+            .is_some_and(|latest| latest.epoch >= observation.epoch)
+        {
+            return false;
+        }
+        self.observations.push_back(observation);
+        while self.observations.len() > HISTORY_EPOCHS {
+            self.observations.pop_front();
+        }
+        true
+    }
+
+    /// The most recent epoch state.
+    pub fn latest(&self) -> Option<&EpochObservation> {
+        self.observations.back()
+    }
+
+    /// Number of epoch states held.
+    pub fn len(&self) -> usize {
+        self.observations.len()
+    }
+
+    /// Whether no epoch state is held.
+    pub fn is_empty(&self) -> bool {
+        self.observations.is_empty()
+    }
+
+    /// Micro-seeds distributed per epoch, from accumulator deltas.
+    ///
+    /// A gap between recorded epochs is spread evenly over the missing
+    /// epochs. Without any delta, the latest fee pool stands in.
+    pub fn distributed(&self) -> Vec<f64> {
+        let deltas: Vec<f64> = self
+            .observations
+            .iter()
+            .zip(self.observations.iter().skip(1))
+            .filter_map(|(prev, next)| {
+                let delta = next.reward_per_token.checked_sub(prev.reward_per_token)?;
+                let gap = next.epoch - prev.epoch;
+                Some(
+                    delta as f64 * next.total_vys_staked as f64
+                        / REWARD_PER_TOKEN_SCALE
+                        / gap as f64,
+                )
+            })
+            .collect();
+        if deltas.is_empty() {
+            self.latest()
+                .map(|latest| vec![latest.fee_pool_balance as f64])
+                .unwrap_or_default()
+        } else {
+            deltas
+        }
+    }
+}
+
+/// Projected reward for one epoch and the running total up to it, in
+/// micro-seeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochProjection {
+    pub epoch: u64,
+    pub expected: u64,
+    pub low: u64,
+    pub high: u64,
+    pub cumulative_expected: u64,
+    pub cumulative_low: u64,
+    pub cumulative_high: u64,
+}
+
+/// Reward projection for a node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RewardEstimate {
+    /// The node's share of total VYS.
+    pub share: f64,
+    /// Epochs of history the estimate rests on.
+    pub samples: usize,
+    /// Whether the spread was assumed rather than observed.
+    pub low_confidence: bool,
+    pub epochs: Vec<EpochProjection>,
+}
+
+fn mean_and_spread(samples: &[f64]) -> (f64, f64) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    if samples.len() < MIN_SAMPLES {
+        return (mean, mean * FALLBACK_SPREAD);
+    }
+    let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance.sqrt())
+}
+
+/// Project a node's rewards for `horizon` epochs starting at `first_epoch`.
+///
+/// `share` is the node's fraction of total VYS; `pending_fee_pool` the fees
+/// collected so far in the epoch in progress, if known.
+///
+/// # Errors
+///
+/// - [`VysError::InvalidContribution`] if `share` is outside `[0, 1]` or
+///   `horizon` exceeds [`MAX_HORIZON`]
+pub fn estimate(
+    history: &EpochHistory,
+    share: f64,
+    pending_fee_pool: Option<u64>,
+    first_epoch: u64,
+    horizon: u32,
+) -> Result<RewardEstimate> {
+    if !(0.0..=1.0).contains(&share) {
+        return Err(VysError::InvalidContribution(format!(
+            "VYS share {share} outside [0, 1]"
+        )));
+    }
+    if horizon > MAX_HORIZON {
+        return Err(VysError::InvalidContribution(format!(
+            "horizon {horizon} exceeds {MAX_HORIZON} epochs"
+        )));
+    }
+
+    let samples = history.distributed();
+    let (mean, spread) = mean_and_spread(&samples);
+    let expected = share * mean;
+    let half_width = CONFIDENCE_Z * share * spread;
+
+    let mut epochs = Vec::with_capacity(horizon as usize);
+    let (mut total, mut floors) = (0.0_f64, 0.0_f64);
+    for k in 0..u64::from(horizon) {
+        let floor = match pending_fee_pool {
+            Some(pool) if k == 0 => share * pool as f64,
+            _ => 0.0,
+        };
+        let point = expected.max(floor);
+        let low = (expected - half_width).max(floor);
+        let high = (expected + half_width).max(floor);
+        total += point;
+        floors += floor;
+        // Independent epochs: the total's interval grows with sqrt(k).
+        let cumulative_width = half_width * ((k + 1) as f64).sqrt();
+        epochs.push(EpochProjection {
+            epoch: first_epoch + k,
+            expected: point as u64,
+            low: low as u64,
+            high: high as u64,
+            cumulative_expected: total as u64,
+            cumulative_low: (total - cumulative_width).max(floors) as u64,
+            cumulative_high: (total + cumulative_width) as u64,
+        });
+    }
+
+    Ok(RewardEstimate {
+        share,
+        samples: samples.len(),
+        low_confidence: samples.len() < MIN_SAMPLES,
+        epochs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALE: u128 = 1_000_000_000_000_000_000;
+
+    /// History distributing `pools[i]` micro-seeds over 1,000 staked VYS.
+    fn history(pools: &[u64]) -> EpochHistory {
+        let mut history = EpochHistory::new();
+        let mut rpt = 0u128;
+        history.record(EpochObservation {
+            epoch: 100,
+            reward_per_token: rpt,
+            total_vys_staked: 1_000,
+            fee_pool_balance: 0,
+        });
+        for (i, pool) in pools.iter().enumerate() {
+            rpt += u128::from(*pool) * SCALE / 1_000;
+            history.record(EpochObservation {
+                epoch: 101 + i as u64,
+                reward_per_token: rpt,
+                total_vys_staked: 1_000,
+                fee_pool_balance: *pool,
+            });
+        }
+        history
+    }
+
+    #[test]
+    fn test_distributed_recovers_fee_pools() {
+        let history = history(&[10_000, 20_000, 30_000]);
+        let distributed = history.distributed();
+        assert_eq!(distributed.len(), 3);
+        for (got, want) in distributed.iter().zip([10_000.0, 20_000.0, 30_000.0]) {
+            assert!((got - want).abs() < 1e-6, "{got} != {want}");
+        }
+    }
+
+    #[test]
+    fn test_steady_history_gives_tight_bounds() {
+        let estimate = estimate(&history(&[50_000; 5]), 0.1, None, 106, 3).expect("estimate");
+        assert_eq!(estimate.samples, 5);
+        assert!(!estimate.low_confidence);
+        let first = estimate.epochs[0];
+        assert_eq!(first.epoch, 106);
+        assert_eq!(
+            (first.low, first.expected, first.high),
+            (5_000, 5_000, 5_000)
+        );
+        assert_eq!(estimate.epochs[2].cumulative_expected, 15_000);
+    }
+
+    #[test]
+    fn test_bounds_widen_with_variance_and_horizon() {
+        let estimate = estimate(
+            &history(&[40_000, 60_000, 40_000, 60_000]),
+            0.5,
+            None,
+            105,
+            4,
+        )
+        .expect("estimate");
+        let first = estimate.epochs[0];
+        assert_eq!(first.expected, 25_000);
+        assert!(first.low < first.expected && first.high > first.expected);
+
+        let width = |p: &EpochProjection| p.cumulative_high - p.cumulative_low;
+        let last = estimate.epochs[3];
+        assert_eq!(last.cumulative_expected, 100_000);
+        // Wider than one epoch's interval, narrower than four stacked.
+        assert!(width(&last) > width(&first));
+        assert!(width(&last) < 4 * width(&first));
+    }
+
+    #[test]
+    fn test_pending_pool_floors_first_epoch() {
+        let estimate = estimate(&history(&[10_000]), 1.0, Some(25_000), 102, 2).expect("estimate");
+        assert!(estimate.low_confidence);
+        assert_eq!(estimate.epochs[0].low, 25_000);
+        assert_eq!(estimate.epochs[0].expected, 25_000);
+        assert_eq!(estimate.epochs[1].expected, 10_000);
+        assert_eq!(
+            estimate.epochs[1].low,
+            10_000 - (CONFIDENCE_Z * 5_000.0) as u64
+        );
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_ordered() {
+        let mut history = history(&[1; HISTORY_EPOCHS + 5]);
+        assert_eq!(history.len(), HISTORY_EPOCHS);
+        let latest = *history.latest().expect("latest");
+        assert!(!history.record(latest));
+        assert!(estimate(&history, 1.5, None, 0, 1).is_err());
+        assert!(estimate(&history, 0.5, None, 0, MAX_HORIZON + 1).is_err());
+        assert!(estimate(&EpochHistory::new(), 0.5, None, 0, 1)
+            .expect("empty")
+            .epochs
+            .iter()
+            .all(|p| p.expected == 0));
+    }
+}
@@ -11,10 +11,12 @@
 //! - [`accounting`] — VYS reward accumulator
 //! - [`claims`] — Pull-based claims
 //! - [`decay`] — Decay, slash, and CR formula
+//! - [`estimate`] — Reward projection from epoch history
 
 pub mod accounting;
 pub mod claims;
 pub mod decay;
+pub mod estimate;
 
 /// Error types for VYS operations.
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,535 @@
+//! Whisper attachments via chunked encrypted transfer.
+//!
+//! Attachments never travel inside the Sphinx-routed message itself. The
+//! sender encrypts the file into 4 MB chunks (see
+//! [`ochra_storage::chunker`]), publishes their Reed-Solomon shards, and
+//! sends an [`AttachmentPointer`] (manifest + key) inside the
+//! ratchet-encrypted [`WhisperMessage`](ochra_types::whisper::WhisperMessage).
+//! The recipient fetches the chunks, verifies each against the manifest, and
+//! decrypts once all chunks have arrived.
+//!
+//! ## Encryption
+//!
+//! Each attachment gets a fresh random 32-byte key. Chunk `i` is sealed with
+//! ChaCha20-Poly1305 under that key with nonce `LE32(i) || 0^8`. Chunk ids
+//! are Merkle leaf hashes of the ciphertext, so storage nodes can serve and
+//! verify chunks without learning anything about the file.
+//!
+//! ## Lifetime
+//!
+//! | Parameter | Value |
+//! |-----------|-------|
+//! | Maximum size | 100 MB |
+//! | Default TTL | 7 days |
+//!
+//! After `expires_at` the sender stops serving shards and the recipient drops
+//! any partial download; see [`AttachmentStore::gc`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+use ochra_crypto::{blake3, chacha20};
+use ochra_storage::chunker::{self, Chunk};
+use ochra_storage::reed_solomon::{ReedSolomonCodec, Shard};
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, WhisperError};
+
+/// Maximum attachment size in bytes (100 MB).
+pub const MAX_ATTACHMENT_SIZE: usize = 100 * 1024 * 1024;
+
+/// Default attachment lifetime in seconds (7 days).
+pub const DEFAULT_ATTACHMENT_TTL_SECS: u64 = 7 * 24 * 3600;
+
+/// Maximum file name length in characters.
+pub const MAX_FILE_NAME_LEN: usize = 255;
+
+/// Attachment manifest describing the encrypted chunks.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttachmentManifest {
+    /// Merkle root over the encrypted chunk ids.
+    pub content_hash: [u8; 32],
+    /// Original file name.
+    pub file_name: String,
+    /// MIME type (e.g. "image/png").
+    pub mime_type: String,
+    /// Plaintext size in bytes.
+    pub size: u64,
+    /// Encrypted chunk ids in order.
+    pub chunk_ids: Vec<[u8; 32]>,
+    /// Unix timestamp after which the attachment is no longer served.
+    pub expires_at: u64,
+}
+
+/// Manifest and decryption key, carried inside a ratchet-encrypted message.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttachmentPointer {
+    /// Attachment manifest.
+    pub manifest: AttachmentManifest,
+    /// ChaCha20-Poly1305 key for the chunks.
+    pub key: [u8; 32],
+}
+
+impl AttachmentPointer {
+    /// Serialize into a Whisper message body.
+    pub fn to_body(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| WhisperError::Attachment(e.to_string()))
+    }
+
+    /// Parse from a Whisper message body.
+    pub fn from_body(body: &[u8]) -> Result<Self> {
+        serde_json::from_slice(body).map_err(|e| WhisperError::Attachment(e.to_string()))
+    }
+}
+
+/// Nonce for chunk `index`.
+fn chunk_nonce(index: u32) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..4].copy_from_slice(&index.to_le_bytes());
+    nonce
+}
+
+/// Chunk and encrypt a file for sending.
+///
+/// Returns the pointer to embed in the message and the encrypted chunks to
+/// publish.
+pub fn prepare_attachment(
+    data: &[u8],
+    file_name: &str,
+    mime_type: &str,
+    expires_at: u64,
+) -> Result<(AttachmentPointer, Vec<Chunk>)> {
+    prepare_attachment_from_reader(data, file_name, mime_type, expires_at)
+}
+
+/// Chunk and encrypt a file for sending, reading one chunk at a time.
+///
+/// Only the current plaintext chunk is held in memory. Reading stops with an
+/// error as soon as the input passes [`MAX_ATTACHMENT_SIZE`].
+pub fn prepare_attachment_from_reader<R: Read>(
+    mut reader: R,
+    file_name: &str,
+    mime_type: &str,
+    expires_at: u64,
+) -> Result<(AttachmentPointer, Vec<Chunk>)> {
+    if file_name.chars().count() > MAX_FILE_NAME_LEN {
+        return Err(WhisperError::Attachment("file name too long".to_string()));
+    }
+
+    let mut key = [0u8; 32];
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut key);
+
+    let mut chunks = Vec::new();
+    let mut size = 0usize;
+    let mut buf = vec![0u8; chunker::CHUNK_SIZE];
+    loop {
+        let len = read_chunk(&mut reader, &mut buf)?;
+        if len == 0 {
+            break;
+        }
+        size += len;
+        if size > MAX_ATTACHMENT_SIZE {
+            return Err(WhisperError::Attachment(format!(
+                "attachment exceeds maximum {MAX_ATTACHMENT_SIZE} bytes"
+            )));
+        }
+        let index = chunks.len() as u32;
+        let ciphertext = chacha20::encrypt(&key, &chunk_nonce(index), &buf[..len], &[])
+            .map_err(|e| WhisperError::Crypto(e.to_string()))?;
+        chunks.push(Chunk {
+            chunk_id: blake3::merkle_leaf(&ciphertext),
+            data: ciphertext,
+            index,
+        });
+    }
+    if chunks.is_empty() {
+        return Err(WhisperError::Attachment("attachment is empty".to_string()));
+    }
+    let chunk_ids: Vec<[u8; 32]> = chunks.iter().map(|c| c.chunk_id).collect();
+
+    let manifest = AttachmentManifest {
+        content_hash: chunker::build_merkle_root(&chunk_ids),
+        file_name: file_name.to_string(),
+        mime_type: mime_type.to_string(),
+        size: size as u64,
+        chunk_ids,
+        expires_at,
+    };
+    Ok((AttachmentPointer { manifest, key }, chunks))
+}
+
+/// Fill `buf` from `reader`, stopping early only at end of input.
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(WhisperError::Attachment(format!("read failed: {e}"))),
+        }
+    }
+    Ok(filled)
+}
+
+/// Encode an encrypted chunk into its k=4, n=8 shards for publication.
+///
+/// Returns the shards and the unpadded chunk length needed to decode.
+pub fn shard_chunk(chunk: &Chunk) -> Result<(Vec<Shard>, usize)> {
+    let codec = ReedSolomonCodec::new();
+    let (data_shards, original_len) = codec
+        .split_into_data_shards(&chunk.data)
+        .map_err(|e| WhisperError::Attachment(e.to_string()))?;
+    let parity = codec
+        .encode(&data_shards)
+        .map_err(|e| WhisperError::Attachment(e.to_string()))?;
+    let shards = data_shards
+        .into_iter()
+        .chain(parity)
+        .enumerate()
+        .map(|(index, data)| Shard { index, data })
+        .collect();
+    Ok((shards, original_len))
+}
+
+/// Download progress for one attachment.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentProgress {
+    /// Attachment content hash.
+    pub content_hash: [u8; 32],
+    /// Chunks verified so far.
+    pub received_chunks: u32,
+    /// Total chunks.
+    pub total_chunks: u32,
+    /// Ciphertext bytes verified so far.
+    pub received_bytes: u64,
+}
+
+impl AttachmentProgress {
+    /// Whether every chunk has been received.
+    pub fn is_complete(&self) -> bool {
+        self.received_chunks == self.total_chunks
+    }
+}
+
+/// An in-progress attachment download.
+#[derive(Clone, Debug)]
+struct Download {
+    pointer: AttachmentPointer,
+    chunks: BTreeMap<u32, Vec<u8>>,
+}
+
+impl Download {
+    fn progress(&self) -> AttachmentProgress {
+        AttachmentProgress {
+            content_hash: self.pointer.manifest.content_hash,
+            received_chunks: self.chunks.len() as u32,
+            total_chunks: self.pointer.manifest.chunk_ids.len() as u32,
+            received_bytes: self.chunks.values().map(|c| c.len() as u64).sum(),
+        }
+    }
+}
+
+/// In-memory tracker for sent and received attachments.
+///
+/// Received chunks are held in RAM only until the download is taken or
+/// expires, and sent attachments are tracked so their shards can be dropped
+/// once they expire.
+#[derive(Debug, Default)]
+pub struct AttachmentStore {
+    downloads: HashMap<[u8; 32], Download>,
+    published: HashMap<[u8; 32], u64>,
+}
+
+impl AttachmentStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an attachment this node published.
+    pub fn record_published(&mut self, manifest: &AttachmentManifest) {
+        self.published
+            .insert(manifest.content_hash, manifest.expires_at);
+    }
+
+    /// Begin downloading the attachment referenced by `pointer`.
+    pub fn start_download(
+        &mut self,
+        pointer: AttachmentPointer,
+        now: u64,
+    ) -> Result<AttachmentProgress> {
+        let manifest = &pointer.manifest;
+        if manifest.expires_at <= now {
+            return Err(WhisperError::Attachment("attachment expired".to_string()));
+        }
+        if manifest.chunk_ids.is_empty()
+            || chunker::build_merkle_root(&manifest.chunk_ids) != manifest.content_hash
+        {
+            return Err(WhisperError::Attachment(
+                "manifest chunk ids do not match content hash".to_string(),
+            ));
+        }
+        let download = self
+            .downloads
+            .entry(manifest.content_hash)
+            .or_insert(Download {
+                pointer,
+                chunks: BTreeMap::new(),
+            });
+        Ok(download.progress())
+    }
+
+    /// Accept a fetched chunk after verifying it against the manifest.
+    pub fn accept_chunk(
+        &mut self,
+        content_hash: &[u8; 32],
+        index: u32,
+        data: Vec<u8>,
+    ) -> Result<AttachmentProgress> {
+        let download = self
+            .downloads
+            .get_mut(content_hash)
+            .ok_or_else(|| WhisperError::Attachment("unknown attachment".to_string()))?;
+        let expected = download
+            .pointer
+            .manifest
+            .chunk_ids
+            .get(index as usize)
+            .ok_or_else(|| WhisperError::Attachment(format!("chunk index {index} out of range")))?;
+        if blake3::merkle_leaf(&data) != *expected {
+            return Err(WhisperError::Attachment(format!(
+                "chunk {index} failed verification"
+            )));
+        }
+        download.chunks.insert(index, data);
+        Ok(download.progress())
+    }
+
+    /// Progress of a download, if one is active.
+    pub fn progress(&self, content_hash: &[u8; 32]) -> Option<AttachmentProgress> {
+        self.downloads.get(content_hash).map(Download::progress)
+    }
+
+    /// Indices of chunks still missing for a download.
+    pub fn missing_chunks(&self, content_hash: &[u8; 32]) -> Vec<u32> {
+        let Some(download) = self.downloads.get(content_hash) else {
+            return Vec::new();
+        };
+        (0..download.pointer.manifest.chunk_ids.len() as u32)
+            .filter(|i| !download.chunks.contains_key(i))
+            .collect()
+    }
+
+    /// Decrypt and remove a completed download.
+    pub fn take_completed(&mut self, content_hash: &[u8; 32]) -> Result<Vec<u8>> {
+        let complete = self
+            .downloads
+            .get(content_hash)
+            .is_some_and(|d| d.progress().is_complete());
+        if !complete {
+            return Err(WhisperError::Attachment("download incomplete".to_string()));
+        }
+        let Some(download) = self.downloads.remove(content_hash) else {
+            return Err(WhisperError::Attachment("unknown attachment".to_string()));
+        };
+
+        let key = download.pointer.key;
+        let mut out = Vec::with_capacity(download.pointer.manifest.size as usize);
+        for (index, ciphertext) in &download.chunks {
+            let plaintext = chacha20::decrypt(&key, &chunk_nonce(*index), ciphertext, &[])
+                .map_err(|e| WhisperError::Crypto(e.to_string()))?;
+            out.extend_from_slice(&plaintext);
+        }
+        if out.len() as u64 != download.pointer.manifest.size {
+            return Err(WhisperError::Attachment(format!(
+                "size mismatch: expected {}, got {}",
+                download.pointer.manifest.size,
+                out.len()
+            )));
+        }
+        Ok(out)
+    }
+
+    /// Drop expired downloads and published attachments.
+    ///
+    /// Returns the content hashes of expired published attachments so the
+    /// caller can release their shards.
+    pub fn gc(&mut self, now: u64) -> Vec<[u8; 32]> {
+        self.downloads
+            .retain(|_, d| d.pointer.manifest.expires_at > now);
+        let expired: Vec<[u8; 32]> = self
+            .published
+            .iter()
+            .filter(|(_, expires_at)| **expires_at <= now)
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in &expired {
+            self.published.remove(hash);
+        }
+        expired
+    }
+
+    /// Number of active downloads.
+    pub fn download_count(&self) -> usize {
+        self.downloads.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_prepare_and_download_roundtrip() {
+        let data = sample(chunker::CHUNK_SIZE + 1000);
+        let (pointer, chunks) =
+            prepare_attachment(&data, "photo.jpg", "image/jpeg", NOW + 60).expect("prepare");
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(pointer.manifest.size, data.len() as u64);
+
+        let body = pointer.to_body().expect("encode");
+        let pointer = AttachmentPointer::from_body(&body).expect("decode");
+        let hash = pointer.manifest.content_hash;
+
+        let mut store = AttachmentStore::new();
+        store.start_download(pointer, NOW).expect("start");
+        assert_eq!(store.missing_chunks(&hash), vec![0, 1]);
+
+        let progress = store
+            .accept_chunk(&hash, 1, chunks[1].data.clone())
+            .expect("chunk 1");
+        assert_eq!(progress.received_chunks, 1);
+        assert!(!progress.is_complete());
+        assert!(store.take_completed(&hash).is_err());
+
+        let progress = store
+            .accept_chunk(&hash, 0, chunks[0].data.clone())
+            .expect("chunk 0");
+        assert!(progress.is_complete());
+
+        let out = store.take_completed(&hash).expect("complete");
+        assert_eq!(out, data);
+        assert_eq!(store.download_count(), 0);
+    }
+
+    #[test]
+    fn test_chunks_are_encrypted() {
+        let data = vec![0x41u8; 4096];
+        let (_, chunks) = prepare_attachment(&data, "a.txt", "text/plain", NOW).expect("prepare");
+        assert_ne!(&chunks[0].data[..data.len()], data.as_slice());
+    }
+
+    #[test]
+    fn test_tampered_chunk_rejected() {
+        let data = sample(1024);
+        let (pointer, chunks) =
+            prepare_attachment(&data, "a.bin", "application/octet-stream", NOW + 60)
+                .expect("prepare");
+        let hash = pointer.manifest.content_hash;
+        let mut store = AttachmentStore::new();
+        store.start_download(pointer, NOW).expect("start");
+
+        let mut bad = chunks[0].data.clone();
+        bad[0] ^= 0xFF;
+        assert!(store.accept_chunk(&hash, 0, bad).is_err());
+        assert!(store
+            .accept_chunk(&hash, 5, chunks[0].data.clone())
+            .is_err());
+        assert_eq!(store.progress(&hash).map(|p| p.received_chunks), Some(0));
+    }
+
+    #[test]
+    fn test_manifest_mismatch_rejected() {
+        let (mut pointer, _) =
+            prepare_attachment(&sample(100), "a", "text/plain", NOW + 60).expect("prepare");
+        pointer.manifest.chunk_ids[0] = [9u8; 32];
+        let mut store = AttachmentStore::new();
+        assert!(store.start_download(pointer, NOW).is_err());
+    }
+
+    #[test]
+    fn test_expired_attachment_rejected() {
+        let (pointer, _) =
+            prepare_attachment(&sample(100), "a", "text/plain", NOW).expect("prepare");
+        let mut store = AttachmentStore::new();
+        assert!(store.start_download(pointer, NOW).is_err());
+    }
+
+    #[test]
+    fn test_gc_drops_expired() {
+        let (pointer, _) =
+            prepare_attachment(&sample(100), "a", "text/plain", NOW + 60).expect("prepare");
+        let hash = pointer.manifest.content_hash;
+        let mut store = AttachmentStore::new();
+        store.record_published(&pointer.manifest);
+        store.start_download(pointer, NOW).expect("start");
+
+        assert!(store.gc(NOW + 59).is_empty());
+        assert_eq!(store.download_count(), 1);
+
+        assert_eq!(store.gc(NOW + 60), vec![hash]);
+        assert_eq!(store.download_count(), 0);
+    }
+
+    #[test]
+    fn test_shard_chunk() {
+        let (_, chunks) =
+            prepare_attachment(&sample(1000), "a", "text/plain", NOW + 60).expect("prepare");
+        let (shards, original_len) = shard_chunk(&chunks[0]).expect("shard");
+        assert_eq!(shards.len(), 8);
+        assert_eq!(original_len, chunks[0].data.len());
+
+        let mut present: [Option<Vec<u8>>; 8] = Default::default();
+        for shard in shards.into_iter().skip(2) {
+            present[shard.index] = Some(shard.data);
+        }
+        let decoded = ReedSolomonCodec::new().decode(&present).expect("decode");
+        assert_eq!(&decoded[..original_len], chunks[0].data.as_slice());
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let data = vec![0u8; MAX_ATTACHMENT_SIZE + 1];
+        assert!(prepare_attachment(&data, "big", "application/octet-stream", NOW).is_err());
+    }
+
+    #[test]
+    fn test_reader_fills_chunks_across_short_reads() {
+        let data = sample(2 * chunker::CHUNK_SIZE + 5);
+        let reader = data[..10]
+            .chain(&data[10..chunker::CHUNK_SIZE + 3])
+            .chain(&data[chunker::CHUNK_SIZE + 3..]);
+        let (pointer, chunks) =
+            prepare_attachment_from_reader(reader, "a", "text/plain", NOW + 60).expect("prepare");
+        assert_eq!(pointer.manifest.size, data.len() as u64);
+        let plaintext_lens: Vec<usize> = chunks.iter().map(|c| c.data.len() - 16).collect();
+        assert_eq!(
+            plaintext_lens,
+            vec![chunker::CHUNK_SIZE, chunker::CHUNK_SIZE, 5]
+        );
+
+        let mut store = AttachmentStore::new();
+        let hash = pointer.manifest.content_hash;
+        store.start_download(pointer, NOW).expect("start");
+        for chunk in chunks {
+            store
+                .accept_chunk(&hash, chunk.index, chunk.data)
+                .expect("accept");
+        }
+        assert_eq!(store.take_completed(&hash).expect("complete"), data);
+    }
+
+    #[test]
+    fn test_reader_size_limit_and_empty() {
+        let oversized = std::io::repeat(0).take(MAX_ATTACHMENT_SIZE as u64 + 1);
+        assert!(prepare_attachment_from_reader(oversized, "big", "text/plain", NOW).is_err());
+        assert!(prepare_attachment_from_reader(std::io::empty(), "a", "text/plain", NOW).is_err());
+    }
+}
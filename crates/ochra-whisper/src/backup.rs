@@ -0,0 +1,677 @@
+//! Opt-in encrypted backup of Whisper session ratchets.
+//!
+//! Whisper keeps no history by default (Section 7.1): ratchet state lives
+//! on one device and is zeroized at teardown, so a reinstall loses every
+//! session. A user may opt in to periodic backups of ratchet checkpoints,
+//! sealed under a [`RecoveryKey`] derived from the PIK and a passphrase, and
+//! kept in a local file or a DHT dead drop.
+//!
+//! ## Forward-secrecy tradeoff
+//!
+//! A checkpoint holds chain keys. Anyone with the sealed backup, the PIK and
+//! the passphrase can derive every message key from a checkpoint until the
+//! session's next DH ratchet step. The [`BackupScope`] decides how far back
+//! that reaches, and its [`Exposure`] states it. A [`BackupPolicy`] can only
+//! be built from the exposure the user accepted, so widening the scope
+//! needs a fresh acknowledgement.
+//!
+//! A restored checkpoint is receive-only: send keys past it may already
+//! have been used, so the device must complete a DH ratchet step before
+//! sending.
+//!
+//! ## Dead drops
+//!
+//! The dead drop is a BEP 44 mutable record under a key derived from the
+//! PIK alone, so a reinstalled device finds it once the PIK is recovered.
+//! Backups that do not fit one record are erasure-coded with
+//! `ochra_dht::sharding`, the record holding the shard manifest.
+//!
+//! | Parameter | Value |
+//! |-----------|-------|
+//! | Recovery KDF | Argon2id m=64 MB, t=3, p=4 |
+//! | Cipher | ChaCha20-Poly1305, header as AAD |
+//! | Max checkpoints per session | 8 |
+
+use std::collections::{BTreeMap, VecDeque};
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::SigningKey;
+use ochra_crypto::secret::SecretBytes;
+use ochra_dht::bep44::DhtRecord;
+use ochra_dht::record_types::RecordTypeRegistry;
+use ochra_dht::sharding::{self, RecordShard, ShardingPolicy};
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, WhisperError};
+
+/// Sealed backup format version.
+pub const BACKUP_VERSION: u8 = 1;
+
+/// Most checkpoints kept per session.
+pub const MAX_CHECKPOINTS_PER_SESSION: u8 = 8;
+
+/// Salt of the dead-drop record.
+pub const DEAD_DROP_SALT: &[u8] = b"whisper-backup";
+
+/// Version, KDF parameters, salt, creation time, and nonce.
+const HEADER_LEN: usize = 1 + 12 + 16 + 8 + 12;
+
+/// Sharding used for dead drops too large for one record.
+const DEAD_DROP_SHARDING: ShardingPolicy = ShardingPolicy {
+    threshold: ochra_dht::MAX_RECORD_SIZE + 1,
+    data_shards: 8,
+    total_shards: 12,
+};
+
+fn backup_error(detail: impl std::fmt::Display) -> WhisperError {
+    WhisperError::Backup(detail.to_string())
+}
+
+/// Argon2id cost of deriving a recovery key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory in KiB.
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+/// Default recovery KDF cost.
+pub const RECOVERY_KDF: KdfParams = KdfParams {
+    m_cost: 65536,
+    t_cost: 3,
+    p_cost: 4,
+};
+
+/// Key that seals backups. Never leaves the device unwrapped.
+#[derive(Clone)]
+pub struct RecoveryKey {
+    key: SecretBytes<32>,
+    salt: [u8; 16],
+    params: KdfParams,
+}
+
+impl RecoveryKey {
+    /// Derive the key from the PIK secret and a passphrase.
+    ///
+    /// # Errors
+    ///
+    /// - [`WhisperError::Backup`] if the passphrase is empty
+    /// - [`WhisperError::Crypto`] if Argon2id rejects the parameters
+    pub fn derive(
+        pik_secret: &[u8; 32],
+        passphrase: &str,
+        salt: [u8; 16],
+        params: KdfParams,
+    ) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(backup_error("passphrase must not be empty"));
+        }
+        let stretched = ochra_crypto::argon2id::derive_key_custom(
+            passphrase.as_bytes(),
+            &salt,
+            params.m_cost,
+            params.t_cost,
+            params.p_cost,
+            32,
+        )
+        .map_err(|e| WhisperError::Crypto(e.to_string()))?;
+        let key = blake3::derive_key(
+            blake3::contexts::WHISPER_BACKUP_KEY,
+            &blake3::encode_multi_field(&[pik_secret, &stretched]),
+        );
+        Ok(Self {
+            key: SecretBytes::new(key),
+            salt,
+            params,
+        })
+    }
+
+    /// Rebuild a key previously derived with [`derive`](Self::derive).
+    pub fn from_parts(key: [u8; 32], salt: [u8; 16], params: KdfParams) -> Self {
+        Self {
+            key: SecretBytes::new(key),
+            salt,
+            params,
+        }
+    }
+
+    /// The raw key, for wrapping at rest.
+    pub fn expose(&self) -> &[u8; 32] {
+        self.key.expose()
+    }
+
+    pub fn salt(&self) -> [u8; 16] {
+        self.salt
+    }
+
+    pub fn params(&self) -> KdfParams {
+        self.params
+    }
+}
+
+/// A point in one session's ratchet from which later messages decrypt.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RatchetCheckpoint {
+    pub session_id: [u8; 16],
+    /// Serialized ratchet state: root key, receiving chain, and skipped
+    /// message keys at the checkpoint.
+    pub ratchet_state: Vec<u8>,
+    /// Sequence number of the next message after the checkpoint.
+    pub next_sequence: u64,
+    pub taken_at: u64,
+}
+
+/// How many checkpoints of each session a backup keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "checkpoints")]
+pub enum BackupScope {
+    /// Only the newest checkpoint; restores sessions and later messages.
+    Latest,
+    /// The newest `n` checkpoints (at most
+    /// [`MAX_CHECKPOINTS_PER_SESSION`]); restores history back to the
+    /// oldest one kept.
+    Window(u8),
+}
+
+impl BackupScope {
+    /// Checkpoints kept per session.
+    pub fn retained(self) -> usize {
+        match self {
+            Self::Latest => 1,
+            Self::Window(n) => usize::from(n.clamp(1, MAX_CHECKPOINTS_PER_SESSION)),
+        }
+    }
+
+    /// What a leaked backup exposes under this scope.
+    pub fn exposure(self) -> Exposure {
+        let checkpoints_per_session = self.retained();
+        Exposure {
+            checkpoints_per_session,
+            exposes_earlier_messages: checkpoints_per_session > 1,
+            summary: if checkpoints_per_session > 1 {
+                "With the backup, PIK and passphrase, an attacker can read each \
+                 session from its oldest backed-up checkpoint until the session's \
+                 next DH ratchet step after the newest one."
+            } else {
+                "With the backup, PIK and passphrase, an attacker can read each \
+                 session from its newest checkpoint until the session's next DH \
+                 ratchet step. Earlier messages stay forward-secret."
+            },
+        }
+    }
+}
+
+/// Forward secrecy given up by enabling a backup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Exposure {
+    pub checkpoints_per_session: usize,
+    /// Whether messages before the newest checkpoint are exposed.
+    pub exposes_earlier_messages: bool,
+    /// Plain-language statement to show before enabling.
+    pub summary: &'static str,
+}
+
+/// Where sealed backups are kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupDestination {
+    /// A file in the daemon's data directory.
+    Local,
+    /// A DHT dead drop keyed by the PIK.
+    DeadDrop,
+}
+
+/// An enabled backup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupPolicy {
+    pub scope: BackupScope,
+    pub destination: BackupDestination,
+    /// Seconds between exports.
+    pub interval_secs: u64,
+}
+
+impl BackupPolicy {
+    /// Shortest interval between exports.
+    pub const MIN_INTERVAL_SECS: u64 = 300;
+
+    /// Enable backups after the user accepted `acknowledged`.
+    ///
+    /// # Errors
+    ///
+    /// - [`WhisperError::Backup`] if `acknowledged` is not the exposure of
+    ///   `scope`, the window is out of range, or the interval is too short
+    pub fn new(
+        scope: BackupScope,
+        destination: BackupDestination,
+        interval_secs: u64,
+        acknowledged: &Exposure,
+    ) -> Result<Self> {
+        if let BackupScope::Window(n) = scope {
+            if n == 0 || n > MAX_CHECKPOINTS_PER_SESSION {
+                return Err(backup_error(format!(
+                    "window must keep 1 to {MAX_CHECKPOINTS_PER_SESSION} checkpoints"
+                )));
+            }
+        }
+        if *acknowledged != scope.exposure() {
+            return Err(backup_error(
+                "acknowledged exposure does not match the backup scope",
+            ));
+        }
+        if interval_secs < Self::MIN_INTERVAL_SECS {
+            return Err(backup_error(format!(
+                "interval must be at least {} seconds",
+                Self::MIN_INTERVAL_SECS
+            )));
+        }
+        Ok(Self {
+            scope,
+            destination,
+            interval_secs,
+        })
+    }
+}
+
+/// Checkpoints recorded since the daemon started, bounded by the scope.
+#[derive(Debug)]
+pub struct CheckpointLog {
+    retained: usize,
+    sessions: BTreeMap<[u8; 16], VecDeque<RatchetCheckpoint>>,
+    /// Bumped on every change, so exports can skip unchanged logs.
+    generation: u64,
+}
+
+impl Default for CheckpointLog {
+    fn default() -> Self {
+        Self::new(BackupScope::Latest)
+    }
+}
+
+impl CheckpointLog {
+    /// An empty log keeping checkpoints for `scope`.
+    pub fn new(scope: BackupScope) -> Self {
+        Self {
+            retained: scope.retained(),
+            sessions: BTreeMap::new(),
+            generation: 0,
+        }
+    }
+
+    /// Change the scope, dropping checkpoints it no longer keeps.
+    pub fn set_scope(&mut self, scope: BackupScope) {
+        self.retained = scope.retained();
+        for checkpoints in self.sessions.values_mut() {
+            while checkpoints.len() > self.retained {
+                checkpoints.pop_front();
+            }
+        }
+        self.generation += 1;
+    }
+
+    /// Record a checkpoint, dropping the session's oldest if over scope.
+    pub fn record(&mut self, checkpoint: RatchetCheckpoint) {
+        let checkpoints = self.sessions.entry(checkpoint.session_id).or_default();
+        checkpoints.push_back(checkpoint);
+        while checkpoints.len() > self.retained {
+            checkpoints.pop_front();
+        }
+        self.generation += 1;
+    }
+
+    /// Drop a session's checkpoints, e.g. after it was blocked.
+    pub fn forget(&mut self, session_id: &[u8; 16]) -> bool {
+        let removed = self.sessions.remove(session_id).is_some();
+        if removed {
+            self.generation += 1;
+        }
+        removed
+    }
+
+    /// Every checkpoint, oldest first within each session.
+    pub fn snapshot(&self) -> Vec<RatchetCheckpoint> {
+        self.sessions.values().flatten().cloned().collect()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn session_count(&self) -> usize {
+        self.sessions.len()
+    }
+}
+
+/// Encrypted checkpoints with the parameters needed to open them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SealedBackup {
+    pub params: KdfParams,
+    pub salt: [u8; 16],
+    pub created_at: u64,
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupPayload {
+    scope: BackupScope,
+    checkpoints: Vec<RatchetCheckpoint>,
+}
+
+impl SealedBackup {
+    fn header(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN);
+        out.push(BACKUP_VERSION);
+        out.extend_from_slice(&self.params.m_cost.to_le_bytes());
+        out.extend_from_slice(&self.params.t_cost.to_le_bytes());
+        out.extend_from_slice(&self.params.p_cost.to_le_bytes());
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&self.created_at.to_le_bytes());
+        out.extend_from_slice(&self.nonce);
+        out
+    }
+
+    /// Encode as header followed by ciphertext.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.header();
+        out.extend_from_slice(&self.ciphertext);
+        out
+    }
+
+    /// Decode a sealed backup. The contents are checked when opened.
+    ///
+    /// # Errors
+    ///
+    /// - [`WhisperError::Backup`] if the bytes are truncated or of an
+    ///   unknown version
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LEN + ochra_crypto::chacha20::TAG_SIZE {
+            return Err(backup_error("sealed backup truncated"));
+        }
+        if bytes[0] != BACKUP_VERSION {
+            return Err(backup_error(format!(
+                "unsupported backup version {}",
+                bytes[0]
+            )));
+        }
+        let u32_at =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let mut salt = [0u8; 16];
+        salt.copy_from_slice(&bytes[13..29]);
+        let mut created_at = [0u8; 8];
+        created_at.copy_from_slice(&bytes[29..37]);
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&bytes[37..HEADER_LEN]);
+        Ok(Self {
+            params: KdfParams {
+                m_cost: u32_at(1),
+                t_cost: u32_at(5),
+                p_cost: u32_at(9),
+            },
+            salt,
+            created_at: u64::from_le_bytes(created_at),
+            nonce,
+            ciphertext: bytes[HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+/// Seal `checkpoints` under `key`.
+///
+/// # Errors
+///
+/// - [`WhisperError::Backup`] if the checkpoints cannot be encoded
+/// - [`WhisperError::Crypto`] if encryption fails
+pub fn seal(
+    key: &RecoveryKey,
+    scope: BackupScope,
+    checkpoints: Vec<RatchetCheckpoint>,
+    created_at: u64,
+) -> Result<SealedBackup> {
+    let mut plaintext = Vec::new();
+    ciborium::into_writer(&BackupPayload { scope, checkpoints }, &mut plaintext)
+        .map_err(backup_error)?;
+    let mut nonce = [0u8; 12];
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut nonce);
+    let mut sealed = SealedBackup {
+        params: key.params,
+        salt: key.salt,
+        created_at,
+        nonce,
+        ciphertext: Vec::new(),
+    };
+    sealed.ciphertext =
+        ochra_crypto::chacha20::encrypt(key.expose(), &nonce, &plaintext, &sealed.header())
+            .map_err(|e| WhisperError::Crypto(e.to_string()))?;
+    Ok(sealed)
+}
+
+/// Open a sealed backup with the PIK and passphrase.
+///
+/// Returns the scope the backup was made under and its checkpoints. The
+/// checkpoints are receive-only until the session's next DH ratchet step.
+///
+/// # Errors
+///
+/// - [`WhisperError::Crypto`] if the PIK or passphrase is wrong or the
+///   backup was altered
+/// - [`WhisperError::Backup`] if the contents cannot be decoded
+pub fn open(
+    sealed: &SealedBackup,
+    pik_secret: &[u8; 32],
+    passphrase: &str,
+) -> Result<(BackupScope, Vec<RatchetCheckpoint>)> {
+    let key = RecoveryKey::derive(pik_secret, passphrase, sealed.salt, sealed.params)?;
+    open_with_key(sealed, &key)
+}
+
+/// Open a sealed backup with an already derived key.
+///
+/// # Errors
+///
+/// - as [`open`]
+pub fn open_with_key(
+    sealed: &SealedBackup,
+    key: &RecoveryKey,
+) -> Result<(BackupScope, Vec<RatchetCheckpoint>)> {
+    let plaintext = ochra_crypto::chacha20::decrypt(
+        key.expose(),
+        &sealed.nonce,
+        &sealed.ciphertext,
+        &sealed.header(),
+    )
+    .map_err(|_| WhisperError::Crypto("wrong PIK or passphrase, or altered backup".into()))?;
+    let payload: BackupPayload =
+        ciborium::from_reader(plaintext.as_slice()).map_err(backup_error)?;
+    Ok((payload.scope, payload.checkpoints))
+}
+
+/// Key that signs this PIK's dead-drop record.
+pub fn dead_drop_key(pik_secret: &[u8; 32]) -> SigningKey {
+    SigningKey::from_bytes(&blake3::derive_key(
+        blake3::contexts::WHISPER_BACKUP_DROP,
+        pik_secret,
+    ))
+}
+
+/// DHT key of this PIK's dead drop.
+pub fn dead_drop_address(pik_secret: &[u8; 32]) -> [u8; 32] {
+    let public_key = dead_drop_key(pik_secret).verifying_key().to_bytes();
+    blake3::hash(&[public_key.as_slice(), DEAD_DROP_SALT].concat())
+}
+
+/// Records to publish a backup as a dead drop.
+#[derive(Clone, Debug)]
+pub struct DeadDrop {
+    /// The mutable record at [`dead_drop_address`].
+    pub record: DhtRecord,
+    /// Shards to store as immutable records, if the backup was sharded.
+    pub shards: Vec<RecordShard>,
+}
+
+/// Build the dead drop for `sealed`. `seq` must increase with each export.
+///
+/// # Errors
+///
+/// - [`WhisperError::Backup`] if the backup is too large for a dead drop
+pub fn dead_drop(pik_secret: &[u8; 32], sealed: &SealedBackup, seq: u64) -> Result<DeadDrop> {
+    let (record, shards) = sharding::sign_value(
+        &dead_drop_key(pik_secret),
+        DEAD_DROP_SALT,
+        seq,
+        sealed.to_bytes(),
+        &DEAD_DROP_SHARDING,
+    )
+    .map_err(backup_error)?;
+    Ok(DeadDrop { record, shards })
+}
+
+/// Recover the sealed backup from a fetched dead drop.
+///
+/// # Errors
+///
+/// - [`WhisperError::Backup`] if the record is not this PIK's dead drop,
+///   its signature fails, or too few shards were fetched
+pub fn sealed_from_dead_drop(
+    pik_secret: &[u8; 32],
+    record: &DhtRecord,
+    shards: &[RecordShard],
+) -> Result<SealedBackup> {
+    record.validate().map_err(backup_error)?;
+    let expected = dead_drop_key(pik_secret).verifying_key().to_bytes();
+    let DhtRecord::Mutable {
+        public_key, salt, ..
+    } = record
+    else {
+        return Err(backup_error("dead drop must be a mutable record"));
+    };
+    if *public_key != expected || salt.as_slice() != DEAD_DROP_SALT {
+        return Err(backup_error("record is not this PIK's dead drop"));
+    }
+    let value = RecordTypeRegistry::standard()
+        .resolve_value(record, shards)
+        .map_err(backup_error)?;
+    SealedBackup::from_bytes(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIK: [u8; 32] = [7; 32];
+    const CHEAP: KdfParams = KdfParams {
+        m_cost: 1024,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    fn key() -> RecoveryKey {
+        RecoveryKey::derive(&PIK, "correct horse", [1; 16], CHEAP).expect("derive")
+    }
+
+    fn checkpoint(session: u8, seq: u64, state_len: usize) -> RatchetCheckpoint {
+        RatchetCheckpoint {
+            session_id: [session; 16],
+            ratchet_state: vec![seq as u8; state_len],
+            next_sequence: seq,
+            taken_at: 1_000 + seq,
+        }
+    }
+
+    #[test]
+    fn test_seal_and_open_round_trip() {
+        let checkpoints = vec![checkpoint(1, 4, 96), checkpoint(2, 9, 96)];
+        let sealed = seal(&key(), BackupScope::Latest, checkpoints.clone(), 5_000).expect("seal");
+        let decoded = SealedBackup::from_bytes(&sealed.to_bytes()).expect("decode");
+        assert_eq!(decoded, sealed);
+
+        let (scope, opened) = open(&decoded, &PIK, "correct horse").expect("open");
+        assert_eq!(scope, BackupScope::Latest);
+        assert_eq!(opened, checkpoints);
+
+        assert!(open(&decoded, &PIK, "wrong horse").is_err());
+        assert!(open(&decoded, &[8; 32], "correct horse").is_err());
+        assert!(RecoveryKey::derive(&PIK, "", [1; 16], CHEAP).is_err());
+    }
+
+    #[test]
+    fn test_altered_header_fails_to_open() {
+        let sealed = seal(
+            &key(),
+            BackupScope::Latest,
+            vec![checkpoint(1, 1, 32)],
+            5_000,
+        )
+        .expect("seal");
+        let mut bytes = sealed.to_bytes();
+        bytes[29] ^= 1; // created_at
+        let altered = SealedBackup::from_bytes(&bytes).expect("decode");
+        assert!(open_with_key(&altered, &key()).is_err());
+    }
+
+    #[test]
+    fn test_policy_requires_matching_acknowledgement() {
+        let latest = BackupScope::Latest.exposure();
+        let window = BackupScope::Window(4).exposure();
+        assert!(!latest.exposes_earlier_messages);
+        assert!(window.exposes_earlier_messages);
+
+        assert!(
+            BackupPolicy::new(BackupScope::Latest, BackupDestination::Local, 3600, &latest).is_ok()
+        );
+        assert!(matches!(
+            BackupPolicy::new(
+                BackupScope::Window(4),
+                BackupDestination::Local,
+                3600,
+                &latest
+            ),
+            Err(WhisperError::Backup(_))
+        ));
+        assert!(BackupPolicy::new(
+            BackupScope::Window(9),
+            BackupDestination::Local,
+            3600,
+            &window
+        )
+        .is_err());
+        assert!(
+            BackupPolicy::new(BackupScope::Latest, BackupDestination::Local, 60, &latest).is_err()
+        );
+    }
+
+    #[test]
+    fn test_log_keeps_scope_window() {
+        let mut log = CheckpointLog::new(BackupScope::Window(2));
+        for seq in 0..5 {
+            log.record(checkpoint(1, seq, 8));
+        }
+        log.record(checkpoint(2, 0, 8));
+        let seqs: Vec<u64> = log.snapshot().iter().map(|c| c.next_sequence).collect();
+        assert_eq!(seqs, vec![3, 4, 0]);
+
+        let before = log.generation();
+        log.set_scope(BackupScope::Latest);
+        assert_eq!(log.snapshot().len(), 2);
+        assert!(log.forget(&[2; 16]));
+        assert!(!log.forget(&[2; 16]));
+        assert!(log.generation() > before);
+        assert_eq!(log.session_count(), 1);
+    }
+
+    #[test]
+    fn test_dead_drop_round_trip_small_and_sharded() {
+        for sessions in [1u8, 40] {
+            let checkpoints = (0..sessions).map(|s| checkpoint(s, 1, 96)).collect();
+            let sealed = seal(&key(), BackupScope::Latest, checkpoints, 5_000).expect("seal");
+            let drop = dead_drop(&PIK, &sealed, 3).expect("dead drop");
+            assert_eq!(drop.shards.is_empty(), sessions == 1);
+            assert_eq!(drop.record.storage_key(), dead_drop_address(&PIK));
+
+            let fetched: Vec<RecordShard> = drop.shards.iter().skip(2).cloned().collect();
+            let recovered = sealed_from_dead_drop(&PIK, &drop.record, &fetched).expect("recover");
+            assert_eq!(recovered, sealed);
+            assert!(sealed_from_dead_drop(&[8; 32], &drop.record, &fetched).is_err());
+        }
+    }
+}
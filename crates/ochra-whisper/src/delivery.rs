@@ -0,0 +1,473 @@
+//! Outbound message delivery tracking (Section 7.4).
+//!
+//! Every outbound message moves through
+//! [`WhisperDeliveryState`]: it is `queued` when the user sends it, `sent`
+//! once handed to the rendezvous circuit, `delivered` when the peer's
+//! `WhisperAck` covers its sequence, and `read` when a `ReadAck` message
+//! does. Both acks are cumulative: acknowledging sequence `n` covers every
+//! earlier message in the session.
+//!
+//! Messages without a delivery ack are resent with exponential backoff
+//! ([`retry_delay`]) and marked `failed` after [`MAX_DELIVERY_ATTEMPTS`].
+//! Only sends the caller reports with [`Outbox::transmitted`] count: a
+//! message that could not be handed to any route stays `queued` and is
+//! offered again on the same backoff. Failed messages are forgotten after
+//! [`FAILED_RETENTION_SECS`], during which a late ack still counts.
+//! The [`Outbox`] keeps the ciphertext needed for resends in memory only;
+//! whoever persists the returned [`DeliveryChange`]s must store the state,
+//! never the message.
+//!
+//! Peers that advertise `read_receipts: false` never send `ReadAck`s; for
+//! their sessions `delivered` is final and messages are forgotten on
+//! delivery.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use ochra_types::whisper::WhisperDeliveryState;
+
+use crate::{Result, WhisperError};
+
+/// Delay before the first resend.
+pub const INITIAL_RETRY_SECS: u64 = 5;
+
+/// Upper bound on the delay between resends.
+pub const MAX_RETRY_SECS: u64 = 300;
+
+/// Send attempts before a message is marked failed.
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 6;
+
+/// How long a failed message is kept for a late ack.
+pub const FAILED_RETENTION_SECS: u64 = 3600;
+
+/// Size of a `ReadAck` message body: the big-endian `up_to_sequence`.
+pub const READ_ACK_BODY_LEN: usize = 8;
+
+/// Delay before the next resend after `attempts` sends.
+///
+/// Doubles from [`INITIAL_RETRY_SECS`] per attempt, capped at
+/// [`MAX_RETRY_SECS`].
+pub fn retry_delay(attempts: u32) -> u64 {
+    let doublings = attempts.saturating_sub(1).min(16);
+    (INITIAL_RETRY_SECS << doublings).min(MAX_RETRY_SECS)
+}
+
+/// Whether a message in state `from` may move to `to`.
+///
+/// States only move forward. `failed` is reachable from the pending states
+/// and can still be overtaken by a late delivery or read ack.
+pub fn can_advance(from: WhisperDeliveryState, to: WhisperDeliveryState) -> bool {
+    use WhisperDeliveryState::*;
+    matches!(
+        (from, to),
+        (Queued, Sent | Delivered | Read | Failed)
+            | (Sent, Delivered | Read | Failed)
+            | (Delivered, Read)
+            | (Failed, Delivered | Read)
+    )
+}
+
+/// Encode the body of a `ReadAck` message.
+pub fn read_ack_body(up_to_sequence: u64) -> Vec<u8> {
+    up_to_sequence.to_be_bytes().to_vec()
+}
+
+/// Decode the body of a `ReadAck` message.
+pub fn parse_read_ack(body: &[u8]) -> Result<u64> {
+    let bytes: [u8; READ_ACK_BODY_LEN] = body.try_into().map_err(|_| {
+        WhisperError::Delivery(format!(
+            "read ack body must be {READ_ACK_BODY_LEN} bytes, got {}",
+            body.len()
+        ))
+    })?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// A message that changed delivery state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeliveryChange {
+    /// Session the message belongs to.
+    pub session_id: [u8; 16],
+    /// Message sequence within the session.
+    pub sequence: u64,
+    /// New delivery state.
+    pub state: WhisperDeliveryState,
+    /// Send attempts so far.
+    pub attempts: u32,
+}
+
+/// A message due to be (re)sent.
+#[derive(Clone, Debug)]
+pub struct SendAttempt {
+    /// Session the message belongs to.
+    pub session_id: [u8; 16],
+    /// Message sequence within the session.
+    pub sequence: u64,
+    /// Encrypted message to hand to the route.
+    pub payload: Vec<u8>,
+    /// 1 for the first send.
+    pub attempt: u32,
+}
+
+/// Result of an [`Outbox::poll`].
+#[derive(Debug, Default)]
+pub struct OutboxPoll {
+    /// Messages to send now.
+    pub attempts: Vec<SendAttempt>,
+    /// State changes caused by the poll (give-ups).
+    pub changes: Vec<DeliveryChange>,
+}
+
+#[derive(Debug)]
+struct OutboundMessage {
+    /// Ciphertext for resends; dropped once no resend can happen.
+    payload: Option<Vec<u8>>,
+    state: WhisperDeliveryState,
+    attempts: u32,
+    /// When a pending message is next offered, or when a failed one is
+    /// forgotten.
+    next_attempt_at: u64,
+}
+
+/// In-memory delivery state machine for outbound messages.
+#[derive(Debug, Default)]
+pub struct Outbox {
+    messages: BTreeMap<([u8; 16], u64), OutboundMessage>,
+    next_sequence: HashMap<[u8; 16], u64>,
+    /// Sessions whose peer does not send read receipts.
+    no_read_receipts: HashSet<[u8; 16]>,
+}
+
+impl Outbox {
+    /// Create an empty outbox.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a message for sending and return its sequence number.
+    ///
+    /// Sequences start at 1 and increase per session.
+    pub fn enqueue(&mut self, session_id: [u8; 16], payload: Vec<u8>, now: u64) -> u64 {
+        let sequence = self.reserve(session_id);
+        self.insert(session_id, sequence, payload, now);
+        sequence
+    }
+
+    /// Allocate the next sequence number of a session, for a message that
+    /// must carry it before it is queued with [`Outbox::insert`].
+    pub fn reserve(&mut self, session_id: [u8; 16]) -> u64 {
+        let next = self.next_sequence.entry(session_id).or_insert(1);
+        let sequence = *next;
+        *next += 1;
+        sequence
+    }
+
+    /// Continue a session's sequence numbers at `next_sequence` or later,
+    /// after it was restored from a backup.
+    pub fn resume(&mut self, session_id: [u8; 16], next_sequence: u64) {
+        let next = self.next_sequence.entry(session_id).or_insert(1);
+        *next = (*next).max(next_sequence);
+    }
+
+    /// Queue a message under a sequence from [`Outbox::reserve`].
+    pub fn insert(&mut self, session_id: [u8; 16], sequence: u64, payload: Vec<u8>, now: u64) {
+        self.messages.insert(
+            (session_id, sequence),
+            OutboundMessage {
+                payload: Some(payload),
+                state: WhisperDeliveryState::Queued,
+                attempts: 0,
+                next_attempt_at: now,
+            },
+        );
+    }
+
+    /// Collect messages due for a send, give up on exhausted ones, and
+    /// forget failed ones past [`FAILED_RETENTION_SECS`].
+    ///
+    /// Report each attempt actually handed to a route with
+    /// [`Outbox::transmitted`]; the others are offered again after the
+    /// backoff.
+    pub fn poll(&mut self, now: u64) -> OutboxPoll {
+        self.messages.retain(|_, msg| {
+            msg.state != WhisperDeliveryState::Failed || msg.next_attempt_at > now
+        });
+        let mut poll = OutboxPoll::default();
+        for (&(session_id, sequence), msg) in &mut self.messages {
+            if !msg.state.is_pending() || msg.next_attempt_at > now {
+                continue;
+            }
+            if msg.attempts >= MAX_DELIVERY_ATTEMPTS {
+                msg.state = WhisperDeliveryState::Failed;
+                msg.payload = None;
+                msg.next_attempt_at = now + FAILED_RETENTION_SECS;
+                poll.changes.push(DeliveryChange {
+                    session_id,
+                    sequence,
+                    state: msg.state,
+                    attempts: msg.attempts,
+                });
+                continue;
+            }
+            let Some(payload) = &msg.payload else {
+                continue;
+            };
+            let attempt = msg.attempts + 1;
+            msg.next_attempt_at = now + retry_delay(attempt);
+            poll.attempts.push(SendAttempt {
+                session_id,
+                sequence,
+                payload: payload.clone(),
+                attempt,
+            });
+        }
+        poll
+    }
+
+    /// Record that a polled attempt was handed to a route. Returns the
+    /// change if the message was still `queued`.
+    pub fn transmitted(&mut self, session_id: [u8; 16], sequence: u64) -> Option<DeliveryChange> {
+        let msg = self.messages.get_mut(&(session_id, sequence))?;
+        if !msg.state.is_pending() {
+            return None;
+        }
+        msg.attempts += 1;
+        if msg.state != WhisperDeliveryState::Queued {
+            return None;
+        }
+        msg.state = WhisperDeliveryState::Sent;
+        Some(DeliveryChange {
+            session_id,
+            sequence,
+            state: msg.state,
+            attempts: msg.attempts,
+        })
+    }
+
+    /// Record whether a session's peer sends read receipts.
+    pub fn set_read_receipts(&mut self, session_id: [u8; 16], expected: bool) {
+        if expected {
+            self.no_read_receipts.remove(&session_id);
+        } else {
+            self.no_read_receipts.insert(session_id);
+        }
+    }
+
+    /// Apply a cumulative delivery ack from the peer.
+    pub fn acknowledge(
+        &mut self,
+        session_id: [u8; 16],
+        up_to_sequence: u64,
+    ) -> Vec<DeliveryChange> {
+        let changes = self.advance(session_id, up_to_sequence, WhisperDeliveryState::Delivered);
+        if self.no_read_receipts.contains(&session_id) {
+            self.messages.retain(|&(sid, seq), msg| {
+                sid != session_id
+                    || seq > up_to_sequence
+                    || msg.state != WhisperDeliveryState::Delivered
+            });
+        }
+        changes
+    }
+
+    /// Apply a cumulative read ack from the peer.
+    ///
+    /// Read messages need no further tracking and are forgotten.
+    pub fn mark_read(&mut self, session_id: [u8; 16], up_to_sequence: u64) -> Vec<DeliveryChange> {
+        let changes = self.advance(session_id, up_to_sequence, WhisperDeliveryState::Read);
+        self.messages
+            .retain(|&(sid, seq), _| sid != session_id || seq > up_to_sequence);
+        changes
+    }
+
+    /// Forget a closed session's messages. Returns how many were dropped.
+    pub fn close_session(&mut self, session_id: &[u8; 16]) -> usize {
+        let before = self.messages.len();
+        self.messages.retain(|(sid, _), _| sid != session_id);
+        self.next_sequence.remove(session_id);
+        self.no_read_receipts.remove(session_id);
+        before - self.messages.len()
+    }
+
+    /// Current state of a message, if still tracked.
+    pub fn state(&self, session_id: &[u8; 16], sequence: u64) -> Option<WhisperDeliveryState> {
+        self.messages
+            .get(&(*session_id, sequence))
+            .map(|msg| msg.state)
+    }
+
+    /// Number of messages still awaiting a delivery ack.
+    pub fn pending(&self) -> usize {
+        self.messages
+            .values()
+            .filter(|msg| msg.state.is_pending())
+            .count()
+    }
+
+    fn advance(
+        &mut self,
+        session_id: [u8; 16],
+        up_to_sequence: u64,
+        to: WhisperDeliveryState,
+    ) -> Vec<DeliveryChange> {
+        let mut changes = Vec::new();
+        for (&(_, sequence), msg) in self
+            .messages
+            .range_mut((session_id, 0)..=(session_id, up_to_sequence))
+        {
+            if !can_advance(msg.state, to) {
+                continue;
+            }
+            msg.state = to;
+            msg.payload = None;
+            changes.push(DeliveryChange {
+                session_id,
+                sequence,
+                state: to,
+                attempts: msg.attempts,
+            });
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use WhisperDeliveryState::*;
+
+    const SESSION: [u8; 16] = [7u8; 16];
+
+    /// Record every attempt in `poll` as transmitted.
+    fn transmit_all(outbox: &mut Outbox, poll: &OutboxPoll) -> Vec<DeliveryChange> {
+        poll.attempts
+            .iter()
+            .filter_map(|a| outbox.transmitted(a.session_id, a.sequence))
+            .collect()
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        assert_eq!(retry_delay(1), INITIAL_RETRY_SECS);
+        assert_eq!(retry_delay(2), INITIAL_RETRY_SECS * 2);
+        assert_eq!(retry_delay(3), INITIAL_RETRY_SECS * 4);
+        assert_eq!(retry_delay(30), MAX_RETRY_SECS);
+    }
+
+    #[test]
+    fn test_states_only_move_forward() {
+        assert!(can_advance(Queued, Sent));
+        assert!(can_advance(Sent, Read));
+        assert!(can_advance(Failed, Delivered));
+        assert!(!can_advance(Delivered, Sent));
+        assert!(!can_advance(Read, Delivered));
+        assert!(!can_advance(Delivered, Failed));
+    }
+
+    #[test]
+    fn test_send_ack_and_read() {
+        let mut outbox = Outbox::new();
+        let first = outbox.enqueue(SESSION, b"one".to_vec(), 100);
+        let second = outbox.enqueue(SESSION, b"two".to_vec(), 100);
+        assert_eq!((first, second), (1, 2));
+
+        let poll = outbox.poll(100);
+        assert_eq!(poll.attempts.len(), 2);
+        assert!(poll.changes.is_empty());
+        assert_eq!(outbox.state(&SESSION, 1), Some(Queued));
+        let sent = transmit_all(&mut outbox, &poll);
+        assert_eq!(sent.len(), 2);
+        assert!(sent.iter().all(|c| c.state == Sent));
+
+        let delivered = outbox.acknowledge(SESSION, 1);
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(outbox.state(&SESSION, 1), Some(Delivered));
+        assert_eq!(outbox.state(&SESSION, 2), Some(Sent));
+        assert!(outbox.acknowledge(SESSION, 1).is_empty());
+
+        let read = outbox.mark_read(SESSION, 2);
+        assert_eq!(read.len(), 2);
+        assert!(read.iter().all(|c| c.state == Read));
+        assert_eq!(outbox.state(&SESSION, 1), None);
+        assert_eq!(outbox.pending(), 0);
+    }
+
+    #[test]
+    fn test_resend_with_backoff_then_fail() {
+        let mut outbox = Outbox::new();
+        outbox.enqueue(SESSION, b"hi".to_vec(), 0);
+
+        let mut now = 0;
+        for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+            let poll = outbox.poll(now);
+            assert_eq!(poll.attempts.len(), 1);
+            assert_eq!(poll.attempts[0].attempt, attempt);
+            transmit_all(&mut outbox, &poll);
+            assert!(outbox.poll(now).attempts.is_empty());
+            now += retry_delay(attempt);
+        }
+
+        let poll = outbox.poll(now);
+        assert!(poll.attempts.is_empty());
+        assert_eq!(poll.changes.len(), 1);
+        assert_eq!(poll.changes[0].state, Failed);
+
+        // A late ack still counts
+        assert_eq!(outbox.acknowledge(SESSION, 1).len(), 1);
+        assert_eq!(outbox.state(&SESSION, 1), Some(Delivered));
+    }
+
+    #[test]
+    fn test_untransmitted_stays_queued_and_failed_is_pruned() {
+        let mut outbox = Outbox::new();
+        outbox.enqueue(SESSION, b"hi".to_vec(), 0);
+
+        // No route: offered again on the backoff, never failed
+        let mut now = 0;
+        for _ in 0..(MAX_DELIVERY_ATTEMPTS * 3) {
+            let poll = outbox.poll(now);
+            assert!(poll.changes.is_empty());
+            now += MAX_RETRY_SECS;
+        }
+        assert_eq!(outbox.state(&SESSION, 1), Some(Queued));
+
+        for _ in 0..=MAX_DELIVERY_ATTEMPTS {
+            let poll = outbox.poll(now);
+            transmit_all(&mut outbox, &poll);
+            now += MAX_RETRY_SECS;
+        }
+        assert_eq!(outbox.state(&SESSION, 1), Some(Failed));
+        outbox.poll(now + FAILED_RETENTION_SECS);
+        assert_eq!(outbox.state(&SESSION, 1), None);
+    }
+
+    #[test]
+    fn test_delivered_is_final_without_read_receipts() {
+        let mut outbox = Outbox::new();
+        outbox.set_read_receipts(SESSION, false);
+        outbox.enqueue(SESSION, b"one".to_vec(), 0);
+        outbox.enqueue(SESSION, b"two".to_vec(), 0);
+        let poll = outbox.poll(0);
+        transmit_all(&mut outbox, &poll);
+
+        assert_eq!(outbox.acknowledge(SESSION, 1).len(), 1);
+        assert_eq!(outbox.state(&SESSION, 1), None);
+        assert_eq!(outbox.state(&SESSION, 2), Some(Sent));
+    }
+
+    #[test]
+    fn test_read_ack_body_and_close() {
+        assert_eq!(parse_read_ack(&read_ack_body(42)).expect("parse"), 42);
+        assert!(parse_read_ack(&[1, 2, 3]).is_err());
+
+        let mut outbox = Outbox::new();
+        outbox.enqueue(SESSION, b"a".to_vec(), 0);
+        outbox.enqueue([8u8; 16], b"b".to_vec(), 0);
+        assert_eq!(outbox.close_session(&SESSION), 1);
+        assert_eq!(outbox.pending(), 1);
+        assert_eq!(outbox.enqueue(SESSION, b"c".to_vec(), 0), 1);
+
+        outbox.resume(SESSION, 7);
+        outbox.resume(SESSION, 3);
+        assert_eq!(outbox.reserve(SESSION), 7);
+    }
+}
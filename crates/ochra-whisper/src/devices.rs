@@ -0,0 +1,476 @@
+//! Multi-device linking for Whisper.
+//!
+//! A handle may be reachable on several devices. The primary device (the one
+//! holding the handle signing key) authorizes each secondary device, and
+//! every authorized device advertises its own store-and-forward mailbox in
+//! the handle descriptor.
+//!
+//! ## Linking Flow
+//!
+//! 1. The secondary device generates [`DeviceKeys`] and displays a
+//!    [`DeviceLinkRequest`] as an `ochra://link-device/<base64url>` QR code.
+//! 2. The primary scans it and signs a [`DeviceAuthorization`] with the
+//!    handle signing key.
+//! 3. The primary republishes the descriptor with the new device's mailbox.
+//!
+//! ## Fan-out
+//!
+//! Live sessions terminate on one device. That device bridges ratchet state
+//! snapshots and delivered plaintexts to its siblings as [`FanoutEnvelope`]s,
+//! ECIES-sealed to each device's fan-out key.
+//!
+//! | Parameter | Value |
+//! |-----------|-------|
+//! | Max linked devices (incl. primary) | 5 |
+//! | Link request TTL | 5 minutes |
+
+use ochra_crypto::ecies;
+use ochra_crypto::ed25519::{KeyPair, Signature, VerifyingKey};
+use ochra_crypto::x25519::{X25519PublicKey, X25519StaticSecret};
+use ochra_types::whisper::MailboxEntry;
+use serde::{Deserialize, Serialize};
+
+use crate::mailbox::MailboxKey;
+use crate::{Result, WhisperError};
+
+/// URL scheme for device link QR codes.
+const LINK_SCHEME: &str = "ochra://link-device/";
+
+/// Maximum linked devices per handle, including the primary.
+pub const MAX_LINKED_DEVICES: usize = 5;
+
+/// Seconds a link request remains valid after creation.
+pub const LINK_REQUEST_TTL_SECS: u64 = 300;
+
+/// Maximum device name length in characters.
+pub const MAX_DEVICE_NAME_LEN: usize = 64;
+
+/// Keys held by a single device.
+pub struct DeviceKeys {
+    device_id: [u8; 16],
+    fanout: X25519StaticSecret,
+    mailbox: MailboxKey,
+}
+
+impl DeviceKeys {
+    /// Generate keys for a new device.
+    pub fn generate() -> Self {
+        let mut device_id = [0u8; 16];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut device_id);
+        Self {
+            device_id,
+            fanout: X25519StaticSecret::random(),
+            mailbox: MailboxKey::generate(),
+        }
+    }
+
+    /// This device's identifier.
+    pub fn device_id(&self) -> [u8; 16] {
+        self.device_id
+    }
+
+    /// This device's mailbox keys.
+    pub fn mailbox(&self) -> &MailboxKey {
+        &self.mailbox
+    }
+
+    /// X25519 fan-out public key.
+    pub fn fanout_pk(&self) -> [u8; 32] {
+        self.fanout.public_key().to_bytes()
+    }
+
+    /// Build a link request to show as a QR code.
+    ///
+    /// `relay_node_id` is the relay that will hold this device's mailbox.
+    pub fn link_request(
+        &self,
+        device_name: &str,
+        relay_node_id: [u8; 32],
+        now: u64,
+    ) -> DeviceLinkRequest {
+        let mut nonce = [0u8; 16];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut nonce);
+        DeviceLinkRequest {
+            device_id: self.device_id,
+            device_name: device_name.to_string(),
+            fanout_pk: self.fanout_pk(),
+            mailbox: self.mailbox.entry(self.device_id, relay_node_id),
+            nonce,
+            created_at: now,
+        }
+    }
+
+    /// Open a fan-out envelope addressed to this device.
+    pub fn open_fanout(&self, envelope: &FanoutEnvelope) -> Result<Vec<u8>> {
+        if envelope.device_id != self.device_id {
+            return Err(WhisperError::DeviceLink(
+                "fan-out envelope addressed to another device".to_string(),
+            ));
+        }
+        let ct = ecies::EciesCiphertext::from_bytes(&envelope.sealed)
+            .map_err(|e| WhisperError::Crypto(e.to_string()))?;
+        ecies::decrypt(&self.fanout, &ct).map_err(|e| WhisperError::Crypto(e.to_string()))
+    }
+}
+
+/// Link request displayed by a secondary device.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeviceLinkRequest {
+    /// Secondary device identifier.
+    pub device_id: [u8; 16],
+    /// Human-readable device name (e.g. "Laptop").
+    pub device_name: String,
+    /// X25519 fan-out public key.
+    pub fanout_pk: [u8; 32],
+    /// Mailbox the device will poll.
+    pub mailbox: MailboxEntry,
+    /// One-time nonce binding the authorization to this request.
+    pub nonce: [u8; 16],
+    /// Unix timestamp of creation.
+    pub created_at: u64,
+}
+
+impl DeviceLinkRequest {
+    /// Encode as an `ochra://link-device/<base64url>` URL for a QR code.
+    pub fn to_qr_url(&self) -> Result<String> {
+        let json = serde_json::to_vec(self).map_err(|e| WhisperError::DeviceLink(e.to_string()))?;
+        let encoded =
+            base64::Engine::encode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, &json);
+        Ok(format!("{LINK_SCHEME}{encoded}"))
+    }
+
+    /// Parse a scanned `ochra://link-device/<base64url>` URL.
+    pub fn from_qr_url(url: &str) -> Result<Self> {
+        let payload = url.strip_prefix(LINK_SCHEME).ok_or_else(|| {
+            WhisperError::DeviceLink("missing ochra://link-device/ prefix".to_string())
+        })?;
+        let json =
+            base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload)
+                .map_err(|e| WhisperError::DeviceLink(format!("base64 decode error: {e}")))?;
+        let request: Self = serde_json::from_slice(&json)
+            .map_err(|e| WhisperError::DeviceLink(format!("invalid link request: {e}")))?;
+        if request.mailbox.device_id != request.device_id {
+            return Err(WhisperError::DeviceLink(
+                "mailbox belongs to another device".to_string(),
+            ));
+        }
+        if request.device_name.chars().count() > MAX_DEVICE_NAME_LEN {
+            return Err(WhisperError::DeviceLink("device name too long".to_string()));
+        }
+        Ok(request)
+    }
+}
+
+/// Authorization of a device, signed by the handle signing key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    /// Authorized device identifier.
+    pub device_id: [u8; 16],
+    /// Human-readable device name.
+    pub device_name: String,
+    /// X25519 fan-out public key.
+    pub fanout_pk: [u8; 32],
+    /// Mailbox advertised for the device.
+    pub mailbox: MailboxEntry,
+    /// Nonce from the link request.
+    pub link_nonce: [u8; 16],
+    /// Unix timestamp of authorization.
+    pub authorized_at: u64,
+    /// Handle signing public key of the authorizing primary.
+    pub handle_signing_pk: [u8; 32],
+    /// Ed25519 signature by the handle signing key (64 bytes).
+    pub sig: Vec<u8>,
+}
+
+impl DeviceAuthorization {
+    /// Bytes covered by the handle signature.
+    fn signing_message(&self) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(19 + 32 + 16 * 3 + 32 * 4 + 8);
+        msg.extend_from_slice(b"whisper-device-auth");
+        msg.extend_from_slice(&self.handle_signing_pk);
+        msg.extend_from_slice(&self.device_id);
+        msg.extend_from_slice(&self.fanout_pk);
+        msg.extend_from_slice(&self.mailbox.device_id);
+        msg.extend_from_slice(&self.mailbox.relay_node_id);
+        msg.extend_from_slice(&self.mailbox.mailbox_pk);
+        msg.extend_from_slice(&self.mailbox.seal_pk);
+        msg.extend_from_slice(&self.link_nonce);
+        msg.extend_from_slice(&self.authorized_at.to_le_bytes());
+        msg.extend_from_slice(self.device_name.as_bytes());
+        msg
+    }
+
+    /// Verify the handle signature.
+    pub fn verify(&self) -> Result<()> {
+        let sig: [u8; 64] = self
+            .sig
+            .as_slice()
+            .try_into()
+            .map_err(|_| WhisperError::InvalidSignature)?;
+        let vk = VerifyingKey::from_bytes(&self.handle_signing_pk)
+            .map_err(|_| WhisperError::InvalidSignature)?;
+        vk.verify(&self.signing_message(), &Signature::from_bytes(&sig))
+            .map_err(|_| WhisperError::InvalidSignature)
+    }
+}
+
+/// Primary-side registry of linked devices for one handle.
+#[derive(Clone, Debug)]
+pub struct DeviceRegistry {
+    handle_signing_pk: [u8; 32],
+    devices: Vec<DeviceAuthorization>,
+}
+
+impl DeviceRegistry {
+    /// Create a registry for the handle, containing only the primary.
+    pub fn new(
+        handle_key: &KeyPair,
+        primary: &DeviceKeys,
+        relay_node_id: [u8; 32],
+        now: u64,
+    ) -> Self {
+        let mut registry = Self {
+            handle_signing_pk: handle_key.verifying_key.to_bytes(),
+            devices: Vec::new(),
+        };
+        let request = primary.link_request("primary", relay_node_id, now);
+        let auth = registry.sign(handle_key, &request, now);
+        registry.devices.push(auth);
+        registry
+    }
+
+    fn sign(
+        &self,
+        handle_key: &KeyPair,
+        request: &DeviceLinkRequest,
+        now: u64,
+    ) -> DeviceAuthorization {
+        let mut auth = DeviceAuthorization {
+            device_id: request.device_id,
+            device_name: request.device_name.clone(),
+            fanout_pk: request.fanout_pk,
+            mailbox: request.mailbox.clone(),
+            link_nonce: request.nonce,
+            authorized_at: now,
+            handle_signing_pk: self.handle_signing_pk,
+            sig: Vec::new(),
+        };
+        auth.sig = handle_key
+            .signing_key
+            .sign(&auth.signing_message())
+            .to_bytes()
+            .to_vec();
+        auth
+    }
+
+    /// Authorize a scanned link request.
+    pub fn authorize(
+        &mut self,
+        handle_key: &KeyPair,
+        request: &DeviceLinkRequest,
+        now: u64,
+    ) -> Result<DeviceAuthorization> {
+        if handle_key.verifying_key.to_bytes() != self.handle_signing_pk {
+            return Err(WhisperError::InvalidSignature);
+        }
+        if request.created_at > now || now - request.created_at > LINK_REQUEST_TTL_SECS {
+            return Err(WhisperError::DeviceLink("link request expired".to_string()));
+        }
+        if self.get(&request.device_id).is_some() {
+            return Err(WhisperError::DeviceLink(
+                "device already linked".to_string(),
+            ));
+        }
+        if self.devices.len() >= MAX_LINKED_DEVICES {
+            return Err(WhisperError::TooManyDevices(MAX_LINKED_DEVICES));
+        }
+        let auth = self.sign(handle_key, request, now);
+        self.devices.push(auth.clone());
+        Ok(auth)
+    }
+
+    /// Revoke a linked device. Returns `false` if it was not linked.
+    pub fn revoke(&mut self, device_id: &[u8; 16]) -> bool {
+        let before = self.devices.len();
+        self.devices.retain(|d| &d.device_id != device_id);
+        before != self.devices.len()
+    }
+
+    /// Look up a linked device.
+    pub fn get(&self, device_id: &[u8; 16]) -> Option<&DeviceAuthorization> {
+        self.devices.iter().find(|d| &d.device_id == device_id)
+    }
+
+    /// All linked devices, primary first.
+    pub fn devices(&self) -> &[DeviceAuthorization] {
+        &self.devices
+    }
+
+    /// Mailbox entries to advertise in the handle descriptor.
+    pub fn mailbox_entries(&self) -> Vec<MailboxEntry> {
+        self.devices.iter().map(|d| d.mailbox.clone()).collect()
+    }
+
+    /// Seal `payload` for every linked device except `from_device`.
+    ///
+    /// Used to bridge ratchet state snapshots and delivered plaintexts from
+    /// the device terminating a live session to its siblings.
+    pub fn fan_out(
+        &self,
+        from_device: &[u8; 16],
+        session_id: [u8; 16],
+        payload: &[u8],
+    ) -> Result<Vec<FanoutEnvelope>> {
+        self.devices
+            .iter()
+            .filter(|d| &d.device_id != from_device)
+            .map(|d| {
+                let sealed = ecies::encrypt(&X25519PublicKey::from_bytes(d.fanout_pk), payload)
+                    .map_err(|e| WhisperError::Crypto(e.to_string()))?
+                    .to_bytes();
+                Ok(FanoutEnvelope {
+                    device_id: d.device_id,
+                    session_id,
+                    sealed,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Payload bridged from one linked device to another.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FanoutEnvelope {
+    /// Recipient device.
+    pub device_id: [u8; 16],
+    /// Whisper session the payload belongs to.
+    pub session_id: [u8; 16],
+    /// Payload sealed to the device's fan-out key.
+    pub sealed: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn setup() -> (KeyPair, DeviceKeys, DeviceRegistry) {
+        let handle_key = KeyPair::generate();
+        let primary = DeviceKeys::generate();
+        let registry = DeviceRegistry::new(&handle_key, &primary, [7u8; 32], NOW);
+        (handle_key, primary, registry)
+    }
+
+    #[test]
+    fn test_qr_roundtrip() {
+        let device = DeviceKeys::generate();
+        let request = device.link_request("Laptop", [8u8; 32], NOW);
+        let url = request.to_qr_url().expect("encode");
+        assert!(url.starts_with(LINK_SCHEME));
+
+        let parsed = DeviceLinkRequest::from_qr_url(&url).expect("parse");
+        assert_eq!(parsed.device_id, device.device_id());
+        assert_eq!(parsed.device_name, "Laptop");
+        assert_eq!(parsed.mailbox.mailbox_pk, device.mailbox().mailbox_pk());
+
+        assert!(DeviceLinkRequest::from_qr_url("ochra://invite/abc").is_err());
+    }
+
+    #[test]
+    fn test_authorize_and_verify() {
+        let (handle_key, _primary, mut registry) = setup();
+        let laptop = DeviceKeys::generate();
+        let request = laptop.link_request("Laptop", [8u8; 32], NOW);
+
+        let auth = registry
+            .authorize(&handle_key, &request, NOW + 30)
+            .expect("authorize");
+        auth.verify().expect("verify");
+        assert_eq!(registry.devices().len(), 2);
+
+        let mut tampered = auth.clone();
+        tampered.mailbox.relay_node_id = [9u8; 32];
+        assert!(tampered.verify().is_err());
+    }
+
+    #[test]
+    fn test_authorize_rejects_expired_and_duplicate() {
+        let (handle_key, _primary, mut registry) = setup();
+        let laptop = DeviceKeys::generate();
+        let request = laptop.link_request("Laptop", [8u8; 32], NOW);
+
+        assert!(registry
+            .authorize(&handle_key, &request, NOW + LINK_REQUEST_TTL_SECS + 1)
+            .is_err());
+        registry
+            .authorize(&handle_key, &request, NOW)
+            .expect("authorize");
+        assert!(registry.authorize(&handle_key, &request, NOW).is_err());
+    }
+
+    #[test]
+    fn test_authorize_requires_handle_key() {
+        let (_handle_key, _primary, mut registry) = setup();
+        let request = DeviceKeys::generate().link_request("Phone", [8u8; 32], NOW);
+        assert!(matches!(
+            registry.authorize(&KeyPair::generate(), &request, NOW),
+            Err(WhisperError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_device_limit() {
+        let (handle_key, _primary, mut registry) = setup();
+        for _ in 1..MAX_LINKED_DEVICES {
+            let request = DeviceKeys::generate().link_request("d", [8u8; 32], NOW);
+            registry
+                .authorize(&handle_key, &request, NOW)
+                .expect("authorize");
+        }
+        let request = DeviceKeys::generate().link_request("extra", [8u8; 32], NOW);
+        assert!(matches!(
+            registry.authorize(&handle_key, &request, NOW),
+            Err(WhisperError::TooManyDevices(MAX_LINKED_DEVICES))
+        ));
+    }
+
+    #[test]
+    fn test_mailbox_entries_and_revoke() {
+        let (handle_key, primary, mut registry) = setup();
+        let laptop = DeviceKeys::generate();
+        let request = laptop.link_request("Laptop", [8u8; 32], NOW);
+        registry
+            .authorize(&handle_key, &request, NOW)
+            .expect("authorize");
+
+        let entries = registry.mailbox_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].device_id, primary.device_id());
+        assert_eq!(entries[1].device_id, laptop.device_id());
+
+        assert!(registry.revoke(&laptop.device_id()));
+        assert!(!registry.revoke(&laptop.device_id()));
+        assert_eq!(registry.mailbox_entries().len(), 1);
+    }
+
+    #[test]
+    fn test_fan_out_to_siblings() {
+        let (handle_key, primary, mut registry) = setup();
+        let laptop = DeviceKeys::generate();
+        let request = laptop.link_request("Laptop", [8u8; 32], NOW);
+        registry
+            .authorize(&handle_key, &request, NOW)
+            .expect("authorize");
+
+        let envelopes = registry
+            .fan_out(&primary.device_id(), [3u8; 16], b"ratchet snapshot")
+            .expect("fan out");
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(envelopes[0].device_id, laptop.device_id());
+
+        let payload = laptop.open_fanout(&envelopes[0]).expect("open");
+        assert_eq!(payload, b"ratchet snapshot");
+        assert!(primary.open_fanout(&envelopes[0]).is_err());
+    }
+}
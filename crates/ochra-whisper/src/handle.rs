@@ -0,0 +1,666 @@
+//! Handle registration cost and lifecycle (Section 7.2).
+//!
+//! Registering a handle costs an Argon2id PoW whose difficulty grows with
+//! how desirable the name is: every character below
+//! [`SHORT_HANDLE_LEN`] adds a leading zero bit (doubling the work), and
+//! names from a built-in dictionary add [`DICTIONARY_PENALTY_BITS`]. A Seed
+//! burn of [`burn_price`] may stand in for the PoW.
+//!
+//! Proofs are bound to the handle, the handle signing key, and a
+//! [`PROOF_PERIOD_SECS`] period, and stay valid for [`PROOF_VALID_PERIODS`]
+//! periods. Within that window the owner refreshes the descriptor with the
+//! same proof; after it, the descriptor must carry a new proof at the
+//! cheaper [`renewal_difficulty`]. A handle not refreshed for
+//! [`HANDLE_TTL_SECS`] expires, and after a further [`HANDLE_GRACE_SECS`]
+//! anyone may register it — so abandoned handles return to the pool.
+//!
+//! Nodes storing a descriptor run [`check_descriptor`] against the
+//! descriptor they already hold before accepting the put: their
+//! [`RecordStore`](ochra_dht::bep44::RecordStore) registers the
+//! handle descriptor type from [`register_record_type`].
+
+use ochra_crypto::argon2id::Argon2Params;
+use ochra_dht::record_types::{
+    handle_descriptor_schema, RecordType, RecordTypeRegistry, HANDLE_DESCRIPTOR_SALT,
+};
+use ochra_pow::argon2id_pow::{self, PowChallenge, PowSolution, NONCE_LEN, POW_OUTPUT_LEN};
+use ochra_types::whisper::HandleDescriptor;
+
+use crate::{Result, WhisperError};
+
+/// Minimum handle length in characters.
+pub const MIN_HANDLE_LEN: usize = 3;
+
+/// Maximum handle length in characters.
+pub const MAX_HANDLE_LEN: usize = 20;
+
+/// Prefixes no handle may start with.
+pub const RESERVED_PREFIXES: &[&str] = &["ochra_", "admin_", "mod_", "system_", "host_"];
+
+/// PoW difficulty, in leading zero bits, of an ordinary handle.
+pub const BASE_HANDLE_DIFFICULTY: u32 = 8;
+
+/// Handles shorter than this cost one extra bit per missing character.
+pub const SHORT_HANDLE_LEN: usize = 10;
+
+/// Extra bits for handles in the dictionary.
+pub const DICTIONARY_PENALTY_BITS: u32 = 4;
+
+/// Renewal proofs are this many bits cheaper than registration.
+pub const RENEWAL_DISCOUNT_BITS: u32 = 2;
+
+/// Seed burn, in micro-seeds, that substitutes for a base-difficulty PoW.
+/// Doubles with every extra bit.
+pub const BURN_BASE_MICRO_SEEDS: u64 = ochra_types::MICRO_SEEDS_PER_SEED / 10;
+
+/// A descriptor expires this long after its last refresh (7 days).
+pub const HANDLE_TTL_SECS: u64 = 7 * 24 * 3600;
+
+/// An expired handle stays reserved for its owner this long (30 days).
+pub const HANDLE_GRACE_SECS: u64 = 30 * 24 * 3600;
+
+/// Length of a proof period (90 days).
+pub const PROOF_PERIOD_SECS: u64 = 90 * 24 * 3600;
+
+/// Number of periods, including the one it was made in, a proof stays valid.
+pub const PROOF_VALID_PERIODS: u32 = 2;
+
+/// Domain prefix of the handle PoW challenge.
+const POW_PREFIX: &[u8] = b"handle-pow";
+
+/// Common words that cost [`DICTIONARY_PENALTY_BITS`] extra. Sorted.
+const DICTIONARY: &[&str] = &[
+    "about", "account", "alice", "apple", "art", "bank", "best", "bob", "book", "cat", "chat",
+    "city", "cloud", "code", "cool", "crypto", "data", "dev", "dog", "easy", "free", "game", "god",
+    "gold", "good", "help", "home", "info", "king", "life", "love", "mail", "man", "market",
+    "money", "music", "news", "official", "one", "pay", "queen", "root", "sale", "seed", "shop",
+    "star", "store", "support", "team", "tech", "test", "user", "wallet", "web", "world", "zero",
+];
+
+/// Lowercase form used for storage, lookup, and proofs.
+pub fn normalize(handle: &str) -> String {
+    handle.to_ascii_lowercase()
+}
+
+/// Check length, character set, and reserved prefixes.
+pub fn validate_handle(handle: &str) -> Result<()> {
+    let len = handle.chars().count();
+    if !(MIN_HANDLE_LEN..=MAX_HANDLE_LEN).contains(&len) {
+        return Err(WhisperError::InvalidHandle(format!(
+            "handle must be {MIN_HANDLE_LEN}-{MAX_HANDLE_LEN} characters"
+        )));
+    }
+    if !handle
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(WhisperError::InvalidHandle(
+            "handle may only contain a-z, 0-9, and _".to_string(),
+        ));
+    }
+    let lower = normalize(handle);
+    if let Some(prefix) = RESERVED_PREFIXES.iter().find(|p| lower.starts_with(*p)) {
+        return Err(WhisperError::ReservedHandle((*prefix).to_string()));
+    }
+    Ok(())
+}
+
+/// DHT address of a handle's descriptor.
+pub fn handle_address(handle: &str) -> [u8; 32] {
+    ochra_crypto::blake3::derive_key(
+        ochra_crypto::blake3::contexts::HANDLE_LOOKUP,
+        normalize(handle).as_bytes(),
+    )
+}
+
+/// Whether the handle is a dictionary word.
+pub fn is_dictionary_word(handle: &str) -> bool {
+    DICTIONARY
+        .binary_search(&normalize(handle).as_str())
+        .is_ok()
+}
+
+/// PoW difficulty, in leading zero bits, to register `handle`.
+pub fn registration_difficulty(handle: &str) -> u32 {
+    let len = handle.chars().count();
+    let short_bits = SHORT_HANDLE_LEN.saturating_sub(len) as u32;
+    let dictionary_bits = if is_dictionary_word(handle) {
+        DICTIONARY_PENALTY_BITS
+    } else {
+        0
+    };
+    BASE_HANDLE_DIFFICULTY + short_bits + dictionary_bits
+}
+
+/// PoW difficulty to renew `handle` once its proof has aged out.
+pub fn renewal_difficulty(handle: &str) -> u32 {
+    registration_difficulty(handle).saturating_sub(RENEWAL_DISCOUNT_BITS)
+}
+
+/// Seed burn, in micro-seeds, accepted in place of a PoW at `difficulty`.
+pub fn burn_price(difficulty: u32) -> u64 {
+    let extra = difficulty.saturating_sub(BASE_HANDLE_DIFFICULTY).min(32);
+    BURN_BASE_MICRO_SEEDS.saturating_mul(1 << extra)
+}
+
+/// Proof period containing `now`.
+pub fn proof_period(now: u64) -> u32 {
+    (now / PROOF_PERIOD_SECS) as u32
+}
+
+/// The PoW challenge for `handle` in `period`.
+///
+/// The solver binds the handle signing key as the content hash, so a proof
+/// cannot be reused by another key.
+pub fn pow_challenge(handle: &str, period: u32, difficulty: u32) -> PowChallenge {
+    let mut nonce_prefix = POW_PREFIX.to_vec();
+    nonce_prefix.extend_from_slice(&period.to_be_bytes());
+    PowChallenge {
+        target_hash: handle_address(handle),
+        difficulty,
+        nonce_prefix,
+    }
+}
+
+/// Solve the handle PoW for `handle_signing_pk`.
+///
+/// At registration difficulty this can take minutes for short handles.
+pub fn solve_handle_pow(
+    handle: &str,
+    handle_signing_pk: &[u8; 32],
+    period: u32,
+    difficulty: u32,
+) -> Result<HandleProof> {
+    solve_handle_pow_with(
+        handle,
+        handle_signing_pk,
+        period,
+        difficulty,
+        argon2id_pow::default_params(),
+    )
+}
+
+/// [`solve_handle_pow`] with calibrated PoW parameters, declared in the
+/// proof.
+pub fn solve_handle_pow_with(
+    handle: &str,
+    handle_signing_pk: &[u8; 32],
+    period: u32,
+    difficulty: u32,
+    params: Argon2Params,
+) -> Result<HandleProof> {
+    let challenge = pow_challenge(handle, period, difficulty);
+    let solution = argon2id_pow::solve_pow_with(&challenge, handle_signing_pk, params)?;
+    Ok(HandleProof::Pow { period, solution })
+}
+
+/// Registration or renewal proof carried in `HandleDescriptor.pow_proof`.
+#[derive(Clone, Debug)]
+pub enum HandleProof {
+    /// Argon2id PoW over [`pow_challenge`].
+    Pow { period: u32, solution: PowSolution },
+    /// Seeds burned in the given transaction.
+    Burn {
+        period: u32,
+        burn_tx: [u8; 32],
+        amount_micro_seeds: u64,
+    },
+}
+
+const PROOF_TAG_POW: u8 = 1;
+const PROOF_TAG_BURN: u8 = 2;
+/// PoW made with non-default parameters, declared after the hash.
+const PROOF_TAG_POW_PARAMS: u8 = 3;
+
+impl HandleProof {
+    /// Proof period the proof was made for.
+    pub fn period(&self) -> u32 {
+        match self {
+            Self::Pow { period, .. } | Self::Burn { period, .. } => *period,
+        }
+    }
+
+    /// Serialize: tag byte, period, then the PoW nonce and hash (followed by
+    /// its parameters when not the default) or the burn transaction and
+    /// amount.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            Self::Pow { period, solution } => {
+                let declared = solution.params != argon2id_pow::default_params();
+                out.push(if declared {
+                    PROOF_TAG_POW_PARAMS
+                } else {
+                    PROOF_TAG_POW
+                });
+                out.extend_from_slice(&period.to_be_bytes());
+                out.extend_from_slice(&solution.nonce);
+                out.extend_from_slice(&solution.hash);
+                if declared {
+                    let p = &solution.params;
+                    for cost in [p.m_cost, p.t_cost, p.p_cost] {
+                        out.extend_from_slice(&cost.to_be_bytes());
+                    }
+                }
+            }
+            Self::Burn {
+                period,
+                burn_tx,
+                amount_micro_seeds,
+            } => {
+                out.push(PROOF_TAG_BURN);
+                out.extend_from_slice(&period.to_be_bytes());
+                out.extend_from_slice(burn_tx);
+                out.extend_from_slice(&amount_micro_seeds.to_be_bytes());
+            }
+        }
+        out
+    }
+
+    /// Deserialize a proof produced by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let invalid = || WhisperError::InvalidHandle("malformed handle proof".to_string());
+        let (&tag, rest) = bytes.split_first().ok_or_else(invalid)?;
+        let (period, rest) = rest.split_at_checked(4).ok_or_else(invalid)?;
+        let period = u32::from_be_bytes(period.try_into().map_err(|_| invalid())?);
+        match tag {
+            PROOF_TAG_POW if rest.len() == NONCE_LEN + POW_OUTPUT_LEN => {
+                let (nonce, hash) = rest.split_at(NONCE_LEN);
+                Ok(Self::Pow {
+                    period,
+                    solution: PowSolution {
+                        nonce: nonce.try_into().map_err(|_| invalid())?,
+                        hash: hash.try_into().map_err(|_| invalid())?,
+                        params: argon2id_pow::default_params(),
+                    },
+                })
+            }
+            PROOF_TAG_POW_PARAMS if rest.len() == NONCE_LEN + POW_OUTPUT_LEN + 12 => {
+                let (nonce, rest) = rest.split_at(NONCE_LEN);
+                let (hash, params) = rest.split_at(POW_OUTPUT_LEN);
+                let cost = |i: usize| -> Result<u32> {
+                    let bytes = params.get(i * 4..i * 4 + 4).ok_or_else(invalid)?;
+                    Ok(u32::from_be_bytes(bytes.try_into().map_err(|_| invalid())?))
+                };
+                Ok(Self::Pow {
+                    period,
+                    solution: PowSolution {
+                        nonce: nonce.try_into().map_err(|_| invalid())?,
+                        hash: hash.try_into().map_err(|_| invalid())?,
+                        params: Argon2Params {
+                            m_cost: cost(0)?,
+                            t_cost: cost(1)?,
+                            p_cost: cost(2)?,
+                        },
+                    },
+                })
+            }
+            PROOF_TAG_BURN if rest.len() == 40 => {
+                let (burn_tx, amount) = rest.split_at(32);
+                Ok(Self::Burn {
+                    period,
+                    burn_tx: burn_tx.try_into().map_err(|_| invalid())?,
+                    amount_micro_seeds: u64::from_be_bytes(
+                        amount.try_into().map_err(|_| invalid())?,
+                    ),
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Where a handle is in its lifecycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandleLifecycle {
+    /// Refreshed within [`HANDLE_TTL_SECS`].
+    Active,
+    /// Not refreshed in time; only the owner may revive it.
+    Expired,
+    /// Past the grace period; anyone may register it.
+    Available,
+}
+
+/// Lifecycle of a descriptor last refreshed at `refresh_at`.
+pub fn lifecycle(refresh_at: u64, now: u64) -> HandleLifecycle {
+    let age = now.saturating_sub(refresh_at);
+    if age <= HANDLE_TTL_SECS {
+        HandleLifecycle::Active
+    } else if age <= HANDLE_TTL_SECS + HANDLE_GRACE_SECS {
+        HandleLifecycle::Expired
+    } else {
+        HandleLifecycle::Available
+    }
+}
+
+/// What an accepted descriptor put cost.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofCheck {
+    /// A refresh reusing the stored descriptor's still-valid proof.
+    Refresh,
+    /// A verified PoW.
+    Pow,
+    /// A burn of sufficient amount; the caller must confirm `burn_tx` burned
+    /// at least that many Seeds before accepting.
+    Burn {
+        burn_tx: [u8; 32],
+        amount_micro_seeds: u64,
+    },
+}
+
+/// Check a descriptor put against the descriptor already stored, if any.
+///
+/// The descriptor signature is checked separately.
+pub fn check_descriptor(
+    descriptor: &HandleDescriptor,
+    previous: Option<&HandleDescriptor>,
+    now: u64,
+) -> Result<ProofCheck> {
+    validate_handle(&descriptor.handle)?;
+    let previous = previous
+        .filter(|p| normalize(&p.handle) == normalize(&descriptor.handle))
+        .filter(|p| lifecycle(p.refresh_at, now) != HandleLifecycle::Available);
+    let renewal = match previous {
+        Some(p) if p.handle_signing_pk != descriptor.handle_signing_pk => {
+            return Err(WhisperError::HandleTaken(descriptor.handle.clone()));
+        }
+        Some(_) => true,
+        None => false,
+    };
+
+    let required = if renewal {
+        renewal_difficulty(&descriptor.handle)
+    } else {
+        registration_difficulty(&descriptor.handle)
+    };
+    check_proof(
+        descriptor,
+        previous.map(|p| p.pow_proof.as_slice()),
+        required,
+        now,
+    )
+}
+
+/// Register the handle descriptor record type with its proof check,
+/// replacing the standard type's decoding and signer rules alone.
+///
+/// A put is checked with [`check_descriptor`] against the descriptor it
+/// replaces, at the time `clock` returns. A burn is accepted on its
+/// declared amount; confirming the burn transaction is left to the
+/// quorum.
+pub fn register_record_type(
+    registry: &mut RecordTypeRegistry,
+    clock: impl Fn() -> u64 + Send + Sync + 'static,
+) {
+    registry.register(
+        HANDLE_DESCRIPTOR_SALT,
+        RecordType::new("handle_descriptor").shardable().with(
+            handle_descriptor_schema().with_rule(move |d, r| {
+                let previous = r
+                    .previous
+                    .and_then(|v| ciborium::from_reader::<HandleDescriptor, _>(v).ok());
+                check_descriptor(d, previous.as_ref(), clock())
+                    .map(drop)
+                    .map_err(|e| e.to_string())
+            }),
+        ),
+    );
+}
+
+/// Check the descriptor's proof against `required` bits, accepting a reuse
+/// of `previous_proof` while it is valid.
+pub(crate) fn check_proof(
+    descriptor: &HandleDescriptor,
+    previous_proof: Option<&[u8]>,
+    required: u32,
+    now: u64,
+) -> Result<ProofCheck> {
+    let proof = HandleProof::from_bytes(&descriptor.pow_proof)?;
+    let current = proof_period(now);
+    if proof.period() > current || proof.period() + PROOF_VALID_PERIODS <= current {
+        return Err(WhisperError::InvalidHandle(format!(
+            "proof for period {} is not valid in period {current}",
+            proof.period()
+        )));
+    }
+    if previous_proof == Some(descriptor.pow_proof.as_slice()) {
+        return Ok(ProofCheck::Refresh);
+    }
+
+    match proof {
+        HandleProof::Pow { period, solution } => {
+            let challenge = pow_challenge(&descriptor.handle, period, required);
+            if !argon2id_pow::verify_pow_with_content(
+                &challenge,
+                &descriptor.handle_signing_pk,
+                &solution,
+            ) {
+                return Err(WhisperError::InvalidPow);
+            }
+            Ok(ProofCheck::Pow)
+        }
+        HandleProof::Burn {
+            burn_tx,
+            amount_micro_seeds,
+            ..
+        } => {
+            let price = burn_price(required);
+            if amount_micro_seeds < price {
+                return Err(WhisperError::InvalidHandle(format!(
+                    "burn of {amount_micro_seeds} micro-seeds is below the price of {price}"
+                )));
+            }
+            Ok(ProofCheck::Burn {
+                burn_tx,
+                amount_micro_seeds,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochra_types::whisper::{HandleStatus, PresencePrivacy, ThrottleStrictness};
+
+    const NOW: u64 = 400 * 24 * 3600;
+
+    fn descriptor(
+        handle: &str,
+        pk: [u8; 32],
+        proof: &HandleProof,
+        refresh_at: u64,
+    ) -> HandleDescriptor {
+        HandleDescriptor {
+            handle: handle.to_string(),
+            handle_signing_pk: pk,
+            intro_points: Vec::new(),
+            auth_key: [0u8; 32],
+            pq_auth_key: Vec::new(),
+            registered_at: refresh_at,
+            refresh_at,
+            pow_proof: proof.to_bytes(),
+            status: HandleStatus::Active,
+            mailboxes: Vec::new(),
+            privacy: PresencePrivacy::default(),
+            strictness: ThrottleStrictness::default(),
+            sig: [0u8; 64],
+        }
+    }
+
+    fn burn(amount: u64) -> HandleProof {
+        HandleProof::Burn {
+            period: proof_period(NOW),
+            burn_tx: [9u8; 32],
+            amount_micro_seeds: amount,
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(validate_handle("alice_99").is_ok());
+        assert!(validate_handle("ab").is_err());
+        assert!(validate_handle("a_very_long_handle_name").is_err());
+        assert!(validate_handle("bad-char").is_err());
+        assert!(matches!(
+            validate_handle("Admin_bob"),
+            Err(WhisperError::ReservedHandle(_))
+        ));
+    }
+
+    #[test]
+    fn test_difficulty_curve() {
+        assert!(DICTIONARY.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            registration_difficulty("quiet_otter_7"),
+            BASE_HANDLE_DIFFICULTY
+        );
+        assert_eq!(registration_difficulty("xqz"), BASE_HANDLE_DIFFICULTY + 7);
+        assert_eq!(
+            registration_difficulty("Love"),
+            BASE_HANDLE_DIFFICULTY + 6 + DICTIONARY_PENALTY_BITS
+        );
+        assert_eq!(renewal_difficulty("xqz"), BASE_HANDLE_DIFFICULTY + 5);
+        assert_eq!(burn_price(BASE_HANDLE_DIFFICULTY), BURN_BASE_MICRO_SEEDS);
+        assert_eq!(
+            burn_price(BASE_HANDLE_DIFFICULTY + 3),
+            BURN_BASE_MICRO_SEEDS * 8
+        );
+    }
+
+    #[test]
+    fn test_pow_must_meet_handle_difficulty() {
+        let pk = [1u8; 32];
+        let period = proof_period(NOW);
+        let proof = solve_handle_pow("quiet_otter_7", &pk, period, 0).expect("solve");
+        let leading = match &proof {
+            HandleProof::Pow { solution, .. } => solution
+                .hash
+                .iter()
+                .position(|b| *b != 0)
+                .map(|i| i as u32 * 8 + solution.hash[i].leading_zeros())
+                .unwrap_or(256),
+            HandleProof::Burn { .. } => 0,
+        };
+        assert!(matches!(proof, HandleProof::Pow { .. }));
+
+        let desc = descriptor("quiet_otter_7", pk, &proof, NOW);
+        let result = check_descriptor(&desc, None, NOW);
+        assert_eq!(result.is_ok(), leading >= BASE_HANDLE_DIFFICULTY);
+
+        // A proof never verifies for another signing key
+        let stolen = descriptor("quiet_otter_7", [2u8; 32], &proof, NOW);
+        assert!(check_descriptor(&stolen, None, NOW).is_err());
+    }
+
+    #[test]
+    fn test_declared_pow_params_round_trip() {
+        let params = Argon2Params {
+            m_cost: 32 * 1024,
+            t_cost: 2,
+            p_cost: 2,
+        };
+        let proof = HandleProof::Pow {
+            period: 7,
+            solution: PowSolution {
+                nonce: [3u8; NONCE_LEN],
+                hash: [4u8; POW_OUTPUT_LEN],
+                params,
+            },
+        };
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes[0], PROOF_TAG_POW_PARAMS);
+        let decoded = HandleProof::from_bytes(&bytes).expect("decode");
+        assert!(matches!(
+            decoded,
+            HandleProof::Pow { solution, .. } if solution.params == params
+        ));
+        assert!(HandleProof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_burn_and_refresh() {
+        let price = burn_price(registration_difficulty("xqz"));
+        let cheap = descriptor("xqz", [1u8; 32], &burn(price - 1), NOW);
+        assert!(check_descriptor(&cheap, None, NOW).is_err());
+
+        let paid = descriptor("xqz", [1u8; 32], &burn(price), NOW);
+        assert!(matches!(
+            check_descriptor(&paid, None, NOW),
+            Ok(ProofCheck::Burn { amount_micro_seeds, .. }) if amount_micro_seeds == price
+        ));
+
+        let refreshed = descriptor("xqz", [1u8; 32], &burn(price), NOW + 3600);
+        assert_eq!(
+            check_descriptor(&refreshed, Some(&paid), NOW + 3600).expect("refresh"),
+            ProofCheck::Refresh
+        );
+
+        let squatter = descriptor("XQZ", [2u8; 32], &burn(price), NOW + 3600);
+        assert!(matches!(
+            check_descriptor(&squatter, Some(&paid), NOW + 3600),
+            Err(WhisperError::HandleTaken(_))
+        ));
+    }
+
+    #[test]
+    fn test_abandoned_handles_expire() {
+        assert_eq!(
+            lifecycle(NOW, NOW + HANDLE_TTL_SECS),
+            HandleLifecycle::Active
+        );
+        assert_eq!(
+            lifecycle(NOW, NOW + HANDLE_TTL_SECS + 1),
+            HandleLifecycle::Expired
+        );
+        let gone = NOW + HANDLE_TTL_SECS + HANDLE_GRACE_SECS + 1;
+        assert_eq!(lifecycle(NOW, gone), HandleLifecycle::Available);
+
+        // The old proof ages out, and the name is free for another key
+        let price = burn_price(registration_difficulty("xqz"));
+        let old = descriptor("xqz", [1u8; 32], &burn(price), NOW);
+        let later = NOW + PROOF_VALID_PERIODS as u64 * PROOF_PERIOD_SECS;
+        assert!(check_descriptor(&old, None, later).is_err());
+        let fresh = HandleProof::Burn {
+            period: proof_period(later),
+            burn_tx: [8u8; 32],
+            amount_micro_seeds: price,
+        };
+        let taker = descriptor("xqz", [2u8; 32], &fresh, later);
+        assert!(check_descriptor(&taker, Some(&old), later).is_ok());
+    }
+
+    #[test]
+    fn test_store_checks_proof_against_stored_descriptor() {
+        use ochra_crypto::ed25519::KeyPair;
+        use ochra_dht::bep44::{create_mutable_record, RecordStore};
+
+        let kp = KeyPair::generate();
+        let pk = kp.verifying_key.to_bytes();
+        let mut store = RecordStore::new();
+        register_record_type(store.record_types_mut(), || NOW);
+        let put = |store: &mut RecordStore, seq: u64, amount: u64| {
+            let mut value = Vec::new();
+            ciborium::into_writer(&descriptor("otter", pk, &burn(amount), NOW), &mut value)
+                .expect("encode");
+            let record = create_mutable_record(&kp.signing_key, HANDLE_DESCRIPTOR_SALT, seq, value)
+                .expect("record");
+            store.put(record)
+        };
+
+        // A first put pays the registration price, a replacing one only
+        // the renewal price
+        let renewal = burn_price(renewal_difficulty("otter"));
+        let registration = burn_price(registration_difficulty("otter"));
+        assert!(renewal < registration);
+        assert!(matches!(
+            put(&mut store, 1, renewal),
+            Err(ochra_dht::DhtError::InvalidRecord {
+                record_type: "handle_descriptor",
+                ..
+            })
+        ));
+        assert!(put(&mut store, 1, registration).is_ok());
+        assert!(put(&mut store, 2, renewal).is_ok());
+    }
+}
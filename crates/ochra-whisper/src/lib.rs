@@ -0,0 +1,123 @@
+//! # ochra-whisper
+//!
+//! Whisper messaging subsystem (Section 7).
+//!
+//! Live Whisper sessions run over anonymous rendezvous circuits. This crate
+//! holds the pieces that sit around those sessions.
+//!
+//! ## Modules
+//!
+//! - [`backup`] — Opt-in encrypted ratchet checkpoint backups
+//! - [`attachment`] — Chunked, encrypted attachments referenced from messages
+//! - [`delivery`] — Delivery states, resends, and read acks for outbound messages
+//! - [`devices`] — Multi-device linking and per-device fan-out
+//! - [`handle`] — Handle registration cost, renewal, and expiry
+//! - [`mailbox`] — Relay-held store-and-forward mailboxes for offline recipients
+//! - [`presence`] — Typing, read receipt, and online presence privacy settings
+//! - [`resolver`] — Handle resolution cache with negative entries and fetch limits
+//! - [`throttle`] — Puzzle and payment tickets unknown senders attach, per recipient strictness
+//! - [`transition`] — Signed handle renames and transfers, and following them
+
+pub mod attachment;
+pub mod backup;
+pub mod delivery;
+pub mod devices;
+pub mod handle;
+pub mod mailbox;
+pub mod presence;
+pub mod resolver;
+pub mod throttle;
+pub mod transition;
+
+/// Error types for Whisper operations.
+#[derive(Debug, thiserror::Error)]
+pub enum WhisperError {
+    /// Encryption or decryption failed.
+    #[error("crypto error: {0}")]
+    Crypto(String),
+
+    /// Signature verification failed.
+    #[error("invalid signature")]
+    InvalidSignature,
+
+    /// The anti-spam proof-of-work is missing or invalid.
+    #[error("invalid proof-of-work")]
+    InvalidPow,
+
+    /// The request timestamp is outside the accepted window.
+    #[error("stale request: timestamp {timestamp}, now {now}")]
+    StaleRequest {
+        /// Timestamp carried by the request.
+        timestamp: u64,
+        /// Local time when the request was checked.
+        now: u64,
+    },
+
+    /// The envelope has already expired or its expiry is out of range.
+    #[error("invalid expiry: {0}")]
+    InvalidExpiry(String),
+
+    /// The envelope exceeds the maximum size.
+    #[error("envelope too large: {size} bytes (max {max})")]
+    EnvelopeTooLarge {
+        /// Actual envelope size.
+        size: usize,
+        /// Maximum allowed size.
+        max: usize,
+    },
+
+    /// An unknown sender's throttle ticket is missing or insufficient.
+    #[error("throttled: {0}")]
+    Throttled(String),
+
+    /// The mailbox holds the maximum number of envelopes.
+    #[error("mailbox full: {0} envelopes held")]
+    MailboxFull(usize),
+
+    /// Attachment preparation or download failed.
+    #[error("attachment error: {0}")]
+    Attachment(String),
+
+    /// A delivery or read ack is malformed.
+    #[error("delivery error: {0}")]
+    Delivery(String),
+
+    /// The handle fails length, character, or proof checks.
+    #[error("invalid handle: {0}")]
+    InvalidHandle(String),
+
+    /// The handle starts with a reserved prefix.
+    #[error("reserved handle prefix: {0}")]
+    ReservedHandle(String),
+
+    /// The handle is held by another signing key.
+    #[error("handle taken: {0}")]
+    HandleTaken(String),
+
+    /// Handle lookups are being fetched faster than the resolver allows.
+    #[error("handle lookup rate limited: {0}")]
+    LookupLimited(String),
+
+    /// A handle transition record is invalid or breaks the chain.
+    #[error("handle transition error: {0}")]
+    Transition(String),
+
+    /// Device linking failed.
+    #[error("device link error: {0}")]
+    DeviceLink(String),
+
+    /// The handle already has the maximum number of linked devices.
+    #[error("too many linked devices (max {0})")]
+    TooManyDevices(usize),
+
+    /// A session backup cannot be made or restored.
+    #[error("backup error: {0}")]
+    Backup(String),
+
+    /// Proof-of-work computation failed.
+    #[error("pow error: {0}")]
+    Pow(#[from] ochra_pow::PowError),
+}
+
+/// Convenience result type for Whisper operations.
+pub type Result<T> = std::result::Result<T, WhisperError>;
@@ -0,0 +1,866 @@
+//! Store-and-forward mailboxes for offline Whisper recipients.
+//!
+//! When rendezvous with a recipient fails, the sender may instead deposit an
+//! envelope at one of the mailboxes advertised in the recipient's
+//! [`HandleDescriptor`](ochra_types::whisper::HandleDescriptor). The relay
+//! holding the mailbox keeps envelopes in memory only, for a bounded time.
+//!
+//! ## Protocol
+//!
+//! 1. The recipient generates a [`MailboxKey`]: an Ed25519 key that addresses
+//!    and authorizes the mailbox, and an X25519 key envelopes are sealed to.
+//! 2. The sender seals its payload with ECIES, binds it to the mailbox with an
+//!    Argon2id PoW, attaches the throttle ticket the recipient's strictness
+//!    asks for, and sends a [`WhisperDeposit`] over a Sphinx circuit.
+//! 3. The recipient polls with a signed [`WhisperPoll`] through an anonymous
+//!    circuit and receives all held envelopes. The poll also sets the
+//!    strictness the relay applies to later deposits.
+//! 4. After processing, the recipient sends a signed [`WhisperMailboxAck`] and
+//!    the relay deletes the acknowledged envelopes.
+//!
+//! ## Addressing
+//!
+//! `mailbox_addr = BLAKE3::hash("whisper-mailbox" || mailbox_pk)`
+//!
+//! ## Parameters
+//!
+//! | Parameter | Value |
+//! |-----------|-------|
+//! | Default hold time | 24 hours |
+//! | Maximum hold time | 72 hours |
+//! | Envelopes per mailbox | 100 |
+//! | Maximum envelope size | 8,192 bytes |
+//! | Deposit PoW difficulty | 4 leading zero bits |
+//! | Strictness before the first poll | `standard` |
+//! | Poll/ack clock skew | 300 seconds |
+
+use std::collections::{HashMap, VecDeque};
+
+use ochra_crypto::blake3;
+use ochra_crypto::ecies;
+use ochra_crypto::ed25519::{KeyPair, Signature, VerifyingKey};
+use ochra_crypto::x25519::{X25519PublicKey, X25519StaticSecret};
+use ochra_pow::argon2id_pow::{self, PowChallenge, PowSolution};
+use ochra_transport::messages::{
+    MailboxEnvelope, SpamTicket, WhisperDeposit, WhisperMailboxAck, WhisperPoll,
+    WhisperPollResponse,
+};
+use ochra_types::whisper::{MailboxEntry, ThrottleStrictness};
+
+use crate::throttle::{self, TicketCheck};
+use crate::{Result, WhisperError};
+
+/// Default time a relay holds an undelivered envelope (hours).
+pub const DEFAULT_HOLD_HOURS: u64 = 24;
+
+/// Maximum time a relay holds an undelivered envelope (hours).
+pub const MAX_HOLD_HOURS: u64 = 72;
+
+/// Maximum number of envelopes held per mailbox.
+pub const MAX_ENVELOPES_PER_MAILBOX: usize = 100;
+
+/// Maximum sealed envelope size in bytes (one Sphinx packet).
+pub const MAX_SEALED_SIZE: usize = ochra_types::SPHINX_PACKET_SIZE;
+
+/// Leading zero bits required on the deposit PoW.
+pub const DEPOSIT_POW_DIFFICULTY: u32 = 4;
+
+/// Maximum accepted clock skew on poll and ack timestamps (seconds).
+pub const MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+/// Compute the address of the mailbox owned by `mailbox_pk`.
+pub fn mailbox_addr(mailbox_pk: &[u8; 32]) -> [u8; 32] {
+    let mut data = Vec::with_capacity(15 + 32);
+    data.extend_from_slice(b"whisper-mailbox");
+    data.extend_from_slice(mailbox_pk);
+    blake3::hash(&data)
+}
+
+/// Build the PoW challenge binding a deposit to its mailbox, id and expiry.
+///
+/// The solver additionally binds the hash of the sealed bytes as the PoW
+/// content hash, so a relay cannot be fed a recycled proof.
+pub fn deposit_challenge(
+    mailbox_addr: &[u8; 32],
+    envelope_id: &[u8; 16],
+    expires_at: u64,
+    difficulty: u32,
+) -> PowChallenge {
+    let mut data = Vec::with_capacity(15 + 32 + 16 + 8);
+    data.extend_from_slice(b"whisper-deposit");
+    data.extend_from_slice(mailbox_addr);
+    data.extend_from_slice(envelope_id);
+    data.extend_from_slice(&expires_at.to_le_bytes());
+    PowChallenge {
+        target_hash: blake3::hash(&data),
+        difficulty,
+        nonce_prefix: Vec::new(),
+    }
+}
+
+/// Message signed by the mailbox key to poll.
+fn poll_message(mailbox_pk: &[u8; 32], timestamp: u64, strictness: ThrottleStrictness) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(20 + 32 + 8 + 1);
+    msg.extend_from_slice(b"whisper-mailbox-poll");
+    msg.extend_from_slice(mailbox_pk);
+    msg.extend_from_slice(&timestamp.to_le_bytes());
+    msg.push(match strictness {
+        ThrottleStrictness::Off => 0,
+        ThrottleStrictness::Low => 1,
+        ThrottleStrictness::Standard => 2,
+        ThrottleStrictness::High => 3,
+    });
+    msg
+}
+
+/// Message signed by the mailbox key to acknowledge delivery.
+fn ack_message(mailbox_pk: &[u8; 32], envelope_ids: &[[u8; 16]], timestamp: u64) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(19 + 32 + 8 + envelope_ids.len() * 16);
+    msg.extend_from_slice(b"whisper-mailbox-ack");
+    msg.extend_from_slice(mailbox_pk);
+    msg.extend_from_slice(&timestamp.to_le_bytes());
+    for id in envelope_ids {
+        msg.extend_from_slice(id);
+    }
+    msg
+}
+
+/// Verify a mailbox-key signature and the request timestamp.
+fn verify_request(
+    mailbox_pk: &[u8; 32],
+    message: &[u8],
+    sig: &[u8],
+    timestamp: u64,
+    now: u64,
+) -> Result<()> {
+    if timestamp.abs_diff(now) > MAX_CLOCK_SKEW_SECS {
+        return Err(WhisperError::StaleRequest { timestamp, now });
+    }
+    let sig: [u8; 64] = sig.try_into().map_err(|_| WhisperError::InvalidSignature)?;
+    let vk = VerifyingKey::from_bytes(mailbox_pk).map_err(|_| WhisperError::InvalidSignature)?;
+    vk.verify(message, &Signature::from_bytes(&sig))
+        .map_err(|_| WhisperError::InvalidSignature)
+}
+
+/// Seal `plaintext` for a mailbox and build a deposit with a solved PoW,
+/// plus the puzzle ticket the recipient's `strictness` requires.
+///
+/// To pay instead of solving the puzzle, build at
+/// [`ThrottleStrictness::Off`] and attach a payment ticket.
+pub fn build_deposit(
+    entry: &MailboxEntry,
+    plaintext: &[u8],
+    now: u64,
+    hold_secs: u64,
+    difficulty: u32,
+    strictness: ThrottleStrictness,
+) -> Result<WhisperDeposit> {
+    let sealed = ecies::encrypt(&X25519PublicKey::from_bytes(entry.seal_pk), plaintext)
+        .map_err(|e| WhisperError::Crypto(e.to_string()))?
+        .to_bytes();
+    if sealed.len() > MAX_SEALED_SIZE {
+        return Err(WhisperError::EnvelopeTooLarge {
+            size: sealed.len(),
+            max: MAX_SEALED_SIZE,
+        });
+    }
+
+    let mut envelope_id = [0u8; 16];
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut envelope_id);
+    let addr = mailbox_addr(&entry.mailbox_pk);
+    let expires_at = now + hold_secs;
+
+    let challenge = deposit_challenge(&addr, &envelope_id, expires_at, difficulty);
+    let solution = argon2id_pow::solve_pow(&challenge, &blake3::hash(&sealed))?;
+    let ticket = match strictness {
+        ThrottleStrictness::Off => None,
+        _ => Some(throttle::solve_puzzle(
+            &addr,
+            &envelope_id,
+            &sealed,
+            throttle::puzzle_difficulty(strictness),
+        )?),
+    };
+
+    Ok(WhisperDeposit {
+        mailbox_addr: addr,
+        envelope_id,
+        sealed,
+        expires_at,
+        pow_nonce: solution.nonce,
+        pow_hash: solution.hash,
+        ticket,
+    })
+}
+
+/// Build one deposit per linked device advertised in `entries`, each paired
+/// with the node ID of the relay holding its mailbox.
+///
+/// A device advertising several mailboxes receives the envelope only at the
+/// first one listed.
+pub fn build_device_deposits(
+    entries: &[MailboxEntry],
+    plaintext: &[u8],
+    now: u64,
+    hold_secs: u64,
+    difficulty: u32,
+    strictness: ThrottleStrictness,
+) -> Result<Vec<([u8; 32], WhisperDeposit)>> {
+    let mut seen = Vec::with_capacity(entries.len());
+    let mut deposits = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if seen.contains(&entry.device_id) {
+            continue;
+        }
+        seen.push(entry.device_id);
+        deposits.push((
+            entry.relay_node_id,
+            build_deposit(entry, plaintext, now, hold_secs, difficulty, strictness)?,
+        ));
+    }
+    Ok(deposits)
+}
+
+/// Recipient-side mailbox keys.
+pub struct MailboxKey {
+    signing: KeyPair,
+    seal: X25519StaticSecret,
+}
+
+impl MailboxKey {
+    /// Generate fresh mailbox keys.
+    pub fn generate() -> Self {
+        Self {
+            signing: KeyPair::generate(),
+            seal: X25519StaticSecret::random(),
+        }
+    }
+
+    /// Derive mailbox keys from a device secret, so the same mailbox is
+    /// used across restarts.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self {
+            signing: KeyPair::from_bytes(&blake3::derive_key(
+                blake3::contexts::MAILBOX_SIGNING_KEY,
+                seed,
+            )),
+            seal: X25519StaticSecret::from_bytes(blake3::derive_key(
+                blake3::contexts::MAILBOX_SEAL_KEY,
+                seed,
+            )),
+        }
+    }
+
+    /// Ed25519 mailbox public key.
+    pub fn mailbox_pk(&self) -> [u8; 32] {
+        self.signing.verifying_key.to_bytes()
+    }
+
+    /// Address of this mailbox.
+    pub fn address(&self) -> [u8; 32] {
+        mailbox_addr(&self.mailbox_pk())
+    }
+
+    /// Descriptor entry advertising this mailbox for `device_id` at
+    /// `relay_node_id`.
+    pub fn entry(&self, device_id: [u8; 16], relay_node_id: [u8; 32]) -> MailboxEntry {
+        MailboxEntry {
+            device_id,
+            relay_node_id,
+            mailbox_pk: self.mailbox_pk(),
+            seal_pk: self.seal.public_key().to_bytes(),
+        }
+    }
+
+    /// Build a signed poll request that also sets the relay's strictness
+    /// for this mailbox.
+    pub fn poll(&self, strictness: ThrottleStrictness, now: u64) -> WhisperPoll {
+        let mailbox_pk = self.mailbox_pk();
+        let sig = self
+            .signing
+            .signing_key
+            .sign(&poll_message(&mailbox_pk, now, strictness));
+        WhisperPoll {
+            mailbox_pk,
+            timestamp: now,
+            sig: sig.to_bytes().to_vec(),
+            strictness,
+        }
+    }
+
+    /// Build a signed ack deleting `envelope_ids` from the relay.
+    pub fn ack(&self, envelope_ids: Vec<[u8; 16]>, now: u64) -> WhisperMailboxAck {
+        let mailbox_pk = self.mailbox_pk();
+        let sig = self
+            .signing
+            .signing_key
+            .sign(&ack_message(&mailbox_pk, &envelope_ids, now));
+        WhisperMailboxAck {
+            mailbox_pk,
+            envelope_ids,
+            timestamp: now,
+            sig: sig.to_bytes().to_vec(),
+        }
+    }
+
+    /// Open a sealed envelope returned by a poll.
+    pub fn open(&self, envelope: &MailboxEnvelope) -> Result<Vec<u8>> {
+        let ct = ecies::EciesCiphertext::from_bytes(&envelope.sealed)
+            .map_err(|e| WhisperError::Crypto(e.to_string()))?;
+        ecies::decrypt(&self.seal, &ct).map_err(|e| WhisperError::Crypto(e.to_string()))
+    }
+}
+
+/// Relay-side mailbox limits.
+#[derive(Clone, Debug)]
+pub struct MailboxConfig {
+    /// Maximum hold time accepted on deposit (seconds).
+    pub max_hold_secs: u64,
+    /// Maximum envelopes held per mailbox.
+    pub max_envelopes: usize,
+    /// Leading zero bits required on the deposit PoW.
+    pub pow_difficulty: u32,
+    /// Strictness applied to a mailbox that has not polled yet.
+    pub default_strictness: ThrottleStrictness,
+}
+
+impl Default for MailboxConfig {
+    fn default() -> Self {
+        Self {
+            max_hold_secs: MAX_HOLD_HOURS * 3600,
+            max_envelopes: MAX_ENVELOPES_PER_MAILBOX,
+            pow_difficulty: DEPOSIT_POW_DIFFICULTY,
+            default_strictness: ThrottleStrictness::default(),
+        }
+    }
+}
+
+impl MailboxConfig {
+    /// Default limits with the deposit difficulty of `profile`.
+    pub fn for_network(profile: &ochra_types::profile::NetworkProfile) -> Self {
+        Self {
+            pow_difficulty: profile.deposit_pow_difficulty,
+            ..Self::default()
+        }
+    }
+}
+
+/// An envelope held by the relay.
+#[derive(Clone, Debug)]
+struct HeldEnvelope {
+    envelope: MailboxEnvelope,
+    expires_at: u64,
+}
+
+/// In-memory store of mailboxes held by a relay.
+///
+/// Nothing here is persisted: a relay restart drops every held envelope,
+/// in keeping with Whisper's zero-persistence principle.
+#[derive(Debug, Default)]
+pub struct MailboxStore {
+    config: MailboxConfig,
+    mailboxes: HashMap<[u8; 32], VecDeque<HeldEnvelope>>,
+    /// Strictness each mailbox last polled with, and when.
+    strictness: HashMap<[u8; 32], (ThrottleStrictness, u64)>,
+}
+
+impl MailboxStore {
+    /// Create an empty store with the given limits.
+    pub fn new(config: MailboxConfig) -> Self {
+        Self {
+            config,
+            mailboxes: HashMap::new(),
+            strictness: HashMap::new(),
+        }
+    }
+
+    /// Accept a deposit.
+    ///
+    /// `quorum_key` is the group key of the quorum for the epoch of a
+    /// payment ticket (see [`throttle::payment_epoch`]), if known.
+    ///
+    /// Returns `Ok(false)` if an envelope with the same id is already held.
+    pub fn deposit(
+        &mut self,
+        deposit: &WhisperDeposit,
+        now: u64,
+        quorum_key: Option<&[u8; 32]>,
+    ) -> Result<bool> {
+        if deposit.sealed.len() > MAX_SEALED_SIZE {
+            return Err(WhisperError::EnvelopeTooLarge {
+                size: deposit.sealed.len(),
+                max: MAX_SEALED_SIZE,
+            });
+        }
+        if deposit.expires_at <= now {
+            return Err(WhisperError::InvalidExpiry(format!(
+                "expired at {}, now {now}",
+                deposit.expires_at
+            )));
+        }
+        if deposit.expires_at - now > self.config.max_hold_secs {
+            return Err(WhisperError::InvalidExpiry(format!(
+                "hold of {}s exceeds maximum {}s",
+                deposit.expires_at - now,
+                self.config.max_hold_secs
+            )));
+        }
+
+        let challenge = deposit_challenge(
+            &deposit.mailbox_addr,
+            &deposit.envelope_id,
+            deposit.expires_at,
+            self.config.pow_difficulty,
+        );
+        // Deposits carry no parameters: they are always made at the default.
+        let solution = PowSolution {
+            nonce: deposit.pow_nonce,
+            hash: deposit.pow_hash,
+            params: argon2id_pow::default_params(),
+        };
+        if !argon2id_pow::verify_pow_with_content(
+            &challenge,
+            &blake3::hash(&deposit.sealed),
+            &solution,
+        ) {
+            return Err(WhisperError::InvalidPow);
+        }
+        let check = throttle::check_ticket(
+            deposit.ticket.as_ref(),
+            self.strictness_for(&deposit.mailbox_addr),
+            &deposit.mailbox_addr,
+            &deposit.envelope_id,
+            &deposit.sealed,
+            quorum_key,
+        )?;
+
+        let queue = self.mailboxes.entry(deposit.mailbox_addr).or_default();
+        queue.retain(|h| h.expires_at > now);
+        if queue
+            .iter()
+            .any(|h| h.envelope.envelope_id == deposit.envelope_id)
+        {
+            return Ok(false);
+        }
+        if let TicketCheck::Payment { tx_hash, .. } = check {
+            let reused = queue.iter().any(|h| {
+                matches!(h.envelope.ticket, Some(SpamTicket::Payment { tx_hash: t, .. }) if t == tx_hash)
+            });
+            if reused {
+                return Err(WhisperError::Throttled(
+                    "payment already used for a held envelope".to_string(),
+                ));
+            }
+        }
+        if queue.len() >= self.config.max_envelopes {
+            return Err(WhisperError::MailboxFull(queue.len()));
+        }
+
+        queue.push_back(HeldEnvelope {
+            envelope: MailboxEnvelope {
+                envelope_id: deposit.envelope_id,
+                sealed: deposit.sealed.clone(),
+                deposited_at: now,
+                ticket: deposit.ticket.clone(),
+            },
+            expires_at: deposit.expires_at,
+        });
+        Ok(true)
+    }
+
+    /// Answer a signed poll with every live envelope in the mailbox.
+    ///
+    /// Envelopes stay held until acknowledged.
+    pub fn poll(&mut self, poll: &WhisperPoll, now: u64) -> Result<WhisperPollResponse> {
+        verify_request(
+            &poll.mailbox_pk,
+            &poll_message(&poll.mailbox_pk, poll.timestamp, poll.strictness),
+            &poll.sig,
+            poll.timestamp,
+            now,
+        )?;
+
+        let addr = mailbox_addr(&poll.mailbox_pk);
+        self.strictness.insert(addr, (poll.strictness, now));
+        let envelopes = match self.mailboxes.get_mut(&addr) {
+            Some(queue) => {
+                queue.retain(|h| h.expires_at > now);
+                queue.iter().map(|h| h.envelope.clone()).collect()
+            }
+            None => Vec::new(),
+        };
+        Ok(WhisperPollResponse { envelopes })
+    }
+
+    /// Delete acknowledged envelopes. Returns the number removed.
+    pub fn ack(&mut self, ack: &WhisperMailboxAck, now: u64) -> Result<usize> {
+        verify_request(
+            &ack.mailbox_pk,
+            &ack_message(&ack.mailbox_pk, &ack.envelope_ids, ack.timestamp),
+            &ack.sig,
+            ack.timestamp,
+            now,
+        )?;
+
+        let addr = mailbox_addr(&ack.mailbox_pk);
+        let Some(queue) = self.mailboxes.get_mut(&addr) else {
+            return Ok(0);
+        };
+        let before = queue.len();
+        queue.retain(|h| !ack.envelope_ids.contains(&h.envelope.envelope_id));
+        let removed = before - queue.len();
+        if queue.is_empty() {
+            self.mailboxes.remove(&addr);
+        }
+        Ok(removed)
+    }
+
+    /// Strictness applied to deposits for the mailbox at `addr`.
+    pub fn strictness_for(&self, addr: &[u8; 32]) -> ThrottleStrictness {
+        self.strictness
+            .get(addr)
+            .map_or(self.config.default_strictness, |(s, _)| *s)
+    }
+
+    /// Drop expired envelopes and empty mailboxes. Returns the number dropped.
+    ///
+    /// A mailbox's strictness is forgotten once it has not polled for the
+    /// maximum hold time.
+    pub fn sweep(&mut self, now: u64) -> usize {
+        let max_hold = self.config.max_hold_secs;
+        self.strictness
+            .retain(|_, (_, polled_at)| now.saturating_sub(*polled_at) <= max_hold);
+        let mut dropped = 0;
+        self.mailboxes.retain(|_, queue| {
+            let before = queue.len();
+            queue.retain(|h| h.expires_at > now);
+            dropped += before - queue.len();
+            !queue.is_empty()
+        });
+        dropped
+    }
+
+    /// Number of mailboxes with at least one held envelope.
+    pub fn mailbox_count(&self) -> usize {
+        self.mailboxes.len()
+    }
+
+    /// Total number of held envelopes.
+    pub fn envelope_count(&self) -> usize {
+        self.mailboxes.values().map(VecDeque::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+    const HOUR: u64 = 3600;
+
+    fn store() -> MailboxStore {
+        MailboxStore::new(MailboxConfig {
+            pow_difficulty: 0,
+            default_strictness: ThrottleStrictness::Off,
+            ..MailboxConfig::default()
+        })
+    }
+
+    fn deposit_for(key: &MailboxKey, body: &[u8]) -> WhisperDeposit {
+        build_deposit(
+            &key.entry([1u8; 16], [7u8; 32]),
+            body,
+            NOW,
+            24 * HOUR,
+            0,
+            ThrottleStrictness::Off,
+        )
+        .expect("build deposit")
+    }
+
+    #[test]
+    fn test_deposit_difficulty_from_profile() {
+        use ochra_types::profile::NetworkProfile;
+
+        let mainnet = MailboxConfig::for_network(&NetworkProfile::mainnet());
+        assert_eq!(mainnet.pow_difficulty, DEPOSIT_POW_DIFFICULTY);
+        let testnet = MailboxConfig::for_network(&NetworkProfile::testnet());
+        assert!(testnet.pow_difficulty < DEPOSIT_POW_DIFFICULTY);
+    }
+
+    #[test]
+    fn test_seeded_key_is_stable() {
+        let key = MailboxKey::from_seed(&[5u8; 32]);
+        assert_eq!(key.address(), MailboxKey::from_seed(&[5u8; 32]).address());
+        assert_ne!(key.address(), MailboxKey::from_seed(&[6u8; 32]).address());
+
+        let deposit = deposit_for(&key, b"still here");
+        let envelope = MailboxEnvelope {
+            envelope_id: deposit.envelope_id,
+            sealed: deposit.sealed.clone(),
+            deposited_at: NOW,
+            ticket: None,
+        };
+        assert_eq!(
+            MailboxKey::from_seed(&[5u8; 32])
+                .open(&envelope)
+                .expect("open"),
+            b"still here"
+        );
+    }
+
+    #[test]
+    fn test_deposit_poll_open_ack() {
+        let key = MailboxKey::generate();
+        let mut store = store();
+
+        let deposit = deposit_for(&key, b"are you there?");
+        assert_eq!(deposit.mailbox_addr, key.address());
+        assert!(store.deposit(&deposit, NOW, None).expect("deposit"));
+
+        let resp = store
+            .poll(&key.poll(ThrottleStrictness::Off, NOW + 60), NOW + 60)
+            .expect("poll");
+        assert_eq!(resp.envelopes.len(), 1);
+        let body = key.open(&resp.envelopes[0]).expect("open");
+        assert_eq!(body, b"are you there?");
+
+        let removed = store
+            .ack(&key.ack(vec![deposit.envelope_id], NOW + 61), NOW + 61)
+            .expect("ack");
+        assert_eq!(removed, 1);
+        assert_eq!(store.envelope_count(), 0);
+        assert_eq!(store.mailbox_count(), 0);
+    }
+
+    #[test]
+    fn test_poll_does_not_delete() {
+        let key = MailboxKey::generate();
+        let mut store = store();
+        store
+            .deposit(&deposit_for(&key, b"one"), NOW, None)
+            .expect("deposit");
+
+        store
+            .poll(&key.poll(ThrottleStrictness::Off, NOW), NOW)
+            .expect("poll");
+        let resp = store
+            .poll(&key.poll(ThrottleStrictness::Off, NOW + 1), NOW + 1)
+            .expect("poll");
+        assert_eq!(resp.envelopes.len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_deposit_ignored() {
+        let key = MailboxKey::generate();
+        let mut store = store();
+        let deposit = deposit_for(&key, b"hello");
+
+        assert!(store.deposit(&deposit, NOW, None).expect("first"));
+        assert!(!store.deposit(&deposit, NOW, None).expect("second"));
+        assert_eq!(store.envelope_count(), 1);
+    }
+
+    #[test]
+    fn test_tampered_deposit_fails_pow() {
+        let key = MailboxKey::generate();
+        let mut store = store();
+
+        let mut deposit = deposit_for(&key, b"hello");
+        deposit.sealed[40] ^= 0xFF;
+        assert!(matches!(
+            store.deposit(&deposit, NOW, None),
+            Err(WhisperError::InvalidPow)
+        ));
+
+        let mut redirected = deposit_for(&key, b"hello");
+        redirected.mailbox_addr = [9u8; 32];
+        assert!(matches!(
+            store.deposit(&redirected, NOW, None),
+            Err(WhisperError::InvalidPow)
+        ));
+    }
+
+    #[test]
+    fn test_expiry_bounds() {
+        let key = MailboxKey::generate();
+        let mut store = store();
+
+        let too_long = build_deposit(
+            &key.entry([1u8; 16], [7u8; 32]),
+            b"x",
+            NOW,
+            100 * HOUR,
+            0,
+            ThrottleStrictness::Off,
+        )
+        .expect("build deposit");
+        assert!(matches!(
+            store.deposit(&too_long, NOW, None),
+            Err(WhisperError::InvalidExpiry(_))
+        ));
+
+        let deposit = deposit_for(&key, b"x");
+        assert!(matches!(
+            store.deposit(&deposit, NOW + 25 * HOUR, None),
+            Err(WhisperError::InvalidExpiry(_))
+        ));
+    }
+
+    #[test]
+    fn test_sweep_drops_expired() {
+        let key = MailboxKey::generate();
+        let mut store = store();
+        store
+            .deposit(&deposit_for(&key, b"a"), NOW, None)
+            .expect("deposit");
+
+        assert_eq!(store.sweep(NOW + HOUR), 0);
+        assert_eq!(store.sweep(NOW + 24 * HOUR), 1);
+        assert_eq!(store.mailbox_count(), 0);
+    }
+
+    #[test]
+    fn test_mailbox_full() {
+        let key = MailboxKey::generate();
+        let mut store = MailboxStore::new(MailboxConfig {
+            max_envelopes: 2,
+            pow_difficulty: 0,
+            default_strictness: ThrottleStrictness::Off,
+            ..MailboxConfig::default()
+        });
+        store
+            .deposit(&deposit_for(&key, b"1"), NOW, None)
+            .expect("deposit");
+        store
+            .deposit(&deposit_for(&key, b"2"), NOW, None)
+            .expect("deposit");
+        assert!(matches!(
+            store.deposit(&deposit_for(&key, b"3"), NOW, None),
+            Err(WhisperError::MailboxFull(2))
+        ));
+    }
+
+    #[test]
+    fn test_poll_and_ack_require_mailbox_key() {
+        let key = MailboxKey::generate();
+        let other = MailboxKey::generate();
+        let mut store = store();
+        let deposit = deposit_for(&key, b"secret");
+        store.deposit(&deposit, NOW, None).expect("deposit");
+
+        let mut forged = other.poll(ThrottleStrictness::Off, NOW);
+        forged.mailbox_pk = key.mailbox_pk();
+        assert!(matches!(
+            store.poll(&forged, NOW),
+            Err(WhisperError::InvalidSignature)
+        ));
+
+        let mut forged_ack = other.ack(vec![deposit.envelope_id], NOW);
+        forged_ack.mailbox_pk = key.mailbox_pk();
+        assert!(store.ack(&forged_ack, NOW).is_err());
+
+        assert!(matches!(
+            store.poll(
+                &key.poll(ThrottleStrictness::Off, NOW),
+                NOW + 2 * MAX_CLOCK_SKEW_SECS
+            ),
+            Err(WhisperError::StaleRequest { .. })
+        ));
+        assert_eq!(store.envelope_count(), 1);
+    }
+
+    #[test]
+    fn test_relay_enforces_polled_strictness() {
+        let key = MailboxKey::generate();
+        let mut store = store();
+        let mut tampered = key.poll(ThrottleStrictness::High, NOW);
+        tampered.strictness = ThrottleStrictness::Off;
+        assert!(matches!(
+            store.poll(&tampered, NOW),
+            Err(WhisperError::InvalidSignature)
+        ));
+        store
+            .poll(&key.poll(ThrottleStrictness::Low, NOW), NOW)
+            .expect("poll");
+        assert_eq!(
+            store.strictness_for(&key.address()),
+            ThrottleStrictness::Low
+        );
+
+        assert!(matches!(
+            store.deposit(&deposit_for(&key, b"spam"), NOW, None),
+            Err(WhisperError::Throttled(_))
+        ));
+        let quorum = ochra_crypto::ed25519::KeyPair::from_bytes(&[9; 32]);
+        let quorum_key = quorum.verifying_key.to_bytes();
+        let amount_micro_seeds = throttle::payment_price(ThrottleStrictness::Low);
+        let receipt =
+            throttle::payment_receipt_message(&[5; 32], &key.address(), amount_micro_seeds, 3);
+        let paid = |body: &[u8]| WhisperDeposit {
+            ticket: Some(SpamTicket::Payment {
+                tx_hash: [5; 32],
+                amount_micro_seeds,
+                epoch: 3,
+                quorum_sig: quorum.signing_key.sign(&receipt).to_bytes().to_vec(),
+            }),
+            ..deposit_for(&key, body)
+        };
+        // A payment without its quorum's key is not accepted in place of a
+        // puzzle
+        assert!(matches!(
+            store.deposit(&paid(b"paid"), NOW, None),
+            Err(WhisperError::Throttled(_))
+        ));
+        assert!(store
+            .deposit(&paid(b"paid"), NOW, Some(&quorum_key))
+            .expect("deposit"));
+        assert!(matches!(
+            store.deposit(&paid(b"paid again"), NOW, Some(&quorum_key)),
+            Err(WhisperError::Throttled(_))
+        ));
+
+        let resp = store
+            .poll(&key.poll(ThrottleStrictness::Low, NOW + 1), NOW + 1)
+            .expect("poll");
+        assert!(resp.envelopes[0].ticket.is_some());
+        store.sweep(NOW + MAX_HOLD_HOURS * HOUR + 2);
+        assert_eq!(
+            store.strictness_for(&key.address()),
+            ThrottleStrictness::Off
+        );
+    }
+
+    #[test]
+    fn test_device_deposits_one_per_device() {
+        let laptop = MailboxKey::generate();
+        let desktop = MailboxKey::generate();
+        let entries = vec![
+            laptop.entry([1u8; 16], [7u8; 32]),
+            laptop.entry([1u8; 16], [8u8; 32]),
+            desktop.entry([2u8; 16], [7u8; 32]),
+        ];
+
+        let deposits =
+            build_device_deposits(&entries, b"hi both", NOW, HOUR, 0, ThrottleStrictness::Off)
+                .expect("build deposits");
+        assert_eq!(deposits.len(), 2);
+        assert_eq!(deposits[0].0, [7u8; 32]);
+        assert_eq!(deposits[0].1.mailbox_addr, laptop.address());
+        assert_eq!(deposits[1].1.mailbox_addr, desktop.address());
+    }
+
+    #[test]
+    fn test_other_recipient_cannot_open() {
+        let key = MailboxKey::generate();
+        let other = MailboxKey::generate();
+        let deposit = deposit_for(&key, b"for key only");
+        let envelope = MailboxEnvelope {
+            envelope_id: deposit.envelope_id,
+            sealed: deposit.sealed,
+            deposited_at: NOW,
+            ticket: None,
+        };
+        assert!(other.open(&envelope).is_err());
+    }
+}
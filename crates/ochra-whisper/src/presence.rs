@@ -0,0 +1,147 @@
+//! Presence privacy policy (Section 7.4).
+//!
+//! Decides which presence signals — typing indicators, read receipts, and
+//! online presence — go to a Whisper peer. Global settings apply to every
+//! session; per-contact [`PresenceOverride`]s replace individual toggles for
+//! sessions opened with that contact. The global settings are what the
+//! handle descriptor advertises, since a handle lookup does not reveal who is
+//! asking.
+
+use std::collections::HashMap;
+
+use ochra_types::whisper::{PresenceOverride, PresencePrivacy};
+
+/// Global and per-contact presence settings, plus session bindings.
+#[derive(Debug, Default)]
+pub struct PresencePolicy {
+    global: PresencePrivacy,
+    overrides: HashMap<[u8; 32], PresenceOverride>,
+    /// Sessions opened with a known contact, by session ID.
+    sessions: HashMap<[u8; 16], [u8; 32]>,
+}
+
+impl PresencePolicy {
+    /// Create a policy with the given global settings and no overrides.
+    pub fn new(global: PresencePrivacy) -> Self {
+        Self {
+            global,
+            ..Self::default()
+        }
+    }
+
+    /// The global settings.
+    pub fn global(&self) -> PresencePrivacy {
+        self.global
+    }
+
+    /// Replace the global settings.
+    pub fn set_global(&mut self, global: PresencePrivacy) {
+        self.global = global;
+    }
+
+    /// The override for a contact, if any.
+    pub fn override_for(&self, contact: &[u8; 32]) -> Option<PresenceOverride> {
+        self.overrides.get(contact).copied()
+    }
+
+    /// Set a contact's override. An empty override removes it.
+    pub fn set_override(&mut self, contact: [u8; 32], o: PresenceOverride) {
+        if o.is_empty() {
+            self.overrides.remove(&contact);
+        } else {
+            self.overrides.insert(contact, o);
+        }
+    }
+
+    /// Effective settings toward a contact, or toward an unknown peer.
+    pub fn for_contact(&self, contact: Option<&[u8; 32]>) -> PresencePrivacy {
+        match contact.and_then(|c| self.overrides.get(c)) {
+            Some(o) => self.global.with_override(o),
+            None => self.global,
+        }
+    }
+
+    /// Record that a session was opened with a contact.
+    pub fn bind_session(&mut self, session_id: [u8; 16], contact: [u8; 32]) {
+        self.sessions.insert(session_id, contact);
+    }
+
+    /// Forget a closed session.
+    pub fn unbind_session(&mut self, session_id: &[u8; 16]) {
+        self.sessions.remove(session_id);
+    }
+
+    /// Effective settings toward a session's peer.
+    pub fn for_session(&self, session_id: &[u8; 16]) -> PresencePrivacy {
+        self.for_contact(self.sessions.get(session_id))
+    }
+
+    /// Settings to advertise in the handle descriptor.
+    pub fn advertised(&self) -> PresencePrivacy {
+        self.global
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTACT: [u8; 32] = [3u8; 32];
+    const SESSION: [u8; 16] = [4u8; 16];
+
+    fn quiet() -> PresencePrivacy {
+        PresencePrivacy {
+            typing_indicators: false,
+            read_receipts: false,
+            online_presence: true,
+        }
+    }
+
+    #[test]
+    fn test_override_applies_to_bound_session() {
+        let mut policy = PresencePolicy::new(quiet());
+        policy.set_override(
+            CONTACT,
+            PresenceOverride {
+                read_receipts: Some(true),
+                ..PresenceOverride::default()
+            },
+        );
+
+        assert!(!policy.for_session(&SESSION).read_receipts);
+        policy.bind_session(SESSION, CONTACT);
+        let effective = policy.for_session(&SESSION);
+        assert!(effective.read_receipts);
+        assert!(!effective.typing_indicators);
+        assert_eq!(policy.advertised(), quiet());
+
+        policy.unbind_session(&SESSION);
+        assert!(!policy.for_session(&SESSION).read_receipts);
+    }
+
+    #[test]
+    fn test_empty_override_is_removed() {
+        let mut policy = PresencePolicy::default();
+        policy.set_override(
+            CONTACT,
+            PresenceOverride {
+                typing_indicators: Some(false),
+                ..PresenceOverride::default()
+            },
+        );
+        assert!(policy.override_for(&CONTACT).is_some());
+        policy.set_override(CONTACT, PresenceOverride::default());
+        assert!(policy.override_for(&CONTACT).is_none());
+    }
+
+    #[test]
+    fn test_typing_requires_online_presence() {
+        let mut policy = PresencePolicy::default();
+        assert!(policy.global().sends_typing());
+        policy.set_global(PresencePrivacy {
+            online_presence: false,
+            ..PresencePrivacy::default()
+        });
+        assert!(!policy.for_contact(None).sends_typing());
+    }
+}
@@ -0,0 +1,395 @@
+//! Handle resolution cache.
+//!
+//! Resolving a handle costs a DHT GET over a Sphinx circuit, so resolvers
+//! keep what they learn. A resolved descriptor is fresh for at most
+//! [`MAX_FRESH_SECS`], then served stale for up to [`STALE_SECS`] while a
+//! single background fetch revalidates it. Neither window outlives the
+//! descriptor's own expiry, 7 days after its `refresh_at`. A handle with
+//! no descriptor is remembered for [`NEGATIVE_TTL_SECS`].
+//!
+//! Fetches are rate limited twice: one fetch per handle per
+//! [`MIN_FETCH_INTERVAL_SECS`], and a token bucket of [`FETCH_BURST`]
+//! fetches refilled one per [`FETCH_REFILL_SECS`] across all handles.
+
+use std::collections::HashMap;
+
+use ochra_types::whisper::HandleDescriptor;
+
+use crate::handle::{self, HandleLifecycle, HANDLE_TTL_SECS};
+
+/// Longest a resolved descriptor is served without revalidation.
+pub const MAX_FRESH_SECS: u64 = 15 * 60;
+
+/// How long past freshness a descriptor is still served while it is
+/// revalidated.
+pub const STALE_SECS: u64 = 60 * 60;
+
+/// How long a missing handle is remembered.
+pub const NEGATIVE_TTL_SECS: u64 = 5 * 60;
+
+/// Minimum spacing of fetches for the same handle.
+pub const MIN_FETCH_INTERVAL_SECS: u64 = 10;
+
+/// Fetches allowed back to back across all handles.
+pub const FETCH_BURST: u32 = 20;
+
+/// Seconds to regain one fetch token.
+pub const FETCH_REFILL_SECS: u64 = 3;
+
+/// Entries kept before the soonest-expiring are evicted.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// What the cache knows about a handle.
+#[derive(Clone, Debug)]
+pub enum CacheLookup {
+    /// A descriptor within its TTL.
+    Fresh(HandleDescriptor),
+    /// A descriptor past its TTL. If `refresh` is set, the caller should
+    /// fetch it again in the background.
+    Stale {
+        descriptor: HandleDescriptor,
+        refresh: bool,
+    },
+    /// The handle was recently found to have no descriptor.
+    Missing,
+    /// Nothing usable is cached; the caller should fetch now.
+    Fetch,
+    /// Nothing usable is cached and fetching is rate limited.
+    Limited,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    /// `None` for a negative entry.
+    descriptor: Option<HandleDescriptor>,
+    fresh_until: u64,
+    stale_until: u64,
+}
+
+#[derive(Debug)]
+struct FetchBucket {
+    tokens: u32,
+    refilled_at: u64,
+}
+
+impl FetchBucket {
+    fn take(&mut self, now: u64) -> bool {
+        let earned = now.saturating_sub(self.refilled_at) / FETCH_REFILL_SECS;
+        if earned > 0 {
+            self.refilled_at += earned * FETCH_REFILL_SECS;
+            let earned = u32::try_from(earned).unwrap_or(u32::MAX);
+            self.tokens = self.tokens.saturating_add(earned).min(FETCH_BURST);
+        }
+        if self.tokens == 0 {
+            return false;
+        }
+        self.tokens -= 1;
+        true
+    }
+}
+
+/// Cache of resolved handles, keyed by normalized handle.
+///
+/// The descriptor stored for a handle is the one at the end of its
+/// transition chain, so it may name a different handle.
+#[derive(Debug)]
+pub struct HandleCache {
+    entries: HashMap<String, Entry>,
+    /// When each in-flight fetch started.
+    fetching: HashMap<String, u64>,
+    bucket: FetchBucket,
+    capacity: usize,
+}
+
+impl Default for HandleCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl HandleCache {
+    /// Create an empty cache holding at most `capacity` handles.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            fetching: HashMap::new(),
+            bucket: FetchBucket {
+                tokens: FETCH_BURST,
+                refilled_at: 0,
+            },
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Look up `handle`, reserving a fetch if one is needed and allowed.
+    pub fn lookup(&mut self, handle: &str, now: u64) -> CacheLookup {
+        let key = handle::normalize(handle);
+        let cached = self
+            .entries
+            .get(&key)
+            .filter(|e| now < e.stale_until)
+            .cloned();
+        match cached {
+            Some(Entry {
+                descriptor: None, ..
+            }) => CacheLookup::Missing,
+            Some(Entry {
+                descriptor: Some(descriptor),
+                fresh_until,
+                ..
+            }) => {
+                if now < fresh_until {
+                    CacheLookup::Fresh(descriptor)
+                } else {
+                    let refresh = self.admit(key, now);
+                    CacheLookup::Stale {
+                        descriptor,
+                        refresh,
+                    }
+                }
+            }
+            None => {
+                if self.admit(key, now) {
+                    CacheLookup::Fetch
+                } else {
+                    CacheLookup::Limited
+                }
+            }
+        }
+    }
+
+    /// Reserve a fetch of `key` if it is not already in flight and a token
+    /// is available.
+    fn admit(&mut self, key: String, now: u64) -> bool {
+        // A fetch that never reported back stops blocking after the interval.
+        self.fetching
+            .retain(|_, started| now < *started + MIN_FETCH_INTERVAL_SECS);
+        if self.fetching.contains_key(&key) || !self.bucket.take(now) {
+            return false;
+        }
+        self.fetching.insert(key, now);
+        true
+    }
+
+    /// Record the descriptor `handle` resolved to.
+    ///
+    /// A descriptor that is no longer active is cached as missing.
+    pub fn insert(&mut self, handle: &str, descriptor: HandleDescriptor, now: u64) {
+        if handle::lifecycle(descriptor.refresh_at, now) != HandleLifecycle::Active {
+            self.insert_missing(handle, now);
+            return;
+        }
+        let expires = descriptor.refresh_at + HANDLE_TTL_SECS;
+        let fresh_until = (now + MAX_FRESH_SECS).min(expires);
+        let stale_until = (fresh_until + STALE_SECS).min(expires);
+        self.put(
+            handle,
+            Entry {
+                descriptor: Some(descriptor),
+                fresh_until,
+                stale_until,
+            },
+        );
+    }
+
+    /// Record that `handle` has no descriptor.
+    pub fn insert_missing(&mut self, handle: &str, now: u64) {
+        let until = now + NEGATIVE_TTL_SECS;
+        self.put(
+            handle,
+            Entry {
+                descriptor: None,
+                fresh_until: until,
+                stale_until: until,
+            },
+        );
+    }
+
+    fn put(&mut self, handle: &str, entry: Entry) {
+        let key = handle::normalize(handle);
+        self.fetching.remove(&key);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let soonest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.stale_until)
+                .map(|(k, _)| k.clone());
+            if let Some(soonest) = soonest {
+                self.entries.remove(&soonest);
+            }
+        }
+        self.entries.insert(key, entry);
+    }
+
+    /// Drop `handle` and any entry that resolved to it.
+    ///
+    /// Returns the number of entries removed.
+    pub fn invalidate(&mut self, handle: &str) -> usize {
+        let key = handle::normalize(handle);
+        let before = self.entries.len();
+        self.entries.retain(|k, e| {
+            *k != key
+                && e.descriptor
+                    .as_ref()
+                    .is_none_or(|d| handle::normalize(&d.handle) != key)
+        });
+        before - self.entries.len()
+    }
+
+    /// Drop every resolved descriptor, keeping negative entries.
+    ///
+    /// Returns the number of entries removed.
+    pub fn invalidate_resolved(&mut self) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| e.descriptor.is_none());
+        before - self.entries.len()
+    }
+
+    /// Number of cached handles, including expired entries not yet evicted.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ochra_types::whisper::{HandleStatus, PresencePrivacy, ThrottleStrictness};
+
+    use super::*;
+
+    const NOW: u64 = 10_000_000;
+
+    fn descriptor(handle: &str, refresh_at: u64) -> HandleDescriptor {
+        HandleDescriptor {
+            handle: handle.to_string(),
+            handle_signing_pk: [1; 32],
+            intro_points: Vec::new(),
+            auth_key: [2; 32],
+            pq_auth_key: Vec::new(),
+            registered_at: refresh_at,
+            refresh_at,
+            pow_proof: Vec::new(),
+            status: HandleStatus::Active,
+            mailboxes: Vec::new(),
+            privacy: PresencePrivacy::default(),
+            strictness: ThrottleStrictness::default(),
+            sig: [0; 64],
+        }
+    }
+
+    #[test]
+    fn test_fresh_then_stale_then_gone() {
+        let mut cache = HandleCache::default();
+        assert!(matches!(cache.lookup("Alice", NOW), CacheLookup::Fetch));
+        cache.insert("alice", descriptor("alice", NOW), NOW);
+
+        assert!(matches!(
+            cache.lookup("ALICE", NOW + MAX_FRESH_SECS - 1),
+            CacheLookup::Fresh(_)
+        ));
+        let stale_at = NOW + MAX_FRESH_SECS;
+        assert!(matches!(
+            cache.lookup("alice", stale_at),
+            CacheLookup::Stale { refresh: true, .. }
+        ));
+        // The revalidation is already in flight.
+        assert!(matches!(
+            cache.lookup("alice", stale_at + 1),
+            CacheLookup::Stale { refresh: false, .. }
+        ));
+        assert!(matches!(
+            cache.lookup("alice", stale_at + STALE_SECS),
+            CacheLookup::Fetch
+        ));
+    }
+
+    #[test]
+    fn test_ttl_capped_by_descriptor_expiry() {
+        let mut cache = HandleCache::default();
+        let refresh_at = NOW - HANDLE_TTL_SECS + 60;
+        cache.insert("bob", descriptor("bob", refresh_at), NOW);
+        assert!(matches!(
+            cache.lookup("bob", NOW + 59),
+            CacheLookup::Fresh(_)
+        ));
+        assert!(matches!(cache.lookup("bob", NOW + 60), CacheLookup::Fetch));
+
+        // An expired descriptor does not resolve.
+        cache.insert("carol", descriptor("carol", NOW - HANDLE_TTL_SECS - 1), NOW);
+        assert!(matches!(cache.lookup("carol", NOW), CacheLookup::Missing));
+    }
+
+    #[test]
+    fn test_negative_caching() {
+        let mut cache = HandleCache::default();
+        assert!(matches!(cache.lookup("dave", NOW), CacheLookup::Fetch));
+        cache.insert_missing("dave", NOW);
+        assert!(matches!(
+            cache.lookup("dave", NOW + NEGATIVE_TTL_SECS - 1),
+            CacheLookup::Missing
+        ));
+        assert!(matches!(
+            cache.lookup("dave", NOW + NEGATIVE_TTL_SECS),
+            CacheLookup::Fetch
+        ));
+    }
+
+    #[test]
+    fn test_fetches_rate_limited() {
+        let mut cache = HandleCache::default();
+        // A second lookup of a handle being fetched waits for the first.
+        assert!(matches!(cache.lookup("erin", NOW), CacheLookup::Fetch));
+        assert!(matches!(
+            cache.lookup("erin", NOW + 1),
+            CacheLookup::Limited
+        ));
+        assert!(matches!(
+            cache.lookup("erin", NOW + MIN_FETCH_INTERVAL_SECS),
+            CacheLookup::Fetch
+        ));
+
+        let mut cache = HandleCache::default();
+        for i in 0..FETCH_BURST {
+            let handle = format!("user_{i}");
+            assert!(matches!(cache.lookup(&handle, NOW), CacheLookup::Fetch));
+        }
+        assert!(matches!(cache.lookup("frank", NOW), CacheLookup::Limited));
+        assert!(matches!(
+            cache.lookup("frank", NOW + FETCH_REFILL_SECS),
+            CacheLookup::Fetch
+        ));
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let mut cache = HandleCache::default();
+        // @old_name moved to @new_name; both resolve to the new descriptor.
+        cache.insert("old_name", descriptor("new_name", NOW), NOW);
+        cache.insert("new_name", descriptor("new_name", NOW), NOW);
+        cache.insert("other", descriptor("other", NOW), NOW);
+        cache.insert_missing("ghost", NOW);
+
+        assert_eq!(cache.invalidate("New_Name"), 2);
+        assert!(matches!(cache.lookup("old_name", NOW), CacheLookup::Fetch));
+        assert!(matches!(cache.lookup("other", NOW), CacheLookup::Fresh(_)));
+
+        assert_eq!(cache.invalidate_resolved(), 1);
+        assert!(matches!(cache.lookup("ghost", NOW), CacheLookup::Missing));
+    }
+
+    #[test]
+    fn test_eviction_at_capacity() {
+        let mut cache = HandleCache::new(2);
+        cache.insert_missing("first", NOW);
+        cache.insert("second", descriptor("second", NOW), NOW);
+        cache.insert("third", descriptor("third", NOW), NOW);
+        assert_eq!(cache.len(), 2);
+        assert!(matches!(cache.lookup("second", NOW), CacheLookup::Fresh(_)));
+        assert!(matches!(cache.lookup("first", NOW), CacheLookup::Fetch));
+    }
+}
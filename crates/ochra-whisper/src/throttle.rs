@@ -0,0 +1,499 @@
+//! Receiver-driven throttling of unknown senders.
+//!
+//! Each recipient picks a [`ThrottleStrictness`], advertised in its handle
+//! descriptor and in its mailbox polls. A sender who is not a contact
+//! attaches a [`SpamTicket`]: an Argon2id puzzle solved over the message, or
+//! a Seed payment to the recipient. Mailbox relays check the ticket against
+//! the strictness the mailbox last polled with, and the recipient checks it
+//! again with its [`ThrottleGate`] before delivery.
+//!
+//! A payment counts only with the quorum's receipt: a FROST signature over
+//! [`payment_receipt_message`] by the quorum of the epoch that settled the
+//! transfer. Without the quorum key for that epoch, or with a signature that
+//! does not verify, a puzzle is required. Relays refuse a second held
+//! envelope paid by the same transaction, and the recipient refuses a
+//! transaction it has already accepted a ticket for.
+//!
+//! ## Levels
+//!
+//! | Strictness | Puzzle | Or payment |
+//! |------------|--------|------------|
+//! | `off` | — | — |
+//! | `low` | 6 bits | 0.001 Seeds |
+//! | `standard` | 8 bits | 0.01 Seeds |
+//! | `high` | 10 bits | 0.1 Seeds |
+
+use std::collections::{HashSet, VecDeque};
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, VerifyingKey};
+use ochra_pow::argon2id_pow::{self, PowChallenge, PowSolution};
+use ochra_transport::messages::SpamTicket;
+use ochra_types::whisper::ThrottleStrictness;
+use ochra_types::MICRO_SEEDS_PER_SEED;
+
+use crate::{Result, WhisperError};
+
+/// Payment transactions remembered to refuse reuse.
+pub const MAX_SPENT_PAYMENTS: usize = 10_000;
+
+/// Leading zero bits a puzzle needs at `strictness`; 0 when off.
+pub fn puzzle_difficulty(strictness: ThrottleStrictness) -> u32 {
+    match strictness {
+        ThrottleStrictness::Off => 0,
+        ThrottleStrictness::Low => 6,
+        ThrottleStrictness::Standard => 8,
+        ThrottleStrictness::High => 10,
+    }
+}
+
+/// Micro-seeds a payment ticket needs at `strictness`; 0 when off.
+pub fn payment_price(strictness: ThrottleStrictness) -> u64 {
+    match strictness {
+        ThrottleStrictness::Off => 0,
+        ThrottleStrictness::Low => MICRO_SEEDS_PER_SEED / 1000,
+        ThrottleStrictness::Standard => MICRO_SEEDS_PER_SEED / 100,
+        ThrottleStrictness::High => MICRO_SEEDS_PER_SEED / 10,
+    }
+}
+
+/// Build the puzzle challenge binding a ticket to its recipient `target`
+/// and message `id`.
+///
+/// The solver also binds the hash of the message bytes as the PoW content
+/// hash, so a solved puzzle cannot be moved to another message.
+pub fn puzzle_challenge(target: &[u8; 32], id: &[u8; 16], difficulty: u32) -> PowChallenge {
+    let mut data = Vec::with_capacity(14 + 32 + 16);
+    data.extend_from_slice(b"whisper-puzzle");
+    data.extend_from_slice(target);
+    data.extend_from_slice(id);
+    PowChallenge {
+        target_hash: blake3::hash(&data),
+        difficulty,
+        nonce_prefix: Vec::new(),
+    }
+}
+
+/// Solve a puzzle at `difficulty` for a message, normally
+/// [`puzzle_difficulty`] of the recipient's strictness.
+pub fn solve_puzzle(
+    target: &[u8; 32],
+    id: &[u8; 16],
+    content: &[u8],
+    difficulty: u32,
+) -> Result<SpamTicket> {
+    let challenge = puzzle_challenge(target, id, difficulty);
+    let solution = argon2id_pow::solve_pow(&challenge, &blake3::hash(content))?;
+    Ok(SpamTicket::Puzzle {
+        nonce: solution.nonce,
+        hash: solution.hash,
+    })
+}
+
+/// Whether `ticket` is a puzzle solved at `difficulty` for a message.
+pub fn verify_puzzle(
+    ticket: &SpamTicket,
+    target: &[u8; 32],
+    id: &[u8; 16],
+    content: &[u8],
+    difficulty: u32,
+) -> bool {
+    let SpamTicket::Puzzle { nonce, hash } = ticket else {
+        return false;
+    };
+    let solution = PowSolution {
+        nonce: *nonce,
+        hash: *hash,
+        params: argon2id_pow::default_params(),
+    };
+    argon2id_pow::verify_pow_with_content(
+        &puzzle_challenge(target, id, difficulty),
+        &blake3::hash(content),
+        &solution,
+    )
+}
+
+/// The message the quorum signs to attest that `tx_hash` paid
+/// `amount_micro_seeds` for messages to `target`, settled in `epoch`.
+pub fn payment_receipt_message(
+    tx_hash: &[u8; 32],
+    target: &[u8; 32],
+    amount_micro_seeds: u64,
+    epoch: u32,
+) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(15 + 32 + 32 + 8 + 4);
+    msg.extend_from_slice(b"whisper-payment");
+    msg.extend_from_slice(tx_hash);
+    msg.extend_from_slice(target);
+    msg.extend_from_slice(&amount_micro_seeds.to_le_bytes());
+    msg.extend_from_slice(&epoch.to_le_bytes());
+    msg
+}
+
+/// The epoch whose quorum key checks `ticket`, or `None` for a puzzle.
+pub fn payment_epoch(ticket: &SpamTicket) -> Option<u32> {
+    match ticket {
+        SpamTicket::Payment { epoch, .. } => Some(*epoch),
+        SpamTicket::Puzzle { .. } => None,
+    }
+}
+
+/// Whether `quorum_sig` is the quorum's receipt for a payment.
+fn verify_payment_receipt(
+    quorum_key: &[u8; 32],
+    tx_hash: &[u8; 32],
+    target: &[u8; 32],
+    amount_micro_seeds: u64,
+    epoch: u32,
+    quorum_sig: &[u8],
+) -> bool {
+    let Ok(sig) = <[u8; 64]>::try_from(quorum_sig) else {
+        return false;
+    };
+    let Ok(key) = VerifyingKey::from_bytes(quorum_key) else {
+        return false;
+    };
+    key.verify(
+        &payment_receipt_message(tx_hash, target, amount_micro_seeds, epoch),
+        &Signature::from_bytes(&sig),
+    )
+    .is_ok()
+}
+
+/// What a checked ticket paid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TicketCheck {
+    /// No ticket was needed.
+    NotRequired,
+    /// A verified puzzle.
+    Puzzle,
+    /// A payment of sufficient amount with a valid quorum receipt.
+    Payment {
+        tx_hash: [u8; 32],
+        amount_micro_seeds: u64,
+    },
+}
+
+/// Check `ticket` against `strictness` for a message.
+///
+/// `quorum_key` is the group key of the quorum for the ticket's
+/// [`payment_epoch`], if known.
+///
+/// # Errors
+///
+/// - [`WhisperError::Throttled`] if a ticket is required and missing, a
+///   payment is below the price, or no quorum key is known for it
+/// - [`WhisperError::InvalidPow`] if the puzzle does not verify
+/// - [`WhisperError::InvalidSignature`] if the payment receipt does not
+///   verify
+pub fn check_ticket(
+    ticket: Option<&SpamTicket>,
+    strictness: ThrottleStrictness,
+    target: &[u8; 32],
+    id: &[u8; 16],
+    content: &[u8],
+    quorum_key: Option<&[u8; 32]>,
+) -> Result<TicketCheck> {
+    if strictness == ThrottleStrictness::Off {
+        return Ok(TicketCheck::NotRequired);
+    }
+    match ticket {
+        None => Err(WhisperError::Throttled(format!(
+            "a ticket is required at {strictness:?} strictness"
+        ))),
+        Some(puzzle @ SpamTicket::Puzzle { .. }) => {
+            if !verify_puzzle(puzzle, target, id, content, puzzle_difficulty(strictness)) {
+                return Err(WhisperError::InvalidPow);
+            }
+            Ok(TicketCheck::Puzzle)
+        }
+        Some(SpamTicket::Payment {
+            tx_hash,
+            amount_micro_seeds,
+            epoch,
+            quorum_sig,
+        }) => {
+            let price = payment_price(strictness);
+            if *amount_micro_seeds < price {
+                return Err(WhisperError::Throttled(format!(
+                    "payment of {amount_micro_seeds} micro-seeds is below the price of {price}"
+                )));
+            }
+            let Some(quorum_key) = quorum_key else {
+                return Err(WhisperError::Throttled(format!(
+                    "no quorum key for epoch {epoch} to check the payment; a puzzle is required"
+                )));
+            };
+            if !verify_payment_receipt(
+                quorum_key,
+                tx_hash,
+                target,
+                *amount_micro_seeds,
+                *epoch,
+                quorum_sig,
+            ) {
+                return Err(WhisperError::InvalidSignature);
+            }
+            Ok(TicketCheck::Payment {
+                tx_hash: *tx_hash,
+                amount_micro_seeds: *amount_micro_seeds,
+            })
+        }
+    }
+}
+
+/// Recipient-side check of inbound messages.
+#[derive(Debug, Default)]
+pub struct ThrottleGate {
+    strictness: ThrottleStrictness,
+    spent: HashSet<[u8; 32]>,
+    spent_order: VecDeque<[u8; 32]>,
+}
+
+impl ThrottleGate {
+    /// Create a gate enforcing `strictness`.
+    pub fn new(strictness: ThrottleStrictness) -> Self {
+        Self {
+            strictness,
+            ..Self::default()
+        }
+    }
+
+    /// The strictness being enforced.
+    pub fn strictness(&self) -> ThrottleStrictness {
+        self.strictness
+    }
+
+    /// Change the strictness for messages checked from now on.
+    pub fn set_strictness(&mut self, strictness: ThrottleStrictness) {
+        self.strictness = strictness;
+    }
+
+    /// Admit a message before delivery. Contacts are exempt.
+    ///
+    /// # Errors
+    ///
+    /// - As [`check_ticket`], and [`WhisperError::Throttled`] if a payment
+    ///   ticket's transaction was already used
+    pub fn admit(
+        &mut self,
+        sender_known: bool,
+        ticket: Option<&SpamTicket>,
+        target: &[u8; 32],
+        id: &[u8; 16],
+        content: &[u8],
+        quorum_key: Option<&[u8; 32]>,
+    ) -> Result<TicketCheck> {
+        if sender_known {
+            return Ok(TicketCheck::NotRequired);
+        }
+        let check = check_ticket(ticket, self.strictness, target, id, content, quorum_key)?;
+        if let TicketCheck::Payment { tx_hash, .. } = &check {
+            if !self.spent.insert(*tx_hash) {
+                return Err(WhisperError::Throttled(
+                    "payment already used for another message".to_string(),
+                ));
+            }
+            self.spent_order.push_back(*tx_hash);
+            if self.spent_order.len() > MAX_SPENT_PAYMENTS {
+                if let Some(oldest) = self.spent_order.pop_front() {
+                    self.spent.remove(&oldest);
+                }
+            }
+        }
+        Ok(check)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ochra_crypto::ed25519::KeyPair;
+
+    const TARGET: [u8; 32] = [3; 32];
+    const ID: [u8; 16] = [4; 16];
+    const EPOCH: u32 = 12;
+
+    fn quorum() -> KeyPair {
+        KeyPair::from_bytes(&[9; 32])
+    }
+
+    fn quorum_key() -> [u8; 32] {
+        quorum().verifying_key.to_bytes()
+    }
+
+    fn payment(tx: u8, amount_micro_seeds: u64) -> SpamTicket {
+        let tx_hash = [tx; 32];
+        let receipt = payment_receipt_message(&tx_hash, &TARGET, amount_micro_seeds, EPOCH);
+        SpamTicket::Payment {
+            tx_hash,
+            amount_micro_seeds,
+            epoch: EPOCH,
+            quorum_sig: quorum().signing_key.sign(&receipt).to_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_puzzle_bound_to_message() {
+        let ticket = solve_puzzle(&TARGET, &ID, b"hello", 0).expect("solve");
+        assert!(verify_puzzle(&ticket, &TARGET, &ID, b"hello", 0));
+        assert!(!verify_puzzle(&ticket, &TARGET, &ID, b"hellO", 0));
+        assert!(!verify_puzzle(&ticket, &[5; 32], &ID, b"hello", 0));
+        assert!(!verify_puzzle(&ticket, &TARGET, &[5; 16], b"hello", 0));
+        assert!(!verify_puzzle(&payment(1, 1), &TARGET, &ID, b"hello", 0));
+
+        let forged = SpamTicket::Puzzle {
+            nonce: [0; 16],
+            hash: [0; 32],
+        };
+        assert!(matches!(
+            check_ticket(
+                Some(&forged),
+                ThrottleStrictness::Low,
+                &TARGET,
+                &ID,
+                b"hello",
+                None
+            ),
+            Err(WhisperError::InvalidPow)
+        ));
+    }
+
+    #[test]
+    fn test_strictness_scales_requirements() {
+        assert_eq!(puzzle_difficulty(ThrottleStrictness::Off), 0);
+        assert!(
+            puzzle_difficulty(ThrottleStrictness::High)
+                > puzzle_difficulty(ThrottleStrictness::Standard)
+        );
+        assert_eq!(
+            check_ticket(None, ThrottleStrictness::Off, &TARGET, &ID, b"hi", None).expect("off"),
+            TicketCheck::NotRequired
+        );
+        assert!(matches!(
+            check_ticket(None, ThrottleStrictness::Low, &TARGET, &ID, b"hi", None),
+            Err(WhisperError::Throttled(_))
+        ));
+
+        let price = payment_price(ThrottleStrictness::Standard);
+        assert!(price > payment_price(ThrottleStrictness::Low));
+        assert!(check_ticket(
+            Some(&payment(1, price)),
+            ThrottleStrictness::Standard,
+            &TARGET,
+            &ID,
+            b"hi",
+            Some(&quorum_key())
+        )
+        .is_ok());
+        assert!(matches!(
+            check_ticket(
+                Some(&payment(1, price)),
+                ThrottleStrictness::High,
+                &TARGET,
+                &ID,
+                b"hi",
+                Some(&quorum_key())
+            ),
+            Err(WhisperError::Throttled(_))
+        ));
+    }
+
+    #[test]
+    fn test_payment_needs_quorum_receipt() {
+        let price = payment_price(ThrottleStrictness::Low);
+        let check = |ticket: &SpamTicket, key: Option<&[u8; 32]>| {
+            check_ticket(
+                Some(ticket),
+                ThrottleStrictness::Low,
+                &TARGET,
+                &ID,
+                b"hi",
+                key,
+            )
+        };
+        let ticket = payment(1, price);
+        assert_eq!(payment_epoch(&ticket), Some(EPOCH));
+        assert!(matches!(
+            check(&ticket, Some(&quorum_key())),
+            Ok(TicketCheck::Payment { .. })
+        ));
+        // Without the epoch's quorum key a payment proves nothing
+        assert!(matches!(
+            check(&ticket, None),
+            Err(WhisperError::Throttled(_))
+        ));
+        let other_key = KeyPair::from_bytes(&[8; 32]).verifying_key.to_bytes();
+        assert!(matches!(
+            check(&ticket, Some(&other_key)),
+            Err(WhisperError::InvalidSignature)
+        ));
+
+        // A self-declared amount or another target breaks the receipt
+        let mut inflated = ticket.clone();
+        if let SpamTicket::Payment {
+            amount_micro_seeds, ..
+        } = &mut inflated
+        {
+            *amount_micro_seeds = price * 100;
+        }
+        assert!(matches!(
+            check(&inflated, Some(&quorum_key())),
+            Err(WhisperError::InvalidSignature)
+        ));
+        assert!(matches!(
+            check_ticket(
+                Some(&ticket),
+                ThrottleStrictness::Low,
+                &[7; 32],
+                &ID,
+                b"hi",
+                Some(&quorum_key())
+            ),
+            Err(WhisperError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_gate_exempts_contacts_and_refuses_reused_payments() {
+        let mut gate = ThrottleGate::new(ThrottleStrictness::High);
+        assert_eq!(
+            gate.admit(true, None, &TARGET, &ID, b"hi", None)
+                .expect("contact"),
+            TicketCheck::NotRequired
+        );
+        assert!(gate.admit(false, None, &TARGET, &ID, b"hi", None).is_err());
+
+        let ticket = payment(7, payment_price(ThrottleStrictness::High));
+        assert!(matches!(
+            gate.admit(
+                false,
+                Some(&ticket),
+                &TARGET,
+                &ID,
+                b"hi",
+                Some(&quorum_key())
+            ),
+            Ok(TicketCheck::Payment { .. })
+        ));
+        assert!(matches!(
+            gate.admit(
+                false,
+                Some(&ticket),
+                &TARGET,
+                &[5; 16],
+                b"again",
+                Some(&quorum_key())
+            ),
+            Err(WhisperError::Throttled(_))
+        ));
+
+        gate.set_strictness(ThrottleStrictness::Off);
+        assert_eq!(
+            gate.admit(false, None, &TARGET, &ID, b"hi", None)
+                .expect("off"),
+            TicketCheck::NotRequired
+        );
+    }
+}
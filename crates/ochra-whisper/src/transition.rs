@@ -0,0 +1,366 @@
+//! Handle renames and transfers (Section 7.2).
+//!
+//! When a handle is renamed, deprecated with a successor, or handed to a new
+//! owner key, the old key signs a [`HandleTransition`] and publishes it at
+//! [`transition_addr`] of the old handle. Resolvers that reach the old handle
+//! follow the chain with [`follow_transitions`], checking at each hop that
+//! the record is signed by the key that held the previous handle.
+//!
+//! For [`TRANSITION_GRACE_SECS`] after the transition both the old and the new
+//! handle resolve, and clients warn contacts who still use the old one. Once
+//! the grace window ends the record lapses and the old handle follows the
+//! normal tombstone lifecycle.
+//!
+//! ## Addressing
+//!
+//! `transition_addr = BLAKE3::hash("handle-transition" || lowercase(old_handle))`
+
+use std::collections::HashSet;
+
+use ochra_crypto::blake3;
+use ochra_crypto::ed25519::{Signature, SigningKey, VerifyingKey};
+use ochra_types::whisper::{HandleDescriptor, HandleTransition, HandleTransitionKind};
+
+use crate::handle::{self, ProofCheck};
+use crate::{Result, WhisperError};
+
+/// How long both handles resolve after a transition (30 days, matching the
+/// deprecation tombstone).
+pub const TRANSITION_GRACE_SECS: u64 = 30 * 24 * 3600;
+
+/// Maximum number of transitions a resolver follows.
+pub const MAX_TRANSITION_HOPS: usize = 4;
+
+/// DHT address of the transition record published for `old_handle`.
+pub fn transition_addr(old_handle: &str) -> [u8; 32] {
+    let name = handle::normalize(old_handle);
+    let mut data = Vec::with_capacity(17 + name.len());
+    data.extend_from_slice(b"handle-transition");
+    data.extend_from_slice(name.as_bytes());
+    blake3::hash(&data)
+}
+
+/// Bytes signed by the old handle key.
+fn signable(t: &HandleTransition) -> Vec<u8> {
+    let old = handle::normalize(&t.old_handle);
+    let new = handle::normalize(&t.new_handle);
+    let mut msg = Vec::with_capacity(17 + 2 + old.len() + new.len() + 64 + 1 + 16);
+    msg.extend_from_slice(b"handle-transition");
+    msg.push(old.len() as u8);
+    msg.extend_from_slice(old.as_bytes());
+    msg.extend_from_slice(&t.old_signing_pk);
+    msg.push(new.len() as u8);
+    msg.extend_from_slice(new.as_bytes());
+    msg.extend_from_slice(&t.new_signing_pk);
+    msg.push(match t.kind {
+        HandleTransitionKind::Rename => 1,
+        HandleTransitionKind::Transfer => 2,
+    });
+    msg.extend_from_slice(&t.issued_at.to_be_bytes());
+    msg.extend_from_slice(&t.grace_until.to_be_bytes());
+    msg
+}
+
+/// Sign a transition from `old_handle` (held by `old_key`) to `new_handle`
+/// held by `new_signing_pk`.
+pub fn sign_transition(
+    old_key: &SigningKey,
+    old_handle: &str,
+    new_handle: &str,
+    new_signing_pk: [u8; 32],
+    kind: HandleTransitionKind,
+    now: u64,
+) -> Result<HandleTransition> {
+    let mut t = HandleTransition {
+        old_handle: handle::normalize(old_handle),
+        old_signing_pk: old_key.verifying_key().to_bytes(),
+        new_handle: handle::normalize(new_handle),
+        new_signing_pk,
+        kind,
+        issued_at: now,
+        grace_until: now + TRANSITION_GRACE_SECS,
+        sig: [0u8; 64],
+    };
+    check_shape(&t)?;
+    t.sig = old_key.sign(&signable(&t)).to_bytes();
+    Ok(t)
+}
+
+/// Handles are valid, something actually changes, and the grace window is
+/// the fixed length.
+fn check_shape(t: &HandleTransition) -> Result<()> {
+    handle::validate_handle(&t.old_handle)?;
+    handle::validate_handle(&t.new_handle)?;
+    if handle::normalize(&t.old_handle) == handle::normalize(&t.new_handle)
+        && t.old_signing_pk == t.new_signing_pk
+    {
+        return Err(WhisperError::Transition(
+            "transition changes neither handle nor key".to_string(),
+        ));
+    }
+    if t.grace_until != t.issued_at + TRANSITION_GRACE_SECS {
+        return Err(WhisperError::Transition(format!(
+            "grace window must be {TRANSITION_GRACE_SECS} seconds"
+        )));
+    }
+    Ok(())
+}
+
+/// Verify a transition against the key that holds its old handle.
+///
+/// Storing nodes pass the key from the old handle's descriptor before
+/// accepting the put; resolvers pass the key reached by the previous hop.
+pub fn check_transition(t: &HandleTransition, holder_pk: &[u8; 32], now: u64) -> Result<()> {
+    check_shape(t)?;
+    if &t.old_signing_pk != holder_pk {
+        return Err(WhisperError::Transition(format!(
+            "transition for @{} is not signed by its holder",
+            t.old_handle
+        )));
+    }
+    if now >= t.grace_until {
+        return Err(WhisperError::Transition(format!(
+            "transition for @{} has lapsed",
+            t.old_handle
+        )));
+    }
+    let vk = VerifyingKey::from_bytes(holder_pk).map_err(|_| WhisperError::InvalidSignature)?;
+    vk.verify(&signable(t), &Signature::from_bytes(&t.sig))
+        .map_err(|_| WhisperError::InvalidSignature)
+}
+
+/// Check a descriptor that takes over its handle through a transfer.
+///
+/// `previous` is the stored descriptor of the same handle. The new key pays
+/// the renewal difficulty rather than a fresh registration.
+pub fn check_transferred_descriptor(
+    descriptor: &HandleDescriptor,
+    previous: &HandleDescriptor,
+    transition: &HandleTransition,
+    now: u64,
+) -> Result<ProofCheck> {
+    check_transition(transition, &previous.handle_signing_pk, now)?;
+    let name = handle::normalize(&descriptor.handle);
+    if handle::normalize(&previous.handle) != name
+        || handle::normalize(&transition.new_handle) != name
+        || transition.new_signing_pk != descriptor.handle_signing_pk
+    {
+        return Err(WhisperError::Transition(
+            "descriptor does not match the transition".to_string(),
+        ));
+    }
+    handle::check_proof(
+        descriptor,
+        None,
+        handle::renewal_difficulty(&descriptor.handle),
+        now,
+    )
+}
+
+/// Where a chain of transitions leads.
+#[derive(Clone, Debug)]
+pub struct Resolution {
+    /// Handle at the end of the chain.
+    pub handle: String,
+    /// Key expected to sign that handle's descriptor.
+    pub signing_pk: [u8; 32],
+    /// Transitions followed, in order.
+    pub hops: Vec<HandleTransition>,
+}
+
+impl Resolution {
+    /// Whether the starting handle has moved.
+    pub fn moved(&self) -> bool {
+        !self.hops.is_empty()
+    }
+
+    /// Whether any hop handed the handle to a different owner.
+    pub fn transferred(&self) -> bool {
+        self.hops
+            .iter()
+            .any(|t| t.kind == HandleTransitionKind::Transfer)
+    }
+
+    /// When the starting handle stops resolving, if it has moved.
+    pub fn grace_until(&self) -> Option<u64> {
+        self.hops.first().map(|t| t.grace_until)
+    }
+}
+
+/// Follow transitions from `handle`, held by `signing_pk`.
+///
+/// `fetch` looks up the record at a [`transition_addr`]. A record that is
+/// absent or has lapsed ends the chain; a forged or looping one is an error.
+pub fn follow_transitions(
+    handle: &str,
+    signing_pk: [u8; 32],
+    now: u64,
+    mut fetch: impl FnMut(&[u8; 32]) -> Option<HandleTransition>,
+) -> Result<Resolution> {
+    let mut current = Resolution {
+        handle: handle::normalize(handle),
+        signing_pk,
+        hops: Vec::new(),
+    };
+    let mut seen = HashSet::from([(current.handle.clone(), current.signing_pk)]);
+
+    while let Some(t) = fetch(&transition_addr(&current.handle)) {
+        // A same-handle transfer stays at its own address; reaching its new
+        // key means the chain has ended.
+        let arrived = handle::normalize(&t.new_handle) == current.handle
+            && t.new_signing_pk == current.signing_pk;
+        if now >= t.grace_until || arrived {
+            break;
+        }
+        if handle::normalize(&t.old_handle) != current.handle {
+            return Err(WhisperError::Transition(format!(
+                "record at @{} names @{}",
+                current.handle, t.old_handle
+            )));
+        }
+        check_transition(&t, &current.signing_pk, now)?;
+        if current.hops.len() == MAX_TRANSITION_HOPS {
+            return Err(WhisperError::Transition(format!(
+                "more than {MAX_TRANSITION_HOPS} transitions from @{handle}"
+            )));
+        }
+        current.handle = handle::normalize(&t.new_handle);
+        current.signing_pk = t.new_signing_pk;
+        if !seen.insert((current.handle.clone(), current.signing_pk)) {
+            return Err(WhisperError::Transition(format!(
+                "transition loop at @{}",
+                current.handle
+            )));
+        }
+        current.hops.push(t);
+    }
+    Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const NOW: u64 = 1_000_000;
+
+    fn publish(dht: &mut HashMap<[u8; 32], HandleTransition>, t: HandleTransition) {
+        dht.insert(transition_addr(&t.old_handle), t);
+    }
+
+    #[test]
+    fn test_sign_and_check() {
+        let old = SigningKey::generate();
+        let new = SigningKey::generate();
+        let t = sign_transition(
+            &old,
+            "Alice_old",
+            "alice_new",
+            new.verifying_key().to_bytes(),
+            HandleTransitionKind::Rename,
+            NOW,
+        )
+        .expect("sign");
+        assert_eq!(t.old_handle, "alice_old");
+        let holder = old.verifying_key().to_bytes();
+        assert!(check_transition(&t, &holder, NOW).is_ok());
+        assert!(check_transition(&t, &new.verifying_key().to_bytes(), NOW).is_err());
+        assert!(check_transition(&t, &holder, t.grace_until).is_err());
+
+        let mut tampered = t.clone();
+        tampered.kind = HandleTransitionKind::Transfer;
+        assert!(matches!(
+            check_transition(&tampered, &holder, NOW),
+            Err(WhisperError::InvalidSignature)
+        ));
+
+        let same = old.verifying_key().to_bytes();
+        assert!(sign_transition(
+            &old,
+            "alice",
+            "ALICE",
+            same,
+            HandleTransitionKind::Rename,
+            NOW
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_follow_chain() {
+        let a = SigningKey::generate();
+        let b = SigningKey::generate();
+        let c = SigningKey::generate();
+        let mut dht = HashMap::new();
+        let rename = sign_transition(
+            &a,
+            "first",
+            "second",
+            b.verifying_key().to_bytes(),
+            HandleTransitionKind::Rename,
+            NOW,
+        )
+        .expect("sign");
+        let transfer = sign_transition(
+            &b,
+            "second",
+            "second",
+            c.verifying_key().to_bytes(),
+            HandleTransitionKind::Transfer,
+            NOW + 10,
+        )
+        .expect("sign");
+        publish(&mut dht, rename);
+
+        let r = follow_transitions("first", a.verifying_key().to_bytes(), NOW + 1, |addr| {
+            dht.get(addr).cloned()
+        })
+        .expect("follow");
+        assert_eq!(r.handle, "second");
+        assert!(r.moved() && !r.transferred());
+        assert_eq!(r.grace_until(), Some(NOW + TRANSITION_GRACE_SECS));
+
+        // The new handle then changes owner under the same name
+        publish(&mut dht, transfer);
+        let r = follow_transitions("first", a.verifying_key().to_bytes(), NOW + 20, |addr| {
+            dht.get(addr).cloned()
+        })
+        .expect("follow");
+        assert_eq!(r.handle, "second");
+        assert_eq!(r.signing_pk, c.verifying_key().to_bytes());
+        assert_eq!(r.hops.len(), 2);
+        assert!(r.transferred());
+
+        // After the grace window the record lapses and the chain ends
+        let later = NOW + TRANSITION_GRACE_SECS;
+        let r = follow_transitions("first", a.verifying_key().to_bytes(), later, |addr| {
+            dht.get(addr).cloned()
+        })
+        .expect("follow");
+        assert!(!r.moved());
+    }
+
+    #[test]
+    fn test_forged_record_is_rejected() {
+        let owner = SigningKey::generate();
+        let attacker = SigningKey::generate();
+        let mut dht = HashMap::new();
+        publish(
+            &mut dht,
+            sign_transition(
+                &attacker,
+                "victim",
+                "phish",
+                attacker.verifying_key().to_bytes(),
+                HandleTransitionKind::Rename,
+                NOW,
+            )
+            .expect("sign"),
+        );
+        let r = follow_transitions("victim", owner.verifying_key().to_bytes(), NOW, |addr| {
+            dht.get(addr).cloned()
+        });
+        assert!(matches!(r, Err(WhisperError::Transition(_))));
+    }
+}
@@ -1,20 +1,83 @@
 //! JSON-RPC client that connects to the Ochra daemon over a Unix domain
-//! socket and forwards requests from the Tauri frontend.
+//! socket (or a named pipe on Windows) and forwards requests from the Tauri
+//! frontend.
 //!
 //! The daemon speaks newline-delimited JSON-RPC 2.0 (one request per line,
 //! one response per line). This module handles the connection lifecycle,
 //! serialization, and deserialization.
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::UnixStream;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 use tracing::{debug, error};
 
+/// Named pipe the daemon listens on under Windows.
+#[cfg(windows)]
+pub const PIPE_NAME: &str = r"\\.\pipe\ochra-daemon";
+
+/// Resolve the daemon's IPC endpoint.
+///
+/// `OCHRA_SOCKET_PATH` overrides the default, which mirrors the daemon's:
+/// the named pipe on Windows, otherwise `daemon.sock` in the data directory
+/// (`OCHRA_DATA_DIR`, or the platform default under `$HOME`).
+pub fn daemon_endpoint() -> String {
+    if let Ok(path) = std::env::var("OCHRA_SOCKET_PATH") {
+        return path;
+    }
+    #[cfg(windows)]
+    {
+        PIPE_NAME.to_string()
+    }
+    #[cfg(not(windows))]
+    {
+        let data_dir = std::env::var("OCHRA_DATA_DIR")
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|_| {
+                let subpath = if cfg!(target_os = "macos") {
+                    "Library/Application Support/Ochra"
+                } else {
+                    ".ochra"
+                };
+                std::env::var("HOME")
+                    .map(|h| std::path::PathBuf::from(h).join(subpath))
+                    .unwrap_or_else(|_| std::path::PathBuf::from("/tmp/ochra"))
+            });
+        data_dir.join("daemon.sock").to_string_lossy().into_owned()
+    }
+}
+
+/// Open a connection to the daemon's Unix socket.
+#[cfg(not(windows))]
+async fn connect(endpoint: &str) -> std::io::Result<impl AsyncRead + AsyncWrite + Unpin> {
+    tokio::net::UnixStream::connect(endpoint).await
+}
+
+/// Open a connection to the daemon's named pipe, waiting briefly while every
+/// pipe instance is busy.
+#[cfg(windows)]
+async fn connect(endpoint: &str) -> std::io::Result<impl AsyncRead + AsyncWrite + Unpin> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    /// `ERROR_PIPE_BUSY`: all pipe instances are connected.
+    const ERROR_PIPE_BUSY: i32 = 231;
+
+    let mut attempts = 0;
+    loop {
+        match ClientOptions::new().open(endpoint) {
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && attempts < 20 => {
+                attempts += 1;
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
 /// Send a single JSON-RPC request to the daemon and return the parsed
 /// response.
 ///
 /// # Arguments
 ///
-/// * `socket_path` - Path to the daemon's Unix socket (e.g. `/tmp/ochra-daemon.sock`).
+/// * `socket_path` - The daemon's Unix socket path or named pipe name (see
+///   [`daemon_endpoint`]).
 /// * `request`     - A complete JSON-RPC 2.0 request as a `serde_json::Value`.
 ///
 /// # Errors
@@ -26,7 +89,7 @@ pub async fn send_rpc_request(
     request: &serde_json::Value,
 ) -> Result<serde_json::Value, IpcBridgeError> {
     // Connect to the daemon socket.
-    let stream = UnixStream::connect(socket_path).await.map_err(|e| {
+    let stream = connect(socket_path).await.map_err(|e| {
         error!(
             "Failed to connect to daemon socket at {}: {}",
             socket_path, e
@@ -39,7 +102,7 @@ pub async fn send_rpc_request(
 
     debug!("Connected to daemon socket at {}", socket_path);
 
-    let (reader, mut writer) = stream.into_split();
+    let (reader, mut writer) = tokio::io::split(stream);
     let mut reader = BufReader::new(reader);
 
     // Serialize the request to a single line of JSON, terminated by newline.
@@ -6,9 +6,6 @@ mod ipc_bridge;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
-/// Default Unix socket path for the Ochra daemon.
-const DEFAULT_SOCKET_PATH: &str = "/tmp/ochra-daemon.sock";
-
 // ---------------------------------------------------------------------------
 // Tauri IPC command: greet (test / health-check)
 // ---------------------------------------------------------------------------
@@ -47,14 +44,13 @@ pub struct IpcResponse {
     pub error: Option<serde_json::Value>,
 }
 
-/// Forward a JSON-RPC request to the Ochra daemon over its Unix socket and
-/// return the response to the frontend.
+/// Forward a JSON-RPC request to the Ochra daemon over its Unix socket or
+/// named pipe and return the response to the frontend.
 ///
 /// The frontend calls this via `invoke("ipc_request", { request: { method, params } })`.
 #[tauri::command]
 async fn ipc_request(request: IpcRequest) -> Result<IpcResponse, String> {
-    let socket_path =
-        std::env::var("OCHRA_SOCKET_PATH").unwrap_or_else(|_| DEFAULT_SOCKET_PATH.to_string());
+    let socket_path = ipc_bridge::daemon_endpoint();
 
     let rpc_request = serde_json::json!({
         "jsonrpc": "2.0",